@@ -0,0 +1,2521 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use log::{debug, info};
+
+use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::eir::{
+    self, AsmDialect, AsmPiece, AtomicOp, BasicBlock, BinaryOp, BlockId, Function, GlobalAttributes, Hotness, Instruction,
+    InstructionId, Literal, Module, Operand, RegisterId, Terminator, UnaryOp,
+};
+use crate::core::coverage;
+use crate::core::mangle::Symbol;
+use crate::core::source_map::SourceMap;
+use crate::core::types::{NumericType, Type, TypeId, TypeKind};
+
+use super::codegen::{self, Backend, CodegenOptions, Intrinsic, IntrinsicType};
+
+/// EIRの値をC99で表現するときの型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CType {
+    /// unit値（`eidos_unit`、常に0）
+    Unit,
+    /// `bool`
+    Bool,
+    /// `int64_t`
+    Int,
+    /// `double`
+    Float,
+    /// `uint32_t`（Unicodeスカラー値）
+    Char,
+    /// `const char *`（NUL終端のUTF-8文字列）
+    String,
+    /// `eidos_variant`（`Option` と `Result` の値）
+    Variant,
+    /// `int8_t`〜`int32_t`、`uint8_t`〜`uint64_t`、`float`（`i64` と `f64` は `Int` と `Float`）
+    Sized(NumericType),
+    /// `char *`（固定長配列の先頭のアドレス）
+    Array,
+    /// `eidos_slice`（データのアドレスと長さ）
+    Slice,
+    /// `eidos_range`（整数の範囲の始点と終点）
+    Range,
+}
+
+impl CType {
+    /// EIRの型から変換（C99で表現できない型はNone）
+    pub fn from_type(ty: &Type) -> Option<Self> {
+        match &ty.kind {
+            TypeKind::Unit => Some(CType::Unit),
+            TypeKind::Bool => Some(CType::Bool),
+            TypeKind::Int => Some(CType::Int),
+            TypeKind::Float => Some(CType::Float),
+            TypeKind::Char => Some(CType::Char),
+            TypeKind::String => Some(CType::String),
+            TypeKind::Numeric(numeric) => Some(CType::Sized(*numeric)),
+            TypeKind::Enum { .. } if ty.is_fallible() => Some(CType::Variant),
+            TypeKind::FixedArray { .. } => Some(CType::Array),
+            TypeKind::Slice(_) => Some(CType::Slice),
+            TypeKind::Struct { .. } => match CType::from_type(ty.range_element()?) {
+                Some(element) if element.is_integer() => Some(CType::Range),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    
+    /// C言語での型名
+    pub fn c_name(&self) -> &'static str {
+        match self {
+            CType::Unit => "eidos_unit",
+            CType::Bool => "bool",
+            CType::Int => "int64_t",
+            CType::Float => "double",
+            CType::Char => "uint32_t",
+            CType::String => "const char *",
+            CType::Variant => "eidos_variant",
+            CType::Sized(numeric) => match numeric {
+                NumericType::I8 => "int8_t",
+                NumericType::I16 => "int16_t",
+                NumericType::I32 => "int32_t",
+                NumericType::I64 => "int64_t",
+                NumericType::U8 => "uint8_t",
+                NumericType::U16 => "uint16_t",
+                NumericType::U32 => "uint32_t",
+                NumericType::U64 => "uint64_t",
+                NumericType::F32 => "float",
+                NumericType::F64 => "double",
+            },
+            CType::Array => "char *",
+            CType::Slice => "eidos_slice",
+            CType::Range => "eidos_range",
+        }
+    }
+    
+    /// 整数型かどうか
+    fn is_integer(&self) -> bool {
+        match self {
+            CType::Int => true,
+            CType::Sized(numeric) => !numeric.is_float(),
+            _ => false,
+        }
+    }
+    
+    /// `eidos_payload` の共用体でこの型の値を入れるメンバー（`Option` と `Result` は入れ子にできない）
+    fn payload_member(&self) -> Option<&'static str> {
+        match self {
+            CType::Unit | CType::Int => Some("i"),
+            CType::Float => Some("f"),
+            CType::Sized(numeric) if numeric.is_float() => Some("f"),
+            CType::Sized(_) => Some("i"),
+            CType::Bool => Some("b"),
+            CType::Char => Some("c"),
+            CType::String => Some("s"),
+            CType::Variant | CType::Array | CType::Slice | CType::Range => None,
+        }
+    }
+}
+
+/// エラーメッセージではEidosの型名で表示する
+impl std::fmt::Display for CType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CType::Unit => write!(f, "()"),
+            CType::Bool => write!(f, "Bool"),
+            CType::Int => write!(f, "Int"),
+            CType::Float => write!(f, "Float"),
+            CType::Char => write!(f, "Char"),
+            CType::String => write!(f, "String"),
+            CType::Variant => write!(f, "Option・Result"),
+            CType::Sized(numeric) => write!(f, "{}", numeric),
+            CType::Array => write!(f, "[T; N]"),
+            CType::Slice => write!(f, "&[T]"),
+            CType::Range => write!(f, "Range<T>"),
+        }
+    }
+}
+
+/// 生成したCコードが依存するランタイム層
+///
+/// 入出力やメモリ確保をどう実現するかはランタイムが決める。`prelude` は次の関数を定義しなければならない。
+///
+/// - `void *eidos_alloc(size_t size)`
+/// - `void eidos_panic(const char *message)`: メッセージと `eidos_current_frame` からたどれる
+///   スタックトレースを出力して実行を止める
+/// - `void eidos_unreachable(void)`
+/// - `void eidos_print_int(int64_t)` / `eidos_print_float(double)` / `eidos_print_bool(bool)` /
+///   `eidos_print_char(uint32_t)` / `eidos_print_string(const char *)` / `eidos_print_newline(void)`
+pub trait CRuntime: Send + Sync {
+    /// ランタイムの名前
+    fn name(&self) -> &str;
+    
+    /// 生成コードの先頭に置くインクルードと補助関数
+    fn prelude(&self) -> String;
+    
+    /// Eidosのエントリー関数を呼び出すCのエントリーポイントを生成
+    fn entry_point(&self, entry: &str, return_type: CType) -> String;
+    
+    /// 標準ライブラリの `fs` モジュールを実装する補助関数（ファイルシステムを持たないランタイムはNone）
+    ///
+    /// 返すコードは `FS_FUNCTIONS` に挙げた `eidos_fs_*` 関数をすべて定義しなければならない。
+    fn fs_helpers(&self) -> Option<String> {
+        None
+    }
+    
+    /// 標準ライブラリの `thread` モジュールを実装する補助関数（スレッドを持たないランタイムはNone）
+    ///
+    /// 返すコードは `eidos_thread_spawn(int64_t (*)(void))` と、`THREAD_FUNCTIONS` に挙げた
+    /// `eidos_thread_*` 関数をすべて定義しなければならない。
+    fn thread_helpers(&self) -> Option<String> {
+        None
+    }
+    
+    /// カバレッジ計測のカウンタと、終了時にプロファイルを書き出す補助関数（書き出せないランタイムはNone）
+    ///
+    /// 返すコードは `eidos_cov_hit(int64_t)` と `eidos_cov_branch(int64_t, bool)` を定義しなければならない。
+    fn coverage_helpers(&self, _module: &str, _counters: usize) -> Option<String> {
+        None
+    }
+    
+    /// 組み込み関数の呼び出しをCの文に変換（ランタイムが扱わない関数はNone）
+    fn builtin_call(&self, name: &str, args: &[(String, CType)]) -> Option<String> {
+        let newline = match name {
+            "print" => false,
+            "println" => true,
+            _ => return None,
+        };
+        
+        let mut statements = Vec::with_capacity(args.len() + 1);
+        for (expr, ty) in args {
+            let helper = match ty {
+                CType::Unit | CType::Int => "eidos_print_int",
+                CType::Bool => "eidos_print_bool",
+                CType::Float => "eidos_print_float",
+                CType::Sized(NumericType::U64) => "eidos_print_u64",
+                CType::Sized(numeric) if numeric.is_float() => "eidos_print_float",
+                CType::Sized(_) => "eidos_print_int",
+                CType::Char => "eidos_print_char",
+                CType::String => "eidos_print_string",
+                // 中の値の型は実行時には分からないので出力できない
+                CType::Variant | CType::Array | CType::Slice | CType::Range => return None,
+            };
+            statements.push(format!("{}({})", helper, expr));
+        }
+        if newline {
+            statements.push("eidos_print_newline()".to_string());
+        }
+        Some(statements.join("; "))
+    }
+}
+
+/// 標準Cライブラリを使うホスト環境向けのランタイム
+pub struct HostedRuntime;
+
+impl CRuntime for HostedRuntime {
+    fn name(&self) -> &str {
+        "hosted"
+    }
+    
+    fn prelude(&self) -> String {
+        r#"#include <stdio.h>
+#include <stdlib.h>
+
+static void *eidos_alloc(size_t size) {
+    void *ptr = malloc(size);
+    if (ptr == NULL) {
+        fputs("eidos: out of memory\n", stderr);
+        abort();
+    }
+    return ptr;
+}
+
+static void eidos_panic(const char *message) {
+    const eidos_frame *frame;
+    fflush(stdout);
+    fprintf(stderr, "eidos: panic: %s\n", message);
+    fputs("stack trace (most recent call first):\n", stderr);
+    for (frame = eidos_current_frame; frame != NULL; frame = frame->caller) {
+        if (frame->line != 0) {
+            fprintf(stderr, "    at %s (%s:%lu)\n", frame->function, frame->file, (unsigned long)frame->line);
+        } else {
+            fprintf(stderr, "    at %s\n", frame->function);
+        }
+    }
+    exit(101);
+}
+
+static void eidos_unreachable(void) { eidos_panic("reached unreachable code"); }
+
+static void eidos_print_int(int64_t value) { printf("%lld", (long long)value); }
+/* 読み戻して同じ値になる最短の桁数で出力する（JavaScriptとJITの表示と同じく 0.1 + 0.2 は 0.30000000000000004） */
+static void eidos_print_float(double value) {
+    char text[32];
+    int precision;
+    for (precision = 15; precision < 17; precision++) {
+        snprintf(text, sizeof text, "%.*g", precision, value);
+        if (strtod(text, NULL) == value) {
+            break;
+        }
+    }
+    if (precision == 17) {
+        snprintf(text, sizeof text, "%.17g", value);
+    }
+    fputs(text, stdout);
+}
+static void eidos_print_bool(bool value) { fputs(value ? "true" : "false", stdout); }
+static void eidos_print_string(const char *value) { fputs(value, stdout); }
+static void eidos_print_newline(void) { putchar('\n'); }
+
+static void eidos_print_char(uint32_t c) {
+    if (c < 0x80) {
+        putchar((int)c);
+    } else if (c < 0x800) {
+        putchar((int)(0xC0 | (c >> 6)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else if (c < 0x10000) {
+        putchar((int)(0xE0 | (c >> 12)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else {
+        putchar((int)(0xF0 | (c >> 18)));
+        putchar((int)(0x80 | ((c >> 12) & 0x3F)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    }
+}
+"#
+        .to_string()
+    }
+    
+    fn fs_helpers(&self) -> Option<String> {
+        Some(HOSTED_FS_HELPERS.to_string())
+    }
+    
+    fn thread_helpers(&self) -> Option<String> {
+        Some(HOSTED_THREAD_HELPERS.to_string())
+    }
+    
+    fn coverage_helpers(&self, module: &str, counters: usize) -> Option<String> {
+        let profile = string_literal(&format!("{}.profile.json", module));
+        let module = string_literal(&serde_json::Value::from(module).to_string());
+        Some(
+            HOSTED_COVERAGE_HELPERS
+                .replace("EIDOS_COV_COUNTERS", &counters.to_string())
+                .replace("EIDOS_COV_PROFILE", &profile)
+                .replace("EIDOS_COV_MODULE", &module),
+        )
+    }
+    
+    fn entry_point(&self, entry: &str, return_type: CType) -> String {
+        let body = match return_type {
+            ty if ty.is_integer() => format!("    return (int){}();\n", entry),
+            _ => format!("    {}();\n    return 0;\n", entry),
+        };
+        format!("int main(int argc, char **argv) {{\n    (void)argc;\n    (void)argv;\n{}}}\n", body)
+    }
+}
+
+/// libcを前提としない組み込み環境向けのランタイム
+///
+/// 出力は利用側が用意する `void eidos_putchar(int c)` を通して行い、メモリは静的なヒープ領域から確保する。
+/// エントリーポイントは `main` ではなく `entry_symbol` の名前で公開される。
+pub struct FreestandingRuntime {
+    entry_symbol: String,
+    heap_size: usize,
+}
+
+impl FreestandingRuntime {
+    pub fn new() -> Self {
+        Self {
+            entry_symbol: "eidos_start".to_string(),
+            heap_size: 64 * 1024,
+        }
+    }
+    
+    /// 公開するエントリーポイントの名前を設定
+    pub fn with_entry_symbol(mut self, symbol: &str) -> Self {
+        self.entry_symbol = symbol.to_string();
+        self
+    }
+    
+    /// 静的ヒープ領域のサイズ（バイト）を設定
+    pub fn with_heap_size(mut self, size: usize) -> Self {
+        self.heap_size = size;
+        self
+    }
+}
+
+impl CRuntime for FreestandingRuntime {
+    fn name(&self) -> &str {
+        "freestanding"
+    }
+    
+    fn prelude(&self) -> String {
+        let mut prelude = String::new();
+        let _ = writeln!(prelude, "extern void eidos_putchar(int c);\n");
+        let _ = writeln!(prelude, "#define EIDOS_HEAP_SIZE {}", self.heap_size);
+        prelude.push_str(
+            r#"static unsigned char eidos_heap[EIDOS_HEAP_SIZE];
+static size_t eidos_heap_used = 0;
+
+static void eidos_panic(const char *message);
+static void eidos_unreachable(void) { eidos_panic("reached unreachable code"); }
+
+static void *eidos_alloc(size_t size) {
+    size_t aligned = (size + 7) & ~(size_t)7;
+    if (aligned > EIDOS_HEAP_SIZE - eidos_heap_used) {
+        eidos_unreachable();
+    }
+    void *ptr = &eidos_heap[eidos_heap_used];
+    eidos_heap_used += aligned;
+    return ptr;
+}
+
+static void eidos_print_string(const char *value) {
+    while (*value != '\0') {
+        eidos_putchar((unsigned char)*value++);
+    }
+}
+
+static void eidos_print_newline(void) { eidos_putchar('\n'); }
+static void eidos_print_bool(bool value) { eidos_print_string(value ? "true" : "false"); }
+
+static void eidos_print_int(int64_t value) {
+    char digits[20];
+    int count = 0;
+    uint64_t magnitude = value < 0 ? (uint64_t)0 - (uint64_t)value : (uint64_t)value;
+    if (value < 0) {
+        eidos_putchar('-');
+    }
+    do {
+        digits[count++] = (char)('0' + magnitude % 10);
+        magnitude /= 10;
+    } while (magnitude != 0);
+    while (count > 0) {
+        eidos_putchar(digits[--count]);
+    }
+}
+
+static void eidos_print_float(double value) {
+    int i;
+    if (value < 0) {
+        eidos_putchar('-');
+        value = -value;
+    }
+    eidos_print_int((int64_t)value);
+    eidos_putchar('.');
+    value -= (double)(int64_t)value;
+    for (i = 0; i < 6; i++) {
+        value *= 10;
+        eidos_putchar('0' + (int)value % 10);
+    }
+}
+
+static void eidos_print_char(uint32_t c) {
+    if (c < 0x80) {
+        eidos_putchar((int)c);
+    } else if (c < 0x800) {
+        eidos_putchar((int)(0xC0 | (c >> 6)));
+        eidos_putchar((int)(0x80 | (c & 0x3F)));
+    } else if (c < 0x10000) {
+        eidos_putchar((int)(0xE0 | (c >> 12)));
+        eidos_putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        eidos_putchar((int)(0x80 | (c & 0x3F)));
+    } else {
+        eidos_putchar((int)(0xF0 | (c >> 18)));
+        eidos_putchar((int)(0x80 | ((c >> 12) & 0x3F)));
+        eidos_putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        eidos_putchar((int)(0x80 | (c & 0x3F)));
+    }
+}
+
+static void eidos_panic(const char *message) {
+    const eidos_frame *frame;
+    eidos_print_string("eidos: panic: ");
+    eidos_print_string(message);
+    eidos_print_string("\nstack trace (most recent call first):\n");
+    for (frame = eidos_current_frame; frame != NULL; frame = frame->caller) {
+        eidos_print_string("    at ");
+        eidos_print_string(frame->function);
+        if (frame->line != 0) {
+            eidos_print_string(" (");
+            eidos_print_string(frame->file);
+            eidos_putchar(':');
+            eidos_print_int((int64_t)frame->line);
+            eidos_putchar(')');
+        }
+        eidos_putchar('\n');
+    }
+    for (;;) {
+    }
+}
+"#,
+        );
+        prelude
+    }
+    
+    fn entry_point(&self, entry: &str, _return_type: CType) -> String {
+        format!("void {}(void) {{\n    {}();\n}}\n", self.entry_symbol, entry)
+    }
+}
+
+/// 標準ライブラリの `fs` モジュールの関数と、それを実装する補助関数（引数の型、戻り値の型）
+///
+/// 配列を返す `read_dir` と `walk_dir` はC99バックエンドでは表現できないため含めない。
+const FS_FUNCTIONS: &[(&str, &str, &[CType], CType)] = &[
+    ("exists", "eidos_fs_exists", &[CType::String], CType::Bool),
+    ("is_file", "eidos_fs_is_file", &[CType::String], CType::Bool),
+    ("is_dir", "eidos_fs_is_dir", &[CType::String], CType::Bool),
+    ("size", "eidos_fs_size", &[CType::String], CType::Int),
+    ("modified", "eidos_fs_modified", &[CType::String], CType::Int),
+    ("create_dir", "eidos_fs_create_dir", &[CType::String], CType::Unit),
+    ("copy", "eidos_fs_copy", &[CType::String, CType::String], CType::Int),
+    ("rename", "eidos_fs_rename", &[CType::String, CType::String], CType::Unit),
+    ("remove_file", "eidos_fs_remove_file", &[CType::String], CType::Unit),
+    ("remove_dir", "eidos_fs_remove_dir", &[CType::String], CType::Unit),
+    ("remove_dir_all", "eidos_fs_remove_dir_all", &[CType::String], CType::Unit),
+    ("temp_dir", "eidos_fs_temp_dir", &[], CType::String),
+    ("temp_file", "eidos_fs_temp_file", &[CType::String], CType::String),
+];
+
+/// `HostedRuntime` の `fs` モジュールの実装（POSIX.1-2008）
+///
+/// 失敗した操作は原因を標準エラー出力に表示して終了コード1で終了する。
+const HOSTED_FS_HELPERS: &str = r#"#include <dirent.h>
+#include <errno.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+static void eidos_fs_fail(const char *op, const char *path) {
+    fprintf(stderr, "eidos: fs::%s failed: %s: %s\n", op, path, strerror(errno));
+    exit(1);
+}
+
+static bool eidos_fs_exists(const char *path) {
+    struct stat st;
+    return stat(path, &st) == 0;
+}
+
+static bool eidos_fs_is_file(const char *path) {
+    struct stat st;
+    return stat(path, &st) == 0 && S_ISREG(st.st_mode);
+}
+
+static bool eidos_fs_is_dir(const char *path) {
+    struct stat st;
+    return stat(path, &st) == 0 && S_ISDIR(st.st_mode);
+}
+
+static int64_t eidos_fs_size(const char *path) {
+    struct stat st;
+    if (stat(path, &st) != 0) {
+        eidos_fs_fail("size", path);
+    }
+    return (int64_t)st.st_size;
+}
+
+static int64_t eidos_fs_modified(const char *path) {
+    struct stat st;
+    if (stat(path, &st) != 0) {
+        eidos_fs_fail("modified", path);
+    }
+    return (int64_t)st.st_mtime;
+}
+
+static void eidos_fs_create_dir(const char *path) {
+    size_t len = strlen(path);
+    size_t i;
+    char *buffer = (char *)eidos_alloc(len + 1);
+    memcpy(buffer, path, len + 1);
+    for (i = 1; i <= len; i++) {
+        if (buffer[i] == '/' || buffer[i] == '\0') {
+            char saved = buffer[i];
+            buffer[i] = '\0';
+            if (mkdir(buffer, 0777) != 0 && errno != EEXIST) {
+                eidos_fs_fail("create_dir", path);
+            }
+            buffer[i] = saved;
+        }
+    }
+    free(buffer);
+}
+
+static int64_t eidos_fs_copy(const char *from, const char *to) {
+    char chunk[8192];
+    size_t read;
+    int64_t total = 0;
+    FILE *in = fopen(from, "rb");
+    FILE *out;
+    if (in == NULL) {
+        eidos_fs_fail("copy", from);
+    }
+    out = fopen(to, "wb");
+    if (out == NULL) {
+        eidos_fs_fail("copy", to);
+    }
+    while ((read = fread(chunk, 1, sizeof chunk, in)) > 0) {
+        if (fwrite(chunk, 1, read, out) != read) {
+            eidos_fs_fail("copy", to);
+        }
+        total += (int64_t)read;
+    }
+    if (ferror(in)) {
+        eidos_fs_fail("copy", from);
+    }
+    fclose(in);
+    if (fclose(out) != 0) {
+        eidos_fs_fail("copy", to);
+    }
+    return total;
+}
+
+static void eidos_fs_rename(const char *from, const char *to) {
+    if (rename(from, to) != 0) {
+        eidos_fs_fail("rename", from);
+    }
+}
+
+static void eidos_fs_remove_file(const char *path) {
+    if (unlink(path) != 0) {
+        eidos_fs_fail("remove_file", path);
+    }
+}
+
+static void eidos_fs_remove_dir(const char *path) {
+    if (rmdir(path) != 0) {
+        eidos_fs_fail("remove_dir", path);
+    }
+}
+
+static void eidos_fs_remove_dir_all(const char *path) {
+    struct stat st;
+    DIR *dir;
+    struct dirent *entry;
+    if (lstat(path, &st) != 0) {
+        eidos_fs_fail("remove_dir_all", path);
+    }
+    if (!S_ISDIR(st.st_mode)) {
+        eidos_fs_remove_file(path);
+        return;
+    }
+    dir = opendir(path);
+    if (dir == NULL) {
+        eidos_fs_fail("remove_dir_all", path);
+    }
+    while ((entry = readdir(dir)) != NULL) {
+        size_t len;
+        char *child;
+        if (strcmp(entry->d_name, ".") == 0 || strcmp(entry->d_name, "..") == 0) {
+            continue;
+        }
+        len = strlen(path) + strlen(entry->d_name) + 2;
+        child = (char *)eidos_alloc(len);
+        snprintf(child, len, "%s/%s", path, entry->d_name);
+        eidos_fs_remove_dir_all(child);
+        free(child);
+    }
+    closedir(dir);
+    eidos_fs_remove_dir(path);
+}
+
+static const char *eidos_fs_temp_dir(void) {
+    const char *dir = getenv("TMPDIR");
+    return dir != NULL && *dir != '\0' ? dir : "/tmp";
+}
+
+static const char *eidos_fs_temp_file(const char *prefix) {
+    const char *dir = eidos_fs_temp_dir();
+    size_t len = strlen(dir) + strlen(prefix) + 8;
+    char *path = (char *)eidos_alloc(len);
+    int fd;
+    snprintf(path, len, "%s/%sXXXXXX", dir, prefix);
+    fd = mkstemp(path);
+    if (fd < 0) {
+        eidos_fs_fail("temp_file", path);
+    }
+    close(fd);
+    return path;
+}
+"#;
+
+/// 標準ライブラリの `thread` モジュールの関数と、それを実装する補助関数（引数の型、戻り値の型）
+///
+/// 関数を引数に取る `spawn` と、アトミック命令に変換する `atomic_*` は含めない。
+const THREAD_FUNCTIONS: &[(&str, &str, &[CType], CType)] = &[
+    ("join", "eidos_thread_join", &[CType::Int], CType::Int),
+    ("sleep", "eidos_thread_sleep", &[CType::Int], CType::Unit),
+    ("channel", "eidos_thread_channel", &[], CType::Int),
+    ("send", "eidos_thread_send", &[CType::Int, CType::Int], CType::Unit),
+    ("recv", "eidos_thread_recv", &[CType::Int], CType::Int),
+];
+
+/// `HostedRuntime` の `thread` モジュールの実装（POSIXスレッド）
+///
+/// スレッドとチャネルのハンドルは確保した構造体のアドレス。スレッドの作成や合流に失敗すると終了コード1で終了する。
+const HOSTED_THREAD_HELPERS: &str = r#"#include <errno.h>
+#include <pthread.h>
+#include <time.h>
+
+typedef int64_t (*eidos_thread_entry)(void);
+
+typedef struct {
+    pthread_t thread;
+    eidos_thread_entry entry;
+    int64_t result;
+} eidos_thread;
+
+typedef struct eidos_channel_item {
+    int64_t value;
+    struct eidos_channel_item *next;
+} eidos_channel_item;
+
+typedef struct {
+    pthread_mutex_t lock;
+    pthread_cond_t ready;
+    eidos_channel_item *head;
+    eidos_channel_item *tail;
+} eidos_channel;
+
+static void eidos_thread_fail(const char *op) {
+    fprintf(stderr, "eidos: thread::%s failed\n", op);
+    exit(1);
+}
+
+static void *eidos_thread_main(void *arg) {
+    eidos_thread *thread = (eidos_thread *)arg;
+    thread->result = thread->entry();
+    return NULL;
+}
+
+static int64_t eidos_thread_spawn(eidos_thread_entry entry) {
+    eidos_thread *thread = (eidos_thread *)eidos_alloc(sizeof(eidos_thread));
+    thread->entry = entry;
+    thread->result = 0;
+    if (pthread_create(&thread->thread, NULL, eidos_thread_main, thread) != 0) {
+        eidos_thread_fail("spawn");
+    }
+    return (int64_t)(intptr_t)thread;
+}
+
+static int64_t eidos_thread_join(int64_t handle) {
+    eidos_thread *thread = (eidos_thread *)(intptr_t)handle;
+    int64_t result;
+    if (pthread_join(thread->thread, NULL) != 0) {
+        eidos_thread_fail("join");
+    }
+    result = thread->result;
+    free(thread);
+    return result;
+}
+
+static void eidos_thread_sleep(int64_t milliseconds) {
+    struct timespec duration;
+    if (milliseconds < 0) {
+        milliseconds = 0;
+    }
+    duration.tv_sec = (time_t)(milliseconds / 1000);
+    duration.tv_nsec = (long)(milliseconds % 1000) * 1000000L;
+    while (nanosleep(&duration, &duration) != 0 && errno == EINTR) {
+    }
+}
+
+static int64_t eidos_thread_channel(void) {
+    eidos_channel *channel = (eidos_channel *)eidos_alloc(sizeof(eidos_channel));
+    pthread_mutex_init(&channel->lock, NULL);
+    pthread_cond_init(&channel->ready, NULL);
+    channel->head = NULL;
+    channel->tail = NULL;
+    return (int64_t)(intptr_t)channel;
+}
+
+static void eidos_thread_send(int64_t handle, int64_t value) {
+    eidos_channel *channel = (eidos_channel *)(intptr_t)handle;
+    eidos_channel_item *item = (eidos_channel_item *)eidos_alloc(sizeof(eidos_channel_item));
+    item->value = value;
+    item->next = NULL;
+    pthread_mutex_lock(&channel->lock);
+    if (channel->tail != NULL) {
+        channel->tail->next = item;
+    } else {
+        channel->head = item;
+    }
+    channel->tail = item;
+    pthread_cond_signal(&channel->ready);
+    pthread_mutex_unlock(&channel->lock);
+}
+
+static int64_t eidos_thread_recv(int64_t handle) {
+    eidos_channel *channel = (eidos_channel *)(intptr_t)handle;
+    eidos_channel_item *item;
+    int64_t value;
+    pthread_mutex_lock(&channel->lock);
+    while (channel->head == NULL) {
+        pthread_cond_wait(&channel->ready, &channel->lock);
+    }
+    item = channel->head;
+    channel->head = item->next;
+    if (channel->head == NULL) {
+        channel->tail = NULL;
+    }
+    pthread_mutex_unlock(&channel->lock);
+    value = item->value;
+    free(item);
+    return value;
+}
+"#;
+
+/// 実行時エラーのスタックトレースに使う呼び出しフレーム
+///
+/// 各関数は先頭で自身のフレームをスタック上に置いて `eidos_current_frame` につなぎ、戻る直前に外す。
+/// `line` は実行中の文の行番号で、生成コードが文ごとに更新する（0は位置が不明）。
+const FRAME_DEFINITIONS: &str = r#"typedef struct eidos_frame {
+    const char *function;
+    const char *file;
+    uint32_t line;
+    const struct eidos_frame *caller;
+} eidos_frame;
+
+static const eidos_frame *eidos_current_frame = NULL;
+"#;
+
+/// ランタイムに依存しない補助関数（文字列の比較と連結、検査付きの整数除算）
+const CORE_HELPERS: &str = r#"typedef uint8_t eidos_unit;
+
+static int64_t eidos_div_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("division overflow");
+    }
+    return a / b;
+}
+
+static int64_t eidos_rem_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("remainder overflow");
+    }
+    return a % b;
+}
+
+static bool eidos_string_eq(const char *a, const char *b) {
+    while (*a != '\0' && *a == *b) {
+        a++;
+        b++;
+    }
+    return *a == *b;
+}
+
+static const char *eidos_string_concat(const char *a, const char *b) {
+    size_t len_a = 0;
+    size_t len_b = 0;
+    size_t i;
+    char *result;
+    while (a[len_a] != '\0') {
+        len_a++;
+    }
+    while (b[len_b] != '\0') {
+        len_b++;
+    }
+    result = (char *)eidos_alloc(len_a + len_b + 1);
+    for (i = 0; i < len_a; i++) {
+        result[i] = a[i];
+    }
+    for (i = 0; i <= len_b; i++) {
+        result[len_a + i] = b[i];
+    }
+    return result;
+}
+"#;
+
+/// `u64` の検査付きの除算と出力（`uses_u64` のときだけ出力する）
+const U64_HELPERS: &str = r#"static uint64_t eidos_div_u64(uint64_t a, uint64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    return a / b;
+}
+
+static uint64_t eidos_rem_u64(uint64_t a, uint64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    return a % b;
+}
+
+static void eidos_print_u64(uint64_t value) {
+    if (value > (uint64_t)INT64_MAX) {
+        eidos_print_int((int64_t)(value / 10));
+        value %= 10;
+    }
+    eidos_print_int((int64_t)value);
+}
+"#;
+
+/// `HostedRuntime` のカバレッジ計測の実装
+///
+/// 最初のカウンタを増やすときに `atexit` で登録し、終了時にカウンタの値を JSON のプロファイルとして
+/// `EIDOS_COVERAGE_FILE`（未設定なら `<モジュール名>.profile.json`）に書き出す。
+const HOSTED_COVERAGE_HELPERS: &str = r#"static uint64_t eidos_cov_counters[EIDOS_COV_COUNTERS];
+
+static void eidos_cov_write(void) {
+    const char *path = getenv("EIDOS_COVERAGE_FILE");
+    FILE *file = fopen(path != NULL ? path : EIDOS_COV_PROFILE, "w");
+    size_t i;
+    if (file == NULL) {
+        fputs("eidos: cannot write the coverage profile\n", stderr);
+        return;
+    }
+    fputs("{\"module\": ", file);
+    fputs(EIDOS_COV_MODULE, file);
+    fputs(", \"counters\": [", file);
+    for (i = 0; i < EIDOS_COV_COUNTERS; i++) {
+        fprintf(file, i == 0 ? "%llu" : ", %llu", (unsigned long long)eidos_cov_counters[i]);
+    }
+    fputs("]}\n", file);
+    fclose(file);
+}
+
+static void eidos_cov_hit(int64_t counter) {
+    static bool registered = false;
+    if (!registered) {
+        registered = true;
+        atexit(eidos_cov_write);
+    }
+    eidos_cov_counters[counter]++;
+}
+
+static void eidos_cov_branch(int64_t counter, bool taken) { eidos_cov_hit(taken ? counter : counter + 1); }
+"#;
+
+/// 関数の実行頻度の見込みを伝える属性（`uses_hotness` のときだけ出力する）
+///
+/// GCCとClang以外のコンパイラでは何もしない。
+const HOTNESS_HELPERS: &str = r#"#if defined(__GNUC__)
+#define EIDOS_HOT __attribute__((hot))
+#define EIDOS_COLD __attribute__((cold))
+#else
+#define EIDOS_HOT
+#define EIDOS_COLD
+#endif
+"#;
+
+/// `Option` と `Result` の値の表現（`uses_variants` のときだけ出力する）
+///
+/// `ok` は `Some`・`Ok` かどうかで、中の値は型に応じて共用体のメンバーに入れる。
+/// `None` と `Err` も同じ構造体なので、`?` は失敗した値をそのまま呼び出し元に返せる。
+const VARIANT_HELPERS: &str = r#"typedef union {
+    int64_t i;
+    double f;
+    bool b;
+    uint32_t c;
+    const char *s;
+} eidos_payload;
+
+typedef struct {
+    bool ok;
+    eidos_payload value;
+} eidos_variant;
+
+static eidos_payload eidos_unwrap(eidos_variant variant, bool ok, const char *message) {
+    if (variant.ok != ok) {
+        eidos_panic(message);
+    }
+    return variant.value;
+}
+"#;
+
+/// スライスの値の表現（`uses_slices` のときだけ出力する）
+const SLICE_HELPERS: &str = r#"typedef struct {
+    char *data;
+    int64_t length;
+} eidos_slice;
+"#;
+
+/// 範囲の値の表現（`uses_ranges` のときだけ出力する）
+const RANGE_HELPERS: &str = r#"typedef struct {
+    int64_t start;
+    int64_t end;
+} eidos_range;
+"#;
+
+/// EIRをポータブルなC99ソースコードに変換するバックエンド
+///
+/// 基本ブロックはラベルと `goto` で、ブロックパラメータは分岐の直前に代入されるローカル変数で表す。
+/// 入出力などの組み込み関数は差し替え可能な `CRuntime` を通して生成する。
+pub struct CEmitter {
+    runtime: Box<dyn CRuntime>,
+    /// `declare_function` / `declare_global` で追加された宣言
+    declarations: Vec<String>,
+}
+
+impl CEmitter {
+    /// ホスト環境向けのランタイムを使うエミッタを作成
+    pub fn new() -> Self {
+        Self::with_runtime(Box::new(HostedRuntime))
+    }
+    
+    /// 指定したランタイムを使うエミッタを作成
+    pub fn with_runtime(runtime: Box<dyn CRuntime>) -> Self {
+        Self {
+            runtime,
+            declarations: Vec::new(),
+        }
+    }
+    
+    /// EIRモジュールをC99のソースコードに変換
+    pub fn emit(&self, module: &Module) -> Result<String> {
+        self.emit_with(module, &CodegenOptions::default())
+    }
+    
+    /// オプションに従ってEIRモジュールを変換
+    ///
+    /// デバッグ情報を含めるときは、命令の前に元のソースの位置を `#line` で書き、Cのデバッガやコンパイラの
+    /// 診断が `.eid` の行を指すようにする。位置に範囲があれば `options.sources` で行を求める。
+    fn emit_with(&self, module: &Module, options: &CodegenOptions) -> Result<String> {
+        info!("Cコードを生成中: {} (ランタイム: {})", module.name, self.runtime.name());
+        
+        // C99のコードではリストを表現できないので、`argv` を `List<String>` にして渡せない
+        if module.entry_abi().is_some_and(|abi| abi.takes_args) {
+            return Err(EidosError::BackendError(
+                "Cバックエンドはコマンドライン引数を受け取る main（fn main(args: List<String>)）に対応していません".to_string(),
+            ));
+        }
+        
+        // `fs` モジュールはPOSIXの関数で実装するため、使うときだけ補助関数を出力する
+        let fs_helpers = match uses_stdlib_module(module, "fs::") {
+            Some(function) => Some(self.runtime.fs_helpers().ok_or_else(|| {
+                EidosError::BackendError(format!(
+                    "ランタイム '{}' はファイルシステムを扱えません（'{}' の呼び出し）",
+                    self.runtime.name(),
+                    function
+                ))
+            })?),
+            None => None,
+        };
+        // `thread` モジュールも同様に、使うときだけPOSIXスレッドの補助関数を出力する
+        let thread_helpers = match uses_stdlib_module(module, "thread::") {
+            Some(function) => Some(self.runtime.thread_helpers().ok_or_else(|| {
+                EidosError::BackendError(format!(
+                    "ランタイム '{}' はスレッドを扱えません（'{}' の呼び出し）",
+                    self.runtime.name(),
+                    function
+                ))
+            })?),
+            None => None,
+        };
+        // `BigInt` の演算はCのランタイムに実装していない
+        if let Some(function) = uses_stdlib_module(module, "bigint::") {
+            return Err(EidosError::BackendError(format!(
+                "Cバックエンドは BigInt 型に対応していません（'{}' の呼び出し）。--emit js か eid run を使ってください",
+                function
+            )));
+        }
+        
+        let mut out = String::new();
+        let _ = writeln!(out, "/* Eidos module '{}' (runtime: {}) */", sanitize_comment(&module.name), self.runtime.name());
+        if fs_helpers.is_some() || thread_helpers.is_some() {
+            out.push_str("#define _POSIX_C_SOURCE 200809L\n");
+        }
+        out.push_str("#include <stdint.h>\n#include <stdbool.h>\n#include <stddef.h>\n\n");
+        // スレッドを使うときは、スタックトレースをたどる現在のフレームをスレッドごとに持つ
+        if thread_helpers.is_some() {
+            out.push_str(&FRAME_DEFINITIONS.replace("static const eidos_frame *", "static __thread const eidos_frame *"));
+        } else {
+            out.push_str(FRAME_DEFINITIONS);
+        }
+        out.push('\n');
+        out.push_str(&self.runtime.prelude());
+        out.push('\n');
+        out.push_str(CORE_HELPERS);
+        out.push('\n');
+        for helpers in fs_helpers.into_iter().chain(thread_helpers) {
+            out.push_str(&helpers);
+            out.push('\n');
+        }
+        if uses_u64(module) {
+            out.push_str(U64_HELPERS);
+            out.push('\n');
+        }
+        if uses_variants(module) {
+            out.push_str(VARIANT_HELPERS);
+            out.push('\n');
+        }
+        if uses_slices(module) {
+            out.push_str(SLICE_HELPERS);
+            out.push('\n');
+        }
+        if uses_ranges(module) {
+            out.push_str(RANGE_HELPERS);
+            out.push('\n');
+        }
+        if uses_hotness(module) {
+            out.push_str(HOTNESS_HELPERS);
+            out.push('\n');
+        }
+        if let Some(counters) = coverage::counter_count(module) {
+            let helpers = self.runtime.coverage_helpers(&module.name, counters).ok_or_else(|| {
+                EidosError::BackendError(format!("ランタイム '{}' はカバレッジの計測に対応していません", self.runtime.name()))
+            })?;
+            out.push_str(&helpers);
+            out.push('\n');
+        }
+        
+        for declaration in &self.declarations {
+            let _ = writeln!(out, "{}", declaration);
+        }
+        
+        // 外部関数と大域変数は名前順に出力し、生成結果を安定させる
+        let mut externals: Vec<_> = module.external_functions.values().collect();
+        externals.sort_by(|a, b| a.name.cmp(&b.name));
+        for external in externals {
+            let params = external
+                .parameter_types
+                .iter()
+                .map(|id| c_type_name(module, *id))
+                .collect::<Result<Vec<_>>>()?;
+            let mut params = if params.is_empty() && !external.is_variadic {
+                "void".to_string()
+            } else {
+                params.join(", ")
+            };
+            if external.is_variadic {
+                params.push_str(if params.is_empty() { "..." } else { ", ..." });
+            }
+            let _ = writeln!(out, "extern {} {}({});", return_type_name(module, external.return_type)?, external.name, params);
+        }
+        
+        let mut globals: Vec<_> = module.globals.values().collect();
+        globals.sort_by(|a, b| a.name.cmp(&b.name));
+        for global in globals {
+            let ty = c_type(module, global.ty)?;
+            // 定数は変数自体を const にして読み取り専用のデータに置く（文字列ならポインタも変更できない）
+            let qualifier = if global.attributes.is_constant { " const" } else { "" };
+            match &global.initializer {
+                Some(init) => {
+                    let _ = writeln!(out, "static {}{} {} = {};", ty.c_name(), qualifier, global_name(&global.name), literal_to_c(init));
+                }
+                None => {
+                    let _ = writeln!(out, "static {} {};", ty.c_name(), global_name(&global.name));
+                }
+            }
+        }
+        
+        let mut functions: Vec<&Function> = module.functions.values().collect();
+        functions.sort_by_key(|f| f.id.0);
+        
+        // 相互再帰に備えて、先にすべての関数のプロトタイプを宣言する
+        out.push('\n');
+        for function in &functions {
+            let hotness = match function.attributes.hotness {
+                Hotness::Normal => "",
+                Hotness::Hot => "EIDOS_HOT ",
+                Hotness::Cold => "EIDOS_COLD ",
+            };
+            let _ = writeln!(out, "{}{};", hotness, function_signature(module, function)?);
+        }
+        
+        for function in &functions {
+            debug!("Cコードを生成中: 関数 {}", function.name);
+            out.push('\n');
+            out.push_str(&FunctionEmitter::new(module, function, self.runtime.as_ref(), options).emit()?);
+        }
+        
+        if let Some(entry) = module.entry_point.and_then(|id| module.get_function(id)) {
+            let return_type = c_type(module, entry.return_type)?;
+            out.push('\n');
+            out.push_str(&self.runtime.entry_point(&function_name(&entry.name), return_type));
+        }
+        
+        Ok(out)
+    }
+}
+
+impl Backend for CEmitter {
+    fn name(&self) -> &str {
+        "c"
+    }
+    
+    fn compile(&mut self, module: &Module, options: &CodegenOptions) -> Result<Vec<u8>> {
+        Ok(self.emit_with(module, options)?.into_bytes())
+    }
+    
+    fn declare_function(&mut self, name: &str, params: &[Type], return_type: &Type) -> Result<()> {
+        let params = params
+            .iter()
+            .map(|ty| type_to_c(ty).map(|t| t.c_name()))
+            .collect::<Result<Vec<_>>>()?;
+        let params = if params.is_empty() { "void".to_string() } else { params.join(", ") };
+        let ret = match type_to_c(return_type)? {
+            CType::Unit => "void",
+            ty => ty.c_name(),
+        };
+        self.declarations.push(format!("extern {} {}({});", ret, name, params));
+        Ok(())
+    }
+    
+    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>, attributes: &GlobalAttributes) -> Result<()> {
+        let ty = type_to_c(ty)?;
+        let qualifier = if attributes.is_constant { " const" } else { "" };
+        self.declarations.push(match initializer {
+            Some(init) => format!("{}{} {} = {};", ty.c_name(), qualifier, name, literal_to_c(init)),
+            None => format!("extern {} {};", ty.c_name(), name),
+        });
+        Ok(())
+    }
+}
+
+/// 1つの関数のCコードを生成する
+struct FunctionEmitter<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    runtime: &'a dyn CRuntime,
+    /// `Alloca` で確保したスロット（Cではローカル変数へのポインタ）
+    slots: HashSet<RegisterId>,
+    /// `GetElementPtr` で求めた配列の要素（Cでは要素へのポインタ）
+    elements: HashSet<RegisterId>,
+    /// PHIノードのための、分岐元ブロックで行う代入
+    phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>>,
+    /// 分岐先になるブロック（ラベルが必要なもの）
+    targets: HashSet<BlockId>,
+    /// 最後に `frame.line` に書き込んだ行番号（ブロックの先頭で忘れる）
+    frame_line: Option<usize>,
+    /// 命令の前に `#line` を書くか
+    line_directives: bool,
+    /// 位置情報の範囲から行を求めるソースマップ
+    sources: Option<&'a SourceMap>,
+    body: String,
+}
+
+impl<'a> FunctionEmitter<'a> {
+    fn new(module: &'a Module, function: &'a Function, runtime: &'a dyn CRuntime, options: &'a CodegenOptions) -> Self {
+        let mut slots = HashSet::new();
+        let mut elements = HashSet::new();
+        let mut phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>> = HashMap::new();
+        let mut targets = HashSet::new();
+        // 同じブロックへの移動の順序が実行ごとに変わらないよう、ブロックIDの順に集める
+        let mut blocks: Vec<&BasicBlock> = function.blocks.values().collect();
+        blocks.sort_by_key(|block| block.id.0);
+        for block in blocks {
+            match &block.terminator {
+                Some(Terminator::Branch { target, .. }) => {
+                    targets.insert(*target);
+                }
+                Some(Terminator::BranchCond { true_target, false_target, .. }) => {
+                    targets.insert(*true_target);
+                    targets.insert(*false_target);
+                }
+                Some(Terminator::Switch { default_target, cases, .. }) => {
+                    targets.insert(*default_target);
+                    targets.extend(cases.iter().map(|(_, target, _)| *target));
+                }
+                _ => {}
+            }
+            
+            for (_, instruction) in &block.instructions {
+                match instruction {
+                    Instruction::Branch { target } => {
+                        targets.insert(*target);
+                    }
+                    Instruction::BranchCond { true_target, false_target, .. } => {
+                        targets.insert(*true_target);
+                        targets.insert(*false_target);
+                    }
+                    Instruction::Alloca { result, .. } => {
+                        slots.insert(*result);
+                    }
+                    Instruction::GetElementPtr { result, .. } => {
+                        elements.insert(*result);
+                    }
+                    Instruction::Phi { incoming, result } => {
+                        for (value, from) in incoming {
+                            phi_moves.entry(*from).or_default().push((*result, value.clone()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        
+        Self {
+            module,
+            function,
+            runtime,
+            slots,
+            elements,
+            phi_moves,
+            targets,
+            frame_line: None,
+            line_directives: options.debug_info,
+            sources: options.sources.as_ref(),
+            body: String::new(),
+        }
+    }
+    
+    /// 元のソースの位置（ソースマップがあれば範囲から求め直す）
+    fn source_location(&self, location: &SourceLocation) -> SourceLocation {
+        match self.sources {
+            Some(sources) => sources.resolve(location),
+            None => location.clone(),
+        }
+    }
+    
+    fn emit(mut self) -> Result<String> {
+        let param_count = self.function.parameters.len() as u32;
+        let mut out = format!("{} {{\n", function_signature(self.module, self.function)?);
+        
+        // パラメータ以外のレジスタはすべて関数の先頭で宣言する
+        let mut registers: Vec<_> = self.function.register_types.iter().collect();
+        registers.sort_by_key(|(reg, _)| reg.0);
+        for (reg, type_id) in registers {
+            if reg.0 < param_count {
+                continue;
+            }
+            let ty = c_type(self.module, *type_id)?;
+            if self.slots.contains(reg) && ty == CType::Array {
+                // 配列は8バイト境界にそろえた領域に置く
+                let size = self.module.get_type(*type_id).and_then(Type::layout).map_or(0, |(size, _)| size);
+                let _ = writeln!(out, "    int64_t {}_slot[{}];", register_name(*reg), size.div_ceil(8).max(1));
+                let _ = writeln!(out, "    char *{} = (char *){}_slot;", register_name(*reg), register_name(*reg));
+            } else if self.elements.contains(reg) {
+                let _ = writeln!(out, "    {} *{};", ty.c_name(), register_name(*reg));
+            } else if self.slots.contains(reg) {
+                let _ = writeln!(out, "    {} {}_slot;", ty.c_name(), register_name(*reg));
+                let _ = writeln!(out, "    {} *{} = &{}_slot;", ty.c_name(), register_name(*reg), register_name(*reg));
+            } else {
+                let _ = writeln!(out, "    {} {};", ty.c_name(), register_name(*reg));
+            }
+        }
+        
+        // スタックトレース用のフレームを呼び出し元のフレームにつなぐ
+        let (file, line) = match &self.function.location {
+            Some(location) => {
+                let location = self.source_location(location);
+                (location.file.display().to_string(), location.line)
+            }
+            None => (String::new(), 0),
+        };
+        let _ = writeln!(
+            out,
+            "    eidos_frame frame = {{ {}, {}, {}, eidos_current_frame }};",
+            string_literal(&self.function.name),
+            string_literal(&file),
+            line
+        );
+        out.push_str("    eidos_current_frame = &frame;\n");
+        
+        let mut block_ids: Vec<BlockId> = self.function.blocks.keys().copied().collect();
+        block_ids.sort_by_key(|id| (*id != self.function.entry_block, id.0));
+        for id in block_ids {
+            let block = &self.function.blocks[&id];
+            self.emit_block(block)?;
+        }
+        
+        out.push_str(&self.body);
+        out.push_str("}\n");
+        Ok(out)
+    }
+    
+    fn line(&mut self, text: &str) {
+        self.body.push_str("    ");
+        self.body.push_str(text);
+        self.body.push('\n');
+    }
+    
+    fn emit_block(&mut self, block: &BasicBlock) -> Result<()> {
+        if self.targets.contains(&block.id) {
+            let _ = writeln!(self.body, "{}:;", block_label(block.id));
+        }
+        
+        // ブロックには複数の分岐元から入るので、行番号は改めて書き込む
+        self.frame_line = None;
+        for (id, instruction) in &block.instructions {
+            self.update_frame_line(*id);
+            self.emit_instruction(block.id, *id, instruction)?;
+        }
+        
+        match &block.terminator {
+            Some(terminator) => self.emit_terminator(block.id, terminator),
+            None if block.instructions.iter().any(|(_, i)| is_control_instruction(i)) => Ok(()),
+            None => {
+                self.line("eidos_unreachable();");
+                Ok(())
+            }
+        }
+    }
+    
+    /// 命令のソース上の行が変わったら、フレームの行番号を更新する
+    fn update_frame_line(&mut self, id: InstructionId) {
+        let location = match self.function.get_location(id) {
+            Some(location) if location.line != 0 => self.source_location(location),
+            _ => return,
+        };
+        if self.frame_line != Some(location.line) {
+            self.frame_line = Some(location.line);
+            if self.line_directives {
+                let _ = writeln!(self.body, "#line {} {}", location.line, string_literal(&location.file.display().to_string()));
+            }
+            self.line(&format!("frame.line = {};", location.line));
+        }
+    }
+    
+    fn emit_instruction(&mut self, block: BlockId, id: InstructionId, instruction: &Instruction) -> Result<()> {
+        match instruction {
+            Instruction::BinaryOp { op, lhs, rhs, result } => {
+                let checked = !self.function.unchecked.contains(&id);
+                let expr = self.binary_expr(*op, lhs, rhs, checked)?;
+                self.line(&format!("{} = {};", register_name(*result), expr));
+            }
+            Instruction::UnaryOp { op, operand, result } => {
+                let value = self.operand(operand)?;
+                let expr = match op {
+                    UnaryOp::Neg => format!("-{}", value),
+                    UnaryOp::Not => format!("!{}", value),
+                    UnaryOp::BitNot => format!("~{}", value),
+                    UnaryOp::Cast => format!("({}){}", self.register_type(*result)?.c_name(), value),
+                };
+                self.line(&format!("{} = {};", register_name(*result), expr));
+            }
+            Instruction::Load { address, result } => {
+                let place = self.place(address)?;
+                self.line(&format!("{} = {};", register_name(*result), place));
+            }
+            Instruction::Store { address, value } => {
+                let place = self.place(address)?;
+                let value = self.operand(value)?;
+                self.line(&format!("{} = {};", place, value));
+            }
+            Instruction::Call { function, arguments, result } => {
+                self.emit_call(function, arguments, *result, false)?;
+            }
+            Instruction::ExternalCall { function, arguments, result } => {
+                self.emit_call(function, arguments, *result, true)?;
+            }
+            Instruction::Return { value } => {
+                self.emit_return(block, value.as_ref())?;
+            }
+            Instruction::Branch { target } => {
+                self.emit_jump(block, *target, &[])?;
+            }
+            Instruction::BranchCond { condition, true_target, false_target } => {
+                let terminator = Terminator::BranchCond {
+                    condition: condition.clone(),
+                    true_target: *true_target,
+                    true_args: Vec::new(),
+                    false_target: *false_target,
+                    false_args: Vec::new(),
+                };
+                self.emit_terminator(block, &terminator)?;
+            }
+            // スロットは関数の先頭で宣言済み
+            Instruction::Alloca { .. } => {}
+            Instruction::Cast { value, target_type, result } => {
+                let ty = c_type(self.module, *target_type)?;
+                let value = self.operand(value)?;
+                self.line(&format!("{} = ({}){};", register_name(*result), ty.c_name(), value));
+            }
+            // 分岐元のブロックで代入済み
+            Instruction::Phi { .. } => {}
+            Instruction::Select { condition, true_value, false_value, result } => {
+                let condition = self.operand(condition)?;
+                let true_value = self.operand(true_value)?;
+                let false_value = self.operand(false_value)?;
+                self.line(&format!("{} = {} ? {} : {};", register_name(*result), condition, true_value, false_value));
+            }
+            Instruction::DebugInfo { info } => {
+                self.line(&format!("/* {} */", sanitize_comment(info)));
+            }
+            Instruction::GetElementPtr { base, indices, result } => {
+                let ty = match base {
+                    Operand::Register(reg) => self.function.get_register_type(*reg).and_then(|id| self.module.get_type(id)),
+                    _ => None,
+                };
+                let (Some(ty), Some(index)) = (ty, indices.first()) else {
+                    return Err(unsupported("GetElementPtr"));
+                };
+                let stride = ty.element_type().and_then(Type::stride).ok_or_else(|| unsupported("GetElementPtr"))?;
+                let base = self.operand(base)?;
+                let data = match ty.kind {
+                    TypeKind::Slice(_) => format!("{}.data", base),
+                    _ => base,
+                };
+                let index = self.operand(index)?;
+                let element = self.register_type(*result)?;
+                self.line(&format!(
+                    "{} = ({} *)({} + {} * {});",
+                    register_name(*result),
+                    element.c_name(),
+                    data,
+                    index,
+                    stride
+                ));
+            }
+            Instruction::Atomic { op, address, value, expected, result } => {
+                self.emit_atomic(*op, address, value.as_ref(), expected.as_ref(), *result)?;
+            }
+            Instruction::InlineAsm { asm, constraints, args, result, dialect: AsmDialect::Native } => {
+                self.emit_inline_asm(asm, constraints, args, *result)?;
+            }
+            Instruction::InlineAsm { .. } => return Err(unsupported("wasm!")),
+            // タプルは範囲（始点と終点の組）だけを構造体で表す
+            Instruction::Tuple { elements, result } => match (self.register_type(*result)?, elements.as_slice()) {
+                (CType::Range, [start, end]) => {
+                    let start = self.operand(start)?;
+                    let end = self.operand(end)?;
+                    self.line(&format!("{} = (eidos_range){{ {}, {} }};", register_name(*result), start, end));
+                }
+                _ => return Err(unsupported("Tuple")),
+            },
+            Instruction::Extract { tuple, index, result } => {
+                let field = match (self.operand_type(tuple)?, index) {
+                    (CType::Range, 0) => "start",
+                    (CType::Range, 1) => "end",
+                    _ => return Err(unsupported("Extract")),
+                };
+                let tuple = self.operand(tuple)?;
+                self.line(&format!("{} = {}.{};", register_name(*result), tuple, field));
+            }
+            Instruction::MakeSlice { array, length, result } => {
+                let array = self.operand(array)?;
+                let length = self.operand(length)?;
+                self.line(&format!("{} = (eidos_slice){{ {}, {} }};", register_name(*result), array, length));
+            }
+            Instruction::SliceLen { slice, result } => {
+                let slice = self.operand(slice)?;
+                self.line(&format!("{} = {}.length;", register_name(*result), slice));
+            }
+        }
+        Ok(())
+    }
+    
+    fn emit_call(&mut self, function: &str, arguments: &[Operand], result: Option<RegisterId>, external: bool) -> Result<()> {
+        let mut args = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            args.push((self.operand(argument)?, self.operand_type(argument)?));
+        }
+        
+        // 同名のユーザー定義関数があれば組み込み関数より優先する
+        let user_function = if external { None } else { self.module.get_function_by_name(function) };
+        if !external && user_function.is_none() {
+            if let Some(name) = function.strip_prefix("fs::") {
+                return self.emit_helper_call("fs", FS_FUNCTIONS, name, args, result);
+            }
+            if let Some(name) = function.strip_prefix("thread::") {
+                // 起動する関数は関数ポインタとして渡す
+                if let ("spawn", [(entry, _)]) = (name, args.as_slice()) {
+                    let call = format!("eidos_thread_spawn({})", entry);
+                    match result {
+                        Some(result) => self.line(&format!("{} = {};", register_name(result), call)),
+                        None => self.line(&format!("{};", call)),
+                    }
+                    return Ok(());
+                }
+                return self.emit_helper_call("thread", THREAD_FUNCTIONS, name, args, result);
+            }
+            if function.starts_with("Option::") || function.starts_with("Result::") {
+                return self.emit_variant_call(function, args, result);
+            }
+            if let ("panic", [(message, CType::String)]) = (function, args.as_slice()) {
+                self.line(&format!("eidos_panic({});", message));
+                return Ok(());
+            }
+            if let Some(statement) = self.runtime.builtin_call(function, &args) {
+                self.line(&format!("{};", statement));
+                if let Some(result) = result {
+                    self.line(&format!("{} = 0;", register_name(result)));
+                }
+                return Ok(());
+            }
+        }
+        
+        // 組み込み関数はCの式に置き換える（置き換えられないものは関数の本体を呼び出す）
+        if let Some(intrinsic) = user_function.and_then(codegen::intrinsic_of) {
+            if let Some(template) = intrinsic.c {
+                let args: Vec<String> = args.into_iter().map(|(expr, _)| expr).collect();
+                let expr = Intrinsic::expand(template, &args);
+                match result {
+                    Some(result) if intrinsic.return_type != IntrinsicType::Unit => {
+                        self.line(&format!("{} = {};", register_name(result), expr));
+                    }
+                    Some(result) => {
+                        self.line(&format!("{};", expr));
+                        self.line(&format!("{} = 0;", register_name(result)));
+                    }
+                    None => self.line(&format!("{};", expr)),
+                }
+                return Ok(());
+            }
+        }
+        
+        let callee = match user_function {
+            Some(_) => function_name(function),
+            None if external => function.to_string(),
+            None => return Err(codegen::undefined_function(self.module, function)),
+        };
+        let call = format!(
+            "{}({})",
+            callee,
+            args.into_iter().map(|(expr, _)| expr).collect::<Vec<_>>().join(", ")
+        );
+        
+        let returns_unit = match user_function {
+            Some(target) => c_type(self.module, target.return_type)? == CType::Unit,
+            None => false,
+        };
+        match result {
+            Some(result) if !returns_unit => self.line(&format!("{} = {};", register_name(result), call)),
+            Some(result) => {
+                self.line(&format!("{};", call));
+                self.line(&format!("{} = 0;", register_name(result)));
+            }
+            None => self.line(&format!("{};", call)),
+        }
+        Ok(())
+    }
+    
+    /// インライン・アセンブリをGCC拡張の `__asm__` 文に変換する（テンプレートの `{N}` は `%N` にする）
+    fn emit_inline_asm(&mut self, asm: &str, constraints: &str, args: &[Operand], result: Option<RegisterId>) -> Result<()> {
+        let template: String = eir::parse_asm_template(asm)
+            .into_iter()
+            .map(|piece| match piece {
+                AsmPiece::Text(text) => text.replace('%', "%%"),
+                AsmPiece::Operand(index) => format!("%{}", index),
+            })
+            .collect();
+        
+        let mut outputs = Vec::new();
+        let mut inputs = Vec::new();
+        let mut clobbers = Vec::new();
+        let mut args = args.iter();
+        let mismatch = || EidosError::BackendError(format!("インライン・アセンブリの制約 \"{}\" とオペランドが一致しません", constraints));
+        for constraint in constraints.split(',').filter(|constraint| !constraint.is_empty()) {
+            if let Some(register) = constraint.strip_prefix("~{").and_then(|rest| rest.strip_suffix('}')) {
+                clobbers.push(string_literal(register));
+            } else if constraint.starts_with('=') {
+                let result = result.ok_or_else(mismatch)?;
+                outputs.push(format!("{}({})", string_literal(constraint), register_name(result)));
+            } else {
+                let arg = args.next().ok_or_else(mismatch)?;
+                inputs.push(format!("{}({})", string_literal(constraint), self.operand(arg)?));
+            }
+        }
+        self.line(&format!(
+            "__asm__ volatile ({} : {} : {} : {});",
+            string_literal(&template),
+            outputs.join(", "),
+            inputs.join(", "),
+            clobbers.join(", ")
+        ));
+        Ok(())
+    }
+    
+    /// アトミック操作をGCCとClangの `__atomic` 組み込み関数に変換する（順序はすべて逐次一貫）
+    fn emit_atomic(
+        &mut self,
+        op: AtomicOp,
+        address: &Operand,
+        value: Option<&Operand>,
+        expected: Option<&Operand>,
+        result: Option<RegisterId>,
+    ) -> Result<()> {
+        let place = format!("&{}", self.place(address)?);
+        let value = value.map(|value| self.operand(value)).transpose()?;
+        let operand = |name: &str| {
+            value.clone().ok_or_else(|| EidosError::BackendError(format!("アトミック操作 {} に値がありません", name)))
+        };
+        let expr = match op {
+            AtomicOp::Load => format!("__atomic_load_n({}, __ATOMIC_SEQ_CST)", place),
+            AtomicOp::Store => {
+                self.line(&format!("__atomic_store_n({}, {}, __ATOMIC_SEQ_CST);", place, operand("store")?));
+                if let Some(result) = result {
+                    self.line(&format!("{} = 0;", register_name(result)));
+                }
+                return Ok(());
+            }
+            AtomicOp::CAS => {
+                let expected = expected
+                    .ok_or_else(|| EidosError::BackendError("アトミック操作 cas に比較する値がありません".to_string()))?;
+                let expected = self.operand(expected)?;
+                // 比較する値は書き換えられるので、一時変数に置く
+                let ty = self.operand_type(address)?;
+                let call = format!(
+                    "__atomic_compare_exchange_n({}, &expected, {}, false, __ATOMIC_SEQ_CST, __ATOMIC_SEQ_CST)",
+                    place,
+                    operand("cas")?
+                );
+                self.line("{");
+                self.line(&format!("    {} expected = {};", ty.c_name(), expected));
+                match result {
+                    Some(result) => self.line(&format!("    {} = {};", register_name(result), call)),
+                    None => self.line(&format!("    {};", call)),
+                }
+                self.line("}");
+                return Ok(());
+            }
+            AtomicOp::Add | AtomicOp::Sub | AtomicOp::And | AtomicOp::Or | AtomicOp::Xor => {
+                let name = format!("{:?}", op).to_lowercase();
+                format!("__atomic_fetch_{}({}, {}, __ATOMIC_SEQ_CST)", name, place, operand(&name)?)
+            }
+        };
+        match result {
+            Some(result) => self.line(&format!("{} = {};", register_name(result), expr)),
+            None => self.line(&format!("{};", expr)),
+        }
+        Ok(())
+    }
+    
+    /// 標準ライブラリの `fs` や `thread` モジュールの関数を、ランタイムの補助関数の呼び出しに変換する
+    fn emit_helper_call(
+        &mut self,
+        module: &str,
+        functions: &[(&str, &str, &[CType], CType)],
+        name: &str,
+        args: Vec<(String, CType)>,
+        result: Option<RegisterId>,
+    ) -> Result<()> {
+        let (_, helper, params, return_type) = functions
+            .iter()
+            .find(|(function, ..)| *function == name)
+            .ok_or_else(|| {
+                EidosError::BackendError(format!("Cバックエンドは関数 '{}::{}' に対応していません", module, name))
+            })?;
+        let arg_types: Vec<CType> = args.iter().map(|(_, ty)| *ty).collect();
+        if arg_types != *params {
+            return Err(EidosError::BackendError(format!(
+                "'{}::{}' の引数の型が一致しません: 期待 ({}), 実際 ({})",
+                module,
+                name,
+                join_types(params),
+                join_types(&arg_types)
+            )));
+        }
+        
+        let call = format!(
+            "{}({})",
+            helper,
+            args.into_iter().map(|(expr, _)| expr).collect::<Vec<_>>().join(", ")
+        );
+        match result {
+            Some(result) if *return_type != CType::Unit => {
+                self.line(&format!("{} = {};", register_name(result), call));
+            }
+            Some(result) => {
+                self.line(&format!("{};", call));
+                self.line(&format!("{} = 0;", register_name(result)));
+            }
+            None => self.line(&format!("{};", call)),
+        }
+        Ok(())
+    }
+    
+    /// `Option` と `Result` の関数を、`eidos_variant` の構築やメンバーの参照に変換する
+    fn emit_variant_call(&mut self, function: &str, args: Vec<(String, CType)>, result: Option<RegisterId>) -> Result<()> {
+        let result_type = match result {
+            Some(result) => self.register_type(result)?,
+            None => CType::Unit,
+        };
+        let member = |ty: CType| {
+            ty.payload_member().ok_or_else(|| {
+                EidosError::BackendError(format!("Cバックエンドは入れ子の Option・Result に対応していません（'{}'）", function))
+            })
+        };
+        let arg = |index: usize| {
+            args.get(index).cloned().ok_or_else(|| {
+                EidosError::BackendError(format!("'{}' の引数が足りません", function))
+            })
+        };
+        
+        let expr = match function {
+            "Option::some" | "Result::ok" | "Result::err" => {
+                let (value, ty) = arg(0)?;
+                let ok = if function == "Result::err" { "false" } else { "true" };
+                format!("(eidos_variant){{ {}, {{ .{} = {} }} }}", ok, member(ty)?, value)
+            }
+            "Option::none" => "(eidos_variant){ false, { .i = 0 } }".to_string(),
+            "Option::is_some" | "Result::is_ok" => format!("{}.ok", arg(0)?.0),
+            "Option::is_none" | "Result::is_err" => format!("!{}.ok", arg(0)?.0),
+            "Option::unwrap" | "Result::unwrap" | "Result::unwrap_err" => {
+                let (ok, message) = match function {
+                    "Option::unwrap" => (true, "called `Option::unwrap()` on a `None` value"),
+                    "Result::unwrap" => (true, "called `Result::unwrap()` on an `Err` value"),
+                    _ => (false, "called `Result::unwrap_err()` on an `Ok` value"),
+                };
+                format!("eidos_unwrap({}, {}, {}).{}", arg(0)?.0, ok, string_literal(message), member(result_type)?)
+            }
+            "Option::unwrap_or" | "Result::unwrap_or" => {
+                let (variant, _) = arg(0)?;
+                format!("{}.ok ? {}.value.{} : {}", variant, variant, member(result_type)?, arg(1)?.0)
+            }
+            _ => return Err(EidosError::BackendError(format!("Cバックエンドは関数 '{}' に対応していません", function))),
+        };
+        match result {
+            Some(result) => self.line(&format!("{} = {};", register_name(result), expr)),
+            None => self.line(&format!("(void)({});", expr)),
+        }
+        Ok(())
+    }
+    
+    fn emit_return(&mut self, block: BlockId, value: Option<&Operand>) -> Result<()> {
+        self.emit_phi_moves(block)?;
+        let returns_unit = c_type(self.module, self.function.return_type)? == CType::Unit;
+        self.line("eidos_current_frame = frame.caller;");
+        match value {
+            Some(value) if !returns_unit => {
+                let value = self.operand(value)?;
+                self.line(&format!("return {};", value));
+            }
+            _ => self.line("return;"),
+        }
+        Ok(())
+    }
+    
+    /// 分岐先のブロックパラメータに引数を代入してからジャンプする
+    fn emit_jump(&mut self, from: BlockId, target: BlockId, args: &[Operand]) -> Result<()> {
+        self.emit_phi_moves(from)?;
+        
+        let params = self
+            .function
+            .get_block(target)
+            .map(|block| block.parameters.clone())
+            .unwrap_or_default();
+        if params.len() != args.len() {
+            return Err(EidosError::BackendError(format!(
+                "関数 '{}' のブロック {} への分岐の引数の数が一致しません",
+                self.function.name, target
+            )));
+        }
+        
+        // 引数が他のパラメータを参照していてもよいよう、いったん一時変数に退避する
+        if params.len() > 1 {
+            let mut temps = Vec::new();
+            for (i, ((param, _), arg)) in params.iter().zip(args).enumerate() {
+                let ty = self.register_type(*param)?;
+                let value = self.operand(arg)?;
+                temps.push(format!("{} tmp{} = {};", ty.c_name(), i, value));
+            }
+            self.line("{");
+            for temp in temps {
+                self.line(&format!("    {}", temp));
+            }
+            for (i, (param, _)) in params.iter().enumerate() {
+                self.line(&format!("    {} = tmp{};", register_name(*param), i));
+            }
+            self.line("}");
+        } else if let (Some((param, _)), Some(arg)) = (params.first(), args.first()) {
+            let value = self.operand(arg)?;
+            self.line(&format!("{} = {};", register_name(*param), value));
+        }
+        
+        self.line(&format!("goto {};", block_label(target)));
+        Ok(())
+    }
+    
+    fn emit_phi_moves(&mut self, from: BlockId) -> Result<()> {
+        if let Some(moves) = self.phi_moves.get(&from).cloned() {
+            for (result, value) in moves {
+                let value = self.operand(&value)?;
+                self.line(&format!("{} = {};", register_name(result), value));
+            }
+        }
+        Ok(())
+    }
+    
+    fn emit_terminator(&mut self, block: BlockId, terminator: &Terminator) -> Result<()> {
+        match terminator {
+            Terminator::Branch { target, args } => self.emit_jump(block, *target, args),
+            Terminator::BranchCond { condition, true_target, true_args, false_target, false_args } => {
+                let condition = self.operand(condition)?;
+                self.line(&format!("if ({}) {{", condition));
+                let true_branch = self.nested(|emitter| emitter.emit_jump(block, *true_target, true_args))?;
+                self.body.push_str(&true_branch);
+                self.line("} else {");
+                let false_branch = self.nested(|emitter| emitter.emit_jump(block, *false_target, false_args))?;
+                self.body.push_str(&false_branch);
+                self.line("}");
+                Ok(())
+            }
+            Terminator::Return { value } => self.emit_return(block, value.as_ref()),
+            Terminator::Switch { value, default_target, default_args, cases } => {
+                let value = self.operand(value)?;
+                self.line(&format!("switch ({}) {{", value));
+                for (literal, target, args) in cases {
+                    self.line(&format!("case {}:", literal_to_c(literal)));
+                    let case = self.nested(|emitter| emitter.emit_jump(block, *target, args))?;
+                    self.body.push_str(&case);
+                }
+                self.line("default:");
+                let default = self.nested(|emitter| emitter.emit_jump(block, *default_target, default_args))?;
+                self.body.push_str(&default);
+                self.line("}");
+                Ok(())
+            }
+            Terminator::IndirectCall { .. } => Err(unsupported("IndirectCall")),
+            Terminator::Unreachable => {
+                self.line("eidos_unreachable();");
+                Ok(())
+            }
+        }
+    }
+    
+    /// 1段深いインデントで生成したコードを返す
+    fn nested<F>(&mut self, emit: F) -> Result<String>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let outer = std::mem::take(&mut self.body);
+        let result = emit(self);
+        let inner = std::mem::replace(&mut self.body, outer);
+        result?;
+        Ok(inner.lines().map(|line| format!("    {}\n", line)).collect())
+    }
+    
+    /// `checked` が偽の除算・剰余は、値域解析で0除算もオーバーフローも起きないと分かっているので検査しない
+    fn binary_expr(&self, op: BinaryOp, lhs: &Operand, rhs: &Operand, checked: bool) -> Result<String> {
+        let ty = self.operand_type(lhs)?;
+        let a = self.operand(lhs)?;
+        let b = self.operand(rhs)?;
+        
+        if ty == CType::String {
+            return match op {
+                BinaryOp::Add => Ok(format!("eidos_string_concat({}, {})", a, b)),
+                BinaryOp::Eq => Ok(format!("eidos_string_eq({}, {})", a, b)),
+                BinaryOp::Ne => Ok(format!("!eidos_string_eq({}, {})", a, b)),
+                _ => Err(EidosError::BackendError(format!("文字列に対する演算 {} はCバックエンドでは未対応です", op))),
+            };
+        }
+        if ty == CType::Variant {
+            return Err(EidosError::BackendError(format!("Option・Result に対する演算 {} はCバックエンドでは未対応です", op)));
+        }
+        let is_float = ty == CType::Float || ty == CType::Sized(NumericType::F32);
+        if is_float && matches!(op, BinaryOp::Rem | BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::Shl | BinaryOp::Shr) {
+            return Err(EidosError::BackendError(format!("浮動小数点数に対する演算 {} はCバックエンドでは未対応です", op)));
+        }
+        // 整数の0除算はCでは未定義動作なので、検査してスタックトレースを出す補助関数を使う
+        if ty.is_integer() && checked {
+            let unsigned = ty == CType::Sized(NumericType::U64);
+            match op {
+                BinaryOp::Div if unsigned => return Ok(format!("eidos_div_u64({}, {})", a, b)),
+                BinaryOp::Rem if unsigned => return Ok(format!("eidos_rem_u64({}, {})", a, b)),
+                BinaryOp::Div => return Ok(format!("eidos_div_i64({}, {})", a, b)),
+                BinaryOp::Rem => return Ok(format!("eidos_rem_i64({}, {})", a, b)),
+                _ => {}
+            }
+        }
+        
+        let symbol = match op {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        };
+        // シフト量はビット幅で剰余をとる（ビット幅以上のシフトはCでは未定義動作）
+        let b = match (op, ty) {
+            (BinaryOp::Shl | BinaryOp::Shr, CType::Sized(numeric)) => format!("({} & {})", b, numeric.bits() - 1),
+            (BinaryOp::Shl | BinaryOp::Shr, _) => format!("({} & 63)", b),
+            _ => b,
+        };
+        // ビット幅を指定した整数型は符号なしで計算して、結果をレジスタの型に代入するときに折り返す
+        // （`int` への汎整数拡張による符号付きオーバーフローの未定義動作を避ける）
+        if let CType::Sized(numeric) = ty {
+            if !numeric.is_float() && matches!(op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Shl) {
+                let unsigned = if numeric.bits() == 64 { "uint64_t" } else { "uint32_t" };
+                return Ok(format!("({}){} {} ({}){}", unsigned, a, symbol, unsigned, b));
+            }
+        }
+        // 負の数の左シフトも未定義動作なので、`Int` も符号なしで計算する
+        if ty == CType::Int && op == BinaryOp::Shl {
+            return Ok(format!("(uint64_t){} << {}", a, b));
+        }
+        Ok(format!("{} {} {}", a, symbol, b))
+    }
+    
+    fn operand(&self, operand: &Operand) -> Result<String> {
+        match operand {
+            Operand::Register(reg) => Ok(register_name(*reg)),
+            Operand::Literal(literal) => Ok(literal_to_c(literal)),
+            Operand::Global(name) => {
+                if self.module.get_global(name).is_some() {
+                    Ok(global_name(name))
+                } else if self.module.get_function_by_name(name).is_some() {
+                    Ok(function_name(name))
+                } else {
+                    Err(EidosError::BackendError(format!("未定義のシンボル '{}' を参照しています", name)))
+                }
+            }
+            Operand::Function(id) => match self.module.get_function(*id) {
+                Some(function) => Ok(function_name(&function.name)),
+                None => Err(EidosError::BackendError(format!("未定義の関数 {} を参照しています", id))),
+            },
+            Operand::ExternalFunction(name) => Ok(name.clone()),
+            Operand::Symbol(_) | Operand::Block(_) => Err(EidosError::BackendError(format!(
+                "オペランド {} はCバックエンドでは表現できません",
+                operand
+            ))),
+        }
+    }
+    
+    /// `Load` と `Store` のアドレスが指す場所（グローバル変数は変数そのもの）
+    fn place(&self, address: &Operand) -> Result<String> {
+        match address {
+            Operand::Global(name) if self.module.get_global(name).is_some() => Ok(global_name(name)),
+            _ => Ok(format!("*{}", self.operand(address)?)),
+        }
+    }
+    
+    fn operand_type(&self, operand: &Operand) -> Result<CType> {
+        match operand {
+            Operand::Register(reg) => self.register_type(*reg),
+            Operand::Literal(literal) => Ok(match literal {
+                Literal::Int(_) => CType::Int,
+                Literal::Float(_) => CType::Float,
+                Literal::Bool(_) => CType::Bool,
+                Literal::Char(_) => CType::Char,
+                Literal::String(_) => CType::String,
+                Literal::Unit => CType::Unit,
+            }),
+            Operand::Global(name) => match self.module.get_global(name) {
+                Some(global) => c_type(self.module, global.ty),
+                None => Ok(CType::Int),
+            },
+            _ => Ok(CType::Int),
+        }
+    }
+    
+    fn register_type(&self, reg: RegisterId) -> Result<CType> {
+        match self.function.get_register_type(reg) {
+            Some(type_id) => c_type(self.module, type_id),
+            None => Err(EidosError::BackendError(format!(
+                "関数 '{}' のレジスタ {} の型が見つかりません",
+                self.function.name, reg
+            ))),
+        }
+    }
+}
+
+/// モジュールが標準ライブラリのモジュール（`fs::` のような接頭辞）を呼び出していれば、関数とブロックのIDの順で最初の関数名を返す
+fn uses_stdlib_module<'a>(module: &'a Module, prefix: &str) -> Option<&'a str> {
+    let mut functions: Vec<&Function> = module.functions.values().collect();
+    functions.sort_by_key(|function| function.id.0);
+    functions
+        .into_iter()
+        .flat_map(|function| {
+            let mut blocks: Vec<&BasicBlock> = function.blocks.values().collect();
+            blocks.sort_by_key(|block| block.id.0);
+            blocks
+        })
+        .flat_map(|block| block.instructions.iter())
+        .find_map(|(_, instruction)| match instruction {
+            Instruction::Call { function, .. }
+                if function.starts_with(prefix) && module.get_function_by_name(function).is_none() =>
+            {
+                Some(function.as_str())
+            }
+            _ => None,
+        })
+}
+
+/// モジュールが `u64` の値を扱っているかどうか
+fn uses_u64(module: &Module) -> bool {
+    module.types.values().any(|ty| matches!(ty.kind, TypeKind::Numeric(NumericType::U64)))
+}
+
+/// モジュールが `Option` か `Result` の値を扱っているかどうか
+fn uses_variants(module: &Module) -> bool {
+    module.types.values().any(Type::is_fallible)
+}
+
+/// モジュールがスライスの型を使うかどうか
+fn uses_slices(module: &Module) -> bool {
+    module.types.values().any(|ty| matches!(ty.kind, TypeKind::Slice(_)))
+}
+
+/// モジュールが範囲の型を使うかどうか
+fn uses_ranges(module: &Module) -> bool {
+    module.types.values().any(|ty| CType::from_type(ty) == Some(CType::Range))
+}
+
+/// モジュールに実行頻度の見込みを持つ関数があるかどうか
+fn uses_hotness(module: &Module) -> bool {
+    module.functions.values().any(|function| function.attributes.hotness != Hotness::Normal)
+}
+
+fn unsupported(instruction: &str) -> EidosError {
+    EidosError::BackendError(format!("Cバックエンドは {} 命令に対応していません", instruction))
+}
+
+fn is_control_instruction(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Return { .. } | Instruction::Branch { .. } | Instruction::BranchCond { .. })
+}
+
+fn join_types(types: &[CType]) -> String {
+    types.iter().map(CType::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn type_to_c(ty: &Type) -> Result<CType> {
+    CType::from_type(ty).ok_or_else(|| {
+        EidosError::BackendError(format!("型 {} はCバックエンドでは未対応です", ty))
+    })
+}
+
+fn c_type(module: &Module, id: TypeId) -> Result<CType> {
+    match module.get_type(id) {
+        Some(ty) => type_to_c(ty),
+        None => Err(EidosError::BackendError(format!("型 {} がモジュールに登録されていません", id))),
+    }
+}
+
+fn c_type_name(module: &Module, id: TypeId) -> Result<&'static str> {
+    c_type(module, id).map(|ty| ty.c_name())
+}
+
+fn return_type_name(module: &Module, id: TypeId) -> Result<&'static str> {
+    Ok(match c_type(module, id)? {
+        CType::Unit => "void",
+        ty => ty.c_name(),
+    })
+}
+
+fn function_signature(module: &Module, function: &Function) -> Result<String> {
+    let mut params = Vec::with_capacity(function.parameters.len());
+    for (i, (_, type_id)) in function.parameters.iter().enumerate() {
+        // パラメータは add_parameter により先頭から順にレジスタが割り当てられている
+        params.push(format!("{} {}", c_type_name(module, *type_id)?, register_name(RegisterId(i as u32))));
+    }
+    let params = if params.is_empty() { "void".to_string() } else { params.join(", ") };
+    Ok(format!("{} {}({})", return_type_name(module, function.return_type)?, function_name(&function.name), params))
+}
+
+/// Eidosの関数名をマングリングしたシンボル名に変換（Cの予約語や `main`、外部関数と衝突しない）
+fn function_name(name: &str) -> String {
+    Symbol::from_qualified(name).mangle()
+}
+
+fn global_name(name: &str) -> String {
+    Symbol::from_qualified(name).mangle()
+}
+
+fn register_name(reg: RegisterId) -> String {
+    format!("r{}", reg.0)
+}
+
+fn block_label(id: BlockId) -> String {
+    format!("bb{}", id.0)
+}
+
+fn sanitize_comment(text: &str) -> String {
+    text.replace("*/", "* /")
+}
+
+fn literal_to_c(literal: &Literal) -> String {
+    match literal {
+        Literal::Int(value) if *value == i64::MIN => "INT64_MIN".to_string(),
+        Literal::Int(value) => format!("INT64_C({})", value),
+        Literal::Float(value) if value.is_nan() => "(0.0 / 0.0)".to_string(),
+        Literal::Float(value) if value.is_infinite() => {
+            if *value > 0.0 { "(1.0 / 0.0)".to_string() } else { "(-1.0 / 0.0)".to_string() }
+        }
+        Literal::Float(value) => format!("{:?}", value),
+        Literal::Bool(value) => value.to_string(),
+        Literal::Char(value) => format!("UINT32_C({})", value),
+        Literal::String(value) => string_literal(value),
+        Literal::Unit => "0".to_string(),
+    }
+}
+
+/// UTF-8のバイト列をCの文字列リテラルにする（ASCII以外は8進エスケープ）
+fn string_literal(value: &str) -> String {
+    let mut out = String::from("\"");
+    for byte in value.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            // トライグラフとして解釈されないようにする
+            b'?' => out.push_str("\\?"),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            b'\r' => out.push_str("\\r"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => {
+                let _ = write!(out, "\\{:03o}", byte);
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::{FunctionId, Global, Linkage};
+    
+    fn add_function(module: &mut Module) -> FunctionId {
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int(), Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "add", func_type, int);
+        let a = function.add_parameter("a", int);
+        let b = function.add_parameter("b", int);
+        let sum = function.create_register(int);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::BinaryOp {
+            op: BinaryOp::Add,
+            lhs: Operand::Register(a),
+            rhs: Operand::Register(b),
+            result: sum,
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(sum)),
+        });
+        module.add_function(function)
+    }
+    
+    #[test]
+    fn test_emit_function() {
+        let mut module = Module::new("test");
+        add_function(&mut module);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.contains("int64_t _E3add(int64_t r0, int64_t r1);"));
+        assert!(source.contains("r2 = r0 + r1;"));
+        assert!(source.contains("return r2;"));
+        // エントリーポイントがなければ main は生成しない
+        assert!(!source.contains("int main("));
+        assert!(!source.contains("EIDOS_COLD"));
+    }
+    
+    #[test]
+    fn test_hotness_attributes() {
+        let mut module = Module::new("test");
+        let id = add_function(&mut module);
+        module.functions.get_mut(&id).unwrap().attributes.hotness = Hotness::Cold;
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.contains("#define EIDOS_COLD __attribute__((cold))"), "{}", source);
+        assert!(source.contains("EIDOS_COLD int64_t _E3add(int64_t r0, int64_t r1);"), "{}", source);
+    }
+    
+    #[test]
+    fn test_block_parameters_and_entry_point() {
+        let mut module = Module::new("test");
+        let unit = module.add_type(Type::unit());
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(Vec::new(), Type::unit()));
+        let mut function = Function::new(FunctionId(0), "main", func_type, unit);
+        let join = function.create_block();
+        let value = function.create_register(int);
+        function.get_block_mut(join).unwrap().add_parameter(value, int);
+        function.add_instruction(join, Instruction::Call {
+            function: "println".to_string(),
+            arguments: vec![Operand::Register(value)],
+            result: None,
+        });
+        function.get_block_mut(join).unwrap().set_terminator(Terminator::Return { value: None });
+        let entry = function.entry_block;
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Branch {
+            target: join,
+            args: vec![Operand::Literal(Literal::Int(42))],
+        });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.contains("r0 = INT64_C(42);\n    goto bb1;"));
+        assert!(source.contains("eidos_print_int(r0); eidos_print_newline();"));
+        assert!(source.contains("void _E4main(void)"));
+        assert!(source.contains("int main(int argc, char **argv)"));
+        
+        let freestanding = CEmitter::with_runtime(Box::new(FreestandingRuntime::new().with_entry_symbol("app_main")))
+            .emit(&module)
+            .unwrap();
+        assert!(freestanding.contains("extern void eidos_putchar(int c);"));
+        assert!(freestanding.contains("void app_main(void) {\n    _E4main();\n}"));
+        assert!(!freestanding.contains("int main("));
+    }
+    
+    #[test]
+    fn test_entry_point_exit_code() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![], Type::int()));
+        let mut function = Function::new(FunctionId(0), "main", func_type, int);
+        let entry = function.entry_block;
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return { value: Some(Operand::Literal(Literal::Int(3))) });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+        
+        // `Int` の戻り値はプロセスの終了コードになる
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.contains("    return (int)_E4main();\n"), "{}", source);
+        
+        let args = module.add_type(Type::array(Type::string()));
+        let main = module.functions.get_mut(&id).unwrap();
+        main.add_parameter("args", args);
+        assert_eq!(module.entry_abi().map(|abi| (abi.takes_args, abi.returns_exit_code)), Some((true, true)));
+        let error = CEmitter::new().emit(&module).unwrap_err();
+        assert!(error.to_string().contains("コマンドライン引数を受け取る main"), "{}", error);
+    }
+    
+    #[test]
+    fn test_stack_frames_and_checked_division() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int(), Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "divide", func_type, int);
+        function.location = Some(SourceLocation::new("math.eid".into(), 3, 1, 2));
+        let a = function.add_parameter("a", int);
+        let b = function.add_parameter("b", int);
+        let quotient = function.create_register(int);
+        let entry = function.entry_block;
+        let div = function.add_instruction(entry, Instruction::BinaryOp {
+            op: BinaryOp::Div,
+            lhs: Operand::Register(a),
+            rhs: Operand::Register(b),
+            result: quotient,
+        });
+        function.set_location(div, SourceLocation::new("math.eid".into(), 4, 5, 5));
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(quotient)),
+        });
+        module.add_function(function);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.contains("eidos_frame frame = { \"divide\", \"math.eid\", 3, eidos_current_frame };"));
+        assert!(source.contains("frame.line = 4;\n    r2 = eidos_div_i64(r0, r1);"));
+        assert!(source.contains("eidos_current_frame = frame.caller;\n    return r2;"));
+        assert!(source.contains("eidos_panic(\"division by zero\");"));
+    }
+    
+    #[test]
+    fn test_line_directives_resolve_spans() {
+        let sources = SourceMap::new();
+        let text = "fn divide(a, b) {\n    // 割り算\n    a / b\n}\n";
+        let file = sources.add("math.eid".into(), text.to_string());
+        let start = text.find("a / b").unwrap();
+        
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int(), Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "divide", func_type, int);
+        let a = function.add_parameter("a", int);
+        let b = function.add_parameter("b", int);
+        let quotient = function.create_register(int);
+        let entry = function.entry_block;
+        let div = function.add_instruction(entry, Instruction::BinaryOp {
+            op: BinaryOp::Div,
+            lhs: Operand::Register(a),
+            rhs: Operand::Register(b),
+            result: quotient,
+        });
+        // 行と列は範囲から求め直す
+        let location = SourceLocation::new("<unknown>".into(), 1, 1, 1).with_span(file.span_of(start..start + 5));
+        function.set_location(div, location);
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(quotient)),
+        });
+        module.add_function(function);
+        
+        let options = CodegenOptions { debug_info: true, sources: Some(sources), ..Default::default() };
+        let source = String::from_utf8(CEmitter::new().compile(&module, &options).unwrap()).unwrap();
+        assert!(source.contains("#line 3 \"math.eid\"\n    frame.line = 3;"), "{}", source);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(!source.contains("#line"));
+        assert!(source.contains("frame.line = 1;"));
+    }
+    
+    #[test]
+    fn test_fs_calls_use_runtime_helpers() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(Vec::new(), Type::int()));
+        let mut function = Function::new(FunctionId(0), "main", func_type, int);
+        let size = function.create_register(int);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::Call {
+            function: "fs::size".to_string(),
+            arguments: vec![Operand::Literal(Literal::String("data.txt".to_string()))],
+            result: Some(size),
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(size)),
+        });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.starts_with("/* Eidos module 'test' (runtime: hosted) */\n#define _POSIX_C_SOURCE 200809L\n"));
+        assert!(source.contains("static int64_t eidos_fs_size(const char *path)"));
+        assert!(source.contains("r0 = eidos_fs_size(\"data.txt\");"));
+        
+        let error = CEmitter::with_runtime(Box::new(FreestandingRuntime::new())).emit(&module).unwrap_err();
+        assert!(error.to_string().contains("fs::size"));
+    }
+    
+    #[test]
+    fn test_intrinsic_call_becomes_builtin() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut ctpop = Function::new(FunctionId(0), "ctpop", func_type, int);
+        let x = ctpop.add_parameter("x", int);
+        ctpop.attributes.tags.insert(codegen::INTRINSIC_ATTRIBUTE.to_string());
+        let entry = ctpop.entry_block;
+        ctpop.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(x)),
+        });
+        module.add_function(ctpop);
+        let main_type = module.add_type(Type::function(Vec::new(), Type::int()));
+        let mut main = Function::new(FunctionId(1), "main", main_type, int);
+        let count = main.create_register(int);
+        let entry = main.entry_block;
+        main.add_instruction(entry, Instruction::Call {
+            function: "ctpop".to_string(),
+            arguments: vec![Operand::Literal(Literal::Int(11))],
+            result: Some(count),
+        });
+        main.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(count)),
+        });
+        let id = module.add_function(main);
+        module.set_entry_point(id);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.contains("r0 = (int64_t)__builtin_popcountll((uint64_t)INT64_C(11));"), "{}", source);
+        codegen::check_intrinsics(&module).unwrap();
+        
+        // 表にない関数には付けられない
+        module.get_function_mut(id).unwrap().attributes.tags.insert(codegen::INTRINSIC_ATTRIBUTE.to_string());
+        let error = codegen::check_intrinsics(&module).unwrap_err().to_string();
+        assert!(error.contains("'main' は組み込み関数ではありません"), "{}", error);
+    }
+    
+    #[test]
+    fn test_inline_asm() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "count", func_type, int);
+        let x = function.add_parameter("x", int);
+        let count = function.create_register(int);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::InlineAsm {
+            asm: "popcnt {1}, {0} # 100%".to_string(),
+            constraints: "=r,r,~{cc}".to_string(),
+            args: vec![Operand::Register(x)],
+            result: Some(count),
+            dialect: AsmDialect::Native,
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(count)),
+        });
+        module.add_function(function);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        assert!(source.contains("__asm__ volatile (\"popcnt %1, %0 # 100%%\" : \"=r\"(r1) : \"r\"(r0) : \"cc\");"), "{}", source);
+        
+        let function = module.get_function_mut(FunctionId(0)).unwrap();
+        if let Some((_, Instruction::InlineAsm { dialect, .. })) = function.get_block_mut(entry).unwrap().instructions.first_mut() {
+            *dialect = AsmDialect::Wasm;
+        }
+        assert!(CEmitter::new().emit(&module).unwrap_err().to_string().contains("wasm!"));
+    }
+    
+    #[test]
+    fn test_threads_and_atomics() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let boolean = module.add_type(Type::bool());
+        module.add_global("counter", Global {
+            name: "counter".to_string(),
+            ty: int,
+            initializer: Some(Literal::Int(0)),
+            linkage: Linkage::Internal,
+            alignment: None,
+            attributes: GlobalAttributes::default(),
+        });
+        let func_type = module.add_type(Type::function(Vec::new(), Type::int()));
+        let counter = Operand::Global("counter".to_string());
+        
+        let mut worker = Function::new(FunctionId(0), "worker", func_type, int);
+        let previous = worker.create_register(int);
+        let swapped = worker.create_register(boolean);
+        let entry = worker.entry_block;
+        worker.add_instruction(entry, Instruction::Atomic {
+            op: AtomicOp::Add,
+            address: counter.clone(),
+            value: Some(Operand::Literal(Literal::Int(1))),
+            expected: None,
+            result: Some(previous),
+        });
+        worker.add_instruction(entry, Instruction::Atomic {
+            op: AtomicOp::CAS,
+            address: counter,
+            value: Some(Operand::Literal(Literal::Int(10))),
+            expected: Some(Operand::Literal(Literal::Int(1))),
+            result: Some(swapped),
+        });
+        worker.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(previous)),
+        });
+        module.add_function(worker);
+        
+        let mut main = Function::new(FunctionId(1), "main", func_type, int);
+        let thread = main.create_register(int);
+        let result = main.create_register(int);
+        let entry = main.entry_block;
+        main.add_instruction(entry, Instruction::Call {
+            function: "thread::spawn".to_string(),
+            arguments: vec![Operand::Global("worker".to_string())],
+            result: Some(thread),
+        });
+        main.add_instruction(entry, Instruction::Call {
+            function: "thread::join".to_string(),
+            arguments: vec![Operand::Register(thread)],
+            result: Some(result),
+        });
+        main.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(result)),
+        });
+        let id = module.add_function(main);
+        module.set_entry_point(id);
+        
+        let source = CEmitter::new().emit(&module).unwrap();
+        let counter = global_name("counter");
+        assert!(source.contains("static __thread const eidos_frame *eidos_current_frame"), "{}", source);
+        assert!(source.contains(&format!("r0 = __atomic_fetch_add(&{}, INT64_C(1), __ATOMIC_SEQ_CST);", counter)), "{}", source);
+        assert!(source.contains(&format!(
+            "    {{\n        int64_t expected = INT64_C(1);\n        r1 = __atomic_compare_exchange_n(&{}, &expected, INT64_C(10), false, __ATOMIC_SEQ_CST, __ATOMIC_SEQ_CST);\n    }}\n",
+            counter
+        )), "{}", source);
+        assert!(source.contains(&format!("r0 = eidos_thread_spawn({});", function_name("worker"))), "{}", source);
+        assert!(source.contains("r1 = eidos_thread_join(r0);"), "{}", source);
+        
+        let error = CEmitter::with_runtime(Box::new(FreestandingRuntime::new())).emit(&module).unwrap_err();
+        assert!(error.to_string().contains("thread::spawn"));
+    }
+    
+    #[test]
+    fn test_string_literal_escapes() {
+        assert_eq!(string_literal("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(string_literal("??="), "\"\\?\\?=\"");
+        assert_eq!(string_literal("é"), "\"\\303\\251\"");
+    }
+}
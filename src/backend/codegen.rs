@@ -1,15 +1,21 @@
 use std::path::Path;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use log::{info, debug, error};
 
 use crate::core::{Result, EidosError};
-use crate::core::eir::{Module, Function, FunctionId, BlockId, InstructionId, RegisterId, Instruction, Operand, Literal};
-use crate::core::types::{Type, TypeId};
+use crate::core::error::did_you_mean;
+use crate::core::eir::{Module, Function, FunctionId, BlockId, InstructionId, RegisterId, Instruction, Operand, Literal, GlobalAttributes};
+use crate::core::types::{Type, TypeId, TypeKind};
 use crate::core::symbol::SymbolId;
+use crate::core::session::CompileSession;
+use crate::core::source_map::SourceMap;
 
 use super::llvm::LLVMBackend;
 use super::wasm::WasmBackend;
+use super::c_emitter::{CEmitter, CRuntime};
+use super::js::JsBackend;
 
 /// 出力コードの形式
 pub enum OutputFormat {
@@ -23,6 +29,10 @@ pub enum OutputFormat {
     Wasm,
     /// WebAssembly テキスト形式
     Wat,
+    /// C99 ソースコード
+    C,
+    /// JavaScript（ESモジュール）
+    JavaScript,
 }
 
 /// コード生成のターゲット
@@ -35,6 +45,101 @@ pub enum Target {
     Wasm,
 }
 
+/// 再配置モデル
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum RelocationModel {
+    /// ターゲットの既定
+    #[default]
+    Default,
+    /// 固定アドレスに配置する
+    Static,
+    /// 位置独立コード（共有ライブラリ向け）
+    Pic,
+    /// 位置独立ではないが、共有ライブラリのシンボルを参照できる
+    DynamicNoPic,
+}
+
+/// コードモデル（コードとデータを置けるアドレスの範囲）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CodeModel {
+    /// ターゲットの既定
+    #[default]
+    Default,
+    /// 下位2GBに収まる
+    Small,
+    /// OSカーネル向け（上位2GBに収まる）
+    Kernel,
+    /// コードは下位2GB、データは制限なし
+    Medium,
+    /// 制限なし
+    Large,
+}
+
+/// リンク時最適化の方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LtoMode {
+    /// モジュールごとに最適化したビットコードを出力し、モジュールをまたぐ最適化はリンカに任せる
+    Thin,
+    /// すべてのモジュールを1つにまとめてから最適化する
+    Full,
+}
+
+/// `--codegen` に `キー=値` で渡す設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodegenSetting {
+    /// `cpu=<名前>`（`native` ならホストのCPU）
+    Cpu(String),
+    /// `features=+avx2,-neon` のような、有効・無効にするCPUの機能
+    Features(Vec<String>),
+}
+
+impl std::str::FromStr for CodegenSetting {
+    type Err = String;
+    
+    fn from_str(setting: &str) -> std::result::Result<Self, String> {
+        let Some((key, value)) = setting.split_once('=') else {
+            return Err(format!("'{}' は キー=値 の形式ではありません（例: cpu=native）", setting));
+        };
+        if value.is_empty() {
+            return Err(format!("{} の値が空です", key));
+        }
+        match key {
+            "cpu" => Ok(CodegenSetting::Cpu(value.to_string())),
+            "features" => {
+                let features: Vec<String> = value.split(',').map(|feature| feature.trim().to_string()).collect();
+                match features.iter().find(|feature| !feature.starts_with(['+', '-']) || feature.len() < 2) {
+                    Some(feature) => Err(format!("CPUの機能 '{}' には + か - を付けてください（例: +avx2）", feature)),
+                    None => Ok(CodegenSetting::Features(features)),
+                }
+            }
+            _ => Err(format!("不明な設定 '{}' です（cpu、features を指定できます）", key)),
+        }
+    }
+}
+
+/// ネイティブコードを実行するマシンの設定（LLVMバックエンドが使う）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MachineOptions {
+    /// CPU名（`None` なら汎用のCPU、`native` ならホストのCPU）
+    pub cpu: Option<String>,
+    /// 有効・無効にするCPUの機能（`+avx2` や `-neon`）
+    pub features: Vec<String>,
+    /// 再配置モデル
+    pub relocation_model: RelocationModel,
+    /// コードモデル
+    pub code_model: CodeModel,
+}
+
+impl MachineOptions {
+    /// `--codegen` の設定を反映（同じCPUを何度も指定したときは最後の指定を使う）
+    pub fn apply(&mut self, setting: CodegenSetting) {
+        match setting {
+            CodegenSetting::Cpu(cpu) => self.cpu = Some(cpu),
+            CodegenSetting::Features(features) => self.features.extend(features),
+        }
+    }
+}
+
 /// コード生成オプション
 pub struct CodegenOptions {
     /// 出力形式
@@ -45,8 +150,12 @@ pub struct CodegenOptions {
     pub opt_level: u8,
     /// デバッグ情報を含める
     pub debug_info: bool,
-    /// LTO (Link Time Optimization) を有効にする
-    pub lto: bool,
+    /// LTO (Link Time Optimization) の方式（有効なら機械語の代わりにビットコードを出力する）
+    pub lto: Option<LtoMode>,
+    /// 実行するマシンの設定
+    pub machine: MachineOptions,
+    /// デバッグ情報の位置を求めるソースマップ（位置情報の範囲から、元のファイルと行を引く）
+    pub sources: Option<SourceMap>,
 }
 
 impl Default for CodegenOptions {
@@ -56,7 +165,9 @@ impl Default for CodegenOptions {
             target: Target::Native,
             opt_level: 2,
             debug_info: false,
-            lto: false,
+            lto: None,
+            machine: MachineOptions::default(),
+            sources: None,
         }
     }
 }
@@ -67,13 +178,192 @@ pub trait Backend {
     fn name(&self) -> &str;
     
     /// コンパイル
-    fn compile(&self, module: &Module, options: &CodegenOptions) -> Result<Vec<u8>>;
+    ///
+    /// `declare_function` / `declare_global` で宣言したシンボルはバックエンドが持ち、その後のコンパイルの出力に
+    /// 含める。並列にコンパイルするときは、スレッドごとにバックエンドを作る。
+    fn compile(&mut self, module: &Module, options: &CodegenOptions) -> Result<Vec<u8>>;
     
     /// 関数宣言
     fn declare_function(&mut self, name: &str, params: &[Type], return_type: &Type) -> Result<()>;
     
-    /// シンボルをグローバル変数として宣言
-    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>) -> Result<()>;
+    /// シンボルをグローバル変数として宣言（`attributes` で定数かどうかなどを指定する）
+    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>, attributes: &GlobalAttributes) -> Result<()>;
+}
+
+/// 組み込み関数にする関数に付ける属性（`#[intrinsic] fn ctpop(x: int) -> int { ... }`）
+///
+/// 関数の本体は、組み込み関数をネイティブの命令に変換できないバックエンドで代わりに呼び出す実装になる。
+pub const INTRINSIC_ATTRIBUTE: &str = "intrinsic";
+
+/// 組み込み関数の引数と戻り値の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntrinsicType {
+    Unit,
+    Int,
+    Float,
+}
+
+impl IntrinsicType {
+    fn matches(&self, ty: &Type) -> bool {
+        matches!(
+            (self, &ty.kind),
+            (IntrinsicType::Unit, TypeKind::Unit) | (IntrinsicType::Int, TypeKind::Int) | (IntrinsicType::Float, TypeKind::Float)
+        )
+    }
+}
+
+impl std::fmt::Display for IntrinsicType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntrinsicType::Unit => write!(f, "()"),
+            IntrinsicType::Int => write!(f, "int"),
+            IntrinsicType::Float => write!(f, "float"),
+        }
+    }
+}
+
+/// バックエンドがライブラリ呼び出しではなくネイティブの命令に変換する組み込み関数
+///
+/// `c` と `js` は引数を `{0}`, `{1}`, ... に埋め込む式のひな形、`llvm` は呼び出すLLVMの組み込み関数の名前、
+/// `wasm` は命令の名前。JITは名前で命令を選ぶ。欄が `None` のバックエンドでは関数の本体を呼び出す。
+#[derive(Debug, Clone, Copy)]
+pub struct Intrinsic {
+    pub name: &'static str,
+    pub params: &'static [IntrinsicType],
+    pub return_type: IntrinsicType,
+    pub c: Option<&'static str>,
+    pub js: Option<&'static str>,
+    pub llvm: Option<&'static str>,
+    pub wasm: Option<&'static str>,
+}
+
+/// 組み込み関数の表（新しい組み込み関数はここに加える）
+pub const INTRINSICS: &[Intrinsic] = &[
+    Intrinsic {
+        name: "ctpop",
+        params: &[IntrinsicType::Int],
+        return_type: IntrinsicType::Int,
+        c: Some("(int64_t)__builtin_popcountll((uint64_t){0})"),
+        js: None,
+        llvm: Some("llvm.ctpop.i64"),
+        wasm: Some("i64.popcnt"),
+    },
+    Intrinsic {
+        name: "ctlz",
+        params: &[IntrinsicType::Int],
+        return_type: IntrinsicType::Int,
+        c: Some("({0} == 0 ? 64 : (int64_t)__builtin_clzll((uint64_t){0}))"),
+        js: None,
+        llvm: Some("llvm.ctlz.i64"),
+        wasm: Some("i64.clz"),
+    },
+    Intrinsic {
+        name: "cttz",
+        params: &[IntrinsicType::Int],
+        return_type: IntrinsicType::Int,
+        c: Some("({0} == 0 ? 64 : (int64_t)__builtin_ctzll((uint64_t){0}))"),
+        js: None,
+        llvm: Some("llvm.cttz.i64"),
+        wasm: Some("i64.ctz"),
+    },
+    Intrinsic {
+        name: "sqrt",
+        params: &[IntrinsicType::Float],
+        return_type: IntrinsicType::Float,
+        c: Some("__builtin_sqrt({0})"),
+        js: Some("Math.sqrt({0})"),
+        llvm: Some("llvm.sqrt.f64"),
+        wasm: Some("f64.sqrt"),
+    },
+    Intrinsic {
+        name: "memcpy",
+        params: &[IntrinsicType::Int, IntrinsicType::Int, IntrinsicType::Int],
+        return_type: IntrinsicType::Unit,
+        c: Some("__builtin_memcpy((void *)(intptr_t){0}, (const void *)(intptr_t){1}, (size_t){2})"),
+        js: None,
+        llvm: Some("llvm.memcpy.p0.p0.i64"),
+        wasm: Some("memory.copy"),
+    },
+    Intrinsic {
+        name: "wasm_memory_grow",
+        params: &[IntrinsicType::Int],
+        return_type: IntrinsicType::Int,
+        c: None,
+        js: None,
+        llvm: None,
+        wasm: Some("memory.grow"),
+    },
+];
+
+impl Intrinsic {
+    /// 式のひな形の `{0}`, `{1}`, ... を引数で置き換える
+    pub fn expand(template: &str, args: &[String]) -> String {
+        args.iter()
+            .enumerate()
+            .rev()
+            .fold(template.to_string(), |expr, (index, arg)| expr.replace(&format!("{{{}}}", index), arg))
+    }
+}
+
+/// 名前が組み込み関数の表にあれば返す（`bits::ctpop` のようにモジュールの中で定義した関数も最後の名前で探す）
+pub fn intrinsic(name: &str) -> Option<&'static Intrinsic> {
+    let name = name.rsplit("::").next().unwrap_or(name);
+    INTRINSICS.iter().find(|intrinsic| intrinsic.name == name)
+}
+
+/// `#[intrinsic]` を付けた関数なら、その組み込み関数を返す
+pub fn intrinsic_of(function: &Function) -> Option<&'static Intrinsic> {
+    if !function.attributes.tags.contains(INTRINSIC_ATTRIBUTE) {
+        return None;
+    }
+    intrinsic(&function.name)
+}
+
+/// 未定義の関数の呼び出しのエラー（モジュールの関数と外部関数から近い名前を提案する）
+pub fn undefined_function(module: &Module, name: &str) -> EidosError {
+    let candidates = module
+        .functions
+        .values()
+        .map(|function| function.name.as_str())
+        .chain(module.external_functions.keys().map(String::as_str));
+    EidosError::BackendError(format!("未定義の関数 '{}' の呼び出しです{}", name, did_you_mean(name, candidates)))
+}
+
+/// `#[intrinsic]` を付けた関数が表にあり、引数と戻り値の型が表と一致するかを検査
+pub fn check_intrinsics(module: &Module) -> Result<()> {
+    let mut functions: Vec<&Function> = module
+        .functions
+        .values()
+        .filter(|function| function.attributes.tags.contains(INTRINSIC_ATTRIBUTE))
+        .collect();
+    functions.sort_by_key(|function| function.id.0);
+    for function in functions {
+        let location = function.location.as_ref().map_or_else(String::new, |location| format!("{}:{}: ", location.file.display(), location.line));
+        let Some(intrinsic) = intrinsic(&function.name) else {
+            let known: Vec<&str> = INTRINSICS.iter().map(|intrinsic| intrinsic.name).collect();
+            return Err(EidosError::CodeGen(format!(
+                "{}'{}' は組み込み関数ではありません（組み込み関数: {}）",
+                location,
+                function.name,
+                known.join(", ")
+            )));
+        };
+        let param_types: Vec<Option<&Type>> = function.parameters.iter().map(|(_, id)| module.get_type(*id)).collect();
+        let params_match = param_types.len() == intrinsic.params.len()
+            && param_types.iter().zip(intrinsic.params).all(|(ty, expected)| ty.is_some_and(|ty| expected.matches(ty)));
+        let return_matches = module.get_type(function.return_type).is_some_and(|ty| intrinsic.return_type.matches(ty));
+        if !params_match || !return_matches {
+            let params: Vec<String> = intrinsic.params.iter().map(ToString::to_string).collect();
+            return Err(EidosError::CodeGen(format!(
+                "{}組み込み関数 '{}' の型は fn({}) -> {} でなければなりません",
+                location,
+                function.name,
+                params.join(", "),
+                intrinsic.return_type
+            )));
+        }
+    }
+    Ok(())
 }
 
 /// コード生成器
@@ -86,6 +376,8 @@ pub struct CodeGenerator {
     register_types: HashMap<RegisterId, TypeId>,
     /// シンボルと名前のマッピング
     symbol_names: HashMap<SymbolId, String>,
+    /// 所要時間を記録するセッション
+    session: Rc<CompileSession>,
 }
 
 impl CodeGenerator {
@@ -96,6 +388,7 @@ impl CodeGenerator {
             type_cache: HashMap::new(),
             register_types: HashMap::new(),
             symbol_names: HashMap::new(),
+            session: Rc::new(CompileSession::new(false)),
         }
     }
     
@@ -106,18 +399,44 @@ impl CodeGenerator {
             type_cache: HashMap::new(),
             register_types: HashMap::new(),
             symbol_names: HashMap::new(),
+            session: Rc::new(CompileSession::new(false)),
+        }
+    }
+    
+    /// C99ソースコードを出力するコード生成器を作成（ランタイムは差し替え可能）
+    pub fn new_c(runtime: Box<dyn CRuntime>) -> Self {
+        Self {
+            backend: Box::new(CEmitter::with_runtime(runtime)),
+            type_cache: HashMap::new(),
+            register_types: HashMap::new(),
+            symbol_names: HashMap::new(),
+            session: Rc::new(CompileSession::new(false)),
         }
     }
     
+    /// ESモジュールを出力するコード生成器を作成
+    pub fn new_js() -> Self {
+        Self {
+            backend: Box::new(JsBackend::new()),
+            type_cache: HashMap::new(),
+            register_types: HashMap::new(),
+            symbol_names: HashMap::new(),
+            session: Rc::new(CompileSession::new(false)),
+        }
+    }
+    
+    /// コンパイルの各段階の所要時間を記録するセッションを設定
+    pub fn with_session(mut self, session: Rc<CompileSession>) -> Self {
+        self.session = session;
+        self
+    }
+    
     /// コンパイル実行
     pub fn compile(&mut self, module: &Module, options: &CodegenOptions, output_path: &Path) -> Result<()> {
-        info!("コード生成を開始: {}", module.name);
-        
-        // バックエンドを使用してコンパイル
-        let code = self.backend.compile(module, options)?;
+        let code = self.generate_code(module, options)?;
         
         // 出力ファイルに書き込み
-        std::fs::write(output_path, code).map_err(|e| {
+        self.session.time("出力の書き込み", || std::fs::write(output_path, code)).map_err(|e| {
             EidosError::IO(e)
         })?;
         
@@ -125,14 +444,22 @@ impl CodeGenerator {
         Ok(())
     }
     
+    /// モジュールからコードを生成し、ファイルには書き込まずに返す
+    pub fn generate_code(&mut self, module: &Module, options: &CodegenOptions) -> Result<Vec<u8>> {
+        info!("コード生成を開始: {}", module.name);
+        let phase = format!("コード生成（{}）", self.backend.name());
+        self.session.time(&phase, || self.backend.compile(module, options))
+    }
+    
     /// モジュールを処理
     fn process_module(&mut self, module: &Module) -> Result<()> {
         // グローバル変数を宣言
-        for (symbol_id, (type_id, initializer)) in &module.globals {
-            let symbol_name = self.get_symbol_name(*symbol_id, module);
-            let ty = module.type_map.get(type_id).unwrap();
+        for global in module.globals.values() {
+            let ty = module.get_type(global.ty).ok_or_else(|| {
+                EidosError::CodeGen(format!("グローバル変数 '{}' の型が見つかりません", global.name))
+            })?;
             
-            self.backend.declare_global(&symbol_name, ty, initializer.as_ref())?;
+            self.backend.declare_global(&global.name, ty, global.initializer.as_ref(), &global.attributes)?;
         }
         
         // 関数を処理
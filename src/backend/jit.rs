@@ -0,0 +1,2666 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::cell::{Cell, RefCell};
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+
+use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
+use cranelift_codegen::ir::{
+    self, types, AbiParam, Block, FuncRef, InstBuilder, MemFlags, StackSlotData, StackSlotKind,
+    TrapCode, UserFuncName, Value,
+};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_codegen::Context;
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Switch, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, FuncId, Linkage, Module as _};
+use log::{debug, info};
+
+use crate::core::{Result, EidosError};
+use crate::core::bigint::{self, BigIntFunction};
+use crate::core::eir::{
+    BinaryOp, BlockId, Function, Instruction, InstructionId, Literal, Module, Operand, RegisterId, Terminator,
+    UnaryOp,
+};
+use crate::core::types::{NumericType, Type, TypeId, TypeKind};
+
+use super::codegen;
+
+/// 実行時エラーで使うトラップコード（到達不能コード）
+const UNREACHABLE_TRAP: TrapCode = TrapCode::unwrap_user(1);
+
+/// JITで扱える値の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum JitType {
+    Unit,
+    Bool,
+    Int,
+    Float,
+    Char,
+    String,
+    /// 固定長配列の先頭のアドレスと、スライス（データのアドレスと長さを並べた16バイトの領域）・タプルのアドレス
+    Pointer,
+    /// ビット幅を指定した数値型（値はその幅のまま持ち、演算もその幅で折り返す）
+    Sized(NumericType),
+}
+
+impl JitType {
+    fn from_type(ty: &Type) -> Option<Self> {
+        match &ty.kind {
+            TypeKind::Unit => Some(JitType::Unit),
+            TypeKind::Bool => Some(JitType::Bool),
+            TypeKind::Int => Some(JitType::Int),
+            TypeKind::Float => Some(JitType::Float),
+            TypeKind::Char => Some(JitType::Char),
+            TypeKind::String => Some(JitType::String),
+            // `BigInt` の値は10進数の文字列で表す
+            TypeKind::BigInt => Some(JitType::String),
+            TypeKind::Numeric(numeric) => Some(JitType::Sized(*numeric)),
+            // 範囲（`Range<T>`）は要素が2つのタプルと同じに扱う
+            TypeKind::FixedArray { .. } | TypeKind::Slice(_) | TypeKind::Tuple(_) | TypeKind::Struct { .. } => {
+                Some(JitType::Pointer)
+            }
+            _ => None,
+        }
+    }
+    
+    /// Craneliftでの値の型（unitは常に0を持つ `i8`、文字列はNUL終端文字列へのポインタ）
+    fn clif(self, pointer: types::Type) -> types::Type {
+        match self {
+            JitType::Unit | JitType::Bool => types::I8,
+            JitType::Int => types::I64,
+            JitType::Float => types::F64,
+            JitType::Char => types::I32,
+            JitType::String | JitType::Pointer => pointer,
+            JitType::Sized(numeric) => match numeric {
+                NumericType::I8 | NumericType::U8 => types::I8,
+                NumericType::I16 | NumericType::U16 => types::I16,
+                NumericType::I32 | NumericType::U32 => types::I32,
+                NumericType::I64 | NumericType::U64 => types::I64,
+                NumericType::F32 => types::F32,
+                NumericType::F64 => types::F64,
+            },
+        }
+    }
+    
+    fn is_integer(self) -> bool {
+        match self {
+            JitType::Unit | JitType::Bool | JitType::Int | JitType::Char => true,
+            JitType::Sized(numeric) => !numeric.is_float(),
+            _ => false,
+        }
+    }
+    
+    fn is_float(self) -> bool {
+        match self {
+            JitType::Float => true,
+            JitType::Sized(numeric) => numeric.is_float(),
+            _ => false,
+        }
+    }
+    
+    /// 符号付きとして扱う整数か（文字と真偽値は符号なし）
+    fn is_signed(self) -> bool {
+        match self {
+            JitType::Int => true,
+            JitType::Sized(numeric) => numeric.is_signed(),
+            _ => false,
+        }
+    }
+    
+    /// トレース用の実行時関数に型を渡すときの番号
+    fn code(self) -> i64 {
+        match self {
+            JitType::Unit => 0,
+            JitType::Bool => 1,
+            JitType::Int => 2,
+            JitType::Float => 3,
+            JitType::Char => 4,
+            JitType::String => 5,
+            JitType::Pointer => 6,
+            JitType::Sized(numeric) => 7 + NumericType::ALL.iter().position(|ty| *ty == numeric).unwrap_or(0) as i64,
+        }
+    }
+    
+    fn from_code(code: i64) -> Self {
+        match code {
+            1 => JitType::Bool,
+            2 => JitType::Int,
+            3 => JitType::Float,
+            4 => JitType::Char,
+            5 => JitType::String,
+            6 => JitType::Pointer,
+            7..=16 => JitType::Sized(NumericType::ALL[(code - 7) as usize]),
+            _ => JitType::Unit,
+        }
+    }
+}
+
+impl fmt::Display for JitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JitType::Unit => "()",
+            JitType::Bool => "Bool",
+            JitType::Int => "Int",
+            JitType::Float => "Float",
+            JitType::Char => "Char",
+            JitType::String => "String",
+            JitType::Pointer => "Pointer",
+            JitType::Sized(numeric) => numeric.name(),
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// JITで実行した結果の値
+#[derive(Debug, Clone, PartialEq)]
+pub enum JitValue {
+    Unit,
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+}
+
+impl fmt::Display for JitValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JitValue::Unit => write!(f, "()"),
+            JitValue::Int(value) => write!(f, "{}", value),
+            JitValue::UInt(value) => write!(f, "{}", value),
+            JitValue::Float(value) => write!(f, "{}", value),
+            JitValue::Bool(value) => write!(f, "{}", value),
+            JitValue::Char(value) => write!(f, "{:?}", value),
+            JitValue::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// 関数のシグネチャ（TypeIdはビルドごとに変わるため、JitTypeで比較する）
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FunctionSignature {
+    params: Vec<JitType>,
+    returns: JitType,
+    /// タプルの引数と戻り値の要素の型（タプルでなければ空）。タプルを返す関数は要素を複数の値として返す
+    param_elements: Vec<Vec<JitType>>,
+    elements: Vec<JitType>,
+}
+
+/// コンパイル済み関数のキャッシュエントリ
+#[derive(Debug, Clone)]
+struct CompiledFunction {
+    id: FuncId,
+    signature: FunctionSignature,
+    /// 関数本体の指紋（変化がなければ再コンパイルしない）
+    fingerprint: u64,
+}
+
+/// ホスト側で実装する実行時関数
+struct HostFunction {
+    name: &'static str,
+    address: *const u8,
+    params: &'static [JitType],
+    returns: Option<JitType>,
+}
+
+fn host_functions() -> Vec<HostFunction> {
+    vec![
+        HostFunction { name: "eidos_jit_print_int", address: eidos_jit_print_int as *const u8, params: &[JitType::Int], returns: None },
+        HostFunction { name: "eidos_jit_print_u64", address: eidos_jit_print_u64 as *const u8, params: &[JitType::Sized(NumericType::U64)], returns: None },
+        HostFunction { name: "eidos_jit_print_float", address: eidos_jit_print_float as *const u8, params: &[JitType::Float], returns: None },
+        HostFunction { name: "eidos_jit_print_bool", address: eidos_jit_print_bool as *const u8, params: &[JitType::Bool], returns: None },
+        HostFunction { name: "eidos_jit_print_char", address: eidos_jit_print_char as *const u8, params: &[JitType::Char], returns: None },
+        HostFunction { name: "eidos_jit_print_string", address: eidos_jit_print_string as *const u8, params: &[JitType::String], returns: None },
+        HostFunction { name: "eidos_jit_print_newline", address: eidos_jit_print_newline as *const u8, params: &[], returns: None },
+        HostFunction { name: "eidos_jit_string_eq", address: eidos_jit_string_eq as *const u8, params: &[JitType::String, JitType::String], returns: Some(JitType::Bool) },
+        HostFunction { name: "eidos_jit_string_concat", address: eidos_jit_string_concat as *const u8, params: &[JitType::String, JitType::String], returns: Some(JitType::String) },
+        HostFunction { name: "eidos_jit_int_to_string", address: eidos_jit_int_to_string as *const u8, params: &[JitType::Int], returns: Some(JitType::String) },
+        HostFunction { name: "eidos_jit_parse_int", address: eidos_jit_parse_int as *const u8, params: &[JitType::String], returns: Some(JitType::Int) },
+        HostFunction { name: "eidos_jit_parse_float", address: eidos_jit_parse_float as *const u8, params: &[JitType::String], returns: Some(JitType::Float) },
+        HostFunction { name: "eidos_jit_panic", address: eidos_jit_panic as *const u8, params: &[JitType::String], returns: None },
+        HostFunction { name: "eidos_jit_panicking", address: eidos_jit_panicking as *const u8, params: &[], returns: Some(JitType::Bool) },
+        HostFunction { name: "eidos_jit_unwind", address: eidos_jit_unwind as *const u8, params: &[JitType::String], returns: None },
+        HostFunction { name: "eidos_jit_bigint_1", address: eidos_jit_bigint_1 as *const u8, params: &[JitType::String, JitType::String], returns: Some(JitType::String) },
+        HostFunction { name: "eidos_jit_bigint_2", address: eidos_jit_bigint_2 as *const u8, params: &[JitType::String, JitType::String, JitType::String], returns: Some(JitType::String) },
+        HostFunction { name: "eidos_jit_bigint_3", address: eidos_jit_bigint_3 as *const u8, params: &[JitType::String, JitType::String, JitType::String, JitType::String], returns: Some(JitType::String) },
+        HostFunction { name: "eidos_jit_trace_arg", address: eidos_jit_trace_arg as *const u8, params: &[JitType::Int, JitType::Int], returns: None },
+        HostFunction { name: "eidos_jit_trace_enter", address: eidos_jit_trace_enter as *const u8, params: &[JitType::String], returns: None },
+        HostFunction { name: "eidos_jit_trace_return", address: eidos_jit_trace_return as *const u8, params: &[JitType::String, JitType::Int, JitType::Int], returns: None },
+        HostFunction { name: "eidos_jit_trace_instruction", address: eidos_jit_trace_instruction as *const u8, params: &[JitType::String], returns: None },
+    ]
+}
+
+extern "C" fn eidos_jit_print_int(value: i64) {
+    print!("{}", value);
+}
+
+extern "C" fn eidos_jit_print_u64(value: u64) {
+    print!("{}", value);
+}
+
+extern "C" fn eidos_jit_print_float(value: f64) {
+    print!("{}", value);
+}
+
+extern "C" fn eidos_jit_print_bool(value: i8) {
+    print!("{}", value != 0);
+}
+
+extern "C" fn eidos_jit_print_char(value: u32) {
+    print!("{}", char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER));
+}
+
+extern "C" fn eidos_jit_print_string(value: *const c_char) {
+    // SAFETY: JITコードが渡す文字列は、エンジンが保持するか連結で確保したNUL終端文字列
+    let value = unsafe { CStr::from_ptr(value) };
+    print!("{}", value.to_string_lossy());
+}
+
+extern "C" fn eidos_jit_print_newline() {
+    println!();
+}
+
+extern "C" fn eidos_jit_string_eq(lhs: *const c_char, rhs: *const c_char) -> i8 {
+    // SAFETY: eidos_jit_print_string と同じ
+    let (lhs, rhs) = unsafe { (CStr::from_ptr(lhs), CStr::from_ptr(rhs)) };
+    (lhs == rhs) as i8
+}
+
+extern "C" fn eidos_jit_string_concat(lhs: *const c_char, rhs: *const c_char) -> *const c_char {
+    // SAFETY: eidos_jit_print_string と同じ
+    let (lhs, rhs) = unsafe { (CStr::from_ptr(lhs), CStr::from_ptr(rhs)) };
+    let mut bytes = lhs.to_bytes().to_vec();
+    bytes.extend_from_slice(rhs.to_bytes());
+    // 連結結果の寿命は追跡できないため、意図的に解放しない
+    match CString::new(bytes) {
+        Ok(value) => value.into_raw(),
+        Err(_) => std::ptr::null(),
+    }
+}
+
+/// 返した文字列の寿命は追跡できないため、意図的に解放しない
+fn leak_string(text: String) -> *const c_char {
+    match CString::new(text) {
+        Ok(value) => value.into_raw(),
+        Err(_) => std::ptr::null(),
+    }
+}
+
+extern "C" fn eidos_jit_int_to_string(value: i64) -> *const c_char {
+    leak_string(value.to_string())
+}
+
+extern "C" fn eidos_jit_parse_int(text: *const c_char) -> i64 {
+    // SAFETY: eidos_jit_print_string と同じ
+    let text = unsafe { CStr::from_ptr(text) };
+    text.to_str().ok().and_then(|text| text.parse().ok()).unwrap_or(0)
+}
+
+extern "C" fn eidos_jit_parse_float(text: *const c_char) -> f64 {
+    // SAFETY: eidos_jit_print_string と同じ
+    let text = unsafe { CStr::from_ptr(text) };
+    text.to_str().ok().and_then(|text| text.parse().ok()).unwrap_or(f64::NAN)
+}
+
+/// JITコードの実行中に起きたパニック
+struct JitPanic {
+    message: String,
+    /// パニックした関数から呼び出し元へさかのぼる関数名
+    trace: Vec<String>,
+}
+
+impl JitPanic {
+    /// Cのランタイムと同じ形のメッセージとスタックトレースを持つ実行時エラーにする
+    fn into_error(self) -> EidosError {
+        let mut message = format!("panic: {}\nstack trace (most recent call first):", self.message);
+        for function in &self.trace {
+            message.push_str(&format!("\n    at {}", function));
+        }
+        EidosError::Runtime(message)
+    }
+}
+
+/// パニックしたまま `JitEngine` に戻っていないスレッドの数
+///
+/// JITコードは関数の呼び出しから戻るたびにこれを読み、0でなければ `eidos_jit_panicking` で
+/// 自分のスレッドがパニック中かを確かめる（パニックしていなければ読むだけで済む）。
+static PANICKING_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// このスレッドで起きたパニック（`JitEngine` がエントリー関数から戻った後に取り出す）
+    static PANIC: RefCell<Option<JitPanic>> = const { RefCell::new(None) };
+}
+
+/// パニックを記録する。JITコードはこの後、呼び出し元へ順に戻ってエントリー関数から抜ける
+fn raise_panic(message: String) {
+    let _ = std::io::stdout().flush();
+    PANIC.with(|panic| {
+        let mut panic = panic.borrow_mut();
+        if panic.is_none() {
+            PANICKING_THREADS.fetch_add(1, Ordering::SeqCst);
+            *panic = Some(JitPanic { message, trace: Vec::new() });
+        }
+    });
+}
+
+/// このスレッドで起きたパニックを取り出す
+fn take_panic() -> Option<JitPanic> {
+    let panic = PANIC.with(|panic| panic.borrow_mut().take())?;
+    PANICKING_THREADS.fetch_sub(1, Ordering::SeqCst);
+    // 途中で抜けた関数の分だけトレースの字下げが残っている
+    TRACE_DEPTH.with(|depth| depth.set(0));
+    Some(panic)
+}
+
+/// `panic(message)`: パニックを記録する（配列の範囲外の添字など）
+extern "C" fn eidos_jit_panic(message: *const c_char) {
+    // SAFETY: eidos_jit_print_string と同じ
+    let message = unsafe { CStr::from_ptr(message) };
+    raise_panic(message.to_string_lossy().into_owned());
+}
+
+extern "C" fn eidos_jit_panicking() -> i8 {
+    PANIC.with(|panic| panic.borrow().is_some()) as i8
+}
+
+/// パニックで関数から抜けるときに、その関数をスタックトレースに加える
+extern "C" fn eidos_jit_unwind(function: *const c_char) {
+    // SAFETY: functionはエンジンが保持するNUL終端文字列
+    let function = unsafe { CStr::from_ptr(function) }.to_string_lossy().into_owned();
+    PANIC.with(|panic| {
+        if let Some(panic) = panic.borrow_mut().as_mut() {
+            panic.trace.push(function);
+        }
+    });
+}
+
+/// `bigint::name` を呼び出す（失敗したらパニックを記録して空の文字列を返す）
+fn bigint_call(name: *const c_char, args: &[*const c_char]) -> *const c_char {
+    // SAFETY: eidos_jit_print_string と同じ
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    let args: Vec<String> = args.iter().map(|arg| unsafe { CStr::from_ptr(*arg) }.to_string_lossy().into_owned()).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match bigint::call(&name, &args) {
+        Ok(value) => leak_string(value),
+        Err(message) => {
+            raise_panic(format!("bigint::{}: {}", name, message));
+            leak_string(String::new())
+        }
+    }
+}
+
+extern "C" fn eidos_jit_bigint_1(name: *const c_char, a: *const c_char) -> *const c_char {
+    bigint_call(name, &[a])
+}
+
+extern "C" fn eidos_jit_bigint_2(name: *const c_char, a: *const c_char, b: *const c_char) -> *const c_char {
+    bigint_call(name, &[a, b])
+}
+
+extern "C" fn eidos_jit_bigint_3(name: *const c_char, a: *const c_char, b: *const c_char, c: *const c_char) -> *const c_char {
+    bigint_call(name, &[a, b, c])
+}
+
+thread_local! {
+    /// トレース中の関数の呼び出しの深さ
+    static TRACE_DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// `eidos_jit_trace_enter` の前に渡された引数
+    static TRACE_ARGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// `eidos_jit_trace_*` に64ビットで渡された値を表示用の文字列にする
+fn trace_value(ty: i64, bits: i64) -> String {
+    let value = match JitType::from_code(ty) {
+        JitType::Unit => JitValue::Unit,
+        JitType::Bool => JitValue::Bool(bits != 0),
+        JitType::Int => JitValue::Int(bits),
+        JitType::Float => JitValue::Float(f64::from_bits(bits as u64)),
+        JitType::Char => JitValue::Char(char::from_u32(bits as u32).unwrap_or(char::REPLACEMENT_CHARACTER)),
+        JitType::String if bits == 0 => JitValue::String(String::new()),
+        // SAFETY: eidos_jit_print_string と同じ
+        JitType::String => JitValue::String(unsafe { CStr::from_ptr(bits as *const c_char) }.to_string_lossy().into_owned()),
+        JitType::Pointer => return format!("{:#x}", bits),
+        // 浮動小数点数は `f64` に、整数は64ビットに広げて渡される
+        JitType::Sized(NumericType::U64) => JitValue::UInt(bits as u64),
+        JitType::Sized(numeric) if numeric.is_float() => JitValue::Float(f64::from_bits(bits as u64)),
+        JitType::Sized(_) => JitValue::Int(bits),
+    };
+    value.to_string()
+}
+
+fn trace_indent() -> String {
+    "  ".repeat(TRACE_DEPTH.with(Cell::get))
+}
+
+extern "C" fn eidos_jit_trace_arg(ty: i64, bits: i64) {
+    TRACE_ARGS.with(|args| args.borrow_mut().push(trace_value(ty, bits)));
+}
+
+extern "C" fn eidos_jit_trace_enter(name: *const c_char) {
+    // SAFETY: nameはエンジンが保持するNUL終端文字列
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    let args = TRACE_ARGS.with(|args| std::mem::take(&mut *args.borrow_mut()));
+    eprintln!("[trace] {}→ {}({})", trace_indent(), name, args.join(", "));
+    TRACE_DEPTH.with(|depth| depth.set(depth.get() + 1));
+}
+
+extern "C" fn eidos_jit_trace_return(name: *const c_char, ty: i64, bits: i64) {
+    TRACE_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    // SAFETY: eidos_jit_trace_enter と同じ
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    eprintln!("[trace] {}← {} = {}", trace_indent(), name, trace_value(ty, bits));
+}
+
+extern "C" fn eidos_jit_trace_instruction(text: *const c_char) {
+    // SAFETY: eidos_jit_trace_enter と同じ
+    let text = unsafe { CStr::from_ptr(text) }.to_string_lossy();
+    eprintln!("[trace] {}  {}", trace_indent(), text);
+}
+
+/// 実行のトレースで表示する内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TraceMode {
+    /// 関数の呼び出し（引数）と戻り値
+    #[default]
+    Calls,
+    /// 関数の呼び出しに加えて、実行したEIRの命令
+    Instr,
+}
+
+/// 実行のトレースの設定（`eid run --trace`）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceOptions {
+    pub mode: TraceMode,
+    /// 名前にこの文字列を含む関数だけをトレースする
+    pub filter: Option<String>,
+}
+
+impl TraceOptions {
+    fn traces(&self, function: &str) -> bool {
+        self.filter.as_deref().is_none_or(|filter| function.contains(filter))
+    }
+}
+
+/// `JitEngine::hot_reload` の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotReload {
+    /// 差し替えた関数の名前（変更がなければ空）
+    Swapped(Vec<String>),
+    /// 実行中のプログラムには反映できない変更（理由）。プログラムを最初から実行し直す必要がある
+    Restart(String),
+}
+
+/// 別のスレッドで実行中のプログラム（`JitEngine::start`）
+pub struct JitProgram {
+    handle: JoinHandle<Result<JitValue>>,
+}
+
+impl JitProgram {
+    /// エントリー関数から戻ったかどうか
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+    
+    /// プログラムの終了を待ち、エントリー関数の値を返す
+    pub fn join(self) -> Result<JitValue> {
+        self.handle
+            .join()
+            .map_err(|_| EidosError::BackendError("JITで実行したプログラムが異常終了しました".to_string()))?
+    }
+}
+
+/// CraneliftでEIRをメモリ上にコンパイルして実行するJITエンジン
+///
+/// REPLの入力ごとに `run` を呼び出す。関数はエンジンの寿命の間キャッシュされ、本体が変わらない関数は
+/// 再コンパイルしない。同じシグネチャで再定義された関数はその場で差し替えられ、既存の呼び出し元も
+/// 新しい定義を呼ぶ。シグネチャが変わった場合は新しいシンボルとして定義し、同じモジュール内の関数を
+/// すべて再コンパイルする（モジュールに含まれない古い呼び出し元は以前の定義を呼び続ける）。
+/// `eid run --watch --hot` は `start` でプログラムを実行し始め、保存のたびに `hot_reload` で関数を差し替える。
+pub struct JitEngine {
+    module: JITModule,
+    builder_context: FunctionBuilderContext,
+    /// 名前ごとのコンパイル済み関数
+    functions: HashMap<String, CompiledFunction>,
+    /// ホスト関数の宣言
+    host: HashMap<&'static str, FuncId>,
+    /// 文字列リテラルの実体（JITコードはこのポインタを直接参照する）
+    strings: HashMap<String, CString>,
+    /// `run` の呼び出し回数（シンボル名の一意化に使う）
+    generation: u64,
+    /// 最後にコンパイルしたエントリー関数の本体の指紋
+    entry: Option<u64>,
+    /// 関数の出入りと命令を標準エラー出力に表示するコードを埋め込む
+    trace: Option<TraceOptions>,
+}
+
+impl JitEngine {
+    /// ホスト環境向けのJITエンジンを作成
+    pub fn new() -> Result<Self> {
+        Self::with_opt_level(0)
+    }
+    
+    /// 最適化レベル（0-3）を指定してJITエンジンを作成（1以上でCraneliftの最適化を有効にする）
+    pub fn with_opt_level(opt_level: u8) -> Result<Self> {
+        let mut flags = settings::builder();
+        flags.set("opt_level", if opt_level == 0 { "none" } else { "speed" }).map_err(jit_error)?;
+        // 関数の差し替えにはGOT経由の呼び出しが必要
+        flags.set("is_pic", "true").map_err(jit_error)?;
+        flags.set("use_colocated_libcalls", "false").map_err(jit_error)?;
+        // タプルを返す関数は要素を複数の値として返し、レジスタに収まらない分はメモリ経由で返す
+        flags.set("enable_multi_ret_implicit_sret", "true").map_err(jit_error)?;
+        let isa = cranelift_native::builder()
+            .map_err(|msg| EidosError::BackendError(format!("JITはこのホスト環境に対応していません: {}", msg)))?
+            .finish(settings::Flags::new(flags))
+            .map_err(jit_error)?;
+        
+        let host_functions = host_functions();
+        let mut builder = JITBuilder::with_isa(isa, default_libcall_names());
+        builder.hotswap(true);
+        for host in &host_functions {
+            builder.symbol(host.name, host.address);
+        }
+        let mut module = JITModule::new(builder);
+        
+        let pointer = module.target_config().pointer_type();
+        let mut host = HashMap::new();
+        for function in &host_functions {
+            let mut signature = module.make_signature();
+            signature.params.extend(function.params.iter().map(|ty| abi_param(ty.clif(pointer))));
+            signature.returns.extend(function.returns.map(|ty| abi_param(ty.clif(pointer))));
+            let id = module
+                .declare_function(function.name, Linkage::Import, &signature)
+                .map_err(jit_error)?;
+            host.insert(function.name, id);
+        }
+        
+        Ok(Self {
+            module,
+            builder_context: FunctionBuilderContext::new(),
+            functions: HashMap::new(),
+            host,
+            strings: HashMap::new(),
+            generation: 0,
+            entry: None,
+            trace: None,
+        })
+    }
+    
+    /// 実行をトレースする（これ以降にコンパイルする関数に、トレースを表示するコードを埋め込む）
+    pub fn with_trace(mut self, trace: TraceOptions) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+    
+    /// コンパイル済みの関数名
+    pub fn compiled_functions(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.functions.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+    
+    /// モジュールの関数をコンパイルし、エントリー関数があれば実行してその値を返す
+    ///
+    /// 実行中にパニックしたら、メッセージとスタックトレースを `EidosError::Runtime` として返す
+    /// （プロセスは終了しないので、REPLは続けて次の入力を実行できる）。
+    pub fn run(&mut self, module: &Module) -> Result<JitValue> {
+        match self.compile(module, true)? {
+            Some((id, returns)) => {
+                let code = self.module.get_finalized_function(id);
+                // SAFETY: codeは直前に確定した、引数なしで `returns` を返す関数
+                unsafe { run_entry(code, returns) }
+            }
+            None => Ok(JitValue::Unit),
+        }
+    }
+    
+    /// モジュールをコンパイルし、エントリー関数を別のスレッドで実行し始める
+    ///
+    /// 実行中も `hot_reload` で関数を差し替えられる。コンパイル済みのコードはエンジンを破棄しても
+    /// 解放されないので、エンジンより長くプログラムが動いていても構わない。
+    pub fn start(&mut self, module: &Module) -> Result<JitProgram> {
+        let (id, returns) = self
+            .compile(module, true)?
+            .ok_or_else(|| EidosError::BackendError(format!("モジュール '{}' にエントリー関数がありません", module.name)))?;
+        // 関数ポインタはスレッド間で送れないため、アドレスとして渡す
+        let code = self.module.get_finalized_function(id) as usize;
+        let handle = std::thread::Builder::new()
+            .name("eidos-jit".to_string())
+            // SAFETY: codeは確定済みの、引数なしで `returns` を返す関数
+            .spawn(move || unsafe { run_entry(code as *const u8, returns) })
+            .map_err(EidosError::IO)?;
+        Ok(JitProgram { handle })
+    }
+    
+    /// 実行中のプログラム（`start`）に、モジュールで本体が変わった関数を反映する
+    ///
+    /// 本体だけが変わった関数（と新しく追加された関数）を再コンパイルし、呼び出し先をアトミックに
+    /// 切り替える。実行中の呼び出しは古い本体のまま終わり、次の呼び出しから新しい本体を使う。
+    /// 既存の関数のシグネチャかエントリー関数が変わった場合は、実行中のコードから新しい定義を
+    /// 正しく呼べないので何もせず `HotReload::Restart` を返す。
+    pub fn hot_reload(&mut self, module: &Module) -> Result<HotReload> {
+        let mut swapped = Vec::new();
+        for function in module.functions.values().filter(|function| Some(function.id) != module.entry_point) {
+            let signature = function_signature(module, function)?;
+            match self.functions.get(&function.name) {
+                Some(cached) if cached.signature != signature => {
+                    return Ok(HotReload::Restart(format!("関数 '{}' のシグネチャが変わりました", function.name)));
+                }
+                Some(cached) if cached.fingerprint == fingerprint(module, function)? => {}
+                _ => swapped.push(function.name.clone()),
+            }
+        }
+        if entry_fingerprint(module)? != self.entry {
+            return Ok(HotReload::Restart("エントリー関数が変わりました".to_string()));
+        }
+        
+        swapped.sort();
+        if !swapped.is_empty() {
+            self.compile(module, false)?;
+        }
+        Ok(HotReload::Swapped(swapped))
+    }
+    
+    /// モジュールの関数をコンパイルし、`with_entry` ならエントリー関数もコンパイルしてその宣言を返す
+    fn compile(&mut self, module: &Module, with_entry: bool) -> Result<Option<(FuncId, JitType)>> {
+        self.generation += 1;
+        info!("JITコンパイル中: {} (世代 {})", module.name, self.generation);
+        
+        let mut functions: Vec<&Function> = module
+            .functions
+            .values()
+            .filter(|function| Some(function.id) != module.entry_point)
+            .collect();
+        functions.sort_by_key(|function| function.id.0);
+        
+        // どの関数を定義し直すかを決める
+        let mut pending: HashMap<String, CompiledFunction> = HashMap::new();
+        let mut redefined = HashSet::new();
+        let mut signature_changed = false;
+        for function in &functions {
+            let signature = function_signature(module, function)?;
+            let fingerprint = fingerprint(module, function)?;
+            match self.functions.get(&function.name) {
+                Some(cached) if cached.signature == signature && cached.fingerprint == fingerprint => {
+                    debug!("関数 {} は変更がないため再利用します", function.name);
+                }
+                Some(cached) if cached.signature == signature => {
+                    redefined.insert(function.name.clone());
+                    pending.insert(function.name.clone(), CompiledFunction { id: cached.id, signature, fingerprint });
+                }
+                cached => {
+                    signature_changed |= cached.is_some();
+                    let id = self.declare(&function.name, Linkage::Export, &signature)?;
+                    pending.insert(function.name.clone(), CompiledFunction { id, signature, fingerprint });
+                }
+            }
+        }
+        // シグネチャが変わった関数の呼び出し元は新しいシンボルを呼ぶよう再コンパイルする
+        if signature_changed {
+            for function in &functions {
+                if let Some(cached) = self.functions.get(&function.name) {
+                    if !pending.contains_key(&function.name) {
+                        redefined.insert(function.name.clone());
+                        pending.insert(function.name.clone(), cached.clone());
+                    }
+                }
+            }
+        }
+        
+        let mut visible = self.functions.clone();
+        visible.extend(pending.iter().map(|(name, compiled)| (name.clone(), compiled.clone())));
+        
+        // 変換エラーで既存の定義を壊さないよう、すべての関数を先に変換する
+        let mut translated = Vec::new();
+        for function in &functions {
+            if let Some(compiled) = pending.get(&function.name) {
+                debug!("関数 {} をコンパイル中", function.name);
+                let body = self.translate(module, function, compiled.id, &visible)?;
+                translated.push((function.name.clone(), compiled.id, body));
+            }
+        }
+        
+        let entry = match module.entry_point.and_then(|id| module.get_function(id)).filter(|_| with_entry) {
+            // JITで実行するプログラムにはコマンドライン引数がない
+            Some(function) if !function.parameters.is_empty() => {
+                return Err(EidosError::BackendError(
+                    "JITは引数を受け取るエントリー関数（fn main(args: List<String>)）を実行できません".to_string(),
+                ));
+            }
+            Some(function) => {
+                let signature = function_signature(module, function)?;
+                if !signature.elements.is_empty() {
+                    return Err(EidosError::BackendError("JITはタプルを返すエントリー関数を実行できません".to_string()));
+                }
+                let name = format!("__eidos_entry_{}", self.generation);
+                let id = self.declare(&name, Linkage::Local, &signature)?;
+                let body = self.translate(module, function, id, &visible)?;
+                translated.push((name, id, body));
+                Some((id, signature.returns))
+            }
+            None => None,
+        };
+        
+        for (name, id, body) in translated {
+            if redefined.contains(&name) {
+                self.module.prepare_for_function_redefine(id).map_err(jit_error)?;
+            }
+            let mut context = Context::for_function(body);
+            self.module.define_function(id, &mut context).map_err(jit_error)?;
+        }
+        self.module.finalize_definitions().map_err(jit_error)?;
+        self.functions.extend(pending);
+        if with_entry {
+            self.entry = entry_fingerprint(module)?;
+        }
+        Ok(entry)
+    }
+    
+    /// コンパイル済みの引数のない関数を呼び出す（ベンチマークで同じ関数を繰り返し実行するのに使う）
+    pub fn call(&self, name: &str) -> Result<JitValue> {
+        let compiled = self
+            .functions
+            .get(name)
+            .ok_or_else(|| EidosError::BackendError(format!("関数 '{}' はコンパイルされていません", name)))?;
+        if !compiled.signature.params.is_empty() {
+            return Err(EidosError::BackendError(format!("関数 '{}' は引数を取るため直接呼び出せません", name)));
+        }
+        if !compiled.signature.elements.is_empty() {
+            return Err(EidosError::BackendError(format!("関数 '{}' はタプルを返すため直接呼び出せません", name)));
+        }
+        let code = self.module.get_finalized_function(compiled.id);
+        // SAFETY: codeは確定済みの、引数なしで `returns` を返す関数
+        unsafe { run_entry(code, compiled.signature.returns) }
+    }
+    
+    fn declare(&mut self, name: &str, linkage: Linkage, signature: &FunctionSignature) -> Result<FuncId> {
+        let signature = self.make_signature(signature);
+        // 再定義のたびに新しいシンボルにして、古い宣言と衝突しないようにする
+        let symbol = format!("{}#{}", name, self.generation);
+        self.module.declare_function(&symbol, linkage, &signature).map_err(jit_error)
+    }
+    
+    fn make_signature(&self, signature: &FunctionSignature) -> ir::Signature {
+        let pointer = self.module.target_config().pointer_type();
+        let mut sig = self.module.make_signature();
+        sig.params.extend(signature.params.iter().map(|ty| abi_param(ty.clif(pointer))));
+        if !signature.elements.is_empty() {
+            sig.returns.extend(signature.elements.iter().map(|ty| abi_param(ty.clif(pointer))));
+        } else if signature.returns != JitType::Unit {
+            sig.returns.push(abi_param(signature.returns.clif(pointer)));
+        }
+        sig
+    }
+    
+    fn translate(
+        &mut self,
+        eir: &Module,
+        function: &Function,
+        id: FuncId,
+        functions: &HashMap<String, CompiledFunction>,
+    ) -> Result<ir::Function> {
+        let signature = function_signature(eir, function)?;
+        let mut body = ir::Function::with_name_signature(
+            UserFuncName::user(0, id.as_u32()),
+            self.make_signature(&signature),
+        );
+        
+        let trace = self.trace.as_ref().filter(|trace| trace.traces(&function.name)).map(|trace| trace.mode);
+        let translator = FunctionTranslator::new(
+            eir,
+            function,
+            signature.returns,
+            &mut self.module,
+            functions,
+            &self.host,
+            &mut self.strings,
+            FunctionBuilder::new(&mut body, &mut self.builder_context),
+        )?
+        .with_trace(trace);
+        translator.translate()?;
+        Ok(body)
+    }
+}
+
+/// 分岐の行き先（引数があれば、代入してから本来の分岐先へ進む中継ブロック）
+struct Edge {
+    block: Block,
+    assignments: Option<(BlockId, Vec<(Value, JitType)>)>,
+}
+
+/// 1つのEIR関数をCranelift IRに変換する
+///
+/// EIRのレジスタはCraneliftの変数にそのまま対応させ、SSA化はcranelift-frontendに任せる。
+/// ブロックパラメータとPHIノードは、分岐元で変数へ代入することで表現する。
+struct FunctionTranslator<'a> {
+    eir: &'a Module,
+    function: &'a Function,
+    returns: JitType,
+    jit: &'a mut JITModule,
+    functions: &'a HashMap<String, CompiledFunction>,
+    host: &'a HashMap<&'static str, FuncId>,
+    strings: &'a mut HashMap<String, CString>,
+    builder: FunctionBuilder<'a>,
+    pointer: types::Type,
+    registers: HashMap<RegisterId, JitType>,
+    /// `Alloca` で確保したスロットと `GetElementPtr` で求めた要素（どちらもアドレスを持つ）
+    slots: HashSet<RegisterId>,
+    phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>>,
+    blocks: HashMap<BlockId, Block>,
+    func_refs: HashMap<FuncId, FuncRef>,
+    trace: Option<TraceMode>,
+}
+
+impl<'a> FunctionTranslator<'a> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        eir: &'a Module,
+        function: &'a Function,
+        returns: JitType,
+        jit: &'a mut JITModule,
+        functions: &'a HashMap<String, CompiledFunction>,
+        host: &'a HashMap<&'static str, FuncId>,
+        strings: &'a mut HashMap<String, CString>,
+        builder: FunctionBuilder<'a>,
+    ) -> Result<Self> {
+        let pointer = jit.target_config().pointer_type();
+        
+        let mut registers = HashMap::new();
+        for (reg, type_id) in &function.register_types {
+            registers.insert(*reg, jit_type(eir, *type_id)?);
+        }
+        
+        let mut slots = HashSet::new();
+        let mut phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>> = HashMap::new();
+        for block in function.blocks.values() {
+            for (_, instruction) in &block.instructions {
+                match instruction {
+                    Instruction::Alloca { result, .. } | Instruction::GetElementPtr { result, .. } => {
+                        slots.insert(*result);
+                    }
+                    Instruction::Phi { incoming, result } => {
+                        for (value, from) in incoming {
+                            phi_moves.entry(*from).or_default().push((*result, value.clone()));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        
+        Ok(Self {
+            eir,
+            function,
+            returns,
+            jit,
+            functions,
+            host,
+            strings,
+            builder,
+            pointer,
+            registers,
+            slots,
+            phi_moves,
+            blocks: HashMap::new(),
+            func_refs: HashMap::new(),
+            trace: None,
+        })
+    }
+    
+    fn with_trace(mut self, trace: Option<TraceMode>) -> Self {
+        self.trace = trace;
+        self
+    }
+    
+    fn translate(mut self) -> Result<()> {
+        let mut registers: Vec<(RegisterId, JitType)> = self.registers.iter().map(|(reg, ty)| (*reg, *ty)).collect();
+        registers.sort_by_key(|(reg, _)| reg.0);
+        for (reg, ty) in registers {
+            let clif = if self.slots.contains(&reg) { self.pointer } else { ty.clif(self.pointer) };
+            self.builder.declare_var(variable(reg), clif);
+        }
+        
+        // EIRのエントリーブロックは分岐先にもなりうるため、引数を受け取る専用のブロックを前に置く
+        let prologue = self.builder.create_block();
+        self.builder.append_block_params_for_function_params(prologue);
+        let mut block_ids: Vec<BlockId> = self.function.blocks.keys().copied().collect();
+        block_ids.sort_by_key(|id| (*id != self.function.entry_block, id.0));
+        for id in &block_ids {
+            let block = self.builder.create_block();
+            self.blocks.insert(*id, block);
+        }
+        
+        self.builder.switch_to_block(prologue);
+        let params = self.builder.block_params(prologue).to_vec();
+        for (index, value) in params.into_iter().enumerate() {
+            self.builder.def_var(variable(RegisterId(index as u32)), value);
+            if self.trace.is_some() {
+                let ty = self.register_type(RegisterId(index as u32))?;
+                self.trace_value("eidos_jit_trace_arg", None, value, ty)?;
+            }
+        }
+        if self.trace.is_some() {
+            let function = self.function;
+            let name = self.string_pointer(&function.name)?;
+            self.call_host("eidos_jit_trace_enter", &[name]);
+        }
+        let entry = self.block(self.function.entry_block)?;
+        self.builder.ins().jump(entry, &[]);
+        
+        for id in block_ids {
+            self.translate_block(id)?;
+        }
+        
+        self.builder.seal_all_blocks();
+        self.builder.finalize();
+        Ok(())
+    }
+    
+    fn translate_block(&mut self, id: BlockId) -> Result<()> {
+        let function = self.function;
+        let block = &function.blocks[&id];
+        let clif_block = self.block(id)?;
+        self.builder.switch_to_block(clif_block);
+        
+        for (instruction_id, instruction) in &block.instructions {
+            self.trace_instruction(id, instruction)?;
+            self.translate_instruction(id, *instruction_id, instruction)?;
+            // 制御命令より後ろには到達しない
+            if is_control_instruction(instruction) {
+                return Ok(());
+            }
+        }
+        
+        match &block.terminator {
+            Some(terminator) => {
+                self.trace_instruction(id, terminator)?;
+                self.translate_terminator(id, terminator)
+            }
+            None => {
+                self.builder.ins().trap(UNREACHABLE_TRAP);
+                Ok(())
+            }
+        }
+    }
+    
+    fn translate_instruction(&mut self, block: BlockId, id: InstructionId, instruction: &Instruction) -> Result<()> {
+        match instruction {
+            Instruction::BinaryOp { op, lhs, rhs, result } => {
+                let checked = !self.function.unchecked.contains(&id);
+                let (value, ty) = self.binary_op(*op, lhs, rhs, checked)?;
+                self.assign(*result, value, ty)
+            }
+            Instruction::UnaryOp { op, operand, result } => {
+                let (value, ty) = self.operand(operand)?;
+                let value = match (op, ty) {
+                    (UnaryOp::Neg, _) if ty.is_float() => self.builder.ins().fneg(value),
+                    (UnaryOp::Neg, _) => self.builder.ins().ineg(value),
+                    (UnaryOp::Not, JitType::Bool) | (UnaryOp::BitNot, JitType::Bool) => {
+                        self.builder.ins().bxor_imm(value, 1)
+                    }
+                    (UnaryOp::Not, _) | (UnaryOp::BitNot, _) => self.builder.ins().bnot(value),
+                    (UnaryOp::Cast, _) => {
+                        let target = self.register_type(*result)?;
+                        let value = self.convert(value, ty, target)?;
+                        return self.assign(*result, value, target);
+                    }
+                };
+                self.assign(*result, value, ty)
+            }
+            Instruction::Load { address, result } => {
+                let address = self.address(address)?;
+                // タプルは変数の領域からコピーし、後で変数に代入しても読み出した値が変わらないようにする
+                if let Some(tuple) = self.eir_type(&Operand::Register(*result)).filter(|ty| ty.field_types().is_some()) {
+                    let copy = self.tuple_slot(tuple)?;
+                    self.copy_tuple(tuple, address, copy)?;
+                    self.builder.def_var(variable(*result), copy);
+                    return Ok(());
+                }
+                let ty = self.register_type(*result)?;
+                let value = self.builder.ins().load(ty.clif(self.pointer), MemFlags::trusted(), address, 0);
+                self.assign(*result, value, ty)
+            }
+            Instruction::Store { address, value } => {
+                let slot_type = match address {
+                    Operand::Register(reg) => self.register_type(*reg)?,
+                    _ => return Err(self.unsupported_operand(address)),
+                };
+                let address = self.address(address)?;
+                let tuple = self.eir_type(value).filter(|ty| ty.field_types().is_some());
+                let (value, ty) = self.operand(value)?;
+                if let Some(tuple) = tuple {
+                    return self.copy_tuple(tuple, value, address);
+                }
+                let value = self.convert(value, ty, slot_type)?;
+                self.builder.ins().store(MemFlags::trusted(), value, address, 0);
+                Ok(())
+            }
+            Instruction::Call { function, arguments, result } => {
+                self.call(function, arguments, *result, false)
+            }
+            Instruction::ExternalCall { function, arguments, result } => {
+                self.call(function, arguments, *result, true)
+            }
+            Instruction::Return { value } => self.translate_return(block, value.as_ref()),
+            Instruction::Branch { target } => self.jump(block, *target, &[]),
+            Instruction::BranchCond { condition, true_target, false_target } => {
+                let terminator = Terminator::BranchCond {
+                    condition: condition.clone(),
+                    true_target: *true_target,
+                    true_args: Vec::new(),
+                    false_target: *false_target,
+                    false_args: Vec::new(),
+                };
+                self.translate_terminator(block, &terminator)
+            }
+            Instruction::Alloca { size, result } => {
+                // タプルの変数には要素をまとめて格納する
+                let size = match self.eir_type(&Operand::Register(*result)).filter(|ty| ty.field_types().is_some()) {
+                    Some(tuple) => tuple.layout().map_or(*size, |(tuple_size, _)| tuple_size.max(*size)),
+                    None => *size,
+                };
+                let slot = self.builder.create_sized_stack_slot(StackSlotData::new(
+                    StackSlotKind::ExplicitSlot,
+                    size as u32,
+                    3,
+                ));
+                let address = self.builder.ins().stack_addr(self.pointer, slot, 0);
+                self.builder.def_var(variable(*result), address);
+                Ok(())
+            }
+            Instruction::Cast { value, target_type, result } => {
+                let target = jit_type(self.eir, *target_type)?;
+                let (value, ty) = self.operand(value)?;
+                let value = self.convert(value, ty, target)?;
+                self.assign(*result, value, target)
+            }
+            // 分岐元のブロックで代入済み
+            Instruction::Phi { .. } => Ok(()),
+            Instruction::Select { condition, true_value, false_value, result } => {
+                let (condition, _) = self.operand(condition)?;
+                let (true_value, ty) = self.operand(true_value)?;
+                let (false_value, false_ty) = self.operand(false_value)?;
+                let false_value = self.convert(false_value, false_ty, ty)?;
+                let value = self.builder.ins().select(condition, true_value, false_value);
+                self.assign(*result, value, ty)
+            }
+            Instruction::DebugInfo { .. } => Ok(()),
+            Instruction::GetElementPtr { base, indices, result } => {
+                let Some(element) = self.eir_type(base).and_then(Type::element_type).and_then(Type::stride) else {
+                    return Err(unsupported("GetElementPtr"));
+                };
+                let is_slice = matches!(self.eir_type(base).map(|ty| &ty.kind), Some(TypeKind::Slice(_)));
+                let (mut address, _) = self.operand(base)?;
+                if is_slice {
+                    address = self.builder.ins().load(self.pointer, MemFlags::trusted(), address, 0);
+                }
+                let Some(index) = indices.first() else {
+                    return Err(unsupported("GetElementPtr"));
+                };
+                let (index, ty) = self.operand(index)?;
+                let index = self.convert(index, ty, JitType::Int)?;
+                let offset = self.builder.ins().imul_imm(index, element as i64);
+                let offset = if self.pointer == types::I64 { offset } else { self.builder.ins().ireduce(self.pointer, offset) };
+                let address = self.builder.ins().iadd(address, offset);
+                self.builder.def_var(variable(*result), address);
+                Ok(())
+            }
+            Instruction::Atomic { .. } => Err(unsupported("Atomic")),
+            Instruction::InlineAsm { .. } => Err(unsupported("InlineAsm")),
+            // タプルは要素を型のレイアウトどおりに並べたスタックの領域で、そのアドレスを値として渡す
+            Instruction::Tuple { elements, result } => {
+                let Some(tuple) = self.eir_type(&Operand::Register(*result)) else {
+                    return Err(unsupported("Tuple"));
+                };
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.operand(element)?);
+                }
+                let address = self.tuple_slot(tuple)?;
+                self.store_tuple(tuple, address, values)?;
+                self.builder.def_var(variable(*result), address);
+                Ok(())
+            }
+            Instruction::Extract { tuple, index, result } => {
+                let Some(offset) = self.eir_type(tuple).and_then(Type::field_offsets).and_then(|offsets| offsets.get(*index).copied()) else {
+                    return Err(unsupported("Extract"));
+                };
+                let (address, _) = self.operand(tuple)?;
+                let ty = self.register_type(*result)?;
+                let value = self.builder.ins().load(ty.clif(self.pointer), MemFlags::trusted(), address, offset as i32);
+                self.assign(*result, value, ty)
+            }
+            // スライスはデータのアドレスと長さを並べた16バイトの領域で、そのアドレスを値として渡す
+            Instruction::MakeSlice { array, length, result } => {
+                let slot = self.builder.create_sized_stack_slot(StackSlotData::new(StackSlotKind::ExplicitSlot, 16, 3));
+                let (array, _) = self.operand(array)?;
+                let (length, ty) = self.operand(length)?;
+                let length = self.convert(length, ty, JitType::Int)?;
+                self.builder.ins().stack_store(array, slot, 0);
+                self.builder.ins().stack_store(length, slot, 8);
+                let address = self.builder.ins().stack_addr(self.pointer, slot, 0);
+                self.builder.def_var(variable(*result), address);
+                Ok(())
+            }
+            Instruction::SliceLen { slice, result } => {
+                let (slice, _) = self.operand(slice)?;
+                let length = self.builder.ins().load(types::I64, MemFlags::trusted(), slice, 8);
+                self.assign(*result, length, JitType::Int)
+            }
+        }
+    }
+    
+    fn translate_terminator(&mut self, block: BlockId, terminator: &Terminator) -> Result<()> {
+        match terminator {
+            Terminator::Branch { target, args } => self.jump(block, *target, args),
+            Terminator::BranchCond { condition, true_target, true_args, false_target, false_args } => {
+                let (condition, _) = self.operand(condition)?;
+                self.phi_moves(block)?;
+                let true_edge = self.edge(*true_target, true_args)?;
+                let false_edge = self.edge(*false_target, false_args)?;
+                self.builder.ins().brif(condition, true_edge.block, &[], false_edge.block, &[]);
+                self.fill_edge(true_edge)?;
+                self.fill_edge(false_edge)
+            }
+            Terminator::Return { value } => self.translate_return(block, value.as_ref()),
+            Terminator::Switch { value, default_target, default_args, cases } => {
+                let (value, ty) = self.operand(value)?;
+                let value = self.convert(value, ty, JitType::Int)?;
+                self.phi_moves(block)?;
+                
+                let mut switch = Switch::new();
+                let mut edges = Vec::with_capacity(cases.len() + 1);
+                for (literal, target, args) in cases {
+                    let key = match literal {
+                        Literal::Int(v) => *v as u64,
+                        Literal::Bool(v) => *v as u64,
+                        Literal::Char(v) => *v as u64,
+                        _ => {
+                            return Err(EidosError::BackendError(format!(
+                                "switchの分岐値 {} はJITでは未対応です",
+                                literal
+                            )))
+                        }
+                    };
+                    let edge = self.edge(*target, args)?;
+                    switch.set_entry(key as u128, edge.block);
+                    edges.push(edge);
+                }
+                let default = self.edge(*default_target, default_args)?;
+                switch.emit(&mut self.builder, value, default.block);
+                edges.push(default);
+                
+                for edge in edges {
+                    self.fill_edge(edge)?;
+                }
+                Ok(())
+            }
+            Terminator::IndirectCall { .. } => Err(unsupported("IndirectCall")),
+            Terminator::Unreachable => {
+                self.builder.ins().trap(UNREACHABLE_TRAP);
+                Ok(())
+            }
+        }
+    }
+    
+    fn translate_return(&mut self, block: BlockId, value: Option<&Operand>) -> Result<()> {
+        let function = self.function;
+        self.phi_moves(block)?;
+        let returns = self.eir.get_type(function.return_type).filter(|ty| ty.field_types().is_some());
+        match value {
+            Some(value) if returns.is_some() => {
+                let (address, _) = self.operand(value)?;
+                if self.trace.is_some() {
+                    let name = self.string_pointer(&function.name)?;
+                    self.trace_value("eidos_jit_trace_return", Some(name), address, JitType::Pointer)?;
+                }
+                let values = self.load_tuple(returns.expect("タプルの戻り値の型"), address)?;
+                let values: Vec<Value> = values.into_iter().map(|(value, _)| value).collect();
+                self.builder.ins().return_(&values);
+            }
+            Some(value) if self.returns != JitType::Unit => {
+                let (value, ty) = self.operand(value)?;
+                let value = self.convert(value, ty, self.returns)?;
+                if self.trace.is_some() {
+                    let name = self.string_pointer(&function.name)?;
+                    self.trace_value("eidos_jit_trace_return", Some(name), value, self.returns)?;
+                }
+                self.builder.ins().return_(&[value]);
+            }
+            _ => {
+                if self.trace.is_some() {
+                    let name = self.string_pointer(&function.name)?;
+                    let unit = self.builder.ins().iconst(types::I8, 0);
+                    self.trace_value("eidos_jit_trace_return", Some(name), unit, JitType::Unit)?;
+                }
+                self.builder.ins().return_(&[]);
+            }
+        }
+        Ok(())
+    }
+    
+    /// 値を型の番号と64ビットの値にしてトレース用の実行時関数に渡す（`name` があれば先に渡す）
+    fn trace_value(&mut self, helper: &'static str, name: Option<Value>, value: Value, ty: JitType) -> Result<()> {
+        let bits = match ty {
+            JitType::Float => self.builder.ins().bitcast(types::I64, MemFlags::new(), value),
+            JitType::Int => value,
+            JitType::String | JitType::Pointer if self.pointer == types::I64 => value,
+            JitType::Sized(numeric) if numeric.is_float() => {
+                let value = self.convert(value, ty, JitType::Float)?;
+                self.builder.ins().bitcast(types::I64, MemFlags::new(), value)
+            }
+            JitType::Sized(_) => self.convert(value, ty, JitType::Int)?,
+            _ => self.builder.ins().uextend(types::I64, value),
+        };
+        let ty = self.builder.ins().iconst(types::I64, ty.code());
+        let mut args: Vec<Value> = name.into_iter().collect();
+        args.extend([ty, bits]);
+        self.call_host(helper, &args);
+        Ok(())
+    }
+    
+    /// `--trace=instr` では、命令を実行する前にその命令を表示する
+    fn trace_instruction(&mut self, block: BlockId, instruction: &dyn fmt::Display) -> Result<()> {
+        if self.trace == Some(TraceMode::Instr) {
+            let text = self.string_pointer(&format!("{}: {}", block, instruction))?;
+            self.call_host("eidos_jit_trace_instruction", &[text]);
+        }
+        Ok(())
+    }
+    
+    fn jump(&mut self, from: BlockId, target: BlockId, args: &[Operand]) -> Result<()> {
+        self.phi_moves(from)?;
+        let values = self.branch_args(target, args)?;
+        self.assign_block_params(target, values)?;
+        let block = self.block(target)?;
+        self.builder.ins().jump(block, &[]);
+        Ok(())
+    }
+    
+    /// 分岐先ごとの引数の代入を行う中継ブロックを用意する（引数がなければ分岐先そのもの）
+    ///
+    /// 引数の値は分岐の前に現在のブロックで評価しておく。
+    fn edge(&mut self, target: BlockId, args: &[Operand]) -> Result<Edge> {
+        if args.is_empty() {
+            return Ok(Edge { block: self.block(target)?, assignments: None });
+        }
+        let values = self.branch_args(target, args)?;
+        Ok(Edge { block: self.builder.create_block(), assignments: Some((target, values)) })
+    }
+    
+    fn fill_edge(&mut self, edge: Edge) -> Result<()> {
+        if let Some((target, values)) = edge.assignments {
+            self.builder.switch_to_block(edge.block);
+            self.assign_block_params(target, values)?;
+            let target = self.block(target)?;
+            self.builder.ins().jump(target, &[]);
+        }
+        Ok(())
+    }
+    
+    fn branch_args(&mut self, target: BlockId, args: &[Operand]) -> Result<Vec<(Value, JitType)>> {
+        let expected = self.function.get_block(target).map_or(0, |block| block.parameters.len());
+        if expected != args.len() {
+            return Err(EidosError::BackendError(format!(
+                "関数 '{}' のブロック {} への分岐の引数の数が一致しません",
+                self.function.name, target
+            )));
+        }
+        args.iter().map(|arg| self.operand(arg)).collect()
+    }
+    
+    fn assign_block_params(&mut self, target: BlockId, values: Vec<(Value, JitType)>) -> Result<()> {
+        let function = self.function;
+        let params = function.get_block(target).map(|block| block.parameters.as_slice()).unwrap_or(&[]);
+        for ((param, _), (value, ty)) in params.iter().zip(values) {
+            self.assign(*param, value, ty)?;
+        }
+        Ok(())
+    }
+    
+    fn phi_moves(&mut self, from: BlockId) -> Result<()> {
+        if let Some(moves) = self.phi_moves.get(&from).cloned() {
+            let values = moves
+                .iter()
+                .map(|(_, value)| self.operand(value))
+                .collect::<Result<Vec<_>>>()?;
+            for ((result, _), (value, ty)) in moves.iter().zip(values) {
+                self.assign(*result, value, ty)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// 組み込み関数をCraneliftの命令に変換（対応する命令がなければ関数の本体を呼び出すのでNone）
+    fn intrinsic(&mut self, name: &str, args: &[(Value, JitType)]) -> Option<(Value, JitType)> {
+        let ins = self.builder.ins();
+        match (name, args) {
+            ("ctpop", [(x, JitType::Int)]) => Some((ins.popcnt(*x), JitType::Int)),
+            ("ctlz", [(x, JitType::Int)]) => Some((ins.clz(*x), JitType::Int)),
+            ("cttz", [(x, JitType::Int)]) => Some((ins.ctz(*x), JitType::Int)),
+            ("sqrt", [(x, JitType::Float)]) => Some((ins.sqrt(*x), JitType::Float)),
+            _ => None,
+        }
+    }
+    
+    fn call(&mut self, name: &str, arguments: &[Operand], result: Option<RegisterId>, external: bool) -> Result<()> {
+        let mut args = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            args.push(self.operand(argument)?);
+        }
+        
+        // 同名のユーザー定義関数があれば組み込み関数より優先する
+        let user_function = if external { None } else { self.functions.get(name).cloned() };
+        let intrinsic = user_function.as_ref().and_then(|_| self.eir.get_function_by_name(name)).and_then(codegen::intrinsic_of);
+        if let Some((value, ty)) = intrinsic.and_then(|intrinsic| self.intrinsic(intrinsic.name, &args)) {
+            if let Some(result) = result {
+                self.assign(result, value, ty)?;
+            }
+            return Ok(());
+        }
+        if let Some(callee) = user_function {
+            if callee.signature.params.len() != args.len() {
+                return Err(EidosError::BackendError(format!(
+                    "関数 '{}' の引数の数が一致しません（期待: {}, 実際: {}）",
+                    name,
+                    callee.signature.params.len(),
+                    args.len()
+                )));
+            }
+            let mut values = Vec::with_capacity(args.len());
+            for ((value, ty), param) in args.into_iter().zip(&callee.signature.params) {
+                values.push(self.convert(value, ty, *param)?);
+            }
+            let func_ref = self.func_ref(callee.id);
+            let call = self.builder.ins().call(func_ref, &values);
+            self.check_panic()?;
+            // タプルの戻り値は、受け取った要素を呼び出し元のスタックの領域に並べる
+            if !callee.signature.elements.is_empty() {
+                let results = self.builder.inst_results(call).to_vec();
+                let tuple = result.and_then(|result| self.eir_type(&Operand::Register(result)));
+                if let (Some(result), Some(tuple)) = (result, tuple) {
+                    let values = results.into_iter().zip(callee.signature.elements.iter().copied()).collect();
+                    let address = self.tuple_slot(tuple)?;
+                    self.store_tuple(tuple, address, values)?;
+                    self.builder.def_var(variable(result), address);
+                }
+                return Ok(());
+            }
+            let value = match callee.signature.returns {
+                JitType::Unit => self.builder.ins().iconst(types::I8, 0),
+                _ => self.builder.inst_results(call)[0],
+            };
+            if let Some(result) = result {
+                self.assign(result, value, callee.signature.returns)?;
+            }
+            return Ok(());
+        }
+        
+        if !external && (name == "print" || name == "println") {
+            for (value, ty) in args {
+                let (helper, value) = match ty {
+                    JitType::Unit | JitType::Int => ("eidos_jit_print_int", self.convert(value, ty, JitType::Int)?),
+                    JitType::Bool => ("eidos_jit_print_bool", value),
+                    JitType::Float => ("eidos_jit_print_float", value),
+                    JitType::Sized(NumericType::U64) => ("eidos_jit_print_u64", value),
+                    JitType::Sized(numeric) if numeric.is_float() => ("eidos_jit_print_float", self.convert(value, ty, JitType::Float)?),
+                    JitType::Sized(_) => ("eidos_jit_print_int", self.convert(value, ty, JitType::Int)?),
+                    JitType::Char => ("eidos_jit_print_char", value),
+                    JitType::String => ("eidos_jit_print_string", value),
+                    JitType::Pointer => return Err(EidosError::BackendError("配列・スライス・タプルは表示できません".to_string())),
+                };
+                self.call_host(helper, &[value]);
+            }
+            if name == "println" {
+                self.call_host("eidos_jit_print_newline", &[]);
+            }
+            if let Some(result) = result {
+                let unit = self.builder.ins().iconst(types::I8, 0);
+                self.assign(result, unit, JitType::Unit)?;
+            }
+            return Ok(());
+        }
+        
+        if let ("panic", false, [(message, JitType::String)]) = (name, external, args.as_slice()) {
+            self.call_host("eidos_jit_panic", &[*message]);
+            self.unwind()?;
+            // 後に続く命令には到達しない
+            let next = self.builder.create_block();
+            self.builder.switch_to_block(next);
+            return Ok(());
+        }
+        
+        if let Some(function) = name.strip_prefix("bigint::").and_then(bigint::function).filter(|_| !external) {
+            return self.call_bigint(function, args, result);
+        }
+        
+        if external {
+            if let Some(external) = self.eir.external_functions.get(name) {
+                if external.is_variadic {
+                    return Err(EidosError::BackendError(format!(
+                        "可変長引数の外部関数 '{}' はJITでは呼び出せません",
+                        name
+                    )));
+                }
+                let params = external
+                    .parameter_types
+                    .iter()
+                    .map(|id| jit_type(self.eir, *id))
+                    .collect::<Result<Vec<_>>>()?;
+                let returns = jit_type(self.eir, external.return_type)?;
+                let mut signature = self.jit.make_signature();
+                signature.params.extend(params.iter().map(|ty| abi_param(ty.clif(self.pointer))));
+                if returns != JitType::Unit {
+                    signature.returns.push(abi_param(returns.clif(self.pointer)));
+                }
+                // 外部関数はプロセス内のシンボルから解決する
+                let id = self
+                    .jit
+                    .declare_function(name, Linkage::Import, &signature)
+                    .map_err(jit_error)?;
+                
+                let mut values = Vec::with_capacity(args.len());
+                for ((value, ty), param) in args.into_iter().zip(&params) {
+                    values.push(self.convert(value, ty, *param)?);
+                }
+                let func_ref = self.func_ref(id);
+                let call = self.builder.ins().call(func_ref, &values);
+                let value = match returns {
+                    JitType::Unit => self.builder.ins().iconst(types::I8, 0),
+                    _ => self.builder.inst_results(call)[0],
+                };
+                if let Some(result) = result {
+                    self.assign(result, value, returns)?;
+                }
+                return Ok(());
+            }
+        }
+        
+        Err(codegen::undefined_function(self.eir, name))
+    }
+    
+    /// `bigint::*` をホストの関数で呼び出す（`Int` の引数も10進数の文字列にして渡す）
+    fn call_bigint(&mut self, function: &BigIntFunction, args: Vec<(Value, JitType)>, result: Option<RegisterId>) -> Result<()> {
+        let helper = match args.len() {
+            1 => "eidos_jit_bigint_1",
+            2 => "eidos_jit_bigint_2",
+            3 => "eidos_jit_bigint_3",
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "関数 'bigint::{}' の引数の数が一致しません（期待: {}, 実際: {}）",
+                    function.name,
+                    function.params.len(),
+                    args.len()
+                )))
+            }
+        };
+        let mut values = vec![self.string_pointer(function.name)?];
+        for (value, ty) in args {
+            let value = match ty {
+                JitType::String => value,
+                _ => {
+                    let value = self.convert(value, ty, JitType::Int)?;
+                    self.call_host("eidos_jit_int_to_string", &[value]).unwrap()
+                }
+            };
+            values.push(value);
+        }
+        let text = self.call_host(helper, &values).unwrap();
+        self.check_panic()?;
+        let (value, ty) = match function.returns {
+            TypeKind::Int => (self.call_host("eidos_jit_parse_int", &[text]).unwrap(), JitType::Int),
+            TypeKind::Float => (self.call_host("eidos_jit_parse_float", &[text]).unwrap(), JitType::Float),
+            TypeKind::Bool => {
+                let expected = self.string_pointer("true")?;
+                (self.call_host("eidos_jit_string_eq", &[text, expected]).unwrap(), JitType::Bool)
+            }
+            _ => (text, JitType::String),
+        };
+        if let Some(result) = result {
+            self.assign(result, value, ty)?;
+        }
+        Ok(())
+    }
+    
+    /// `condition` が真ならメッセージを出してパニックにし、偽なら続きのブロックに進む
+    fn panic_if(&mut self, condition: Value, message: &str) -> Result<()> {
+        let panic = self.builder.create_block();
+        let next = self.builder.create_block();
+        self.builder.set_cold_block(panic);
+        self.builder.ins().brif(condition, panic, &[], next, &[]);
+        
+        self.builder.switch_to_block(panic);
+        let message = self.string_pointer(message)?;
+        self.call_host("eidos_jit_panic", &[message]);
+        self.unwind()?;
+        
+        self.builder.switch_to_block(next);
+        Ok(())
+    }
+    
+    /// 呼び出した関数がパニックしていたら、この関数からも抜ける
+    ///
+    /// パニック中のスレッドがなければ、カウンタを1回読むだけで続きのブロックに進む。
+    fn check_panic(&mut self) -> Result<()> {
+        let counter = self.builder.ins().iconst(self.pointer, &PANICKING_THREADS as *const AtomicUsize as i64);
+        let count = self.builder.ins().load(self.pointer, MemFlags::trusted(), counter, 0);
+        let check = self.builder.create_block();
+        let unwind = self.builder.create_block();
+        let next = self.builder.create_block();
+        self.builder.set_cold_block(check);
+        self.builder.set_cold_block(unwind);
+        self.builder.ins().brif(count, check, &[], next, &[]);
+        
+        self.builder.switch_to_block(check);
+        let panicking = self.call_host("eidos_jit_panicking", &[]).unwrap();
+        self.builder.ins().brif(panicking, unwind, &[], next, &[]);
+        
+        self.builder.switch_to_block(unwind);
+        self.unwind()?;
+        
+        self.builder.switch_to_block(next);
+        Ok(())
+    }
+    
+    /// パニックで関数から抜ける（関数をスタックトレースに加え、戻り値の型の0を返す）
+    fn unwind(&mut self) -> Result<()> {
+        let name = self.string_pointer(&self.function.name)?;
+        self.call_host("eidos_jit_unwind", &[name]);
+        let returns: Vec<types::Type> = self.builder.func.signature.returns.iter().map(|param| param.value_type).collect();
+        let values: Vec<Value> = returns
+            .into_iter()
+            .map(|ty| match ty {
+                types::F64 => self.builder.ins().f64const(0.0),
+                types::F32 => self.builder.ins().f32const(0.0),
+                ty => self.builder.ins().iconst(ty, 0),
+            })
+            .collect();
+        self.builder.ins().return_(&values);
+        Ok(())
+    }
+    
+    fn call_host(&mut self, name: &'static str, args: &[Value]) -> Option<Value> {
+        let id = self.host[name];
+        let func_ref = self.func_ref(id);
+        let call = self.builder.ins().call(func_ref, args);
+        self.builder.inst_results(call).first().copied()
+    }
+    
+    fn func_ref(&mut self, id: FuncId) -> FuncRef {
+        if let Some(func_ref) = self.func_refs.get(&id) {
+            return *func_ref;
+        }
+        let func_ref = self.jit.declare_func_in_func(id, self.builder.func);
+        self.func_refs.insert(id, func_ref);
+        func_ref
+    }
+    
+    /// `checked` が偽の除算・剰余は、値域解析で0除算もオーバーフローも起きないと分かっているので検査しない
+    fn binary_op(&mut self, op: BinaryOp, lhs: &Operand, rhs: &Operand, checked: bool) -> Result<(Value, JitType)> {
+        let (a, lhs_ty) = self.operand(lhs)?;
+        let (b, rhs_ty) = self.operand(rhs)?;
+        // 即値の整数は相手のオペランドの型（`u64` など）に合わせて計算する
+        let ty = if matches!(lhs, Operand::Literal(_)) { rhs_ty } else { lhs_ty };
+        let a = self.convert(a, lhs_ty, ty)?;
+        let b = self.convert(b, rhs_ty, ty)?;
+        
+        // 整数の0除算と `INT64_MIN / -1` はプロセスを落とすので、Cのランタイムと同じメッセージでパニックにする
+        if checked && matches!(op, BinaryOp::Div | BinaryOp::Rem) && ty.is_integer() {
+            let zero = self.builder.ins().icmp_imm(IntCC::Equal, b, 0);
+            self.panic_if(zero, "division by zero")?;
+            if ty == JitType::Int {
+                let min = self.builder.ins().icmp_imm(IntCC::Equal, a, i64::MIN);
+                let minus_one = self.builder.ins().icmp_imm(IntCC::Equal, b, -1);
+                let overflow = self.builder.ins().band(min, minus_one);
+                let message = if op == BinaryOp::Div { "division overflow" } else { "remainder overflow" };
+                self.panic_if(overflow, message)?;
+            }
+        }
+        // 64ビットより狭い符号付き整数の `MIN / -1` はCと同じく64ビットで計算してから折り返す
+        if matches!(op, BinaryOp::Div | BinaryOp::Rem) && matches!(ty, JitType::Sized(numeric) if numeric.is_signed()) {
+            let a = self.convert(a, ty, JitType::Int)?;
+            let b = self.convert(b, ty, JitType::Int)?;
+            let value = if op == BinaryOp::Div { self.builder.ins().sdiv(a, b) } else { self.builder.ins().srem(a, b) };
+            return Ok((self.convert(value, JitType::Int, ty)?, ty));
+        }
+        let ins = self.builder.ins();
+        
+        let value = match ty {
+            JitType::String => {
+                return match op {
+                    BinaryOp::Add => Ok((self.call_host("eidos_jit_string_concat", &[a, b]).unwrap(), JitType::String)),
+                    BinaryOp::Eq => Ok((self.call_host("eidos_jit_string_eq", &[a, b]).unwrap(), JitType::Bool)),
+                    BinaryOp::Ne => {
+                        let eq = self.call_host("eidos_jit_string_eq", &[a, b]).unwrap();
+                        Ok((self.builder.ins().bxor_imm(eq, 1), JitType::Bool))
+                    }
+                    _ => Err(EidosError::BackendError(format!("文字列に対する演算 {} はJITでは未対応です", op))),
+                };
+            }
+            _ if ty.is_float() => match op {
+                BinaryOp::Add => ins.fadd(a, b),
+                BinaryOp::Sub => ins.fsub(a, b),
+                BinaryOp::Mul => ins.fmul(a, b),
+                BinaryOp::Div => ins.fdiv(a, b),
+                BinaryOp::Eq => return Ok((ins.fcmp(FloatCC::Equal, a, b), JitType::Bool)),
+                BinaryOp::Ne => return Ok((ins.fcmp(FloatCC::NotEqual, a, b), JitType::Bool)),
+                BinaryOp::Lt => return Ok((ins.fcmp(FloatCC::LessThan, a, b), JitType::Bool)),
+                BinaryOp::Le => return Ok((ins.fcmp(FloatCC::LessThanOrEqual, a, b), JitType::Bool)),
+                BinaryOp::Gt => return Ok((ins.fcmp(FloatCC::GreaterThan, a, b), JitType::Bool)),
+                BinaryOp::Ge => return Ok((ins.fcmp(FloatCC::GreaterThanOrEqual, a, b), JitType::Bool)),
+                _ => {
+                    return Err(EidosError::BackendError(format!(
+                        "浮動小数点数に対する演算 {} はJITでは未対応です",
+                        op
+                    )))
+                }
+            },
+            _ => {
+                let signed = ty.is_signed();
+                let compare = |signed_cc: IntCC, unsigned_cc: IntCC| if signed { signed_cc } else { unsigned_cc };
+                match op {
+                    BinaryOp::Add => ins.iadd(a, b),
+                    BinaryOp::Sub => ins.isub(a, b),
+                    BinaryOp::Mul => ins.imul(a, b),
+                    BinaryOp::Div if signed => ins.sdiv(a, b),
+                    BinaryOp::Div => ins.udiv(a, b),
+                    BinaryOp::Rem if signed => ins.srem(a, b),
+                    BinaryOp::Rem => ins.urem(a, b),
+                    BinaryOp::BitAnd | BinaryOp::And => ins.band(a, b),
+                    BinaryOp::BitOr | BinaryOp::Or => ins.bor(a, b),
+                    BinaryOp::BitXor => ins.bxor(a, b),
+                    BinaryOp::Shl => ins.ishl(a, b),
+                    BinaryOp::Shr if signed => ins.sshr(a, b),
+                    BinaryOp::Shr => ins.ushr(a, b),
+                    BinaryOp::Eq => return Ok((ins.icmp(IntCC::Equal, a, b), JitType::Bool)),
+                    BinaryOp::Ne => return Ok((ins.icmp(IntCC::NotEqual, a, b), JitType::Bool)),
+                    BinaryOp::Lt => {
+                        let cc = compare(IntCC::SignedLessThan, IntCC::UnsignedLessThan);
+                        return Ok((ins.icmp(cc, a, b), JitType::Bool));
+                    }
+                    BinaryOp::Le => {
+                        let cc = compare(IntCC::SignedLessThanOrEqual, IntCC::UnsignedLessThanOrEqual);
+                        return Ok((ins.icmp(cc, a, b), JitType::Bool));
+                    }
+                    BinaryOp::Gt => {
+                        let cc = compare(IntCC::SignedGreaterThan, IntCC::UnsignedGreaterThan);
+                        return Ok((ins.icmp(cc, a, b), JitType::Bool));
+                    }
+                    BinaryOp::Ge => {
+                        let cc = compare(IntCC::SignedGreaterThanOrEqual, IntCC::UnsignedGreaterThanOrEqual);
+                        return Ok((ins.icmp(cc, a, b), JitType::Bool));
+                    }
+                }
+            }
+        };
+        Ok((value, ty))
+    }
+    
+    /// 値を別の型に変換する（明示的なキャストと、型の食い違いの吸収の両方に使う）
+    fn convert(&mut self, value: Value, from: JitType, to: JitType) -> Result<Value> {
+        if from == to {
+            return Ok(value);
+        }
+        let from_clif = from.clif(self.pointer);
+        let to_clif = to.clif(self.pointer);
+        let converted = match (from, to) {
+            (_, JitType::Unit) => self.builder.ins().iconst(types::I8, 0),
+            (_, JitType::Bool) if from.is_float() => {
+                let zero = if from_clif == types::F32 {
+                    self.builder.ins().f32const(0.0)
+                } else {
+                    self.builder.ins().f64const(0.0)
+                };
+                self.builder.ins().fcmp(FloatCC::NotEqual, value, zero)
+            }
+            (_, _) if from.is_float() && to.is_float() => {
+                if from_clif.bits() < to_clif.bits() {
+                    self.builder.ins().fpromote(to_clif, value)
+                } else {
+                    self.builder.ins().fdemote(to_clif, value)
+                }
+            }
+            // 8・16ビットへの飽和変換はないので、32ビットに変換してから切り詰める
+            (_, _) if from.is_float() && to.is_integer() => {
+                let wide = if to_clif.bits() < 32 { types::I32 } else { to_clif };
+                let converted = if to.is_signed() {
+                    self.builder.ins().fcvt_to_sint_sat(wide, value)
+                } else {
+                    self.builder.ins().fcvt_to_uint_sat(wide, value)
+                };
+                if wide == to_clif { converted } else { self.builder.ins().ireduce(to_clif, converted) }
+            }
+            (_, _) if from.is_integer() && to.is_float() => {
+                if from.is_signed() {
+                    self.builder.ins().fcvt_from_sint(to_clif, value)
+                } else {
+                    self.builder.ins().fcvt_from_uint(to_clif, value)
+                }
+            }
+            (_, JitType::Bool) if from.is_integer() => self.builder.ins().icmp_imm(IntCC::NotEqual, value, 0),
+            // 広げるときは元の型の符号に合わせて拡張する（`u64` と `Int` のように幅が同じならビット列はそのまま）
+            (_, _) if from.is_integer() && to.is_integer() => {
+                if from_clif.bits() < to_clif.bits() && from.is_signed() {
+                    self.builder.ins().sextend(to_clif, value)
+                } else if from_clif.bits() < to_clif.bits() {
+                    self.builder.ins().uextend(to_clif, value)
+                } else if from_clif.bits() > to_clif.bits() {
+                    self.builder.ins().ireduce(to_clif, value)
+                } else {
+                    value
+                }
+            }
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "{} から {} への変換はJITでは未対応です",
+                    from, to
+                )))
+            }
+        };
+        Ok(converted)
+    }
+    
+    /// タプル型 `tuple` の値を置くスタックの領域を確保し、そのアドレスを返す
+    fn tuple_slot(&mut self, tuple: &Type) -> Result<Value> {
+        let Some((size, _)) = tuple.layout() else {
+            return Err(EidosError::BackendError(format!("型 {} はJITでは未対応です", tuple)));
+        };
+        let slot = self.builder.create_sized_stack_slot(StackSlotData::new(StackSlotKind::ExplicitSlot, size as u32, 3));
+        Ok(self.builder.ins().stack_addr(self.pointer, slot, 0))
+    }
+    
+    /// タプルの要素の型とメモリ上の位置
+    fn tuple_fields(&self, tuple: &Type) -> Result<Vec<(JitType, i32)>> {
+        let (Some(elements), Some(offsets)) = (tuple.field_types(), tuple.field_offsets()) else {
+            return Err(EidosError::BackendError(format!("型 {} はJITでは未対応です", tuple)));
+        };
+        elements
+            .into_iter()
+            .zip(offsets)
+            .map(|(element, offset)| {
+                let ty = JitType::from_type(element)
+                    .ok_or_else(|| EidosError::BackendError(format!("型 {} はJITでは未対応です", element)))?;
+                Ok((ty, offset as i32))
+            })
+            .collect()
+    }
+    
+    /// タプルの要素を `address` の領域に書き込む（値はそれぞれ要素の型に変換する）
+    fn store_tuple(&mut self, tuple: &Type, address: Value, values: Vec<(Value, JitType)>) -> Result<()> {
+        for ((value, ty), (field, offset)) in values.into_iter().zip(self.tuple_fields(tuple)?) {
+            let value = self.convert(value, ty, field)?;
+            self.builder.ins().store(MemFlags::trusted(), value, address, offset);
+        }
+        Ok(())
+    }
+    
+    /// `address` の領域からタプルの要素を読み出す
+    fn load_tuple(&mut self, tuple: &Type, address: Value) -> Result<Vec<(Value, JitType)>> {
+        let mut values = Vec::new();
+        for (field, offset) in self.tuple_fields(tuple)? {
+            values.push((self.builder.ins().load(field.clif(self.pointer), MemFlags::trusted(), address, offset), field));
+        }
+        Ok(values)
+    }
+    
+    /// タプルの要素を `from` の領域から `to` の領域にコピーする
+    fn copy_tuple(&mut self, tuple: &Type, from: Value, to: Value) -> Result<()> {
+        let values = self.load_tuple(tuple, from)?;
+        self.store_tuple(tuple, to, values)
+    }
+    
+    fn assign(&mut self, reg: RegisterId, value: Value, ty: JitType) -> Result<()> {
+        let target = self.register_type(reg)?;
+        let value = self.convert(value, ty, target)?;
+        self.builder.def_var(variable(reg), value);
+        Ok(())
+    }
+    
+    fn address(&mut self, operand: &Operand) -> Result<Value> {
+        match operand {
+            Operand::Register(reg) if self.slots.contains(reg) => Ok(self.builder.use_var(variable(*reg))),
+            _ => Err(self.unsupported_operand(operand)),
+        }
+    }
+    
+    fn operand(&mut self, operand: &Operand) -> Result<(Value, JitType)> {
+        match operand {
+            Operand::Register(reg) => {
+                let ty = self.register_type(*reg)?;
+                Ok((self.builder.use_var(variable(*reg)), ty))
+            }
+            Operand::Literal(literal) => self.literal(literal),
+            Operand::Global(name) => match self.eir.get_global(name) {
+                // 初期値を持つ定数だけをその場の値として扱う
+                Some(global) if global.attributes.is_constant && global.initializer.is_some() => {
+                    let ty = jit_type(self.eir, global.ty)?;
+                    let (value, literal_ty) = self.literal(global.initializer.as_ref().unwrap())?;
+                    Ok((self.convert(value, literal_ty, ty)?, ty))
+                }
+                _ => Err(self.unsupported_operand(operand)),
+            },
+            _ => Err(self.unsupported_operand(operand)),
+        }
+    }
+    
+    fn literal(&mut self, literal: &Literal) -> Result<(Value, JitType)> {
+        let ins = self.builder.ins();
+        Ok(match literal {
+            Literal::Int(v) => (ins.iconst(types::I64, *v), JitType::Int),
+            Literal::Float(v) => (ins.f64const(*v), JitType::Float),
+            Literal::Bool(v) => (ins.iconst(types::I8, *v as i64), JitType::Bool),
+            Literal::Char(v) => (ins.iconst(types::I32, *v as i64), JitType::Char),
+            Literal::Unit => (ins.iconst(types::I8, 0), JitType::Unit),
+            Literal::String(text) => (self.string_pointer(text)?, JitType::String),
+        })
+    }
+    
+    /// 文字列の実体をエンジンに保持し、そのアドレスを返す
+    fn string_pointer(&mut self, text: &str) -> Result<Value> {
+        if !self.strings.contains_key(text) {
+            let value = CString::new(text).map_err(|_| {
+                EidosError::BackendError("NUL文字を含む文字列はJITでは扱えません".to_string())
+            })?;
+            self.strings.insert(text.to_string(), value);
+        }
+        // CStringのバッファはHashMapの再配置では移動しない
+        let address = self.strings[text].as_ptr() as i64;
+        Ok(self.builder.ins().iconst(self.pointer, address))
+    }
+    
+    /// オペランドのEIRでの型（レジスタのときだけ）
+    fn eir_type(&self, operand: &Operand) -> Option<&'a Type> {
+        let eir = self.eir;
+        match operand {
+            Operand::Register(reg) => eir.get_type(self.function.get_register_type(*reg)?),
+            _ => None,
+        }
+    }
+    
+    fn register_type(&self, reg: RegisterId) -> Result<JitType> {
+        self.registers.get(&reg).copied().ok_or_else(|| {
+            EidosError::BackendError(format!(
+                "関数 '{}' のレジスタ {} の型が見つかりません",
+                self.function.name, reg
+            ))
+        })
+    }
+    
+    fn block(&self, id: BlockId) -> Result<Block> {
+        self.blocks.get(&id).copied().ok_or_else(|| {
+            EidosError::BackendError(format!(
+                "関数 '{}' に存在しないブロック {} への分岐です",
+                self.function.name, id
+            ))
+        })
+    }
+    
+    fn unsupported_operand(&self, operand: &Operand) -> EidosError {
+        EidosError::BackendError(format!("オペランド {} はJITでは扱えません", operand))
+    }
+}
+
+/// エントリー関数を呼び出し、実行中にパニックしていればメッセージとスタックトレースをエラーとして返す
+///
+/// # Safety
+///
+/// `call_entry` と同じ。
+unsafe fn run_entry(code: *const u8, returns: JitType) -> Result<JitValue> {
+    let value = call_entry(code, returns);
+    match take_panic() {
+        Some(panic) => Err(panic.into_error()),
+        None => Ok(value),
+    }
+}
+
+/// 引数なしのエントリー関数を呼び出し、戻り値をJitValueに変換する
+///
+/// # Safety
+///
+/// `code` は引数を取らず、`returns` に対応する型を返す確定済みの関数でなければならない。
+unsafe fn call_entry(code: *const u8, returns: JitType) -> JitValue {
+    match returns {
+        JitType::Unit => {
+            let entry: extern "C" fn() = std::mem::transmute(code);
+            entry();
+            JitValue::Unit
+        }
+        JitType::Bool => {
+            let entry: extern "C" fn() -> i8 = std::mem::transmute(code);
+            JitValue::Bool(entry() != 0)
+        }
+        JitType::Int => {
+            let entry: extern "C" fn() -> i64 = std::mem::transmute(code);
+            JitValue::Int(entry())
+        }
+        // 関数は配列やスライスを返せない（`eir_builder` で拒否する）ため、アドレスを整数として返す
+        JitType::Pointer => {
+            let entry: extern "C" fn() -> usize = std::mem::transmute(code);
+            JitValue::Int(entry() as i64)
+        }
+        JitType::Float => {
+            let entry: extern "C" fn() -> f64 = std::mem::transmute(code);
+            JitValue::Float(entry())
+        }
+        JitType::Char => {
+            let entry: extern "C" fn() -> u32 = std::mem::transmute(code);
+            JitValue::Char(char::from_u32(entry()).unwrap_or(char::REPLACEMENT_CHARACTER))
+        }
+        JitType::Sized(numeric) => call_sized_entry(code, numeric),
+        JitType::String => {
+            let entry: extern "C" fn() -> *const c_char = std::mem::transmute(code);
+            let value = entry();
+            if value.is_null() {
+                JitValue::String(String::new())
+            } else {
+                JitValue::String(CStr::from_ptr(value).to_string_lossy().into_owned())
+            }
+        }
+    }
+}
+
+/// ビット幅を指定した数値型を返すエントリー関数を呼び出す
+///
+/// # Safety
+///
+/// `call_entry` と同じ。
+unsafe fn call_sized_entry(code: *const u8, numeric: NumericType) -> JitValue {
+    match numeric {
+        NumericType::I8 => JitValue::Int(std::mem::transmute::<*const u8, extern "C" fn() -> i8>(code)() as i64),
+        NumericType::I16 => JitValue::Int(std::mem::transmute::<*const u8, extern "C" fn() -> i16>(code)() as i64),
+        NumericType::I32 => JitValue::Int(std::mem::transmute::<*const u8, extern "C" fn() -> i32>(code)() as i64),
+        NumericType::I64 => JitValue::Int(std::mem::transmute::<*const u8, extern "C" fn() -> i64>(code)()),
+        NumericType::U8 => JitValue::Int(std::mem::transmute::<*const u8, extern "C" fn() -> u8>(code)() as i64),
+        NumericType::U16 => JitValue::Int(std::mem::transmute::<*const u8, extern "C" fn() -> u16>(code)() as i64),
+        NumericType::U32 => JitValue::Int(std::mem::transmute::<*const u8, extern "C" fn() -> u32>(code)() as i64),
+        NumericType::U64 => JitValue::UInt(std::mem::transmute::<*const u8, extern "C" fn() -> u64>(code)()),
+        NumericType::F32 => JitValue::Float(std::mem::transmute::<*const u8, extern "C" fn() -> f32>(code)() as f64),
+        NumericType::F64 => JitValue::Float(std::mem::transmute::<*const u8, extern "C" fn() -> f64>(code)()),
+    }
+}
+
+fn variable(reg: RegisterId) -> Variable {
+    Variable::from_u32(reg.0)
+}
+
+/// 8ビット値は呼び出し側でゼロ拡張してホストのABIに合わせる
+fn abi_param(ty: types::Type) -> AbiParam {
+    if ty == types::I8 {
+        AbiParam::new(ty).uext()
+    } else {
+        AbiParam::new(ty)
+    }
+}
+
+fn jit_type(module: &Module, id: TypeId) -> Result<JitType> {
+    match module.get_type(id) {
+        Some(ty) => JitType::from_type(ty).ok_or_else(|| {
+            EidosError::BackendError(format!("型 {} はJITでは未対応です", ty))
+        }),
+        None => Err(EidosError::BackendError(format!("型 {} がモジュールに登録されていません", id))),
+    }
+}
+
+fn function_signature(module: &Module, function: &Function) -> Result<FunctionSignature> {
+    Ok(FunctionSignature {
+        params: function
+            .parameters
+            .iter()
+            .map(|(_, id)| jit_type(module, *id))
+            .collect::<Result<Vec<_>>>()?,
+        returns: jit_type(module, function.return_type)?,
+        param_elements: function
+            .parameters
+            .iter()
+            .map(|(_, id)| tuple_elements(module, *id))
+            .collect::<Result<Vec<_>>>()?,
+        elements: tuple_elements(module, function.return_type)?,
+    })
+}
+
+/// タプル型（範囲を含む）の要素のJitType（タプルでなければ空）
+fn tuple_elements(module: &Module, id: TypeId) -> Result<Vec<JitType>> {
+    match module.get_type(id).and_then(Type::field_types) {
+        Some(elements) => elements
+            .into_iter()
+            .map(|element| {
+                JitType::from_type(element)
+                    .ok_or_else(|| EidosError::BackendError(format!("型 {} はJITでは未対応です", element)))
+            })
+            .collect(),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// 関数本体の指紋を計算する
+///
+/// TypeIdはモジュールを作り直すたびに変わるため、型はJitTypeに置き換えてから比較する。
+fn fingerprint(module: &Module, function: &Function) -> Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    function.name.hash(&mut hasher);
+    function.entry_block.0.hash(&mut hasher);
+    
+    let mut registers: Vec<_> = function.register_types.iter().collect();
+    registers.sort_by_key(|(reg, _)| reg.0);
+    for (reg, type_id) in registers {
+        reg.0.hash(&mut hasher);
+        jit_type(module, *type_id)?.hash(&mut hasher);
+        tuple_elements(module, *type_id)?.hash(&mut hasher);
+    }
+    
+    let mut blocks: Vec<_> = function.blocks.values().collect();
+    blocks.sort_by_key(|block| block.id.0);
+    for block in blocks {
+        block.id.0.hash(&mut hasher);
+        for (reg, _) in &block.parameters {
+            reg.0.hash(&mut hasher);
+        }
+        for (_, instruction) in &block.instructions {
+            match instruction {
+                Instruction::Cast { value, target_type, result } => {
+                    format!("Cast {:?} {:?}", value, result).hash(&mut hasher);
+                    jit_type(module, *target_type)?.hash(&mut hasher);
+                }
+                _ => format!("{:?}", instruction).hash(&mut hasher),
+            }
+        }
+        format!("{:?}", block.terminator).hash(&mut hasher);
+    }
+    Ok(hasher.finish())
+}
+
+/// エントリー関数の本体の指紋（エントリー関数がなければ `None`）
+fn entry_fingerprint(module: &Module) -> Result<Option<u64>> {
+    module
+        .entry_point
+        .and_then(|id| module.get_function(id))
+        .map(|function| fingerprint(module, function))
+        .transpose()
+}
+
+fn is_control_instruction(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Return { .. } | Instruction::Branch { .. } | Instruction::BranchCond { .. })
+}
+
+fn unsupported(instruction: &str) -> EidosError {
+    EidosError::BackendError(format!("JITは {} 命令に対応していません", instruction))
+}
+
+fn jit_error<E: fmt::Display>(error: E) -> EidosError {
+    EidosError::BackendError(format!("JITコンパイルに失敗しました: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::FunctionId;
+    use crate::core::eir_builder::build_test_module;
+    
+    /// `fn name(a: int) -> int { a * factor }` を追加する
+    fn add_scale_function(module: &mut Module, id: u32, name: &str, factor: i64) {
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(id), name, func_type, int);
+        let a = function.add_parameter("a", int);
+        let product = function.create_register(int);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::BinaryOp {
+            op: BinaryOp::Mul,
+            lhs: Operand::Register(a),
+            rhs: Operand::Literal(Literal::Int(factor)),
+            result: product,
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(product)),
+        });
+        module.add_function(function);
+    }
+    
+    /// `callee(arg)` の値を返すエントリー関数を追加する
+    fn add_entry(module: &mut Module, id: u32, callee: &str, arg: i64) {
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(Vec::new(), Type::int()));
+        let mut function = Function::new(FunctionId(id), "main", func_type, int);
+        let result = function.create_register(int);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::Call {
+            function: callee.to_string(),
+            arguments: vec![Operand::Literal(Literal::Int(arg))],
+            result: Some(result),
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(result)),
+        });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+    }
+    
+    #[test]
+    fn test_run_entry_function() {
+        let mut module = Module::new("test");
+        add_scale_function(&mut module, 0, "double", 2);
+        add_entry(&mut module, 1, "double", 21);
+        
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(42));
+        assert_eq!(engine.compiled_functions(), vec!["double"]);
+    }
+    
+    #[test]
+    fn test_intrinsic_replaces_body() {
+        let mut module = Module::new("test");
+        // 本体（a * 2）ではなく popcnt 命令になる
+        add_scale_function(&mut module, 0, "ctpop", 2);
+        module.get_function_mut(FunctionId(0)).unwrap().attributes.tags.insert(codegen::INTRINSIC_ATTRIBUTE.to_string());
+        add_entry(&mut module, 1, "ctpop", 0b1011);
+        
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(3));
+    }
+    
+    #[test]
+    fn test_call_compiled_function() {
+        let mut module = Module::new("test");
+        add_scale_function(&mut module, 0, "double", 2);
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(Vec::new(), Type::int()));
+        let mut answer = Function::new(FunctionId(1), "answer", func_type, int);
+        let entry = answer.entry_block;
+        answer.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Literal(Literal::Int(42))),
+        });
+        module.add_function(answer);
+        
+        let mut engine = JitEngine::with_opt_level(3).unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Unit);
+        assert_eq!(engine.call("answer").unwrap(), JitValue::Int(42));
+        assert_eq!(engine.call("answer").unwrap(), JitValue::Int(42));
+        assert!(engine.call("double").is_err());
+        assert!(engine.call("missing").is_err());
+    }
+    
+    #[test]
+    fn test_redefinition_across_runs() {
+        let mut engine = JitEngine::new().unwrap();
+        
+        let mut first = Module::new("first");
+        add_scale_function(&mut first, 0, "scale", 2);
+        add_entry(&mut first, 1, "scale", 5);
+        assert_eq!(engine.run(&first).unwrap(), JitValue::Int(10));
+        
+        // 同じシグネチャでscaleを再定義する
+        let mut second = Module::new("second");
+        add_scale_function(&mut second, 0, "scale", 3);
+        add_entry(&mut second, 1, "scale", 5);
+        assert_eq!(engine.run(&second).unwrap(), JitValue::Int(15));
+        
+        // 変更のない関数はキャッシュを再利用する
+        let mut third = Module::new("third");
+        add_scale_function(&mut third, 0, "scale", 3);
+        add_entry(&mut third, 1, "scale", 7);
+        assert_eq!(engine.run(&third).unwrap(), JitValue::Int(21));
+    }
+    
+    #[test]
+    fn test_hot_reload_keeps_running_program() {
+        let mut engine = JitEngine::new().unwrap();
+        let mut first = Module::new("first");
+        add_scale_function(&mut first, 0, "scale", 2);
+        add_entry(&mut first, 1, "scale", 5);
+        let program = engine.start(&first).unwrap();
+        assert_eq!(program.join().unwrap(), JitValue::Int(10));
+        
+        // 本体だけの変更は差し替える（エントリー関数は実行中のものをそのまま使う）
+        let mut second = Module::new("second");
+        add_scale_function(&mut second, 0, "scale", 3);
+        add_scale_function(&mut second, 2, "triple", 3);
+        add_entry(&mut second, 1, "scale", 5);
+        assert_eq!(
+            engine.hot_reload(&second).unwrap(),
+            HotReload::Swapped(vec!["scale".to_string(), "triple".to_string()])
+        );
+        assert_eq!(engine.hot_reload(&second).unwrap(), HotReload::Swapped(Vec::new()));
+        
+        let mut changed_entry = Module::new("third");
+        add_scale_function(&mut changed_entry, 0, "scale", 3);
+        add_entry(&mut changed_entry, 1, "scale", 7);
+        assert!(matches!(engine.hot_reload(&changed_entry).unwrap(), HotReload::Restart(_)));
+        
+        // シグネチャの変更は反映しない
+        let mut changed_signature = Module::new("fourth");
+        let int = changed_signature.add_type(Type::int());
+        let func_type = changed_signature.add_type(Type::function(Vec::new(), Type::int()));
+        let mut scale = Function::new(FunctionId(0), "scale", func_type, int);
+        let entry = scale.entry_block;
+        scale.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Literal(Literal::Int(0))),
+        });
+        changed_signature.add_function(scale);
+        assert_eq!(
+            engine.hot_reload(&changed_signature).unwrap(),
+            HotReload::Restart("関数 'scale' のシグネチャが変わりました".to_string())
+        );
+        
+        // 差し替えた本体を呼ぶ
+        let mut callers = Module::new("callers");
+        add_scale_function(&mut callers, 0, "scale", 3);
+        add_entry(&mut callers, 1, "scale", 5);
+        callers.entry_point = None;
+        engine.run(&callers).unwrap();
+        assert_eq!(engine.call("main").unwrap(), JitValue::Int(15));
+    }
+    
+    #[test]
+    fn test_trace_does_not_change_results() {
+        let mut module = Module::new("test");
+        add_scale_function(&mut module, 0, "double", 2);
+        add_entry(&mut module, 1, "double", 21);
+        
+        for mode in [TraceMode::Calls, TraceMode::Instr] {
+            let trace = TraceOptions { mode, filter: Some("dou".to_string()) };
+            let mut engine = JitEngine::new().unwrap().with_trace(trace);
+            assert_eq!(engine.run(&module).unwrap(), JitValue::Int(42));
+        }
+        
+        assert_eq!(trace_value(JitType::Float.code(), 1.5f64.to_bits() as i64), "1.5");
+        assert_eq!(trace_value(JitType::Char.code(), 'あ' as i64), "'あ'");
+        assert_eq!(trace_value(JitType::Bool.code(), 1), "true");
+    }
+    
+    #[test]
+    fn test_bigint_calls() {
+        // bigint::mul(bigint::from_int(4294967296), "4294967296") と、その結果が Int に収まるか
+        let mut module = Module::new("test");
+        let string = module.add_type(Type::string());
+        let boolean = module.add_type(Type::bool());
+        let func_type = module.add_type(Type::function(Vec::new(), Type::string()));
+        let mut function = Function::new(FunctionId(0), "main", func_type, string);
+        let (a, product, fits) = (function.create_register(string), function.create_register(string), function.create_register(boolean));
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::Call {
+            function: "bigint::from_int".to_string(),
+            arguments: vec![Operand::Literal(Literal::Int(1 << 32))],
+            result: Some(a),
+        });
+        function.add_instruction(entry, Instruction::Call {
+            function: "bigint::mul".to_string(),
+            arguments: vec![Operand::Register(a), Operand::Literal(Literal::String("4294967296".to_string()))],
+            result: Some(product),
+        });
+        function.add_instruction(entry, Instruction::Call {
+            function: "bigint::fits_int".to_string(),
+            arguments: vec![Operand::Register(product)],
+            result: Some(fits),
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(product)),
+        });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+        
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::String("18446744073709551616".to_string()));
+    }
+    
+    #[test]
+    fn test_loop_with_block_parameters() {
+        // sum(n) = n + (n-1) + ... + 1
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let boolean = module.add_type(Type::bool());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "sum", func_type, int);
+        let n = function.add_parameter("n", int);
+        let entry = function.entry_block;
+        let header = function.create_block();
+        let body = function.create_block();
+        let exit = function.create_block();
+        
+        let i = function.create_register(int);
+        let acc = function.create_register(int);
+        let header_block = function.get_block_mut(header).unwrap();
+        header_block.add_parameter(i, int);
+        header_block.add_parameter(acc, int);
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Branch {
+            target: header,
+            args: vec![Operand::Register(n), Operand::Literal(Literal::Int(0))],
+        });
+        
+        let done = function.create_register(boolean);
+        function.add_instruction(header, Instruction::BinaryOp {
+            op: BinaryOp::Le,
+            lhs: Operand::Register(i),
+            rhs: Operand::Literal(Literal::Int(0)),
+            result: done,
+        });
+        function.get_block_mut(header).unwrap().set_terminator(Terminator::BranchCond {
+            condition: Operand::Register(done),
+            true_target: exit,
+            true_args: Vec::new(),
+            false_target: body,
+            false_args: Vec::new(),
+        });
+        
+        let next_i = function.create_register(int);
+        let next_acc = function.create_register(int);
+        function.add_instruction(body, Instruction::BinaryOp {
+            op: BinaryOp::Sub,
+            lhs: Operand::Register(i),
+            rhs: Operand::Literal(Literal::Int(1)),
+            result: next_i,
+        });
+        function.add_instruction(body, Instruction::BinaryOp {
+            op: BinaryOp::Add,
+            lhs: Operand::Register(acc),
+            rhs: Operand::Register(i),
+            result: next_acc,
+        });
+        function.get_block_mut(body).unwrap().set_terminator(Terminator::Branch {
+            target: header,
+            args: vec![Operand::Register(next_i), Operand::Register(next_acc)],
+        });
+        function.get_block_mut(exit).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(acc)),
+        });
+        module.add_function(function);
+        add_entry(&mut module, 1, "sum", 10);
+        
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(55));
+    }
+    
+    #[test]
+    fn test_arrays_and_slices() {
+        let source = r#"
+            fn sum(values: &[Int]) -> Int {
+                let mut total = 0;
+                let mut i = 0;
+                while i < len(values) {
+                    total = total + values[i];
+                    i = i + 1;
+                }
+                total
+            }
+            fn main() -> Int {
+                let mut a: [Int; 4] = [1, 2, 3, 4];
+                a[3] = 10;
+                let b = a;
+                a[0] = 100;
+                sum(&a) * 1000 + sum(&b) + [7; 3][2]
+            }
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        
+        // 配列の代入は要素をコピーする
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(115_023));
+    }
+    
+    #[test]
+    fn test_tuples() {
+        let source = r#"
+            fn divmod(a: Int, b: Int) -> (Int, Int) {
+                (a / b, a % b)
+            }
+            fn stats(x: Int) -> (Int, Float, Bool, Int, Int) {
+                (x, 1.5, x > 0, x * 2, x * 3)
+            }
+            fn main() -> Int {
+                let (q, r) = divmod(17, 5);
+                let (a, _, positive, b, c) = stats(4);
+                let mut p = (1, 2);
+                let old = p;
+                p = (10, 20);
+                let (x, y) = old;
+                let (z, _) = p;
+                let sign = if positive { 0 } else { 5000000 };
+                q * 1000000 + r * 100000 + (a + b + c) * 1000 + z * 10 + x + y + sign
+            }
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        
+        // 変数に代入し直しても、先に読み出したタプルは変わらない
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(3_224_103));
+    }
+    
+    #[test]
+    fn test_integer_division() {
+        // 0除算とオーバーフローの検査を通っても、商は0の方向に切り捨て、剰余は被除数の符号になる
+        let source = r#"
+            fn div(a: Int, b: Int) -> Int { a / b }
+            fn rem(a: Int, b: Int) -> Int { a % b }
+            fn main() -> Int {
+                div(-7, 2) * 100 + rem(-7, 2) * 10 + div(9223372036854775807, -1) / 1000000000000000000
+            }
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(-319));
+    }
+    
+    #[test]
+    fn test_sized_numeric_types() {
+        // ビット幅を指定した整数はその幅で折り返し、符号なしの型は符号なしで比較する
+        let source = r#"
+            fn wrap(a: u8, b: u8) -> u8 { a + b }
+            fn narrow(a: i32, b: i32) -> i32 { a * b }
+            fn half(a: i8, b: i8) -> i8 { a / b }
+            fn less(a: u32, b: u32) -> Bool { a < b }
+            fn main() -> Int {
+                let w = wrap(250 as u8, 10 as u8) as Int;
+                let n = narrow(65536 as i32, 65537 as i32) as Int;
+                let h = half(-128 as i8, -1 as i8) as Int;
+                let f = (1.5 as f32 + 2.25 as f32) as Float;
+                let l = if less(0 as u32 - 1 as u32, 1 as u32) { 1000000000 } else { 0 };
+                n * 1000 + w * 100 + h + (f * 4.0) as Int * 10 + l
+            }
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(65_536_422));
+        
+        let source = r#"
+            fn max() -> u64 { 0 as u64 - 1 as u64 }
+            fn main() {}
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        engine.run(&module).unwrap();
+        assert_eq!(engine.call("max").unwrap(), JitValue::UInt(u64::MAX));
+    }
+    
+    #[test]
+    fn test_panic_returns_error() {
+        let source = r#"
+            fn div(a: Int, b: Int) -> Int { a / b }
+            fn at(i: Int) -> Int {
+                let values = [1, 2, 3];
+                values[i]
+            }
+            fn main() -> Int { div(1, 0) }
+        "#;
+        let mut engine = JitEngine::new().unwrap();
+        let error = engine.run(&build_test_module("test", source).unwrap()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "実行時エラー: panic: division by zero\nstack trace (most recent call first):\n    at div\n    at main"
+        );
+        
+        // パニックした後も同じエンジンで続けて実行できる
+        let source = source.replace("div(1, 0)", "div(-9223372036854775807 - 1, -1)");
+        let error = engine.run(&build_test_module("test", &source).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("panic: division overflow"), "{}", error);
+        let source = source.replace("div(-9223372036854775807 - 1, -1)", "at(1) * 10 + at(5)");
+        let error = engine.run(&build_test_module("test", &source).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("    at at\n    at main"), "{}", error);
+        let source = source.replace("at(5)", "at(2)");
+        assert_eq!(engine.run(&build_test_module("test", &source).unwrap()).unwrap(), JitValue::Int(23));
+    }
+    
+    #[test]
+    fn test_for_loops() {
+        let source = r#"
+            fn sum(values: &[Int]) -> Int {
+                let mut total = 0;
+                for value in values {
+                    total = total + value;
+                }
+                total
+            }
+            fn main() -> Int {
+                let mut squares = 0;
+                for i in 0..5 {
+                    squares = squares + i * i;
+                }
+                let primes: [Int; 4] = [2, 3, 5, 7];
+                let mut product = 1;
+                for p in primes {
+                    product = product * p;
+                }
+                let range = 3..1;
+                let mut empty = 0;
+                for _ in range {
+                    empty = empty + 1;
+                }
+                squares * 100000 + product * 100 + sum(primes) + empty
+            }
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        
+        // 範囲・固定長配列・スライスは同じ反復の仕組みで回る（終わりが始まりより前の範囲は1度も回らない）
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(3_021_017));
+    }
+    
+    #[test]
+    fn test_comprehensions() {
+        let source = r#"
+            fn sum(values: &[Int]) -> Int {
+                let mut total = 0;
+                for value in values {
+                    total = total + value;
+                }
+                total
+            }
+            fn main() -> Int {
+                let xs: [Int; 5] = [3, -1, 4, -1, 5];
+                let doubled = [x * 2 for x in xs];
+                let positive = [x for x in xs if x > 0];
+                let squares = [i * i for i in 0..4];
+                let halves = [i / 2 for i in 0..10 if i % 2 == 0];
+                let mut size = 0;
+                for _ in positive {
+                    size = size + 1;
+                }
+                sum(doubled) * 1000000 + sum(positive) * 10000 + sum(squares) * 100 + sum(halves) + size * 1000
+            }
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        
+        // 条件のない内包表記は固定長配列、条件のある内包表記は条件を満たした要素だけのスライスになる
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(20_124_410));
+    }
+    
+    #[test]
+    fn test_defer() {
+        let source = r#"
+            fn main() -> Int {
+                let mut trace = 0;
+                let value = {
+                    defer trace = trace * 10 + 1;
+                    defer {
+                        let step = 2;
+                        trace = trace * 10 + step;
+                    };
+                    trace = trace * 10 + 3;
+                    trace
+                };
+                let mut i = 0;
+                while i < 2 {
+                    defer trace = trace * 10 + 5;
+                    trace = trace * 10 + 4;
+                    i = i + 1;
+                }
+                value * 10000000 + trace
+            }
+        "#;
+        let module = build_test_module("test", source).unwrap();
+        
+        // ブロックの値を求めてから、登録と逆の順に評価する（ループの本体では反復ごとに評価する）
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(33_214_545));
+    }
+    
+    #[test]
+    fn test_assertions_and_contracts() {
+        let source = r#"
+            fn isqrt(n: Int) -> Int
+                require n >= 0
+                ensure result * result <= n && (result + 1) * (result + 1) > n
+            {
+                let mut root = 0;
+                while (root + 1) * (root + 1) <= n {
+                    root = root + 1;
+                }
+                root
+            }
+            
+            fn main() -> Int {
+                let mut checked = 0;
+                assert({ checked = checked + 1; isqrt(50) == 7 }, "isqrt");
+                assert(isqrt(0) == 0);
+                isqrt(1000) * 10 + checked
+            }
+        "#;
+        let program = crate::frontend::parse_test_source(source).unwrap();
+        let module = crate::core::eir::ModuleBuilder::new("test".to_string()).build_from_ast(&program).unwrap();
+        crate::core::eir_verifier::verify(&module).unwrap();
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(311));
+        
+        // 検査しないときは条件の式も評価しない
+        let module = crate::core::eir::ModuleBuilder::new("test".to_string()).without_assertions().build_from_ast(&program).unwrap();
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(310));
+    }
+    
+    #[test]
+    fn test_comptime_generates_code() {
+        let source = r#"
+            const fn getter(name: String, value: String) -> String {
+                "fn " + name + "() -> Int { " + value + " }\n"
+            }
+            const fn ones(n: Int) -> String {
+                let mut sum = "1";
+                let mut i = 1;
+                while i < n {
+                    sum = sum + " + 1";
+                    i += 1;
+                }
+                sum
+            }
+            const WIDTH: Int = 640;
+            
+            comptime {
+                getter("width", "640") + getter("eight", ones(8))
+            }
+            
+            fn main() -> Int {
+                let area = comptime { WIDTH * 2 };
+                width() + eight() + area
+            }
+        "#;
+        let program = crate::frontend::parse_test_source(source).unwrap();
+        let program = crate::frontend::ConstEvaluator::new().expand_comptime(program).unwrap();
+        let module = crate::core::eir::ModuleBuilder::new("test".to_string()).build_from_ast(&program).unwrap();
+        let mut engine = JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), JitValue::Int(1928));
+    }
+}
@@ -0,0 +1,1481 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use log::{debug, info};
+
+use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::bigint;
+use crate::core::coverage;
+use crate::core::eir::{
+    BasicBlock, BinaryOp, BlockId, Function, GlobalAttributes, Instruction, InstructionId, Literal, Module,
+    Operand, RegisterId, Terminator, UnaryOp,
+};
+use crate::core::source_map::SourceMap;
+use crate::core::types::{NumericType, Type, TypeId, TypeKind};
+
+use super::codegen::{self, Backend, CodegenOptions, Intrinsic};
+
+/// JavaScriptで予約されている識別子（関数名として使う場合は末尾に `_` を付ける）
+const RESERVED_WORDS: &[&str] = &[
+    "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete",
+    "do", "else", "enum", "export", "extends", "false", "finally", "for", "function", "if", "implements",
+    "import", "in", "instanceof", "interface", "let", "new", "null", "package", "private", "protected",
+    "public", "return", "static", "super", "switch", "this", "throw", "true", "try", "typeof", "var",
+    "void", "while", "with", "yield", "arguments", "eval", "undefined", "NaN", "Infinity",
+];
+
+/// 生成モジュールの先頭に置く出力ランタイム
+///
+/// 出力先は `setOutput` で差し替えられるため、ブラウザのプレイグラウンドでも画面に表示できる。
+/// 各関数は `__enter` で呼び出しフレームを積み、実行時エラーの `__panic` はそれをEidosのスタックトレースとして報告する。
+const PRELUDE: &str = r#"let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+"#;
+
+/// `Option` と `Result` の値を扱うモジュールでだけ出力する補助関数
+///
+/// 値は `{ ok, value }` のオブジェクトで表し、`ok` が `Some`・`Ok` かどうかを示す。
+const VARIANT_HELPERS: &str = r#"
+function __unwrap(variant, ok, message) {
+  if (variant.ok !== ok) __panic(message);
+  return variant.value;
+}
+"#;
+
+/// 固定長配列を使うモジュールでだけ出力する補助関数
+///
+/// 配列はJavaScriptの配列で、スライスは `{ data, length }` で表す。`GetElementPtr` の要素は、
+/// スロットと同じく `value` プロパティで読み書きするオブジェクトになる。
+const ARRAY_HELPERS: &str = r#"
+function __element(array, index) {
+  const i = Number(index);
+  return { get value() { return array[i]; }, set value(v) { array[i] = v; } };
+}
+"#;
+
+/// `BigInt` の値を使うモジュールでだけ出力する補助関数
+///
+/// `BigInt` の値はJavaScriptの `bigint` で表し、ビット幅で折り返さない。`bigint::*` の呼び出しはこのオブジェクトのメソッドになる。
+const BIGINT_HELPERS: &str = r#"
+const __bigint = {
+  parse(text) {
+    if (!/^-?[0-9]+$/.test(text)) __panic(`bigint::parse: '${text}' は整数ではありません`);
+    return BigInt(text);
+  },
+  from_int: (value) => value,
+  to_int: (value) => BigInt.asIntN(64, value),
+  fits_int: (value) => BigInt.asIntN(64, value) === value,
+  to_float: (value) => Number(value),
+  to_string: (value) => value.toString(),
+  add: (a, b) => a + b,
+  sub: (a, b) => a - b,
+  mul: (a, b) => a * b,
+  div(a, b) {
+    if (b === 0n) __panic("bigint::div: division by zero");
+    return a / b;
+  },
+  rem(a, b) {
+    if (b === 0n) __panic("bigint::rem: division by zero");
+    return a % b;
+  },
+  neg: (value) => -value,
+  abs: (value) => (value < 0n ? -value : value),
+  cmp: (a, b) => (a < b ? -1n : a > b ? 1n : 0n),
+  pow(base, exponent) {
+    if (exponent < 0n || exponent > 0xffffffffn) __panic(`bigint::pow の指数 ${exponent} は使えません`);
+    return base ** exponent;
+  },
+  gcd(a, b) {
+    [a, b] = [a < 0n ? -a : a, b < 0n ? -b : b];
+    while (b !== 0n) [a, b] = [b, a % b];
+    return a;
+  },
+  mod_pow(base, exponent, modulus) {
+    if (exponent < 0n || modulus === 0n) __panic("bigint::mod_pow の指数が負か、法が0です");
+    if (modulus < 0n) modulus = -modulus;
+    const reduce = (value) => ((value % modulus) + modulus) % modulus;
+    let result = reduce(1n);
+    base = reduce(base);
+    for (; exponent > 0n; exponent >>= 1n) {
+      if (exponent & 1n) result = reduce(result * base);
+      base = reduce(base * base);
+    }
+    return result;
+  },
+};
+"#;
+
+/// カバレッジ計測のカウンタを挿入したモジュールでだけ出力する補助関数
+///
+/// `coverageProfile()` でカウンタの値を取得できる。Node.js では終了時にプロファイルを
+/// `EIDOS_COVERAGE_FILE`（未設定なら `<モジュール名>.profile.json`）に書き出す。
+const COVERAGE_HELPERS: &str = r#"
+const __cov = new Array(EIDOS_COV_COUNTERS).fill(0);
+function __cov_hit(counter) {
+  __cov[Number(counter)]++;
+}
+function __cov_branch(counter, taken) {
+  __cov[Number(counter) + (taken ? 0 : 1)]++;
+}
+/** カバレッジのプロファイル（`eidos cov report` に渡す JSON の値） */
+export function coverageProfile() {
+  return { module: EIDOS_COV_MODULE, counters: __cov.slice() };
+}
+if (typeof process !== "undefined" && process.versions?.node) {
+  const { writeFileSync } = await import("node:fs");
+  process.on("exit", () => {
+    const path = process.env.EIDOS_COVERAGE_FILE ?? EIDOS_COV_PROFILE;
+    writeFileSync(path, JSON.stringify(coverageProfile()) + "\n");
+  });
+}
+"#;
+
+/// 値のJavaScript上での表現
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsType {
+    /// `undefined`
+    Unit,
+    /// `boolean`
+    Bool,
+    /// `bigint`（64ビット整数として扱う）
+    Int,
+    /// `number`
+    Float,
+    /// `string`（1文字の文字列）
+    Char,
+    /// `string`
+    String,
+    /// `{ ok, value }`（`Option` と `Result` の値）
+    Variant,
+    /// ビット幅を指定した数値型（整数型は `bigint`、`f32` は `number`）
+    Sized(NumericType),
+    /// `bigint`（任意精度の整数として扱い、折り返さない）
+    BigInt,
+    /// 関数などJavaScriptの値をそのまま使うもの
+    Any,
+}
+
+impl JsType {
+    fn from_type(ty: &Type) -> Self {
+        match &ty.kind {
+            TypeKind::Unit => JsType::Unit,
+            TypeKind::Bool => JsType::Bool,
+            TypeKind::Int => JsType::Int,
+            TypeKind::Float => JsType::Float,
+            TypeKind::Char => JsType::Char,
+            TypeKind::String => JsType::String,
+            TypeKind::Numeric(numeric) => JsType::Sized(*numeric),
+            TypeKind::BigInt => JsType::BigInt,
+            TypeKind::Enum { .. } if ty.is_fallible() => JsType::Variant,
+            _ => JsType::Any,
+        }
+    }
+    
+    /// リテラルの型
+    fn of_literal(literal: &Literal) -> Self {
+        match literal {
+            Literal::Int(_) => JsType::Int,
+            Literal::Float(_) => JsType::Float,
+            Literal::Bool(_) => JsType::Bool,
+            Literal::Char(_) => JsType::Char,
+            Literal::String(_) => JsType::String,
+            Literal::Unit => JsType::Unit,
+        }
+    }
+    
+    /// `bigint` で表す整数型かどうか
+    fn is_integer(&self) -> bool {
+        match self {
+            JsType::Int => true,
+            JsType::Sized(numeric) => !numeric.is_float(),
+            _ => false,
+        }
+    }
+    
+    fn is_float(&self) -> bool {
+        matches!(self, JsType::Float | JsType::Sized(NumericType::F32))
+    }
+    
+    /// 計算結果をこの型の値に収める式（整数型はビット幅で折り返し、`f32` は単精度に丸める）
+    fn wrap(&self, expr: &str) -> String {
+        match self {
+            JsType::Int => format!("BigInt.asIntN(64, {})", expr),
+            JsType::Sized(NumericType::F32) => format!("Math.fround({})", expr),
+            JsType::Sized(numeric) if numeric.is_signed() => format!("BigInt.asIntN({}, {})", numeric.bits(), expr),
+            JsType::Sized(numeric) if !numeric.is_float() => format!("BigInt.asUintN({}, {})", numeric.bits(), expr),
+            _ => expr.to_string(),
+        }
+    }
+    
+    /// TypeScriptの型名
+    fn ts_name(&self) -> &'static str {
+        match self {
+            JsType::Unit => "void",
+            JsType::Bool => "boolean",
+            JsType::Int => "bigint",
+            JsType::Float => "number",
+            JsType::Char | JsType::String => "string",
+            JsType::Variant => "{ ok: boolean; value: unknown }",
+            JsType::Sized(numeric) if numeric.is_float() => "number",
+            JsType::Sized(_) | JsType::BigInt => "bigint",
+            JsType::Any => "unknown",
+        }
+    }
+}
+
+/// EIRを読みやすいESモジュールに変換するバックエンド
+///
+/// 整数は `BigInt` で表し、演算結果は `BigInt.asIntN(64, ...)` などで型のビット幅に折り返す。
+/// 基本ブロックが1つの関数はそのまま、複数ある関数は `switch` による状態機械として出力する。
+pub struct JsBackend {
+    /// `declare_function` / `declare_global` で追加された外部シンボル
+    declarations: Vec<String>,
+}
+
+impl JsBackend {
+    pub fn new() -> Self {
+        Self {
+            declarations: Vec::new(),
+        }
+    }
+    
+    /// EIRモジュールをESモジュールのソースコードに変換
+    pub fn emit(&self, module: &Module) -> Result<String> {
+        Ok(self.emit_module(module, None)?.0)
+    }
+    
+    /// EIRモジュールを変換し、元のソースとの対応を表すソースマップ（Source Map v3 のJSON）を作る
+    ///
+    /// 関数の先頭と、フレームの行番号を更新する文を元のソースの行に対応付ける。位置に範囲があれば
+    /// `sources` で行と列を求め、元のソースの内容もソースマップに含める。`map_file` は生成したコードの
+    /// 末尾の `sourceMappingURL` に書くソースマップのファイル名。
+    pub fn emit_with_source_map(&self, module: &Module, sources: &SourceMap, map_file: &str) -> Result<(String, String)> {
+        let (mut code, mappings) = self.emit_module(module, Some(sources))?;
+        let _ = writeln!(code, "//# sourceMappingURL={}", map_file);
+        Ok((code, source_map_json(&mappings, sources)))
+    }
+    
+    /// 生成したコードと、その行と元のソースの位置の対応（ソースマップを作るときだけ集める）
+    fn emit_module(&self, module: &Module, sources: Option<&SourceMap>) -> Result<(String, Vec<(usize, SourceLocation)>)> {
+        info!("JavaScriptを生成中: {}", module.name);
+        
+        let mut out = String::new();
+        let _ = writeln!(out, "// Eidos module '{}'", module.name.replace('\n', " "));
+        out.push_str(PRELUDE);
+        if module.types.values().any(Type::is_fallible) {
+            out.push_str(VARIANT_HELPERS);
+        }
+        if module.types.values().any(|ty| matches!(ty.kind, TypeKind::FixedArray { .. })) {
+            out.push_str(ARRAY_HELPERS);
+        }
+        if module.types.values().any(|ty| ty.kind == TypeKind::BigInt) {
+            out.push_str(BIGINT_HELPERS);
+        }
+        if let Some(counters) = coverage::counter_count(module) {
+            out.push_str(
+                &COVERAGE_HELPERS
+                    .replace("EIDOS_COV_COUNTERS", &counters.to_string())
+                    .replace("EIDOS_COV_MODULE", &string_literal(&module.name))
+                    .replace("EIDOS_COV_PROFILE", &string_literal(&format!("{}.profile.json", module.name))),
+            );
+        }
+        
+        // 外部関数と外部変数は実行環境（globalThis）から取得する
+        let mut externals: Vec<&str> = module.external_functions.keys().map(|name| name.as_str()).collect();
+        externals.extend(self.declarations.iter().map(|name| name.as_str()));
+        externals.sort();
+        externals.dedup();
+        if !externals.is_empty() {
+            out.push('\n');
+        }
+        for name in externals {
+            let _ = writeln!(out, "const {} = globalThis[{}];", external_name(name), string_literal(name));
+        }
+        
+        let mut globals: Vec<_> = module.globals.values().collect();
+        globals.sort_by(|a, b| a.name.cmp(&b.name));
+        if !globals.is_empty() {
+            out.push('\n');
+        }
+        for global in globals {
+            let keyword = if global.attributes.is_constant { "const" } else { "let" };
+            let init = match &global.initializer {
+                Some(literal) => cast(&literal_to_js(literal), JsType::of_literal(literal), js_type(module, global.ty)),
+                None => "undefined".to_string(),
+            };
+            let _ = writeln!(out, "{} {} = {};", keyword, global_name(&global.name), init);
+        }
+        
+        let mut functions: Vec<&Function> = module.functions.values().collect();
+        functions.sort_by_key(|f| f.id.0);
+        let mut mappings = Vec::new();
+        // 出力済みの行数（`counted` バイト目までを数えた）
+        let (mut line_count, mut counted) = (0, 0);
+        for function in functions {
+            debug!("JavaScriptを生成中: 関数 {}", function.name);
+            out.push('\n');
+            let (code, lines) = FunctionEmitter::new(module, function, sources).emit()?;
+            line_count += out[counted..].matches('\n').count();
+            counted = out.len();
+            mappings.extend(lines.into_iter().map(|(line, location)| (line_count + line, location)));
+            out.push_str(&code);
+        }
+        
+        if let Some(entry) = module.entry_point.and_then(|id| module.get_function(id)) {
+            let _ = writeln!(out, "\nexport default {};", function_name(&entry.name));
+        }
+        
+        Ok((out, mappings))
+    }
+    
+    /// 生成したモジュールに対応するTypeScriptの型定義（`.d.ts`）を生成
+    pub fn emit_declarations(&self, module: &Module) -> Result<String> {
+        let mut out = String::new();
+        let _ = writeln!(out, "// Eidos module '{}'", module.name.replace('\n', " "));
+        out.push_str("export function setOutput(sink: (line: string) => void): void;\n");
+        if coverage::counter_count(module).is_some() {
+            out.push_str("export function coverageProfile(): { module: string; counters: number[] };\n");
+        }
+        
+        let mut functions: Vec<&Function> = module.functions.values().collect();
+        functions.sort_by_key(|f| f.id.0);
+        for function in &functions {
+            let params = function
+                .parameters
+                .iter()
+                .map(|(name, type_id)| format!("{}: {}", sanitize_identifier(name), ts_type(module, *type_id)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let return_type = ts_type(module, function.return_type);
+            let return_type = if function.attributes.is_async { format!("Promise<{}>", return_type) } else { return_type };
+            let _ = writeln!(out, "export function {}({}): {};", function_name(&function.name), params, return_type);
+        }
+        
+        if let Some(entry) = module.entry_point.and_then(|id| module.get_function(id)) {
+            let _ = writeln!(out, "export default {};", function_name(&entry.name));
+        }
+        Ok(out)
+    }
+}
+
+impl Backend for JsBackend {
+    fn name(&self) -> &str {
+        "js"
+    }
+    
+    fn compile(&mut self, module: &Module, _options: &CodegenOptions) -> Result<Vec<u8>> {
+        Ok(self.emit(module)?.into_bytes())
+    }
+    
+    fn declare_function(&mut self, name: &str, _params: &[Type], _return_type: &Type) -> Result<()> {
+        self.declarations.push(name.to_string());
+        Ok(())
+    }
+    
+    fn declare_global(&mut self, name: &str, _ty: &Type, _initializer: Option<&Literal>, _attributes: &GlobalAttributes) -> Result<()> {
+        self.declarations.push(name.to_string());
+        Ok(())
+    }
+}
+
+/// 1つの関数のJavaScriptコードを生成する
+struct FunctionEmitter<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    /// PHIノードのための、分岐元ブロックで行う代入
+    phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>>,
+    /// 基本ブロックを状態機械として出力するか
+    state_machine: bool,
+    /// 最後に `__frame.line` に書き込んだ行番号（ブロックの先頭で忘れる）
+    frame_line: Option<usize>,
+    /// 位置情報の範囲から行を求めるソースマップ（あれば生成した行と元の位置の対応を集める）
+    sources: Option<&'a SourceMap>,
+    /// `body` の中の行の先頭のバイト位置と、元のソースの位置の対応
+    mappings: Vec<(usize, SourceLocation)>,
+    indent: usize,
+    body: String,
+}
+
+impl<'a> FunctionEmitter<'a> {
+    fn new(module: &'a Module, function: &'a Function, sources: Option<&'a SourceMap>) -> Self {
+        let mut phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>> = HashMap::new();
+        // 同じブロックへの移動の順序が実行ごとに変わらないよう、ブロックIDの順に集める
+        let mut blocks: Vec<&BasicBlock> = function.blocks.values().collect();
+        blocks.sort_by_key(|block| block.id.0);
+        for block in blocks {
+            for (_, instruction) in &block.instructions {
+                if let Instruction::Phi { incoming, result } = instruction {
+                    for (value, from) in incoming {
+                        phi_moves.entry(*from).or_default().push((*result, value.clone()));
+                    }
+                }
+            }
+        }
+        
+        Self {
+            module,
+            function,
+            phi_moves,
+            state_machine: function.blocks.len() > 1,
+            frame_line: None,
+            sources,
+            mappings: Vec::new(),
+            indent: 1,
+            body: String::new(),
+        }
+    }
+    
+    /// 元のソースの位置（ソースマップがあれば範囲から求め直す）
+    fn source_location(&self, location: &SourceLocation) -> SourceLocation {
+        match self.sources {
+            Some(sources) => sources.resolve(location),
+            None => location.clone(),
+        }
+    }
+    
+    /// 関数のコードと、その行（関数の先頭から数える）と元のソースの位置の対応
+    fn emit(mut self) -> Result<(String, Vec<(usize, SourceLocation)>)> {
+        let param_count = self.function.parameters.len() as u32;
+        
+        // パラメータは add_parameter により先頭から順にレジスタが割り当てられている
+        let params = (0..param_count).map(|i| register_name(RegisterId(i))).collect::<Vec<_>>().join(", ");
+        // 非同期関数はJavaScriptの非同期関数にして、実行環境のイベントループで中断・再開する
+        let keyword = if self.function.attributes.is_async { "async function" } else { "function" };
+        let mut out = format!("export {} {}({}) {{\n", keyword, function_name(&self.function.name), params);
+        
+        let mut locals: Vec<RegisterId> = self
+            .function
+            .register_types
+            .keys()
+            .copied()
+            .filter(|reg| reg.0 >= param_count)
+            .collect();
+        locals.sort_by_key(|reg| reg.0);
+        if !locals.is_empty() {
+            let names = locals.iter().map(|reg| register_name(*reg)).collect::<Vec<_>>().join(", ");
+            let _ = writeln!(out, "  let {};", names);
+        }
+        
+        // スタックトレース用の呼び出しフレームを積む
+        let location = self.function.location.as_ref().map(|location| self.source_location(location));
+        let (file, line) = match &location {
+            Some(location) => (location.file.display().to_string(), location.line),
+            None => (String::new(), 0),
+        };
+        let mut mappings = Vec::new();
+        if let Some(location) = location.filter(|_| self.sources.is_some()) {
+            mappings.push((0, location));
+        }
+        let _ = writeln!(
+            out,
+            "  const __frame = __enter({}, {}, {});",
+            string_literal(&self.function.name),
+            string_literal(&file),
+            line
+        );
+        
+        let mut block_ids: Vec<BlockId> = self.function.blocks.keys().copied().collect();
+        block_ids.sort_by_key(|id| (*id != self.function.entry_block, id.0));
+        
+        if self.state_machine {
+            let _ = writeln!(out, "  let __block = {};", self.function.entry_block.0);
+            out.push_str("  for (;;) {\n    switch (__block) {\n");
+            self.indent = 4;
+            for id in block_ids {
+                let block = &self.function.blocks[&id];
+                let _ = writeln!(self.body, "      case {}: {{", id.0);
+                self.emit_block(block)?;
+                self.body.push_str("      }\n");
+            }
+            self.body.push_str("    }\n  }\n");
+        } else {
+            for id in block_ids {
+                let block = &self.function.blocks[&id];
+                self.emit_block(block)?;
+            }
+        }
+        
+        let (mut line, mut counted) = (out.matches('\n').count(), 0);
+        for (offset, location) in self.mappings.drain(..) {
+            line += self.body[counted..offset].matches('\n').count();
+            counted = offset;
+            mappings.push((line, location));
+        }
+        out.push_str(&self.body);
+        out.push_str("}\n");
+        Ok((out, mappings))
+    }
+    
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.body.push_str("  ");
+        }
+        self.body.push_str(text);
+        self.body.push('\n');
+    }
+    
+    fn emit_block(&mut self, block: &BasicBlock) -> Result<()> {
+        // ブロックには複数の分岐元から入るので、行番号は改めて書き込む
+        self.frame_line = None;
+        for (id, instruction) in &block.instructions {
+            self.update_frame_line(*id);
+            self.emit_instruction(block.id, *id, instruction)?;
+        }
+        
+        match &block.terminator {
+            Some(terminator) => self.emit_terminator(block.id, terminator),
+            None if block.instructions.iter().any(|(_, i)| is_control_instruction(i)) => Ok(()),
+            None => {
+                self.line("__unreachable();");
+                Ok(())
+            }
+        }
+    }
+    
+    /// 命令のソース上の行が変わったら、フレームの行番号を更新する
+    fn update_frame_line(&mut self, id: InstructionId) {
+        let location = match self.function.get_location(id) {
+            Some(location) if location.line != 0 => self.source_location(location),
+            _ => return,
+        };
+        if self.frame_line != Some(location.line) {
+            self.frame_line = Some(location.line);
+            let offset = self.body.len();
+            self.line(&format!("__frame.line = {};", location.line));
+            if self.sources.is_some() {
+                self.mappings.push((offset, location));
+            }
+        }
+    }
+    
+    fn emit_instruction(&mut self, block: BlockId, id: InstructionId, instruction: &Instruction) -> Result<()> {
+        match instruction {
+            Instruction::BinaryOp { op, lhs, rhs, result } => {
+                let checked = !self.function.unchecked.contains(&id);
+                let expr = self.binary_expr(*op, lhs, rhs, checked)?;
+                self.line(&format!("{} = {};", register_name(*result), expr));
+            }
+            Instruction::UnaryOp { op, operand, result } => {
+                let ty = self.operand_type(operand);
+                let value = self.operand(operand)?;
+                let expr = match op {
+                    UnaryOp::Neg if ty == JsType::Int || matches!(ty, JsType::Sized(_)) => ty.wrap(&format!("-{}", value)),
+                    UnaryOp::Neg => format!("-{}", value),
+                    UnaryOp::Not => format!("!{}", value),
+                    UnaryOp::BitNot if matches!(ty, JsType::Sized(_)) => ty.wrap(&format!("~{}", value)),
+                    UnaryOp::BitNot => format!("~{}", value),
+                    UnaryOp::Cast => cast(&value, ty, self.register_type(*result)),
+                };
+                self.line(&format!("{} = {};", register_name(*result), expr));
+            }
+            Instruction::Load { address, result } => {
+                let place = self.place(address)?;
+                self.line(&format!("{} = {};", register_name(*result), place));
+            }
+            Instruction::Store { address, value } => {
+                let place = self.place(address)?;
+                let value = self.operand(value)?;
+                self.line(&format!("{} = {};", place, value));
+            }
+            Instruction::Call { function, arguments, result } => {
+                self.emit_call(function, arguments, *result, false)?;
+            }
+            Instruction::ExternalCall { function, arguments, result } => {
+                self.emit_call(function, arguments, *result, true)?;
+            }
+            Instruction::Return { value } => {
+                self.emit_return(block, value.as_ref())?;
+            }
+            Instruction::Branch { target } => {
+                self.emit_jump(block, *target, &[])?;
+            }
+            Instruction::BranchCond { condition, true_target, false_target } => {
+                let terminator = Terminator::BranchCond {
+                    condition: condition.clone(),
+                    true_target: *true_target,
+                    true_args: Vec::new(),
+                    false_target: *false_target,
+                    false_args: Vec::new(),
+                };
+                self.emit_terminator(block, &terminator)?;
+            }
+            // ミュータブルなスロットは `value` プロパティを持つオブジェクトで表す
+            Instruction::Alloca { result, .. } => match self.eir_type(&Operand::Register(*result)).map(|ty| &ty.kind) {
+                Some(TypeKind::FixedArray { length, .. }) => {
+                    self.line(&format!("{} = new Array({});", register_name(*result), length));
+                }
+                _ => self.line(&format!("{} = {{ value: undefined }};", register_name(*result))),
+            },
+            Instruction::Cast { value, target_type, result } => {
+                let from = self.operand_type(value);
+                let to = js_type(self.module, *target_type);
+                let value = self.operand(value)?;
+                let expr = cast(&value, from, to);
+                self.line(&format!("{} = {};", register_name(*result), expr));
+            }
+            // 分岐元のブロックで代入済み
+            Instruction::Phi { .. } => {}
+            Instruction::Select { condition, true_value, false_value, result } => {
+                let condition = self.operand(condition)?;
+                let true_value = self.operand(true_value)?;
+                let false_value = self.operand(false_value)?;
+                self.line(&format!("{} = {} ? {} : {};", register_name(*result), condition, true_value, false_value));
+            }
+            Instruction::Tuple { elements, result } => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.operand(element)?);
+                }
+                self.line(&format!("{} = [{}];", register_name(*result), values.join(", ")));
+            }
+            Instruction::Extract { tuple, index, result } => {
+                let tuple = self.operand(tuple)?;
+                self.line(&format!("{} = {}[{}];", register_name(*result), tuple, index));
+            }
+            Instruction::DebugInfo { info } => {
+                self.line(&format!("// {}", info.replace('\n', " ")));
+            }
+            Instruction::GetElementPtr { base, indices, result } => {
+                let (Some(ty), Some(index)) = (self.eir_type(base), indices.first()) else {
+                    return Err(unsupported("GetElementPtr"));
+                };
+                let array = match ty.kind {
+                    TypeKind::Slice(_) => format!("{}.data", self.operand(base)?),
+                    _ => self.operand(base)?,
+                };
+                let index = self.operand(index)?;
+                self.line(&format!("{} = __element({}, {});", register_name(*result), array, index));
+            }
+            Instruction::MakeSlice { array, length, result } => {
+                let array = self.operand(array)?;
+                let length = self.operand(length)?;
+                self.line(&format!("{} = {{ data: {}, length: {} }};", register_name(*result), array, length));
+            }
+            Instruction::SliceLen { slice, result } => {
+                let slice = self.operand(slice)?;
+                self.line(&format!("{} = {}.length;", register_name(*result), slice));
+            }
+            Instruction::Atomic { .. } => return Err(unsupported("Atomic")),
+            Instruction::InlineAsm { .. } => return Err(unsupported("InlineAsm")),
+        }
+        Ok(())
+    }
+    
+    fn emit_call(&mut self, function: &str, arguments: &[Operand], result: Option<RegisterId>, external: bool) -> Result<()> {
+        let mut args = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            args.push(self.operand(argument)?);
+        }
+        
+        // 同名のユーザー定義関数があれば組み込み関数より優先する
+        let user_function = if external { None } else { self.module.get_function_by_name(function) };
+        if !external && user_function.is_none() && (function.starts_with("Option::") || function.starts_with("Result::")) {
+            return self.emit_variant_call(function, &args, result);
+        }
+        // 組み込み関数はJavaScriptの式に置き換える（置き換えられないものは関数の本体を呼び出す）
+        if let Some(template) = user_function.and_then(codegen::intrinsic_of).and_then(|intrinsic| intrinsic.js) {
+            let expr = Intrinsic::expand(template, &args);
+            match result {
+                Some(result) => self.line(&format!("{} = {};", register_name(result), expr)),
+                None => self.line(&format!("{};", expr)),
+            }
+            return Ok(());
+        }
+        let callee = match (user_function, function) {
+            (Some(_), _) => function_name(function),
+            (None, coverage::COVERAGE_HIT) if external => "__cov_hit".to_string(),
+            (None, coverage::COVERAGE_BRANCH) if external => "__cov_branch".to_string(),
+            (None, _) if external => external_name(function),
+            (None, "print") => "__print".to_string(),
+            (None, "println") => "__println".to_string(),
+            (None, "panic") => "__panic".to_string(),
+            (None, _) if function.strip_prefix("bigint::").and_then(bigint::function).is_some() => {
+                format!("__bigint.{}", &function["bigint::".len()..])
+            }
+            (None, _) if self.module.external_functions.contains_key(function) => external_name(function),
+            (None, _) => {
+                return Err(codegen::undefined_function(self.module, function));
+            }
+        };
+        
+        // 非同期関数の呼び出しは型チェックで必ず `await` しているので、ここで完了を待つ
+        let wait = if user_function.is_some_and(|f| f.attributes.is_async) { "await " } else { "" };
+        let call = format!("{}{}({})", wait, callee, args.join(", "));
+        match result {
+            Some(result) => self.line(&format!("{} = {};", register_name(result), call)),
+            None => self.line(&format!("{};", call)),
+        }
+        Ok(())
+    }
+    
+    /// `Option` と `Result` の関数を、`{ ok, value }` の構築やプロパティの参照に変換する
+    fn emit_variant_call(&mut self, function: &str, args: &[String], result: Option<RegisterId>) -> Result<()> {
+        let arg = |index: usize| {
+            args.get(index).ok_or_else(|| {
+                EidosError::BackendError(format!("'{}' の引数が足りません", function))
+            })
+        };
+        let expr = match function {
+            "Option::some" | "Result::ok" => format!("{{ ok: true, value: {} }}", arg(0)?),
+            "Result::err" => format!("{{ ok: false, value: {} }}", arg(0)?),
+            "Option::none" => "{ ok: false, value: undefined }".to_string(),
+            "Option::is_some" | "Result::is_ok" => format!("{}.ok", arg(0)?),
+            "Option::is_none" | "Result::is_err" => format!("!{}.ok", arg(0)?),
+            "Option::unwrap" => format!("__unwrap({}, true, \"called `Option::unwrap()` on a `None` value\")", arg(0)?),
+            "Result::unwrap" => format!("__unwrap({}, true, \"called `Result::unwrap()` on an `Err` value\")", arg(0)?),
+            "Result::unwrap_err" => format!("__unwrap({}, false, \"called `Result::unwrap_err()` on an `Ok` value\")", arg(0)?),
+            "Option::unwrap_or" | "Result::unwrap_or" => {
+                let variant = arg(0)?;
+                format!("{}.ok ? {}.value : {}", variant, variant, arg(1)?)
+            }
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "JavaScriptバックエンドは関数 '{}' に対応していません",
+                    function
+                )))
+            }
+        };
+        match result {
+            Some(result) => self.line(&format!("{} = {};", register_name(result), expr)),
+            None => self.line(&format!("({});", expr)),
+        }
+        Ok(())
+    }
+    
+    fn emit_return(&mut self, block: BlockId, value: Option<&Operand>) -> Result<()> {
+        self.emit_phi_moves(block)?;
+        let returns_unit = js_type(self.module, self.function.return_type) == JsType::Unit;
+        self.line("__leave();");
+        match value {
+            Some(value) if !returns_unit => {
+                let value = self.operand(value)?;
+                self.line(&format!("return {};", value));
+            }
+            _ => self.line("return;"),
+        }
+        Ok(())
+    }
+    
+    /// 分岐先のブロックパラメータに引数を代入してから分岐先の状態に移る
+    fn emit_jump(&mut self, from: BlockId, target: BlockId, args: &[Operand]) -> Result<()> {
+        self.emit_phi_moves(from)?;
+        
+        let params = self
+            .function
+            .get_block(target)
+            .map(|block| block.parameters.clone())
+            .unwrap_or_default();
+        if params.len() != args.len() {
+            return Err(EidosError::BackendError(format!(
+                "関数 '{}' のブロック {} への分岐の引数の数が一致しません",
+                self.function.name, target
+            )));
+        }
+        
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(self.operand(arg)?);
+        }
+        match params.len() {
+            0 => {}
+            1 => self.line(&format!("{} = {};", register_name(params[0].0), values[0])),
+            // 引数が他のパラメータを参照していてもよいよう、分割代入でまとめて代入する
+            _ => {
+                let names = params.iter().map(|(reg, _)| register_name(*reg)).collect::<Vec<_>>();
+                self.line(&format!("[{}] = [{}];", names.join(", "), values.join(", ")));
+            }
+        }
+        
+        if self.state_machine {
+            self.line(&format!("__block = {};", target.0));
+            self.line("continue;");
+        }
+        Ok(())
+    }
+    
+    fn emit_phi_moves(&mut self, from: BlockId) -> Result<()> {
+        if let Some(moves) = self.phi_moves.get(&from).cloned() {
+            for (result, value) in moves {
+                let value = self.operand(&value)?;
+                self.line(&format!("{} = {};", register_name(result), value));
+            }
+        }
+        Ok(())
+    }
+    
+    fn emit_terminator(&mut self, block: BlockId, terminator: &Terminator) -> Result<()> {
+        match terminator {
+            Terminator::Branch { target, args } => self.emit_jump(block, *target, args),
+            Terminator::BranchCond { condition, true_target, true_args, false_target, false_args } => {
+                let condition = self.operand(condition)?;
+                self.line(&format!("if ({}) {{", condition));
+                self.indent += 1;
+                self.emit_jump(block, *true_target, true_args)?;
+                self.indent -= 1;
+                self.line("} else {");
+                self.indent += 1;
+                self.emit_jump(block, *false_target, false_args)?;
+                self.indent -= 1;
+                self.line("}");
+                Ok(())
+            }
+            Terminator::Return { value } => self.emit_return(block, value.as_ref()),
+            Terminator::Switch { value, default_target, default_args, cases } => {
+                let value = self.operand(value)?;
+                self.line(&format!("switch ({}) {{", value));
+                for (literal, target, args) in cases {
+                    self.line(&format!("  case {}:", literal_to_js(literal)));
+                    self.indent += 2;
+                    self.emit_jump(block, *target, args)?;
+                    self.indent -= 2;
+                }
+                self.line("  default:");
+                self.indent += 2;
+                self.emit_jump(block, *default_target, default_args)?;
+                self.indent -= 2;
+                self.line("}");
+                Ok(())
+            }
+            Terminator::IndirectCall { function_ptr, arguments, return_block, return_args } => {
+                let callee = self.operand(function_ptr)?;
+                let mut args = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    args.push(self.operand(argument)?);
+                }
+                self.line(&format!("{}({});", callee, args.join(", ")));
+                self.emit_jump(block, *return_block, return_args)
+            }
+            Terminator::Unreachable => {
+                self.line("__unreachable();");
+                Ok(())
+            }
+        }
+    }
+    
+    /// `checked` が偽の除算・剰余は、値域解析で0除算もオーバーフローも起きないと分かっているので検査しない
+    fn binary_expr(&self, op: BinaryOp, lhs: &Operand, rhs: &Operand, checked: bool) -> Result<String> {
+        let ty = self.operand_type(lhs);
+        let a = self.operand(lhs)?;
+        let b = self.operand(rhs)?;
+        
+        // 整数の0除算はRangeErrorではなく、スタックトレース付きの実行時エラーにする
+        // （ビット幅を指定した整数型の `-128 / -1` などは型のビット幅に折り返す）
+        if ty.is_integer() && checked {
+            match op {
+                BinaryOp::Div if ty == JsType::Int => return Ok(format!("__div({}, {})", a, b)),
+                BinaryOp::Div => return Ok(ty.wrap(&format!("__div({}, {})", a, b))),
+                BinaryOp::Rem => return Ok(format!("__rem({}, {})", a, b)),
+                _ => {}
+            }
+        }
+        
+        let symbol = match op {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::Eq => "===",
+            BinaryOp::Ne => "!==",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        };
+        // シフト量はビット幅で剰余をとる（Cバックエンドと同じ結果にする）
+        let b = match (op, ty) {
+            (BinaryOp::Shl | BinaryOp::Shr, JsType::Sized(numeric)) => format!("({} & {}n)", b, numeric.bits() - 1),
+            (BinaryOp::Shl | BinaryOp::Shr, _) => format!("({} & 63n)", b),
+            _ => b,
+        };
+        
+        // 整数のオーバーフローは2の補数で型のビット幅に折り返し、`f32` の演算結果は単精度に丸める
+        let wraps = match ty {
+            JsType::Sized(NumericType::F32) => matches!(op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div),
+            _ if ty.is_integer() => matches!(op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Shl),
+            _ => false,
+        };
+        if wraps {
+            return Ok(ty.wrap(&format!("{} {} {}", a, symbol, b)));
+        }
+        Ok(format!("{} {} {}", a, symbol, b))
+    }
+    
+    fn operand(&self, operand: &Operand) -> Result<String> {
+        match operand {
+            Operand::Register(reg) => Ok(register_name(*reg)),
+            Operand::Literal(literal) => Ok(literal_to_js(literal)),
+            Operand::Global(name) => {
+                if self.module.get_global(name).is_some() {
+                    Ok(global_name(name))
+                } else if self.module.get_function_by_name(name).is_some() {
+                    Ok(function_name(name))
+                } else {
+                    Err(EidosError::BackendError(format!("未定義のシンボル '{}' を参照しています", name)))
+                }
+            }
+            Operand::Function(id) => match self.module.get_function(*id) {
+                Some(function) => Ok(function_name(&function.name)),
+                None => Err(EidosError::BackendError(format!("未定義の関数 {} を参照しています", id))),
+            },
+            Operand::ExternalFunction(name) => Ok(external_name(name)),
+            Operand::Symbol(_) | Operand::Block(_) => Err(EidosError::BackendError(format!(
+                "オペランド {} はJavaScriptバックエンドでは表現できません",
+                operand
+            ))),
+        }
+    }
+    
+    /// `Load` と `Store` のアドレスが指す場所（グローバル変数は変数そのもの、スロットは `.value`）
+    fn place(&self, address: &Operand) -> Result<String> {
+        match address {
+            Operand::Global(name) if self.module.get_global(name).is_some() => Ok(global_name(name)),
+            _ => Ok(format!("{}.value", self.operand(address)?)),
+        }
+    }
+    
+    /// オペランドのEIRでの型（レジスタのときだけ）
+    fn eir_type(&self, operand: &Operand) -> Option<&'a Type> {
+        let module = self.module;
+        match operand {
+            Operand::Register(reg) => module.get_type(self.function.get_register_type(*reg)?),
+            _ => None,
+        }
+    }
+    
+    fn operand_type(&self, operand: &Operand) -> JsType {
+        match operand {
+            Operand::Register(reg) => self.register_type(*reg),
+            Operand::Literal(literal) => JsType::of_literal(literal),
+            Operand::Global(name) => match self.module.get_global(name) {
+                Some(global) => js_type(self.module, global.ty),
+                None => JsType::Any,
+            },
+            _ => JsType::Any,
+        }
+    }
+    
+    fn register_type(&self, reg: RegisterId) -> JsType {
+        match self.function.get_register_type(reg) {
+            Some(type_id) => js_type(self.module, type_id),
+            None => JsType::Any,
+        }
+    }
+}
+
+fn unsupported(instruction: &str) -> EidosError {
+    EidosError::BackendError(format!("JavaScriptバックエンドは {} 命令に対応していません", instruction))
+}
+
+fn is_control_instruction(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Return { .. } | Instruction::Branch { .. } | Instruction::BranchCond { .. })
+}
+
+fn js_type(module: &Module, id: TypeId) -> JsType {
+    module.get_type(id).map(JsType::from_type).unwrap_or(JsType::Any)
+}
+
+/// 宣言ファイルに書くTypeScriptの型（タプルは要素の型を並べた配列型 `[bigint, number]` にする）
+fn ts_type(module: &Module, id: TypeId) -> String {
+    fn name(ty: &Type) -> String {
+        match &ty.kind {
+            TypeKind::Tuple(elements) => format!("[{}]", elements.iter().map(name).collect::<Vec<_>>().join(", ")),
+            TypeKind::Array(element) => format!("{}[]", name(element)),
+            _ => JsType::from_type(ty).ts_name().to_string(),
+        }
+    }
+    module.get_type(id).map_or_else(|| JsType::Any.ts_name().to_string(), name)
+}
+
+/// 値を別の型に変換する式
+fn cast(value: &str, from: JsType, to: JsType) -> String {
+    match (from, to) {
+        _ if from == to => value.to_string(),
+        _ if from.is_integer() && to.is_integer() => to.wrap(value),
+        _ if from.is_integer() && to.is_float() => to.wrap(&format!("Number({})", value)),
+        _ if from.is_float() && to.is_integer() => to.wrap(&format!("BigInt(Math.trunc({}))", value)),
+        _ if from.is_float() && to.is_float() => to.wrap(value),
+        (JsType::Bool, _) if to.is_integer() => format!("({} ? 1n : 0n)", value),
+        (JsType::Char, _) if to.is_integer() => to.wrap(&format!("BigInt({}.codePointAt(0))", value)),
+        (_, JsType::Char) if from.is_integer() => format!("String.fromCodePoint(Number({}))", value),
+        (_, JsType::String) => format!("String({})", value),
+        _ => value.to_string(),
+    }
+}
+
+fn function_name(name: &str) -> String {
+    let name = sanitize_identifier(name);
+    if RESERVED_WORDS.contains(&name.as_str()) || name.starts_with("__") {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+fn global_name(name: &str) -> String {
+    format!("g_{}", sanitize_identifier(name))
+}
+
+fn external_name(name: &str) -> String {
+    format!("ext_{}", sanitize_identifier(name))
+}
+
+fn register_name(reg: RegisterId) -> String {
+    format!("r{}", reg.0)
+}
+
+/// JavaScriptの識別子に使えない文字を `$XXXX` 形式に置き換える
+fn sanitize_identifier(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        let valid = c == '_' || c == '$' || c.is_alphabetic() || (i > 0 && c.is_alphanumeric());
+        if valid {
+            out.push(c);
+        } else {
+            let _ = write!(out, "${:04X}", c as u32);
+        }
+    }
+    out
+}
+
+fn literal_to_js(literal: &Literal) -> String {
+    match literal {
+        Literal::Int(value) => format!("{}n", value),
+        Literal::Float(value) if value.is_nan() => "NaN".to_string(),
+        Literal::Float(value) if value.is_infinite() => {
+            if *value > 0.0 { "Infinity".to_string() } else { "-Infinity".to_string() }
+        }
+        Literal::Float(value) => format!("{:?}", value),
+        Literal::Bool(value) => value.to_string(),
+        Literal::Char(value) => match char::from_u32(*value) {
+            Some(c) => string_literal(&c.to_string()),
+            None => format!("String.fromCodePoint({})", value),
+        },
+        Literal::String(value) => string_literal(value),
+        Literal::Unit => "undefined".to_string(),
+    }
+}
+
+/// 生成したコードの行と元のソースの位置の対応を、Source Map v3 のJSONにする
+///
+/// 行ごとに先頭の列を1つの位置に対応付ける。元のファイルはソースマップに登録されていれば内容も含める。
+fn source_map_json(mappings: &[(usize, SourceLocation)], sources: &SourceMap) -> String {
+    let mut files: Vec<&std::path::Path> = Vec::new();
+    let mut encoded = String::new();
+    let (mut last, mut previous) = (None, [0i64; 3]);
+    for (generated, location) in mappings {
+        // 行の区切りは `;`（同じ行の2つ目以降の対応は使わない）
+        let separators = match last {
+            Some(last) if *generated <= last => continue,
+            Some(last) => generated - last,
+            None => *generated,
+        };
+        encoded.push_str(&";".repeat(separators));
+        last = Some(*generated);
+        let file = match files.iter().position(|file| *file == location.file) {
+            Some(index) => index,
+            None => {
+                files.push(&location.file);
+                files.len() - 1
+            }
+        };
+        // 生成したコードの列、元のファイル、行、列（行と列は0から数え、列以外は前の対応との差）
+        let current = [file as i64, location.line.saturating_sub(1) as i64, location.column.saturating_sub(1) as i64];
+        encode_vlq(&mut encoded, 0);
+        for (value, previous) in current.iter().zip(previous.iter_mut()) {
+            encode_vlq(&mut encoded, value - *previous);
+            *previous = *value;
+        }
+    }
+    
+    let contents: Vec<Option<String>> = files
+        .iter()
+        .map(|path| sources.find(path).map(|file| file.text().to_string()))
+        .collect();
+    serde_json::json!({
+        "version": 3,
+        "sources": files.iter().map(|path| path.display().to_string()).collect::<Vec<_>>(),
+        "sourcesContent": contents,
+        "names": [],
+        "mappings": encoded,
+    })
+    .to_string()
+}
+
+/// ソースマップの位置の差を Base64 VLQ で書く
+fn encode_vlq(out: &mut String, value: i64) {
+    const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut rest = if value < 0 { ((-value) << 1) | 1 } else { value << 1 };
+    loop {
+        let digit = (rest & 31) as usize;
+        rest >>= 5;
+        out.push(DIGITS[if rest > 0 { digit | 32 } else { digit }] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+fn string_literal(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // 行終端として扱われる文字
+            '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\x{:02x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::FunctionId;
+    use crate::core::eir_builder::build_test_module;
+    
+    fn countdown_module() -> Module {
+        // fn countdown(n: int) -> int { while n > 0 { n = n - 1 } n } に相当するEIR
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let boolean = module.add_type(Type::bool());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "countdown", func_type, int);
+        let n = function.add_parameter("n", int);
+        let header = function.create_block();
+        let body = function.create_block();
+        let exit = function.create_block();
+        let current = function.create_register(int);
+        let cond = function.create_register(boolean);
+        let next = function.create_register(int);
+        
+        let entry = function.entry_block;
+        function.get_block_mut(header).unwrap().add_parameter(current, int);
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Branch {
+            target: header,
+            args: vec![Operand::Register(n)],
+        });
+        function.add_instruction(header, Instruction::BinaryOp {
+            op: BinaryOp::Gt,
+            lhs: Operand::Register(current),
+            rhs: Operand::Literal(Literal::Int(0)),
+            result: cond,
+        });
+        function.get_block_mut(header).unwrap().set_terminator(Terminator::BranchCond {
+            condition: Operand::Register(cond),
+            true_target: body,
+            true_args: Vec::new(),
+            false_target: exit,
+            false_args: Vec::new(),
+        });
+        function.add_instruction(body, Instruction::BinaryOp {
+            op: BinaryOp::Sub,
+            lhs: Operand::Register(current),
+            rhs: Operand::Literal(Literal::Int(1)),
+            result: next,
+        });
+        function.get_block_mut(body).unwrap().set_terminator(Terminator::Branch {
+            target: header,
+            args: vec![Operand::Register(next)],
+        });
+        function.get_block_mut(exit).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(current)),
+        });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+        module
+    }
+    
+    #[test]
+    fn test_emit_state_machine() {
+        let source = JsBackend::new().emit(&countdown_module()).unwrap();
+        assert!(source.contains("export function countdown(r0) {"));
+        assert!(source.contains("r1 = r0;\n        __block = 1;\n        continue;"));
+        assert!(source.contains("r2 = r1 > 0n;"));
+        assert!(source.contains("r3 = BigInt.asIntN(64, r1 - 1n);"));
+        assert!(source.contains("export default countdown;"));
+    }
+    
+    #[test]
+    fn test_stack_frames_and_checked_division() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int(), Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "divide", func_type, int);
+        function.location = Some(SourceLocation::new("math.eid".into(), 3, 1, 2));
+        let a = function.add_parameter("a", int);
+        let b = function.add_parameter("b", int);
+        let quotient = function.create_register(int);
+        let entry = function.entry_block;
+        let div = function.add_instruction(entry, Instruction::BinaryOp {
+            op: BinaryOp::Div,
+            lhs: Operand::Register(a),
+            rhs: Operand::Register(b),
+            result: quotient,
+        });
+        function.set_location(div, SourceLocation::new("math.eid".into(), 4, 5, 5));
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(quotient)),
+        });
+        module.add_function(function);
+        
+        let source = JsBackend::new().emit(&module).unwrap();
+        assert!(source.contains("const __frame = __enter(\"divide\", \"math.eid\", 3);"));
+        assert!(source.contains("__frame.line = 4;\n  r2 = __div(r0, r1);"));
+        assert!(source.contains("__leave();\n  return r2;"));
+    }
+    
+    #[test]
+    fn test_source_map() {
+        let sources = SourceMap::new();
+        let text = "fn divide(a, b) {\n    // 割り算\n    a / b\n}\n";
+        let file = sources.add("math.eid".into(), text.to_string());
+        let start = text.find("a / b").unwrap();
+        
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int(), Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "divide", func_type, int);
+        let a = function.add_parameter("a", int);
+        let b = function.add_parameter("b", int);
+        let quotient = function.create_register(int);
+        let entry = function.entry_block;
+        let div = function.add_instruction(entry, Instruction::BinaryOp {
+            op: BinaryOp::Div,
+            lhs: Operand::Register(a),
+            rhs: Operand::Register(b),
+            result: quotient,
+        });
+        let location = SourceLocation::new("<unknown>".into(), 1, 1, 1).with_span(file.span_of(start..start + 5));
+        function.set_location(div, location);
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(quotient)),
+        });
+        module.add_function(function);
+        
+        let (source, map) = JsBackend::new().emit_with_source_map(&module, &sources, "math.mjs.map").unwrap();
+        assert!(source.ends_with("//# sourceMappingURL=math.mjs.map\n"));
+        let map: serde_json::Value = serde_json::from_str(&map).unwrap();
+        assert_eq!(map["sources"], serde_json::json!(["math.eid"]));
+        assert_eq!(map["sourcesContent"], serde_json::json!([text]));
+        
+        // `__frame.line = 3;` の行だけが、3行目の5列目（0から数えて2行目の4列目）に対応する
+        let line = source.lines().position(|line| line.trim() == "__frame.line = 3;").unwrap();
+        let groups: Vec<&str> = map["mappings"].as_str().unwrap().split(';').collect();
+        assert_eq!(groups.len(), line + 1);
+        assert_eq!(groups[line], "AAEI");
+        assert!(groups[..line].iter().all(|group| group.is_empty()));
+    }
+    
+    #[test]
+    fn test_bigint_calls() {
+        // fn square(a: BigInt) -> BigInt { a * a }
+        let mut module = Module::new("test");
+        let bigint = module.add_type(Type::bigint());
+        let func_type = module.add_type(Type::function(vec![Type::bigint()], Type::bigint()));
+        let mut function = Function::new(FunctionId(0), "square", func_type, bigint);
+        let a = function.add_parameter("a", bigint);
+        let product = function.create_register(bigint);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::Call {
+            function: "bigint::mul".to_string(),
+            arguments: vec![Operand::Register(a), Operand::Register(a)],
+            result: Some(product),
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(product)),
+        });
+        module.add_function(function);
+        
+        let backend = JsBackend::new();
+        let source = backend.emit(&module).unwrap();
+        assert!(source.contains("const __bigint = {"));
+        assert!(source.contains("r1 = __bigint.mul(r0, r0);"), "{}", source);
+        let declarations = backend.emit_declarations(&module).unwrap();
+        assert!(declarations.contains("export function square(a: bigint): bigint;"), "{}", declarations);
+    }
+    
+    #[test]
+    fn test_array_elements() {
+        let source = "fn first(values: &[Int]) -> Int { values[0] } let a = [1, 2, 3]; println(first(&a));";
+        let module = build_test_module("test", source).unwrap();
+        
+        let source = JsBackend::new().emit(&module).unwrap();
+        assert!(source.contains("function __element(array, index)"));
+        assert!(source.contains(" = new Array(3);"), "{}", source);
+        assert!(source.contains(" = __element(r0.data, 0n);"), "{}", source);
+        assert!(source.contains(" = { data: r0, length: 3n };"), "{}", source);
+    }
+    
+    #[test]
+    fn test_emit_declarations() {
+        let declarations = JsBackend::new().emit_declarations(&countdown_module()).unwrap();
+        assert!(declarations.contains("export function countdown(n: bigint): bigint;"));
+        assert!(declarations.contains("export default countdown;"));
+    }
+    
+    #[test]
+    fn test_tuple_return() {
+        // fn divmod(a: int, b: int) -> (int, int) と、その結果を分解して使う呼び出し側
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let pair_type = Type::tuple(vec![Type::int(), Type::int()]);
+        let pair = module.add_type(pair_type.clone());
+        let func_type = module.add_type(Type::function(vec![Type::int(), Type::int()], pair_type));
+        let mut function = Function::new(FunctionId(0), "divmod", func_type, pair);
+        let a = function.add_parameter("a", int);
+        let b = function.add_parameter("b", int);
+        let quotient = function.create_register(int);
+        let remainder = function.create_register(int);
+        let result = function.create_register(pair);
+        let entry = function.entry_block;
+        for (op, register) in [(BinaryOp::Div, quotient), (BinaryOp::Rem, remainder)] {
+            function.add_instruction(entry, Instruction::BinaryOp {
+                op,
+                lhs: Operand::Register(a),
+                rhs: Operand::Register(b),
+                result: register,
+            });
+        }
+        function.add_instruction(entry, Instruction::Tuple {
+            elements: vec![Operand::Register(quotient), Operand::Register(remainder)],
+            result,
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(result)),
+        });
+        module.add_function(function);
+        
+        let source = JsBackend::new().emit(&module).unwrap();
+        assert!(source.contains("r4 = [r2, r3];"), "{}", source);
+        let declarations = JsBackend::new().emit_declarations(&module).unwrap();
+        assert!(declarations.contains("export function divmod(a: bigint, b: bigint): [bigint, bigint];"), "{}", declarations);
+    }
+    
+    #[test]
+    fn test_async_functions() {
+        // async fn fetch(x: int) -> int { x } と、それを待つ async fn twice(x: int) -> int { await fetch(x) }
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        for (id, name) in [(0, "fetch"), (1, "twice")] {
+            let mut function = Function::new(FunctionId(id), name, func_type, int);
+            function.attributes.is_async = true;
+            let x = function.add_parameter("x", int);
+            let entry = function.entry_block;
+            let value = if name == "twice" {
+                let result = function.create_register(int);
+                function.add_instruction(entry, Instruction::Call {
+                    function: "fetch".to_string(),
+                    arguments: vec![Operand::Register(x)],
+                    result: Some(result),
+                });
+                result
+            } else {
+                x
+            };
+            function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+                value: Some(Operand::Register(value)),
+            });
+            module.add_function(function);
+        }
+        
+        let source = JsBackend::new().emit(&module).unwrap();
+        assert!(source.contains("export async function twice(r0) {"), "{}", source);
+        assert!(source.contains("r1 = await fetch(r0);"), "{}", source);
+        let declarations = JsBackend::new().emit_declarations(&module).unwrap();
+        assert!(declarations.contains("export function fetch(x: bigint): Promise<bigint>;"), "{}", declarations);
+    }
+    
+    #[test]
+    fn test_identifiers_and_literals() {
+        assert_eq!(function_name("delete"), "delete_");
+        assert_eq!(function_name("__print"), "__print_");
+        assert_eq!(function_name("変換"), "変換");
+        assert_eq!(literal_to_js(&Literal::Int(-3)), "-3n");
+        assert_eq!(literal_to_js(&Literal::Char('a' as u32)), "\"a\"");
+        assert_eq!(string_literal("a\"b\n"), "\"a\\\"b\\n\"");
+    }
+}
@@ -12,10 +12,10 @@ use inkwell::OptimizationLevel as LLVMOptLevel;
 use log::{debug, info, error};
 
 use crate::core::{Result, EidosError};
-use crate::core::eir::{Module, Function, FunctionId, BlockId, Instruction, Operand, Literal, BinaryOp, UnaryOp};
+use crate::core::eir::{Module, Function, FunctionId, BlockId, Instruction, Operand, Literal, BinaryOp, UnaryOp, GlobalAttributes, AsmDialect, AsmPiece, AtomicOp, parse_asm_template};
 use crate::core::types::{Type, TypeKind};
 
-use super::codegen::{Backend, CodegenOptions, OutputFormat, Target as CodegenTarget};
+use super::codegen::{self, Backend, CodegenOptions, OutputFormat, Target as CodegenTarget};
 
 /// LLVM バックエンド
 pub struct LLVMBackend {
@@ -168,7 +168,7 @@ impl LLVMBackend {
             _ => {
                 // その他の型は未対応
                 return Err(EidosError::CodeGen(format!(
-                    "未対応の型: {:?}", ty
+                    "未対応の型: {}", ty
                 )));
             }
         };
@@ -217,8 +217,8 @@ impl LLVMBackend {
     }
     
     /// ターゲットマシンを取得
-    fn get_target_machine(&self, target: &CodegenTarget) -> Result<TargetMachine> {
-        let triple = match target {
+    fn get_target_machine(&self, options: &CodegenOptions) -> Result<TargetMachine> {
+        let triple = match &options.target {
             CodegenTarget::Native => Target::get_host_target_triple(),
             CodegenTarget::Target(triple_str) => {
                 inkwell::targets::TargetTriple::create(triple_str)
@@ -231,14 +231,40 @@ impl LLVMBackend {
         // 最適化レベルを変換
         let opt_level = LLVMOptLevel::Default;
         
+        // `native` はホストのCPUと、そのCPUが持つ機能に置き換える（個別に指定した機能はその後に並べて優先させる）
+        let machine = &options.machine;
+        let mut features = Vec::new();
+        let cpu = match machine.cpu.as_deref() {
+            Some("native") => {
+                features.push(TargetMachine::get_host_cpu_features().to_string());
+                TargetMachine::get_host_cpu_name().to_string()
+            }
+            Some(cpu) => cpu.to_string(),
+            None => "generic".to_string(),
+        };
+        features.extend(machine.features.iter().cloned());
+        let reloc_mode = match machine.relocation_model {
+            codegen::RelocationModel::Default => RelocMode::Default,
+            codegen::RelocationModel::Static => RelocMode::Static,
+            codegen::RelocationModel::Pic => RelocMode::PIC,
+            codegen::RelocationModel::DynamicNoPic => RelocMode::DynamicNoPic,
+        };
+        let code_model = match machine.code_model {
+            codegen::CodeModel::Default => CodeModel::Default,
+            codegen::CodeModel::Small => CodeModel::Small,
+            codegen::CodeModel::Kernel => CodeModel::Kernel,
+            codegen::CodeModel::Medium => CodeModel::Medium,
+            codegen::CodeModel::Large => CodeModel::Large,
+        };
+        
         // ターゲットマシンを作成
         let target_machine = target.create_target_machine(
             &triple,
-            "generic", // CPU
-            "", // features
+            &cpu,
+            &features.join(","),
             opt_level,
-            RelocMode::Default,
-            CodeModel::Default,
+            reloc_mode,
+            code_model,
         )
         .ok_or_else(|| EidosError::CodeGen("ターゲットマシンの作成に失敗".to_string()))?;
         
@@ -264,7 +290,7 @@ impl LLVMBackend {
                     .map(|&func| func.as_global_value().as_pointer_value().into())
                     .ok_or_else(|| EidosError::CodeGen(format!("関数が見つかりません: {}", name)))
             },
-            _ => Err(EidosError::CodeGen(format!("未対応のオペランド: {:?}", operand))),
+            _ => Err(EidosError::CodeGen(format!("未対応のオペランド: {}", operand))),
         }
     }
 
@@ -338,7 +364,7 @@ impl LLVMBackend {
                         }
                     },
                     // その他の演算子...
-                    _ => return Err(EidosError::CodeGen(format!("未対応の二項演算子: {:?}", op))),
+                    _ => return Err(EidosError::CodeGen(format!("未対応の二項演算子: {}", op))),
                 };
                 
                 // 結果を値マップに保存
@@ -369,7 +395,7 @@ impl LLVMBackend {
                         }
                     },
                     // その他の演算子...
-                    _ => return Err(EidosError::CodeGen(format!("未対応の単項演算子: {:?}", op))),
+                    _ => return Err(EidosError::CodeGen(format!("未対応の単項演算子: {}", op))),
                 };
                 
                 // 結果を値マップに保存
@@ -970,11 +996,185 @@ impl LLVMBackend {
                 }
                 Ok(None)
             },
+            Instruction::Tuple { elements, result } => {
+                // 要素の型を並べた構造体（集約値）として作る。複数の値を返す関数はこれをそのまま返す
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.build_operand(builder, element, value_map)?);
+                }
+                let field_types: Vec<_> = values.iter().map(|value| value.get_type()).collect();
+                let mut aggregate = self.context.struct_type(&field_types, false).get_undef();
+                for (index, value) in values.into_iter().enumerate() {
+                    aggregate = builder
+                        .build_insert_value(aggregate, value, index as u32, result)
+                        .map_err(|e| EidosError::CodeGen(format!("タプルの要素{}の設定に失敗しました: {:?}", index, e)))?
+                        .into_struct_value();
+                }
+                value_map.insert(result.clone(), aggregate.into());
+                Ok(Some(aggregate.into()))
+            },
+            Instruction::Extract { tuple, index, result } => {
+                let aggregate = self.build_operand(builder, tuple, value_map)?;
+                if !aggregate.is_struct_value() {
+                    return Err(EidosError::CodeGen(format!("タプルでない値から要素を取り出そうとしました: {:?}", tuple)));
+                }
+                let value = builder
+                    .build_extract_value(aggregate.into_struct_value(), *index as u32, result)
+                    .map_err(|e| EidosError::CodeGen(format!("タプルの要素{}の取り出しに失敗しました: {:?}", index, e)))?;
+                value_map.insert(result.clone(), value);
+                Ok(Some(value))
+            },
+            Instruction::MakeSlice { array, length, result } => {
+                // スライスは配列の先頭へのポインタと長さを並べた構造体
+                let data = self.build_operand(builder, array, value_map)?;
+                let length = self.build_operand(builder, length, value_map)?;
+                let slice_type = self.context.struct_type(&[data.get_type(), self.context.i64_type().into()], false);
+                let mut slice = slice_type.get_undef();
+                for (index, value) in [data, length].into_iter().enumerate() {
+                    slice = builder
+                        .build_insert_value(slice, value, index as u32, result)
+                        .map_err(|e| EidosError::CodeGen(format!("スライスの作成に失敗しました: {:?}", e)))?
+                        .into_struct_value();
+                }
+                value_map.insert(result.clone(), slice.into());
+                Ok(Some(slice.into()))
+            },
+            Instruction::SliceLen { slice, result } => {
+                let slice = self.build_operand(builder, slice, value_map)?;
+                if !slice.is_struct_value() {
+                    return Err(EidosError::CodeGen(format!("スライスでない値の長さを取り出そうとしました: {:?}", slice)));
+                }
+                let value = builder
+                    .build_extract_value(slice.into_struct_value(), 1, result)
+                    .map_err(|e| EidosError::CodeGen(format!("スライスの長さの取り出しに失敗しました: {:?}", e)))?;
+                value_map.insert(result.clone(), value);
+                Ok(Some(value))
+            },
+            Instruction::Atomic { op, address, value, expected, result } => {
+                use inkwell::{AtomicOrdering, AtomicRMWBinOp};
+                
+                let ptr = self.build_operand(builder, address, value_map)?.into_pointer_value();
+                let value = value.as_ref().map(|value| self.build_operand(builder, value, value_map)).transpose()?;
+                let operand = || value.ok_or_else(|| EidosError::CodeGen(format!("アトミック操作 {:?} に値がありません", op)));
+                let atomic_error = |e| EidosError::CodeGen(format!("アトミック操作 {:?} の生成に失敗しました: {:?}", op, e));
+                let built: Option<BasicValueEnum> = match op {
+                    AtomicOp::Load => {
+                        let load = builder.build_load(self.context.i64_type(), ptr, "atomic_load").map_err(atomic_error)?;
+                        load.as_instruction_value()
+                            .ok_or_else(|| EidosError::CodeGen("アトミックな読み込みの命令がありません".to_string()))?
+                            .set_atomic_ordering(AtomicOrdering::SequentiallyConsistent)
+                            .map_err(atomic_error)?;
+                        Some(load)
+                    },
+                    AtomicOp::Store => {
+                        let store = builder.build_store(ptr, operand()?).map_err(atomic_error)?;
+                        store.set_atomic_ordering(AtomicOrdering::SequentiallyConsistent).map_err(atomic_error)?;
+                        None
+                    },
+                    AtomicOp::CAS => {
+                        let expected = expected
+                            .as_ref()
+                            .ok_or_else(|| EidosError::CodeGen("アトミック操作 CAS に比較する値がありません".to_string()))?;
+                        let expected = self.build_operand(builder, expected, value_map)?;
+                        let pair = builder
+                            .build_cmpxchg(ptr, expected, operand()?, AtomicOrdering::SequentiallyConsistent, AtomicOrdering::SequentiallyConsistent)
+                            .map_err(atomic_error)?;
+                        // cmpxchg は (元の値, 置き換えたか) の組を返す
+                        Some(builder.build_extract_value(pair, 1, "atomic_cas").map_err(atomic_error)?)
+                    },
+                    AtomicOp::Add | AtomicOp::Sub | AtomicOp::And | AtomicOp::Or | AtomicOp::Xor => {
+                        let rmw_op = match op {
+                            AtomicOp::Add => AtomicRMWBinOp::Add,
+                            AtomicOp::Sub => AtomicRMWBinOp::Sub,
+                            AtomicOp::And => AtomicRMWBinOp::And,
+                            AtomicOp::Or => AtomicRMWBinOp::Or,
+                            _ => AtomicRMWBinOp::Xor,
+                        };
+                        let previous = builder
+                            .build_atomicrmw(rmw_op, ptr, operand()?.into_int_value(), AtomicOrdering::SequentiallyConsistent)
+                            .map_err(atomic_error)?;
+                        Some(previous.into())
+                    },
+                };
+                if let (Some(result), Some(value)) = (result, built) {
+                    value_map.insert(result.clone(), value);
+                }
+                Ok(built)
+            },
+            Instruction::InlineAsm { asm, constraints, args, result, dialect } => {
+                if *dialect != AsmDialect::Native {
+                    return Err(EidosError::CodeGen(format!("LLVMバックエンドでは {}! は使えません", dialect.keyword())));
+                }
+                // テンプレートの `{N}` をLLVMの `$N` にする
+                let template: String = parse_asm_template(asm)
+                    .into_iter()
+                    .map(|piece| match piece {
+                        AsmPiece::Text(text) => text.replace('$', "$$"),
+                        AsmPiece::Operand(index) => format!("${}", index),
+                    })
+                    .collect();
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(self.build_operand(builder, arg, value_map)?);
+                }
+                let param_types: Vec<_> = values.iter().map(|value| value.get_type().into()).collect();
+                let function_type = match result {
+                    Some(_) => self.context.i64_type().fn_type(&param_types, false),
+                    None => self.context.void_type().fn_type(&param_types, false),
+                };
+                let inline_asm = self.context.create_inline_asm(function_type, template, constraints.clone(), true, false, None, false);
+                let call_args: Vec<inkwell::values::BasicMetadataValueEnum> = values.into_iter().map(Into::into).collect();
+                let call = builder
+                    .build_indirect_call(function_type, inline_asm, &call_args, "asm")
+                    .map_err(|e| EidosError::CodeGen(format!("インライン・アセンブリの生成に失敗しました: {:?}", e)))?;
+                let value = call.try_as_basic_value().left();
+                if let (Some(result), Some(value)) = (result, value) {
+                    value_map.insert(result.clone(), value);
+                }
+                Ok(value)
+            },
             // その他の命令...
-            _ => Err(EidosError::CodeGen(format!("未対応の命令: {:?}", instr))),
+            _ => Err(EidosError::CodeGen(format!("未対応の命令: {}", instr))),
         }
     }
 
+    /// 引数をそのままLLVMの組み込み関数 `name` に渡して結果を返す本体を作る
+    fn build_intrinsic_body(&self, llvm_module: &LLVMModule, function: FunctionValue, name: &str) -> Result<()> {
+        let intrinsic = inkwell::intrinsics::Intrinsic::find(name)
+            .ok_or_else(|| EidosError::CodeGen(format!("LLVMの組み込み関数 {} が見つかりません", name)))?;
+        let builder = self.context.create_builder();
+        builder.position_at_end(self.context.append_basic_block(function, "entry"));
+        
+        let mut args: Vec<BasicValueEnum> = Vec::new();
+        for param in function.get_param_iter() {
+            // memcpy のアドレスは整数で受け取るのでポインタに変換する
+            let arg = if name.starts_with("llvm.memcpy") && args.len() < 2 {
+                let pointer_type = self.context.i8_type().ptr_type(inkwell::AddressSpace::default());
+                builder.build_int_to_ptr(param.into_int_value(), pointer_type, "address").unwrap().into()
+            } else {
+                param
+            };
+            args.push(arg);
+        }
+        let overloaded_types: Vec<BasicTypeEnum> = args.iter().map(|arg| arg.get_type()).collect();
+        let overloaded_types = if name.starts_with("llvm.memcpy") { &overloaded_types[..] } else { &overloaded_types[..1] };
+        let declaration = intrinsic
+            .get_declaration(llvm_module, overloaded_types)
+            .ok_or_else(|| EidosError::CodeGen(format!("LLVMの組み込み関数 {} を宣言できません", name)))?;
+        
+        let mut call_args: Vec<inkwell::values::BasicMetadataValueEnum> = args.into_iter().map(Into::into).collect();
+        // ctlz / cttz は 0 に対してもビット幅を返させ、memcpy は volatile にしない
+        if name.starts_with("llvm.ctlz") || name.starts_with("llvm.cttz") || name.starts_with("llvm.memcpy") {
+            call_args.push(self.context.bool_type().const_zero().into());
+        }
+        let call = builder.build_call(declaration, &call_args, "intrinsic").unwrap();
+        match call.try_as_basic_value().left() {
+            Some(value) => builder.build_return(Some(&value)).unwrap(),
+            None => builder.build_return(None).unwrap(),
+        };
+        Ok(())
+    }
+    
     /// 型のアライメントを取得
     fn get_type_alignment(&self, ty: &Type) -> u32 {
         match ty {
@@ -1160,7 +1360,7 @@ impl LLVMBackend {
         }
     }
 
-    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>) -> Result<()> {
+    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>, attributes: &GlobalAttributes) -> Result<()> {
         info!("グローバル変数を宣言: {}", name);
         
         // LLVMモジュールを作成（まだ作成していない場合）
@@ -1182,21 +1382,21 @@ impl LLVMBackend {
         }
         
         // スレッドローカルストレージかチェック
-        if name.starts_with("__thread_") || name.ends_with("_tls") {
+        if attributes.is_thread_local {
             global.set_thread_local(true);
             debug!("グローバル変数 {} をスレッドローカルとして設定", name);
         }
         
         // セクション属性の設定
-        if name.starts_with("__const_") {
-            // 定数セクションに配置
+        if attributes.is_constant {
+            // 変更できない変数は定数セクションに配置
             global.set_section(".rodata");
             global.set_constant(true);
-        } else if name.starts_with("__bss_") {
-            // 未初期化データセクションに配置
+        } else if matches!(initializer, None | Some(Literal::Int(0)) | Some(Literal::Bool(false))) {
+            // ゼロで初期化する変数は未初期化データセクションに配置
             global.set_section(".bss");
-        } else if name.starts_with("__data_") {
-            // 初期化済みデータセクションに配置
+        } else {
+            // 初期値を持つ変更できる変数は初期化済みデータセクションに配置
             global.set_section(".data");
         }
         
@@ -1261,17 +1461,17 @@ impl LLVMBackend {
             
             global.set_initializer(&init_val);
             
-            // 定数の場合は属性を設定
-            match lit {
-                Literal::Int(_) | Literal::Float(_) | Literal::Bool(_) | Literal::Char(_) | Literal::String(_) => {
-                    // イミュータブルな定数として設定
-                    global.set_constant(true);
-                    // 重複を排除するためにユニークなセクションに配置
-                    global.set_section(".rodata.cst");
-                    // 必要に応じてコンパイラが最適化できるようにする
-                    global.set_unnamed_addr(true);
-                },
-                _ => {}
+            // 定数の場合は属性を設定（`static mut` の初期値は書き換えられるので定数にしない）
+            if attributes.is_constant {
+                match lit {
+                    Literal::Int(_) | Literal::Float(_) | Literal::Bool(_) | Literal::Char(_) => {
+                        // 重複を排除するためにユニークなセクションに配置
+                        global.set_section(".rodata.cst");
+                        // 必要に応じてコンパイラが最適化できるようにする
+                        global.set_unnamed_addr(true);
+                    },
+                    _ => {}
+                }
             }
         } else {
             // 初期化子がない場合はゼロで初期化
@@ -1346,7 +1546,7 @@ impl Backend for LLVMBackend {
         "llvm"
     }
     
-    fn compile(&self, module: &Module, options: &CodegenOptions) -> Result<Vec<u8>> {
+    fn compile(&mut self, module: &Module, options: &CodegenOptions) -> Result<Vec<u8>> {
         // LLVM モジュールを作成
         let llvm_module = self.context.create_module(&module.name);
         
@@ -1375,6 +1575,12 @@ impl Backend for LLVMBackend {
             // 関数を作成
             let function = llvm_module.add_function(&func.name, function_type, None);
             
+            // 組み込み関数は本体の代わりにLLVMの組み込み関数を呼ぶだけにする（呼び出し側でインライン化されて命令になる）
+            if let Some(name) = codegen::intrinsic_of(func).and_then(|intrinsic| intrinsic.llvm) {
+                self.build_intrinsic_body(&llvm_module, function, name)?;
+                continue;
+            }
+            
             // 関数本体を生成
             let builder = self.context.create_builder();
             
@@ -1418,13 +1624,31 @@ impl Backend for LLVMBackend {
         })?;
         
         // ターゲットマシンを取得
-        let target_machine = self.get_target_machine(&options.target)?;
+        let target_machine = self.get_target_machine(options)?;
+        
+        // LLVM IRで出力しても後段のコンパイラが同じマシン向けに生成するよう、ターゲットとCPUを記録する
+        llvm_module.set_triple(&target_machine.get_triple());
+        llvm_module.set_data_layout(&target_machine.get_target_data().get_data_layout());
+        let cpu = target_machine.get_cpu().to_string();
+        let features = target_machine.get_feature_string().to_string();
+        let mut function = llvm_module.get_first_function();
+        while let Some(value) = function {
+            if value.count_basic_blocks() > 0 {
+                let loc = inkwell::attributes::AttributeLoc::Function;
+                value.add_attribute(loc, self.context.create_string_attribute("target-cpu", &cpu));
+                if !features.is_empty() {
+                    value.add_attribute(loc, self.context.create_string_attribute("target-features", &features));
+                }
+            }
+            function = value.get_next_function();
+        }
         
         // 出力フォーマットを決定
         let file_type = match options.format {
             OutputFormat::Object => FileType::Object,
             OutputFormat::Assembly => FileType::Assembly,
             OutputFormat::LLVMIR => FileType::Assembly, // LLVMIRは別処理
+            OutputFormat::Bitcode => FileType::Object, // ビットコードは別処理
         };
         
         // LLVM IRを直接出力する場合
@@ -1435,6 +1659,11 @@ impl Backend for LLVMBackend {
             return Ok(ir.into_bytes());
         }
         
+        // リンク時最適化では、リンカがモジュールをまたいで最適化してから機械語にするので、ビットコードを渡す
+        if options.format == OutputFormat::Bitcode {
+            return Ok(llvm_module.write_bitcode_to_memory().as_slice().to_vec());
+        }
+        
         // 機械語コードを生成
         let code = target_machine.write_to_memory_buffer(&llvm_module, file_type)
             .map_err(|e| EidosError::CodeGen(format!("コード生成に失敗: {:?}", e)))?;
@@ -1507,7 +1736,7 @@ impl Backend for LLVMBackend {
         Ok(())
     }
     
-    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>) -> Result<()> {
+    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>, attributes: &GlobalAttributes) -> Result<()> {
         info!("グローバル変数を宣言: {}", name);
         
         // LLVMモジュールを作成（まだ作成していない場合）
@@ -1529,21 +1758,21 @@ impl Backend for LLVMBackend {
         }
         
         // スレッドローカルストレージかチェック
-        if name.starts_with("__thread_") || name.ends_with("_tls") {
+        if attributes.is_thread_local {
             global.set_thread_local(true);
             debug!("グローバル変数 {} をスレッドローカルとして設定", name);
         }
         
         // セクション属性の設定
-        if name.starts_with("__const_") {
-            // 定数セクションに配置
+        if attributes.is_constant {
+            // 変更できない変数は定数セクションに配置
             global.set_section(".rodata");
             global.set_constant(true);
-        } else if name.starts_with("__bss_") {
-            // 未初期化データセクションに配置
+        } else if matches!(initializer, None | Some(Literal::Int(0)) | Some(Literal::Bool(false))) {
+            // ゼロで初期化する変数は未初期化データセクションに配置
             global.set_section(".bss");
-        } else if name.starts_with("__data_") {
-            // 初期化済みデータセクションに配置
+        } else {
+            // 初期値を持つ変更できる変数は初期化済みデータセクションに配置
             global.set_section(".data");
         }
         
@@ -1608,17 +1837,17 @@ impl Backend for LLVMBackend {
             
             global.set_initializer(&init_val);
             
-            // 定数の場合は属性を設定
-            match lit {
-                Literal::Int(_) | Literal::Float(_) | Literal::Bool(_) | Literal::Char(_) | Literal::String(_) => {
-                    // イミュータブルな定数として設定
-                    global.set_constant(true);
-                    // 重複を排除するためにユニークなセクションに配置
-                    global.set_section(".rodata.cst");
-                    // 必要に応じてコンパイラが最適化できるようにする
-                    global.set_unnamed_addr(true);
-                },
-                _ => {}
+            // 定数の場合は属性を設定（`static mut` の初期値は書き換えられるので定数にしない）
+            if attributes.is_constant {
+                match lit {
+                    Literal::Int(_) | Literal::Float(_) | Literal::Bool(_) | Literal::Char(_) => {
+                        // 重複を排除するためにユニークなセクションに配置
+                        global.set_section(".rodata.cst");
+                        // 必要に応じてコンパイラが最適化できるようにする
+                        global.set_unnamed_addr(true);
+                    },
+                    _ => {}
+                }
             }
         } else {
             // 初期化子がない場合はゼロで初期化
@@ -1,7 +1,14 @@
 pub mod llvm;
 pub mod wasm;
+pub mod c_emitter;
+pub mod js;
+pub mod jit;
 pub mod codegen;
 pub mod optimizer;
 
 pub use codegen::CodeGenerator;
+pub use c_emitter::{CEmitter, CRuntime, FreestandingRuntime, HostedRuntime};
+pub use js::JsBackend;
+pub use wasm::WasmBackend;
+pub use jit::{JitEngine, JitValue};
 pub use optimizer::Optimizer; 
\ No newline at end of file
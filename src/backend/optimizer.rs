@@ -1,12 +1,23 @@
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 use log::{debug, info};
 
-use crate::core::Result;
-use crate::core::eir::{Module, Function, FunctionId, BlockId, InstructionId, Instruction, Operand};
+use crate::core::{Result, SourceLocation};
+use crate::core::session::CompileSession;
+use crate::core::analysis::{AnalysisCache, Cfg, DominatorTree, Loop};
+use crate::core::call_graph::CallGraph;
+use crate::core::limits;
+use crate::core::induction;
+use crate::core::loop_fusion;
+use crate::core::range_analysis;
+use crate::core::size_opt;
+use crate::core::eir::{self, Module, Function, FunctionId, BlockId, InstructionId, Instruction, Operand};
+use crate::frontend::ConstEvaluator;
+use crate::stdlib::{StdlibFunctionType, StdlibRegistry, Value};
 
 /// 最適化パス
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
 pub enum OptimizationPass {
     /// 定数畳み込み
     ConstantFolding,
@@ -28,6 +39,18 @@ pub enum OptimizationPass {
     LoopUnrolling,
     /// SIMD最適化
     SIMDOptimization,
+    /// 値域解析による定数化と除算検査の除去
+    RangeAnalysis,
+    /// 帰納変数の強度低減と使われない帰納変数の削除
+    InductionVariables,
+    /// 隣り合うループの融合
+    LoopFusion,
+    /// `switch` の表引きの算術への置き換え
+    SwitchLowering,
+    /// 到達しないブロックの別の関数への切り出し
+    ColdBlockOutlining,
+    /// 本体が同じ関数の統合
+    FunctionMerging,
 }
 
 impl OptimizationPass {
@@ -44,8 +67,36 @@ impl OptimizationPass {
             Self::ControlFlowOptimization,
             Self::LoopUnrolling,
             Self::SIMDOptimization,
+            Self::RangeAnalysis,
+            Self::InductionVariables,
+            Self::LoopFusion,
+            Self::SwitchLowering,
+            Self::ColdBlockOutlining,
+            Self::FunctionMerging,
         ]
     }
+    
+    /// `--time-passes` などに表示するパスの名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ConstantFolding => "定数畳み込み",
+            Self::DeadCodeElimination => "不要コード削除",
+            Self::CommonSubexpressionElimination => "共通部分式削除",
+            Self::FunctionInlining => "関数インライン化",
+            Self::LoopInvariantCodeMotion => "ループの不変コード移動",
+            Self::MemoryToRegister => "メモリToレジスタ",
+            Self::InstructionCombining => "命令の組み合わせ",
+            Self::ControlFlowOptimization => "制御フロー最適化",
+            Self::LoopUnrolling => "ループアンロール",
+            Self::SIMDOptimization => "SIMD最適化",
+            Self::RangeAnalysis => "値域解析",
+            Self::InductionVariables => "帰納変数の最適化",
+            Self::LoopFusion => "ループ融合",
+            Self::SwitchLowering => "switchの表引き化",
+            Self::ColdBlockOutlining => "コールドブロックの切り出し",
+            Self::FunctionMerging => "同一関数の統合",
+        }
+    }
 }
 
 /// 最適化レベル
@@ -79,7 +130,7 @@ impl From<u8> for OptimizationLevel {
 pub struct OptimizationOptions {
     /// 最適化レベル
     pub level: OptimizationLevel,
-    /// サイズ最適化フラグ
+    /// サイズ最適化フラグ（`level` によらずサイズ最適化のパスを実行する）
     pub optimize_size: bool,
     /// インライン化しきい値
     pub inline_threshold: usize,
@@ -91,6 +142,10 @@ pub struct OptimizationOptions {
     pub profile_guided: bool,
     /// 無効化する最適化パス
     pub disabled_passes: HashSet<OptimizationPass>,
+    /// パスの前後でEIRを検証するか（デバッグビルドでは既定で有効）
+    pub verify_ir: bool,
+    /// 実行後のモジュールを記録するパス（`eidos ir --dot-after` で可視化する）
+    pub snapshot_passes: HashSet<OptimizationPass>,
 }
 
 impl Default for OptimizationOptions {
@@ -103,6 +158,8 @@ impl Default for OptimizationOptions {
             enable_simd: true,
             profile_guided: false,
             disabled_passes: HashSet::new(),
+            verify_ir: cfg!(debug_assertions),
+            snapshot_passes: HashSet::new(),
         }
     }
 }
@@ -113,6 +170,16 @@ pub struct Optimizer {
     options: OptimizationOptions,
     /// 関数実行数の統計
     fn_execution_counts: HashMap<FunctionId, usize>,
+    /// パスごとの所要時間を記録するセッション
+    session: Rc<CompileSession>,
+    /// 純粋関数の名前（呼び出しを共通部分式削除やループ外への移動の対象にできる）
+    pure_functions: HashSet<String>,
+    /// `const fn` の評価器（定数引数の呼び出しを定数畳み込みで評価する）
+    const_evaluator: ConstEvaluator,
+    /// 関数ごとの制御フローグラフ・支配木・ループ森（パスの中で使い回す）
+    analyses: AnalysisCache,
+    /// `snapshot_passes` のパスを実行した後のモジュール（パスの名前と組にする）
+    snapshots: Vec<(String, Module)>,
 }
 
 impl Optimizer {
@@ -121,6 +188,11 @@ impl Optimizer {
         Self {
             options,
             fn_execution_counts: HashMap::new(),
+            session: Rc::new(CompileSession::new(false)),
+            pure_functions: HashSet::new(),
+            const_evaluator: ConstEvaluator::new(),
+            analyses: AnalysisCache::new(),
+            snapshots: Vec::new(),
         }
     }
     
@@ -136,52 +208,177 @@ impl Optimizer {
         Self::new(options)
     }
     
+    /// パスごとの所要時間を記録するセッションを設定
+    pub fn with_session(mut self, session: Rc<CompileSession>) -> Self {
+        self.session = session;
+        self
+    }
+    
+    /// 型チェックで集めた `const fn` を設定
+    pub fn with_const_evaluator(mut self, const_evaluator: ConstEvaluator) -> Self {
+        self.const_evaluator = const_evaluator;
+        self
+    }
+    
+    /// 記録したモジュールを、パスを実行した順に取り出す
+    pub fn take_snapshots(&mut self) -> Vec<(String, Module)> {
+        std::mem::take(&mut self.snapshots)
+    }
+    
     /// モジュールを最適化
     pub fn optimize_module(&mut self, module: &mut Module) -> Result<()> {
         info!("モジュール '{}' の最適化を開始", module.name);
-        
-        // 最適化レベルに応じた最適化パスを実行
-        match self.options.level {
-            OptimizationLevel::None => {
-                // 最適化なし
-                debug!("最適化スキップ: 最適化レベル = None");
-            },
-            OptimizationLevel::Size => {
-                // サイズ最適化
-                debug!("サイズ最適化を実行");
-                self.run_size_optimization_passes(module)?;
-            },
-            OptimizationLevel::Speed1 => {
-                // 速度最適化（レベル1）
-                debug!("速度最適化（レベル1）を実行");
-                self.run_speed1_optimization_passes(module)?;
-            },
-            OptimizationLevel::Speed2 => {
-                // 速度最適化（レベル2）
-                debug!("速度最適化（レベル2）を実行");
-                self.run_speed2_optimization_passes(module)?;
-            },
-            OptimizationLevel::Speed3 => {
-                // 速度最適化（レベル3）
-                debug!("速度最適化（レベル3）を実行");
-                self.run_speed3_optimization_passes(module)?;
-            },
-        }
+        self.pure_functions = Self::collect_pure_functions(module)?;
+        // 最適化の前から壊れていれば、最初のパスのせいにしない
+        if self.options.verify_ir {
+            eir::verify_after(module, "EIRの構築")?;
+        }
+        
+        let session = Rc::clone(&self.session);
+        session.time("最適化", || -> Result<()> {
+            // 最適化レベルに応じた最適化パスを実行
+            let level = if self.options.optimize_size { OptimizationLevel::Size } else { self.options.level };
+            match level {
+                OptimizationLevel::None => {
+                    // 最適化なし
+                    debug!("最適化スキップ: 最適化レベル = None");
+                },
+                OptimizationLevel::Size => {
+                    // サイズ最適化
+                    debug!("サイズ最適化を実行");
+                    self.run_size_optimization_passes(module)?;
+                },
+                OptimizationLevel::Speed1 => {
+                    // 速度最適化（レベル1）
+                    debug!("速度最適化（レベル1）を実行");
+                    self.run_speed1_optimization_passes(module)?;
+                },
+                OptimizationLevel::Speed2 => {
+                    // 速度最適化（レベル2）
+                    debug!("速度最適化（レベル2）を実行");
+                    self.run_speed2_optimization_passes(module)?;
+                },
+                OptimizationLevel::Speed3 => {
+                    // 速度最適化（レベル3）
+                    debug!("速度最適化（レベル3）を実行");
+                    self.run_speed3_optimization_passes(module)?;
+                },
+            }
+            Ok(())
+        })?;
         
         info!("モジュール '{}' の最適化が完了", module.name);
         Ok(())
     }
     
+    /// 純粋関数の名前を集める
+    ///
+    /// ユーザー定義関数は型チェックで純粋性が保証されたもの、標準ライブラリ関数は `Pure` として登録されたもの。
+    /// 純粋関数もコレクションの状態などを読むことはあるため、副作用のある命令をまたいで再利用はしない。
+    fn collect_pure_functions(module: &Module) -> Result<HashSet<String>> {
+        let mut pure_functions: HashSet<String> = module
+            .functions
+            .values()
+            .filter(|func| func.attributes.pure)
+            .map(|func| func.name.clone())
+            .collect();
+        
+        StdlibRegistry::initialize()?;
+        let registry = StdlibRegistry::global();
+        let registry = registry.read().unwrap();
+        for function in registry.functions.values() {
+            if function.fn_type == StdlibFunctionType::Pure {
+                pure_functions.insert(function.full_name());
+                pure_functions.insert(function.name.clone());
+            }
+        }
+        Ok(pure_functions)
+    }
+    
+    /// 純粋関数の呼び出しかどうか
+    fn is_pure_call(&self, instr: &Instruction) -> bool {
+        match instr {
+            Instruction::Call { function, .. } => self.pure_functions.contains(function),
+            _ => false,
+        }
+    }
+    
+    /// メモリや外部の状態を書き換えうる命令かどうか
+    fn writes_state(&self, instr: &Instruction) -> bool {
+        match instr {
+            Instruction::Store { .. } => true,
+            Instruction::Call { .. } => !self.is_pure_call(instr),
+            _ => false,
+        }
+    }
+    
+    /// 最適化パスを実行し、所要時間をセッションに記録（`verify_ir` ならパスの後にEIRを検証する）
+    ///
+    /// 解析結果はパスの中でだけ使い回し、パスが終わるたびに捨てる。
+    fn timed<F>(&mut self, name: &str, module: &mut Module, pass: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self, &mut Module) -> Result<()>,
+    {
+        let session = Rc::clone(&self.session);
+        session.time(name, || pass(self, module))?;
+        self.analyses.clear();
+        if self.options.verify_ir {
+            eir::verify_after(module, name)?;
+        }
+        if self.options.snapshot_passes.iter().any(|pass| name.starts_with(pass.name())) {
+            self.snapshots.push((name.to_string(), module.clone()));
+        }
+        Ok(())
+    }
+    
     /// サイズ最適化パスを実行
+    ///
+    /// コードを大きくするインライン化・ループアンロール・帰納変数の最適化はせず、`switch` の表引き化と
+    /// コールドブロックの切り出しの後に、切り出した関数を含めて同じ本体の関数をまとめる。
     fn run_size_optimization_passes(&mut self, module: &mut Module) -> Result<()> {
         // 定数畳み込み
-        self.run_constant_folding(module)?;
+        if !self.options.disabled_passes.contains(&OptimizationPass::ConstantFolding) {
+            self.timed("定数畳み込み", module, Self::run_constant_folding)?;
+        }
+        
+        // 除算の検査を取り除く
+        if !self.options.disabled_passes.contains(&OptimizationPass::RangeAnalysis) {
+            self.timed("値域解析", module, |_, module| {
+                range_analysis::optimize(module);
+                Ok(())
+            })?;
+        }
         
         // 不要コード削除
-        self.run_dead_code_elimination(module)?;
+        if !self.options.disabled_passes.contains(&OptimizationPass::DeadCodeElimination) {
+            self.timed("不要コード削除", module, Self::run_dead_code_elimination)?;
+        }
         
         // 制御フロー最適化
-        self.run_control_flow_optimization(module)?;
+        if !self.options.disabled_passes.contains(&OptimizationPass::ControlFlowOptimization) {
+            self.timed("制御フロー最適化", module, Self::run_control_flow_optimization)?;
+        }
+        
+        if !self.options.disabled_passes.contains(&OptimizationPass::SwitchLowering) {
+            self.timed("switchの表引き化", module, |_, module| {
+                size_opt::lower_switches(module);
+                Ok(())
+            })?;
+        }
+        
+        if !self.options.disabled_passes.contains(&OptimizationPass::ColdBlockOutlining) {
+            self.timed("コールドブロックの切り出し", module, |_, module| {
+                size_opt::outline_cold_blocks(module);
+                Ok(())
+            })?;
+        }
+        
+        if !self.options.disabled_passes.contains(&OptimizationPass::FunctionMerging) {
+            self.timed("同一関数の統合", module, |_, module| {
+                size_opt::merge_identical_functions(module);
+                Ok(())
+            })?;
+        }
         
         Ok(())
     }
@@ -192,25 +389,32 @@ impl Optimizer {
         
         // 基本的な最適化パス
         if !self.options.disabled_passes.contains(&OptimizationPass::ConstantFolding) {
-            self.run_constant_folding(module)?;
+            self.timed("定数畳み込み", module, Self::run_constant_folding)?;
+        }
+        
+        if !self.options.disabled_passes.contains(&OptimizationPass::RangeAnalysis) {
+            self.timed("値域解析", module, |_, module| {
+                range_analysis::optimize(module);
+                Ok(())
+            })?;
         }
         
         if !self.options.disabled_passes.contains(&OptimizationPass::DeadCodeElimination) {
-            self.run_dead_code_elimination(module)?;
+            self.timed("不要コード削除", module, Self::run_dead_code_elimination)?;
         }
         
         if !self.options.disabled_passes.contains(&OptimizationPass::CommonSubexpressionElimination) {
-            self.run_common_subexpression_elimination(module)?;
+            self.timed("共通部分式削除", module, Self::run_common_subexpression_elimination)?;
         }
         
         // 必須の制御フロー最適化
         if !self.options.disabled_passes.contains(&OptimizationPass::ControlFlowOptimization) {
-            self.run_control_flow_optimization(module)?;
+            self.timed("制御フロー最適化", module, Self::run_control_flow_optimization)?;
         }
         
         // 基本的なメモリ最適化
         if !self.options.disabled_passes.contains(&OptimizationPass::MemoryToRegister) {
-            self.run_memory_to_register(module)?;
+            self.timed("メモリToレジスタ", module, Self::run_memory_to_register)?;
         }
         
         Ok(())
@@ -225,30 +429,44 @@ impl Optimizer {
         
         // さらに追加のパス
         if !self.options.disabled_passes.contains(&OptimizationPass::InstructionCombining) {
-            self.run_instruction_combining(module)?;
+            self.timed("命令の組み合わせ", module, Self::run_instruction_combining)?;
         }
         
         if !self.options.disabled_passes.contains(&OptimizationPass::FunctionInlining) {
             // 通常の関数インライン化
-            self.run_function_inlining(module, false)?;
+            self.timed("関数インライン化", module, |optimizer, module| optimizer.run_function_inlining(module, false))?;
         }
         
         // 再度、定数畳み込みと不要コード削除を実行
         if !self.options.disabled_passes.contains(&OptimizationPass::ConstantFolding) {
-            self.run_constant_folding(module)?;
+            self.timed("定数畳み込み", module, Self::run_constant_folding)?;
         }
         
         if !self.options.disabled_passes.contains(&OptimizationPass::DeadCodeElimination) {
-            self.run_dead_code_elimination(module)?;
+            self.timed("不要コード削除", module, Self::run_dead_code_elimination)?;
         }
         
         // 基本的なループ最適化
         if !self.options.disabled_passes.contains(&OptimizationPass::LoopInvariantCodeMotion) {
-            self.run_loop_invariant_code_motion(module)?;
+            self.timed("ループの不変コード移動", module, Self::run_loop_invariant_code_motion)?;
+        }
+        
+        if !self.options.disabled_passes.contains(&OptimizationPass::LoopFusion) {
+            self.timed("ループ融合", module, |_, module| {
+                loop_fusion::optimize(module);
+                Ok(())
+            })?;
+        }
+        
+        if !self.options.disabled_passes.contains(&OptimizationPass::InductionVariables) {
+            self.timed("帰納変数の最適化", module, |_, module| {
+                induction::optimize(module);
+                Ok(())
+            })?;
         }
         
         // 命令スケジューリング（基本的なもの）
-        self.run_instruction_scheduling(module)?;
+        self.timed("命令スケジューリング", module, Self::run_instruction_scheduling)?;
         
         Ok(())
     }
@@ -263,29 +481,29 @@ impl Optimizer {
         // さらに積極的な最適化
         if !self.options.disabled_passes.contains(&OptimizationPass::FunctionInlining) {
             // 積極的な関数インライン化
-            self.run_function_inlining(module, true)?;
+            self.timed("関数インライン化（積極的）", module, |optimizer, module| optimizer.run_function_inlining(module, true))?;
         }
         
         if !self.options.disabled_passes.contains(&OptimizationPass::LoopInvariantCodeMotion) {
             // 積極的なループの不変コード移動
-            self.run_loop_invariant_code_motion(module)?;
+            self.timed("ループの不変コード移動", module, Self::run_loop_invariant_code_motion)?;
         }
         
         // ループアンロール最適化
         if !self.options.disabled_passes.contains(&OptimizationPass::LoopUnrolling) {
-            self.run_loop_unrolling(module)?;
+            self.timed("ループアンロール", module, Self::run_loop_unrolling)?;
         }
         
         // SIMD最適化
         if !self.options.disabled_passes.contains(&OptimizationPass::SIMDOptimization) {
-            self.run_simd_optimization(module)?;
+            self.timed("SIMD最適化", module, Self::run_simd_optimization)?;
         }
         
         // レジスタ割り当て最適化
-        self.run_register_allocation(module)?;
+        self.timed("レジスタ割り当て", module, Self::run_register_allocation)?;
         
         // 命令スケジューリング
-        self.run_instruction_scheduling(module)?;
+        self.timed("命令スケジューリング", module, Self::run_instruction_scheduling)?;
         
         Ok(())
     }
@@ -380,11 +598,35 @@ impl Optimizer {
                     _ => None,
                 }
             },
+            // 引数がすべて定数の `const fn` の呼び出しは、コンパイル時に評価した値に置き換える
+            // （評価に失敗した呼び出しはそのまま残し、実行時に同じエラーを報告させる）
+            Instruction::Call { function, args, .. } if self.const_evaluator.is_const_function(function) => {
+                let args = args.iter().map(Self::operand_value).collect::<Option<Vec<_>>>()?;
+                let value = self.const_evaluator.call(function, &args, &SourceLocation::unknown()).ok()?;
+                match value {
+                    Value::Int(i) => Some(Operand::ConstantInt(i)),
+                    Value::Float(f) => Some(Operand::ConstantFloat(f)),
+                    Value::Bool(b) => Some(Operand::ConstantBool(b)),
+                    Value::Str(s) => Some(Operand::ConstantString(s)),
+                    _ => None,
+                }
+            },
             // その他の命令は畳み込み不可
             _ => None,
         }
     }
     
+    /// 定数オペランドの値
+    fn operand_value(operand: &Operand) -> Option<Value> {
+        match operand {
+            Operand::ConstantInt(i) => Some(Value::Int(*i)),
+            Operand::ConstantFloat(f) => Some(Value::Float(*f)),
+            Operand::ConstantBool(b) => Some(Value::Bool(*b)),
+            Operand::ConstantString(s) => Some(Value::Str(s.clone())),
+            _ => None,
+        }
+    }
+    
     /// 不要コード削除
     fn run_dead_code_elimination(&mut self, module: &mut Module) -> Result<()> {
         debug!("不要コード削除最適化を実行");
@@ -392,12 +634,16 @@ impl Optimizer {
         for (func_id, func) in module.functions.iter_mut() {
             debug!("関数 '{}' の不要コード削除を実行", func.name);
             
+            // 到達できないブロックの命令はどれも使われない
+            self.remove_unreachable_code(func);
+            
             // 使用されている命令のセット
             let mut used_instructions = HashSet::new();
             
-            // 副作用のある命令と関数の終了命令をマーク
+            // 副作用のある命令と関数の終了命令をマーク（結果が使われない純粋関数の呼び出しは削除できる）
             for (instr_id, instr) in func.instructions.iter() {
                 match instr {
+                    Instruction::Call { .. } if self.is_pure_call(instr) => {},
                     Instruction::Return { .. } |
                     Instruction::Call { .. } |
                     Instruction::Store { .. } |
@@ -506,6 +752,11 @@ impl Optimizer {
                     let instr_id = block.instructions[i];
                     
                     if let Some(instr) = func.instructions.get(&instr_id) {
+                        // 状態を書き換える命令の後では、読み出しと純粋関数の呼び出しの結果を再利用できない
+                        if self.writes_state(instr) {
+                            block_expr_map.retain(|hash, _| !hash.starts_with("load:") && !hash.starts_with("call:"));
+                        }
+                        
                         // 命令のハッシュを計算
                         let instr_hash = self.compute_instruction_hash(instr);
                         
@@ -549,6 +800,11 @@ impl Optimizer {
                 let indices_str: Vec<String> = indices.iter().map(|idx| self.operand_to_string(idx)).collect();
                 Some(format!("gep:{}:{}", self.operand_to_string(base), indices_str.join(":")))
             },
+            // 純粋関数は同じ引数なら同じ結果を返す
+            Instruction::Call { function, args, .. } if self.is_pure_call(instr) => {
+                let args_str: Vec<String> = args.iter().map(|arg| self.operand_to_string(arg)).collect();
+                Some(format!("call:{}:{}", function, args_str.join(":")))
+            },
             // メモリ書き込みや分岐などの副作用のある命令は除外
             Instruction::Store { .. } |
             Instruction::Call { .. } |
@@ -586,11 +842,19 @@ impl Optimizer {
     fn run_function_inlining(&mut self, module: &mut Module, aggressive: bool) -> Result<()> {
         debug!("関数インライン化最適化を実行 (aggressive: {})", aggressive);
         
+        // 呼び出される関数を先にインライン化しておけば、呼び出し元には展開済みの本体が入る
+        let call_graph = CallGraph::new(module);
+        
         // インライン化する関数のリスト
         let mut inline_candidates: Vec<FunctionId> = Vec::new();
         
         // インライン化候補の関数を識別
         for (func_id, func) in &module.functions {
+            // 再帰の循環に含まれる関数は展開が終わらないので候補にしない
+            if call_graph.is_recursive(*func_id) {
+                continue;
+            }
+            
             // 小さい関数または頻繁に呼び出される関数をインライン化候補に
             let size = func.blocks.len();
             let call_count = self.fn_execution_counts.get(func_id).cloned().unwrap_or(0);
@@ -609,8 +873,15 @@ impl Optimizer {
             }
         }
         
-        // 各関数内の関数呼び出しをインライン化
-        for (caller_id, caller) in module.functions.iter_mut() {
+        // 展開済みの本体に含まれる、インライン化した呼び出しの入れ子の深さ（`--recursion-limit` まで）
+        let mut inline_depths: HashMap<FunctionId, usize> = HashMap::new();
+        
+        // 各関数内の関数呼び出しを、呼び出される側から順にインライン化
+        for caller_id in call_graph.bottom_up_order() {
+            // 呼び出し先を参照できるよう、処理中の呼び出し元はモジュールから取り出しておく
+            let Some(mut caller) = module.functions.remove(&caller_id) else {
+                continue;
+            };
             let mut inline_sites: Vec<(BlockId, usize, InstructionId, FunctionId)> = Vec::new();
             
             // インライン化する呼び出しサイトを特定
@@ -628,13 +899,23 @@ impl Optimizer {
             
             // インライン化を実行
             for (block_id, instr_index, call_instr_id, callee_id) in inline_sites {
+                let depth = inline_depths.get(&callee_id).copied().unwrap_or(0) + 1;
+                if depth > limits::recursion_limit() {
+                    debug!("インライン化の入れ子が深すぎるため、関数 {:?} 内の呼び出し {:?} の展開をスキップ", caller_id, call_instr_id);
+                    continue;
+                }
+                
                 // インライン化処理
                 debug!("関数 {:?} 内の呼び出し {:?} を関数 {:?} でインライン化", caller_id, call_instr_id, callee_id);
                 
                 if let Some(callee) = module.functions.get(&callee_id).cloned() {
-                    self.inline_function(module, caller, &block_id, call_instr_id, &callee)?;
+                    self.inline_function(module, &mut caller, &block_id, call_instr_id, &callee)?;
+                    let caller_depth = inline_depths.entry(caller_id).or_insert(0);
+                    *caller_depth = (*caller_depth).max(depth);
                 }
             }
+            
+            module.functions.insert(caller_id, caller);
         }
         
         Ok(())
@@ -794,10 +1075,10 @@ impl Optimizer {
         for (func_id, func) in module.functions.iter_mut() {
             debug!("関数 '{}' のループ不変コード移動を実行", func.name);
             
-            // ループを検出
-            let loops = self.detect_loops(func);
+            // 内側のループから順に、不変命令を外へ移していく
+            let loops = self.analyses.loops(func);
             
-            for loop_info in loops {
+            for loop_info in loops.innermost_first() {
                 // ループの不変命令を特定
                 let invariant_instructions = self.identify_loop_invariants(func, &loop_info);
                 
@@ -809,208 +1090,8 @@ impl Optimizer {
         Ok(())
     }
     
-    /// ループ構造を検出
-    fn detect_loops(&self, func: &Function) -> Vec<LoopInfo> {
-        let mut loops = Vec::new();
-        
-        // ドミネータツリーを構築
-        let dominators = self.compute_dominators(func);
-        
-        // バックエッジを検出してループを見つける
-        for (block_id, block) in &func.blocks {
-            // ブロックの最後の命令を取得
-            if let Some(&last_instr_id) = block.instructions.last() {
-                if let Some(instr) = func.instructions.get(&last_instr_id) {
-                    match instr {
-                        Instruction::Branch { target } => {
-                            // 無条件分岐: targetがcurrent_blockをドミネートする場合はループ
-                            if dominators.get(block_id).map_or(false, |doms| doms.contains(target)) {
-                                // ループ発見
-                                let header = *target;
-                                loops.push(self.construct_loop_info(func, header, *block_id, &dominators));
-                            }
-                        },
-                        Instruction::ConditionalBranch { true_target, false_target, .. } => {
-                            // 条件分岐: 各ターゲットがcurrent_blockをドミネートする場合はループ
-                            if dominators.get(block_id).map_or(false, |doms| doms.contains(true_target)) {
-                                // true_targetへのバックエッジを持つループ
-                                let header = *true_target;
-                                loops.push(self.construct_loop_info(func, header, *block_id, &dominators));
-                            }
-                            if dominators.get(block_id).map_or(false, |doms| doms.contains(false_target)) {
-                                // false_targetへのバックエッジを持つループ
-                                let header = *false_target;
-                                loops.push(self.construct_loop_info(func, header, *block_id, &dominators));
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-            }
-        }
-        
-        loops
-    }
-    
-    /// ドミネータを計算
-    fn compute_dominators(&self, func: &Function) -> HashMap<BlockId, HashSet<BlockId>> {
-        let mut dominators: HashMap<BlockId, HashSet<BlockId>> = HashMap::new();
-        
-        // すべてのブロックIDを取得
-        let all_blocks: HashSet<BlockId> = func.blocks.keys().cloned().collect();
-        
-        // エントリブロックのドミネータはエントリブロック自身のみ
-        if let Some(entry) = func.entry_block {
-            let mut entry_doms = HashSet::new();
-            entry_doms.insert(entry);
-            dominators.insert(entry, entry_doms);
-            
-            // 他のすべてのブロックのドミネータは最初すべてのブロック
-            for block_id in all_blocks.iter() {
-                if *block_id != entry {
-                    dominators.insert(*block_id, all_blocks.clone());
-                }
-            }
-            
-            let mut changed = true;
-            while changed {
-                changed = false;
-                
-                // 各ブロックのドミネータを更新
-                for block_id in all_blocks.iter() {
-                    if *block_id == entry {
-                        continue; // エントリブロックはスキップ
-                    }
-                    
-                    // 現在のブロックの前任者（predecessors）を取得
-                    let predecessors = self.get_predecessors(func, *block_id);
-                    if predecessors.is_empty() {
-                        continue; // 到達不能なブロックはスキップ
-                    }
-                    
-                    // 新しいドミネータセットを計算
-                    let mut new_doms = all_blocks.clone();
-                    
-                    for pred in predecessors {
-                        if let Some(pred_doms) = dominators.get(&pred) {
-                            // 前任者のドミネータと交差
-                            new_doms = new_doms.intersection(pred_doms).cloned().collect();
-                        }
-                    }
-                    
-                    // ブロック自身を追加
-                    new_doms.insert(*block_id);
-                    
-                    // ドミネータが変更されたかチェック
-                    if let Some(old_doms) = dominators.get(block_id) {
-                        if old_doms != &new_doms {
-                            dominators.insert(*block_id, new_doms);
-                            changed = true;
-                        }
-                    }
-                }
-            }
-        }
-        
-        dominators
-    }
-    
-    /// ブロックの前任者（predecessors）を取得
-    fn get_predecessors(&self, func: &Function, block_id: BlockId) -> Vec<BlockId> {
-        let mut predecessors = Vec::new();
-        
-        for (pred_id, pred_block) in &func.blocks {
-            // 最後の命令を取得
-            if let Some(&last_instr_id) = pred_block.instructions.last() {
-                if let Some(instr) = func.instructions.get(&last_instr_id) {
-                    match instr {
-                        Instruction::Branch { target } => {
-                            if *target == block_id {
-                                predecessors.push(*pred_id);
-                            }
-                        },
-                        Instruction::ConditionalBranch { true_target, false_target, .. } => {
-                            if *true_target == block_id || *false_target == block_id {
-                                predecessors.push(*pred_id);
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-            }
-        }
-        
-        predecessors
-    }
-    
-    /// ループ情報を構築
-    fn construct_loop_info(&self, func: &Function, header: BlockId, backedge: BlockId, 
-                          dominators: &HashMap<BlockId, HashSet<BlockId>>) -> LoopInfo {
-        let mut body = HashSet::new();
-        body.insert(header);
-        
-        // バックエッジブロックから遡って、ループ本体に含まれるブロックを特定
-        let mut worklist = vec![backedge];
-        while let Some(block_id) = worklist.pop() {
-            if body.insert(block_id) {
-                // 前任者をワークリストに追加
-                for pred in self.get_predecessors(func, block_id) {
-                    if pred != header && !body.contains(&pred) {
-                        worklist.push(pred);
-                    }
-                }
-            }
-        }
-        
-        // プリヘッダブロックを特定
-        let mut preheader = None;
-        let header_preds = self.get_predecessors(func, header);
-        for pred in header_preds {
-            if !body.contains(&pred) {
-                // ループ外からのエッジを持つブロックをプリヘッダとする
-                preheader = Some(pred);
-                break;
-            }
-        }
-        
-        // 出口ブロックを特定
-        let mut exits = HashSet::new();
-        for &block_id in &body {
-            if let Some(block) = func.blocks.get(&block_id) {
-                // ブロックの最後の命令を取得
-                if let Some(&last_instr_id) = block.instructions.last() {
-                    if let Some(instr) = func.instructions.get(&last_instr_id) {
-                        match instr {
-                            Instruction::Branch { target } => {
-                                if !body.contains(target) {
-                                    exits.insert(*target);
-                                }
-                            },
-                            Instruction::ConditionalBranch { true_target, false_target, .. } => {
-                                if !body.contains(true_target) {
-                                    exits.insert(*true_target);
-                                }
-                                if !body.contains(false_target) {
-                                    exits.insert(*false_target);
-                                }
-                            },
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
-        
-        LoopInfo {
-            header,
-            body,
-            preheader,
-            exits,
-        }
-    }
-    
     /// ループの不変命令を識別
-    fn identify_loop_invariants(&self, func: &Function, loop_info: &LoopInfo) -> HashSet<InstructionId> {
+    fn identify_loop_invariants(&self, func: &Function, loop_info: &Loop) -> HashSet<InstructionId> {
         let mut invariants = HashSet::new();
         let mut changed = true;
         
@@ -1024,6 +1105,12 @@ impl Optimizer {
             }
         }
         
+        // ループ内で状態が書き換わる場合、純粋関数の呼び出しでも結果が変わりうる
+        let loop_writes_state = all_instrs
+            .iter()
+            .filter_map(|instr_id| func.instructions.get(instr_id))
+            .any(|instr| self.writes_state(instr));
+        
         // 不変命令を繰り返し見つけるまで探索
         while changed {
             changed = false;
@@ -1051,7 +1138,12 @@ impl Optimizer {
                             });
                             
                             // 副作用のない命令でかつすべての依存が不変の場合
-                            if all_deps_invariant && !self.has_side_effects(instr) {
+                            let is_movable = if self.is_pure_call(instr) {
+                                !loop_writes_state
+                            } else {
+                                !self.has_side_effects(instr)
+                            };
+                            if all_deps_invariant && is_movable {
                                 invariants.insert(instr_id);
                                 changed = true;
                             }
@@ -1102,7 +1194,7 @@ impl Optimizer {
     }
     
     /// 不変命令をループプリヘッダに移動
-    fn move_invariants_to_preheader(&self, func: &mut Function, loop_info: &LoopInfo, invariants: &HashSet<InstructionId>) {
+    fn move_invariants_to_preheader(&self, func: &mut Function, loop_info: &Loop, invariants: &HashSet<InstructionId>) {
         // プリヘッダがない場合は作成
         let preheader_id = match loop_info.preheader {
             Some(id) => id,
@@ -1144,38 +1236,49 @@ impl Optimizer {
         let mut visited = HashSet::new();
         let mut temp_visited = HashSet::new();
         
-        // 深さ優先探索でトポロジカルソートを実行
+        // 深さ優先探索でトポロジカルソートを実行（依存の連鎖が長くてもスタックがあふれないよう、
+        // 探索中の命令と残りの依存先を明示的なスタックに積む）
         fn visit(
-            node: InstructionId,
+            root: InstructionId,
             dependency_graph: &HashMap<InstructionId, HashSet<InstructionId>>,
             visited: &mut HashSet<InstructionId>,
             temp_visited: &mut HashSet<InstructionId>,
             sorted: &mut Vec<InstructionId>,
         ) -> bool {
-            if temp_visited.contains(&node) {
-                // 循環依存を検出
-                return false;
-            }
-            
-            if visited.contains(&node) {
+            if visited.contains(&root) {
                 return true;
             }
             
-            temp_visited.insert(node);
+            let deps_of = |node: InstructionId| -> Vec<InstructionId> {
+                dependency_graph.get(&node).map(|deps| deps.iter().copied().collect()).unwrap_or_default()
+            };
             
-            if let Some(deps) = dependency_graph.get(&node) {
-                for &dep in deps {
-                    if !visit(dep, dependency_graph, visited, temp_visited, sorted) {
+            let mut stack = Vec::new();
+            let mut next = Some(root);
+            loop {
+                if let Some(node) = next.take() {
+                    if !temp_visited.insert(node) {
+                        // 循環依存を検出
                         return false;
                     }
+                    stack.push((node, deps_of(node)));
+                }
+                
+                let Some((node, deps)) = stack.last_mut() else {
+                    return true;
+                };
+                match deps.pop() {
+                    Some(dep) if visited.contains(&dep) => {}
+                    Some(dep) => next = Some(dep),
+                    None => {
+                        let node = *node;
+                        stack.pop();
+                        temp_visited.remove(&node);
+                        visited.insert(node);
+                        sorted.push(node);
+                    }
                 }
             }
-            
-            temp_visited.remove(&node);
-            visited.insert(node);
-            sorted.push(node);
-            
-            true
         }
         
         // 全ての不変命令をトポロジカルソート
@@ -1231,8 +1334,9 @@ impl Optimizer {
             let allocations = self.identify_allocations(func);
             
             // 各アロケーションをレジスタに昇格できるか分析
+            let cfg = self.analyses.cfg(func);
             for alloc_id in allocations {
-                self.promote_allocation_to_register(func, alloc_id);
+                self.promote_allocation_to_register(func, &cfg, alloc_id);
             }
         }
         
@@ -1256,7 +1360,7 @@ impl Optimizer {
     }
     
     /// アロケーションをレジスタに昇格
-    fn promote_allocation_to_register(&self, func: &mut Function, alloc_id: InstructionId) {
+    fn promote_allocation_to_register(&self, func: &mut Function, cfg: &Cfg, alloc_id: InstructionId) {
         // アロケーション命令を取得
         let alloc_instr = match func.instructions.get(&alloc_id) {
             Some(Instruction::Alloca { ty, result }) => (ty.clone(), result.clone()),
@@ -1301,7 +1405,6 @@ impl Optimizer {
             let mut value_at_block = HashMap::new();
             
             // 制御フローグラフを走査して変数の状態を追跡
-            let cfg = self.build_cfg(func);
             let mut visited = HashSet::new();
             let mut worklist = Vec::new();
             
@@ -1335,11 +1438,9 @@ impl Optimizer {
                 }
                 
                 // 後続ブロックに現在の値を伝播
-                if let Some(successors) = cfg.get(&block_id) {
-                    for &succ in successors {
-                        value_at_block.insert(succ, current_value.clone());
-                        worklist.push(succ);
-                    }
+                for &succ in cfg.successors(block_id) {
+                    value_at_block.insert(succ, current_value.clone());
+                    worklist.push(succ);
                 }
             }
             
@@ -1403,35 +1504,6 @@ impl Optimizer {
         false
     }
     
-    /// 制御フローグラフを構築
-    fn build_cfg(&self, func: &Function) -> HashMap<BlockId, Vec<BlockId>> {
-        let mut cfg = HashMap::new();
-        
-        for (block_id, block) in &func.blocks {
-            let mut successors = Vec::new();
-            
-            // ブロックの最後の命令を取得
-            if let Some(&last_instr_id) = block.instructions.last() {
-                if let Some(instr) = func.instructions.get(&last_instr_id) {
-                    match instr {
-                        Instruction::Branch { target } => {
-                            successors.push(*target);
-                        },
-                        Instruction::ConditionalBranch { true_target, false_target, .. } => {
-                            successors.push(*true_target);
-                            successors.push(*false_target);
-                        },
-                        _ => {}
-                    }
-                }
-            }
-            
-            cfg.insert(*block_id, successors);
-        }
-        
-        cfg
-    }
-    
     /// 命令の組み合わせ
     fn run_instruction_combining(&mut self, module: &mut Module) -> Result<()> {
         debug!("命令組み合わせ最適化を実行");
@@ -1720,63 +1792,22 @@ impl Optimizer {
     }
     
     /// 到達不能コードの削除
-    fn remove_unreachable_code(&self, func: &mut Function) {
+    fn remove_unreachable_code(&mut self, func: &mut Function) {
         // エントリポイントから到達可能なブロックを特定
-        let reachable = self.find_reachable_blocks(func);
-        
-        // 到達不能なブロックを削除
-        let all_blocks: HashSet<BlockId> = func.blocks.keys().cloned().collect();
-        let unreachable: Vec<BlockId> = all_blocks.difference(&reachable).cloned().collect();
-        
-        for block_id in unreachable {
-            debug!("到達不能ブロック {:?} を削除", block_id);
-            func.blocks.remove(&block_id);
-        }
-    }
-    
-    /// 到達可能なブロックを特定
-    fn find_reachable_blocks(&self, func: &Function) -> HashSet<BlockId> {
-        let mut reachable = HashSet::new();
-        let mut worklist = Vec::new();
-        
-        // エントリブロックをワークリストに追加
-        if let Some(entry) = func.entry_block {
-            worklist.push(entry);
-            reachable.insert(entry);
+        let cfg = self.analyses.cfg(func);
+        if cfg.post_order().len() == func.blocks.len() {
+            return;
         }
         
-        // 到達可能なブロックを幅優先探索
-        while let Some(block_id) = worklist.pop() {
-            if let Some(block) = func.blocks.get(&block_id) {
-                // ブロックの最後の命令を取得
-                if let Some(&last_instr_id) = block.instructions.last() {
-                    if let Some(instr) = func.instructions.get(&last_instr_id) {
-                        // 分岐先をワークリストに追加
-                        match instr {
-                            Instruction::Branch { target } => {
-                                if !reachable.contains(target) {
-                                    reachable.insert(*target);
-                                    worklist.push(*target);
-                                }
-                            },
-                            Instruction::ConditionalBranch { true_target, false_target, .. } => {
-                                if !reachable.contains(true_target) {
-                                    reachable.insert(*true_target);
-                                    worklist.push(*true_target);
-                                }
-                                if !reachable.contains(false_target) {
-                                    reachable.insert(*false_target);
-                                    worklist.push(*false_target);
-                                }
-                            },
-                            _ => {}
-                        }
-                    }
-                }
+        // 到達不能なブロックを削除
+        func.blocks.retain(|block_id, _| {
+            let reachable = cfg.is_reachable(*block_id);
+            if !reachable {
+                debug!("到達不能ブロック {:?} を削除", block_id);
             }
-        }
-        
-        reachable
+            reachable
+        });
+        self.analyses.invalidate(func.id);
     }
     
     /// 分岐の単純化
@@ -1989,7 +2020,8 @@ impl Optimizer {
         
         for (_, func) in module.functions.iter_mut() {
             // 変数の生存区間を分析
-            let liveness = self.analyze_liveness(func);
+            let dominators = self.analyses.dominators(func);
+            let liveness = self.analyze_liveness(func, &dominators);
             
             // 干渉グラフを構築
             let interference_graph = self.build_interference_graph(func, &liveness);
@@ -2005,16 +2037,13 @@ impl Optimizer {
     }
     
     /// 変数の生存区間分析
-    fn analyze_liveness(&self, func: &Function) -> HashMap<String, Vec<(BlockId, usize)>> {
+    fn analyze_liveness(&self, func: &Function, dominators: &DominatorTree) -> HashMap<String, Vec<(BlockId, usize)>> {
         let mut liveness = HashMap::new();
         
         // 各変数の定義点と使用点を記録
         let mut def_points = HashMap::new();
         let mut use_points = HashMap::new();
         
-        // 関数の制御フローグラフを構築
-        let cfg = self.build_cfg(func);
-        
         // 各ブロックごとの定義変数と使用変数を収集
         for (block_id, block) in &func.blocks {
             for (instr_idx, &instr_id) in block.instructions.iter().enumerate() {
@@ -2052,8 +2081,8 @@ impl Optimizer {
                 }
                 
                 // 支配下にあるブロックの使用点を調査
-                let dominated_blocks = self.find_dominated_blocks(func, def_block);
-                for dominated_block in dominated_blocks {
+                let dominated_blocks = dominators.dominated_blocks(def_block);
+                for dominated_block in dominated_blocks.into_iter().filter(|block| *block != def_block) {
                     for &(use_block, use_idx) in &uses {
                         if dominated_block == use_block {
                             live_ranges.push((use_block, use_idx));
@@ -2152,32 +2181,6 @@ impl Optimizer {
         used_vars
     }
     
-    /// あるブロックが支配するブロックを見つける
-    fn find_dominated_blocks(&self, func: &Function, dominator: BlockId) -> Vec<BlockId> {
-        let mut dominated = Vec::new();
-        let cfg = self.build_cfg(func);
-        
-        // 単純な深さ優先探索
-        let mut visited = HashSet::new();
-        let mut stack = vec![dominator];
-        
-        while let Some(block_id) = stack.pop() {
-            if !visited.insert(block_id) {
-                continue;
-            }
-            
-            if block_id != dominator {
-                dominated.push(block_id);
-            }
-            
-            if let Some(successors) = cfg.get(&block_id) {
-                stack.extend(successors);
-            }
-        }
-        
-        dominated
-    }
-    
     /// 干渉グラフを構築
     fn build_interference_graph(&self, func: &Function, liveness: &HashMap<String, Vec<(BlockId, usize)>>) -> HashMap<String, HashSet<String>> {
         let mut interference_graph = HashMap::new();
@@ -2489,24 +2492,25 @@ impl Optimizer {
         debug!("ループアンロール最適化を実行");
         
         for (func_id, func) in module.functions.iter_mut() {
-            // ループを検出
-            let loops = self.detect_loops(func);
+            // ループを検出（展開でブロックが増えるので、内側のループだけを対象にする）
+            let loops = self.analyses.loops(func);
             
             // 各ループについてアンロールを検討
-            for loop_info in loops {
+            for loop_info in loops.loops().iter().filter(|loop_info| loop_info.children.is_empty()) {
                 // アンロール可能か評価
-                if self.can_unroll_loop(func, &loop_info) {
+                if self.can_unroll_loop(func, loop_info) {
                     debug!("ループ（ヘッダー: {:?}）をアンロール", loop_info.header);
-                    self.unroll_loop(func, &loop_info, self.options.unroll_factor)?;
+                    self.unroll_loop(func, loop_info, self.options.unroll_factor)?;
                 }
             }
+            self.analyses.invalidate(func.id);
         }
         
         Ok(())
     }
     
     /// ループがアンロール可能かどうか判定
-    fn can_unroll_loop(&self, func: &Function, loop_info: &LoopInfo) -> bool {
+    fn can_unroll_loop(&self, func: &Function, loop_info: &Loop) -> bool {
         // ループの複雑さを評価
         let loop_size = loop_info.body.len();
         
@@ -2556,7 +2560,7 @@ impl Optimizer {
     }
     
     /// ループの反復回数を分析
-    fn analyze_loop_trip_count(&self, func: &Function, loop_info: &LoopInfo) -> Option<usize> {
+    fn analyze_loop_trip_count(&self, func: &Function, loop_info: &Loop) -> Option<usize> {
         // ループ内のブロックを取得
         let header_block = func.blocks.get(&loop_info.header)?;
         
@@ -2603,7 +2607,7 @@ impl Optimizer {
     }
     
     /// インダクション変数の増分を特定
-    fn find_induction_variable_increment(&self, func: &Function, loop_info: &LoopInfo, var_name: &str) -> Option<usize> {
+    fn find_induction_variable_increment(&self, func: &Function, loop_info: &Loop, var_name: &str) -> Option<usize> {
         // ループ内でのインダクション変数の更新を探す
         for block_id in &loop_info.body {
             if let Some(block) = func.blocks.get(block_id) {
@@ -2630,7 +2634,7 @@ impl Optimizer {
     }
     
     /// ループをアンロール
-    fn unroll_loop(&mut self, func: &mut Function, loop_info: &LoopInfo, factor: usize) -> Result<()> {
+    fn unroll_loop(&mut self, func: &mut Function, loop_info: &Loop, factor: usize) -> Result<()> {
         if factor <= 1 {
             return Ok(());  // アンロール不要
         }
@@ -2758,7 +2762,7 @@ impl Optimizer {
     }
     
     /// ループの終了条件を特定
-    fn find_loop_exit_condition(&self, func: &Function, loop_info: &LoopInfo) -> Result<(InstructionId, Operand)> {
+    fn find_loop_exit_condition(&self, func: &Function, loop_info: &Loop) -> Result<(InstructionId, Operand)> {
         // ループヘッダーブロックを取得
         let header_block = match func.blocks.get(&loop_info.header) {
             Some(block) => block,
@@ -2789,15 +2793,16 @@ impl Optimizer {
         
         for (_func_id, func) in module.functions.iter_mut() {
             // ベクトル化可能なループを特定
-            let loops = self.detect_loops(func);
+            let loops = self.analyses.loops(func);
             
-            for loop_info in loops {
+            for loop_info in loops.innermost_first() {
                 // ベクトル化可能か評価
-                if self.can_vectorize_loop(func, &loop_info) {
+                if self.can_vectorize_loop(func, loop_info) {
                     debug!("ループ（ヘッダー: {:?}）をベクトル化", loop_info.header);
-                    self.vectorize_loop(func, &loop_info)?;
+                    self.vectorize_loop(func, loop_info)?;
                 }
             }
+            self.analyses.invalidate(func.id);
             
             // ベクトル命令のパターンを特定
             self.identify_and_convert_vector_patterns(func)?;
@@ -2807,7 +2812,7 @@ impl Optimizer {
     }
     
     /// ループがベクトル化可能かどうか判定
-    fn can_vectorize_loop(&self, func: &Function, loop_info: &LoopInfo) -> bool {
+    fn can_vectorize_loop(&self, func: &Function, loop_info: &Loop) -> bool {
         // 依存関係がないかチェック
         let mut memory_accesses = HashMap::new();
         let mut has_cross_iteration_dependency = false;
@@ -2857,7 +2862,7 @@ impl Optimizer {
     }
     
     /// 連続したメモリアクセスパターンを持つか検証
-    fn has_continuous_memory_access(&self, func: &Function, loop_info: &LoopInfo, induction_var: &str) -> bool {
+    fn has_continuous_memory_access(&self, func: &Function, loop_info: &Loop, induction_var: &str) -> bool {
         // 連続したメモリアクセス（配列アクセス）を検出
         for &block_id in &loop_info.body {
             if let Some(block) = func.blocks.get(&block_id) {
@@ -2884,7 +2889,7 @@ impl Optimizer {
     }
     
     /// ループのインダクション変数を特定
-    fn find_loop_induction_variable(&self, func: &Function, loop_info: &LoopInfo) -> Option<&str> {
+    fn find_loop_induction_variable(&self, func: &Function, loop_info: &Loop) -> Option<&str> {
         // ループヘッダーの条件分岐を検索
         if let Some(header_block) = func.blocks.get(&loop_info.header) {
             for &instr_id in &header_block.instructions {
@@ -2912,7 +2917,7 @@ impl Optimizer {
     }
     
     /// ループをベクトル化
-    fn vectorize_loop(&mut self, func: &mut Function, loop_info: &LoopInfo) -> Result<()> {
+    fn vectorize_loop(&mut self, func: &mut Function, loop_info: &Loop) -> Result<()> {
         let induction_var = match self.find_loop_induction_variable(func, loop_info) {
             Some(var) => var,
             None => return Err(EidosError::Optimization("ベクトル化可能なインダクション変数が見つかりません".to_string())),
@@ -3295,26 +3300,6 @@ impl Optimizer {
         }
     }
 
-    /// ループのバックエッジを取得
-    fn get_back_edges(&self, func: &Function) -> Vec<BlockId> {
-        let mut back_edges = Vec::new();
-
-        for &block_id in &self.body {
-            if block_id == self.header {
-                continue;
-            }
-
-            if let Some(block) = func.blocks.get(&block_id) {
-                // ブロックの後継がヘッダーブロックを含むか確認
-                if block.successors.contains(&self.header) {
-                    back_edges.push(block_id);
-                }
-            }
-        }
-
-        back_edges
-    }
-
     /// ループが単純なカウントループかどうかを判定
     pub fn is_simple_counting_loop(&self, func: &Function) -> bool {
         // 単純なカウントループの条件:
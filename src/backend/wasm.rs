@@ -0,0 +1,2773 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, info};
+use wasm_encoder::{
+    BlockType, CodeSection, ConstExpr, DataSection, ElementSection, Elements, EntityType, ExportKind, ExportSection,
+    Function as WasmFunction, FunctionSection, GlobalSection, GlobalType, ImportSection, Instruction as Wasm, MemArg,
+    MemorySection, MemoryType, Module as WasmModule, RefType, TableSection, TableType, TypeSection, ValType,
+};
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::sync::WasiCtxBuilder;
+use wasmtime_wasi::WasiCtx;
+
+use crate::core::{EidosError, Result};
+use crate::core::eir::{
+    self, AsmDialect, AsmPiece, AtomicOp, BasicBlock, BinaryOp, BlockId, Function, GlobalAttributes, Instruction, Literal,
+    Operand, RegisterId, Terminator, UnaryOp,
+};
+use crate::core::types::{NumericType, Type, TypeId, TypeKind};
+
+use super::codegen::{self, Backend, CodegenOptions};
+
+/// 信頼できないプログラムを実行するときの制限
+#[derive(Debug, Clone, Default)]
+pub struct RunLimits {
+    /// 線形メモリの上限（バイト数）
+    pub max_memory: Option<usize>,
+    /// 実行時間の上限
+    pub timeout: Option<Duration>,
+    /// 標準入力・環境変数・ファイルシステムへのアクセスを与えない（標準出力と標準エラーは使える）
+    pub no_io: bool,
+}
+
+/// ストアに持たせる状態
+struct RunState {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
+/// WebAssemblyモジュールを wasmtime で実行するランタイム
+///
+/// 実行時間の上限はエポックによる割り込みで、メモリの上限はストアのリミッタで課すので、
+/// 上限を超えたプログラムはトラップとして止まり、ホストのプロセスは影響を受けない。
+pub struct WasmRuntime {
+    engine: Engine,
+    limits: RunLimits,
+    args: Vec<String>,
+}
+
+impl WasmRuntime {
+    /// 制限なしのランタイムを作成
+    pub fn new() -> Result<Self> {
+        Self::with_limits(RunLimits::default())
+    }
+
+    /// 制限付きのランタイムを作成
+    pub fn with_limits(limits: RunLimits) -> Result<Self> {
+        let mut config = Config::new();
+        config.epoch_interruption(limits.timeout.is_some());
+        // アトミック命令（スレッド提案）を使うモジュールも読み込めるようにする
+        config.wasm_threads(true);
+        let engine = Engine::new(&config).map_err(|e| runtime_error("実行環境を初期化できません", e))?;
+        Ok(Self { engine, limits, args: Vec::new() })
+    }
+
+    /// プログラムに渡す引数（先頭はプログラム名）
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// モジュールの `_start`（なければ `main`）を実行し、プロセスの終了コードを返す
+    ///
+    /// エントリー関数が整数を返せばその値を、`proc_exit` で終了すれば渡された値を終了コードにする。
+    pub fn run_module(&mut self, bytes: &[u8]) -> Result<i32> {
+        let module = Module::new(&self.engine, bytes).map_err(|e| runtime_error("モジュールを読み込めません", e))?;
+        let mut linker = Linker::new(&self.engine);
+        wasmtime_wasi::add_to_linker(&mut linker, |state: &mut RunState| &mut state.wasi)
+            .map_err(|e| runtime_error("WASIを登録できません", e))?;
+        add_host_functions(&mut linker)?;
+
+        let mut wasi = WasiCtxBuilder::new();
+        wasi.inherit_stdout().inherit_stderr();
+        wasi.args(&self.args).map_err(|e| runtime_error("引数を渡せません", e))?;
+        if !self.limits.no_io {
+            wasi.inherit_stdin();
+            wasi.inherit_env().map_err(|e| runtime_error("環境変数を渡せません", e))?;
+            let dir = wasmtime_wasi::sync::Dir::open_ambient_dir(".", wasmtime_wasi::sync::ambient_authority())?;
+            wasi.preopened_dir(dir, ".").map_err(|e| runtime_error("ディレクトリを渡せません", e))?;
+        }
+        // 上限を超える `memory.grow` は -1 を返すのではなくトラップにし、上限に達したことを知らせる
+        let mut limits = StoreLimitsBuilder::new().trap_on_grow_failure(true);
+        if let Some(max_memory) = self.limits.max_memory {
+            limits = limits.memory_size(max_memory);
+        }
+        let state = RunState { wasi: wasi.build(), limits: limits.build() };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+
+        // 上限の時間が過ぎたらエポックを進め、実行中のコードを割り込ませる
+        if let Some(timeout) = self.limits.timeout {
+            store.set_epoch_deadline(1);
+            let engine = self.engine.clone();
+            thread::spawn(move || {
+                thread::sleep(timeout);
+                engine.increment_epoch();
+            });
+        }
+
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .map_err(|e| self.trap_error("モジュールを初期化できません", e))?;
+        let entry = ["_start", "main"]
+            .iter()
+            .find_map(|name| instance.get_func(&mut store, name))
+            .ok_or_else(|| EidosError::Runtime("エントリポイント（_start または main）がありません".to_string()))?;
+        debug!("エントリポイントを実行中");
+        let result = if let Ok(entry) = entry.typed::<(), ()>(&store) {
+            entry.call(&mut store, ()).map(|()| 0)
+        } else if let Ok(entry) = entry.typed::<(), i32>(&store) {
+            entry.call(&mut store, ())
+        } else if let Ok(entry) = entry.typed::<(), i64>(&store) {
+            // 終了コードはOSが扱える範囲（下位のビット）に切り詰める
+            entry.call(&mut store, ()).map(|code| code as i32)
+        } else {
+            return Err(EidosError::Runtime(
+                "エントリポイントは引数を取らず、何も返さないか整数を返す関数でなければなりません".to_string(),
+            ));
+        };
+        match result {
+            Ok(code) => Ok(code),
+            // `proc_exit` による終了は、渡された値を終了コードにする
+            Err(e) => match e.downcast_ref::<wasmtime_wasi::I32Exit>() {
+                Some(exit) => Ok(exit.0),
+                None => Err(self.trap_error("実行中にトラップが発生しました", e)),
+            },
+        }
+    }
+
+    /// 制限によるトラップは、どの制限を超えたかが分かるメッセージにする
+    fn trap_error(&self, context: &str, error: anyhow::Error) -> EidosError {
+        match (error.downcast_ref::<Trap>(), &self.limits) {
+            (Some(Trap::Interrupt), RunLimits { timeout: Some(timeout), .. }) => {
+                EidosError::Runtime(format!("実行時間の上限（{}秒）を超えました", timeout.as_secs_f64()))
+            }
+            _ if error.to_string().contains("memory") && self.limits.max_memory.is_some() => EidosError::Runtime(format!(
+                "メモリの上限（{} バイト）を超えました: {}",
+                self.limits.max_memory.unwrap_or_default(),
+                error
+            )),
+            _ => runtime_error(context, error),
+        }
+    }
+}
+
+/// `WasmBackend` が生成したモジュールがインポートする出力関数（`HOST_FUNCTIONS`）を登録する
+fn add_host_functions(linker: &mut Linker<RunState>) -> Result<()> {
+    let error = |e: anyhow::Error| runtime_error("出力関数を登録できません", e);
+    linker.func_wrap(HOST_MODULE, "print_int", |value: i64| print!("{}", value)).map_err(error)?;
+    linker.func_wrap(HOST_MODULE, "print_u64", |value: i64| print!("{}", value as u64)).map_err(error)?;
+    linker.func_wrap(HOST_MODULE, "print_float", |value: f64| print!("{}", value)).map_err(error)?;
+    linker.func_wrap(HOST_MODULE, "print_bool", |value: i32| print!("{}", value != 0)).map_err(error)?;
+    linker
+        .func_wrap(HOST_MODULE, "print_char", |value: i32| {
+            print!("{}", char::from_u32(value as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+        })
+        .map_err(error)?;
+    linker
+        .func_wrap(HOST_MODULE, "print_string", |mut caller: Caller<'_, RunState>, ptr: i32| -> anyhow::Result<()> {
+            print!("{}", read_string(&mut caller, ptr)?);
+            Ok(())
+        })
+        .map_err(error)?;
+    linker.func_wrap(HOST_MODULE, "print_newline", || println!()).map_err(error)?;
+    // パニックはネイティブのプログラムと同じく、メッセージを標準エラー出力に書いて終了コード 101 で終わる
+    linker
+        .func_wrap(HOST_MODULE, "panic", |mut caller: Caller<'_, RunState>, ptr: i32| -> anyhow::Result<()> {
+            let message = read_string(&mut caller, ptr)?;
+            let _ = std::io::stdout().flush();
+            eprintln!("eidos: panic: {}", message);
+            Err(wasmtime_wasi::I32Exit(101).into())
+        })
+        .map_err(error)?;
+    Ok(())
+}
+
+/// 線形メモリの `ptr` にある文字列（先頭4バイトのバイト数に続くUTF-8の内容）を読む
+fn read_string(caller: &mut Caller<'_, RunState>, ptr: i32) -> anyhow::Result<String> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| anyhow::anyhow!("モジュールが memory をエクスポートしていません"))?;
+    let data = memory.data(&caller);
+    let out_of_bounds = || anyhow::anyhow!("文字列 {:#x} が線形メモリの外を指しています", ptr as u32);
+    let start = ptr as u32 as usize;
+    let header: [u8; 4] = data
+        .get(start..start + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(out_of_bounds)?;
+    let end = (start + 4).checked_add(u32::from_le_bytes(header) as usize).ok_or_else(out_of_bounds)?;
+    let bytes = data.get(start + 4..end).ok_or_else(out_of_bounds)?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn runtime_error(context: &str, error: impl std::fmt::Display) -> EidosError {
+    EidosError::Runtime(format!("{}: {}", context, error))
+}
+
+/// 生成したモジュールが実行環境からインポートする関数のモジュール名
+pub const HOST_MODULE: &str = "eidos";
+
+/// 実行環境が `HOST_MODULE` で与える関数（名前と引数の型）
+///
+/// 文字列は線形メモリ上のポインタで渡す。ポインタの先の4バイトはリトルエンディアンのバイト数で、UTF-8の内容が続く。
+/// `panic` は実行を打ち切り、呼び出し元に戻らない。
+pub const HOST_FUNCTIONS: &[(&str, &[ValType])] = &[
+    ("print_int", &[ValType::I64]),
+    ("print_u64", &[ValType::I64]),
+    ("print_float", &[ValType::F64]),
+    ("print_bool", &[ValType::I32]),
+    ("print_char", &[ValType::I32]),
+    ("print_string", &[ValType::I32]),
+    ("print_newline", &[]),
+    ("panic", &[ValType::I32]),
+];
+
+/// シャドウスタックの底（0番地はヌルポインタと区別するため使わない）
+const STACK_BOTTOM: u32 = 8;
+
+/// 配列・タプル・スライスの領域を置くシャドウスタックの大きさ（バイト数）
+const STACK_SIZE: u32 = 64 * 1024;
+
+/// 文字列リテラルを置く線形メモリの先頭（シャドウスタックはその手前から0番地の方へ伸びる）
+const DATA_START: u32 = STACK_BOTTOM + STACK_SIZE;
+
+/// 線形メモリのページの大きさ
+const PAGE_SIZE: u64 = 65536;
+
+/// 値のWebAssembly上での表現
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WasmType {
+    /// 値を持たない
+    Unit,
+    /// `i32`（0か1）
+    Bool,
+    /// `i64`
+    Int,
+    /// `f64`
+    Float,
+    /// `i32`（Unicodeのコードポイント）
+    Char,
+    /// `i32`（線形メモリ上の文字列へのポインタ）
+    String,
+    /// ビット幅を指定した数値型（32ビット以下の整数型は `i32` に符号拡張・ゼロ拡張して持つ）
+    Sized(NumericType),
+    /// `i32`（線形メモリ上の配列・タプル・構造体・スライスの領域のアドレス）
+    Pointer,
+    /// `i32`（関数表の添字）
+    Function,
+}
+
+impl WasmType {
+    /// 型の表現（WebAssemblyの値で表せない型はNone）
+    fn from_type(ty: &Type) -> Option<Self> {
+        match &ty.kind {
+            TypeKind::Unit => Some(WasmType::Unit),
+            TypeKind::Bool => Some(WasmType::Bool),
+            TypeKind::Int => Some(WasmType::Int),
+            TypeKind::Float => Some(WasmType::Float),
+            TypeKind::Char => Some(WasmType::Char),
+            TypeKind::String => Some(WasmType::String),
+            TypeKind::Numeric(numeric) => Some(WasmType::Sized(*numeric)),
+            TypeKind::FixedArray { .. } | TypeKind::Slice(_) | TypeKind::Tuple(_) | TypeKind::Struct { .. } => {
+                Some(WasmType::Pointer)
+            }
+            TypeKind::Function { .. } => Some(WasmType::Function),
+            _ => None,
+        }
+    }
+    
+    /// リテラルの型
+    fn of_literal(literal: &Literal) -> Self {
+        match literal {
+            Literal::Int(_) => WasmType::Int,
+            Literal::Float(_) => WasmType::Float,
+            Literal::Bool(_) => WasmType::Bool,
+            Literal::Char(_) => WasmType::Char,
+            Literal::String(_) => WasmType::String,
+            Literal::Unit => WasmType::Unit,
+        }
+    }
+    
+    /// WebAssemblyの値の型（`Unit` はNone）
+    fn val_type(&self) -> Option<ValType> {
+        match self {
+            WasmType::Unit => None,
+            WasmType::Int | WasmType::Sized(NumericType::I64 | NumericType::U64) => Some(ValType::I64),
+            WasmType::Float | WasmType::Sized(NumericType::F64) => Some(ValType::F64),
+            WasmType::Sized(NumericType::F32) => Some(ValType::F32),
+            _ => Some(ValType::I32),
+        }
+    }
+    
+    fn is_integer(&self) -> bool {
+        match self {
+            WasmType::Int => true,
+            WasmType::Sized(numeric) => !numeric.is_float(),
+            _ => false,
+        }
+    }
+    
+    /// 符号付きとして比較・除算・拡張するか
+    fn is_signed(&self) -> bool {
+        match self {
+            WasmType::Int | WasmType::Float => true,
+            WasmType::Sized(numeric) => numeric.is_signed() || numeric.is_float(),
+            _ => false,
+        }
+    }
+    
+    /// エラーメッセージに出す型の名前
+    fn name(&self) -> &'static str {
+        match self {
+            WasmType::Unit => "()",
+            WasmType::Bool => "Bool",
+            WasmType::Int => "Int",
+            WasmType::Float => "Float",
+            WasmType::Char => "Char",
+            WasmType::String => "String",
+            WasmType::Sized(numeric) => numeric.name(),
+            WasmType::Pointer => "ポインタ",
+            WasmType::Function => "関数",
+        }
+    }
+    
+    /// 線形メモリから値を読み出す命令（アドレスはスタックに積んである。`Unit` はNone）
+    fn load(&self, offset: u32) -> Option<Wasm<'static>> {
+        let memarg = |align| MemArg { offset: offset as u64, align, memory_index: 0 };
+        Some(match self {
+            WasmType::Unit => return None,
+            WasmType::Bool | WasmType::Sized(NumericType::U8) => Wasm::I32Load8U(memarg(0)),
+            WasmType::Sized(NumericType::I8) => Wasm::I32Load8S(memarg(0)),
+            WasmType::Sized(NumericType::I16) => Wasm::I32Load16S(memarg(1)),
+            WasmType::Sized(NumericType::U16) => Wasm::I32Load16U(memarg(1)),
+            WasmType::Int | WasmType::Sized(NumericType::I64 | NumericType::U64) => Wasm::I64Load(memarg(3)),
+            WasmType::Float | WasmType::Sized(NumericType::F64) => Wasm::F64Load(memarg(3)),
+            WasmType::Sized(NumericType::F32) => Wasm::F32Load(memarg(2)),
+            _ => Wasm::I32Load(memarg(2)),
+        })
+    }
+    
+    /// 線形メモリに値を書き込む命令（アドレスと値はスタックに積んである。`Unit` はNone）
+    fn store(&self, offset: u32) -> Option<Wasm<'static>> {
+        let memarg = |align| MemArg { offset: offset as u64, align, memory_index: 0 };
+        Some(match self {
+            WasmType::Unit => return None,
+            WasmType::Bool | WasmType::Sized(NumericType::I8 | NumericType::U8) => Wasm::I32Store8(memarg(0)),
+            WasmType::Sized(NumericType::I16 | NumericType::U16) => Wasm::I32Store16(memarg(1)),
+            WasmType::Int | WasmType::Sized(NumericType::I64 | NumericType::U64) => Wasm::I64Store(memarg(3)),
+            WasmType::Float | WasmType::Sized(NumericType::F64) => Wasm::F64Store(memarg(3)),
+            WasmType::Sized(NumericType::F32) => Wasm::F32Store(memarg(2)),
+            _ => Wasm::I32Store(memarg(2)),
+        })
+    }
+    
+    /// `i32` で持つ32ビット未満の整数型のビット幅
+    fn narrow_bits(&self) -> Option<u32> {
+        match self {
+            WasmType::Sized(numeric) if !numeric.is_float() && numeric.bits() < 32 => Some(numeric.bits()),
+            _ => None,
+        }
+    }
+}
+
+/// EIRをWebAssemblyのバイナリ形式のモジュールに変換するバックエンド
+///
+/// 関数の基本ブロックは、ブロックの番号を持つローカル変数と `br_table` で分岐する `loop` の中に並べる。
+/// アドレスを値として使わない `Alloca` のスロットはローカル変数で表す。配列・タプル・構造体・スライスは
+/// 線形メモリのシャドウスタック（スタックポインタはグローバル変数）に置いた領域のアドレスを値として渡し、
+/// タプルと構造体を返す関数は要素を複数の戻り値として返す。関数の値は関数表の添字で、`call_indirect` で呼び出す。
+/// 出力は `HOST_MODULE` の関数（`HOST_FUNCTIONS`）で行い、線形メモリを `memory`、エントリー関数を `main`
+/// としてエクスポートする。`Option` と非同期関数の中断にはまだ対応していない
+/// （非同期関数は呼び出した時点で最後まで実行する）。
+#[derive(Default)]
+pub struct WasmBackend {
+    /// `declare_function` で宣言された外部関数（`env` からインポートする）
+    functions: Vec<(String, Vec<Type>, Type)>,
+    /// `declare_global` で宣言されたグローバル変数（初期値のないものは `env` からインポートする）
+    globals: Vec<(String, Type, Option<Literal>, GlobalAttributes)>,
+}
+
+impl WasmBackend {
+    pub fn new() -> Self {
+        Self {
+            functions: Vec::new(),
+            globals: Vec::new(),
+        }
+    }
+    
+    /// EIRモジュールをWebAssemblyのモジュールに変換
+    pub fn emit(&self, module: &eir::Module) -> Result<Vec<u8>> {
+        info!("WebAssemblyを生成中: {}", module.name);
+        
+        let mut types = TypeTable::default();
+        let mut strings = StringTable::default();
+        let mut symbols = Symbols::default();
+        let mut imports = ImportSection::new();
+        
+        for (name, params) in HOST_FUNCTIONS {
+            let index = types.function(params.to_vec(), Vec::new());
+            imports.import(HOST_MODULE, name, EntityType::Function(index));
+            symbols.host.insert(name, symbols.function_count);
+            symbols.function_count += 1;
+        }
+        
+        // 外部関数は実行環境（`env`）からインポートする
+        let mut externals: Vec<(&str, Vec<WasmType>, Vec<WasmType>)> = Vec::new();
+        let mut names: Vec<&String> = module.external_functions.keys().collect();
+        names.sort();
+        for name in names {
+            let external = &module.external_functions[name];
+            if external.is_variadic {
+                return Err(EidosError::BackendError(format!(
+                    "可変長引数の外部関数 '{}' はWebAssemblyバックエンドでは呼び出せません",
+                    name
+                )));
+            }
+            let params = external.parameter_types.iter().map(|id| wasm_type(module, *id)).collect::<Result<Vec<_>>>()?;
+            externals.push((name, params, return_types(eir_type(module, external.return_type)?)?));
+        }
+        for (name, params, return_type) in &self.functions {
+            if module.get_function_by_name(name).is_some() || externals.iter().any(|(external, ..)| external == name) {
+                continue;
+            }
+            let params = params.iter().map(type_of).collect::<Result<Vec<_>>>()?;
+            externals.push((name, params, return_types(return_type)?));
+        }
+        for (name, params, returns) in externals {
+            let index = types.signature(&params, &returns);
+            imports.import("env", name, EntityType::Function(index));
+            symbols.functions.insert(name.to_string(), (symbols.function_count, params, returns));
+            symbols.function_count += 1;
+        }
+        
+        // アトミック操作の対象のグローバル変数は、アトミック命令で読み書きできるよう線形メモリに置く
+        let atomic_globals: HashSet<&str> = module
+            .functions
+            .values()
+            .flat_map(|function| function.blocks.values())
+            .flat_map(|block| &block.instructions)
+            .filter_map(|(_, instruction)| match instruction {
+                Instruction::Atomic { address: Operand::Global(name), .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        
+        // 初期値のない宣言だけのグローバル変数も `env` からインポートする
+        let mut defined_globals: Vec<(&str, WasmType, Option<&Literal>)> = Vec::new();
+        let mut global_names: Vec<&String> = module.globals.keys().collect();
+        global_names.sort();
+        for name in global_names {
+            let global = &module.globals[name];
+            defined_globals.push((name, global_type(eir_type(module, global.ty)?)?, global.initializer.as_ref()));
+        }
+        for (name, ty, initializer, attributes) in &self.globals {
+            if module.get_global(name).is_some() {
+                continue;
+            }
+            let ty = global_type(ty)?;
+            match (initializer, ty.val_type()) {
+                (Some(initializer), _) => defined_globals.push((name, ty, Some(initializer))),
+                (None, Some(val_type)) => {
+                    let mutable = !attributes.is_constant;
+                    imports.import("env", name, EntityType::Global(GlobalType { val_type, mutable }));
+                    symbols.globals.insert(name.clone(), (symbols.global_count, ty));
+                    symbols.global_count += 1;
+                }
+                (None, None) => {}
+            }
+        }
+        let mut globals = GlobalSection::new();
+        for (name, ty, initializer) in defined_globals {
+            if atomic_globals.contains(name) {
+                if !is_atomic(ty) {
+                    return Err(EidosError::BackendError(format!(
+                        "WebAssemblyバックエンドは {} 型のグローバル変数 '{}' へのアトミック操作に対応していません",
+                        ty.name(),
+                        name
+                    )));
+                }
+                let value = match initializer {
+                    Some(Literal::Int(value)) => *value,
+                    _ => 0,
+                };
+                symbols.memory_globals.insert(name.to_string(), (strings.cell(value), ty));
+                continue;
+            }
+            let Some(val_type) = ty.val_type() else {
+                continue;
+            };
+            let init = match initializer {
+                Some(literal) => const_expr(literal, ty, &mut strings),
+                None => zero(val_type),
+            };
+            globals.global(GlobalType { val_type, mutable: true }, &init);
+            symbols.globals.insert(name.to_string(), (symbols.global_count, ty));
+            symbols.global_count += 1;
+        }
+        // シャドウスタックのスタックポインタ（文字列リテラルの手前から0番地の方へ伸びる）
+        globals.global(GlobalType { val_type: ValType::I32, mutable: true }, &ConstExpr::i32_const(DATA_START as i32));
+        symbols.stack_pointer = symbols.global_count;
+        symbols.global_count += 1;
+        
+        let mut functions: Vec<&Function> = module.functions.values().collect();
+        functions.sort_by_key(|f| f.id.0);
+        let mut function_section = FunctionSection::new();
+        for function in &functions {
+            let params = function
+                .parameters
+                .iter()
+                .map(|(_, id)| wasm_type(module, *id))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| in_function(function, e))?;
+            let returns = eir_type(module, function.return_type)
+                .and_then(return_types)
+                .map_err(|e| in_function(function, e))?;
+            function_section.function(types.signature(&params, &returns));
+            symbols.table.insert(function.name.clone(), symbols.table.len() as u32);
+            symbols.functions.insert(function.name.clone(), (symbols.function_count, params, returns));
+            symbols.function_count += 1;
+        }
+        
+        // 関数の値（関数表の添字）から `call_indirect` で呼び出せるよう、モジュールの関数を関数表に並べる
+        let mut tables = TableSection::new();
+        let mut elements = ElementSection::new();
+        if !functions.is_empty() {
+            let count = functions.len() as u32;
+            tables.table(TableType { element_type: RefType::FUNCREF, minimum: count, maximum: Some(count) });
+            let indices: Vec<u32> = functions.iter().map(|function| symbols.functions[&function.name].0).collect();
+            elements.active(None, &ConstExpr::i32_const(0), Elements::Functions(&indices));
+        }
+        
+        let mut code = CodeSection::new();
+        for function in &functions {
+            debug!("WebAssemblyを生成中: 関数 {}", function.name);
+            let body = FunctionEmitter::new(module, function, &symbols, &mut types, &mut strings)
+                .emit()
+                .map_err(|e| in_function(function, e))?;
+            code.function(&body);
+        }
+        
+        let mut exports = ExportSection::new();
+        exports.export("memory", ExportKind::Memory, 0);
+        let entry = module.entry_point.and_then(|id| module.get_function(id)).map(|f| f.name.as_str());
+        for function in &functions {
+            let index = symbols.functions[&function.name].0;
+            if function.name != "memory" && (function.name != "main" || entry.is_none()) {
+                exports.export(&function.name, ExportKind::Func, index);
+            }
+        }
+        if let Some(entry) = entry {
+            exports.export("main", ExportKind::Func, symbols.functions[entry].0);
+        }
+        
+        let data_end = DATA_START as u64 + strings.data.len() as u64;
+        let mut memory = MemorySection::new();
+        memory.memory(MemoryType {
+            minimum: data_end.div_ceil(PAGE_SIZE).max(1),
+            maximum: None,
+            memory64: false,
+            shared: false,
+        });
+        let mut data = DataSection::new();
+        if !strings.data.is_empty() {
+            data.active(0, &ConstExpr::i32_const(DATA_START as i32), strings.data.iter().copied());
+        }
+        
+        let mut wasm = WasmModule::new();
+        wasm.section(&types.section)
+            .section(&imports)
+            .section(&function_section)
+            .section(&tables)
+            .section(&memory)
+            .section(&globals)
+            .section(&exports)
+            .section(&elements)
+            .section(&code)
+            .section(&data);
+        Ok(wasm.finish())
+    }
+}
+
+impl Backend for WasmBackend {
+    fn name(&self) -> &str {
+        "wasm"
+    }
+    
+    fn compile(&mut self, module: &eir::Module, _options: &CodegenOptions) -> Result<Vec<u8>> {
+        self.emit(module)
+    }
+    
+    fn declare_function(&mut self, name: &str, params: &[Type], return_type: &Type) -> Result<()> {
+        self.functions.push((name.to_string(), params.to_vec(), return_type.clone()));
+        Ok(())
+    }
+    
+    fn declare_global(&mut self, name: &str, ty: &Type, initializer: Option<&Literal>, attributes: &GlobalAttributes) -> Result<()> {
+        self.globals.push((name.to_string(), ty.clone(), initializer.cloned(), attributes.clone()));
+        Ok(())
+    }
+}
+
+/// 関数の型（引数と戻り値の型の組）ごとに1つ作る型セクション
+#[derive(Default)]
+struct TypeTable {
+    section: TypeSection,
+    indices: HashMap<(Vec<ValType>, Vec<ValType>), u32>,
+}
+
+impl TypeTable {
+    fn function(&mut self, params: Vec<ValType>, results: Vec<ValType>) -> u32 {
+        let next = self.indices.len() as u32;
+        *self.indices.entry((params, results)).or_insert_with_key(|(params, results)| {
+            self.section.function(params.iter().copied(), results.iter().copied());
+            next
+        })
+    }
+    
+    /// EIRの関数の引数と戻り値の型に対応する型（`Unit` の引数と戻り値は省く）
+    fn signature(&mut self, params: &[WasmType], returns: &[WasmType]) -> u32 {
+        let params = params.iter().filter_map(WasmType::val_type).collect();
+        self.function(params, returns.iter().filter_map(WasmType::val_type).collect())
+    }
+}
+
+/// 線形メモリに置く文字列リテラル（同じ内容は1つにまとめる）とグローバル変数
+#[derive(Default)]
+struct StringTable {
+    data: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl StringTable {
+    /// 文字列のアドレス（先頭4バイトにバイト数を置き、4バイト境界にそろえる）
+    fn intern(&mut self, value: &str) -> u32 {
+        if let Some(offset) = self.offsets.get(value) {
+            return *offset;
+        }
+        let offset = DATA_START + self.data.len() as u32;
+        self.data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(value.as_bytes());
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+        self.offsets.insert(value.to_string(), offset);
+        offset
+    }
+    
+    /// 線形メモリに置くグローバル変数のアドレス（8バイトの領域に初期値を書き、8バイト境界にそろえる）
+    fn cell(&mut self, value: i64) -> u32 {
+        self.data.resize(self.data.len().next_multiple_of(8), 0);
+        let offset = DATA_START + self.data.len() as u32;
+        self.data.extend_from_slice(&value.to_le_bytes());
+        offset
+    }
+}
+
+/// 関数とグローバル変数のインデックス
+#[derive(Default)]
+struct Symbols {
+    /// `HOST_FUNCTIONS` の関数
+    host: HashMap<&'static str, u32>,
+    /// 外部関数とモジュールの関数（インデックス、引数と戻り値の型）
+    functions: HashMap<String, (u32, Vec<WasmType>, Vec<WasmType>)>,
+    function_count: u32,
+    /// モジュールの関数の関数表での添字
+    table: HashMap<String, u32>,
+    /// グローバル変数（インデックスと型）
+    globals: HashMap<String, (u32, WasmType)>,
+    global_count: u32,
+    /// 線形メモリに置いたグローバル変数（アドレスと型）
+    memory_globals: HashMap<String, (u32, WasmType)>,
+    /// シャドウスタックのスタックポインタのグローバル変数
+    stack_pointer: u32,
+}
+
+/// 1つの関数の本体を生成する
+struct FunctionEmitter<'a> {
+    module: &'a eir::Module,
+    function: &'a Function,
+    symbols: &'a Symbols,
+    types: &'a mut TypeTable,
+    strings: &'a mut StringTable,
+    /// レジスタのローカル変数（`Unit` のレジスタはNone）と型
+    locals: HashMap<RegisterId, (Option<u32>, WasmType)>,
+    /// `Alloca` で確保したスロットのうち、値そのものをスロットのローカル変数に持つもの
+    slots: HashMap<RegisterId, WasmType>,
+    /// シャドウスタックに置いた `Alloca` のスロットと `GetElementPtr` で求めた要素（どちらも `i32` のアドレスを持つ）
+    addresses: HashSet<RegisterId>,
+    /// シャドウスタックに領域を確保するか
+    uses_frame: bool,
+    /// シャドウスタック上のこの関数の領域の先頭アドレスを持つローカル変数
+    frame_local: u32,
+    /// 関数に入ったときのスタックポインタを持つローカル変数（戻るときに書き戻す）
+    stack_local: u32,
+    /// シャドウスタック上のこの関数の領域の大きさ
+    frame_size: u32,
+    /// PHIノードのための、分岐元ブロックで行う代入
+    phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>>,
+    /// `br_table` で分岐するときのブロックの番号
+    block_indices: HashMap<BlockId, u32>,
+    /// 実行中のブロックの番号を持つローカル変数
+    block_local: u32,
+    /// 生成中のブロックから分岐の `loop` までの深さ
+    loop_depth: u32,
+    /// パラメータのローカル変数の数
+    param_locals: u32,
+    /// パラメータ以外のローカル変数の型
+    local_types: Vec<ValType>,
+    body: Vec<Wasm<'static>>,
+}
+
+impl<'a> FunctionEmitter<'a> {
+    fn new(
+        module: &'a eir::Module,
+        function: &'a Function,
+        symbols: &'a Symbols,
+        types: &'a mut TypeTable,
+        strings: &'a mut StringTable,
+    ) -> Self {
+        let mut phi_moves: HashMap<BlockId, Vec<(RegisterId, Operand)>> = HashMap::new();
+        let mut allocas = Vec::new();
+        let mut addresses = HashSet::new();
+        // 読み書きのアドレス以外に使われるレジスタ（スロットならアドレスを値として使っている）
+        let mut escaping = HashSet::new();
+        let is_tuple = |reg: &RegisterId| {
+            function
+                .get_register_type(*reg)
+                .and_then(|id| module.get_type(id))
+                .is_some_and(|ty| ty.field_types().is_some())
+        };
+        // タプルを作る・読み出す・受け取る命令と `MakeSlice` はシャドウスタックに領域を確保する
+        let mut uses_frame = false;
+        // 同じブロックへの移動の順序が実行ごとに変わらないよう、ブロックIDの順に集める
+        let mut blocks: Vec<&BasicBlock> = function.blocks.values().collect();
+        blocks.sort_by_key(|block| block.id.0);
+        for block in blocks {
+            for (_, instruction) in &block.instructions {
+                match instruction {
+                    Instruction::Phi { incoming, result } => {
+                        for (value, from) in incoming {
+                            phi_moves.entry(*from).or_default().push((*result, value.clone()));
+                        }
+                    }
+                    Instruction::Alloca { result, .. } => allocas.push(*result),
+                    Instruction::GetElementPtr { result, .. } => {
+                        addresses.insert(*result);
+                    }
+                    Instruction::Tuple { .. } | Instruction::MakeSlice { .. } => uses_frame = true,
+                    Instruction::Load { result, .. }
+                    | Instruction::Call { result: Some(result), .. }
+                    | Instruction::ExternalCall { result: Some(result), .. }
+                        if is_tuple(result) =>
+                    {
+                        uses_frame = true
+                    }
+                    _ => {}
+                }
+                let operands = match instruction {
+                    Instruction::Load { .. } => Vec::new(),
+                    Instruction::Store { value, .. } => vec![value],
+                    instruction => instruction.operands(),
+                };
+                escaping.extend(operands.into_iter().filter_map(|operand| match operand {
+                    Operand::Register(reg) => Some(*reg),
+                    _ => None,
+                }));
+            }
+            if let Some(terminator) = &block.terminator {
+                escaping.extend(terminator.operands().into_iter().filter_map(|operand| match operand {
+                    Operand::Register(reg) => Some(*reg),
+                    _ => None,
+                }));
+            }
+        }
+        
+        // 配列・タプルのスロットと、アドレスを値として使うスロットはシャドウスタックに置く
+        let mut slots = HashMap::new();
+        for reg in allocas {
+            let in_memory = function
+                .get_register_type(reg)
+                .and_then(|id| module.get_type(id))
+                .is_some_and(|ty| ty.layout().is_some() && WasmType::from_type(ty) == Some(WasmType::Pointer));
+            if in_memory || escaping.contains(&reg) {
+                addresses.insert(reg);
+                uses_frame = true;
+            } else {
+                slots.insert(reg, WasmType::Unit);
+            }
+        }
+        
+        Self {
+            module,
+            function,
+            symbols,
+            types,
+            strings,
+            locals: HashMap::new(),
+            slots,
+            addresses,
+            uses_frame,
+            frame_local: 0,
+            stack_local: 0,
+            frame_size: 0,
+            phi_moves,
+            block_indices: HashMap::new(),
+            block_local: 0,
+            loop_depth: 0,
+            param_locals: 0,
+            local_types: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+    
+    fn emit(mut self) -> Result<WasmFunction> {
+        // パラメータは add_parameter により先頭から順にレジスタが割り当てられている
+        for (index, (_, type_id)) in self.function.parameters.iter().enumerate() {
+            let ty = wasm_type(self.module, *type_id)?;
+            let local = ty.val_type().map(|_| {
+                self.param_locals += 1;
+                self.param_locals - 1
+            });
+            self.locals.insert(RegisterId(index as u32), (local, ty));
+        }
+        
+        let mut registers: Vec<(RegisterId, TypeId)> = self.function.register_types.iter().map(|(reg, ty)| (*reg, *ty)).collect();
+        for block in self.function.blocks.values() {
+            registers.extend(block.parameters.iter().copied());
+        }
+        registers.sort_by_key(|(reg, _)| reg.0);
+        registers.dedup_by_key(|(reg, _)| *reg);
+        for (reg, type_id) in registers {
+            if self.locals.contains_key(&reg) {
+                continue;
+            }
+            let ty = match self.module.get_type(type_id) {
+                Some(ty) if self.slots.contains_key(&reg) || self.addresses.contains(&reg) => match WasmType::from_type(ty) {
+                    Some(ty) => ty,
+                    None => return Err(unsupported(&format!("{} 型のスロット", ty))),
+                },
+                _ => wasm_type(self.module, type_id)?,
+            };
+            if let Some(slot) = self.slots.get_mut(&reg) {
+                *slot = ty;
+            }
+            // アドレスを持つレジスタの型は、読み書きする値の型にしておく
+            let local = if self.addresses.contains(&reg) {
+                Some(self.add_local(ValType::I32))
+            } else {
+                ty.val_type().map(|val_type| self.add_local(val_type))
+            };
+            self.locals.insert(reg, (local, ty));
+        }
+        self.block_local = self.add_local(ValType::I32);
+        if self.uses_frame {
+            self.frame_local = self.add_local(ValType::I32);
+            self.stack_local = self.add_local(ValType::I32);
+        }
+        
+        let mut block_ids: Vec<BlockId> = self.function.blocks.keys().copied().collect();
+        block_ids.sort_by_key(|id| (*id != self.function.entry_block, id.0));
+        self.block_indices = block_ids.iter().enumerate().map(|(index, id)| (*id, index as u32)).collect();
+        
+        // 各ブロックは `block` を抜けた先に置き、分岐は番号を書き込んでから `loop` の先頭に戻る
+        let count = block_ids.len() as u32;
+        self.body.push(Wasm::Loop(BlockType::Empty));
+        for _ in 0..count {
+            self.body.push(Wasm::Block(BlockType::Empty));
+        }
+        self.body.push(Wasm::LocalGet(self.block_local));
+        self.body.push(Wasm::BrTable(Cow::Owned((0..count).collect()), count.saturating_sub(1)));
+        for (index, id) in block_ids.iter().enumerate() {
+            self.body.push(Wasm::End);
+            self.loop_depth = count - 1 - index as u32;
+            self.emit_block(&self.function.blocks[id])?;
+        }
+        self.body.push(Wasm::End);
+        self.body.push(Wasm::Unreachable);
+        self.body.push(Wasm::End);
+        
+        // シャドウスタックを使う関数は、先頭でこの関数の領域を確保する（戻るときは emit_return で解放する）
+        if self.uses_frame {
+            let frame_size = self.frame_size.next_multiple_of(8);
+            let message = self.strings.intern("stack overflow");
+            let prologue = [
+                Wasm::GlobalGet(self.symbols.stack_pointer),
+                Wasm::LocalTee(self.stack_local),
+                Wasm::I32Const((STACK_BOTTOM + frame_size) as i32),
+                Wasm::I32LtU,
+                Wasm::If(BlockType::Empty),
+                Wasm::I32Const(message as i32),
+                Wasm::Call(self.symbols.host["panic"]),
+                Wasm::Unreachable,
+                Wasm::End,
+                Wasm::LocalGet(self.stack_local),
+                Wasm::I32Const(frame_size as i32),
+                Wasm::I32Sub,
+                Wasm::LocalTee(self.frame_local),
+                Wasm::GlobalSet(self.symbols.stack_pointer),
+            ];
+            self.body.splice(0..0, prologue);
+        }
+        
+        let mut function = WasmFunction::new(self.local_types.iter().map(|ty| (1, *ty)));
+        for instruction in &self.body {
+            function.instruction(instruction);
+        }
+        Ok(function)
+    }
+    
+    fn emit_block(&mut self, block: &BasicBlock) -> Result<()> {
+        for (_, instruction) in &block.instructions {
+            self.emit_instruction(block.id, instruction)?;
+        }
+        
+        match &block.terminator {
+            Some(terminator) => self.emit_terminator(block.id, terminator),
+            None if block.instructions.iter().any(|(_, i)| is_control_instruction(i)) => Ok(()),
+            None => {
+                self.body.push(Wasm::Unreachable);
+                Ok(())
+            }
+        }
+    }
+    
+    fn emit_instruction(&mut self, block: BlockId, instruction: &Instruction) -> Result<()> {
+        match instruction {
+            Instruction::BinaryOp { op, lhs, rhs, result } => {
+                self.emit_binary(*op, lhs, rhs)?;
+                self.set_register(*result)?;
+            }
+            Instruction::UnaryOp { op, operand, result } => {
+                let ty = self.operand_type(operand)?;
+                match op {
+                    UnaryOp::Neg if ty.is_integer() => {
+                        self.push_zero(ty)?;
+                        self.push_operand(operand, ty)?;
+                        self.push_integer_op(BinaryOp::Sub, ty)?;
+                    }
+                    UnaryOp::Neg => {
+                        self.push_operand(operand, ty)?;
+                        self.body.push(match ty.val_type() {
+                            Some(ValType::F32) => Wasm::F32Neg,
+                            Some(ValType::F64) => Wasm::F64Neg,
+                            _ => return Err(unsupported_operation("-", ty)),
+                        });
+                    }
+                    UnaryOp::Not => {
+                        self.push_operand(operand, ty)?;
+                        self.body.push(Wasm::I32Eqz);
+                    }
+                    UnaryOp::BitNot if ty.is_integer() => {
+                        self.push_operand(operand, ty)?;
+                        self.push_literal(&Literal::Int(-1), ty)?;
+                        self.push_integer_op(BinaryOp::BitXor, ty)?;
+                    }
+                    UnaryOp::BitNot => return Err(unsupported_operation("~", ty)),
+                    UnaryOp::Cast => {
+                        self.push_operand(operand, ty)?;
+                        self.emit_cast(ty, self.register_type(*result)?)?;
+                    }
+                }
+                self.set_register(*result)?;
+            }
+            Instruction::Load { address, result } => {
+                match address {
+                    Operand::Register(slot) if self.slots.contains_key(slot) => self.get_register(*slot)?,
+                    Operand::Global(name) if self.symbols.memory_globals.contains_key(name) => {
+                        let (address, ty) = self.symbols.memory_globals[name];
+                        self.body.push(Wasm::I32Const(address as i32));
+                        self.push_load(ty, 0);
+                    }
+                    Operand::Global(name) => {
+                        if let Some((index, _)) = self.symbols.globals.get(name) {
+                            self.body.push(Wasm::GlobalGet(*index));
+                        }
+                    }
+                    // タプルは新しい領域にコピーし、後でスロットに代入しても読み出した値が変わらないようにする
+                    Operand::Register(reg) => match self.tuple_type(&Operand::Register(*result)) {
+                        Some(tuple) => {
+                            self.push_frame_area(tuple)?;
+                            self.set_register(*result)?;
+                            self.copy_tuple(tuple, *reg, *result)?;
+                            return Ok(());
+                        }
+                        None => {
+                            let ty = self.register_type(*result)?;
+                            self.push_operand(address, WasmType::Pointer)?;
+                            self.push_load(ty, 0);
+                        }
+                    },
+                    _ => return Err(unsupported("アドレスを計算する Load")),
+                }
+                self.set_register(*result)?;
+            }
+            Instruction::Store { address, value } => match address {
+                Operand::Register(slot) if self.slots.contains_key(slot) => {
+                    self.push_operand(value, self.slots[slot])?;
+                    if let Some((Some(local), _)) = self.locals.get(slot) {
+                        self.body.push(Wasm::LocalSet(*local));
+                    }
+                }
+                Operand::Global(name) if self.symbols.memory_globals.contains_key(name) => {
+                    let (address, ty) = self.symbols.memory_globals[name];
+                    self.body.push(Wasm::I32Const(address as i32));
+                    self.push_operand(value, ty)?;
+                    self.push_store(ty, 0);
+                }
+                Operand::Global(name) => {
+                    let (index, ty) = *self
+                        .symbols
+                        .globals
+                        .get(name)
+                        .ok_or_else(|| EidosError::BackendError(format!("未定義のグローバル変数 '{}' に書き込んでいます", name)))?;
+                    self.push_operand(value, ty)?;
+                    if ty.val_type().is_some() {
+                        self.body.push(Wasm::GlobalSet(index));
+                    }
+                }
+                Operand::Register(reg) => match self.tuple_type(value) {
+                    Some(tuple) => {
+                        let Operand::Register(from) = value else {
+                            return Err(unsupported("タプルのリテラルの Store"));
+                        };
+                        self.copy_tuple(tuple, *from, *reg)?;
+                    }
+                    None => {
+                        let ty = self.register_type(*reg)?;
+                        self.push_operand(address, WasmType::Pointer)?;
+                        self.push_operand(value, ty)?;
+                        self.push_store(ty, 0);
+                    }
+                },
+                _ => return Err(unsupported("アドレスを計算する Store")),
+            },
+            Instruction::Call { function, arguments, result } => {
+                self.emit_call(function, arguments, *result, false)?;
+            }
+            Instruction::ExternalCall { function, arguments, result } => {
+                self.emit_call(function, arguments, *result, true)?;
+            }
+            Instruction::Return { value } => {
+                self.emit_return(block, value.as_ref())?;
+            }
+            Instruction::Branch { target } => {
+                self.emit_jump(block, *target, &[])?;
+            }
+            Instruction::BranchCond { condition, true_target, false_target } => {
+                let terminator = Terminator::BranchCond {
+                    condition: condition.clone(),
+                    true_target: *true_target,
+                    true_args: Vec::new(),
+                    false_target: *false_target,
+                    false_args: Vec::new(),
+                };
+                self.emit_terminator(block, &terminator)?;
+            }
+            // ローカル変数で表すスロットは関数の先頭で宣言済み
+            Instruction::Alloca { result, .. } if self.slots.contains_key(result) => {}
+            // タプルのスロットには要素をまとめて格納する
+            Instruction::Alloca { size, result } => {
+                let size = match self.tuple_type(&Operand::Register(*result)).and_then(Type::layout) {
+                    Some((tuple_size, _)) => tuple_size.max(*size),
+                    None => *size,
+                };
+                let offset = self.frame_area(size);
+                self.push_frame_address(offset);
+                self.set_register(*result)?;
+            }
+            Instruction::Cast { value, target_type, result } => {
+                let from = self.operand_type(value)?;
+                self.push_operand(value, from)?;
+                self.emit_cast(from, wasm_type(self.module, *target_type)?)?;
+                self.set_register(*result)?;
+            }
+            // 分岐元のブロックで代入済み
+            Instruction::Phi { .. } => {}
+            Instruction::Select { condition, true_value, false_value, result } => {
+                let ty = self.register_type(*result)?;
+                if ty.val_type().is_some() {
+                    self.push_operand(true_value, ty)?;
+                    self.push_operand(false_value, ty)?;
+                    self.push_operand(condition, WasmType::Bool)?;
+                    self.body.push(Wasm::Select);
+                    self.set_register(*result)?;
+                }
+            }
+            Instruction::DebugInfo { .. } => {}
+            Instruction::GetElementPtr { base, indices, result } => {
+                let base_type = self.eir_type(base);
+                let (Some(stride), Some(index)) = (base_type.and_then(Type::element_type).and_then(Type::stride), indices.first())
+                else {
+                    return Err(unsupported("GetElementPtr"));
+                };
+                self.push_operand(base, WasmType::Pointer)?;
+                // スライスは領域の先頭に置いたデータのアドレスから数える
+                if base_type.is_some_and(|ty| matches!(ty.kind, TypeKind::Slice(_))) {
+                    self.push_load(WasmType::Pointer, 0);
+                }
+                let ty = self.operand_type(index)?;
+                self.push_operand(index, ty)?;
+                self.emit_cast(ty, WasmType::Sized(NumericType::U32))?;
+                self.body.push(Wasm::I32Const(stride as i32));
+                self.body.push(Wasm::I32Mul);
+                self.body.push(Wasm::I32Add);
+                self.set_register(*result)?;
+            }
+            // タプルは要素を型のレイアウトどおりに並べたシャドウスタックの領域で、そのアドレスを値として渡す
+            Instruction::Tuple { elements, result } => {
+                let Some(tuple) = self.tuple_type(&Operand::Register(*result)) else {
+                    return Err(unsupported("Tuple"));
+                };
+                self.push_frame_area(tuple)?;
+                self.set_register(*result)?;
+                for (element, (ty, offset)) in elements.iter().zip(tuple_fields(tuple)?) {
+                    self.get_register(*result)?;
+                    self.push_operand(element, ty)?;
+                    self.push_store(ty, offset);
+                }
+            }
+            Instruction::Extract { tuple, index, result } => {
+                let Some((_, offset)) = self.tuple_type(tuple).map(tuple_fields).transpose()?.and_then(|fields| fields.get(*index).copied())
+                else {
+                    return Err(unsupported("Extract"));
+                };
+                let ty = self.register_type(*result)?;
+                self.push_operand(tuple, WasmType::Pointer)?;
+                self.push_load(ty, offset);
+                self.set_register(*result)?;
+            }
+            // スライスはデータのアドレスと長さを並べた16バイトの領域で、そのアドレスを値として渡す
+            Instruction::MakeSlice { array, length, result } => {
+                let offset = self.frame_area(16);
+                self.push_frame_address(offset);
+                self.set_register(*result)?;
+                self.get_register(*result)?;
+                self.push_operand(array, WasmType::Pointer)?;
+                self.push_store(WasmType::Pointer, 0);
+                self.get_register(*result)?;
+                let ty = self.operand_type(length)?;
+                self.push_operand(length, ty)?;
+                self.emit_cast(ty, WasmType::Int)?;
+                self.push_store(WasmType::Int, 8);
+            }
+            Instruction::SliceLen { slice, result } => {
+                self.push_operand(slice, WasmType::Pointer)?;
+                self.push_load(WasmType::Int, 8);
+                self.set_register(*result)?;
+            }
+            Instruction::Atomic { op, address, value, expected, result } => {
+                self.emit_atomic(*op, address, value.as_ref(), expected.as_ref(), *result)?;
+            }
+            Instruction::InlineAsm { asm, args, result, dialect: AsmDialect::Wasm, .. } => {
+                self.emit_inline_wasm(asm, args, *result)?;
+            }
+            Instruction::InlineAsm { .. } => return Err(unsupported("asm!")),
+        }
+        Ok(())
+    }
+    
+    /// アトミック操作を線形メモリのアトミック命令（64ビット整数は `AtomicOp::wasm_instruction`、32ビット整数はその `i32` 版）にする
+    ///
+    /// 読み書きしたのと同じ型の値を結果にする。ただし `CAS` の結果は書き換えたかどうかの `Bool` で、
+    /// `Store` の結果は0にする（Cバックエンドと同じ）。
+    fn emit_atomic(
+        &mut self,
+        op: AtomicOp,
+        address: &Operand,
+        value: Option<&Operand>,
+        expected: Option<&Operand>,
+        result: Option<RegisterId>,
+    ) -> Result<()> {
+        let ty = match address {
+            Operand::Global(name) if self.symbols.memory_globals.contains_key(name) => {
+                let (address, ty) = self.symbols.memory_globals[name];
+                self.body.push(Wasm::I32Const(address as i32));
+                ty
+            }
+            Operand::Register(reg) if self.addresses.contains(reg) => {
+                self.get_register(*reg)?;
+                self.register_type(*reg)?
+            }
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "WebAssemblyバックエンドは {} へのアトミック操作に対応していません",
+                    address
+                )))
+            }
+        };
+        if !is_atomic(ty) {
+            return Err(unsupported_operation(&format!("{:?}", op).to_lowercase(), ty));
+        }
+        let wide = ty.val_type() == Some(ValType::I64);
+        let memarg = MemArg { offset: 0, align: if wide { 3 } else { 2 }, memory_index: 0 };
+        let operand = |name: &str| {
+            value.ok_or_else(|| EidosError::BackendError(format!("アトミック操作 {} に値がありません", name)))
+        };
+        let instruction = match (op, wide) {
+            (AtomicOp::Load, false) => Wasm::I32AtomicLoad(memarg),
+            (AtomicOp::Load, true) => Wasm::I64AtomicLoad(memarg),
+            (AtomicOp::Store, _) => {
+                self.push_operand(operand("store")?, ty)?;
+                self.body.push(if wide { Wasm::I64AtomicStore(memarg) } else { Wasm::I32AtomicStore(memarg) });
+                if let Some(result) = result {
+                    self.push_zero(self.register_type(result)?)?;
+                    self.set_register(result)?;
+                }
+                return Ok(());
+            }
+            (AtomicOp::CAS, _) => {
+                let expected = expected
+                    .ok_or_else(|| EidosError::BackendError("アトミック操作 cas に比較する値がありません".to_string()))?;
+                self.push_operand(expected, ty)?;
+                self.push_operand(operand("cas")?, ty)?;
+                self.body.push(if wide { Wasm::I64AtomicRmwCmpxchg(memarg) } else { Wasm::I32AtomicRmwCmpxchg(memarg) });
+                // 読み出した値が比較する値と等しければ書き換えている
+                self.push_operand(expected, ty)?;
+                self.push_integer_op(BinaryOp::Eq, ty)?;
+                return match result {
+                    Some(result) => self.set_register(result),
+                    None => {
+                        self.body.push(Wasm::Drop);
+                        Ok(())
+                    }
+                };
+            }
+            (AtomicOp::Add, false) => Wasm::I32AtomicRmwAdd(memarg),
+            (AtomicOp::Add, true) => Wasm::I64AtomicRmwAdd(memarg),
+            (AtomicOp::Sub, false) => Wasm::I32AtomicRmwSub(memarg),
+            (AtomicOp::Sub, true) => Wasm::I64AtomicRmwSub(memarg),
+            (AtomicOp::And, false) => Wasm::I32AtomicRmwAnd(memarg),
+            (AtomicOp::And, true) => Wasm::I64AtomicRmwAnd(memarg),
+            (AtomicOp::Or, false) => Wasm::I32AtomicRmwOr(memarg),
+            (AtomicOp::Or, true) => Wasm::I64AtomicRmwOr(memarg),
+            (AtomicOp::Xor, false) => Wasm::I32AtomicRmwXor(memarg),
+            (AtomicOp::Xor, true) => Wasm::I64AtomicRmwXor(memarg),
+        };
+        if op != AtomicOp::Load {
+            self.push_operand(operand(&format!("{:?}", op).to_lowercase())?, ty)?;
+        }
+        self.body.push(instruction);
+        match result {
+            Some(result) => self.set_register(result),
+            None => {
+                self.body.push(Wasm::Drop);
+                Ok(())
+            }
+        }
+    }
+    
+    /// `wasm!` の命令列を書き込む
+    ///
+    /// オペランドはそれぞれ専用のローカル変数に置き、テンプレートの `{N}` はそのローカル変数の番号に置き換える。
+    /// 入力は命令列の前に代入し、出力（`{0}`）は命令列の後でレジスタに代入する。
+    fn emit_inline_wasm(&mut self, asm: &str, args: &[Operand], result: Option<RegisterId>) -> Result<()> {
+        let mut locals = Vec::with_capacity(args.len() + 1);
+        let output = match result {
+            Some(reg) => {
+                let ty = self.register_type(reg)?;
+                let val_type = ty.val_type().ok_or_else(|| unsupported_operation("wasm!", ty))?;
+                let local = self.add_local(val_type);
+                locals.push(local);
+                Some((reg, local))
+            }
+            None => None,
+        };
+        for arg in args {
+            let ty = self.operand_type(arg)?;
+            let val_type = ty.val_type().ok_or_else(|| unsupported_operation("wasm!", ty))?;
+            let local = self.add_local(val_type);
+            self.push_operand(arg, ty)?;
+            self.body.push(Wasm::LocalSet(local));
+            locals.push(local);
+        }
+        
+        let mut text = String::new();
+        for piece in eir::parse_asm_template(asm) {
+            match piece {
+                AsmPiece::Text(piece) => text.push_str(&piece),
+                AsmPiece::Operand(index) => match locals.get(index) {
+                    Some(local) => text.push_str(&local.to_string()),
+                    None => {
+                        return Err(EidosError::BackendError(format!(
+                            "wasm! のテンプレートの {{{}}} に対応するオペランドがありません",
+                            index
+                        )))
+                    }
+                },
+            }
+        }
+        self.body.extend(parse_wasm_text(&text)?);
+        
+        if let Some((reg, local)) = output {
+            self.body.push(Wasm::LocalGet(local));
+            self.set_register(reg)?;
+        }
+        Ok(())
+    }
+    
+    fn emit_call(&mut self, function: &str, arguments: &[Operand], result: Option<RegisterId>, external: bool) -> Result<()> {
+        // 同名のユーザー定義関数があれば組み込み関数より優先する
+        let user_function = if external { None } else { self.module.get_function_by_name(function) };
+        
+        // 組み込み関数は命令に置き換える（置き換えられないものは関数の本体を呼び出す）
+        if let Some(callee) = user_function {
+            if let Some(name) = codegen::intrinsic_of(callee).and_then(|intrinsic| intrinsic.wasm) {
+                for (argument, (_, ty)) in arguments.iter().zip(&callee.parameters) {
+                    self.push_operand(argument, wasm_type(self.module, *ty)?)?;
+                }
+                self.emit_intrinsic(name, arguments.len())?;
+                return match result {
+                    Some(result) => self.set_register(result),
+                    None if wasm_type(self.module, callee.return_type)?.val_type().is_some() => {
+                        self.body.push(Wasm::Drop);
+                        Ok(())
+                    }
+                    None => Ok(()),
+                };
+            }
+        }
+        
+        if user_function.is_none() && !external {
+            match function {
+                "print" | "println" => return self.emit_print(arguments, function == "println"),
+                "panic" => {
+                    let message = arguments.first().ok_or_else(|| {
+                        EidosError::BackendError("'panic' の引数が足りません".to_string())
+                    })?;
+                    self.push_operand(message, WasmType::String)?;
+                    self.body.push(Wasm::Call(self.symbols.host["panic"]));
+                    self.body.push(Wasm::Unreachable);
+                    return Ok(());
+                }
+                _ if function.starts_with("Option::") || function.starts_with("Result::") || function.starts_with("bigint::") => {
+                    return Err(EidosError::BackendError(format!(
+                        "WebAssemblyバックエンドは関数 '{}' に対応していません",
+                        function
+                    )));
+                }
+                _ => {}
+            }
+        }
+        
+        let (index, params, returns) = self
+            .symbols
+            .functions
+            .get(function)
+            .ok_or_else(|| codegen::undefined_function(self.module, function))?;
+        if params.len() != arguments.len() {
+            return Err(EidosError::BackendError(format!(
+                "関数 '{}' の引数の数が一致しません（{} 個のところ {} 個）",
+                function,
+                params.len(),
+                arguments.len()
+            )));
+        }
+        for (argument, ty) in arguments.iter().zip(params) {
+            self.push_operand(argument, *ty)?;
+        }
+        self.body.push(Wasm::Call(*index));
+        self.receive_results(returns, result)
+    }
+    
+    /// 呼び出した関数の戻り値をレジスタに受け取る
+    ///
+    /// タプルと構造体の戻り値は要素ごとの複数の値なので、新しい領域に並べてそのアドレスをレジスタに代入する。
+    fn receive_results(&mut self, returns: &[WasmType], result: Option<RegisterId>) -> Result<()> {
+        let results: Vec<ValType> = returns.iter().filter_map(WasmType::val_type).collect();
+        let tuple = result.and_then(|reg| self.tuple_type(&Operand::Register(reg)));
+        match (result, tuple) {
+            (Some(result), Some(tuple)) => {
+                let locals: Vec<u32> = results.iter().map(|ty| self.add_local(*ty)).collect();
+                for local in locals.iter().rev() {
+                    self.body.push(Wasm::LocalSet(*local));
+                }
+                self.push_frame_area(tuple)?;
+                self.set_register(result)?;
+                let fields = tuple_fields(tuple)?.into_iter().filter(|(ty, _)| ty.val_type().is_some());
+                for ((ty, offset), local) in fields.zip(locals) {
+                    self.get_register(result)?;
+                    self.body.push(Wasm::LocalGet(local));
+                    self.push_store(ty, offset);
+                }
+                Ok(())
+            }
+            (Some(result), None) if !results.is_empty() => self.set_register(result),
+            _ => {
+                for _ in results {
+                    self.body.push(Wasm::Drop);
+                }
+                Ok(())
+            }
+        }
+    }
+    
+    /// `print` と `println` の引数を型ごとの出力関数で表示する
+    fn emit_print(&mut self, arguments: &[Operand], newline: bool) -> Result<()> {
+        for argument in arguments {
+            let ty = self.operand_type(argument)?;
+            self.push_operand(argument, ty)?;
+            let helper = match ty {
+                WasmType::Unit => {
+                    self.body.push(Wasm::I64Const(0));
+                    "print_int"
+                }
+                WasmType::Bool => "print_bool",
+                WasmType::Char => "print_char",
+                WasmType::String => "print_string",
+                WasmType::Float | WasmType::Sized(NumericType::F64) => "print_float",
+                WasmType::Sized(NumericType::F32) => {
+                    self.body.push(Wasm::F64PromoteF32);
+                    "print_float"
+                }
+                WasmType::Sized(NumericType::U64) => "print_u64",
+                WasmType::Int | WasmType::Sized(NumericType::I64) => "print_int",
+                WasmType::Sized(_) => {
+                    self.emit_cast(ty, WasmType::Int)?;
+                    "print_int"
+                }
+                WasmType::Pointer | WasmType::Function => return Err(unsupported_operation("print", ty)),
+            };
+            self.body.push(Wasm::Call(self.symbols.host[helper]));
+        }
+        if newline {
+            self.body.push(Wasm::Call(self.symbols.host["print_newline"]));
+        }
+        Ok(())
+    }
+    
+    /// 組み込み関数の表の `wasm` 欄の命令（引数はスタックに積んである）
+    fn emit_intrinsic(&mut self, name: &str, arity: usize) -> Result<()> {
+        match name {
+            "i64.popcnt" => self.body.push(Wasm::I64Popcnt),
+            "i64.clz" => self.body.push(Wasm::I64Clz),
+            "i64.ctz" => self.body.push(Wasm::I64Ctz),
+            "f64.sqrt" => self.body.push(Wasm::F64Sqrt),
+            // 線形メモリのアドレスと大きさは `i32` なので、引数の `Int` を切り詰める
+            "memory.copy" if arity == 3 => {
+                let scratch = self.scratch_locals(3);
+                for local in scratch.iter().rev() {
+                    self.body.push(Wasm::LocalSet(*local));
+                }
+                for local in scratch {
+                    self.body.push(Wasm::LocalGet(local));
+                    self.body.push(Wasm::I32WrapI64);
+                }
+                self.body.push(Wasm::MemoryCopy { src_mem: 0, dst_mem: 0 });
+            }
+            "memory.grow" => {
+                self.body.push(Wasm::I32WrapI64);
+                self.body.push(Wasm::MemoryGrow(0));
+                self.body.push(Wasm::I64ExtendI32S);
+            }
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "WebAssemblyバックエンドは組み込み関数の命令 '{}' に対応していません",
+                    name
+                )))
+            }
+        }
+        Ok(())
+    }
+    
+    /// 組み込み関数の引数を並べ替えるための `i64` のローカル変数を追加する
+    fn scratch_locals(&mut self, count: usize) -> Vec<u32> {
+        (0..count).map(|_| self.add_local(ValType::I64)).collect()
+    }
+    
+    /// パラメータの後ろにローカル変数を追加し、そのインデックスを返す
+    fn add_local(&mut self, ty: ValType) -> u32 {
+        self.local_types.push(ty);
+        self.param_locals + self.local_types.len() as u32 - 1
+    }
+    
+    fn emit_return(&mut self, block: BlockId, value: Option<&Operand>) -> Result<()> {
+        self.emit_phi_moves(block)?;
+        let return_type = eir_type(self.module, self.function.return_type)?;
+        let ty = type_of(return_type)?;
+        match value {
+            // タプルと構造体は要素を読み出して複数の値として返す
+            Some(value) if return_type.field_types().is_some() => {
+                for (field, offset) in tuple_fields(return_type)? {
+                    if field.val_type().is_some() {
+                        self.push_operand(value, WasmType::Pointer)?;
+                        self.push_load(field, offset);
+                    }
+                }
+            }
+            Some(value) if ty.val_type().is_some() => self.push_operand(value, ty)?,
+            None if ty.val_type().is_some() => {
+                return Err(EidosError::BackendError(format!("関数 '{}' が値を返さずに戻ります", self.function.name)));
+            }
+            _ => {}
+        }
+        if self.uses_frame {
+            self.body.push(Wasm::LocalGet(self.stack_local));
+            self.body.push(Wasm::GlobalSet(self.symbols.stack_pointer));
+        }
+        self.body.push(Wasm::Return);
+        Ok(())
+    }
+    
+    /// 関数の値（関数表の添字）で関数を呼び出し、戻ったら `return_block` に分岐する
+    fn emit_indirect_call(
+        &mut self,
+        block: BlockId,
+        function_ptr: &Operand,
+        arguments: &[Operand],
+        return_block: BlockId,
+        return_args: &[Operand],
+    ) -> Result<()> {
+        let (params, returns) = match (self.eir_type(function_ptr).map(|ty| &ty.kind), self.function_name(function_ptr)) {
+            (Some(TypeKind::Function { params, return_type }), _) => (
+                params.iter().map(type_of).collect::<Result<Vec<_>>>()?,
+                return_types(return_type)?,
+            ),
+            (_, Some(name)) => {
+                let (_, params, returns) = &self.symbols.functions[name];
+                (params.clone(), returns.clone())
+            }
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "関数 '{}' の {} は関数の値ではありません",
+                    self.function.name, function_ptr
+                )))
+            }
+        };
+        if params.len() != arguments.len() {
+            return Err(EidosError::BackendError(format!(
+                "関数 '{}' の間接呼び出しの引数の数が一致しません（{} 個のところ {} 個）",
+                self.function.name,
+                params.len(),
+                arguments.len()
+            )));
+        }
+        for (argument, ty) in arguments.iter().zip(&params) {
+            self.push_operand(argument, *ty)?;
+        }
+        self.push_operand(function_ptr, WasmType::Function)?;
+        let ty = self.types.signature(&params, &returns);
+        self.body.push(Wasm::CallIndirect { ty, table: 0 });
+        self.receive_results(&returns, None)?;
+        self.emit_jump(block, return_block, return_args)
+    }
+    
+    /// 分岐先のブロックパラメータに引数を代入し、分岐先の番号を書き込んで `loop` の先頭に戻る
+    fn emit_jump(&mut self, from: BlockId, target: BlockId, args: &[Operand]) -> Result<()> {
+        self.emit_phi_moves(from)?;
+        
+        let params = self
+            .function
+            .get_block(target)
+            .map(|block| block.parameters.clone())
+            .unwrap_or_default();
+        if params.len() != args.len() {
+            return Err(EidosError::BackendError(format!(
+                "関数 '{}' のブロック {} への分岐の引数の数が一致しません",
+                self.function.name, target
+            )));
+        }
+        
+        // 引数が他のパラメータを参照していてもよいよう、すべて積んでから逆順に代入する
+        for (arg, (reg, _)) in args.iter().zip(&params) {
+            let ty = self.register_type(*reg)?;
+            self.push_operand(arg, ty)?;
+        }
+        for (reg, _) in params.iter().rev() {
+            self.set_register(*reg)?;
+        }
+        
+        let index = *self.block_indices.get(&target).ok_or_else(|| {
+            EidosError::BackendError(format!("関数 '{}' に分岐先のブロック {} がありません", self.function.name, target))
+        })?;
+        self.body.push(Wasm::I32Const(index as i32));
+        self.body.push(Wasm::LocalSet(self.block_local));
+        self.body.push(Wasm::Br(self.loop_depth));
+        Ok(())
+    }
+    
+    fn emit_phi_moves(&mut self, from: BlockId) -> Result<()> {
+        if let Some(moves) = self.phi_moves.get(&from).cloned() {
+            for (result, value) in moves {
+                let ty = self.register_type(result)?;
+                self.push_operand(&value, ty)?;
+                self.set_register(result)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// 条件が成り立てば `then` の分岐をする（`if` の中では `loop` が1段深くなる）
+    fn emit_conditional_jump(&mut self, from: BlockId, target: BlockId, args: &[Operand]) -> Result<()> {
+        self.body.push(Wasm::If(BlockType::Empty));
+        self.loop_depth += 1;
+        self.emit_jump(from, target, args)?;
+        self.loop_depth -= 1;
+        self.body.push(Wasm::End);
+        Ok(())
+    }
+    
+    fn emit_terminator(&mut self, block: BlockId, terminator: &Terminator) -> Result<()> {
+        match terminator {
+            Terminator::Branch { target, args } => self.emit_jump(block, *target, args),
+            Terminator::BranchCond { condition, true_target, true_args, false_target, false_args } => {
+                self.push_operand(condition, WasmType::Bool)?;
+                self.emit_conditional_jump(block, *true_target, true_args)?;
+                self.emit_jump(block, *false_target, false_args)
+            }
+            Terminator::Return { value } => self.emit_return(block, value.as_ref()),
+            Terminator::Switch { value, default_target, default_args, cases } => {
+                let ty = self.operand_type(value)?;
+                for (literal, target, args) in cases {
+                    self.push_operand(value, ty)?;
+                    self.push_literal(literal, ty)?;
+                    self.push_integer_op(BinaryOp::Eq, ty)?;
+                    self.emit_conditional_jump(block, *target, args)?;
+                }
+                self.emit_jump(block, *default_target, default_args)
+            }
+            Terminator::IndirectCall { function_ptr, arguments, return_block, return_args } => {
+                self.emit_indirect_call(block, function_ptr, arguments, *return_block, return_args)
+            }
+            Terminator::Unreachable => {
+                self.body.push(Wasm::Unreachable);
+                Ok(())
+            }
+        }
+    }
+    
+    fn emit_binary(&mut self, op: BinaryOp, lhs: &Operand, rhs: &Operand) -> Result<()> {
+        // リテラルの型は相手のオペランドに合わせる
+        let ty = match lhs {
+            Operand::Literal(_) => self.operand_type(rhs)?,
+            _ => self.operand_type(lhs)?,
+        };
+        match ty.val_type() {
+            Some(ValType::F32 | ValType::F64) if op == BinaryOp::Rem => {
+                // 剰余は a - trunc(a / b) * b で求める
+                let f32 = ty.val_type() == Some(ValType::F32);
+                self.push_operand(lhs, ty)?;
+                self.push_operand(lhs, ty)?;
+                self.push_operand(rhs, ty)?;
+                self.body.push(if f32 { Wasm::F32Div } else { Wasm::F64Div });
+                self.body.push(if f32 { Wasm::F32Trunc } else { Wasm::F64Trunc });
+                self.push_operand(rhs, ty)?;
+                self.body.push(if f32 { Wasm::F32Mul } else { Wasm::F64Mul });
+                self.body.push(if f32 { Wasm::F32Sub } else { Wasm::F64Sub });
+                Ok(())
+            }
+            Some(ValType::F32 | ValType::F64) => {
+                self.push_operand(lhs, ty)?;
+                self.push_operand(rhs, ty)?;
+                self.push_float_op(op, ty)
+            }
+            Some(_) if matches!(ty, WasmType::String | WasmType::Pointer | WasmType::Function) => {
+                Err(unsupported_operation(&op.to_string(), ty))
+            }
+            Some(_) => {
+                self.push_operand(lhs, ty)?;
+                self.push_operand(rhs, ty)?;
+                // シフト量はビット幅で剰余をとる（Cバックエンドと同じ結果にする）
+                if let (BinaryOp::Shl | BinaryOp::Shr, Some(bits)) = (op, ty.narrow_bits()) {
+                    self.body.push(Wasm::I32Const(bits as i32 - 1));
+                    self.body.push(Wasm::I32And);
+                }
+                self.push_integer_op(op, ty)
+            }
+            None => Err(unsupported_operation(&op.to_string(), ty)),
+        }
+    }
+    
+    /// 整数（`Bool` と `Char` を含む）の二項演算の命令（オペランドはスタックに積んである）
+    ///
+    /// 32ビット未満の整数型の演算結果は、型のビット幅に折り返す。
+    fn push_integer_op(&mut self, op: BinaryOp, ty: WasmType) -> Result<()> {
+        let wide = ty.val_type() == Some(ValType::I64);
+        let signed = ty.is_signed();
+        let instruction = match (op, wide) {
+            (BinaryOp::Add, false) => Wasm::I32Add,
+            (BinaryOp::Add, true) => Wasm::I64Add,
+            (BinaryOp::Sub, false) => Wasm::I32Sub,
+            (BinaryOp::Sub, true) => Wasm::I64Sub,
+            (BinaryOp::Mul, false) => Wasm::I32Mul,
+            (BinaryOp::Mul, true) => Wasm::I64Mul,
+            (BinaryOp::Div, false) if signed => Wasm::I32DivS,
+            (BinaryOp::Div, false) => Wasm::I32DivU,
+            (BinaryOp::Div, true) if signed => Wasm::I64DivS,
+            (BinaryOp::Div, true) => Wasm::I64DivU,
+            (BinaryOp::Rem, false) if signed => Wasm::I32RemS,
+            (BinaryOp::Rem, false) => Wasm::I32RemU,
+            (BinaryOp::Rem, true) if signed => Wasm::I64RemS,
+            (BinaryOp::Rem, true) => Wasm::I64RemU,
+            (BinaryOp::BitAnd | BinaryOp::And, false) => Wasm::I32And,
+            (BinaryOp::BitAnd | BinaryOp::And, true) => Wasm::I64And,
+            (BinaryOp::BitOr | BinaryOp::Or, false) => Wasm::I32Or,
+            (BinaryOp::BitOr | BinaryOp::Or, true) => Wasm::I64Or,
+            (BinaryOp::BitXor, false) => Wasm::I32Xor,
+            (BinaryOp::BitXor, true) => Wasm::I64Xor,
+            (BinaryOp::Shl, false) => Wasm::I32Shl,
+            (BinaryOp::Shl, true) => Wasm::I64Shl,
+            (BinaryOp::Shr, false) if signed => Wasm::I32ShrS,
+            (BinaryOp::Shr, false) => Wasm::I32ShrU,
+            (BinaryOp::Shr, true) if signed => Wasm::I64ShrS,
+            (BinaryOp::Shr, true) => Wasm::I64ShrU,
+            (BinaryOp::Eq, false) => Wasm::I32Eq,
+            (BinaryOp::Eq, true) => Wasm::I64Eq,
+            (BinaryOp::Ne, false) => Wasm::I32Ne,
+            (BinaryOp::Ne, true) => Wasm::I64Ne,
+            (BinaryOp::Lt, false) if signed => Wasm::I32LtS,
+            (BinaryOp::Lt, false) => Wasm::I32LtU,
+            (BinaryOp::Lt, true) if signed => Wasm::I64LtS,
+            (BinaryOp::Lt, true) => Wasm::I64LtU,
+            (BinaryOp::Le, false) if signed => Wasm::I32LeS,
+            (BinaryOp::Le, false) => Wasm::I32LeU,
+            (BinaryOp::Le, true) if signed => Wasm::I64LeS,
+            (BinaryOp::Le, true) => Wasm::I64LeU,
+            (BinaryOp::Gt, false) if signed => Wasm::I32GtS,
+            (BinaryOp::Gt, false) => Wasm::I32GtU,
+            (BinaryOp::Gt, true) if signed => Wasm::I64GtS,
+            (BinaryOp::Gt, true) => Wasm::I64GtU,
+            (BinaryOp::Ge, false) if signed => Wasm::I32GeS,
+            (BinaryOp::Ge, false) => Wasm::I32GeU,
+            (BinaryOp::Ge, true) if signed => Wasm::I64GeS,
+            (BinaryOp::Ge, true) => Wasm::I64GeU,
+        };
+        self.body.push(instruction);
+        if matches!(op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Shl | BinaryOp::BitXor) {
+            self.wrap_narrow(ty);
+        }
+        Ok(())
+    }
+    
+    fn push_float_op(&mut self, op: BinaryOp, ty: WasmType) -> Result<()> {
+        let f32 = ty.val_type() == Some(ValType::F32);
+        let instruction = match (op, f32) {
+            (BinaryOp::Add, false) => Wasm::F64Add,
+            (BinaryOp::Add, true) => Wasm::F32Add,
+            (BinaryOp::Sub, false) => Wasm::F64Sub,
+            (BinaryOp::Sub, true) => Wasm::F32Sub,
+            (BinaryOp::Mul, false) => Wasm::F64Mul,
+            (BinaryOp::Mul, true) => Wasm::F32Mul,
+            (BinaryOp::Div, false) => Wasm::F64Div,
+            (BinaryOp::Div, true) => Wasm::F32Div,
+            (BinaryOp::Eq, false) => Wasm::F64Eq,
+            (BinaryOp::Eq, true) => Wasm::F32Eq,
+            (BinaryOp::Ne, false) => Wasm::F64Ne,
+            (BinaryOp::Ne, true) => Wasm::F32Ne,
+            (BinaryOp::Lt, false) => Wasm::F64Lt,
+            (BinaryOp::Lt, true) => Wasm::F32Lt,
+            (BinaryOp::Le, false) => Wasm::F64Le,
+            (BinaryOp::Le, true) => Wasm::F32Le,
+            (BinaryOp::Gt, false) => Wasm::F64Gt,
+            (BinaryOp::Gt, true) => Wasm::F32Gt,
+            (BinaryOp::Ge, false) => Wasm::F64Ge,
+            (BinaryOp::Ge, true) => Wasm::F32Ge,
+            _ => return Err(unsupported_operation(&op.to_string(), ty)),
+        };
+        self.body.push(instruction);
+        Ok(())
+    }
+    
+    /// 32ビット未満の整数型の値を、型のビット幅に折り返す（符号付きは符号拡張、符号なしはゼロ拡張）
+    fn wrap_narrow(&mut self, ty: WasmType) {
+        match (ty.narrow_bits(), ty.is_signed()) {
+            (Some(8), true) => self.body.push(Wasm::I32Extend8S),
+            (Some(16), true) => self.body.push(Wasm::I32Extend16S),
+            (Some(bits), false) => {
+                self.body.push(Wasm::I32Const((1 << bits) - 1));
+                self.body.push(Wasm::I32And);
+            }
+            _ => {}
+        }
+    }
+    
+    /// スタックの値を `from` から `to` に変換する
+    fn emit_cast(&mut self, from: WasmType, to: WasmType) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        if to == WasmType::Bool && from.is_integer() {
+            self.push_zero(from)?;
+            return self.push_integer_op(BinaryOp::Ne, from);
+        }
+        let integer_like = |ty: WasmType| ty.is_integer() || matches!(ty, WasmType::Bool | WasmType::Char);
+        if !(integer_like(from) || from.val_type().is_some_and(is_float)) || !(integer_like(to) || to.val_type().is_some_and(is_float)) {
+            return Err(EidosError::BackendError(format!(
+                "WebAssemblyバックエンドは {} から {} への変換に対応していません",
+                from.name(),
+                to.name()
+            )));
+        }
+        // 浮動小数点数から整数への変換は、範囲外の値をトラップにせず飽和させる
+        let instruction = match (from.val_type(), to.val_type(), from.is_signed(), to.is_signed()) {
+            (Some(ValType::I64), Some(ValType::I32), ..) => Some(Wasm::I32WrapI64),
+            (Some(ValType::I32), Some(ValType::I64), true, _) => Some(Wasm::I64ExtendI32S),
+            (Some(ValType::I32), Some(ValType::I64), false, _) => Some(Wasm::I64ExtendI32U),
+            (Some(ValType::I64), Some(ValType::F64), signed, _) => Some(if signed { Wasm::F64ConvertI64S } else { Wasm::F64ConvertI64U }),
+            (Some(ValType::I64), Some(ValType::F32), signed, _) => Some(if signed { Wasm::F32ConvertI64S } else { Wasm::F32ConvertI64U }),
+            (Some(ValType::I32), Some(ValType::F64), signed, _) => Some(if signed { Wasm::F64ConvertI32S } else { Wasm::F64ConvertI32U }),
+            (Some(ValType::I32), Some(ValType::F32), signed, _) => Some(if signed { Wasm::F32ConvertI32S } else { Wasm::F32ConvertI32U }),
+            (Some(ValType::F64), Some(ValType::I64), _, signed) => Some(if signed { Wasm::I64TruncSatF64S } else { Wasm::I64TruncSatF64U }),
+            (Some(ValType::F32), Some(ValType::I64), _, signed) => Some(if signed { Wasm::I64TruncSatF32S } else { Wasm::I64TruncSatF32U }),
+            (Some(ValType::F64), Some(ValType::I32), _, signed) => Some(if signed { Wasm::I32TruncSatF64S } else { Wasm::I32TruncSatF64U }),
+            (Some(ValType::F32), Some(ValType::I32), _, signed) => Some(if signed { Wasm::I32TruncSatF32S } else { Wasm::I32TruncSatF32U }),
+            (Some(ValType::F64), Some(ValType::F32), ..) => Some(Wasm::F32DemoteF64),
+            (Some(ValType::F32), Some(ValType::F64), ..) => Some(Wasm::F64PromoteF32),
+            _ => None,
+        };
+        if let Some(instruction) = instruction {
+            self.body.push(instruction);
+        }
+        self.wrap_narrow(to);
+        Ok(())
+    }
+    
+    /// オペランドの値を積む（リテラルは `ty` の表現にする）
+    fn push_operand(&mut self, operand: &Operand, ty: WasmType) -> Result<()> {
+        match operand {
+            Operand::Register(reg) if self.slots.contains_key(reg) => Err(EidosError::BackendError(format!(
+                "関数 '{}' のスロット {} のアドレスは、WebAssemblyバックエンドでは値として使えません",
+                self.function.name, reg
+            ))),
+            Operand::Register(reg) => {
+                if let Some(local) = self.register_local(*reg)? {
+                    self.body.push(Wasm::LocalGet(local));
+                }
+                Ok(())
+            }
+            Operand::Literal(literal) => self.push_literal(literal, ty),
+            Operand::Global(name) if self.symbols.memory_globals.contains_key(name) => {
+                let (address, global_ty) = self.symbols.memory_globals[name];
+                self.body.push(Wasm::I32Const(address as i32));
+                self.push_load(global_ty, 0);
+                Ok(())
+            }
+            Operand::Global(name) => match self.symbols.globals.get(name) {
+                Some((index, global_ty)) => {
+                    if global_ty.val_type().is_some() {
+                        self.body.push(Wasm::GlobalGet(*index));
+                    }
+                    Ok(())
+                }
+                None if self.symbols.table.contains_key(name) => {
+                    self.body.push(Wasm::I32Const(self.symbols.table[name] as i32));
+                    Ok(())
+                }
+                None => Err(EidosError::BackendError(format!(
+                    "WebAssemblyバックエンドでは '{}' を値として使えません",
+                    name
+                ))),
+            },
+            Operand::Function(_) => match self.function_name(operand) {
+                Some(name) => {
+                    self.body.push(Wasm::I32Const(self.symbols.table[name] as i32));
+                    Ok(())
+                }
+                None => Err(EidosError::BackendError(format!("未定義の関数 {} を参照しています", operand))),
+            },
+            _ => Err(EidosError::BackendError(format!(
+                "オペランド {} はWebAssemblyバックエンドでは表現できません",
+                operand
+            ))),
+        }
+    }
+    
+    fn push_literal(&mut self, literal: &Literal, ty: WasmType) -> Result<()> {
+        let ty = match (literal, ty.val_type()) {
+            // 整数のリテラルは相手の型（`i32` や `f64` など）の値にする
+            (Literal::Int(_) | Literal::Float(_), Some(_)) if ty != WasmType::String => ty,
+            _ => WasmType::of_literal(literal),
+        };
+        let instruction = match (literal, ty.val_type()) {
+            (_, None) => return Ok(()),
+            (Literal::Int(value), Some(ValType::I64)) => Wasm::I64Const(*value),
+            (Literal::Int(value), Some(ValType::I32)) => Wasm::I32Const(*value as i32),
+            (Literal::Int(value), Some(ValType::F64)) => Wasm::F64Const(*value as f64),
+            (Literal::Int(value), Some(ValType::F32)) => Wasm::F32Const(*value as f32),
+            (Literal::Float(value), Some(ValType::F32)) => Wasm::F32Const(*value as f32),
+            (Literal::Float(value), _) => Wasm::F64Const(*value),
+            (Literal::Bool(value), _) => Wasm::I32Const(*value as i32),
+            (Literal::Char(value), _) => Wasm::I32Const(*value as i32),
+            (Literal::String(value), _) => Wasm::I32Const(self.strings.intern(value) as i32),
+            (literal, _) => {
+                return Err(EidosError::BackendError(format!(
+                    "リテラル {} はWebAssemblyバックエンドでは表現できません",
+                    literal
+                )))
+            }
+        };
+        self.body.push(instruction);
+        Ok(())
+    }
+    
+    /// 型の0を積む
+    fn push_zero(&mut self, ty: WasmType) -> Result<()> {
+        self.push_literal(&Literal::Int(0), ty)
+    }
+    
+    /// レジスタの値を積む（`Unit` のレジスタは何も積まない）
+    fn get_register(&mut self, reg: RegisterId) -> Result<()> {
+        if let Some(local) = self.register_local(reg)? {
+            self.body.push(Wasm::LocalGet(local));
+        }
+        Ok(())
+    }
+    
+    /// シャドウスタック上のこの関数の領域から `size` バイトを割り当て、その位置を返す
+    fn frame_area(&mut self, size: usize) -> u32 {
+        let offset = self.frame_size.next_multiple_of(8);
+        self.frame_size = offset + size as u32;
+        offset
+    }
+    
+    fn push_frame_address(&mut self, offset: u32) {
+        self.body.push(Wasm::LocalGet(self.frame_local));
+        if offset > 0 {
+            self.body.push(Wasm::I32Const(offset as i32));
+            self.body.push(Wasm::I32Add);
+        }
+    }
+    
+    /// タプルを置く領域を割り当て、そのアドレスを積む
+    fn push_frame_area(&mut self, tuple: &Type) -> Result<()> {
+        let Some((size, _)) = tuple.layout() else {
+            return Err(EidosError::BackendError(format!("WebAssemblyバックエンドは {} 型の値に対応していません", tuple)));
+        };
+        let offset = self.frame_area(size);
+        self.push_frame_address(offset);
+        Ok(())
+    }
+    
+    /// タプルの要素を `from` が指す領域から `to` が指す領域にコピーする
+    fn copy_tuple(&mut self, tuple: &Type, from: RegisterId, to: RegisterId) -> Result<()> {
+        for (ty, offset) in tuple_fields(tuple)? {
+            self.get_register(to)?;
+            self.get_register(from)?;
+            self.push_load(ty, offset);
+            self.push_store(ty, offset);
+        }
+        Ok(())
+    }
+    
+    /// スタックのアドレスから値を読み出す（`Unit` はアドレスを捨てるだけ）
+    fn push_load(&mut self, ty: WasmType, offset: u32) {
+        self.body.push(ty.load(offset).unwrap_or(Wasm::Drop));
+    }
+    
+    /// スタックのアドレスに値を書き込む（`Unit` は値を積まないので、アドレスを捨てるだけ）
+    fn push_store(&mut self, ty: WasmType, offset: u32) {
+        self.body.push(ty.store(offset).unwrap_or(Wasm::Drop));
+    }
+    
+    /// スタックの値をレジスタに代入する（`Unit` のレジスタには何もしない）
+    fn set_register(&mut self, reg: RegisterId) -> Result<()> {
+        if let Some(local) = self.register_local(reg)? {
+            self.body.push(Wasm::LocalSet(local));
+        }
+        Ok(())
+    }
+    
+    fn register_local(&self, reg: RegisterId) -> Result<Option<u32>> {
+        match self.locals.get(&reg) {
+            Some((local, _)) => Ok(*local),
+            None => Err(unknown_register(self.function, reg)),
+        }
+    }
+    
+    fn register_type(&self, reg: RegisterId) -> Result<WasmType> {
+        match self.locals.get(&reg) {
+            Some((_, ty)) => Ok(*ty),
+            None => Err(unknown_register(self.function, reg)),
+        }
+    }
+    
+    /// 関数を直接指すオペランドなら、その関数の名前
+    fn function_name(&self, operand: &Operand) -> Option<&'a str> {
+        let module = self.module;
+        match operand {
+            Operand::Function(id) => module.get_function(*id).map(|function| function.name.as_str()),
+            Operand::Global(name) if !self.symbols.globals.contains_key(name) => {
+                module.get_function_by_name(name).map(|function| function.name.as_str())
+            }
+            _ => None,
+        }
+    }
+    
+    /// オペランドのEIRの型（レジスタ以外はNone）
+    fn eir_type(&self, operand: &Operand) -> Option<&'a Type> {
+        let module = self.module;
+        match operand {
+            Operand::Register(reg) => module.get_type(self.function.get_register_type(*reg)?),
+            _ => None,
+        }
+    }
+    
+    /// オペランドがタプルか構造体なら、その型
+    fn tuple_type(&self, operand: &Operand) -> Option<&'a Type> {
+        self.eir_type(operand).filter(|ty| ty.field_types().is_some())
+    }
+    
+    fn operand_type(&self, operand: &Operand) -> Result<WasmType> {
+        match operand {
+            Operand::Register(reg) => self.register_type(*reg),
+            Operand::Literal(literal) => Ok(WasmType::of_literal(literal)),
+            Operand::Global(name) if self.symbols.memory_globals.contains_key(name) => Ok(self.symbols.memory_globals[name].1),
+            Operand::Global(name) => match self.symbols.globals.get(name) {
+                Some((_, ty)) => Ok(*ty),
+                None if self.symbols.table.contains_key(name) => Ok(WasmType::Function),
+                None => Err(EidosError::BackendError(format!("未定義のグローバル変数 '{}' を参照しています", name))),
+            },
+            Operand::Function(_) => Ok(WasmType::Function),
+            _ => Err(EidosError::BackendError(format!(
+                "オペランド {} はWebAssemblyバックエンドでは表現できません",
+                operand
+            ))),
+        }
+    }
+}
+
+fn unsupported(instruction: &str) -> EidosError {
+    EidosError::BackendError(format!("WebAssemblyバックエンドは {} 命令に対応していません", instruction))
+}
+
+fn unsupported_operation(op: &str, ty: WasmType) -> EidosError {
+    EidosError::BackendError(format!("WebAssemblyバックエンドは {} の値の演算 '{}' に対応していません", ty.name(), op))
+}
+
+fn unknown_register(function: &Function, reg: RegisterId) -> EidosError {
+    EidosError::BackendError(format!("関数 '{}' のレジスタ {} の型が分かりません", function.name, reg))
+}
+
+/// 関数の中で起きたエラーに関数の名前を付ける
+fn in_function(function: &Function, error: EidosError) -> EidosError {
+    match error {
+        EidosError::BackendError(message) if !message.contains(&function.name) => {
+            EidosError::BackendError(format!("関数 '{}': {}", function.name, message))
+        }
+        error => error,
+    }
+}
+
+fn is_control_instruction(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Return { .. } | Instruction::Branch { .. } | Instruction::BranchCond { .. })
+}
+
+/// アトミック命令で読み書きできる型（32ビットと64ビットの整数）
+fn is_atomic(ty: WasmType) -> bool {
+    ty.is_integer() && ty.narrow_bits().is_none()
+}
+
+fn is_float(ty: ValType) -> bool {
+    matches!(ty, ValType::F32 | ValType::F64)
+}
+
+/// タプルの要素・構造体のフィールドの型とメモリ上の位置
+fn tuple_fields(tuple: &Type) -> Result<Vec<(WasmType, u32)>> {
+    let (Some(fields), Some(offsets)) = (tuple.field_types(), tuple.field_offsets()) else {
+        return Err(EidosError::BackendError(format!("WebAssemblyバックエンドは {} 型の値に対応していません", tuple)));
+    };
+    fields.into_iter().zip(offsets).map(|(field, offset)| Ok((type_of(field)?, offset as u32))).collect()
+}
+
+fn type_of(ty: &Type) -> Result<WasmType> {
+    WasmType::from_type(ty).ok_or_else(|| {
+        EidosError::BackendError(format!("WebAssemblyバックエンドは {} 型の値に対応していません", ty))
+    })
+}
+
+/// 戻り値の型（タプルと構造体は要素ごとの複数の値として返す）
+fn return_types(ty: &Type) -> Result<Vec<WasmType>> {
+    match ty.field_types() {
+        Some(fields) => fields.into_iter().map(type_of).collect(),
+        None => Ok(vec![type_of(ty)?]),
+    }
+}
+
+/// グローバル変数の型（線形メモリに置く値のグローバル変数には対応していない）
+fn global_type(ty: &Type) -> Result<WasmType> {
+    match type_of(ty)? {
+        WasmType::Pointer | WasmType::Function => Err(EidosError::BackendError(format!(
+            "WebAssemblyバックエンドは {} 型のグローバル変数に対応していません",
+            ty
+        ))),
+        ty => Ok(ty),
+    }
+}
+
+fn eir_type(module: &eir::Module, id: TypeId) -> Result<&Type> {
+    module.get_type(id).ok_or_else(|| EidosError::BackendError(format!("型 {} が見つかりません", id)))
+}
+
+fn wasm_type(module: &eir::Module, id: TypeId) -> Result<WasmType> {
+    type_of(eir_type(module, id)?)
+}
+
+/// `wasm!` で使える、即値を取らない命令
+const PLAIN_INSTRUCTIONS: &[(&str, Wasm<'static>)] = &[
+    ("unreachable", Wasm::Unreachable),
+    ("nop", Wasm::Nop),
+    ("drop", Wasm::Drop),
+    ("select", Wasm::Select),
+    ("i32.eqz", Wasm::I32Eqz),
+    ("i32.eq", Wasm::I32Eq),
+    ("i32.ne", Wasm::I32Ne),
+    ("i32.lt_s", Wasm::I32LtS),
+    ("i32.lt_u", Wasm::I32LtU),
+    ("i32.gt_s", Wasm::I32GtS),
+    ("i32.gt_u", Wasm::I32GtU),
+    ("i32.le_s", Wasm::I32LeS),
+    ("i32.le_u", Wasm::I32LeU),
+    ("i32.ge_s", Wasm::I32GeS),
+    ("i32.ge_u", Wasm::I32GeU),
+    ("i64.eqz", Wasm::I64Eqz),
+    ("i64.eq", Wasm::I64Eq),
+    ("i64.ne", Wasm::I64Ne),
+    ("i64.lt_s", Wasm::I64LtS),
+    ("i64.lt_u", Wasm::I64LtU),
+    ("i64.gt_s", Wasm::I64GtS),
+    ("i64.gt_u", Wasm::I64GtU),
+    ("i64.le_s", Wasm::I64LeS),
+    ("i64.le_u", Wasm::I64LeU),
+    ("i64.ge_s", Wasm::I64GeS),
+    ("i64.ge_u", Wasm::I64GeU),
+    ("f32.eq", Wasm::F32Eq),
+    ("f32.ne", Wasm::F32Ne),
+    ("f32.lt", Wasm::F32Lt),
+    ("f32.gt", Wasm::F32Gt),
+    ("f32.le", Wasm::F32Le),
+    ("f32.ge", Wasm::F32Ge),
+    ("f64.eq", Wasm::F64Eq),
+    ("f64.ne", Wasm::F64Ne),
+    ("f64.lt", Wasm::F64Lt),
+    ("f64.gt", Wasm::F64Gt),
+    ("f64.le", Wasm::F64Le),
+    ("f64.ge", Wasm::F64Ge),
+    ("i32.clz", Wasm::I32Clz),
+    ("i32.ctz", Wasm::I32Ctz),
+    ("i32.popcnt", Wasm::I32Popcnt),
+    ("i32.add", Wasm::I32Add),
+    ("i32.sub", Wasm::I32Sub),
+    ("i32.mul", Wasm::I32Mul),
+    ("i32.div_s", Wasm::I32DivS),
+    ("i32.div_u", Wasm::I32DivU),
+    ("i32.rem_s", Wasm::I32RemS),
+    ("i32.rem_u", Wasm::I32RemU),
+    ("i32.and", Wasm::I32And),
+    ("i32.or", Wasm::I32Or),
+    ("i32.xor", Wasm::I32Xor),
+    ("i32.shl", Wasm::I32Shl),
+    ("i32.shr_s", Wasm::I32ShrS),
+    ("i32.shr_u", Wasm::I32ShrU),
+    ("i32.rotl", Wasm::I32Rotl),
+    ("i32.rotr", Wasm::I32Rotr),
+    ("i64.clz", Wasm::I64Clz),
+    ("i64.ctz", Wasm::I64Ctz),
+    ("i64.popcnt", Wasm::I64Popcnt),
+    ("i64.add", Wasm::I64Add),
+    ("i64.sub", Wasm::I64Sub),
+    ("i64.mul", Wasm::I64Mul),
+    ("i64.div_s", Wasm::I64DivS),
+    ("i64.div_u", Wasm::I64DivU),
+    ("i64.rem_s", Wasm::I64RemS),
+    ("i64.rem_u", Wasm::I64RemU),
+    ("i64.and", Wasm::I64And),
+    ("i64.or", Wasm::I64Or),
+    ("i64.xor", Wasm::I64Xor),
+    ("i64.shl", Wasm::I64Shl),
+    ("i64.shr_s", Wasm::I64ShrS),
+    ("i64.shr_u", Wasm::I64ShrU),
+    ("i64.rotl", Wasm::I64Rotl),
+    ("i64.rotr", Wasm::I64Rotr),
+    ("f32.abs", Wasm::F32Abs),
+    ("f32.neg", Wasm::F32Neg),
+    ("f32.ceil", Wasm::F32Ceil),
+    ("f32.floor", Wasm::F32Floor),
+    ("f32.trunc", Wasm::F32Trunc),
+    ("f32.nearest", Wasm::F32Nearest),
+    ("f32.sqrt", Wasm::F32Sqrt),
+    ("f32.add", Wasm::F32Add),
+    ("f32.sub", Wasm::F32Sub),
+    ("f32.mul", Wasm::F32Mul),
+    ("f32.div", Wasm::F32Div),
+    ("f32.min", Wasm::F32Min),
+    ("f32.max", Wasm::F32Max),
+    ("f32.copysign", Wasm::F32Copysign),
+    ("f64.abs", Wasm::F64Abs),
+    ("f64.neg", Wasm::F64Neg),
+    ("f64.ceil", Wasm::F64Ceil),
+    ("f64.floor", Wasm::F64Floor),
+    ("f64.trunc", Wasm::F64Trunc),
+    ("f64.nearest", Wasm::F64Nearest),
+    ("f64.sqrt", Wasm::F64Sqrt),
+    ("f64.add", Wasm::F64Add),
+    ("f64.sub", Wasm::F64Sub),
+    ("f64.mul", Wasm::F64Mul),
+    ("f64.div", Wasm::F64Div),
+    ("f64.min", Wasm::F64Min),
+    ("f64.max", Wasm::F64Max),
+    ("f64.copysign", Wasm::F64Copysign),
+    ("i32.wrap_i64", Wasm::I32WrapI64),
+    ("i32.trunc_f32_s", Wasm::I32TruncF32S),
+    ("i32.trunc_f32_u", Wasm::I32TruncF32U),
+    ("i32.trunc_f64_s", Wasm::I32TruncF64S),
+    ("i32.trunc_f64_u", Wasm::I32TruncF64U),
+    ("i64.extend_i32_s", Wasm::I64ExtendI32S),
+    ("i64.extend_i32_u", Wasm::I64ExtendI32U),
+    ("i64.trunc_f32_s", Wasm::I64TruncF32S),
+    ("i64.trunc_f32_u", Wasm::I64TruncF32U),
+    ("i64.trunc_f64_s", Wasm::I64TruncF64S),
+    ("i64.trunc_f64_u", Wasm::I64TruncF64U),
+    ("f32.convert_i32_s", Wasm::F32ConvertI32S),
+    ("f32.convert_i32_u", Wasm::F32ConvertI32U),
+    ("f32.convert_i64_s", Wasm::F32ConvertI64S),
+    ("f32.convert_i64_u", Wasm::F32ConvertI64U),
+    ("f32.demote_f64", Wasm::F32DemoteF64),
+    ("f64.convert_i32_s", Wasm::F64ConvertI32S),
+    ("f64.convert_i32_u", Wasm::F64ConvertI32U),
+    ("f64.convert_i64_s", Wasm::F64ConvertI64S),
+    ("f64.convert_i64_u", Wasm::F64ConvertI64U),
+    ("f64.promote_f32", Wasm::F64PromoteF32),
+    ("i32.reinterpret_f32", Wasm::I32ReinterpretF32),
+    ("i64.reinterpret_f64", Wasm::I64ReinterpretF64),
+    ("f32.reinterpret_i32", Wasm::F32ReinterpretI32),
+    ("f64.reinterpret_i64", Wasm::F64ReinterpretI64),
+    ("i32.extend8_s", Wasm::I32Extend8S),
+    ("i32.extend16_s", Wasm::I32Extend16S),
+    ("i64.extend8_s", Wasm::I64Extend8S),
+    ("i64.extend16_s", Wasm::I64Extend16S),
+    ("i64.extend32_s", Wasm::I64Extend32S),
+    ("i32.trunc_sat_f32_s", Wasm::I32TruncSatF32S),
+    ("i32.trunc_sat_f32_u", Wasm::I32TruncSatF32U),
+    ("i32.trunc_sat_f64_s", Wasm::I32TruncSatF64S),
+    ("i32.trunc_sat_f64_u", Wasm::I32TruncSatF64U),
+    ("i64.trunc_sat_f32_s", Wasm::I64TruncSatF32S),
+    ("i64.trunc_sat_f32_u", Wasm::I64TruncSatF32U),
+    ("i64.trunc_sat_f64_s", Wasm::I64TruncSatF64S),
+    ("i64.trunc_sat_f64_u", Wasm::I64TruncSatF64U),
+];
+
+/// 線形メモリを読み書きする命令を `MemArg` から作る関数
+type MemoryInstruction = fn(MemArg) -> Wasm<'static>;
+
+/// `wasm!` で使える線形メモリの読み書きの命令と、読み書きするバイト数の2の対数（省略したときのアラインメント）
+const MEMORY_INSTRUCTIONS: &[(&str, MemoryInstruction, u32)] = &[
+    ("i32.load", Wasm::I32Load, 2),
+    ("i64.load", Wasm::I64Load, 3),
+    ("f32.load", Wasm::F32Load, 2),
+    ("f64.load", Wasm::F64Load, 3),
+    ("i32.load8_s", Wasm::I32Load8S, 0),
+    ("i32.load8_u", Wasm::I32Load8U, 0),
+    ("i32.load16_s", Wasm::I32Load16S, 1),
+    ("i32.load16_u", Wasm::I32Load16U, 1),
+    ("i64.load8_s", Wasm::I64Load8S, 0),
+    ("i64.load8_u", Wasm::I64Load8U, 0),
+    ("i64.load16_s", Wasm::I64Load16S, 1),
+    ("i64.load16_u", Wasm::I64Load16U, 1),
+    ("i64.load32_s", Wasm::I64Load32S, 2),
+    ("i64.load32_u", Wasm::I64Load32U, 2),
+    ("i32.store", Wasm::I32Store, 2),
+    ("i64.store", Wasm::I64Store, 3),
+    ("f32.store", Wasm::F32Store, 2),
+    ("f64.store", Wasm::F64Store, 3),
+    ("i32.store8", Wasm::I32Store8, 0),
+    ("i32.store16", Wasm::I32Store16, 1),
+    ("i64.store8", Wasm::I64Store8, 0),
+    ("i64.store16", Wasm::I64Store16, 1),
+    ("i64.store32", Wasm::I64Store32, 2),
+];
+
+/// `wasm!` のテンプレート（WebAssemblyのテキスト形式の命令を空白で区切って並べたもの）を命令に変換する
+///
+/// 使えるのは `PLAIN_INSTRUCTIONS` と `MEMORY_INSTRUCTIONS`（`offset=` と `align=` を指定できる）の命令、
+/// `local.get`・`local.set`・`local.tee`・`*.const`・`br`・`br_if`・`memory.size`・`memory.grow` で、
+/// `block`・`loop`・`if` は結果を持たず、テンプレートの中で閉じていなければならない。`;;` から行末まではコメント。
+fn parse_wasm_text(text: &str) -> Result<Vec<Wasm<'static>>> {
+    let error = |message: String| EidosError::BackendError(format!("wasm! のテンプレート: {}", message));
+    let mut tokens = text
+        .lines()
+        .flat_map(|line| line.split(";;").next().unwrap_or_default().split_whitespace())
+        .peekable();
+    let mut instructions = Vec::new();
+    // 開いている `block`・`loop`・`if` の数
+    let mut depth = 0u32;
+    while let Some(name) = tokens.next() {
+        let mut immediate = || tokens.next().ok_or_else(|| error(format!("命令 '{}' の即値がありません", name)));
+        let instruction = match name {
+            "local.get" | "local.set" | "local.tee" => {
+                let text = immediate()?;
+                let index = text.parse().map_err(|_| error(format!("'{}' はローカル変数の番号ではありません", text)))?;
+                match name {
+                    "local.get" => Wasm::LocalGet(index),
+                    "local.set" => Wasm::LocalSet(index),
+                    _ => Wasm::LocalTee(index),
+                }
+            }
+            "i32.const" | "i64.const" => {
+                let text = immediate()?;
+                let value = parse_wasm_integer(text).ok_or_else(|| error(format!("'{}' は整数ではありません", text)))?;
+                if name == "i32.const" {
+                    if value < i32::MIN as i64 || value > u32::MAX as i64 {
+                        return Err(error(format!("{} は i32 の範囲を超えています", text)));
+                    }
+                    Wasm::I32Const(value as i32)
+                } else {
+                    Wasm::I64Const(value)
+                }
+            }
+            "f32.const" | "f64.const" => {
+                let text = immediate()?;
+                let value: f64 = text.parse().map_err(|_| error(format!("'{}' は浮動小数点数ではありません", text)))?;
+                if name == "f32.const" { Wasm::F32Const(value as f32) } else { Wasm::F64Const(value) }
+            }
+            "block" | "loop" | "if" => {
+                depth += 1;
+                match name {
+                    "block" => Wasm::Block(BlockType::Empty),
+                    "loop" => Wasm::Loop(BlockType::Empty),
+                    _ => Wasm::If(BlockType::Empty),
+                }
+            }
+            "else" if depth > 0 => Wasm::Else,
+            "end" if depth > 0 => {
+                depth -= 1;
+                Wasm::End
+            }
+            "else" | "end" => return Err(error(format!("'{}' に対応する block・loop・if がありません", name))),
+            "br" | "br_if" => {
+                let text = immediate()?;
+                let label: u32 = text.parse().map_err(|_| error(format!("'{}' はラベルの番号ではありません", text)))?;
+                // 分岐先は関数の制御構造とぶつからないよう、テンプレートの中のブロックに限る
+                if label >= depth {
+                    return Err(error(format!("{} {} の分岐先がテンプレートの外にあります", name, label)));
+                }
+                if name == "br" { Wasm::Br(label) } else { Wasm::BrIf(label) }
+            }
+            "memory.size" => Wasm::MemorySize(0),
+            "memory.grow" => Wasm::MemoryGrow(0),
+            _ => {
+                if let Some((_, make, natural)) = MEMORY_INSTRUCTIONS.iter().find(|(memory, ..)| *memory == name) {
+                    let mut memarg = MemArg { offset: 0, align: *natural, memory_index: 0 };
+                    while let Some(option) = tokens.next_if(|token| token.starts_with("offset=") || token.starts_with("align=")) {
+                        let (key, value) = option.split_once('=').unwrap_or_default();
+                        let value = parse_wasm_integer(value)
+                            .filter(|value| *value >= 0 && (key == "offset" || (*value as u64).is_power_of_two()))
+                            .ok_or_else(|| error(format!("'{}' は {} の指定として正しくありません", option, name)))?;
+                        if key == "offset" {
+                            memarg.offset = value as u64;
+                        } else {
+                            memarg.align = value.trailing_zeros();
+                        }
+                    }
+                    make(memarg)
+                } else if let Some((_, instruction)) = PLAIN_INSTRUCTIONS.iter().find(|(plain, _)| *plain == name) {
+                    instruction.clone()
+                } else {
+                    return Err(error(format!("命令 '{}' には対応していません", name)));
+                }
+            }
+        };
+        instructions.push(instruction);
+    }
+    if depth > 0 {
+        return Err(error("閉じていない block・loop・if があります".to_string()));
+    }
+    Ok(instructions)
+}
+
+/// WebAssemblyのテキスト形式の整数（符号、`0x`、桁区切りの `_` を含む）
+fn parse_wasm_integer(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let digits = digits.replace('_', "");
+    let magnitude = match digits.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => digits.parse::<u64>().ok()?,
+    };
+    if negative {
+        (magnitude <= 1 << 63).then(|| (magnitude as i64).wrapping_neg())
+    } else {
+        Some(magnitude as i64)
+    }
+}
+
+/// グローバル変数の初期値
+fn const_expr(literal: &Literal, ty: WasmType, strings: &mut StringTable) -> ConstExpr {
+    match (literal, ty.val_type()) {
+        (Literal::Int(value), Some(ValType::I32)) => ConstExpr::i32_const(*value as i32),
+        (Literal::Int(value), Some(ValType::F64)) => ConstExpr::f64_const(*value as f64),
+        (Literal::Int(value), Some(ValType::F32)) => ConstExpr::f32_const(*value as f32),
+        (Literal::Int(value), _) => ConstExpr::i64_const(*value),
+        (Literal::Float(value), Some(ValType::F32)) => ConstExpr::f32_const(*value as f32),
+        (Literal::Float(value), _) => ConstExpr::f64_const(*value),
+        (Literal::Bool(value), _) => ConstExpr::i32_const(*value as i32),
+        (Literal::Char(value), _) => ConstExpr::i32_const(*value as i32),
+        (Literal::String(value), _) => ConstExpr::i32_const(strings.intern(value) as i32),
+        (Literal::Unit, _) => ConstExpr::i32_const(0),
+    }
+}
+
+fn zero(ty: ValType) -> ConstExpr {
+    match ty {
+        ValType::I64 => ConstExpr::i64_const(0),
+        ValType::F32 => ConstExpr::f32_const(0.0),
+        ValType::F64 => ConstExpr::f64_const(0.0),
+        _ => ConstExpr::i32_const(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::FunctionId;
+    use crate::core::eir_builder::build_test_module;
+    
+    fn compile(source: &str) -> Result<Vec<u8>> {
+        WasmBackend::new().emit(&build_test_module("test", source)?)
+    }
+    
+    fn run(source: &str) -> i32 {
+        let bytes = compile(source).unwrap();
+        wasmparser::Validator::new().validate_all(&bytes).unwrap();
+        WasmRuntime::new().unwrap().run_module(&bytes).unwrap()
+    }
+    
+    #[test]
+    fn test_loops_and_calls() {
+        let source = "
+            fn fib(n: Int) -> Int { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }
+            fn main() -> Int {
+                var total = 0;
+                var i = 0;
+                while i < 10 { total += fib(i); i += 1 }
+                total
+            }";
+        assert_eq!(run(source), 88);
+    }
+    
+    #[test]
+    fn test_sized_integers_wrap() {
+        assert_eq!(run("fn main() -> Int { let x: i8 = 127; let y: i8 = x + 1; y as Int }"), -128);
+        assert_eq!(run("fn main() -> Int { let x: u8 = 0; let y: u8 = x - 1; y as Int }"), 255);
+        assert_eq!(run("fn main() -> Int { let x = 7.9; x as Int }"), 7);
+    }
+    
+    #[test]
+    fn test_inline_wasm() {
+        let source = r#"
+            effect fn max(a: Int, b: Int) -> Int {
+                wasm!("local.get {1} local.get {2} local.get {1} local.get {2} i64.gt_s select local.set {0}", out(Int), in(a), in(b))
+            }
+            effect fn bits(x: i32) -> i32 {
+                wasm!("local.get {1} i32.popcnt local.set {0} ;; 立っているビットの数", out(i32), in(x))
+            }
+            effect fn main() -> Int {
+                max(3, 9) * 10 + bits(255 as i32) as Int
+            }
+        "#;
+        assert_eq!(run(source), 98);
+        
+        let error = compile("effect fn f(x: Int) -> Int { wasm!(\"local.get {1} i64.frobnicate local.set {0}\", out(Int), in(x)) }")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("i64.frobnicate"), "{}", error);
+        let error = compile("effect fn f(x: Int) -> Int { wasm!(\"block br 1 end local.get {1} local.set {0}\", out(Int), in(x)) }")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("br 1"), "{}", error);
+        let error = compile("effect fn f(x: Int) -> Int { asm!(\"popcnt {1}, {0}\", out(Int), in(x)) }").unwrap_err().to_string();
+        assert!(error.contains("asm!"), "{}", error);
+    }
+    
+    #[test]
+    fn test_arrays_and_slices() {
+        let source = r#"
+            fn sum(values: &[Int]) -> Int {
+                let mut total = 0;
+                let mut i = 0;
+                while i < len(values) {
+                    total = total + values[i];
+                    i = i + 1;
+                }
+                total
+            }
+            fn main() -> Int {
+                let mut a: [Int; 4] = [1, 2, 3, 4];
+                a[3] = 10;
+                let b = a;
+                a[0] = 100;
+                let bytes: [u8; 3] = [200 as u8, 100 as u8, 1 as u8];
+                sum(&a) * 1000 + sum(&b) + [7; 3][2] + (bytes[0] + bytes[1]) as Int * 1000000
+            }
+        "#;
+        // 配列の代入は要素をコピーし、u8 の要素は1バイトずつ読み書きする
+        assert_eq!(run(source), 44_115_023);
+    }
+    
+    #[test]
+    fn test_tuples() {
+        let source = r#"
+            fn divmod(a: Int, b: Int) -> (Int, Int) {
+                (a / b, a % b)
+            }
+            fn stats(x: Int) -> (Int, Float, Bool, Int, Int) {
+                (x, 1.5, x > 0, x * 2, x * 3)
+            }
+            fn main() -> Int {
+                let (q, r) = divmod(17, 5);
+                let (a, _, positive, b, c) = stats(4);
+                let mut p = (1, 2);
+                let old = p;
+                p = (10, 20);
+                let (x, y) = old;
+                let (z, _) = p;
+                let sign = if positive { 0 } else { 5000000 };
+                q * 1000000 + r * 100000 + (a + b + c) * 1000 + z * 10 + x + y + sign
+            }
+        "#;
+        // タプルを返す関数は要素を複数の戻り値として返す
+        let bytes = compile(source).unwrap();
+        let results = wasmparser::Parser::new(0).parse_all(&bytes).find_map(|payload| match payload.unwrap() {
+            wasmparser::Payload::TypeSection(reader) => reader
+                .into_iter_err_on_gc_types()
+                .map(|ty| ty.unwrap())
+                .find(|ty| ty.results().len() == 5)
+                .map(|ty| ty.results().to_vec()),
+            _ => None,
+        });
+        use wasmparser::ValType as V;
+        assert_eq!(results, Some(vec![V::I64, V::F64, V::I32, V::I64, V::I64]));
+        assert_eq!(run(source), 3_224_103);
+    }
+    
+    #[test]
+    fn test_for_loops_and_comprehensions() {
+        let source = r#"
+            fn sum(values: &[Int]) -> Int {
+                let mut total = 0;
+                for value in values {
+                    total = total + value;
+                }
+                total
+            }
+            fn main() -> Int {
+                let mut squares = 0;
+                for i in 0..5 {
+                    squares = squares + i * i;
+                }
+                let primes: [Int; 4] = [2, 3, 5, 7];
+                let mut product = 1;
+                for p in primes {
+                    product = product * p;
+                }
+                let xs: [Int; 5] = [3, -1, 4, -1, 5];
+                let doubled = [x * 2 for x in xs];
+                let positive = [x for x in xs if x > 0];
+                squares * 100000 + product * 100 + sum(primes) + sum(doubled) * 10000000 + sum(positive) * 1000
+            }
+        "#;
+        assert_eq!(run(source), 203_033_017);
+    }
+    
+    #[test]
+    fn test_stack_overflow() {
+        // シャドウスタックを使い切る再帰は、線形メモリの文字列を壊さずにパニックする
+        let source = r#"
+            fn deep(n: Int) -> Int {
+                let values: [Int; 64] = [n; 64];
+                if n == 0 { 0 } else { deep(n - 1) + values[63] }
+            }
+            fn main() -> Int { deep(100000) }
+        "#;
+        assert_eq!(run(source), 101);
+        assert_eq!(run(&source.replace("100000", "100")), 5050);
+    }
+    
+    #[test]
+    fn test_indirect_call() {
+        let mut module = eir::Module::new("test");
+        let int = module.add_type(Type::int());
+        let unit = module.add_type(Type::unit());
+        module.add_global("seen", eir::Global {
+            name: "seen".to_string(),
+            ty: int,
+            initializer: Some(Literal::Int(0)),
+            linkage: eir::Linkage::Internal,
+            alignment: None,
+            attributes: GlobalAttributes::default(),
+        });
+        let seen = Operand::Global("seen".to_string());
+        
+        let record_type = module.add_type(Type::function(vec![Type::int()], Type::unit()));
+        let mut record = Function::new(FunctionId(0), "record", record_type, unit);
+        let value = record.add_parameter("value", int);
+        let doubled = record.create_register(int);
+        let entry = record.entry_block;
+        record.add_instruction(entry, Instruction::BinaryOp {
+            op: BinaryOp::Mul,
+            lhs: Operand::Register(value),
+            rhs: Operand::Literal(Literal::Int(2)),
+            result: doubled,
+        });
+        record.add_instruction(entry, Instruction::Store { address: seen.clone(), value: Operand::Register(doubled) });
+        record.get_block_mut(entry).unwrap().set_terminator(Terminator::Return { value: None });
+        module.add_function(record);
+        
+        // 関数の値で `record` を呼び出し、戻ってから分岐先のブロックでグローバル変数を読む
+        let main_type = module.add_type(Type::function(Vec::new(), Type::int()));
+        let mut main = Function::new(FunctionId(1), "main", main_type, int);
+        let result = main.create_register(int);
+        let entry = main.entry_block;
+        let done = main.create_block();
+        main.get_block_mut(entry).unwrap().set_terminator(Terminator::IndirectCall {
+            function_ptr: Operand::Function(FunctionId(0)),
+            arguments: vec![Operand::Literal(Literal::Int(21))],
+            return_block: done,
+            return_args: Vec::new(),
+        });
+        main.add_instruction(done, Instruction::Load { address: seen, result });
+        main.get_block_mut(done).unwrap().set_terminator(Terminator::Return { value: Some(Operand::Register(result)) });
+        module.add_function(main);
+        module.set_entry_point(FunctionId(1));
+        
+        let bytes = WasmBackend::new().emit(&module).unwrap();
+        wasmparser::Validator::new().validate_all(&bytes).unwrap();
+        assert_eq!(WasmRuntime::new().unwrap().run_module(&bytes).unwrap(), 42);
+    }
+    
+    #[test]
+    fn test_atomics() {
+        let mut module = eir::Module::new("test");
+        let int = module.add_type(Type::int());
+        let boolean = module.add_type(Type::bool());
+        module.add_global("counter", eir::Global {
+            name: "counter".to_string(),
+            ty: int,
+            initializer: Some(Literal::Int(5)),
+            linkage: eir::Linkage::Internal,
+            alignment: None,
+            attributes: GlobalAttributes::default(),
+        });
+        let counter = Operand::Global("counter".to_string());
+        let func_type = module.add_type(Type::function(Vec::new(), Type::int()));
+        let mut main = Function::new(FunctionId(0), "main", func_type, int);
+        let entry = main.entry_block;
+        let atomic = |main: &mut Function, op, address: &Operand, value: Option<i64>, expected: Option<i64>, ty| {
+            let result = main.create_register(ty);
+            main.add_instruction(entry, Instruction::Atomic {
+                op,
+                address: address.clone(),
+                value: value.map(|value| Operand::Literal(Literal::Int(value))),
+                expected: expected.map(|value| Operand::Literal(Literal::Int(value))),
+                result: Some(result),
+            });
+            Operand::Register(result)
+        };
+        let previous = atomic(&mut main, AtomicOp::Add, &counter, Some(1), None, int);
+        let swapped = atomic(&mut main, AtomicOp::CAS, &counter, Some(10), Some(6), boolean);
+        let failed = atomic(&mut main, AtomicOp::CAS, &counter, Some(20), Some(6), boolean);
+        let before_sub = atomic(&mut main, AtomicOp::Sub, &counter, Some(4), None, int);
+        let loaded = atomic(&mut main, AtomicOp::Load, &counter, None, None, int);
+        // アドレスを渡すスロットはシャドウスタックに置くので、アトミック操作の対象にできる
+        let slot = main.create_register(int);
+        main.add_instruction(entry, Instruction::Alloca { size: 8, result: slot });
+        main.add_instruction(entry, Instruction::Store {
+            address: Operand::Register(slot),
+            value: Operand::Literal(Literal::Int(7)),
+        });
+        let before_xor = atomic(&mut main, AtomicOp::Xor, &Operand::Register(slot), Some(5), None, int);
+        let after_xor = main.create_register(int);
+        main.add_instruction(entry, Instruction::Load { address: Operand::Register(slot), result: after_xor });
+        
+        let mut total = Operand::Literal(Literal::Int(0));
+        for (value, scale) in [
+            (previous, 1_000),
+            (swapped, 100_000),
+            (failed, 1_000_000),
+            (before_sub, 10),
+            (loaded, 1),
+            (before_xor, 10_000_000),
+            (Operand::Register(after_xor), 100_000_000),
+        ] {
+            let value = match value {
+                Operand::Register(reg) if main.get_register_type(reg) == Some(boolean) => {
+                    let cast = main.create_register(int);
+                    main.add_instruction(entry, Instruction::Cast { value, target_type: int, result: cast });
+                    Operand::Register(cast)
+                }
+                value => value,
+            };
+            let scaled = main.create_register(int);
+            main.add_instruction(entry, Instruction::BinaryOp {
+                op: BinaryOp::Mul,
+                lhs: value,
+                rhs: Operand::Literal(Literal::Int(scale)),
+                result: scaled,
+            });
+            let sum = main.create_register(int);
+            main.add_instruction(entry, Instruction::BinaryOp {
+                op: BinaryOp::Add,
+                lhs: total,
+                rhs: Operand::Register(scaled),
+                result: sum,
+            });
+            total = Operand::Register(sum);
+        }
+        main.get_block_mut(entry).unwrap().set_terminator(Terminator::Return { value: Some(total) });
+        module.add_function(main);
+        module.set_entry_point(FunctionId(0));
+        
+        let bytes = WasmBackend::new().emit(&module).unwrap();
+        let features = wasmparser::WasmFeatures { threads: true, ..Default::default() };
+        wasmparser::Validator::new_with_features(features).validate_all(&bytes).unwrap();
+        assert_eq!(WasmRuntime::new().unwrap().run_module(&bytes).unwrap(), 270_105_106);
+    }
+    
+    #[test]
+    fn test_imports_and_exports() {
+        let bytes = compile("fn main() { println(\"hello\", 1, true) }").unwrap();
+        let mut imports = Vec::new();
+        let mut exports = Vec::new();
+        for payload in wasmparser::Parser::new(0).parse_all(&bytes) {
+            match payload.unwrap() {
+                wasmparser::Payload::ImportSection(reader) => {
+                    imports.extend(reader.into_iter().map(|import| import.unwrap().name.to_string()));
+                }
+                wasmparser::Payload::ExportSection(reader) => {
+                    exports.extend(reader.into_iter().map(|export| export.unwrap().name.to_string()));
+                }
+                _ => {}
+            }
+        }
+        let host: Vec<String> = HOST_FUNCTIONS.iter().map(|(name, _)| name.to_string()).collect();
+        assert_eq!(imports, host);
+        assert!(exports.contains(&"memory".to_string()) && exports.contains(&"main".to_string()), "{:?}", exports);
+        // 文字列リテラルはバイト数に続けて線形メモリに置く
+        assert!(bytes.windows(9).any(|window| window == b"\x05\0\0\0hello"));
+    }
+    
+    #[test]
+    fn test_unsupported_values() {
+        let mut module = eir::Module::new("test");
+        let int = module.add_type(Type::int());
+        let pair = module.add_type(Type::tuple(vec![Type::int(), Type::bigint()]));
+        let func_type = module.add_type(Type::function(vec![], Type::int()));
+        let mut function = Function::new(FunctionId(0), "pair", func_type, int);
+        let result = function.create_register(pair);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::Tuple {
+            elements: vec![Operand::Literal(Literal::Int(1)), Operand::Literal(Literal::Int(2))],
+            result,
+        });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Literal(Literal::Int(0))),
+        });
+        module.add_function(function);
+        
+        let error = WasmBackend::new().emit(&module).unwrap_err().to_string();
+        assert!(error.contains("関数 'pair'"), "{}", error);
+    }
+}
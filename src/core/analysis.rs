@@ -0,0 +1,609 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::rc::Rc;
+
+use crate::core::arena::IdMap;
+use crate::core::eir::{BlockId, Function, FunctionId, Terminator};
+
+/// 関数の制御フローグラフ
+///
+/// 各ブロックの後続・先行ブロックと、エントリーブロックから到達できるブロックの後行順を持つ。
+/// 後行順は後続ブロックを分岐命令に書かれた順に深さ優先でたどって求めるので、同じ関数からは常に同じ順序になる。
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    entry: BlockId,
+    successors: IdMap<BlockId, Vec<BlockId>>,
+    predecessors: IdMap<BlockId, Vec<BlockId>>,
+    post_order: Vec<BlockId>,
+    /// 到達できるブロックの後行順での位置
+    post_order_index: IdMap<BlockId, usize>,
+}
+
+impl Cfg {
+    /// 関数の制御フローグラフを構築
+    pub fn new(function: &Function) -> Self {
+        let mut successors = IdMap::new();
+        let mut predecessors: IdMap<BlockId, Vec<BlockId>> =
+            function.blocks.keys().map(|id| (*id, Vec::new())).collect();
+        for (id, block) in &function.blocks {
+            let targets = block.successors();
+            for target in &targets {
+                if let Some(preds) = predecessors.get_mut(target) {
+                    preds.push(*id);
+                }
+            }
+            successors.insert(*id, targets);
+        }
+        
+        let entry = function.entry_block;
+        let mut post_order = Vec::new();
+        if function.blocks.contains_key(&entry) {
+            let mut visited = HashSet::from([entry]);
+            // （ブロック, 次にたどる後続ブロックの位置）
+            let mut stack = vec![(entry, 0)];
+            while let Some(top) = stack.last_mut() {
+                let (block, next) = *top;
+                top.1 += 1;
+                match successors[&block].get(next).copied() {
+                    Some(target) => {
+                        if function.blocks.contains_key(&target) && visited.insert(target) {
+                            stack.push((target, 0));
+                        }
+                    }
+                    None => {
+                        post_order.push(block);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        let post_order_index = post_order.iter().enumerate().map(|(index, block)| (*block, index)).collect();
+        
+        Self {
+            entry,
+            successors,
+            predecessors,
+            post_order,
+            post_order_index,
+        }
+    }
+    
+    /// エントリーブロック
+    pub fn entry(&self) -> BlockId {
+        self.entry
+    }
+    
+    /// 後続ブロック
+    pub fn successors(&self, block: BlockId) -> &[BlockId] {
+        self.successors.get(&block).map_or(&[], Vec::as_slice)
+    }
+    
+    /// 先行ブロック（到達できないブロックからの分岐も含む）
+    pub fn predecessors(&self, block: BlockId) -> &[BlockId] {
+        self.predecessors.get(&block).map_or(&[], Vec::as_slice)
+    }
+    
+    /// 到達できるブロックの後行順（後続ブロックが先、エントリーブロックが最後）
+    pub fn post_order(&self) -> &[BlockId] {
+        &self.post_order
+    }
+    
+    /// 到達できるブロックの逆後行順（エントリーブロックが最初）
+    pub fn reverse_post_order(&self) -> impl DoubleEndedIterator<Item = BlockId> + '_ {
+        self.post_order.iter().rev().copied()
+    }
+    
+    /// エントリーブロックから到達できるか
+    pub fn is_reachable(&self, block: BlockId) -> bool {
+        self.post_order_index.contains_key(&block)
+    }
+}
+
+/// 支配木
+///
+/// Cooper・Harvey・Kennedy の反復法で直接支配ブロックを求める。
+/// 到達できないブロックは木に含まれず、どのブロックとも支配関係を持たない。
+#[derive(Debug, Clone)]
+pub struct DominatorTree {
+    /// 直接支配ブロック（エントリーブロックは自分自身）
+    idom: IdMap<BlockId, BlockId>,
+    children: IdMap<BlockId, Vec<BlockId>>,
+    /// 支配木を深さ優先でたどったときの（行きがけ順, 帰りがけ順）
+    intervals: IdMap<BlockId, (usize, usize)>,
+}
+
+impl DominatorTree {
+    /// 制御フローグラフから支配木を構築
+    pub fn new(cfg: &Cfg) -> Self {
+        let order: Vec<BlockId> = cfg.reverse_post_order().collect();
+        let index: IdMap<BlockId, usize> = order.iter().enumerate().map(|(i, block)| (*block, i)).collect();
+        
+        let mut idom = IdMap::new();
+        if let Some(&entry) = order.first() {
+            idom.insert(entry, entry);
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in order.iter().skip(1) {
+                let mut preds = cfg.predecessors(block).iter().copied().filter(|pred| idom.contains_key(pred));
+                let Some(first) = preds.next() else {
+                    continue;
+                };
+                let new_idom = preds.fold(first, |a, b| Self::intersect(&idom, &index, a, b));
+                if idom.get(&block) != Some(&new_idom) {
+                    idom.insert(block, new_idom);
+                    changed = true;
+                }
+            }
+        }
+        
+        let mut children: IdMap<BlockId, Vec<BlockId>> = order.iter().map(|block| (*block, Vec::new())).collect();
+        for &block in order.iter().skip(1) {
+            children[&idom[&block]].push(block);
+        }
+        
+        let mut intervals = IdMap::new();
+        if let Some(&entry) = order.first() {
+            let mut counter = 0;
+            let mut stack = vec![(entry, 0)];
+            let mut pre = IdMap::new();
+            pre.insert(entry, counter);
+            while let Some(top) = stack.last_mut() {
+                let (block, next) = *top;
+                top.1 += 1;
+                match children[&block].get(next).copied() {
+                    Some(child) => {
+                        counter += 1;
+                        pre.insert(child, counter);
+                        stack.push((child, 0));
+                    }
+                    None => {
+                        counter += 1;
+                        intervals.insert(block, (pre[&block], counter));
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        
+        Self { idom, children, intervals }
+    }
+    
+    /// 支配木で2つのブロックの共通の祖先をたどる
+    fn intersect(idom: &IdMap<BlockId, BlockId>, index: &IdMap<BlockId, usize>, mut a: BlockId, mut b: BlockId) -> BlockId {
+        while a != b {
+            while index[&a] > index[&b] {
+                a = idom[&a];
+            }
+            while index[&b] > index[&a] {
+                b = idom[&b];
+            }
+        }
+        a
+    }
+    
+    /// 直接支配ブロック（エントリーブロックと到達できないブロックは `None`）
+    pub fn immediate_dominator(&self, block: BlockId) -> Option<BlockId> {
+        self.idom.get(&block).copied().filter(|idom| *idom != block)
+    }
+    
+    /// 直接支配しているブロック
+    pub fn children(&self, block: BlockId) -> &[BlockId] {
+        self.children.get(&block).map_or(&[], Vec::as_slice)
+    }
+    
+    /// `a` が `b` を支配するか（ブロックは自分自身を支配する）
+    pub fn dominates(&self, a: BlockId, b: BlockId) -> bool {
+        match (self.intervals.get(&a), self.intervals.get(&b)) {
+            (Some(&(a_pre, a_post)), Some(&(b_pre, b_post))) => a_pre <= b_pre && b_post <= a_post,
+            _ => false,
+        }
+    }
+    
+    /// `a` が `b` を支配し、`a` と `b` が異なるか
+    pub fn strictly_dominates(&self, a: BlockId, b: BlockId) -> bool {
+        a != b && self.dominates(a, b)
+    }
+    
+    /// ブロックが支配するブロック（自分自身を含む、支配木の行きがけ順）
+    pub fn dominated_blocks(&self, block: BlockId) -> Vec<BlockId> {
+        let mut blocks = Vec::new();
+        if !self.intervals.contains_key(&block) {
+            return blocks;
+        }
+        let mut stack = vec![block];
+        while let Some(block) = stack.pop() {
+            blocks.push(block);
+            stack.extend(self.children(block).iter().rev());
+        }
+        blocks
+    }
+}
+
+/// ループ森の中のループのID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoopId(pub u32);
+
+/// 自然ループ
+#[derive(Debug, Clone)]
+pub struct Loop {
+    /// ループに入るときに必ず通るブロック
+    pub header: BlockId,
+    /// ヘッダーへ戻る分岐（バックエッジ）を持つブロック
+    pub latches: Vec<BlockId>,
+    /// ヘッダーを含む、ループに属するブロック
+    pub body: HashSet<BlockId>,
+    /// ループの外からヘッダーへ入る唯一のブロックで、ヘッダーにしか分岐しないもの
+    pub preheader: Option<BlockId>,
+    /// ループから出た先のブロック
+    pub exits: HashSet<BlockId>,
+    /// すぐ外側のループ
+    pub parent: Option<LoopId>,
+    /// すぐ内側のループ
+    pub children: Vec<LoopId>,
+    /// 入れ子の深さ（いちばん外側のループが1）
+    pub depth: usize,
+}
+
+impl Loop {
+    /// ブロックがループに属するか
+    pub fn contains(&self, block: BlockId) -> bool {
+        self.body.contains(&block)
+    }
+}
+
+/// 関数のループの入れ子構造
+///
+/// ヘッダーが後続ブロックを支配する分岐をバックエッジとし、同じヘッダーのバックエッジは1つのループにまとめる。
+/// ループのIDはヘッダーの逆後行順に振るので、外側のループは内側のループより小さいIDを持つ。
+#[derive(Debug, Clone)]
+pub struct LoopForest {
+    loops: Vec<Loop>,
+    /// ブロックが属するいちばん内側のループ
+    innermost: IdMap<BlockId, LoopId>,
+}
+
+impl LoopForest {
+    /// 制御フローグラフと支配木からループを検出
+    pub fn new(cfg: &Cfg, dominators: &DominatorTree) -> Self {
+        let mut latches: IdMap<BlockId, Vec<BlockId>> = IdMap::new();
+        for block in cfg.reverse_post_order() {
+            for &target in cfg.successors(block) {
+                if !dominators.dominates(target, block) {
+                    continue;
+                }
+                match latches.get_mut(&target) {
+                    Some(list) => list.push(block),
+                    None => {
+                        latches.insert(target, vec![block]);
+                    }
+                }
+            }
+        }
+        let headers: Vec<BlockId> = cfg.reverse_post_order().filter(|block| latches.contains_key(block)).collect();
+        
+        let mut loops: Vec<Loop> = headers
+            .into_iter()
+            .map(|header| Self::natural_loop(cfg, header, latches.remove(&header).unwrap_or_default()))
+            .collect();
+        
+        // すぐ外側のループは、ヘッダーを含むループのうち最も小さいもの
+        for i in 0..loops.len() {
+            let parent = (0..loops.len())
+                .filter(|&j| j != i && loops[j].body.len() > loops[i].body.len() && loops[j].contains(loops[i].header))
+                .min_by_key(|&j| loops[j].body.len());
+            if let Some(parent) = parent {
+                loops[i].parent = Some(LoopId(parent as u32));
+                loops[parent].children.push(LoopId(i as u32));
+                loops[i].depth = loops[parent].depth + 1;
+            }
+        }
+        
+        let mut innermost = IdMap::new();
+        for (i, lp) in loops.iter().enumerate() {
+            let mut blocks: Vec<BlockId> = lp.body.iter().copied().collect();
+            blocks.sort_by_key(|block| block.0);
+            for block in blocks {
+                innermost.insert(block, LoopId(i as u32));
+            }
+        }
+        
+        Self { loops, innermost }
+    }
+    
+    /// ヘッダーとバックエッジからループのブロックを集める
+    fn natural_loop(cfg: &Cfg, header: BlockId, latches: Vec<BlockId>) -> Loop {
+        let mut body = HashSet::from([header]);
+        let mut worklist = latches.clone();
+        while let Some(block) = worklist.pop() {
+            if body.insert(block) {
+                worklist.extend(cfg.predecessors(block).iter().filter(|pred| cfg.is_reachable(**pred)));
+            }
+        }
+        
+        let outside: Vec<BlockId> = cfg
+            .predecessors(header)
+            .iter()
+            .copied()
+            .filter(|pred| cfg.is_reachable(*pred) && !body.contains(pred))
+            .collect();
+        let preheader = match outside.as_slice() {
+            [pred] if cfg.successors(*pred) == [header] => Some(*pred),
+            _ => None,
+        };
+        let exits = body
+            .iter()
+            .flat_map(|block| cfg.successors(*block))
+            .filter(|target| !body.contains(target))
+            .copied()
+            .collect();
+        
+        Loop {
+            header,
+            latches,
+            body,
+            preheader,
+            exits,
+            parent: None,
+            children: Vec::new(),
+            depth: 1,
+        }
+    }
+    
+    /// すべてのループ（外側のループが先）
+    pub fn loops(&self) -> &[Loop] {
+        &self.loops
+    }
+    
+    pub fn get(&self, id: LoopId) -> &Loop {
+        &self.loops[id.0 as usize]
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.loops.is_empty()
+    }
+    
+    /// どのループにも含まれないループ
+    pub fn top_level(&self) -> impl Iterator<Item = LoopId> + '_ {
+        (0..self.loops.len() as u32).map(LoopId).filter(|id| self.get(*id).parent.is_none())
+    }
+    
+    /// 内側のループが先に来る順序（内側から変形するパスに使う）
+    pub fn innermost_first(&self) -> impl Iterator<Item = &Loop> + '_ {
+        self.loops.iter().rev()
+    }
+    
+    /// ブロックが属するいちばん内側のループ
+    pub fn innermost_loop(&self, block: BlockId) -> Option<LoopId> {
+        self.innermost.get(&block).copied()
+    }
+    
+    /// ブロックのループの入れ子の深さ（ループの外なら0）
+    pub fn loop_depth(&self, block: BlockId) -> usize {
+        self.innermost_loop(block).map_or(0, |id| self.get(id).depth)
+    }
+}
+
+/// 関数ごとの解析結果のキャッシュ
+///
+/// 解析は最初に求められたときに計算して、関数のIDごとに保持する。
+/// 関数の制御フローを書き換えたら、`invalidate` でその関数の結果を捨てなければならない。
+#[derive(Debug, Default)]
+pub struct AnalysisCache {
+    functions: HashMap<FunctionId, FunctionAnalyses>,
+}
+
+/// 1つの関数について計算済みの解析結果
+#[derive(Debug, Default)]
+struct FunctionAnalyses {
+    cfg: Option<Rc<Cfg>>,
+    dominators: Option<Rc<DominatorTree>>,
+    loops: Option<Rc<LoopForest>>,
+}
+
+impl AnalysisCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 制御フローグラフ
+    pub fn cfg(&mut self, function: &Function) -> Rc<Cfg> {
+        let analyses = self.functions.entry(function.id).or_default();
+        Rc::clone(analyses.cfg.get_or_insert_with(|| Rc::new(Cfg::new(function))))
+    }
+    
+    /// 支配木
+    pub fn dominators(&mut self, function: &Function) -> Rc<DominatorTree> {
+        let cfg = self.cfg(function);
+        let analyses = self.functions.entry(function.id).or_default();
+        Rc::clone(analyses.dominators.get_or_insert_with(|| Rc::new(DominatorTree::new(&cfg))))
+    }
+    
+    /// ループ森
+    pub fn loops(&mut self, function: &Function) -> Rc<LoopForest> {
+        let cfg = self.cfg(function);
+        let dominators = self.dominators(function);
+        let analyses = self.functions.entry(function.id).or_default();
+        Rc::clone(analyses.loops.get_or_insert_with(|| Rc::new(LoopForest::new(&cfg, &dominators))))
+    }
+    
+    /// 関数の解析結果を捨てる
+    pub fn invalidate(&mut self, id: FunctionId) {
+        self.functions.remove(&id);
+    }
+    
+    /// すべての関数の解析結果を捨てる
+    pub fn clear(&mut self) {
+        self.functions.clear();
+    }
+}
+
+/// 関数の制御フローグラフを Graphviz の DOT 形式で出力
+///
+/// 各ブロックは命令を並べたラベルの箱で示し、条件分岐の辺には真偽を `T`・`F` で添える。
+/// 到達できないブロックは灰色で示す。`dominators` なら、支配木の直接支配の関係を青い点線で重ねる。
+pub fn cfg_to_dot(function: &Function, title: &str, dominators: bool) -> String {
+    let cfg = Cfg::new(function);
+    let mut out = String::from("digraph cfg {\n");
+    let _ = writeln!(out, "    label={};\n    labelloc=t;", dot_string(title));
+    out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+    
+    for (id, block) in &function.blocks {
+        let mut lines = vec![format!("{}:", id)];
+        lines.extend(block.instructions.iter().map(|(_, instruction)| format!("    {}", instruction)));
+        if let Some(terminator) = &block.terminator {
+            lines.push(format!("    {}", terminator));
+        }
+        let label: Vec<String> = lines.iter().map(|line| dot_escape(line)).collect();
+        let style = if cfg.is_reachable(*id) { "" } else { ", color=gray, fontcolor=gray" };
+        let _ = writeln!(out, "    {} [label=\"{}\\l\"{}];", id, label.join("\\l"), style);
+    }
+    
+    for (id, block) in &function.blocks {
+        match &block.terminator {
+            Some(Terminator::BranchCond { true_target, false_target, .. }) if true_target != false_target => {
+                let _ = writeln!(out, "    {} -> {} [label=\"T\"];", id, true_target);
+                let _ = writeln!(out, "    {} -> {} [label=\"F\"];", id, false_target);
+            }
+            _ => {
+                for target in cfg.successors(*id) {
+                    let _ = writeln!(out, "    {} -> {};", id, target);
+                }
+            }
+        }
+    }
+    
+    if dominators {
+        let tree = DominatorTree::new(&cfg);
+        for block in cfg.reverse_post_order() {
+            if let Some(idom) = tree.immediate_dominator(block) {
+                let _ = writeln!(out, "    {} -> {} [style=dotted, color=blue, constraint=false];", idom, block);
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// DOT の文字列リテラル
+pub(crate) fn dot_string(text: &str) -> String {
+    format!("\"{}\"", dot_escape(text))
+}
+
+/// DOT の文字列リテラルの中で特別な意味を持つ文字をエスケープ
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::Operand;
+    use crate::core::ir_builder::IrBuilder;
+    use crate::core::types::TypeId;
+    
+    /// 入れ子のループと、到達できないブロックを持つ関数
+    ///
+    /// ```text
+    /// block_0 → block_1 ⇄ block_4、block_1 → block_5（出口）
+    /// block_1 → block_2 ⇄ block_3、block_2 → block_4
+    /// block_6 → block_1（到達しない）
+    /// ```
+    fn nested_loops() -> Function {
+        let int = TypeId(0);
+        let mut function = Function::new(FunctionId(0), "nested", TypeId(1), int);
+        let condition = Operand::Register(function.add_parameter("c", int));
+        let mut builder = IrBuilder::new(function);
+        let outer = builder.create_block();
+        let inner = builder.create_block();
+        let inner_latch = builder.create_block();
+        let outer_latch = builder.create_block();
+        let exit = builder.create_block();
+        let dead = builder.create_block();
+        builder.br(outer, Vec::new());
+        builder.position_at_end(outer);
+        builder.cond_br(condition.clone(), inner, exit);
+        builder.position_at_end(inner);
+        builder.cond_br(condition, inner_latch, outer_latch);
+        builder.position_at_end(inner_latch);
+        builder.br(inner, Vec::new());
+        builder.position_at_end(outer_latch);
+        builder.br(outer, Vec::new());
+        builder.position_at_end(exit);
+        builder.ret(None);
+        builder.position_at_end(dead);
+        builder.br(outer, Vec::new());
+        builder.finish()
+    }
+    
+    #[test]
+    fn test_cfg_and_dominators() {
+        let function = nested_loops();
+        let cfg = Cfg::new(&function);
+        let order: Vec<u32> = cfg.reverse_post_order().map(|block| block.0).collect();
+        assert_eq!(order, vec![0, 1, 5, 2, 4, 3]);
+        assert_eq!(cfg.predecessors(BlockId(1)), &[BlockId(0), BlockId(4), BlockId(6)]);
+        assert!(!cfg.is_reachable(BlockId(6)));
+        
+        let dominators = DominatorTree::new(&cfg);
+        assert_eq!(dominators.immediate_dominator(BlockId(0)), None);
+        assert_eq!(dominators.immediate_dominator(BlockId(4)), Some(BlockId(2)));
+        assert_eq!(dominators.immediate_dominator(BlockId(5)), Some(BlockId(1)));
+        assert!(dominators.dominates(BlockId(1), BlockId(3)));
+        assert!(dominators.dominates(BlockId(3), BlockId(3)));
+        assert!(!dominators.strictly_dominates(BlockId(3), BlockId(3)));
+        assert!(!dominators.dominates(BlockId(3), BlockId(4)));
+        assert!(!dominators.dominates(BlockId(0), BlockId(6)));
+        let mut dominated: Vec<u32> = dominators.dominated_blocks(BlockId(2)).iter().map(|block| block.0).collect();
+        dominated.sort();
+        assert_eq!(dominated, vec![2, 3, 4]);
+    }
+    
+    #[test]
+    fn test_cfg_to_dot() {
+        let function = nested_loops();
+        let dot = cfg_to_dot(&function, "nested \"before\"", true);
+        assert!(dot.starts_with("digraph cfg {\n    label=\"nested \\\"before\\\"\";\n"));
+        assert!(dot.contains("    block_0 [label=\"block_0:\\l    br block_1\\l\"];\n"));
+        assert!(dot.contains("    block_6 [label=\"block_6:\\l    br block_1\\l\", color=gray, fontcolor=gray];\n"));
+        assert!(dot.contains("    block_1 -> block_2 [label=\"T\"];\n    block_1 -> block_5 [label=\"F\"];\n"));
+        assert!(dot.contains("    block_2 -> block_4 [style=dotted, color=blue, constraint=false];\n"));
+        assert!(!dot.contains("-> block_6"));
+    }
+    
+    #[test]
+    fn test_loop_forest() {
+        let function = nested_loops();
+        let mut cache = AnalysisCache::new();
+        let loops = cache.loops(&function);
+        assert_eq!(loops.loops().len(), 2);
+        
+        let outer = loops.get(LoopId(0));
+        assert_eq!(outer.header, BlockId(1));
+        assert_eq!(outer.latches, vec![BlockId(4)]);
+        assert_eq!(outer.body.len(), 4);
+        assert_eq!(outer.preheader, Some(BlockId(0)));
+        assert_eq!(outer.exits, HashSet::from([BlockId(5)]));
+        assert_eq!(outer.children, vec![LoopId(1)]);
+        
+        let inner = loops.get(LoopId(1));
+        assert_eq!(inner.header, BlockId(2));
+        assert_eq!(inner.parent, Some(LoopId(0)));
+        // 外側のヘッダーは条件分岐なので、内側のループにはプリヘッダーがない
+        assert_eq!(inner.preheader, None);
+        assert_eq!(inner.exits, HashSet::from([BlockId(4)]));
+        
+        assert_eq!(loops.loop_depth(BlockId(3)), 2);
+        assert_eq!(loops.loop_depth(BlockId(4)), 1);
+        assert_eq!(loops.loop_depth(BlockId(6)), 0);
+        assert_eq!(loops.top_level().collect::<Vec<_>>(), vec![LoopId(0)]);
+        assert_eq!(loops.innermost_first().next().map(|lp| lp.header), Some(BlockId(2)));
+        
+        // 捨てるまでは同じ解析結果を使い回す
+        assert!(Rc::ptr_eq(&cache.cfg(&function), &cache.cfg(&function)));
+        let cfg = cache.cfg(&function);
+        cache.invalidate(function.id);
+        assert!(!Rc::ptr_eq(&cfg, &cache.cfg(&function)));
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 連番で振られ、アリーナの添字として使えるID
+pub trait ArenaKey: Copy + Eq {
+    /// アリーナ内の添字
+    fn index(self) -> usize;
+}
+
+/// 連番のIDをキーにする、ベクタ上のアリーナ
+///
+/// `HashMap` と同じ操作を提供しつつ、要素をIDの添字に直接置くためハッシュ計算が要らず、
+/// 走査は常にIDの昇順になる。削除した場所は空きとして残し、IDは再利用しない。
+#[derive(Clone)]
+pub struct IdMap<K, V> {
+    slots: Vec<Option<(K, V)>>,
+    len: usize,
+}
+
+impl<K: ArenaKey, V> IdMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+    
+    /// 要素を置き、同じIDの古い要素があれば返す
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let index = key.index();
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || None);
+        }
+        let old = self.slots[index].replace((key, value)).map(|(_, value)| value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+    
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.slots.get(key.index())?.as_ref().map(|(_, value)| value)
+    }
+    
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.slots.get_mut(key.index())?.as_mut().map(|(_, value)| value)
+    }
+    
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+    
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = self.slots.get_mut(key.index())?.take().map(|(_, value)| value);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+    
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+    
+    /// 条件を満たす要素だけを残す
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &mut V) -> bool) {
+        for slot in &mut self.slots {
+            if let Some((key, value)) = slot {
+                if !keep(key, value) {
+                    *slot = None;
+                    self.len -= 1;
+                }
+            }
+        }
+    }
+    
+    /// IDの昇順に、IDと要素を返す
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { slots: self.slots.iter() }
+    }
+    
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut { slots: self.slots.iter_mut() }
+    }
+    
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+    
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+    
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+}
+
+impl<K: ArenaKey, V> Default for IdMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: ArenaKey + fmt::Debug, V: fmt::Debug> fmt::Debug for IdMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: ArenaKey, V> Index<&K> for IdMap<K, V> {
+    type Output = V;
+    
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("IdMap に存在しないIDです")
+    }
+}
+
+impl<K: ArenaKey, V> IndexMut<&K> for IdMap<K, V> {
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key).expect("IdMap に存在しないIDです")
+    }
+}
+
+impl<K: ArenaKey, V> FromIterator<(K, V)> for IdMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+/// IDの昇順に並べた `(ID, 値)` の列として直列化する
+impl<K: ArenaKey + Serialize, V: Serialize> Serialize for IdMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // 空きの場所があるので、要素数はイテレータからではなく `len` で渡す
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+        for entry in self {
+            seq.serialize_element(&entry)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, K: ArenaKey + Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for IdMap<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl<'a, K: ArenaKey, V> IntoIterator for &'a IdMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: ArenaKey, V> IntoIterator for &'a mut IdMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+    
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// `IdMap` の要素をIDの昇順に返すイテレータ
+pub struct Iter<'a, K, V> {
+    slots: std::slice::Iter<'a, Option<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.find_map(|slot| slot.as_ref().map(|(key, value)| (key, value)))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.slots.by_ref().rev().find_map(|slot| slot.as_ref().map(|(key, value)| (key, value)))
+    }
+}
+
+/// `IdMap` の要素をIDの昇順に可変で返すイテレータ
+pub struct IterMut<'a, K, V> {
+    slots: std::slice::IterMut<'a, Option<(K, V)>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.find_map(|slot| slot.as_mut().map(|(key, value)| (&*key, value)))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.slots.by_ref().rev().find_map(|slot| slot.as_mut().map(|(key, value)| (&*key, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Id(u32);
+    
+    impl ArenaKey for Id {
+        fn index(self) -> usize {
+            self.0 as usize
+        }
+    }
+    
+    #[test]
+    fn test_id_map() {
+        let mut map = IdMap::new();
+        assert_eq!(map.insert(Id(3), "c"), None);
+        assert_eq!(map.insert(Id(0), "a"), None);
+        assert_eq!(map.insert(Id(3), "d"), Some("c"));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&Id(3)], "d");
+        assert_eq!(map.get(&Id(1)), None);
+        assert_eq!(map.get(&Id(100)), None);
+        
+        // 走査は挿入順ではなくIDの昇順
+        let keys: Vec<u32> = map.keys().map(|id| id.0).collect();
+        assert_eq!(keys, vec![0, 3]);
+        assert_eq!(map.iter().next_back(), Some((&Id(3), &"d")));
+        
+        assert_eq!(map.remove(&Id(0)), Some("a"));
+        assert_eq!(map.remove(&Id(0)), None);
+        map.insert(Id(5), "e");
+        map.retain(|id, _| id.0 != 5);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec!["d"]);
+        assert_eq!(map.len(), 1);
+    }
+}
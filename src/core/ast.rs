@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 
-use super::SourceLocation;
+use super::{Name, SourceLocation};
+use super::bigint::BigInt;
+use super::eir::AsmDialect;
 use super::types::Type;
 use super::symbol::SymbolId;
 
@@ -35,6 +37,23 @@ pub enum UnaryOp {
     BitNot, // ~
 }
 
+impl UnaryOp {
+    /// ソースコード上の記号
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Neg => "-",
+            Self::Not => "!",
+            Self::BitNot => "~",
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
 /// AST中の二項演算子
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryOp {
@@ -65,10 +84,49 @@ pub enum BinaryOp {
     Or,       // ||
 }
 
+impl BinaryOp {
+    /// ソースコード上の記号
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::BitXor => "^",
+            Self::LShift => "<<",
+            Self::RShift => ">>",
+            Self::Eq => "==",
+            Self::NotEq => "!=",
+            Self::Lt => "<",
+            Self::LtEq => "<=",
+            Self::Gt => ">",
+            Self::GtEq => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+        }
+    }
+    
+    /// 整数型にのみ使えるビット演算子かどうか
+    pub fn is_bitwise(&self) -> bool {
+        matches!(self, Self::BitAnd | Self::BitOr | Self::BitXor | Self::LShift | Self::RShift)
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
 /// リテラル値
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Int(i64),
+    /// `i64` の範囲を超える整数（`BigInt` 型）
+    BigInt(BigInt),
     Float(f64),
     Bool(bool),
     Char(char),
@@ -82,9 +140,9 @@ pub enum Node {
     // リテラル
     Literal(Literal),
     
-    // 識別子参照
+    // 識別子参照（名前はインターンしているので、複製しても割り当てが起きない）
     Identifier {
-        name: String,
+        name: Name,
         symbol: Option<SymbolId>,
     },
     
@@ -101,6 +159,12 @@ pub enum Node {
         right: Box<ASTNode>,
     },
     
+    // 型変換（`x as i32`）。変換できるのは数値型・`Bool`・`Char` の間だけ
+    Cast {
+        expr: Box<ASTNode>,
+        target_type: Type,
+    },
+    
     // 条件式（if-then-else）
     IfExpr {
         condition: Box<ASTNode>,
@@ -114,6 +178,44 @@ pub enum Node {
         result: Option<Box<ASTNode>>,
     },
     
+    // マップリテラル（{ "a": 1, "b": 2 }）
+    MapLiteral {
+        entries: Vec<(ASTNode, ASTNode)>,
+    },
+    
+    // 固定長配列のリテラル（`[1, 2, 3]`）
+    ArrayLiteral {
+        elements: Vec<ASTNode>,
+    },
+    
+    // 同じ値を並べた固定長配列（`[0; 8]`）。長さは整数リテラルで書く
+    ArrayRepeat {
+        value: Box<ASTNode>,
+        count: usize,
+    },
+    
+    // 添字による要素の参照（`a[i]`）。範囲外の添字は実行時にパニックする
+    Index {
+        base: Box<ASTNode>,
+        index: Box<ASTNode>,
+    },
+    
+    // 配列全体を指すスライス（`&a`）
+    SliceOf {
+        array: Box<ASTNode>,
+    },
+    
+    // タプルのリテラル（`(1, "a")`）。要素は2つ以上
+    TupleLiteral {
+        elements: Vec<ASTNode>,
+    },
+    
+    // 範囲（`start..end`）。`start` 以上 `end` 未満の整数を順に返すイテレータ
+    RangeExpr {
+        start: Box<ASTNode>,
+        end: Box<ASTNode>,
+    },
+    
     // 変数宣言
     VarDecl {
         name: String,
@@ -123,6 +225,47 @@ pub enum Node {
         is_mutable: bool,
     },
     
+    // タプルを分解する変数宣言（`let (q, r) = divmod(7, 2);`）。名前が `_` の要素は束縛しない
+    TupleDecl {
+        names: Vec<String>,
+        type_annotation: Option<Type>,
+        initializer: Box<ASTNode>,
+        is_mutable: bool,
+    },
+    
+    // グローバル変数の宣言（`const N: Int = 1;` / `static [mut] N: Int = 1;`）。初期化式はコンパイル時に評価する
+    GlobalDecl {
+        name: String,
+        type_annotation: Option<Type>,
+        initializer: Box<ASTNode>,
+        kind: GlobalKind,
+    },
+    
+    // `unsafe { ... }` ブロック。`static mut` の変数はこの中でのみ読み書きできる
+    UnsafeBlock {
+        body: Box<ASTNode>,
+    },
+    
+    // コンパイル時に実行するブロック（`comptime { ... }`）。式の位置では値のリテラルに、
+    // トップレベルでは値の文字列を解析した宣言に置き換える（`ConstEvaluator::expand_comptime`）
+    Comptime {
+        body: Box<ASTNode>,
+    },
+    
+    // 非同期関数の呼び出しの完了を待つ式（`await fetch(url)`）
+    Await {
+        expr: Box<ASTNode>,
+    },
+    
+    // インライン・アセンブリ（`asm!("popcnt {0}, {1}", out(Int), in(x), clobber("cc"))`）
+    InlineAsm {
+        dialect: AsmDialect,
+        template: String,
+        inputs: Vec<ASTNode>,
+        output: Option<Type>,
+        clobbers: Vec<String>,
+    },
+    
     // 関数定義
     FunctionDef {
         name: String,
@@ -130,6 +273,23 @@ pub enum Node {
         params: Vec<FunctionParam>,
         return_type: Option<Type>,
         body: Box<ASTNode>,
+        // `effect fn` で宣言された（副作用のある関数を呼び出せる）かどうか
+        is_effectful: bool,
+        // `const fn` で宣言された（コンパイル時に評価できる）かどうか
+        is_const: bool,
+        // `async fn` で宣言された（`await` を使える非同期関数）かどうか
+        is_async: bool,
+        // 関数に付けた属性（`#[bench]` なら "bench"）
+        attributes: Vec<String>,
+        // `pub fn` で宣言された（定義したモジュールの外から呼び出せる）かどうか
+        is_public: bool,
+    },
+    
+    // モジュール定義（`mod name { ... }`）。意味解析で中の関数を `name::f` の修飾名に展開する
+    ModuleDef {
+        name: String,
+        items: Vec<ASTNode>,
+        is_public: bool,
     },
     
     // 関数呼び出し
@@ -138,18 +298,57 @@ pub enum Node {
         args: Vec<ASTNode>,
     },
     
+    // `expr?`。`None`・`Err` なら関数からそのまま返し、`Some`・`Ok` なら中の値を取り出す
+    Try {
+        expr: Box<ASTNode>,
+    },
+    
+    // `try { ... } catch e { ... }`。本体の `?` が失敗すると、そのエラーを `e` に束縛して `catch` のブロックを評価する
+    TryCatch {
+        body: Box<ASTNode>,
+        error_name: String,
+        handler: Box<ASTNode>,
+    },
+    
+    // `defer 式;`。囲むブロックを抜けるとき（`?` で関数から返すときを含む）に、登録と逆の順に式を評価する
+    Defer {
+        expr: Box<ASTNode>,
+    },
+    
     // 代入
     Assignment {
         target: Box<ASTNode>,
         value: Box<ASTNode>,
     },
     
+    // 複合代入（+=, -=, *=）
+    CompoundAssignment {
+        op: BinaryOp,
+        target: Box<ASTNode>,
+        value: Box<ASTNode>,
+    },
+    
     // ループ
     WhileLoop {
         condition: Box<ASTNode>,
         body: Box<ASTNode>,
     },
     
+    // `for 変数 in 式 { ... }`。範囲・固定長配列・スライスの要素を順に変数に束縛して本体を実行する
+    ForLoop {
+        variable: String,
+        iterable: Box<ASTNode>,
+        body: Box<ASTNode>,
+    },
+    
+    // 内包表記 `[式 for 変数 in 式 if 条件]`。`for` ループで条件を満たす要素の式の値を集めた配列になる
+    Comprehension {
+        element: Box<ASTNode>,
+        variable: String,
+        iterable: Box<ASTNode>,
+        condition: Option<Box<ASTNode>>,
+    },
+    
     // 型定義
     TypeDef {
         name: String,
@@ -157,12 +356,89 @@ pub enum Node {
         definition: Type,
     },
     
+    // 型プロバイダ（`provide json_schema "schema/user.json";`）。`types` はスキーマから生成した `TypeDef`
+    TypeProvider {
+        dsl_name: String,
+        schema: String,
+        types: Vec<ASTNode>,
+    },
+    
     // DSLブロック
     DSLBlock {
         name: String,
         content: String,
         processed_ast: Option<Box<ASTNode>>,
     },
+    
+    // DSL定義（`syntax name { rule ... => ...; keyword 別名 = 予約語; layout; }`）
+    SyntaxDef {
+        name: String,
+        rules: Vec<SyntaxRule>,
+        keywords: Vec<KeywordAlias>,
+        /// `layout;` を宣言した（ブロックをインデントで区切る）
+        layout: bool,
+    },
+    
+    // PEG文法によるDSL定義（`peg name { ... }`）
+    PegDef {
+        name: String,
+        source: String,
+    },
+    
+    // DSLの有効化（`use dsl name;`）。宣言したファイルまたはブロックの中でのみ有効
+    UseDsl {
+        name: String,
+    },
+}
+
+/// グローバル変数の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlobalKind {
+    /// `const`：変更できず、参照した位置に値を埋め込む
+    Const,
+    /// `static`：変更できず、プログラム全体で1つの領域を持つ
+    Static,
+    /// `static mut`：変更でき、`unsafe` ブロックの中でのみ読み書きできる
+    StaticMut,
+}
+
+impl GlobalKind {
+    /// 宣言に使うキーワード
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Const => "const",
+            Self::Static => "static",
+            Self::StaticMut => "static mut",
+        }
+    }
+}
+
+/// `syntax` ブロック内の規則を構成する要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxFragment {
+    /// そのまま一致させる（または出力する）トークン
+    Token(String),
+    /// `$name` 形式のキャプチャ変数
+    Capture(String),
+}
+
+/// `syntax` ブロック内の書き換え規則（`rule パターン => 展開;`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxRule {
+    pub pattern: Vec<SyntaxFragment>,
+    pub expansion: Vec<SyntaxFragment>,
+}
+
+/// `syntax` ブロック内の予約語・演算子の別名（`keyword もし = if;`）
+///
+/// DSLを有効化したスコープでは、別名の単語を元の予約語・演算子として字句を読み替える。
+/// ASTには元の予約語で書いた場合と同じノードができる。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordAlias {
+    /// 別名（識別子の形の単語）
+    pub alias: String,
+    /// 元の予約語・演算子の字面（`if`・`&&` など）
+    pub keyword: String,
 }
 
 /// 関数パラメータ
@@ -204,6 +480,102 @@ impl ASTNode {
         self.type_info = type_info;
         self
     }
+    
+    /// 直下の子ノードを出現順に取得
+    pub fn children(&self) -> Vec<&ASTNode> {
+        match &self.kind {
+            Node::UnaryExpr { expr, .. } => vec![expr.as_ref()],
+            Node::BinaryExpr { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+            Node::IfExpr { condition, then_branch, else_branch } => {
+                let mut children = vec![condition.as_ref(), then_branch.as_ref()];
+                children.extend(else_branch.as_deref());
+                children
+            }
+            Node::BlockExpr { statements, result } => statements.iter().chain(result.as_deref()).collect(),
+            Node::MapLiteral { entries } => entries.iter().flat_map(|(key, value)| [key, value]).collect(),
+            Node::ArrayLiteral { elements } => elements.iter().collect(),
+            Node::ArrayRepeat { value, .. } => vec![value.as_ref()],
+            Node::Index { base, index } => vec![base.as_ref(), index.as_ref()],
+            Node::SliceOf { array } => vec![array.as_ref()],
+            Node::TupleLiteral { elements } => elements.iter().collect(),
+            Node::RangeExpr { start, end } => vec![start.as_ref(), end.as_ref()],
+            Node::VarDecl { initializer, .. } => initializer.as_deref().into_iter().collect(),
+            Node::TupleDecl { initializer, .. } => vec![initializer.as_ref()],
+            Node::GlobalDecl { initializer, .. } => vec![initializer.as_ref()],
+            Node::UnsafeBlock { body } | Node::Comptime { body } => vec![body.as_ref()],
+            Node::InlineAsm { inputs, .. } => inputs.iter().collect(),
+            Node::Try { expr } | Node::Await { expr } | Node::Cast { expr, .. } => vec![expr.as_ref()],
+            Node::TryCatch { body, handler, .. } => vec![body.as_ref(), handler.as_ref()],
+            Node::Defer { expr } => vec![expr.as_ref()],
+            Node::FunctionDef { body, .. } => vec![body.as_ref()],
+            Node::ModuleDef { items, .. } => items.iter().collect(),
+            Node::FunctionCall { callee, args } => std::iter::once(callee.as_ref()).chain(args).collect(),
+            Node::Assignment { target, value } | Node::CompoundAssignment { target, value, .. } => vec![target.as_ref(), value.as_ref()],
+            Node::WhileLoop { condition, body } => vec![condition.as_ref(), body.as_ref()],
+            Node::ForLoop { iterable, body, .. } => vec![iterable.as_ref(), body.as_ref()],
+            Node::Comprehension { element, iterable, condition, .. } => {
+                let mut children = vec![iterable.as_ref(), element.as_ref()];
+                children.extend(condition.as_deref());
+                children
+            }
+            Node::TypeProvider { types, .. } => types.iter().collect(),
+            Node::DSLBlock { processed_ast, .. } => processed_ast.as_deref().into_iter().collect(),
+            Node::Literal(_)
+            | Node::Identifier { .. }
+            | Node::TypeDef { .. }
+            | Node::SyntaxDef { .. }
+            | Node::PegDef { .. }
+            | Node::UseDsl { .. } => Vec::new(),
+        }
+    }
+    
+    /// 直下の子ノードを出現順に可変参照で取得
+    pub fn children_mut(&mut self) -> Vec<&mut ASTNode> {
+        match &mut self.kind {
+            Node::UnaryExpr { expr, .. } => vec![expr.as_mut()],
+            Node::BinaryExpr { left, right, .. } => vec![left.as_mut(), right.as_mut()],
+            Node::IfExpr { condition, then_branch, else_branch } => {
+                let mut children = vec![condition.as_mut(), then_branch.as_mut()];
+                children.extend(else_branch.as_deref_mut());
+                children
+            }
+            Node::BlockExpr { statements, result } => statements.iter_mut().chain(result.as_deref_mut()).collect(),
+            Node::MapLiteral { entries } => entries.iter_mut().flat_map(|(key, value)| [key, value]).collect(),
+            Node::ArrayLiteral { elements } => elements.iter_mut().collect(),
+            Node::ArrayRepeat { value, .. } => vec![value.as_mut()],
+            Node::Index { base, index } => vec![base.as_mut(), index.as_mut()],
+            Node::SliceOf { array } => vec![array.as_mut()],
+            Node::TupleLiteral { elements } => elements.iter_mut().collect(),
+            Node::RangeExpr { start, end } => vec![start.as_mut(), end.as_mut()],
+            Node::VarDecl { initializer, .. } => initializer.as_deref_mut().into_iter().collect(),
+            Node::TupleDecl { initializer, .. } => vec![initializer.as_mut()],
+            Node::GlobalDecl { initializer, .. } => vec![initializer.as_mut()],
+            Node::UnsafeBlock { body } | Node::Comptime { body } => vec![body.as_mut()],
+            Node::InlineAsm { inputs, .. } => inputs.iter_mut().collect(),
+            Node::Try { expr } | Node::Await { expr } | Node::Cast { expr, .. } => vec![expr.as_mut()],
+            Node::TryCatch { body, handler, .. } => vec![body.as_mut(), handler.as_mut()],
+            Node::Defer { expr } => vec![expr.as_mut()],
+            Node::FunctionDef { body, .. } => vec![body.as_mut()],
+            Node::ModuleDef { items, .. } => items.iter_mut().collect(),
+            Node::FunctionCall { callee, args } => std::iter::once(callee.as_mut()).chain(args).collect(),
+            Node::Assignment { target, value } | Node::CompoundAssignment { target, value, .. } => vec![target.as_mut(), value.as_mut()],
+            Node::WhileLoop { condition, body } => vec![condition.as_mut(), body.as_mut()],
+            Node::ForLoop { iterable, body, .. } => vec![iterable.as_mut(), body.as_mut()],
+            Node::Comprehension { element, iterable, condition, .. } => {
+                let mut children = vec![iterable.as_mut(), element.as_mut()];
+                children.extend(condition.as_deref_mut());
+                children
+            }
+            Node::TypeProvider { types, .. } => types.iter_mut().collect(),
+            Node::DSLBlock { processed_ast, .. } => processed_ast.as_deref_mut().into_iter().collect(),
+            Node::Literal(_)
+            | Node::Identifier { .. }
+            | Node::TypeDef { .. }
+            | Node::SyntaxDef { .. }
+            | Node::PegDef { .. }
+            | Node::UseDsl { .. } => Vec::new(),
+        }
+    }
 }
 
 /// プログラム全体のAST
@@ -233,4 +605,154 @@ impl Program {
     pub fn get_node(&self, id: NodeId) -> Option<&ASTNode> {
         self.node_map.get(&id)
     }
-} 
\ No newline at end of file
+} 
+
+/// ASTを読み取るだけの走査（リントや解析ツール向け）
+///
+/// 既定の実装は子ノードを出現順に訪れる。扱いたい種類のノードのメソッドだけを実装し、その中で
+/// `walk(self, node)` を呼べば子ノードへ進む（呼ばなければ子ノードを飛ばす）。ノードの種類を
+/// 増やしても、既定の実装は `ASTNode::children` を通してそのノードの子ノードを訪れる。
+///
+/// ```ignore
+/// struct CallCounter(usize);
+///
+/// impl Visitor for CallCounter {
+///     fn visit_function_call(&mut self, node: &ASTNode) {
+///         self.0 += 1;
+///         walk(self, node);
+///     }
+/// }
+/// ```
+pub trait Visitor {
+    /// トップレベルのノードを順に訪れる
+    fn visit_program(&mut self, program: &Program) {
+        for node in &program.nodes {
+            self.visit_node(node);
+        }
+    }
+    
+    /// ノードを訪れる（既定の実装は、ノードの種類ごとのメソッドに振り分ける）
+    fn visit_node(&mut self, node: &ASTNode) {
+        match &node.kind {
+            Node::FunctionDef { .. } => self.visit_function_def(node),
+            Node::FunctionCall { .. } => self.visit_function_call(node),
+            Node::BlockExpr { .. } => self.visit_block(node),
+            Node::Identifier { name, .. } => self.visit_identifier(node, name),
+            Node::Literal(literal) => self.visit_literal(node, literal),
+            _ => walk(self, node),
+        }
+    }
+    
+    fn visit_function_def(&mut self, node: &ASTNode) {
+        walk(self, node);
+    }
+    
+    fn visit_function_call(&mut self, node: &ASTNode) {
+        walk(self, node);
+    }
+    
+    fn visit_block(&mut self, node: &ASTNode) {
+        walk(self, node);
+    }
+    
+    fn visit_identifier(&mut self, _node: &ASTNode, _name: &str) {}
+    
+    fn visit_literal(&mut self, _node: &ASTNode, _literal: &Literal) {}
+}
+
+/// `node` の子ノードを出現順に `visitor` で訪れる
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, node: &ASTNode) {
+    for child in node.children() {
+        visitor.visit_node(child);
+    }
+}
+
+/// ASTを書き換える走査（脱糖やDSLの処理向け）
+///
+/// 既定の実装は子ノードを先に書き換えてから、ノード自体を `rewrite` に渡す（内側から外側へ）。
+/// 外側のノードを先に見たい場合は `mutate_node` を実装し、その中で `walk_mut(self, node)` を呼ぶ。
+pub trait Mutator {
+    /// トップレベルのノードを順に書き換え、`Program::node_map` も書き換えた後のノードにする
+    fn mutate_program(&mut self, program: &mut Program) {
+        for node in &mut program.nodes {
+            self.mutate_node(node);
+            program.node_map.insert(node.id, node.clone());
+        }
+    }
+    
+    /// ノードを書き換える（既定の実装は、子ノードを書き換えた後に `rewrite` を呼ぶ）
+    fn mutate_node(&mut self, node: &mut ASTNode) {
+        walk_mut(self, node);
+        self.rewrite(node);
+    }
+    
+    /// 子ノードを書き換え終えたノードを書き換える（ノードごと置き換えるときは `*node` に代入する）
+    fn rewrite(&mut self, _node: &mut ASTNode) {}
+}
+
+/// `node` の子ノードを出現順に `mutator` で書き換える
+pub fn walk_mut<M: Mutator + ?Sized>(mutator: &mut M, node: &mut ASTNode) {
+    for child in node.children_mut() {
+        mutator.mutate_node(child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn parse(source: &str) -> Program {
+        crate::frontend::parse_test_source(source).unwrap()
+    }
+    
+    #[test]
+    fn test_visitor_and_mutator() {
+        struct Calls(Vec<String>);
+        
+        impl Visitor for Calls {
+            fn visit_function_call(&mut self, node: &ASTNode) {
+                if let Node::FunctionCall { callee, .. } = &node.kind {
+                    if let Node::Identifier { name, .. } = &callee.kind {
+                        self.0.push(name.to_string());
+                    }
+                }
+                walk(self, node);
+            }
+        }
+        
+        let mut program = parse("fn f(x: Int) -> Int { g(h(x)) + 1 }\nprintln(f(2));");
+        let mut calls = Calls(Vec::new());
+        calls.visit_program(&program);
+        assert_eq!(calls.0, ["g", "h", "println", "f"]);
+        
+        // 整数リテラルを2倍にする
+        struct Double;
+        
+        impl Mutator for Double {
+            fn rewrite(&mut self, node: &mut ASTNode) {
+                if let Node::Literal(Literal::Int(value)) = &mut node.kind {
+                    *value *= 2;
+                }
+            }
+        }
+        
+        struct Ints(Vec<i64>);
+        
+        impl Visitor for Ints {
+            fn visit_literal(&mut self, _node: &ASTNode, literal: &Literal) {
+                if let Literal::Int(value) = literal {
+                    self.0.push(*value);
+                }
+            }
+        }
+        
+        Double.mutate_program(&mut program);
+        let mut ints = Ints(Vec::new());
+        ints.visit_program(&program);
+        assert_eq!(ints.0, [2, 4]);
+        // `node_map` のノードも書き換わる
+        let mut ints = Ints(Vec::new());
+        ints.visit_node(program.get_node(program.nodes[0].id).unwrap());
+        assert_eq!(ints.0, [2]);
+    }
+}
@@ -0,0 +1,517 @@
+use std::fmt::Write;
+
+use crate::core::ast::{ASTNode, FunctionParam, Literal, Node, Program, SyntaxFragment, TypeInfo, UnaryOp};
+use crate::core::types::Type;
+
+/// 外部の解析ツール向けに、ASTを機械で読める形式で出力する
+///
+/// JSON と S式のどちらも同じ木から作るので、フィールドの名前と順序は共通になる。ノードは
+/// `kind`（`Node` のバリアント名）と `line`・`column` に続けてバリアントのフィールドを持つ。
+/// ノードIDは解析のたびに変わるため出力しない。型は `Type` の表示と同じ文字列にする。
+#[derive(Debug, Clone, Default)]
+pub struct AstDumper {
+    /// DSLブロックの展開結果（`processed_ast`）も出力する
+    pub include_expansions: bool,
+}
+
+/// 出力形式に依らない木（フィールドの順序を保つ）
+enum Tree {
+    Node {
+        kind: &'static str,
+        /// ソース上の（行, 列）
+        location: Option<(usize, usize)>,
+        fields: Vec<(&'static str, Tree)>,
+    },
+    List(Vec<Tree>),
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Tree {
+    fn string(text: impl Into<String>) -> Self {
+        Self::String(text.into())
+    }
+    
+    fn option<T>(value: Option<T>, f: impl FnOnce(T) -> Tree) -> Self {
+        value.map_or(Self::Null, f)
+    }
+    
+    /// 1行に収める（子にノードを含まない）か
+    fn is_atom(&self) -> bool {
+        match self {
+            Self::Node { .. } => false,
+            Self::List(items) => items.iter().all(Tree::is_atom),
+            _ => true,
+        }
+    }
+}
+
+impl AstDumper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// DSLブロックの展開結果も出力する
+    pub fn with_expansions(mut self, enabled: bool) -> Self {
+        self.include_expansions = enabled;
+        self
+    }
+    
+    /// プログラムを JSON で出力（2スペースで字下げする）
+    pub fn to_json(&self, program: &Program) -> String {
+        let mut out = String::new();
+        write_json(&mut out, &self.program(program), 0);
+        out.push('\n');
+        out
+    }
+    
+    /// プログラムを S式で出力
+    ///
+    /// ノードは `(Kind :line 1 :column 5 :field value ...)`、リストは `(...)`、`null` は `nil` になる。
+    pub fn to_sexp(&self, program: &Program) -> String {
+        let mut out = String::new();
+        write_sexp(&mut out, &self.program(program), 0);
+        out.push('\n');
+        out
+    }
+    
+    /// 1つのノードを S式で出力
+    pub fn node_to_sexp(&self, node: &ASTNode) -> String {
+        let mut out = String::new();
+        write_sexp(&mut out, &self.node(node), 0);
+        out
+    }
+    
+    fn program(&self, program: &Program) -> Tree {
+        Tree::Node {
+            kind: "Program",
+            location: None,
+            fields: vec![
+                ("file", Tree::string(program.file_path.clone())),
+                ("items", self.nodes(&program.nodes)),
+            ],
+        }
+    }
+    
+    fn nodes(&self, nodes: &[ASTNode]) -> Tree {
+        Tree::List(nodes.iter().map(|node| self.node(node)).collect())
+    }
+    
+    fn boxed(&self, node: &Option<Box<ASTNode>>) -> Tree {
+        Tree::option(node.as_deref(), |node| self.node(node))
+    }
+    
+    fn node(&self, node: &ASTNode) -> Tree {
+        let (kind, mut fields) = match &node.kind {
+            Node::Literal(literal) => {
+                let (name, value) = match literal {
+                    Literal::Int(value) => ("int", Tree::Int(*value)),
+                    Literal::BigInt(value) => ("bigint", Tree::string(value.to_string())),
+                    Literal::Float(value) => ("float", Tree::Float(*value)),
+                    Literal::Bool(value) => ("bool", Tree::Bool(*value)),
+                    Literal::Char(value) => ("char", Tree::string(value.to_string())),
+                    Literal::String(value) => ("string", Tree::string(value.clone())),
+                    Literal::Unit => ("unit", Tree::Null),
+                };
+                ("Literal", vec![("literal", Tree::string(name)), ("value", value)])
+            }
+            Node::Identifier { name, .. } => ("Identifier", vec![("name", Tree::string(name.clone()))]),
+            Node::UnaryExpr { op, expr } => {
+                let symbol = match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "!",
+                    UnaryOp::BitNot => "~",
+                };
+                ("UnaryExpr", vec![("op", Tree::string(symbol)), ("expr", self.node(expr))])
+            }
+            Node::BinaryExpr { op, left, right } => (
+                "BinaryExpr",
+                vec![("op", Tree::string(op.symbol())), ("left", self.node(left)), ("right", self.node(right))],
+            ),
+            Node::Cast { expr, target_type } => (
+                "Cast",
+                vec![("expr", self.node(expr)), ("target_type", type_tree(target_type))],
+            ),
+            Node::IfExpr { condition, then_branch, else_branch } => (
+                "IfExpr",
+                vec![
+                    ("condition", self.node(condition)),
+                    ("then_branch", self.node(then_branch)),
+                    ("else_branch", self.boxed(else_branch)),
+                ],
+            ),
+            Node::BlockExpr { statements, result } => (
+                "BlockExpr",
+                vec![("statements", self.nodes(statements)), ("result", self.boxed(result))],
+            ),
+            Node::MapLiteral { entries } => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| Tree::List(vec![self.node(key), self.node(value)]))
+                    .collect();
+                ("MapLiteral", vec![("entries", Tree::List(entries))])
+            }
+            Node::ArrayLiteral { elements } => ("ArrayLiteral", vec![("elements", self.nodes(elements))]),
+            Node::ArrayRepeat { value, count } => (
+                "ArrayRepeat",
+                vec![("value", self.node(value)), ("count", Tree::Int(*count as i64))],
+            ),
+            Node::Index { base, index } => ("Index", vec![("base", self.node(base)), ("index", self.node(index))]),
+            Node::SliceOf { array } => ("SliceOf", vec![("array", self.node(array))]),
+            Node::TupleLiteral { elements } => ("TupleLiteral", vec![("elements", self.nodes(elements))]),
+            Node::RangeExpr { start, end } => ("RangeExpr", vec![("start", self.node(start)), ("end", self.node(end))]),
+            Node::VarDecl { name, type_annotation, initializer, is_mutable, .. } => (
+                "VarDecl",
+                vec![
+                    ("name", Tree::string(name.clone())),
+                    ("type_annotation", Tree::option(type_annotation.as_ref(), type_tree)),
+                    ("initializer", self.boxed(initializer)),
+                    ("is_mutable", Tree::Bool(*is_mutable)),
+                ],
+            ),
+            Node::TupleDecl { names, type_annotation, initializer, is_mutable } => (
+                "TupleDecl",
+                vec![
+                    ("names", Tree::List(names.iter().map(|name| Tree::string(name.clone())).collect())),
+                    ("type_annotation", Tree::option(type_annotation.as_ref(), type_tree)),
+                    ("initializer", self.node(initializer)),
+                    ("is_mutable", Tree::Bool(*is_mutable)),
+                ],
+            ),
+            Node::GlobalDecl { name, type_annotation, initializer, kind } => (
+                "GlobalDecl",
+                vec![
+                    ("name", Tree::string(name.clone())),
+                    ("kind", Tree::string(kind.keyword())),
+                    ("type_annotation", Tree::option(type_annotation.as_ref(), type_tree)),
+                    ("initializer", self.node(initializer)),
+                ],
+            ),
+            Node::UnsafeBlock { body } => ("UnsafeBlock", vec![("body", self.node(body))]),
+            Node::Comptime { body } => ("Comptime", vec![("body", self.node(body))]),
+            Node::InlineAsm { dialect, template, inputs, output, clobbers } => (
+                "InlineAsm",
+                vec![
+                    ("dialect", Tree::string(dialect.keyword())),
+                    ("template", Tree::string(template.clone())),
+                    ("inputs", self.nodes(inputs)),
+                    ("output", Tree::option(output.as_ref(), type_tree)),
+                    ("clobbers", Tree::List(clobbers.iter().map(|clobber| Tree::string(clobber.clone())).collect())),
+                ],
+            ),
+            Node::FunctionDef {
+                name,
+                params,
+                return_type,
+                body,
+                is_effectful,
+                is_const,
+                is_async,
+                attributes,
+                is_public,
+                ..
+            } => (
+                "FunctionDef",
+                vec![
+                    ("name", Tree::string(name.clone())),
+                    ("params", Tree::List(params.iter().map(param_tree).collect())),
+                    ("return_type", Tree::option(return_type.as_ref(), type_tree)),
+                    ("body", self.node(body)),
+                    ("is_effectful", Tree::Bool(*is_effectful)),
+                    ("is_const", Tree::Bool(*is_const)),
+                    ("is_async", Tree::Bool(*is_async)),
+                    ("is_public", Tree::Bool(*is_public)),
+                    ("attributes", Tree::List(attributes.iter().cloned().map(Tree::String).collect())),
+                ],
+            ),
+            Node::ModuleDef { name, items, is_public } => (
+                "ModuleDef",
+                vec![
+                    ("name", Tree::string(name.clone())),
+                    ("is_public", Tree::Bool(*is_public)),
+                    ("items", self.nodes(items)),
+                ],
+            ),
+            Node::FunctionCall { callee, args } => (
+                "FunctionCall",
+                vec![("callee", self.node(callee)), ("args", self.nodes(args))],
+            ),
+            Node::Try { expr } => ("Try", vec![("expr", self.node(expr))]),
+            Node::Await { expr } => ("Await", vec![("expr", self.node(expr))]),
+            Node::TryCatch { body, error_name, handler } => (
+                "TryCatch",
+                vec![
+                    ("body", self.node(body)),
+                    ("error_name", Tree::string(error_name.clone())),
+                    ("handler", self.node(handler)),
+                ],
+            ),
+            Node::Defer { expr } => ("Defer", vec![("expr", self.node(expr))]),
+            Node::Assignment { target, value } => (
+                "Assignment",
+                vec![("target", self.node(target)), ("value", self.node(value))],
+            ),
+            Node::CompoundAssignment { op, target, value } => (
+                "CompoundAssignment",
+                vec![("op", Tree::string(op.symbol())), ("target", self.node(target)), ("value", self.node(value))],
+            ),
+            Node::WhileLoop { condition, body } => (
+                "WhileLoop",
+                vec![("condition", self.node(condition)), ("body", self.node(body))],
+            ),
+            Node::ForLoop { variable, iterable, body } => (
+                "ForLoop",
+                vec![
+                    ("variable", Tree::string(variable.clone())),
+                    ("iterable", self.node(iterable)),
+                    ("body", self.node(body)),
+                ],
+            ),
+            Node::Comprehension { element, variable, iterable, condition } => (
+                "Comprehension",
+                vec![
+                    ("element", self.node(element)),
+                    ("variable", Tree::string(variable.clone())),
+                    ("iterable", self.node(iterable)),
+                    ("condition", self.boxed(condition)),
+                ],
+            ),
+            Node::TypeDef { name, definition, .. } => (
+                "TypeDef",
+                vec![("name", Tree::string(name.clone())), ("definition", type_tree(definition))],
+            ),
+            Node::TypeProvider { dsl_name, schema, types } => (
+                "TypeProvider",
+                vec![
+                    ("dsl_name", Tree::string(dsl_name.clone())),
+                    ("schema", Tree::string(schema.clone())),
+                    ("types", self.nodes(types)),
+                ],
+            ),
+            Node::DSLBlock { name, content, processed_ast } => {
+                let mut fields = vec![("name", Tree::string(name.clone())), ("content", Tree::string(content.clone()))];
+                if self.include_expansions {
+                    fields.push(("processed_ast", self.boxed(processed_ast)));
+                }
+                ("DSLBlock", fields)
+            }
+            Node::SyntaxDef { name, rules, keywords, layout } => {
+                let rules = rules
+                    .iter()
+                    .map(|rule| Tree::Node {
+                        kind: "SyntaxRule",
+                        location: None,
+                        fields: vec![
+                            ("pattern", fragments_tree(&rule.pattern)),
+                            ("expansion", fragments_tree(&rule.expansion)),
+                        ],
+                    })
+                    .collect();
+                let mut fields = vec![("name", Tree::string(name.clone())), ("rules", Tree::List(rules))];
+                if !keywords.is_empty() {
+                    let keywords = keywords
+                        .iter()
+                        .map(|keyword| Tree::Node {
+                            kind: "KeywordAlias",
+                            location: None,
+                            fields: vec![
+                                ("alias", Tree::string(keyword.alias.clone())),
+                                ("keyword", Tree::string(keyword.keyword.clone())),
+                            ],
+                        })
+                        .collect();
+                    fields.push(("keywords", Tree::List(keywords)));
+                }
+                if *layout {
+                    fields.push(("layout", Tree::Bool(true)));
+                }
+                ("SyntaxDef", fields)
+            }
+            Node::PegDef { name, source } => (
+                "PegDef",
+                vec![("name", Tree::string(name.clone())), ("source", Tree::string(source.clone()))],
+            ),
+            Node::UseDsl { name } => ("UseDsl", vec![("name", Tree::string(name.clone()))]),
+        };
+        
+        // 型検査の後のASTなら、推論・指定された型も出力する
+        match &node.type_info {
+            TypeInfo::Resolved(ty) | TypeInfo::Explicit(ty) => fields.push(("type", type_tree(ty))),
+            TypeInfo::Unknown => {}
+        }
+        Tree::Node {
+            kind,
+            location: Some((node.location.line, node.location.column)),
+            fields,
+        }
+    }
+}
+
+fn type_tree(ty: &Type) -> Tree {
+    Tree::String(ty.to_string())
+}
+
+fn param_tree(param: &FunctionParam) -> Tree {
+    Tree::Node {
+        kind: "Param",
+        location: None,
+        fields: vec![
+            ("name", Tree::string(param.name.clone())),
+            ("type", Tree::option(param.param_type.as_ref(), type_tree)),
+        ],
+    }
+}
+
+/// `syntax` の規則の断片（キャプチャ変数は `$name`）
+fn fragments_tree(fragments: &[SyntaxFragment]) -> Tree {
+    Tree::List(
+        fragments
+            .iter()
+            .map(|fragment| match fragment {
+                SyntaxFragment::Token(token) => Tree::string(token.clone()),
+                SyntaxFragment::Capture(name) => Tree::String(format!("${}", name)),
+            })
+            .collect(),
+    )
+}
+
+/// 文字列リテラル（JSON と S式で共通のエスケープ）
+fn quote(text: &str) -> String {
+    serde_json::Value::String(text.to_string()).to_string()
+}
+
+/// 浮動小数点数（整数値でも小数点を付け、JSON にない NaN・無限大は文字列にする）
+fn float(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else {
+        quote(&value.to_string())
+    }
+}
+
+fn write_json(out: &mut String, tree: &Tree, indent: usize) {
+    let pad = "  ".repeat(indent + 1);
+    match tree {
+        Tree::Node { kind, location, fields } => {
+            let _ = write!(out, "{{\n{}\"kind\": {}", pad, quote(kind));
+            if let Some((line, column)) = location {
+                let _ = write!(out, ",\n{}\"line\": {},\n{}\"column\": {}", pad, line, pad, column);
+            }
+            for (name, value) in fields {
+                let _ = write!(out, ",\n{}{}: ", pad, quote(name));
+                write_json(out, value, indent + 1);
+            }
+            let _ = write!(out, "\n{}}}", "  ".repeat(indent));
+        }
+        Tree::List(items) if items.is_empty() => out.push_str("[]"),
+        Tree::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(if i == 0 { "\n" } else { ",\n" });
+                out.push_str(&pad);
+                write_json(out, item, indent + 1);
+            }
+            let _ = write!(out, "\n{}]", "  ".repeat(indent));
+        }
+        Tree::String(text) => out.push_str(&quote(text)),
+        Tree::Int(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        Tree::Float(value) => out.push_str(&float(*value)),
+        Tree::Bool(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        Tree::Null => out.push_str("null"),
+    }
+}
+
+/// S式を出力（子にノードを含まないフィールドは見出しと同じ行に、それより後は1行ずつ字下げして並べる）
+fn write_sexp(out: &mut String, tree: &Tree, indent: usize) {
+    let pad = "  ".repeat(indent + 1);
+    match tree {
+        Tree::Node { kind, location, fields } => {
+            let _ = write!(out, "({}", kind);
+            if let Some((line, column)) = location {
+                let _ = write!(out, " :line {} :column {}", line, column);
+            }
+            // 子にノードを含むフィールドの後は、残りのフィールドもすべて改行して並べる
+            let mut broken = false;
+            for (name, value) in fields {
+                broken |= !value.is_atom();
+                if broken {
+                    let _ = write!(out, "\n{}:{} ", pad, name);
+                } else {
+                    let _ = write!(out, " :{} ", name);
+                }
+                write_sexp(out, value, indent + 1);
+            }
+            out.push(')');
+        }
+        Tree::List(items) if tree.is_atom() => {
+            out.push('(');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                write_sexp(out, item, indent);
+            }
+            out.push(')');
+        }
+        Tree::List(items) => {
+            out.push('(');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    let _ = write!(out, "\n{}", pad);
+                }
+                write_sexp(out, item, indent + 1);
+            }
+            out.push(')');
+        }
+        Tree::String(text) => out.push_str(&quote(text)),
+        Tree::Int(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        Tree::Float(value) => out.push_str(&float(*value)),
+        Tree::Bool(value) => {
+            let _ = write!(out, "{}", value);
+        }
+        Tree::Null => out.push_str("nil"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::parse_test_source;
+    
+    fn parse(source: &str) -> Program {
+        parse_test_source(source).unwrap()
+    }
+    
+    #[test]
+    fn test_dump_ast() {
+        let program = parse("fn f(x: Int) -> Int {\n    -x + 1.0\n}\n");
+        let dumper = AstDumper::new();
+        
+        let json = dumper.to_json(&program);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let function = &value["items"][0];
+        assert_eq!(function["kind"], "FunctionDef");
+        assert_eq!(function["line"], 1);
+        assert_eq!(function["params"][0]["name"], "x");
+        assert_eq!(function["params"][0]["type"], "Int");
+        assert_eq!(function["return_type"], "Int");
+        assert!(json.contains("\"literal\": \"float\",\n"));
+        assert!(json.contains("\"value\": 1.0\n"));
+        
+        let sexp = dumper.to_sexp(&program);
+        assert!(sexp.starts_with("(Program :file \"<test>\"\n  :items ((FunctionDef :line 1 :column 1 :name \"f\""));
+        assert!(sexp.contains("(UnaryExpr :line 2 :column 5 :op \"-\"\n"));
+        assert!(sexp.contains("\n      :attributes ())))\n"));
+        // 解析し直してもノードIDに左右されず同じ出力になる
+        assert_eq!(sexp, dumper.to_sexp(&parse("fn f(x: Int) -> Int {\n    -x + 1.0\n}\n")));
+    }
+}
@@ -0,0 +1,708 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::types::TypeKind;
+
+/// 標準ライブラリの `bigint` モジュールの関数
+///
+/// `BigInt` の演算はコード生成でこれらの関数の呼び出しに下げるので、EIRの構築（引数の変換と結果の型）、
+/// 標準ライブラリの登録、バックエンドの実行時関数のすべてがこの表を使う。
+pub struct BigIntFunction {
+    pub name: &'static str,
+    pub params: &'static [TypeKind],
+    pub returns: TypeKind,
+    pub description: &'static str,
+}
+
+/// `bigint` モジュールの関数の一覧
+pub const FUNCTIONS: &[BigIntFunction] = &[
+    BigIntFunction {
+        name: "parse",
+        params: &[TypeKind::String],
+        returns: TypeKind::BigInt,
+        description: "10進数の文字列（先頭に - を付けてよい）を BigInt に変換します。整数でなければ実行時エラーになります。",
+    },
+    BigIntFunction {
+        name: "from_int",
+        params: &[TypeKind::Int],
+        returns: TypeKind::BigInt,
+        description: "Int を BigInt に変換します。",
+    },
+    BigIntFunction {
+        name: "to_int",
+        params: &[TypeKind::BigInt],
+        returns: TypeKind::Int,
+        description: "下位64ビットを Int として返します（Int の範囲外の値は折り返します）。",
+    },
+    BigIntFunction {
+        name: "fits_int",
+        params: &[TypeKind::BigInt],
+        returns: TypeKind::Bool,
+        description: "値が Int の範囲に収まるかどうかを返します。",
+    },
+    BigIntFunction {
+        name: "to_float",
+        params: &[TypeKind::BigInt],
+        returns: TypeKind::Float,
+        description: "最も近い Float に変換します（大きすぎる値は無限大になります）。",
+    },
+    BigIntFunction {
+        name: "to_string",
+        params: &[TypeKind::BigInt],
+        returns: TypeKind::String,
+        description: "10進数の文字列に変換します。",
+    },
+    BigIntFunction {
+        name: "add",
+        params: &[TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "和を返します（+ 演算子と同じ）。",
+    },
+    BigIntFunction {
+        name: "sub",
+        params: &[TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "差を返します（- 演算子と同じ）。",
+    },
+    BigIntFunction {
+        name: "mul",
+        params: &[TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "積を返します（* 演算子と同じ）。",
+    },
+    BigIntFunction {
+        name: "div",
+        params: &[TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "0に向かって切り捨てた商を返します（/ 演算子と同じ）。0で割ると実行時エラーになります。",
+    },
+    BigIntFunction {
+        name: "rem",
+        params: &[TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "符号が被除数と同じ剰余を返します（% 演算子と同じ）。0で割ると実行時エラーになります。",
+    },
+    BigIntFunction {
+        name: "neg",
+        params: &[TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "符号を反転した値を返します（単項の - 演算子と同じ）。",
+    },
+    BigIntFunction {
+        name: "abs",
+        params: &[TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "絶対値を返します。",
+    },
+    BigIntFunction {
+        name: "cmp",
+        params: &[TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::Int,
+        description: "左辺が小さければ -1、等しければ 0、大きければ 1 を返します。",
+    },
+    BigIntFunction {
+        name: "pow",
+        params: &[TypeKind::BigInt, TypeKind::Int],
+        returns: TypeKind::BigInt,
+        description: "base の exponent 乗を返します。exponent が負なら実行時エラーになります。",
+    },
+    BigIntFunction {
+        name: "gcd",
+        params: &[TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "最大公約数（0以上）を返します。",
+    },
+    BigIntFunction {
+        name: "mod_pow",
+        params: &[TypeKind::BigInt, TypeKind::BigInt, TypeKind::BigInt],
+        returns: TypeKind::BigInt,
+        description: "base の exponent 乗を modulus で割った余り（0以上）を返します。exponent が負か modulus が0なら実行時エラーになります。",
+    },
+];
+
+/// `bigint` モジュールの関数を名前（`bigint::` を除いたもの）で探す
+pub fn function(name: &str) -> Option<&'static BigIntFunction> {
+    FUNCTIONS.iter().find(|function| function.name == name)
+}
+
+/// `bigint` モジュールの関数を、10進数の文字列で表した値に対して実行する
+///
+/// `BigInt` の値を文字列で持つ実行環境（標準ライブラリの実行とJIT）が使う。`args` の `BigInt` と `Int` の
+/// 引数は10進数の文字列で、戻り値の `Int` と `Bool` も文字列（`"1"`、`"true"`）にして返す。
+pub fn call(name: &str, args: &[&str]) -> Result<String, String> {
+    let function = function(name).ok_or_else(|| format!("bigint::{} という関数はありません", name))?;
+    if args.len() != function.params.len() {
+        return Err(format!("bigint::{} の引数は{}個です（{}個渡されました）", name, function.params.len(), args.len()));
+    }
+    let big = |index: usize| args[index].parse::<BigInt>();
+    let int = |index: usize| args[index].parse::<i64>().map_err(|_| format!("'{}' は Int ではありません", args[index]));
+    let division_by_zero = || "division by zero".to_string();
+    let result = match name {
+        "parse" => big(0)?.to_string(),
+        "from_int" => int(0)?.to_string(),
+        "to_int" => big(0)?.to_i64_wrapping().to_string(),
+        "fits_int" => big(0)?.to_i64().is_some().to_string(),
+        "to_float" => big(0)?.to_f64().to_string(),
+        "to_string" => big(0)?.to_string(),
+        "add" => (&big(0)? + &big(1)?).to_string(),
+        "sub" => (&big(0)? - &big(1)?).to_string(),
+        "mul" => (&big(0)? * &big(1)?).to_string(),
+        "div" => big(0)?.div_rem(&big(1)?).ok_or_else(division_by_zero)?.0.to_string(),
+        "rem" => big(0)?.div_rem(&big(1)?).ok_or_else(division_by_zero)?.1.to_string(),
+        "neg" => (-&big(0)?).to_string(),
+        "abs" => big(0)?.abs().to_string(),
+        "cmp" => (big(0)?.cmp(&big(1)?) as i64).to_string(),
+        "pow" => {
+            let exponent = u32::try_from(int(1)?).map_err(|_| format!("bigint::pow の指数 {} は使えません", args[1]))?;
+            big(0)?.pow(exponent).to_string()
+        }
+        "gcd" => big(0)?.gcd(&big(1)?).to_string(),
+        "mod_pow" => big(0)?
+            .mod_pow(&big(1)?, &big(2)?)
+            .ok_or_else(|| "bigint::mod_pow の指数が負か、法が0です".to_string())?
+            .to_string(),
+        _ => unreachable!("表にある関数はすべて実装している"),
+    };
+    Ok(result)
+}
+
+/// 任意精度の整数（`BigInt` 型の値）
+///
+/// 絶対値を下位の桁から並べた32ビットの桁で持つ。最上位の桁は0にせず、0は空の桁で表して符号を持たない。
+/// 直列化では10進数の文字列にする。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+    
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let negative = negative && !digits.is_empty();
+        Self { negative, digits }
+    }
+    
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+    
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+    
+    pub fn abs(&self) -> Self {
+        Self { negative: false, digits: self.digits.clone() }
+    }
+    
+    /// 符号（-1、0、1）
+    pub fn signum(&self) -> i64 {
+        match (self.is_zero(), self.negative) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        }
+    }
+    
+    /// 符号付きの数字の並びを解析する（`-` または `+` で始まってよい。`_` の区切りは無視する）
+    pub fn parse_radix(text: &str, radix: u32) -> Option<Self> {
+        let (negative, body) = match text.strip_prefix('-') {
+            Some(body) => (true, body),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let mut digits = Vec::new();
+        let mut seen = false;
+        for c in body.chars().filter(|c| *c != '_') {
+            mul_add_small(&mut digits, radix, c.to_digit(radix)?);
+            seen = true;
+        }
+        seen.then(|| Self::from_parts(negative, digits))
+    }
+    
+    /// 値が `i64` に収まれば返す
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+        let magnitude = self.low_u64();
+        match self.negative {
+            false => i64::try_from(magnitude).ok(),
+            true if magnitude <= 1 << 63 => Some((magnitude as i64).wrapping_neg()),
+            true => None,
+        }
+    }
+    
+    /// 下位64ビットを2の補数の `i64` として返す（`as i64` と同じく範囲外の値は折り返す）
+    pub fn to_i64_wrapping(&self) -> i64 {
+        let low = self.low_u64() as i64;
+        if self.negative {
+            low.wrapping_neg()
+        } else {
+            low
+        }
+    }
+    
+    fn low_u64(&self) -> u64 {
+        let digit = |i: usize| self.digits.get(i).copied().unwrap_or(0) as u64;
+        digit(0) | (digit(1) << 32)
+    }
+    
+    /// 最も近い `f64`（大きすぎる値は無限大）
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.digits.iter().rev().fold(0.0, |acc, digit| acc * 4294967296.0 + *digit as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+    
+    /// 0に向かって切り捨てる商と、符号が被除数と同じ剰余（`Int` の `/` と `%` と同じ）。0で割ると `None`
+    pub fn div_rem(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem_magnitude(&self.digits, &divisor.digits);
+        Some((
+            Self::from_parts(self.negative != divisor.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+    
+    /// べき乗
+    pub fn pow(&self, mut exponent: u32) -> BigInt {
+        let mut base = self.clone();
+        let mut result = BigInt::from(1);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+    
+    /// 最大公約数（常に0以上）
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let (_, remainder) = a.div_rem(&b).expect("除数は0でない");
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+    
+    /// `self` の `exponent` 乗を `modulus` で割った余り（0以上 `|modulus|` 未満）
+    ///
+    /// 指数が負か、法が0なら `None`。
+    pub fn mod_pow(&self, exponent: &BigInt, modulus: &BigInt) -> Option<BigInt> {
+        if exponent.negative || modulus.is_zero() {
+            return None;
+        }
+        let modulus = modulus.abs();
+        let reduce = |value: &BigInt| {
+            let (_, remainder) = value.div_rem(&modulus).expect("法は0でない");
+            if remainder.negative {
+                &remainder + &modulus
+            } else {
+                remainder
+            }
+        };
+        let mut base = reduce(self);
+        let mut result = reduce(&BigInt::from(1));
+        for digit in &exponent.digits {
+            for bit in 0..32 {
+                if digit >> bit & 1 == 1 {
+                    result = reduce(&(&result * &base));
+                }
+                base = reduce(&(&base * &base));
+            }
+        }
+        Some(result)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        Self::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = String;
+    
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse_radix(text, 10).ok_or_else(|| format!("'{}' は整数ではありません", text))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // 10^9 ごとに区切った桁を下位から求める
+        let mut magnitude = self.digits.clone();
+        let mut chunks = Vec::new();
+        while !magnitude.is_empty() {
+            chunks.push(div_rem_small(&mut magnitude, 1_000_000_000));
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().unwrap_or(&0))?;
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.digits, &other.digits),
+            (true, true) => cmp_magnitude(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+    
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_magnitude(&self.digits, &other.digits));
+        }
+        match cmp_magnitude(&self.digits, &other.digits) {
+            Ordering::Less => BigInt::from_parts(other.negative, sub_magnitude(&other.digits, &self.digits)),
+            _ => BigInt::from_parts(self.negative, sub_magnitude(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+    
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+    
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(self.negative != other.negative, mul_magnitude(&self.digits, &other.digits))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+    
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.digits.clone())
+    }
+}
+
+impl Serialize for BigInt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for BigInt {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (i, digit) in long.iter().enumerate() {
+        let sum = *digit as u64 + short.get(i).copied().unwrap_or(0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    result
+}
+
+/// `a - b`（`a` の絶対値は `b` 以上）
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, digit) in a.iter().enumerate() {
+        let difference = *digit as i64 - b.get(i).copied().unwrap_or(0) as i64 - borrow;
+        result.push(difference as u32);
+        borrow = (difference < 0) as i64;
+    }
+    result
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let product = *x as u64 * *y as u64 + result[i + j] as u64 + carry;
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    result
+}
+
+/// `digits * factor + addend` で置き換える
+fn mul_add_small(digits: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for digit in digits.iter_mut() {
+        let value = *digit as u64 * factor as u64 + carry;
+        *digit = value as u32;
+        carry = value >> 32;
+    }
+    if carry > 0 {
+        digits.push(carry as u32);
+    }
+}
+
+/// `digits` を `divisor` で割った商で置き換え、余りを返す
+fn div_rem_small(digits: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for digit in digits.iter_mut().rev() {
+        let value = (remainder << 32) | *digit as u64;
+        *digit = (value / divisor as u64) as u32;
+        remainder = value % divisor as u64;
+    }
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    remainder as u32
+}
+
+/// 絶対値どうしの商と余り（Knuth の Algorithm D。`v` は0でない）
+fn div_rem_magnitude(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_magnitude(u, v) == Ordering::Less {
+        return (Vec::new(), u.to_vec());
+    }
+    if let [divisor] = v {
+        let mut quotient = u.to_vec();
+        let remainder = div_rem_small(&mut quotient, *divisor);
+        return (quotient, vec![remainder]);
+    }
+    
+    // 除数の最上位の桁の最上位ビットが立つように、両方を左にずらす
+    let shift = v[v.len() - 1].leading_zeros();
+    let v = shift_left(v, shift, v.len());
+    let mut u = shift_left(u, shift, u.len() + 1);
+    let n = v.len();
+    let m = u.len() - n - 1;
+    let mut quotient = vec![0u32; m + 1];
+    const BASE: u64 = 1 << 32;
+    for j in (0..=m).rev() {
+        // 上位2桁から商の桁を見積もり、見積もりすぎなら減らす
+        let numerator = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut q = numerator / v[n - 1] as u64;
+        let mut r = numerator % v[n - 1] as u64;
+        while q >= BASE || q * v[n - 2] as u64 > ((r << 32) | u[j + n - 2] as u64) {
+            q -= 1;
+            r += v[n - 1] as u64;
+            if r >= BASE {
+                break;
+            }
+        }
+        
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let product = q * v[i] as u64 + carry;
+            carry = product >> 32;
+            let difference = u[i + j] as i64 - borrow - (product & 0xFFFF_FFFF) as i64;
+            u[i + j] = difference as u32;
+            borrow = (difference < 0) as i64;
+        }
+        let difference = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = difference as u32;
+        
+        // 引きすぎた（見積もりが1大きかった）ときは1回分を足し戻す
+        if difference < 0 {
+            q -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = q as u32;
+    }
+    
+    let remainder = shift_right(&u[..n], shift);
+    (quotient, remainder)
+}
+
+/// `shift` ビット（32未満）左にずらし、`len` 桁にする（あふれた桁は `len` に含まれる前提）
+fn shift_left(digits: &[u32], shift: u32, len: usize) -> Vec<u32> {
+    let mut result = vec![0u32; len];
+    let mut carry = 0u32;
+    for (i, digit) in digits.iter().enumerate() {
+        result[i] = (digit << shift) | carry;
+        carry = if shift == 0 { 0 } else { digit >> (32 - shift) };
+    }
+    if digits.len() < len {
+        result[digits.len()] = carry;
+    }
+    result
+}
+
+fn shift_right(digits: &[u32], shift: u32) -> Vec<u32> {
+    let mut result = digits.to_vec();
+    if shift == 0 {
+        return result;
+    }
+    for i in 0..result.len() {
+        let high = digits.get(i + 1).map_or(0, |digit| digit << (32 - shift));
+        result[i] = (digits[i] >> shift) | high;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn big(text: &str) -> BigInt {
+        text.parse().unwrap()
+    }
+    
+    #[test]
+    fn test_parse_and_display() {
+        for text in ["0", "1", "-1", "4294967296", "18446744073709551616", "-123456789012345678901234567890"] {
+            assert_eq!(big(text).to_string(), text);
+        }
+        assert_eq!(big("-0"), BigInt::zero());
+        assert_eq!(BigInt::parse_radix("ff_ff", 16), Some(BigInt::from(0xFFFF)));
+        assert_eq!(BigInt::parse_radix("12a", 10), None);
+        assert_eq!(BigInt::parse_radix("-", 10), None);
+        assert_eq!(BigInt::from(i64::MIN).to_string(), i64::MIN.to_string());
+        assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(big("9223372036854775808").to_i64(), None);
+        assert_eq!(big("18446744073709551617").to_i64_wrapping(), 1);
+        assert_eq!(big("-2").to_f64(), -2.0);
+    }
+    
+    #[test]
+    fn test_arithmetic_matches_i128() {
+        let values: [i128; 10] = [
+            0,
+            1,
+            -7,
+            4_294_967_295,
+            -4_294_967_296,
+            i64::MAX as i128,
+            i64::MIN as i128,
+            123_456_789_012_345_678_901_234_567,
+            -98_765_432_109_876_543_210,
+            (1 << 100) + 12345,
+        ];
+        for a in values {
+            for b in values {
+                let (x, y) = (big(&a.to_string()), big(&b.to_string()));
+                assert_eq!((&x + &y).to_string(), (a + b).to_string(), "{} + {}", a, b);
+                assert_eq!((&x - &y).to_string(), (a - b).to_string(), "{} - {}", a, b);
+                if let Some(product) = a.checked_mul(b) {
+                    assert_eq!((&x * &y).to_string(), product.to_string(), "{} * {}", a, b);
+                }
+                assert_eq!(x.cmp(&y), a.cmp(&b), "{} <=> {}", a, b);
+                match x.div_rem(&y) {
+                    Some((q, r)) => {
+                        assert_eq!(q.to_string(), (a / b).to_string(), "{} / {}", a, b);
+                        assert_eq!(r.to_string(), (a % b).to_string(), "{} % {}", a, b);
+                    }
+                    None => assert_eq!(b, 0),
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn test_long_division() {
+        // 除数が複数桁で、商の見積もりの補正が必要になる値
+        let a = &BigInt::from(3).pow(200) + &BigInt::from(17);
+        let b = &BigInt::from(2).pow(127) - &BigInt::from(1);
+        let (q, r) = a.div_rem(&b).unwrap();
+        assert_eq!(&(&q * &b) + &r, a);
+        assert!(r < b && !r.is_negative());
+        
+        let c = &BigInt::from(10).pow(40) - &BigInt::from(1);
+        assert_eq!(c.div_rem(&BigInt::from(10).pow(20)).unwrap().0, &BigInt::from(10).pow(20) - &BigInt::from(1));
+    }
+    
+    #[test]
+    fn test_call_functions() {
+        assert_eq!(call("mul", &["99999999999999999999", "-3"]).unwrap(), "-299999999999999999997");
+        assert_eq!(call("cmp", &["-5", "3"]).unwrap(), "-1");
+        assert_eq!(call("to_int", &["18446744073709551615"]).unwrap(), "-1");
+        assert_eq!(call("fits_int", &["9223372036854775808"]).unwrap(), "false");
+        assert_eq!(call("pow", &["10", "20"]).unwrap(), "100000000000000000000");
+        assert_eq!(call("div", &["1", "0"]).unwrap_err(), "division by zero");
+        assert!(call("parse", &["12x"]).unwrap_err().contains("整数ではありません"));
+        assert!(call("pow", &["2", "-1"]).is_err());
+        assert!(call("add", &["1"]).unwrap_err().contains("引数は2個"));
+        // すべての関数を実装している
+        for function in FUNCTIONS {
+            let args: Vec<&str> = function.params.iter().map(|_| "3").collect();
+            assert!(call(function.name, &args).is_ok(), "{}", function.name);
+        }
+    }
+    
+    #[test]
+    fn test_pow_gcd_mod_pow() {
+        assert_eq!(BigInt::from(2).pow(100).to_string(), "1267650600228229401496703205376");
+        assert_eq!(BigInt::from(-3).pow(3), BigInt::from(-27));
+        assert_eq!(big("-48").gcd(&big("180")), BigInt::from(12));
+        assert_eq!(BigInt::from(4).mod_pow(&BigInt::from(13), &BigInt::from(497)), Some(BigInt::from(445)));
+        assert_eq!(BigInt::from(-2).mod_pow(&BigInt::from(3), &BigInt::from(5)), Some(BigInt::from(2)));
+        assert_eq!(BigInt::from(5).mod_pow(&BigInt::zero(), &BigInt::from(1)), Some(BigInt::zero()));
+        assert_eq!(BigInt::from(5).mod_pow(&BigInt::from(-1), &BigInt::from(7)), None);
+        // フェルマーの小定理: 素数 p = 2^127 - 1 について a^(p-1) ≡ 1 (mod p)
+        let p = &BigInt::from(2).pow(127) - &BigInt::from(1);
+        let exponent = &p - &BigInt::from(1);
+        assert_eq!(big("123456789").mod_pow(&exponent, &p), Some(BigInt::from(1)));
+    }
+}
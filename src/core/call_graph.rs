@@ -0,0 +1,310 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::core::analysis::dot_string;
+use crate::core::eir::{FunctionId, Instruction, Module};
+
+/// モジュールの呼び出しグラフ
+///
+/// 関数の中の `call` 命令から、呼び出す関数への辺を張る。モジュールに定義のない関数
+/// （標準ライブラリや外部関数）の呼び出しは辺にせず、呼び出し元ごとに名前だけを記録する。
+/// 強連結成分は呼び出される側が先に来る順（ボトムアップ順）に並べるので、
+/// インライン化のように呼び出し先を先に処理したいパスはその順に関数をたどればよい。
+#[derive(Debug, Clone)]
+pub struct CallGraph {
+    /// IDの昇順に並べた関数
+    functions: Vec<FunctionId>,
+    names: HashMap<FunctionId, String>,
+    callees: HashMap<FunctionId, Vec<FunctionId>>,
+    callers: HashMap<FunctionId, Vec<FunctionId>>,
+    external_callees: HashMap<FunctionId, Vec<String>>,
+    /// 強連結成分（ボトムアップ順）
+    sccs: Vec<Vec<FunctionId>>,
+    /// 関数が属する強連結成分の位置
+    scc_index: HashMap<FunctionId, usize>,
+}
+
+impl CallGraph {
+    /// モジュールの呼び出しグラフを構築
+    pub fn new(module: &Module) -> Self {
+        let mut functions: Vec<FunctionId> = module.functions.keys().copied().collect();
+        functions.sort_by_key(|id| id.0);
+        let ids: HashMap<&str, FunctionId> = module
+            .functions
+            .values()
+            .map(|function| (function.name.as_str(), function.id))
+            .collect();
+        
+        let mut callees: HashMap<FunctionId, Vec<FunctionId>> = HashMap::new();
+        let mut callers: HashMap<FunctionId, Vec<FunctionId>> = HashMap::new();
+        let mut external_callees: HashMap<FunctionId, Vec<String>> = HashMap::new();
+        for &id in &functions {
+            let function = &module.functions[&id];
+            let mut seen = HashSet::new();
+            let names = function
+                .blocks
+                .values()
+                .flat_map(|block| &block.instructions)
+                .filter_map(|(_, instruction)| match instruction {
+                    Instruction::Call { function, .. } | Instruction::ExternalCall { function, .. } => Some(function),
+                    _ => None,
+                });
+            for name in names {
+                if !seen.insert(name.as_str()) {
+                    continue;
+                }
+                match ids.get(name.as_str()) {
+                    Some(&callee) => {
+                        callees.entry(id).or_default().push(callee);
+                        callers.entry(callee).or_default().push(id);
+                    }
+                    None => external_callees.entry(id).or_default().push(name.clone()),
+                }
+            }
+        }
+        
+        let mut graph = Self {
+            names: module.functions.values().map(|function| (function.id, function.name.clone())).collect(),
+            functions,
+            callees,
+            callers,
+            external_callees,
+            sccs: Vec::new(),
+            scc_index: HashMap::new(),
+        };
+        graph.sccs = Tarjan::run(&graph);
+        graph.scc_index = graph
+            .sccs
+            .iter()
+            .enumerate()
+            .flat_map(|(index, scc)| scc.iter().map(move |id| (*id, index)))
+            .collect();
+        graph
+    }
+    
+    /// IDの昇順に並べたすべての関数
+    pub fn functions(&self) -> &[FunctionId] {
+        &self.functions
+    }
+    
+    /// 関数が呼び出すモジュール内の関数（最初に呼び出す順）
+    pub fn callees(&self, function: FunctionId) -> &[FunctionId] {
+        self.callees.get(&function).map_or(&[], Vec::as_slice)
+    }
+    
+    /// 関数を呼び出すモジュール内の関数
+    pub fn callers(&self, function: FunctionId) -> &[FunctionId] {
+        self.callers.get(&function).map_or(&[], Vec::as_slice)
+    }
+    
+    /// 関数が呼び出す、モジュールに定義のない関数の名前
+    pub fn external_callees(&self, function: FunctionId) -> &[String] {
+        self.external_callees.get(&function).map_or(&[], Vec::as_slice)
+    }
+    
+    /// 強連結成分（呼び出される側が先）
+    pub fn sccs(&self) -> &[Vec<FunctionId>] {
+        &self.sccs
+    }
+    
+    /// 呼び出される関数が先に来る順に並べたすべての関数
+    ///
+    /// 相互再帰の関数どうしの順序は決まらないので、IDの昇順にする。
+    pub fn bottom_up_order(&self) -> Vec<FunctionId> {
+        self.sccs.iter().flatten().copied().collect()
+    }
+    
+    /// 関数が（相互）再帰の循環に含まれるか
+    pub fn is_recursive(&self, function: FunctionId) -> bool {
+        match self.scc_index.get(&function) {
+            Some(&index) => self.sccs[index].len() > 1 || self.callees(function).contains(&function),
+            None => false,
+        }
+    }
+    
+    /// Graphvizの DOT 形式で出力
+    ///
+    /// 相互再帰の関数はまとめて枠で囲み、モジュールに定義のない関数は破線の楕円で示す。
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph callgraph {\n    node [shape=box];\n");
+        for scc in &self.sccs {
+            if scc.len() > 1 {
+                let _ = writeln!(out, "    subgraph cluster_{} {{\n        style=dashed;", scc[0]);
+                for id in scc {
+                    let _ = writeln!(out, "        {};", id);
+                }
+                out.push_str("    }\n");
+            }
+        }
+        for id in &self.functions {
+            let _ = writeln!(out, "    {} [label={}];", id, dot_string(&self.names[id]));
+        }
+        
+        let externals: BTreeSet<&String> = self.external_callees.values().flatten().collect();
+        let external_ids: HashMap<&String, usize> = externals.iter().enumerate().map(|(i, name)| (*name, i)).collect();
+        for (i, name) in externals.iter().enumerate() {
+            let _ = writeln!(out, "    ext_{} [label={}, shape=ellipse, style=dashed];", i, dot_string(name));
+        }
+        
+        for id in &self.functions {
+            for callee in self.callees(*id) {
+                let _ = writeln!(out, "    {} -> {};", id, callee);
+            }
+            for name in self.external_callees(*id) {
+                let _ = writeln!(out, "    {} -> ext_{} [style=dashed];", id, external_ids[name]);
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Tarjan のアルゴリズムによる強連結成分の分解
+///
+/// 強連結成分は、そこから到達できる成分をすべて出し終えてから出すので、呼び出される側が先になる。
+/// 呼び出しの連鎖が長くてもスタックがあふれないよう、深さ優先探索は再帰ではなく明示的なスタックで行う。
+struct Tarjan<'a> {
+    graph: &'a CallGraph,
+    index: HashMap<FunctionId, usize>,
+    low_link: HashMap<FunctionId, usize>,
+    stack: Vec<FunctionId>,
+    on_stack: HashSet<FunctionId>,
+    sccs: Vec<Vec<FunctionId>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn run(graph: &'a CallGraph) -> Vec<Vec<FunctionId>> {
+        let mut tarjan = Self {
+            graph,
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            sccs: Vec::new(),
+        };
+        for &id in &graph.functions {
+            if !tarjan.index.contains_key(&id) {
+                tarjan.visit(id);
+            }
+        }
+        tarjan.sccs
+    }
+    
+    fn visit(&mut self, root: FunctionId) {
+        // 探索中の関数と、次に調べる呼び出し先の位置
+        let mut path = vec![(root, 0)];
+        self.enter(root);
+        while let Some((id, next)) = path.last_mut() {
+            let id = *id;
+            if let Some(&callee) = self.graph.callees(id).get(*next) {
+                *next += 1;
+                if !self.index.contains_key(&callee) {
+                    self.enter(callee);
+                    path.push((callee, 0));
+                } else if self.on_stack.contains(&callee) {
+                    let low = self.low_link[&id].min(self.index[&callee]);
+                    self.low_link.insert(id, low);
+                }
+                continue;
+            }
+            
+            path.pop();
+            self.leave(id);
+            if let Some(&(caller, _)) = path.last() {
+                let low = self.low_link[&caller].min(self.low_link[&id]);
+                self.low_link.insert(caller, low);
+            }
+        }
+    }
+    
+    fn enter(&mut self, id: FunctionId) {
+        let index = self.index.len();
+        self.index.insert(id, index);
+        self.low_link.insert(id, index);
+        self.stack.push(id);
+        self.on_stack.insert(id);
+    }
+    
+    /// 呼び出し先をすべて調べ終えた関数が強連結成分の根なら、成分を取り出す
+    fn leave(&mut self, id: FunctionId) {
+        if self.low_link[&id] == self.index[&id] {
+            let mut scc = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(&member);
+                scc.push(member);
+                if member == id {
+                    break;
+                }
+            }
+            scc.sort_by_key(|member| member.0);
+            self.sccs.push(scc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::{Function, Operand};
+    use crate::core::ir_builder::IrBuilder;
+    use crate::core::types::{Type, TypeId};
+    
+    /// `callees` を順に呼び出す関数を追加
+    fn add_function(module: &mut Module, id: u32, name: &str, callees: &[&str]) {
+        let mut builder = IrBuilder::new(Function::new(FunctionId(id), name, TypeId(1), TypeId(0)));
+        for callee in callees {
+            builder.call(*callee, vec![Operand::Literal(crate::core::eir::Literal::Int(1))], None);
+        }
+        builder.ret(None);
+        module.add_function(builder.finish());
+    }
+    
+    #[test]
+    fn test_call_graph() {
+        let mut module = Module::new("test");
+        module.add_type(Type::unit());
+        add_function(&mut module, 0, "main", &["is_even", "helper", "println"]);
+        add_function(&mut module, 1, "is_even", &["is_odd"]);
+        add_function(&mut module, 2, "is_odd", &["is_even"]);
+        add_function(&mut module, 3, "helper", &["fact", "println"]);
+        add_function(&mut module, 4, "fact", &["fact"]);
+        
+        let graph = CallGraph::new(&module);
+        assert_eq!(graph.callees(FunctionId(0)), &[FunctionId(1), FunctionId(3)]);
+        assert_eq!(graph.callers(FunctionId(1)), &[FunctionId(0), FunctionId(2)]);
+        assert_eq!(graph.external_callees(FunctionId(3)), &["println".to_string()]);
+        
+        // 呼び出される側が先、相互再帰の関数は1つの強連結成分にまとまる
+        let sccs: Vec<Vec<u32>> = graph.sccs().iter().map(|scc| scc.iter().map(|id| id.0).collect()).collect();
+        assert_eq!(sccs, vec![vec![1, 2], vec![4], vec![3], vec![0]]);
+        assert!(graph.is_recursive(FunctionId(1)));
+        assert!(graph.is_recursive(FunctionId(4)));
+        assert!(!graph.is_recursive(FunctionId(3)));
+        
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph callgraph {\n"));
+        assert!(dot.contains("    subgraph cluster_func_1 {\n        style=dashed;\n        func_1;\n        func_2;\n    }\n"));
+        assert!(dot.contains("    func_4 [label=\"fact\"];\n"));
+        assert!(dot.contains("    ext_0 [label=\"println\", shape=ellipse, style=dashed];\n"));
+        assert!(dot.contains("    func_4 -> func_4;\n"));
+        assert!(dot.contains("    func_0 -> ext_0 [style=dashed];\n"));
+    }
+    
+    #[test]
+    fn test_long_call_chain() {
+        // 再帰で辿るとスタックがあふれる長さの呼び出しの連鎖
+        const LENGTH: u32 = 100_000;
+        let mut module = Module::new("test");
+        module.add_type(Type::unit());
+        for id in 0..LENGTH {
+            let callee = format!("f{}", id + 1);
+            let callees: &[&str] = if id + 1 < LENGTH { &[&callee] } else { &["f0"] };
+            add_function(&mut module, id, &format!("f{}", id), callees);
+        }
+        
+        let graph = CallGraph::new(&module);
+        assert_eq!(graph.sccs().len(), 1);
+        assert_eq!(graph.sccs()[0].len(), LENGTH as usize);
+        assert!(graph.is_recursive(FunctionId(LENGTH - 1)));
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::eir::{Instruction, Literal, Module, Operand, Terminator};
+use crate::core::{EidosError, Result};
+
+/// ブロックの実行回数を数える外部関数（引数はカウンタの番号）
+pub const COVERAGE_HIT: &str = "eidos_cov_hit";
+
+/// 条件分岐の結果を数える外部関数（引数はカウンタの番号と条件。真なら番号、偽なら番号 + 1 のカウンタを増やす）
+pub const COVERAGE_BRANCH: &str = "eidos_cov_branch";
+
+/// 1つの基本ブロックのカウンタ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockCounter {
+    pub counter: usize,
+    pub function: String,
+    /// 関数のエントリーブロックか（関数の呼び出し回数になる）
+    pub entry: bool,
+    pub file: PathBuf,
+    /// ブロックの命令が対応する行（昇順）
+    pub lines: Vec<usize>,
+}
+
+/// 1つの条件分岐のカウンタ（`counter` が真、`counter + 1` が偽の回数）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchCounter {
+    pub counter: usize,
+    pub function: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+/// カウンタとソース上の位置の対応（`eidos build --coverage` が出力し、`eidos cov report` が読む）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageMap {
+    pub module: String,
+    /// カウンタの総数
+    pub counters: usize,
+    pub blocks: Vec<BlockCounter>,
+    pub branches: Vec<BranchCounter>,
+}
+
+/// 実行したプログラムが終了時に書き出すカウンタの値
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageProfile {
+    pub module: String,
+    pub counters: Vec<u64>,
+}
+
+/// モジュールにカバレッジ計測のカウンタを挿入
+///
+/// すべての基本ブロックの先頭（φ命令の後）で `COVERAGE_HIT` を、条件分岐で終わるブロックの末尾で
+/// `COVERAGE_BRANCH` を呼び出す。行はブロックの命令に記録したソース上の位置から求めるので、
+/// 位置を持たないブロック（合流点など）は実行回数だけを数える。
+pub fn instrument(module: &mut Module) -> CoverageMap {
+    let mut map = CoverageMap {
+        module: module.name.clone(),
+        counters: 0,
+        blocks: Vec::new(),
+        branches: Vec::new(),
+    };
+    let mut ids: Vec<_> = module.functions.keys().copied().collect();
+    ids.sort_by_key(|id| id.0);
+    for id in ids {
+        let function = module.functions.get_mut(&id).unwrap();
+        let default_file = function.location.as_ref().map(|location| location.file.clone()).unwrap_or_default();
+        let block_ids: Vec<_> = function.blocks.keys().copied().collect();
+        for block_id in block_ids {
+            let block = &function.blocks[&block_id];
+            let locations: Vec<_> = block
+                .instructions
+                .iter()
+                .filter_map(|(id, _)| function.locations.get(id))
+                .filter(|location| location.line != 0)
+                .collect();
+            let file = locations.first().map(|location| location.file.clone()).unwrap_or_else(|| default_file.clone());
+            let mut lines: Vec<usize> = locations
+                .iter()
+                .filter(|location| location.file == file)
+                .map(|location| location.line)
+                .collect();
+            lines.sort_unstable();
+            lines.dedup();
+            let branch_line = locations.last().map_or(0, |location| location.line);
+            let condition = match &block.terminator {
+                Some(Terminator::BranchCond { condition, .. }) => Some(condition.clone()),
+                _ => None,
+            };
+            let phis = block
+                .instructions
+                .iter()
+                .take_while(|(_, instruction)| matches!(instruction, Instruction::Phi { .. }))
+                .count();
+            
+            let hit = Instruction::ExternalCall {
+                function: COVERAGE_HIT.to_string(),
+                arguments: vec![counter_operand(map.counters)],
+                result: None,
+            };
+            let hit_id = function.next_instruction_id();
+            map.blocks.push(BlockCounter {
+                counter: map.counters,
+                function: function.name.clone(),
+                entry: block_id == function.entry_block,
+                file: file.clone(),
+                lines,
+            });
+            map.counters += 1;
+            
+            let branch = condition.map(|condition| {
+                let branch = Instruction::ExternalCall {
+                    function: COVERAGE_BRANCH.to_string(),
+                    arguments: vec![counter_operand(map.counters), condition],
+                    result: None,
+                };
+                map.branches.push(BranchCounter {
+                    counter: map.counters,
+                    function: function.name.clone(),
+                    file: file.clone(),
+                    line: branch_line,
+                });
+                map.counters += 2;
+                (function.next_instruction_id(), branch)
+            });
+            
+            let block = function.blocks.get_mut(&block_id).unwrap();
+            block.instructions.insert(phis, (hit_id, hit));
+            if let Some(branch) = branch {
+                block.instructions.push(branch);
+            }
+        }
+    }
+    map
+}
+
+fn counter_operand(counter: usize) -> Operand {
+    Operand::Literal(Literal::Int(counter as i64))
+}
+
+/// 計測用のカウンタを挿入したモジュールのカウンタの総数（挿入していなければNone）
+pub fn counter_count(module: &Module) -> Option<usize> {
+    module
+        .functions
+        .values()
+        .flat_map(|function| function.blocks.values())
+        .flat_map(|block| &block.instructions)
+        .filter_map(|(_, instruction)| match instruction {
+            Instruction::ExternalCall { function, arguments, .. } => {
+                let width = match function.as_str() {
+                    COVERAGE_HIT => 1,
+                    COVERAGE_BRANCH => 2,
+                    _ => return None,
+                };
+                match arguments.first() {
+                    Some(Operand::Literal(Literal::Int(counter))) => Some(*counter as usize + width),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .max()
+}
+
+/// 1つのファイルのカバレッジ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    pub file: PathBuf,
+    /// 行ごとの実行回数
+    pub lines: BTreeMap<usize, u64>,
+    /// 条件分岐の行と、真・偽になった回数
+    pub branches: Vec<(usize, u64, u64)>,
+    /// 関数の名前、定義の行、呼び出し回数
+    pub functions: Vec<(String, usize, u64)>,
+}
+
+impl FileCoverage {
+    /// 実行された行の数と行の総数
+    pub fn line_summary(&self) -> (usize, usize) {
+        (self.lines.values().filter(|count| **count > 0).count(), self.lines.len())
+    }
+    
+    /// 通った分岐の数と分岐の総数（1つの条件分岐は真と偽の2つに数える）
+    pub fn branch_summary(&self) -> (usize, usize) {
+        let taken = self.branches.iter().map(|(_, t, f)| (*t > 0) as usize + (*f > 0) as usize).sum();
+        (taken, self.branches.len() * 2)
+    }
+}
+
+impl CoverageMap {
+    /// プロファイルを合算し、ファイルごとのカバレッジを求める（ファイル名の順）
+    pub fn report(&self, profiles: &[CoverageProfile]) -> Result<Vec<FileCoverage>> {
+        let mut counters = vec![0u64; self.counters];
+        for profile in profiles {
+            if profile.module != self.module || profile.counters.len() != self.counters {
+                return Err(EidosError::Runtime(format!(
+                    "プロファイル（モジュール '{}'、カウンタ {} 個）が対応表（モジュール '{}'、カウンタ {} 個）と一致しません",
+                    profile.module,
+                    profile.counters.len(),
+                    self.module,
+                    self.counters
+                )));
+            }
+            for (total, count) in counters.iter_mut().zip(&profile.counters) {
+                *total = total.saturating_add(*count);
+            }
+        }
+        
+        let mut files: BTreeMap<PathBuf, FileCoverage> = BTreeMap::new();
+        for block in &self.blocks {
+            if block.lines.is_empty() && !block.entry {
+                continue;
+            }
+            let count = counters[block.counter];
+            let file = files.entry(block.file.clone()).or_insert_with(|| FileCoverage {
+                file: block.file.clone(),
+                ..Default::default()
+            });
+            // 複数のブロックにまたがる行は、最も多く実行されたブロックの回数にする
+            for line in &block.lines {
+                let entry = file.lines.entry(*line).or_insert(0);
+                *entry = (*entry).max(count);
+            }
+            if block.entry {
+                let line = block.lines.first().copied().unwrap_or(0);
+                file.functions.push((block.function.clone(), line, count));
+            }
+        }
+        for branch in &self.branches {
+            if let Some(file) = files.get_mut(&branch.file) {
+                file.branches.push((branch.line, counters[branch.counter], counters[branch.counter + 1]));
+            }
+        }
+        Ok(files.into_values().collect())
+    }
+}
+
+/// カバレッジを lcov のトレースファイルの形式で出力
+pub fn to_lcov(files: &[FileCoverage]) -> String {
+    let mut out = String::new();
+    for file in files {
+        let _ = writeln!(out, "TN:\nSF:{}", file.file.display());
+        for (name, line, _) in &file.functions {
+            let _ = writeln!(out, "FN:{},{}", line, name);
+        }
+        for (name, _, count) in &file.functions {
+            let _ = writeln!(out, "FNDA:{},{}", count, name);
+        }
+        let _ = writeln!(out, "FNF:{}", file.functions.len());
+        let _ = writeln!(out, "FNH:{}", file.functions.iter().filter(|(_, _, count)| *count > 0).count());
+        for (index, (line, taken, not_taken)) in file.branches.iter().enumerate() {
+            // 分岐を含むブロックが実行されなかった場合は `-` にする
+            let show = |count: u64| if taken + not_taken == 0 { "-".to_string() } else { count.to_string() };
+            let _ = writeln!(out, "BRDA:{},{},0,{}", line, index, show(*taken));
+            let _ = writeln!(out, "BRDA:{},{},1,{}", line, index, show(*not_taken));
+        }
+        let (branches_hit, branches_found) = file.branch_summary();
+        let _ = writeln!(out, "BRF:{}\nBRH:{}", branches_found, branches_hit);
+        for (line, count) in &file.lines {
+            let _ = writeln!(out, "DA:{},{}", line, count);
+        }
+        let (lines_hit, lines_found) = file.line_summary();
+        let _ = writeln!(out, "LF:{}\nLH:{}\nend_of_record", lines_found, lines_hit);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    
+    use super::*;
+    use crate::core::eir::{self, BinaryOp, Function, FunctionId};
+    use crate::core::error::SourceLocation;
+    use crate::core::ir_builder::IrBuilder;
+    use crate::core::types::Type;
+    
+    #[test]
+    fn test_instrument_and_report() {
+        let mut module = Module::new("abs");
+        let int = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "abs", func_type, int);
+        let x = Operand::Register(function.add_parameter("x", int));
+        
+        let mut builder = IrBuilder::new(function);
+        let negative = builder.create_block();
+        let positive = builder.create_block();
+        let at = |line| Some(SourceLocation::new(PathBuf::from("abs.eid"), line, 5, 1));
+        builder.set_location(at(2));
+        let is_negative = builder.binary(BinaryOp::Lt, x.clone(), Operand::Literal(Literal::Int(0)), bool_type);
+        builder.cond_br(Operand::Register(is_negative), negative, positive);
+        builder.position_at_end(negative);
+        builder.set_location(at(3));
+        let negated = builder.unary(crate::core::eir::UnaryOp::Neg, x.clone(), int);
+        builder.ret(Some(Operand::Register(negated)));
+        builder.position_at_end(positive);
+        builder.set_location(None);
+        builder.ret(Some(x));
+        module.add_function(builder.finish());
+        
+        let map = instrument(&mut module);
+        eir::verify(&module).unwrap();
+        assert_eq!(map.counters, 5);
+        assert_eq!(counter_count(&module), Some(5));
+        let entry = &module.functions[&FunctionId(0)].blocks[&crate::core::eir::BlockId(0)];
+        assert!(matches!(&entry.instructions[0].1, Instruction::ExternalCall { function, .. } if function == COVERAGE_HIT));
+        assert!(matches!(&entry.instructions.last().unwrap().1, Instruction::ExternalCall { function, .. } if function == COVERAGE_BRANCH));
+        assert_eq!(map.branches[0].line, 2);
+        
+        // `abs(5)` を2回呼び出した実行
+        let profile = CoverageProfile { module: "abs".to_string(), counters: vec![2, 0, 2, 0, 2] };
+        let files = map.report(&[profile]).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file, Path::new("abs.eid"));
+        assert_eq!(files[0].line_summary(), (1, 2));
+        assert_eq!(files[0].branch_summary(), (1, 2));
+        let lcov = to_lcov(&files);
+        assert!(lcov.starts_with("TN:\nSF:abs.eid\nFN:2,abs\nFNDA:2,abs\nFNF:1\nFNH:1\n"));
+        assert!(lcov.contains("BRDA:2,0,0,0\nBRDA:2,0,1,2\nBRF:2\nBRH:1\nDA:2,2\nDA:3,0\nLF:2\nLH:1\nend_of_record\n"));
+        
+        let mismatched = CoverageProfile { module: "abs".to_string(), counters: vec![1] };
+        assert!(map.report(&[mismatched]).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::core::eir::{Function, FunctionId, Instruction, Module, Operand};
+use crate::core::error::SourceLocation;
+
+/// `main` から呼ばれなくても残す関数に付ける属性（`#[export] fn name() { ... }`）
+///
+/// 外部のコードから名前で呼び出す関数（FFIのコールバックなど）に付ける。
+pub const EXPORT_ATTRIBUTE: &str = "export";
+
+/// 取り除いた関数
+#[derive(Debug, Clone)]
+pub struct DeadFunction {
+    pub name: String,
+    /// 関数を定義したソース上の位置
+    pub location: Option<SourceLocation>,
+    /// 取り除いた命令の数
+    pub instructions: usize,
+}
+
+impl fmt::Display for DeadFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "未使用の関数 '{}' を取り除きました（{} 命令", self.name, self.instructions)?;
+        if let Some(location) = &self.location {
+            write!(f, ", {}:{}", location.file.display(), location.line)?;
+        }
+        write!(f, "）")
+    }
+}
+
+/// エントリーポイントと `#[export]` の関数から到達できない関数を取り除く（取り除いた関数をID順に返す）
+///
+/// 呼び出しに加えて、関数をオペランドとして参照する命令（関数ポインタとして外部関数に渡す場合を含む）も
+/// 到達とみなす。エントリーポイントのないモジュールはライブラリとしてどの関数も外から呼ばれうるので、
+/// 何も取り除かない。
+pub fn eliminate(module: &mut Module) -> Vec<DeadFunction> {
+    let Some(live) = live_functions(module) else {
+        return Vec::new();
+    };
+    let mut dead: Vec<FunctionId> = module.functions.keys().filter(|id| !live.contains(id)).copied().collect();
+    dead.sort_by_key(|id| id.0);
+    dead.into_iter()
+        .filter_map(|id| module.functions.remove(&id))
+        .map(|function| DeadFunction {
+            instructions: function.blocks.values().map(|block| block.instructions.len()).sum(),
+            name: function.name,
+            location: function.location,
+        })
+        .collect()
+}
+
+/// エントリーポイントと `#[export]` の関数から到達できる関数（エントリーポイントがなければ `None`）
+fn live_functions(module: &Module) -> Option<HashSet<FunctionId>> {
+    let entry = module.entry_point?;
+    let ids: HashMap<&str, FunctionId> = module
+        .functions
+        .values()
+        .map(|function| (function.name.as_str(), function.id))
+        .collect();
+    
+    let mut live = HashSet::new();
+    let mut worklist: Vec<FunctionId> = module
+        .functions
+        .values()
+        .filter(|function| function.attributes.tags.contains(EXPORT_ATTRIBUTE))
+        .map(|function| function.id)
+        .collect();
+    worklist.push(entry);
+    while let Some(id) = worklist.pop() {
+        if !live.insert(id) {
+            continue;
+        }
+        if let Some(function) = module.functions.get(&id) {
+            worklist.extend(references(module, function, &ids));
+        }
+    }
+    Some(live)
+}
+
+/// 関数が呼び出す、または参照する関数
+fn references(module: &Module, function: &Function, ids: &HashMap<&str, FunctionId>) -> Vec<FunctionId> {
+    let mut referenced = Vec::new();
+    for block in function.blocks.values() {
+        let operands = block
+            .instructions
+            .iter()
+            .flat_map(|(_, instruction)| instruction.operands())
+            .chain(block.terminator.iter().flat_map(|terminator| terminator.operands()));
+        for operand in operands {
+            match operand {
+                Operand::Function(id) => referenced.push(*id),
+                // グローバル変数と同じ名前でなければ関数の名前として参照している
+                Operand::Global(name) if module.get_global(name).is_none() => referenced.extend(ids.get(name.as_str())),
+                _ => {}
+            }
+        }
+        for (_, instruction) in &block.instructions {
+            if let Instruction::Call { function, .. } = instruction {
+                referenced.extend(ids.get(function.as_str()));
+            }
+        }
+    }
+    referenced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::{Literal, Terminator};
+    use crate::core::types::Type;
+    
+    /// `callees` を呼び出し、`pointers` を関数ポインタとして参照する関数
+    fn function(module: &mut Module, id: u32, name: &str, callees: &[&str], pointers: &[u32]) -> Function {
+        let unit = module.add_type(Type::unit());
+        let mut function = Function::new(FunctionId(id), name, unit, unit);
+        let entry = function.entry_block;
+        for callee in callees {
+            function.add_instruction(entry, Instruction::Call { function: callee.to_string(), arguments: Vec::new(), result: None });
+        }
+        for pointer in pointers {
+            let arguments = vec![Operand::Function(FunctionId(*pointer))];
+            function.add_instruction(entry, Instruction::ExternalCall { function: "qsort".to_string(), arguments, result: None });
+        }
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return { value: Some(Operand::Literal(Literal::Unit)) });
+        function
+    }
+    
+    #[test]
+    fn test_unreachable_functions_are_removed() {
+        let mut module = Module::new("test");
+        let functions = [
+            function(&mut module, 0, "main", &["helper"], &[]),
+            function(&mut module, 1, "helper", &[], &[2]),
+            function(&mut module, 2, "compare", &[], &[]),
+            function(&mut module, 3, "unused", &["helper"], &[]),
+            function(&mut module, 4, "callback", &[], &[]),
+        ];
+        for function in functions {
+            module.add_function(function);
+        }
+        module.get_function_mut(FunctionId(4)).unwrap().attributes.tags.insert(EXPORT_ATTRIBUTE.to_string());
+        module.set_entry_point(FunctionId(0));
+        
+        let dead = eliminate(&mut module);
+        let names: Vec<&str> = dead.iter().map(|function| function.name.as_str()).collect();
+        assert_eq!(names, ["unused"]);
+        assert_eq!(dead[0].to_string(), "未使用の関数 'unused' を取り除きました（1 命令）");
+        assert_eq!(module.functions.len(), 4);
+    }
+    
+    #[test]
+    fn test_library_without_entry_point_is_kept() {
+        let mut module = Module::new("test");
+        let function = function(&mut module, 0, "unused", &[], &[]);
+        module.add_function(function);
+        
+        assert!(eliminate(&mut module).is_empty());
+        assert_eq!(module.functions.len(), 1);
+    }
+}
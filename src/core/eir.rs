@@ -1,27 +1,70 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::sync::Arc;
 
-use crate::core::types::{Type, TypeId};
+use serde::{Deserialize, Serialize};
+
+use crate::core::arena::{ArenaKey, IdMap};
+use crate::core::eirc;
+use crate::core::error::SourceLocation;
+use crate::core::types::{Type, TypeId, TypeKind};
 use crate::core::symbol::SymbolId;
 
 /// EIR (Eidos Intermediate Representation) モジュール
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Module {
     /// モジュールの名前
     pub name: String,
     /// モジュール内の関数
+    #[serde(serialize_with = "eirc::sorted_map")]
     pub functions: HashMap<FunctionId, Function>,
     /// グローバル変数
+    #[serde(serialize_with = "eirc::sorted_map")]
     pub globals: HashMap<String, Global>,
     /// 外部関数の宣言
+    #[serde(serialize_with = "eirc::sorted_map")]
     pub external_functions: HashMap<String, ExternalFunction>,
     /// 型情報
+    #[serde(serialize_with = "eirc::sorted_map")]
     pub types: HashMap<TypeId, Type>,
     /// エントリーポイント関数のID（存在する場合）
     pub entry_point: Option<FunctionId>,
 }
 
+/// エントリー関数の呼び出し規約
+///
+/// ソースで定義する `main` は `fn main()`・`fn main() -> Int`・`fn main(args: List<String>)`・
+/// `fn main(args: List<String>) -> Int` のいずれかの形にする。実行環境は、引数を受け取る `main` に
+/// プログラム名とそれに続くコマンドライン引数を渡し、`Int` の戻り値をプロセスの終了コードにする。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EntryAbi {
+    /// コマンドライン引数（`List<String>`）を受け取る
+    pub takes_args: bool,
+    /// 戻り値（`Int`）を終了コードにする
+    pub returns_exit_code: bool,
+}
+
+impl EntryAbi {
+    /// 関数の引数と戻り値の型から呼び出し規約を判定（規約に合わなければNone）
+    pub fn of(module: &Module, function: &Function) -> Option<Self> {
+        let kind = |id: TypeId| module.get_type(id).map(|ty| &ty.kind);
+        let takes_args = match function.parameters.as_slice() {
+            [] => false,
+            [(_, ty)] => match kind(*ty)? {
+                TypeKind::Array(element) if element.kind == TypeKind::String => true,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let returns_exit_code = match kind(function.return_type)? {
+            TypeKind::Unit => false,
+            TypeKind::Int => true,
+            _ => return None,
+        };
+        Some(Self { takes_args, returns_exit_code })
+    }
+}
+
 impl Module {
     /// 新しいモジュールを作成
     pub fn new(name: &str) -> Self {
@@ -79,6 +122,12 @@ impl Module {
         self.functions.values().find(|f| f.name == name)
     }
     
+    /// エントリー関数の呼び出し規約（エントリー関数がないか、規約に合わなければNone）
+    pub fn entry_abi(&self) -> Option<EntryAbi> {
+        let entry = self.entry_point.and_then(|id| self.get_function(id))?;
+        EntryAbi::of(self, entry)
+    }
+    
     /// グローバル変数を取得
     pub fn get_global(&self, name: &str) -> Option<&Global> {
         self.globals.get(name)
@@ -91,7 +140,7 @@ impl Module {
 }
 
 /// 関数ID
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct FunctionId(pub u32);
 
 impl fmt::Display for FunctionId {
@@ -101,7 +150,7 @@ impl fmt::Display for FunctionId {
 }
 
 /// 基本ブロックID
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BlockId(pub u32);
 
 impl fmt::Display for BlockId {
@@ -110,8 +159,14 @@ impl fmt::Display for BlockId {
     }
 }
 
+impl ArenaKey for BlockId {
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
 /// 命令ID
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct InstructionId(pub u32);
 
 impl fmt::Display for InstructionId {
@@ -120,8 +175,14 @@ impl fmt::Display for InstructionId {
     }
 }
 
+impl ArenaKey for InstructionId {
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
 /// レジスタID
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct RegisterId(pub u32);
 
 impl fmt::Display for RegisterId {
@@ -130,8 +191,14 @@ impl fmt::Display for RegisterId {
     }
 }
 
+impl ArenaKey for RegisterId {
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
 /// EIR 関数
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Function {
     /// 関数ID
     pub id: FunctionId,
@@ -144,7 +211,7 @@ pub struct Function {
     /// 戻り値の型
     pub return_type: TypeId,
     /// 基本ブロック
-    pub blocks: HashMap<BlockId, BasicBlock>,
+    pub blocks: IdMap<BlockId, BasicBlock>,
     /// エントリーブロック
     pub entry_block: BlockId,
     /// 次に割り当てるブロックID
@@ -154,16 +221,25 @@ pub struct Function {
     /// 次に割り当てる命令ID
     pub next_instruction_id: u32,
     /// レジスタの型情報
-    pub register_types: HashMap<RegisterId, TypeId>,
+    pub register_types: IdMap<RegisterId, TypeId>,
     /// 関数の属性
     pub attributes: FunctionAttributes,
+    /// 関数を定義したソース上の位置（ASTから構築した場合）
+    pub location: Option<SourceLocation>,
+    /// 命令に対応するソース上の位置（実行時エラーのスタックトレースに使う）
+    pub locations: IdMap<InstructionId, SourceLocation>,
+    /// 実行時の検査を省いてよい除算・剰余（値域解析で除数が0にならず、オーバーフローもしないと分かったもの）
+    #[serde(serialize_with = "eirc::sorted_set")]
+    pub unchecked: HashSet<InstructionId>,
+    /// 命令ごとの属性（既定値の命令は登録しない）
+    pub instruction_attributes: IdMap<InstructionId, InstructionAttributes>,
 }
 
 impl Function {
     /// 新しい関数を作成
     pub fn new(id: FunctionId, name: &str, function_type: TypeId, return_type: TypeId) -> Self {
         let entry_block_id = BlockId(0);
-        let mut blocks = HashMap::new();
+        let mut blocks = IdMap::new();
         blocks.insert(entry_block_id, BasicBlock::new(entry_block_id));
         
         Self {
@@ -177,8 +253,12 @@ impl Function {
             next_block_id: 1,
             next_register_id: 0,
             next_instruction_id: 0,
-            register_types: HashMap::new(),
+            register_types: IdMap::new(),
             attributes: FunctionAttributes::default(),
+            location: None,
+            locations: IdMap::new(),
+            unchecked: HashSet::new(),
+            instruction_attributes: IdMap::new(),
         }
     }
     
@@ -214,6 +294,49 @@ impl Function {
         instr_id
     }
     
+    /// 命令に対応するソース上の位置を記録
+    pub fn set_location(&mut self, instr_id: InstructionId, location: SourceLocation) {
+        self.locations.insert(instr_id, location);
+    }
+    
+    /// 命令に対応するソース上の位置を取得
+    pub fn get_location(&self, instr_id: InstructionId) -> Option<&SourceLocation> {
+        self.locations.get(&instr_id)
+    }
+    
+    /// 命令の属性を取得（登録していなければ `None`）
+    pub fn get_instruction_attributes(&self, instr_id: InstructionId) -> Option<&InstructionAttributes> {
+        self.instruction_attributes.get(&instr_id)
+    }
+    
+    /// 命令の属性を変更する（登録していなければ既定値で登録する）
+    pub fn instruction_attributes_mut(&mut self, instr_id: InstructionId) -> &mut InstructionAttributes {
+        if !self.instruction_attributes.contains_key(&instr_id) {
+            self.instruction_attributes.insert(instr_id, InstructionAttributes::default());
+        }
+        self.instruction_attributes.get_mut(&instr_id).unwrap()
+    }
+    
+    /// 別の関数の命令に付いた位置情報・検査の省略・属性を、この関数の命令に写す
+    pub fn copy_instruction_metadata(&mut self, instr_id: InstructionId, from: &Function, from_id: InstructionId) {
+        if let Some(location) = from.get_location(from_id) {
+            self.set_location(instr_id, location.clone());
+        }
+        if from.unchecked.contains(&from_id) {
+            self.unchecked.insert(instr_id);
+        }
+        if let Some(attributes) = from.get_instruction_attributes(from_id) {
+            self.instruction_attributes.insert(instr_id, attributes.clone());
+        }
+    }
+    
+    /// 取り除いた命令の位置情報・検査の省略・属性を消す
+    pub fn forget_instruction(&mut self, instr_id: InstructionId) {
+        self.locations.remove(&instr_id);
+        self.unchecked.remove(&instr_id);
+        self.instruction_attributes.remove(&instr_id);
+    }
+    
     /// 命令を置き換え
     pub fn replace_instruction(&mut self, block_id: BlockId, instr_id: InstructionId, new_instruction: Instruction) -> bool {
         if let Some(block) = self.blocks.get_mut(&block_id) {
@@ -273,7 +396,7 @@ impl Function {
 }
 
 /// 基本ブロック
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BasicBlock {
     /// ブロックID
     pub id: BlockId,
@@ -315,10 +438,24 @@ impl BasicBlock {
             self.predecessors.push(block_id);
         }
     }
+    
+    /// 分岐先のブロック（終了命令がなければ最後の分岐命令から求める）
+    pub fn successors(&self) -> Vec<BlockId> {
+        if let Some(terminator) = &self.terminator {
+            return terminator.successors();
+        }
+        match self.instructions.last() {
+            Some((_, Instruction::Branch { target })) => vec![*target],
+            Some((_, Instruction::BranchCond { true_target, false_target, .. })) => {
+                distinct_blocks(vec![*true_target, *false_target])
+            }
+            _ => Vec::new(),
+        }
+    }
 }
 
 /// ブロック終了命令
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Terminator {
     /// 無条件分岐
     Branch {
@@ -356,8 +493,73 @@ pub enum Terminator {
     Unreachable,
 }
 
+impl Terminator {
+    /// 分岐先のブロック（同じブロックへの分岐は1つにまとめる）
+    pub fn successors(&self) -> Vec<BlockId> {
+        let targets = match self {
+            Self::Branch { target, .. } => vec![*target],
+            Self::BranchCond { true_target, false_target, .. } => vec![*true_target, *false_target],
+            Self::Switch { default_target, cases, .. } => {
+                let mut targets = vec![*default_target];
+                targets.extend(cases.iter().map(|(_, target, _)| *target));
+                targets
+            },
+            Self::IndirectCall { return_block, .. } => vec![*return_block],
+            Self::Return { .. } | Self::Unreachable => Vec::new(),
+        };
+        distinct_blocks(targets)
+    }
+    
+    /// 条件・戻り値・分岐先に渡す引数などのオペランドを取得
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Self::Branch { args, .. } => args.iter().collect(),
+            Self::BranchCond { condition, true_args, false_args, .. } => {
+                std::iter::once(condition).chain(true_args.iter()).chain(false_args.iter()).collect()
+            },
+            Self::Return { value } => value.iter().collect(),
+            Self::Switch { value, default_args, cases, .. } => std::iter::once(value)
+                .chain(default_args.iter())
+                .chain(cases.iter().flat_map(|(_, _, args)| args.iter()))
+                .collect(),
+            Self::IndirectCall { function_ptr, arguments, return_args, .. } => std::iter::once(function_ptr)
+                .chain(arguments.iter())
+                .chain(return_args.iter())
+                .collect(),
+            Self::Unreachable => Vec::new(),
+        }
+    }
+    
+    /// 条件・戻り値・分岐先に渡す引数などのオペランドを可変で取得
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Self::Branch { args, .. } => args.iter_mut().collect(),
+            Self::BranchCond { condition, true_args, false_args, .. } => {
+                std::iter::once(condition).chain(true_args.iter_mut()).chain(false_args.iter_mut()).collect()
+            },
+            Self::Return { value } => value.iter_mut().collect(),
+            Self::Switch { value, default_args, cases, .. } => std::iter::once(value)
+                .chain(default_args.iter_mut())
+                .chain(cases.iter_mut().flat_map(|(_, _, args)| args.iter_mut()))
+                .collect(),
+            Self::IndirectCall { function_ptr, arguments, return_args, .. } => std::iter::once(function_ptr)
+                .chain(arguments.iter_mut())
+                .chain(return_args.iter_mut())
+                .collect(),
+            Self::Unreachable => Vec::new(),
+        }
+    }
+}
+
+/// 最初に現れた順を保って重複したブロックを取り除く
+fn distinct_blocks(mut blocks: Vec<BlockId>) -> Vec<BlockId> {
+    let mut seen = HashSet::new();
+    blocks.retain(|block| seen.insert(*block));
+    blocks
+}
+
 /// EIR 命令
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Instruction {
     /// 二項演算
     BinaryOp {
@@ -382,7 +584,7 @@ pub enum Instruction {
         address: Operand,
         value: Operand,
     },
-    /// 関数呼び出し
+    /// 関数呼び出し（複数の値を返す関数の結果はタプル型のレジスタ1つで受け取り、`Extract` で分解する）
     Call {
         function: String,
         arguments: Vec<Operand>,
@@ -408,6 +610,9 @@ pub enum Instruction {
         result: RegisterId,
     },
     /// 構造体メンバーアクセス
+    ///
+    /// `base` が固定長配列かスライスなら、`indices` の1つ目の添字の要素のアドレスを求める。
+    /// 結果のレジスタの型は要素の型で、要素の間隔は要素の型の `Type::stride` のバイト数。
     GetElementPtr {
         base: Operand,
         indices: Vec<Operand>,
@@ -431,11 +636,37 @@ pub enum Instruction {
         false_value: Operand,
         result: RegisterId,
     },
-    /// アトミック操作
+    /// 値を並べたタプルを作る（複数の値を返す関数は、戻り値をタプルにまとめて返す）
+    Tuple {
+        elements: Vec<Operand>,
+        result: RegisterId,
+    },
+    /// タプルの `index` 番目の値を取り出す
+    Extract {
+        tuple: Operand,
+        index: usize,
+        result: RegisterId,
+    },
+    /// 固定長配列の先頭のアドレスと長さから、配列を指すスライスを作る
+    MakeSlice {
+        array: Operand,
+        length: Operand,
+        result: RegisterId,
+    },
+    /// スライスの長さ（要素の数）を取り出す
+    SliceLen {
+        slice: Operand,
+        result: RegisterId,
+    },
+    /// アトミック操作（順序はすべて逐次一貫）
+    ///
+    /// `value` は書き込む値や加える値で、`Load` では `None`。`expected` は `CAS` で比較する値で、
+    /// `CAS` の結果は置き換えたかどうかの真偽値、読み込みと読み書き操作の結果は操作する前の値。
     Atomic {
         op: AtomicOp,
         address: Operand,
         value: Option<Operand>,
+        expected: Option<Operand>,
         result: Option<RegisterId>,
     },
     /// 外部関数呼び出し
@@ -445,11 +676,15 @@ pub enum Instruction {
         result: Option<RegisterId>,
     },
     /// インライン・アセンブリ
+    ///
+    /// `asm` の `{0}`, `{1}`, ... は出力、入力の順に並べたオペランドを指す。`constraints` はLLVMの
+    /// 制約文字列（`"=r,r,~{cc}"`）で、出力・入力・破壊するレジスタを表す。
     InlineAsm {
         asm: String,
         constraints: String,
         args: Vec<Operand>,
         result: Option<RegisterId>,
+        dialect: AsmDialect,
     },
     /// デバッグ情報
     DebugInfo {
@@ -470,6 +705,10 @@ impl Instruction {
             Self::Cast { result, .. } => Some(*result),
             Self::Phi { result, .. } => Some(*result),
             Self::Select { result, .. } => Some(*result),
+            Self::Tuple { result, .. } => Some(*result),
+            Self::Extract { result, .. } => Some(*result),
+            Self::MakeSlice { result, .. } => Some(*result),
+            Self::SliceLen { result, .. } => Some(*result),
             Self::Atomic { result, .. } => *result,
             Self::ExternalCall { result, .. } => *result,
             Self::InlineAsm { result, .. } => *result,
@@ -477,6 +716,29 @@ impl Instruction {
         }
     }
     
+    /// この命令が結果を定義するレジスタを可変で取得（命令を別の関数へ移すときの付け替えに使う）
+    pub fn defined_register_mut(&mut self) -> Option<&mut RegisterId> {
+        match self {
+            Self::BinaryOp { result, .. }
+            | Self::UnaryOp { result, .. }
+            | Self::Load { result, .. }
+            | Self::Alloca { result, .. }
+            | Self::GetElementPtr { result, .. }
+            | Self::Cast { result, .. }
+            | Self::Phi { result, .. }
+            | Self::Select { result, .. }
+            | Self::Tuple { result, .. }
+            | Self::Extract { result, .. }
+            | Self::MakeSlice { result, .. }
+            | Self::SliceLen { result, .. } => Some(result),
+            Self::Call { result, .. }
+            | Self::Atomic { result, .. }
+            | Self::ExternalCall { result, .. }
+            | Self::InlineAsm { result, .. } => result.as_mut(),
+            _ => None,
+        }
+    }
+    
     /// この命令が使用するレジスタを取得
     pub fn used_registers(&self) -> Vec<RegisterId> {
         let mut registers = Vec::new();
@@ -535,10 +797,25 @@ impl Instruction {
                 extract_registers(true_value, &mut registers);
                 extract_registers(false_value, &mut registers);
             },
-            Self::Atomic { address, value, .. } => {
+            Self::Tuple { elements, .. } => {
+                for element in elements {
+                    extract_registers(element, &mut registers);
+                }
+            },
+            Self::Extract { tuple, .. } => {
+                extract_registers(tuple, &mut registers);
+            },
+            Self::MakeSlice { array, length, .. } => {
+                extract_registers(array, &mut registers);
+                extract_registers(length, &mut registers);
+            },
+            Self::SliceLen { slice, .. } => {
+                extract_registers(slice, &mut registers);
+            },
+            Self::Atomic { address, value, expected, .. } => {
                 extract_registers(address, &mut registers);
-                if let Some(val) = value {
-                    extract_registers(val, &mut registers);
+                for operand in value.iter().chain(expected.iter()) {
+                    extract_registers(operand, &mut registers);
                 }
             },
             Self::ExternalCall { arguments, .. } => {
@@ -556,10 +833,62 @@ impl Instruction {
         
         registers
     }
+    
+    /// この命令のオペランドを取得
+    pub fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Self::BinaryOp { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::UnaryOp { operand, .. } => vec![operand],
+            Self::Load { address, .. } => vec![address],
+            Self::Store { address, value } => vec![address, value],
+            Self::Call { arguments, .. } | Self::ExternalCall { arguments, .. } => arguments.iter().collect(),
+            Self::Return { value } => value.iter().collect(),
+            Self::BranchCond { condition, .. } => vec![condition],
+            Self::GetElementPtr { base, indices, .. } => std::iter::once(base).chain(indices.iter()).collect(),
+            Self::Cast { value, .. } => vec![value],
+            Self::Phi { incoming, .. } => incoming.iter().map(|(value, _)| value).collect(),
+            Self::Select { condition, true_value, false_value, .. } => vec![condition, true_value, false_value],
+            Self::Tuple { elements, .. } => elements.iter().collect(),
+            Self::Extract { tuple, .. } => vec![tuple],
+            Self::MakeSlice { array, length, .. } => vec![array, length],
+            Self::SliceLen { slice, .. } => vec![slice],
+            Self::Atomic { address, value, expected, .. } => {
+                std::iter::once(address).chain(expected.iter()).chain(value.iter()).collect()
+            }
+            Self::InlineAsm { args, .. } => args.iter().collect(),
+            Self::Branch { .. } | Self::Alloca { .. } | Self::DebugInfo { .. } => Vec::new(),
+        }
+    }
+    
+    /// この命令のオペランドを可変で取得（定数の伝播などでオペランドを置き換える）
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Self::BinaryOp { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::UnaryOp { operand, .. } => vec![operand],
+            Self::Load { address, .. } => vec![address],
+            Self::Store { address, value } => vec![address, value],
+            Self::Call { arguments, .. } | Self::ExternalCall { arguments, .. } => arguments.iter_mut().collect(),
+            Self::Return { value } => value.iter_mut().collect(),
+            Self::BranchCond { condition, .. } => vec![condition],
+            Self::GetElementPtr { base, indices, .. } => std::iter::once(base).chain(indices.iter_mut()).collect(),
+            Self::Cast { value, .. } => vec![value],
+            Self::Phi { incoming, .. } => incoming.iter_mut().map(|(value, _)| value).collect(),
+            Self::Select { condition, true_value, false_value, .. } => vec![condition, true_value, false_value],
+            Self::Tuple { elements, .. } => elements.iter_mut().collect(),
+            Self::Extract { tuple, .. } => vec![tuple],
+            Self::MakeSlice { array, length, .. } => vec![array, length],
+            Self::SliceLen { slice, .. } => vec![slice],
+            Self::Atomic { address, value, expected, .. } => {
+                std::iter::once(address).chain(expected.iter_mut()).chain(value.iter_mut()).collect()
+            }
+            Self::InlineAsm { args, .. } => args.iter_mut().collect(),
+            Self::Branch { .. } | Self::Alloca { .. } | Self::DebugInfo { .. } => Vec::new(),
+        }
+    }
 }
 
 /// 命令オペランド
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Operand {
     /// レジスタ
     Register(RegisterId),
@@ -578,7 +907,7 @@ pub enum Operand {
 }
 
 /// リテラル値
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Literal {
     /// 整数値
     Int(i64),
@@ -595,7 +924,7 @@ pub enum Literal {
 }
 
 /// 二項演算子
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BinaryOp {
     /// 加算
     Add,
@@ -636,7 +965,7 @@ pub enum BinaryOp {
 }
 
 /// 単項演算子
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UnaryOp {
     /// 符号反転
     Neg,
@@ -648,8 +977,79 @@ pub enum UnaryOp {
     Cast,
 }
 
+/// インライン・アセンブリの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AsmDialect {
+    /// ターゲットのネイティブ命令（`asm!`）
+    Native,
+    /// WebAssemblyのテキスト形式の命令列（`wasm!`）
+    Wasm,
+}
+
+impl AsmDialect {
+    /// ソースで使うマクロの名前
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Self::Native => "asm",
+            Self::Wasm => "wasm",
+        }
+    }
+}
+
+/// インライン・アセンブリのテンプレートの断片
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmPiece {
+    /// そのまま出力する命令の文字列
+    Text(String),
+    /// `{N}` で参照する N 番目のオペランド
+    Operand(usize),
+}
+
+/// インライン・アセンブリのテンプレートを断片に分ける（`{{` と `}}` は波括弧そのもの）
+pub fn parse_asm_template(template: &str) -> Vec<AsmPiece> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(digit);
+                }
+                match (digits.parse(), chars.next_if_eq(&'}')) {
+                    (Ok(index), Some(_)) => {
+                        if !text.is_empty() {
+                            pieces.push(AsmPiece::Text(std::mem::take(&mut text)));
+                        }
+                        pieces.push(AsmPiece::Operand(index));
+                    }
+                    // `{N}` の形でなければ文字列として残す
+                    _ => {
+                        text.push('{');
+                        text.push_str(&digits);
+                    }
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(AsmPiece::Text(text));
+    }
+    pieces
+}
+
 /// アトミック操作の種類
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AtomicOp {
     /// 読み込み
     Load,
@@ -669,8 +1069,24 @@ pub enum AtomicOp {
     Xor,
 }
 
+impl AtomicOp {
+    /// WebAssemblyの共有メモリのアトミック命令（64ビット整数）
+    pub fn wasm_instruction(&self) -> &'static str {
+        match self {
+            AtomicOp::Load => "i64.atomic.load",
+            AtomicOp::Store => "i64.atomic.store",
+            AtomicOp::CAS => "i64.atomic.rmw.cmpxchg",
+            AtomicOp::Add => "i64.atomic.rmw.add",
+            AtomicOp::Sub => "i64.atomic.rmw.sub",
+            AtomicOp::And => "i64.atomic.rmw.and",
+            AtomicOp::Or => "i64.atomic.rmw.or",
+            AtomicOp::Xor => "i64.atomic.rmw.xor",
+        }
+    }
+}
+
 /// グローバル変数
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Global {
     /// 名前
     pub name: String,
@@ -687,7 +1103,7 @@ pub struct Global {
 }
 
 /// リンケージの種類
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Linkage {
     /// デフォルト
     Default,
@@ -702,7 +1118,7 @@ pub enum Linkage {
 }
 
 /// グローバル変数の属性
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GlobalAttributes {
     /// 定数かどうか
     pub is_constant: bool,
@@ -715,7 +1131,7 @@ pub struct GlobalAttributes {
 }
 
 /// 関数属性
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FunctionAttributes {
     /// インライン化の指示
     pub inline: InlineDirective,
@@ -725,12 +1141,17 @@ pub struct FunctionAttributes {
     pub pure: bool,
     /// 副作用なしかどうか
     pub no_side_effects: bool,
+    /// 非同期関数（`async fn`）かどうか
+    pub is_async: bool,
+    /// 実行される頻度の見込み
+    pub hotness: Hotness,
     /// この関数の属性タグ
+    #[serde(serialize_with = "eirc::sorted_set")]
     pub tags: HashSet<String>,
 }
 
 /// インライン化の指示
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InlineDirective {
     /// デフォルト
     Default,
@@ -748,8 +1169,67 @@ impl Default for InlineDirective {
     }
 }
 
+/// 実行される頻度の見込み（バックエンドが配置や最適化の重みに使う）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Hotness {
+    /// 見込みなし
+    #[default]
+    Normal,
+    /// 頻繁に実行される
+    Hot,
+    /// ほとんど実行されない
+    Cold,
+}
+
+impl Hotness {
+    /// ソースの属性名（`#[hot]`、`#[cold]`）から変換
+    pub fn from_attribute(name: &str) -> Option<Self> {
+        match name {
+            "hot" => Some(Self::Hot),
+            "cold" => Some(Self::Cold),
+            _ => None,
+        }
+    }
+}
+
+/// `assert`・`require`・`ensure` が失敗したときの `panic` の呼び出しに付けるタグ
+///
+/// 値域解析で条件が常に真と分かった検査は、このタグを目印に取り除く（`range_analysis::elide_assertions`）。
+pub const ASSERTION_TAG: &str = "assertion";
+
+/// 命令の属性
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstructionAttributes {
+    /// 呼び出し命令でのインライン化の指示
+    pub inline: InlineDirective,
+    /// この命令が実行される頻度の見込み
+    pub hotness: Hotness,
+    /// DSLの処理系などが付けるタグ
+    pub tags: BTreeSet<String>,
+}
+
+impl InstructionAttributes {
+    /// EIRのテキスト表現で命令の後ろに付ける注記
+    fn annotations(&self) -> Vec<String> {
+        let mut annotations = Vec::new();
+        match self.inline {
+            InlineDirective::Default => {}
+            InlineDirective::Always => annotations.push("inline(always)".to_string()),
+            InlineDirective::Never => annotations.push("noinline".to_string()),
+            InlineDirective::Hint => annotations.push("inline".to_string()),
+        }
+        match self.hotness {
+            Hotness::Normal => {}
+            Hotness::Hot => annotations.push("hot".to_string()),
+            Hotness::Cold => annotations.push("cold".to_string()),
+        }
+        annotations.extend(self.tags.iter().cloned());
+        annotations
+    }
+}
+
 /// 外部関数定義
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExternalFunction {
     /// 関数名
     pub name: String,
@@ -766,7 +1246,7 @@ pub struct ExternalFunction {
 }
 
 /// 呼び出し規約
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CallingConvention {
     /// デフォルト
     Default,
@@ -818,4 +1298,269 @@ impl FunctionUseGraph {
     pub fn get_use_locations(&self, reg: RegisterId) -> Vec<(BlockId, InstructionId)> {
         self.register_uses.get(&reg).cloned().unwrap_or_default()
     }
-}
\ No newline at end of file
+}
+
+/// EIRのテキスト表現（スナップショットテストや `--emit eir` で使う）
+///
+/// `HashMap` の順序に依存しないよう、グローバル・外部関数は名前順、関数とブロックはID順に並べる。
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "module {}", self.name)?;
+        if let Some(entry) = self.entry_point {
+            writeln!(f, "entry {}", entry)?;
+        }
+        
+        let mut globals: Vec<&Global> = self.globals.values().collect();
+        globals.sort_by(|a, b| a.name.cmp(&b.name));
+        if !globals.is_empty() {
+            writeln!(f)?;
+        }
+        for global in globals {
+            let keyword = if global.attributes.is_constant { "const" } else { "global" };
+            write!(f, "{} @{}: {}", keyword, global.name, self.type_name(global.ty))?;
+            if let Some(init) = &global.initializer {
+                write!(f, " = {}", init)?;
+            }
+            writeln!(f)?;
+        }
+        
+        let mut externals: Vec<&ExternalFunction> = self.external_functions.values().collect();
+        externals.sort_by(|a, b| a.name.cmp(&b.name));
+        if !externals.is_empty() {
+            writeln!(f)?;
+        }
+        for external in externals {
+            let mut params: Vec<String> = external.parameter_types.iter().map(|ty| self.type_name(*ty)).collect();
+            if external.is_variadic {
+                params.push("...".to_string());
+            }
+            writeln!(f, "extern fn {}({}) -> {}", external.name, params.join(", "), self.type_name(external.return_type))?;
+        }
+        
+        let mut functions: Vec<&Function> = self.functions.values().collect();
+        functions.sort_by_key(|function| function.id.0);
+        for function in functions {
+            writeln!(f)?;
+            let params: Vec<String> = function
+                .parameters
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, self.type_name(*ty)))
+                .collect();
+            writeln!(
+                f,
+                "fn {} {}({}) -> {} {{",
+                function.id,
+                function.name,
+                params.join(", "),
+                self.type_name(function.return_type)
+            )?;
+            
+            let mut blocks: Vec<&BasicBlock> = function.blocks.values().collect();
+            blocks.sort_by_key(|block| block.id.0);
+            for block in blocks {
+                write!(f, "{}", block.id)?;
+                if !block.parameters.is_empty() {
+                    let params: Vec<String> = block
+                        .parameters
+                        .iter()
+                        .map(|(reg, ty)| format!("{}: {}", reg, self.type_name(*ty)))
+                        .collect();
+                    write!(f, "({})", params.join(", "))?;
+                }
+                writeln!(f, ":")?;
+                for (id, instruction) in &block.instructions {
+                    match instruction {
+                        // 型IDは型を作った順に振られるので、変換先は型名で表示する
+                        Instruction::Cast { value, target_type, result } => {
+                            write!(f, "    {} = cast {} to {}", result, value, self.type_name(*target_type))?
+                        }
+                        _ => write!(f, "    {}", instruction)?,
+                    }
+                    let mut annotations = Vec::new();
+                    if function.unchecked.contains(id) {
+                        annotations.push("unchecked".to_string());
+                    }
+                    if let Some(attributes) = function.get_instruction_attributes(*id) {
+                        annotations.extend(attributes.annotations());
+                    }
+                    if !annotations.is_empty() {
+                        write!(f, " ; {}", annotations.join(", "))?;
+                    }
+                    writeln!(f)?;
+                }
+                match &block.terminator {
+                    Some(terminator) => writeln!(f, "    {}", terminator)?,
+                    None => writeln!(f, "    <no terminator>")?,
+                }
+            }
+            writeln!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Module {
+    /// 型IDを表示用の型名にする（モジュールに登録されていなければIDのまま）
+    pub(crate) fn type_name(&self, id: TypeId) -> String {
+        match self.types.get(&id) {
+            Some(ty) => ty.to_string(),
+            None => id.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BinaryOp { op, lhs, rhs, result } => write!(f, "{} = {} {}, {}", result, op, lhs, rhs),
+            Self::UnaryOp { op, operand, result } => write!(f, "{} = {} {}", result, op, operand),
+            Self::Load { address, result } => write!(f, "{} = load {}", result, address),
+            Self::Store { address, value } => write!(f, "store {}, {}", address, value),
+            Self::Call { function, arguments, result } => {
+                write_result(f, *result)?;
+                write!(f, "call {}({})", function, join_operands(arguments))
+            },
+            Self::Return { value: Some(value) } => write!(f, "ret {}", value),
+            Self::Return { value: None } => write!(f, "ret"),
+            Self::Branch { target } => write!(f, "br {}", target),
+            Self::BranchCond { condition, true_target, false_target } => {
+                write!(f, "br {}, {}, {}", condition, true_target, false_target)
+            },
+            Self::Alloca { size, result } => write!(f, "{} = alloca {}", result, size),
+            Self::GetElementPtr { base, indices, result } => {
+                write!(f, "{} = gep {}[{}]", result, base, join_operands(indices))
+            },
+            Self::Cast { value, target_type, result } => write!(f, "{} = cast {} to {}", result, value, target_type),
+            Self::Phi { incoming, result } => {
+                let incoming: Vec<String> = incoming.iter().map(|(value, block)| format!("[{}, {}]", value, block)).collect();
+                write!(f, "{} = phi {}", result, incoming.join(", "))
+            },
+            Self::Select { condition, true_value, false_value, result } => {
+                write!(f, "{} = select {}, {}, {}", result, condition, true_value, false_value)
+            },
+            Self::Tuple { elements, result } => write!(f, "{} = tuple ({})", result, join_operands(elements)),
+            Self::Extract { tuple, index, result } => write!(f, "{} = extract {}, {}", result, tuple, index),
+            Self::MakeSlice { array, length, result } => write!(f, "{} = slice {}, {}", result, array, length),
+            Self::SliceLen { slice, result } => write!(f, "{} = slice_len {}", result, slice),
+            Self::Atomic { op, address, value, expected, result } => {
+                write_result(f, *result)?;
+                write!(f, "atomic {} {}", mnemonic(op), address)?;
+                for operand in expected.iter().chain(value.iter()) {
+                    write!(f, ", {}", operand)?;
+                }
+                Ok(())
+            },
+            Self::ExternalCall { function, arguments, result } => {
+                write_result(f, *result)?;
+                write!(f, "call extern {}({})", function, join_operands(arguments))
+            },
+            Self::InlineAsm { asm, constraints, args, result, dialect } => {
+                write_result(f, *result)?;
+                write!(f, "{} {:?}, {:?}({})", dialect.keyword(), asm, constraints, join_operands(args))
+            },
+            Self::DebugInfo { info } => write!(f, "; {}", info),
+        }
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Branch { target, args } => write!(f, "br {}", block_call(*target, args)),
+            Self::BranchCond { condition, true_target, true_args, false_target, false_args } => write!(
+                f,
+                "br {}, {}, {}",
+                condition,
+                block_call(*true_target, true_args),
+                block_call(*false_target, false_args)
+            ),
+            Self::Return { value: Some(value) } => write!(f, "ret {}", value),
+            Self::Return { value: None } => write!(f, "ret"),
+            Self::Switch { value, default_target, default_args, cases } => {
+                write!(f, "switch {}, default {}", value, block_call(*default_target, default_args))?;
+                for (literal, target, args) in cases {
+                    write!(f, ", {} => {}", literal, block_call(*target, args))?;
+                }
+                Ok(())
+            },
+            Self::IndirectCall { function_ptr, arguments, return_block, return_args } => write!(
+                f,
+                "call_indirect {}({}) -> {}",
+                function_ptr,
+                join_operands(arguments),
+                block_call(*return_block, return_args)
+            ),
+            Self::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Register(reg) => write!(f, "{}", reg),
+            Self::Literal(literal) => write!(f, "{}", literal),
+            Self::Global(name) => write!(f, "@{}", name),
+            Self::Function(id) => write!(f, "{}", id),
+            Self::ExternalFunction(name) => write!(f, "extern {}", name),
+            Self::Symbol(id) => write!(f, "{}", id),
+            Self::Block(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            // `1.0` が `1` と表示されないように常にDebug形式を使う
+            Self::Float(value) => write!(f, "{:?}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Char(value) => match char::from_u32(*value) {
+                Some(c) => write!(f, "{:?}", c),
+                None => write!(f, "char({})", value),
+            },
+            Self::String(value) => write!(f, "{:?}", value),
+            Self::Unit => write!(f, "()"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", mnemonic(self))
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", mnemonic(self))
+    }
+}
+
+/// 演算子の列挙子名を小文字にしてニーモニックにする（`BitAnd` → `bitand`）
+fn mnemonic(op: &impl fmt::Debug) -> String {
+    format!("{:?}", op).to_lowercase()
+}
+
+fn write_result(f: &mut fmt::Formatter<'_>, result: Option<RegisterId>) -> fmt::Result {
+    match result {
+        Some(reg) => write!(f, "{} = ", reg),
+        None => Ok(()),
+    }
+}
+
+fn join_operands(operands: &[Operand]) -> String {
+    operands.iter().map(|operand| operand.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn block_call(target: BlockId, args: &[Operand]) -> String {
+    if args.is_empty() {
+        target.to_string()
+    } else {
+        format!("{}({})", target, join_operands(args))
+    }
+}
+
+pub use super::eir_builder::ModuleBuilder;
+pub use super::eir_verifier::{verify, verify_after};
\ No newline at end of file
@@ -0,0 +1,2499 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::ast::{self, ASTNode, Node, Program};
+use crate::core::bigint;
+use crate::core::eir::{
+    self, AsmDialect, AsmPiece, AtomicOp, BinaryOp, BlockId, EntryAbi, Function, FunctionId, Global, GlobalAttributes, Hotness,
+    Instruction, Linkage, Literal, Module, Operand, RegisterId, UnaryOp,
+};
+use crate::core::ir_builder::IrBuilder;
+use crate::core::types::{NumericType, Type, TypeId, TypeKind};
+
+/// トップレベルの文をまとめる暗黙のエントリー関数名
+const ENTRY_FUNCTION_NAME: &str = "main";
+
+/// ミュータブル変数のスタックスロットサイズ（バイト）
+const SLOT_SIZE: usize = 8;
+
+/// `Option` と `Result` の値を作る名前と、対応する標準ライブラリの関数
+const VARIANT_CONSTRUCTORS: &[(&str, &str)] = &[
+    ("Some", "Option::some"),
+    ("None", "Option::none"),
+    ("Ok", "Result::ok"),
+    ("Err", "Result::err"),
+];
+
+/// ローカル変数の束縛
+#[derive(Debug, Clone)]
+enum Binding {
+    /// イミュータブル変数：値をそのまま保持する
+    Value(Operand),
+    /// ミュータブル変数：スタックスロットのアドレスを保持する
+    Slot(RegisterId),
+    /// 固定長配列：スタックに確保した配列の先頭のアドレスを保持する（`let mut` なら要素に代入できる）
+    Array { address: RegisterId, is_mutable: bool },
+}
+
+/// 変換中の `try` ブロックで `?` が失敗したときの飛び先
+struct TryHandler {
+    /// `catch` のブロック
+    block: BlockId,
+    /// `try` の位置のスコープの深さ（`catch` へ分岐する前に、これより深い `defer` の式を評価する）
+    depth: usize,
+    /// `catch` のブロックがパラメータとして受け取るエラーの値と型（最初の `?` で決まる）
+    error: Option<(RegisterId, Type)>,
+}
+
+/// 変換する関数定義（`Node::FunctionDef` のうち変換に使う部分）
+struct FunctionDecl<'a> {
+    name: &'a str,
+    params: &'a [ast::FunctionParam],
+    return_type: Option<&'a Type>,
+    body: &'a ASTNode,
+    /// `effect fn` で宣言されたかどうか
+    is_effectful: bool,
+    /// `async fn` で宣言されたかどうか
+    is_async: bool,
+    /// 関数に付けた属性（`#[export]` など）
+    attributes: &'a [String],
+    location: &'a SourceLocation,
+}
+
+/// `defer` で登録した、スコープを抜けるときに評価する式
+#[derive(Clone)]
+struct Deferred {
+    /// 登録したスコープの深さ（`scopes.len()`）
+    depth: usize,
+    /// 登録した位置の束縛（後で宣言した同じ名前の変数に影響されないようにする）
+    scopes: Vec<HashMap<String, Binding>>,
+    expr: ASTNode,
+}
+
+/// 関数を構築中の状態
+struct FunctionContext {
+    /// 構築中の関数と、命令を追加する位置
+    builder: IrBuilder,
+    scopes: Vec<HashMap<String, Binding>>,
+    /// 内側ほど後ろにある、変換中の `try` ブロック
+    try_handlers: Vec<TryHandler>,
+    /// 登録した順に並べた、まだスコープを抜けていない `defer` の式
+    defers: Vec<Deferred>,
+    /// `panic` の後の命令を置くために作った、到達しないブロック
+    unreachable_blocks: HashSet<BlockId>,
+    /// `BigInt` のリテラルから作ったレジスタと、そのリテラルの値と位置
+    bigint_literals: HashMap<RegisterId, (bigint::BigInt, SourceLocation)>,
+}
+
+impl FunctionContext {
+    fn new(function: Function) -> Self {
+        Self {
+            builder: IrBuilder::new(function),
+            scopes: vec![HashMap::new()],
+            try_handlers: Vec::new(),
+            defers: Vec::new(),
+            unreachable_blocks: HashSet::new(),
+            bigint_literals: HashMap::new(),
+        }
+    }
+    
+    /// 位置が分かっていれば、以後の命令をその位置に対応付ける（元の位置を返す）
+    fn enter_location(&mut self, location: &SourceLocation) -> Option<SourceLocation> {
+        if location.line == 0 {
+            return self.builder.location().cloned();
+        }
+        self.builder.set_location(Some(location.clone()))
+    }
+    
+    /// 現在の位置に到達しないか（`panic` などで抜けた後）
+    fn is_unreachable(&self) -> bool {
+        self.builder.is_terminated() || self.unreachable_blocks.contains(&self.builder.current_block())
+    }
+    
+    fn bind(&mut self, name: &str, binding: Binding) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), binding);
+        }
+    }
+    
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+}
+
+/// ASTからEIRモジュールを構築するビルダー
+pub struct ModuleBuilder {
+    module: Module,
+    next_function_id: u32,
+    builtin_types: HashMap<&'static str, TypeId>,
+    /// グローバル変数ごとの種類
+    globals: HashMap<String, ast::GlobalKind>,
+    /// ユーザー定義関数ごとの戻り値の型（呼び出し結果の型に使う）
+    function_returns: HashMap<String, Type>,
+    /// ユーザー定義関数ごとの引数の型（渡す値を引数の型に合わせるのに使う）
+    function_params: HashMap<String, Vec<Type>>,
+    /// 暗黙のエントリー関数が最後の式の値を返すかどうか（REPL用）
+    return_last_value: bool,
+    /// `assert`（と、`require`・`ensure` の契約）を検査するかどうか
+    check_assertions: bool,
+}
+
+impl ModuleBuilder {
+    /// 新しいモジュールビルダーを作成
+    pub fn new(name: String) -> Self {
+        Self {
+            module: Module::new(&name),
+            next_function_id: 0,
+            builtin_types: HashMap::new(),
+            globals: HashMap::new(),
+            function_returns: HashMap::new(),
+            function_params: HashMap::new(),
+            return_last_value: false,
+            check_assertions: true,
+        }
+    }
+    
+    /// 暗黙のエントリー関数が、トップレベルの最後の式の値を返すようにする
+    pub fn returning_last_value(mut self) -> Self {
+        self.return_last_value = true;
+        self
+    }
+    
+    /// `assert` と契約を検査しない（条件の式も評価しない）
+    pub fn without_assertions(mut self) -> Self {
+        self.check_assertions = false;
+        self
+    }
+    
+    /// 型チェック済みのASTからEIRモジュールを構築
+    pub fn build_from_ast(&mut self, program: &Program) -> Result<Module> {
+        let mut top_level = Vec::new();
+        
+        // 宣言より前の関数からも参照できるよう、先にグローバル変数と関数の戻り値の型を登録する
+        for node in &program.nodes {
+            match &node.kind {
+                Node::GlobalDecl { name, type_annotation, initializer, kind } => {
+                    self.lower_global(name, type_annotation.as_ref(), initializer, *kind)?;
+                }
+                Node::FunctionDef { name, params, return_type, .. } => {
+                    self.function_returns.insert(name.clone(), return_type.clone().unwrap_or_else(Type::unit));
+                    self.function_params.insert(
+                        name.clone(),
+                        params.iter().map(|p| p.param_type.clone().unwrap_or_else(Type::int)).collect(),
+                    );
+                }
+                _ => {}
+            }
+        }
+        
+        for node in &program.nodes {
+            match &node.kind {
+                Node::FunctionDef { name, params, return_type, body, is_effectful, is_async, attributes, .. } => {
+                    self.lower_function(FunctionDecl {
+                        name,
+                        params,
+                        return_type: return_type.as_ref(),
+                        body,
+                        is_effectful: *is_effectful,
+                        is_async: *is_async,
+                        attributes,
+                        location: &node.location,
+                    })?;
+                }
+                Node::GlobalDecl { .. } => {}
+                _ => top_level.push(node),
+            }
+        }
+        
+        // 関数外の文は暗黙のエントリー関数にまとめる
+        if !top_level.is_empty() && self.module.get_function_by_name(ENTRY_FUNCTION_NAME).is_none() {
+            // 変数宣言で終わる場合は返す値がない
+            let last_value = match top_level.last() {
+                Some(node) if self.return_last_value && !matches!(node.kind, Node::VarDecl { .. } | Node::TupleDecl { .. }) => top_level.pop(),
+                _ => None,
+            };
+            let (return_type, return_type_id) = match last_value {
+                Some(node) => {
+                    let id = self.node_type(node);
+                    let ty = self.module.get_type(id).cloned().unwrap_or_else(Type::unit);
+                    (ty, id)
+                }
+                None => (Type::unit(), self.builtin_type("unit")),
+            };
+            let func_type = self.module.add_type(Type::function(Vec::new(), return_type));
+            let function = Function::new(self.allocate_function_id(), ENTRY_FUNCTION_NAME, func_type, return_type_id);
+            let mut ctx = FunctionContext::new(function);
+            ctx.builder.function_mut().location = top_level.first().map(|node| node.location.clone());
+            // トップレベルで `await` していれば、エントリー関数も非同期関数になる
+            ctx.builder.function_mut().attributes.is_async = top_level.iter().chain(&last_value).any(|node| contains_await(node));
+            
+            for node in top_level {
+                self.lower_statement(&mut ctx, node)?;
+            }
+            let value = match last_value {
+                Some(node) => Some(self.lower_expression(&mut ctx, node)?),
+                None => None,
+            };
+            self.exit_scope(&mut ctx)?;
+            ctx.builder.ret(value);
+            
+            let id = self.module.add_function(ctx.builder.finish());
+            self.module.set_entry_point(id);
+        } else if let Some(main) = self.module.get_function_by_name(ENTRY_FUNCTION_NAME) {
+            // 実行環境が引数と終了コードを受け渡せる形の `main` だけをエントリー関数にできる
+            if EntryAbi::of(&self.module, main).is_none() {
+                let params: Vec<String> = main.parameters.iter().map(|(_, ty)| self.module.type_name(*ty)).collect();
+                return Err(EidosError::Type {
+                    message: format!(
+                        "エントリー関数 'main' の型 ({}) -> {} は使えません（fn main()、fn main() -> Int、fn main(args: List<String>)、fn main(args: List<String>) -> Int のいずれかにしてください）",
+                        params.join(", "),
+                        self.module.type_name(main.return_type)
+                    ),
+                    location: main.location.clone().unwrap_or_else(SourceLocation::unknown),
+                });
+            }
+            let id = main.id;
+            self.module.set_entry_point(id);
+        }
+        
+        Ok(self.module.clone())
+    }
+    
+    fn allocate_function_id(&mut self) -> FunctionId {
+        let id = FunctionId(self.next_function_id);
+        self.next_function_id += 1;
+        id
+    }
+    
+    /// 組み込み型のTypeIdを取得（モジュールに一度だけ登録する）
+    fn builtin_type(&mut self, name: &'static str) -> TypeId {
+        if let Some(id) = self.builtin_types.get(name) {
+            return *id;
+        }
+        let ty = match name {
+            "bool" => Type::bool(),
+            "int" => Type::int(),
+            "float" => Type::float(),
+            "char" => Type::char(),
+            "string" => Type::string(),
+            "bigint" => Type::bigint(),
+            _ => match NumericType::from_name(name) {
+                Some(numeric) => Type::numeric(numeric),
+                None => Type::unit(),
+            },
+        };
+        let id = self.module.add_type(ty);
+        self.builtin_types.insert(name, id);
+        id
+    }
+    
+    /// AST上の型をモジュールに登録してTypeIdを得る
+    fn lower_type(&mut self, ty: Option<&Type>) -> TypeId {
+        match ty.map(|t| &t.kind) {
+            Some(TypeKind::Bool) => self.builtin_type("bool"),
+            Some(TypeKind::Int) => self.builtin_type("int"),
+            Some(TypeKind::Float) => self.builtin_type("float"),
+            Some(TypeKind::Char) => self.builtin_type("char"),
+            Some(TypeKind::String) => self.builtin_type("string"),
+            Some(TypeKind::BigInt) => self.builtin_type("bigint"),
+            Some(TypeKind::Numeric(numeric)) => self.builtin_type(numeric.name()),
+            Some(TypeKind::Unit) | None => self.builtin_type("unit"),
+            Some(_) => self.module.add_type(ty.cloned().unwrap_or_else(Type::unit)),
+        }
+    }
+    
+    /// ノードに付与された型情報からTypeIdを得る
+    fn node_type(&mut self, node: &ASTNode) -> TypeId {
+        match &node.type_info {
+            ast::TypeInfo::Resolved(ty) | ast::TypeInfo::Explicit(ty) => self.lower_type(Some(ty)),
+            ast::TypeInfo::Unknown => match &node.kind {
+                Node::Literal(ast::Literal::Bool(_)) => self.builtin_type("bool"),
+                Node::Literal(ast::Literal::Float(_)) => self.builtin_type("float"),
+                Node::Literal(ast::Literal::Char(_)) => self.builtin_type("char"),
+                Node::Literal(ast::Literal::String(_)) => self.builtin_type("string"),
+                Node::Literal(ast::Literal::Unit) => self.builtin_type("unit"),
+                _ => self.builtin_type("int"),
+            },
+        }
+    }
+    
+    /// グローバル変数をモジュールに追加
+    ///
+    /// 初期化式は型チェックでコンパイル時に評価し、リテラルに置き換えてある。
+    /// `static mut` 以外は変更できないので、定数として読み取り専用の領域に置く。
+    fn lower_global(
+        &mut self,
+        name: &str,
+        type_annotation: Option<&Type>,
+        initializer: &ASTNode,
+        kind: ast::GlobalKind,
+    ) -> Result<()> {
+        let literal = match &initializer.kind {
+            Node::Literal(lit) => Self::lower_literal(lit),
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "{} '{}' の初期化式がコンパイル時に評価されていません",
+                    kind.keyword(),
+                    name
+                )))
+            }
+        };
+        let ty = match type_annotation {
+            Some(ty) => self.lower_type(Some(ty)),
+            None => self.node_type(initializer),
+        };
+        if let (Literal::Int(value), Some(numeric)) = (&literal, self.sized_numeric(ty)) {
+            if numeric.is_float() || !numeric.contains(*value) {
+                return Err(EidosError::Type {
+                    message: format!("{} '{}' の初期値 {} は {} 型で表せません", kind.keyword(), name, value, numeric),
+                    location: initializer.location.clone(),
+                });
+            }
+        }
+        
+        self.module.add_global(name, Global {
+            name: name.to_string(),
+            ty,
+            initializer: Some(literal),
+            linkage: Linkage::Internal,
+            alignment: None,
+            attributes: GlobalAttributes {
+                is_constant: kind != ast::GlobalKind::StaticMut,
+                ..GlobalAttributes::default()
+            },
+        });
+        self.globals.insert(name.to_string(), kind);
+        Ok(())
+    }
+    
+    /// 関数定義をEIR関数に変換
+    ///
+    /// `effect fn` でない関数は型チェックで純粋であることを確認済みなので、純粋関数として印を付ける。
+    /// 関数に付けた属性（`#[export]` など）は属性タグにする。
+    fn lower_function(&mut self, decl: FunctionDecl<'_>) -> Result<()> {
+        let FunctionDecl { name, params, return_type, body, is_effectful, is_async, attributes, location } = decl;
+        let param_types: Vec<Type> = params
+            .iter()
+            .map(|p| p.param_type.clone().unwrap_or_else(Type::int))
+            .collect();
+        // 配列はスタックに確保するので、関数をまたいでは配列を指すスライスの引数で渡す
+        let by_value = param_types.iter().find(|ty| matches!(ty.kind, TypeKind::FixedArray { .. }));
+        let returned = return_type.filter(|ty| matches!(ty.kind, TypeKind::FixedArray { .. } | TypeKind::Slice(_)));
+        if let Some(ty) = by_value.or(returned) {
+            let advice = match ty.element_type() {
+                Some(element) if by_value.is_some() => format!("引数の型を &[{}] にしてください", element),
+                _ => "呼び出し元で確保した配列をスライスの引数で受け取ってください".to_string(),
+            };
+            let role = if by_value.is_some() { "引数" } else { "戻り値" };
+            return Err(EidosError::Type {
+                message: format!("関数 '{}' の{}を {} 型にはできません（{}）", name, role, ty, advice),
+                location: location.clone(),
+            });
+        }
+        for ty in param_types.iter().chain(return_type) {
+            self.check_tuple_type(ty, location)?;
+        }
+        let ret = return_type.cloned().unwrap_or_else(Type::unit);
+        let func_type = self.module.add_type(Type::function(param_types, ret));
+        let return_type_id = self.lower_type(return_type);
+        
+        let function = Function::new(self.allocate_function_id(), name, func_type, return_type_id);
+        let mut ctx = FunctionContext::new(function);
+        let function = ctx.builder.function_mut();
+        function.location = Some(location.clone());
+        function.attributes.pure = !is_effectful && name != ENTRY_FUNCTION_NAME;
+        function.attributes.no_side_effects = function.attributes.pure;
+        function.attributes.is_async = is_async;
+        function.attributes.hotness = attributes.iter().find_map(|name| Hotness::from_attribute(name)).unwrap_or_default();
+        function.attributes.tags.extend(attributes.iter().cloned());
+        
+        for param in params {
+            let type_id = self.lower_type(param.param_type.as_ref());
+            let reg = ctx.builder.function_mut().add_parameter(&param.name, type_id);
+            ctx.bind(&param.name, Binding::Value(Operand::Register(reg)));
+        }
+        
+        let result = self.lower_expression(&mut ctx, body)?;
+        let value = match return_type.map(|t| &t.kind) {
+            None | Some(TypeKind::Unit) => None,
+            Some(_) => Some(self.coerce(&mut ctx, result, return_type_id, &body.location)?),
+        };
+        ctx.builder.ret(value);
+        
+        self.module.add_function(ctx.builder.finish());
+        Ok(())
+    }
+    
+    /// 文を変換（値は捨てる）
+    fn lower_statement(&mut self, ctx: &mut FunctionContext, node: &ASTNode) -> Result<()> {
+        let outer = ctx.enter_location(&node.location);
+        let result = self.lower_statement_kind(ctx, node);
+        ctx.builder.set_location(outer);
+        result
+    }
+    
+    fn lower_statement_kind(&mut self, ctx: &mut FunctionContext, node: &ASTNode) -> Result<()> {
+        match &node.kind {
+            Node::VarDecl { name, type_annotation, initializer, is_mutable, .. } => {
+                let mut value = match initializer.as_deref() {
+                    Some(init @ ASTNode { kind: Node::ArrayLiteral { .. } | Node::ArrayRepeat { .. }, .. }) => {
+                        self.lower_array(ctx, init, type_annotation.as_ref())?
+                    }
+                    Some(init @ ASTNode { kind: Node::TupleLiteral { .. }, .. }) => {
+                        self.lower_tuple(ctx, init, type_annotation.as_ref())?
+                    }
+                    Some(init) => self.lower_expression(ctx, init)?,
+                    None => Operand::Literal(Literal::Unit),
+                };
+                if let (Some(ty), Some(init)) = (type_annotation, initializer) {
+                    let type_id = self.lower_type(Some(ty));
+                    value = self.coerce(ctx, value, type_id, &init.location)?;
+                }
+                
+                // 配列の変数は配列の領域を直接指す（別の配列から作るときは要素をコピーする）
+                let ty = self.operand_type(ctx, &value);
+                if let TypeKind::FixedArray { .. } = ty.kind {
+                    let fresh = matches!(
+                        initializer.as_deref().map(|init| &init.kind),
+                        Some(Node::ArrayLiteral { .. } | Node::ArrayRepeat { .. } | Node::Comprehension { .. })
+                    );
+                    let address = match value {
+                        Operand::Register(address) if fresh => address,
+                        _ => {
+                            let address = self.alloca_array(ctx, &ty);
+                            self.copy_array(ctx, value, Operand::Register(address), &ty);
+                            address
+                        }
+                    };
+                    ctx.bind(name, Binding::Array { address, is_mutable: *is_mutable });
+                    return Ok(());
+                }
+                
+                // ミュータブル変数はスタックスロットに格納し、更新はStoreで行う。
+                // 初期化式のない `let` も、後の代入で初期化するのでスロットに置く
+                // （読み取る前に代入されることは確実な初期化の検査で確認済み）
+                if *is_mutable || initializer.is_none() {
+                    let type_id = match type_annotation {
+                        Some(ty) => self.lower_type(Some(ty)),
+                        None if initializer.is_some() => self.operand_type_id(ctx, &value),
+                        None => self.builtin_type("int"),
+                    };
+                    let slot = ctx.builder.alloca(SLOT_SIZE, type_id);
+                    if initializer.is_some() {
+                        ctx.builder.store(Operand::Register(slot), value);
+                    }
+                    ctx.bind(name, Binding::Slot(slot));
+                } else {
+                    ctx.bind(name, Binding::Value(value));
+                }
+                Ok(())
+            }
+            Node::TupleDecl { names, type_annotation, initializer, is_mutable } => {
+                // タプルのリテラルから束縛するときはタプルを作らず、要素の値をそのまま変数にする
+                let elements = match &initializer.kind {
+                    Node::TupleLiteral { elements } if elements.len() == names.len() => {
+                        let elements = self.lower_tuple_elements(ctx, initializer, type_annotation.as_ref())?;
+                        let ty = Type::tuple(elements.iter().map(|(_, ty)| ty.clone()).collect());
+                        self.check_tuple_type(&ty, &initializer.location)?;
+                        elements.into_iter().map(|(value, _)| value).collect()
+                    }
+                    _ => {
+                        let mut tuple = self.lower_expression(ctx, initializer)?;
+                        if let Some(ty) = type_annotation {
+                            let type_id = self.lower_type(Some(ty));
+                            tuple = self.coerce(ctx, tuple, type_id, &initializer.location)?;
+                        }
+                        let ty = self.operand_type(ctx, &tuple);
+                        let element_types = match &ty.kind {
+                            TypeKind::Tuple(elements) if elements.len() == names.len() => elements.clone(),
+                            _ => {
+                                return Err(EidosError::Type {
+                                    message: format!("要素が{}個のタプルが必要ですが、{} 型の値です", names.len(), ty),
+                                    location: initializer.location.clone(),
+                                })
+                            }
+                        };
+                        let mut elements = Vec::with_capacity(names.len());
+                        for (index, element) in element_types.iter().enumerate() {
+                            let type_id = self.lower_type(Some(element));
+                            elements.push(Operand::Register(ctx.builder.extract(tuple.clone(), index, type_id)));
+                        }
+                        elements
+                    }
+                };
+                for (name, value) in names.iter().zip(elements) {
+                    if name == "_" {
+                        continue;
+                    }
+                    if *is_mutable {
+                        let type_id = self.operand_type_id(ctx, &value);
+                        let slot = ctx.builder.alloca(SLOT_SIZE, type_id);
+                        ctx.builder.store(Operand::Register(slot), value);
+                        ctx.bind(name, Binding::Slot(slot));
+                    } else {
+                        ctx.bind(name, Binding::Value(value));
+                    }
+                }
+                Ok(())
+            }
+            Node::Defer { expr } => {
+                let deferred = Deferred { depth: ctx.scopes.len(), scopes: ctx.scopes.clone(), expr: (**expr).clone() };
+                ctx.defers.push(deferred);
+                Ok(())
+            }
+            _ => {
+                let value = self.lower_expression(ctx, node)?;
+                // 失敗するかもしれない値は、`?` で伝播するか中の値を取り出して処理しなければならない
+                let ty = self.operand_type(ctx, &value);
+                if ty.is_fallible() {
+                    let kind = if ty.option_payload().is_some() { "Option" } else { "Result" };
+                    return Err(EidosError::Type {
+                        message: format!(
+                            "{} の値を使わずに捨てています（`?` で伝播するか {}::unwrap_or などで処理してください）",
+                            ty, kind
+                        ),
+                        location: node.location.clone(),
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+    
+    /// 式を変換し、結果のオペランドを返す
+    ///
+    /// 式から生成した命令には式のソース上の位置を記録する。
+    /// 部分式の変換が終わると位置を戻すので、親の式の命令には親の位置が付く。
+    fn lower_expression(&mut self, ctx: &mut FunctionContext, node: &ASTNode) -> Result<Operand> {
+        let outer = ctx.enter_location(&node.location);
+        let result = self.lower_expression_kind(ctx, node);
+        ctx.builder.set_location(outer);
+        result
+    }
+    
+    fn lower_expression_kind(&mut self, ctx: &mut FunctionContext, node: &ASTNode) -> Result<Operand> {
+        match &node.kind {
+            Node::Literal(lit) => {
+                let literal = Operand::Literal(Self::lower_literal(lit));
+                // `BigInt` の値は実行時には10進数の文字列で表す
+                if let ast::Literal::BigInt(digits) = lit {
+                    let value = self.call_bigint(ctx, "parse", vec![literal]);
+                    if let Operand::Register(register) = value {
+                        ctx.bigint_literals.insert(register, (digits.clone(), node.location.clone()));
+                    }
+                    return Ok(value);
+                }
+                // `42i32` のような接尾辞付きのリテラルは、その型のレジスタに変換する
+                match &node.type_info {
+                    ast::TypeInfo::Explicit(ty) if matches!(ty.kind, TypeKind::Numeric(_)) => {
+                        let type_id = self.lower_type(Some(ty));
+                        Ok(Operand::Register(ctx.builder.cast(literal, type_id)))
+                    }
+                    _ => Ok(literal),
+                }
+            }
+            
+            Node::Identifier { name, .. } => match ctx.lookup(name).cloned() {
+                Some(Binding::Value(operand)) => Ok(operand),
+                Some(Binding::Array { address, .. }) => Ok(Operand::Register(address)),
+                Some(Binding::Slot(slot)) => {
+                    let type_id = ctx.builder.function().get_register_type(slot).unwrap_or_else(|| self.builtin_type("int"));
+                    Ok(Operand::Register(ctx.builder.load(Operand::Register(slot), type_id)))
+                }
+                None if self.is_variant_constructor(name) => self.lower_variant_call(ctx, name, Vec::new()),
+                None => Ok(self.lower_global_reference(ctx, name)),
+            },
+            
+            Node::UnaryExpr { op, expr } => {
+                let operand = self.lower_expression(ctx, expr)?;
+                if self.is_bigint(ctx, &operand) {
+                    if *op != ast::UnaryOp::Neg {
+                        return Err(EidosError::Type {
+                            message: format!("BigInt 型には演算子 {} は使えません", op),
+                            location: node.location.clone(),
+                        });
+                    }
+                    return Ok(self.call_bigint(ctx, "neg", vec![operand]));
+                }
+                let operand_type = self.operand_type_id(ctx, &operand);
+                let type_id = match op {
+                    ast::UnaryOp::Neg | ast::UnaryOp::BitNot => operand_type,
+                    ast::UnaryOp::Not => self.builtin_type("bool"),
+                };
+                let op = match op {
+                    ast::UnaryOp::Neg => UnaryOp::Neg,
+                    ast::UnaryOp::Not => UnaryOp::Not,
+                    ast::UnaryOp::BitNot => UnaryOp::BitNot,
+                };
+                Ok(Operand::Register(ctx.builder.unary(op, operand, type_id)))
+            }
+            
+            Node::BinaryExpr { op, left, right } => {
+                let lhs = self.lower_expression(ctx, left)?;
+                let rhs = self.lower_expression(ctx, right)?;
+                if self.is_bigint(ctx, &lhs) || self.is_bigint(ctx, &rhs) {
+                    return self.lower_bigint_binary(ctx, node, *op, lhs, rhs);
+                }
+                let (lhs, rhs, type_id) = self.binary_operands(ctx, node, *op, lhs, rhs)?;
+                Ok(Operand::Register(ctx.builder.binary(Self::lower_binary_op(*op), lhs, rhs, type_id)))
+            }
+            
+            Node::Assignment { target, value } => {
+                let (address, type_id) = self.assignment_address(ctx, target)?;
+                let location = value.location.clone();
+                let value = self.lower_expression(ctx, value)?;
+                let value = self.coerce(ctx, value, type_id, &location)?;
+                // 配列の変数への代入は、要素をコピーする
+                match self.module.get_type(type_id).cloned() {
+                    Some(ty @ Type { kind: TypeKind::FixedArray { .. }, .. }) => self.copy_array(ctx, value, address, &ty),
+                    _ => ctx.builder.store(address, value),
+                }
+                Ok(Operand::Literal(Literal::Unit))
+            }
+            
+            Node::CompoundAssignment { op, target, value } => {
+                let (address, type_id) = self.assignment_address(ctx, target)?;
+                self.check_not_array(ctx, *op, &address, &node.location)?;
+                
+                let current = ctx.builder.load(address.clone(), type_id);
+                let location = value.location.clone();
+                let rhs = self.lower_expression(ctx, value)?;
+                if self.is_bigint(ctx, &Operand::Register(current)) {
+                    let result = self.lower_bigint_binary(ctx, node, *op, Operand::Register(current), rhs)?;
+                    ctx.builder.store(address, result);
+                    return Ok(Operand::Literal(Literal::Unit));
+                }
+                let rhs = match op {
+                    ast::BinaryOp::LShift | ast::BinaryOp::RShift => rhs,
+                    _ => self.coerce(ctx, rhs, type_id, &location)?,
+                };
+                let result = ctx.builder.binary(Self::lower_binary_op(*op), Operand::Register(current), rhs, type_id);
+                ctx.builder.store(address, Operand::Register(result));
+                Ok(Operand::Literal(Literal::Unit))
+            }
+            
+            Node::FunctionCall { callee, args } => {
+                let function = match &callee.kind {
+                    Node::Identifier { name, .. } => name.to_string(),
+                    _ => {
+                        return Err(EidosError::BackendError(
+                            "間接呼び出しはまだサポートされていません".to_string(),
+                        ))
+                    }
+                };
+                if function.starts_with("thread::") && !self.function_returns.contains_key(&function) {
+                    if let Some(op) = Self::atomic_op(&function) {
+                        return self.lower_atomic_call(ctx, node, &function, op, args);
+                    }
+                    if function == "thread::spawn" {
+                        self.check_thread_entry(node, args)?;
+                    }
+                }
+                if function.starts_with("bigint::") && !self.function_returns.contains_key(&function) {
+                    return self.lower_bigint_call(ctx, node, &function, args);
+                }
+                if function == "next" && !self.function_returns.contains_key(&function) {
+                    return self.lower_next_call(ctx, node, args);
+                }
+                if function == "assert" && !self.function_returns.contains_key(&function) {
+                    return self.lower_assert(ctx, node, args);
+                }
+                let params = self.function_params.get(&function).cloned().unwrap_or_default();
+                let mut arguments = Vec::with_capacity(args.len());
+                for (i, arg) in args.iter().enumerate() {
+                    let value = self.lower_expression(ctx, arg)?;
+                    let value = match params.get(i) {
+                        Some(param) => {
+                            let type_id = self.lower_type(Some(param));
+                            self.coerce(ctx, value, type_id, &arg.location)?
+                        }
+                        None => value,
+                    };
+                    arguments.push(value);
+                }
+                if self.is_variant_constructor(&function) || function.starts_with("Option::") || function.starts_with("Result::") {
+                    return self.lower_variant_call(ctx, &function, arguments);
+                }
+                if function == "panic" && !self.function_returns.contains_key(&function) {
+                    return self.lower_panic(ctx, node, arguments);
+                }
+                // `len(a)` は、固定長配列なら型の長さ、スライスなら持っている長さ
+                if function == "len" && !self.function_returns.contains_key(&function) {
+                    if let [array] = arguments.as_slice() {
+                        if let Some(length) = self.array_length(ctx, array) {
+                            return Ok(length);
+                        }
+                    }
+                }
+                if matches!(function.as_str(), "print" | "println") && !self.function_returns.contains_key(&function) {
+                    for argument in &mut arguments {
+                        let ty = self.operand_type(ctx, argument);
+                        if ty.is_fallible() {
+                            return Err(EidosError::Type {
+                                message: format!("{} 型の値は直接出力できません（unwrap などで中の値を取り出してください）", ty),
+                                location: node.location.clone(),
+                            });
+                        }
+                        if ty.kind == TypeKind::BigInt {
+                            *argument = self.call_bigint(ctx, "to_string", vec![argument.clone()]);
+                        }
+                        if ty.element_type().is_some() {
+                            return Err(EidosError::Type {
+                                message: format!("{} 型の値は直接出力できません（要素を1つずつ出力してください）", ty),
+                                location: node.location.clone(),
+                            });
+                        }
+                    }
+                }
+                let type_id = match self.function_returns.get(&function).cloned() {
+                    Some(ty) => self.lower_type(Some(&ty)),
+                    None => self.node_type(node),
+                };
+                let result = ctx.builder.call(function, arguments, Some(type_id));
+                Ok(Operand::Register(result.expect("戻り値の型を渡した呼び出しには結果がある")))
+            }
+            
+            Node::Try { expr } => self.lower_try(ctx, node, expr),
+            
+            // 非同期関数は完了するまで実行してから戻るので、待つ式は呼び出しそのものになる。
+            // 中断できるバックエンド（JavaScript）は、非同期関数の呼び出しを自分で待つ
+            Node::Await { expr } => self.lower_expression(ctx, expr),
+            
+            Node::Cast { expr, target_type } => {
+                let value = self.lower_expression(ctx, expr)?;
+                let from = self.operand_type(ctx, &value);
+                let is_integer = |ty: &Type| ty.numeric_type().is_some_and(|numeric| !numeric.is_float());
+                // `BigInt` と整数型・浮動小数点数型の間の変換は `bigint` モジュールの関数で行う
+                match (&from.kind, &target_type.kind) {
+                    (TypeKind::BigInt, TypeKind::BigInt) => return Ok(value),
+                    (_, TypeKind::BigInt) if is_integer(&from) => return self.coerce_bigint(ctx, value, &node.location),
+                    (TypeKind::BigInt, _) if target_type.numeric_type().is_some() => {
+                        let converted = if target_type.numeric_type().is_some_and(|numeric| numeric.is_float()) {
+                            self.call_bigint(ctx, "to_float", vec![value])
+                        } else {
+                            self.call_bigint(ctx, "to_int", vec![value])
+                        };
+                        if matches!(target_type.kind, TypeKind::Int | TypeKind::Float) {
+                            return Ok(converted);
+                        }
+                        let type_id = self.lower_type(Some(target_type));
+                        return Ok(Operand::Register(ctx.builder.cast(converted, type_id)));
+                    }
+                    _ => {}
+                }
+                // 数値型の間と、`Bool`・`Char` から整数型、整数型から `Char` への変換だけを許す
+                let castable = match (&from.kind, &target_type.kind) {
+                    _ if from.numeric_type().is_some() && target_type.numeric_type().is_some() => true,
+                    (TypeKind::Bool | TypeKind::Char, _) => is_integer(target_type),
+                    (_, TypeKind::Char) => is_integer(&from),
+                    _ => false,
+                };
+                if !castable {
+                    return Err(EidosError::Type {
+                        message: format!("{} 型の値は `as` で {} 型に変換できません", from, target_type),
+                        location: node.location.clone(),
+                    });
+                }
+                let type_id = self.lower_type(Some(target_type));
+                Ok(Operand::Register(ctx.builder.cast(value, type_id)))
+            }
+            
+            Node::TryCatch { body, error_name, handler } => {
+                let catch_block = ctx.builder.create_block();
+                let join_block = ctx.builder.create_block();
+                
+                ctx.try_handlers.push(TryHandler { block: catch_block, depth: ctx.scopes.len(), error: None });
+                let body_value = self.lower_expression(ctx, body);
+                let try_handler = ctx.try_handlers.pop();
+                let body_value = body_value?;
+                let type_id = self.operand_type_id(ctx, &body_value);
+                ctx.builder.br(join_block, vec![body_value]);
+                
+                // 本体に `?` がなければ `catch` のブロックには到達しない
+                ctx.builder.position_at_end(catch_block);
+                let error = match try_handler.and_then(|try_handler| try_handler.error) {
+                    Some((register, _)) => Operand::Register(register),
+                    None => Operand::Literal(Literal::Unit),
+                };
+                ctx.scopes.push(HashMap::new());
+                ctx.bind(error_name, Binding::Value(error));
+                let handler_value = self.lower_expression(ctx, handler);
+                ctx.scopes.pop();
+                ctx.builder.br(join_block, vec![handler_value?]);
+                
+                let result = ctx.builder.add_block_param(join_block, type_id);
+                ctx.builder.position_at_end(join_block);
+                Ok(Operand::Register(result))
+            }
+            
+            Node::BlockExpr { statements, result } => {
+                ctx.scopes.push(HashMap::new());
+                for stmt in statements {
+                    self.lower_statement(ctx, stmt)?;
+                }
+                let value = match result {
+                    Some(expr) => self.lower_expression(ctx, expr)?,
+                    None => Operand::Literal(Literal::Unit),
+                };
+                self.exit_scope(ctx)?;
+                ctx.scopes.pop();
+                Ok(value)
+            }
+            
+            Node::MapLiteral { entries } => {
+                // `Map::new()` で作ったマップに `Map::insert` で要素を詰める
+                let map = ctx.builder.call("Map::new", Vec::new(), Some(self.node_type(node)));
+                let map = map.expect("戻り値の型を渡した呼び出しには結果がある");
+                for (key, value) in entries {
+                    let key = self.lower_expression(ctx, key)?;
+                    let value = self.lower_expression(ctx, value)?;
+                    ctx.builder.call("Map::insert", vec![Operand::Register(map), key, value], None);
+                }
+                Ok(Operand::Register(map))
+            }
+            
+            Node::ArrayLiteral { .. } | Node::ArrayRepeat { .. } => self.lower_array(ctx, node, None),
+            
+            Node::TupleLiteral { .. } => self.lower_tuple(ctx, node, None),
+            
+            Node::RangeExpr { start, end } => {
+                let start_value = self.lower_expression(ctx, start)?;
+                let end_value = self.lower_expression(ctx, end)?;
+                for (value, side) in [(&start_value, start), (&end_value, end)] {
+                    let ty = self.operand_type(ctx, value);
+                    if ty.numeric_type().is_none_or(|numeric| numeric.is_float()) {
+                        return Err(EidosError::Type {
+                            message: format!("範囲の両端には整数が必要です（{} 型の値です）", ty),
+                            location: side.location.clone(),
+                        });
+                    }
+                }
+                let (start_value, end_value, _) = self.binary_operands(ctx, node, ast::BinaryOp::Lt, start_value, end_value)?;
+                let element = self.operand_type(ctx, &start_value);
+                let range = self.lower_type(Some(&Type::range(element)));
+                Ok(Operand::Register(ctx.builder.tuple(vec![start_value, end_value], range)))
+            }
+            
+            Node::Index { base, index } => {
+                let (address, type_id) = self.element_address(ctx, base, index)?;
+                Ok(Operand::Register(ctx.builder.load(address, type_id)))
+            }
+            
+            Node::SliceOf { array } => {
+                let value = self.lower_expression(ctx, array)?;
+                let ty = self.operand_type(ctx, &value);
+                match &ty.kind {
+                    TypeKind::FixedArray { element, length } => {
+                        let slice = self.lower_type(Some(&Type::slice((**element).clone())));
+                        let length = Operand::Literal(Literal::Int(*length as i64));
+                        Ok(Operand::Register(ctx.builder.make_slice(value, length, slice)))
+                    }
+                    TypeKind::Slice(_) => Ok(value),
+                    _ => Err(EidosError::Type {
+                        message: format!("'&' でスライスを作れるのは固定長配列だけです（{} 型の値です）", ty),
+                        location: array.location.clone(),
+                    }),
+                }
+            }
+            
+            Node::IfExpr { condition, then_branch, else_branch } => {
+                self.lower_if(ctx, node, condition, then_branch, else_branch.as_deref())
+            }
+            
+            Node::WhileLoop { condition, body } => {
+                let header = ctx.builder.create_block();
+                let body_block = ctx.builder.create_block();
+                let exit = ctx.builder.create_block();
+                
+                ctx.builder.br(header, Vec::new());
+                
+                ctx.builder.position_at_end(header);
+                let cond = self.lower_expression(ctx, condition)?;
+                ctx.builder.cond_br(cond, body_block, exit);
+                
+                ctx.builder.position_at_end(body_block);
+                self.lower_statement(ctx, body)?;
+                if !ctx.builder.is_terminated() {
+                    ctx.builder.br(header, Vec::new());
+                }
+                
+                ctx.builder.position_at_end(exit);
+                Ok(Operand::Literal(Literal::Unit))
+            }
+            
+            Node::ForLoop { variable, iterable, body } => self.lower_for(ctx, variable, iterable, body),
+            
+            Node::Comprehension { element, variable, iterable, condition } => {
+                self.lower_comprehension(ctx, element, variable, iterable, condition.as_deref())
+            }
+            
+            Node::VarDecl { .. } | Node::TupleDecl { .. } => {
+                self.lower_statement(ctx, node)?;
+                Ok(Operand::Literal(Literal::Unit))
+            }
+            
+            Node::UnsafeBlock { body } => self.lower_expression(ctx, body),
+            
+            Node::InlineAsm { dialect, template, inputs, output, clobbers } => {
+                self.lower_inline_asm(ctx, node, *dialect, template, inputs, output.as_ref(), clobbers)
+            }
+            
+            Node::DSLBlock { processed_ast: Some(expanded), .. } => self.lower_expression(ctx, expanded),
+            
+            Node::TypeDef { .. }
+            | Node::TypeProvider { .. }
+            | Node::GlobalDecl { .. }
+            | Node::SyntaxDef { .. }
+            | Node::PegDef { .. }
+            | Node::UseDsl { .. } => Ok(Operand::Literal(Literal::Unit)),
+            
+            other => Err(EidosError::BackendError(format!(
+                "EIRへの変換がサポートされていないノードです: {:?}",
+                other
+            ))),
+        }
+    }
+    
+    /// ローカル変数でない名前の参照を変換
+    ///
+    /// `const` は値をその場に埋め込み、`static` はグローバル変数を直接参照する。
+    /// `static mut` は参照した時点の値を使うよう、読み込んだレジスタを返す。
+    fn lower_global_reference(&mut self, ctx: &mut FunctionContext, name: &str) -> Operand {
+        let global = self.module.get_global(name);
+        match (self.globals.get(name), global) {
+            // ビット幅を指定した数値型の定数は、型が分かるようにその型のレジスタに変換する
+            (Some(ast::GlobalKind::Const), Some(Global { initializer: Some(literal), ty, .. })) => {
+                let (literal, ty) = (literal.clone(), *ty);
+                if self.sized_numeric(ty).is_none() {
+                    return Operand::Literal(literal);
+                }
+                Operand::Register(ctx.builder.cast(Operand::Literal(literal), ty))
+            }
+            (Some(ast::GlobalKind::StaticMut), Some(global)) => {
+                Operand::Register(ctx.builder.load(Operand::Global(name.to_string()), global.ty))
+            }
+            _ => Operand::Global(name.to_string()),
+        }
+    }
+    
+    /// `Some`・`None`・`Ok`・`Err` を、同名のユーザー定義関数やグローバル変数がなければ組み込みとして扱う
+    fn is_variant_constructor(&self, name: &str) -> bool {
+        VARIANT_CONSTRUCTORS.iter().any(|(constructor, _)| *constructor == name)
+            && !self.function_returns.contains_key(name)
+            && !self.globals.contains_key(name)
+    }
+    
+    /// `Option` と `Result` を作る・調べる呼び出しを変換し、結果の型を引数の型から決める
+    ///
+    /// `Ok(x)` のように片側の型しか分からない場合、もう片側は関数の戻り値の型から補う。
+    fn lower_variant_call(&mut self, ctx: &mut FunctionContext, name: &str, arguments: Vec<Operand>) -> Result<Operand> {
+        let function = VARIANT_CONSTRUCTORS
+            .iter()
+            .find(|(constructor, _)| *constructor == name)
+            .map_or(name, |(_, function)| *function)
+            .to_string();
+        let argument_types: Vec<Type> = arguments.iter().map(|argument| self.operand_type(ctx, argument)).collect();
+        let returns = self.module.get_type(ctx.builder.function().return_type).cloned();
+        let returned_option = returns.as_ref().and_then(|ty| ty.option_payload()).cloned();
+        let returned_result = returns.as_ref().and_then(|ty| ty.result_payloads()).map(|(ok, err)| (ok.clone(), err.clone()));
+        
+        let first = argument_types.first().cloned();
+        let ty = match (function.as_str(), first) {
+            ("Option::some", Some(value)) => Type::option(value),
+            ("Option::none", _) => Type::option(returned_option.unwrap_or_else(Type::int)),
+            ("Result::ok", Some(value)) => Type::result(value, returned_result.map_or_else(Type::int, |(_, err)| err)),
+            ("Result::err", Some(error)) => Type::result(returned_result.map_or_else(Type::int, |(ok, _)| ok), error),
+            ("Option::is_some" | "Option::is_none" | "Result::is_ok" | "Result::is_err", _) => Type::bool(),
+            ("Option::unwrap" | "Option::unwrap_or", Some(option)) => option.option_payload().cloned().unwrap_or_else(Type::int),
+            ("Result::unwrap" | "Result::unwrap_or", Some(result)) => {
+                result.result_payloads().map_or_else(Type::int, |(ok, _)| ok.clone())
+            }
+            ("Result::unwrap_err", Some(result)) => result.result_payloads().map_or_else(Type::int, |(_, err)| err.clone()),
+            _ => {
+                return Err(EidosError::BackendError(format!(
+                    "'{}' の呼び出しの引数が足りません",
+                    function
+                )))
+            }
+        };
+        
+        let type_id = self.lower_type(Some(&ty));
+        let result = ctx.builder.call(function, arguments, Some(type_id));
+        Ok(Operand::Register(result.expect("戻り値の型を渡した呼び出しには結果がある")))
+    }
+    
+    /// `expr?` を変換
+    ///
+    /// 失敗（`None`・`Err`）なら、`try` ブロックの中ではエラーを渡して `catch` のブロックへ分岐し、
+    /// それ以外では値をそのまま関数から返す。成功なら中の値を取り出して続ける。
+    /// 関数から返す場合、値の種類は関数の戻り値と同じでなければならず、`Result` ではエラーの型も一致しなければならない。
+    fn lower_try(&mut self, ctx: &mut FunctionContext, node: &ASTNode, expr: &ASTNode) -> Result<Operand> {
+        let value = self.lower_expression(ctx, expr)?;
+        let ty = self.operand_type(ctx, &value);
+        let returns = self.module.get_type(ctx.builder.function().return_type).cloned().unwrap_or_else(Type::unit);
+        let type_error = |message: String| EidosError::Type { message, location: node.location.clone() };
+        
+        let kind = if ty.option_payload().is_some() {
+            "Option"
+        } else if ty.result_payloads().is_some() {
+            "Result"
+        } else {
+            return Err(type_error(format!("`?` は Option か Result の値にしか使えません（{} 型の値です）", ty)));
+        };
+        if !ctx.try_handlers.is_empty() {
+            return self.lower_try_in_block(ctx, node, value, kind);
+        }
+        let compatible = match (ty.result_payloads(), returns.result_payloads()) {
+            (Some((_, error)), Some((_, returned))) => {
+                if error.to_string() != returned.to_string() {
+                    return Err(type_error(format!(
+                        "`?` で返すエラーの型 {} が関数 '{}' の戻り値のエラーの型 {} と一致しません",
+                        error, ctx.builder.function().name, returned
+                    )));
+                }
+                true
+            }
+            (None, _) => returns.option_payload().is_some(),
+            (Some(_), None) => false,
+        };
+        if !compatible {
+            return Err(type_error(format!(
+                "`?` は {} を返す関数の中でのみ使えます（関数 '{}' の戻り値は {} 型です）",
+                kind, ctx.builder.function().name, returns
+            )));
+        }
+        
+        let succeeded = if kind == "Option" { "Option::is_some" } else { "Result::is_ok" };
+        let condition = self.lower_variant_call(ctx, succeeded, vec![value.clone()])?;
+        let failure = ctx.builder.create_block();
+        let success = ctx.builder.create_block();
+        ctx.builder.cond_br(condition, success, failure);
+        
+        // 失敗した値は中身の表現が同じなので、そのまま呼び出し元に返せる
+        ctx.builder.position_at_end(failure);
+        self.lower_defers(ctx, 0)?;
+        ctx.builder.ret(Some(value.clone()));
+        
+        ctx.builder.position_at_end(success);
+        self.lower_variant_call(ctx, &format!("{}::unwrap", kind), vec![value])
+    }
+    
+    /// `try` ブロックの中の `expr?` を変換（失敗したら `catch` のブロックへエラーを渡す）
+    ///
+    /// `None` のエラーは unit 値とし、1つの `try` ブロックの中の `?` はすべて同じ型のエラーを渡さなければならない。
+    fn lower_try_in_block(&mut self, ctx: &mut FunctionContext, node: &ASTNode, value: Operand, kind: &str) -> Result<Operand> {
+        let (succeeded, error_type) = match self.operand_type(ctx, &value).result_payloads() {
+            Some((_, error)) => ("Result::is_ok", error.clone()),
+            None => ("Option::is_some", Type::unit()),
+        };
+        let condition = self.lower_variant_call(ctx, succeeded, vec![value.clone()])?;
+        let failure = ctx.builder.create_block();
+        let success = ctx.builder.create_block();
+        ctx.builder.cond_br(condition, success, failure);
+        
+        ctx.builder.position_at_end(failure);
+        let error = match kind {
+            "Result" => self.lower_variant_call(ctx, "Result::unwrap_err", vec![value.clone()])?,
+            _ => Operand::Literal(Literal::Unit),
+        };
+        let error_type_id = self.lower_type(Some(&error_type));
+        let depth = ctx.try_handlers.last().expect("try ブロックの中で呼び出される").depth;
+        self.lower_defers(ctx, depth)?;
+        let try_handler = ctx.try_handlers.last_mut().expect("try ブロックの中で呼び出される");
+        let catch_block = try_handler.block;
+        match &try_handler.error {
+            Some((_, expected)) if expected.to_string() != error_type.to_string() => {
+                return Err(EidosError::Type {
+                    message: format!(
+                        "`try` ブロックの中の `?` が渡すエラーの型が一致しません（{} と {}）",
+                        expected, error_type
+                    ),
+                    location: node.location.clone(),
+                });
+            }
+            Some(_) => {}
+            None => {
+                let register = ctx.builder.add_block_param(catch_block, error_type_id);
+                try_handler.error = Some((register, error_type));
+            }
+        }
+        ctx.builder.br(catch_block, vec![error]);
+        
+        ctx.builder.position_at_end(success);
+        self.lower_variant_call(ctx, &format!("{}::unwrap", kind), vec![value])
+    }
+    
+    /// 一番内側のスコープを抜ける（そのスコープで登録した `defer` の式を評価し、登録を消す）
+    fn exit_scope(&mut self, ctx: &mut FunctionContext) -> Result<()> {
+        let depth = ctx.scopes.len();
+        let result = self.lower_defers(ctx, depth - 1);
+        ctx.defers.retain(|deferred| deferred.depth < depth);
+        result
+    }
+    
+    /// スコープの深さが `depth` より深い `defer` の式を、登録と逆の順に変換する（登録は消さない）
+    ///
+    /// 式は登録した位置の束縛で変換する。式の中の `?` が関数から返すときは、その式より前に登録した式だけを評価する。
+    /// 到達しない位置（`?` で返した後など）では何もしない。
+    fn lower_defers(&mut self, ctx: &mut FunctionContext, depth: usize) -> Result<()> {
+        for index in (0..ctx.defers.len()).rev() {
+            if ctx.defers[index].depth <= depth || ctx.is_unreachable() {
+                break;
+            }
+            let later = ctx.defers.split_off(index);
+            let deferred = &later[0];
+            let scopes = std::mem::replace(&mut ctx.scopes, deferred.scopes.clone());
+            let result = self.lower_statement(ctx, &deferred.expr);
+            ctx.scopes = scopes;
+            ctx.defers.extend(later);
+            result?;
+        }
+        Ok(())
+    }
+    
+    /// `panic(message)` を変換（メッセージとスタックトレースを出力して実行を止める）
+    ///
+    /// 呼び出しの後は到達しないので、続く式は新しいブロックに変換する。
+    fn lower_panic(&mut self, ctx: &mut FunctionContext, node: &ASTNode, arguments: Vec<Operand>) -> Result<Operand> {
+        let is_message = arguments.len() == 1 && self.operand_type(ctx, &arguments[0]).kind == TypeKind::String;
+        if !is_message {
+            return Err(EidosError::Type {
+                message: "panic にはメッセージの文字列を1つ渡してください（`panic(\"message\")`）".to_string(),
+                location: node.location.clone(),
+            });
+        }
+        ctx.builder.call("panic", arguments, None);
+        ctx.builder.unreachable();
+        let block = ctx.builder.create_block();
+        ctx.builder.position_at_end(block);
+        ctx.unreachable_blocks.insert(block);
+        Ok(Operand::Literal(Literal::Unit))
+    }
+    
+    /// オペランドの型のTypeId（レジスタとグローバル変数は登録した型、リテラルはその型）
+    fn operand_type_id(&mut self, ctx: &FunctionContext, operand: &Operand) -> TypeId {
+        let registered = match operand {
+            Operand::Register(reg) => ctx.builder.function().get_register_type(*reg),
+            Operand::Global(name) => self.module.get_global(name).map(|global| global.ty),
+            _ => None,
+        };
+        match (registered, operand) {
+            (Some(id), _) => id,
+            (None, Operand::Literal(Literal::Bool(_))) => self.builtin_type("bool"),
+            (None, Operand::Literal(Literal::Float(_))) => self.builtin_type("float"),
+            (None, Operand::Literal(Literal::Char(_))) => self.builtin_type("char"),
+            (None, Operand::Literal(Literal::String(_))) => self.builtin_type("string"),
+            (None, Operand::Literal(Literal::Unit)) => self.builtin_type("unit"),
+            _ => self.builtin_type("int"),
+        }
+    }
+    
+    fn operand_type(&mut self, ctx: &FunctionContext, operand: &Operand) -> Type {
+        let id = self.operand_type_id(ctx, operand);
+        self.module.get_type(id).cloned().unwrap_or_else(Type::int)
+    }
+    
+    /// `asm!` / `wasm!` をインライン・アセンブリの命令に下げる
+    ///
+    /// オペランドに使えるのは数値・`Bool`・`Char` の値だけで、テンプレートの `{N}` はオペランドの数より
+    /// 小さくなければならない。`wasm!` では破壊するレジスタを指定できない。
+    #[allow(clippy::too_many_arguments)]
+    fn lower_inline_asm(
+        &mut self,
+        ctx: &mut FunctionContext,
+        node: &ASTNode,
+        dialect: AsmDialect,
+        template: &str,
+        inputs: &[ASTNode],
+        output: Option<&Type>,
+        clobbers: &[String],
+    ) -> Result<Operand> {
+        let keyword = dialect.keyword();
+        let type_error = |message: String| EidosError::Type {
+            message,
+            location: node.location.clone(),
+        };
+        let is_scalar = |ty: &Type| ty.numeric_type().is_some() || matches!(ty.kind, TypeKind::Bool | TypeKind::Char);
+        
+        let mut args = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let value = self.lower_expression(ctx, input)?;
+            let ty = self.operand_type(ctx, &value);
+            if !is_scalar(&ty) {
+                return Err(type_error(format!("{}! のオペランドに {} 型の値は渡せません", keyword, ty)));
+            }
+            args.push(value);
+        }
+        if let Some(ty) = output.filter(|ty| !is_scalar(ty)) {
+            return Err(type_error(format!("{}! の出力を {} 型にはできません", keyword, ty)));
+        }
+        if dialect == AsmDialect::Wasm && !clobbers.is_empty() {
+            return Err(type_error("wasm! では clobber を指定できません".to_string()));
+        }
+        let operands = usize::from(output.is_some()) + inputs.len();
+        for piece in eir::parse_asm_template(template) {
+            if let AsmPiece::Operand(index) = piece {
+                if index >= operands {
+                    return Err(type_error(format!(
+                        "{}! のテンプレートの {{{}}} に対応するオペランドがありません（オペランドは{}個）",
+                        keyword, index, operands
+                    )));
+                }
+            }
+        }
+        
+        let mut constraints: Vec<String> = output.iter().map(|_| "=r".to_string()).collect();
+        constraints.extend(inputs.iter().map(|_| "r".to_string()));
+        constraints.extend(clobbers.iter().map(|clobber| format!("~{{{}}}", clobber)));
+        let result = output.map(|ty| {
+            let type_id = self.lower_type(Some(ty));
+            ctx.builder.function_mut().create_register(type_id)
+        });
+        ctx.builder.emit(Instruction::InlineAsm {
+            asm: template.to_string(),
+            constraints: constraints.join(","),
+            args,
+            result,
+            dialect,
+        });
+        Ok(result.map_or(Operand::Literal(Literal::Unit), Operand::Register))
+    }
+    
+    /// `thread::atomic_*` の関数が表すアトミック操作
+    fn atomic_op(function: &str) -> Option<AtomicOp> {
+        match function {
+            "thread::atomic_load" => Some(AtomicOp::Load),
+            "thread::atomic_store" => Some(AtomicOp::Store),
+            "thread::atomic_add" => Some(AtomicOp::Add),
+            "thread::atomic_cas" => Some(AtomicOp::CAS),
+            _ => None,
+        }
+    }
+    
+    /// `thread::atomic_*` の呼び出しを、最初の引数の `static mut` 変数へのアトミック命令に下げる
+    ///
+    /// 変数は整数型でなければならず、残りの引数は変数の型に合わせる。
+    fn lower_atomic_call(
+        &mut self,
+        ctx: &mut FunctionContext,
+        node: &ASTNode,
+        function: &str,
+        op: AtomicOp,
+        args: &[ASTNode],
+    ) -> Result<Operand> {
+        let type_error = |message: String| EidosError::Type {
+            message,
+            location: node.location.clone(),
+        };
+        let arity = match op {
+            AtomicOp::Load => 1,
+            AtomicOp::CAS => 3,
+            _ => 2,
+        };
+        if args.len() != arity {
+            return Err(type_error(format!("{} には{}個の引数が必要ですが、{}個渡されました", function, arity, args.len())));
+        }
+        let variable = match &args[0].kind {
+            Node::Identifier { name, .. } if ctx.lookup(name).is_none() => match (self.globals.get(name.as_str()), self.module.get_global(name)) {
+                (Some(ast::GlobalKind::StaticMut), Some(global)) => Some((name.to_string(), global.ty)),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some((name, type_id)) = variable else {
+            return Err(type_error(format!("{} の最初の引数は static mut 変数でなければなりません", function)));
+        };
+        let ty = self.module.get_type(type_id).cloned().unwrap_or_else(Type::unit);
+        if ty.numeric_type().is_none_or(|numeric| numeric.is_float()) {
+            return Err(type_error(format!("{} で操作できるのは整数型の変数だけです（'{}' は {} 型）", function, name, ty)));
+        }
+        
+        let mut operands = Vec::with_capacity(arity - 1);
+        for arg in &args[1..] {
+            let value = self.lower_expression(ctx, arg)?;
+            operands.push(self.coerce(ctx, value, type_id, &arg.location)?);
+        }
+        let mut operands = operands.into_iter();
+        let (expected, result_type) = match op {
+            AtomicOp::CAS => (operands.next(), Some(self.builtin_type("bool"))),
+            AtomicOp::Store => (None, None),
+            _ => (None, Some(type_id)),
+        };
+        let result = ctx.builder.atomic(op, Operand::Global(name), operands.next(), expected, result_type);
+        Ok(result.map_or(Operand::Literal(Literal::Unit), Operand::Register))
+    }
+    
+    /// `thread::spawn` の引数が、引数を取らず `Int` を返すユーザー定義関数の名前かを検査
+    fn check_thread_entry(&self, node: &ASTNode, args: &[ASTNode]) -> Result<()> {
+        let entry = match args {
+            [arg] => match &arg.kind {
+                Node::Identifier { name, .. } => Some(name.as_str()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let valid = entry.is_some_and(|name| {
+            self.function_params.get(name).is_some_and(Vec::is_empty)
+                && self.function_returns.get(name).is_some_and(|ty| ty.kind == TypeKind::Int)
+        });
+        if !valid {
+            return Err(EidosError::Type {
+                message: "thread::spawn には引数を取らず Int を返す関数の名前を渡してください".to_string(),
+                location: node.location.clone(),
+            });
+        }
+        Ok(())
+    }
+    
+    /// オペランドが `BigInt` 型かどうか
+    fn is_bigint(&mut self, ctx: &FunctionContext, operand: &Operand) -> bool {
+        self.operand_type(ctx, operand).kind == TypeKind::BigInt
+    }
+    
+    /// `bigint` モジュールの関数を呼び出す（戻り値の型は関数の表から決める）
+    fn call_bigint(&mut self, ctx: &mut FunctionContext, name: &str, arguments: Vec<Operand>) -> Operand {
+        let returns = bigint::function(name).map(|function| Type::new(function.returns.clone()));
+        let type_id = self.lower_type(returns.as_ref());
+        let result = ctx.builder.call(format!("bigint::{}", name), arguments, Some(type_id));
+        Operand::Register(result.expect("戻り値の型を渡した呼び出しには結果がある"))
+    }
+    
+    /// 整数の値を `BigInt` に変換する（ビット幅を指定した整数型は、一度 `Int` にしてから変換する）
+    fn coerce_bigint(&mut self, ctx: &mut FunctionContext, value: Operand, location: &SourceLocation) -> Result<Operand> {
+        let ty = self.operand_type(ctx, &value);
+        match ty.numeric_type() {
+            _ if ty.kind == TypeKind::BigInt => Ok(value),
+            Some(numeric) if !numeric.is_float() => {
+                let value = match ty.kind {
+                    TypeKind::Int => value,
+                    _ => {
+                        let int = self.builtin_type("int");
+                        Operand::Register(ctx.builder.cast(value, int))
+                    }
+                };
+                Ok(self.call_bigint(ctx, "from_int", vec![value]))
+            }
+            _ => Err(EidosError::Type {
+                message: format!("BigInt 型が必要ですが、{} 型の値です", ty),
+                location: location.clone(),
+            }),
+        }
+    }
+    
+    /// `BigInt` の二項演算を `bigint` モジュールの関数の呼び出しにする
+    ///
+    /// もう片方のオペランドが整数なら `BigInt` に変換する。比較は `bigint::cmp` の結果を0と比べる。
+    fn lower_bigint_binary(
+        &mut self,
+        ctx: &mut FunctionContext,
+        node: &ASTNode,
+        op: ast::BinaryOp,
+        lhs: Operand,
+        rhs: Operand,
+    ) -> Result<Operand> {
+        let lhs = self.coerce_bigint(ctx, lhs, &node.location)?;
+        let rhs = self.coerce_bigint(ctx, rhs, &node.location)?;
+        let name = match op {
+            ast::BinaryOp::Add => "add",
+            ast::BinaryOp::Sub => "sub",
+            ast::BinaryOp::Mul => "mul",
+            ast::BinaryOp::Div => "div",
+            ast::BinaryOp::Mod => "rem",
+            ast::BinaryOp::Eq
+            | ast::BinaryOp::NotEq
+            | ast::BinaryOp::Lt
+            | ast::BinaryOp::LtEq
+            | ast::BinaryOp::Gt
+            | ast::BinaryOp::GtEq => {
+                let ordering = self.call_bigint(ctx, "cmp", vec![lhs, rhs]);
+                let bool_type = self.builtin_type("bool");
+                let result = ctx.builder.binary(Self::lower_binary_op(op), ordering, Operand::Literal(Literal::Int(0)), bool_type);
+                return Ok(Operand::Register(result));
+            }
+            _ => {
+                return Err(EidosError::Type {
+                    message: format!("BigInt 型には演算子 {} は使えません", op),
+                    location: node.location.clone(),
+                })
+            }
+        };
+        Ok(self.call_bigint(ctx, name, vec![lhs, rhs]))
+    }
+    
+    /// `bigint::*` の呼び出しを変換する（`BigInt` の引数に渡した整数は `BigInt` に変換する）
+    fn lower_bigint_call(&mut self, ctx: &mut FunctionContext, node: &ASTNode, function: &str, args: &[ASTNode]) -> Result<Operand> {
+        let name = &function["bigint::".len()..];
+        let Some(signature) = bigint::function(name) else {
+            return Err(EidosError::Type {
+                message: format!("bigint::{} という関数はありません", name),
+                location: node.location.clone(),
+            });
+        };
+        if args.len() != signature.params.len() {
+            return Err(EidosError::Type {
+                message: format!("{} の引数は{}個です（{}個渡されました）", function, signature.params.len(), args.len()),
+                location: node.location.clone(),
+            });
+        }
+        let mut arguments = Vec::with_capacity(args.len());
+        for (arg, param) in args.iter().zip(signature.params) {
+            let value = self.lower_expression(ctx, arg)?;
+            let value = match param {
+                TypeKind::BigInt => self.coerce_bigint(ctx, value, &arg.location)?,
+                _ => value,
+            };
+            arguments.push(value);
+        }
+        Ok(self.call_bigint(ctx, name, arguments))
+    }
+    
+    /// `Int`（`i64`）と `Float`（`f64`）以外のビット幅を指定した数値型ならその型を返す
+    fn sized_numeric(&self, type_id: TypeId) -> Option<NumericType> {
+        match self.module.get_type(type_id).map(|ty| &ty.kind) {
+            Some(TypeKind::Numeric(numeric)) => Some(*numeric),
+            _ => None,
+        }
+    }
+    
+    /// 値を代入先・引数・戻り値の型に合わせる
+    ///
+    /// ビット幅の違う数値は暗黙には変換しない（`as` で変換する）。
+    /// 接尾辞のない整数・浮動小数点数のリテラルだけは、範囲を検査してその型に変換する。
+    /// 値を持たない式（`else` のない `if` など）は、`Unit` 以外の型の値としては使えない。
+    fn coerce(&mut self, ctx: &mut FunctionContext, value: Operand, target: TypeId, location: &SourceLocation) -> Result<Operand> {
+        let source = self.operand_type_id(ctx, &value);
+        let (from, to) = (self.operand_type(ctx, &value), self.module.get_type(target).cloned());
+        if let Some(to) = to.as_ref().filter(|to| from.kind == TypeKind::Unit && to.kind != TypeKind::Unit) {
+            if !ctx.is_unreachable() {
+                return Err(EidosError::Type {
+                    message: format!("{} 型が必要ですが、値を持たない式（{} 型）です", to, from),
+                    location: location.clone(),
+                });
+            }
+        }
+        if to.as_ref().is_some_and(|to| to.kind == TypeKind::BigInt) && from.kind != TypeKind::BigInt {
+            return self.coerce_bigint(ctx, value, location);
+        }
+        if let Some(to) = to.as_ref().filter(|to| from.kind == TypeKind::BigInt && to.numeric_type().is_some()) {
+            // `i64` に収まらない整数リテラルは、数値型の値としては使えない
+            let literal = match value {
+                Operand::Register(register) => ctx.bigint_literals.get(&register),
+                _ => None,
+            };
+            return Err(match literal {
+                Some((digits, literal_location)) => EidosError::Type {
+                    message: format!("整数リテラル {} は {} 型で表せません", digits, to),
+                    location: literal_location.clone(),
+                },
+                None => EidosError::Type {
+                    message: format!("{} 型が必要ですが、BigInt 型の値です（`as {}` で変換してください）", to, to),
+                    location: location.clone(),
+                },
+            });
+        }
+        if let Some(to) = to.as_ref().filter(|to| to.element_type().is_some() || from.element_type().is_some()) {
+            return self.coerce_array(ctx, value, &from, to, location);
+        }
+        if let Some(to) = to.as_ref().filter(|to| matches!((&from.kind, &to.kind), (TypeKind::Tuple(_), _) | (_, TypeKind::Tuple(_)))) {
+            return self.coerce_tuple(ctx, value, &from, to, location);
+        }
+        let (Some(to), Some(from_numeric)) = (to, from.numeric_type()) else {
+            return Ok(value);
+        };
+        let Some(to_numeric) = to.numeric_type() else {
+            return Ok(value);
+        };
+        if from_numeric == to_numeric || (self.sized_numeric(source).is_none() && self.sized_numeric(target).is_none()) {
+            return Ok(value);
+        }
+        
+        match &value {
+            Operand::Literal(Literal::Int(literal)) if !to_numeric.is_float() => {
+                if !to_numeric.contains(*literal) {
+                    return Err(EidosError::Type {
+                        message: format!("整数リテラル {} は {} 型で表せません", literal, to_numeric),
+                        location: location.clone(),
+                    });
+                }
+            }
+            Operand::Literal(Literal::Float(_)) if to_numeric.is_float() => {}
+            _ => {
+                return Err(EidosError::Type {
+                    message: format!("{} 型が必要ですが、{} 型の値です（`as {}` で変換してください）", to_numeric, from_numeric, to_numeric),
+                    location: location.clone(),
+                });
+            }
+        }
+        Ok(Operand::Register(ctx.builder.cast(value, target)))
+    }
+    
+    /// 二項演算のオペランドと結果の型を決める
+    ///
+    /// ビット幅を指定した数値型のオペランドがあれば、もう片方をその型に合わせ、算術演算の結果もその型にする。
+    /// それ以外の算術演算の結果は左辺の型（片方が `Float` なら `Float`）、比較と論理演算の結果は `Bool`。
+    /// シフト演算の結果は左辺の型で、右辺はどの整数型でもよい。
+    fn binary_operands(
+        &mut self,
+        ctx: &mut FunctionContext,
+        node: &ASTNode,
+        op: ast::BinaryOp,
+        lhs: Operand,
+        rhs: Operand,
+    ) -> Result<(Operand, Operand, TypeId)> {
+        let (lhs_type, rhs_type) = (self.operand_type_id(ctx, &lhs), self.operand_type_id(ctx, &rhs));
+        for operand in [&lhs, &rhs] {
+            self.check_not_array(ctx, op, operand, &node.location)?;
+        }
+        if op.is_bitwise() {
+            for operand in [&lhs, &rhs] {
+                self.check_integer_operand(ctx, op, operand, &node.location)?;
+            }
+        }
+        let comparison = matches!(
+            op,
+            ast::BinaryOp::Eq
+                | ast::BinaryOp::NotEq
+                | ast::BinaryOp::Lt
+                | ast::BinaryOp::LtEq
+                | ast::BinaryOp::Gt
+                | ast::BinaryOp::GtEq
+                | ast::BinaryOp::And
+                | ast::BinaryOp::Or
+        );
+        
+        if matches!(op, ast::BinaryOp::LShift | ast::BinaryOp::RShift) {
+            return Ok((lhs, rhs, lhs_type));
+        }
+        let sized = match (self.sized_numeric(lhs_type), self.sized_numeric(rhs_type)) {
+            (Some(_), _) => lhs_type,
+            (None, Some(_)) => rhs_type,
+            (None, None) => {
+                let type_id = if comparison {
+                    self.builtin_type("bool")
+                } else if self.module.get_type(rhs_type).is_some_and(|ty| ty.kind == TypeKind::Float) {
+                    rhs_type
+                } else {
+                    lhs_type
+                };
+                return Ok((lhs, rhs, type_id));
+            }
+        };
+        let location = &node.location;
+        let lhs = self.coerce(ctx, lhs, sized, location)?;
+        let rhs = self.coerce(ctx, rhs, sized, location)?;
+        let type_id = if comparison { self.builtin_type("bool") } else { sized };
+        Ok((lhs, rhs, type_id))
+    }
+    
+    /// `if` 式を下げる
+    ///
+    /// 両方の分岐がリテラルか変数の参照だけなら `select` に、それ以外は分岐して合流ブロックのパラメータで値を受け取る。
+    /// `else` のない `if` 式の値は `()`。
+    fn lower_if(
+        &mut self,
+        ctx: &mut FunctionContext,
+        node: &ASTNode,
+        condition: &ASTNode,
+        then_branch: &ASTNode,
+        else_branch: Option<&ASTNode>,
+    ) -> Result<Operand> {
+        let cond = self.lower_expression(ctx, condition)?;
+        let condition_type = self.operand_type(ctx, &cond);
+        if condition_type.kind != TypeKind::Bool && !Self::is_inferred_int(&cond, &condition_type) {
+            return Err(EidosError::Type {
+                message: format!("if の条件は bool 型である必要がありますが、{} 型の値です", condition_type),
+                location: condition.location.clone(),
+            });
+        }
+        
+        let Some(else_branch) = else_branch else {
+            let then_block = ctx.builder.create_block();
+            let join_block = ctx.builder.create_block();
+            ctx.builder.cond_br(cond, then_block, join_block);
+            ctx.builder.position_at_end(then_block);
+            self.lower_expression(ctx, then_branch)?;
+            ctx.builder.br(join_block, Vec::new());
+            ctx.builder.position_at_end(join_block);
+            return Ok(Operand::Literal(Literal::Unit));
+        };
+        
+        if Self::is_simple_branch(then_branch) && Self::is_simple_branch(else_branch) {
+            let then_value = self.lower_expression(ctx, then_branch)?;
+            let else_value = self.lower_expression(ctx, else_branch)?;
+            let type_id = self.branch_type(ctx, node, &then_value, &else_value)?;
+            let true_value = self.coerce(ctx, then_value, type_id, &then_branch.location)?;
+            let false_value = self.coerce(ctx, else_value, type_id, &else_branch.location)?;
+            return Ok(Operand::Register(ctx.builder.select(cond, true_value, false_value, type_id)));
+        }
+        
+        let then_block = ctx.builder.create_block();
+        let else_block = ctx.builder.create_block();
+        let join_block = ctx.builder.create_block();
+        ctx.builder.cond_br(cond, then_block, else_block);
+        
+        // 分岐の最後のブロックと値（`return` などで抜ける分岐は値を持たない）
+        let mut arms = Vec::with_capacity(2);
+        for (block, branch) in [(then_block, then_branch), (else_block, else_branch)] {
+            ctx.builder.position_at_end(block);
+            let value = self.lower_expression(ctx, branch)?;
+            let value = if ctx.is_unreachable() { None } else { Some(value) };
+            arms.push((ctx.builder.current_block(), value, &branch.location));
+        }
+        
+        let type_id = match (&arms[0].1, &arms[1].1) {
+            (Some(then_value), Some(else_value)) => self.branch_type(ctx, node, then_value, else_value)?,
+            (Some(value), None) | (None, Some(value)) => self.operand_type_id(ctx, value),
+            (None, None) => self.builtin_type("unit"),
+        };
+        for (block, value, location) in arms {
+            ctx.builder.position_at_end(block);
+            match value {
+                Some(value) => {
+                    let value = self.coerce(ctx, value, type_id, location)?;
+                    ctx.builder.br(join_block, vec![value]);
+                }
+                None => {
+                    ctx.builder.unreachable();
+                }
+            }
+        }
+        
+        let result = ctx.builder.add_block_param(join_block, type_id);
+        ctx.builder.position_at_end(join_block);
+        Ok(Operand::Register(result))
+    }
+    
+    /// `if` 式の2つの分岐の値をそろえる型を求める
+    ///
+    /// ビット幅を指定した数値型の分岐があれば、もう一方の数値をその型に合わせる。
+    /// 型が確定していないかもしれない `Int` の値は、もう一方の型に合わせる。
+    fn branch_type(&mut self, ctx: &FunctionContext, node: &ASTNode, then_value: &Operand, else_value: &Operand) -> Result<TypeId> {
+        let (then_type, else_type) = (self.operand_type_id(ctx, then_value), self.operand_type_id(ctx, else_value));
+        let (then_ty, else_ty) = (self.operand_type(ctx, then_value), self.operand_type(ctx, else_value));
+        if then_ty.kind == else_ty.kind {
+            return Ok(then_type);
+        }
+        if self.sized_numeric(then_type).is_some() && else_ty.numeric_type().is_some() {
+            return Ok(then_type);
+        }
+        if self.sized_numeric(else_type).is_some() && then_ty.numeric_type().is_some() {
+            return Ok(else_type);
+        }
+        if Self::is_inferred_int(then_value, &then_ty) {
+            return Ok(else_type);
+        }
+        if Self::is_inferred_int(else_value, &else_ty) {
+            return Ok(then_type);
+        }
+        Err(EidosError::Type {
+            message: format!(
+                "if 式の分岐の型が一致しません（then の分岐は {} 型、else の分岐は {} 型です）",
+                then_ty, else_ty
+            ),
+            location: node.location.clone(),
+        })
+    }
+    
+    /// 型が確定していないかもしれない値か（戻り値の型が分からない呼び出しの結果は `Int` のレジスタになる）
+    fn is_inferred_int(value: &Operand, ty: &Type) -> bool {
+        matches!(value, Operand::Register(_)) && ty.kind == TypeKind::Int
+    }
+    
+    /// 両方を評価しても副作用のない単純な分岐か（リテラルと変数の参照だけ）
+    fn is_simple_branch(node: &ASTNode) -> bool {
+        match &node.kind {
+            Node::Literal(_) | Node::Identifier { .. } => true,
+            Node::BlockExpr { statements, result: Some(result) } => statements.is_empty() && Self::is_simple_branch(result),
+            _ => false,
+        }
+    }
+    
+    /// 演算子の被演算子が配列・スライスでないことを検査
+    fn check_not_array(&mut self, ctx: &FunctionContext, op: ast::BinaryOp, operand: &Operand, location: &SourceLocation) -> Result<()> {
+        let ty = self.operand_type(ctx, operand);
+        if ty.element_type().is_none() {
+            return Ok(());
+        }
+        Err(EidosError::Type {
+            message: format!("演算子 '{}' は {} 型の値には使えません", op.symbol(), ty),
+            location: location.clone(),
+        })
+    }
+    
+    /// ビット演算の被演算子が整数型であることを検査
+    ///
+    /// 型検査（`TypeChecker`）と同じ規則で、`Int` と整数の数値型だけを通す。型の決まっていない値（推論中の型・型パラメータ）は見送る。
+    fn check_integer_operand(
+        &mut self,
+        ctx: &FunctionContext,
+        op: ast::BinaryOp,
+        operand: &Operand,
+        location: &SourceLocation,
+    ) -> Result<()> {
+        let ty = self.operand_type(ctx, operand);
+        if ty.is_integer() || ty.is_undetermined() {
+            return Ok(());
+        }
+        Err(EidosError::Type {
+            message: format!("演算子 '{}' は整数型にのみ使えますが、{} 型の値です", op.symbol(), ty),
+            location: location.clone(),
+        })
+    }
+    
+    /// 配列のリテラル（`[a, b]`・`[v; n]`）を、スタックに確保した領域に要素を格納する命令に下げる
+    ///
+    /// 要素の型は、`expected`（変数の型注釈）があればその要素の型、なければ最初の要素の型にする。
+    fn lower_array(&mut self, ctx: &mut FunctionContext, node: &ASTNode, expected: Option<&Type>) -> Result<Operand> {
+        let expected = expected.and_then(Type::element_type).cloned();
+        let (values, count) = match &node.kind {
+            Node::ArrayLiteral { elements } => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push((self.lower_expression(ctx, element)?, &element.location));
+                }
+                let count = values.len();
+                (values, count)
+            }
+            Node::ArrayRepeat { value, count } => (vec![(self.lower_expression(ctx, value)?, &value.location)], *count),
+            _ => return Err(EidosError::Internal(format!("配列のリテラルではありません: {:?}", node.kind))),
+        };
+        let element = match (expected, values.first()) {
+            (Some(element), _) => element,
+            (None, Some((first, _))) => self.operand_type(ctx, first),
+            (None, None) => {
+                return Err(EidosError::Type {
+                    message: "空の配列は要素の型が分かりません（`let a: [Int; 0] = [];` のように型を指定してください）".to_string(),
+                    location: node.location.clone(),
+                })
+            }
+        };
+        let ty = Type::fixed_array(element.clone(), count);
+        self.check_array_type(&ty, &node.location)?;
+        let element_id = self.lower_type(Some(&element));
+        let mut stored = Vec::with_capacity(values.len());
+        for (value, location) in values {
+            let value = self.coerce(ctx, value, element_id, location)?;
+            let actual = self.operand_type(ctx, &value);
+            if actual.to_string() != element.to_string() && !Self::is_inferred_int(&value, &actual) {
+                return Err(EidosError::Type {
+                    message: format!("配列の要素の型が一致しません（{} 型の配列に {} 型の値があります）", element, actual),
+                    location: location.clone(),
+                });
+            }
+            stored.push(value);
+        }
+        
+        let array = Operand::Register(self.alloca_array(ctx, &ty));
+        if let Node::ArrayRepeat { .. } = node.kind {
+            let value = stored.pop().expect("繰り返す値は1つ");
+            self.for_each_index(ctx, count, |ctx, index| {
+                let address = ctx.builder.gep(array.clone(), vec![index], element_id);
+                ctx.builder.store(Operand::Register(address), value.clone());
+            });
+        } else {
+            for (index, value) in stored.into_iter().enumerate() {
+                let address = ctx.builder.gep(array.clone(), vec![Operand::Literal(Literal::Int(index as i64))], element_id);
+                ctx.builder.store(Operand::Register(address), value);
+            }
+        }
+        Ok(array)
+    }
+    
+    /// 配列の要素にできる型か（大きさの決まった値だけを要素にでき、配列の配列はまだ扱わない）
+    fn check_array_type(&self, ty: &Type, location: &SourceLocation) -> Result<()> {
+        let element = ty.element_type().expect("配列型の要素の型");
+        if element.element_type().is_none() && element.layout().is_some() {
+            return Ok(());
+        }
+        Err(EidosError::Type {
+            message: format!("{} 型の値は配列の要素にできません", element),
+            location: location.clone(),
+        })
+    }
+    
+    /// 固定長配列 `ty` の領域をスタックに確保する（結果のレジスタは配列型で、先頭のアドレスを持つ）
+    fn alloca_array(&mut self, ctx: &mut FunctionContext, ty: &Type) -> RegisterId {
+        let (size, _) = ty.layout().expect("要素の型を検査した配列型");
+        let type_id = self.lower_type(Some(ty));
+        ctx.builder.alloca(size, type_id)
+    }
+    
+    /// 固定長配列 `from` の要素を、同じ型の配列 `to` にコピーする
+    fn copy_array(&mut self, ctx: &mut FunctionContext, from: Operand, to: Operand, ty: &Type) {
+        let (element, length) = match &ty.kind {
+            TypeKind::FixedArray { element, length } => ((**element).clone(), *length),
+            _ => return,
+        };
+        let element_id = self.lower_type(Some(&element));
+        self.for_each_index(ctx, length, |ctx, index| {
+            let source = ctx.builder.gep(from.clone(), vec![index.clone()], element_id);
+            let value = ctx.builder.load(Operand::Register(source), element_id);
+            let target = ctx.builder.gep(to.clone(), vec![index], element_id);
+            ctx.builder.store(Operand::Register(target), Operand::Register(value));
+        });
+    }
+    
+    /// `for` ループを下げる
+    fn lower_for(&mut self, ctx: &mut FunctionContext, variable: &str, iterable: &ASTNode, body: &ASTNode) -> Result<Operand> {
+        let value = self.lower_expression(ctx, iterable)?;
+        self.lower_loop(ctx, variable, value, iterable, |this, ctx| this.lower_statement(ctx, body))?;
+        Ok(Operand::Literal(Literal::Unit))
+    }
+    
+    /// 内包表記を、`for` ループで要素をスタック上の配列に集める命令に下げる
+    ///
+    /// 配列の長さは元の固定長配列の長さ（範囲ならリテラルの両端の差）で、要素の型は要素の式の型にする。
+    /// 条件がなければその配列を、条件があれば集めた要素を指すスライスを値にする。
+    fn lower_comprehension(
+        &mut self,
+        ctx: &mut FunctionContext,
+        element: &ASTNode,
+        variable: &str,
+        iterable: &ASTNode,
+        condition: Option<&ASTNode>,
+    ) -> Result<Operand> {
+        let value = self.lower_expression(ctx, iterable)?;
+        let capacity = match (&iterable.kind, self.operand_type(ctx, &value).kind) {
+            (_, TypeKind::FixedArray { length, .. }) => Some(length),
+            (Node::RangeExpr { start, end }, _) => match (&start.kind, &end.kind) {
+                (Node::Literal(ast::Literal::Int(start)), Node::Literal(ast::Literal::Int(end))) => {
+                    Some(end.saturating_sub(*start).max(0) as usize)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(capacity) = capacity else {
+            return Err(EidosError::Type {
+                message: "内包表記の元には固定長配列か、両端が整数リテラルの範囲が必要です（要素を集める配列の長さを決めるため）".to_string(),
+                location: iterable.location.clone(),
+            });
+        };
+        
+        // 要素の型は要素の式を下げるまで分からないので、配列はループの前のブロックに後から確保する
+        let preheader = ctx.builder.current_block();
+        let int = self.builtin_type("int");
+        let count = ctx.builder.alloca(SLOT_SIZE, int);
+        ctx.builder.store(Operand::Register(count), Operand::Literal(Literal::Int(0)));
+        let mut buffer: Option<(Operand, Type)> = None;
+        self.lower_loop(ctx, variable, value, iterable, |this, ctx| {
+            let merge = ctx.builder.create_block();
+            if let Some(condition) = condition {
+                let condition = this.lower_expression(ctx, condition)?;
+                let then_block = ctx.builder.create_block();
+                ctx.builder.cond_br(condition, then_block, merge);
+                ctx.builder.position_at_end(then_block);
+            }
+            let mut value = this.lower_expression(ctx, element)?;
+            let (array, element_type) = match &buffer {
+                Some(buffer) => buffer.clone(),
+                None => {
+                    let element_type = this.operand_type(ctx, &value);
+                    let ty = Type::fixed_array(element_type.clone(), capacity);
+                    this.check_array_type(&ty, &element.location)?;
+                    let current = ctx.builder.current_block();
+                    ctx.builder.position_at_end(preheader);
+                    let array = Operand::Register(this.alloca_array(ctx, &ty));
+                    ctx.builder.position_at_end(current);
+                    buffer.insert((array, element_type)).clone()
+                }
+            };
+            let element_id = this.lower_type(Some(&element_type));
+            value = this.coerce(ctx, value, element_id, &element.location)?;
+            let index = Operand::Register(ctx.builder.load(Operand::Register(count), int));
+            let address = ctx.builder.gep(array, vec![index.clone()], element_id);
+            ctx.builder.store(Operand::Register(address), value);
+            let next = ctx.builder.binary(BinaryOp::Add, index, Operand::Literal(Literal::Int(1)), int);
+            ctx.builder.store(Operand::Register(count), Operand::Register(next));
+            ctx.builder.br(merge, Vec::new());
+            ctx.builder.position_at_end(merge);
+            Ok(())
+        })?;
+        
+        let (array, element_type) = buffer.expect("要素の式はループの本体で一度だけ下げる");
+        if condition.is_none() {
+            return Ok(array);
+        }
+        let slice = self.lower_type(Some(&Type::slice(element_type)));
+        let length = Operand::Register(ctx.builder.load(Operand::Register(count), int));
+        Ok(Operand::Register(ctx.builder.make_slice(array, length, slice)))
+    }
+    
+    /// `value`（範囲・固定長配列・スライス）の要素を順に `variable` に束縛して `body` を繰り返す
+    ///
+    /// 範囲はそのまま、固定長配列とスライスは添字の範囲 `0..len` をイテレータにして、スタックスロットに置く。
+    /// 反復ごとに `next` と同じ手順で次の値を取り出すが、`Option` は作らずに分岐する。
+    fn lower_loop(
+        &mut self,
+        ctx: &mut FunctionContext,
+        variable: &str,
+        value: Operand,
+        iterable: &ASTNode,
+        mut body: impl FnMut(&mut Self, &mut FunctionContext) -> Result<()>,
+    ) -> Result<()> {
+        let ty = self.operand_type(ctx, &value);
+        let (range, elements) = if ty.range_element().is_some() {
+            (value, None)
+        } else if let Some(element) = ty.element_type().cloned() {
+            let length = self.array_length(ctx, &value).expect("配列の長さ");
+            let range_type = self.lower_type(Some(&Type::range(Type::int())));
+            let indices = ctx.builder.tuple(vec![Operand::Literal(Literal::Int(0)), length], range_type);
+            (Operand::Register(indices), Some((value, element)))
+        } else {
+            return Err(EidosError::Type {
+                message: format!("'for' で反復できるのは範囲・固定長配列・スライスだけです（{} 型の値です）", ty),
+                location: iterable.location.clone(),
+            });
+        };
+        let range_type = self.operand_type_id(ctx, &range);
+        let iterator = ctx.builder.alloca(SLOT_SIZE, range_type);
+        ctx.builder.store(Operand::Register(iterator), range);
+        
+        let header = ctx.builder.create_block();
+        let body_block = ctx.builder.create_block();
+        let exit = ctx.builder.create_block();
+        ctx.builder.br(header, Vec::new());
+        
+        ctx.builder.position_at_end(header);
+        let (has_next, current) = self.lower_next(ctx, iterator);
+        ctx.builder.cond_br(has_next, body_block, exit);
+        
+        ctx.builder.position_at_end(body_block);
+        let current = match elements {
+            Some((array, element)) => {
+                let element_id = self.lower_type(Some(&element));
+                let address = ctx.builder.gep(array, vec![current], element_id);
+                Operand::Register(ctx.builder.load(Operand::Register(address), element_id))
+            }
+            None => current,
+        };
+        ctx.scopes.push(HashMap::new());
+        if variable != "_" {
+            ctx.bind(variable, Binding::Value(current));
+        }
+        let result = body(self, ctx);
+        ctx.scopes.pop();
+        result?;
+        if !ctx.builder.is_terminated() {
+            ctx.builder.br(header, Vec::new());
+        }
+        
+        ctx.builder.position_at_end(exit);
+        Ok(())
+    }
+    
+    /// `next(it)` を下げる（`it` は `let mut` で宣言した範囲の変数。次の値を `Option` で返し、変数を進める）
+    fn lower_next_call(&mut self, ctx: &mut FunctionContext, node: &ASTNode, args: &[ASTNode]) -> Result<Operand> {
+        let iterator = match args {
+            [ASTNode { kind: Node::Identifier { name, .. }, .. }] => match ctx.lookup(name) {
+                Some(Binding::Slot(slot)) => Some(*slot),
+                _ => None,
+            },
+            _ => None,
+        };
+        let range = iterator.map(|slot| self.operand_type(ctx, &Operand::Register(slot)));
+        let (Some(iterator), Some(element)) = (iterator, range.as_ref().and_then(Type::range_element).cloned()) else {
+            return Err(EidosError::Type {
+                message: "next の引数には `let mut` で宣言した範囲の変数が必要です".to_string(),
+                location: node.location.clone(),
+            });
+        };
+        let (has_next, current) = self.lower_next(ctx, iterator);
+        let option = self.lower_type(Some(&Type::option(element)));
+        let some = ctx.builder.call("Option::some", vec![current], Some(option)).expect("戻り値の型を渡した呼び出しには結果がある");
+        let none = ctx.builder.call("Option::none", Vec::new(), Some(option)).expect("戻り値の型を渡した呼び出しには結果がある");
+        Ok(Operand::Register(ctx.builder.select(has_next, Operand::Register(some), Operand::Register(none), option)))
+    }
+    
+    /// イテレータのプロトコル：スロット `iterator` の範囲から次の値を取り出し、範囲を進める
+    ///
+    /// 値があるかどうかの `Bool` と、取り出した値を返す（値がなければ範囲は進めない）。
+    fn lower_next(&mut self, ctx: &mut FunctionContext, iterator: RegisterId) -> (Operand, Operand) {
+        let range = self.operand_type(ctx, &Operand::Register(iterator));
+        let range_type = self.lower_type(Some(&range));
+        let element = self.lower_type(range.range_element());
+        let bool_type = self.builtin_type("bool");
+        let current = ctx.builder.load(Operand::Register(iterator), range_type);
+        let start = Operand::Register(ctx.builder.extract(Operand::Register(current), 0, element));
+        let end = Operand::Register(ctx.builder.extract(Operand::Register(current), 1, element));
+        let has_next = Operand::Register(ctx.builder.binary(BinaryOp::Lt, start.clone(), end.clone(), bool_type));
+        let next = ctx.builder.binary(BinaryOp::Add, start.clone(), Operand::Literal(Literal::Int(1)), element);
+        let advanced = ctx.builder.select(has_next.clone(), Operand::Register(next), start.clone(), element);
+        let updated = ctx.builder.tuple(vec![Operand::Register(advanced), end], range_type);
+        ctx.builder.store(Operand::Register(iterator), Operand::Register(updated));
+        (has_next, start)
+    }
+    
+    /// 0 から `count - 1` までの添字で `body` を繰り返すループを作る（添字はブロックパラメータで受け渡す）
+    fn for_each_index(
+        &mut self,
+        ctx: &mut FunctionContext,
+        count: usize,
+        mut body: impl FnMut(&mut FunctionContext, Operand),
+    ) {
+        let int = self.builtin_type("int");
+        let bool_type = self.builtin_type("bool");
+        let header = ctx.builder.create_block();
+        let body_block = ctx.builder.create_block();
+        let exit = ctx.builder.create_block();
+        let index = ctx.builder.add_block_param(header, int);
+        ctx.builder.br(header, vec![Operand::Literal(Literal::Int(0))]);
+        
+        ctx.builder.position_at_end(header);
+        let condition = ctx.builder.binary(BinaryOp::Lt, Operand::Register(index), Operand::Literal(Literal::Int(count as i64)), bool_type);
+        ctx.builder.cond_br(Operand::Register(condition), body_block, exit);
+        
+        ctx.builder.position_at_end(body_block);
+        body(ctx, Operand::Register(index));
+        let next = ctx.builder.binary(BinaryOp::Add, Operand::Register(index), Operand::Literal(Literal::Int(1)), int);
+        ctx.builder.br(header, vec![Operand::Register(next)]);
+        
+        ctx.builder.position_at_end(exit);
+    }
+    
+    /// `base[index]` の要素のアドレスと要素の型を求める
+    ///
+    /// 添字が範囲外なら実行時にパニックする。固定長配列の長さは型から、スライスの長さは `SliceLen` で求め、
+    /// 整数リテラルの添字が固定長配列の範囲外ならコンパイル時のエラーにする。
+    fn element_address(&mut self, ctx: &mut FunctionContext, base: &ASTNode, index: &ASTNode) -> Result<(Operand, TypeId)> {
+        let array = self.lower_expression(ctx, base)?;
+        let ty = self.operand_type(ctx, &array);
+        let Some(element) = ty.element_type().cloned() else {
+            return Err(EidosError::Type {
+                message: format!("添字を付けられるのは固定長配列とスライスだけです（{} 型の値です）", ty),
+                location: base.location.clone(),
+            });
+        };
+        let position = self.lower_expression(ctx, index)?;
+        let index_type = self.operand_type(ctx, &position);
+        let int = self.builtin_type("int");
+        let position = match index_type.numeric_type() {
+            Some(_) if index_type.kind == TypeKind::Int => position,
+            Some(numeric) if !numeric.is_float() => Operand::Register(ctx.builder.cast(position, int)),
+            _ => {
+                return Err(EidosError::Type {
+                    message: format!("配列の添字は整数である必要があります（{} 型の値です）", index_type),
+                    location: index.location.clone(),
+                })
+            }
+        };
+        
+        match (&ty.kind, &position) {
+            (TypeKind::FixedArray { length, .. }, Operand::Literal(Literal::Int(value))) => {
+                if *value < 0 || *value as usize >= *length {
+                    return Err(EidosError::Type {
+                        message: format!("添字 {} は {} 型の配列の範囲外です", value, ty),
+                        location: index.location.clone(),
+                    });
+                }
+            }
+            _ => {
+                let length = self.array_length(ctx, &array).expect("配列型の長さ");
+                self.check_bounds(ctx, position.clone(), length);
+            }
+        }
+        let element_id = self.lower_type(Some(&element));
+        let address = ctx.builder.gep(array, vec![position], element_id);
+        Ok((Operand::Register(address), element_id))
+    }
+    
+    /// `assert(cond)`・`assert(cond, "message")` を変換（条件が偽なら位置とメッセージを出力して実行を止める）
+    ///
+    /// 失敗したときの `panic` の呼び出しには `ASSERTION_TAG` を付け、値域解析で常に真と分かれば取り除けるようにする。
+    fn lower_assert(&mut self, ctx: &mut FunctionContext, node: &ASTNode, args: &[ASTNode]) -> Result<Operand> {
+        let (condition, message) = match args {
+            [condition] => (condition, "アサーションに失敗しました"),
+            [condition, ASTNode { kind: Node::Literal(ast::Literal::String(message)), .. }] => (condition, message.as_str()),
+            _ => {
+                return Err(EidosError::Type {
+                    message: "assert には条件と、省略できるメッセージの文字列リテラルを渡してください".to_string(),
+                    location: node.location.clone(),
+                })
+            }
+        };
+        if !self.check_assertions {
+            return Ok(Operand::Literal(Literal::Unit));
+        }
+        let value = self.lower_expression(ctx, condition)?;
+        if self.operand_type(ctx, &value).kind != TypeKind::Bool {
+            return Err(EidosError::Type {
+                message: "assert の条件は Bool 型でなければなりません".to_string(),
+                location: condition.location.clone(),
+            });
+        }
+        let failure = ctx.builder.create_block();
+        let success = ctx.builder.create_block();
+        ctx.builder.cond_br(value, success, failure);
+        
+        ctx.builder.position_at_end(failure);
+        let location = &node.location;
+        let message = format!("{}:{}:{}: {}", location.file.display(), location.line, location.column, message);
+        let call = ctx.builder.emit(Instruction::Call {
+            function: "panic".to_string(),
+            arguments: vec![Operand::Literal(Literal::String(message))],
+            result: None,
+        });
+        let attributes = ctx.builder.function_mut().instruction_attributes_mut(call);
+        attributes.tags.insert(eir::ASSERTION_TAG.to_string());
+        attributes.hotness = Hotness::Cold;
+        ctx.builder.unreachable();
+        
+        ctx.builder.position_at_end(success);
+        Ok(Operand::Literal(Literal::Unit))
+    }
+    
+    /// `0 <= index < length` でなければパニックする
+    fn check_bounds(&mut self, ctx: &mut FunctionContext, index: Operand, length: Operand) {
+        let bool_type = self.builtin_type("bool");
+        let non_negative = ctx.builder.binary(BinaryOp::Ge, index.clone(), Operand::Literal(Literal::Int(0)), bool_type);
+        let below = ctx.builder.binary(BinaryOp::Lt, index, length, bool_type);
+        let in_bounds = ctx.builder.binary(BinaryOp::And, Operand::Register(non_negative), Operand::Register(below), bool_type);
+        let failure = ctx.builder.create_block();
+        let success = ctx.builder.create_block();
+        ctx.builder.cond_br(Operand::Register(in_bounds), success, failure);
+        
+        ctx.builder.position_at_end(failure);
+        let message = Operand::Literal(Literal::String("index out of bounds".to_string()));
+        ctx.builder.call("panic", vec![message], None);
+        ctx.builder.unreachable();
+        
+        ctx.builder.position_at_end(success);
+    }
+    
+    /// 固定長配列かスライスの長さ（配列でなければ `None`）
+    fn array_length(&mut self, ctx: &mut FunctionContext, array: &Operand) -> Option<Operand> {
+        match self.operand_type(ctx, array).kind {
+            TypeKind::FixedArray { length, .. } => Some(Operand::Literal(Literal::Int(length as i64))),
+            TypeKind::Slice(_) => {
+                let int = self.builtin_type("int");
+                Some(Operand::Register(ctx.builder.slice_len(array.clone(), int)))
+            }
+            _ => None,
+        }
+    }
+    
+    /// 配列の値を代入先・引数の型に合わせる（固定長配列はスライスの型に渡すと配列全体を指すスライスになる）
+    fn coerce_array(
+        &mut self,
+        ctx: &mut FunctionContext,
+        value: Operand,
+        from: &Type,
+        to: &Type,
+        location: &SourceLocation,
+    ) -> Result<Operand> {
+        if from.to_string() == to.to_string() {
+            return Ok(value);
+        }
+        if let (TypeKind::FixedArray { element, length }, TypeKind::Slice(target)) = (&from.kind, &to.kind) {
+            if element.to_string() == target.to_string() {
+                let slice = self.lower_type(Some(to));
+                let length = Operand::Literal(Literal::Int(*length as i64));
+                return Ok(Operand::Register(ctx.builder.make_slice(value, length, slice)));
+            }
+        }
+        Err(EidosError::Type {
+            message: format!("{} 型が必要ですが、{} 型の値です", to, from),
+            location: location.clone(),
+        })
+    }
+    
+    /// タプルのリテラルを変換する（`expected` はタプルの型の注釈）
+    fn lower_tuple(&mut self, ctx: &mut FunctionContext, node: &ASTNode, expected: Option<&Type>) -> Result<Operand> {
+        let elements = self.lower_tuple_elements(ctx, node, expected)?;
+        let ty = Type::tuple(elements.iter().map(|(_, ty)| ty.clone()).collect());
+        self.check_tuple_type(&ty, &node.location)?;
+        let type_id = self.lower_type(Some(&ty));
+        let values = elements.into_iter().map(|(value, _)| value).collect();
+        Ok(Operand::Register(ctx.builder.tuple(values, type_id)))
+    }
+    
+    /// タプルのリテラルの要素を順に変換し、注釈の要素の型に合わせる（値と要素の型の組を返す）
+    fn lower_tuple_elements(
+        &mut self,
+        ctx: &mut FunctionContext,
+        node: &ASTNode,
+        expected: Option<&Type>,
+    ) -> Result<Vec<(Operand, Type)>> {
+        let Node::TupleLiteral { elements } = &node.kind else {
+            return Err(EidosError::Internal(format!("タプルのリテラルではありません: {:?}", node.kind)));
+        };
+        let expected = match expected.map(|ty| &ty.kind) {
+            Some(TypeKind::Tuple(types)) if types.len() == elements.len() => Some(types),
+            Some(_) => {
+                return Err(EidosError::Type {
+                    message: format!("{} 型が必要ですが、要素が{}個のタプルです", expected.expect("注釈の型"), elements.len()),
+                    location: node.location.clone(),
+                })
+            }
+            None => None,
+        };
+        let mut values = Vec::with_capacity(elements.len());
+        for (index, element) in elements.iter().enumerate() {
+            let mut value = self.lower_expression(ctx, element)?;
+            if let Some(ty) = expected.map(|types| &types[index]) {
+                let type_id = self.lower_type(Some(ty));
+                value = self.coerce(ctx, value, type_id, &element.location)?;
+            }
+            let ty = self.operand_type(ctx, &value);
+            values.push((value, ty));
+        }
+        Ok(values)
+    }
+    
+    /// タプルの要素にできる型か（大きさの決まった値だけを要素にでき、配列とタプルの入れ子はまだ扱わない）
+    fn check_tuple_type(&self, ty: &Type, location: &SourceLocation) -> Result<()> {
+        let TypeKind::Tuple(elements) = &ty.kind else {
+            return Ok(());
+        };
+        let invalid = elements.iter().find(|element| {
+            element.layout().is_none() || element.element_type().is_some() || matches!(element.kind, TypeKind::Tuple(_))
+        });
+        match invalid {
+            Some(element) => Err(EidosError::Type {
+                message: format!("{} 型の値はタプルの要素にできません", element),
+                location: location.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+    
+    /// タプルの値を代入先・引数の型に合わせる（要素ごとに合わせ、変換が必要なら組み立て直す）
+    fn coerce_tuple(
+        &mut self,
+        ctx: &mut FunctionContext,
+        value: Operand,
+        from: &Type,
+        to: &Type,
+        location: &SourceLocation,
+    ) -> Result<Operand> {
+        if from.to_string() == to.to_string() {
+            return Ok(value);
+        }
+        let mismatch = || EidosError::Type {
+            message: format!("{} 型が必要ですが、{} 型の値です", to, from),
+            location: location.clone(),
+        };
+        let (TypeKind::Tuple(sources), TypeKind::Tuple(targets)) = (&from.kind, &to.kind) else {
+            return Err(mismatch());
+        };
+        if sources.len() != targets.len() {
+            return Err(mismatch());
+        }
+        let mut elements = Vec::with_capacity(targets.len());
+        for (index, (source, target)) in sources.iter().zip(targets).enumerate() {
+            let source_id = self.lower_type(Some(source));
+            let element = Operand::Register(ctx.builder.extract(value.clone(), index, source_id));
+            let target_id = self.lower_type(Some(target));
+            let element = self.coerce(ctx, element, target_id, location)?;
+            if self.operand_type(ctx, &element).to_string() != target.to_string() {
+                return Err(mismatch());
+            }
+            elements.push(element);
+        }
+        let type_id = self.lower_type(Some(to));
+        Ok(Operand::Register(ctx.builder.tuple(elements, type_id)))
+    }
+    
+    /// 代入先のアドレスと値の型を求める
+    fn assignment_address(&mut self, ctx: &mut FunctionContext, target: &ASTNode) -> Result<(Operand, TypeId)> {
+        let name = match &target.kind {
+            Node::Identifier { name, .. } => name.as_str(),
+            Node::Index { base, index } => {
+                // 要素に代入できるのは `let mut` で宣言した配列の変数だけ（スライスの要素は書き換えられない）
+                let writable = match &base.kind {
+                    Node::Identifier { name, .. } => matches!(ctx.lookup(name), Some(Binding::Array { is_mutable: true, .. })),
+                    _ => false,
+                };
+                if !writable {
+                    return Err(EidosError::Type {
+                        message: "要素に代入できるのは `let mut` で宣言した配列の変数だけです".to_string(),
+                        location: target.location.clone(),
+                    });
+                }
+                return self.element_address(ctx, base, index);
+            }
+            _ => {
+                return Err(EidosError::BackendError(
+                    "代入先は変数である必要があります".to_string(),
+                ))
+            }
+        };
+        match ctx.lookup(name) {
+            Some(Binding::Slot(slot)) => {
+                let type_id = ctx.builder.function().get_register_type(*slot).unwrap_or_else(|| self.builtin_type("int"));
+                Ok((Operand::Register(*slot), type_id))
+            }
+            Some(Binding::Array { address, is_mutable: true }) => {
+                let type_id = ctx.builder.function().get_register_type(*address).unwrap_or_else(|| self.builtin_type("int"));
+                Ok((Operand::Register(*address), type_id))
+            }
+            Some(Binding::Value(_) | Binding::Array { .. }) => Err(EidosError::BackendError(format!(
+                "イミュータブルな変数 '{}' には代入できません",
+                name
+            ))),
+            None => match (self.globals.get(name), self.module.get_global(name)) {
+                (Some(ast::GlobalKind::StaticMut), Some(global)) => Ok((Operand::Global(name.to_string()), global.ty)),
+                (Some(kind), _) => Err(EidosError::BackendError(format!(
+                    "{} の変数 '{}' には代入できません",
+                    kind.keyword(),
+                    name
+                ))),
+                (None, _) => Err(EidosError::BackendError(format!("未定義の変数 '{}' への代入です", name))),
+            },
+        }
+    }
+    
+    fn lower_literal(lit: &ast::Literal) -> Literal {
+        match lit {
+            ast::Literal::Int(v) => Literal::Int(*v),
+            ast::Literal::Float(v) => Literal::Float(*v),
+            ast::Literal::Bool(v) => Literal::Bool(*v),
+            ast::Literal::Char(c) => Literal::Char(*c as u32),
+            ast::Literal::String(s) => Literal::String(s.clone()),
+            ast::Literal::BigInt(v) => Literal::String(v.to_string()),
+            ast::Literal::Unit => Literal::Unit,
+        }
+    }
+    
+    fn lower_binary_op(op: ast::BinaryOp) -> BinaryOp {
+        match op {
+            ast::BinaryOp::Add => BinaryOp::Add,
+            ast::BinaryOp::Sub => BinaryOp::Sub,
+            ast::BinaryOp::Mul => BinaryOp::Mul,
+            ast::BinaryOp::Div => BinaryOp::Div,
+            ast::BinaryOp::Mod => BinaryOp::Rem,
+            ast::BinaryOp::BitAnd => BinaryOp::BitAnd,
+            ast::BinaryOp::BitOr => BinaryOp::BitOr,
+            ast::BinaryOp::BitXor => BinaryOp::BitXor,
+            ast::BinaryOp::LShift => BinaryOp::Shl,
+            ast::BinaryOp::RShift => BinaryOp::Shr,
+            ast::BinaryOp::Eq => BinaryOp::Eq,
+            ast::BinaryOp::NotEq => BinaryOp::Ne,
+            ast::BinaryOp::Lt => BinaryOp::Lt,
+            ast::BinaryOp::LtEq => BinaryOp::Le,
+            ast::BinaryOp::Gt => BinaryOp::Gt,
+            ast::BinaryOp::GtEq => BinaryOp::Ge,
+            ast::BinaryOp::And => BinaryOp::And,
+            ast::BinaryOp::Or => BinaryOp::Or,
+        }
+    }
+}
+
+/// 式の中で `await` しているかどうか（関数定義の中は見ない）
+fn contains_await(node: &ASTNode) -> bool {
+    match &node.kind {
+        Node::Await { .. } => true,
+        Node::FunctionDef { .. } => false,
+        _ => node.children().into_iter().any(contains_await),
+    }
+}
+
+/// テスト用に、ソースを構文解析してEIRモジュールを構築する
+#[cfg(test)]
+pub(crate) fn build_test_module(name: &str, source: &str) -> Result<Module> {
+    let program = crate::frontend::parse_test_source(source)?;
+    ModuleBuilder::new(name.to_string()).build_from_ast(&program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn build(source: &str) -> Result<Module> {
+        build_test_module("test", source)
+    }
+    
+    #[test]
+    fn test_reassignment_and_compound_assignment() {
+        // 可変な変数はスロットに置き、代入は書き込み、複合代入は読み込み・演算・書き込みにする
+        let module = build("fn f(a: Int) -> Int { var x = a; x = x + 1; x += 2; x -= 3; let mut y = x; y *= 4; y }").unwrap();
+        let text = module.to_string();
+        let body = [
+            "%1 = alloca 8", "store %1, %0",
+            "%2 = load %1", "%3 = add %2, 1", "store %1, %3",
+            "%4 = load %1", "%5 = add %4, 2", "store %1, %5",
+            "%6 = load %1", "%7 = sub %6, 3", "store %1, %7",
+            "%8 = load %1", "%9 = alloca 8", "store %9, %8",
+            "%10 = load %9", "%11 = mul %10, 4", "store %9, %11",
+            "%12 = load %9", "ret %12",
+        ];
+        let lines: Vec<&str> = text.lines().map(str::trim).skip_while(|line| *line != "block_0:").skip(1).take(body.len()).collect();
+        assert_eq!(lines, body, "{}", text);
+    }
+    
+    #[test]
+    fn test_assignment_to_immutable_binding() {
+        // `let` の変数と引数には、代入も複合代入もできない
+        for source in [
+            "fn f() -> Int { let a = 1; a = 2; a }",
+            "fn f() -> Int { let a = 1; a += 2; a }",
+            "fn f(a: Int) -> Int { a *= 2; a }",
+        ] {
+            let error = build(source).unwrap_err();
+            assert!(error.to_string().contains("イミュータブルな変数 'a' には代入できません"), "{}: {}", source, error);
+        }
+        
+        // 配列の要素に代入できるのは `let mut` の変数だけ
+        let error = build("fn f() -> Int { let a = [1, 2]; a[0] = 3; a[0] }").unwrap_err();
+        assert!(error.to_string().contains("要素に代入できるのは"), "{}", error);
+        assert!(build("fn f() -> Int { let mut a = [1, 2]; a[0] = 3; a[0] }").is_ok());
+        
+        let error = build("fn f() -> Int { b = 1; 0 }").unwrap_err();
+        assert!(error.to_string().contains("未定義の変数 'b' への代入です"), "{}", error);
+    }
+    
+    #[test]
+    fn test_deferred_initialization() {
+        // 初期化式のない `let` は、分岐の中での代入で初期化できる
+        let module = build(
+            "fn sign(n: Int) -> Int { let sign: Int; if n < 0 { sign = -1; } else { sign = 1; } sign } \
+             fn main() -> Int { let x: Int; x = 10; sign(-5) * x + sign(3) }",
+        )
+        .unwrap();
+        crate::core::eir_verifier::verify(&module).unwrap();
+        let mut engine = crate::backend::jit::JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), crate::backend::jit::JitValue::Int(-9));
+    }
+    
+    #[test]
+    fn test_bigint_literal_out_of_range() {
+        // `i64` に収まらないリテラルを `Int` として使うと、リテラルの位置で範囲のエラーにする
+        for (source, position) in [
+            ("fn f() -> Int {\n    let x: Int = 9223372036854775808;\n    x\n}", (2, 18)),
+            ("fn f() -> Int {\n    0;\n    9223372036854775808\n}", (3, 5)),
+            ("fn g(n: Int) -> Int { n }\nfn f() -> Int {\n    g(9223372036854775808)\n}", (3, 7)),
+        ] {
+            match build(source).unwrap_err() {
+                EidosError::Type { message, location } => {
+                    assert_eq!(message, "整数リテラル 9223372036854775808 は Int 型で表せません", "{}", source);
+                    assert_eq!((location.line, location.column), position, "{}", source);
+                }
+                error => panic!("{}: {}", source, error),
+            }
+        }
+        
+        // `BigInt` の変数や `as` での変換はそのまま使える
+        assert!(build("fn f() -> Int { let x = 9223372036854775808; (x - 1) as Int }").is_ok());
+        let error = build("fn f() -> Int { let x: BigInt = 1; x }").unwrap_err();
+        assert!(error.to_string().contains("Int 型が必要ですが、BigInt 型の値です"), "{}", error);
+    }
+    
+    #[test]
+    fn test_if_without_else_has_no_value() {
+        // `else` のない `if` は `Unit` 型で、値として使うと型エラーになる
+        for source in [
+            "fn f() -> Int { let x = if true { 1 }; x }",
+            "fn f() -> Int { let x: Int = if true { 1 }; x }",
+            "fn g(n: Int) -> Int { n }\nfn f() -> Int { g(if true { 1 }) }",
+            "fn f() -> Int { if true { 1 } }",
+        ] {
+            let error = build(source).unwrap_err();
+            assert!(error.to_string().contains("Int 型が必要ですが、値を持たない式（() 型）です"), "{}: {}", source, error);
+        }
+        
+        // 文としての `if` と、`else` のある `if` の値はそのまま使える
+        assert!(build("fn f(n: Int) -> Int { let mut x = 0; if n > 0 { x = n; } x }").is_ok());
+        assert!(build("fn f() { let x = if true { 1 }; }").is_ok());
+        assert!(build("fn f(n: Int) -> Int { let x = if n > 0 { 1 } else { 2 }; x }").is_ok());
+    }
+}
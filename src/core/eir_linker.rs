@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::core::eir::{Function, FunctionId, Instruction, Module};
+use crate::core::types::{Type, TypeId};
+use crate::core::{EidosError, Result};
+
+/// 複数のEIRモジュールを1つにまとめる（リンク時最適化の入力にする）
+///
+/// 関数と大域変数は名前で参照し合うので、まとめた後は別のモジュールの関数も呼び出しの解決や
+/// 最適化の対象になる。モジュールごとに振った型と関数のIDは重なりうるので、後のモジュールの
+/// IDを振り直す（同じIDで同じ型なら、そのまま共有する）。まとめたモジュールの名前は最初の
+/// モジュールの名前にする。
+///
+/// 同じ名前の関数や大域変数を複数のモジュールが定義している場合と、エントリーポイントを持つ
+/// モジュールが複数ある場合はエラーにする。同じ名前の外部関数の宣言は、型が一致すれば1つにまとめる。
+pub fn link(modules: Vec<Module>) -> Result<Module> {
+    let mut modules = modules.into_iter();
+    let mut linked = modules
+        .next()
+        .ok_or_else(|| EidosError::Internal("リンクするモジュールがありません".to_string()))?;
+    for module in modules {
+        link_into(&mut linked, module)?;
+    }
+    if let Some(max) = linked.types.keys().max() {
+        Type::reserve_ids(*max);
+    }
+    Ok(linked)
+}
+
+fn link_into(linked: &mut Module, mut module: Module) -> Result<()> {
+    if linked.entry_point.is_some() && module.entry_point.is_some() {
+        return Err(EidosError::CodeGen(format!(
+            "モジュール '{}' と '{}' の両方にエントリーポイントがあります",
+            linked.name, module.name
+        )));
+    }
+    let types = remap_types(linked, &module);
+    let mut next_function = linked.functions.keys().map(|id| id.0 + 1).max().unwrap_or(0);
+    
+    let mut functions: Vec<Function> = module.functions.drain().map(|(_, function)| function).collect();
+    functions.sort_by_key(|function| function.id.0);
+    for mut function in functions {
+        if linked.get_function_by_name(&function.name).is_some() {
+            return Err(duplicate("関数", &function.name, &module.name));
+        }
+        let id = FunctionId(next_function);
+        next_function += 1;
+        if module.entry_point == Some(function.id) {
+            linked.entry_point = Some(id);
+        }
+        function.id = id;
+        remap_function(&mut function, &types);
+        linked.add_function(function);
+    }
+    
+    let mut globals: Vec<_> = module.globals.into_iter().collect();
+    globals.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, mut global) in globals {
+        if linked.globals.contains_key(&name) {
+            return Err(duplicate("大域変数", &name, &module.name));
+        }
+        remap(&types, &mut global.ty);
+        linked.add_global(&name, global);
+    }
+    
+    let mut externals: Vec<_> = module.external_functions.into_iter().collect();
+    externals.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, mut external) in externals {
+        remap(&types, &mut external.function_type);
+        remap(&types, &mut external.return_type);
+        for ty in &mut external.parameter_types {
+            remap(&types, ty);
+        }
+        match linked.external_functions.get(&name) {
+            Some(existing) if linked.type_name(existing.function_type) != linked.type_name(external.function_type) => {
+                return Err(EidosError::CodeGen(format!(
+                    "外部関数 '{}' の型がモジュール '{}'（{}）と '{}'（{}）で異なります",
+                    name,
+                    linked.name,
+                    linked.type_name(existing.function_type),
+                    module.name,
+                    linked.type_name(external.function_type)
+                )));
+            }
+            Some(_) => {}
+            None => linked.declare_external_function(&name, external),
+        }
+    }
+    Ok(())
+}
+
+/// 後のモジュールの型を、まとめたモジュールに登録してIDの対応を返す
+fn remap_types(linked: &mut Module, module: &Module) -> HashMap<TypeId, TypeId> {
+    let mut next = linked.types.keys().chain(module.types.keys()).map(|id| id.0 + 1).max().unwrap_or(0);
+    let mut ids: Vec<TypeId> = module.types.keys().copied().collect();
+    ids.sort();
+    
+    let mut types = HashMap::new();
+    for id in ids {
+        let ty = &module.types[&id];
+        let new_id = match linked.types.get(&id) {
+            None => id,
+            Some(existing) if existing == ty => id,
+            Some(_) => {
+                next += 1;
+                TypeId(next - 1)
+            }
+        };
+        linked.types.entry(new_id).or_insert_with(|| Type { id: new_id, kind: ty.kind.clone() });
+        types.insert(id, new_id);
+    }
+    types
+}
+
+/// 型IDを振り直す（モジュールに登録されていない型のIDはそのまま）
+fn remap(types: &HashMap<TypeId, TypeId>, ty: &mut TypeId) {
+    if let Some(new_id) = types.get(ty) {
+        *ty = *new_id;
+    }
+}
+
+fn remap_function(function: &mut Function, types: &HashMap<TypeId, TypeId>) {
+    remap(types, &mut function.function_type);
+    remap(types, &mut function.return_type);
+    for (_, ty) in &mut function.parameters {
+        remap(types, ty);
+    }
+    for ty in function.register_types.values_mut() {
+        remap(types, ty);
+    }
+    for block in function.blocks.values_mut() {
+        for (_, ty) in &mut block.parameters {
+            remap(types, ty);
+        }
+        for (_, instruction) in &mut block.instructions {
+            if let Instruction::Cast { target_type, .. } = instruction {
+                remap(types, target_type);
+            }
+        }
+    }
+}
+
+fn duplicate(kind: &str, name: &str, module: &str) -> EidosError {
+    EidosError::CodeGen(format!("{} '{}' はモジュール '{}' でも定義されています", kind, name, module))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir_builder::build_test_module;
+    
+    fn build(name: &str, source: &str) -> Module {
+        build_test_module(name, source).unwrap()
+    }
+    
+    #[test]
+    fn test_link_modules() {
+        let main = build("main", "fn square(x: Int) -> Int { x * x } println(square(3));");
+        let mut library = build("library", "#[export] fn half(x: Float) -> Float { x / 2.0 }");
+        // 別々のプロセスで作ったモジュールのように、`main` の型と同じIDを振り直して重ねる
+        let mut ids: Vec<TypeId> = main.types.keys().copied().collect();
+        ids.sort();
+        let mut types: Vec<Type> = library.types.drain().map(|(_, ty)| ty).collect();
+        types.sort_by_key(|ty| ty.id);
+        let mut renamed = HashMap::new();
+        for (index, ty) in types.into_iter().enumerate() {
+            let id = ids.get(index).copied().unwrap_or(TypeId(ids[ids.len() - 1].0 + 1 + index));
+            renamed.insert(ty.id, id);
+            library.types.insert(id, Type { id, kind: ty.kind });
+        }
+        for function in library.functions.values_mut() {
+            remap_function(function, &renamed);
+        }
+        
+        let linked = link(vec![main, library]).unwrap();
+        assert_eq!(linked.name, "main");
+        assert_eq!(linked.get_function(linked.entry_point.unwrap()).unwrap().name, "main");
+        let half = linked.get_function_by_name("half").unwrap();
+        assert_eq!(linked.type_name(half.return_type), "Float");
+        assert!(linked.to_string().contains("half(x: Float) -> Float {"), "{}", linked);
+        crate::core::eir::verify(&linked).unwrap();
+    }
+    
+    #[test]
+    fn test_conflicts() {
+        let error = link(vec![build("a", "fn f() -> Int { 1 }"), build("b", "fn f() -> Int { 2 }")]).unwrap_err();
+        assert!(error.to_string().contains("関数 'f' はモジュール 'b' でも定義されています"), "{}", error);
+        
+        let error = link(vec![build("a", "println(1);"), build("b", "println(2);")]).unwrap_err();
+        assert!(error.to_string().contains("'a' と 'b' の両方にエントリーポイント"), "{}", error);
+    }
+}
@@ -0,0 +1,526 @@
+use std::collections::HashSet;
+
+use crate::core::{Result, EidosError};
+use crate::core::eir::{
+    BasicBlock, BinaryOp, BlockId, Function, Instruction, InstructionId, Module, Operand, RegisterId, Terminator,
+};
+use crate::core::types::{Type, TypeId, TypeKind};
+
+/// EIRモジュールが整合しているかを検証
+///
+/// 最適化パスの誤りが後段の誤ったコード生成として現れる前に見つけるため、パスの合間に実行する。
+/// 次の点を検査し、見つかった問題をすべてまとめて `EidosError::Internal` として返す。
+///
+/// - エントリーポイントと各関数のエントリーブロックが存在する
+/// - 分岐先、PHIノードの流入元、命令の位置情報と属性が参照するブロックと命令が存在する
+/// - 制御命令はブロックの末尾にだけあり、すべてのブロックが終了命令で終わる
+/// - レジスタは一度だけ定義され、使う前に型とともに定義されている
+/// - 演算の両辺、分岐の引数と分岐先のパラメータ、戻り値と関数の戻り値の型が一致する
+/// - タプルの作成と取り出しで、要素の数と型がタプル型と一致する
+pub fn verify(module: &Module) -> Result<()> {
+    report("EIRの検証に失敗しました", problems(module))
+}
+
+/// 変換の後にEIRモジュールを検証（エラーメッセージに変換の名前を含める）
+pub fn verify_after(module: &Module, stage: &str) -> Result<()> {
+    report(&format!("{}の後のEIRの検証に失敗しました", stage), problems(module))
+}
+
+fn report(summary: &str, problems: Vec<String>) -> Result<()> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(EidosError::Internal(format!("{}（{}件）:\n  {}", summary, problems.len(), problems.join("\n  "))))
+}
+
+fn problems(module: &Module) -> Vec<String> {
+    let mut problems = Vec::new();
+    
+    if let Some(entry) = module.entry_point {
+        if module.get_function(entry).is_none() {
+            problems.push(format!("エントリーポイントの関数 {} が存在しません", entry));
+        }
+    }
+    
+    let mut functions: Vec<&Function> = module.functions.values().collect();
+    functions.sort_by_key(|function| function.id.0);
+    for function in functions {
+        let mut verifier = FunctionVerifier { module, function, problems: Vec::new() };
+        verifier.verify();
+        // 両方の分岐先が同じブロックの場合などに、同じ問題が続けて見つかる
+        verifier.problems.dedup();
+        problems.extend(
+            verifier
+                .problems
+                .into_iter()
+                .map(|problem| format!("関数 '{}': {}", function.name, problem)),
+        );
+    }
+    problems
+}
+
+/// 1つの関数の検証
+struct FunctionVerifier<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    problems: Vec<String>,
+}
+
+impl<'a> FunctionVerifier<'a> {
+    fn verify(&mut self) {
+        let function = self.function;
+        if !function.blocks.contains_key(&function.entry_block) {
+            self.problems.push(format!("エントリーブロック {} が存在しません", function.entry_block));
+            return;
+        }
+        
+        let mut blocks: Vec<&BasicBlock> = function.blocks.values().collect();
+        blocks.sort_by_key(|block| block.id.0);
+        
+        // レジスタの定義（パラメータ、ブロックのパラメータ、命令の結果）
+        let mut defined: HashSet<RegisterId> = (0..function.parameters.len() as u32).map(RegisterId).collect();
+        let mut instructions = HashSet::new();
+        for block in &blocks {
+            for (register, _) in &block.parameters {
+                self.define(&mut defined, *register, block.id);
+            }
+            for (id, instruction) in &block.instructions {
+                if !instructions.insert(*id) {
+                    self.problems.push(format!("{}: 命令ID {} が重複しています", block.id, id));
+                }
+                if let Some(register) = instruction.defined_register() {
+                    self.define(&mut defined, register, block.id);
+                }
+            }
+        }
+        
+        for (id, block) in &function.blocks {
+            if *id != block.id {
+                self.problems.push(format!("{} がブロック {} として登録されています", block.id, id));
+            }
+        }
+        for block in &blocks {
+            self.verify_block(block, &defined);
+        }
+        
+        let side_tables: [(&str, Vec<InstructionId>); 2] = [
+            ("位置情報", function.locations.keys().copied().collect()),
+            ("属性", function.instruction_attributes.keys().copied().collect()),
+        ];
+        for (table, ids) in side_tables {
+            let mut dangling: Vec<InstructionId> = ids.into_iter().filter(|id| !instructions.contains(id)).collect();
+            dangling.sort_by_key(|id| id.0);
+            for id in dangling {
+                self.problems.push(format!("{}が存在しない命令 {} を参照しています", table, id));
+            }
+        }
+    }
+    
+    fn define(&mut self, defined: &mut HashSet<RegisterId>, register: RegisterId, block: BlockId) {
+        if !defined.insert(register) {
+            self.problems.push(format!("{}: レジスタ {} が複数回定義されています", block, register));
+        }
+        if self.function.get_register_type(register).is_none() {
+            self.problems.push(format!("{}: レジスタ {} の型が登録されていません", block, register));
+        }
+    }
+    
+    fn verify_block(&mut self, block: &BasicBlock, defined: &HashSet<RegisterId>) {
+        let last = block.instructions.len().saturating_sub(1);
+        for (index, (id, instruction)) in block.instructions.iter().enumerate() {
+            for register in instruction.used_registers() {
+                if !defined.contains(&register) {
+                    self.problems.push(format!("{}: 命令 {} が未定義のレジスタ {} を使っています", block.id, id, register));
+                }
+            }
+            
+            // 終了命令を持たない古い形式のブロックは、末尾の制御命令で終わってよい
+            if is_control_instruction(instruction) && (index != last || block.terminator.is_some()) {
+                self.problems.push(format!("{}: 制御命令 {} がブロックの途中にあります", block.id, id));
+            }
+            self.verify_instruction(block.id, *id, instruction);
+        }
+        
+        match &block.terminator {
+            Some(terminator) => self.verify_terminator(block.id, terminator, defined),
+            None if block.instructions.last().is_some_and(|(_, i)| is_control_instruction(i)) => {}
+            None => self.problems.push(format!("{}: 終了命令がありません", block.id)),
+        }
+    }
+    
+    fn verify_instruction(&mut self, block: BlockId, id: InstructionId, instruction: &Instruction) {
+        match instruction {
+            Instruction::BinaryOp { op, lhs, rhs, .. } if !matches!(op, BinaryOp::Shl | BinaryOp::Shr) => {
+                let (lhs_type, rhs_type) = (self.operand_type(lhs), self.operand_type(rhs));
+                if let (Some(lhs_type), Some(rhs_type)) = (lhs_type, rhs_type) {
+                    if !self.same_type(lhs_type, rhs_type) {
+                        self.problems.push(format!(
+                            "{}: 命令 {} の両辺の型が一致しません（{} と {}）",
+                            block,
+                            id,
+                            self.type_name(lhs_type),
+                            self.type_name(rhs_type)
+                        ));
+                    }
+                }
+            }
+            Instruction::Phi { incoming, .. } => {
+                for (_, from) in incoming {
+                    if !self.function.blocks.contains_key(from) {
+                        self.problems.push(format!("{}: 命令 {} の流入元 {} が存在しません", block, id, from));
+                    }
+                }
+            }
+            Instruction::Tuple { elements, result } => {
+                let Some(result_type) = self.function.get_register_type(*result) else {
+                    return;
+                };
+                let Some(element_types) = self.tuple_elements(result_type) else {
+                    self.problems.push(format!("{}: 命令 {} の結果が {} 型でタプル型ではありません", block, id, self.type_name(result_type)));
+                    return;
+                };
+                if element_types.len() != elements.len() {
+                    self.problems.push(format!(
+                        "{}: 命令 {} の結果は要素が{}個のタプルですが、{}個の値が渡されています",
+                        block,
+                        id,
+                        element_types.len(),
+                        elements.len()
+                    ));
+                    return;
+                }
+                for (index, (element, expected)) in elements.iter().zip(element_types).enumerate() {
+                    if let Some(ty) = self.operand_type(element) {
+                        if !self.matches_type(ty, expected) {
+                            self.problems.push(format!(
+                                "{}: 命令 {} の{}番目の要素は {} 型ですが、{} 型の値が渡されています",
+                                block,
+                                id,
+                                index,
+                                expected,
+                                self.type_name(ty)
+                            ));
+                        }
+                    }
+                }
+            }
+            Instruction::Extract { tuple, index, result } => {
+                let Some(tuple_type) = self.operand_type(tuple) else {
+                    return;
+                };
+                let Some(element_types) = self.tuple_elements(tuple_type) else {
+                    self.problems.push(format!("{}: 命令 {} が {} 型の値から要素を取り出しています", block, id, self.type_name(tuple_type)));
+                    return;
+                };
+                let Some(expected) = element_types.get(*index) else {
+                    self.problems.push(format!(
+                        "{}: 命令 {} の添字 {} が要素{}個のタプルの範囲外です",
+                        block,
+                        id,
+                        index,
+                        element_types.len()
+                    ));
+                    return;
+                };
+                if let Some(ty) = self.function.get_register_type(*result) {
+                    if !self.matches_type(ty, expected) {
+                        self.problems.push(format!(
+                            "{}: 命令 {} の結果が {} 型ですが、取り出す要素は {} 型です",
+                            block,
+                            id,
+                            self.type_name(ty),
+                            expected
+                        ));
+                    }
+                }
+            }
+            Instruction::MakeSlice { array, result, .. } => {
+                let is_array = |ty: &Type| matches!(ty.kind, TypeKind::FixedArray { .. });
+                let is_slice = |ty: &Type| matches!(ty.kind, TypeKind::Slice(_));
+                if let Some(ty) = self.operand_type(array).filter(|ty| !self.has_type(*ty, is_array)) {
+                    self.problems.push(format!("{}: 命令 {} が {} 型の値からスライスを作っています", block, id, self.type_name(ty)));
+                }
+                if let Some(ty) = self.function.get_register_type(*result).filter(|ty| !self.has_type(*ty, is_slice)) {
+                    self.problems.push(format!("{}: 命令 {} の結果が {} 型でスライス型ではありません", block, id, self.type_name(ty)));
+                }
+            }
+            Instruction::SliceLen { slice, .. } => {
+                let is_slice = |ty: &Type| matches!(ty.kind, TypeKind::Slice(_));
+                if let Some(ty) = self.operand_type(slice).filter(|ty| !self.has_type(*ty, is_slice)) {
+                    self.problems.push(format!("{}: 命令 {} が {} 型の値の長さを取り出しています", block, id, self.type_name(ty)));
+                }
+            }
+            Instruction::Branch { target } => {
+                self.check_target(block, *target);
+            }
+            Instruction::BranchCond { true_target, false_target, .. } => {
+                self.check_target(block, *true_target);
+                self.check_target(block, *false_target);
+            }
+            _ => {}
+        }
+    }
+    
+    fn verify_terminator(&mut self, block: BlockId, terminator: &Terminator, defined: &HashSet<RegisterId>) {
+        let mut used = Vec::new();
+        match terminator {
+            Terminator::Branch { target, args } => {
+                self.check_edge(block, *target, args);
+                used.extend(args);
+            }
+            Terminator::BranchCond { condition, true_target, true_args, false_target, false_args } => {
+                if let Some(ty) = self.operand_type(condition) {
+                    let kind = self.module.get_type(ty).map(|ty| &ty.kind);
+                    if !matches!(kind, Some(TypeKind::Bool | TypeKind::Int) | None) {
+                        self.problems.push(format!("{}: 分岐の条件が {} 型です", block, self.type_name(ty)));
+                    }
+                }
+                self.check_edge(block, *true_target, true_args);
+                self.check_edge(block, *false_target, false_args);
+                used.push(condition);
+                used.extend(true_args);
+                used.extend(false_args);
+            }
+            Terminator::Switch { value, default_target, default_args, cases } => {
+                self.check_edge(block, *default_target, default_args);
+                for (_, target, args) in cases {
+                    self.check_edge(block, *target, args);
+                    used.extend(args);
+                }
+                used.push(value);
+                used.extend(default_args);
+            }
+            Terminator::IndirectCall { function_ptr, arguments, return_block, return_args } => {
+                self.check_edge(block, *return_block, return_args);
+                used.push(function_ptr);
+                used.extend(arguments);
+                used.extend(return_args);
+            }
+            Terminator::Return { value } => {
+                if let Some(value) = value {
+                    if let Some(ty) = self.operand_type(value) {
+                        if !self.same_type(ty, self.function.return_type) {
+                            self.problems.push(format!(
+                                "{}: 戻り値が {} 型ですが、関数の戻り値の型は {} です",
+                                block,
+                                self.type_name(ty),
+                                self.type_name(self.function.return_type)
+                            ));
+                        }
+                    }
+                    used.push(value);
+                }
+            }
+            Terminator::Unreachable => {}
+        }
+        
+        for operand in used {
+            if let Operand::Register(register) = operand {
+                if !defined.contains(register) {
+                    self.problems.push(format!("{}: 終了命令が未定義のレジスタ {} を使っています", block, register));
+                }
+            }
+        }
+    }
+    
+    fn check_target(&mut self, block: BlockId, target: BlockId) -> Option<&'a BasicBlock> {
+        let found = self.function.blocks.get(&target);
+        if found.is_none() {
+            self.problems.push(format!("{}: 分岐先 {} が存在しません", block, target));
+        }
+        found
+    }
+    
+    /// 分岐先が存在し、渡す引数が分岐先のパラメータと数も型も一致するか
+    fn check_edge(&mut self, block: BlockId, target: BlockId, args: &[Operand]) {
+        let Some(target_block) = self.check_target(block, target) else {
+            return;
+        };
+        if target_block.parameters.len() != args.len() {
+            self.problems.push(format!(
+                "{}: 分岐先 {} のパラメータは{}個ですが、引数が{}個渡されています",
+                block,
+                target,
+                target_block.parameters.len(),
+                args.len()
+            ));
+            return;
+        }
+        for ((_, param_type), arg) in target_block.parameters.iter().zip(args) {
+            if let Some(arg_type) = self.operand_type(arg) {
+                if !self.same_type(arg_type, *param_type) {
+                    self.problems.push(format!(
+                        "{}: 分岐先 {} のパラメータは {} 型ですが、{} 型の引数が渡されています",
+                        block,
+                        target,
+                        self.type_name(*param_type),
+                        self.type_name(arg_type)
+                    ));
+                }
+            }
+        }
+    }
+    
+    /// レジスタのオペランドの型（リテラルなどは型を決めずに `None`）
+    fn operand_type(&self, operand: &Operand) -> Option<TypeId> {
+        match operand {
+            Operand::Register(register) => self.function.get_register_type(*register),
+            _ => None,
+        }
+    }
+    
+    /// 型IDが違っても、同じ型なら一致とみなす（`Option<int>` のように中の型をIDで持つ型は名前で比べる）
+    fn same_type(&self, a: TypeId, b: TypeId) -> bool {
+        match (self.module.get_type(a), self.module.get_type(b)) {
+            (Some(a), Some(b)) => a.kind == b.kind || a.to_string() == b.to_string(),
+            _ => a == b,
+        }
+    }
+    
+    /// 型IDの型が `expected` と同じ型か（`same_type` と同じく名前でも比べる）
+    fn matches_type(&self, id: TypeId, expected: &Type) -> bool {
+        self.module
+            .get_type(id)
+            .is_none_or(|ty| ty.kind == expected.kind || ty.to_string() == expected.to_string())
+    }
+    
+    /// 型IDの型が `predicate` を満たすか（登録されていない型は満たすとみなす）
+    fn has_type(&self, id: TypeId, predicate: impl Fn(&Type) -> bool) -> bool {
+        self.module.get_type(id).is_none_or(predicate)
+    }
+    
+    /// タプル型・構造体型の要素の型（どちらでもなければ `None`）
+    fn tuple_elements(&self, id: TypeId) -> Option<Vec<&'a Type>> {
+        self.module.get_type(id)?.field_types()
+    }
+    
+    fn type_name(&self, id: TypeId) -> String {
+        self.module.get_type(id).map_or_else(|| id.to_string(), ToString::to_string)
+    }
+}
+
+fn is_control_instruction(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Return { .. } | Instruction::Branch { .. } | Instruction::BranchCond { .. })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::{FunctionId, Hotness, Literal};
+    
+    /// `if` で分岐し、合流ブロックのパラメータで値を受け取る関数
+    fn branching_module() -> (Module, BlockId) {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "pick", func_type, int);
+        let x = function.add_parameter("x", int);
+        let join = function.create_block();
+        let value = function.create_register(int);
+        function.get_block_mut(join).unwrap().add_parameter(value, int);
+        function.get_block_mut(join).unwrap().set_terminator(Terminator::Return {
+            value: Some(Operand::Register(value)),
+        });
+        let entry = function.entry_block;
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::BranchCond {
+            condition: Operand::Register(x),
+            true_target: join,
+            true_args: vec![Operand::Register(x)],
+            false_target: join,
+            false_args: vec![Operand::Literal(Literal::Int(0))],
+        });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+        (module, join)
+    }
+    
+    #[test]
+    fn test_valid_module() {
+        let (module, _) = branching_module();
+        assert!(verify(&module).is_ok());
+    }
+    
+    #[test]
+    fn test_broken_modules() {
+        // 分岐先が消えたブロックを参照している
+        let (mut module, join) = branching_module();
+        module.functions.get_mut(&FunctionId(0)).unwrap().blocks.remove(&join);
+        let error = verify(&module).unwrap_err().to_string();
+        assert!(error.contains("関数 'pick': block_0: 分岐先 block_1 が存在しません"), "{}", error);
+        
+        // 終了命令の後に制御命令がある
+        let (mut module, join) = branching_module();
+        let function = module.functions.get_mut(&FunctionId(0)).unwrap();
+        function.add_instruction(join, Instruction::Return { value: None });
+        assert!(verify(&module).unwrap_err().to_string().contains("ブロックの途中にあります"));
+        
+        // 分岐先のパラメータと引数の型が一致しない
+        let (mut module, join) = branching_module();
+        let float = module.add_type(Type::float());
+        let function = module.functions.get_mut(&FunctionId(0)).unwrap();
+        let y = function.create_register(float);
+        function.add_instruction(function.entry_block, Instruction::Cast {
+            value: Operand::Literal(Literal::Int(1)),
+            target_type: float,
+            result: y,
+        });
+        let entry = function.entry_block;
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Branch {
+            target: join,
+            args: vec![Operand::Register(y)],
+        });
+        let error = verify(&module).unwrap_err().to_string();
+        assert!(error.contains("パラメータは Int 型ですが、Float 型の引数が渡されています"), "{}", error);
+        
+        // エントリーポイントが存在しない
+        let (mut module, _) = branching_module();
+        module.functions.clear();
+        let error = verify_after(&module, "関数インライン化").unwrap_err().to_string();
+        assert!(error.contains("関数インライン化の後のEIRの検証に失敗しました（1件）"), "{}", error);
+        assert!(error.contains("エントリーポイントの関数"));
+        
+        // 取り除いた命令の属性が残っている
+        let (mut module, _) = branching_module();
+        let function = module.functions.get_mut(&FunctionId(0)).unwrap();
+        function.instruction_attributes_mut(InstructionId(99)).hotness = Hotness::Cold;
+        let error = verify(&module).unwrap_err().to_string();
+        assert!(error.contains("属性が存在しない命令 instr_99 を参照しています"), "{}", error);
+    }
+    
+    #[test]
+    fn test_tuple_instructions() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let float = module.add_type(Type::float());
+        let pair_type = Type::tuple(vec![Type::int(), Type::float()]);
+        let pair = module.add_type(pair_type.clone());
+        let func_type = module.add_type(Type::function(vec![Type::int()], pair_type));
+        let mut function = Function::new(FunctionId(0), "divmod", func_type, pair);
+        let x = function.add_parameter("x", int);
+        let y = function.create_register(float);
+        let tuple = function.create_register(pair);
+        let first = function.create_register(int);
+        let entry = function.entry_block;
+        function.add_instruction(entry, Instruction::Cast { value: Operand::Register(x), target_type: float, result: y });
+        function.add_instruction(entry, Instruction::Tuple { elements: vec![Operand::Register(x), Operand::Register(y)], result: tuple });
+        function.add_instruction(entry, Instruction::Extract { tuple: Operand::Register(tuple), index: 0, result: first });
+        function.get_block_mut(entry).unwrap().set_terminator(Terminator::Return { value: Some(Operand::Register(tuple)) });
+        let id = module.add_function(function);
+        module.set_entry_point(id);
+        assert!(verify(&module).is_ok());
+        
+        // 要素の型が入れ替わっている
+        let function = module.functions.get_mut(&FunctionId(0)).unwrap();
+        let (_, instruction) = &mut function.get_block_mut(entry).unwrap().instructions[1];
+        *instruction = Instruction::Tuple { elements: vec![Operand::Register(y), Operand::Register(x)], result: tuple };
+        let error = verify(&module).unwrap_err().to_string();
+        assert!(error.contains("0番目の要素は Int 型ですが、Float 型の値が渡されています"), "{}", error);
+        
+        // 範囲外の要素を取り出している
+        let function = module.functions.get_mut(&FunctionId(0)).unwrap();
+        let (_, instruction) = &mut function.get_block_mut(entry).unwrap().instructions[2];
+        *instruction = Instruction::Extract { tuple: Operand::Register(tuple), index: 2, result: first };
+        let error = verify(&module).unwrap_err().to_string();
+        assert!(error.contains("添字 2 が要素2個のタプルの範囲外です"), "{}", error);
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io;
+use std::path::Path;
+
+use serde::{Serialize, Serializer};
+
+use crate::core::eir::Module;
+use crate::core::types::Type;
+use crate::core::{EidosError, Result};
+
+/// 事前にコンパイルしたEIRモジュールのファイルの拡張子
+pub const EXTENSION: &str = "eirc";
+
+/// `.eirc` ファイルの先頭に置くマジックナンバー
+pub const MAGIC: &[u8; 4] = b"EIRC";
+
+/// `.eirc` の形式のバージョン
+///
+/// EIRの命令や型の定義を変えて、以前のファイルを正しく読めなくなったら上げる。
+pub const FORMAT_VERSION: u32 = 2;
+
+/// ヘッダーの長さ（マジックナンバーと、リトルエンディアンの形式のバージョン）
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+/// EIRモジュールを `.eirc` のバイト列に変換
+///
+/// 本体は bincode で直列化する。`HashMap` と `HashSet` はキーの順に並べて書き出すので、
+/// 同じモジュールからは常に同じバイト列ができる。
+pub fn encode(module: &Module) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bincode::serialize_into(&mut bytes, module)
+        .map_err(|e| EidosError::CodeGen(format!("EIRモジュール '{}' を直列化できません: {}", module.name, e)))?;
+    Ok(bytes)
+}
+
+/// `.eirc` のバイト列からEIRモジュールを復元
+///
+/// 読み込んだ型のIDは書き出したときのままなので、この後に作る型とIDが重ならないようにする。
+pub fn decode(bytes: &[u8]) -> Result<Module> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid_data("EIRモジュールのファイル（.eirc）ではありません".to_string()));
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+    let version = u32::from_le_bytes(version);
+    if version != FORMAT_VERSION {
+        return Err(invalid_data(format!(
+            ".eirc の形式のバージョン {} には対応していません（対応しているのは {}）。元のソースから作り直してください",
+            version, FORMAT_VERSION
+        )));
+    }
+    
+    let module: Module = bincode::deserialize(&bytes[HEADER_LEN..])
+        .map_err(|e| invalid_data(format!("EIRモジュールを読み込めません: {}", e)))?;
+    if let Some(max) = module.types.keys().max() {
+        Type::reserve_ids(*max);
+    }
+    Ok(module)
+}
+
+/// `.eirc` ファイルを読み込む
+pub fn read(path: &Path) -> Result<Module> {
+    decode(&std::fs::read(path)?)
+}
+
+fn invalid_data(message: String) -> EidosError {
+    EidosError::IO(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+/// `HashMap` をキーの順に直列化する（`#[serde(serialize_with = "eirc::sorted_map")]`）
+pub fn sorted_map<K: Ord + Serialize, V: Serialize, S: Serializer>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+/// `HashSet` を要素の順に直列化する（`#[serde(serialize_with = "eirc::sorted_set")]`）
+pub fn sorted_set<T: Ord + Serialize, S: Serializer>(set: &HashSet<T>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(set.iter().collect::<BTreeSet<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir_builder::build_test_module;
+    
+    fn build(source: &str) -> Module {
+        build_test_module("test", source).unwrap()
+    }
+    
+    #[test]
+    fn test_round_trip() {
+        let module = build(
+            "static mut COUNT: Int = 0;
+             #[export] fn bump(n: Int) -> Int { unsafe { COUNT += n; COUNT } }
+             fn half(x: Float) -> Float { x / 2.0 }
+             println(bump(2), half(3.0));",
+        );
+        let bytes = encode(&module).unwrap();
+        assert_eq!(&bytes[..4], MAGIC);
+        
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.to_string(), module.to_string());
+        assert_eq!(decoded.entry_point, module.entry_point);
+        let bump = decoded.get_function_by_name("bump").unwrap();
+        assert!(bump.attributes.tags.contains("export"));
+        assert!(bump.location.is_some());
+        // 書き出しの順序は `HashMap` の走査順によらない
+        assert_eq!(encode(&decoded).unwrap(), bytes);
+        
+        // 読み込んだ後に作る型は、読み込んだ型とIDが重ならない
+        let max = decoded.types.keys().max().copied().unwrap();
+        assert!(Type::int().id > max);
+    }
+    
+    #[test]
+    fn test_rejects_other_files() {
+        let bytes = encode(&build("println(1);")).unwrap();
+        
+        let error = decode(b"module main").unwrap_err();
+        assert!(error.to_string().contains("ファイル（.eirc）ではありません"), "{}", error);
+        
+        let mut newer = bytes.clone();
+        newer[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        let error = decode(&newer).unwrap_err();
+        assert!(error.to_string().contains("バージョン"), "{}", error);
+        
+        let error = decode(&bytes[..bytes.len() / 2]).unwrap_err();
+        assert!(error.to_string().contains("読み込めません"), "{}", error);
+    }
+}
@@ -2,75 +2,169 @@ use std::path::PathBuf;
 use thiserror::Error;
 use miette::{Diagnostic, SourceSpan, MietteError, Report};
 use log::error;
+use serde::{Deserialize, Serialize};
+
+use super::error_codes::{ErrorCode, ExitCode};
+use super::source_map::Span;
 
 /// Eidos言語の処理中に発生する可能性のあるすべてのエラー
+///
+/// 各バリアントには安定したエラーコードが割り当てられており、`eidos explain <コード>` で詳しい説明を表示できる。
+/// コードの対応は `code()` と `error_codes` モジュールを参照。
 #[derive(Error, Debug, Diagnostic)]
 pub enum EidosError {
-    #[error("字句解析エラー: {0}")]
-    #[diagnostic(code(eidos::lexer))]
-    LexerError(String),
-
-    #[error("構文解析エラー: {0}")]
-    #[diagnostic(code(eidos::parser))]
-    ParserError(String),
+    #[error("字句解析エラー: {message} ({}:{line}:{column})", .file.display())]
+    #[diagnostic(code(E0001))]
+    Lexer {
+        message: String,
+        file: PathBuf,
+        line: usize,
+        column: usize,
+    },
 
-    #[error("型エラー: {0}")]
-    #[diagnostic(code(eidos::type_checker))]
-    TypeError(String),
+    #[error("構文解析エラー: {message} ({}:{line}:{column})", .file.display())]
+    #[diagnostic(code(E0002))]
+    Parser {
+        message: String,
+        file: PathBuf,
+        line: usize,
+        column: usize,
+    },
 
-    #[error("名前解決エラー: {0}")]
-    #[diagnostic(code(eidos::name_resolver))]
-    NameResolutionError(String),
+    #[error("意味解析エラー: {message} ({}:{line}:{column})", .file.display())]
+    #[diagnostic(code(E0003))]
+    Semantic {
+        message: String,
+        file: PathBuf,
+        line: usize,
+        column: usize,
+    },
 
-    #[error("意味解析エラー: {0}")]
-    #[diagnostic(code(eidos::semantic_analyzer))]
-    SemanticError(String),
+    #[error("型エラー: {message} ({})", .location.to_string())]
+    #[diagnostic(code(E0004))]
+    Type {
+        message: String,
+        location: SourceLocation,
+    },
 
-    #[error("バックエンドエラー: {0}")]
-    #[diagnostic(code(eidos::backend))]
-    BackendError(String),
+    #[error("DSLエラー: {message}")]
+    #[diagnostic(code(E0005))]
+    DSL {
+        message: String,
+        dsl_name: String,
+    },
 
-    #[error("IOエラー: {0}")]
-    #[diagnostic(code(eidos::io))]
-    IOError(#[from] std::io::Error),
+    #[error("最適化エラー: {0}")]
+    #[diagnostic(code(E0006))]
+    Optimization(String),
 
-    #[error("DSLエラー: {0}")]
-    #[diagnostic(code(eidos::dsl))]
-    DSLError(String),
+    #[error("コード生成エラー: {0}")]
+    #[diagnostic(code(E0007))]
+    CodeGen(String),
 
-    #[error("内部エラー: {0}")]
-    #[diagnostic(code(eidos::internal))]
-    InternalError(String),
+    #[error("バックエンドエラー: {0}")]
+    #[diagnostic(code(E0008))]
+    BackendError(String),
     
     #[error("実行時エラー: {0}")]
-    #[diagnostic(code(eidos::runtime))]
-    RuntimeError(String),
+    #[diagnostic(code(E0009))]
+    Runtime(String),
+    
+    #[error("IOエラー: {0}")]
+    #[diagnostic(code(E0010))]
+    IO(#[from] std::io::Error),
     
     #[error("機能未実装: {0}")]
-    #[diagnostic(code(eidos::not_implemented))]
+    #[diagnostic(code(E0011))]
     NotImplemented(String),
     
-    #[error("環境エラー: {0}")]
-    #[diagnostic(code(eidos::environment))]
-    EnvironmentError(String),
+    #[error("内部エラー: {0}")]
+    #[diagnostic(code(E0012))]
+    Internal(String),
     
     #[error("複合エラー: 複数の問題が検出されました")]
-    #[diagnostic(code(eidos::multiple))]
+    #[diagnostic(code(E0013))]
     MultipleErrors(Vec<Box<EidosError>>),
 }
 
+impl EidosError {
+    /// このエラーのエラーコード
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Lexer { .. } => ErrorCode::E0001,
+            Self::Parser { .. } => ErrorCode::E0002,
+            Self::Semantic { .. } => ErrorCode::E0003,
+            Self::Type { .. } => ErrorCode::E0004,
+            Self::DSL { .. } => ErrorCode::E0005,
+            Self::Optimization(_) => ErrorCode::E0006,
+            Self::CodeGen(_) => ErrorCode::E0007,
+            Self::BackendError(_) => ErrorCode::E0008,
+            Self::Runtime(_) => ErrorCode::E0009,
+            Self::IO(_) => ErrorCode::E0010,
+            Self::NotImplemented(_) => ErrorCode::E0011,
+            Self::Internal(_) => ErrorCode::E0012,
+            Self::MultipleErrors(_) => ErrorCode::E0013,
+        }
+    }
+    
+    /// このエラーで終了するときの終了コード（複合エラーは最も重大なもの）
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::MultipleErrors(errors) => errors.iter().map(|error| error.exit_code()).max().unwrap_or(ExitCode::ProgramError),
+            error => error.code().exit_code(),
+        }
+    }
+}
+
+/// `std::io::Error` は複製できないため、IOエラーは種類とメッセージが同じエラーとして複製する
+impl Clone for EidosError {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Lexer { message, file, line, column } => Self::Lexer { message: message.clone(), file: file.clone(), line: *line, column: *column },
+            Self::Parser { message, file, line, column } => Self::Parser { message: message.clone(), file: file.clone(), line: *line, column: *column },
+            Self::Semantic { message, file, line, column } => Self::Semantic { message: message.clone(), file: file.clone(), line: *line, column: *column },
+            Self::Type { message, location } => Self::Type { message: message.clone(), location: location.clone() },
+            Self::DSL { message, dsl_name } => Self::DSL { message: message.clone(), dsl_name: dsl_name.clone() },
+            Self::Optimization(message) => Self::Optimization(message.clone()),
+            Self::CodeGen(message) => Self::CodeGen(message.clone()),
+            Self::BackendError(message) => Self::BackendError(message.clone()),
+            Self::Runtime(message) => Self::Runtime(message.clone()),
+            Self::IO(error) => Self::IO(std::io::Error::new(error.kind(), error.to_string())),
+            Self::NotImplemented(message) => Self::NotImplemented(message.clone()),
+            Self::Internal(message) => Self::Internal(message.clone()),
+            Self::MultipleErrors(errors) => Self::MultipleErrors(errors.clone()),
+        }
+    }
+}
+
+/// 診断を表示し終えたコマンドの失敗
+///
+/// `check` のように見つかった問題を自分で表示するコマンドは、問題の数などのまとめと終了コードだけを
+/// この型で返す。
+#[derive(Error, Debug)]
+#[error("{message}")]
+pub struct CommandFailed {
+    pub message: String,
+    pub exit_code: ExitCode,
+}
+
 /// エラー位置情報
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SourceLocation {
     pub file: PathBuf,
     pub line: usize,
     pub column: usize,
     pub length: usize,
+    /// ソースマップ全体での範囲（`SourceMap` に登録したファイルを字句解析したときだけ持つ）
+    ///
+    /// 位置はコンパイルごとに変わるので、`.eirc` などには書き出さない。
+    #[serde(skip)]
+    pub span: Option<Span>,
 }
 
 impl SourceLocation {
     pub fn new(file: PathBuf, line: usize, column: usize, length: usize) -> Self {
-        Self { file, line, column, length }
+        Self { file, line, column, length, span: None }
     }
     
     pub fn unknown() -> Self {
@@ -79,9 +173,16 @@ impl SourceLocation {
             line: 0,
             column: 0,
             length: 0,
+            span: None,
         }
     }
     
+    /// ソースマップ全体での範囲を付ける
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+    
     /// ファイル名と行番号を含む文字列表現
     pub fn to_string(&self) -> String {
         format!("{}:{}:{}", 
@@ -204,10 +305,106 @@ impl ErrorCollector {
     pub fn all_errors(&self) -> &[Box<EidosError>] {
         &self.errors
     }
+    
+    /// すべてのエラーを、複合エラーを展開して返す
+    pub fn into_errors(self) -> Vec<EidosError> {
+        let mut errors = Vec::new();
+        let mut pending: Vec<EidosError> = self.errors.into_iter().rev().map(|error| *error).collect();
+        while let Some(error) = pending.pop() {
+            match error {
+                EidosError::MultipleErrors(inner) => pending.extend(inner.into_iter().rev().map(|error| *error)),
+                error => errors.push(error),
+            }
+        }
+        errors
+    }
+}
+
+/// 提案する候補の最大数
+const MAX_SUGGESTIONS: usize = 3;
+
+/// 解決できなかった名前 `name` に最も近い候補を返す（「もしかして」の提案）
+///
+/// 近さは大文字と小文字を区別しない編集距離（Levenshtein距離）で測る。名前の長さの3分の1（最低1）までの
+/// 距離の候補のうち、最も近いものを名前の順に返す。`math::sqrt` のような修飾名は、モジュール名の誤り
+/// （`mth::sqrt`）も関数名の誤り（`math::sqr`）も同じように扱う。
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let lowered = name.to_lowercase();
+    let limit = (lowered.chars().count() / 3).max(1);
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance = edit_distance(&lowered, &candidate.to_lowercase());
+            (distance <= limit && candidate != name).then_some((distance, candidate))
+        })
+        .collect();
+    scored.sort();
+    scored.dedup_by_key(|(_, candidate)| *candidate);
+    // 最も近い候補だけを提案する
+    let best = scored.first().map_or(0, |(distance, _)| *distance);
+    scored
+        .into_iter()
+        .take_while(|(distance, _)| *distance == best)
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
 }
 
+/// 診断のメッセージに添える提案（候補がなければ空文字列）
+///
+/// `format!("未定義の識別子: {}{}", name, did_you_mean(name, candidates))` のように使う。
+pub fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    let suggestions = suggest(name, candidates);
+    if suggestions.is_empty() {
+        return String::new();
+    }
+    let quoted: Vec<String> = suggestions.iter().map(|candidate| format!("'{}'", candidate)).collect();
+    format!("（もしかして: {}）", quoted.join("、"))
+}
+
+/// 2つの文字列の編集距離（挿入・削除・置換の最小回数）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Result型のエイリアス
+pub type Result<T> = std::result::Result<T, EidosError>;
+
 /// Result型のエイリアス
-pub type EidosResult<T> = Result<T, EidosError>;
+pub type EidosResult<T> = Result<T>;
 
 /// ソース位置情報付きのResult型
-pub type SourceResult<T> = Result<T, SourceError>; 
\ No newline at end of file
+pub type SourceResult<T> = std::result::Result<T, SourceError>; 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_suggest_nearest_names() {
+        assert_eq!(edit_distance("facorial", "factorial"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        
+        let names = ["factorial", "fibonacci", "format", "main"];
+        assert_eq!(suggest("facorial", names), vec!["factorial"]);
+        assert_eq!(suggest("Factorial", names), vec!["factorial"]);
+        assert!(suggest("zzz", names).is_empty());
+        // 同じ名前は提案しない
+        assert!(suggest("main", names).is_empty());
+        
+        let functions = ["math::sqrt", "math::abs", "string::split", "math::sqrt"];
+        assert_eq!(suggest("mth::sqrt", functions), vec!["math::sqrt"]);
+        assert_eq!(did_you_mean("math::sqr", functions), "（もしかして: 'math::sqrt'）");
+        assert_eq!(did_you_mean("x", functions), "");
+    }
+}
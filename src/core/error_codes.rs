@@ -0,0 +1,277 @@
+use std::fmt;
+
+/// エラーコード
+///
+/// 一度割り当てたコードは意味を変えず、廃止しても番号を再利用しない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorCode {
+    E0001,
+    E0002,
+    E0003,
+    E0004,
+    E0005,
+    E0006,
+    E0007,
+    E0008,
+    E0009,
+    E0010,
+    E0011,
+    E0012,
+    E0013,
+}
+
+/// `eidos explain` で表示するエラーコードの説明
+#[derive(Debug, Clone, Copy)]
+pub struct Explanation {
+    /// 一行の要約
+    pub title: &'static str,
+    /// 詳しい説明
+    pub description: &'static str,
+    /// このエラーになるコードの例（ソースコードで説明できない場合は空）
+    pub example: &'static str,
+    /// 修正方法
+    pub fix: &'static str,
+}
+
+impl ErrorCode {
+    /// すべてのエラーコード（番号順）
+    pub const ALL: &'static [ErrorCode] = &[
+        Self::E0001,
+        Self::E0002,
+        Self::E0003,
+        Self::E0004,
+        Self::E0005,
+        Self::E0006,
+        Self::E0007,
+        Self::E0008,
+        Self::E0009,
+        Self::E0010,
+        Self::E0011,
+        Self::E0012,
+        Self::E0013,
+    ];
+    
+    /// `E0001` 形式の文字列
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::E0001 => "E0001",
+            Self::E0002 => "E0002",
+            Self::E0003 => "E0003",
+            Self::E0004 => "E0004",
+            Self::E0005 => "E0005",
+            Self::E0006 => "E0006",
+            Self::E0007 => "E0007",
+            Self::E0008 => "E0008",
+            Self::E0009 => "E0009",
+            Self::E0010 => "E0010",
+            Self::E0011 => "E0011",
+            Self::E0012 => "E0012",
+            Self::E0013 => "E0013",
+        }
+    }
+    
+    /// 文字列からエラーコードを取得（`E0004`、`e0004`、`0004`、`4` のいずれでもよい）
+    pub fn parse(code: &str) -> Option<Self> {
+        let code = code.trim();
+        let digits = code.strip_prefix(['E', 'e']).unwrap_or(code);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let number: u32 = digits.parse().ok()?;
+        Self::ALL.iter().copied().find(|c| c.number() == number)
+    }
+    
+    /// コードの番号部分
+    pub fn number(&self) -> u32 {
+        self.as_str()[1..].parse().unwrap_or(0)
+    }
+    
+    /// このエラーで終了するときの終了コード
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::E0001 | Self::E0002 | Self::E0003 | Self::E0004 | Self::E0005 | Self::E0013 => ExitCode::ProgramError,
+            Self::E0006 | Self::E0012 => ExitCode::InternalError,
+            Self::E0007 | Self::E0008 | Self::E0009 | Self::E0010 | Self::E0011 => ExitCode::Failure,
+        }
+    }
+    
+    /// このコードの説明
+    pub fn explanation(&self) -> Explanation {
+        match self {
+            Self::E0001 => Explanation {
+                title: "字句解析エラー",
+                description: "ソースコードをトークンに分割できませんでした。\
+                    閉じられていない文字列リテラル、不明なエスケープシーケンス、言語で使えない文字などが原因です。",
+                example: "fn main() {\n    println(\"閉じられていない文字列);\n}",
+                fix: "エラー位置の文字列リテラルを閉じるか、使えない文字を取り除いてください。\
+                    使えるエスケープシーケンスは `\\n`、`\\t`、`\\r`、`\\\\`、`\\\"` などです。",
+            },
+            Self::E0002 => Explanation {
+                title: "構文解析エラー",
+                description: "トークン列が言語の文法に合いませんでした。\
+                    括弧の対応が取れていない、式が来るべき位置に別のものがある、文の区切りがないなどが原因です。",
+                example: "fn add(a: Int, b: Int) -> Int {\n    a +\n}",
+                fix: "エラー位置の前後で括弧や演算子の対応を確認してください。\
+                    属性（`#[...]`）は関数定義にだけ付けられます。",
+            },
+            Self::E0003 => Explanation {
+                title: "意味解析エラー",
+                description: "構文は正しいものの、名前の解決やスコープの規則に反しています。\
+                    同じスコープでの名前の重複、存在しないモジュールやシンボルの参照、未定義の変数の使用などが原因です。",
+                example: "fn main() -> Int {\n    undefined_variable\n}",
+                fix: "参照している名前のつづりと、定義されているスコープを確認してください。\
+                    別モジュールのシンボルは `module::name` の形で参照します。",
+            },
+            Self::E0004 => Explanation {
+                title: "型エラー",
+                description: "式の型が期待される型と一致しないか、型を推論できませんでした。\
+                    演算子のオペランドの型の不一致、関数の引数や戻り値の型の誤り、\
+                    `const fn` からの定数でない関数の呼び出し、純粋な関数からの副作用のある関数の呼び出しなども含みます。",
+                example: "fn add(a: Int, b: Int) -> Int {\n    a + \"string\"\n}",
+                fix: "エラーメッセージの「期待」と「実際」の型を比べ、値を変換するか型注釈を直してください。\
+                    副作用のある関数を呼ぶ関数は `effect fn` で宣言します。",
+            },
+            Self::E0005 => Explanation {
+                title: "DSLエラー",
+                description: "ユーザー定義のDSLの定義、またはDSLで書かれたコードの処理に失敗しました。\
+                    入力がDSLのどの規則にも一致しない場合や、展開結果が空になる場合などです。",
+                example: "syntax sql {\n    rule SELECT * FROM users => all_users();\n}\n\nfn main() {\n    sql { DELETE FROM users }\n}",
+                fix: "DSLの規則と入力を見比べてください。\
+                    `eidos build --trace-macros` で展開の過程を表示できます。",
+            },
+            Self::E0006 => Explanation {
+                title: "最適化エラー",
+                description: "EIRの最適化パスが想定外の形の中間表現に出会いました。\
+                    通常はコンパイラの不具合です。",
+                example: "",
+                fix: "`--opt-level 0` で最適化を無効にすると回避できる場合があります。\
+                    再現するソースコードを添えて不具合を報告してください。",
+            },
+            Self::E0007 => Explanation {
+                title: "コード生成エラー",
+                description: "EIRからターゲットのコードを生成できませんでした。\
+                    バックエンドが対応していない型や命令、型情報の欠けた関数などが原因です。",
+                example: "",
+                fix: "別の出力形式（`--emit c` など）で生成できるか試してください。\
+                    特定の構文だけで起こる場合は、その構文を使わない書き方に変えるか不具合を報告してください。",
+            },
+            Self::E0008 => Explanation {
+                title: "バックエンドエラー",
+                description: "LLVMやJITなどのバックエンドの初期化、または実行に失敗しました。\
+                    ホスト環境がJITに対応していない場合や、コンパイルされていない関数を呼び出した場合などです。",
+                example: "",
+                fix: "LLVM 16がインストールされているか、ターゲットがホスト環境に対応しているかを確認してください。",
+            },
+            Self::E0009 => Explanation {
+                title: "実行時エラー",
+                description: "プログラムの実行中、または標準ライブラリ関数の呼び出し中にエラーが起きました。\
+                    引数の型や個数の誤り、存在しないファイルやコレクションへのアクセス、0除算などです。",
+                example: "fn main() -> Int {\n    10 / 0\n}",
+                fix: "エラーメッセージに示された関数の引数と、実行時の値を確認してください。",
+            },
+            Self::E0010 => Explanation {
+                title: "入出力エラー",
+                description: "ファイルの読み書きなどの入出力に失敗しました。",
+                example: "",
+                fix: "ファイルのパスが正しいか、読み書きの権限があるかを確認してください。",
+            },
+            Self::E0011 => Explanation {
+                title: "未実装の機能",
+                description: "言語仕様にはあるものの、まだコンパイラが対応していない機能を使っています。",
+                example: "",
+                fix: "エラーメッセージに示された機能を使わない書き方に変えてください。",
+            },
+            Self::E0012 => Explanation {
+                title: "内部エラー",
+                description: "コンパイラ内部の整合性が崩れました。\
+                    入力のプログラムではなくコンパイラの不具合です。",
+                example: "",
+                fix: "再現するソースコードとコンパイラのバージョンを添えて不具合を報告してください。",
+            },
+            Self::E0013 => Explanation {
+                title: "複数のエラー",
+                description: "1回のコンパイルで複数のエラーが見つかりました。\
+                    それぞれのエラーに個別のエラーコードが付いています。",
+                example: "",
+                fix: "一覧の先頭のエラーから順に直してください。後のエラーは先のエラーの影響で起きていることがあります。",
+            },
+        }
+    }
+}
+
+/// コマンドの終了コード
+///
+/// スクリプトから失敗の種類を区別できるよう、すべてのサブコマンドで同じ値を使う。
+/// 後のものほど重大で、複数のエラーがあれば最も重大なものの値で終了する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExitCode {
+    /// 成功
+    Success = 0,
+    /// 入力のプログラムにエラーがある（字句・構文・意味・型・DSLのエラー）
+    ProgramError = 1,
+    /// コマンドラインの誤り（clap が報告する）
+    Usage = 2,
+    /// 入出力、バックエンド、実行時のエラーなど、プログラムの誤り以外による失敗
+    Failure = 3,
+    /// コンパイラ内部のエラー（Rustのパニックによる異常終了と同じ値）
+    InternalError = 101,
+}
+
+impl ExitCode {
+    /// プロセスの終了コードの値
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+    
+    /// `--json` で出力する名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::ProgramError => "program_error",
+            Self::Usage => "usage",
+            Self::Failure => "failure",
+            Self::InternalError => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_error_code() {
+        assert_eq!(ErrorCode::parse("E0004"), Some(ErrorCode::E0004));
+        assert_eq!(ErrorCode::parse("e0004"), Some(ErrorCode::E0004));
+        assert_eq!(ErrorCode::parse("0004"), Some(ErrorCode::E0004));
+        assert_eq!(ErrorCode::parse("4"), Some(ErrorCode::E0004));
+        assert_eq!(ErrorCode::parse("E9999"), None);
+        assert_eq!(ErrorCode::parse("E"), None);
+        assert_eq!(ErrorCode::parse("type"), None);
+    }
+    
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(ErrorCode::E0004.exit_code(), ExitCode::ProgramError);
+        assert_eq!(ErrorCode::E0010.exit_code(), ExitCode::Failure);
+        assert_eq!(ErrorCode::E0012.exit_code().code(), 101);
+        assert!(ExitCode::InternalError > ExitCode::Failure && ExitCode::Failure > ExitCode::ProgramError);
+    }
+    
+    #[test]
+    fn test_codes_are_sorted_and_unique() {
+        for pair in ErrorCode::ALL.windows(2) {
+            assert!(pair[0].number() < pair[1].number());
+        }
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::parse(code.as_str()), Some(*code));
+            assert!(!code.explanation().title.is_empty());
+        }
+    }
+}
@@ -0,0 +1,798 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::analysis::{Cfg, DominatorTree, Loop, LoopForest};
+use crate::core::eir::{BinaryOp, BlockId, Function, Instruction, InstructionId, Literal, Module, Operand, RegisterId, Terminator};
+use crate::core::range_analysis::{RangeAnalysis, ValueRange};
+use crate::core::types::{TypeId, TypeKind};
+
+/// 帰納変数の最適化による変換の件数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InductionStats {
+    /// 反復ごとに加算する新しい帰納変数に置き換えた `i * c` と `i * c + b`
+    pub strength_reduced: usize,
+    /// シフトと加減算に置き換えた定数による乗算
+    pub multiplications: usize,
+    /// 取り除いた使われない帰納変数
+    pub dead: usize,
+}
+
+/// 整数型の性質
+#[derive(Debug, Clone, Copy)]
+struct IntegerType {
+    bits: u32,
+    /// オーバーフローが未定義動作になる型か（`Int` はCで符号付きのまま計算する）
+    exact: bool,
+}
+
+/// 書き換えの対象にするループの形（ヘッダーへはプリヘッダーとただ1つのラッチからだけ入る）
+#[derive(Debug, Clone)]
+pub(crate) struct LoopShape {
+    pub header: BlockId,
+    pub preheader: BlockId,
+    pub latch: BlockId,
+    /// ループに属するブロック（ID順）
+    pub body: Vec<BlockId>,
+}
+
+/// 基本帰納変数（ヘッダーのパラメータで、反復ごとに定数 `step` ずつ増えるもの）
+#[derive(Debug, Clone)]
+pub(crate) struct BasicIv {
+    pub param: RegisterId,
+    pub ty: TypeId,
+    /// プリヘッダーから渡す初期値
+    pub init: Operand,
+    pub step: i64,
+    /// ラッチからヘッダーへ渡す、次の反復の値（`param + step`）
+    pub next: RegisterId,
+}
+
+/// 新しい帰納変数に置き換える命令の結果（`iv * scale + offset`）
+#[derive(Debug, Clone)]
+struct Candidate {
+    result: RegisterId,
+    iv: usize,
+    scale: i64,
+    offset: Option<Operand>,
+}
+
+/// 帰納変数の最適化
+///
+/// - 反復ごとに定数ずつ増えるループ変数 `i` について、ループ内の `i * c`（`i << k` を含む）と `i * c + b`
+///   （`b` はループ不変）を、プリヘッダーで初期値を計算してラッチで `c * step` ずつ足す新しい帰納変数に置き換える。
+///   配列の添字から求めるアドレスは、ループの中で乗算のない「増えていくポインタ」になる
+/// - ループ内の残りの定数による乗算を、シフトと加減算にする（`x * 8` は `x << 3`、`x * 9` は `(x << 3) + x`）
+/// - 自分の更新にしか使われなくなった帰納変数を取り除く
+///
+/// `Int` の演算はCで符号付きのまま計算するので、値域解析で新しい帰納変数の値がオーバーフローしないと
+/// 分かる場合だけ置き換える。ビット幅を指定した整数型はどちらのバックエンドでも折り返すので検査しない。
+pub fn optimize(module: &mut Module) -> InductionStats {
+    let integers: HashMap<TypeId, IntegerType> = module
+        .types
+        .iter()
+        .filter_map(|(id, ty)| {
+            let integer = match &ty.kind {
+                TypeKind::Int => IntegerType { bits: 64, exact: true },
+                TypeKind::Numeric(numeric) if !numeric.is_float() => IntegerType { bits: numeric.bits(), exact: false },
+                _ => return None,
+            };
+            Some((*id, integer))
+        })
+        .collect();
+    
+    let mut stats = InductionStats::default();
+    let mut ids: Vec<_> = module.functions.keys().copied().collect();
+    ids.sort_by_key(|id| id.0);
+    for id in ids {
+        // 値域は元のレジスタについてだけ引くので、ループを書き換えていっても求め直さなくてよい
+        let analysis = RangeAnalysis::new(module, &module.functions[&id]);
+        let Some(function) = module.functions.get_mut(&id) else {
+            continue;
+        };
+        let cfg = Cfg::new(function);
+        let dominators = DominatorTree::new(&cfg);
+        let loops = LoopForest::new(&cfg, &dominators);
+        for lp in loops.innermost_first() {
+            let Some(shape) = loop_shape(function, &cfg, lp) else {
+                continue;
+            };
+            stats.strength_reduced += reduce_induction_variables(function, &shape, &analysis, &integers);
+            stats.multiplications += expand_multiplications(function, &shape, &analysis, &integers);
+            stats.dead += remove_dead_induction_variables(function, &shape);
+        }
+    }
+    stats
+}
+
+/// 範囲のイテレータの展開
+///
+/// `for` は範囲をスタックスロットに置き、ヘッダーで `next` と同じ手順（範囲を読み、始点と終点を比べ、
+/// 始点を1つ進めて書き戻す）で次の値を取り出す。スロットがそのループでしか使われていなければ、始点を
+/// ヘッダーのパラメータにしてラッチで1ずつ増やし、終点はループ不変の値にする。展開したループの数を返す。
+///
+/// 展開したループのループ変数は、値域解析・ループ融合・帰納変数の最適化が扱える。
+pub fn promote_range_iterators(module: &mut Module) -> usize {
+    module.functions.values_mut().map(promote_iterators).sum()
+}
+
+/// 関数の中の範囲のイテレータを展開し、展開したループの数を返す
+pub(crate) fn promote_iterators(function: &mut Function) -> usize {
+    let mut headers: Vec<BlockId> = function.blocks.keys().copied().collect();
+    headers.sort_by_key(|block| block.0);
+    headers.into_iter().filter(|header| promote_iterator(function, *header)).count()
+}
+
+/// `next` を下げたヘッダーのレジスタ
+struct IteratorHeader {
+    slot: RegisterId,
+    start: RegisterId,
+    end: RegisterId,
+    has_next: RegisterId,
+    next: (InstructionId, RegisterId),
+}
+
+/// ヘッダーの命令が `next` を下げた8つの命令だけなら、そのレジスタ
+fn iterator_header(function: &Function, header: BlockId) -> Option<IteratorHeader> {
+    let block = function.blocks.get(&header)?;
+    let [
+        (_, Instruction::Load { address: Operand::Register(slot), result: current }),
+        (_, Instruction::Extract { tuple: Operand::Register(first), index: 0, result: start }),
+        (_, Instruction::Extract { tuple: Operand::Register(second), index: 1, result: end }),
+        (_, Instruction::BinaryOp { op: BinaryOp::Lt, lhs: Operand::Register(compared), rhs: Operand::Register(bound), result: has_next }),
+        (next_id, Instruction::BinaryOp { op: BinaryOp::Add, lhs: Operand::Register(advanced), rhs: Operand::Literal(Literal::Int(1)), result: next }),
+        (_, Instruction::Select { condition: Operand::Register(condition), true_value: Operand::Register(taken), false_value: Operand::Register(kept), result: selected }),
+        (_, Instruction::Tuple { elements, result: range }),
+        (_, Instruction::Store { address: Operand::Register(stored_slot), value: Operand::Register(stored) }),
+    ] = block.instructions.as_slice()
+    else {
+        return None;
+    };
+    let matches = first == current
+        && second == current
+        && [compared, advanced, kept].iter().all(|reg| *reg == start)
+        && bound == end
+        && condition == has_next
+        && taken == next
+        && matches!(elements.as_slice(), [Operand::Register(value), Operand::Register(limit)] if value == selected && limit == end)
+        && stored_slot == slot
+        && stored == range
+        && matches!(&block.terminator, Some(Terminator::BranchCond { condition: Operand::Register(reg), .. }) if reg == has_next);
+    matches.then_some(IteratorHeader { slot: *slot, start: *start, end: *end, has_next: *has_next, next: (*next_id, *next) })
+}
+
+/// レジスタが命令と終端命令のオペランドに現れる回数
+fn use_counts(function: &Function) -> HashMap<RegisterId, usize> {
+    let mut counts = HashMap::new();
+    for block in function.blocks.values() {
+        let instructions = block.instructions.iter().flat_map(|(_, instruction)| instruction.operands());
+        for operand in instructions.chain(block.terminator.iter().flat_map(|terminator| terminator.operands())) {
+            if let Operand::Register(reg) = operand {
+                *counts.entry(*reg).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+fn promote_iterator(function: &mut Function, header: BlockId) -> bool {
+    let Some(iterator) = iterator_header(function, header) else {
+        return false;
+    };
+    // スロットを使うのは、ループの前の初期値の書き込みとヘッダーの読み書きだけ
+    let counts = use_counts(function);
+    if counts.get(&iterator.slot) != Some(&3) {
+        return false;
+    }
+    let mut preheader = None;
+    let mut latches = Vec::new();
+    for (block_id, block) in &function.blocks {
+        let targets_header = block.terminator.as_ref().is_some_and(|terminator| terminator.successors().contains(&header));
+        if !targets_header {
+            continue;
+        }
+        if !matches!(block.terminator, Some(Terminator::Branch { target, .. }) if target == header) {
+            return false;
+        }
+        let init = block.instructions.iter().find_map(|(_, instruction)| match instruction {
+            Instruction::Store { address: Operand::Register(slot), value } if *slot == iterator.slot => Some(value.clone()),
+            _ => None,
+        });
+        match init {
+            Some(init) if *block_id != header => preheader = Some((*block_id, init)),
+            _ => latches.push(*block_id),
+        }
+    }
+    let Some((preheader, init)) = preheader else {
+        return false;
+    };
+    latches.sort_by_key(|block| block.0);
+    let Some(ty) = function.get_register_type(iterator.start) else {
+        return false;
+    };
+    
+    // 初期値が範囲を作る命令なら両端をそのまま使い、そうでなければループの前で取り出す
+    let range = match &init {
+        Operand::Register(reg) => function.blocks.values().flat_map(|block| &block.instructions).find_map(|(_, instruction)| match instruction {
+            Instruction::Tuple { elements, result } if result == reg && elements.len() == 2 => Some((*reg, elements.clone())),
+            _ => None,
+        }),
+        _ => None,
+    };
+    let mut replacements = HashMap::new();
+    let start = match range {
+        Some((reg, elements)) => {
+            replacements.insert(iterator.end, elements[1].clone());
+            if counts.get(&reg) == Some(&1) {
+                remove_instructions(function, |_, instruction| instruction.defined_register() == Some(reg));
+            }
+            elements[0].clone()
+        }
+        None => {
+            let start = function.create_register(ty);
+            function.add_instruction(preheader, Instruction::Extract { tuple: init.clone(), index: 0, result: start });
+            function.add_instruction(preheader, Instruction::Extract { tuple: init, index: 1, result: iterator.end });
+            Operand::Register(start)
+        }
+    };
+    
+    // ヘッダーには終了条件の比較だけを残し、次の値はラッチごとに求める
+    let (next_id, _) = iterator.next;
+    if let Some(block) = function.blocks.get_mut(&header) {
+        block.instructions.retain(|(id, _)| *id != next_id);
+        block.parameters.push((iterator.start, ty));
+    }
+    let header_ids: HashSet<InstructionId> = function.blocks[&header].instructions.iter().map(|(id, _)| *id).collect();
+    remove_instructions(function, |id, instruction| {
+        let in_header = header_ids.contains(id) && instruction.defined_register() != Some(iterator.has_next);
+        let slot = match instruction {
+            Instruction::Alloca { result, .. } => *result == iterator.slot,
+            Instruction::Store { address: Operand::Register(address), .. } => *address == iterator.slot,
+            _ => false,
+        };
+        in_header || slot
+    });
+    for (index, latch) in latches.iter().enumerate() {
+        let (id, next) = match index {
+            0 => iterator.next,
+            _ => (function.next_instruction_id(), function.create_register(ty)),
+        };
+        let Some(block) = function.blocks.get_mut(latch) else {
+            continue;
+        };
+        let increment = Operand::Literal(Literal::Int(1));
+        block.instructions.push((id, Instruction::BinaryOp { op: BinaryOp::Add, lhs: Operand::Register(iterator.start), rhs: increment, result: next }));
+        if let Some(Terminator::Branch { args, .. }) = block.terminator.as_mut() {
+            args.push(Operand::Register(next));
+        }
+    }
+    if let Some(Terminator::Branch { args, .. }) = function.blocks.get_mut(&preheader).and_then(|block| block.terminator.as_mut()) {
+        args.push(start);
+    }
+    replace_uses(function, &replacements);
+    true
+}
+
+pub(crate) fn loop_shape(function: &Function, cfg: &Cfg, lp: &Loop) -> Option<LoopShape> {
+    let preheader = lp.preheader?;
+    let [latch] = lp.latches[..] else {
+        return None;
+    };
+    let preds: HashSet<BlockId> = cfg.predecessors(lp.header).iter().copied().collect();
+    if preheader == latch || preds != HashSet::from([preheader, latch]) {
+        return None;
+    }
+    if !matches!(function.blocks.get(&preheader)?.terminator, Some(Terminator::Branch { target, .. }) if target == lp.header) {
+        return None;
+    }
+    if edge_args(function.blocks.get(&latch)?.terminator.as_ref()?, lp.header).len() != 1 {
+        return None;
+    }
+    let mut body: Vec<BlockId> = lp.body.iter().copied().collect();
+    body.sort_by_key(|block| block.0);
+    Some(LoopShape { header: lp.header, preheader, latch, body })
+}
+
+/// 終端命令が `target` へ渡す引数（`target` への辺ごと）
+pub(crate) fn edge_args(terminator: &Terminator, target: BlockId) -> Vec<&Vec<Operand>> {
+    match terminator {
+        Terminator::Branch { target: t, args } if *t == target => vec![args],
+        Terminator::BranchCond { true_target, true_args, false_target, false_args, .. } => {
+            let mut edges = Vec::new();
+            if *true_target == target {
+                edges.push(true_args);
+            }
+            if *false_target == target {
+                edges.push(false_args);
+            }
+            edges
+        }
+        Terminator::Switch { default_target, default_args, cases, .. } => std::iter::once((default_target, default_args))
+            .chain(cases.iter().map(|(_, t, args)| (t, args)))
+            .filter(|(t, _)| **t == target)
+            .map(|(_, args)| args)
+            .collect(),
+        Terminator::IndirectCall { return_block, return_args, .. } if *return_block == target => vec![return_args],
+        _ => Vec::new(),
+    }
+}
+
+pub(crate) fn edge_args_mut(terminator: &mut Terminator, target: BlockId) -> Vec<&mut Vec<Operand>> {
+    match terminator {
+        Terminator::Branch { target: t, args } if *t == target => vec![args],
+        Terminator::BranchCond { true_target, true_args, false_target, false_args, .. } => {
+            let mut edges = Vec::new();
+            if *true_target == target {
+                edges.push(true_args);
+            }
+            if *false_target == target {
+                edges.push(false_args);
+            }
+            edges
+        }
+        Terminator::Switch { default_target, default_args, cases, .. } => std::iter::once((&*default_target, default_args))
+            .chain(cases.iter_mut().map(|(_, t, args)| (&*t, args)))
+            .filter(|(t, _)| **t == target)
+            .map(|(_, args)| args)
+            .collect(),
+        Terminator::IndirectCall { return_block, return_args, .. } if *return_block == target => vec![return_args],
+        _ => Vec::new(),
+    }
+}
+
+/// ヘッダーの `index` 番目のパラメータに、プリヘッダーとラッチが渡す引数
+fn incoming(function: &Function, shape: &LoopShape, index: usize) -> Option<(Operand, Operand)> {
+    let argument = |block: BlockId| {
+        let terminator = function.blocks.get(&block)?.terminator.as_ref()?;
+        edge_args(terminator, shape.header).first()?.get(index).cloned()
+    };
+    Some((argument(shape.preheader)?, argument(shape.latch)?))
+}
+
+/// ループ内のブロックで定義されるレジスタと、その命令
+pub(crate) fn loop_definitions<'a>(function: &'a Function, shape: &LoopShape) -> HashMap<RegisterId, &'a Instruction> {
+    let mut definitions = HashMap::new();
+    for block_id in &shape.body {
+        for (_, instruction) in &function.blocks[block_id].instructions {
+            if let Some(reg) = instruction.defined_register() {
+                definitions.insert(reg, instruction);
+            }
+        }
+    }
+    definitions
+}
+
+pub(crate) fn basic_induction_variables(function: &Function, shape: &LoopShape) -> Vec<BasicIv> {
+    let definitions = loop_definitions(function, shape);
+    let mut ivs = Vec::new();
+    for (index, (param, ty)) in function.blocks[&shape.header].parameters.iter().enumerate() {
+        let Some((init, Operand::Register(next))) = incoming(function, shape, index) else {
+            continue;
+        };
+        let step = match definitions.get(&next).copied() {
+            Some(Instruction::BinaryOp { op: BinaryOp::Add, lhs: Operand::Register(reg), rhs: Operand::Literal(Literal::Int(step)), .. })
+            | Some(Instruction::BinaryOp { op: BinaryOp::Add, lhs: Operand::Literal(Literal::Int(step)), rhs: Operand::Register(reg), .. })
+                if reg == param =>
+            {
+                *step
+            }
+            Some(Instruction::BinaryOp { op: BinaryOp::Sub, lhs: Operand::Register(reg), rhs: Operand::Literal(Literal::Int(step)), .. })
+                if reg == param && *step != i64::MIN =>
+            {
+                -*step
+            }
+            _ => continue,
+        };
+        ivs.push(BasicIv { param: *param, ty: *ty, init, step, next });
+    }
+    ivs
+}
+
+/// ループ内の `i * c` と `i * c + b` を新しい帰納変数に置き換え、置き換えた数を返す
+fn reduce_induction_variables(
+    function: &mut Function,
+    shape: &LoopShape,
+    analysis: &RangeAnalysis,
+    integers: &HashMap<TypeId, IntegerType>,
+) -> usize {
+    let ivs = basic_induction_variables(function, shape);
+    if ivs.is_empty() {
+        return 0;
+    }
+    let definitions = loop_definitions(function, shape);
+    let params: HashMap<RegisterId, usize> = ivs.iter().enumerate().map(|(index, iv)| (iv.param, index)).collect();
+    let invariant = |operand: &Operand| match operand {
+        Operand::Literal(Literal::Int(_)) => true,
+        Operand::Register(reg) => !definitions.contains_key(reg) && !function.blocks[&shape.header].parameters.iter().any(|(p, _)| p == reg),
+        _ => false,
+    };
+    
+    // `i * c`・`i << k` を探し、続いてその結果にループ不変の値を足す `i * c + b` を探す
+    let mut candidates = Vec::new();
+    let mut scaled: HashMap<RegisterId, (usize, i64)> = HashMap::new();
+    for block_id in &shape.body {
+        for (_, instruction) in &function.blocks[block_id].instructions {
+            let Instruction::BinaryOp { op, lhs, rhs, result } = instruction else {
+                continue;
+            };
+            let (reg, scale) = match (op, lhs, rhs) {
+                (BinaryOp::Mul, Operand::Register(reg), Operand::Literal(Literal::Int(c)))
+                | (BinaryOp::Mul, Operand::Literal(Literal::Int(c)), Operand::Register(reg)) => (reg, *c),
+                (BinaryOp::Shl, Operand::Register(reg), Operand::Literal(Literal::Int(k))) if (1..63).contains(k) => (reg, 1i64 << k),
+                _ => continue,
+            };
+            let Some(index) = params.get(reg).copied() else {
+                continue;
+            };
+            if scale == 0 || scale == 1 || function.register_types.get(result) != Some(&ivs[index].ty) {
+                continue;
+            }
+            if let (BinaryOp::Shl, Some(integer)) = (op, integers.get(&ivs[index].ty)) {
+                if scale.trailing_zeros() >= integer.bits {
+                    continue;
+                }
+            }
+            scaled.insert(*result, (index, scale));
+            candidates.push(Candidate { result: *result, iv: index, scale, offset: None });
+        }
+    }
+    for block_id in &shape.body {
+        for (_, instruction) in &function.blocks[block_id].instructions {
+            let Instruction::BinaryOp { op: BinaryOp::Add, lhs, rhs, result } = instruction else {
+                continue;
+            };
+            let (reg, offset) = match (lhs, rhs) {
+                (Operand::Register(reg), offset) | (offset, Operand::Register(reg)) if scaled.contains_key(reg) && invariant(offset) => (reg, offset),
+                _ => continue,
+            };
+            let (index, scale) = scaled[reg];
+            if function.register_types.get(result) == Some(&ivs[index].ty) {
+                candidates.push(Candidate { result: *result, iv: index, scale, offset: Some(offset.clone()) });
+            }
+        }
+    }
+    
+    let uses = uses_outside(function, &shape.body);
+    let mut replacements: HashMap<RegisterId, Operand> = HashMap::new();
+    let mut removed: HashSet<RegisterId> = HashSet::new();
+    for candidate in candidates {
+        let iv = &ivs[candidate.iv];
+        let Some(integer) = integers.get(&iv.ty).copied() else {
+            continue;
+        };
+        // ループの外で使う値は最後に計算した反復の値なので、ヘッダーのパラメータでは置き換えられない
+        if uses.contains(&candidate.result) {
+            continue;
+        }
+        let step = if integer.exact {
+            let fits = |range: Option<ValueRange>, offset: Option<ValueRange>| range.and_then(|range| affine_range(range, candidate.scale, offset)).is_some();
+            let offset = match &candidate.offset {
+                Some(offset) => match analysis.operand_range(offset, shape.preheader) {
+                    Some(range) => Some(range),
+                    None => continue,
+                },
+                None => None,
+            };
+            let Some(step) = iv.step.checked_mul(candidate.scale) else {
+                continue;
+            };
+            if !fits(analysis.range(iv.param), offset) || !fits(analysis.range(iv.next), offset) {
+                continue;
+            }
+            step
+        } else {
+            iv.step.wrapping_mul(candidate.scale)
+        };
+        
+        // 初期値はプリヘッダーで計算する（`Int` の定数はその場で畳み込む）
+        let scaled_init = match &iv.init {
+            Operand::Literal(Literal::Int(value)) if integer.exact => Operand::Literal(Literal::Int(value.wrapping_mul(candidate.scale))),
+            init => emit(function, shape.preheader, iv.ty, BinaryOp::Mul, init.clone(), Operand::Literal(Literal::Int(candidate.scale))),
+        };
+        let init = match (&scaled_init, &candidate.offset) {
+            (_, None) => scaled_init,
+            (Operand::Literal(Literal::Int(a)), Some(Operand::Literal(Literal::Int(b)))) if integer.exact => Operand::Literal(Literal::Int(a.wrapping_add(*b))),
+            (_, Some(offset)) => emit(function, shape.preheader, iv.ty, BinaryOp::Add, scaled_init.clone(), offset.clone()),
+        };
+        let param = add_induction_variable(function, shape, iv.ty, init, step);
+        replacements.insert(candidate.result, Operand::Register(param));
+        removed.insert(candidate.result);
+    }
+    
+    if removed.is_empty() {
+        return 0;
+    }
+    remove_instructions(function, |_, instruction| instruction.defined_register().is_some_and(|reg| removed.contains(&reg)));
+    replace_uses(function, &replacements);
+    removed.len()
+}
+
+/// `range * scale + offset` の値域（どこかでオーバーフローすれば `None`）
+fn affine_range(range: ValueRange, scale: i64, offset: Option<ValueRange>) -> Option<ValueRange> {
+    let a = range.min.checked_mul(scale)?;
+    let b = range.max.checked_mul(scale)?;
+    let (mut min, mut max) = (a.min(b), a.max(b));
+    if let Some(offset) = offset {
+        min = min.checked_add(offset.min)?;
+        max = max.checked_add(offset.max)?;
+    }
+    Some(ValueRange::new(min, max))
+}
+
+/// ブロックの末尾（終端命令の前）に二項演算を追加し、結果のレジスタを返す
+fn emit(function: &mut Function, block: BlockId, ty: TypeId, op: BinaryOp, lhs: Operand, rhs: Operand) -> Operand {
+    let result = function.create_register(ty);
+    function.add_instruction(block, Instruction::BinaryOp { op, lhs, rhs, result });
+    Operand::Register(result)
+}
+
+/// ヘッダーに、`init` から始まり反復ごとに `step` ずつ増えるパラメータを追加する
+fn add_induction_variable(function: &mut Function, shape: &LoopShape, ty: TypeId, init: Operand, step: i64) -> RegisterId {
+    let param = function.create_register(ty);
+    if let Some(header) = function.blocks.get_mut(&shape.header) {
+        header.add_parameter(param, ty);
+    }
+    let next = emit(function, shape.latch, ty, BinaryOp::Add, Operand::Register(param), Operand::Literal(Literal::Int(step)));
+    for (block, argument) in [(shape.preheader, init), (shape.latch, next)] {
+        if let Some(terminator) = function.blocks.get_mut(&block).and_then(|block| block.terminator.as_mut()) {
+            for args in edge_args_mut(terminator, shape.header) {
+                args.push(argument.clone());
+            }
+        }
+    }
+    param
+}
+
+/// ループ外のブロックで使われるレジスタ
+fn uses_outside(function: &Function, body: &[BlockId]) -> HashSet<RegisterId> {
+    let mut uses = HashSet::new();
+    for (block_id, block) in &function.blocks {
+        if body.contains(block_id) {
+            continue;
+        }
+        for (_, instruction) in &block.instructions {
+            uses.extend(instruction.used_registers());
+        }
+        if let Some(terminator) = &block.terminator {
+            uses.extend(terminator.operands().into_iter().filter_map(|operand| match operand {
+                Operand::Register(reg) => Some(*reg),
+                _ => None,
+            }));
+        }
+    }
+    uses
+}
+
+pub(crate) fn replace_uses(function: &mut Function, replacements: &HashMap<RegisterId, Operand>) {
+    let replace = |operand: &mut Operand| {
+        if let Operand::Register(reg) = operand {
+            if let Some(replacement) = replacements.get(reg) {
+                *operand = replacement.clone();
+            }
+        }
+    };
+    for block in function.blocks.values_mut() {
+        for (_, instruction) in block.instructions.iter_mut() {
+            instruction.operands_mut().into_iter().for_each(replace);
+        }
+        if let Some(terminator) = block.terminator.as_mut() {
+            terminator.operands_mut().into_iter().for_each(replace);
+        }
+    }
+}
+
+/// 条件に合う命令を取り除き、その位置情報・検査の省略・属性も消す
+pub(crate) fn remove_instructions(function: &mut Function, remove: impl Fn(&InstructionId, &Instruction) -> bool) {
+    let mut removed = Vec::new();
+    for block in function.blocks.values_mut() {
+        block.instructions.retain(|(id, instruction)| {
+            let keep = !remove(id, instruction);
+            if !keep {
+                removed.push(*id);
+            }
+            keep
+        });
+    }
+    for id in removed {
+        function.forget_instruction(id);
+    }
+}
+
+/// ループ内の定数による乗算をシフトと加減算にし、置き換えた数を返す
+fn expand_multiplications(
+    function: &mut Function,
+    shape: &LoopShape,
+    analysis: &RangeAnalysis,
+    integers: &HashMap<TypeId, IntegerType>,
+) -> usize {
+    let mut expanded = 0;
+    for block_id in &shape.body {
+        let mut position = 0;
+        while position < function.blocks[block_id].instructions.len() {
+            let (id, instruction) = &function.blocks[block_id].instructions[position];
+            position += 1;
+            let Instruction::BinaryOp { op: BinaryOp::Mul, lhs, rhs, result } = instruction else {
+                continue;
+            };
+            let (value, constant) = match (lhs, rhs) {
+                (value, Operand::Literal(Literal::Int(c))) | (Operand::Literal(Literal::Int(c)), value) if !matches!(value, Operand::Literal(_)) => (value.clone(), *c),
+                _ => continue,
+            };
+            let (id, result) = (*id, *result);
+            let Some(integer) = function.register_types.get(&result).and_then(|ty| integers.get(ty)).copied() else {
+                continue;
+            };
+            if constant < 2 {
+                continue;
+            }
+            // `x * 2^k` は `x << k`、`x * (2^k + 1)` は `(x << k) + x`、`x * (2^k - 1)` は `(x << k) - x`
+            let (shift, combine) = if constant.count_ones() == 1 {
+                (constant.trailing_zeros(), None)
+            } else if (constant - 1).count_ones() == 1 {
+                ((constant - 1).trailing_zeros(), Some(BinaryOp::Add))
+            } else if let Some(power) = constant.checked_add(1).filter(|power| power.count_ones() == 1) {
+                (power.trailing_zeros(), Some(BinaryOp::Sub))
+            } else {
+                continue;
+            };
+            if shift >= integer.bits {
+                continue;
+            }
+            // `(x << k) - x` は `x << k` だけがオーバーフローする場合があるので、`Int` では値域で確かめる
+            if combine == Some(BinaryOp::Sub) && integer.exact {
+                let shifted = analysis.operand_range(&value, *block_id).and_then(|range| affine_range(range, 1i64 << shift, None));
+                if shifted.is_none() {
+                    continue;
+                }
+            }
+            
+            let shift = Operand::Literal(Literal::Int(shift as i64));
+            let replacement = match combine {
+                None => Instruction::BinaryOp { op: BinaryOp::Shl, lhs: value, rhs: shift, result },
+                Some(op) => {
+                    let shifted = function.create_register(function.register_types[&result]);
+                    let shift_id = function.next_instruction_id();
+                    let instruction = Instruction::BinaryOp { op: BinaryOp::Shl, lhs: value.clone(), rhs: shift, result: shifted };
+                    if let Some(block) = function.blocks.get_mut(block_id) {
+                        block.instructions.insert(position - 1, (shift_id, instruction));
+                    }
+                    if let Some(location) = function.get_location(id).cloned() {
+                        function.set_location(shift_id, location);
+                    }
+                    position += 1;
+                    Instruction::BinaryOp { op, lhs: Operand::Register(shifted), rhs: value, result }
+                }
+            };
+            function.replace_instruction(*block_id, id, replacement);
+            expanded += 1;
+        }
+    }
+    expanded
+}
+
+/// 自分の更新にしか使われない帰納変数を取り除き、取り除いた数を返す
+fn remove_dead_induction_variables(function: &mut Function, shape: &LoopShape) -> usize {
+    let mut removed = 0;
+    loop {
+        let mut uses: HashMap<RegisterId, usize> = HashMap::new();
+        for block in function.blocks.values() {
+            for (_, instruction) in &block.instructions {
+                for reg in instruction.used_registers() {
+                    *uses.entry(reg).or_default() += 1;
+                }
+            }
+            for operand in block.terminator.iter().flat_map(|terminator| terminator.operands()) {
+                if let Operand::Register(reg) = operand {
+                    *uses.entry(*reg).or_default() += 1;
+                }
+            }
+        }
+        // パラメータは `next` の計算だけに、`next` はラッチの分岐の引数だけに使われている
+        let dead = basic_induction_variables(function, shape)
+            .into_iter()
+            .find(|iv| uses.get(&iv.param) == Some(&1) && uses.get(&iv.next) == Some(&1));
+        let Some(iv) = dead else {
+            break;
+        };
+        let Some(index) = function.blocks[&shape.header].parameters.iter().position(|(reg, _)| *reg == iv.param) else {
+            break;
+        };
+        if let Some(header) = function.blocks.get_mut(&shape.header) {
+            header.parameters.remove(index);
+        }
+        for block in [shape.preheader, shape.latch] {
+            if let Some(terminator) = function.blocks.get_mut(&block).and_then(|block| block.terminator.as_mut()) {
+                for args in edge_args_mut(terminator, shape.header) {
+                    if index < args.len() {
+                        args.remove(index);
+                    }
+                }
+            }
+        }
+        remove_instructions(function, |_, instruction| instruction.defined_register() == Some(iv.next));
+        removed += 1;
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::FunctionId;
+    use crate::core::ir_builder::IrBuilder;
+    use crate::core::types::Type;
+    
+    /// `var i = 3; var acc = 0; while i < bound { acc = (acc + (i * 8 + 5)) * 3; i += 2 }; acc` に相当する関数
+    fn counted_loop(module: &mut Module, bound: Operand, with_parameter: bool) -> Function {
+        let int = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        let mut function = Function::new(FunctionId(0), "f", int, int);
+        if with_parameter {
+            function.add_parameter("n", int);
+        }
+        let mut builder = IrBuilder::new(function);
+        let header = builder.create_block();
+        let body = builder.create_block();
+        let exit = builder.create_block();
+        builder.br(header, vec![Operand::Literal(Literal::Int(3)), Operand::Literal(Literal::Int(0))]);
+        let i = builder.add_block_param(header, int);
+        let acc = builder.add_block_param(header, int);
+        builder.position_at_end(header);
+        let condition = builder.binary(BinaryOp::Lt, Operand::Register(i), bound, bool_type);
+        builder.cond_br(Operand::Register(condition), body, exit);
+        builder.position_at_end(body);
+        let scaled = builder.binary(BinaryOp::Mul, Operand::Register(i), Operand::Literal(Literal::Int(8)), int);
+        let address = builder.binary(BinaryOp::Add, Operand::Register(scaled), Operand::Literal(Literal::Int(5)), int);
+        let sum = builder.binary(BinaryOp::Add, Operand::Register(acc), Operand::Register(address), int);
+        let tripled = builder.binary(BinaryOp::Mul, Operand::Register(sum), Operand::Literal(Literal::Int(3)), int);
+        let next = builder.binary(BinaryOp::Add, Operand::Register(i), Operand::Literal(Literal::Int(2)), int);
+        builder.br(header, vec![Operand::Register(next), Operand::Register(tripled)]);
+        builder.position_at_end(exit);
+        builder.ret(Some(Operand::Register(acc)));
+        builder.finish()
+    }
+    
+    #[test]
+    fn test_address_becomes_incremented_induction_variable() {
+        let mut module = Module::new("test");
+        let function = counted_loop(&mut module, Operand::Literal(Literal::Int(100)), false);
+        module.add_function(function);
+        
+        let stats = optimize(&mut module);
+        assert_eq!(stats, InductionStats { strength_reduced: 2, multiplications: 1, dead: 1 });
+        let text = module.to_string();
+        // `i * 8 + 5` は初期値 3 * 8 + 5 = 29 から 2 * 8 = 16 ずつ増える変数になり、`i * 8` の変数は使われなくなる
+        assert!(text.contains("br block_1(3, 0, 29)"), "{}", text);
+        assert!(text.contains("= add %10, 16"), "{}", text);
+        assert!(!text.contains("mul"), "{}", text);
+        assert!(text.contains("%12 = shl %5, 1\n    %6 = add %12, %5"), "{}", text);
+        crate::core::eir::verify_after(&module, "帰納変数の最適化").unwrap();
+    }
+    
+    #[test]
+    fn test_unbounded_int_loop_is_not_reduced() {
+        // `i < n` の `n` は範囲が分からず、`i * 8` がオーバーフローしないと言えないので置き換えない
+        let mut module = Module::new("test");
+        let function = counted_loop(&mut module, Operand::Register(RegisterId(0)), true);
+        module.add_function(function);
+        
+        let stats = optimize(&mut module);
+        assert_eq!(stats, InductionStats { strength_reduced: 0, multiplications: 2, dead: 0 });
+        let text = module.to_string();
+        assert!(text.contains("= shl %1, 3"), "{}", text);
+        assert!(!text.contains("mul"), "{}", text);
+    }
+    
+    #[test]
+    fn test_range_iterator_is_promoted() {
+        // `for` のイテレータのスロットは消え、始点がヘッダーのパラメータ、終点がループ不変の値になる
+        let source = "fn sum(n: Int) -> Int { var total = 0; for i in 2..n { total += i; } total } fn main() -> Int { sum(10) }";
+        let mut module = crate::core::eir_builder::build_test_module("test", source).unwrap();
+        assert_eq!(promote_range_iterators(&mut module), 1);
+        crate::core::eir::verify_after(&module, "イテレータの展開").unwrap();
+        let text = module.to_string();
+        assert!(!text.contains("tuple") && !text.contains("extract"), "{}", text);
+        assert!(text.contains("br block_1(2)") && text.contains("= lt %5, %0"), "{}", text);
+        
+        let mut engine = crate::backend::jit::JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), crate::backend::jit::JitValue::Int(44));
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 文字列を置くチャンクの大きさ（これより長い名前は、その長さのチャンクを1つ使う）
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// インターンした名前（識別子）
+///
+/// 同じ文字列からは常に同じ `Name` ができるので、比較とハッシュは文字列ではなく置いた場所のアドレスで行い、
+/// 複製はポインタのコピーで済む（トークン列やASTを複製しても名前の分は割り当てが起きない）。
+/// 文字列は字句解析・構文解析・型検査で共有する1つのインターナーに置き、プログラムの終了まで解放しない。
+/// 並び順（`Ord`）だけは文字列の順にして、名前で並べた結果が実行ごとに変わらないようにする。
+#[derive(Clone, Copy)]
+pub struct Name(&'static str);
+
+/// インターナー（文字列から `Name` への表と、文字列を置くバンプアロケータ）
+#[derive(Default)]
+struct Interner {
+    names: HashMap<&'static str, Name>,
+    /// 文字列を詰めて置くチャンク。容量を超えて伸ばさないので、置いた文字列は移動しない
+    chunks: Vec<String>,
+}
+
+impl Interner {
+    /// 文字列をチャンクの末尾に置く
+    fn alloc(&mut self, text: &str) -> &'static str {
+        if self.chunks.last().is_none_or(|chunk| chunk.capacity() - chunk.len() < text.len()) {
+            self.chunks.push(String::with_capacity(CHUNK_SIZE.max(text.len())));
+        }
+        let chunk = self.chunks.last_mut().expect("チャンクを追加した直後");
+        let start = chunk.len();
+        chunk.push_str(text);
+        // SAFETY: チャンクは容量の範囲でしか伸ばさないので中身は再配置されず、インターナーは静的変数として
+        // プログラムの終了まで残るため、置いた文字列は `'static` の間有効
+        unsafe { &*(&chunk[start..] as *const str) }
+    }
+}
+
+fn interner() -> &'static RwLock<Interner> {
+    static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+impl Name {
+    /// 文字列をインターンする（インターン済みなら同じ `Name` を返す）
+    pub fn new(text: &str) -> Self {
+        if let Some(name) = interner().read().unwrap_or_else(PoisonError::into_inner).names.get(text) {
+            return *name;
+        }
+        let mut interner = interner().write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(name) = interner.names.get(text) {
+            return *name;
+        }
+        let name = Name(interner.alloc(text));
+        interner.names.insert(name.0, name);
+        name
+    }
+    
+    pub fn as_str(self) -> &'static str {
+        self.0
+    }
+}
+
+/// 空の名前（トークンの種類だけを比べるときの値などに使う）
+impl Default for Name {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Name {}
+
+impl Hash for Name {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0.as_ptr() as usize).hash(state);
+    }
+}
+
+impl PartialOrd for Name {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Name {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(other.0)
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+    
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl PartialEq<str> for Name {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Name {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Name {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<Name> for str {
+    fn eq(&self, other: &Name) -> bool {
+        self == other.0
+    }
+}
+
+impl PartialEq<Name> for &str {
+    fn eq(&self, other: &Name) -> bool {
+        *self == other.0
+    }
+}
+
+impl PartialEq<Name> for String {
+    fn eq(&self, other: &Name) -> bool {
+        self == other.0
+    }
+}
+
+impl From<&str> for Name {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<&String> for Name {
+    fn from(text: &String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for Name {
+    fn from(text: String) -> Self {
+        Self::new(&text)
+    }
+}
+
+impl From<Name> for String {
+    fn from(name: Name) -> Self {
+        name.0.to_string()
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.0, f)
+    }
+}
+
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Name::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_interned_names() {
+        let a = Name::new("合計");
+        let b = Name::from("合計".to_string());
+        assert_eq!(a, b);
+        assert!(std::ptr::eq(a.as_str(), b.as_str()));
+        assert_ne!(a, Name::new("合計2"));
+        assert_eq!(a, "合計");
+        assert_eq!(a.len(), "合計".len());
+        assert_eq!(format!("{} {:?}", a, a), "合計 \"合計\"");
+        
+        // チャンクより長い名前も置ける
+        let long = "x".repeat(CHUNK_SIZE + 1);
+        assert_eq!(Name::new(&long).as_str(), long);
+        
+        let mut names = vec![Name::new("b"), Name::new("c"), Name::new("a")];
+        names.sort();
+        assert_eq!(names, ["a", "b", "c"].map(Name::new));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::core::error::SourceLocation;
+use crate::core::eir::{
+    AtomicOp, BinaryOp, BlockId, Function, Instruction, InstructionId, Operand, RegisterId, Terminator, UnaryOp,
+};
+use crate::core::types::TypeId;
+
+/// EIR 関数に命令を追加するビルダー
+///
+/// 命令を追加する位置（カーソル）として現在のブロックを持ち、型付きの命令ごとの関数で
+/// 結果のレジスタの割り当てと命令の追加をまとめて行う。設定したソース上の位置は、
+/// 以後に追加する命令すべてに記録する。
+///
+/// ```ignore
+/// let mut builder = IrBuilder::new(function);
+/// let sum = builder.binary(BinaryOp::Add, lhs, rhs, int);
+/// builder.ret(Some(Operand::Register(sum)));
+/// let function = builder.finish();
+/// ```
+#[derive(Debug, Clone)]
+pub struct IrBuilder {
+    function: Function,
+    current_block: BlockId,
+    location: Option<SourceLocation>,
+}
+
+impl IrBuilder {
+    /// 関数のエントリーブロックの末尾を指すビルダーを作成
+    pub fn new(function: Function) -> Self {
+        let entry = function.entry_block;
+        Self {
+            function,
+            current_block: entry,
+            location: None,
+        }
+    }
+    
+    /// 構築中の関数
+    pub fn function(&self) -> &Function {
+        &self.function
+    }
+    
+    /// 構築中の関数を可変で取得
+    pub fn function_mut(&mut self) -> &mut Function {
+        &mut self.function
+    }
+    
+    /// 構築を終えて関数を返す
+    pub fn finish(self) -> Function {
+        self.function
+    }
+    
+    /// 新しい基本ブロックを作成（カーソルは動かさない）
+    pub fn create_block(&mut self) -> BlockId {
+        self.function.create_block()
+    }
+    
+    /// ブロックにパラメータを追加し、そのレジスタを返す
+    pub fn add_block_param(&mut self, block: BlockId, type_id: TypeId) -> RegisterId {
+        let register = self.function.create_register(type_id);
+        if let Some(block) = self.function.get_block_mut(block) {
+            block.add_parameter(register, type_id);
+        }
+        register
+    }
+    
+    /// 以後の命令をブロックの末尾に追加する
+    pub fn position_at_end(&mut self, block: BlockId) {
+        self.current_block = block;
+    }
+    
+    /// 命令を追加しているブロック
+    pub fn current_block(&self) -> BlockId {
+        self.current_block
+    }
+    
+    /// 現在のブロックに終了命令が設定済みか
+    pub fn is_terminated(&self) -> bool {
+        self.function
+            .get_block(self.current_block)
+            .is_some_and(|block| block.terminator.is_some())
+    }
+    
+    /// 以後の命令に記録するソース上の位置
+    pub fn location(&self) -> Option<&SourceLocation> {
+        self.location.as_ref()
+    }
+    
+    /// 以後の命令に記録するソース上の位置を設定（元の位置を返す）
+    pub fn set_location(&mut self, location: Option<SourceLocation>) -> Option<SourceLocation> {
+        std::mem::replace(&mut self.location, location)
+    }
+    
+    /// 現在のブロックの末尾に命令を追加
+    pub fn emit(&mut self, instruction: Instruction) -> InstructionId {
+        let id = self.function.add_instruction(self.current_block, instruction);
+        if let Some(location) = &self.location {
+            self.function.set_location(id, location.clone());
+        }
+        id
+    }
+    
+    /// 二項演算
+    pub fn binary(&mut self, op: BinaryOp, lhs: Operand, rhs: Operand, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::BinaryOp { op, lhs, rhs, result });
+        result
+    }
+    
+    /// 単項演算
+    pub fn unary(&mut self, op: UnaryOp, operand: Operand, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::UnaryOp { op, operand, result });
+        result
+    }
+    
+    /// アドレスから型 `type_id` の値を読む
+    pub fn load(&mut self, address: Operand, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::Load { address, result });
+        result
+    }
+    
+    /// アドレスに値を書く
+    pub fn store(&mut self, address: Operand, value: Operand) {
+        self.emit(Instruction::Store { address, value });
+    }
+    
+    /// 型 `type_id` の値を置くスタックスロットを確保
+    pub fn alloca(&mut self, size: usize, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::Alloca { size, result });
+        result
+    }
+    
+    /// `base` から `indices` で要素のアドレスを求める
+    pub fn gep(&mut self, base: Operand, indices: Vec<Operand>, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::GetElementPtr { base, indices, result });
+        result
+    }
+    
+    /// 関数呼び出し（戻り値の型を渡したときだけ結果のレジスタを返す）
+    pub fn call(&mut self, function: impl Into<String>, arguments: Vec<Operand>, return_type: Option<TypeId>) -> Option<RegisterId> {
+        let result = return_type.map(|type_id| self.function.create_register(type_id));
+        self.emit(Instruction::Call { function: function.into(), arguments, result });
+        result
+    }
+    
+    /// 値を型 `target_type` に変換
+    pub fn cast(&mut self, value: Operand, target_type: TypeId) -> RegisterId {
+        let result = self.function.create_register(target_type);
+        self.emit(Instruction::Cast { value, target_type, result });
+        result
+    }
+    
+    /// 条件で2つの値の一方を選ぶ
+    pub fn select(&mut self, condition: Operand, true_value: Operand, false_value: Operand, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::Select { condition, true_value, false_value, result });
+        result
+    }
+    
+    /// 値を並べてタプル型 `type_id` の値を作る
+    pub fn tuple(&mut self, elements: Vec<Operand>, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::Tuple { elements, result });
+        result
+    }
+    
+    /// タプルの `index` 番目の値（型 `type_id`）を取り出す
+    pub fn extract(&mut self, tuple: Operand, index: usize, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::Extract { tuple, index, result });
+        result
+    }
+    
+    /// 固定長配列 `array` の先頭から `length` 個の要素を指すスライス（型 `type_id`）を作る
+    pub fn make_slice(&mut self, array: Operand, length: Operand, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::MakeSlice { array, length, result });
+        result
+    }
+    
+    /// スライスの長さ（型 `type_id`）を取り出す
+    pub fn slice_len(&mut self, slice: Operand, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::SliceLen { slice, result });
+        result
+    }
+    
+    /// `address` の値にアトミック操作を行う（結果の型は `type_id`、結果を持たない操作は `None`）
+    pub fn atomic(
+        &mut self,
+        op: AtomicOp,
+        address: Operand,
+        value: Option<Operand>,
+        expected: Option<Operand>,
+        type_id: Option<TypeId>,
+    ) -> Option<RegisterId> {
+        let result = type_id.map(|ty| self.function.create_register(ty));
+        self.emit(Instruction::Atomic { op, address, value, expected, result });
+        result
+    }
+    
+    /// 前のブロックごとの値を合流させる
+    pub fn phi(&mut self, incoming: Vec<(Operand, BlockId)>, type_id: TypeId) -> RegisterId {
+        let result = self.function.create_register(type_id);
+        self.emit(Instruction::Phi { incoming, result });
+        result
+    }
+    
+    /// 現在のブロックに終了命令を設定（設定済みなら何もせず `false` を返す）
+    pub fn terminate(&mut self, terminator: Terminator) -> bool {
+        match self.function.get_block_mut(self.current_block) {
+            Some(block) if block.terminator.is_none() => {
+                block.set_terminator(terminator);
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// 無条件分岐
+    pub fn br(&mut self, target: BlockId, args: Vec<Operand>) -> bool {
+        self.terminate(Terminator::Branch { target, args })
+    }
+    
+    /// 引数を渡さない条件分岐
+    pub fn cond_br(&mut self, condition: Operand, true_target: BlockId, false_target: BlockId) -> bool {
+        self.terminate(Terminator::BranchCond {
+            condition,
+            true_target,
+            true_args: Vec::new(),
+            false_target,
+            false_args: Vec::new(),
+        })
+    }
+    
+    /// 関数から戻る
+    pub fn ret(&mut self, value: Option<Operand>) -> bool {
+        self.terminate(Terminator::Return { value })
+    }
+    
+    /// 到達しないことを示す
+    pub fn unreachable(&mut self) -> bool {
+        self.terminate(Terminator::Unreachable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    
+    use super::*;
+    use crate::core::eir::{self, FunctionId, Literal, Module};
+    use crate::core::types::Type;
+    
+    #[test]
+    fn test_build_function() {
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        let func_type = module.add_type(Type::function(vec![Type::int()], Type::int()));
+        let mut function = Function::new(FunctionId(0), "abs", func_type, int);
+        let x = Operand::Register(function.add_parameter("x", int));
+        
+        let mut builder = IrBuilder::new(function);
+        let negative = builder.create_block();
+        let positive = builder.create_block();
+        let join = builder.create_block();
+        let result = builder.add_block_param(join, int);
+        
+        builder.set_location(Some(SourceLocation::new(PathBuf::from("abs.eid"), 1, 5, 1)));
+        let is_negative = builder.binary(BinaryOp::Lt, x.clone(), Operand::Literal(Literal::Int(0)), bool_type);
+        assert!(builder.cond_br(Operand::Register(is_negative), negative, positive));
+        
+        builder.position_at_end(positive);
+        builder.br(join, vec![x.clone()]);
+        
+        builder.position_at_end(negative);
+        let negated = builder.unary(UnaryOp::Neg, x, int);
+        assert!(builder.br(join, vec![Operand::Register(negated)]));
+        // 終了命令を設定済みのブロックには上書きしない
+        assert!(!builder.unreachable());
+        
+        builder.position_at_end(join);
+        assert!(!builder.is_terminated());
+        builder.ret(Some(Operand::Register(result)));
+        
+        let function = builder.finish();
+        let entry = function.get_block(function.entry_block).unwrap();
+        let (id, _) = entry.instructions[0];
+        assert_eq!(function.get_location(id).map(|location| location.line), Some(1));
+        assert!(matches!(function.get_block(negative).unwrap().terminator, Some(Terminator::Branch { .. })));
+        assert_eq!(function.get_register_type(result), Some(int));
+        
+        module.add_function(function);
+        eir::verify(&module).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use std::cell::Cell;
+use std::io;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::core::{EidosError, Result};
+
+/// 入れ子の深さの既定の上限（`--recursion-limit` で変更できる）
+pub const DEFAULT_RECURSION_LIMIT: usize = 128;
+
+/// 設定できる入れ子の深さの上限（スタックは1GiBになる）
+pub const MAX_RECURSION_LIMIT: usize = 4096;
+
+/// 入れ子1段あたりに見込むスタックの大きさ（デバッグビルドでは、式の括弧1段で100KiBほど使う）
+const STACK_PER_LEVEL: usize = 256 * 1024;
+
+/// 入れ子の深さの上限
+static RECURSION_LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_RECURSION_LIMIT);
+
+thread_local! {
+    /// このスレッドで入っている入れ子の深さ
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// 入れ子の深さの上限を設定（構文解析・型検査・DSLの展開に共通。1から `MAX_RECURSION_LIMIT` の範囲に収める）
+pub fn set_recursion_limit(limit: usize) {
+    RECURSION_LIMIT.store(limit.clamp(1, MAX_RECURSION_LIMIT), Ordering::Relaxed);
+}
+
+pub fn recursion_limit() -> usize {
+    RECURSION_LIMIT.load(Ordering::Relaxed)
+}
+
+/// 上限の深さまで再帰できるスタックの大きさ（上限を既定より小さくしても、既定の分は確保する）
+pub fn stack_size() -> usize {
+    recursion_limit().max(DEFAULT_RECURSION_LIMIT).saturating_mul(STACK_PER_LEVEL)
+}
+
+/// 入れ子の構文に1段入る（戻り値を捨てると1段戻る）
+///
+/// 深さはスレッドごとに数えるので、DSLの展開結果を別の構文解析器で解析する場合のように、処理が入れ子に
+/// なっても合わせた深さで上限を確かめる。上限を超える場合は、`construct`（「式」など）を示すメッセージを返す。
+pub fn enter(construct: &str) -> std::result::Result<DepthGuard, String> {
+    DEPTH.with(|depth| {
+        if depth.get() >= recursion_limit() {
+            return Err(format!(
+                "{}の入れ子が深すぎます（上限は {}。`--recursion-limit` で変更できます）",
+                construct,
+                recursion_limit()
+            ));
+        }
+        depth.set(depth.get() + 1);
+        Ok(DepthGuard { _thread: PhantomData })
+    })
+}
+
+/// `enter` で入った入れ子（捨てると1段戻る）
+#[must_use]
+pub struct DepthGuard {
+    /// 深さはスレッドごとに数えるので、別のスレッドには渡せない
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// 上限の深さまで再帰できる大きさのスタックを持つスレッドで `f` を実行する
+///
+/// メインスレッドのスタックの大きさは変えられないので、コマンドの処理全体をこのスレッドで行う。
+pub fn with_stack<T: Send>(f: impl FnOnce() -> T + Send) -> Result<T> {
+    thread::scope(|scope| {
+        let handle = thread::Builder::new()
+            .name("eidos-main".to_string())
+            .stack_size(stack_size())
+            .spawn_scoped(scope, f)
+            .map_err(|e| {
+                let message = format!(
+                    "{}MiBのスタックを持つスレッドを作成できません（`--recursion-limit` を小さくしてください）: {}",
+                    stack_size() >> 20,
+                    e
+                );
+                EidosError::IO(io::Error::new(e.kind(), message))
+            })?;
+        Ok(handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_depth_limit() {
+        fn nest(levels: usize) -> std::result::Result<usize, String> {
+            if levels == 0 {
+                return Ok(DEPTH.with(Cell::get));
+            }
+            let _guard = enter("式")?;
+            nest(levels - 1)
+        }
+        
+        assert_eq!(nest(DEFAULT_RECURSION_LIMIT), Ok(DEFAULT_RECURSION_LIMIT));
+        let error = nest(DEFAULT_RECURSION_LIMIT + 1).unwrap_err();
+        assert!(error.starts_with("式の入れ子が深すぎます（上限は 128"), "{}", error);
+        // エラーで抜けた後も深さは元に戻る
+        assert_eq!(DEPTH.with(Cell::get), 0);
+    }
+}
@@ -0,0 +1,563 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::analysis::{Cfg, DominatorTree, LoopForest};
+use crate::core::eir::{BasicBlock, BinaryOp, BlockId, Function, Instruction, Literal, Module, Operand, RegisterId, Terminator};
+use crate::core::induction::{self, BasicIv, LoopShape};
+use crate::core::range_analysis::RangeAnalysis;
+
+/// ループ融合による変換の件数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FusionStats {
+    /// 1つにまとめた隣り合うループの組
+    pub fused: usize,
+    /// まとめたループからプリヘッダーへ移した、ループ不変のロード
+    pub hoisted_loads: usize,
+}
+
+/// ループ本体のメモリへの読み書き
+#[derive(Debug, Clone)]
+struct Access {
+    base: Operand,
+    /// 現在の反復のループ変数を添字とする要素（`a[i]`）への読み書きか
+    element: bool,
+    write: bool,
+}
+
+/// ループ本体の副作用
+#[derive(Debug, Default)]
+struct Effects {
+    accesses: Vec<Access>,
+    /// 関数呼び出し・アトミック操作・インライン・アセンブリを含む
+    calls: bool,
+    /// 検査付きの除算・剰余か、`panic` へ分岐しうる検査（配列の範囲外アクセスなど）を含む
+    traps: bool,
+    /// 終了条件以外で、ループを途中で抜けうる（`break` など）
+    exits: bool,
+}
+
+/// 融合する2つのループ
+struct Fusion {
+    first: LoopShape,
+    second: LoopShape,
+    first_iv: BasicIv,
+    second_iv: BasicIv,
+    /// 2つ目のループの本体の入口
+    second_entry: BlockId,
+    /// 2つ目のループを出た先
+    second_exit: BlockId,
+    first_condition: RegisterId,
+    second_condition: RegisterId,
+}
+
+/// 隣り合うループの融合
+///
+/// 1つ目のループを出た先がそのまま2つ目のループのプリヘッダーで、2つのループのループ変数が同じ初期値・
+/// 増分・終了条件を持つとき、2つ目のループの本体を1つ目のループの本体の後ろにつなぎ、1つのループにする。
+/// DSLが生成する `for i {a[i] = b[i] + 1}; for i {c[i] = a[i] * 2}` のような配列の走査を1回にまとめる。
+///
+/// 次のどれかに当たる場合は融合しない（反復の順序が変わると結果が変わりうる）。
+/// - 2つ目のループが、1つ目のループで計算した値を使う
+/// - 同じ場所を指しうる読み書きがあり（少なくとも一方が書き込み）、どちらも現在の反復の要素 `a[i]` でない
+/// - 一方が関数を呼び出し、他方がメモリを読み書きするか関数を呼び出すか実行時エラーになりうる
+/// - 両方が実行時エラーになりうる
+///
+/// - 一方が終了条件以外でループを抜けうる（値域解析で常に成り立つと分かる配列の範囲の検査は抜けない）
+///
+/// `for` の範囲のイテレータは先に展開し（`induction::promote_range_iterators`）、ループ変数にする。
+/// 融合したループでは、書き込まれない場所からのロードをプリヘッダーへ移し、同じ場所のロードは1つにまとめる。
+pub fn optimize(module: &mut Module) -> FusionStats {
+    let mut stats = FusionStats::default();
+    let mut ids: Vec<_> = module.functions.keys().copied().collect();
+    ids.sort_by_key(|id| id.0);
+    for id in ids {
+        let Some(function) = module.functions.get_mut(&id) else {
+            continue;
+        };
+        induction::promote_iterators(function);
+        // 融合するとループの構造が変わるので、1組ずつ融合してはループと値域を求め直す
+        loop {
+            let analysis = RangeAnalysis::new(module, &module.functions[&id]);
+            let Some(fusion) = find_fusion(&module.functions[&id], &analysis) else {
+                break;
+            };
+            let Some(function) = module.functions.get_mut(&id) else {
+                break;
+            };
+            fuse(function, &fusion);
+            stats.fused += 1;
+            stats.hoisted_loads += hoist_invariant_loads(function, &fusion.first);
+        }
+    }
+    stats
+}
+
+fn find_fusion(function: &Function, analysis: &RangeAnalysis) -> Option<Fusion> {
+    let cfg = Cfg::new(function);
+    let dominators = DominatorTree::new(&cfg);
+    let loops = LoopForest::new(&cfg, &dominators);
+    let shapes: Vec<LoopShape> = loops.loops().iter().filter_map(|lp| induction::loop_shape(function, &cfg, lp)).collect();
+    for first in &shapes {
+        for second in &shapes {
+            if let Some(fusion) = plan(function, &cfg, analysis, first, second) {
+                return Some(fusion);
+            }
+        }
+    }
+    None
+}
+
+/// `header` が `br %c, body, exit`（引数なし）で終わるとき、`(c, body, exit)`
+fn loop_exit(function: &Function, shape: &LoopShape) -> Option<(RegisterId, BlockId, BlockId)> {
+    match function.blocks.get(&shape.header)?.terminator.as_ref()? {
+        Terminator::BranchCond { condition: Operand::Register(condition), true_target, true_args, false_target, false_args }
+            if true_args.is_empty() && false_args.is_empty() && shape.body.contains(true_target) && !shape.body.contains(false_target) =>
+        {
+            Some((*condition, *true_target, *false_target))
+        }
+        _ => None,
+    }
+}
+
+/// ヘッダーで定義される比較 `iv op bound` の `(op, iv, bound)`
+fn loop_condition(function: &Function, header: BlockId, condition: RegisterId) -> Option<(BinaryOp, RegisterId, Operand)> {
+    function.blocks.get(&header)?.instructions.iter().find_map(|(_, instruction)| match instruction {
+        Instruction::BinaryOp { op, lhs: Operand::Register(iv), rhs, result } if *result == condition => Some((*op, *iv, rhs.clone())),
+        _ => None,
+    })
+}
+
+fn plan(function: &Function, cfg: &Cfg, analysis: &RangeAnalysis, first: &LoopShape, second: &LoopShape) -> Option<Fusion> {
+    let (first_condition, _, first_exit) = loop_exit(function, first)?;
+    let (second_condition, second_entry, second_exit) = loop_exit(function, second)?;
+    // 1つ目のループを出た先が、命令のない2つ目のループのプリヘッダー
+    if first_exit != second.preheader || cfg.predecessors(first_exit) != [first.header] {
+        return None;
+    }
+    let preheader = function.blocks.get(&second.preheader)?;
+    if !preheader.parameters.is_empty() || !preheader.instructions.is_empty() {
+        return None;
+    }
+    // 2つ目のループのヘッダーは終了条件の比較だけ
+    let second_header = function.blocks.get(&second.header)?;
+    if second_header.instructions.len() != 1 {
+        return None;
+    }
+    for shape in [first, second] {
+        if !matches!(function.blocks.get(&shape.latch)?.terminator, Some(Terminator::Branch { target, .. }) if target == shape.header) {
+            return None;
+        }
+    }
+    
+    // 同じ初期値・増分のループ変数を、同じ上限と比べる
+    let (first_op, first_var, first_bound) = loop_condition(function, first.header, first_condition)?;
+    let (second_op, second_var, second_bound) = loop_condition(function, second.header, second_condition)?;
+    let first_iv = induction::basic_induction_variables(function, first).into_iter().find(|iv| iv.param == first_var)?;
+    let second_iv = induction::basic_induction_variables(function, second).into_iter().find(|iv| iv.param == second_var)?;
+    if first_op != second_op || first_iv.step != second_iv.step || !same_operand(&first_iv.init, &second_iv.init) || !same_operand(&first_bound, &second_bound) {
+        return None;
+    }
+    
+    let first_defined = defined_in(function, first);
+    let second_defined = defined_in(function, second);
+    if [&first_bound, &second_bound].into_iter().any(|bound| is_defined_by(bound, &first_defined) || is_defined_by(bound, &second_defined)) {
+        return None;
+    }
+    // 2つ目のループ（プリヘッダーが渡す初期値を含む）は、1つ目のループの値を使わない
+    let uses_first = [second.preheader]
+        .iter()
+        .chain(&second.body)
+        .filter_map(|block| function.blocks.get(block))
+        .any(|block| block_uses(block).iter().any(|reg| first_defined.contains(reg)));
+    if uses_first {
+        return None;
+    }
+    
+    let first_effects = effects(function, analysis, first, first_iv.param);
+    let second_effects = effects(function, analysis, second, second_iv.param);
+    if first_effects.exits || second_effects.exits || !independent(&first_effects, &second_effects, &stack_slots(function)) {
+        return None;
+    }
+    Some(Fusion {
+        first: first.clone(),
+        second: second.clone(),
+        first_iv,
+        second_iv,
+        second_entry,
+        second_exit,
+        first_condition,
+        second_condition,
+    })
+}
+
+fn same_operand(a: &Operand, b: &Operand) -> bool {
+    match (a, b) {
+        (Operand::Register(a), Operand::Register(b)) => a == b,
+        (Operand::Literal(Literal::Int(a)), Operand::Literal(Literal::Int(b))) => a == b,
+        (Operand::Global(a), Operand::Global(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// ループの中で定義されるレジスタ（ヘッダーのパラメータを含む）
+fn defined_in(function: &Function, shape: &LoopShape) -> HashSet<RegisterId> {
+    let mut defined: HashSet<RegisterId> = induction::loop_definitions(function, shape).into_keys().collect();
+    for block in &shape.body {
+        defined.extend(function.blocks[block].parameters.iter().map(|(reg, _)| *reg));
+    }
+    defined
+}
+
+fn is_defined_by(operand: &Operand, defined: &HashSet<RegisterId>) -> bool {
+    matches!(operand, Operand::Register(reg) if defined.contains(reg))
+}
+
+/// ブロックの命令と終端命令が使うレジスタ
+fn block_uses(block: &BasicBlock) -> Vec<RegisterId> {
+    let mut uses: Vec<RegisterId> = block.instructions.iter().flat_map(|(_, instruction)| instruction.used_registers()).collect();
+    for operand in block.terminator.iter().flat_map(|terminator| terminator.operands()) {
+        if let Operand::Register(reg) = operand {
+            uses.push(*reg);
+        }
+    }
+    uses
+}
+
+/// ループ本体の副作用を集める（`iv` はそのループのループ変数）
+fn effects(function: &Function, analysis: &RangeAnalysis, shape: &LoopShape, iv: RegisterId) -> Effects {
+    let definitions = induction::loop_definitions(function, shape);
+    let mut effects = Effects::default();
+    for block in &shape.body {
+        // ヘッダーの終了条件以外でループの外へ出る分岐（行き先が `unreachable` で終わるなら `panic`）
+        for target in side_exits(function, analysis, shape, *block) {
+            match function.blocks.get(&target).and_then(|target| target.terminator.as_ref()) {
+                Some(Terminator::Unreachable) => effects.traps = true,
+                _ => effects.exits = true,
+            }
+        }
+        match function.blocks[block].terminator {
+            Some(Terminator::Return { .. }) => effects.exits = true,
+            Some(Terminator::IndirectCall { .. }) => effects.calls = true,
+            _ => {}
+        }
+        for (id, instruction) in &function.blocks[block].instructions {
+            match instruction {
+                Instruction::Load { address, .. } => effects.accesses.push(access(address, &definitions, iv, false)),
+                Instruction::Store { address, .. } => effects.accesses.push(access(address, &definitions, iv, true)),
+                Instruction::Call { .. } | Instruction::ExternalCall { .. } | Instruction::InlineAsm { .. } | Instruction::Atomic { .. } => {
+                    effects.calls = true;
+                }
+                Instruction::BinaryOp { op: BinaryOp::Div | BinaryOp::Rem, .. } if !function.unchecked.contains(id) => effects.traps = true,
+                _ => {}
+            }
+        }
+    }
+    effects
+}
+
+/// ブロックからループの外へ出うる分岐の行き先（ヘッダーの終了条件と、常に成り立つ条件で分けない側を除く）
+fn side_exits(function: &Function, analysis: &RangeAnalysis, shape: &LoopShape, block: BlockId) -> Vec<BlockId> {
+    if block == shape.header {
+        return Vec::new();
+    }
+    let Some(terminator) = function.blocks[&block].terminator.as_ref() else {
+        return Vec::new();
+    };
+    let targets = match terminator {
+        Terminator::BranchCond { condition, true_target, false_target, .. } => {
+            match analysis.operand_range(condition, block).and_then(|range| range.as_constant()) {
+                Some(0) => vec![*false_target],
+                Some(_) => vec![*true_target],
+                None => vec![*true_target, *false_target],
+            }
+        }
+        terminator => terminator.successors(),
+    };
+    targets.into_iter().filter(|target| !shape.body.contains(target)).collect()
+}
+
+/// アドレスが指す場所の先頭（`gep base[...]` で求めたアドレスなら `base`）と添字
+fn split_address<'a>(address: &'a Operand, definitions: &HashMap<RegisterId, &'a Instruction>) -> (&'a Operand, &'a [Operand]) {
+    match address {
+        Operand::Register(reg) => match definitions.get(reg) {
+            Some(Instruction::GetElementPtr { base, indices, .. }) => (base, indices),
+            _ => (address, &[]),
+        },
+        _ => (address, &[]),
+    }
+}
+
+/// アドレスを、読み書きする場所の表現にする（`gep base[iv]` で `base` がループ不変なら要素）
+fn access(address: &Operand, definitions: &HashMap<RegisterId, &Instruction>, iv: RegisterId, write: bool) -> Access {
+    let (base, indices) = split_address(address, definitions);
+    let invariant = !matches!(base, Operand::Register(base) if definitions.contains_key(base));
+    let current = matches!(indices, [Operand::Register(index)] if *index == iv);
+    Access { base: base.clone(), element: invariant && current, write }
+}
+
+fn independent(first: &Effects, second: &Effects, slots: &HashSet<RegisterId>) -> bool {
+    let touches = |effects: &Effects| effects.calls || !effects.accesses.is_empty();
+    if (first.calls && (touches(second) || second.traps)) || (second.calls && (touches(first) || first.traps)) {
+        return false;
+    }
+    if first.traps && second.traps {
+        return false;
+    }
+    first.accesses.iter().all(|a| {
+        second.accesses.iter().all(|b| !(a.write || b.write) || !may_alias(&a.base, &b.base, slots) || (a.element && b.element))
+    })
+}
+
+/// 2つの場所が重なりうるか（グローバル変数とスタックスロットは、別のものどうしなら重ならない）
+fn may_alias(a: &Operand, b: &Operand, slots: &HashSet<RegisterId>) -> bool {
+    let named = |operand: &Operand| match operand {
+        Operand::Global(_) => true,
+        Operand::Register(reg) => slots.contains(reg),
+        _ => false,
+    };
+    if named(a) && named(b) {
+        return same_operand(a, b);
+    }
+    true
+}
+
+/// `Alloca` で確保したスタックスロット
+fn stack_slots(function: &Function) -> HashSet<RegisterId> {
+    function
+        .blocks
+        .values()
+        .flat_map(|block| &block.instructions)
+        .filter_map(|(_, instruction)| match instruction {
+            Instruction::Alloca { result, .. } => Some(*result),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 2つ目のループの本体を1つ目のループの本体の後ろにつなぐ
+fn fuse(function: &mut Function, fusion: &Fusion) {
+    let Fusion { first, second, .. } = fusion;
+    let second_index = function.blocks[&second.header].parameters.iter().position(|(reg, _)| *reg == fusion.second_iv.param);
+    let carried: Vec<(RegisterId, _)> = function.blocks[&second.header]
+        .parameters
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(index, _)| Some(*index) != second_index)
+        .map(|(_, param)| param)
+        .collect();
+    let without_iv = |args: Vec<Operand>| -> Vec<Operand> {
+        args.into_iter().enumerate().filter(|(index, _)| Some(*index) != second_index).map(|(_, arg)| arg).collect()
+    };
+    let branch_args = |function: &Function, block: BlockId, target: BlockId| -> Vec<Operand> {
+        function.blocks[&block].terminator.as_ref().and_then(|terminator| induction::edge_args(terminator, target).first().map(|args| args.to_vec())).unwrap_or_default()
+    };
+    let second_init = without_iv(branch_args(function, second.preheader, second.header));
+    let first_next = branch_args(function, first.latch, first.header);
+    let second_next = without_iv(branch_args(function, second.latch, second.header));
+    
+    // 2つ目のループのループ変数以外のパラメータは、1つ目のループのヘッダーへ移す
+    if let Some(header) = function.blocks.get_mut(&first.header) {
+        header.parameters.extend(carried);
+        if let Some(Terminator::BranchCond { false_target, .. }) = header.terminator.as_mut() {
+            *false_target = fusion.second_exit;
+        }
+    }
+    if let Some(Terminator::Branch { args, .. }) = function.blocks.get_mut(&first.preheader).and_then(|block| block.terminator.as_mut()) {
+        args.extend(second_init);
+    }
+    if let Some(latch) = function.blocks.get_mut(&first.latch) {
+        latch.terminator = Some(Terminator::Branch { target: fusion.second_entry, args: Vec::new() });
+    }
+    if let Some(latch) = function.blocks.get_mut(&second.latch) {
+        latch.terminator = Some(Terminator::Branch { target: first.header, args: first_next.into_iter().chain(second_next).collect() });
+    }
+    for block in [second.header, second.preheader] {
+        for (id, _) in function.blocks.remove(&block).map(|block| block.instructions).unwrap_or_default() {
+            function.forget_instruction(id);
+        }
+    }
+    
+    let replacements = HashMap::from([
+        (fusion.second_iv.param, Operand::Register(fusion.first_iv.param)),
+        (fusion.second_condition, Operand::Register(fusion.first_condition)),
+    ]);
+    induction::replace_uses(function, &replacements);
+    
+    // 2つ目のループ変数の更新は、ラッチの引数から外したので使われなければ消す
+    let next = fusion.second_iv.next;
+    if !function.blocks.values().any(|block| block_uses(block).contains(&next)) {
+        induction::remove_instructions(function, |_, instruction| instruction.defined_register() == Some(next));
+    }
+}
+
+/// 融合したループの中で書き込まれない場所からのロードをプリヘッダーへ移し、移した数を返す
+fn hoist_invariant_loads(function: &mut Function, shape: &LoopShape) -> usize {
+    let cfg = Cfg::new(function);
+    let dominators = DominatorTree::new(&cfg);
+    let loops = LoopForest::new(&cfg, &dominators);
+    let Some(body) = loops.loops().iter().find(|lp| lp.header == shape.header).map(|lp| lp.body.clone()) else {
+        return 0;
+    };
+    let mut blocks: Vec<BlockId> = body.iter().copied().collect();
+    blocks.sort_by_key(|block| block.0);
+    let shape = LoopShape { header: shape.header, preheader: shape.preheader, latch: shape.latch, body: blocks };
+    let definitions = induction::loop_definitions(function, &shape);
+    let defined = defined_in(function, &shape);
+    
+    let mut calls = false;
+    let mut written = Vec::new();
+    for (_, instruction) in shape.body.iter().flat_map(|block| &function.blocks[block].instructions) {
+        match instruction {
+            Instruction::Store { address, .. } | Instruction::Atomic { address, .. } => written.push(split_address(address, &definitions).0.clone()),
+            Instruction::Call { .. } | Instruction::ExternalCall { .. } | Instruction::InlineAsm { .. } => calls = true,
+            _ => {}
+        }
+    }
+    if calls {
+        return 0;
+    }
+    // スロットとグローバル変数は常に読めるので、ループが一度も回らない場合に先に読んでもよい
+    let slots = stack_slots(function);
+    let hoistable = |address: &Operand| {
+        let always_valid = match address {
+            Operand::Global(_) => true,
+            Operand::Register(reg) => slots.contains(reg) && !defined.contains(reg),
+            _ => false,
+        };
+        always_valid && !written.iter().any(|base| may_alias(base, address, &slots))
+    };
+    
+    let mut hoisted: Vec<(Operand, RegisterId)> = Vec::new();
+    let mut replacements: HashMap<RegisterId, Operand> = HashMap::new();
+    for block_id in &shape.body {
+        let Some(block) = function.blocks.get_mut(block_id) else {
+            continue;
+        };
+        let mut moved = Vec::new();
+        block.instructions.retain(|(id, instruction)| match instruction {
+            Instruction::Load { address, result } if hoistable(address) => {
+                match hoisted.iter().find(|(hoisted, _)| same_operand(hoisted, address)) {
+                    Some((_, earlier)) => {
+                        replacements.insert(*result, Operand::Register(*earlier));
+                    }
+                    None => {
+                        hoisted.push((address.clone(), *result));
+                        moved.push((*id, instruction.clone()));
+                    }
+                }
+                false
+            }
+            _ => true,
+        });
+        if let Some(preheader) = function.blocks.get_mut(&shape.preheader) {
+            preheader.instructions.extend(moved);
+        }
+    }
+    induction::replace_uses(function, &replacements);
+    hoisted.len() + replacements.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::FunctionId;
+    use crate::core::ir_builder::IrBuilder;
+    use crate::core::types::Type;
+    
+    /// `for i in 0..100 { a[i] = b[i] + 1 }; for j in 0..100 { c[j] = a[j + offset] * k }` に相当する関数
+    fn adjacent_loops(module: &mut Module, offset: i64) -> Function {
+        let int = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        let mut builder = IrBuilder::new(Function::new(FunctionId(0), "f", int, int));
+        let [a, b, c] = [800, 800, 800].map(|size| builder.alloca(size, int));
+        let k = builder.alloca(8, int);
+        builder.store(Operand::Register(k), Operand::Literal(Literal::Int(2)));
+        
+        let counted = |builder: &mut IrBuilder, body: &dyn Fn(&mut IrBuilder, RegisterId)| {
+            let header = builder.create_block();
+            let body_block = builder.create_block();
+            let exit = builder.create_block();
+            builder.br(header, vec![Operand::Literal(Literal::Int(0))]);
+            let i = builder.add_block_param(header, int);
+            builder.position_at_end(header);
+            let condition = builder.binary(BinaryOp::Lt, Operand::Register(i), Operand::Literal(Literal::Int(100)), bool_type);
+            builder.cond_br(Operand::Register(condition), body_block, exit);
+            builder.position_at_end(body_block);
+            body(builder, i);
+            let next = builder.binary(BinaryOp::Add, Operand::Register(i), Operand::Literal(Literal::Int(1)), int);
+            builder.br(header, vec![Operand::Register(next)]);
+            builder.position_at_end(exit);
+        };
+        counted(&mut builder, &|builder, i| {
+            let source = builder.gep(Operand::Register(b), vec![Operand::Register(i)], int);
+            let value = builder.load(Operand::Register(source), int);
+            let value = builder.binary(BinaryOp::Add, Operand::Register(value), Operand::Literal(Literal::Int(1)), int);
+            let target = builder.gep(Operand::Register(a), vec![Operand::Register(i)], int);
+            builder.store(Operand::Register(target), Operand::Register(value));
+        });
+        counted(&mut builder, &|builder, j| {
+            let index = match offset {
+                0 => Operand::Register(j),
+                _ => Operand::Register(builder.binary(BinaryOp::Add, Operand::Register(j), Operand::Literal(Literal::Int(offset)), int)),
+            };
+            let source = builder.gep(Operand::Register(a), vec![index], int);
+            let value = builder.load(Operand::Register(source), int);
+            let scale = builder.load(Operand::Register(k), int);
+            let value = builder.binary(BinaryOp::Mul, Operand::Register(value), Operand::Register(scale), int);
+            let target = builder.gep(Operand::Register(c), vec![Operand::Register(j)], int);
+            builder.store(Operand::Register(target), Operand::Register(value));
+        });
+        builder.ret(Some(Operand::Literal(Literal::Int(0))));
+        builder.finish()
+    }
+    
+    #[test]
+    fn test_adjacent_loops_are_fused() {
+        let mut module = Module::new("test");
+        let function = adjacent_loops(&mut module, 0);
+        module.add_function(function);
+        
+        let stats = optimize(&mut module);
+        assert_eq!(stats, FusionStats { fused: 1, hoisted_loads: 1 });
+        crate::core::eir::verify_after(&module, "ループ融合").unwrap();
+        let text = module.to_string();
+        // 比較は1つだけになり、2つ目のループの本体は1つ目のループ変数 `%4` で添字を求める
+        assert_eq!(text.matches("= lt ").count(), 1, "{}", text);
+        assert!(text.contains("= gep %2[%4]"), "{}", text);
+        assert!(!text.contains("block_4"), "{}", text);
+        // `k` のロードはループの前で1回だけ行う
+        let entry = &text[..text.find("block_1").unwrap_or(0)];
+        assert!(entry.contains("= load %3"), "{}", text);
+    }
+    
+    #[test]
+    fn test_dependence_on_other_iteration_prevents_fusion() {
+        // 2つ目のループが `a[j + 1]` を読むと、融合後はまだ書かれていない要素を読むことになる
+        let mut module = Module::new("test");
+        let function = adjacent_loops(&mut module, 1);
+        module.add_function(function);
+        
+        assert_eq!(optimize(&mut module), FusionStats::default());
+        assert_eq!(module.to_string().matches("= lt ").count(), 2);
+    }
+    
+    #[test]
+    fn test_for_loops_from_source_are_fused() {
+        // ModuleBuilder が下げた `for` は、範囲のイテレータを展開し、範囲の検査が常に成り立つと分かれば融合する
+        let source = "fn main() -> Int { \
+            let mut a = [0, 0, 0, 0, 0]; let b = [1, 2, 3, 4, 5]; let mut c = [0, 0, 0, 0, 0]; \
+            for i in 0..5 { a[i] = b[i] + 1; } \
+            for i in 0..5 { c[i] = a[i] * 2; } \
+            c[0] + c[4] }";
+        let mut module = crate::core::eir_builder::build_test_module("test", source).unwrap();
+        assert_eq!(optimize(&mut module).fused, 1, "{}", module);
+        crate::core::eir::verify_after(&module, "ループ融合").unwrap();
+        let mut engine = crate::backend::jit::JitEngine::new().unwrap();
+        assert_eq!(engine.run(&module).unwrap(), crate::backend::jit::JitValue::Int(16));
+        
+        // 2つ目のループが1つ目のループのまだ書いていない要素を読むなら融合しない
+        let source = source.replace("a[i] * 2", "a[4 - i] * 2");
+        let mut module = crate::core::eir_builder::build_test_module("test", &source).unwrap();
+        assert_eq!(optimize(&mut module).fused, 0, "{}", module);
+    }
+}
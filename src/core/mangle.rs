@@ -0,0 +1,312 @@
+use std::fmt;
+
+/// マングリングしたシンボル名の接頭辞
+pub const PREFIX: &str = "_E";
+
+/// コード生成が出力するシンボル
+///
+/// モジュールパス、ジェネリック関数の型引数、オーバーロードを区別する引数の型を
+/// 次の規則でCの識別子に使える文字だけの名前に符号化する。
+///
+/// ```text
+/// <symbol> ::= "_E" <path> [ "I" <type>+ "E" ] [ "S" <type>* "E" ]
+/// <path>   ::= <ident> | "N" <ident> <ident>+ "E"
+/// <ident>  ::= <長さ> <英数字と `_`> | "u" <長さ> <エスケープした文字列>
+/// <type>   ::= <path> [ "I" <type>+ "E" ]
+/// ```
+///
+/// 英数字と `_` 以外を含む識別子や数字で始まる識別子は `u` 形式にし、`_` を `__`、そのほかの文字を `_<16進のコードポイント>_` と書く。
+/// 例えば `geometry::area` は `_EN8geometry4areaE`、`max<int>` は `_E3maxI3intE` になる。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// モジュールパスと名前（`geometry::area` なら `["geometry", "area"]`）
+    pub path: Vec<String>,
+    /// ジェネリック関数の型引数
+    pub type_args: Vec<TypeName>,
+    /// オーバーロードを区別する引数の型（オーバーロードされていなければ `None`）
+    pub params: Option<Vec<TypeName>>,
+}
+
+/// シンボルに埋め込む型の名前（`Vec<int>` なら `path` が `["Vec"]`、`args` が `[int]`）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeName {
+    /// 型のモジュールパスと名前
+    pub path: Vec<String>,
+    /// 型引数
+    pub args: Vec<TypeName>,
+}
+
+impl Symbol {
+    /// `geometry::area` のような修飾名からシンボルを作成
+    pub fn from_qualified(name: &str) -> Self {
+        Self {
+            path: split_path(name),
+            type_args: Vec::new(),
+            params: None,
+        }
+    }
+    
+    /// ジェネリック関数の型引数を設定
+    pub fn with_type_args(mut self, type_args: Vec<TypeName>) -> Self {
+        self.type_args = type_args;
+        self
+    }
+    
+    /// オーバーロードを区別する引数の型を設定
+    pub fn with_params(mut self, params: Vec<TypeName>) -> Self {
+        self.params = Some(params);
+        self
+    }
+    
+    /// マングリングしたシンボル名
+    pub fn mangle(&self) -> String {
+        let mut out = PREFIX.to_string();
+        write_path(&mut out, &self.path);
+        if !self.type_args.is_empty() {
+            out.push('I');
+            self.type_args.iter().for_each(|arg| arg.write(&mut out));
+            out.push('E');
+        }
+        if let Some(params) = &self.params {
+            out.push('S');
+            params.iter().for_each(|param| param.write(&mut out));
+            out.push('E');
+        }
+        out
+    }
+    
+    /// マングリングしたシンボル名を読む（規則に合わなければ `None`）
+    pub fn parse(mangled: &str) -> Option<Self> {
+        let mut reader = Reader { rest: mangled.strip_prefix(PREFIX)? };
+        let path = reader.path()?;
+        let type_args = if reader.eat('I') { reader.types(true)? } else { Vec::new() };
+        let params = if reader.eat('S') { Some(reader.types(false)?) } else { None };
+        if !reader.rest.is_empty() {
+            return None;
+        }
+        Some(Self { path, type_args, params })
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.join("::"))?;
+        write_type_args(f, &self.type_args)?;
+        if let Some(params) = &self.params {
+            let params: Vec<String> = params.iter().map(ToString::to_string).collect();
+            write!(f, "({})", params.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl TypeName {
+    /// `int` や `util::Point` のような修飾名から型の名前を作成
+    pub fn new(name: &str) -> Self {
+        Self {
+            path: split_path(name),
+            args: Vec::new(),
+        }
+    }
+    
+    /// 型引数を設定
+    pub fn with_args(mut self, args: Vec<TypeName>) -> Self {
+        self.args = args;
+        self
+    }
+    
+    fn write(&self, out: &mut String) {
+        write_path(out, &self.path);
+        if !self.args.is_empty() {
+            out.push('I');
+            self.args.iter().for_each(|arg| arg.write(out));
+            out.push('E');
+        }
+    }
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.join("::"))?;
+        write_type_args(f, &self.args)
+    }
+}
+
+/// マングリングしたシンボル名を読みやすい名前に戻す（規則に合わなければ `None`）
+pub fn demangle(symbol: &str) -> Option<String> {
+    Symbol::parse(symbol).map(|symbol| symbol.to_string())
+}
+
+/// テキスト中のマングリングしたシンボル名をすべて読みやすい名前に置き換える
+///
+/// プロファイラやデバッガの出力をそのまま通せるよう、識別子の区切りごとに置き換え、
+/// 規則に合わない識別子はそのまま残す。
+pub fn demangle_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PREFIX) {
+        // 識別子の途中に現れた `_E` は無視する
+        let inside_identifier = rest[..start].chars().next_back().is_some_and(is_identifier_char);
+        let end = rest[start..].find(|c: char| !is_identifier_char(c)).map_or(rest.len(), |len| start + len);
+        out.push_str(&rest[..start]);
+        match demangle(&rest[start..end]) {
+            Some(name) if !inside_identifier => out.push_str(&name),
+            _ => out.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn split_path(name: &str) -> Vec<String> {
+    name.split("::").map(str::to_string).collect()
+}
+
+fn write_type_args(f: &mut fmt::Formatter<'_>, args: &[TypeName]) -> fmt::Result {
+    if args.is_empty() {
+        return Ok(());
+    }
+    let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+    write!(f, "<{}>", args.join(", "))
+}
+
+fn write_path(out: &mut String, path: &[String]) {
+    if path.len() == 1 {
+        write_ident(out, &path[0]);
+    } else {
+        out.push('N');
+        path.iter().for_each(|segment| write_ident(out, segment));
+        out.push('E');
+    }
+}
+
+fn write_ident(out: &mut String, ident: &str) {
+    // 先頭が数字だと長さと区別できないので、`u` 形式でエスケープする
+    let leading_digit = ident.starts_with(|c: char| c.is_ascii_digit());
+    if !leading_digit && ident.chars().all(is_identifier_char) {
+        out.push_str(&format!("{}{}", ident.len(), ident));
+        return;
+    }
+    let mut escaped = String::new();
+    for (i, c) in ident.chars().enumerate() {
+        match c {
+            '_' => escaped.push_str("__"),
+            c if c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()) => escaped.push(c),
+            c => escaped.push_str(&format!("_{:x}_", c as u32)),
+        }
+    }
+    out.push_str(&format!("u{}{}", escaped.len(), escaped));
+}
+
+/// マングリングしたシンボル名の読み取り
+struct Reader<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Reader<'a> {
+    fn eat(&mut self, c: char) -> bool {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    fn path(&mut self) -> Option<Vec<String>> {
+        if !self.eat('N') {
+            return Some(vec![self.ident()?]);
+        }
+        let mut path = vec![self.ident()?];
+        while !self.eat('E') {
+            path.push(self.ident()?);
+        }
+        (path.len() > 1).then_some(path)
+    }
+    
+    /// `E` までの型の並び（`non_empty` なら1個以上必要）
+    fn types(&mut self, non_empty: bool) -> Option<Vec<TypeName>> {
+        let mut types = Vec::new();
+        while !self.eat('E') {
+            let path = self.path()?;
+            let args = if self.eat('I') { self.types(true)? } else { Vec::new() };
+            types.push(TypeName { path, args });
+        }
+        (!non_empty || !types.is_empty()).then_some(types)
+    }
+    
+    fn ident(&mut self) -> Option<String> {
+        let escaped = self.eat('u');
+        let digits = self.rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(self.rest.len());
+        let len: usize = self.rest[..digits].parse().ok().filter(|&len| len > 0)?;
+        let text = self.rest.get(digits..digits + len)?;
+        self.rest = &self.rest[digits + len..];
+        if !escaped {
+            return Some(text.to_string());
+        }
+        
+        let mut ident = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '_' {
+                ident.push(c);
+                continue;
+            }
+            let mut hex = String::new();
+            for c in chars.by_ref() {
+                if c == '_' {
+                    break;
+                }
+                hex.push(c);
+            }
+            if hex.is_empty() {
+                ident.push('_');
+            } else {
+                ident.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+        }
+        Some(ident)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_mangle_round_trip() {
+        let area = Symbol::from_qualified("geometry::area");
+        assert_eq!(area.mangle(), "_EN8geometry4areaE");
+        assert_eq!(demangle("_EN8geometry4areaE").as_deref(), Some("geometry::area"));
+        
+        let generic = Symbol::from_qualified("max")
+            .with_type_args(vec![TypeName::new("Vec").with_args(vec![TypeName::new("int")])])
+            .with_params(vec![TypeName::new("util::Point"), TypeName::new("string")]);
+        assert_eq!(generic.mangle(), "_E3maxI3VecI3intEESN4util5PointE6stringE");
+        assert_eq!(Symbol::parse(&generic.mangle()), Some(generic.clone()));
+        assert_eq!(generic.to_string(), "max<Vec<int>>(util::Point, string)");
+        
+        // 英数字以外を含む識別子もCの識別子として使える形に符号化する
+        let unicode = Symbol::from_qualified("図形::面積_計算");
+        let mangled = unicode.mangle();
+        assert!(mangled.chars().all(is_identifier_char));
+        assert_eq!(demangle(&mangled).as_deref(), Some("図形::面積_計算"));
+        assert_eq!(demangle(&Symbol::from_qualified("1st").mangle()).as_deref(), Some("1st"));
+    }
+    
+    #[test]
+    fn test_demangle_text() {
+        assert_eq!(demangle("_E3ma"), None);
+        assert_eq!(demangle("main"), None);
+        assert_eq!(
+            demangle_text("  12.5%  _EN8geometry4areaE+0x1f  eidos_EN1a1bE  _E4main"),
+            "  12.5%  geometry::area+0x1f  eidos_EN1a1bE  main"
+        );
+    }
+}
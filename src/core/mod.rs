@@ -1,7 +1,32 @@
 pub mod error;
+pub mod error_codes;
+pub mod arena;
+pub mod analysis;
+pub mod call_graph;
 pub mod ast;
+pub mod ast_dump;
 pub mod types;
+pub mod bigint;
 pub mod eir;
+pub mod eirc;
+pub mod eir_builder;
+pub mod ir_builder;
+pub mod eir_verifier;
+pub mod eir_linker;
+pub mod mangle;
 pub mod symbol;
+pub mod intern;
+pub mod session;
+pub mod limits;
+pub mod source_map;
+pub mod coverage;
+pub mod range_analysis;
+pub mod induction;
+pub mod loop_fusion;
+pub mod size_opt;
+pub mod dead_functions;
 
-pub use error::{EidosError, Result, SourceLocation}; 
\ No newline at end of file
+pub use error::{EidosError, Result, SourceLocation};
+pub use intern::Name;
+pub use source_map::{SourceMap, Span};
+pub use error_codes::{ErrorCode, ExitCode}; 
\ No newline at end of file
@@ -0,0 +1,943 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::core::analysis::{Cfg, DominatorTree};
+use crate::core::eir::{
+    BinaryOp, BlockId, Function, Instruction, InstructionId, Literal, Module, Operand, RegisterId, Terminator, UnaryOp,
+    ASSERTION_TAG,
+};
+use crate::core::error::SourceLocation;
+use crate::core::types::{NumericType, TypeKind};
+
+/// 値域が広がり続けるレジスタを、型の上限・下限まで一気に広げるまでの更新回数
+const WIDENING_THRESHOLD: u32 = 3;
+
+/// 拡大の後に値域を狭め直す回数
+const NARROWING_PASSES: u32 = 2;
+
+/// 整数の値域（両端を含む）
+///
+/// 真偽値は 0（偽）と 1（真）の値域として扱う。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueRange {
+    pub min: i64,
+    pub max: i64,
+}
+
+impl ValueRange {
+    pub const FULL: ValueRange = ValueRange { min: i64::MIN, max: i64::MAX };
+    const BOOL: ValueRange = ValueRange { min: 0, max: 1 };
+    
+    pub fn new(min: i64, max: i64) -> Self {
+        Self { min, max }
+    }
+    
+    pub fn constant(value: i64) -> Self {
+        Self { min: value, max: value }
+    }
+    
+    /// 値が1つに決まっていれば、その値
+    pub fn as_constant(&self) -> Option<i64> {
+        (self.min == self.max).then_some(self.min)
+    }
+    
+    pub fn contains(&self, value: i64) -> bool {
+        self.min <= value && value <= self.max
+    }
+    
+    pub fn union(&self, other: &ValueRange) -> ValueRange {
+        ValueRange::new(self.min.min(other.min), self.max.max(other.max))
+    }
+    
+    /// 共通部分（空なら `None`）
+    pub fn intersect(&self, other: &ValueRange) -> Option<ValueRange> {
+        let range = ValueRange::new(self.min.max(other.min), self.max.min(other.max));
+        (range.min <= range.max).then_some(range)
+    }
+    
+    /// 値域のすべての値を表せる、最も狭い符号付き整数型
+    pub fn narrowest_signed_type(&self) -> NumericType {
+        [NumericType::I8, NumericType::I16, NumericType::I32]
+            .into_iter()
+            .find(|ty| ty.contains(self.min) && ty.contains(self.max))
+            .unwrap_or(NumericType::I64)
+    }
+    
+    fn from_bool(value: Option<bool>) -> ValueRange {
+        match value {
+            Some(value) => ValueRange::constant(value as i64),
+            None => ValueRange::BOOL,
+        }
+    }
+}
+
+impl fmt::Display for ValueRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.min, self.max)
+    }
+}
+
+/// 分岐の条件から分かる、レジスタと別のオペランドの大小関係（`register op other` が成り立つ）
+#[derive(Debug, Clone)]
+struct Constraint {
+    register: RegisterId,
+    op: BinaryOp,
+    other: Operand,
+}
+
+/// 関数の整数レジスタの値域解析
+///
+/// 整数型と真偽値のレジスタについて、取りうる値の範囲を制御フローグラフ上の不動点反復で求める。
+/// 条件分岐の先では条件の比較からレジスタの範囲を絞り込み（`if i < 10` の本体では `i <= 9`）、
+/// その絞り込みは分岐先が支配するブロックにも及ぶ。
+/// ループで広がり続ける範囲は、何度か更新した後に型の上限・下限まで広げて反復を打ち切り、その後に求め直して狭める。
+/// オーバーフローしうる演算の結果は、型で表せるすべての値とみなす。
+pub struct RangeAnalysis {
+    /// 求めた値域（追跡しない型のレジスタは含まない）
+    ranges: HashMap<RegisterId, ValueRange>,
+    /// 整数型・真偽値のレジスタの、型で表せる範囲
+    bounds: HashMap<RegisterId, ValueRange>,
+    /// 命令かブロックのパラメータで定義されるレジスタ（それ以外はパラメータ）
+    defined: HashSet<RegisterId>,
+    /// レジスタを定義する比較命令（分岐の条件の解釈に使う）
+    comparisons: HashMap<RegisterId, (BinaryOp, Operand, Operand)>,
+    /// ブロックに入った時点で成り立つ制約（先行ブロックが1つで、その分岐の条件から分かるもの）
+    constraints: HashMap<BlockId, Vec<Constraint>>,
+    cfg: Cfg,
+    dominators: DominatorTree,
+}
+
+impl RangeAnalysis {
+    /// 関数の値域を求める（レジスタの型はモジュールから引く）
+    pub fn new(module: &Module, function: &Function) -> Self {
+        let cfg = Cfg::new(function);
+        let dominators = DominatorTree::new(&cfg);
+        let bounds = function
+            .register_types
+            .iter()
+            .filter_map(|(reg, type_id)| type_bounds(module.get_type(*type_id).map(|ty| &ty.kind)?).map(|range| (*reg, range)))
+            .collect();
+        let mut analysis = Self {
+            ranges: HashMap::new(),
+            bounds,
+            defined: HashSet::new(),
+            comparisons: HashMap::new(),
+            constraints: HashMap::new(),
+            cfg,
+            dominators,
+        };
+        analysis.collect_definitions(function);
+        analysis.solve(function);
+        analysis
+    }
+    
+    /// レジスタの値域（関数全体で成り立つもの。追跡しない型のレジスタは `None`）
+    pub fn range(&self, reg: RegisterId) -> Option<ValueRange> {
+        match self.ranges.get(&reg) {
+            Some(range) => Some(*range),
+            None if !self.defined.contains(&reg) => self.bounds.get(&reg).copied(),
+            None => None,
+        }
+    }
+    
+    /// `block` の中で使われるオペランドの値域（支配する分岐の条件で絞り込む）
+    pub fn operand_range(&self, operand: &Operand, block: BlockId) -> Option<ValueRange> {
+        match operand {
+            Operand::Register(reg) => {
+                let mut range = self.range(*reg)?;
+                let mut current = Some(block);
+                while let Some(b) = current {
+                    for constraint in self.constraints.get(&b).into_iter().flatten() {
+                        if constraint.register == *reg {
+                            range = self.refine(range, constraint);
+                        }
+                    }
+                    current = self.dominators.immediate_dominator(b).filter(|idom| *idom != b);
+                }
+                Some(range)
+            }
+            Operand::Literal(literal) => literal_range(literal),
+            _ => None,
+        }
+    }
+    
+    fn collect_definitions(&mut self, function: &Function) {
+        for block in function.blocks.values() {
+            self.defined.extend(block.parameters.iter().map(|(reg, _)| *reg));
+            for (_, instruction) in &block.instructions {
+                if let Some(reg) = instruction.defined_register() {
+                    self.defined.insert(reg);
+                }
+                if let Instruction::BinaryOp { op, lhs, rhs, result } = instruction {
+                    if is_comparison(*op) {
+                        self.comparisons.insert(*result, (*op, lhs.clone(), rhs.clone()));
+                    }
+                }
+            }
+        }
+        for block in function.blocks.keys() {
+            let preds: HashSet<BlockId> = self.cfg.predecessors(*block).iter().copied().collect();
+            if preds.len() != 1 {
+                continue;
+            }
+            let pred = *preds.iter().next().expect("先行ブロックは1つ");
+            if pred == *block {
+                continue;
+            }
+            let constraints = self.edge_constraints(function, pred, *block);
+            if !constraints.is_empty() {
+                self.constraints.insert(*block, constraints);
+            }
+        }
+    }
+    
+    /// `from` から `to` へ分岐したときに成り立つ制約
+    fn edge_constraints(&self, function: &Function, from: BlockId, to: BlockId) -> Vec<Constraint> {
+        let Some(Terminator::BranchCond { condition: Operand::Register(condition), true_target, false_target, .. }) =
+            function.blocks.get(&from).and_then(|block| block.terminator.as_ref())
+        else {
+            return Vec::new();
+        };
+        if true_target == false_target {
+            return Vec::new();
+        }
+        let Some((op, lhs, rhs)) = self.comparisons.get(condition) else {
+            return Vec::new();
+        };
+        let op = if to == *true_target { *op } else { negate(*op) };
+        let mut constraints = Vec::new();
+        if let Operand::Register(register) = lhs {
+            constraints.push(Constraint { register: *register, op, other: rhs.clone() });
+        }
+        if let Operand::Register(register) = rhs {
+            constraints.push(Constraint { register: *register, op: swap(op), other: lhs.clone() });
+        }
+        constraints
+    }
+    
+    /// 制約で値域を絞り込む（相手の値域は絞り込む前のものを使う）
+    fn refine(&self, range: ValueRange, constraint: &Constraint) -> ValueRange {
+        let other = match &constraint.other {
+            Operand::Register(reg) => self.range(*reg),
+            Operand::Literal(literal) => literal_range(literal),
+            _ => None,
+        };
+        let Some(other) = other else {
+            return range;
+        };
+        let limit = match constraint.op {
+            BinaryOp::Lt => ValueRange::new(i64::MIN, other.max.saturating_sub(1)),
+            BinaryOp::Le => ValueRange::new(i64::MIN, other.max),
+            BinaryOp::Gt => ValueRange::new(other.min.saturating_add(1), i64::MAX),
+            BinaryOp::Ge => ValueRange::new(other.min, i64::MAX),
+            BinaryOp::Eq => other,
+            BinaryOp::Ne => match other.as_constant() {
+                Some(value) if value == range.min && range.min < range.max => ValueRange::new(range.min + 1, range.max),
+                Some(value) if value == range.max && range.min < range.max => ValueRange::new(range.min, range.max - 1),
+                _ => range,
+            },
+            _ => range,
+        };
+        // 矛盾する（到達しない）場合は絞り込まない
+        range.intersect(&limit).unwrap_or(range)
+    }
+    
+    /// 値域が変わらなくなるまで、到達できるブロックを逆後行順に評価する
+    fn solve(&mut self, function: &Function) {
+        let order: Vec<BlockId> = self.cfg.reverse_post_order().collect();
+        let mut updates: HashMap<RegisterId, u32> = HashMap::new();
+        while self.sweep(function, &order, false, &mut updates) {}
+        // 拡大で型の上限まで広げた値域を、ループの条件などで求め直して狭める
+        for _ in 0..NARROWING_PASSES {
+            if !self.sweep(function, &order, true, &mut updates) {
+                break;
+            }
+        }
+    }
+    
+    /// すべてのブロックを1回ずつ評価し、値域が変わったかを返す（`narrowing` では値域を狭める方向にだけ変える）
+    fn sweep(&mut self, function: &Function, order: &[BlockId], narrowing: bool, updates: &mut HashMap<RegisterId, u32>) -> bool {
+        let mut changed = false;
+        for block_id in order {
+            let Some(block) = function.blocks.get(block_id) else {
+                continue;
+            };
+            // ブロックのパラメータは、各先行ブロックの分岐が渡す引数の和
+            for (index, (reg, _)) in block.parameters.iter().enumerate() {
+                let mut incoming = None;
+                for pred in self.cfg.predecessors(*block_id) {
+                    if !self.cfg.is_reachable(*pred) {
+                        continue;
+                    }
+                    let Some(arg) = branch_argument(function, *pred, *block_id, index) else {
+                        incoming = Some(ValueRange::FULL);
+                        break;
+                    };
+                    match self.edge_operand_range(function, arg, *pred, *block_id) {
+                        Some(range) => incoming = Some(incoming.map_or(range, |r: ValueRange| r.union(&range))),
+                        None if self.is_pending(arg) => {}
+                        None => {
+                            incoming = Some(ValueRange::FULL);
+                            break;
+                        }
+                    }
+                }
+                if let Some(range) = incoming {
+                    changed |= self.apply(*reg, range, narrowing, updates);
+                }
+            }
+            for (_, instruction) in &block.instructions {
+                let Some(reg) = instruction.defined_register() else {
+                    continue;
+                };
+                if !self.bounds.contains_key(&reg) {
+                    continue;
+                }
+                if let Some(range) = self.evaluate(function, instruction, *block_id) {
+                    changed |= self.apply(reg, range, narrowing, updates);
+                }
+            }
+        }
+        changed
+    }
+    
+    fn apply(&mut self, reg: RegisterId, range: ValueRange, narrowing: bool, updates: &mut HashMap<RegisterId, u32>) -> bool {
+        if !narrowing {
+            return self.update(reg, range, updates);
+        }
+        // 不動点から求め直した値域と今の値域の共通部分も、値のとりうる範囲を含む
+        let Some(old) = self.ranges.get(&reg).copied() else {
+            return false;
+        };
+        match old.intersect(&range) {
+            Some(new) if new != old => {
+                self.ranges.insert(reg, new);
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// まだ値域を求めていない（後で求める）レジスタか
+    fn is_pending(&self, operand: &Operand) -> bool {
+        matches!(operand, Operand::Register(reg) if self.defined.contains(reg) && !self.ranges.contains_key(reg) && self.bounds.contains_key(reg))
+    }
+    
+    /// 分岐の引数の値域（分岐の条件による絞り込みを含む）
+    fn edge_operand_range(&self, function: &Function, operand: &Operand, from: BlockId, to: BlockId) -> Option<ValueRange> {
+        let mut range = self.operand_range(operand, from)?;
+        if let Operand::Register(reg) = operand {
+            for constraint in self.edge_constraints(function, from, to) {
+                if constraint.register == *reg {
+                    range = self.refine(range, &constraint);
+                }
+            }
+        }
+        Some(range)
+    }
+    
+    /// 値域を広げ、変わったかを返す（型の範囲を超えた分は型の範囲に収める）
+    fn update(&mut self, reg: RegisterId, range: ValueRange, updates: &mut HashMap<RegisterId, u32>) -> bool {
+        let bounds = self.bounds.get(&reg).copied().unwrap_or(ValueRange::FULL);
+        let range = if bounds.min <= range.min && range.max <= bounds.max { range } else { bounds };
+        let Some(old) = self.ranges.get(&reg).copied() else {
+            self.ranges.insert(reg, range);
+            return true;
+        };
+        let mut new = old.union(&range);
+        if new == old {
+            return false;
+        }
+        let count = updates.entry(reg).or_default();
+        *count += 1;
+        if *count > WIDENING_THRESHOLD {
+            if new.min < old.min {
+                new.min = bounds.min;
+            }
+            if new.max > old.max {
+                new.max = bounds.max;
+            }
+        }
+        self.ranges.insert(reg, new);
+        true
+    }
+    
+    /// 命令の結果の値域（オペランドの値域がまだ分からなければ `None`）
+    fn evaluate(&self, function: &Function, instruction: &Instruction, block: BlockId) -> Option<ValueRange> {
+        let bounds = instruction.defined_register().and_then(|reg| self.bounds.get(&reg).copied())?;
+        let operand = |operand: &Operand| -> Option<Option<ValueRange>> {
+            if self.is_pending(operand) {
+                return None;
+            }
+            Some(self.operand_range(operand, block))
+        };
+        let range = match instruction {
+            Instruction::BinaryOp { op, lhs, rhs, .. } => match (operand(lhs)?, operand(rhs)?) {
+                (Some(a), Some(b)) => binary_range(*op, a, b),
+                _ if is_comparison(*op) || matches!(op, BinaryOp::And | BinaryOp::Or) => Some(ValueRange::BOOL),
+                _ => None,
+            },
+            Instruction::UnaryOp { op, operand: value, .. } => operand(value)?.and_then(|a| unary_range(*op, a)),
+            Instruction::Cast { value, .. } => operand(value)?,
+            Instruction::Select { condition, true_value, false_value, .. } => {
+                let (t, f) = (operand(true_value)?, operand(false_value)?);
+                match operand(condition)?.and_then(|c| c.as_constant()) {
+                    Some(0) => f,
+                    Some(_) => t,
+                    None => t.zip(f).map(|(t, f)| t.union(&f)),
+                }
+            }
+            Instruction::Phi { incoming, .. } => {
+                let mut range: Option<ValueRange> = None;
+                for (value, pred) in incoming {
+                    if self.is_pending(value) {
+                        continue;
+                    }
+                    let Some(incoming) = self.edge_operand_range(function, value, *pred, block) else {
+                        return Some(bounds);
+                    };
+                    range = Some(range.map_or(incoming, |r| r.union(&incoming)));
+                }
+                range
+            }
+            _ => None,
+        };
+        Some(range.unwrap_or(bounds))
+    }
+}
+
+/// 整数型・真偽値で表せる範囲（追跡しない型は `None`。`u64` は `i64` に収まらないので追跡しない）
+fn type_bounds(kind: &TypeKind) -> Option<ValueRange> {
+    match kind {
+        TypeKind::Bool => Some(ValueRange::BOOL),
+        TypeKind::Int => Some(ValueRange::FULL),
+        TypeKind::Numeric(numeric) if numeric.is_float() || *numeric == NumericType::U64 => None,
+        TypeKind::Numeric(numeric) if numeric.is_signed() => {
+            let max = numeric.max_value()? as i64;
+            Some(ValueRange::new(-max - 1, max))
+        }
+        TypeKind::Numeric(numeric) => Some(ValueRange::new(0, numeric.max_value()? as i64)),
+        _ => None,
+    }
+}
+
+fn literal_range(literal: &Literal) -> Option<ValueRange> {
+    match literal {
+        Literal::Int(value) => Some(ValueRange::constant(*value)),
+        Literal::Bool(value) => Some(ValueRange::constant(*value as i64)),
+        _ => None,
+    }
+}
+
+/// 分岐が `to` のブロックのパラメータ `index` に渡す引数
+fn branch_argument(function: &Function, from: BlockId, to: BlockId, index: usize) -> Option<&Operand> {
+    let args = match function.blocks.get(&from)?.terminator.as_ref()? {
+        Terminator::Branch { target, args } if *target == to => args,
+        Terminator::BranchCond { true_target, true_args, .. } if *true_target == to => true_args,
+        Terminator::BranchCond { false_target, false_args, .. } if *false_target == to => false_args,
+        _ => return None,
+    };
+    args.get(index)
+}
+
+fn is_comparison(op: BinaryOp) -> bool {
+    matches!(op, BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge)
+}
+
+/// 比較の否定（`a < b` でない ⇔ `a >= b`）
+fn negate(op: BinaryOp) -> BinaryOp {
+    match op {
+        BinaryOp::Eq => BinaryOp::Ne,
+        BinaryOp::Ne => BinaryOp::Eq,
+        BinaryOp::Lt => BinaryOp::Ge,
+        BinaryOp::Le => BinaryOp::Gt,
+        BinaryOp::Gt => BinaryOp::Le,
+        BinaryOp::Ge => BinaryOp::Lt,
+        other => other,
+    }
+}
+
+/// 比較の左右の入れ替え（`a < b` ⇔ `b > a`）
+fn swap(op: BinaryOp) -> BinaryOp {
+    match op {
+        BinaryOp::Lt => BinaryOp::Gt,
+        BinaryOp::Le => BinaryOp::Ge,
+        BinaryOp::Gt => BinaryOp::Lt,
+        BinaryOp::Ge => BinaryOp::Le,
+        other => other,
+    }
+}
+
+/// 値域どうしの比較の結果（どちらとも言えなければ `None`）
+fn compare(op: BinaryOp, a: ValueRange, b: ValueRange) -> Option<bool> {
+    match op {
+        BinaryOp::Lt if a.max < b.min => Some(true),
+        BinaryOp::Lt if a.min >= b.max => Some(false),
+        BinaryOp::Le if a.max <= b.min => Some(true),
+        BinaryOp::Le if a.min > b.max => Some(false),
+        BinaryOp::Gt => compare(BinaryOp::Lt, b, a),
+        BinaryOp::Ge => compare(BinaryOp::Le, b, a),
+        BinaryOp::Eq if a.as_constant().is_some() && a == b => Some(true),
+        BinaryOp::Eq if a.intersect(&b).is_none() => Some(false),
+        BinaryOp::Ne => compare(BinaryOp::Eq, a, b).map(|equal| !equal),
+        _ => None,
+    }
+}
+
+/// 二項演算の結果の値域（オーバーフローしうる場合は `None`）
+fn binary_range(op: BinaryOp, a: ValueRange, b: ValueRange) -> Option<ValueRange> {
+    match op {
+        BinaryOp::Add => Some(ValueRange::new(a.min.checked_add(b.min)?, a.max.checked_add(b.max)?)),
+        BinaryOp::Sub => Some(ValueRange::new(a.min.checked_sub(b.max)?, a.max.checked_sub(b.min)?)),
+        BinaryOp::Mul => corners(a, b, i64::checked_mul),
+        BinaryOp::Div => {
+            // 除数の負の部分と正の部分ごとに、商は両端の組み合わせで最大・最小になる
+            let mut result: Option<ValueRange> = None;
+            for part in [ValueRange::new(b.min, -1), ValueRange::new(1, b.max)] {
+                if let Some(part) = part.intersect(&b) {
+                    let quotient = corners(a, part, i64::checked_div)?;
+                    result = Some(result.map_or(quotient, |r| r.union(&quotient)));
+                }
+            }
+            result
+        }
+        BinaryOp::Rem => {
+            // 余りの絶対値は除数の絶対値より小さく、符号は被除数に従う
+            let limit = b.min.checked_abs()?.max(b.max.checked_abs()?).checked_sub(1)?;
+            if b.contains(0) && b.min == b.max {
+                return None;
+            }
+            let min = if a.min >= 0 { 0 } else { a.min.max(-limit) };
+            let max = if a.max <= 0 { 0 } else { a.max.min(limit) };
+            Some(ValueRange::new(min, max))
+        }
+        BinaryOp::BitAnd if a.min >= 0 && b.min >= 0 => Some(ValueRange::new(0, a.max.min(b.max))),
+        BinaryOp::BitAnd if a.min >= 0 => Some(ValueRange::new(0, a.max)),
+        BinaryOp::BitAnd if b.min >= 0 => Some(ValueRange::new(0, b.max)),
+        BinaryOp::BitOr | BinaryOp::BitXor if a.min >= 0 && b.min >= 0 => {
+            let bits = 64 - a.max.max(b.max).leading_zeros();
+            Some(ValueRange::new(0, if bits >= 63 { i64::MAX } else { (1i64 << bits) - 1 }))
+        }
+        BinaryOp::Shl => match b.as_constant() {
+            Some(shift @ 0..=62) => corners(a, ValueRange::constant(1i64 << shift), i64::checked_mul),
+            _ => None,
+        },
+        BinaryOp::Shr => match b.as_constant() {
+            Some(shift @ 0..=63) => Some(ValueRange::new(a.min >> shift, a.max >> shift)),
+            _ => None,
+        },
+        BinaryOp::And => Some(match (a.as_constant(), b.as_constant()) {
+            (Some(0), _) | (_, Some(0)) => ValueRange::constant(0),
+            (Some(_), Some(_)) => ValueRange::constant(1),
+            _ => ValueRange::BOOL,
+        }),
+        BinaryOp::Or => Some(match (a.as_constant(), b.as_constant()) {
+            (Some(0), Some(0)) => ValueRange::constant(0),
+            (Some(1), _) | (_, Some(1)) => ValueRange::constant(1),
+            _ => ValueRange::BOOL,
+        }),
+        op if is_comparison(op) => Some(ValueRange::from_bool(compare(op, a, b))),
+        _ => None,
+    }
+}
+
+/// 両端の4つの組み合わせの最小・最大（どれかがオーバーフローすれば `None`）
+fn corners(a: ValueRange, b: ValueRange, f: fn(i64, i64) -> Option<i64>) -> Option<ValueRange> {
+    let values = [f(a.min, b.min)?, f(a.min, b.max)?, f(a.max, b.min)?, f(a.max, b.max)?];
+    Some(ValueRange::new(*values.iter().min()?, *values.iter().max()?))
+}
+
+fn unary_range(op: UnaryOp, a: ValueRange) -> Option<ValueRange> {
+    match op {
+        UnaryOp::Neg => Some(ValueRange::new(a.max.checked_neg()?, a.min.checked_neg()?)),
+        UnaryOp::Not if ValueRange::BOOL.intersect(&a) == Some(a) => Some(ValueRange::new(1 - a.max, 1 - a.min)),
+        UnaryOp::BitNot => Some(ValueRange::new(!a.max, !a.min)),
+        _ => None,
+    }
+}
+
+/// 必ず0で割る除算・剰余を警告するリントの名前
+pub const DIVISION_BY_ZERO: &str = "division_by_zero";
+/// 結果が常に同じになる比較を警告するリントの名前
+pub const CONSTANT_COMPARISON: &str = "constant_comparison";
+/// 値域解析のリントの一覧（プロジェクトの設定の `[lints]` で有効・無効を切り替えられる）
+pub const LINTS: &[&str] = &[DIVISION_BY_ZERO, CONSTANT_COMPARISON];
+
+/// 値域解析で見つかった、プログラムの誤りと思われる箇所
+#[derive(Debug, Clone)]
+pub struct RangeWarning {
+    /// 警告したリントの名前
+    pub lint: &'static str,
+    pub function: String,
+    pub location: Option<SourceLocation>,
+    pub message: String,
+}
+
+impl fmt::Display for RangeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}:{}:{}: {}", location.file.display(), location.line, location.column, self.message),
+            None => write!(f, "関数 '{}': {}", self.function, self.message),
+        }
+    }
+}
+
+/// 必ず0で割る除算・剰余と、結果が常に同じになる比較を探す
+///
+/// 比較はソース上の位置を持つもの（プログラムに書かれたもの）だけを対象にし、両辺が定数の比較は除く。
+pub fn diagnose(module: &Module) -> Vec<RangeWarning> {
+    let mut functions: Vec<&Function> = module.functions.values().collect();
+    functions.sort_by_key(|function| function.id.0);
+    let mut warnings = Vec::new();
+    for function in functions {
+        let analysis = RangeAnalysis::new(module, function);
+        let mut blocks: Vec<BlockId> = function.blocks.keys().copied().filter(|b| analysis.cfg.is_reachable(*b)).collect();
+        blocks.sort_by_key(|block| block.0);
+        for block_id in blocks {
+            for (id, instruction) in &function.blocks[&block_id].instructions {
+                let Instruction::BinaryOp { op, lhs, rhs, result } = instruction else {
+                    continue;
+                };
+                let location = function.get_location(*id).cloned();
+                let divisor = analysis.operand_range(rhs, block_id);
+                let (lint, message) = match op {
+                    BinaryOp::Div | BinaryOp::Rem if analysis.bounds.contains_key(result) && divisor.and_then(|d| d.as_constant()) == Some(0) => {
+                        let message = format!("{} の除数は常に 0 です（実行すると必ずゼロ除算のエラーになります）", if *op == BinaryOp::Div { "除算" } else { "剰余" });
+                        (DIVISION_BY_ZERO, message)
+                    }
+                    op if is_comparison(*op) && location.is_some() && !(is_constant(lhs) && is_constant(rhs)) => {
+                        match analysis.range(*result).and_then(|range| range.as_constant()) {
+                            Some(value) => (CONSTANT_COMPARISON, format!("この比較は常に{}になります", if value != 0 { "真" } else { "偽" })),
+                            None => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+                warnings.push(RangeWarning { lint, function: function.name.clone(), location, message });
+            }
+        }
+    }
+    warnings
+}
+
+fn is_constant(operand: &Operand) -> bool {
+    matches!(operand, Operand::Literal(_))
+}
+
+/// 値域解析による変換の件数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RangeStats {
+    /// 値が1つに決まり、定数に置き換えたレジスタ
+    pub constants: usize,
+    /// 2のべき乗の定数による除算・剰余を、シフト・ビット積に置き換えた数
+    pub strength_reduced: usize,
+    /// 除数が0にならず、オーバーフローもしないと分かり、検査を省いた除算・剰余
+    pub unchecked: usize,
+}
+
+/// 値域解析の結果を使ってモジュールを変換
+///
+/// - 値が1つに決まる演算（常に真の比較など）の結果を、使っている箇所で定数に置き換える
+/// - 被除数が負にならない、2のべき乗の定数による除算・剰余をシフト・ビット積にする
+/// - 除数が0にならず、`i64::MIN / -1` のオーバーフローも起きない除算・剰余を `unchecked` に加え、
+///   バックエンドに実行時の検査を省かせる
+pub fn optimize(module: &mut Module) -> RangeStats {
+    let mut stats = RangeStats::default();
+    let mut ids: Vec<_> = module.functions.keys().copied().collect();
+    ids.sort_by_key(|id| id.0);
+    for id in ids {
+        let analysis = RangeAnalysis::new(module, &module.functions[&id]);
+        // 定数に置き換えるのは `Int` と `Bool` のレジスタだけ（ビット幅を指定した型は型を残すためレジスタのままにする）
+        let foldable: HashMap<RegisterId, bool> = module.functions[&id]
+            .register_types
+            .iter()
+            .filter_map(|(reg, ty)| match module.get_type(*ty).map(|ty| &ty.kind) {
+                Some(TypeKind::Int) => Some((*reg, false)),
+                Some(TypeKind::Bool) => Some((*reg, true)),
+                _ => None,
+            })
+            .collect();
+        let Some(function) = module.functions.get_mut(&id) else {
+            continue;
+        };
+        
+        let mut constants: HashMap<RegisterId, Literal> = HashMap::new();
+        let mut blocks: Vec<BlockId> = function.blocks.keys().copied().filter(|b| analysis.cfg.is_reachable(*b)).collect();
+        blocks.sort_by_key(|block| block.0);
+        for block_id in blocks {
+            let Some(block) = function.blocks.get_mut(&block_id) else {
+                continue;
+            };
+            for (instr_id, instruction) in block.instructions.iter_mut() {
+                let pure = matches!(instruction, Instruction::BinaryOp { .. } | Instruction::UnaryOp { .. } | Instruction::Select { .. });
+                if let Some(reg) = instruction.defined_register().filter(|_| pure) {
+                    let value = analysis.range(reg).and_then(|range| range.as_constant());
+                    if let (Some(is_bool), Some(value)) = (foldable.get(&reg), value) {
+                        constants.insert(reg, if *is_bool { Literal::Bool(value != 0) } else { Literal::Int(value) });
+                        continue;
+                    }
+                }
+                let Instruction::BinaryOp { op, lhs, rhs, result } = instruction else {
+                    continue;
+                };
+                if !matches!(op, BinaryOp::Div | BinaryOp::Rem) || !analysis.bounds.contains_key(result) {
+                    continue;
+                }
+                let (Some(dividend), Some(divisor)) =
+                    (analysis.operand_range(lhs, block_id), analysis.operand_range(rhs, block_id))
+                else {
+                    continue;
+                };
+                match (divisor.as_constant(), dividend.min >= 0) {
+                    (Some(power), true) if power > 1 && power.count_ones() == 1 => {
+                        let (new_op, operand) = if *op == BinaryOp::Div {
+                            (BinaryOp::Shr, power.trailing_zeros() as i64)
+                        } else {
+                            (BinaryOp::BitAnd, power - 1)
+                        };
+                        *op = new_op;
+                        *rhs = Operand::Literal(Literal::Int(operand));
+                        stats.strength_reduced += 1;
+                    }
+                    _ if !divisor.contains(0) && (!divisor.contains(-1) || !dividend.contains(i64::MIN)) => {
+                        function.unchecked.insert(*instr_id);
+                        stats.unchecked += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        
+        stats.constants += constants.len();
+        if !constants.is_empty() {
+            replace_uses(function, &constants);
+        }
+    }
+    stats
+}
+
+/// 条件が常に真と分かったアサーション・契約の検査を取り除き、取り除いた数を返す
+///
+/// 偽のときの分岐先が `ASSERTION_TAG` を付けた `panic` の呼び出しで始まる条件分岐を、真のときの分岐先への
+/// 無条件分岐にする。分岐先のブロックに到達しなくなれば、ブロックも削除する。
+pub fn elide_assertions(module: &mut Module) -> usize {
+    let mut elided = 0;
+    let mut ids: Vec<_> = module.functions.keys().copied().collect();
+    ids.sort_by_key(|id| id.0);
+    for id in ids {
+        let analysis = RangeAnalysis::new(module, &module.functions[&id]);
+        let Some(function) = module.functions.get_mut(&id) else {
+            continue;
+        };
+        let is_assertion = |function: &Function, block: BlockId| {
+            let Some((instr_id, Instruction::Call { function: callee, .. })) = function.blocks.get(&block).and_then(|b| b.instructions.first()) else {
+                return false;
+            };
+            callee == "panic" && function.get_instruction_attributes(*instr_id).is_some_and(|attributes| attributes.tags.contains(ASSERTION_TAG))
+        };
+        let mut failures = Vec::new();
+        let mut blocks: Vec<BlockId> = function.blocks.keys().copied().filter(|b| analysis.cfg.is_reachable(*b)).collect();
+        blocks.sort_by_key(|block| block.0);
+        for block_id in blocks {
+            let Some(Terminator::BranchCond { condition, true_target, true_args, false_target, .. }) = function.blocks[&block_id].terminator.clone() else {
+                continue;
+            };
+            let proven = analysis.operand_range(&condition, block_id).and_then(|range| range.as_constant()) == Some(1);
+            if !proven || !is_assertion(function, false_target) {
+                continue;
+            }
+            if let Some(block) = function.blocks.get_mut(&block_id) {
+                block.set_terminator(Terminator::Branch { target: true_target, args: true_args });
+            }
+            failures.push(false_target);
+            elided += 1;
+        }
+        if failures.is_empty() {
+            continue;
+        }
+        let cfg = Cfg::new(function);
+        for block in failures {
+            if !cfg.is_reachable(block) {
+                function.blocks.remove(&block);
+            }
+        }
+    }
+    elided
+}
+
+/// レジスタを使っている箇所を定数に置き換える（定義した命令は残し、使われなくなったものは後のパスで消す）
+fn replace_uses(function: &mut Function, constants: &HashMap<RegisterId, Literal>) {
+    let replace = |operand: &mut Operand| {
+        if let Operand::Register(reg) = operand {
+            if let Some(literal) = constants.get(reg) {
+                *operand = Operand::Literal(literal.clone());
+            }
+        }
+    };
+    for block in function.blocks.values_mut() {
+        for (_, instruction) in block.instructions.iter_mut() {
+            instruction.operands_mut().into_iter().for_each(replace);
+        }
+        if let Some(terminator) = block.terminator.as_mut() {
+            terminator.operands_mut().into_iter().for_each(replace);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::eir::FunctionId;
+    use crate::core::ir_builder::IrBuilder;
+    use crate::core::types::Type;
+    
+    /// `fn f(n: Int) -> Int { if n > 0 { n / 4 } else { 10 / n } }` に相当する関数
+    fn branchy(module: &mut Module) -> (Function, RegisterId, RegisterId) {
+        let int = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        let mut function = Function::new(FunctionId(0), "f", int, int);
+        let n = function.add_parameter("n", int);
+        let mut builder = IrBuilder::new(function);
+        let positive = builder.create_block();
+        let other = builder.create_block();
+        let join = builder.create_block();
+        let condition = builder.binary(BinaryOp::Gt, Operand::Register(n), Operand::Literal(Literal::Int(0)), bool_type);
+        builder.cond_br(Operand::Register(condition), positive, other);
+        builder.position_at_end(positive);
+        let quarter = builder.binary(BinaryOp::Div, Operand::Register(n), Operand::Literal(Literal::Int(4)), int);
+        // 本体の中では `n > 0` なので、この比較は常に真
+        builder.set_location(Some(SourceLocation::new("test.eid".into(), 3, 9, 6)));
+        let again = builder.binary(BinaryOp::Ge, Operand::Register(n), Operand::Literal(Literal::Int(1)), bool_type);
+        builder.set_location(None);
+        let value = builder.select(Operand::Register(again), Operand::Register(quarter), Operand::Literal(Literal::Int(0)), int);
+        builder.br(join, vec![Operand::Register(value)]);
+        builder.position_at_end(other);
+        builder.binary(BinaryOp::Div, Operand::Literal(Literal::Int(10)), Operand::Register(n), int);
+        builder.br(join, vec![Operand::Literal(Literal::Int(10))]);
+        let result = builder.add_block_param(join, int);
+        builder.position_at_end(join);
+        builder.ret(Some(Operand::Register(result)));
+        (builder.finish(), again, result)
+    }
+    
+    #[test]
+    fn test_ranges_refined_by_branches() {
+        let mut module = Module::new("test");
+        let (function, again, result) = branchy(&mut module);
+        let analysis = RangeAnalysis::new(&module, &function);
+        assert_eq!(analysis.range(again), Some(ValueRange::constant(1)));
+        // `n / 4` は `n` が正なので [0, i64::MAX / 4]、もう一方の分岐は 10
+        assert_eq!(analysis.range(result), Some(ValueRange::new(0, i64::MAX / 4)));
+        assert_eq!(ValueRange::new(-5, 300).narrowest_signed_type(), NumericType::I16);
+    }
+    
+    #[test]
+    fn test_loop_counter_widens_and_refines() {
+        // `i = 0; while i < 10 { i = i + 1 }` をブロックのパラメータで表した関数
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        let mut builder = IrBuilder::new(Function::new(FunctionId(0), "count", int, int));
+        let header = builder.create_block();
+        let body = builder.create_block();
+        let exit = builder.create_block();
+        builder.br(header, vec![Operand::Literal(Literal::Int(0))]);
+        let i = builder.add_block_param(header, int);
+        builder.position_at_end(header);
+        let condition = builder.binary(BinaryOp::Lt, Operand::Register(i), Operand::Literal(Literal::Int(10)), bool_type);
+        builder.cond_br(Operand::Register(condition), body, exit);
+        builder.position_at_end(body);
+        let next = builder.binary(BinaryOp::Add, Operand::Register(i), Operand::Literal(Literal::Int(1)), int);
+        let remainder = builder.binary(BinaryOp::Rem, Operand::Literal(Literal::Int(100)), Operand::Register(next), int);
+        builder.br(header, vec![Operand::Register(next)]);
+        builder.position_at_end(exit);
+        builder.ret(Some(Operand::Register(i)));
+        let function = builder.finish();
+        
+        let analysis = RangeAnalysis::new(&module, &function);
+        assert_eq!(analysis.range(i), Some(ValueRange::new(0, 10)));
+        assert_eq!(analysis.range(next), Some(ValueRange::new(1, 10)));
+        assert_eq!(analysis.operand_range(&Operand::Register(i), exit), Some(ValueRange::constant(10)));
+        assert_eq!(analysis.range(remainder), Some(ValueRange::new(0, 9)));
+    }
+    
+    #[test]
+    fn test_diagnose_and_optimize() {
+        let mut module = Module::new("test");
+        let (function, _, _) = branchy(&mut module);
+        module.add_function(function);
+        
+        let warnings = diagnose(&module);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "test.eid:3:9: この比較は常に真になります");
+        assert_eq!(warnings[0].lint, CONSTANT_COMPARISON);
+        
+        let stats = optimize(&mut module);
+        assert_eq!(stats, RangeStats { constants: 1, strength_reduced: 1, unchecked: 0 });
+        let text = module.to_string();
+        assert!(text.contains("= shr %0, 2"), "{}", text);
+        assert!(text.contains("= select true, "), "{}", text);
+        // `10 / n` の `n` は0になりうるので検査を残す
+        assert!(!text.contains("unchecked"), "{}", text);
+    }
+    
+    #[test]
+    fn test_division_checks_removed() {
+        // `n / ((n & 7) + 1)` の除数は [1, 8]、`n % 7` の除数は 7 なので検査を省けるが、
+        // `100 / (n % 7 + 1)` の除数は [-5, 7] で0を除けない
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let mut function = Function::new(FunctionId(0), "g", int, int);
+        let n = function.add_parameter("n", int);
+        let mut builder = IrBuilder::new(function);
+        let low = builder.binary(BinaryOp::BitAnd, Operand::Register(n), Operand::Literal(Literal::Int(7)), int);
+        let divisor = builder.binary(BinaryOp::Add, Operand::Register(low), Operand::Literal(Literal::Int(1)), int);
+        builder.binary(BinaryOp::Div, Operand::Register(n), Operand::Register(divisor), int);
+        let remainder = builder.binary(BinaryOp::Rem, Operand::Register(n), Operand::Literal(Literal::Int(7)), int);
+        let shifted = builder.binary(BinaryOp::Add, Operand::Register(remainder), Operand::Literal(Literal::Int(1)), int);
+        builder.binary(BinaryOp::Div, Operand::Literal(Literal::Int(100)), Operand::Register(shifted), int);
+        builder.ret(None);
+        module.add_function(builder.finish());
+        
+        let stats = optimize(&mut module);
+        assert_eq!(stats.unchecked, 2);
+        let text = module.to_string();
+        assert!(text.contains("div %0, %2 ; unchecked"), "{}", text);
+        assert!(text.contains("rem %0, 7 ; unchecked"), "{}", text);
+        assert!(!text.contains("div 100, %5 ; unchecked"), "{}", text);
+    }
+    
+    #[test]
+    fn test_proven_assertions_elided() {
+        // `fn h(n: Int) { let m = n & 15; assert(m < 16); assert(m < 8) }` に相当する関数
+        let mut module = Module::new("test");
+        let int = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        let unit = module.add_type(Type::unit());
+        let mut function = Function::new(FunctionId(0), "h", int, unit);
+        let n = function.add_parameter("n", int);
+        let mut builder = IrBuilder::new(function);
+        let masked = builder.binary(BinaryOp::BitAnd, Operand::Register(n), Operand::Literal(Literal::Int(15)), int);
+        for bound in [16, 8] {
+            let condition = builder.binary(BinaryOp::Lt, Operand::Register(masked), Operand::Literal(Literal::Int(bound)), bool_type);
+            let failure = builder.create_block();
+            let success = builder.create_block();
+            builder.cond_br(Operand::Register(condition), success, failure);
+            builder.position_at_end(failure);
+            let message = Operand::Literal(Literal::String(format!("test.eid:1:{}: assertion failed", bound)));
+            let call = builder.emit(Instruction::Call { function: "panic".to_string(), arguments: vec![message], result: None });
+            builder.function_mut().instruction_attributes_mut(call).tags.insert(ASSERTION_TAG.to_string());
+            builder.unreachable();
+            builder.position_at_end(success);
+        }
+        builder.ret(None);
+        module.add_function(builder.finish());
+        
+        // `m < 16` は常に真なので取り除き、`m < 8` は偽になりうるので残す
+        assert_eq!(elide_assertions(&mut module), 1);
+        let text = module.to_string();
+        assert!(!text.contains("test.eid:1:16"), "{}", text);
+        assert!(text.contains("test.eid:1:8"), "{}", text);
+        assert_eq!(elide_assertions(&mut module), 0);
+    }
+}
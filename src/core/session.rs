@@ -0,0 +1,342 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::core::source_map::SourceMap;
+
+/// 現在確保されているヒープのバイト数
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+/// 直近のリセット以降に観測したヒープ使用量の最大値
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+/// ヒープ使用量の上限（0 なら上限なし）
+static LIMIT: AtomicUsize = AtomicUsize::new(0);
+
+/// ヒープの使用量を数えるグローバルアロケータ
+///
+/// `#[global_allocator]` として登録すると、`CompileSession` がフェーズごとのピークメモリを記録できる。
+/// 登録しない場合、メモリの列は表示されない。
+/// `set_limit` で上限を設定すると、それを超える確保は失敗し、プロセスは異常終了する。
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    /// ヒープ使用量の上限をバイト数で設定（0 で上限なし）
+    pub fn set_limit(bytes: usize) {
+        LIMIT.store(bytes, Ordering::Relaxed);
+    }
+    
+    /// `size` バイトを追加で確保すると上限を超えるか
+    fn exceeds_limit(size: usize) -> bool {
+        let limit = LIMIT.load(Ordering::Relaxed);
+        limit != 0 && ALLOCATED.load(Ordering::Relaxed).saturating_add(size) > limit
+    }
+    
+    fn record_alloc(size: usize) {
+        let now = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(now, Ordering::Relaxed);
+    }
+    
+    fn record_dealloc(size: usize) {
+        ALLOCATED.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if Self::exceeds_limit(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+    
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if Self::exceeds_limit(layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::record_alloc(layout.size());
+        }
+        ptr
+    }
+    
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::record_dealloc(layout.size());
+    }
+    
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if new_size > layout.size() && Self::exceeds_limit(new_size - layout.size()) {
+            return std::ptr::null_mut();
+        }
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size >= layout.size() {
+                Self::record_alloc(new_size - layout.size());
+            } else {
+                Self::record_dealloc(layout.size() - new_size);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// フェーズ開始時のメモリの状態
+struct MemoryMark {
+    base: usize,
+    outer_peak: usize,
+}
+
+impl MemoryMark {
+    /// ピークをいったん現在値に戻し、フェーズ内の最大値だけを測れるようにする
+    fn begin() -> Self {
+        let base = ALLOCATED.load(Ordering::Relaxed);
+        let outer_peak = PEAK.swap(base, Ordering::Relaxed);
+        Self { base, outer_peak }
+    }
+    
+    /// フェーズ中に増えたヒープ使用量の最大値を返し、外側のフェーズのピークを復元する
+    fn end(self) -> usize {
+        let peak = PEAK.fetch_max(self.outer_peak, Ordering::Relaxed);
+        peak.saturating_sub(self.base)
+    }
+}
+
+/// 1つのフェーズの計測結果（同じフェーズを複数回実行した場合は合算する）
+#[derive(Debug, Clone)]
+pub struct PhaseTiming {
+    /// フェーズ名
+    pub name: String,
+    /// 入れ子の深さ（トップレベルは0）
+    pub depth: usize,
+    /// 外側のフェーズを含めたフェーズ名の列
+    path: Vec<String>,
+    /// 合計の経過時間
+    pub duration: Duration,
+    /// フェーズ開始時点からのヒープ使用量の増分の最大値（バイト）
+    pub peak_memory: usize,
+    /// 実行回数
+    pub count: usize,
+}
+
+#[derive(Default)]
+struct SessionState {
+    stack: Vec<String>,
+    phases: Vec<PhaseTiming>,
+}
+
+/// 1回のコンパイル全体で共有するセッション
+///
+/// フロントエンド、最適化器、バックエンドの各フェーズを `time` で囲むと、経過時間とピークメモリが記録され、
+/// `--time-passes` の指定時に内訳を表示できる。計測が無効なセッションでは `time` はクロージャを呼ぶだけになる。
+pub struct CompileSession {
+    time_passes: bool,
+    started: Instant,
+    state: RefCell<SessionState>,
+    /// 読み込んだソースファイル（診断の表示でも使う）
+    sources: SourceMap,
+}
+
+impl CompileSession {
+    /// 新しいセッションを作成
+    pub fn new(time_passes: bool) -> Self {
+        Self {
+            time_passes,
+            started: Instant::now(),
+            state: RefCell::new(SessionState::default()),
+            sources: SourceMap::new(),
+        }
+    }
+    
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+    
+    /// フェーズごとの計測が有効か
+    pub fn time_passes(&self) -> bool {
+        self.time_passes
+    }
+    
+    /// フェーズを実行し、経過時間とピークメモリを記録する
+    pub fn time<T, F>(&self, name: &str, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        if !self.time_passes {
+            self.state.borrow_mut().stack.push(name.to_string());
+            let result = f();
+            self.state.borrow_mut().stack.pop();
+            return result;
+        }
+        
+        // 表示順が開始順になるよう、入れ子のフェーズより先に枠を確保しておく
+        let path = {
+            let mut state = self.state.borrow_mut();
+            state.stack.push(name.to_string());
+            let path = state.stack.clone();
+            if !state.phases.iter().any(|phase| phase.path == path) {
+                state.phases.push(PhaseTiming {
+                    name: name.to_string(),
+                    depth: path.len() - 1,
+                    path: path.clone(),
+                    duration: Duration::ZERO,
+                    peak_memory: 0,
+                    count: 0,
+                });
+            }
+            path
+        };
+        
+        let mark = MemoryMark::begin();
+        let start = Instant::now();
+        let result = f();
+        let duration = start.elapsed();
+        let peak_memory = mark.end();
+        
+        let mut state = self.state.borrow_mut();
+        state.stack.pop();
+        if let Some(phase) = state.phases.iter_mut().find(|phase| phase.path == path) {
+            phase.duration += duration;
+            phase.peak_memory = phase.peak_memory.max(peak_memory);
+            phase.count += 1;
+        }
+        result
+    }
+    
+    /// 実行中のフェーズ（入れ子のフェーズは外側から `>` でつなぐ）
+    ///
+    /// パニックで抜けたフェーズはそのまま残るので、コンパイラ内部のエラーの報告で
+    /// パニックした時点のフェーズが分かる。計測が無効なセッションでも記録する。
+    pub fn current_phase(&self) -> Option<String> {
+        let state = self.state.borrow();
+        if state.stack.is_empty() {
+            None
+        } else {
+            Some(state.stack.join(" > "))
+        }
+    }
+    
+    /// 記録したフェーズ（最初に実行された順）
+    pub fn phases(&self) -> Vec<PhaseTiming> {
+        self.state.borrow().phases.clone()
+    }
+    
+    /// フェーズごとの内訳を表形式の文字列にする
+    pub fn report(&self) -> String {
+        let total = self.started.elapsed();
+        let phases = self.phases();
+        let track_memory = phases.iter().any(|phase| phase.peak_memory > 0);
+        
+        let mut out = String::new();
+        let _ = writeln!(out, "==== フェーズごとの所要時間 ====");
+        for phase in &phases {
+            let ratio = if total.as_nanos() == 0 {
+                0.0
+            } else {
+                phase.duration.as_secs_f64() / total.as_secs_f64() * 100.0
+            };
+            // 日本語のフェーズ名は幅が揃わないため、数値の列を先に並べる
+            let _ = write!(out, "{:>10} {:>6.1}%", format_duration(phase.duration), ratio);
+            if track_memory {
+                let _ = write!(out, " {:>10}", format_bytes(phase.peak_memory));
+            }
+            let _ = write!(out, "  {}{}", "  ".repeat(phase.depth), phase.name);
+            if phase.count > 1 {
+                let _ = write!(out, " (×{})", phase.count);
+            }
+            out.push('\n');
+        }
+        let _ = write!(out, "{:>10} {:>6.1}%", format_duration(total), 100.0);
+        if track_memory {
+            let _ = write!(out, " {:>10}", format_bytes(PEAK.load(Ordering::Relaxed)));
+        }
+        out.push_str("  合計\n");
+        out
+    }
+    
+    /// 計測が有効なら内訳を標準エラー出力に表示
+    pub fn print_report(&self) {
+        if self.time_passes {
+            eprint!("{}", self.report());
+        }
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let ms = duration.as_secs_f64() * 1000.0;
+    if ms >= 1000.0 {
+        format!("{:.2}s", ms / 1000.0)
+    } else {
+        format!("{:.2}ms", ms)
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_nested_phases_are_aggregated() {
+        let session = CompileSession::new(true);
+        session.time("フロントエンド", || {
+            session.time("字句解析", || ());
+            session.time("構文解析", || ());
+        });
+        for _ in 0..2 {
+            session.time("最適化", || session.time("定数畳み込み", || ()));
+        }
+        
+        let phases = session.phases();
+        let names: Vec<(&str, usize, usize)> = phases
+            .iter()
+            .map(|phase| (phase.name.as_str(), phase.depth, phase.count))
+            .collect();
+        assert_eq!(names, vec![
+            ("フロントエンド", 0, 1),
+            ("字句解析", 1, 1),
+            ("構文解析", 1, 1),
+            ("最適化", 0, 2),
+            ("定数畳み込み", 1, 2),
+        ]);
+        assert!(session.report().contains("定数畳み込み (×2)"));
+    }
+    
+    #[test]
+    fn test_disabled_session_records_nothing() {
+        let session = CompileSession::new(false);
+        assert_eq!(session.time("字句解析", || 42), 42);
+        assert!(session.phases().is_empty());
+        
+        // 実行中のフェーズは計測しなくても分かる
+        let phase = session.time("最適化", || session.time("インライン化", || session.current_phase()));
+        assert_eq!(phase.as_deref(), Some("最適化 > インライン化"));
+        assert_eq!(session.current_phase(), None);
+    }
+    
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
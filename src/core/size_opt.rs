@@ -0,0 +1,603 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::core::eir::{
+    BasicBlock, BinaryOp, BlockId, Function, FunctionId, Hotness, InlineDirective, Instruction, Literal, Module, Operand, RegisterId,
+    Terminator,
+};
+use crate::core::types::{Type, TypeId, TypeKind};
+
+/// 切り出すコールドブロックの最小の命令数（呼び出し1つに置き換えて小さくなるもの）
+const OUTLINE_THRESHOLD: usize = 3;
+
+/// 切り出した関数に付けるタグ
+pub const COLD_TAG: &str = "cold";
+
+/// モジュールのコードサイズ（命令と終了命令の数。`switch` は分岐先の表の行も数える）
+pub fn module_size(module: &Module) -> usize {
+    module.functions.values().map(function_size).sum()
+}
+
+fn function_size(function: &Function) -> usize {
+    function.blocks.values().map(|block| block.instructions.len() + terminator_size(block)).sum()
+}
+
+fn terminator_size(block: &BasicBlock) -> usize {
+    match &block.terminator {
+        Some(Terminator::Switch { cases, .. }) => 1 + cases.len(),
+        Some(_) => 1,
+        None => 0,
+    }
+}
+
+/// パスを実行する前と後のコードサイズ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassSize {
+    pub name: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl PassSize {
+    /// 増減（小さくなれば負）
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+/// パスごとのコードサイズの増減（`--opt-stats` で表示する）
+#[derive(Debug, Clone, Default)]
+pub struct SizeReport {
+    /// 実行した順のパス
+    pub passes: Vec<PassSize>,
+}
+
+impl SizeReport {
+    /// パスを実行し、前後のコードサイズを記録する
+    pub fn measure<T>(&mut self, name: &str, module: &mut Module, pass: impl FnOnce(&mut Module) -> T) -> T {
+        let before = module_size(module);
+        let result = pass(module);
+        self.passes.push(PassSize {
+            name: name.to_string(),
+            before,
+            after: module_size(module),
+        });
+        result
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // パスの名前は全角文字を含み桁が揃わないので、最後の列に置く
+        writeln!(f, "{:>8} {:>8} {:>8}  パス", "前", "後", "増減")?;
+        for pass in &self.passes {
+            writeln!(f, "{:>8} {:>8} {:>+8}  {}", pass.before, pass.after, pass.delta(), pass.name)?;
+        }
+        if let (Some(first), Some(last)) = (self.passes.first(), self.passes.last()) {
+            let delta = last.after as i64 - first.before as i64;
+            writeln!(f, "{:>8} {:>8} {:>+8}  合計", first.before, last.after, delta)?;
+        }
+        Ok(())
+    }
+}
+
+/// `switch` を表引きの算術に置き換える（置き換えた数を返す）
+///
+/// - 既定の分岐先と同じ行き先・同じ引数の `case` を取り除き、`case` が残らなければ無条件分岐にする
+/// - すべての `case` が同じブロックへ分岐し、`case` の値が連続した `Int` で、渡す引数が `case` の値の1次式
+///   （`k * a + b`）か `case` によらない値なら、範囲の判定と `select` で引数を求める無条件分岐にする。
+///   値の表を置くグローバル変数は配列を持てないので、1次式で表せる表だけを扱う
+///
+/// 範囲外の値は乗算の前に範囲の端へ寄せるので、計算はオーバーフローしない `case` の値でだけ行う。
+/// 置き換えた後の命令数が `case` の表より小さくなる場合だけ置き換える。
+pub fn lower_switches(module: &mut Module) -> usize {
+    let has_switch = module
+        .functions
+        .values()
+        .flat_map(|function| function.blocks.values())
+        .any(|block| matches!(block.terminator, Some(Terminator::Switch { .. })));
+    if !has_switch {
+        return 0;
+    }
+    let ints: HashSet<TypeId> = types_of_kind(module, |kind| matches!(kind, TypeKind::Int));
+    let bool_type = find_or_add_type(module, Type::bool(), |kind| matches!(kind, TypeKind::Bool));
+    
+    let mut lowered = 0;
+    for function in module.functions.values_mut() {
+        let mut blocks: Vec<BlockId> = function.blocks.keys().copied().collect();
+        blocks.sort_by_key(|block| block.0);
+        for block in blocks {
+            if lower_switch(function, block, &ints, bool_type) {
+                lowered += 1;
+            }
+        }
+    }
+    lowered
+}
+
+/// 表引きにする `switch` の行の引数（引数の位置ごと）
+enum TableColumn {
+    /// どの `case` でも同じ値
+    Constant(Operand),
+    /// `case` の値 `k` に対する `k * scale + offset`
+    Linear { scale: i64, offset: i64 },
+}
+
+/// 1次式の表として読んだ `switch`
+struct Table {
+    /// `case` の値の範囲
+    low: i64,
+    high: i64,
+    /// 分岐する値の型
+    ty: TypeId,
+    columns: Vec<TableColumn>,
+}
+
+impl Table {
+    /// 置き換えた後の命令数（範囲の判定・1次式の列があれば範囲の端へ寄せる `select`・列ごとの計算と `select`・無条件分岐）
+    fn cost(&self, default_args: &[Operand]) -> usize {
+        let clamp = self.columns.iter().any(|column| matches!(column, TableColumn::Linear { .. }));
+        let columns: usize = self
+            .columns
+            .iter()
+            .zip(default_args)
+            .map(|(column, default)| match column {
+                TableColumn::Constant(constant) => usize::from(!same_operand(constant, default)),
+                TableColumn::Linear { scale, offset } => 1 + usize::from(*scale != 1) + usize::from(*offset != 0),
+            })
+            .sum();
+        4 + usize::from(clamp) + columns
+    }
+}
+
+fn lower_switch(function: &mut Function, block: BlockId, ints: &HashSet<TypeId>, bool_type: TypeId) -> bool {
+    let Some(Terminator::Switch { value, default_target, default_args, cases }) = function.blocks[&block].terminator.clone() else {
+        return false;
+    };
+    let original = cases.len();
+    let cases: Vec<(Literal, BlockId, Vec<Operand>)> = cases
+        .into_iter()
+        .filter(|(_, target, args)| *target != default_target || !same_args(args, &default_args))
+        .collect();
+    if cases.is_empty() {
+        set_terminator(function, block, Terminator::Branch { target: default_target, args: default_args });
+        return true;
+    }
+    
+    let table = table(function, &value, default_target, &cases, ints).filter(|table| table.cost(&default_args) < 1 + cases.len());
+    let Some(Table { low, high, ty, columns }) = table else {
+        if cases.len() == original {
+            return false;
+        }
+        set_terminator(function, block, Terminator::Switch { value, default_target, default_args, cases });
+        return true;
+    };
+    
+    let is_low = emit(function, block, bool_type, BinaryOp::Ge, value.clone(), int(low));
+    let is_high = emit(function, block, bool_type, BinaryOp::Le, value.clone(), int(high));
+    let in_range = emit(function, block, bool_type, BinaryOp::And, is_low, is_high);
+    let mut clamped = None;
+    let mut args = Vec::with_capacity(columns.len());
+    for (column, default) in columns.into_iter().zip(default_args) {
+        let arg = match column {
+            TableColumn::Constant(constant) if same_operand(&constant, &default) => constant,
+            TableColumn::Constant(constant) => select(function, block, ty, in_range.clone(), constant, default),
+            TableColumn::Linear { scale, offset } => {
+                let mut arg = clamped
+                    .get_or_insert_with(|| select(function, block, ty, in_range.clone(), value.clone(), int(low)))
+                    .clone();
+                if scale != 1 {
+                    arg = emit(function, block, ty, BinaryOp::Mul, arg, int(scale));
+                }
+                if offset != 0 {
+                    arg = emit(function, block, ty, BinaryOp::Add, arg, int(offset));
+                }
+                select(function, block, ty, in_range.clone(), arg, default)
+            }
+        };
+        args.push(arg);
+    }
+    set_terminator(function, block, Terminator::Branch { target: default_target, args });
+    true
+}
+
+/// `case` を1次式の表として読む
+fn table(
+    function: &Function,
+    value: &Operand,
+    default_target: BlockId,
+    cases: &[(Literal, BlockId, Vec<Operand>)],
+    ints: &HashSet<TypeId>,
+) -> Option<Table> {
+    let ty = value_register_type(function, value)?;
+    if !ints.contains(&ty) || cases.len() < 2 || cases.iter().any(|(_, target, _)| *target != default_target) {
+        return None;
+    }
+    let mut rows: Vec<(i64, &Vec<Operand>)> = cases
+        .iter()
+        .map(|(literal, _, args)| match literal {
+            Literal::Int(key) => Some((*key, args)),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    rows.sort_by_key(|(key, _)| *key);
+    let (low, high) = (rows[0].0, rows[rows.len() - 1].0);
+    if high.checked_sub(low)? != rows.len() as i64 - 1 || rows.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+        return None;
+    }
+    
+    let width = rows[0].1.len();
+    let mut columns = Vec::with_capacity(width);
+    for position in 0..width {
+        let column: Vec<&Operand> = rows.iter().map(|(_, args)| &args[position]).collect();
+        if column.iter().all(|operand| same_operand(operand, column[0])) {
+            columns.push(TableColumn::Constant(column[0].clone()));
+            continue;
+        }
+        let values: Vec<i64> = column
+            .iter()
+            .map(|operand| match operand {
+                Operand::Literal(Literal::Int(value)) => Some(*value),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        let scale = values[1].checked_sub(values[0])?;
+        let offset = values[0].checked_sub(low.checked_mul(scale)?)?;
+        // 端の値でオーバーフローしなければ、範囲内のどの値でもしない
+        let linear = |key: i64| key.checked_mul(scale).and_then(|product| product.checked_add(offset));
+        let fits = linear(low).is_some() && linear(high).is_some();
+        let matches = rows.iter().zip(&values).all(|((key, _), value)| linear(*key) == Some(*value));
+        if !fits || !matches {
+            return None;
+        }
+        columns.push(TableColumn::Linear { scale, offset });
+    }
+    Some(Table { low, high, ty, columns })
+}
+
+/// 到達しないブロック（`panic` の後など）の命令を別の関数へ切り出す（切り出した数を返す）
+///
+/// 切り出した関数はブロックで使う外のレジスタを引数に受け取り、元のブロックはその呼び出しと
+/// `unreachable` だけになる。命令のソース上の位置は切り出した関数へ移すので、スタックトレースには
+/// 元の行が残る。切り出した関数は `noreturn` でインライン化を禁止し、`cold` のタグを付ける。
+pub fn outline_cold_blocks(module: &mut Module) -> usize {
+    let unit = find_or_add_type(module, Type::unit(), |kind| matches!(kind, TypeKind::Unit));
+    let mut next_id = module.functions.keys().map(|id| id.0 + 1).max().unwrap_or(0);
+    let mut ids: Vec<FunctionId> = module.functions.keys().copied().collect();
+    ids.sort_by_key(|id| id.0);
+    
+    let mut outlined = 0;
+    for id in ids {
+        let function = &module.functions[&id];
+        let mut blocks: Vec<BlockId> = function
+            .blocks
+            .values()
+            .filter(|block| block.id != function.entry_block && is_cold(block))
+            .map(|block| block.id)
+            .collect();
+        blocks.sort_by_key(|block| block.0);
+        
+        for (index, block) in blocks.into_iter().enumerate() {
+            let function = &module.functions[&id];
+            let name = format!("{}::cold{}", function.name, index);
+            let Some((mut cold, arguments)) = outline(function, block, FunctionId(next_id), &name, unit, module) else {
+                continue;
+            };
+            let function = module.functions.get_mut(&id).unwrap();
+            let last = function.blocks[&block].instructions.last().map(|(id, _)| *id);
+            let location = last.and_then(|id| function.get_location(id).cloned());
+            let removed: Vec<_> = std::mem::take(&mut function.blocks.get_mut(&block).unwrap().instructions);
+            for (id, _) in removed {
+                function.forget_instruction(id);
+            }
+            let call = function.add_instruction(block, Instruction::Call { function: name, arguments, result: None });
+            function.instruction_attributes_mut(call).hotness = Hotness::Cold;
+            if let Some(location) = location {
+                function.set_location(call, location);
+            }
+            
+            let function_type = module.add_type(Type::function(
+                cold.parameters.iter().map(|(_, ty)| module.types[ty].clone()).collect(),
+                Type::unit(),
+            ));
+            cold.function_type = function_type;
+            module.add_function(cold);
+            next_id += 1;
+            outlined += 1;
+        }
+    }
+    outlined
+}
+
+/// `unreachable` で終わり、切り出して小さくなるブロックか
+fn is_cold(block: &BasicBlock) -> bool {
+    matches!(block.terminator, Some(Terminator::Unreachable))
+        && block.instructions.len() >= OUTLINE_THRESHOLD
+        && !block.instructions.iter().any(|(_, instruction)| matches!(instruction, Instruction::Phi { .. }))
+}
+
+/// ブロックの命令を移した関数と、呼び出しに渡す引数を作る
+fn outline(
+    function: &Function,
+    block: BlockId,
+    id: FunctionId,
+    name: &str,
+    unit: TypeId,
+    module: &Module,
+) -> Option<(Function, Vec<Operand>)> {
+    let instructions = &function.blocks[&block].instructions;
+    let mut defined = HashSet::new();
+    let mut live_ins = Vec::new();
+    for (_, instruction) in instructions {
+        for reg in instruction.used_registers() {
+            if !defined.contains(&reg) && !live_ins.contains(&reg) {
+                live_ins.push(reg);
+            }
+        }
+        defined.extend(instruction.defined_register());
+    }
+    
+    let mut cold = Function::new(id, name, function.function_type, unit);
+    let mut renamed: HashMap<RegisterId, RegisterId> = HashMap::new();
+    for (index, reg) in live_ins.iter().enumerate() {
+        let ty = function.get_register_type(*reg)?;
+        module.types.get(&ty)?;
+        renamed.insert(*reg, cold.add_parameter(&format!("arg{}", index), ty));
+    }
+    for (old_id, instruction) in instructions {
+        let mut instruction = instruction.clone();
+        if let Some(result) = instruction.defined_register_mut() {
+            let ty = function.get_register_type(*result)?;
+            let new = cold.create_register(ty);
+            renamed.insert(*result, new);
+            *result = new;
+        }
+        for operand in instruction.operands_mut() {
+            if let Operand::Register(reg) = operand {
+                *reg = renamed[reg];
+            }
+        }
+        let new_id = cold.add_instruction(cold.entry_block, instruction);
+        cold.copy_instruction_metadata(new_id, function, *old_id);
+    }
+    let entry = cold.entry_block;
+    cold.blocks.get_mut(&entry)?.set_terminator(Terminator::Unreachable);
+    cold.location = function.location.clone();
+    cold.attributes.noreturn = true;
+    cold.attributes.inline = InlineDirective::Never;
+    cold.attributes.hotness = Hotness::Cold;
+    cold.attributes.tags.insert(COLD_TAG.to_string());
+    
+    let arguments = live_ins.into_iter().map(Operand::Register).collect();
+    Some((cold, arguments))
+}
+
+/// 本体が同じ関数を1つにまとめる（取り除いた関数の数を返す）
+///
+/// パラメータと戻り値の型・ブロック・命令が同じ関数のうち、IDが最も小さいものを残し、ほかの関数への
+/// 呼び出しと参照を残した関数へ向け直す。命令のソース上の位置は比べないので、まとめた関数の
+/// スタックトレースには残した関数の行が出る。エントリーポイントと、ソースで属性（`#[export]` や
+/// `#[intrinsic]` など名前に意味のあるもの）を付けた関数はまとめない。
+pub fn merge_identical_functions(module: &mut Module) -> usize {
+    let mut ids: Vec<FunctionId> = module.functions.keys().copied().collect();
+    ids.sort_by_key(|id| id.0);
+    
+    let mut kept: HashMap<String, FunctionId> = HashMap::new();
+    let mut replaced: HashMap<FunctionId, FunctionId> = HashMap::new();
+    for id in ids {
+        let function = &module.functions[&id];
+        if module.entry_point == Some(id) || function.attributes.tags.iter().any(|tag| tag != COLD_TAG) {
+            continue;
+        }
+        let key = body_key(function);
+        match kept.get(&key) {
+            Some(&original) => {
+                replaced.insert(id, original);
+            }
+            None => {
+                kept.insert(key, id);
+            }
+        }
+    }
+    if replaced.is_empty() {
+        return 0;
+    }
+    
+    let names: HashMap<String, String> = replaced
+        .iter()
+        .map(|(id, original)| (module.functions[id].name.clone(), module.functions[original].name.clone()))
+        .collect();
+    for id in replaced.keys() {
+        module.functions.remove(id);
+    }
+    for function in module.functions.values_mut() {
+        for block in function.blocks.values_mut() {
+            for (_, instruction) in &mut block.instructions {
+                if let Instruction::Call { function, .. } = instruction {
+                    if let Some(name) = names.get(function.as_str()) {
+                        *function = name.clone();
+                    }
+                }
+                redirect(instruction.operands_mut(), &replaced, &names, &module.globals);
+            }
+            if let Some(terminator) = &mut block.terminator {
+                redirect(terminator.operands_mut(), &replaced, &names, &module.globals);
+            }
+        }
+    }
+    replaced.len()
+}
+
+/// 関数の名前とソース上の位置を除いた本体の表現
+fn body_key(function: &Function) -> String {
+    let mut key = format!("{:?} -> {} {}\n", function.parameters.iter().map(|(_, ty)| ty).collect::<Vec<_>>(), function.return_type, function.attributes.noreturn);
+    let mut blocks: Vec<&BasicBlock> = function.blocks.values().collect();
+    blocks.sort_by_key(|block| block.id.0);
+    for block in blocks {
+        key.push_str(&format!("{} {:?}:\n", block.id, block.parameters));
+        for (id, instruction) in &block.instructions {
+            key.push_str(&format!(
+                "{} {} {:?}\n",
+                instruction,
+                function.unchecked.contains(id),
+                function.get_instruction_attributes(*id)
+            ));
+        }
+        if let Some(terminator) = &block.terminator {
+            key.push_str(&format!("{}\n", terminator));
+        }
+    }
+    key
+}
+
+fn redirect(
+    operands: Vec<&mut Operand>,
+    replaced: &HashMap<FunctionId, FunctionId>,
+    names: &HashMap<String, String>,
+    globals: &HashMap<String, crate::core::eir::Global>,
+) {
+    for operand in operands {
+        match operand {
+            Operand::Function(id) => {
+                if let Some(original) = replaced.get(id) {
+                    *id = *original;
+                }
+            }
+            // グローバル変数と同じ名前でなければ関数の名前として参照している
+            Operand::Global(name) if !globals.contains_key(name.as_str()) => {
+                if let Some(original) = names.get(name.as_str()) {
+                    *name = original.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn types_of_kind(module: &Module, kind: impl Fn(&TypeKind) -> bool) -> HashSet<TypeId> {
+    module.types.iter().filter(|(_, ty)| kind(&ty.kind)).map(|(id, _)| *id).collect()
+}
+
+/// モジュールに登録された型を探し、なければ `ty` を登録する
+fn find_or_add_type(module: &mut Module, ty: Type, kind: impl Fn(&TypeKind) -> bool) -> TypeId {
+    let mut found: Vec<TypeId> = types_of_kind(module, kind).into_iter().collect();
+    found.sort_by_key(|id| id.0);
+    match found.first() {
+        Some(id) => *id,
+        None => module.add_type(ty),
+    }
+}
+
+fn value_register_type(function: &Function, value: &Operand) -> Option<TypeId> {
+    match value {
+        Operand::Register(reg) => function.get_register_type(*reg),
+        _ => None,
+    }
+}
+
+fn same_args(a: &[Operand], b: &[Operand]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_operand(a, b))
+}
+
+/// 同じ値を表すオペランドか（`Operand` は比較を実装しないので表示で比べる）
+fn same_operand(a: &Operand, b: &Operand) -> bool {
+    a.to_string() == b.to_string()
+}
+
+fn int(value: i64) -> Operand {
+    Operand::Literal(Literal::Int(value))
+}
+
+fn emit(function: &mut Function, block: BlockId, ty: TypeId, op: BinaryOp, lhs: Operand, rhs: Operand) -> Operand {
+    let result = function.create_register(ty);
+    function.add_instruction(block, Instruction::BinaryOp { op, lhs, rhs, result });
+    Operand::Register(result)
+}
+
+fn select(function: &mut Function, block: BlockId, ty: TypeId, condition: Operand, true_value: Operand, false_value: Operand) -> Operand {
+    let result = function.create_register(ty);
+    function.add_instruction(block, Instruction::Select { condition, true_value, false_value, result });
+    Operand::Register(result)
+}
+
+fn set_terminator(function: &mut Function, block: BlockId, terminator: Terminator) {
+    if let Some(block) = function.get_block_mut(block) {
+        block.set_terminator(terminator);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ir_builder::IrBuilder;
+    
+    /// `if x < 0 { panic(x * 2 + 1) }; x` に相当する関数
+    fn checked(id: u32, name: &str, int_type: TypeId, bool_type: TypeId) -> Function {
+        let mut function = Function::new(FunctionId(id), name, int_type, int_type);
+        let x = Operand::Register(function.add_parameter("x", int_type));
+        let mut builder = IrBuilder::new(function);
+        let fail = builder.create_block();
+        let ok = builder.create_block();
+        let negative = builder.binary(BinaryOp::Lt, x.clone(), int(0), bool_type);
+        builder.cond_br(Operand::Register(negative), fail, ok);
+        builder.position_at_end(fail);
+        let doubled = builder.binary(BinaryOp::Mul, x.clone(), int(2), int_type);
+        let code = builder.binary(BinaryOp::Add, Operand::Register(doubled), int(1), int_type);
+        builder.call("panic", vec![Operand::Register(code)], None);
+        builder.unreachable();
+        builder.position_at_end(ok);
+        builder.ret(Some(x));
+        builder.finish()
+    }
+    
+    #[test]
+    fn test_linear_switch_becomes_arithmetic() {
+        // `match x { 0 => 10, 1 => 13, ..., 7 => 31, _ => -1 }`
+        let mut module = Module::new("test");
+        let int_type = module.add_type(Type::int());
+        module.add_type(Type::bool());
+        let mut function = Function::new(FunctionId(0), "f", int_type, int_type);
+        let x = Operand::Register(function.add_parameter("x", int_type));
+        let mut builder = IrBuilder::new(function);
+        let join = builder.create_block();
+        let result = builder.add_block_param(join, int_type);
+        let cases = (0..8).map(|key| (Literal::Int(key), join, vec![int(key * 3 + 10)])).collect();
+        builder.terminate(Terminator::Switch { value: x, default_target: join, default_args: vec![int(-1)], cases });
+        builder.position_at_end(join);
+        builder.ret(Some(Operand::Register(result)));
+        module.add_function(builder.finish());
+        
+        let mut report = SizeReport::default();
+        assert_eq!(report.measure("switchの表引き化", &mut module, lower_switches), 1);
+        let text = module.to_string();
+        assert!(text.contains("= mul %5, 3"), "{}", text);
+        assert!(text.contains("= add %6, 10"), "{}", text);
+        assert!(text.contains("br block_1(%8)"), "{}", text);
+        assert!(!text.contains("switch"), "{}", text);
+        assert_eq!(report.passes[0].delta(), -1, "{}", report);
+        crate::core::eir::verify_after(&module, "switchの表引き化").unwrap();
+    }
+    
+    #[test]
+    fn test_outlined_cold_blocks_are_merged() {
+        let mut module = Module::new("test");
+        let int_type = module.add_type(Type::int());
+        let bool_type = module.add_type(Type::bool());
+        for (id, name) in ["check_a", "check_b"].into_iter().enumerate() {
+            module.add_function(checked(id as u32, name, int_type, bool_type));
+        }
+        
+        assert_eq!(outline_cold_blocks(&mut module), 2);
+        assert_eq!(merge_identical_functions(&mut module), 1);
+        // 切り出した2つの関数は同じ本体なので、`check_b` からも `check_a` の方を呼ぶ
+        let text = module.to_string();
+        assert_eq!(text.matches("call check_a::cold0(%0)").count(), 2, "{}", text);
+        assert!(!text.contains("check_b::cold0"), "{}", text);
+        let cold = module.get_function_by_name("check_a::cold0").unwrap();
+        assert!(cold.attributes.noreturn && cold.attributes.tags.contains(COLD_TAG));
+        assert_eq!(cold.attributes.hotness, Hotness::Cold);
+        // 切り出した関数の呼び出しにも、ほとんど実行されないことを記録する
+        assert_eq!(text.matches("call check_a::cold0(%0) ; cold").count(), 2, "{}", text);
+        crate::core::eir::verify_after(&module, "コールドブロックの切り出し").unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+use std::time::SystemTime;
+
+use memmap2::Mmap;
+
+use crate::core::SourceLocation;
+
+/// これ以上の大きさのファイルはメモリマップで読む（小さなファイルは読み込んだ方が速い）
+const MMAP_THRESHOLD: u64 = 64 * 1024;
+
+/// ソースマップに登録したファイルの番号（登録した順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(usize);
+
+/// ソースマップ全体で通したバイト位置の範囲
+///
+/// 位置からファイルが決まるので、ファイルのパスを持たずに受け渡せる。行と列は `SourceMap::location` で
+/// 必要になったときに求める。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub lo: usize,
+    pub hi: usize,
+}
+
+impl Span {
+    pub fn new(lo: usize, hi: usize) -> Self {
+        debug_assert!(lo <= hi, "範囲の先頭が終わりより後ろにあります: {}..{}", lo, hi);
+        Self { lo, hi }
+    }
+    
+    /// 範囲の長さ（フィールドを直接書き換えて先頭が終わりより後ろになった範囲は0）
+    pub fn len(self) -> usize {
+        self.hi.saturating_sub(self.lo)
+    }
+    
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+    
+    /// `self` の先頭から `other` の終わりまでの範囲
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+}
+
+/// ソースの各行の先頭のバイト位置の表
+///
+/// 行と列は字句解析器と同じく1から数え、列は文字単位で数える。
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let starts = std::iter::once(0).chain(text.match_indices('\n').map(|(index, _)| index + 1)).collect();
+        Self { starts }
+    }
+    
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+    
+    /// バイト位置の行と列
+    pub fn line_column(&self, text: &str, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let start = self.starts[line - 1];
+        let column = text.get(start..offset).map_or(offset - start, |prefix| prefix.chars().count());
+        (line, column + 1)
+    }
+    
+    /// 行と列のバイト位置（行の終わりの直後までの列を指定できる）
+    pub fn offset(&self, text: &str, line: usize, column: usize) -> Option<usize> {
+        let range = self.line_range(text, line)?;
+        let content = &text[range.clone()];
+        content
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain(std::iter::once(content.len()))
+            .nth(column.checked_sub(1)?)
+            .map(|index| range.start + index)
+    }
+    
+    /// 行の範囲（行末の改行は含まない）
+    pub fn line_range(&self, text: &str, line: usize) -> Option<Range<usize>> {
+        let start = *self.starts.get(line.checked_sub(1)?)?;
+        let end = self.starts.get(line).map_or(text.len(), |next| next - 1);
+        Some(start..end)
+    }
+}
+
+/// ファイルの内容
+enum SourceText {
+    Owned(String),
+    /// メモリマップしたファイル（登録時にUTF-8であることを確かめている）
+    Mapped(Mmap),
+}
+
+/// ソースマップに登録した1つのファイル
+pub struct SourceFile {
+    id: FileId,
+    path: PathBuf,
+    /// ファイルの先頭のソースマップ全体での位置
+    start: usize,
+    text: SourceText,
+    /// 読み込んだときのファイルの更新時刻（メモリ上のソースは `None`）
+    modified: Option<SystemTime>,
+    /// 行の先頭の表（最初に行と列を求めるときに作る）
+    lines: OnceLock<LineIndex>,
+}
+
+impl SourceFile {
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+    
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    pub fn text(&self) -> &str {
+        match &self.text {
+            SourceText::Owned(text) => text,
+            // SAFETY: 登録時に `str::from_utf8` で確かめている
+            SourceText::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+        }
+    }
+    
+    /// メモリマップで読んだファイルかどうか
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.text, SourceText::Mapped(_))
+    }
+    
+    /// ファイル全体の範囲
+    pub fn span(&self) -> Span {
+        Span::new(self.start, self.start + self.text().len())
+    }
+    
+    /// ファイルの中のバイト範囲（字句解析器の `SpannedToken::span` など）をソースマップ全体の範囲にする
+    pub fn span_of(&self, range: Range<usize>) -> Span {
+        Span::new(self.start + range.start, self.start + range.end)
+    }
+    
+    pub fn lines(&self) -> &LineIndex {
+        self.lines.get_or_init(|| LineIndex::new(self.text()))
+    }
+    
+    /// ファイルの中のバイト範囲の位置情報（長さは文字数）
+    pub fn location(&self, range: Range<usize>) -> SourceLocation {
+        let (line, column) = self.lines().line_column(self.text(), range.start);
+        let length = self.text().get(range).map_or(0, |text| text.chars().count());
+        SourceLocation::new(self.path.clone(), line, column, length)
+    }
+    
+    /// 行の内容（行末の改行は含まない）
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let range = self.lines().line_range(self.text(), line)?;
+        Some(self.text()[range].trim_end_matches('\r'))
+    }
+    
+    /// 行と列のファイルの中のバイト位置
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        self.lines().offset(self.text(), line, column)
+    }
+}
+
+/// コンパイル全体で共有するソースファイルの表
+///
+/// 登録したファイルにはソースマップ全体で通したバイト位置を割り当てるので、複数のファイルにまたがる
+/// 位置も `Span` 1つで表せる。大きなファイルはメモリマップで読み、行と列はファイルごとに初めて求める
+/// ときに行の表を作る。複製したソースマップは同じ表を共有し、スレッドをまたいで使える。
+///
+/// メモリマップしたファイルをコンパイル中に書き換えると、読んだ内容が変わる（切り詰めるとプロセスが
+/// 異常終了する）。エディタの多くは別のファイルに書いてから置き換えるので、この場合は影響を受けない。
+#[derive(Clone, Default)]
+pub struct SourceMap {
+    files: Arc<RwLock<Vec<Arc<SourceFile>>>>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// ファイルを読み込んで登録（同じパスで登録済みのファイルが変更されていなければ、それを返す）
+    ///
+    /// 変更されていた場合は新しいファイルとして登録し直す。前の内容を指す `Span` はそのまま使える。
+    pub fn load(&self, path: &Path) -> io::Result<Arc<SourceFile>> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified().ok();
+        let unchanged = |file: &Arc<SourceFile>| {
+            file.modified.is_some() && file.modified == modified && file.text().len() as u64 == metadata.len()
+        };
+        if let Some(file) = self.find(path).filter(unchanged) {
+            return Ok(file);
+        }
+        
+        let text = if metadata.len() >= MMAP_THRESHOLD {
+            // SAFETY: マップした内容はこの後 UTF-8 であることを確かめてから使う。コンパイル中にファイルを
+            // 書き換えた場合の扱いは `SourceMap` の説明のとおり
+            let map = unsafe { Mmap::map(&File::open(path)?)? };
+            std::str::from_utf8(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            SourceText::Mapped(map)
+        } else {
+            SourceText::Owned(fs::read_to_string(path)?)
+        };
+        Ok(self.insert(path.to_path_buf(), text, modified))
+    }
+    
+    /// メモリ上のソース（REPLの入力や生成したコードなど）を登録
+    pub fn add(&self, path: PathBuf, text: String) -> Arc<SourceFile> {
+        self.insert(path, SourceText::Owned(text), None)
+    }
+    
+    fn insert(&self, path: PathBuf, text: SourceText, modified: Option<SystemTime>) -> Arc<SourceFile> {
+        let mut files = self.files.write().unwrap_or_else(PoisonError::into_inner);
+        // ファイルの間を1バイト空け、ファイルの終わりの位置も次のファイルの先頭と区別できるようにする
+        let start = files.last().map_or(0, |last| last.span().hi + 1);
+        let file = Arc::new(SourceFile {
+            id: FileId(files.len()),
+            path,
+            start,
+            text,
+            modified,
+            lines: OnceLock::new(),
+        });
+        files.push(file.clone());
+        file
+    }
+    
+    pub fn file(&self, id: FileId) -> Option<Arc<SourceFile>> {
+        self.files.read().unwrap_or_else(PoisonError::into_inner).get(id.0).cloned()
+    }
+    
+    /// パスで登録したファイル（登録し直したファイルは最新のもの）
+    pub fn find(&self, path: &Path) -> Option<Arc<SourceFile>> {
+        let files = self.files.read().unwrap_or_else(PoisonError::into_inner);
+        files.iter().rev().find(|file| file.path == path).cloned()
+    }
+    
+    /// 位置を含むファイル
+    pub fn lookup(&self, position: usize) -> Option<Arc<SourceFile>> {
+        let files = self.files.read().unwrap_or_else(PoisonError::into_inner);
+        let index = files.partition_point(|file| file.start <= position).checked_sub(1)?;
+        let file = &files[index];
+        (position <= file.span().hi).then(|| file.clone())
+    }
+    
+    /// 範囲の位置情報（ファイル、行、列）
+    pub fn location(&self, span: Span) -> Option<SourceLocation> {
+        let file = self.lookup(span.lo)?;
+        let end = span.hi.min(file.span().hi);
+        Some(file.location(span.lo - file.start..end.max(span.lo) - file.start))
+    }
+    
+    /// 位置情報をソースマップで求め直す（範囲を持たない位置や、登録していない範囲はそのまま返す）
+    pub fn resolve(&self, location: &SourceLocation) -> SourceLocation {
+        match location.span.and_then(|span| self.location(span).map(|resolved| resolved.with_span(span))) {
+            Some(resolved) => resolved,
+            None => location.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for SourceMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = self.files.read().unwrap_or_else(PoisonError::into_inner);
+        f.debug_list().entries(files.iter().map(|file| &file.path)).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::lexer::Lexer;
+    
+    #[test]
+    fn test_resolves_spans_across_files() {
+        let sources = SourceMap::new();
+        let main = sources.add(PathBuf::from("main.eid"), "let a = 1;\nlet 合計 = a;\n".to_string());
+        let util = sources.add(PathBuf::from("util.eid"), "fn f() {}".to_string());
+        assert!(main.span().hi < util.span().lo);
+        
+        // 字句解析器のトークンの範囲から、行と列を求める
+        let tokens: Vec<_> = Lexer::new(main.text(), main.path().to_path_buf()).spanned().map(|token| token.unwrap()).collect();
+        let total = tokens.iter().find(|token| &main.text()[token.span.clone()] == "合計").unwrap();
+        let location = sources.location(main.span_of(total.span.clone())).unwrap();
+        assert_eq!((location.line, location.column, location.length), (2, 5, 2));
+        assert_eq!((location.line, location.column), (total.token.location.line, total.token.location.column));
+        assert_eq!(main.offset(2, 5), Some(total.span.start));
+        
+        let location = sources.location(util.span_of(3..4)).unwrap();
+        assert_eq!((location.file, location.line, location.column), (PathBuf::from("util.eid"), 1, 4));
+        assert_eq!(main.line(2), Some("let 合計 = a;"));
+        assert_eq!(main.line(4), None);
+        assert_eq!(sources.lookup(util.span().hi + 1).map(|file| file.id()), None);
+        
+        // 大きなファイルはメモリマップで読み、変更されるまでは同じファイルを返す
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.eid");
+        let source = "let x = 1;\n".repeat(MMAP_THRESHOLD as usize / 10);
+        fs::write(&path, &source).unwrap();
+        let large = sources.load(&path).unwrap();
+        assert!(large.is_mapped());
+        assert_eq!(large.text(), source);
+        assert_eq!(sources.load(&path).unwrap().id(), large.id());
+        assert_eq!(large.line(large.lines().line_count() - 1), Some("let x = 1;"));
+    }
+    
+    #[test]
+    fn test_lexer_attaches_spans() {
+        let sources = SourceMap::new();
+        sources.add(PathBuf::from("prelude.eid"), "let x = 1;".to_string());
+        let main = sources.add(PathBuf::from("main.eid"), "let a = 1;\n  合計 + a\n".to_string());
+        
+        // トークンの範囲はソースマップ全体で通した位置で、行と列を求め直しても同じ位置になる
+        let tokens = Lexer::for_file(&main).tokenize().unwrap();
+        let text = |span: Span| &main.text()[span.lo - main.span().lo..span.hi - main.span().lo];
+        let total = tokens.iter().find(|token| token.location.span.is_some_and(|span| text(span) == "合計")).unwrap();
+        assert_eq!((total.location.line, total.location.column), (2, 3));
+        assert_eq!(sources.resolve(&total.location), total.location);
+        assert!(tokens.iter().all(|token| token.location.span.is_some()));
+        assert_eq!(Lexer::new(main.text(), main.path().to_path_buf()).tokenize().unwrap()[0].location.span, None);
+        
+        // 範囲を持たない位置はそのまま
+        let location = SourceLocation::new(PathBuf::from("other.eid"), 3, 1, 1);
+        assert_eq!(sources.resolve(&location), location);
+        
+        // 先頭が終わりより後ろになった範囲の長さは0
+        let span = Span { lo: 5, hi: 3 };
+        assert_eq!(span.len(), 0);
+        assert!(span.is_empty());
+    }
+}
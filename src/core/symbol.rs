@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 /// シンボル識別子
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SymbolId(pub usize);
 
 impl fmt::Display for SymbolId {
@@ -187,6 +189,18 @@ impl SymbolTable {
         None
     }
     
+    /// 現在のスコープから見えるシンボルの名前（内側のスコープから順に。「もしかして」の候補に使う）
+    pub fn visible_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        let mut current_scope_id = Some(self.current_scope);
+        while let Some(scope_id) = current_scope_id {
+            let scope = self.scopes.get(&scope_id).unwrap();
+            names.extend(scope.symbols.keys().map(String::as_str));
+            current_scope_id = scope.parent;
+        }
+        names
+    }
+    
     /// シンボルIDからシンボル情報を取得
     pub fn get_symbol(&self, id: SymbolId) -> Option<&Symbol> {
         self.symbols.get(&id)
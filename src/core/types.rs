@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
 
 use super::symbol::SymbolId;
 
 /// 型識別子
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct TypeId(pub usize);
 
 impl fmt::Display for TypeId {
@@ -14,15 +17,119 @@ impl fmt::Display for TypeId {
     }
 }
 
+/// 組み込みの `Option<T>` 型の名前
+pub const OPTION_TYPE_NAME: &str = "Option";
+/// 組み込みの `Result<T, E>` 型の名前
+pub const RESULT_TYPE_NAME: &str = "Result";
+/// 組み込みの `List<T>` 型（配列）の名前
+pub const LIST_TYPE_NAME: &str = "List";
+/// 組み込みの `Range<T>` 型（`start..end` の範囲）の名前
+pub const RANGE_TYPE_NAME: &str = "Range";
+
+/// ビット幅を指定した数値型（`i64` は `Int`、`f64` は `Float` と同じ型）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NumericType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl NumericType {
+    pub const ALL: [NumericType; 10] = [
+        NumericType::I8,
+        NumericType::I16,
+        NumericType::I32,
+        NumericType::I64,
+        NumericType::U8,
+        NumericType::U16,
+        NumericType::U32,
+        NumericType::U64,
+        NumericType::F32,
+        NumericType::F64,
+    ];
+    
+    /// 型名（`i32` など）から取得
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ty| ty.name() == name)
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            NumericType::I8 => "i8",
+            NumericType::I16 => "i16",
+            NumericType::I32 => "i32",
+            NumericType::I64 => "i64",
+            NumericType::U8 => "u8",
+            NumericType::U16 => "u16",
+            NumericType::U32 => "u32",
+            NumericType::U64 => "u64",
+            NumericType::F32 => "f32",
+            NumericType::F64 => "f64",
+        }
+    }
+    
+    pub fn bits(&self) -> u32 {
+        match self {
+            NumericType::I8 | NumericType::U8 => 8,
+            NumericType::I16 | NumericType::U16 => 16,
+            NumericType::I32 | NumericType::U32 | NumericType::F32 => 32,
+            NumericType::I64 | NumericType::U64 | NumericType::F64 => 64,
+        }
+    }
+    
+    pub fn is_float(&self) -> bool {
+        matches!(self, NumericType::F32 | NumericType::F64)
+    }
+    
+    pub fn is_signed(&self) -> bool {
+        matches!(self, NumericType::I8 | NumericType::I16 | NumericType::I32 | NumericType::I64)
+    }
+    
+    /// 整数型の最大値（浮動小数点数型はNone）
+    pub fn max_value(&self) -> Option<u64> {
+        match self {
+            _ if self.is_float() => None,
+            _ if self.is_signed() => Some((1u64 << (self.bits() - 1)) - 1),
+            _ => Some(u64::MAX >> (64 - self.bits())),
+        }
+    }
+    
+    /// 整数値がこの型で表せるかどうか（`u64` の値は `i64` のビット列として持つ）
+    pub fn contains(&self, value: i64) -> bool {
+        match self {
+            _ if self.is_float() => true,
+            NumericType::I64 | NumericType::U64 => true,
+            _ if self.is_signed() => {
+                let max = self.max_value().unwrap_or(0) as i64;
+                (-max - 1..=max).contains(&value)
+            }
+            _ => (0..=self.max_value().unwrap_or(0) as i64).contains(&value),
+        }
+    }
+}
+
+impl fmt::Display for NumericType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 /// 型パラメータ
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TypeParam {
     pub name: String,
     pub constraints: Vec<Type>,
 }
 
 /// 型のバリエーション
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TypeKind {
     // 基本型
     Unit,
@@ -31,9 +138,20 @@ pub enum TypeKind {
     Float,
     Char,
     String,
+    /// `Int`（`i64`）と `Float`（`f64`）以外のビット幅を指定した数値型
+    Numeric(NumericType),
+    /// 任意精度の整数（演算は標準ライブラリの `bigint` モジュールの呼び出しに下げる）
+    BigInt,
     
     // 複合型
     Array(Box<Type>),
+    /// 固定長配列（`[Int; 8]`）。スタックに確保し、値はその先頭のアドレスで持つ
+    FixedArray {
+        element: Box<Type>,
+        length: usize,
+    },
+    /// 配列の要素を参照するスライス（`&[Int]`）。先頭のアドレスと要素数の組
+    Slice(Box<Type>),
     Tuple(Vec<Type>),
     Function {
         params: Vec<Type>,
@@ -68,7 +186,9 @@ pub enum TypeKind {
     DSLType {
         name: String,
         dsl_name: String,
-        custom_data: Option<Rc<dyn std::any::Any>>,
+        // DSLの処理系だけが使うデータ（EIRの直列化では保存しない。型検査を並列に行うためスレッド間で共有できる）
+        #[serde(skip)]
+        custom_data: Option<Arc<dyn std::any::Any + Send + Sync>>,
     },
     
     // 未知の型（型推論中に使用）
@@ -78,8 +198,11 @@ pub enum TypeKind {
     Error,
 }
 
+/// 次に `Type::new` で振る型ID
+static NEXT_TYPE_ID: AtomicUsize = AtomicUsize::new(0);
+
 /// 型を表す構造体
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Type {
     pub id: TypeId,
     pub kind: TypeKind,
@@ -87,17 +210,17 @@ pub struct Type {
 
 impl Type {
     pub fn new(kind: TypeKind) -> Self {
-        static mut NEXT_ID: usize = 0;
-        
-        let id = unsafe {
-            let id = TypeId(NEXT_ID);
-            NEXT_ID += 1;
-            id
-        };
-        
+        let id = TypeId(NEXT_TYPE_ID.fetch_add(1, Ordering::Relaxed));
         Self { id, kind }
     }
     
+    /// 読み込んだ型と重ならないよう、`id` 以下のIDを今後の `Type::new` で使わないようにする
+    ///
+    /// 直列化したEIR（`.eirc`）の型は書き出したプロセスで振ったIDを持つので、読み込んだ後に呼び出す。
+    pub fn reserve_ids(id: TypeId) {
+        NEXT_TYPE_ID.fetch_max(id.0 + 1, Ordering::Relaxed);
+    }
+    
     /// 基本的な組み込み型を作成するヘルパーメソッド
     pub fn unit() -> Self {
         Self::new(TypeKind::Unit)
@@ -115,6 +238,29 @@ impl Type {
         Self::new(TypeKind::Float)
     }
     
+    /// ビット幅を指定した数値型（`i64` は `Int`、`f64` は `Float` になる）
+    pub fn numeric(numeric: NumericType) -> Self {
+        match numeric {
+            NumericType::I64 => Self::int(),
+            NumericType::F64 => Self::float(),
+            _ => Self::new(TypeKind::Numeric(numeric)),
+        }
+    }
+    
+    /// 数値型ならビット幅を返す
+    pub fn numeric_type(&self) -> Option<NumericType> {
+        match &self.kind {
+            TypeKind::Int => Some(NumericType::I64),
+            TypeKind::Float => Some(NumericType::F64),
+            TypeKind::Numeric(numeric) => Some(*numeric),
+            _ => None,
+        }
+    }
+    
+    pub fn bigint() -> Self {
+        Self::new(TypeKind::BigInt)
+    }
+    
     pub fn char() -> Self {
         Self::new(TypeKind::Char)
     }
@@ -127,6 +273,83 @@ impl Type {
         Self::new(TypeKind::Array(Box::new(element_type)))
     }
     
+    pub fn fixed_array(element_type: Type, length: usize) -> Self {
+        Self::new(TypeKind::FixedArray { element: Box::new(element_type), length })
+    }
+    
+    pub fn slice(element_type: Type) -> Self {
+        Self::new(TypeKind::Slice(Box::new(element_type)))
+    }
+    
+    /// 固定長配列とスライスの要素の型
+    pub fn element_type(&self) -> Option<&Type> {
+        match &self.kind {
+            TypeKind::FixedArray { element, .. } | TypeKind::Slice(element) => Some(element),
+            _ => None,
+        }
+    }
+    
+    /// メモリ上の大きさとアラインメント（バイト数）。メモリに置かない型は `None`
+    ///
+    /// 文字列・`BigInt` はポインタ、スライスはポインタと要素数の組として64ビットの環境を前提にする。
+    /// タプルと構造体は要素を宣言の順に置き、それぞれのアラインメントまで詰め物を入れる（Cの構造体と同じ配置）。
+    pub fn layout(&self) -> Option<(usize, usize)> {
+        match &self.kind {
+            TypeKind::Unit | TypeKind::Bool => Some((1, 1)),
+            TypeKind::Char => Some((4, 4)),
+            TypeKind::Int | TypeKind::Float | TypeKind::String | TypeKind::BigInt => Some((8, 8)),
+            TypeKind::Numeric(numeric) => {
+                let bytes = numeric.bits() as usize / 8;
+                Some((bytes, bytes))
+            }
+            TypeKind::FixedArray { element, length } => {
+                let (_, align) = element.layout()?;
+                Some((element.stride()? * length, align))
+            }
+            TypeKind::Slice(_) => Some((16, 8)),
+            TypeKind::Tuple(_) | TypeKind::Struct { .. } => {
+                let fields = self.field_types()?;
+                let offsets = self.field_offsets()?;
+                let size = match (offsets.last(), fields.last()) {
+                    (Some(offset), Some(last)) => offset + last.layout()?.0,
+                    _ => 0,
+                };
+                let align = fields.iter().filter_map(|field| field.layout()).map(|(_, align)| align).max().unwrap_or(1);
+                Some((size, align))
+            }
+            _ => None,
+        }
+    }
+    
+    /// タプルの要素・構造体のフィールドの型（宣言の順）。タプルと構造体でなければ `None`
+    pub fn field_types(&self) -> Option<Vec<&Type>> {
+        match &self.kind {
+            TypeKind::Tuple(elements) => Some(elements.iter().collect()),
+            TypeKind::Struct { fields, .. } => Some(fields.iter().map(|field| &field.field_type).collect()),
+            _ => None,
+        }
+    }
+    
+    /// タプルの各要素・構造体の各フィールドのメモリ上の位置（バイト数）。それぞれのアラインメントにそろえて順に並べる
+    pub fn field_offsets(&self) -> Option<Vec<usize>> {
+        let elements = self.field_types()?;
+        let mut offsets = Vec::with_capacity(elements.len());
+        let mut end = 0usize;
+        for element in elements {
+            let (size, align) = element.layout()?;
+            let offset = end.div_ceil(align) * align;
+            offsets.push(offset);
+            end = offset + size;
+        }
+        Some(offsets)
+    }
+    
+    /// 配列の要素として並べたときの間隔（大きさをアラインメントの倍数に切り上げた値）
+    pub fn stride(&self) -> Option<usize> {
+        let (size, align) = self.layout()?;
+        Some(size.div_ceil(align) * align)
+    }
+    
     pub fn tuple(element_types: Vec<Type>) -> Self {
         Self::new(TypeKind::Tuple(element_types))
     }
@@ -145,6 +368,90 @@ impl Type {
         })
     }
     
+    /// 組み込みの `Option<T>` 型（`None` と `Some(T)` の列挙体）
+    pub fn option(inner: Type) -> Self {
+        Self::new(TypeKind::Enum {
+            name: OPTION_TYPE_NAME.to_string(),
+            variants: vec![
+                EnumVariant { name: "None".to_string(), payload: None },
+                EnumVariant { name: "Some".to_string(), payload: Some(EnumVariantPayload::Tuple(vec![inner])) },
+            ],
+            type_params: Vec::new(),
+        })
+    }
+    
+    /// 組み込みの `Result<T, E>` 型（`Ok(T)` と `Err(E)` の列挙体）
+    pub fn result(ok: Type, err: Type) -> Self {
+        Self::new(TypeKind::Enum {
+            name: RESULT_TYPE_NAME.to_string(),
+            variants: vec![
+                EnumVariant { name: "Ok".to_string(), payload: Some(EnumVariantPayload::Tuple(vec![ok])) },
+                EnumVariant { name: "Err".to_string(), payload: Some(EnumVariantPayload::Tuple(vec![err])) },
+            ],
+            type_params: Vec::new(),
+        })
+    }
+    
+    /// 組み込みの `Range<T>` 型（`start` 以上 `end` 未満の整数を順に返すイテレータ）
+    pub fn range(element: Type) -> Self {
+        let field = |name: &str| StructField { name: name.to_string(), field_type: element.clone() };
+        Self::new(TypeKind::Struct {
+            name: RANGE_TYPE_NAME.to_string(),
+            fields: vec![field("start"), field("end")],
+            type_params: Vec::new(),
+        })
+    }
+    
+    /// `Range<T>` なら `T` を返す
+    pub fn range_element(&self) -> Option<&Type> {
+        match &self.kind {
+            TypeKind::Struct { name, fields, .. } if name == RANGE_TYPE_NAME => Some(&fields.first()?.field_type),
+            _ => None,
+        }
+    }
+    
+    /// `for` で順に取り出す要素の型（範囲・固定長配列・スライスのとき）
+    pub fn iteration_element(&self) -> Option<&Type> {
+        self.range_element().or_else(|| self.element_type())
+    }
+    
+    /// `Option<T>` なら `T` を返す
+    pub fn option_payload(&self) -> Option<&Type> {
+        match &self.kind {
+            TypeKind::Enum { name, variants, .. } if name == OPTION_TYPE_NAME => variants.get(1)?.tuple_payload(),
+            _ => None,
+        }
+    }
+    
+    /// `Result<T, E>` なら `(T, E)` を返す
+    pub fn result_payloads(&self) -> Option<(&Type, &Type)> {
+        match &self.kind {
+            TypeKind::Enum { name, variants, .. } if name == RESULT_TYPE_NAME => {
+                Some((variants.first()?.tuple_payload()?, variants.get(1)?.tuple_payload()?))
+            }
+            _ => None,
+        }
+    }
+    
+    /// 整数型（`Int` と、`i8`〜`u64` の整数の数値型）かどうか。ビット演算とシフトはこの型にだけ使える
+    pub fn is_integer(&self) -> bool {
+        match &self.kind {
+            TypeKind::Int => true,
+            TypeKind::Numeric(numeric) => !numeric.is_float(),
+            _ => false,
+        }
+    }
+    
+    /// まだ型が決まっていない（推論中の型・型パラメータ）かどうか。型の規則の検査はこの型の値を見送る
+    pub fn is_undetermined(&self) -> bool {
+        matches!(self.kind, TypeKind::Unknown | TypeKind::TypeParam { .. })
+    }
+    
+    /// 成功（`Some`・`Ok`）か失敗（`None`・`Err`）のどちらかを表す、`Option` または `Result` 型かどうか
+    pub fn is_fallible(&self) -> bool {
+        self.option_payload().is_some() || self.result_payloads().is_some()
+    }
+    
     pub fn unknown() -> Self {
         Self::new(TypeKind::Unknown)
     }
@@ -154,16 +461,23 @@ impl Type {
     }
 }
 
+/// ソースコードと同じ書き方で型を表示する（`List<Int>`、`Option<String>` など）
+///
+/// エラーメッセージやEIRの表示はすべてこの表記を使う。型IDは表示しないので、同じ型は常に同じ文字列になる。
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.kind {
             TypeKind::Unit => write!(f, "()"),
-            TypeKind::Bool => write!(f, "bool"),
-            TypeKind::Int => write!(f, "int"),
-            TypeKind::Float => write!(f, "float"),
-            TypeKind::Char => write!(f, "char"),
-            TypeKind::String => write!(f, "string"),
-            TypeKind::Array(elem) => write!(f, "[{}]", elem),
+            TypeKind::Bool => write!(f, "Bool"),
+            TypeKind::Int => write!(f, "Int"),
+            TypeKind::Float => write!(f, "Float"),
+            TypeKind::Char => write!(f, "Char"),
+            TypeKind::String => write!(f, "String"),
+            TypeKind::Numeric(numeric) => write!(f, "{}", numeric),
+            TypeKind::BigInt => write!(f, "BigInt"),
+            TypeKind::Array(elem) => write!(f, "{}<{}>", LIST_TYPE_NAME, elem),
+            TypeKind::FixedArray { element, length } => write!(f, "[{}; {}]", element, length),
+            TypeKind::Slice(element) => write!(f, "&[{}]", element),
             TypeKind::Tuple(elems) => {
                 write!(f, "(")?;
                 for (i, elem) in elems.iter().enumerate() {
@@ -184,7 +498,17 @@ impl fmt::Display for Type {
                 }
                 write!(f, ") -> {}", return_type)
             },
+            TypeKind::Struct { .. } if self.range_element().is_some() => {
+                write!(f, "{}<{}>", RANGE_TYPE_NAME, self.range_element().unwrap())
+            },
             TypeKind::Struct { name, .. } => write!(f, "{}", name),
+            TypeKind::Enum { .. } if self.option_payload().is_some() => {
+                write!(f, "{}<{}>", OPTION_TYPE_NAME, self.option_payload().unwrap())
+            },
+            TypeKind::Enum { .. } if self.result_payloads().is_some() => {
+                let (ok, err) = self.result_payloads().unwrap();
+                write!(f, "{}<{}, {}>", RESULT_TYPE_NAME, ok, err)
+            },
             TypeKind::Enum { name, .. } => write!(f, "{}", name),
             TypeKind::TypeRef { name, .. } => write!(f, "{}", name),
             TypeKind::TypeParam { name } => write!(f, "{}", name),
@@ -196,21 +520,31 @@ impl fmt::Display for Type {
 }
 
 /// 構造体のフィールド定義
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StructField {
     pub name: String,
     pub field_type: Type,
 }
 
 /// 列挙体のバリアント定義
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EnumVariant {
     pub name: String,
     pub payload: Option<EnumVariantPayload>,
 }
 
+impl EnumVariant {
+    /// 要素が1つのタプル形式のペイロードなら、その型を返す
+    pub fn tuple_payload(&self) -> Option<&Type> {
+        match &self.payload {
+            Some(EnumVariantPayload::Tuple(types)) if types.len() == 1 => types.first(),
+            _ => None,
+        }
+    }
+}
+
 /// 列挙体バリアントのデータ型
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EnumVariantPayload {
     Tuple(Vec<Type>),
     Struct(Vec<StructField>),
@@ -276,4 +610,55 @@ impl TypeEnvironment {
     pub fn get_symbol_type(&self, symbol: SymbolId) -> Option<&Type> {
         self.symbol_types.get(&symbol).and_then(|id| self.types.get(id))
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_display_uses_source_syntax() {
+        assert_eq!(Type::array(Type::int()).to_string(), "List<Int>");
+        assert_eq!(Type::option(Type::array(Type::string())).to_string(), "Option<List<String>>");
+        assert_eq!(Type::result(Type::float(), Type::string()).to_string(), "Result<Float, String>");
+        assert_eq!(Type::function(vec![Type::char(), Type::bool()], Type::unit()).to_string(), "(Char, Bool) -> ()");
+        assert_eq!(Type::numeric(NumericType::U8).to_string(), "u8");
+        assert_eq!(Type::fixed_array(Type::int(), 8).to_string(), "[Int; 8]");
+        assert_eq!(Type::slice(Type::char()).to_string(), "&[Char]");
+    }
+    
+    #[test]
+    fn test_layout() {
+        assert_eq!(Type::fixed_array(Type::int(), 8).layout(), Some((64, 8)));
+        assert_eq!(Type::fixed_array(Type::numeric(NumericType::U16), 3).layout(), Some((6, 2)));
+        // 入れ子の配列の要素は、内側の配列の大きさごとに並ぶ
+        let row = Type::fixed_array(Type::bool(), 3);
+        assert_eq!(row.stride(), Some(3));
+        assert_eq!(Type::fixed_array(row, 4).layout(), Some((12, 1)));
+        assert_eq!(Type::slice(Type::float()).layout(), Some((16, 8)));
+        assert_eq!(Type::array(Type::int()).layout(), None);
+        
+        let pair = Type::tuple(vec![Type::bool(), Type::float(), Type::char()]);
+        assert_eq!(pair.field_offsets(), Some(vec![0, 8, 16]));
+        assert_eq!(pair.layout(), Some((20, 8)));
+        assert_eq!(pair.stride(), Some(24));
+        
+        // 範囲は開始と終了の値を並べた構造体
+        let range = Type::range(Type::numeric(NumericType::I32));
+        assert_eq!(range.to_string(), "Range<i32>");
+        assert_eq!(range.field_offsets(), Some(vec![0, 4]));
+        assert_eq!(range.layout(), Some((8, 4)));
+    }
+    
+    #[test]
+    fn test_integer_types() {
+        assert!(Type::int().is_integer());
+        assert!(Type::numeric(NumericType::U8).is_integer());
+        for ty in [Type::numeric(NumericType::F32), Type::float(), Type::bool(), Type::unit(), Type::tuple(vec![Type::int()])] {
+            assert!(!ty.is_integer(), "{}", ty);
+        }
+        assert!(Type::new(TypeKind::TypeParam { name: "T".to_string() }).is_undetermined());
+        assert!(Type::unknown().is_undetermined());
+        assert!(!Type::array(Type::int()).is_undetermined());
+    }
+}
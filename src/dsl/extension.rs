@@ -1,8 +1,11 @@
 use std::any::Any;
+use std::path::Path;
 
-use crate::core::Result;
-use crate::core::ast::{ASTNode, Program};
+use crate::core::{EidosError, Result};
+use crate::core::ast::{ASTNode, KeywordAlias, Program};
+use crate::core::error::ErrorCollector;
 use crate::core::types::Type;
+use super::grammar::Grammar;
 
 /// DSL拡張のトレイト
 pub trait DSLExtension: Send + Sync {
@@ -26,6 +29,47 @@ pub trait DSLExtension: Send + Sync {
         Vec::new()
     }
     
+    /// このDSLの文法（エクスポート可能な場合）
+    fn grammar(&self) -> Option<Grammar> {
+        None
+    }
+    
+    /// 有効化されたスコープ内で、文の先頭に現れるとこのDSLの構文として扱われるキーワード
+    fn keywords(&self) -> Vec<String> {
+        Vec::new()
+    }
+    
+    /// 有効化したスコープで読み替える、予約語と演算子の別名（`もし` → `if` など）
+    fn keyword_aliases(&self) -> Vec<KeywordAlias> {
+        Vec::new()
+    }
+    
+    /// DSLブロックの内容をインデントで区切るか（Pythonのような、空白に意味のある言語の埋め込み用）
+    ///
+    /// `true` にすると、`process_block` に渡す内容にオフサイドルールで `NEWLINE`・`INDENT`・`DEDENT` が入る
+    /// （`frontend::lexer::offside_rule`）。
+    fn layout_sensitive(&self) -> bool {
+        false
+    }
+    
+    /// 型プロバイダ：外部のスキーマ（JSON Schema、protobuf、SQLのDDLなど）から型を生成する
+    ///
+    /// `provide <DSL名> "パス";` を構文解析するときに呼ばれる。`schema` は `path` のファイルの内容で、
+    /// 生成した構造体（`TypeKind::Struct`）や列挙型（`TypeKind::Enum`）を名前とともに返す。
+    /// 返した型は、同じファイルの以降の型注釈で名前で参照できる。
+    fn provide_types(&self, _path: &Path, _schema: &str) -> Result<Vec<(String, Type)>> {
+        Err(EidosError::DSL {
+            message: format!("DSL拡張 '{}' は型プロバイダに対応していません", self.name()),
+            dsl_name: self.name().to_string(),
+        })
+    }
+    
+    /// 型検査の後に実行する、このDSL固有の検査（SQLのテーブル名がスキーマにあるか、など）
+    ///
+    /// `program` は型検査まで終えたASTで、DSLブロックは展開前の内容とともに `Node::DSLBlock` として残っている。
+    /// 見つけた誤りを `diagnostics` に加えると、ほかの検査のエラーと一緒に報告される。
+    fn validate(&self, _program: &Program, _diagnostics: &mut ErrorCollector) {}
+    
     /// カスタムデータにアクセス（実装固有の拡張用）
     fn as_any(&self) -> &dyn Any;
 } 
\ No newline at end of file
@@ -0,0 +1,607 @@
+use std::any::Any;
+use std::fmt;
+
+use crate::core::{Result, EidosError};
+use crate::core::ast::{ASTNode, Program};
+use crate::core::types::Type;
+use super::extension::DSLExtension;
+
+/// 文法式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarExpr {
+    /// 終端記号（リテラル）
+    Terminal(String),
+    /// 非終端記号（他の規則への参照）
+    NonTerminal(String),
+    /// 正規表現で表される字句パターン
+    Pattern(String),
+    /// 連接
+    Sequence(Vec<GrammarExpr>),
+    /// 選択
+    Choice(Vec<GrammarExpr>),
+    /// 0回以上の繰り返し
+    ZeroOrMore(Box<GrammarExpr>),
+    /// 1回以上の繰り返し
+    OneOrMore(Box<GrammarExpr>),
+    /// 省略可能
+    Optional(Box<GrammarExpr>),
+}
+
+/// 文法規則
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrammarRule {
+    pub name: String,
+    pub expr: GrammarExpr,
+}
+
+/// DSLの文法（最初の規則が開始記号）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    pub name: String,
+    pub rules: Vec<GrammarRule>,
+}
+
+impl Grammar {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rules: Vec::new(),
+        }
+    }
+    
+    /// 規則を追加
+    pub fn add_rule(&mut self, name: &str, expr: GrammarExpr) {
+        self.rules.push(GrammarRule {
+            name: name.to_string(),
+            expr,
+        });
+    }
+    
+    /// 規則を名前で取得
+    pub fn get_rule(&self, name: &str) -> Option<&GrammarRule> {
+        self.rules.iter().find(|rule| rule.name == name)
+    }
+    
+    /// EBNF（ISO/IEC 14977 形式）で出力
+    pub fn to_ebnf(&self) -> String {
+        let mut output = format!("(* DSL '{}' の文法 *)\n", self.name);
+        for rule in &self.rules {
+            output.push_str(&format!("{} = {} ;\n", rule.name, ebnf_expr(&rule.expr, false)));
+        }
+        output
+    }
+    
+    /// tree-sitter の grammar.js として出力
+    pub fn to_tree_sitter(&self) -> String {
+        let mut output = String::new();
+        output.push_str(&format!("// DSL '{}' の文法（Eidos から生成）\n", self.name));
+        output.push_str("module.exports = grammar({\n");
+        output.push_str(&format!("  name: '{}',\n\n", tree_sitter_ident(&self.name)));
+        output.push_str("  rules: {\n");
+        for (i, rule) in self.rules.iter().enumerate() {
+            let separator = if i + 1 == self.rules.len() { "" } else { "," };
+            output.push_str(&format!(
+                "    {}: $ => {}{}\n",
+                tree_sitter_ident(&rule.name),
+                tree_sitter_expr(&rule.expr),
+                separator
+            ));
+        }
+        output.push_str("  }\n");
+        output.push_str("});\n");
+        output
+    }
+    
+    /// EBNFテキストを解析して文法を作成
+    ///
+    /// `name = expr ;`（`::=` も可）の規則を受け付ける。式では `|`、`,` または空白による連接、
+    /// `{ }` / `[ ]` / `( )`、後置の `*` / `+` / `?`、引用符で囲んだ終端記号、`(* *)` コメントが使える。
+    pub fn parse_ebnf(name: &str, source: &str) -> Result<Self> {
+        let tokens = tokenize_ebnf(name, source)?;
+        let mut parser = EbnfParser { dsl_name: name, tokens, pos: 0 };
+        
+        let mut grammar = Grammar::new(name);
+        while !parser.at_end() {
+            let rule_name = match parser.next() {
+                Some(EbnfToken::Ident(ident)) => ident,
+                other => return Err(parser.error(format!("規則名が必要です: {:?}", other))),
+            };
+            match parser.next() {
+                Some(EbnfToken::Define) => {}
+                _ => return Err(parser.error(format!("規則 '{}' の後には '=' が必要です", rule_name))),
+            }
+            let expr = parser.choice()?;
+            match parser.next() {
+                Some(EbnfToken::Symbol(';')) | Some(EbnfToken::Symbol('.')) => {}
+                _ => return Err(parser.error(format!("規則 '{}' の後には ';' が必要です", rule_name))),
+            }
+            grammar.add_rule(&rule_name, expr);
+        }
+        
+        if grammar.rules.is_empty() {
+            return Err(EidosError::DSL {
+                message: "EBNFに規則が含まれていません".to_string(),
+                dsl_name: name.to_string(),
+            });
+        }
+        
+        grammar.check_references()?;
+        Ok(grammar)
+    }
+    
+    /// 未定義の非終端記号を参照していないか確認
+    pub fn check_references(&self) -> Result<()> {
+        fn visit<'a>(expr: &'a GrammarExpr, names: &mut Vec<&'a str>) {
+            match expr {
+                GrammarExpr::NonTerminal(name) => names.push(name),
+                GrammarExpr::Sequence(items) | GrammarExpr::Choice(items) => {
+                    items.iter().for_each(|item| visit(item, names))
+                }
+                GrammarExpr::ZeroOrMore(inner) | GrammarExpr::OneOrMore(inner) | GrammarExpr::Optional(inner) => {
+                    visit(inner, names)
+                }
+                GrammarExpr::Terminal(_) | GrammarExpr::Pattern(_) => {}
+            }
+        }
+        
+        let mut referenced = Vec::new();
+        for rule in &self.rules {
+            visit(&rule.expr, &mut referenced);
+        }
+        for name in referenced {
+            if self.get_rule(name).is_none() {
+                return Err(EidosError::DSL {
+                    message: format!("未定義の規則 '{}' が参照されています", name),
+                    dsl_name: self.name.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// この文法を元にした `syntax` ブロックのひな形を生成
+    pub fn to_syntax_skeleton(&self) -> String {
+        let mut output = format!("syntax {} {{\n", tree_sitter_ident(&self.name));
+        for rule in &self.rules {
+            output.push_str(&format!("    // {} = {} ;\n", rule.name, ebnf_expr(&rule.expr, false)));
+        }
+        output.push_str("    // rule パターン => 展開;\n");
+        output.push_str("}\n");
+        output
+    }
+}
+
+impl fmt::Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_ebnf())
+    }
+}
+
+/// EBNFからインポートされた、文法のみを持つDSLのひな形
+pub struct GrammarExtension {
+    grammar: Grammar,
+    description: String,
+}
+
+impl GrammarExtension {
+    pub fn new(grammar: Grammar) -> Self {
+        let description = format!("EBNFからインポートされたDSL（{} 規則）", grammar.rules.len());
+        Self { grammar, description }
+    }
+}
+
+impl DSLExtension for GrammarExtension {
+    fn name(&self) -> &str {
+        &self.grammar.name
+    }
+    
+    fn description(&self) -> &str {
+        &self.description
+    }
+    
+    fn process_block(&self, _content: &str, _program: &Program) -> Result<ASTNode> {
+        Err(EidosError::DSL {
+            message: format!(
+                "DSL '{}' は文法のみのひな形です。`syntax` ブロックで展開規則を定義してください",
+                self.grammar.name
+            ),
+            dsl_name: self.grammar.name.clone(),
+        })
+    }
+    
+    fn register_types(&self) -> Vec<(String, Type)> {
+        Vec::new()
+    }
+    
+    fn register_builtins(&self) -> Vec<String> {
+        Vec::new()
+    }
+    
+    fn grammar(&self) -> Option<Grammar> {
+        Some(self.grammar.clone())
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn ebnf_expr(expr: &GrammarExpr, nested: bool) -> String {
+    match expr {
+        GrammarExpr::Terminal(text) => ebnf_terminal(text),
+        GrammarExpr::NonTerminal(name) => name.clone(),
+        GrammarExpr::Pattern(regex) => format!("? {} ?", slash_delimited(regex)),
+        GrammarExpr::Sequence(items) => {
+            let body = items.iter().map(|item| ebnf_expr(item, true)).collect::<Vec<_>>().join(" , ");
+            if nested && items.len() > 1 { format!("( {} )", body) } else { body }
+        }
+        GrammarExpr::Choice(items) => {
+            let body = items.iter().map(|item| ebnf_expr(item, false)).collect::<Vec<_>>().join(" | ");
+            if nested && items.len() > 1 { format!("( {} )", body) } else { body }
+        }
+        GrammarExpr::ZeroOrMore(inner) => format!("{{ {} }}", ebnf_expr(inner, false)),
+        GrammarExpr::OneOrMore(inner) => {
+            let inner = ebnf_expr(inner, true);
+            format!("{} , {{ {} }}", inner, inner)
+        }
+        GrammarExpr::Optional(inner) => format!("[ {} ]", ebnf_expr(inner, false)),
+    }
+}
+
+/// 終端記号を引用符で囲む
+///
+/// EBNFの終端記号にはエスケープがないので、両方の引用符を含む字面は `"` の前後で区切り、`'"'` と連結する。
+fn ebnf_terminal(text: &str) -> String {
+    if !text.contains('"') {
+        return format!("\"{}\"", text);
+    }
+    if !text.contains('\'') {
+        return format!("'{}'", text);
+    }
+    let mut parts = Vec::new();
+    for (i, piece) in text.split('"').enumerate() {
+        if i > 0 {
+            parts.push("'\"'".to_string());
+        }
+        if !piece.is_empty() {
+            parts.push(format!("\"{}\"", piece));
+        }
+    }
+    parts.join(" , ")
+}
+
+/// 正規表現を `/.../` で囲む（EBNFの特殊シーケンスと tree-sitter で使い、エスケープされていない `/` はエスケープする）
+fn slash_delimited(regex: &str) -> String {
+    let mut out = String::with_capacity(regex.len() + 2);
+    out.push('/');
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '/' => out.push_str("\\/"),
+            c => out.push(c),
+        }
+    }
+    out.push('/');
+    out
+}
+
+fn tree_sitter_ident(name: &str) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", ident)
+    } else {
+        ident
+    }
+}
+
+fn tree_sitter_expr(expr: &GrammarExpr) -> String {
+    let list = |items: &[GrammarExpr]| items.iter().map(tree_sitter_expr).collect::<Vec<_>>().join(", ");
+    match expr {
+        GrammarExpr::Terminal(text) => format!("{:?}", text),
+        GrammarExpr::NonTerminal(name) => format!("$.{}", tree_sitter_ident(name)),
+        GrammarExpr::Pattern(regex) => slash_delimited(regex),
+        GrammarExpr::Sequence(items) => format!("seq({})", list(items)),
+        GrammarExpr::Choice(items) => format!("choice({})", list(items)),
+        GrammarExpr::ZeroOrMore(inner) => format!("repeat({})", tree_sitter_expr(inner)),
+        GrammarExpr::OneOrMore(inner) => format!("repeat1({})", tree_sitter_expr(inner)),
+        GrammarExpr::Optional(inner) => format!("optional({})", tree_sitter_expr(inner)),
+    }
+}
+
+/// EBNFの字句
+#[derive(Debug, Clone, PartialEq)]
+enum EbnfToken {
+    Ident(String),
+    Terminal(String),
+    Pattern(String),
+    Define,
+    Symbol(char),
+}
+
+fn tokenize_ebnf(dsl_name: &str, source: &str) -> Result<Vec<EbnfToken>> {
+    let error = |message: String| EidosError::DSL {
+        message,
+        dsl_name: dsl_name.to_string(),
+    };
+    
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' if chars.get(i + 1) == Some(&'*') => {
+                // コメント (* ... *)
+                let mut j = i + 2;
+                while j + 1 < chars.len() && !(chars[j] == '*' && chars[j + 1] == ')') {
+                    j += 1;
+                }
+                if j + 1 >= chars.len() {
+                    return Err(error("EBNFのコメントが閉じられていません".to_string()));
+                }
+                i = j + 2;
+            }
+            '"' | '\'' => {
+                let end = chars[i + 1..].iter().position(|&ch| ch == c).map(|p| i + 1 + p);
+                let end = end.ok_or_else(|| error("EBNFの終端記号が閉じられていません".to_string()))?;
+                tokens.push(EbnfToken::Terminal(chars[i + 1..end].iter().collect()));
+                i = end + 1;
+            }
+            '?' if chars[i + 1..].iter().find(|ch| !ch.is_whitespace()) == Some(&'/') => {
+                // 特殊シーケンス ? /regex/ ? は字句パターンとして扱う（`\/` は正規表現の中の `/`）
+                let start = i + 1 + chars[i + 1..].iter().position(|&ch| ch == '/').unwrap() + 1;
+                let mut regex = String::new();
+                let mut end = None;
+                let mut j = start;
+                while j < chars.len() {
+                    match chars[j] {
+                        '\\' => {
+                            // `\/` は `/` にし、それ以外のエスケープはそのまま残す
+                            match chars.get(j + 1) {
+                                Some('/') => regex.push('/'),
+                                Some(&next) => {
+                                    regex.push('\\');
+                                    regex.push(next);
+                                }
+                                None => regex.push('\\'),
+                            }
+                            j += 1;
+                        }
+                        '/' if chars[j + 1..].iter().find(|ch| !ch.is_whitespace()) == Some(&'?') => {
+                            end = Some(j);
+                            break;
+                        }
+                        c => regex.push(c),
+                    }
+                    j += 1;
+                }
+                let end = end.ok_or_else(|| error("EBNFの特殊シーケンスが閉じられていません".to_string()))?;
+                tokens.push(EbnfToken::Pattern(regex));
+                i = end + 1 + chars[end + 1..].iter().position(|&ch| ch == '?').unwrap() + 1;
+            }
+            ':' if chars.get(i + 1) == Some(&':') && chars.get(i + 2) == Some(&'=') => {
+                tokens.push(EbnfToken::Define);
+                i += 3;
+            }
+            '=' => {
+                tokens.push(EbnfToken::Define);
+                i += 1;
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '-') {
+                    i += 1;
+                }
+                tokens.push(EbnfToken::Ident(chars[start..i].iter().collect()));
+            }
+            '|' | ',' | ';' | '.' | '(' | ')' | '[' | ']' | '{' | '}' | '*' | '+' | '?' => {
+                tokens.push(EbnfToken::Symbol(c));
+                i += 1;
+            }
+            c => return Err(error(format!("EBNFに不明な文字があります: '{}'", c))),
+        }
+    }
+    
+    Ok(tokens)
+}
+
+struct EbnfParser<'a> {
+    dsl_name: &'a str,
+    tokens: Vec<EbnfToken>,
+    pos: usize,
+}
+
+impl<'a> EbnfParser<'a> {
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+    
+    fn peek(&self) -> Option<&EbnfToken> {
+        self.tokens.get(self.pos)
+    }
+    
+    fn next(&mut self) -> Option<EbnfToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+    
+    fn error(&self, message: String) -> EidosError {
+        EidosError::DSL {
+            message,
+            dsl_name: self.dsl_name.to_string(),
+        }
+    }
+    
+    fn expect(&mut self, symbol: char) -> Result<()> {
+        match self.next() {
+            Some(EbnfToken::Symbol(c)) if c == symbol => Ok(()),
+            other => Err(self.error(format!("'{}' が必要です: {:?}", symbol, other))),
+        }
+    }
+    
+    fn choice(&mut self) -> Result<GrammarExpr> {
+        let mut items = vec![self.sequence()?];
+        while self.peek() == Some(&EbnfToken::Symbol('|')) {
+            self.pos += 1;
+            items.push(self.sequence()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { GrammarExpr::Choice(items) })
+    }
+    
+    fn sequence(&mut self) -> Result<GrammarExpr> {
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                Some(EbnfToken::Symbol(',')) => self.pos += 1,
+                Some(EbnfToken::Ident(_))
+                | Some(EbnfToken::Terminal(_))
+                | Some(EbnfToken::Pattern(_))
+                | Some(EbnfToken::Symbol('('))
+                | Some(EbnfToken::Symbol('['))
+                | Some(EbnfToken::Symbol('{')) => {
+                    // 次の規則の開始（`name =`）に到達したら連接を終える
+                    if let (Some(EbnfToken::Ident(_)), Some(EbnfToken::Define)) =
+                        (self.peek(), self.tokens.get(self.pos + 1))
+                    {
+                        break;
+                    }
+                    items.push(self.postfix()?);
+                }
+                _ => break,
+            }
+        }
+        
+        match items.len() {
+            0 => Err(self.error("空の式です".to_string())),
+            1 => Ok(items.remove(0)),
+            _ => Ok(GrammarExpr::Sequence(items)),
+        }
+    }
+    
+    fn postfix(&mut self) -> Result<GrammarExpr> {
+        let mut expr = self.atom()?;
+        loop {
+            expr = match self.peek() {
+                Some(EbnfToken::Symbol('*')) => GrammarExpr::ZeroOrMore(Box::new(expr)),
+                Some(EbnfToken::Symbol('+')) => GrammarExpr::OneOrMore(Box::new(expr)),
+                Some(EbnfToken::Symbol('?')) => GrammarExpr::Optional(Box::new(expr)),
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+        }
+    }
+    
+    fn atom(&mut self) -> Result<GrammarExpr> {
+        match self.next() {
+            Some(EbnfToken::Ident(name)) => Ok(GrammarExpr::NonTerminal(name)),
+            Some(EbnfToken::Terminal(text)) => Ok(GrammarExpr::Terminal(text)),
+            Some(EbnfToken::Pattern(regex)) => Ok(GrammarExpr::Pattern(regex)),
+            Some(EbnfToken::Symbol('(')) => {
+                let expr = self.choice()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(EbnfToken::Symbol('[')) => {
+                let expr = self.choice()?;
+                self.expect(']')?;
+                Ok(GrammarExpr::Optional(Box::new(expr)))
+            }
+            Some(EbnfToken::Symbol('{')) => {
+                let expr = self.choice()?;
+                self.expect('}')?;
+                Ok(GrammarExpr::ZeroOrMore(Box::new(expr)))
+            }
+            other => Err(self.error(format!("式が必要です: {:?}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CALC: &str = r#"
+        (* 四則演算 *)
+        expr = term , { ( "+" | "-" ) , term } ;
+        term = factor ( "*" factor )* ;
+        factor = number | "(" expr ")" ;
+        number = ? /[0-9]+/ ? ;
+    "#;
+    
+    #[test]
+    fn test_parse_ebnf() {
+        let grammar = Grammar::parse_ebnf("calc", CALC).unwrap();
+        
+        assert_eq!(grammar.rules.len(), 4);
+        assert_eq!(grammar.rules[0].name, "expr");
+        assert_eq!(grammar.get_rule("number").unwrap().expr, GrammarExpr::Pattern("[0-9]+".to_string()));
+    }
+    
+    #[test]
+    fn test_ebnf_round_trip() {
+        let grammar = Grammar::parse_ebnf("calc", CALC).unwrap();
+        let reparsed = Grammar::parse_ebnf("calc", &grammar.to_ebnf()).unwrap();
+        
+        assert_eq!(grammar, reparsed);
+    }
+    
+    #[test]
+    fn test_tree_sitter_output() {
+        let grammar = Grammar::parse_ebnf("calc", CALC).unwrap();
+        let output = grammar.to_tree_sitter();
+        
+        assert!(output.contains("name: 'calc'"));
+        assert!(output.contains("factor: $ => choice($.number, seq(\"(\", $.expr, \")\"))"));
+        assert!(output.contains("number: $ => /[0-9]+/"));
+    }
+    
+    #[test]
+    fn test_quotes_and_slashes() {
+        let mut grammar = Grammar::new("quotes");
+        grammar.add_rule(
+            "start",
+            GrammarExpr::Sequence(vec![
+                GrammarExpr::Terminal("it's \"x\"".to_string()),
+                GrammarExpr::Terminal("say \"hi\"".to_string()),
+                GrammarExpr::NonTerminal("path".to_string()),
+            ]),
+        );
+        grammar.add_rule("path", GrammarExpr::Pattern("[a-z/]+/?".to_string()));
+        
+        // 両方の引用符を含む終端記号は `"` を区切りに連結する
+        let ebnf = grammar.to_ebnf();
+        assert!(ebnf.contains(r#""it's " , '"' , "x" , '"' , 'say "hi"' , path"#), "{}", ebnf);
+        let reparsed = Grammar::parse_ebnf("quotes", &ebnf).unwrap();
+        assert_eq!(reparsed.get_rule("path"), grammar.get_rule("path"));
+        let GrammarExpr::Sequence(items) = &reparsed.get_rule("start").unwrap().expr else {
+            panic!("{:?}", reparsed);
+        };
+        let text: String = items
+            .iter()
+            .map_while(|item| match item {
+                GrammarExpr::Terminal(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "it's \"x\"say \"hi\"");
+        
+        // エスケープされていない `/` だけをエスケープする
+        let output = grammar.to_tree_sitter();
+        assert!(output.contains(r"path: $ => /[a-z\/]+\/?/"), "{}", output);
+    }
+    
+    #[test]
+    fn test_undefined_reference() {
+        assert!(Grammar::parse_ebnf("bad", "start = missing ;").is_err());
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::BTreeSet;
+
+use serde_json::{json, Map, Value};
+
+use crate::core::types::{NumericType, LIST_TYPE_NAME, OPTION_TYPE_NAME, RANGE_TYPE_NAME, RESULT_TYPE_NAME};
+use crate::frontend::lexer::KEYWORDS;
+use crate::frontend::parser::CONTEXTUAL_KEYWORDS;
+use super::grammar::GrammarExpr;
+use super::registry::DSLRegistry;
+
+/// 制御構文のキーワード（残りの予約語と別の色にする）
+const CONTROL_KEYWORDS: &[&str] = &["if", "else", "while", "for", "in", "break", "continue", "return"];
+
+/// 真偽値のリテラル
+const BOOLEAN_LITERALS: &[&str] = &["true", "false"];
+
+/// 組み込みの型名
+const BUILTIN_TYPES: &[&str] = &["Int", "Float", "Bool", "Char", "String", "Unit", OPTION_TYPE_NAME, RESULT_TYPE_NAME, LIST_TYPE_NAME, RANGE_TYPE_NAME];
+
+/// 演算子（長いものを先に並べる）
+const OPERATORS: &[&str] = &[
+    "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "<<", ">>", "+=", "-=", "*=", "+", "-", "*", "/", "%", "&",
+    "|", "^", "!", "=", "<", ">", "?",
+];
+
+/// 1つのDSLで強調する単語
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DslHighlight {
+    pub name: String,
+    pub keywords: Vec<String>,
+}
+
+/// 構文強調の定義の元になる、現在有効な文法の単語
+///
+/// DSLで文法が変わるため、基本の言語の予約語に加えて登録済みのDSLのキーワードと、文法の終端記号のうち
+/// 単語の形をしたものを集める。エディタの構文強調（TextMate の文法、tree-sitter のクエリ）はここから生成する。
+#[derive(Debug, Clone)]
+pub struct HighlightSpec {
+    pub keywords: Vec<String>,
+    pub control_keywords: Vec<String>,
+    pub types: Vec<String>,
+    /// 名前の順
+    pub dsls: Vec<DslHighlight>,
+}
+
+impl HighlightSpec {
+    /// 基本の言語とレジストリに登録済みのDSLから作成
+    pub fn from_registry(registry: &DSLRegistry) -> Self {
+        let base: BTreeSet<&str> = KEYWORDS.iter().chain(CONTEXTUAL_KEYWORDS).copied().collect();
+        let keywords = base
+            .iter()
+            .filter(|word| !CONTROL_KEYWORDS.contains(word) && !BOOLEAN_LITERALS.contains(word))
+            .map(|word| word.to_string())
+            .collect();
+        let types = BUILTIN_TYPES
+            .iter()
+            .copied()
+            .chain(NumericType::ALL.iter().map(|ty| ty.name()))
+            .map(str::to_string)
+            .collect();
+        
+        let mut names = registry.list_extensions();
+        names.sort();
+        let dsls = names
+            .into_iter()
+            .filter_map(|name| {
+                let extension = registry.get(&name)?;
+                let mut words: BTreeSet<String> = extension.keywords().into_iter().collect();
+                // 予約語の別名も、元の予約語と同じくキーワードとして強調する
+                words.extend(extension.keyword_aliases().into_iter().map(|alias| alias.alias));
+                if let Some(grammar) = extension.grammar() {
+                    for rule in &grammar.rules {
+                        collect_terminals(&rule.expr, &mut words);
+                    }
+                }
+                words.retain(|word| is_word(word) && !base.contains(word.as_str()));
+                Some(DslHighlight { name, keywords: words.into_iter().collect() })
+            })
+            .collect();
+        
+        Self {
+            keywords,
+            control_keywords: CONTROL_KEYWORDS.iter().map(|word| word.to_string()).collect(),
+            types,
+            dsls,
+        }
+    }
+    
+    /// TextMate の文法（`.tmLanguage.json`）を出力
+    ///
+    /// 登録済みのDSLごとに `@name { ... }` のブロックを埋め込みの領域にし、その中ではDSLのキーワードを
+    /// `keyword.other.dsl.<name>.eidos` として強調する。`use dsl` で有効化したDSLの構文はブロックの外にも
+    /// 現れるので、すべてのDSLのキーワードを `keyword.other.dsl.eidos` としてどこでも強調する。
+    pub fn to_tm_language(&self) -> String {
+        let mut repository = Map::new();
+        repository.insert(
+            "comments".to_string(),
+            json!({
+                "patterns": [
+                    { "name": "comment.line.double-slash.eidos", "match": "//.*$" },
+                    { "name": "comment.block.eidos", "begin": "/\\*", "end": "\\*/" }
+                ]
+            }),
+        );
+        repository.insert(
+            "strings".to_string(),
+            json!({
+                "name": "string.quoted.double.eidos",
+                "begin": "\"",
+                "end": "\"",
+                "patterns": [{ "name": "constant.character.escape.eidos", "match": "\\\\(u\\{[0-9A-Fa-f]{1,6}\\}|.)" }]
+            }),
+        );
+        repository.insert(
+            "characters".to_string(),
+            json!({
+                "name": "string.quoted.single.eidos",
+                "match": "'(\\\\(u\\{[0-9A-Fa-f]{1,6}\\}|.)|[^'\\\\])'"
+            }),
+        );
+        let suffixes: Vec<&str> = NumericType::ALL.iter().map(|ty| ty.name()).collect();
+        repository.insert(
+            "numbers".to_string(),
+            json!({
+                "name": "constant.numeric.eidos",
+                "match": format!(
+                    "\\b(0x[0-9A-Fa-f_]+|0o[0-7_]+|0b[01_]+|[0-9][0-9_]*(\\.[0-9][0-9_]*)?([eE][+-]?[0-9]+)?)({})?\\b",
+                    suffixes.join("|")
+                )
+            }),
+        );
+        repository.insert(
+            "keywords".to_string(),
+            json!({
+                "patterns": [
+                    { "name": "keyword.control.eidos", "match": word_pattern(&self.control_keywords) },
+                    { "name": "constant.language.boolean.eidos", "match": word_pattern(BOOLEAN_LITERALS) },
+                    { "name": "keyword.other.eidos", "match": word_pattern(&self.keywords) }
+                ]
+            }),
+        );
+        repository.insert(
+            "types".to_string(),
+            json!({ "name": "support.type.eidos", "match": word_pattern(&self.types) }),
+        );
+        repository.insert(
+            "functions".to_string(),
+            json!({
+                "match": "\\b(fn)\\s+([A-Za-z_][A-Za-z0-9_]*)",
+                "captures": {
+                    "1": { "name": "keyword.other.eidos" },
+                    "2": { "name": "entity.name.function.eidos" }
+                }
+            }),
+        );
+        repository.insert(
+            "operators".to_string(),
+            json!({ "name": "keyword.operator.eidos", "match": OPERATORS.iter().map(|op| regex_escape(op)).collect::<Vec<_>>().join("|") }),
+        );
+        
+        let all_dsl_keywords: BTreeSet<&String> = self.dsls.iter().flat_map(|dsl| &dsl.keywords).collect();
+        let dsl_keyword_patterns = if all_dsl_keywords.is_empty() {
+            json!([])
+        } else {
+            json!([{ "name": "keyword.other.dsl.eidos", "match": word_pattern(&all_dsl_keywords.into_iter().collect::<Vec<_>>()) }])
+        };
+        repository.insert("dsl-keywords".to_string(), json!({ "patterns": dsl_keyword_patterns }));
+        
+        // 登録済みのDSLのブロック、続けて未登録のDSLのブロック
+        let mut blocks = Vec::new();
+        for dsl in &self.dsls {
+            let mut patterns = Vec::new();
+            if !dsl.keywords.is_empty() {
+                patterns.push(json!({ "name": format!("keyword.other.dsl.{}.eidos", dsl.name), "match": word_pattern(&dsl.keywords) }));
+            }
+            patterns.extend([json!({ "include": "#comments" }), json!({ "include": "#strings" }), json!({ "include": "#numbers" }), json!({ "include": "#dsl-braces" })]);
+            blocks.push(dsl_block(&regex_escape(&dsl.name), &format!("meta.embedded.dsl.{}.eidos", dsl.name), patterns));
+        }
+        blocks.push(dsl_block(
+            "[A-Za-z_][A-Za-z0-9_]*",
+            "meta.embedded.dsl.eidos",
+            vec![json!({ "include": "#comments" }), json!({ "include": "#strings" }), json!({ "include": "#dsl-braces" })],
+        ));
+        repository.insert("dsl-blocks".to_string(), json!({ "patterns": blocks }));
+        repository.insert(
+            "dsl-braces".to_string(),
+            json!({
+                "begin": "\\{",
+                "end": "\\}",
+                "patterns": [{ "include": "#dsl-braces" }, { "include": "#strings" }, { "include": "#comments" }]
+            }),
+        );
+        
+        let patterns: Vec<Value> = [
+            "comments", "dsl-blocks", "strings", "characters", "numbers", "functions", "keywords", "dsl-keywords", "types",
+            "operators",
+        ]
+        .iter()
+        .map(|name| json!({ "include": format!("#{}", name) }))
+        .collect();
+        let grammar = json!({
+            "name": "Eidos",
+            "scopeName": "source.eidos",
+            "fileTypes": ["eid"],
+            "patterns": patterns,
+            "repository": repository,
+        });
+        let mut out = serde_json::to_string_pretty(&grammar).unwrap_or_default();
+        out.push('\n');
+        out
+    }
+    
+    /// tree-sitter の強調クエリ（`highlights.scm`）を出力
+    ///
+    /// ノードの種類は文法ごとに異なるため、字面で一致する匿名ノード（キーワードと記号）だけを使う。
+    /// DSLの節は、`DSLRegistry::export_tree_sitter` で出力したそのDSLの文法に対して使う。
+    pub fn to_tree_sitter_queries(&self) -> String {
+        let mut out = String::from("; Eidos の強調クエリ（`eidos highlight --emit tree-sitter` で生成）\n\n");
+        out.push_str(&query("keyword.control", &self.control_keywords));
+        out.push_str(&query("boolean", BOOLEAN_LITERALS));
+        out.push_str(&query("keyword", &self.keywords));
+        out.push_str(&query("type.builtin", &self.types));
+        out.push_str(&query("operator", OPERATORS));
+        out.push_str(&query("punctuation.bracket", &["(", ")", "{", "}", "[", "]"]));
+        out.push_str(&query("punctuation.delimiter", &[";", ":", ",", "."]));
+        for dsl in &self.dsls {
+            if dsl.keywords.is_empty() {
+                continue;
+            }
+            out.push_str(&format!("\n; DSL '{}'\n", dsl.name));
+            out.push_str(&query("keyword", &dsl.keywords));
+        }
+        out
+    }
+}
+
+/// 文法式の終端記号を集める
+fn collect_terminals(expr: &GrammarExpr, words: &mut BTreeSet<String>) {
+    match expr {
+        GrammarExpr::Terminal(text) => {
+            words.insert(text.clone());
+        }
+        GrammarExpr::NonTerminal(_) | GrammarExpr::Pattern(_) => {}
+        GrammarExpr::Sequence(items) | GrammarExpr::Choice(items) => {
+            for item in items {
+                collect_terminals(item, words);
+            }
+        }
+        GrammarExpr::ZeroOrMore(inner) | GrammarExpr::OneOrMore(inner) | GrammarExpr::Optional(inner) => {
+            collect_terminals(inner, words)
+        }
+    }
+}
+
+/// 識別子の形をした単語か
+fn is_word(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// 単語のいずれかに一致する正規表現
+fn word_pattern<S: AsRef<str>>(words: &[S]) -> String {
+    let words: Vec<String> = words.iter().map(|word| regex_escape(word.as_ref())).collect();
+    format!("\\b({})\\b", words.join("|"))
+}
+
+/// 正規表現で特別な意味を持つ文字をエスケープ
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "\\^$.|?*+()[]{}/".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// `@name { ... }` のブロック
+fn dsl_block(name_pattern: &str, scope: &str, patterns: Vec<Value>) -> Value {
+    json!({
+        "name": scope,
+        "begin": format!("(@)({})\\s*(\\{{)", name_pattern),
+        "end": "\\}",
+        "beginCaptures": {
+            "1": { "name": "punctuation.definition.dsl.eidos" },
+            "2": { "name": "entity.name.tag.dsl.eidos" },
+            "3": { "name": "punctuation.section.block.begin.eidos" }
+        },
+        "endCaptures": { "0": { "name": "punctuation.section.block.end.eidos" } },
+        "patterns": patterns,
+    })
+}
+
+/// 字面の一覧を1つの名前で捕捉するクエリ
+fn query<S: AsRef<str>>(capture: &str, literals: &[S]) -> String {
+    let literals: Vec<String> = literals
+        .iter()
+        .map(|literal| format!("\"{}\"", literal.as_ref().replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("[{}] @{}\n", literals.join(" "), capture)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    
+    use super::*;
+    use crate::core::ast::{KeywordAlias, SyntaxFragment, SyntaxRule};
+    use crate::dsl::SyntaxExtension;
+    
+    #[test]
+    fn test_highlight_spec_includes_dsl_keywords() {
+        let mut registry = DSLRegistry::new();
+        let rule = SyntaxRule {
+            pattern: vec![
+                SyntaxFragment::Token("unless".to_string()),
+                SyntaxFragment::Capture("cond".to_string()),
+                SyntaxFragment::Token("then".to_string()),
+                SyntaxFragment::Token("if".to_string()),
+            ],
+            expansion: vec![SyntaxFragment::Capture("cond".to_string())],
+        };
+        registry.register("control".to_string(), Arc::new(SyntaxExtension::new("control".to_string(), vec![rule])));
+        let japanese = SyntaxExtension::new("ja".to_string(), Vec::new())
+            .with_keyword_aliases(vec![KeywordAlias { alias: "もし".to_string(), keyword: "if".to_string() }]);
+        registry.register("ja".to_string(), Arc::new(japanese));
+        
+        let spec = HighlightSpec::from_registry(&registry);
+        assert!(spec.keywords.contains(&"effect".to_string()));
+        assert!(!spec.keywords.contains(&"if".to_string()));
+        // 基本の言語の予約語はDSLのキーワードにしない
+        assert_eq!(
+            spec.dsls,
+            vec![
+                DslHighlight { name: "control".to_string(), keywords: vec!["then".to_string(), "unless".to_string()] },
+                DslHighlight { name: "ja".to_string(), keywords: vec!["もし".to_string()] },
+            ]
+        );
+        
+        let grammar: Value = serde_json::from_str(&spec.to_tm_language()).unwrap();
+        assert_eq!(grammar["scopeName"], "source.eidos");
+        assert_eq!(grammar["repository"]["dsl-keywords"]["patterns"][0]["match"], "\\b(then|unless|もし)\\b");
+        assert_eq!(grammar["repository"]["dsl-blocks"]["patterns"][0]["name"], "meta.embedded.dsl.control.eidos");
+        
+        let queries = spec.to_tree_sitter_queries();
+        assert!(queries.contains("[\"if\" \"else\" \"while\" \"for\" \"in\" \"break\" \"continue\" \"return\"] @keyword.control\n"));
+        assert!(queries.contains("\n; DSL 'control'\n[\"then\" \"unless\"] @keyword\n"));
+    }
+}
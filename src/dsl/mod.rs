@@ -1,7 +1,16 @@
 pub mod registry;
 pub mod processor;
 pub mod extension;
+pub mod syntax;
+pub mod grammar;
+pub mod peg;
+pub mod quote;
+pub mod highlight;
 
 pub use registry::DSLRegistry;
 pub use processor::DSLProcessor;
-pub use extension::DSLExtension; 
\ No newline at end of file
+pub use extension::DSLExtension;
+pub use syntax::SyntaxExtension;
+pub use grammar::{Grammar, GrammarExpr, GrammarExtension};
+pub use peg::{PegExpr, PegGrammar, PegExtension};
+pub use quote::quote; 
\ No newline at end of file
@@ -0,0 +1,891 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+
+use crate::core::{Result, EidosError};
+use crate::core::ast::{ASTNode, Program};
+use crate::core::types::Type;
+use super::extension::DSLExtension;
+use super::grammar::{Grammar, GrammarExpr};
+use super::syntax::{is_keyword_like, parse_expansion};
+
+/// 解析表現（Parsing Expression）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PegExpr {
+    /// 文字列リテラル
+    Literal(String),
+    /// 文字クラス（`[a-z_]`、`[^0-9]`）
+    Class { ranges: Vec<(char, char)>, negated: bool },
+    /// 任意の1文字（`.`）
+    Any,
+    /// 規則の参照
+    Rule(String),
+    /// 連接
+    Sequence(Vec<PegExpr>),
+    /// 順序付き選択（`/`）
+    Choice(Vec<PegExpr>),
+    /// 0回以上の繰り返し（`*`）
+    ZeroOrMore(Box<PegExpr>),
+    /// 1回以上の繰り返し（`+`）
+    OneOrMore(Box<PegExpr>),
+    /// 省略可能（`?`）
+    Optional(Box<PegExpr>),
+    /// 肯定先読み（`&`）
+    And(Box<PegExpr>),
+    /// 否定先読み（`!`）
+    Not(Box<PegExpr>),
+    /// ラベル付きの式（`name:expr`）。意味アクションから `$name` で参照する
+    Label(String, Box<PegExpr>),
+    /// 意味アクション（`expr { Eidosコード }`）
+    Action(Box<PegExpr>, String),
+}
+
+/// PEGの規則
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PegRule {
+    pub name: String,
+    pub expr: PegExpr,
+}
+
+/// PEG文法（最初の規則が開始記号）
+///
+/// 解析はメモ化付きの再帰下降（パックラット構文解析）で行うため、
+/// バックトラックがあっても入力長に対して線形時間で動作する。
+#[derive(Debug, Clone)]
+pub struct PegGrammar {
+    pub name: String,
+    pub rules: Vec<PegRule>,
+    index: HashMap<String, usize>,
+}
+
+/// 解析結果の木
+#[derive(Debug, Clone)]
+struct PegValue {
+    start: usize,
+    end: usize,
+    label: Option<String>,
+    /// 意味アクションの出力
+    output: Option<String>,
+    has_output: bool,
+    children: Vec<PegValue>,
+}
+
+impl PegValue {
+    fn leaf(start: usize, end: usize) -> Self {
+        Self {
+            start,
+            end,
+            label: None,
+            output: None,
+            has_output: false,
+            children: Vec::new(),
+        }
+    }
+    
+    fn node(start: usize, end: usize, children: Vec<PegValue>) -> Self {
+        let has_output = children.iter().any(|child| child.has_output);
+        Self {
+            start,
+            end,
+            label: None,
+            output: None,
+            has_output,
+            children,
+        }
+    }
+    
+    /// 値を文字列にする
+    ///
+    /// 意味アクションの出力を含まない部分は入力テキストをそのまま使い、
+    /// 含む部分は子の出力を空白区切りで連結する。
+    fn render(&self, input: &[char]) -> String {
+        if let Some(output) = &self.output {
+            return output.clone();
+        }
+        if !self.has_output {
+            return input[self.start..self.end].iter().collect();
+        }
+        self.children
+            .iter()
+            .map(|child| child.render(input))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    
+    /// 意味アクションから参照できるラベルを収集（入れ子のアクションの内側には入らない）
+    fn collect_labels(&self, input: &[char], bindings: &mut HashMap<String, String>) {
+        if let Some(label) = &self.label {
+            bindings.insert(label.clone(), self.render(input));
+        }
+        if self.output.is_none() {
+            for child in &self.children {
+                child.collect_labels(input, bindings);
+            }
+        }
+    }
+}
+
+impl PegGrammar {
+    /// PEG記法のテキストから文法をコンパイル
+    ///
+    /// ```text
+    /// sum    <- l:num _ "+" _ r:sum { $l + $r } / num
+    /// num    <- [0-9]+
+    /// _      <- [ \t\n]*
+    /// ```
+    pub fn compile(name: &str, source: &str) -> Result<Self> {
+        let mut parser = PegSyntaxParser {
+            dsl_name: name,
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        
+        let mut rules = Vec::new();
+        parser.skip_trivia();
+        while !parser.at_end() {
+            rules.push(parser.rule()?);
+            parser.skip_trivia();
+        }
+        
+        Self::from_rules(name, rules)
+    }
+    
+    /// 規則の一覧から文法を作成し、参照を検証する
+    pub fn from_rules(name: &str, rules: Vec<PegRule>) -> Result<Self> {
+        if rules.is_empty() {
+            return Err(peg_error(name, "PEG文法に規則がありません".to_string()));
+        }
+        
+        let mut index = HashMap::new();
+        for (i, rule) in rules.iter().enumerate() {
+            if index.insert(rule.name.clone(), i).is_some() {
+                return Err(peg_error(name, format!("規則 '{}' が重複して定義されています", rule.name)));
+            }
+        }
+        
+        let grammar = Self {
+            name: name.to_string(),
+            rules,
+            index,
+        };
+        for rule in &grammar.rules {
+            grammar.check_references(&rule.expr)?;
+        }
+        Ok(grammar)
+    }
+    
+    fn check_references(&self, expr: &PegExpr) -> Result<()> {
+        match expr {
+            PegExpr::Rule(name) if !self.index.contains_key(name) => {
+                Err(peg_error(&self.name, format!("未定義の規則 '{}' が参照されています", name)))
+            }
+            PegExpr::Sequence(items) | PegExpr::Choice(items) => {
+                items.iter().try_for_each(|item| self.check_references(item))
+            }
+            PegExpr::ZeroOrMore(inner)
+            | PegExpr::OneOrMore(inner)
+            | PegExpr::Optional(inner)
+            | PegExpr::And(inner)
+            | PegExpr::Not(inner)
+            | PegExpr::Label(_, inner)
+            | PegExpr::Action(inner, _) => self.check_references(inner),
+            _ => Ok(()),
+        }
+    }
+    
+    /// 入力全体を開始記号で解析し、意味アクションの出力（またはそのままのテキスト）を返す
+    pub fn parse(&self, input: &str) -> Result<String> {
+        let mut run = PegRun::new(self, input);
+        let value = run.parse_all()?;
+        Ok(value.render(&run.input))
+    }
+    
+    /// 入力全体が文法に一致するかどうか
+    pub fn recognize(&self, input: &str) -> bool {
+        PegRun::new(self, input).parse_all().is_ok()
+    }
+    
+    /// EBNF / tree-sitter 出力用の文法に変換する（先読みと意味アクションは省かれる）
+    pub fn to_grammar(&self) -> Grammar {
+        let mut grammar = Grammar::new(&self.name);
+        for rule in &self.rules {
+            let expr = to_grammar_expr(&rule.expr).unwrap_or_else(|| GrammarExpr::Sequence(Vec::new()));
+            grammar.add_rule(&rule.name, expr);
+        }
+        grammar
+    }
+    
+    /// 開始記号がどの文字列リテラルで始まりうるかを返す（キーワード衝突の検出用）
+    pub fn leading_literals(&self) -> Vec<String> {
+        let mut literals = Vec::new();
+        let mut visited = HashSet::new();
+        self.collect_leading(&self.rules[0].expr, &mut visited, &mut literals);
+        literals
+    }
+    
+    fn collect_leading(&self, expr: &PegExpr, visited: &mut HashSet<String>, out: &mut Vec<String>) {
+        match expr {
+            PegExpr::Literal(text) => {
+                if !out.contains(text) {
+                    out.push(text.clone());
+                }
+            }
+            PegExpr::Rule(name) => {
+                if visited.insert(name.clone()) {
+                    if let Some(&index) = self.index.get(name) {
+                        self.collect_leading(&self.rules[index].expr, visited, out);
+                    }
+                }
+            }
+            // 先頭の要素が空に一致しうる場合は正確に求められないため、先頭の要素のみを見る
+            PegExpr::Sequence(items) => {
+                if let Some(first) = items.first() {
+                    self.collect_leading(first, visited, out);
+                }
+            }
+            PegExpr::Choice(items) => {
+                for item in items {
+                    self.collect_leading(item, visited, out);
+                }
+            }
+            PegExpr::OneOrMore(inner) | PegExpr::Label(_, inner) | PegExpr::Action(inner, _) => {
+                self.collect_leading(inner, visited, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn peg_error(dsl_name: &str, message: String) -> EidosError {
+    EidosError::DSL {
+        message,
+        dsl_name: dsl_name.to_string(),
+    }
+}
+
+fn to_grammar_expr(expr: &PegExpr) -> Option<GrammarExpr> {
+    let collect = |items: &[PegExpr]| items.iter().filter_map(to_grammar_expr).collect::<Vec<_>>();
+    Some(match expr {
+        PegExpr::Literal(text) => GrammarExpr::Terminal(text.clone()),
+        PegExpr::Class { ranges, negated } => {
+            let body: String = ranges
+                .iter()
+                .map(|(lo, hi)| if lo == hi { escape_class_char(*lo) } else { format!("{}-{}", escape_class_char(*lo), escape_class_char(*hi)) })
+                .collect();
+            GrammarExpr::Pattern(format!("[{}{}]", if *negated { "^" } else { "" }, body))
+        }
+        PegExpr::Any => GrammarExpr::Pattern(".".to_string()),
+        PegExpr::Rule(name) => GrammarExpr::NonTerminal(name.clone()),
+        PegExpr::Sequence(items) => GrammarExpr::Sequence(collect(items)),
+        PegExpr::Choice(items) => GrammarExpr::Choice(collect(items)),
+        PegExpr::ZeroOrMore(inner) => GrammarExpr::ZeroOrMore(Box::new(to_grammar_expr(inner)?)),
+        PegExpr::OneOrMore(inner) => GrammarExpr::OneOrMore(Box::new(to_grammar_expr(inner)?)),
+        PegExpr::Optional(inner) => GrammarExpr::Optional(Box::new(to_grammar_expr(inner)?)),
+        PegExpr::And(_) | PegExpr::Not(_) => return None,
+        PegExpr::Label(_, inner) | PegExpr::Action(inner, _) => return to_grammar_expr(inner),
+    })
+}
+
+fn escape_class_char(c: char) -> String {
+    match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        ']' | '\\' | '-' | '^' => format!("\\{}", c),
+        c => c.to_string(),
+    }
+}
+
+/// 1回分の解析の状態（メモ表と失敗位置）
+struct PegRun<'g> {
+    grammar: &'g PegGrammar,
+    input: Vec<char>,
+    memo: HashMap<(usize, usize), Option<PegValue>>,
+    in_progress: HashSet<(usize, usize)>,
+    farthest: usize,
+    expected: Vec<String>,
+}
+
+impl<'g> PegRun<'g> {
+    fn new(grammar: &'g PegGrammar, input: &str) -> Self {
+        Self {
+            grammar,
+            input: input.chars().collect(),
+            memo: HashMap::new(),
+            in_progress: HashSet::new(),
+            farthest: 0,
+            expected: Vec::new(),
+        }
+    }
+    
+    fn parse_all(&mut self) -> Result<PegValue> {
+        match self.rule(0, 0) {
+            Some(value) if value.end == self.input.len() => Ok(value),
+            Some(value) => {
+                self.fail(value.end, "入力の終端".to_string());
+                Err(self.error())
+            }
+            None => Err(self.error()),
+        }
+    }
+    
+    fn error(&self) -> EidosError {
+        let (line, column) = self.line_column(self.farthest);
+        let mut expected = self.expected.clone();
+        expected.sort();
+        expected.dedup();
+        let found = self
+            .input
+            .get(self.farthest)
+            .map_or("入力の終端".to_string(), |c| format!("{:?}", c));
+        peg_error(
+            &self.grammar.name,
+            format!("{}:{} で構文エラー: {} が必要ですが {} がありました", line, column, expected.join(", "), found),
+        )
+    }
+    
+    fn line_column(&self, pos: usize) -> (usize, usize) {
+        let mut line = 1;
+        let mut column = 1;
+        for c in &self.input[..pos.min(self.input.len())] {
+            if *c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        (line, column)
+    }
+    
+    fn fail(&mut self, pos: usize, expected: String) {
+        if pos > self.farthest {
+            self.farthest = pos;
+            self.expected.clear();
+        }
+        if pos == self.farthest {
+            self.expected.push(expected);
+        }
+    }
+    
+    fn rule(&mut self, index: usize, pos: usize) -> Option<PegValue> {
+        let key = (index, pos);
+        if let Some(result) = self.memo.get(&key) {
+            return result.clone();
+        }
+        // 左再帰は失敗として扱う
+        if !self.in_progress.insert(key) {
+            return None;
+        }
+        
+        let grammar = self.grammar;
+        let result = self.eval(&grammar.rules[index].expr, pos);
+        
+        self.in_progress.remove(&key);
+        self.memo.insert(key, result.clone());
+        result
+    }
+    
+    fn eval(&mut self, expr: &PegExpr, pos: usize) -> Option<PegValue> {
+        match expr {
+            PegExpr::Literal(text) => {
+                let len = text.chars().count();
+                if self.input.len() >= pos + len && self.input[pos..pos + len].iter().copied().eq(text.chars()) {
+                    Some(PegValue::leaf(pos, pos + len))
+                } else {
+                    self.fail(pos, format!("{:?}", text));
+                    None
+                }
+            }
+            PegExpr::Class { ranges, negated } => {
+                let matched = self.input.get(pos).map_or(false, |c| {
+                    ranges.iter().any(|(lo, hi)| lo <= c && c <= hi) != *negated
+                });
+                if matched {
+                    Some(PegValue::leaf(pos, pos + 1))
+                } else {
+                    self.fail(pos, "文字クラス".to_string());
+                    None
+                }
+            }
+            PegExpr::Any => {
+                if pos < self.input.len() {
+                    Some(PegValue::leaf(pos, pos + 1))
+                } else {
+                    self.fail(pos, "任意の文字".to_string());
+                    None
+                }
+            }
+            PegExpr::Rule(name) => {
+                let index = self.grammar.index[name];
+                self.rule(index, pos)
+            }
+            PegExpr::Sequence(items) => {
+                let mut children = Vec::with_capacity(items.len());
+                let mut end = pos;
+                for item in items {
+                    let value = self.eval(item, end)?;
+                    end = value.end;
+                    children.push(value);
+                }
+                Some(PegValue::node(pos, end, children))
+            }
+            PegExpr::Choice(items) => items.iter().find_map(|item| self.eval(item, pos)),
+            PegExpr::ZeroOrMore(inner) | PegExpr::OneOrMore(inner) => {
+                let mut children = Vec::new();
+                let mut end = pos;
+                while let Some(value) = self.eval(inner, end) {
+                    // 空文字列に一致し続ける無限ループを防ぐ
+                    if value.end == end {
+                        break;
+                    }
+                    end = value.end;
+                    children.push(value);
+                }
+                if matches!(expr, PegExpr::OneOrMore(_)) && children.is_empty() {
+                    return None;
+                }
+                Some(PegValue::node(pos, end, children))
+            }
+            PegExpr::Optional(inner) => {
+                let children: Vec<PegValue> = self.eval(inner, pos).into_iter().collect();
+                let end = children.first().map_or(pos, |value| value.end);
+                Some(PegValue::node(pos, end, children))
+            }
+            PegExpr::And(inner) | PegExpr::Not(inner) => {
+                // 先読みの内側での失敗はエラー報告に含めない
+                let (farthest, expected) = (self.farthest, self.expected.clone());
+                let matched = self.eval(inner, pos).is_some();
+                self.farthest = farthest;
+                self.expected = expected;
+                
+                if matched == matches!(expr, PegExpr::And(_)) {
+                    Some(PegValue::leaf(pos, pos))
+                } else {
+                    None
+                }
+            }
+            PegExpr::Label(label, inner) => {
+                let mut value = self.eval(inner, pos)?;
+                value.label = Some(label.clone());
+                Some(value)
+            }
+            PegExpr::Action(inner, template) => {
+                let value = self.eval(inner, pos)?;
+                let mut bindings = HashMap::new();
+                value.collect_labels(&self.input, &mut bindings);
+                let text: String = self.input[value.start..value.end].iter().collect();
+                let output = substitute(template, &bindings, &text);
+                
+                let mut node = PegValue::node(value.start, value.end, vec![value]);
+                node.output = Some(output);
+                node.has_output = true;
+                Some(node)
+            }
+        }
+    }
+}
+
+/// 意味アクションのテンプレートに `$name` / `$0`（一致したテキスト全体）を埋め込む
+fn substitute(template: &str, bindings: &HashMap<String, String>, text: &str) -> String {
+    let chars: Vec<char> = template.chars().collect();
+    let mut output = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '$' {
+            let start = i + 1;
+            let mut end = start;
+            while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                end += 1;
+            }
+            let name: String = chars[start..end].iter().collect();
+            if name == "0" {
+                output.push_str(text);
+                i = end;
+                continue;
+            }
+            if let Some(value) = bindings.get(&name) {
+                output.push_str(value);
+                i = end;
+                continue;
+            }
+        }
+        output.push(chars[i]);
+        i += 1;
+    }
+    output
+}
+
+/// PEG記法のパーサー
+struct PegSyntaxParser<'a> {
+    dsl_name: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> PegSyntaxParser<'a> {
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+    
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+    
+    fn error(&self, message: &str) -> EidosError {
+        let consumed: String = self.chars[..self.pos.min(self.chars.len())].iter().collect();
+        let line = consumed.matches('\n').count() + 1;
+        peg_error(self.dsl_name, format!("PEG文法の {} 行目: {}", line, message))
+    }
+    
+    /// 空白と `#` コメントを読み飛ばす
+    fn skip_trivia(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '#' {
+                while let Some(c) = self.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+    
+    fn eat(&mut self, text: &str) -> bool {
+        self.skip_trivia();
+        let len = text.chars().count();
+        if self.chars.len() >= self.pos + len && self.chars[self.pos..self.pos + len].iter().copied().eq(text.chars()) {
+            self.pos += len;
+            true
+        } else {
+            false
+        }
+    }
+    
+    fn identifier(&mut self) -> Option<String> {
+        self.skip_trivia();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        if start == self.pos || self.chars[start].is_ascii_digit() {
+            self.pos = start;
+            None
+        } else {
+            Some(self.chars[start..self.pos].iter().collect())
+        }
+    }
+    
+    /// 現在位置が `name <-` / `name =` で始まる新しい規則かどうか
+    fn at_rule_start(&mut self) -> bool {
+        let saved = self.pos;
+        let result = self.identifier().is_some() && (self.eat("<-") || (self.eat("=") && !self.eat(">")));
+        self.pos = saved;
+        result
+    }
+    
+    fn rule(&mut self) -> Result<PegRule> {
+        let name = self.identifier().ok_or_else(|| self.error("規則名が必要です"))?;
+        if !self.eat("<-") && !self.eat("=") {
+            return Err(self.error(&format!("規則 '{}' の後には '<-' が必要です", name)));
+        }
+        let expr = self.choice()?;
+        self.eat(";");
+        Ok(PegRule { name, expr })
+    }
+    
+    fn choice(&mut self) -> Result<PegExpr> {
+        let mut items = vec![self.sequence()?];
+        while self.eat("/") || self.eat("|") {
+            items.push(self.sequence()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { PegExpr::Choice(items) })
+    }
+    
+    fn sequence(&mut self) -> Result<PegExpr> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None | Some('/') | Some('|') | Some(')') | Some(';') | Some('{') => break,
+                _ if self.at_rule_start() => break,
+                _ => items.push(self.prefix()?),
+            }
+        }
+        
+        let expr = if items.len() == 1 { items.remove(0) } else { PegExpr::Sequence(items) };
+        
+        // 連接の後に続く `{ ... }` は意味アクション
+        self.skip_trivia();
+        if self.peek() == Some('{') {
+            let template = self.action()?;
+            return Ok(PegExpr::Action(Box::new(expr), template));
+        }
+        Ok(expr)
+    }
+    
+    fn prefix(&mut self) -> Result<PegExpr> {
+        if self.eat("&") {
+            return Ok(PegExpr::And(Box::new(self.labeled()?)));
+        }
+        if self.eat("!") {
+            return Ok(PegExpr::Not(Box::new(self.labeled()?)));
+        }
+        self.labeled()
+    }
+    
+    fn labeled(&mut self) -> Result<PegExpr> {
+        let saved = self.pos;
+        if let Some(label) = self.identifier() {
+            self.skip_trivia();
+            if self.peek() == Some(':') {
+                self.pos += 1;
+                return Ok(PegExpr::Label(label, Box::new(self.suffix()?)));
+            }
+        }
+        self.pos = saved;
+        self.suffix()
+    }
+    
+    fn suffix(&mut self) -> Result<PegExpr> {
+        let mut expr = self.primary()?;
+        loop {
+            expr = if self.eat("*") {
+                PegExpr::ZeroOrMore(Box::new(expr))
+            } else if self.eat("+") {
+                PegExpr::OneOrMore(Box::new(expr))
+            } else if self.eat("?") {
+                PegExpr::Optional(Box::new(expr))
+            } else {
+                return Ok(expr);
+            };
+        }
+    }
+    
+    fn primary(&mut self) -> Result<PegExpr> {
+        self.skip_trivia();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.choice()?;
+                if !self.eat(")") {
+                    return Err(self.error("')' が必要です"));
+                }
+                Ok(expr)
+            }
+            Some(q @ '"') | Some(q @ '\'') => {
+                self.pos += 1;
+                let mut text = String::new();
+                loop {
+                    match self.peek() {
+                        None => return Err(self.error("文字列リテラルが閉じられていません")),
+                        Some(c) if c == q => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(_) => text.push(self.literal_char()?),
+                    }
+                }
+                Ok(PegExpr::Literal(text))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let negated = self.peek() == Some('^');
+                if negated {
+                    self.pos += 1;
+                }
+                let mut ranges = Vec::new();
+                loop {
+                    match self.peek() {
+                        None => return Err(self.error("文字クラスが閉じられていません")),
+                        Some(']') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(_) => {
+                            let lo = self.literal_char()?;
+                            let hi = if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                                self.pos += 1;
+                                self.literal_char()?
+                            } else {
+                                lo
+                            };
+                            ranges.push((lo, hi));
+                        }
+                    }
+                }
+                Ok(PegExpr::Class { ranges, negated })
+            }
+            Some('.') => {
+                self.pos += 1;
+                Ok(PegExpr::Any)
+            }
+            _ => match self.identifier() {
+                Some(name) => Ok(PegExpr::Rule(name)),
+                None => Err(self.error(&format!("解析表現が必要です: {:?}", self.peek()))),
+            },
+        }
+    }
+    
+    /// エスケープを考慮して1文字読む
+    fn literal_char(&mut self) -> Result<char> {
+        let c = self.peek().ok_or_else(|| self.error("入力が途中で終了しました"))?;
+        self.pos += 1;
+        if c != '\\' {
+            return Ok(c);
+        }
+        let escaped = self.peek().ok_or_else(|| self.error("エスケープシーケンスが途中で終了しました"))?;
+        self.pos += 1;
+        Ok(match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            c => c,
+        })
+    }
+    
+    /// `{ ... }` の意味アクションを読み取る
+    fn action(&mut self) -> Result<String> {
+        self.pos += 1;
+        let start = self.pos;
+        let mut depth = 1;
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let template: String = self.chars[start..self.pos].iter().collect();
+                        self.pos += 1;
+                        return Ok(template.trim().to_string());
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        Err(self.error("意味アクションが閉じられていません"))
+    }
+}
+
+/// `peg` ブロックで定義されたDSL拡張
+///
+/// DSLブロックの内容をPEG文法で解析し、意味アクションが生成したEidosコードを再解析する。
+pub struct PegExtension {
+    grammar: PegGrammar,
+    description: String,
+}
+
+impl PegExtension {
+    pub fn new(grammar: PegGrammar) -> Self {
+        let description = format!("PEGで定義されたDSL（{} 規則）", grammar.rules.len());
+        Self { grammar, description }
+    }
+    
+    /// 文法を取得
+    pub fn peg_grammar(&self) -> &PegGrammar {
+        &self.grammar
+    }
+}
+
+impl DSLExtension for PegExtension {
+    fn name(&self) -> &str {
+        &self.grammar.name
+    }
+    
+    fn description(&self) -> &str {
+        &self.description
+    }
+    
+    fn process_block(&self, content: &str, _program: &Program) -> Result<ASTNode> {
+        let expanded = self.grammar.parse(content)?;
+        parse_expansion(&self.grammar.name, &expanded)
+    }
+    
+    fn register_types(&self) -> Vec<(String, Type)> {
+        Vec::new()
+    }
+    
+    fn register_builtins(&self) -> Vec<String> {
+        Vec::new()
+    }
+    
+    fn grammar(&self) -> Option<Grammar> {
+        Some(self.grammar.to_grammar())
+    }
+    
+    fn keywords(&self) -> Vec<String> {
+        self.grammar
+            .leading_literals()
+            .into_iter()
+            .filter(|literal| is_keyword_like(literal))
+            .collect()
+    }
+    
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const SUM: &str = r#"
+        # 加算式を Eidos の関数呼び出しに変換する
+        sum <- l:num _ "+" _ r:sum { add($l, $r) } / num
+        num <- [0-9]+
+        _   <- [ \t]*
+    "#;
+    
+    #[test]
+    fn test_actions() {
+        let grammar = PegGrammar::compile("sum", SUM).unwrap();
+        
+        assert_eq!(grammar.parse("1 + 22 + 3").unwrap(), "add(1, add(22, 3))");
+        assert_eq!(grammar.parse("42").unwrap(), "42");
+    }
+    
+    #[test]
+    fn test_predicates() {
+        let source = r#"
+            ident   <- !keyword [a-z]+
+            keyword <- "let" ![a-z]
+        "#;
+        let grammar = PegGrammar::compile("ident", source).unwrap();
+        
+        assert!(grammar.recognize("letter"));
+        assert!(!grammar.recognize("let"));
+    }
+    
+    #[test]
+    fn test_error_position() {
+        let grammar = PegGrammar::compile("sum", SUM).unwrap();
+        match grammar.parse("1 + x") {
+            Err(EidosError::DSL { message, .. }) => assert!(message.contains("1:5"), "{}", message),
+            other => panic!("構文エラーになるべきです: {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_left_recursion_does_not_hang() {
+        let grammar = PegGrammar::compile("lr", "expr <- expr \"+\" \"1\" / \"1\"").unwrap();
+        
+        assert!(grammar.recognize("1"));
+    }
+    
+    #[test]
+    fn test_undefined_rule() {
+        assert!(PegGrammar::compile("bad", "start <- missing").is_err());
+    }
+}
@@ -1,9 +1,15 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::core::{Result, EidosError, SourceLocation};
-use crate::core::ast::{ASTNode, Node, Program};
+use crate::core::ast::{ASTNode, KeywordAlias, Node, Program, SyntaxRule};
+use crate::core::error::ErrorCollector;
+use crate::core::types::Type;
+use crate::frontend::lexer::KeywordMap;
 use super::registry::DSLRegistry;
 use super::extension::DSLExtension;
+use super::syntax::SyntaxExtension;
+use super::peg::{PegExtension, PegGrammar};
 
 /// DSLブロックの処理を行うプロセッサ
 pub struct DSLProcessor;
@@ -16,13 +22,16 @@ impl DSLProcessor {
     /// DSLブロックを処理
     pub fn process_dsl_block(&self, name: &str, content: &str, program: &Program, location: SourceLocation) -> Result<ASTNode> {
         // レジストリからDSL拡張を取得
-        let registry = DSLRegistry::global().read().unwrap();
-        let extension = registry.get(name).ok_or_else(|| {
-            EidosError::DSL {
-                message: format!("DSL拡張 '{}' が見つかりません", name),
-                dsl_name: name.to_string(),
-            }
-        })?;
+        // 展開中に別のDSLが登録・参照されることがあるため、ロックは取得後すぐに解放する
+        let extension = {
+            let registry = DSLRegistry::global().read().unwrap();
+            registry.get(name).ok_or_else(|| {
+                EidosError::DSL {
+                    message: format!("DSL拡張 '{}' が見つかりません", name),
+                    dsl_name: name.to_string(),
+                }
+            })?
+        };
         
         // DSL拡張を使ってブロックを処理
         let ast_node = extension.process_block(content, program)?;
@@ -38,6 +47,33 @@ impl DSLProcessor {
         Ok(node_with_location)
     }
     
+    /// 型プロバイダのDSLでスキーマのファイルを読み込み、生成した型を返す
+    pub fn provide_types(&self, name: &str, path: &Path) -> Result<Vec<(String, Type)>> {
+        let extension = {
+            let registry = DSLRegistry::global().read().unwrap();
+            registry.get(name)
+        };
+        
+        let extension = extension.ok_or_else(|| EidosError::DSL {
+            message: format!("DSL拡張 '{}' が見つかりません", name),
+            dsl_name: name.to_string(),
+        })?;
+        let schema = std::fs::read_to_string(path).map_err(|e| EidosError::DSL {
+            message: format!("スキーマ '{}' を読み込めません: {}", path.display(), e),
+            dsl_name: name.to_string(),
+        })?;
+        extension.provide_types(path, &schema)
+    }
+    
+    /// 登録済みのすべてのDSL拡張の検査（`DSLExtension::validate`）を名前の順に実行
+    pub fn validate(&self, program: &Program, diagnostics: &mut ErrorCollector) {
+        // 検査の中で別のDSLが参照されることがあるため、ロックは取得後すぐに解放する
+        let extensions = DSLRegistry::global().read().unwrap().extensions();
+        for (_, extension) in extensions {
+            extension.validate(program, diagnostics);
+        }
+    }
+    
     /// 特定のDSL拡張が利用可能かどうか
     pub fn is_dsl_available(&self, name: &str) -> bool {
         let registry = DSLRegistry::global().read().unwrap();
@@ -50,9 +86,67 @@ impl DSLProcessor {
         registry.list_extensions()
     }
     
+    /// 有効化するDSLの組み合わせに構文の衝突がないか確認
+    pub fn check_conflicts(&self, active: &[String]) -> Result<()> {
+        let registry = DSLRegistry::global().read().unwrap();
+        registry.check_conflicts(active)
+    }
+    
+    /// 有効なDSLのうち、文の先頭キーワードを構文として持つものを探す
+    pub fn keyword_owner(&self, active: &[String], keyword: &str) -> Option<String> {
+        let registry = DSLRegistry::global().read().unwrap();
+        registry.claimant(active, keyword)
+    }
+    
+    /// DSLが宣言した予約語と演算子の別名の表（未登録のDSLなら空）
+    pub fn keyword_map(&self, name: &str) -> KeywordMap {
+        let registry = DSLRegistry::global().read().unwrap();
+        registry.get(name).map_or_else(KeywordMap::default, |extension| KeywordMap::new(&extension.keyword_aliases()))
+    }
+    
+    /// DSLのブロックをインデントで区切るか（未登録のDSLなら `false`）
+    pub fn is_layout_sensitive(&self, name: &str) -> bool {
+        let registry = DSLRegistry::global().read().unwrap();
+        registry.get(name).is_some_and(|extension| extension.layout_sensitive())
+    }
+    
     /// 新しいDSL拡張を登録
     pub fn register_dsl(&self, name: String, extension: Arc<dyn DSLExtension>) {
         let mut registry = DSLRegistry::global().write().unwrap();
         registry.register(name, extension);
     }
+    
+    /// `syntax` ブロックで定義されたDSLを登録
+    pub fn register_syntax(&self, name: &str, rules: Vec<SyntaxRule>, keywords: Vec<KeywordAlias>, layout: bool) {
+        let extension = SyntaxExtension::new(name.to_string(), rules).with_keyword_aliases(keywords).with_layout(layout);
+        self.register_dsl(name.to_string(), Arc::new(extension));
+    }
+    
+    /// PEG文法をコンパイルし、パックラットパーサーを持つDSLとして登録
+    pub fn register_peg(&self, name: &str, source: &str) -> Result<()> {
+        let grammar = PegGrammar::compile(name, source)?;
+        self.register_dsl(name.to_string(), Arc::new(PegExtension::new(grammar)));
+        Ok(())
+    }
+    
+    /// 登録済みのPEG文法で入力を解析し、意味アクションの出力を返す（実行時の呼び出し用）
+    pub fn run_peg(&self, name: &str, input: &str) -> Result<String> {
+        let extension = {
+            let registry = DSLRegistry::global().read().unwrap();
+            registry.get(name)
+        };
+        
+        let extension = extension.ok_or_else(|| EidosError::DSL {
+            message: format!("DSL拡張 '{}' が見つかりません", name),
+            dsl_name: name.to_string(),
+        })?;
+        
+        match extension.as_any().downcast_ref::<PegExtension>() {
+            Some(peg) => peg.peg_grammar().parse(input),
+            None => Err(EidosError::DSL {
+                message: format!("DSL拡張 '{}' はPEGで定義されていません", name),
+                dsl_name: name.to_string(),
+            }),
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::core::{Result, EidosError, Name, SourceLocation};
+use crate::core::ast::{self, ASTNode, Mutator, Node, Visitor};
+use crate::frontend::lexer::Lexer;
+use crate::frontend::parser::Parser;
+
+/// テンプレートの中でノードを埋め込む位置を表す関数名（`unquote(名前)`）
+pub const UNQUOTE: &str = "unquote";
+
+/// 衛生的な名前の付け替えに使う、展開ごとの番号
+static NEXT_EXPANSION_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// 準引用（quasi-quotation）でASTの断片を作る
+///
+/// `template` はEidosのコードで、`unquote(名前)` の位置に `splices` の同じ名前のノードを埋め込む。
+/// 埋め込むノードは文字列に戻さずにそのまま使うので、演算子の優先順位が崩れず、元の位置も残る。
+/// テンプレートで導入した名前（`let`・`var`・`for` の変数など）は展開ごとに一意な名前に置き換え、
+/// 埋め込んだノードの名前と衝突しないようにする。テンプレート由来のノードの位置は `location` にする。
+///
+/// ```ignore
+/// let node = quote("{ let t = unquote(value); t * t }", &[("value", value)], &location)?;
+/// ```
+pub fn quote(template: &str, splices: &[(&str, ASTNode)], location: &SourceLocation) -> Result<ASTNode> {
+    let tokens = Lexer::new(template, location.file.clone()).tokenize()?;
+    let mut nodes = Parser::for_expansion(tokens, location.file.clone()).parse()?.nodes;
+    let mut node = match nodes.len() {
+        0 => return Err(quote_error("quote のテンプレートが空です".to_string(), location)),
+        1 => nodes.remove(0),
+        _ => {
+            let result = nodes.pop().map(Box::new);
+            ASTNode::new(Node::BlockExpr { statements: nodes, result }, location.clone())
+        }
+    };
+    
+    let mut binders = Binders(HashSet::new());
+    binders.visit_node(&node);
+    let id = NEXT_EXPANSION_ID.fetch_add(1, Ordering::Relaxed);
+    let mut splicer = Splicer {
+        renames: binders.0.into_iter().map(|name| (name.clone(), format!("{}#quote{}", name, id))).collect(),
+        splices: splices.iter().map(|(name, node)| (*name, node)).collect(),
+        location,
+        missing: None,
+    };
+    splicer.mutate_node(&mut node);
+    match splicer.missing {
+        Some(name) => Err(quote_error(format!("quote のテンプレートの unquote({}) に埋め込むノードがありません", name), location)),
+        None => Ok(node),
+    }
+}
+
+/// `unquote(名前)` の呼び出しなら、埋め込む名前
+fn unquoted(node: &ASTNode) -> Option<&str> {
+    let Node::FunctionCall { callee, args } = &node.kind else {
+        return None;
+    };
+    match (&callee.kind, args.as_slice()) {
+        (Node::Identifier { name, .. }, [ASTNode { kind: Node::Identifier { name: splice, .. }, .. }]) if name == UNQUOTE => {
+            Some(splice)
+        }
+        _ => None,
+    }
+}
+
+/// テンプレートで導入した名前を集める（埋め込む位置の中は見ない）
+struct Binders(HashSet<String>);
+
+impl Visitor for Binders {
+    fn visit_node(&mut self, node: &ASTNode) {
+        if unquoted(node).is_some() {
+            return;
+        }
+        match &node.kind {
+            Node::VarDecl { name, .. } | Node::TryCatch { error_name: name, .. } => {
+                self.0.insert(name.clone());
+            }
+            Node::ForLoop { variable, .. } | Node::Comprehension { variable, .. } => {
+                self.0.insert(variable.clone());
+            }
+            Node::TupleDecl { names, .. } => self.0.extend(names.iter().cloned()),
+            _ => {}
+        }
+        ast::walk(self, node);
+    }
+}
+
+/// テンプレートの名前を付け替えて位置を `location` にし、`unquote(名前)` をノードに置き換える
+struct Splicer<'a> {
+    renames: HashMap<String, String>,
+    splices: HashMap<&'a str, &'a ASTNode>,
+    location: &'a SourceLocation,
+    /// 埋め込むノードが渡されなかった名前
+    missing: Option<String>,
+}
+
+impl Splicer<'_> {
+    fn rename(&self, name: &mut String) {
+        if let Some(renamed) = self.renames.get(name) {
+            *name = renamed.clone();
+        }
+    }
+}
+
+impl Mutator for Splicer<'_> {
+    fn mutate_node(&mut self, node: &mut ASTNode) {
+        if let Some(name) = unquoted(node) {
+            match self.splices.get(name) {
+                Some(splice) => *node = (*splice).clone(),
+                None if self.missing.is_none() => self.missing = Some(name.to_string()),
+                None => {}
+            }
+            return;
+        }
+        
+        node.location = self.location.clone();
+        match &mut node.kind {
+            Node::Identifier { name, .. } => {
+                if let Some(renamed) = self.renames.get(name.as_str()) {
+                    *name = Name::new(renamed);
+                }
+            }
+            Node::VarDecl { name, .. } | Node::TryCatch { error_name: name, .. } => self.rename(name),
+            Node::ForLoop { variable, .. } | Node::Comprehension { variable, .. } => self.rename(variable),
+            Node::TupleDecl { names, .. } => names.iter_mut().for_each(|name| self.rename(name)),
+            _ => {}
+        }
+        ast::walk_mut(self, node);
+    }
+}
+
+fn quote_error(message: String, location: &SourceLocation) -> EidosError {
+    EidosError::Parser {
+        message,
+        file: location.file.clone(),
+        line: location.line,
+        column: location.column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ast::BinaryOp;
+    use std::path::PathBuf;
+    
+    fn expression(source: &str, file: &str) -> ASTNode {
+        let tokens = Lexer::new(source, PathBuf::from(file)).tokenize().unwrap();
+        Parser::new(tokens, PathBuf::from(file)).parse().unwrap().nodes.remove(0)
+    }
+    
+    #[test]
+    fn test_quote_splices_nodes_hygienically() {
+        let location = SourceLocation::new(PathBuf::from("dsl.eid"), 7, 3, 5);
+        // 埋め込むノードは呼び出し側の `t` を参照している
+        let value = expression("t + 1", "user.eid");
+        let node = quote("{ let t = unquote(value); t * unquote(value) }", &[("value", value)], &location).unwrap();
+        
+        let Node::BlockExpr { statements, result: Some(result) } = &node.kind else {
+            panic!("ブロック式ではありません: {:?}", node.kind);
+        };
+        let Node::VarDecl { name, initializer: Some(initializer), .. } = &statements[0].kind else {
+            panic!("変数宣言ではありません: {:?}", statements[0].kind);
+        };
+        assert!(name.starts_with("t#quote"), "{}", name);
+        assert_eq!(statements[0].location, location);
+        // 埋め込んだノードの名前は付け替えず、位置も元のまま
+        assert!(matches!(&initializer.kind, Node::BinaryExpr { op: BinaryOp::Add, left, .. }
+            if matches!(&left.kind, Node::Identifier { name, .. } if name == "t")));
+        assert_eq!(initializer.location.file, PathBuf::from("user.eid"));
+        // `t * (t + 1)` の優先順位が保たれる
+        let Node::BinaryExpr { op: BinaryOp::Mul, left, right } = &result.kind else {
+            panic!("乗算ではありません: {:?}", result.kind);
+        };
+        assert!(matches!(&left.kind, Node::Identifier { name: renamed, .. } if renamed == name));
+        assert!(matches!(right.kind, Node::BinaryExpr { op: BinaryOp::Add, .. }));
+        
+        let error = quote("unquote(missing) + 1", &[], &location).unwrap_err();
+        assert!(error.to_string().contains("unquote(missing) に埋め込むノードがありません (dsl.eid:7:3)"), "{}", error);
+    }
+}
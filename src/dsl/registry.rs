@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 use crate::core::Result;
+use crate::core::EidosError;
 use super::extension::DSLExtension;
+use super::grammar::{Grammar, GrammarExtension};
 
 /// DSL拡張を管理するレジストリ
 pub struct DSLRegistry {
@@ -42,6 +44,13 @@ impl DSLRegistry {
         self.extensions.keys().cloned().collect()
     }
     
+    /// すべてのDSL拡張を名前の順に取得
+    pub fn extensions(&self) -> Vec<(String, Arc<dyn DSLExtension>)> {
+        let mut extensions: Vec<_> = self.extensions.iter().map(|(name, extension)| (name.clone(), extension.clone())).collect();
+        extensions.sort_by(|a, b| a.0.cmp(&b.0));
+        extensions
+    }
+    
     /// DSL拡張が存在するかどうか
     pub fn has_extension(&self, name: &str) -> bool {
         self.extensions.contains_key(name)
@@ -51,4 +60,330 @@ impl DSLRegistry {
     pub fn unregister(&mut self, name: &str) -> bool {
         self.extensions.remove(name).is_some()
     }
-} 
\ No newline at end of file
+    
+    /// 登録済みDSLの文法を取得
+    pub fn grammar(&self, name: &str) -> Result<Grammar> {
+        let extension = self.get(name).ok_or_else(|| EidosError::DSL {
+            message: format!("DSL拡張 '{}' が見つかりません", name),
+            dsl_name: name.to_string(),
+        })?;
+        
+        extension.grammar().ok_or_else(|| EidosError::DSL {
+            message: format!("DSL拡張 '{}' は文法のエクスポートに対応していません", name),
+            dsl_name: name.to_string(),
+        })
+    }
+    
+    /// 登録済みDSLの文法をEBNF形式でエクスポート
+    pub fn export_ebnf(&self, name: &str) -> Result<String> {
+        Ok(self.grammar(name)?.to_ebnf())
+    }
+    
+    /// 登録済みDSLの文法をtree-sitterのgrammar.jsとしてエクスポート
+    pub fn export_tree_sitter(&self, name: &str) -> Result<String> {
+        Ok(self.grammar(name)?.to_tree_sitter())
+    }
+    
+    /// 外部のEBNFを読み込み、文法のみを持つ新しいDSLのひな形として登録
+    pub fn import_ebnf(&mut self, name: &str, source: &str) -> Result<Grammar> {
+        if self.has_extension(name) {
+            return Err(EidosError::DSL {
+                message: format!("DSL拡張 '{}' は既に登録されています", name),
+                dsl_name: name.to_string(),
+            });
+        }
+        
+        let grammar = Grammar::parse_ebnf(name, source)?;
+        self.register(name.to_string(), Arc::new(GrammarExtension::new(grammar.clone())));
+        Ok(grammar)
+    }
+    
+    /// 同時に有効なDSLの間で、同じキーワードで始まる構文や、同じ単語を別の予約語にする別名が重複していないか確認
+    pub fn check_conflicts(&self, active: &[String]) -> Result<()> {
+        let mut owners: HashMap<String, &str> = HashMap::new();
+        // 予約語の別名から、宣言したDSLと元の予約語への対応
+        let mut aliases: HashMap<String, (&str, String)> = HashMap::new();
+        
+        for name in active {
+            let extension = match self.get(name) {
+                Some(extension) => extension,
+                None => continue,
+            };
+            
+            for keyword in extension.keywords() {
+                match owners.get(&keyword) {
+                    Some(owner) if *owner != name.as_str() => {
+                        return Err(EidosError::DSL {
+                            message: format!(
+                                "DSL '{}' と '{}' はどちらも '{}' で始まる構文を定義しているため、同時に有効化できません",
+                                owner, name, keyword
+                            ),
+                            dsl_name: name.clone(),
+                        });
+                    }
+                    _ => {
+                        owners.insert(keyword, name);
+                    }
+                }
+            }
+            
+            for alias in extension.keyword_aliases() {
+                match aliases.get(&alias.alias) {
+                    Some((owner, keyword)) if *keyword != alias.keyword => {
+                        return Err(EidosError::DSL {
+                            message: format!(
+                                "DSL '{}' と '{}' は '{}' を別の予約語（'{}' と '{}'）の別名にしているため、同時に有効化できません",
+                                owner, name, alias.alias, keyword, alias.keyword
+                            ),
+                            dsl_name: name.clone(),
+                        });
+                    }
+                    _ => {
+                        aliases.insert(alias.alias, (name.as_str(), alias.keyword));
+                    }
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// 有効なDSLのうち、指定したキーワードで始まる構文を持つものを探す
+    pub fn claimant(&self, active: &[String], keyword: &str) -> Option<String> {
+        active
+            .iter()
+            .find(|name| {
+                self.get(name)
+                    .map(|extension| extension.keywords().iter().any(|k| k == keyword))
+                    .unwrap_or(false)
+            })
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ast::{KeywordAlias, SyntaxFragment, SyntaxRule};
+    use crate::dsl::SyntaxExtension;
+    
+    fn keyword_dsl(name: &str, keyword: &str) -> Arc<dyn DSLExtension> {
+        let rule = SyntaxRule {
+            pattern: vec![
+                SyntaxFragment::Token(keyword.to_string()),
+                SyntaxFragment::Capture("x".to_string()),
+            ],
+            expansion: vec![SyntaxFragment::Capture("x".to_string())],
+        };
+        Arc::new(SyntaxExtension::new(name.to_string(), vec![rule]))
+    }
+    
+    #[test]
+    fn test_conflicting_keywords() {
+        let mut registry = DSLRegistry::new();
+        registry.register("a".to_string(), keyword_dsl("a", "unless"));
+        registry.register("b".to_string(), keyword_dsl("b", "unless"));
+        registry.register("c".to_string(), keyword_dsl("c", "until"));
+        
+        assert!(registry.check_conflicts(&["a".to_string(), "c".to_string()]).is_ok());
+        match registry.check_conflicts(&["a".to_string(), "b".to_string()]) {
+            Err(EidosError::DSL { message, dsl_name }) => {
+                assert_eq!(dsl_name, "b");
+                assert!(message.contains("unless"));
+            }
+            other => panic!("衝突が検出されませんでした: {:?}", other.is_ok()),
+        }
+    }
+    
+    #[test]
+    fn test_conflicting_keyword_aliases() {
+        let alias = |alias: &str, keyword: &str| KeywordAlias { alias: alias.to_string(), keyword: keyword.to_string() };
+        let mut registry = DSLRegistry::new();
+        let japanese = SyntaxExtension::new("ja".to_string(), Vec::new()).with_keyword_aliases(vec![alias("もし", "if")]);
+        let same = SyntaxExtension::new("ja2".to_string(), Vec::new()).with_keyword_aliases(vec![alias("もし", "if")]);
+        let other = SyntaxExtension::new("other".to_string(), Vec::new()).with_keyword_aliases(vec![alias("もし", "while")]);
+        registry.register("ja".to_string(), Arc::new(japanese));
+        registry.register("ja2".to_string(), Arc::new(same));
+        registry.register("other".to_string(), Arc::new(other));
+        
+        // 同じ別名でも元の予約語が同じなら衝突しない
+        assert!(registry.check_conflicts(&["ja".to_string(), "ja2".to_string()]).is_ok());
+        let error = registry.check_conflicts(&["ja".to_string(), "other".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("'もし' を別の予約語（'if' と 'while'）の別名にしている"), "{}", error);
+    }
+    
+    #[test]
+    fn test_validation_hooks() {
+        use crate::core::ast::{self, ASTNode, Node, Program, Visitor};
+        use crate::core::error::ErrorCollector;
+        use crate::core::types::Type;
+        use crate::core::SourceLocation;
+        
+        /// テーブル名が `tables` にない `@sql { from テーブル }` を誤りにするDSL
+        struct Sql {
+            tables: Vec<&'static str>,
+        }
+        
+        struct Blocks<'a>(&'a Sql, &'a mut ErrorCollector);
+        
+        impl Visitor for Blocks<'_> {
+            fn visit_node(&mut self, node: &ASTNode) {
+                if let Node::DSLBlock { name, content, .. } = &node.kind {
+                    let table = content.split_whitespace().skip_while(|word| *word != "from").nth(1);
+                    if let Some(table) = table.filter(|table| name == "sql" && !self.0.tables.contains(table)) {
+                        self.1.add(EidosError::Type {
+                            message: format!("SQL: テーブル '{}' はスキーマにありません", table),
+                            location: node.location.clone(),
+                        });
+                    }
+                }
+                ast::walk(self, node);
+            }
+        }
+        
+        impl DSLExtension for Sql {
+            fn name(&self) -> &str {
+                "sql"
+            }
+            
+            fn description(&self) -> &str {
+                "SQL"
+            }
+            
+            fn process_block(&self, content: &str, _program: &Program) -> Result<ASTNode> {
+                Ok(ASTNode::new(Node::Literal(ast::Literal::String(content.to_string())), SourceLocation::unknown()))
+            }
+            
+            fn register_types(&self) -> Vec<(String, Type)> {
+                Vec::new()
+            }
+            
+            fn register_builtins(&self) -> Vec<String> {
+                Vec::new()
+            }
+            
+            fn validate(&self, program: &Program, diagnostics: &mut ErrorCollector) {
+                Blocks(self, diagnostics).visit_program(program);
+            }
+            
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+        
+        let mut registry = DSLRegistry::new();
+        registry.register("sql".to_string(), Arc::new(Sql { tables: vec!["users"] }));
+        registry.register("ja".to_string(), keyword_dsl("ja", "もし"));
+        assert_eq!(registry.extensions().iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["ja", "sql"]);
+        
+        let block = |content: &str, line| {
+            let kind = Node::DSLBlock { name: "sql".to_string(), content: content.to_string(), processed_ast: None };
+            ASTNode::new(kind, SourceLocation::new("main.eid".into(), line, 1, 1))
+        };
+        let mut program = Program::new("main.eid".to_string());
+        program.add_node(block("select name from users", 1));
+        program.add_node(block("select name from orders", 2));
+        let mut diagnostics = ErrorCollector::new();
+        for (_, extension) in registry.extensions() {
+            extension.validate(&program, &mut diagnostics);
+        }
+        let errors = diagnostics.into_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("SQL: テーブル 'orders' はスキーマにありません (main.eid:2:1)"), "{}", errors[0]);
+    }
+    
+    #[test]
+    fn test_type_provider() {
+        use std::path::Path;
+        use crate::core::ast::{ASTNode, Node, Program};
+        use crate::core::types::{StructField, Type, TypeKind};
+        use crate::dsl::DSLProcessor;
+        use crate::frontend::{Lexer, Parser};
+        
+        /// 1行に1つ、`型名 フィールド:型 ...` の形で構造体を宣言するスキーマ
+        struct Records;
+        
+        impl DSLExtension for Records {
+            fn name(&self) -> &str {
+                "test_records"
+            }
+            
+            fn description(&self) -> &str {
+                "レコードのスキーマ"
+            }
+            
+            fn process_block(&self, _content: &str, _program: &Program) -> Result<ASTNode> {
+                unreachable!("DSLブロックには使わない")
+            }
+            
+            fn register_types(&self) -> Vec<(String, Type)> {
+                Vec::new()
+            }
+            
+            fn register_builtins(&self) -> Vec<String> {
+                Vec::new()
+            }
+            
+            fn provide_types(&self, _path: &Path, schema: &str) -> Result<Vec<(String, Type)>> {
+                let field = |field: &str| {
+                    let (name, ty) = field.split_once(':').unwrap();
+                    let field_type = if ty == "Int" { Type::int() } else { Type::string() };
+                    StructField { name: name.to_string(), field_type }
+                };
+                Ok(schema
+                    .lines()
+                    .filter_map(|line| line.split_once(' '))
+                    .map(|(name, fields)| {
+                        let fields = fields.split_whitespace().map(field).collect();
+                        (name.to_string(), Type::new(TypeKind::Struct { name: name.to_string(), fields, type_params: Vec::new() }))
+                    })
+                    .collect())
+            }
+            
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+        
+        let processor = DSLProcessor::new();
+        processor.register_dsl("test_records".to_string(), Arc::new(Records));
+        processor.register_dsl("test_records_plain".to_string(), keyword_dsl("test_records_plain", "record"));
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("user.schema"), "User id:Int name:String\n").unwrap();
+        let parse = |source: &str| {
+            let file = dir.path().join("main.eid");
+            let tokens = Lexer::new(source, file.clone()).tokenize().unwrap();
+            Parser::new(tokens, file).parse()
+        };
+        
+        let program = parse("provide test_records \"user.schema\";\nfn id(user: User) -> Int { 0 }").unwrap();
+        match &program.nodes[0].kind {
+            Node::TypeProvider { dsl_name, types, .. } => {
+                assert_eq!(dsl_name, "test_records");
+                assert!(matches!(&types[..], [ASTNode { kind: Node::TypeDef { name, .. }, .. }] if name == "User"));
+            }
+            other => panic!("型プロバイダではありません: {:?}", other),
+        }
+        match &program.nodes[1].kind {
+            Node::FunctionDef { params, .. } => assert_eq!(params[0].param_type.as_ref().unwrap().to_string(), "User"),
+            other => panic!("関数定義ではありません: {:?}", other),
+        }
+        
+        let error = parse("provide test_records \"missing.schema\";").unwrap_err();
+        assert!(error.to_string().contains("スキーマ"), "{}", error);
+        let error = parse("provide test_records_plain \"user.schema\";").unwrap_err();
+        assert!(error.to_string().contains("型プロバイダに対応していません"), "{}", error);
+        let error = parse("provide test_records \"user.schema\";\nprovide test_records \"user.schema\";").unwrap_err();
+        assert!(error.to_string().contains("型 'User' は既に型プロバイダで生成されています"), "{}", error);
+    }
+    
+    #[test]
+    fn test_claimant_only_considers_active() {
+        let mut registry = DSLRegistry::new();
+        registry.register("a".to_string(), keyword_dsl("a", "unless"));
+        
+        assert_eq!(registry.claimant(&["a".to_string()], "unless"), Some("a".to_string()));
+        assert_eq!(registry.claimant(&[], "unless"), None);
+        assert_eq!(registry.claimant(&["a".to_string()], "until"), None);
+    }
+}
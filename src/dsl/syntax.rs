@@ -0,0 +1,527 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::ast::{ASTNode, KeywordAlias, Node, Program, SyntaxFragment, SyntaxRule};
+use crate::core::types::Type;
+use crate::frontend::lexer::{Lexer, TokenKind};
+use crate::frontend::parser::Parser;
+use super::extension::DSLExtension;
+use super::grammar::{Grammar, GrammarExpr};
+use super::quote;
+
+/// `syntax` ブロックで定義されたDSL拡張
+///
+/// DSLブロックの内容を規則のパターンと順番に照合し、最初に一致した規則の
+/// 展開結果をEidosのコードとして再解析する。`keyword` で宣言した予約語の別名は、
+/// DSLを有効化したスコープの字句に適用される。`layout;` を宣言したDSLのブロックは、
+/// パターンの `NEWLINE`・`INDENT`・`DEDENT` でインデントに一致させる。
+pub struct SyntaxExtension {
+    name: String,
+    description: String,
+    rules: Vec<SyntaxRule>,
+    keywords: Vec<KeywordAlias>,
+    layout: bool,
+}
+
+impl SyntaxExtension {
+    pub fn new(name: String, rules: Vec<SyntaxRule>) -> Self {
+        let description = format!("syntax ブロックで定義されたDSL（{} 規則）", rules.len());
+        Self {
+            name,
+            description,
+            rules,
+            keywords: Vec::new(),
+            layout: false,
+        }
+    }
+
+    /// 予約語と演算子の別名を設定
+    pub fn with_keyword_aliases(mut self, keywords: Vec<KeywordAlias>) -> Self {
+        self.keywords = keywords;
+        self
+    }
+
+    /// ブロックをインデントで区切るかを設定
+    pub fn with_layout(mut self, layout: bool) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// 定義されている規則を取得
+    pub fn rules(&self) -> &[SyntaxRule] {
+        &self.rules
+    }
+
+    /// DSLブロックの内容を展開し、Eidosのソースコードを返す
+    pub fn expand(&self, content: &str) -> Result<String> {
+        let (rule, bindings) = self.match_rule(content)?;
+        Ok(substitute(&rule.expansion, &bindings))
+    }
+
+    /// DSLブロックの内容に最初に一致した規則と、キャプチャの束縛を返す
+    fn match_rule(&self, content: &str) -> Result<(&SyntaxRule, HashMap<String, Vec<String>>)> {
+        let tokens = self.tokenize(content)?;
+
+        for rule in &self.rules {
+            let mut bindings = HashMap::new();
+            if match_fragments(&rule.pattern, &tokens, &mut bindings) {
+                return Ok((rule, bindings));
+            }
+        }
+
+        Err(EidosError::DSL {
+            message: format!("DSL '{}' のどの規則にも一致しません: {}", self.name, content),
+            dsl_name: self.name.clone(),
+        })
+    }
+
+    /// `quote { ... }` 形式の展開をASTにする
+    ///
+    /// キャプチャは `unquote($x)` の位置にだけ書ける。キャプチャしたトークン列はそれぞれ
+    /// 単独で解析してから埋め込むので、展開結果の中で演算子の優先順位が崩れない。
+    fn expand_quoted(&self, template: &[SyntaxFragment], bindings: &HashMap<String, Vec<String>>) -> Result<ASTNode> {
+        let mut splices: Vec<(&str, ASTNode)> = Vec::new();
+        for (i, fragment) in template.iter().enumerate() {
+            let SyntaxFragment::Capture(name) = fragment else {
+                continue;
+            };
+            let unquoted = i >= 2
+                && matches!(
+                    (&template[i - 2], &template[i - 1], template.get(i + 1)),
+                    (SyntaxFragment::Token(callee), SyntaxFragment::Token(open), Some(SyntaxFragment::Token(close)))
+                        if callee == quote::UNQUOTE && open == "(" && close == ")"
+                );
+            if !unquoted {
+                return Err(EidosError::DSL {
+                    message: format!("quote の中では、キャプチャ '${}' を unquote(${}) で埋め込んでください", name, name),
+                    dsl_name: self.name.clone(),
+                });
+            }
+            if let Some(tokens) = bindings.get(name) {
+                if splices.iter().all(|(spliced, _)| *spliced != name.as_str()) {
+                    splices.push((name, parse_expansion(&self.name, &tokens.join(" "))?));
+                }
+            }
+        }
+
+        // キャプチャを束縛せずに展開すると、`unquote($x)` は `unquote(x)` になる
+        let template = substitute(template, &HashMap::new());
+        quote::quote(&template, &splices, &SourceLocation::new(self.source_path(), 1, 1, 0))
+    }
+
+    fn source_path(&self) -> PathBuf {
+        PathBuf::from(format!("<dsl:{}>", self.name))
+    }
+
+    fn tokenize(&self, content: &str) -> Result<Vec<String>> {
+        let tokens = Lexer::new(content, self.source_path()).tokenize()?;
+        Ok(tokens
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Eof)
+            .map(|token| token.source_text().into_owned())
+            .collect())
+    }
+}
+
+impl DSLExtension for SyntaxExtension {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn process_block(&self, content: &str, _program: &Program) -> Result<ASTNode> {
+        let (rule, bindings) = self.match_rule(content)?;
+        match quoted_template(&rule.expansion) {
+            Some(template) => self.expand_quoted(template, &bindings),
+            None => parse_expansion(&self.name, &substitute(&rule.expansion, &bindings)),
+        }
+    }
+
+    fn register_types(&self) -> Vec<(String, Type)> {
+        Vec::new()
+    }
+
+    fn register_builtins(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn grammar(&self) -> Option<Grammar> {
+        // 各規則のパターンを開始記号の選択肢とし、キャプチャは任意のトークン列として表す
+        let mut grammar = Grammar::new(&self.name);
+        let alternatives: Vec<GrammarExpr> = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let items: Vec<GrammarExpr> = rule
+                    .pattern
+                    .iter()
+                    .map(|fragment| match fragment {
+                        SyntaxFragment::Token(token) => GrammarExpr::Terminal(token.clone()),
+                        SyntaxFragment::Capture(_) => GrammarExpr::NonTerminal("token_tree".to_string()),
+                    })
+                    .collect();
+                if items.len() == 1 {
+                    items.into_iter().next().unwrap()
+                } else {
+                    GrammarExpr::Sequence(items)
+                }
+            })
+            .collect();
+
+        let start = if alternatives.len() == 1 {
+            alternatives.into_iter().next().unwrap()
+        } else {
+            GrammarExpr::Choice(alternatives)
+        };
+        grammar.add_rule(&self.name, start);
+        grammar.add_rule("token_tree", GrammarExpr::OneOrMore(Box::new(GrammarExpr::NonTerminal("token".to_string()))));
+        grammar.add_rule(
+            "token",
+            GrammarExpr::Choice(vec![
+                GrammarExpr::Sequence(vec![
+                    GrammarExpr::Terminal("(".to_string()),
+                    GrammarExpr::ZeroOrMore(Box::new(GrammarExpr::NonTerminal("token".to_string()))),
+                    GrammarExpr::Terminal(")".to_string()),
+                ]),
+                GrammarExpr::Pattern(r"[^\s()]+".to_string()),
+            ]),
+        );
+        Some(grammar)
+    }
+
+    fn keywords(&self) -> Vec<String> {
+        let mut keywords = Vec::new();
+        for rule in &self.rules {
+            if let Some(SyntaxFragment::Token(first)) = rule.pattern.first() {
+                if is_keyword_like(first) && !keywords.contains(first) {
+                    keywords.push(first.clone());
+                }
+            }
+        }
+        keywords
+    }
+
+    fn layout_sensitive(&self) -> bool {
+        self.layout
+    }
+
+    fn keyword_aliases(&self) -> Vec<KeywordAlias> {
+        self.keywords.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// DSLの展開結果（Eidosのソースコード）を解析してASTノードにする
+pub(crate) fn parse_expansion(dsl_name: &str, source: &str) -> Result<ASTNode> {
+    let path = PathBuf::from(format!("<dsl:{}>", dsl_name));
+    let tokens = Lexer::new(source, path.clone()).tokenize()?;
+    let mut parser = Parser::for_expansion(tokens, path);
+    let mut nodes = parser.parse()?.nodes;
+
+    // 展開結果が複数の文になる場合はブロック式としてまとめる
+    match nodes.len() {
+        0 => Err(EidosError::DSL {
+            message: format!("DSL '{}' の展開結果が空です", dsl_name),
+            dsl_name: dsl_name.to_string(),
+        }),
+        1 => Ok(nodes.remove(0)),
+        _ => {
+            let result = nodes.pop().map(Box::new);
+            let location = nodes[0].location.clone();
+            Ok(ASTNode::new(
+                Node::BlockExpr {
+                    statements: nodes,
+                    result,
+                },
+                location,
+            ))
+        }
+    }
+}
+
+/// 展開が `quote { ... }` の形なら、波括弧の中のテンプレート
+fn quoted_template(expansion: &[SyntaxFragment]) -> Option<&[SyntaxFragment]> {
+    match expansion {
+        [SyntaxFragment::Token(keyword), SyntaxFragment::Token(open), template @ .., SyntaxFragment::Token(close)]
+            if keyword == "quote" && open == "{" && close == "}" =>
+        {
+            Some(template)
+        }
+        _ => None,
+    }
+}
+
+/// 識別子として書ける（文の先頭キーワードになりうる）文字列かどうか
+pub(crate) fn is_keyword_like(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => chars.all(|c| c.is_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+/// トークンが括弧の深さをどれだけ変えるか
+fn nesting_delta(token: &str) -> i32 {
+    match token {
+        "(" | "[" | "{" | "INDENT" => 1,
+        ")" | "]" | "}" | "DEDENT" => -1,
+        // `@name {` 形式のDSL開始トークン
+        t if t.starts_with('@') && t.ends_with('{') => 1,
+        _ => 0,
+    }
+}
+
+/// パターンとトークン列を照合し、キャプチャを `bindings` に記録する
+///
+/// キャプチャは括弧の対応が取れた1個以上のトークンに一致し、短い一致から順に試す。
+fn match_fragments(
+    pattern: &[SyntaxFragment],
+    tokens: &[String],
+    bindings: &mut HashMap<String, Vec<String>>,
+) -> bool {
+    let (first, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => return tokens.is_empty(),
+    };
+
+    match first {
+        SyntaxFragment::Token(expected) => {
+            tokens.first() == Some(expected) && match_fragments(rest, &tokens[1..], bindings)
+        }
+        SyntaxFragment::Capture(name) => {
+            // 同じ名前のキャプチャは同じトークン列に一致しなければならない
+            if let Some(bound) = bindings.get(name).cloned() {
+                return tokens.starts_with(&bound)
+                    && match_fragments(rest, &tokens[bound.len()..], bindings);
+            }
+
+            let mut depth = 0;
+            for end in 1..=tokens.len() {
+                depth += nesting_delta(&tokens[end - 1]);
+                if depth < 0 {
+                    return false;
+                }
+                if depth != 0 {
+                    continue;
+                }
+
+                let mut candidate = bindings.clone();
+                candidate.insert(name.clone(), tokens[..end].to_vec());
+                if match_fragments(rest, &tokens[end..], &mut candidate) {
+                    *bindings = candidate;
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+/// 展開テンプレートにキャプチャを埋め込む
+fn substitute(expansion: &[SyntaxFragment], bindings: &HashMap<String, Vec<String>>) -> String {
+    let mut output = Vec::new();
+    for fragment in expansion {
+        match fragment {
+            SyntaxFragment::Token(token) => output.push(token.clone()),
+            SyntaxFragment::Capture(name) => match bindings.get(name) {
+                Some(tokens) => output.extend(tokens.iter().cloned()),
+                // パターンに現れないキャプチャはそのまま識別子として出力する
+                None => output.push(name.clone()),
+            },
+        }
+    }
+    output.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ast::Literal;
+
+    fn tok(s: &str) -> SyntaxFragment {
+        SyntaxFragment::Token(s.to_string())
+    }
+
+    fn cap(s: &str) -> SyntaxFragment {
+        SyntaxFragment::Capture(s.to_string())
+    }
+
+    fn tokens(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_capture_stops_at_next_token() {
+        let pattern = vec![cap("a"), tok("plus"), cap("b")];
+        let mut bindings = HashMap::new();
+
+        assert!(match_fragments(&pattern, &tokens("x * 2 plus y"), &mut bindings));
+        assert_eq!(bindings["a"], tokens("x * 2"));
+        assert_eq!(bindings["b"], tokens("y"));
+    }
+
+    #[test]
+    fn test_capture_respects_parentheses() {
+        let pattern = vec![cap("a"), tok("plus"), cap("b")];
+        let mut bindings = HashMap::new();
+
+        assert!(match_fragments(&pattern, &tokens("f ( a plus b ) plus c"), &mut bindings));
+        assert_eq!(bindings["a"], tokens("f ( a plus b )"));
+        assert_eq!(bindings["b"], tokens("c"));
+    }
+
+    #[test]
+    fn test_literal_mismatch() {
+        let pattern = vec![tok("twice"), cap("x")];
+        let mut bindings = HashMap::new();
+
+        assert!(!match_fragments(&pattern, &tokens("thrice 3"), &mut bindings));
+        assert!(!match_fragments(&pattern, &tokens("twice"), &mut bindings));
+    }
+
+    #[test]
+    fn test_substitute() {
+        let expansion = vec![cap("x"), tok("+"), cap("x")];
+        let mut bindings = HashMap::new();
+        bindings.insert("x".to_string(), tokens("( a * 2 )"));
+
+        assert_eq!(substitute(&expansion, &bindings), "( a * 2 ) + ( a * 2 )");
+    }
+
+    #[test]
+    fn test_quoted_expansion() {
+        // rule twice $x => quote { let t = unquote($x); t + t };
+        let twice = |expansion: Vec<SyntaxFragment>| {
+            SyntaxExtension::new("twice".to_string(), vec![SyntaxRule { pattern: vec![tok("twice"), cap("x")], expansion }])
+        };
+        let mut expansion = vec![tok("quote"), tok("{"), tok("let"), tok("t"), tok("="), tok("unquote"), tok("("), cap("x"), tok(")")];
+        expansion.extend([tok(";"), tok("t"), tok("+"), tok("t"), tok("}")]);
+        let program = Program::new("twice.eid".to_string());
+        let node = twice(expansion).process_block("twice t * 2", &program).unwrap();
+
+        let Node::BlockExpr { statements, result: Some(result) } = &node.kind else {
+            panic!("ブロック式ではありません: {:?}", node.kind);
+        };
+        let Node::VarDecl { name, initializer: Some(initializer), .. } = &statements[0].kind else {
+            panic!("変数宣言ではありません: {:?}", statements[0].kind);
+        };
+        // テンプレートの `t` は付け替え、キャプチャした `t * 2` の `t` はそのまま残る
+        assert!(name.starts_with("t#quote"), "{}", name);
+        assert!(matches!(&initializer.kind, Node::BinaryExpr { left, .. }
+            if matches!(&left.kind, Node::Identifier { name, .. } if name == "t")));
+        assert!(matches!(&result.kind, Node::BinaryExpr { left, .. }
+            if matches!(&left.kind, Node::Identifier { name: renamed, .. } if renamed == name)));
+
+        let error = twice(vec![tok("quote"), tok("{"), cap("x"), tok("+"), cap("x"), tok("}")])
+            .process_block("twice 1", &program)
+            .unwrap_err();
+        assert!(error.to_string().contains("キャプチャ '$x' を unquote($x) で埋め込んでください"), "{}", error);
+    }
+
+    #[test]
+    fn test_literals_keep_their_source_text() {
+        let source = r#"
+            syntax keep { rule keep $x => $x; rule half => 0.5 * 2.0; }
+            let a = @keep { keep 1.0 };
+            let b = @keep { keep "a\"b" };
+            let c = @keep { half };
+            keep '\'';
+        "#;
+        let tokens = Lexer::new(source, PathBuf::from("keep.eid")).tokenize().unwrap();
+        let program = Parser::new(tokens, PathBuf::from("keep.eid")).parse().unwrap();
+        let expanded = |node: &ASTNode| -> Node {
+            let node = match &node.kind {
+                Node::VarDecl { initializer: Some(initializer), .. } => initializer,
+                _ => node,
+            };
+            match &node.kind {
+                Node::DSLBlock { processed_ast: Some(expanded), .. } => expanded.kind.clone(),
+                other => panic!("DSLブロックではありません: {:?}", other),
+            }
+        };
+
+        // 表示の形（`1`、`"a"b"`）ではなく、書かれたとおりの字面で展開する
+        assert!(matches!(expanded(&program.nodes[1]), Node::Literal(Literal::Float(value)) if value == 1.0));
+        assert!(matches!(expanded(&program.nodes[2]), Node::Literal(Literal::String(value)) if value == "a\"b"));
+        assert!(matches!(expanded(&program.nodes[3]), Node::BinaryExpr { right, .. }
+            if matches!(right.kind, Node::Literal(Literal::Float(value)) if value == 2.0)));
+        assert!(matches!(expanded(&program.nodes[4]), Node::Literal(Literal::Char('\''))));
+    }
+
+    #[test]
+    fn test_layout_block() {
+        let source = r#"
+            syntax 条件 {
+                layout;
+                rule if $c : NEWLINE INDENT $a NEWLINE DEDENT else : NEWLINE INDENT $b NEWLINE DEDENT => if $c { $a } else { $b };
+            }
+            let x = @条件 {
+                if f(1,
+              2) < 2:
+                    10
+                else:
+                    20 * 2
+            };
+        "#;
+        let tokens = Lexer::new(source, PathBuf::from("layout.eid")).tokenize().unwrap();
+        let program = Parser::new(tokens, PathBuf::from("layout.eid")).parse().unwrap();
+        assert!(matches!(&program.nodes[0].kind, Node::SyntaxDef { layout: true, .. }));
+        let Node::VarDecl { initializer: Some(initializer), .. } = &program.nodes[1].kind else {
+            panic!("変数宣言ではありません: {:?}", program.nodes[1].kind);
+        };
+        let Node::DSLBlock { content, processed_ast: Some(expanded), .. } = &initializer.kind else {
+            panic!("DSLブロックではありません: {:?}", initializer.kind);
+        };
+        assert_eq!(
+            content,
+            "if f ( 1 , 2 ) < 2 : NEWLINE INDENT 10 NEWLINE DEDENT else : NEWLINE INDENT 20 * 2 NEWLINE DEDENT"
+        );
+        assert!(matches!(expanded.kind, Node::IfExpr { .. }), "{:?}", expanded.kind);
+
+        let tokens = Lexer::new("use dsl 条件;\nlet y = @条件 {\n    if a:\n        1\n      else:\n        2\n};", PathBuf::from("layout.eid"))
+            .tokenize()
+            .unwrap();
+        let error = Parser::new(tokens, PathBuf::from("layout.eid")).parse().unwrap_err();
+        assert!(error.to_string().contains("インデントが外側のどの行とも揃っていません"), "{}", error);
+    }
+
+    #[test]
+    fn test_keyword_aliases() {
+        let parse = |source: &str| {
+            let tokens = Lexer::new(source, PathBuf::from("ja.eid")).tokenize().unwrap();
+            Parser::new(tokens, PathBuf::from("ja.eid")).parse()
+        };
+        let program = parse(
+            "syntax 日本語 { keyword 関数 = fn; keyword 変数 = let; keyword もし = if; keyword でなければ = else; keyword かつ = &&; }\n\
+             関数 判定(x: Int) -> Int { 変数 y = x; もし y > 0 かつ y < 10 { 1 } でなければ { 0 } }",
+        )
+        .unwrap();
+        match &program.nodes[0].kind {
+            Node::SyntaxDef { rules, keywords, .. } => {
+                assert!(rules.is_empty());
+                assert_eq!(keywords[0], KeywordAlias { alias: "関数".to_string(), keyword: "fn".to_string() });
+            }
+            other => panic!("DSL定義ではありません: {:?}", other),
+        }
+        // 元の予約語で書いた場合と同じASTになる
+        let function = format!("{:?}", program.nodes[1].kind);
+        assert!(function.starts_with("FunctionDef { name: \"判定\""), "{}", function);
+        assert!(function.contains("IfExpr") && function.contains("op: And") && function.contains("VarDecl"), "{}", function);
+
+        // ブロックの中で有効化した別名は、ブロックの外では識別子のまま
+        let program = parse("fn g() -> Int { { use dsl 日本語; 変数 y = 1; } もし }").unwrap();
+        let function = format!("{:?}", program.nodes[0].kind);
+        assert!(function.contains("VarDecl") && function.contains("name: \"もし\""), "{}", function);
+
+        let error = parse("syntax 別名 { keyword 表示 = println; }").unwrap_err();
+        assert!(error.to_string().contains("'println' は予約語でも演算子でもない"), "{}", error);
+    }
+}
@@ -0,0 +1,691 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::ast::{ASTNode, BinaryOp, GlobalKind, Literal, Node, Program, UnaryOp};
+use crate::frontend::{Lexer, Parser};
+use crate::stdlib::{StdlibFunctionType, StdlibRegistry, Value};
+
+/// 1回の評価で実行できるステップ数の上限（無限ループでコンパイルが止まらないようにする）
+const MAX_STEPS: usize = 1_000_000;
+/// `const fn` の呼び出しの深さの上限
+const MAX_CALL_DEPTH: usize = 64;
+
+/// `const fn` の定義
+#[derive(Debug, Clone)]
+struct ConstFunction {
+    params: Vec<String>,
+    body: ASTNode,
+}
+
+/// コンパイル時評価器
+///
+/// `const fn` で宣言された関数を型チェック中に実行し、引数がすべてリテラルの呼び出しを結果の値に置き換える。
+/// `const fn` から呼び出せるのは、ほかの `const fn` と純粋な標準ライブラリ関数だけ。
+/// グローバル変数の初期化式もここで評価する。初期化式と `const fn` からは、先に宣言した `const` を参照できる。
+/// `comptime { ... }` ブロックも同じ規則で実行する（`expand_comptime`）。
+#[derive(Debug, Clone, Default)]
+pub struct ConstEvaluator {
+    functions: HashMap<String, ConstFunction>,
+    /// 評価済みのグローバル変数の値
+    globals: HashMap<String, (GlobalKind, Value)>,
+}
+
+/// 1回の評価の状態
+struct Evaluation<'a> {
+    evaluator: &'a ConstEvaluator,
+    /// 変数のスコープ（末尾が最も内側）
+    scopes: Vec<HashMap<String, Value>>,
+    steps: usize,
+    depth: usize,
+}
+
+impl ConstEvaluator {
+    pub fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+        }
+    }
+    
+    /// プログラムから `const fn` を集めて呼び出している関数を検証し、グローバル変数の初期化式を評価する
+    pub fn collect(&mut self, program: &Program) -> Result<()> {
+        StdlibRegistry::initialize()?;
+        
+        for node in &program.nodes {
+            self.collect_functions(node);
+        }
+        for function in self.functions.values() {
+            self.verify_calls(&function.body)?;
+        }
+        for node in &program.nodes {
+            self.collect_global(node)?;
+        }
+        Ok(())
+    }
+    
+    /// トップレベルの文がグローバル変数の宣言なら、初期化式を評価して記録する
+    fn collect_global(&mut self, node: &ASTNode) -> Result<()> {
+        if let Node::GlobalDecl { name, initializer, kind, .. } = &node.kind {
+            let value = self.eval_global(name, initializer, *kind)?;
+            self.globals.insert(name.clone(), (*kind, value));
+        }
+        Ok(())
+    }
+    
+    /// `comptime { ... }` ブロックをコンパイル時に実行し、結果をプログラムに埋め込む
+    ///
+    /// 式の位置のブロックは値のリテラルに置き換える。トップレベルのブロックの値は、生成する宣言の
+    /// ソースコード（文字列）か unit でなければならず、文字列は解析した宣言に置き換える。
+    /// ブロックからは `const fn` と、それより前に宣言（または生成）した `const` を参照できる。
+    /// 意味解析より前に実行するので、生成した宣言はほかの宣言と同じように検査される。
+    pub fn expand_comptime(&mut self, mut program: Program) -> Result<Program> {
+        if !program.nodes.iter().any(contains_comptime) {
+            return Ok(program);
+        }
+        StdlibRegistry::initialize()?;
+        for node in &program.nodes {
+            self.collect_functions(node);
+        }
+        
+        let mut nodes = Vec::with_capacity(program.nodes.len());
+        for mut node in std::mem::take(&mut program.nodes) {
+            if let Node::Comptime { body } = &node.kind {
+                for generated in self.generate(body, &node.location)? {
+                    self.collect_functions(&generated);
+                    self.collect_global(&generated)?;
+                    nodes.push(generated);
+                }
+                continue;
+            }
+            self.fold_comptime(&mut node)?;
+            self.collect_global(&node)?;
+            nodes.push(node);
+        }
+        
+        program.node_map = nodes.iter().map(|node| (node.id, node.clone())).collect();
+        program.nodes = nodes;
+        Ok(program)
+    }
+    
+    /// トップレベルの `comptime` ブロックを実行し、値の文字列を宣言として解析する
+    fn generate(&self, body: &ASTNode, location: &SourceLocation) -> Result<Vec<ASTNode>> {
+        let source = match self.run_comptime(body, location)? {
+            Value::Str(source) => source,
+            Value::Unit => return Ok(Vec::new()),
+            other => {
+                return Err(EidosError::Type {
+                    message: format!(
+                        "トップレベルの comptime ブロックの値は、生成するコードの文字列か unit である必要があります（{} 型です）",
+                        other.type_name()
+                    ),
+                    location: location.clone(),
+                })
+            }
+        };
+        
+        let path = PathBuf::from(format!("<comptime:{}:{}>", location.file.display(), location.line));
+        let parsed = Lexer::new(&source, path.clone())
+            .tokenize()
+            .and_then(|tokens| Parser::new(tokens, path).parse());
+        parsed.map(|program| program.nodes).map_err(|e| EidosError::Type {
+            message: format!("comptime ブロックが生成したコードを解析できません: {}", e),
+            location: location.clone(),
+        })
+    }
+    
+    /// 式の位置の `comptime` ブロックを実行し、値のリテラルに置き換える
+    fn fold_comptime(&self, node: &mut ASTNode) -> Result<()> {
+        if let Node::Comptime { body } = &node.kind {
+            let value = self.run_comptime(body, &node.location)?;
+            let literal = value_literal(&value).ok_or_else(|| EidosError::Type {
+                message: format!("comptime ブロックの値は {} 型にできません", value.type_name()),
+                location: node.location.clone(),
+            })?;
+            node.kind = Node::Literal(literal);
+            return Ok(());
+        }
+        for child in node.children_mut() {
+            self.fold_comptime(child)?;
+        }
+        Ok(())
+    }
+    
+    fn run_comptime(&self, body: &ASTNode, location: &SourceLocation) -> Result<Value> {
+        Evaluation::new(self).eval(body).map_err(|reason| EidosError::Type {
+            message: format!("comptime ブロックをコンパイル時に実行できません: {}", reason),
+            location: location.clone(),
+        })
+    }
+    
+    /// グローバル変数の初期化式を評価（値はリテラルで表せるものに限る）
+    fn eval_global(&self, name: &str, initializer: &ASTNode, kind: GlobalKind) -> Result<Value> {
+        let value = Evaluation::new(self).eval(initializer).map_err(|reason| EidosError::Type {
+            message: format!(
+                "{} '{}' の初期化式はコンパイル時に評価できる必要があります: {}",
+                kind.keyword(),
+                name,
+                reason
+            ),
+            location: initializer.location.clone(),
+        })?;
+        if value_literal(&value).is_none() {
+            return Err(EidosError::Type {
+                message: format!("{} '{}' の値は {} 型にできません", kind.keyword(), name, value.type_name()),
+                location: initializer.location.clone(),
+            });
+        }
+        Ok(value)
+    }
+    
+    /// `const` として宣言したグローバル変数の値
+    fn constant(&self, name: &str) -> Option<&Value> {
+        match self.globals.get(name) {
+            Some((GlobalKind::Const, value)) => Some(value),
+            _ => None,
+        }
+    }
+    
+    fn collect_functions(&mut self, node: &ASTNode) {
+        if let Node::FunctionDef { name, params, body, is_const: true, .. } = &node.kind {
+            self.functions.insert(name.clone(), ConstFunction {
+                params: params.iter().map(|param| param.name.clone()).collect(),
+                body: body.as_ref().clone(),
+            });
+        }
+        for child in node.children() {
+            self.collect_functions(child);
+        }
+    }
+    
+    /// `const fn` の本体が、コンパイル時に実行できる関数だけを呼び出しているか検証
+    fn verify_calls(&self, node: &ASTNode) -> Result<()> {
+        if let Node::FunctionCall { callee, .. } = &node.kind {
+            let name = match &callee.kind {
+                Node::Identifier { name, .. } => Some(name.as_str()),
+                _ => None,
+            };
+            if !name.is_some_and(|name| self.is_callable(name)) {
+                return Err(EidosError::Type {
+                    message: format!(
+                        "const fn からは const fn か純粋な標準ライブラリ関数しか呼び出せません: '{}'",
+                        name.unwrap_or("<式>")
+                    ),
+                    location: node.location.clone(),
+                });
+            }
+        }
+        for child in node.children() {
+            self.verify_calls(child)?;
+        }
+        Ok(())
+    }
+    
+    /// コンパイル時に呼び出せる関数かどうか
+    fn is_callable(&self, name: &str) -> bool {
+        if self.is_const_function(name) {
+            return true;
+        }
+        let registry = StdlibRegistry::global();
+        let registry = registry.read().unwrap();
+        registry.function_type(name) == Some(StdlibFunctionType::Pure)
+    }
+    
+    /// `const fn` として宣言された関数かどうか
+    pub fn is_const_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+    
+    /// `const fn` を呼び出す（`location` はエラーの報告位置）
+    pub fn call(&self, name: &str, args: &[Value], location: &SourceLocation) -> Result<Value> {
+        Evaluation::new(self).call(name, args.to_vec()).map_err(|reason| EidosError::Type {
+            message: format!("const fn '{}' のコンパイル時評価に失敗しました: {}", name, reason),
+            location: location.clone(),
+        })
+    }
+    
+    /// 引数がすべて定数式の `const fn` 呼び出しを評価し、結果のリテラルに置き換える
+    ///
+    /// 結果がリストやマップの呼び出しは、評価してエラーがないことだけを確かめてそのまま残す。
+    /// グローバル変数の初期化式は評価した値のリテラルに置き換える。
+    pub fn fold_program(&self, mut program: Program) -> Result<Program> {
+        for node in &mut program.nodes {
+            self.fold_node(node)?;
+            program.node_map.insert(node.id, node.clone());
+        }
+        Ok(program)
+    }
+    
+    fn fold_node(&self, node: &mut ASTNode) -> Result<()> {
+        if let Node::GlobalDecl { name, initializer, .. } = &mut node.kind {
+            // 文字はリテラルのまま残す（評価すると文字列になる）
+            if !matches!(initializer.kind, Node::Literal(_)) {
+                if let Some(literal) = self.globals.get(name.as_str()).and_then(|(_, value)| value_literal(value)) {
+                    initializer.kind = Node::Literal(literal);
+                }
+            }
+            return Ok(());
+        }
+        
+        // 内側の呼び出しから畳み込む（`f(g(1))` の `g(1)` を先に値にする）
+        for child in node.children_mut() {
+            self.fold_node(child)?;
+        }
+        
+        let value = match &node.kind {
+            Node::FunctionCall { callee, args } => {
+                let name = match &callee.kind {
+                    Node::Identifier { name, .. } if self.is_const_function(name) => name,
+                    _ => return Ok(()),
+                };
+                if !args.iter().all(is_constant_expression) {
+                    return Ok(());
+                }
+                let mut evaluation = Evaluation::new(self);
+                let args = args
+                    .iter()
+                    .map(|arg| evaluation.eval(arg))
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|reason| EidosError::Type {
+                        message: format!("const fn '{}' の引数を評価できません: {}", name, reason),
+                        location: node.location.clone(),
+                    })?;
+                self.call(name, &args, &node.location)?
+            }
+            _ => return Ok(()),
+        };
+        
+        if let Some(literal) = value_literal(&value) {
+            node.kind = Node::Literal(literal);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Evaluation<'a> {
+    fn new(evaluator: &'a ConstEvaluator) -> Self {
+        Self {
+            evaluator,
+            scopes: Vec::new(),
+            steps: 0,
+            depth: 0,
+        }
+    }
+    
+    fn call(&mut self, name: &str, args: Vec<Value>) -> std::result::Result<Value, String> {
+        let evaluator = self.evaluator;
+        let function = match evaluator.functions.get(name) {
+            Some(function) => function,
+            None => {
+                let registry = StdlibRegistry::global();
+                let registry = registry.read().unwrap();
+                if registry.function_type(name) != Some(StdlibFunctionType::Pure) {
+                    return Err(format!("'{}' はコンパイル時に呼び出せません", name));
+                }
+                return registry.execute_function(name, &args).map_err(|e| e.to_string());
+            }
+        };
+        if function.params.len() != args.len() {
+            return Err(format!(
+                "'{}' の引数は{}個ですが、{}個渡されました",
+                name,
+                function.params.len(),
+                args.len()
+            ));
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(format!("呼び出しの深さが上限（{}）を超えました", MAX_CALL_DEPTH));
+        }
+        
+        // 呼び出し先からは呼び出し元の変数が見えないよう、スコープを入れ替える
+        let frame = function.params.iter().cloned().zip(args).collect();
+        let caller_scopes = std::mem::replace(&mut self.scopes, vec![frame]);
+        self.depth += 1;
+        let result = self.eval(&function.body);
+        self.depth -= 1;
+        self.scopes = caller_scopes;
+        result
+    }
+    
+    fn eval(&mut self, node: &ASTNode) -> std::result::Result<Value, String> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(format!("実行ステップ数が上限（{}）を超えました", MAX_STEPS));
+        }
+        
+        match &node.kind {
+            Node::Literal(literal) => literal_value(literal),
+            
+            Node::Identifier { name, .. } => self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name.as_str()))
+                .or_else(|| self.evaluator.constant(name))
+                .cloned()
+                .ok_or_else(|| format!("コンパイル時に値の分からない変数です: '{}'", name)),
+            
+            Node::UnaryExpr { op, expr } => {
+                let value = self.eval(expr)?;
+                match (op, value) {
+                    (UnaryOp::Neg, Value::Int(v)) => v.checked_neg().map(Value::Int).ok_or_else(overflow),
+                    (UnaryOp::Neg, Value::Float(v)) => Ok(Value::Float(-v)),
+                    (UnaryOp::Not, Value::Bool(v)) => Ok(Value::Bool(!v)),
+                    (UnaryOp::BitNot, Value::Int(v)) => Ok(Value::Int(!v)),
+                    (op, value) => Err(format!("演算子 '{}' は {} 型の値に適用できません", op, value.type_name())),
+                }
+            }
+            
+            Node::BinaryExpr { op: BinaryOp::And, left, right } => {
+                Ok(Value::Bool(self.eval_bool(left)? && self.eval_bool(right)?))
+            }
+            Node::BinaryExpr { op: BinaryOp::Or, left, right } => {
+                Ok(Value::Bool(self.eval_bool(left)? || self.eval_bool(right)?))
+            }
+            Node::BinaryExpr { op, left, right } => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                binary(*op, left, right)
+            }
+            
+            Node::IfExpr { condition, then_branch, else_branch } => {
+                if self.eval_bool(condition)? {
+                    self.eval(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.eval(else_branch)
+                } else {
+                    Ok(Value::Unit)
+                }
+            }
+            
+            Node::BlockExpr { statements, result } => {
+                self.scopes.push(HashMap::new());
+                let value = self.eval_block(statements, result.as_deref());
+                self.scopes.pop();
+                value
+            }
+            
+            Node::MapLiteral { entries } => {
+                let mut map = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    map.push((self.eval(key)?, self.eval(value)?));
+                }
+                Ok(Value::Map(map))
+            }
+            
+            Node::VarDecl { name, initializer, .. } => {
+                let value = match initializer {
+                    Some(initializer) => self.eval(initializer)?,
+                    None => Value::Unit,
+                };
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(name.clone(), value);
+                }
+                Ok(Value::Unit)
+            }
+            
+            Node::Assignment { target, value } => {
+                let value = self.eval(value)?;
+                self.assign(target, value)?;
+                Ok(Value::Unit)
+            }
+            Node::CompoundAssignment { op, target, value } => {
+                let current = self.eval(target)?;
+                let value = self.eval(value)?;
+                self.assign(target, binary(*op, current, value)?)?;
+                Ok(Value::Unit)
+            }
+            
+            Node::UnsafeBlock { body } | Node::Comptime { body } => self.eval(body),
+            
+            Node::WhileLoop { condition, body } => {
+                while self.eval_bool(condition)? {
+                    self.eval(body)?;
+                }
+                Ok(Value::Unit)
+            }
+            
+            Node::FunctionCall { callee, args } => {
+                let name = match &callee.kind {
+                    Node::Identifier { name, .. } => name,
+                    _ => return Err("関数名以外の式は呼び出せません".to_string()),
+                };
+                let args = args.iter().map(|arg| self.eval(arg)).collect::<std::result::Result<_, _>>()?;
+                self.call(name, args)
+            }
+            
+            _ => Err("コンパイル時に評価できない式です".to_string()),
+        }
+    }
+    
+    fn eval_block(&mut self, statements: &[ASTNode], result: Option<&ASTNode>) -> std::result::Result<Value, String> {
+        for statement in statements {
+            self.eval(statement)?;
+        }
+        match result {
+            Some(result) => self.eval(result),
+            None => Ok(Value::Unit),
+        }
+    }
+    
+    fn eval_bool(&mut self, node: &ASTNode) -> std::result::Result<bool, String> {
+        self.eval(node)?.as_bool().map_err(|e| e.to_string())
+    }
+    
+    fn assign(&mut self, target: &ASTNode, value: Value) -> std::result::Result<(), String> {
+        let name = match &target.kind {
+            Node::Identifier { name, .. } => name,
+            _ => return Err("代入先には変数が必要です".to_string()),
+        };
+        match self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name.as_str())) {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(format!("コンパイル時に値の分からない変数です: '{}'", name)),
+        }
+    }
+}
+
+fn overflow() -> String {
+    "整数演算がオーバーフローしました".to_string()
+}
+
+/// 二項演算を評価（`&&` と `||` は短絡評価のため呼び出し側で扱う）
+fn binary(op: BinaryOp, left: Value, right: Value) -> std::result::Result<Value, String> {
+    use BinaryOp::*;
+    
+    let value = match (&left, &right) {
+        (Value::Int(l), Value::Int(r)) => {
+            let (l, r) = (*l, *r);
+            match op {
+                Add => Value::Int(l.checked_add(r).ok_or_else(overflow)?),
+                Sub => Value::Int(l.checked_sub(r).ok_or_else(overflow)?),
+                Mul => Value::Int(l.checked_mul(r).ok_or_else(overflow)?),
+                Div | Mod if r == 0 => return Err("0で除算しました".to_string()),
+                Div => Value::Int(l.checked_div(r).ok_or_else(overflow)?),
+                Mod => Value::Int(l.checked_rem(r).ok_or_else(overflow)?),
+                BitAnd => Value::Int(l & r),
+                BitOr => Value::Int(l | r),
+                BitXor => Value::Int(l ^ r),
+                LShift => Value::Int(u32::try_from(r).ok().and_then(|r| l.checked_shl(r)).ok_or_else(overflow)?),
+                RShift => Value::Int(u32::try_from(r).ok().and_then(|r| l.checked_shr(r)).ok_or_else(overflow)?),
+                Eq => Value::Bool(l == r),
+                NotEq => Value::Bool(l != r),
+                Lt => Value::Bool(l < r),
+                LtEq => Value::Bool(l <= r),
+                Gt => Value::Bool(l > r),
+                GtEq => Value::Bool(l >= r),
+                And | Or => return Err(mismatch(op, &left, &right)),
+            }
+        }
+        (Value::Float(l), Value::Float(r)) => {
+            let (l, r) = (*l, *r);
+            match op {
+                Add => Value::Float(l + r),
+                Sub => Value::Float(l - r),
+                Mul => Value::Float(l * r),
+                Div => Value::Float(l / r),
+                Mod => Value::Float(l % r),
+                Eq => Value::Bool(l == r),
+                NotEq => Value::Bool(l != r),
+                Lt => Value::Bool(l < r),
+                LtEq => Value::Bool(l <= r),
+                Gt => Value::Bool(l > r),
+                GtEq => Value::Bool(l >= r),
+                _ => return Err(mismatch(op, &left, &right)),
+            }
+        }
+        (Value::Str(l), Value::Str(r)) => match op {
+            Add => Value::Str(format!("{}{}", l, r)),
+            Eq => Value::Bool(l == r),
+            NotEq => Value::Bool(l != r),
+            Lt => Value::Bool(l < r),
+            LtEq => Value::Bool(l <= r),
+            Gt => Value::Bool(l > r),
+            GtEq => Value::Bool(l >= r),
+            _ => return Err(mismatch(op, &left, &right)),
+        },
+        _ => match op {
+            Eq => Value::Bool(left == right),
+            NotEq => Value::Bool(left != right),
+            _ => return Err(mismatch(op, &left, &right)),
+        },
+    };
+    Ok(value)
+}
+
+fn mismatch(op: BinaryOp, left: &Value, right: &Value) -> String {
+    format!("演算子 '{}' は {} 型と {} 型の値に適用できません", op, left.type_name(), right.type_name())
+}
+
+/// `comptime` ブロックを含むかどうか
+fn contains_comptime(node: &ASTNode) -> bool {
+    matches!(node.kind, Node::Comptime { .. }) || node.children().into_iter().any(contains_comptime)
+}
+
+/// 変数や関数呼び出しを含まず、その場で値の決まる式かどうか
+fn is_constant_expression(node: &ASTNode) -> bool {
+    match &node.kind {
+        Node::Literal(Literal::BigInt(_)) => false,
+        Node::Literal(_) => true,
+        Node::UnaryExpr { .. } | Node::BinaryExpr { .. } | Node::IfExpr { .. } | Node::MapLiteral { .. } => {
+            node.children().into_iter().all(is_constant_expression)
+        }
+        _ => false,
+    }
+}
+
+/// リテラルを値に変換（`BigInt` のリテラルは実行時に作るので、コンパイル時には評価できない）
+fn literal_value(literal: &Literal) -> std::result::Result<Value, String> {
+    Ok(match literal {
+        Literal::Int(i) => Value::Int(*i),
+        Literal::BigInt(value) => return Err(format!("BigInt のリテラル {} はコンパイル時に評価できません", value)),
+        Literal::Float(f) => Value::Float(*f),
+        Literal::Bool(b) => Value::Bool(*b),
+        Literal::Char(c) => Value::Str(c.to_string()),
+        Literal::String(s) => Value::Str(s.clone()),
+        Literal::Unit => Value::Unit,
+    })
+}
+
+/// 値をリテラルで表せる場合は変換
+fn value_literal(value: &Value) -> Option<Literal> {
+    match value {
+        Value::Int(i) => Some(Literal::Int(*i)),
+        Value::Float(f) => Some(Literal::Float(*f)),
+        Value::Bool(b) => Some(Literal::Bool(*b)),
+        Value::Str(s) => Some(Literal::String(s.clone())),
+        Value::Unit => Some(Literal::Unit),
+        Value::List(_) | Value::Map(_) | Value::Option(_) | Value::Result(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::parse_test_source;
+    
+    fn fold(source: &str) -> Result<Program> {
+        let program = parse_test_source(source)?;
+        let mut evaluator = ConstEvaluator::new();
+        evaluator.collect(&program)?;
+        evaluator.fold_program(program)
+    }
+    
+    /// 最後のトップレベル文の `let` の初期化式
+    fn last_initializer(program: &Program) -> &Node {
+        match &program.nodes.last().unwrap().kind {
+            Node::VarDecl { initializer: Some(initializer), .. } => &initializer.kind,
+            other => panic!("変数宣言ではありません: {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_fold_const_fn_call() {
+        let program = fold(
+            "const fn table_size(bits: Int) -> Int { let mut size = 1; size *= bits; size * 2 }
+             let size = table_size(table_size(1) + 2);",
+        )
+        .unwrap();
+        assert_eq!(last_initializer(&program), &Node::Literal(Literal::Int(8)));
+        
+        // 引数がリテラルでない呼び出しや、const fn でない関数の呼び出しはそのまま残す
+        let program = fold("const fn double(x: Int) -> Int { x * 2 } let n = 3; let m = double(n);").unwrap();
+        assert!(matches!(last_initializer(&program), Node::FunctionCall { .. }));
+        let program = fold("fn double(x: Int) -> Int { x * 2 } let m = double(3);").unwrap();
+        assert!(matches!(last_initializer(&program), Node::FunctionCall { .. }));
+    }
+    
+    #[test]
+    fn test_const_fn_calls_only_const_or_pure() {
+        assert!(fold("const fn f(x: Int) -> Int { math::abs(x) }").is_ok());
+        assert!(fold("fn g() -> Int { 1 } const fn f() -> Int { g() }").is_err());
+        assert!(fold("const fn f() -> Int { time::timestamp() }").is_err());
+    }
+    
+    #[test]
+    fn test_evaluation_errors() {
+        assert!(fold("const fn f(x: Int) -> Int { 10 / x } let a = f(0);").is_err());
+        assert!(fold("const fn f(x: Int) -> Int { f(x) } let a = f(1);").is_err());
+        assert!(fold("const fn f(x: Int) -> Int { x * 9223372036854775807 } let a = f(2);").is_err());
+    }
+    
+    #[test]
+    fn test_global_initializers() {
+        let program = fold(
+            "const fn kib(n: Int) -> Int { n * 1024 }
+             const PAGES: Int = 4;
+             static BUFFER_SIZE: Int = kib(PAGES) + 1;",
+        )
+        .unwrap();
+        match &program.nodes.last().unwrap().kind {
+            Node::GlobalDecl { initializer, .. } => assert_eq!(initializer.kind, Node::Literal(Literal::Int(4097))),
+            other => panic!("グローバル変数の宣言ではありません: {:?}", other),
+        }
+        
+        // 実行時の値や、後で宣言する const・static の変数は参照できない
+        assert!(fold("const A: Int = time::timestamp();").is_err());
+        assert!(fold("const A: Int = B; const B: Int = 1;").is_err());
+        assert!(fold("static A: Int = 1; const B: Int = A;").is_err());
+        assert!(fold("const fn f() -> Int { PAGES } const PAGES: Int = 4; const A: Int = f();").is_ok());
+    }
+    
+    #[test]
+    fn test_expand_comptime() {
+        let expand = |source: &str| ConstEvaluator::new().expand_comptime(parse_test_source(source)?);
+        // 生成した `const` は、後に続く comptime ブロックから参照できる
+        let program = expand("comptime { \"const N: Int = \" + \"3;\" } let a = comptime { N * 2 };").unwrap();
+        assert!(matches!(&program.nodes[0].kind, Node::GlobalDecl { name, .. } if name == "N"));
+        assert_eq!(last_initializer(&program), &Node::Literal(Literal::Int(6)));
+        assert_eq!(program.node_map.len(), 2);
+        
+        let error = expand("comptime { 1 }").unwrap_err();
+        assert!(error.to_string().contains("文字列か unit である必要があります（Int 型です）"), "{}", error);
+        let error = expand("fn f(x: Int) -> Int { comptime { x } }").unwrap_err();
+        assert!(error.to_string().contains("値の分からない変数です: 'x'"), "{}", error);
+        let error = expand("comptime { \"fn (\" }").unwrap_err();
+        assert!(error.to_string().contains("生成したコードを解析できません"), "{}", error);
+        assert!(expand("let t = comptime { time::timestamp() };").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::ast::{ASTNode, BinaryOp, Node, Program};
+
+/// 宣言したローカル変数
+struct Variable {
+    name: String,
+    location: SourceLocation,
+}
+
+/// ある地点で確実に代入済みの変数の集合
+///
+/// 代入されていない変数には、分岐の合流などで「代入されない経路」が生じた理由を残し、診断に添える。
+#[derive(Clone, Default)]
+struct State {
+    assigned: HashSet<usize>,
+    reasons: HashMap<usize, String>,
+}
+
+impl State {
+    fn assign(&mut self, variable: usize) {
+        self.assigned.insert(variable);
+        self.reasons.remove(&variable);
+    }
+    
+    /// 2つの経路の合流。片方でしか代入されていない変数は、`reason` を理由として未代入に戻る
+    fn merge(&mut self, other: &State, reason: impl Fn() -> String) {
+        for variable in self.assigned.symmetric_difference(&other.assigned) {
+            self.reasons.entry(*variable).or_insert_with(&reason);
+        }
+        for (variable, text) in &other.reasons {
+            self.reasons.entry(*variable).or_insert_with(|| text.clone());
+        }
+        self.assigned.retain(|variable| other.assigned.contains(variable));
+    }
+}
+
+/// 確実な初期化の検査器
+///
+/// 初期化式なしで宣言したローカル変数（`let x: Int;`）を、代入されない経路が残る地点で読み取るとエラーにする。
+/// 経路は `if`・`&&`・`||`・`while`・`try`/`catch` で分かれ、`while` の本体は一度も実行されない場合があるとみなす。
+/// グローバル変数と関数のパラメータは常に初期化済み。
+#[derive(Default)]
+pub struct InitChecker {
+    variables: Vec<Variable>,
+}
+
+impl InitChecker {
+    pub fn new() -> Self {
+        Self {
+            variables: Vec::new(),
+        }
+    }
+    
+    /// プログラム全体を検査（トップレベルの文は1つの関数の本体として扱う）
+    pub fn check(&mut self, program: &Program) -> Result<()> {
+        let mut scopes = vec![HashMap::new()];
+        let mut state = State::default();
+        for node in &program.nodes {
+            self.check_node(node, &mut scopes, &mut state)?;
+        }
+        Ok(())
+    }
+    
+    /// `scopes` は名前から変数への対応（内側のスコープが優先）、`state` は現在地点の代入状況
+    fn check_node(&mut self, node: &ASTNode, scopes: &mut Vec<HashMap<String, usize>>, state: &mut State) -> Result<()> {
+        match &node.kind {
+            Node::FunctionDef { params, body, .. } => {
+                // 関数本体からは外側のローカル変数は見えず、パラメータは初期化済み
+                let mut scopes = vec![HashMap::new()];
+                let mut state = State::default();
+                for param in params {
+                    let variable = self.declare(&param.name, &node.location, &mut scopes);
+                    state.assign(variable);
+                }
+                self.check_node(body, &mut scopes, &mut state)
+            }
+            Node::VarDecl { name, initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.check_node(initializer, scopes, state)?;
+                }
+                let variable = self.declare(name, &node.location, scopes);
+                if initializer.is_some() {
+                    state.assign(variable);
+                }
+                Ok(())
+            }
+            Node::TupleDecl { names, initializer, .. } => {
+                self.check_node(initializer, scopes, state)?;
+                for name in names.iter().filter(|name| name.as_str() != "_") {
+                    let variable = self.declare(name, &node.location, scopes);
+                    state.assign(variable);
+                }
+                Ok(())
+            }
+            Node::Identifier { name, .. } => {
+                match lookup(name, scopes) {
+                    Some(variable) if !state.assigned.contains(&variable) => Err(self.uninitialized_error(variable, node, state)),
+                    _ => Ok(()),
+                }
+            }
+            Node::Assignment { target, value } => {
+                self.check_node(value, scopes, state)?;
+                match &target.kind {
+                    Node::Identifier { name, .. } => {
+                        if let Some(variable) = lookup(name, scopes) {
+                            state.assign(variable);
+                        }
+                        Ok(())
+                    }
+                    _ => self.check_node(target, scopes, state),
+                }
+            }
+            Node::BinaryExpr { op: op @ (BinaryOp::And | BinaryOp::Or), left, right } => {
+                self.check_node(left, scopes, state)?;
+                // 右辺は評価されない場合があるので、右辺での代入は後に残らない
+                let mut right_state = state.clone();
+                self.check_node(right, scopes, &mut right_state)?;
+                let symbol = if *op == BinaryOp::And { "&&" } else { "||" };
+                state.merge(&right_state, || format!("'{}' の右辺は評価されない場合があります", symbol));
+                Ok(())
+            }
+            Node::IfExpr { condition, then_branch, else_branch } => {
+                self.check_node(condition, scopes, state)?;
+                let mut then_state = state.clone();
+                self.check_scoped(then_branch, scopes, &mut then_state)?;
+                match else_branch {
+                    Some(else_branch) => {
+                        self.check_scoped(else_branch, scopes, state)?;
+                        let line = node.location.line;
+                        state.merge(&then_state, || format!("{}行目の 'if' の一方の分岐で代入されていません", line));
+                    }
+                    None => {
+                        let line = node.location.line;
+                        state.merge(&then_state, || format!("{}行目の 'if' に 'else' がなく、条件が偽の場合に代入されません", line));
+                    }
+                }
+                Ok(())
+            }
+            Node::WhileLoop { condition, body } => {
+                // 2回目以降の反復では代入済みの変数が増えるだけなので、本体は1回の検査で足りる
+                self.check_node(condition, scopes, state)?;
+                let mut body_state = state.clone();
+                self.check_scoped(body, scopes, &mut body_state)?;
+                let line = node.location.line;
+                state.merge(&body_state, || format!("{}行目の 'while' の本体は一度も実行されない場合があります", line));
+                Ok(())
+            }
+            Node::ForLoop { variable, iterable, body } => {
+                // ループ変数は反復のたびに代入され、本体は一度も実行されない場合がある
+                self.check_node(iterable, scopes, state)?;
+                let mut body_state = state.clone();
+                scopes.push(HashMap::new());
+                let variable = self.declare(variable, &node.location, scopes);
+                body_state.assign(variable);
+                let result = self.check_node(body, scopes, &mut body_state);
+                scopes.pop();
+                result?;
+                let line = node.location.line;
+                state.merge(&body_state, || format!("{}行目の 'for' の本体は一度も実行されない場合があります", line));
+                Ok(())
+            }
+            Node::Comprehension { element, variable, iterable, condition } => {
+                // 要素の式と条件は一度も評価されない場合があるので、その中の代入は外に持ち出さない
+                self.check_node(iterable, scopes, state)?;
+                let mut element_state = state.clone();
+                scopes.push(HashMap::new());
+                let variable = self.declare(variable, &node.location, scopes);
+                element_state.assign(variable);
+                let result = condition
+                    .iter()
+                    .chain([element])
+                    .try_for_each(|child| self.check_node(child, scopes, &mut element_state));
+                scopes.pop();
+                result
+            }
+            Node::Defer { expr } => {
+                // 式はブロックを抜けるときに評価するが、読む変数は `defer` の位置で代入済みでなければならない。
+                // 式の中の代入は、ブロックの残りの文からは見えない
+                let mut deferred_state = state.clone();
+                self.check_node(expr, scopes, &mut deferred_state)
+            }
+            Node::TryCatch { body, error_name, handler } => {
+                // 本体は途中で失敗しうるので、`catch` のブロックは本体の前の状態から検査する
+                let mut handler_state = state.clone();
+                self.check_scoped(body, scopes, state)?;
+                scopes.push(HashMap::new());
+                let variable = self.declare(error_name, &node.location, scopes);
+                handler_state.assign(variable);
+                let result = self.check_node(handler, scopes, &mut handler_state);
+                scopes.pop();
+                result?;
+                let line = node.location.line;
+                state.merge(&handler_state, || format!("{}行目の 'try' の本体は途中で失敗する場合があります", line));
+                Ok(())
+            }
+            Node::BlockExpr { .. } => {
+                scopes.push(HashMap::new());
+                let result = self.check_children(node, scopes, state);
+                scopes.pop();
+                result
+            }
+            _ => self.check_children(node, scopes, state),
+        }
+    }
+    
+    fn check_children(&mut self, node: &ASTNode, scopes: &mut Vec<HashMap<String, usize>>, state: &mut State) -> Result<()> {
+        for child in node.children() {
+            self.check_node(child, scopes, state)?;
+        }
+        Ok(())
+    }
+    
+    /// 分岐の本体を独自のスコープで検査（ブロック以外の本体で宣言した変数が外に漏れないように）
+    fn check_scoped(&mut self, node: &ASTNode, scopes: &mut Vec<HashMap<String, usize>>, state: &mut State) -> Result<()> {
+        scopes.push(HashMap::new());
+        let result = self.check_node(node, scopes, state);
+        scopes.pop();
+        result
+    }
+    
+    fn declare(&mut self, name: &str, location: &SourceLocation, scopes: &mut [HashMap<String, usize>]) -> usize {
+        let variable = self.variables.len();
+        self.variables.push(Variable { name: name.to_string(), location: location.clone() });
+        if let Some(scope) = scopes.last_mut() {
+            scope.insert(name.to_string(), variable);
+        }
+        variable
+    }
+    
+    fn uninitialized_error(&self, variable: usize, node: &ASTNode, state: &State) -> EidosError {
+        let declared = &self.variables[variable];
+        let message = match state.reasons.get(&variable) {
+            Some(reason) => format!(
+                "変数 '{}' は代入されていない可能性があります（{}行目で宣言。{}）",
+                declared.name, declared.location.line, reason
+            ),
+            None => format!(
+                "変数 '{}' は代入される前に読み取られています（{}行目で初期化式なしで宣言）",
+                declared.name, declared.location.line
+            ),
+        };
+        EidosError::Type {
+            message,
+            location: node.location.clone(),
+        }
+    }
+}
+
+fn lookup(name: &str, scopes: &[HashMap<String, usize>]) -> Option<usize> {
+    scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::parse_test_source;
+    
+    fn check(source: &str) -> Result<()> {
+        InitChecker::new().check(&parse_test_source(source)?)
+    }
+    
+    #[test]
+    fn test_use_before_assign() {
+        assert!(check("fn f() -> Int { let x: Int; x = 1; x }").is_ok());
+        assert!(check("fn f() -> Int { let x: Int; x }").is_err());
+        assert!(check("fn f() -> Int { var x: Int; x += 1; x }").is_err());
+        
+        // 両方の分岐で代入すれば読み取れる
+        assert!(check("fn f(c: Bool) -> Int { let x: Int; if c { x = 1; } else { x = 2; } x }").is_ok());
+        let error = check("fn f(c: Bool) -> Int { let x: Int; if c { x = 1; } x }").unwrap_err();
+        assert!(error.to_string().contains("'else' がなく"));
+        
+        assert!(check("fn f(c: Bool) -> Bool { let x: Bool; c && { x = true; x } }").is_ok());
+        assert!(check("fn f(c: Bool) -> Bool { let x: Bool; (c && { x = true; x }) || x }").is_err());
+    }
+    
+    #[test]
+    fn test_loop_body_may_not_run() {
+        // `let x: Int; var i = 0; while i < 3 { x = i; } x;` に相当する木を組み立てる
+        let parsed = parse_test_source("let x: Int; var i = 0; i < 3; x = i; x;").unwrap();
+        let [declare_x, declare_i, condition, assign, read]: [ASTNode; 5] = parsed.nodes.try_into().unwrap();
+        let location = condition.location.clone();
+        let body = ASTNode::new(Node::BlockExpr { statements: vec![assign], result: None }, location.clone());
+        let loop_node = ASTNode::new(Node::WhileLoop { condition: Box::new(condition), body: Box::new(body) }, location);
+        
+        let mut program = Program::new("<test>".to_string());
+        for node in [declare_x, declare_i, loop_node.clone()] {
+            program.add_node(node);
+        }
+        assert!(InitChecker::new().check(&program).is_ok());
+        program.add_node(read);
+        let error = InitChecker::new().check(&program).unwrap_err();
+        assert!(error.to_string().contains("'while' の本体は一度も実行されない"));
+    }
+    
+    #[test]
+    fn test_shadowing_and_scopes() {
+        // 内側のブロックで宣言した同名の変数は外側の変数を初期化しない
+        assert!(check("fn f() -> Int { let x: Int; { let x = 1; x; } x }").is_err());
+        assert!(check("fn f(x: Int) -> Int { { let x: Int; x = 2; } x }").is_ok());
+        assert!(check("let x: Int; x = 3; print(x);").is_ok());
+    }
+}
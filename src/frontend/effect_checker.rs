@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::{Result, EidosError};
+use crate::core::ast::{ASTNode, Node, Program};
+use crate::stdlib::{StdlibFunctionType, StdlibRegistry};
+
+/// トップレベルの文をまとめる暗黙のエントリー関数名（常に副作用を持てる）
+const ENTRY_FUNCTION_NAME: &str = "main";
+
+/// 効果（副作用）の検査器
+///
+/// `effect fn` で宣言していない関数は純粋関数とみなし、副作用のある関数を呼び出すとエラーにする。
+/// インライン・アセンブリ（`asm!` / `wasm!`）も副作用とみなす。
+/// トップレベルの文と `main` は副作用のある文脈で実行される。
+///
+/// 非同期関数（`async fn`）の呼び出しは必ず `await` で待ち、`await` は `async fn` の中かトップレベルでだけ使える。
+#[derive(Default)]
+pub struct EffectChecker {
+    /// ユーザー定義関数ごとに、副作用を持てるかどうか
+    functions: HashMap<String, bool>,
+    /// `async fn` で宣言したユーザー定義関数
+    async_functions: HashSet<String>,
+}
+
+impl EffectChecker {
+    pub fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+            async_functions: HashSet::new(),
+        }
+    }
+    
+    /// プログラム全体の効果を検査
+    pub fn check(&mut self, program: &Program) -> Result<()> {
+        StdlibRegistry::initialize()?;
+        
+        // 定義より前の呼び出しも判定できるよう、先にすべての関数を集める
+        for node in &program.nodes {
+            self.collect_functions(node);
+        }
+        // トップレベルの文はエントリー関数の中で `await` できる
+        for node in &program.nodes {
+            self.check_node(node, None, true)?;
+        }
+        Ok(())
+    }
+    
+    fn collect_functions(&mut self, node: &ASTNode) {
+        if let Node::FunctionDef { name, is_effectful, is_async, .. } = &node.kind {
+            self.functions.insert(name.clone(), *is_effectful || name == ENTRY_FUNCTION_NAME);
+            if *is_async {
+                self.async_functions.insert(name.clone());
+            }
+        }
+        for child in node.children() {
+            self.collect_functions(child);
+        }
+    }
+    
+    /// `pure_function` は検査中の純粋関数の名前（副作用のある文脈では `None`）、
+    /// `in_async` は `await` を使える文脈かどうか
+    fn check_node(&self, node: &ASTNode, pure_function: Option<&str>, in_async: bool) -> Result<()> {
+        match &node.kind {
+            Node::FunctionDef { name, body, is_async, .. } => {
+                let context = if self.is_effectful(name) { None } else { Some(name.as_str()) };
+                return self.check_node(body, context, *is_async);
+            }
+            Node::Await { expr } => {
+                if !in_async {
+                    return Err(EidosError::Type {
+                        message: "await は async fn の中かトップレベルでのみ使えます".to_string(),
+                        location: node.location.clone(),
+                    });
+                }
+                let awaited = match &expr.kind {
+                    Node::FunctionCall { callee, .. } => match &callee.kind {
+                        Node::Identifier { name, .. } if self.async_functions.contains(name.as_str()) => Some(name),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let Some(name) = awaited else {
+                    return Err(EidosError::Type {
+                        message: "await できるのは async fn の呼び出しだけです".to_string(),
+                        location: expr.location.clone(),
+                    });
+                };
+                self.check_call(expr, name, pure_function)?;
+                // 呼び出しそのものは待っているので、引数だけを検査する
+                for child in expr.children() {
+                    self.check_node(child, pure_function, in_async)?;
+                }
+                return Ok(());
+            }
+            Node::FunctionCall { callee, .. } => {
+                if let Node::Identifier { name, .. } = &callee.kind {
+                    if self.async_functions.contains(name.as_str()) {
+                        return Err(EidosError::Type {
+                            message: format!("非同期関数 '{}' の呼び出しには await が必要です（'await {}(...)'）", name, name),
+                            location: node.location.clone(),
+                        });
+                    }
+                    self.check_call(node, name, pure_function)?;
+                }
+            }
+            Node::InlineAsm { dialect, .. } => {
+                if let Some(caller) = pure_function {
+                    return Err(EidosError::Type {
+                        message: format!(
+                            "純粋関数 '{}' では {}! は使えません（'effect fn {}' として宣言してください）",
+                            caller,
+                            dialect.keyword(),
+                            caller
+                        ),
+                        location: node.location.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+        
+        for child in node.children() {
+            self.check_node(child, pure_function, in_async)?;
+        }
+        Ok(())
+    }
+    
+    /// 純粋関数から副作用のある関数を呼び出していないか検査
+    fn check_call(&self, call: &ASTNode, name: &str, pure_function: Option<&str>) -> Result<()> {
+        if let Some(caller) = pure_function {
+            if self.is_effectful(name) {
+                return Err(EidosError::Type {
+                    message: format!(
+                        "純粋関数 '{}' から副作用のある関数 '{}' は呼び出せません（'effect fn {}' として宣言してください）",
+                        caller, name, caller
+                    ),
+                    location: call.location.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// 名前で呼び出される関数が副作用を持ちうるかどうか（未定義の名前は意味解析で報告する）
+    fn is_effectful(&self, name: &str) -> bool {
+        if let Some(is_effectful) = self.functions.get(name) {
+            return *is_effectful;
+        }
+        let registry = StdlibRegistry::global();
+        let registry = registry.read().unwrap();
+        registry.function_type(name) == Some(StdlibFunctionType::Effectful)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::parse_test_source;
+    
+    fn check(source: &str) -> Result<()> {
+        EffectChecker::new().check(&parse_test_source(source)?)
+    }
+    
+    #[test]
+    fn test_pure_function_cannot_call_effectful() {
+        assert!(check("fn square(x: Int) -> Int { math::abs(x) * x }").is_ok());
+        assert!(check("fn now() -> Int { time::timestamp() }").is_err());
+        assert!(check("effect fn now() -> Int { time::timestamp() }").is_ok());
+        
+        // 純粋関数から `effect fn` を呼ぶこともできない（定義の順序によらない）
+        assert!(check("fn f() -> Int { g() } effect fn g() -> Int { time::timestamp() }").is_err());
+        assert!(check("fn f() -> Int { g() } fn g() -> Int { 1 }").is_ok());
+    }
+    
+    #[test]
+    fn test_inline_asm_is_effectful() {
+        assert!(check("effect fn count(x: Int) -> Int { asm!(\"popcnt {0}, {1}\", out(Int), in(x)) }").is_ok());
+        let error = check("fn count(x: Int) -> Int { asm!(\"popcnt {0}, {1}\", out(Int), in(x)) }").unwrap_err();
+        assert!(error.to_string().contains("純粋関数 'count' では asm! は使えません"), "{}", error);
+    }
+    
+    #[test]
+    fn test_entry_is_effectful() {
+        assert!(check("time::timestamp();").is_ok());
+        assert!(check("fn main() { time::timestamp(); }").is_ok());
+    }
+    
+    #[test]
+    fn test_await_async_functions() {
+        let fetch = "async fn fetch(x: Int) -> Int { x + 1 } ";
+        assert!(check(&format!("{}async fn twice(x: Int) -> Int {{ await fetch(await fetch(x)) }}", fetch)).is_ok());
+        assert!(check(&format!("{}await fetch(1);", fetch)).is_ok());
+        
+        let error = check(&format!("{}fn f() -> Int {{ await fetch(1) }}", fetch)).unwrap_err();
+        assert!(error.to_string().contains("async fn の中かトップレベルでのみ"), "{}", error);
+        let error = check(&format!("{}async fn f() -> Int {{ fetch(1) }}", fetch)).unwrap_err();
+        assert!(error.to_string().contains("await が必要です"), "{}", error);
+        let error = check("fn g() -> Int { 1 } async fn f() -> Int { await g() }").unwrap_err();
+        assert!(error.to_string().contains("async fn の呼び出しだけ"), "{}", error);
+        
+        // 非同期であることと副作用の有無は独立している
+        let tick = "effect fn tick() -> Int { 1 } ";
+        assert!(check(&format!("{}async fn now() -> Int {{ tick() }}", tick)).is_err());
+        assert!(check(&format!("{}async effect fn now() -> Int {{ tick() }}", tick)).is_ok());
+    }
+}
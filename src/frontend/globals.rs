@@ -0,0 +1,235 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::{Result, EidosError};
+use crate::core::ast::{ASTNode, GlobalKind, Node, Program};
+
+/// トップレベルの文をまとめる暗黙のエントリー関数名（常に副作用を持てる）
+const ENTRY_FUNCTION_NAME: &str = "main";
+
+/// 検査中の文脈
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    /// 検査中の純粋関数の名前（副作用のある文脈では `None`）
+    pure_function: Option<&'a str>,
+    /// `unsafe` ブロックの中かどうか
+    in_unsafe: bool,
+}
+
+/// グローバル変数の検査器
+///
+/// グローバル変数はトップレベルでのみ宣言でき、`const` と `static` の変数には代入できない。
+/// `static mut` の変数は `unsafe` ブロックの中でのみ読み書きでき、読み書きは副作用とみなす。
+#[derive(Default)]
+pub struct GlobalChecker {
+    /// グローバル変数ごとの種類
+    globals: HashMap<String, GlobalKind>,
+}
+
+impl GlobalChecker {
+    pub fn new() -> Self {
+        Self {
+            globals: HashMap::new(),
+        }
+    }
+    
+    /// プログラム全体のグローバル変数の宣言と使用を検査
+    pub fn check(&mut self, program: &Program) -> Result<()> {
+        let functions: HashSet<&str> = program
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.kind {
+                Node::FunctionDef { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        
+        // 宣言より前の関数からも参照できるよう、先にすべてのグローバル変数を集める
+        for node in &program.nodes {
+            if let Node::GlobalDecl { name, kind, .. } = &node.kind {
+                if self.globals.contains_key(name) || functions.contains(name.as_str()) {
+                    return Err(type_error(format!("'{}' は既に定義されています", name), node));
+                }
+                self.globals.insert(name.clone(), *kind);
+            }
+        }
+        
+        let context = Context { pure_function: None, in_unsafe: false };
+        let mut scopes = vec![HashSet::new()];
+        for node in &program.nodes {
+            match &node.kind {
+                Node::GlobalDecl { initializer, .. } => self.check_node(initializer, context, &mut scopes)?,
+                _ => self.check_node(node, context, &mut scopes)?,
+            }
+        }
+        Ok(())
+    }
+    
+    /// `scopes` はローカル変数のスコープ（同名のローカル変数はグローバル変数より優先する）
+    fn check_node(&self, node: &ASTNode, context: Context, scopes: &mut Vec<HashSet<String>>) -> Result<()> {
+        match &node.kind {
+            Node::GlobalDecl { .. } => {
+                return Err(type_error("グローバル変数はトップレベルでのみ宣言できます".to_string(), node));
+            }
+            Node::FunctionDef { name, params, body, is_effectful, .. } => {
+                let effectful = *is_effectful || name == ENTRY_FUNCTION_NAME;
+                let context = Context {
+                    pure_function: if effectful { None } else { Some(name.as_str()) },
+                    in_unsafe: false,
+                };
+                // 関数本体からはトップレベルのローカル変数は見えない
+                let mut scopes = vec![params.iter().map(|param| param.name.clone()).collect()];
+                return self.check_node(body, context, &mut scopes);
+            }
+            Node::UnsafeBlock { body } => {
+                return self.check_node(body, Context { in_unsafe: true, ..context }, scopes);
+            }
+            Node::Identifier { name, .. } => {
+                if let Some(kind) = self.global(name, scopes) {
+                    self.check_read_write(name, kind, node, context)?;
+                }
+                return Ok(());
+            }
+            Node::Assignment { target, .. } | Node::CompoundAssignment { target, .. } => {
+                if let Node::Identifier { name, .. } = &target.kind {
+                    match self.global(name, scopes) {
+                        Some(GlobalKind::StaticMut) | None => {}
+                        Some(kind) => {
+                            return Err(type_error(
+                                format!(
+                                    "{} の変数 '{}' には代入できません（変更するには `static mut` として宣言してください）",
+                                    kind.keyword(),
+                                    name
+                                ),
+                                node,
+                            ));
+                        }
+                    }
+                }
+            }
+            Node::TryCatch { body, error_name, handler } => {
+                self.check_node(body, context, scopes)?;
+                scopes.push(HashSet::from([error_name.clone()]));
+                let result = self.check_node(handler, context, scopes);
+                scopes.pop();
+                return result;
+            }
+            Node::ForLoop { variable, iterable, body } => {
+                self.check_node(iterable, context, scopes)?;
+                scopes.push(HashSet::from([variable.clone()]));
+                let result = self.check_node(body, context, scopes);
+                scopes.pop();
+                return result;
+            }
+            Node::Comprehension { element, variable, iterable, condition } => {
+                self.check_node(iterable, context, scopes)?;
+                scopes.push(HashSet::from([variable.clone()]));
+                let result = condition.iter().chain([element]).try_for_each(|child| self.check_node(child, context, scopes));
+                scopes.pop();
+                return result;
+            }
+            Node::VarDecl { name, initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.check_node(initializer, context, scopes)?;
+                }
+                if let Some(scope) = scopes.last_mut() {
+                    scope.insert(name.clone());
+                }
+                return Ok(());
+            }
+            Node::TupleDecl { names, initializer, .. } => {
+                self.check_node(initializer, context, scopes)?;
+                if let Some(scope) = scopes.last_mut() {
+                    scope.extend(names.iter().cloned());
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        
+        let is_block = matches!(node.kind, Node::BlockExpr { .. });
+        if is_block {
+            scopes.push(HashSet::new());
+        }
+        let mut result = Ok(());
+        for child in node.children() {
+            result = self.check_node(child, context, scopes);
+            if result.is_err() {
+                break;
+            }
+        }
+        if is_block {
+            scopes.pop();
+        }
+        result
+    }
+    
+    /// ローカル変数に隠されていなければ、名前が指すグローバル変数の種類を返す
+    fn global(&self, name: &str, scopes: &[HashSet<String>]) -> Option<GlobalKind> {
+        if scopes.iter().any(|scope| scope.contains(name)) {
+            return None;
+        }
+        self.globals.get(name).copied()
+    }
+    
+    /// `static mut` の変数を読み書きできる文脈かどうかを検査
+    fn check_read_write(&self, name: &str, kind: GlobalKind, node: &ASTNode, context: Context) -> Result<()> {
+        if kind != GlobalKind::StaticMut {
+            return Ok(());
+        }
+        if !context.in_unsafe {
+            return Err(type_error(
+                format!("`static mut` の変数 '{}' は `unsafe {{ ... }}` の中でのみ読み書きできます", name),
+                node,
+            ));
+        }
+        if let Some(function) = context.pure_function {
+            return Err(type_error(
+                format!(
+                    "純粋関数 '{}' から `static mut` の変数 '{}' は読み書きできません（'effect fn {}' として宣言してください）",
+                    function, name, function
+                ),
+                node,
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn type_error(message: String, node: &ASTNode) -> EidosError {
+    EidosError::Type {
+        message,
+        location: node.location.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::parse_test_source;
+    
+    fn check(source: &str) -> Result<()> {
+        GlobalChecker::new().check(&parse_test_source(source)?)
+    }
+    
+    #[test]
+    fn test_static_mut_requires_unsafe() {
+        assert!(check("static mut COUNTER: Int = 0; effect fn tick() { unsafe { COUNTER += 1; } }").is_ok());
+        assert!(check("static mut COUNTER: Int = 0; effect fn get() -> Int { COUNTER }").is_err());
+        
+        // 読み書きは副作用なので、純粋関数からはできない
+        let error = check("static mut COUNTER: Int = 0; fn get() -> Int { unsafe { COUNTER } }").unwrap_err();
+        assert!(error.to_string().contains("純粋関数 'get'"));
+        
+        // 同名のローカル変数は `unsafe` なしで使える
+        assert!(check("static mut COUNTER: Int = 0; fn f(COUNTER: Int) -> Int { COUNTER + 1 }").is_ok());
+    }
+    
+    #[test]
+    fn test_immutable_globals() {
+        assert!(check("const LIMIT: Int = 10; fn f() -> Int { LIMIT * 2 }").is_ok());
+        assert!(check("const LIMIT: Int = 10; fn main() { LIMIT = 11; }").is_err());
+        assert!(check("static NAME: String = \"eidos\"; fn main() { NAME += \"!\"; }").is_err());
+        assert!(check("const A: Int = 1; static A: Int = 2;").is_err());
+        assert!(check("fn main() { const LOCAL: Int = 1; }").is_err());
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use rayon::prelude::*;
+
+use crate::core::EidosError;
+use crate::core::ast::{self, ASTNode, Mutator, Node, Program, Visitor};
+use crate::core::ast_dump::AstDumper;
+
+/// トップレベルの項目ごとに解析結果を記録し、編集の影響を受けた項目だけを解析し直す
+///
+/// 項目（関数・グローバル変数・型などのトップレベルのノード）の結果は、次のどれかが変わるまで使い回す。
+///
+/// - 項目の内容（位置は項目の先頭からの相対で比べるので、項目が移動しただけなら変わらない）
+/// - 項目が参照する名前の項目のシグネチャ（関数なら引数と戻り値の型。`const fn` とそれ以外の項目は内容全体）
+/// - 型・DSLの宣言（すべての項目が依存するとみなす）
+///
+/// 関数の本体だけを編集した場合はその関数だけを解析し直し、シグネチャを変えた場合は呼び出し側も解析し直す。
+/// 使い回した結果のエラーの行は、項目が移動した分だけずらす。`--watch` と言語サーバー向け。
+/// 解析し直す項目どうしは互いに独立しているので、rayonのスレッドプールで並列に解析する。
+///
+/// ```ignore
+/// let mut analysis = IncrementalAnalysis::new();
+/// let update = analysis.update(&program, |slice| TypeChecker::new().check_program(slice).err().into_iter().collect());
+/// let errors = analysis.errors();
+/// ```
+#[derive(Debug, Default)]
+pub struct IncrementalAnalysis {
+    memos: HashMap<String, Memo>,
+    /// 直前の `update` での項目の順序
+    order: Vec<String>,
+}
+
+/// `IncrementalAnalysis::update` の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Update {
+    /// 解析し直した項目の名前（プログラムの順）
+    pub analyzed: Vec<String>,
+    /// 前回の結果を使い回した項目の数
+    pub reused: usize,
+}
+
+/// 1つの項目の解析結果
+#[derive(Debug)]
+struct Memo {
+    /// 項目の内容の指紋
+    content: u64,
+    /// 型・DSLの宣言の指紋
+    environment: u64,
+    /// 参照する名前と、解析した時点のその名前の項目のシグネチャ（項目がなければ `None`）
+    dependencies: Vec<(String, Option<u64>)>,
+    /// 解析した時点の項目の先頭行（`errors` の行の基準）
+    line: usize,
+    errors: Vec<EidosError>,
+}
+
+/// `update` の中で使う、項目ごとの情報
+struct Item<'a> {
+    name: String,
+    node: &'a ASTNode,
+    content: u64,
+    /// 項目が参照する名前（ローカル変数の名前も含む）
+    references: BTreeSet<String>,
+}
+
+impl IncrementalAnalysis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// プログラムの各項目を、前回から影響を受けたものだけ `analyze` で解析し直す
+    ///
+    /// `analyze` には、型・DSLの宣言と、解析する項目と、その項目から（間接的にも）参照される項目だけを
+    /// 含むプログラムを渡す。返されたエラーのうち、位置が解析する項目の範囲の外にあるものは捨てる
+    /// （参照先の項目のエラーは、その項目を解析したときに記録する）。位置のないエラーは項目のエラーとする。
+    /// プログラムからなくなった項目の結果は捨てる。
+    ///
+    /// `analyze` は複数のスレッドから同時に呼ばれる。エラーは解析が終わった順ではなく項目の順に記録する。
+    pub fn update<F>(&mut self, program: &Program, analyze: F) -> Update
+    where
+        F: Fn(&Program) -> Vec<EidosError> + Sync,
+    {
+        let items = items(program);
+        let signatures: HashMap<&str, u64> = items.iter().map(|item| (item.name.as_str(), signature(item))).collect();
+        let environment = fingerprint(
+            &items
+                .iter()
+                .filter(|item| is_declaration(item.node))
+                .map(|item| item.content)
+                .collect::<Vec<_>>(),
+        );
+        
+        // 前回の結果を使える項目と、解析し直す項目に分ける
+        let mut update = Update::default();
+        let mut memos = HashMap::new();
+        let mut pending = Vec::new();
+        for item in &items {
+            let dependencies: Vec<(String, Option<u64>)> = item
+                .references
+                .iter()
+                .map(|name| (name.clone(), signatures.get(name.as_str()).copied()))
+                .collect();
+            let line = item.node.location.line;
+            
+            match self.memos.remove(&item.name) {
+                Some(mut memo) if memo.content == item.content && memo.environment == environment && memo.dependencies == dependencies => {
+                    let delta = line as isize - memo.line as isize;
+                    if delta != 0 {
+                        for error in &mut memo.errors {
+                            shift_error(error, &item.node.location.file, delta);
+                        }
+                        memo.line = line;
+                    }
+                    update.reused += 1;
+                    memos.insert(item.name.clone(), memo);
+                }
+                _ => pending.push((item, dependencies)),
+            }
+        }
+        
+        // 解析し直す項目を並列に解析する（`collect` は項目の順を保つ）
+        let analyzed: Vec<(String, Memo)> = pending
+            .into_par_iter()
+            .map(|(item, dependencies)| {
+                let (first, last) = line_span(item.node);
+                let errors = analyze(&slice(program, &items, item))
+                    .into_iter()
+                    .filter(|error| match error_position(error) {
+                        Some((file, line)) => file == item.node.location.file.as_path() && (first..=last).contains(&line),
+                        None => true,
+                    })
+                    .collect();
+                let memo = Memo { content: item.content, environment, dependencies, line: item.node.location.line, errors };
+                (item.name.clone(), memo)
+            })
+            .collect();
+        for (name, memo) in analyzed {
+            update.analyzed.push(name.clone());
+            memos.insert(name, memo);
+        }
+        
+        self.memos = memos;
+        self.order = items.into_iter().map(|item| item.name).collect();
+        update
+    }
+    
+    /// 直前の `update` で記録したすべてのエラー（項目の順。位置のない同じエラーは1つにまとめる）
+    pub fn errors(&self) -> Vec<EidosError> {
+        let mut unlocated = HashSet::new();
+        self.order
+            .iter()
+            .filter_map(|name| self.memos.get(name))
+            .flat_map(|memo| &memo.errors)
+            .filter(|error| error_position(error).is_some() || unlocated.insert(error.to_string()))
+            .cloned()
+            .collect()
+    }
+    
+    /// 記録した結果をすべて捨てる（次の `update` ではすべての項目を解析し直す）
+    pub fn clear(&mut self) {
+        self.memos.clear();
+        self.order.clear();
+    }
+}
+
+/// 前回の結果を使わずに、プログラムのすべての項目を並列に解析し、エラーを項目の順に返す
+///
+/// 項目の分け方と `analyze` に渡すプログラムは `IncrementalAnalysis::update` と同じ。
+pub fn analyze_all<F>(program: &Program, analyze: F) -> Vec<EidosError>
+where
+    F: Fn(&Program) -> Vec<EidosError> + Sync,
+{
+    let mut analysis = IncrementalAnalysis::new();
+    analysis.update(program, analyze);
+    analysis.errors()
+}
+
+/// プログラムのトップレベルの項目（名前のないノードは `#位置`、重複した名前は `名前#番号` で区別する）
+fn items(program: &Program) -> Vec<Item<'_>> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    program
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            let base = item_name(node).map(str::to_string).unwrap_or_else(|| format!("#{}", index));
+            let count = seen.entry(base.clone()).or_insert(0);
+            *count += 1;
+            let name = if *count == 1 { base } else { format!("{}#{}", base, count) };
+            
+            let mut references = References(BTreeSet::new());
+            references.visit_node(node);
+            if let Some(own) = item_name(node) {
+                references.0.remove(own);
+            }
+            Item { name, node, content: content_fingerprint(node), references: references.0 }
+        })
+        .collect()
+}
+
+/// ほかの項目から名前で参照される項目の名前
+fn item_name(node: &ASTNode) -> Option<&str> {
+    match &node.kind {
+        Node::FunctionDef { name, .. }
+        | Node::GlobalDecl { name, .. }
+        | Node::TypeDef { name, .. }
+        | Node::ModuleDef { name, .. }
+        | Node::SyntaxDef { name, .. }
+        | Node::PegDef { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+/// すべての項目の解析に影響する宣言（型とDSL）か
+fn is_declaration(node: &ASTNode) -> bool {
+    matches!(
+        node.kind,
+        Node::TypeDef { .. } | Node::TypeProvider { .. } | Node::SyntaxDef { .. } | Node::PegDef { .. } | Node::UseDsl { .. }
+    )
+}
+
+/// 項目を参照する側から見た指紋（関数は型だけ。`const fn` は呼び出しが評価されるので本体も含む）
+fn signature(item: &Item) -> u64 {
+    match &item.node.kind {
+        Node::FunctionDef { name, params, return_type, is_effectful, is_const: false, is_async, attributes, is_public, .. } => {
+            let params: Vec<String> = params
+                .iter()
+                .map(|param| format!("{}: {}", param.name, param.param_type.as_ref().map_or("_".to_string(), ToString::to_string)))
+                .collect();
+            let return_type = return_type.as_ref().map_or("_".to_string(), ToString::to_string);
+            fingerprint(&(name, params, return_type, is_effectful, is_async, attributes, is_public))
+        }
+        _ => item.content,
+    }
+}
+
+/// 位置を項目の先頭からの相対にした、項目の内容の指紋
+fn content_fingerprint(node: &ASTNode) -> u64 {
+    let mut relative = node.clone();
+    Relocate { file: node.location.file.clone(), first: node.location.line }.mutate_node(&mut relative);
+    fingerprint(&AstDumper::new().node_to_sexp(&relative))
+}
+
+fn fingerprint<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 型・DSLの宣言と、`item` と、`item` から間接的にも参照される項目だけを含むプログラム
+///
+/// 解析する項目を参照先より前に置き、参照先のエラーで解析が止まっても項目のエラーが見つかるようにする。
+fn slice(program: &Program, items: &[Item], item: &Item) -> Program {
+    let by_name: HashMap<&str, &Item> = items
+        .iter()
+        .filter_map(|item| item_name(item.node).map(|name| (name, item)))
+        .collect();
+    let mut reachable = HashSet::new();
+    let mut pending: Vec<&Item> = vec![item];
+    while let Some(current) = pending.pop() {
+        for name in &current.references {
+            if let Some(&dependency) = by_name.get(name.as_str()) {
+                if reachable.insert(dependency.name.as_str()) {
+                    pending.push(dependency);
+                }
+            }
+        }
+    }
+    
+    let mut slice = Program::new(program.file_path.clone());
+    for declaration in items.iter().filter(|declaration| is_declaration(declaration.node) && declaration.name != item.name) {
+        slice.add_node(declaration.node.clone());
+    }
+    slice.add_node(item.node.clone());
+    for dependency in items.iter().filter(|dependency| {
+        reachable.contains(dependency.name.as_str()) && dependency.name != item.name && !is_declaration(dependency.node)
+    }) {
+        slice.add_node(dependency.node.clone());
+    }
+    slice
+}
+
+/// 項目の中で、項目と同じファイルにあるノードの最初と最後の行
+fn line_span(node: &ASTNode) -> (usize, usize) {
+    let mut span = LineSpan { file: &node.location.file, first: node.location.line, last: node.location.line };
+    span.visit_node(node);
+    (span.first, span.last)
+}
+
+/// エラーの位置（ファイルと行。位置のないエラーは `None`）
+fn error_position(error: &EidosError) -> Option<(&Path, usize)> {
+    let (file, line) = match error {
+        EidosError::Lexer { file, line, .. } | EidosError::Parser { file, line, .. } | EidosError::Semantic { file, line, .. } => (file, *line),
+        EidosError::Type { location, .. } => (&location.file, location.line),
+        _ => return None,
+    };
+    (line != 0).then_some((file.as_path(), line))
+}
+
+/// `file` の中の位置を持つエラーの行を `delta` だけずらす
+fn shift_error(error: &mut EidosError, target: &Path, delta: isize) {
+    let (file, line) = match error {
+        EidosError::Lexer { file, line, .. } | EidosError::Parser { file, line, .. } | EidosError::Semantic { file, line, .. } => (file, line),
+        EidosError::Type { location, .. } => (&mut location.file, &mut location.line),
+        EidosError::MultipleErrors(errors) => {
+            for error in errors {
+                shift_error(error, target, delta);
+            }
+            return;
+        }
+        _ => return,
+    };
+    if *line != 0 && file.as_path() == target {
+        *line = line.saturating_add_signed(delta);
+    }
+}
+
+/// 項目が参照する名前を集める
+struct References(BTreeSet<String>);
+
+impl Visitor for References {
+    fn visit_identifier(&mut self, _node: &ASTNode, name: &str) {
+        self.0.insert(name.to_string());
+    }
+}
+
+/// 項目と同じファイルのノードの行を、項目の先頭からの相対にする
+struct Relocate {
+    file: PathBuf,
+    first: usize,
+}
+
+impl Mutator for Relocate {
+    fn rewrite(&mut self, node: &mut ASTNode) {
+        if node.location.file == self.file {
+            node.location.line = node.location.line.saturating_sub(self.first);
+        }
+    }
+}
+
+/// 項目と同じファイルのノードの行の範囲を求める
+struct LineSpan<'a> {
+    file: &'a Path,
+    first: usize,
+    last: usize,
+}
+
+impl Visitor for LineSpan<'_> {
+    fn visit_node(&mut self, node: &ASTNode) {
+        if node.location.file.as_path() == self.file && node.location.line != 0 {
+            self.first = self.first.min(node.location.line);
+            self.last = self.last.max(node.location.line);
+        }
+        ast::walk(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::parse_test_source;
+    
+    fn parse(source: &str) -> Program {
+        parse_test_source(source).unwrap()
+    }
+    
+    /// `bad` を参照するたびに型エラーを返す解析
+    fn find_bad(program: &Program) -> Vec<EidosError> {
+        struct Bad(Vec<EidosError>);
+        
+        impl Visitor for Bad {
+            fn visit_identifier(&mut self, node: &ASTNode, name: &str) {
+                if name == "bad" {
+                    self.0.push(EidosError::Type { message: "bad".to_string(), location: node.location.clone() });
+                }
+            }
+        }
+        
+        let mut bad = Bad(Vec::new());
+        bad.visit_program(program);
+        bad.0
+    }
+    
+    fn lines(errors: &[EidosError]) -> Vec<usize> {
+        errors.iter().filter_map(|error| error_position(error).map(|(_, line)| line)).collect()
+    }
+    
+    #[test]
+    fn test_reanalyzes_only_affected_items() {
+        let helper = "fn helper(x: Int) -> Int {\n    x + 1\n}\n";
+        let rest = "fn caller() -> Int {\n    helper(2)\n}\nfn other() -> Int {\n    bad\n}\n";
+        let mut analysis = IncrementalAnalysis::new();
+        
+        let update = analysis.update(&parse(&format!("{}{}", helper, rest)), find_bad);
+        assert_eq!(update.analyzed, ["helper", "caller", "other"]);
+        assert_eq!(lines(&analysis.errors()), [8]);
+        
+        // 本体だけの編集は呼び出し側に影響しない
+        let update = analysis.update(&parse(&format!("{}{}", helper.replace("x + 1", "x + 2"), rest)), find_bad);
+        assert_eq!(update, Update { analyzed: vec!["helper".to_string()], reused: 2 });
+        
+        // シグネチャを変えると呼び出し側も解析し直す
+        let changed = helper.replace("(x: Int)", "(x: Int, y: Int)");
+        let update = analysis.update(&parse(&format!("{}{}", changed, rest)), find_bad);
+        assert_eq!(update.analyzed, ["helper", "caller"]);
+        
+        // 前に項目を追加すると、使い回したエラーの行がずれる
+        let update = analysis.update(&parse(&format!("fn first() -> Int {{\n    0\n}}\n{}{}", changed, rest)), find_bad);
+        assert_eq!(update.analyzed, ["first"]);
+        assert_eq!(lines(&analysis.errors()), [11]);
+        
+        // 参照していた名前の項目が新しくできた場合も解析し直す
+        let update = analysis.update(&parse(&format!("{}{}const bad: Int = 1;\n", changed, rest)), find_bad);
+        assert_eq!(update.analyzed, ["other", "bad"]);
+        assert_eq!(lines(&analysis.errors()), [8]);
+    }
+    
+    #[test]
+    fn test_analyze_all_orders_errors_by_item() {
+        let source: String = (0..32).map(|index| format!("fn f{}() -> Int {{\n    bad\n}}\n", index)).collect();
+        let program = parse(&source);
+        let errors = analyze_all(&program, |slice| {
+            // 後の項目ほど早く終わるようにして、終わった順に並ばないことを確かめる
+            let line = slice.nodes[0].location.line;
+            std::thread::sleep(std::time::Duration::from_micros(3200 - line as u64 * 32));
+            find_bad(slice)
+        });
+        assert_eq!(lines(&errors), (0..32).map(|index| index * 3 + 2).collect::<Vec<_>>());
+    }
+}
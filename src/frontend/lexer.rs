@@ -1,8 +1,30 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::io::{self, BufRead};
+use std::ops::Range;
 use std::path::PathBuf;
 use std::str::Chars;
 
-use crate::core::{EidosError, Result, SourceLocation};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::core::{EidosError, Name, Result, SourceLocation, Span};
+use crate::core::ast::KeywordAlias;
+use crate::core::bigint::BigInt;
+use crate::core::source_map::SourceFile;
+use crate::core::types::NumericType;
+
+/// 字句解析で予約語になる単語（識別子には使えない）
+pub const KEYWORDS: &[&str] = &[
+    "let", "var", "fn", "return", "if", "else", "while", "for", "in", "break", "continue", "type", "struct",
+    "enum", "import", "export", "unsafe", "as", "mut", "true", "false", "syntax", "macro", "peg",
+];
+
+/// ソースコードに書けない、双方向テキストの制御文字（ALM、LRM・RLM、LRE〜RLO、LRI〜PDI）
+pub const BIDI_CONTROLS: &[char] = &[
+    '\u{061C}', '\u{200E}', '\u{200F}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}', '\u{2066}',
+    '\u{2067}', '\u{2068}', '\u{2069}',
+];
 
 /// トークンの種類
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -10,12 +32,18 @@ pub enum TokenKind {
     // リテラル
     Integer(i64),
     Float(f64),
+    /// 型の接尾辞付きの整数リテラル（`42i32`。`u64` の値は `i64` のビット列として持つ）
+    SizedInteger(i64, NumericType),
+    /// 型の接尾辞付きの浮動小数点数リテラル（`3.5f32`）
+    SizedFloat(f64, NumericType),
+    /// 接尾辞がなく `i64` の範囲を超える整数リテラル（`BigInt` 型になる）
+    BigInteger(BigInt),
     String(String),
     Character(char),
     Boolean(bool),
     
-    // 識別子
-    Identifier(String),
+    // 識別子（インターンした名前）
+    Identifier(Name),
     
     // キーワード
     Let,
@@ -39,6 +67,9 @@ pub enum TokenKind {
     Mut,
     True,
     False,
+    Syntax,
+    Macro,
+    Peg,
     
     // 区切り文字
     LeftParen,     // (
@@ -51,7 +82,12 @@ pub enum TokenKind {
     Colon,         // :
     Comma,         // ,
     Dot,           // .
+    DotDot,        // ..
     Arrow,         // ->
+    FatArrow,      // =>
+    Dollar,        // $
+    Hash,          // #
+    Question,      // ?
     
     // 演算子
     Plus,          // +
@@ -74,10 +110,19 @@ pub enum TokenKind {
     PipePipe,      // ||
     LessLess,      // <<
     GreaterGreater, // >>
+    PlusEqual,     // +=
+    MinusEqual,    // -=
+    StarEqual,     // *=
     
     // DSL関連
     DSLStart(String), // `@dsl_name {`
     DSLEnd,           // `}`
+    RawBlock(String), // `peg name { ... }` の本体
+    
+    // レイアウト（`offside_rule` がインデントから補う）
+    Newline,          // 論理行の終わり
+    Indent,           // インデントが深くなる位置
+    Dedent,           // インデントが浅くなる位置
     
     // その他
     Eof,
@@ -90,6 +135,10 @@ impl fmt::Display for TokenKind {
             // リテラル
             TokenKind::Integer(val) => write!(f, "{}", val),
             TokenKind::Float(val) => write!(f, "{}", val),
+            TokenKind::SizedInteger(val, ty) if ty.is_signed() => write!(f, "{}{}", val, ty),
+            TokenKind::SizedInteger(val, ty) => write!(f, "{}{}", *val as u64, ty),
+            TokenKind::SizedFloat(val, ty) => write!(f, "{}{}", val, ty),
+            TokenKind::BigInteger(val) => write!(f, "{}", val),
             TokenKind::String(val) => write!(f, "\"{}\"", val),
             TokenKind::Character(val) => write!(f, "'{}'", val),
             TokenKind::Boolean(val) => write!(f, "{}", val),
@@ -119,6 +168,9 @@ impl fmt::Display for TokenKind {
             TokenKind::Mut => write!(f, "mut"),
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
+            TokenKind::Syntax => write!(f, "syntax"),
+            TokenKind::Macro => write!(f, "macro"),
+            TokenKind::Peg => write!(f, "peg"),
             
             // 区切り文字
             TokenKind::LeftParen => write!(f, "("),
@@ -131,7 +183,12 @@ impl fmt::Display for TokenKind {
             TokenKind::Colon => write!(f, ":"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::Dot => write!(f, "."),
+            TokenKind::DotDot => write!(f, ".."),
             TokenKind::Arrow => write!(f, "->"),
+            TokenKind::FatArrow => write!(f, "=>"),
+            TokenKind::Dollar => write!(f, "$"),
+            TokenKind::Hash => write!(f, "#"),
+            TokenKind::Question => write!(f, "?"),
             
             // 演算子
             TokenKind::Plus => write!(f, "+"),
@@ -154,10 +211,19 @@ impl fmt::Display for TokenKind {
             TokenKind::PipePipe => write!(f, "||"),
             TokenKind::LessLess => write!(f, "<<"),
             TokenKind::GreaterGreater => write!(f, ">>"),
+            TokenKind::PlusEqual => write!(f, "+="),
+            TokenKind::MinusEqual => write!(f, "-="),
+            TokenKind::StarEqual => write!(f, "*="),
             
             // DSL関連
             TokenKind::DSLStart(name) => write!(f, "@{} {{", name),
             TokenKind::DSLEnd => write!(f, "}}"),
+            TokenKind::RawBlock(text) => write!(f, "{{{}}}", text),
+            
+            // レイアウト
+            TokenKind::Newline => write!(f, "NEWLINE"),
+            TokenKind::Indent => write!(f, "INDENT"),
+            TokenKind::Dedent => write!(f, "DEDENT"),
             
             // その他
             TokenKind::Eof => write!(f, "EOF"),
@@ -166,44 +232,260 @@ impl fmt::Display for TokenKind {
     }
 }
 
+impl TokenKind {
+    /// 演算子のトークンか（`+`・`&&`・`->` など。括弧と区切り文字は含まない）
+    pub fn is_operator(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Arrow
+                | TokenKind::FatArrow
+                | TokenKind::Question
+                | TokenKind::Plus
+                | TokenKind::Minus
+                | TokenKind::Star
+                | TokenKind::Slash
+                | TokenKind::Percent
+                | TokenKind::Ampersand
+                | TokenKind::Pipe
+                | TokenKind::Caret
+                | TokenKind::Bang
+                | TokenKind::Equal
+                | TokenKind::EqualEqual
+                | TokenKind::BangEqual
+                | TokenKind::Less
+                | TokenKind::LessEqual
+                | TokenKind::Greater
+                | TokenKind::GreaterEqual
+                | TokenKind::AmpersandAmpersand
+                | TokenKind::PipePipe
+                | TokenKind::LessLess
+                | TokenKind::GreaterGreater
+                | TokenKind::PlusEqual
+                | TokenKind::MinusEqual
+                | TokenKind::StarEqual
+        )
+    }
+}
+
+/// 予約語と演算子の別名の表（`もし` → `if` など）
+///
+/// DSLが `keyword もし = if;` で宣言した別名を、元のトークンの種類に読み替える。トークンの位置は
+/// 別名の字面のままなので、エラーの位置やソース上の範囲は書いたとおりになる。
+#[derive(Debug, Clone, Default)]
+pub struct KeywordMap {
+    aliases: HashMap<String, TokenKind>,
+}
+
+impl KeywordMap {
+    /// 別名の一覧から作成（元の字面が1つのトークンにならない別名は無視する）
+    pub fn new(aliases: &[KeywordAlias]) -> Self {
+        let mut map = Self::default();
+        for alias in aliases {
+            if let Some(kind) = single_token(&alias.keyword) {
+                map.aliases.insert(alias.alias.clone(), kind);
+            }
+        }
+        map
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+    
+    /// 別名の単語なら、元のトークンの種類
+    pub fn get(&self, word: &str) -> Option<&TokenKind> {
+        self.aliases.get(word)
+    }
+    
+    /// 別名の識別子のトークンを、元の予約語・演算子のトークンに置き換える
+    pub fn apply(&self, token: &mut Token) {
+        if let TokenKind::Identifier(word) = &token.kind {
+            if let Some(kind) = self.aliases.get(word.as_str()) {
+                token.kind = kind.clone();
+            }
+        }
+    }
+}
+
+/// 字面がちょうど1つのトークンになれば、そのトークンの種類
+pub fn single_token(text: &str) -> Option<TokenKind> {
+    let mut lexer = Lexer::new(text, PathBuf::from("<keyword>"));
+    let kind = lexer.next_token().ok()?.kind;
+    match lexer.next_token().ok()?.kind {
+        TokenKind::Eof if kind != TokenKind::Eof => Some(kind),
+        _ => None,
+    }
+}
+
 /// トークン
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub kind: TokenKind,
     pub location: SourceLocation,
+    /// ソースに書かれた字面（表示が字面と食い違うことのあるリテラルだけ持つ）
+    ///
+    /// `1.0` の表示は `1`、`"a\"b"` の表示は `"a"b"` になり、字句解析し直すと元のトークンに戻らない。
+    pub text: Option<Box<str>>,
 }
 
 impl Token {
     pub fn new(kind: TokenKind, location: SourceLocation) -> Self {
-        Self { kind, location }
+        Self { kind, location, text: None }
+    }
+    
+    /// ソースに書かれたとおりの字面（字句解析器が作ったのでないトークンは表示の形）
+    ///
+    /// DSLブロックの内容や構文規則の要素のように、トークンを文字列に戻してから字句解析し直す場合に使う。
+    pub fn source_text(&self) -> Cow<'_, str> {
+        match &self.text {
+            Some(text) => Cow::Borrowed(text),
+            None => Cow::Owned(self.kind.to_string()),
+        }
+    }
+}
+
+/// 現在の文字より先に読んでおく文字数（数値リテラルの指数部分の判定に2文字使う）
+const CHAR_LOOKAHEAD: usize = 2;
+
+/// 字句解析器が読む文字の列
+enum CharSource<'a> {
+    /// メモリ上のソース全体
+    Text(Chars<'a>),
+    /// バイトのストリーム（UTF-8として1文字ずつ読む）。読み込みに失敗したら、そこでソースが終わったものとする
+    Stream {
+        reader: Box<dyn BufRead + 'a>,
+        error: Option<String>,
+    },
+}
+
+impl CharSource<'_> {
+    fn next_char(&mut self) -> Option<char> {
+        match self {
+            Self::Text(chars) => chars.next(),
+            Self::Stream { reader, error } => read_char(reader.as_mut()).unwrap_or_else(|message| {
+                *reader = Box::new(io::empty());
+                *error = Some(message);
+                None
+            }),
+        }
+    }
+    
+    /// 読み込みのエラーを取り出す
+    fn take_error(&mut self) -> Option<String> {
+        match self {
+            Self::Text(_) => None,
+            Self::Stream { error, .. } => error.take(),
+        }
+    }
+}
+
+/// UTF-8の1文字を読む（終端なら `None`）
+fn read_char(reader: &mut dyn BufRead) -> std::result::Result<Option<char>, String> {
+    let Some(first) = read_byte(reader)? else {
+        return Ok(None);
+    };
+    let width = match first {
+        0x00..=0x7F => return Ok(Some(first as char)),
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return Err(format!("UTF-8として不正なバイト 0x{:02X} があります", first)),
+    };
+    let mut bytes = [first, 0, 0, 0];
+    for byte in &mut bytes[1..width] {
+        *byte = read_byte(reader)?.ok_or_else(|| "UTF-8の文字の途中でソースが終わっています".to_string())?;
+    }
+    std::str::from_utf8(&bytes[..width])
+        .map(|text| text.chars().next())
+        .map_err(|_| format!("UTF-8として不正なバイト列 {:02X?} があります", &bytes[..width]))
+}
+
+fn read_byte(reader: &mut dyn BufRead) -> std::result::Result<Option<u8>, String> {
+    loop {
+        match reader.fill_buf() {
+            Ok([]) => return Ok(None),
+            Ok(buffer) => {
+                let byte = buffer[0];
+                reader.consume(1);
+                return Ok(Some(byte));
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("ソースを読み込めません: {}", e)),
+        }
     }
 }
 
 /// 字句解析器
+///
+/// `Lexer::new` はメモリ上の文字列を、`Lexer::from_reader` はバイトのストリームを読む。どちらも
+/// 現在の文字と `CHAR_LOOKAHEAD` 文字の先読みだけを持って進むので、ストリームを読むときはソース全体を
+/// メモリに置かない。
 pub struct Lexer<'a> {
-    input: &'a str,
-    chars: Chars<'a>,
+    source: CharSource<'a>,
+    /// 現在の文字の後に続く、読み込み済みの文字
+    ahead: VecDeque<char>,
     current: Option<char>,
+    /// 現在の文字のソース上のバイト位置
+    offset: usize,
     line: usize,
     column: usize,
     file_path: PathBuf,
+    /// `peg name {` の読み取り状態（1: 名前待ち、2: 本体待ち）
+    peg_header: u8,
+    /// 読み進めた中で最初に見つけた双方向テキストの制御文字（文字、行、列）
+    bidi_control: Option<(char, usize, usize)>,
+    /// 読み取り中のトークンの字面
+    lexeme: String,
+    /// ソースの先頭のソースマップ全体での位置（`for_file` で作ったときだけ、トークンの位置に範囲を付ける）
+    span_base: Option<usize>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str, file_path: PathBuf) -> Self {
-        let mut chars = input.chars();
-        let current = chars.next();
+        Self::with_source(CharSource::Text(input.chars()), file_path)
+    }
+    
+    /// ソースマップに登録したファイルの字句解析器を作成
+    ///
+    /// トークンの位置に、ソースマップ全体での範囲（`SourceLocation::span`）を付ける。
+    pub fn for_file(file: &'a SourceFile) -> Self {
+        Self {
+            span_base: Some(file.span().lo),
+            ..Self::new(file.text(), file.path().to_path_buf())
+        }
+    }
+    
+    /// バイトのストリームをUTF-8のソースとして読む字句解析器を作成
+    ///
+    /// 読み込みやUTF-8の復号に失敗すると、その位置で `next_token` がエラーを返す。
+    pub fn from_reader(reader: impl BufRead + 'a, file_path: PathBuf) -> Self {
+        Self::with_source(CharSource::Stream { reader: Box::new(reader), error: None }, file_path)
+    }
+    
+    fn with_source(mut source: CharSource<'a>, file_path: PathBuf) -> Self {
+        let current = source.next_char();
+        let ahead = (0..CHAR_LOOKAHEAD).map_while(|_| source.next_char()).collect();
         
         Self {
-            input,
-            chars,
+            source,
+            ahead,
             current,
+            offset: 0,
             line: 1,
             column: 1,
             file_path,
+            peg_header: 0,
+            bidi_control: None,
+            lexeme: String::new(),
+            span_base: None,
         }
     }
     
+    /// 現在の文字のソース上のバイト位置
+    fn offset(&self) -> usize {
+        self.offset
+    }
+    
     /// 現在の位置のソース位置情報を取得
     fn current_location(&self, length: usize) -> SourceLocation {
         SourceLocation::new(
@@ -227,6 +509,9 @@ impl<'a> Lexer<'a> {
     
     /// 次の文字に進む
     fn advance(&mut self) {
+        if let Some(c) = self.current.filter(|c| BIDI_CONTROLS.contains(c)) {
+            self.bidi_control.get_or_insert((c, self.line, self.column));
+        }
         if let Some('\n') = self.current {
             self.line += 1;
             self.column = 1;
@@ -234,7 +519,16 @@ impl<'a> Lexer<'a> {
             self.column += 1;
         }
         
-        self.current = self.chars.next();
+        if let Some(c) = self.current {
+            self.offset += c.len_utf8();
+            self.lexeme.push(c);
+        }
+        self.current = self.ahead.pop_front().or_else(|| self.source.next_char());
+        if self.ahead.len() < CHAR_LOOKAHEAD {
+            if let Some(c) = self.source.next_char() {
+                self.ahead.push_back(c);
+            }
+        }
     }
     
     /// 空白をスキップ
@@ -304,65 +598,137 @@ impl<'a> Lexer<'a> {
     
     /// 次の文字をピーク（先読み）
     fn peek(&self) -> Option<char> {
-        self.chars.clone().next()
+        self.ahead.front().copied()
     }
     
     /// 数値リテラルを解析
-    fn number(&mut self) -> TokenKind {
-        let start_column = self.column;
-        let mut value = 0;
-        let mut is_float = false;
-        let mut decimal_place = 0.1;
+    ///
+    /// `0x` `0o` `0b` の接頭辞で16進数・8進数・2進数を書ける。数字の間の `_` は無視する。
+    /// `42i32` や `3.5f32` のように型名を続けると、その型のリテラルになる。
+    fn number(&mut self) -> Result<TokenKind> {
+        let (file, line, column) = (self.file_path.clone(), self.line, self.column);
+        let error = |message: String| EidosError::Lexer { message, file: file.clone(), line, column };
         
-        // 整数部分を解析
-        while let Some(c) = self.current {
-            if c.is_digit(10) {
-                let digit = c.to_digit(10).unwrap() as i64;
-                value = value * 10 + digit;
+        let (radix, radix_name) = match (self.current, self.peek()) {
+            (Some('0'), Some('x')) => (16, "16進数"),
+            (Some('0'), Some('o')) => (8, "8進数"),
+            (Some('0'), Some('b')) => (2, "2進数"),
+            _ => (10, "10進数"),
+        };
+        if radix != 10 {
+            self.advance();
+            self.advance();
+        }
+        
+        let mut digits = self.digits(radix);
+        let mut is_float = false;
+        if radix == 10 {
+            // 小数部分（`1.` や `x.0.1` のように数字が続かない '.' は含めない）
+            if self.current == Some('.') && self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                is_float = true;
                 self.advance();
-            } else if c == '.' && self.peek().map_or(false, |c| c.is_digit(10)) {
+                digits.push('.');
+                digits.push_str(&self.digits(10));
+            }
+            // 指数部分（`1.0e10`、`2e-3`）
+            let exponent = match (self.current, self.ahead.front().copied(), self.ahead.get(1).copied()) {
+                (Some('e' | 'E'), Some(c), _) if c.is_ascii_digit() => true,
+                (Some('e' | 'E'), Some('+' | '-'), Some(c)) => c.is_ascii_digit(),
+                _ => false,
+            };
+            if exponent {
                 is_float = true;
-                self.advance(); // '.' をスキップ
-                break;
-            } else {
-                break;
+                digits.push('e');
+                self.advance();
+                if let Some(sign @ ('+' | '-')) = self.current {
+                    digits.push(sign);
+                    self.advance();
+                }
+                digits.push_str(&self.digits(10));
             }
         }
         
-        // 小数部分を解析
-        if is_float {
-            let mut float_value = value as f64;
-            
-            while let Some(c) = self.current {
-                if c.is_digit(10) {
-                    let digit = c.to_digit(10).unwrap() as f64;
-                    float_value += digit * decimal_place;
-                    decimal_place *= 0.1;
-                    self.advance();
-                } else {
-                    break;
-                }
+        if let Some(c) = self.current.filter(|c| c.is_ascii_digit()) {
+            return Err(error(format!("{}のリテラルに使えない数字 '{}' があります", radix_name, c)));
+        }
+        if digits.is_empty() {
+            return Err(error(format!("{}のリテラルに数字がありません", radix_name)));
+        }
+        
+        // 型の接尾辞
+        let mut suffix = String::new();
+        while let Some(c) = self.current.filter(|c| c.is_alphanumeric() || *c == '_') {
+            suffix.push(c);
+            self.advance();
+        }
+        let ty = match suffix.as_str() {
+            "" => None,
+            _ => Some(NumericType::from_name(&suffix).ok_or_else(|| {
+                error(format!("不明な数値リテラルの型 '{}' です（i8〜i64、u8〜u64、f32、f64 を指定できます）", suffix))
+            })?),
+        };
+        
+        if is_float || ty.is_some_and(|ty| ty.is_float()) {
+            if let Some(ty) = ty.filter(|ty| !ty.is_float()) {
+                return Err(error(format!("浮動小数点数のリテラルに整数型 '{}' は指定できません", ty)));
             }
-            
-            TokenKind::Float(float_value)
-        } else {
-            TokenKind::Integer(value)
+            if radix != 10 {
+                return Err(error(format!("{}のリテラルに浮動小数点数型は指定できません", radix_name)));
+            }
+            let value: f64 = digits.parse().map_err(|_| error(format!("数値リテラル '{}' を解析できません", digits)))?;
+            return Ok(match ty {
+                Some(ty) if ty != NumericType::F64 => TokenKind::SizedFloat(value, ty),
+                _ => TokenKind::Float(value),
+            });
         }
+        
+        // 接尾辞のない整数リテラルは、`i64` に収まらなければ `BigInt` にする
+        if ty.is_none() && u64::from_str_radix(&digits, radix).map_or(true, |value| value > i64::MAX as u64) {
+            let value = BigInt::parse_radix(&digits, radix)
+                .ok_or_else(|| error(format!("数値リテラル '{}' を解析できません", digits)))?;
+            return Ok(TokenKind::BigInteger(value));
+        }
+        let ty = ty.unwrap_or(NumericType::I64);
+        let value = u64::from_str_radix(&digits, radix)
+            .ok()
+            .filter(|value| ty.max_value().is_some_and(|max| *value <= max))
+            .ok_or_else(|| error(format!("整数リテラル '{}' は {} の範囲を超えています", digits, ty)))?;
+        Ok(match ty {
+            NumericType::I64 => TokenKind::Integer(value as i64),
+            _ => TokenKind::SizedInteger(value as i64, ty),
+        })
+    }
+    
+    /// 指定した基数の数字の並びを読む（`_` の区切りは取り除く）
+    fn digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
+        while let Some(c) = self.current {
+            if c.is_digit(radix) {
+                digits.push(c);
+            } else if c != '_' {
+                break;
+            }
+            self.advance();
+        }
+        digits
     }
     
     /// 識別子またはキーワードを解析
+    ///
+    /// 識別子はUnicodeの XID_Start（または '_'）で始まり XID_Continue が続く文字列で、NFCに正規化する。
+    /// 合成済みの「é」と「e」+結合文字のように、見た目が同じで符号化だけが異なる名前は同じ識別子になる。
     fn identifier(&mut self) -> TokenKind {
-        let start_column = self.column;
-        let mut name = String::new();
+        let mut raw = String::new();
         
         while let Some(c) = self.current {
-            if c.is_alphanumeric() || c == '_' {
-                name.push(c);
+            if unicode_ident::is_xid_continue(c) {
+                raw.push(c);
                 self.advance();
             } else {
                 break;
             }
         }
+        let name: String = raw.nfc().collect();
         
         // キーワードをチェック
         match name.as_str() {
@@ -387,7 +753,10 @@ impl<'a> Lexer<'a> {
             "mut" => TokenKind::Mut,
             "true" => TokenKind::True,
             "false" => TokenKind::False,
-            _ => TokenKind::Identifier(name),
+            "syntax" => TokenKind::Syntax,
+            "macro" => TokenKind::Macro,
+            "peg" => TokenKind::Peg,
+            _ => TokenKind::Identifier(Name::new(&name)),
         }
     }
     
@@ -404,32 +773,7 @@ impl<'a> Lexer<'a> {
                 self.advance();
                 return Ok(TokenKind::String(value));
             } else if c == '\\' {
-                // エスケープシーケンス
-                self.advance();
-                match self.current {
-                    Some('n') => value.push('\n'),
-                    Some('r') => value.push('\r'),
-                    Some('t') => value.push('\t'),
-                    Some('\\') => value.push('\\'),
-                    Some('"') => value.push('"'),
-                    Some(c) => {
-                        return Err(EidosError::Lexer {
-                            message: format!("不明なエスケープシーケンス: \\{}", c),
-                            file: self.file_path.clone(),
-                            line: self.line,
-                            column: self.column,
-                        });
-                    },
-                    None => {
-                        return Err(EidosError::Lexer {
-                            message: "文字列リテラルが途中で終了しました".to_string(),
-                            file: self.file_path.clone(),
-                            line: self.line,
-                            column: self.column,
-                        });
-                    },
-                }
-                self.advance();
+                value.push(self.escape_sequence("文字列")?);
             } else {
                 value.push(c);
                 self.advance();
@@ -450,34 +794,11 @@ impl<'a> Lexer<'a> {
         self.advance();
         
         let c = match self.current {
-            Some('\\') => {
-                // エスケープシーケンス
+            Some('\\') => self.escape_sequence("文字")?,
+            Some(c) => {
                 self.advance();
-                match self.current {
-                    Some('n') => '\n',
-                    Some('r') => '\r',
-                    Some('t') => '\t',
-                    Some('\\') => '\\',
-                    Some('\'') => '\'',
-                    Some(c) => {
-                        return Err(EidosError::Lexer {
-                            message: format!("不明なエスケープシーケンス: \\{}", c),
-                            file: self.file_path.clone(),
-                            line: self.line,
-                            column: self.column,
-                        });
-                    },
-                    None => {
-                        return Err(EidosError::Lexer {
-                            message: "文字リテラルが途中で終了しました".to_string(),
-                            file: self.file_path.clone(),
-                            line: self.line,
-                            column: self.column,
-                        });
-                    },
-                }
+                c
             },
-            Some(c) => c,
             None => {
                 return Err(EidosError::Lexer {
                     message: "文字リテラルが空です".to_string(),
@@ -488,8 +809,6 @@ impl<'a> Lexer<'a> {
             },
         };
         
-        self.advance();
-        
         if self.current != Some('\'') {
             return Err(EidosError::Lexer {
                 message: "文字リテラルが閉じられていません".to_string(),
@@ -505,6 +824,66 @@ impl<'a> Lexer<'a> {
         Ok(TokenKind::Character(c))
     }
     
+    /// エスケープシーケンスを解析し、表す文字を返す（現在の文字は `\`）
+    ///
+    /// `\n` `\r` `\t` `\0` `\\` `\"` `\'` と、1〜6桁の16進数でUnicodeスカラー値を表す `\u{1F600}` を扱う。
+    /// 不正なエスケープはそのエスケープの開始位置（`\` の位置）を指すエラーになる。
+    fn escape_sequence(&mut self, literal: &str) -> Result<char> {
+        let (file, line, column) = (self.file_path.clone(), self.line, self.column);
+        let error = |message: String| EidosError::Lexer { message, file, line, column };
+        
+        self.advance();
+        let c = match self.current {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('u') => {
+                self.advance();
+                return self.unicode_escape().map_err(error);
+            },
+            Some(c) => return Err(error(format!("不明なエスケープシーケンス: \\{}", c))),
+            None => return Err(error(format!("{}リテラルが途中で終了しました", literal))),
+        };
+        self.advance();
+        Ok(c)
+    }
+    
+    /// `\u` に続く `{16進数}` を解析（`\u` は読み込み済み）
+    fn unicode_escape(&mut self) -> std::result::Result<char, String> {
+        if self.current != Some('{') {
+            return Err("Unicodeエスケープは `\\u{1F600}` のように16進数を波括弧で囲んでください".to_string());
+        }
+        self.advance();
+        
+        let mut digits = String::new();
+        while let Some(c) = self.current {
+            if c == '}' {
+                break;
+            }
+            if !c.is_ascii_hexdigit() {
+                return Err(format!("Unicodeエスケープに16進数でない文字 '{}' があります", c));
+            }
+            digits.push(c);
+            self.advance();
+        }
+        if self.current != Some('}') {
+            return Err("Unicodeエスケープが '}' で閉じられていません".to_string());
+        }
+        self.advance();
+        
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(format!("Unicodeエスケープは1〜6桁の16進数で指定してください: \\u{{{}}}", digits));
+        }
+        let value = u32::from_str_radix(&digits, 16).map_err(|e| e.to_string())?;
+        char::from_u32(value).ok_or_else(|| {
+            format!("\\u{{{}}} はUnicodeスカラー値ではありません（サロゲートか U+10FFFF より大きい値です）", digits)
+        })
+    }
+    
     /// DSL開始トークンを解析（@name { 形式）
     fn dsl_start(&mut self) -> Result<TokenKind> {
         // '@' をスキップ
@@ -550,9 +929,105 @@ impl<'a> Lexer<'a> {
         Ok(TokenKind::DSLStart(name))
     }
     
+    /// `peg name { ... }` の本体を生のテキストとして読み取る
+    fn raw_block(&mut self) -> Result<TokenKind> {
+        let start_line = self.line;
+        let start_column = self.column;
+        
+        // '{' をスキップ
+        self.advance();
+        
+        let mut text = String::new();
+        let mut depth = 1;
+        let mut quote: Option<char> = None;
+        let mut in_class = false;
+        let mut in_comment = false;
+        
+        while let Some(c) = self.current {
+            // `#` コメントは行末まで読み、中の引用符や括弧は数えない
+            if in_comment {
+                in_comment = c != '\n';
+                text.push(c);
+                self.advance();
+                continue;
+            }
+            
+            // 文字列・文字クラス内の括弧は数えない（`\` の次の文字は引用符や `]` でも閉じない）
+            if quote.is_some() || in_class {
+                text.push(c);
+                self.advance();
+                if c == '\\' {
+                    if let Some(escaped) = self.current {
+                        text.push(escaped);
+                        self.advance();
+                    }
+                } else if quote == Some(c) {
+                    quote = None;
+                } else if quote.is_none() && c == ']' {
+                    in_class = false;
+                }
+                continue;
+            }
+            
+            match c {
+                '"' | '\'' => quote = Some(c),
+                '[' => in_class = true,
+                // コメントは文法の中だけで、意味アクションの中の `#` はそのまま読む
+                '#' if depth == 1 => in_comment = true,
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.advance();
+                        return Ok(TokenKind::RawBlock(text));
+                    }
+                }
+                _ => {}
+            }
+            text.push(c);
+            self.advance();
+        }
+        
+        Err(EidosError::Lexer {
+            message: "ブロックが閉じられていません".to_string(),
+            file: self.file_path.clone(),
+            line: start_line,
+            column: start_column,
+        })
+    }
+    
     /// 次のトークンを取得
+    ///
+    /// 双方向テキストの制御文字は、コメントや文字列の中も含めてエラーにする。表示される順序と
+    /// コンパイラが読む順序が食い違い、レビューで見えないコードを紛れ込ませられるため（Trojan Source）。
     pub fn next_token(&mut self) -> Result<Token> {
         self.skip_whitespace_and_comments();
+        let start = self.offset;
+        let token = self.scan_token().map(|mut token| {
+            if let Some(base) = self.span_base {
+                token.location.span = Some(Span::new(base + start, base + self.offset));
+            }
+            token
+        });
+        if let Some(message) = self.source.take_error() {
+            return Err(EidosError::Lexer { message, file: self.file_path.clone(), line: self.line, column: self.column });
+        }
+        if let Some((c, line, column)) = self.bidi_control.take() {
+            return Err(EidosError::Lexer {
+                message: format!(
+                    "双方向テキストの制御文字 U+{:04X} はソースコードに書けません（表示される順序が実際の順序と食い違います）",
+                    c as u32
+                ),
+                file: self.file_path.clone(),
+                line,
+                column,
+            });
+        }
+        token
+    }
+    
+    fn scan_token(&mut self) -> Result<Token> {
+        self.skip_whitespace_and_comments();
         
         if self.current.is_none() {
             return Ok(Token::new(
@@ -563,13 +1038,22 @@ impl<'a> Lexer<'a> {
         
         let start_column = self.column;
         let start_line = self.line;
+        self.lexeme.clear();
+        
+        // `peg name {` の本体は生のテキストとして1つのトークンにする
+        if self.peg_header == 2 && self.current == Some('{') {
+            self.peg_header = 0;
+            let kind = self.raw_block()?;
+            let location = SourceLocation::new(self.file_path.clone(), start_line, start_column, 1);
+            return Ok(Token::new(kind, location));
+        }
         
         let kind = match self.current.unwrap() {
             // 識別子または予約語
-            c if c.is_alphabetic() || c == '_' => self.identifier(),
+            c if unicode_ident::is_xid_start(c) || c == '_' => self.identifier(),
             
             // 数値
-            c if c.is_digit(10) => self.number(),
+            c if c.is_digit(10) => self.number()?,
             
             // 文字列
             '"' => self.string()?,
@@ -590,19 +1074,49 @@ impl<'a> Lexer<'a> {
             ';' => { self.advance(); TokenKind::Semicolon },
             ':' => { self.advance(); TokenKind::Colon },
             ',' => { self.advance(); TokenKind::Comma },
-            '.' => { self.advance(); TokenKind::Dot },
+            '.' => {
+                self.advance();
+                if self.current == Some('.') {
+                    self.advance();
+                    TokenKind::DotDot
+                } else {
+                    TokenKind::Dot
+                }
+            },
+            '$' => { self.advance(); TokenKind::Dollar },
+            '#' => { self.advance(); TokenKind::Hash },
+            '?' => { self.advance(); TokenKind::Question },
             
-            '+' => { self.advance(); TokenKind::Plus },
+            '+' => {
+                self.advance();
+                if self.current == Some('=') {
+                    self.advance();
+                    TokenKind::PlusEqual
+                } else {
+                    TokenKind::Plus
+                }
+            },
             '-' => {
                 self.advance();
                 if self.current == Some('>') {
                     self.advance();
                     TokenKind::Arrow
+                } else if self.current == Some('=') {
+                    self.advance();
+                    TokenKind::MinusEqual
                 } else {
                     TokenKind::Minus
                 }
             },
-            '*' => { self.advance(); TokenKind::Star },
+            '*' => {
+                self.advance();
+                if self.current == Some('=') {
+                    self.advance();
+                    TokenKind::StarEqual
+                } else {
+                    TokenKind::Star
+                }
+            },
             '/' => { self.advance(); TokenKind::Slash },
             '%' => { self.advance(); TokenKind::Percent },
             
@@ -640,6 +1154,9 @@ impl<'a> Lexer<'a> {
                 if self.current == Some('=') {
                     self.advance();
                     TokenKind::EqualEqual
+                } else if self.current == Some('>') {
+                    self.advance();
+                    TokenKind::FatArrow
                 } else {
                     TokenKind::Equal
                 }
@@ -676,6 +1193,13 @@ impl<'a> Lexer<'a> {
             },
         };
         
+        // `peg` キーワードの後の名前と本体の開始を追跡
+        self.peg_header = match (&kind, self.peg_header) {
+            (TokenKind::Peg, _) => 1,
+            (TokenKind::Identifier(_), 1) => 2,
+            _ => 0,
+        };
+        
         // 最終的なトークンの位置と長さを計算
         let length = if self.line == start_line {
             self.column - start_column
@@ -692,7 +1216,28 @@ impl<'a> Lexer<'a> {
             length,
         );
         
-        Ok(Token::new(kind, location))
+        let mut token = Token::new(kind, location);
+        if matches!(
+            token.kind,
+            TokenKind::Integer(_)
+                | TokenKind::Float(_)
+                | TokenKind::SizedInteger(..)
+                | TokenKind::SizedFloat(..)
+                | TokenKind::BigInteger(_)
+                | TokenKind::String(_)
+                | TokenKind::Character(_)
+        ) {
+            token.text = Some(self.lexeme.as_str().into());
+        }
+        Ok(token)
+    }
+    
+    /// トークンをソース上のバイト範囲と組にして1つずつ返すイテレータに変換
+    ///
+    /// `Eof` は返さずに終わる。字句エラーの後も続けて読むので、エディタのトークナイザーのように
+    /// 誤りを含むソースを最後まで読める（エラーで位置が進まなかった場合はそこで終わる）。
+    pub fn spanned(self) -> SpannedTokens<'a> {
+        SpannedTokens { lexer: self, done: false }
     }
     
     /// 全てのトークンを取得
@@ -711,4 +1256,428 @@ impl<'a> Lexer<'a> {
         
         Ok(tokens)
     }
-} 
\ No newline at end of file
+}
+
+/// 構文解析器にトークンを1つずつ渡す読み出し元
+///
+/// 字句解析器のほか、マクロを展開しながら読む `MacroExpander::expanding` などがある。
+/// ソースの終わりでは `Eof` を返す。
+pub trait TokenSource {
+    fn next_token(&mut self) -> Result<Token>;
+}
+
+impl TokenSource for Lexer<'_> {
+    fn next_token(&mut self) -> Result<Token> {
+        Lexer::next_token(self)
+    }
+}
+
+/// ソース上のバイト範囲を持つトークン
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    /// トークンのソース上のバイト範囲（`&source[span]` がトークンの字面になる）
+    pub span: Range<usize>,
+}
+
+/// `Lexer::spanned` が返すイテレータ
+pub struct SpannedTokens<'a> {
+    lexer: Lexer<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for SpannedTokens<'a> {
+    type Item = Result<SpannedToken>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.lexer.skip_whitespace_and_comments();
+        let start = self.lexer.offset();
+        match self.lexer.next_token() {
+            Ok(token) if token.kind == TokenKind::Eof => {
+                self.done = true;
+                None
+            }
+            Ok(token) => Some(Ok(SpannedToken { token, span: start..self.lexer.offset() })),
+            Err(error) => {
+                self.done = self.lexer.offset() == start;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// 見た目で区別しにくい2つの識別子（`Token` の位置は後に現れた方）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfusableIdentifier {
+    pub name: String,
+    pub similar: String,
+    pub location: SourceLocation,
+}
+
+impl fmt::Display for ConfusableIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: 識別子 '{}' は '{}' と見分けにくい文字を含んでいます",
+            self.location.file.display(),
+            self.location.line,
+            self.location.column,
+            self.name,
+            self.similar
+        )
+    }
+}
+
+/// トークン列の中で、見た目が紛らわしい別々の識別子の組を探す
+///
+/// UTS #39 のスケルトン（ラテン文字の `a` とキリル文字の `а` などを同じ文字に写したもの）が一致する
+/// 識別子を組にする。ASCIIだけの識別子どうし（`l1` と `ll` など）は対象にしない。
+pub fn confusable_identifiers(tokens: &[Token]) -> Vec<ConfusableIdentifier> {
+    let mut checker = ConfusableChecker::new();
+    for token in tokens {
+        checker.check(token);
+    }
+    checker.found
+}
+
+/// 見た目が紛らわしい識別子の組を、トークンを1つずつ読みながら探す（`confusable_identifiers` と同じ規則）
+///
+/// 覚えるのは識別子ごとのスケルトンだけなので、`scan` で構文解析器に渡すトークンを検査すれば、
+/// トークン列全体を作らずに済む。
+#[derive(Default)]
+pub struct ConfusableChecker {
+    /// スケルトンごとに最初に現れた識別子
+    skeletons: HashMap<String, String>,
+    found: Vec<ConfusableIdentifier>,
+}
+
+impl ConfusableChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// トークンが識別子なら、それまでに読んだ識別子と見比べる
+    pub fn check(&mut self, token: &Token) {
+        let TokenKind::Identifier(name) = &token.kind else {
+            return;
+        };
+        let skeleton: String = unicode_security::skeleton(name).collect();
+        match self.skeletons.get(&skeleton) {
+            Some(similar) if similar.as_str() != &**name && !(similar.is_ascii() && name.is_ascii()) => {
+                if !self.found.iter().any(|f| f.name.as_str() == &**name && f.similar == *similar) {
+                    self.found.push(ConfusableIdentifier {
+                        name: name.to_string(),
+                        similar: similar.clone(),
+                        location: token.location.clone(),
+                    });
+                }
+            }
+            Some(_) => {}
+            None => {
+                self.skeletons.insert(skeleton, name.to_string());
+            }
+        }
+    }
+    
+    /// これまでに見つけた識別子の組
+    pub fn found(&self) -> &[ConfusableIdentifier] {
+        &self.found
+    }
+    
+    /// `source` から読んだトークンを検査してから渡す読み出し元を作る
+    pub fn scan<S: TokenSource>(&mut self, source: S) -> ConfusableScan<'_, S> {
+        ConfusableScan { checker: self, source }
+    }
+}
+
+/// `ConfusableChecker::scan` が返すトークンの読み出し元
+pub struct ConfusableScan<'c, S> {
+    checker: &'c mut ConfusableChecker,
+    source: S,
+}
+
+impl<S: TokenSource> TokenSource for ConfusableScan<'_, S> {
+    fn next_token(&mut self) -> Result<Token> {
+        let token = self.source.next_token()?;
+        self.checker.check(&token);
+        Ok(token)
+    }
+}
+
+/// オフサイドルールで、トークン列に論理行の終わりとインデントの増減のトークンを補う
+///
+/// Pythonと同じ規則で、各論理行の終わりに `Newline` を置き、前の行よりインデントが深い行の前に
+/// `Indent`、浅い行の前には戻った段の数だけ `Dedent` を置く。末尾では最初の行の段まで戻す。
+/// インデントはトークンの列で比べ（タブも1列と数える）、括弧の中の改行は行の続きとして扱う。
+/// インデントの段は最初のトークンの列から始まり、外側のどの段とも揃わない行はエラーにする。
+pub fn offside_rule(tokens: Vec<Token>) -> Result<Vec<Token>> {
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut levels: Vec<usize> = Vec::new();
+    let mut depth = 0usize;
+    let mut line = 0;
+    let mut eof = None;
+    let layout = |kind: TokenKind, location: &SourceLocation| {
+        Token::new(kind, SourceLocation::new(location.file.clone(), location.line, location.column, 0))
+    };
+    
+    for token in tokens {
+        if token.kind == TokenKind::Eof {
+            eof = Some(token);
+            break;
+        }
+        if token.location.line > line && depth == 0 {
+            let column = token.location.column;
+            if !levels.is_empty() {
+                output.push(layout(TokenKind::Newline, &token.location));
+            }
+            if levels.last().is_none_or(|&level| column > level) {
+                if !levels.is_empty() {
+                    output.push(layout(TokenKind::Indent, &token.location));
+                }
+                levels.push(column);
+            }
+            while levels.last().is_some_and(|&level| column < level) {
+                levels.pop();
+                output.push(layout(TokenKind::Dedent, &token.location));
+            }
+            if levels.last() != Some(&column) {
+                return Err(EidosError::Lexer {
+                    message: "インデントが外側のどの行とも揃っていません".to_string(),
+                    file: token.location.file.clone(),
+                    line: token.location.line,
+                    column,
+                });
+            }
+        }
+        
+        match token.kind {
+            TokenKind::LeftParen | TokenKind::LeftBrace | TokenKind::LeftBracket | TokenKind::DSLStart(_) => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBrace | TokenKind::RightBracket => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        line = token.location.line;
+        output.push(token);
+    }
+    
+    if let Some(last) = output.last().map(|token: &Token| token.location.clone()) {
+        output.push(layout(TokenKind::Newline, &last));
+        for _ in 1..levels.len() {
+            output.push(layout(TokenKind::Dedent, &last));
+        }
+    }
+    output.extend(eof);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn lex(source: &str) -> Result<Vec<TokenKind>> {
+        let tokens = Lexer::new(source, PathBuf::from("<test>")).tokenize()?;
+        Ok(tokens.into_iter().map(|token| token.kind).collect())
+    }
+    
+    #[test]
+    fn test_offside_rule() {
+        let layout = |source: &str| -> Result<String> {
+            let tokens = offside_rule(Lexer::new(source, PathBuf::from("<test>")).tokenize()?)?;
+            Ok(tokens.iter().map(|token| token.kind.to_string()).collect::<Vec<_>>().join(" "))
+        };
+        // 括弧の中の改行は行の続き、空行とコメントは無視する
+        assert_eq!(
+            layout("if a:\n    b\n\n    c(\n  d)\n// else\nelse:\n    e\n").unwrap(),
+            "if a : NEWLINE INDENT b NEWLINE c ( d ) NEWLINE DEDENT else : NEWLINE INDENT e NEWLINE DEDENT EOF"
+        );
+        assert_eq!(layout("  a\n    b\n      c").unwrap(), "a NEWLINE INDENT b NEWLINE INDENT c NEWLINE DEDENT DEDENT EOF");
+        assert_eq!(layout("").unwrap(), "EOF");
+        
+        let error = layout("a\n    b\n  c").unwrap_err();
+        assert!(error.to_string().contains("インデントが外側のどの行とも揃っていません"), "{}", error);
+        assert!(layout("  a\nb").is_err());
+    }
+    
+    #[test]
+    fn test_peg_raw_block() {
+        let body = |source: &str| -> (String, usize) {
+            let kinds = lex(source).unwrap();
+            match &kinds[2] {
+                TokenKind::RawBlock(text) => (text.clone(), kinds.len()),
+                kind => panic!("{:?}", kind),
+            }
+        };
+        
+        // コメントの中のアポストロフィは引用符ではない
+        let source = "peg g {\n  # the rule's body }\n  start <- \"a\" { 1 }\n}";
+        assert_eq!(body(source), ("\n  # the rule's body }\n  start <- \"a\" { 1 }\n".to_string(), 4));
+        // 意味アクションの中の `#` はコメントではない
+        assert_eq!(body("peg g { start <- 'a' { \"#\".len() } }").0, " start <- 'a' { \"#\".len() } ");
+        
+        // 文字クラスの中の `\]` は文字クラスを閉じない
+        assert_eq!(body(r"peg g { start <- [\]}] { 1 } } 2"), (r" start <- [\]}] { 1 } ".to_string(), 5));
+        assert_eq!(body(r"peg g { start <- [\\] '\'' }").0, r" start <- [\\] '\'' ");
+    }
+    
+    #[test]
+    fn test_escape_sequences() {
+        let kinds = lex(r#""a\tb\n\"q\"\\ \u{1F600}\u{e9}" '\'' '\u{3042}'"#).unwrap();
+        assert_eq!(kinds[0], TokenKind::String("a\tb\n\"q\"\\ 😀é".to_string()));
+        assert_eq!(kinds[1], TokenKind::Character('\''));
+        assert_eq!(kinds[2], TokenKind::Character('あ'));
+    }
+    
+    #[test]
+    fn test_numeric_literals() {
+        let kinds = lex("0xFF 0b1010 0o17 1_000_000 2.5e3 42i32 255u8 3f32 3.5f32 0xFFFF_FFFF_FFFF_FFFFu64 1.0f64").unwrap();
+        assert_eq!(kinds[..4], [TokenKind::Integer(255), TokenKind::Integer(10), TokenKind::Integer(15), TokenKind::Integer(1_000_000)]);
+        assert_eq!(kinds[4], TokenKind::Float(2500.0));
+        assert_eq!(kinds[5], TokenKind::SizedInteger(42, NumericType::I32));
+        assert_eq!(kinds[6], TokenKind::SizedInteger(255, NumericType::U8));
+        assert_eq!(kinds[7], TokenKind::SizedFloat(3.0, NumericType::F32));
+        assert_eq!(kinds[8], TokenKind::SizedFloat(3.5, NumericType::F32));
+        assert_eq!(kinds[9], TokenKind::SizedInteger(-1, NumericType::U64));
+        assert_eq!(kinds[10], TokenKind::Float(1.0));
+        
+        // 数字が続かない '.' は小数点にしない
+        assert_eq!(lex("1..2").unwrap()[..3], [TokenKind::Integer(1), TokenKind::DotDot, TokenKind::Integer(2)]);
+        
+        // 接尾辞がなく i64 に収まらない整数は BigInt のリテラルになる
+        let kinds = lex("9223372036854775807 9223372036854775808 0xFFFF_FFFF_FFFF_FFFF_FFFF").unwrap();
+        assert_eq!(kinds[0], TokenKind::Integer(i64::MAX));
+        assert_eq!(kinds[1], TokenKind::BigInteger("9223372036854775808".parse().unwrap()));
+        assert_eq!(kinds[2], TokenKind::BigInteger("1208925819614629174706175".parse().unwrap()));
+    }
+    
+    #[test]
+    fn test_invalid_numeric_literals() {
+        for (source, message) in [
+            ("256u8", "u8 の範囲を超えています"),
+            ("9223372036854775808i64", "i64 の範囲を超えています"),
+            ("0b102", "使えない数字 '2'"),
+            ("0x", "数字がありません"),
+            ("1.5i32", "整数型 'i32' は指定できません"),
+            ("10usize", "不明な数値リテラルの型 'usize'"),
+        ] {
+            match lex(source) {
+                Err(EidosError::Lexer { message: actual, column, .. }) => {
+                    assert!(actual.contains(message), "{}: {}", source, actual);
+                    assert_eq!(column, 1, "{}", source);
+                }
+                other => panic!("{}: {:?}", source, other),
+            }
+        }
+    }
+    
+    #[test]
+    fn test_keywords() {
+        for keyword in KEYWORDS {
+            assert!(!matches!(lex(keyword).unwrap()[0], TokenKind::Identifier(_)), "{}", keyword);
+        }
+    }
+    
+    #[test]
+    fn test_spanned_tokens() {
+        let source = "let é = \"a\\nb\"; // x\n1u8 + 300u8 ?";
+        let tokens: Vec<Result<SpannedToken>> = Lexer::new(source, PathBuf::from("<test>")).spanned().collect();
+        let texts: Vec<&str> = tokens
+            .iter()
+            .filter_map(|token| token.as_ref().ok())
+            .map(|token| &source[token.span.clone()])
+            .collect();
+        assert_eq!(texts, vec!["let", "é", "=", "\"a\\nb\"", ";", "1u8", "+", "?"]);
+        
+        // エラーの後も続けて読む
+        assert!(tokens[7].is_err());
+        let last = tokens.last().unwrap().as_ref().unwrap();
+        assert_eq!((last.token.location.line, last.token.location.column), (2, 13));
+    }
+    
+    #[test]
+    fn test_lexes_from_reader() {
+        let source = "let 合計 = 1.5e3; // ✓\nfn f() -> Int { 0x1F }";
+        let spans = |lexer: Lexer| -> Vec<(TokenKind, Range<usize>)> {
+            lexer.spanned().map(|token| token.unwrap()).map(|token| (token.token.kind, token.span)).collect()
+        };
+        // 1バイトずつしか読めないストリームでも、文字列から読んだ場合と同じトークンと範囲になる
+        let reader = io::BufReader::with_capacity(1, source.as_bytes());
+        assert_eq!(spans(Lexer::from_reader(reader, PathBuf::from("<test>"))), spans(Lexer::new(source, PathBuf::from("<test>"))));
+        
+        let error = Lexer::from_reader(&b"let a = \"\xE3\x81\"; b"[..], PathBuf::from("<test>")).tokenize().unwrap_err();
+        assert!(error.to_string().contains("UTF-8として不正なバイト列"), "{}", error);
+    }
+    
+    #[test]
+    fn test_invalid_escape_points_at_backslash() {
+        for (source, message) in [
+            ("\"ab\\q\"", "不明なエスケープシーケンス"),
+            ("\"ab\\u{D800}\"", "Unicodeスカラー値ではありません"),
+            ("\"ab\\u{110000}\"", "Unicodeスカラー値ではありません"),
+            ("\"ab\\u{}\"", "1〜6桁"),
+            ("\"ab\\u{12x}\"", "16進数でない文字 'x'"),
+            ("\"ab\\u41\"", "波括弧"),
+        ] {
+            match lex(source) {
+                Err(EidosError::Lexer { message: actual, line, column, .. }) => {
+                    assert!(actual.contains(message), "{}: {}", source, actual);
+                    assert_eq!((line, column), (1, 4), "{}", source);
+                }
+                other => panic!("{}: {:?}", source, other),
+            }
+        }
+    }
+    
+    #[test]
+    fn test_unicode_identifiers() {
+        let kinds = lex("fn 合計(値_1) { let _x = 値_1; }").unwrap();
+        assert_eq!(kinds[1], TokenKind::Identifier("合計".into()));
+        assert_eq!(kinds[3], TokenKind::Identifier("値_1".into()));
+        
+        // 分解された「e」+結合文字は、合成済みの「é」と同じ識別子になる
+        assert_eq!(lex("caf\u{65}\u{301}").unwrap()[0], TokenKind::Identifier("caf\u{e9}".into()));
+        // 識別子に使えない記号は識別子にしない
+        assert_eq!(lex("a→b").unwrap()[1], TokenKind::Unknown('→'));
+    }
+    
+    #[test]
+    fn test_rejects_bidi_controls() {
+        for source in ["let a = 1; // \u{202E}", "let s = \"x\u{2066}y\";", "let \u{200F}a = 1;"] {
+            match lex(source) {
+                Err(EidosError::Lexer { message, line, .. }) => {
+                    assert!(message.contains("双方向テキストの制御文字"), "{}: {}", source, message);
+                    assert_eq!(line, 1, "{}", source);
+                }
+                other => panic!("{:?}: {:?}", source, other),
+            }
+        }
+        match lex("x\n  /* \u{202E} */ y") {
+            Err(EidosError::Lexer { message, line, column, .. }) => {
+                assert!(message.contains("U+202E"), "{}", message);
+                assert_eq!((line, column), (2, 6));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_confusable_identifiers() {
+        // 2つ目の 'раy' の 'р' と 'а' はキリル文字
+        let tokens = Lexer::new("let pay = 1; let \u{440}\u{430}y = 2; pay + \u{440}\u{430}y;", PathBuf::from("<test>")).tokenize().unwrap();
+        let found = confusable_identifiers(&tokens);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].name.as_str(), found[0].similar.as_str()), ("\u{440}\u{430}y", "pay"));
+        assert_eq!(found[0].location.column, 18);
+        
+        // ASCIIだけの識別子どうしは警告しない
+        let tokens = Lexer::new("let l1 = 1; let ll = 2; let 合計 = 3;", PathBuf::from("<test>")).tokenize().unwrap();
+        assert!(confusable_identifiers(&tokens).is_empty());
+        
+        // トークンを読みながら検査しても同じ組が見つかる
+        let mut checker = ConfusableChecker::new();
+        let mut scan = checker.scan(Lexer::new("let pay = 1; let \u{440}\u{430}y = 2; pay + \u{440}\u{430}y;", PathBuf::from("<test>")));
+        while scan.next_token().unwrap().kind != TokenKind::Eof {}
+        assert_eq!(checker.found(), found.as_slice());
+    }
+}
@@ -0,0 +1,646 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::core::{Result, EidosError, Name, SourceLocation};
+use super::lexer::{Token, TokenKind, TokenSource};
+
+/// マクロ展開の再帰深さのデフォルト上限
+pub const DEFAULT_RECURSION_LIMIT: usize = 64;
+
+/// マクロのパターンまたは本体を構成する要素
+#[derive(Debug, Clone)]
+enum MacroFragment {
+    /// そのまま一致させる（または出力する）トークン
+    Token(Token),
+    /// `$name` 形式のキャプチャ変数
+    Capture(String),
+}
+
+/// マクロの規則（`(パターン) => { 本体 }`）
+#[derive(Debug, Clone)]
+struct MacroRule {
+    pattern: Vec<MacroFragment>,
+    body: Vec<MacroFragment>,
+}
+
+/// マクロ定義
+#[derive(Debug, Clone)]
+pub struct MacroDefinition {
+    pub name: String,
+    pub location: SourceLocation,
+    rules: Vec<MacroRule>,
+}
+
+/// トークン列上で動作する衛生的マクロ展開器
+///
+/// 構文解析の前にトークン列から `macro` 定義を取り除き、`name!(...)` 形式の
+/// 呼び出しを展開する。マクロ本体で `let` / `var` により導入された名前は
+/// 展開ごとに一意な名前へ置き換えられるため、呼び出し側の変数と衝突しない。
+pub struct MacroExpander {
+    macros: HashMap<String, MacroDefinition>,
+    recursion_limit: usize,
+    trace_enabled: bool,
+    trace: Vec<String>,
+    next_expansion_id: usize,
+}
+
+impl MacroExpander {
+    pub fn new() -> Self {
+        Self {
+            macros: HashMap::new(),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            trace_enabled: false,
+            trace: Vec::new(),
+            next_expansion_id: 0,
+        }
+    }
+    
+    /// 再帰深さの上限を設定
+    pub fn with_recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+    
+    /// 展開トレースの記録を有効化
+    pub fn with_trace(mut self, enabled: bool) -> Self {
+        self.trace_enabled = enabled;
+        self
+    }
+    
+    /// 記録された展開トレースを取得
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+    
+    /// マクロが定義されているかどうか
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name)
+    }
+    
+    /// 定義済みのマクロを取得
+    pub fn get_definition(&self, name: &str) -> Option<&MacroDefinition> {
+        self.macros.get(name)
+    }
+    
+    /// トークン列中のマクロ定義を収集し、すべての呼び出しを展開する
+    pub fn expand(&mut self, tokens: Vec<Token>) -> Result<Vec<Token>> {
+        self.expand_tokens(&tokens, 0)
+    }
+    
+    /// `source` から読むトークンのマクロを、`expand` と同じ規則で展開しながら渡す読み出し元を作る
+    ///
+    /// トークン列全体は作らず、読み込んで持つのは読み取り中のマクロ定義か呼び出し1つ分だけ。
+    /// マクロは定義より後の呼び出しだけを展開するので、先頭から順に読めば `expand` と同じ結果になる。
+    pub fn expanding<S: TokenSource>(&mut self, source: S) -> Expanding<'_, S> {
+        Expanding { expander: self, source, ahead: VecDeque::new(), expanded: VecDeque::new() }
+    }
+    
+    fn expand_tokens(&mut self, tokens: &[Token], depth: usize) -> Result<Vec<Token>> {
+        let mut output = Vec::with_capacity(tokens.len());
+        let mut i = 0;
+        
+        while i < tokens.len() {
+            match &tokens[i].kind {
+                TokenKind::Macro => {
+                    i = self.parse_definition(tokens, i)?;
+                }
+                TokenKind::Identifier(name)
+                    if self.macros.contains_key(name.as_str())
+                        && matches!(tokens.get(i + 1).map(|t| &t.kind), Some(TokenKind::Bang))
+                        && matches!(tokens.get(i + 2).map(|t| &t.kind), Some(TokenKind::LeftParen)) =>
+                {
+                    let location = tokens[i].location.clone();
+                    let close = matching_close(tokens, i + 2).ok_or_else(|| {
+                        parse_error(format!("マクロ呼び出し '{}!' の括弧が閉じられていません", name), &location)
+                    })?;
+                    
+                    let expanded = self.expand_invocation(name, &tokens[i + 3..close], &location, depth)?;
+                    output.extend(expanded);
+                    i = close + 1;
+                }
+                _ => {
+                    output.push(tokens[i].clone());
+                    i += 1;
+                }
+            }
+        }
+        
+        Ok(output)
+    }
+    
+    /// マクロ呼び出しを1段展開し、その結果をさらに展開する
+    fn expand_invocation(
+        &mut self,
+        name: &str,
+        args: &[Token],
+        location: &SourceLocation,
+        depth: usize,
+    ) -> Result<Vec<Token>> {
+        if depth >= self.recursion_limit {
+            return Err(parse_error(
+                format!("マクロ '{}' の展開が再帰の上限（{}）を超えました", name, self.recursion_limit),
+                location,
+            ));
+        }
+        
+        let definition = self.macros[name].clone();
+        let mut expansion = None;
+        for rule in &definition.rules {
+            let mut bindings = HashMap::new();
+            if match_pattern(&rule.pattern, args, &mut bindings) {
+                expansion = Some(self.instantiate(rule, &bindings, location));
+                break;
+            }
+        }
+        
+        let expansion = expansion.ok_or_else(|| {
+            parse_error(
+                format!("マクロ '{}' のどの規則にも一致しません: {}!({})", name, name, join_tokens(args)),
+                location,
+            )
+        })?;
+        
+        if self.trace_enabled {
+            self.trace.push(format!(
+                "{}{}!({}) => {}",
+                "  ".repeat(depth),
+                name,
+                join_tokens(args),
+                join_tokens(&expansion)
+            ));
+        }
+        
+        self.expand_tokens(&expansion, depth + 1)
+    }
+    
+    /// 規則の本体にキャプチャを埋め込み、展開結果のトークン列を作る
+    fn instantiate(
+        &mut self,
+        rule: &MacroRule,
+        bindings: &HashMap<String, Vec<Token>>,
+        location: &SourceLocation,
+    ) -> Vec<Token> {
+        let expansion_id = self.next_expansion_id;
+        self.next_expansion_id += 1;
+        
+        // 本体で導入される束縛名を収集（キャプチャ由来の名前は対象外）
+        let mut introduced = HashSet::new();
+        let mut body_tokens = rule.body.iter().filter_map(|f| match f {
+            MacroFragment::Token(token) => Some(&token.kind),
+            MacroFragment::Capture(_) => None,
+        }).peekable();
+        while let Some(kind) = body_tokens.next() {
+            if matches!(kind, TokenKind::Let | TokenKind::Var) {
+                if body_tokens.peek() == Some(&&TokenKind::Mut) {
+                    body_tokens.next();
+                }
+                if let Some(TokenKind::Identifier(name)) = body_tokens.peek() {
+                    introduced.insert(name.clone());
+                }
+            }
+        }
+        
+        let mut output = Vec::new();
+        for fragment in &rule.body {
+            match fragment {
+                MacroFragment::Token(token) => {
+                    let kind = match &token.kind {
+                        TokenKind::Identifier(name) if introduced.contains(name) => {
+                            TokenKind::Identifier(Name::new(&format!("{}#{}", name, expansion_id)))
+                        }
+                        kind => kind.clone(),
+                    };
+                    output.push(Token::new(kind, location.clone()));
+                }
+                MacroFragment::Capture(name) => {
+                    let captured = &bindings[name];
+                    if is_expression(captured) && captured.len() > 1 {
+                        output.extend(parenthesize(captured.clone(), location));
+                    } else {
+                        output.extend(captured.iter().cloned());
+                    }
+                }
+            }
+        }
+        
+        // 式として展開される場合は優先順位を保つために括弧で囲む
+        if is_expression(&output) && output.len() > 1 {
+            parenthesize(output, location)
+        } else {
+            output
+        }
+    }
+    
+    /// `macro name { (パターン) => { 本体 }; ... }` または
+    /// `macro name(パターン) { 本体 }` を解析して登録し、定義の直後の位置を返す
+    fn parse_definition(&mut self, tokens: &[Token], start: usize) -> Result<usize> {
+        let location = tokens[start].location.clone();
+        let name = match tokens.get(start + 1).map(|t| &t.kind) {
+            Some(TokenKind::Identifier(name)) => name.to_string(),
+            _ => return Err(parse_error("マクロ名が必要です".to_string(), &location)),
+        };
+        
+        let mut rules = Vec::new();
+        let mut i = start + 2;
+        
+        match tokens.get(i).map(|t| &t.kind) {
+            // 単一規則の省略形
+            Some(TokenKind::LeftParen) => {
+                let (rule, next) = self.parse_rule(tokens, i, false)?;
+                rules.push(rule);
+                i = next;
+            }
+            Some(TokenKind::LeftBrace) => {
+                let close = matching_close(tokens, i).ok_or_else(|| {
+                    parse_error(format!("マクロ '{}' の定義が閉じられていません", name), &location)
+                })?;
+                i += 1;
+                while i < close {
+                    let (rule, next) = self.parse_rule(tokens, i, true)?;
+                    rules.push(rule);
+                    i = next;
+                    if i < close && tokens[i].kind == TokenKind::Semicolon {
+                        i += 1;
+                    }
+                }
+                i = close + 1;
+            }
+            _ => {
+                return Err(parse_error(
+                    format!("マクロ '{}' の定義には '(' または '{{' が必要です", name),
+                    &location,
+                ))
+            }
+        }
+        
+        if rules.is_empty() {
+            return Err(parse_error(format!("マクロ '{}' に規則がありません", name), &location));
+        }
+        
+        self.macros.insert(name.clone(), MacroDefinition { name, location, rules });
+        Ok(i)
+    }
+    
+    /// `(パターン) => { 本体 }`（`with_arrow` が偽なら `=>` なし）を解析
+    fn parse_rule(&self, tokens: &[Token], start: usize, with_arrow: bool) -> Result<(MacroRule, usize)> {
+        let location = tokens[start].location.clone();
+        if tokens[start].kind != TokenKind::LeftParen {
+            return Err(parse_error("マクロの規則は '(' で始める必要があります".to_string(), &location));
+        }
+        let pattern_close = matching_close(tokens, start)
+            .ok_or_else(|| parse_error("マクロのパターンが閉じられていません".to_string(), &location))?;
+        let pattern = to_fragments(&tokens[start + 1..pattern_close])?;
+        
+        let mut i = pattern_close + 1;
+        if with_arrow {
+            match tokens.get(i).map(|t| &t.kind) {
+                Some(TokenKind::FatArrow) => i += 1,
+                _ => return Err(parse_error("マクロのパターンの後には '=>' が必要です".to_string(), &location)),
+            }
+        }
+        
+        match tokens.get(i).map(|t| &t.kind) {
+            Some(TokenKind::LeftBrace) => {}
+            _ => return Err(parse_error("マクロの本体には '{' が必要です".to_string(), &location)),
+        }
+        let body_close = matching_close(tokens, i)
+            .ok_or_else(|| parse_error("マクロの本体が閉じられていません".to_string(), &location))?;
+        let body = to_fragments(&tokens[i + 1..body_close])?;
+        
+        // 本体で使うキャプチャはパターンで定義されていなければならない
+        let captures: HashSet<&String> = pattern.iter().filter_map(|f| match f {
+            MacroFragment::Capture(name) => Some(name),
+            _ => None,
+        }).collect();
+        for fragment in &body {
+            if let MacroFragment::Capture(name) = fragment {
+                if !captures.contains(name) {
+                    return Err(parse_error(format!("未定義のキャプチャ '${}' がマクロ本体で使われています", name), &location));
+                }
+            }
+        }
+        
+        Ok((MacroRule { pattern, body }, body_close + 1))
+    }
+}
+
+/// `MacroExpander::expanding` が返すトークンの読み出し元
+pub struct Expanding<'m, S> {
+    expander: &'m mut MacroExpander,
+    source: S,
+    /// 読み出し元から先読みして、まだ調べていないトークン
+    ahead: VecDeque<Token>,
+    /// 展開し終えた、そのまま返すトークン
+    expanded: VecDeque<Token>,
+}
+
+impl<S: TokenSource> Expanding<'_, S> {
+    fn read(&mut self) -> Result<Token> {
+        match self.ahead.pop_front() {
+            Some(token) => Ok(token),
+            None => self.source.next_token(),
+        }
+    }
+    
+    /// `macro name ...` の定義をトークン列として読む（定義の誤りは `parse_definition` が報告する）
+    fn read_definition(&mut self, keyword: Token) -> Result<Vec<Token>> {
+        let mut tokens = vec![keyword];
+        match self.read()? {
+            token if token.kind == TokenKind::Eof => {
+                self.ahead.push_front(token);
+                return Ok(tokens);
+            }
+            name => tokens.push(name),
+        }
+        let next = self.read()?;
+        match next.kind {
+            // 単一規則の省略形 `macro name(パターン) { 本体 }`
+            TokenKind::LeftParen => {
+                self.read_group(next, &mut tokens)?;
+                let body = self.read()?;
+                if body.kind == TokenKind::LeftBrace {
+                    self.read_group(body, &mut tokens)?;
+                } else {
+                    self.ahead.push_front(body);
+                }
+            }
+            TokenKind::LeftBrace => self.read_group(next, &mut tokens)?,
+            _ => self.ahead.push_front(next),
+        }
+        Ok(tokens)
+    }
+    
+    /// 開き括弧 `open` から対応する閉じ括弧までを `tokens` に読む（閉じられていなければ `Eof` の手前まで）
+    fn read_group(&mut self, open: Token, tokens: &mut Vec<Token>) -> Result<()> {
+        let mut depth = 0;
+        let mut token = open;
+        loop {
+            if token.kind == TokenKind::Eof {
+                self.ahead.push_front(token);
+                return Ok(());
+            }
+            depth += nesting_delta(&token.kind);
+            tokens.push(token);
+            if depth == 0 {
+                return Ok(());
+            }
+            token = self.read()?;
+        }
+    }
+}
+
+impl<S: TokenSource> TokenSource for Expanding<'_, S> {
+    fn next_token(&mut self) -> Result<Token> {
+        loop {
+            if let Some(token) = self.expanded.pop_front() {
+                return Ok(token);
+            }
+            let token = self.read()?;
+            let name = match &token.kind {
+                TokenKind::Macro => {
+                    let definition = self.read_definition(token)?;
+                    self.expander.parse_definition(&definition, 0)?;
+                    continue;
+                }
+                TokenKind::Identifier(name) if self.expander.is_defined(name) => name.to_string(),
+                _ => return Ok(token),
+            };
+            
+            // `name!(...)` でなければ、先読みしたトークンは後で調べる
+            let bang = self.read()?;
+            if bang.kind != TokenKind::Bang {
+                self.ahead.push_front(bang);
+                return Ok(token);
+            }
+            let open = self.read()?;
+            if open.kind != TokenKind::LeftParen {
+                self.ahead.push_front(open);
+                self.ahead.push_front(bang);
+                return Ok(token);
+            }
+            let mut group = Vec::new();
+            self.read_group(open, &mut group)?;
+            if matching_close(&group, 0) != Some(group.len() - 1) {
+                return Err(parse_error(format!("マクロ呼び出し '{}!' の括弧が閉じられていません", name), &token.location));
+            }
+            let expansion = self.expander.expand_invocation(&name, &group[1..group.len() - 1], &token.location, 0)?;
+            self.expanded.extend(expansion);
+        }
+    }
+}
+
+/// パーサーと同じ形式の構文エラーを作成
+fn parse_error(message: String, location: &SourceLocation) -> EidosError {
+    EidosError::Parser {
+        message,
+        file: location.file.clone(),
+        line: location.line,
+        column: location.column,
+    }
+}
+
+/// `$name` をキャプチャとして扱い、トークン列を規則の要素に変換
+fn to_fragments(tokens: &[Token]) -> Result<Vec<MacroFragment>> {
+    let mut fragments = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if tokens[i].kind == TokenKind::Dollar {
+            match tokens.get(i + 1).map(|t| &t.kind) {
+                Some(TokenKind::Identifier(name)) => {
+                    fragments.push(MacroFragment::Capture(name.to_string()));
+                    i += 2;
+                    continue;
+                }
+                _ => return Err(parse_error("'$' の後にはキャプチャ名が必要です".to_string(), &tokens[i].location)),
+            }
+        }
+        fragments.push(MacroFragment::Token(tokens[i].clone()));
+        i += 1;
+    }
+    Ok(fragments)
+}
+
+/// トークンが括弧の深さをどれだけ変えるか
+fn nesting_delta(kind: &TokenKind) -> i32 {
+    match kind {
+        TokenKind::LeftParen | TokenKind::LeftBrace | TokenKind::LeftBracket | TokenKind::DSLStart(_) => 1,
+        TokenKind::RightParen | TokenKind::RightBrace | TokenKind::RightBracket => -1,
+        _ => 0,
+    }
+}
+
+/// `open` の位置にある開き括弧に対応する閉じ括弧の位置を探す
+fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        depth += nesting_delta(&token.kind);
+        if depth == 0 {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// パターンとトークン列を照合し、キャプチャを `bindings` に記録する
+///
+/// キャプチャは括弧の対応が取れた1個以上のトークンに一致し、短い一致から順に試す。
+fn match_pattern(
+    pattern: &[MacroFragment],
+    tokens: &[Token],
+    bindings: &mut HashMap<String, Vec<Token>>,
+) -> bool {
+    let (first, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => return tokens.is_empty(),
+    };
+    
+    match first {
+        MacroFragment::Token(expected) => {
+            tokens.first().map_or(false, |t| t.kind == expected.kind)
+                && match_pattern(rest, &tokens[1..], bindings)
+        }
+        MacroFragment::Capture(name) => {
+            let mut depth = 0;
+            for end in 1..=tokens.len() {
+                depth += nesting_delta(&tokens[end - 1].kind);
+                if depth < 0 {
+                    return false;
+                }
+                if depth != 0 {
+                    continue;
+                }
+                
+                let mut candidate = bindings.clone();
+                candidate.insert(name.clone(), tokens[..end].to_vec());
+                if match_pattern(rest, &tokens[end..], &mut candidate) {
+                    *bindings = candidate;
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+/// トークン列が（文ではなく）式として扱えるかどうか
+fn is_expression(tokens: &[Token]) -> bool {
+    if let Some(first) = tokens.first() {
+        if matches!(first.kind, TokenKind::Let | TokenKind::Var | TokenKind::Macro | TokenKind::Syntax) {
+            return false;
+        }
+    }
+    
+    let mut depth = 0;
+    for token in tokens {
+        depth += nesting_delta(&token.kind);
+        if depth == 0 && token.kind == TokenKind::Semicolon {
+            return false;
+        }
+    }
+    true
+}
+
+/// トークン列を括弧で囲む
+fn parenthesize(tokens: Vec<Token>, location: &SourceLocation) -> Vec<Token> {
+    let mut output = Vec::with_capacity(tokens.len() + 2);
+    output.push(Token::new(TokenKind::LeftParen, location.clone()));
+    output.extend(tokens);
+    output.push(Token::new(TokenKind::RightParen, location.clone()));
+    output
+}
+
+fn join_tokens(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(|t| t.source_text())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    
+    use super::*;
+    use crate::frontend::lexer::Lexer;
+    
+    fn expand(source: &str, expander: &mut MacroExpander) -> Result<String> {
+        let tokens = Lexer::new(source, PathBuf::from("<test>")).tokenize()?;
+        let expanded = expander.expand(tokens)?;
+        let tokens: Vec<Token> = expanded.into_iter().filter(|t| t.kind != TokenKind::Eof).collect();
+        Ok(join_tokens(&tokens))
+    }
+    
+    /// トークン列を作らずに展開し、`expand` と同じ形で返す
+    fn expand_streaming(source: &str, expander: &mut MacroExpander) -> Result<String> {
+        let mut tokens = expander.expanding(Lexer::new(source, PathBuf::from("<test>")));
+        let mut output = Vec::new();
+        loop {
+            let token = tokens.next_token()?;
+            if token.kind == TokenKind::Eof {
+                return Ok(join_tokens(&output));
+            }
+            output.push(token);
+        }
+    }
+    
+    #[test]
+    fn test_expand_preserves_precedence() {
+        let mut expander = MacroExpander::new();
+        let output = expand("macro square($x) { $x * $x } let y = square!(1 + 2);", &mut expander).unwrap();
+        
+        assert_eq!(output, "let y = ( ( 1 + 2 ) * ( 1 + 2 ) ) ;");
+    }
+    
+    #[test]
+    fn test_multiple_rules() {
+        let mut expander = MacroExpander::new();
+        let source = "macro add { ($a, $b) => { $a + $b }; ($a) => { $a }; } add!(1); add!(1, 2);";
+        let output = expand(source, &mut expander).unwrap();
+        
+        assert_eq!(output, "1 ; ( 1 + 2 ) ;");
+    }
+    
+    #[test]
+    fn test_hygiene_renames_introduced_bindings() {
+        let mut expander = MacroExpander::new();
+        let source = "macro init($v) { let tmp = $v; } let tmp = 1; init!(tmp)";
+        let output = expand(source, &mut expander).unwrap();
+        
+        assert_eq!(output, "let tmp = 1 ; let tmp#0 = tmp ;");
+    }
+    
+    #[test]
+    fn test_recursion_limit() {
+        let mut expander = MacroExpander::new().with_recursion_limit(8);
+        let result = expand("macro forever($x) { forever!($x) } forever!(1)", &mut expander);
+        
+        assert!(result.is_err());
+    }
+    
+    #[test]
+    fn test_trace() {
+        let mut expander = MacroExpander::new().with_trace(true);
+        expand("macro double($x) { $x + $x } double!(3)", &mut expander).unwrap();
+        
+        assert_eq!(expander.trace(), ["double!(3) => ( 3 + 3 )"]);
+    }
+    
+    #[test]
+    fn test_streaming_matches_expand() {
+        for source in [
+            "macro square($x) { $x * $x } let y = square!(1 + 2);",
+            "macro add { ($a, $b) => { $a + $b }; ($a) => { $a }; } add!(1); add!(add!(1), 2);",
+            // 定義より前の呼び出しと、呼び出しでない同名の識別子はそのまま残す
+            "f!(1); macro f($x) { $x } let f = 1; f ! 2; f!(f!(3))",
+            "macro inc($x) { $x + 1 } macro twice($x) { inc!($x) * 2 } twice!(inc!(4))",
+        ] {
+            let expected = expand(source, &mut MacroExpander::new()).unwrap();
+            assert_eq!(expand_streaming(source, &mut MacroExpander::new()).unwrap(), expected, "{}", source);
+        }
+        
+        for source in ["macro f($x) { $x } f!(1", "macro f($x) { $x", "macro 1 { }"] {
+            let expected = expand(source, &mut MacroExpander::new()).unwrap_err().to_string();
+            assert_eq!(expand_streaming(source, &mut MacroExpander::new()).unwrap_err().to_string(), expected, "{}", source);
+        }
+    }
+}
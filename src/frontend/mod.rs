@@ -1,9 +1,30 @@
 pub mod lexer;
 pub mod parser;
+pub mod macro_expander;
 pub mod type_checker;
+pub mod effect_checker;
+pub mod const_eval;
+pub mod modules;
+pub mod globals;
+pub mod definite_init;
 pub mod semantic_analyzer;
+pub mod incremental;
 
 pub use lexer::Lexer;
 pub use parser::Parser;
+pub use macro_expander::MacroExpander;
 pub use semantic_analyzer::SemanticAnalyzer;
-pub use type_checker::TypeChecker; 
\ No newline at end of file
+pub use type_checker::TypeChecker;
+pub use effect_checker::EffectChecker; 
+pub use const_eval::ConstEvaluator;
+pub use globals::GlobalChecker;
+pub use definite_init::InitChecker;
+pub use modules::ModuleResolver;
+pub use incremental::IncrementalAnalysis;
+
+/// テスト用に、ソースを字句解析・構文解析する（ファイル名は `<test>`）
+#[cfg(test)]
+pub(crate) fn parse_test_source(source: &str) -> crate::core::Result<crate::core::ast::Program> {
+    let tokens = Lexer::new(source, "<test>".into()).tokenize()?;
+    Parser::new(tokens, "<test>".into()).parse()
+}
\ No newline at end of file
@@ -0,0 +1,407 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::core::{Result, EidosError, Name, SourceLocation};
+use crate::core::error::did_you_mean;
+use crate::core::ast::{ASTNode, Node, Program};
+use crate::stdlib::StdlibModule;
+
+/// モジュール内の項目（関数またはモジュール）
+#[derive(Debug, Clone)]
+struct Item {
+    /// 項目を定義したモジュールのパス（トップレベルなら空）
+    module: Vec<String>,
+    is_public: bool,
+    is_module: bool,
+}
+
+/// モジュールの解決と可視性の検査
+///
+/// `mod name { ... }` の中の関数を `name::f` という修飾名のトップレベル関数に展開し、
+/// 関数本体の名前参照も修飾名に書き換える。名前は参照したモジュールから外側に向かって探す。
+/// `pub` の付いていない関数とモジュールは、定義したモジュールとその子孫からしか参照できない。
+#[derive(Default)]
+pub struct ModuleResolver {
+    /// 修飾名から項目への対応
+    items: HashMap<String, Item>,
+    /// 参照できない標準ライブラリのモジュール
+    disabled_stdlib: HashSet<StdlibModule>,
+}
+
+impl ModuleResolver {
+    /// プロジェクトの設定で無効にした標準ライブラリのモジュール（`StdlibModule::disabled`）を参照できないリゾルバ
+    pub fn new() -> Self {
+        Self {
+            items: HashMap::new(),
+            disabled_stdlib: StdlibModule::disabled(),
+        }
+    }
+    
+    /// 参照できない標準ライブラリのモジュールを指定
+    pub fn with_disabled_stdlib(mut self, modules: HashSet<StdlibModule>) -> Self {
+        self.disabled_stdlib = modules;
+        self
+    }
+    
+    /// モジュールを解決し、修飾名のトップレベル関数に展開したプログラムを返す
+    pub fn resolve(&mut self, program: Program) -> Result<Program> {
+        // 定義より前の参照も解決できるよう、先にすべての項目を集める
+        for node in &program.nodes {
+            self.collect(node, &[])?;
+        }
+        
+        let mut resolved = Program::new(program.file_path.clone());
+        let mut globals = vec![HashSet::new()];
+        for node in program.nodes {
+            self.flatten(node, &[], &mut globals, &mut resolved)?;
+        }
+        Ok(resolved)
+    }
+    
+    fn collect(&mut self, node: &ASTNode, module: &[String]) -> Result<()> {
+        let (name, is_public, is_module) = match &node.kind {
+            Node::FunctionDef { name, is_public, .. } => (name, *is_public, false),
+            Node::ModuleDef { name, is_public, .. } => (name, *is_public, true),
+            _ => return Ok(()),
+        };
+        
+        if is_module && module.is_empty() && StdlibModule::from_name(name).is_some() {
+            return Err(semantic_error(
+                format!("モジュール名 '{}' は標準ライブラリのモジュールと重複しています", name),
+                &node.location,
+            ));
+        }
+        let qualified = qualify(module, name);
+        if self.items.contains_key(&qualified) {
+            return Err(semantic_error(format!("'{}' は既に定義されています", qualified), &node.location));
+        }
+        self.items.insert(qualified, Item { module: module.to_vec(), is_public, is_module });
+        
+        if let Node::ModuleDef { name, items, .. } = &node.kind {
+            let inner = child_path(module, name);
+            for item in items {
+                self.collect(item, &inner)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// モジュールの中の関数をトップレベルに移す
+    fn flatten(
+        &self,
+        mut node: ASTNode,
+        module: &[String],
+        globals: &mut Vec<HashSet<String>>,
+        out: &mut Program,
+    ) -> Result<()> {
+        match &mut node.kind {
+            Node::ModuleDef { name, items, .. } => {
+                let inner = child_path(module, name);
+                for item in std::mem::take(items) {
+                    self.flatten(item, &inner, globals, out)?;
+                }
+                return Ok(());
+            }
+            Node::FunctionDef { name, .. } => {
+                *name = qualify(module, name);
+                // 関数本体からはトップレベルの変数を参照できない
+                self.rewrite(&mut node, module, &mut Vec::new())?;
+            }
+            _ => self.rewrite(&mut node, module, globals)?,
+        }
+        out.add_node(node);
+        Ok(())
+    }
+    
+    /// 式の中の名前参照を修飾名に書き換える（`locals` はローカル変数のスコープ）
+    fn rewrite(&self, node: &mut ASTNode, module: &[String], locals: &mut Vec<HashSet<String>>) -> Result<()> {
+        match &mut node.kind {
+            Node::Identifier { name, .. } => {
+                let is_local = !name.contains("::") && locals.iter().any(|scope| scope.contains(name.as_str()));
+                if !is_local {
+                    if let Some(qualified) = self.lookup(name, module, &node.location)? {
+                        *name = Name::new(&qualified);
+                    }
+                }
+                return Ok(());
+            }
+            Node::ModuleDef { .. } => {
+                return Err(semantic_error(
+                    "モジュールはトップレベルか他のモジュールの中でのみ定義できます".to_string(),
+                    &node.location,
+                ));
+            }
+            Node::VarDecl { name, initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.rewrite(initializer, module, locals)?;
+                }
+                if let Some(scope) = locals.last_mut() {
+                    scope.insert(name.clone());
+                }
+                return Ok(());
+            }
+            Node::TupleDecl { names, initializer, .. } => {
+                self.rewrite(initializer, module, locals)?;
+                if let Some(scope) = locals.last_mut() {
+                    scope.extend(names.iter().cloned());
+                }
+                return Ok(());
+            }
+            Node::FunctionDef { params, body, .. } => {
+                let mut scopes = vec![params.iter().map(|param| param.name.clone()).collect()];
+                return self.rewrite(body, module, &mut scopes);
+            }
+            Node::TryCatch { body, error_name, handler } => {
+                self.rewrite(body, module, locals)?;
+                locals.push(HashSet::from([error_name.clone()]));
+                let result = self.rewrite(handler, module, locals);
+                locals.pop();
+                return result;
+            }
+            Node::ForLoop { variable, iterable, body } => {
+                self.rewrite(iterable, module, locals)?;
+                locals.push(HashSet::from([variable.clone()]));
+                let result = self.rewrite(body, module, locals);
+                locals.pop();
+                return result;
+            }
+            Node::Comprehension { element, variable, iterable, condition } => {
+                self.rewrite(iterable, module, locals)?;
+                locals.push(HashSet::from([variable.clone()]));
+                let result = condition.iter_mut().chain([element]).try_for_each(|child| self.rewrite(child, module, locals));
+                locals.pop();
+                return result;
+            }
+            _ => {}
+        }
+        
+        let is_block = matches!(node.kind, Node::BlockExpr { .. });
+        if is_block {
+            locals.push(HashSet::new());
+        }
+        let mut result = Ok(());
+        for child in node.children_mut() {
+            result = self.rewrite(child, module, locals);
+            if result.is_err() {
+                break;
+            }
+        }
+        if is_block {
+            locals.pop();
+        }
+        result
+    }
+    
+    /// `module` から参照した名前を、ユーザー定義の関数の修飾名に解決する
+    ///
+    /// 標準ライブラリの関数や変数など、ユーザー定義の関数でない名前は `None` を返す。
+    fn lookup(&self, name: &str, module: &[String], location: &SourceLocation) -> Result<Option<String>> {
+        for depth in (0..=module.len()).rev() {
+            let qualified = qualify(&module[..depth], name);
+            if let Some(item) = self.items.get(&qualified) {
+                if item.is_module {
+                    return Ok(None);
+                }
+                self.check_visible(&qualified, item, module, location)?;
+                return Ok(Some(qualified));
+            }
+        }
+        
+        // `a::f` の `a` がユーザー定義のモジュールなら、`f` がないことを報告する
+        if let Some((path, function)) = name.rsplit_once("::") {
+            for depth in (0..=module.len()).rev() {
+                let qualified = qualify(&module[..depth], path);
+                if self.items.get(&qualified).is_some_and(|item| item.is_module) {
+                    let prefix = format!("{}::", qualified);
+                    let functions = self
+                        .items
+                        .iter()
+                        .filter(|(_, item)| !item.is_module)
+                        .filter_map(|(name, _)| name.strip_prefix(&prefix))
+                        .filter(|name| !name.contains("::"));
+                    return Err(semantic_error(
+                        format!(
+                            "モジュール '{}' に関数 '{}' はありません{}",
+                            qualified,
+                            function,
+                            did_you_mean(function, functions)
+                        ),
+                        location,
+                    ));
+                }
+            }
+        }
+        
+        // 無効にした標準ライブラリのモジュールの関数
+        if let Some((module_name, _)) = name.split_once("::") {
+            if StdlibModule::from_name(module_name).is_some_and(|module| self.disabled_stdlib.contains(&module)) {
+                return Err(semantic_error(
+                    format!(
+                        "標準ライブラリのモジュール '{}' はプロジェクトの設定（eidos.toml の [stdlib]）で無効にされているため、'{}' は使えません",
+                        module_name, name
+                    ),
+                    location,
+                ));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// `from` モジュールから項目が見えるかを検査する
+    ///
+    /// 途中のモジュールも含め、非公開の項目は定義したモジュールとその子孫からしか見えない。
+    fn check_visible(&self, qualified: &str, item: &Item, from: &[String], location: &SourceLocation) -> Result<()> {
+        for depth in 0..item.module.len() {
+            let path = qualify(&item.module[..depth], &item.module[depth]);
+            let visible = from.starts_with(&item.module[..depth])
+                || self.items.get(&path).is_some_and(|module| module.is_public);
+            if !visible {
+                return Err(semantic_error(
+                    format!(
+                        "モジュール '{}' は非公開です（外から使うには `pub mod {}` として宣言してください）",
+                        path, item.module[depth]
+                    ),
+                    location,
+                ));
+            }
+        }
+        
+        if !item.is_public && !from.starts_with(&item.module) {
+            let function = qualified.rsplit("::").next().unwrap_or(qualified);
+            return Err(semantic_error(
+                format!(
+                    "関数 '{}' は非公開です（モジュール '{}' の外から使うには `pub fn {}` として宣言してください）",
+                    qualified,
+                    item.module.join("::"),
+                    function
+                ),
+                location,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// モジュールのパスと名前から修飾名を作る
+fn qualify(module: &[String], name: &str) -> String {
+    if module.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", module.join("::"), name)
+    }
+}
+
+fn child_path(module: &[String], name: &str) -> Vec<String> {
+    let mut path = module.to_vec();
+    path.push(name.to_string());
+    path
+}
+
+fn semantic_error(message: String, location: &SourceLocation) -> EidosError {
+    EidosError::Semantic {
+        message,
+        file: location.file.clone(),
+        line: location.line,
+        column: location.column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::parse_test_source;
+    
+    fn resolve(source: &str) -> Result<Program> {
+        ModuleResolver::new().resolve(parse_test_source(source)?)
+    }
+    
+    /// トップレベルの関数名と、その本体で呼び出している関数名
+    fn functions(program: &Program) -> Vec<(String, Vec<String>)> {
+        fn calls(node: &ASTNode, out: &mut Vec<String>) {
+            if let Node::FunctionCall { callee, .. } = &node.kind {
+                if let Node::Identifier { name, .. } = &callee.kind {
+                    out.push(name.to_string());
+                }
+            }
+            for child in node.children() {
+                calls(child, out);
+            }
+        }
+        program
+            .nodes
+            .iter()
+            .filter_map(|node| match &node.kind {
+                Node::FunctionDef { name, body, .. } => {
+                    let mut called = Vec::new();
+                    calls(body, &mut called);
+                    Some((name.clone(), called))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_flatten_and_qualify() {
+        let program = resolve(
+            "mod geometry {
+                pub fn area(w: Int, h: Int) -> Int { scale(w) * h }
+                fn scale(x: Int) -> Int { x }
+                pub mod units { pub fn cm() -> Int { super_helper() } }
+            }
+            fn super_helper() -> Int { 1 }
+            fn main() -> Int { geometry::area(2, 3) + geometry::units::cm() + math::abs(1) }",
+        )
+        .unwrap();
+        assert_eq!(
+            functions(&program),
+            [
+                ("geometry::area".to_string(), vec!["geometry::scale".to_string()]),
+                ("geometry::scale".to_string(), vec![]),
+                ("geometry::units::cm".to_string(), vec!["super_helper".to_string()]),
+                ("super_helper".to_string(), vec![]),
+                (
+                    "main".to_string(),
+                    vec!["geometry::area".to_string(), "geometry::units::cm".to_string(), "math::abs".to_string()]
+                ),
+            ]
+        );
+    }
+    
+    #[test]
+    fn test_disabled_stdlib_modules() {
+        let program = parse_test_source("fn main() -> Int { math::abs(net::port()) }").unwrap();
+        let error = ModuleResolver::new()
+            .with_disabled_stdlib(HashSet::from([StdlibModule::Net]))
+            .resolve(program)
+            .unwrap_err();
+        assert!(error.to_string().contains("モジュール 'net' はプロジェクトの設定"), "{}", error);
+        assert!(error.to_string().contains("'net::port' は使えません"), "{}", error);
+    }
+    
+    #[test]
+    fn test_private_items_are_rejected() {
+        let error = resolve("mod a { fn secret() -> Int { 1 } } fn main() -> Int { a::secret() }").unwrap_err();
+        assert!(error.to_string().contains("関数 'a::secret' は非公開です"));
+        
+        // 子孫のモジュールからは親の非公開の関数を使える
+        assert!(resolve("mod a { fn secret() -> Int { 1 } mod b { fn f() -> Int { secret() } } }").is_ok());
+        
+        // 途中のモジュールが非公開なら、公開された関数にも外からは届かない
+        let error = resolve("mod a { mod b { pub fn f() -> Int { 1 } } } fn main() -> Int { a::b::f() }").unwrap_err();
+        assert!(error.to_string().contains("モジュール 'a::b' は非公開です"));
+        assert!(resolve("mod a { pub mod b { pub fn f() -> Int { 1 } } } fn main() -> Int { a::b::f() }").is_ok());
+    }
+    
+    #[test]
+    fn test_resolution_errors() {
+        let error = resolve("mod a { pub fn factorial() -> Int { 1 } } fn main() -> Int { a::facorial() }").unwrap_err();
+        assert!(error.to_string().contains("関数 'facorial' はありません（もしかして: 'factorial'）"), "{}", error);
+        assert!(resolve("mod a { fn f() {} fn f() {} }").is_err());
+        assert!(resolve("mod math { pub fn abs(x: Int) -> Int { x } }").is_err());
+        
+        // 引数やローカル変数は同名の関数より優先する
+        let program = resolve("mod a { fn f() -> Int { 1 } fn g(f: Int) -> Int { let h = f; h } }").unwrap();
+        let body = format!("{:?}", program.nodes[1]);
+        assert!(body.contains("name: \"f\"") && !body.contains("a::f"));
+    }
+}
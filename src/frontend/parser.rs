@@ -1,94 +1,1772 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Index, IndexMut};
+use std::path::{Path, PathBuf};
 
-use crate::core::{Result, EidosError, SourceLocation};
-use crate::core::ast::{ASTNode, Node, Program, Literal, UnaryOp, BinaryOp, FunctionParam};
-use super::lexer::{Token, TokenKind};
+use crate::core::{Result, EidosError, Name, SourceLocation};
+use crate::core::ast::{ASTNode, Node, Program, Literal, UnaryOp, BinaryOp, FunctionParam, GlobalKind, KeywordAlias, SyntaxFragment, SyntaxRule, TypeInfo};
+use crate::core::eir::AsmDialect;
+use crate::core::limits;
+use crate::core::types::{NumericType, Type, LIST_TYPE_NAME, OPTION_TYPE_NAME, RANGE_TYPE_NAME, RESULT_TYPE_NAME};
+use crate::dsl::DSLProcessor;
+use super::lexer::{offside_rule, KeywordMap, Token, TokenKind, TokenSource, KEYWORDS};
+
+/// 字句解析では識別子だが、構文解析で位置によって予約語として扱う単語
+pub const CONTEXTUAL_KEYWORDS: &[&str] = &["pub", "mod", "use", "dsl", "effect", "const", "async", "await", "static", "try", "catch", "defer", "require", "ensure", "provide", "comptime", "rule", "keyword", "layout"];
+
+/// 字句解析器から読むときに、現在のトークンより先に読んでおくトークン数（`peek_at` で見られる範囲）
+const TOKEN_LOOKAHEAD: usize = 8;
+
+/// 構文解析器が読むトークン列
+///
+/// 字句解析器などの読み出し元から読む場合は、現在のトークンと `TOKEN_LOOKAHEAD` 個の先読み、直前のトークンだけを持ち、
+/// それより前のトークンは捨てる。構文解析は後戻りしないので、トークン列全体を作らずに済む。
+struct TokenBuffer<'a> {
+    tokens: VecDeque<Token>,
+    /// `tokens` の先頭のトークンの位置
+    start: usize,
+    /// まだ読んでいないトークンを返す読み出し元（`Eof` かエラーまで読んだら `None`）
+    source: Option<Box<dyn TokenSource + 'a>>,
+    /// 字句解析器が返したエラー（トークン列はそこで `Eof` にする）
+    error: Option<EidosError>,
+    /// 有効な予約語の別名。読み込んだトークンにも順に当てはめる
+    localizations: Vec<Localization>,
+}
+
+impl<'a> TokenBuffer<'a> {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens: tokens.into(), start: 0, source: None, error: None, localizations: Vec::new() }
+    }
+    
+    fn from_source(source: impl TokenSource + 'a) -> Self {
+        let mut buffer = Self { source: Some(Box::new(source)), ..Self::new(Vec::new()) };
+        buffer.fill(TOKEN_LOOKAHEAD);
+        buffer
+    }
+    
+    fn get(&self, index: usize) -> Option<&Token> {
+        index.checked_sub(self.start).and_then(|index| self.tokens.get(index))
+    }
+    
+    /// 読み込み済みの最後のトークン
+    fn last(&self) -> &Token {
+        self.tokens.back().expect("トークン列には少なくとも Eof がある")
+    }
+    
+    /// 現在のトークンが `current` になったときに、先読みを補って直前より前のトークンを捨てる
+    fn advance_to(&mut self, current: usize) {
+        self.fill(current + TOKEN_LOOKAHEAD);
+        while self.start + 1 < current && self.tokens.len() > 1 {
+            self.tokens.pop_front();
+            self.start += 1;
+        }
+    }
+    
+    /// `end` 番目のトークンまで読み出し元から読み込む
+    fn fill(&mut self, end: usize) {
+        while self.start + self.tokens.len() <= end {
+            let Some(source) = &mut self.source else {
+                return;
+            };
+            let mut token = match source.next_token() {
+                Ok(token) => token,
+                Err(error) => {
+                    let location = self.tokens.back().map_or_else(SourceLocation::unknown, |token| token.location.clone());
+                    self.error = Some(error);
+                    Token::new(TokenKind::Eof, location)
+                }
+            };
+            for localization in &mut self.localizations {
+                localization.apply(&mut token);
+            }
+            self.localizations.retain(|localization| !localization.finished);
+            if token.kind == TokenKind::Eof {
+                self.source = None;
+            }
+            self.tokens.push_back(token);
+        }
+    }
+    
+    /// `from` 番目のトークンから、現在のスコープの終わりまで予約語の別名を読み替える
+    fn localize(&mut self, from: usize, keywords: KeywordMap) {
+        let mut localization = Localization { keywords, blocks: Vec::new(), finished: false };
+        let skip = from.saturating_sub(self.start);
+        for token in self.tokens.iter_mut().skip(skip) {
+            localization.apply(token);
+        }
+        if !localization.finished && self.source.is_some() {
+            self.localizations.push(localization);
+        }
+    }
+}
+
+impl Index<usize> for TokenBuffer<'_> {
+    type Output = Token;
+    
+    fn index(&self, index: usize) -> &Token {
+        self.get(index).expect("読み込み済みのトークンの位置")
+    }
+}
+
+impl IndexMut<usize> for TokenBuffer<'_> {
+    fn index_mut(&mut self, index: usize) -> &mut Token {
+        &mut self.tokens[index - self.start]
+    }
+}
+
+/// 予約語の別名の読み替え（スコープを閉じる '}' まで。DSLブロックの中身は読み替えない）
+struct Localization {
+    keywords: KeywordMap,
+    /// 開いている括弧ごとに、DSLブロックかどうか
+    blocks: Vec<bool>,
+    /// スコープを閉じる '}' まで読み替えた
+    finished: bool,
+}
+
+impl Localization {
+    fn apply(&mut self, token: &mut Token) {
+        if self.finished {
+            return;
+        }
+        match token.kind {
+            TokenKind::LeftBrace => self.blocks.push(false),
+            TokenKind::DSLStart(_) => self.blocks.push(true),
+            TokenKind::RightBrace => self.finished = self.blocks.pop().is_none(),
+            _ if !self.blocks.contains(&true) => self.keywords.apply(token),
+            _ => {}
+        }
+    }
+}
 
 /// 構文解析器
-pub struct Parser {
-    tokens: Vec<Token>,
+pub struct Parser<'a> {
+    tokens: TokenBuffer<'a>,
     current: usize,
     file_path: PathBuf,
+    /// 有効化されたDSLのスコープ（先頭がファイル全体、以降はネストしたブロック）
+    dsl_scopes: Vec<Vec<String>>,
+    /// DSLブロックが有効化済みかどうかを確認するか（DSLの展開結果の解析では外側で確認済み）
+    check_dsl_scope: bool,
+    /// 型プロバイダ（`provide`）が生成した型（型注釈の名前から引く）
+    provided_types: HashMap<String, Type>,
 }
 
-impl Parser {
+impl<'a> Parser<'a> {
     pub fn new(tokens: Vec<Token>, file_path: PathBuf) -> Self {
+        Self::with_tokens(TokenBuffer::new(tokens), file_path)
+    }
+    
+    /// 字句解析器（またはマクロ展開などを挟んだ読み出し元）から必要な分だけトークンを読む構文解析器を作成
+    ///
+    /// トークン列全体を作らないので、生成された大きなソースも先読みの分のメモリで解析できる。
+    /// 字句解析やマクロ展開のエラーは、そこまでの解析で構文エラーがなければ `parse` が返す。
+    pub fn from_lexer(lexer: impl TokenSource + 'a, file_path: PathBuf) -> Self {
+        Self::with_tokens(TokenBuffer::from_source(lexer), file_path)
+    }
+    
+    fn with_tokens(tokens: TokenBuffer<'a>, file_path: PathBuf) -> Self {
         Self {
             tokens,
             current: 0,
             file_path,
+            dsl_scopes: vec![Vec::new()],
+            check_dsl_scope: true,
+            provided_types: HashMap::new(),
+        }
+    }
+    
+    /// DSLの展開結果を解析する構文解析器を作成
+    ///
+    /// 展開結果に含まれるDSLブロックは、展開前のブロックを読み取った時点で有効化を確認している。
+    pub fn for_expansion(tokens: Vec<Token>, file_path: PathBuf) -> Self {
+        Self {
+            check_dsl_scope: false,
+            ..Self::new(tokens, file_path)
+        }
+    }
+    
+    /// プログラム全体を解析
+    pub fn parse(&mut self) -> Result<Program> {
+        // ファイルパスを文字列に変換
+        let file_path_str = self.file_path.to_string_lossy().to_string();
+        
+        // プログラムオブジェクトを初期化
+        let mut program = Program::new(file_path_str);
+        
+        // EOFまで解析を続ける
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(node) => {
+                    program.add_node(node);
+                }
+                Err(e) => {
+                    // 字句解析のエラーで打ち切ったトークン列の構文エラーより、字句解析のエラーを報告する
+                    return Err(self.tokens.error.take().unwrap_or(e));
+                }
+            }
+        }
+        
+        match self.tokens.error.take() {
+            Some(error) => Err(error),
+            None => Ok(program),
+        }
+    }
+    
+    /// 宣言を解析
+    fn declaration(&mut self) -> Result<ASTNode> {
+        match self.peek().kind.clone() {
+            TokenKind::Let | TokenKind::Var => self.var_declaration(),
+            TokenKind::Fn => self.function_declaration(),
+            TokenKind::Hash => self.attributed_declaration(),
+            TokenKind::Identifier(_) if self.function_starts_at(0) => self.function_declaration(),
+            TokenKind::Identifier(word) if (word == "const" || word == "static") && self.global_starts_at(1) => {
+                self.global_declaration()
+            }
+            TokenKind::Identifier(word) if word == "pub" && (self.check_next(&TokenKind::Fn) || self.check_next(&TokenKind::Identifier(Name::default()))) => {
+                self.public_declaration()
+            }
+            TokenKind::Identifier(word) if word == "mod" && self.check_next(&TokenKind::Identifier(Name::default())) => {
+                self.module_declaration()
+            }
+            TokenKind::Syntax => self.syntax_declaration(),
+            TokenKind::Peg => self.peg_declaration(),
+            TokenKind::Identifier(word) if word == "use" && self.check_next_word("dsl") => self.use_dsl_declaration(),
+            TokenKind::Identifier(word) if word == "provide" && self.check_next(&TokenKind::Identifier(Name::default())) => {
+                self.provide_declaration()
+            }
+            TokenKind::Identifier(word) if word == "defer" && matches!(self.peek_at(1).kind, TokenKind::Identifier(_) | TokenKind::LeftBrace) => {
+                self.defer_statement()
+            }
+            TokenKind::Identifier(word) => {
+                // 有効なDSLがこのキーワードで始まる構文を持つ場合は、その文をDSLとして展開する
+                match DSLProcessor::new().keyword_owner(&self.active_dsls(), &word) {
+                    Some(name) => self.inline_dsl_statement(name),
+                    None => self.expression_statement(),
+                }
+            }
+            _ => self.expression_statement(),
+        }
+    }
+    
+    /// 変数宣言を解析（`let x = ...;` / `let mut x = ...;` / `var x = ...;`）
+    fn var_declaration(&mut self) -> Result<ASTNode> {
+        let keyword = self.advance();
+        let location = keyword.location.clone();
+        
+        // `var` は常に可変、`let` は `mut` が付いた場合のみ可変
+        let is_mutable = match keyword.kind {
+            TokenKind::Var => true,
+            _ => self.match_token(&TokenKind::Mut),
+        };
+        if self.check(&TokenKind::LeftParen) {
+            return self.tuple_declaration(location, is_mutable);
+        }
+        
+        let name = self.consume_identifier("変数名が必要です")?;
+        
+        let type_annotation = if self.match_token(&TokenKind::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        
+        let initializer = if self.match_token(&TokenKind::Equal) {
+            Some(Box::new(self.expression()?))
+        } else {
+            None
+        };
+        
+        self.consume(&TokenKind::Semicolon, "変数宣言の後には ';' が必要です")?;
+        
+        Ok(ASTNode::new(
+            Node::VarDecl {
+                name,
+                symbol: None,
+                type_annotation,
+                initializer,
+                is_mutable,
+            },
+            location,
+        ))
+    }
+    
+    /// タプルを分解する変数宣言を解析（`let (x, y) = ...;`、`let` と `mut` は読み込み済み）
+    fn tuple_declaration(&mut self, location: SourceLocation, is_mutable: bool) -> Result<ASTNode> {
+        self.consume(&TokenKind::LeftParen, "'(' が必要です")?;
+        let mut names: Vec<String> = Vec::new();
+        while !self.check(&TokenKind::RightParen) && !self.is_at_end() {
+            let name_location = self.peek().location.clone();
+            let name = self.consume_identifier("分解する変数名が必要です")?;
+            if name != "_" && names.contains(&name) {
+                return Err(EidosError::Parser {
+                    message: format!("変数 '{}' を同じ宣言で2回束縛しています", name),
+                    file: self.file_path.clone(),
+                    line: name_location.line,
+                    column: name_location.column,
+                });
+            }
+            names.push(name);
+            if !self.match_token(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightParen, "分解する変数名の後には ')' が必要です")?;
+        if names.len() < 2 {
+            return Err(EidosError::Parser {
+                message: "タプルの分解には2つ以上の変数名が必要です".to_string(),
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            });
+        }
+        
+        let type_annotation = if self.match_token(&TokenKind::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        self.consume(&TokenKind::Equal, "タプルを分解する宣言には '=' と初期化式が必要です")?;
+        let initializer = Box::new(self.expression()?);
+        self.consume(&TokenKind::Semicolon, "変数宣言の後には ';' が必要です")?;
+        
+        Ok(ASTNode::new(Node::TupleDecl { names, type_annotation, initializer, is_mutable }, location))
+    }
+    
+    /// グローバル変数の宣言を解析（`const N: Int = 1;` / `static N: Int = 1;` / `static mut N: Int = 0;`）
+    fn global_declaration(&mut self) -> Result<ASTNode> {
+        let location = self.peek().location.clone();
+        let kind = if self.match_word("const") {
+            GlobalKind::Const
+        } else {
+            self.advance();
+            if self.match_token(&TokenKind::Mut) {
+                GlobalKind::StaticMut
+            } else {
+                GlobalKind::Static
+            }
+        };
+        
+        let name = self.consume_identifier("グローバル変数名が必要です")?;
+        let type_annotation = if self.match_token(&TokenKind::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        
+        if !self.check(&TokenKind::Equal) {
+            return Err(EidosError::Parser {
+                message: format!("{} の宣言には初期化式が必要です（`{} {} = 値;`）", kind.keyword(), kind.keyword(), name),
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            });
+        }
+        self.advance();
+        let initializer = Box::new(self.expression()?);
+        self.consume(&TokenKind::Semicolon, "グローバル変数の宣言の後には ';' が必要です")?;
+        
+        Ok(ASTNode::new(
+            Node::GlobalDecl {
+                name,
+                type_annotation,
+                initializer,
+                kind,
+            },
+            location,
+        ))
+    }
+    
+    /// 関数定義を解析（`fn name(a: Int) -> Int { ... }` / `effect fn name() { ... }` / `const fn name() { ... }`）
+    ///
+    /// 先頭に `pub` があれば公開する関数として扱う。
+    fn function_declaration(&mut self) -> Result<ASTNode> {
+        let location = self.peek().location.clone();
+        let is_public = self.match_word("pub");
+        
+        // `effect`・`const`・`async` は `fn` の直前でのみキーワードとして扱う（`async effect fn` のように重ねられる）
+        let mut is_effectful = false;
+        let mut is_const = false;
+        let mut is_async = false;
+        while let TokenKind::Identifier(word) = self.peek().kind.clone() {
+            let modifier = match word.as_str() {
+                "effect" => &mut is_effectful,
+                "const" => &mut is_const,
+                "async" => &mut is_async,
+                _ => break,
+            };
+            *modifier = true;
+            self.advance();
+        }
+        if is_const && is_async {
+            return Err(EidosError::Parser {
+                message: "'const fn' は 'async' にできません".to_string(),
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            });
+        }
+        self.consume(&TokenKind::Fn, "'fn' が必要です")?;
+        let name = self.consume_identifier("関数名が必要です")?;
+        
+        self.consume(&TokenKind::LeftParen, "関数名の後には '(' が必要です")?;
+        let mut params = Vec::new();
+        if !self.check(&TokenKind::RightParen) {
+            loop {
+                let name = self.consume_identifier("引数名が必要です")?;
+                let param_type = if self.match_token(&TokenKind::Colon) {
+                    Some(self.parse_type()?)
+                } else {
+                    None
+                };
+                params.push(FunctionParam { name, symbol: None, param_type });
+                if !self.match_token(&TokenKind::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(&TokenKind::RightParen, "引数リストの後には ')' が必要です")?;
+        
+        // 戻り値の型は `-> T` と `: T` のどちらでも書ける
+        let return_type = if self.match_token(&TokenKind::Arrow) || self.match_token(&TokenKind::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        
+        // 契約（`require 条件`・`ensure 条件`）は戻り値の型の後、本体の前に並べる
+        let mut requires = Vec::new();
+        let mut ensures = Vec::new();
+        loop {
+            if self.match_word("require") {
+                requires.push(self.expression()?);
+            } else if self.match_word("ensure") {
+                ensures.push(self.expression()?);
+            } else {
+                break;
+            }
+        }
+        
+        let brace = self.consume(&TokenKind::LeftBrace, "関数本体には '{' が必要です")?;
+        let mut body = Box::new(self.block(brace.location)?);
+        if !requires.is_empty() || !ensures.is_empty() {
+            body = Box::new(Self::contract_body(&name, *body, requires, ensures));
+        }
+        
+        Ok(ASTNode::new(
+            Node::FunctionDef {
+                name,
+                symbol: None,
+                params,
+                return_type,
+                body,
+                is_effectful,
+                is_const,
+                is_async,
+                attributes: Vec::new(),
+                is_public,
+            },
+            location,
+        ))
+    }
+    
+    /// `pub` の付いた宣言を解析。`pub` を付けられるのは関数とモジュールの定義だけ
+    fn public_declaration(&mut self) -> Result<ASTNode> {
+        if self.function_starts_at(1) {
+            return self.function_declaration();
+        }
+        if matches!(&self.peek_at(1).kind, TokenKind::Identifier(word) if word == "mod") {
+            return self.module_declaration();
+        }
+        
+        let location = self.peek_at(1).location.clone();
+        Err(EidosError::Parser {
+            message: "'pub' を付けられるのは関数とモジュールの定義だけです".to_string(),
+            file: self.file_path.clone(),
+            line: location.line,
+            column: location.column,
+        })
+    }
+    
+    /// モジュール定義を解析（`mod name { ... }` / `pub mod name { ... }`）。中に書けるのは関数とモジュールの定義だけ
+    fn module_declaration(&mut self) -> Result<ASTNode> {
+        let location = self.peek().location.clone();
+        let is_public = self.match_word("pub");
+        self.advance();
+        let name = self.consume_identifier("モジュール名が必要です")?;
+        
+        if self.check(&TokenKind::Semicolon) {
+            return Err(EidosError::Parser {
+                message: format!(
+                    "別ファイルのモジュール（`mod {};`）はまだサポートされていません。`mod {} {{ ... }}` の形で定義してください",
+                    name, name
+                ),
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            });
+        }
+        self.consume(&TokenKind::LeftBrace, "モジュールの本体には '{' が必要です")?;
+        
+        let mut items = Vec::new();
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            let item = self.declaration()?;
+            if !matches!(item.kind, Node::FunctionDef { .. } | Node::ModuleDef { .. }) {
+                return Err(EidosError::Parser {
+                    message: format!("モジュール '{}' に書けるのは関数とモジュールの定義だけです", name),
+                    file: self.file_path.clone(),
+                    line: item.location.line,
+                    column: item.location.column,
+                });
+            }
+            items.push(item);
+        }
+        self.consume(&TokenKind::RightBrace, "モジュールの後には '}' が必要です")?;
+        
+        Ok(ASTNode::new(Node::ModuleDef { name, items, is_public }, location))
+    }
+    
+    /// 属性付きの宣言を解析（`#[bench] fn name() { ... }`）。属性を付けられるのは関数定義だけ
+    fn attributed_declaration(&mut self) -> Result<ASTNode> {
+        let mut attributes = Vec::new();
+        while self.match_token(&TokenKind::Hash) {
+            self.consume(&TokenKind::LeftBracket, "'#' の後には '[' が必要です")?;
+            loop {
+                // `export` はキーワードだが属性名としても使う
+                if self.match_token(&TokenKind::Export) {
+                    attributes.push("export".to_string());
+                } else {
+                    attributes.push(self.consume_identifier("属性名が必要です")?);
+                }
+                if !self.match_token(&TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(&TokenKind::RightBracket, "属性の後には ']' が必要です")?;
+        }
+        
+        let offset = if matches!(&self.peek().kind, TokenKind::Identifier(word) if word == "pub") { 1 } else { 0 };
+        if !self.function_starts_at(offset) {
+            return Err(EidosError::Parser {
+                message: "属性を付けられるのは関数定義だけです".to_string(),
+                file: self.file_path.clone(),
+                line: self.peek().location.line,
+                column: self.peek().location.column,
+            });
+        }
+        
+        let mut function = self.function_declaration()?;
+        if let Node::FunctionDef { attributes: slot, .. } = &mut function.kind {
+            *slot = attributes;
+        }
+        Ok(function)
+    }
+    
+    /// DSL定義を解析（`syntax name { rule パターン => 展開; keyword 別名 = 予約語; layout; ... }`）
+    fn syntax_declaration(&mut self) -> Result<ASTNode> {
+        let keyword = self.advance();
+        let location = keyword.location.clone();
+        
+        let name = self.consume_identifier("DSL名が必要です")?;
+        self.consume(&TokenKind::LeftBrace, "DSL定義の本体には '{' が必要です")?;
+        
+        let mut rules = Vec::new();
+        let mut keywords = Vec::new();
+        let mut layout = false;
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            match &self.peek().kind {
+                TokenKind::Identifier(word) if word == "rule" => {
+                    self.advance();
+                }
+                TokenKind::Identifier(word) if word == "keyword" => {
+                    self.advance();
+                    keywords.push(self.keyword_alias()?);
+                    continue;
+                }
+                // ブロックをインデントで区切る（規則のパターンに NEWLINE・INDENT・DEDENT を書ける）
+                TokenKind::Identifier(word) if word == "layout" => {
+                    self.advance();
+                    self.consume(&TokenKind::Semicolon, "'layout' の後には ';' が必要です")?;
+                    layout = true;
+                    continue;
+                }
+                _ => {
+                    return Err(EidosError::Parser {
+                        message: "DSL定義の規則は 'rule'、'keyword'、'layout' のいずれかで始める必要があります".to_string(),
+                        file: self.file_path.clone(),
+                        line: self.peek().location.line,
+                        column: self.peek().location.column,
+                    });
+                }
+            }
+            
+            let pattern = self.syntax_fragments(&TokenKind::FatArrow)?;
+            if pattern.is_empty() {
+                return Err(EidosError::Parser {
+                    message: "規則のパターンが空です".to_string(),
+                    file: self.file_path.clone(),
+                    line: self.peek().location.line,
+                    column: self.peek().location.column,
+                });
+            }
+            self.consume(&TokenKind::FatArrow, "規則のパターンの後には '=>' が必要です")?;
+            
+            let expansion = self.syntax_fragments(&TokenKind::Semicolon)?;
+            self.consume(&TokenKind::Semicolon, "規則の後には ';' が必要です")?;
+            
+            rules.push(SyntaxRule { pattern, expansion });
+        }
+        
+        self.consume(&TokenKind::RightBrace, "DSL定義の後には '}' が必要です")?;
+        
+        // 後続のコードで使えるよう、解析した時点でレジストリに登録し、現在のスコープで有効化する
+        DSLProcessor::new().register_syntax(&name, rules.clone(), keywords.clone(), layout);
+        self.activate_dsl(&name, &location)?;
+        
+        Ok(ASTNode::new(Node::SyntaxDef { name, rules, keywords, layout }, location))
+    }
+    
+    /// 予約語の別名の宣言を解析（`keyword もし = if;`。`keyword` は読み込み済み）
+    ///
+    /// 別名にできるのは、字句解析の予約語、位置によって予約語になる単語（`pub` など）と演算子だけ。
+    fn keyword_alias(&mut self) -> Result<KeywordAlias> {
+        let alias = self.consume_identifier("'keyword' の後には別名にする単語が必要です")?;
+        self.consume(&TokenKind::Equal, "別名の後には '=' が必要です")?;
+        
+        let target = self.advance();
+        let keyword = target.kind.to_string();
+        let is_keyword = match &target.kind {
+            TokenKind::Identifier(word) => CONTEXTUAL_KEYWORDS.contains(&word.as_str()),
+            kind => kind.is_operator() || KEYWORDS.contains(&keyword.as_str()),
+        };
+        if !is_keyword {
+            return Err(EidosError::Parser {
+                message: format!("'{}' は予約語でも演算子でもないため、別名を付けられません", keyword),
+                file: self.file_path.clone(),
+                line: target.location.line,
+                column: target.location.column,
+            });
+        }
+        self.consume(&TokenKind::Semicolon, "別名の宣言の後には ';' が必要です")?;
+        Ok(KeywordAlias { alias, keyword })
+    }
+    
+    /// PEG文法によるDSL定義を解析（`peg name { 規則 <- 解析表現 { 意味アクション } ... }`）
+    fn peg_declaration(&mut self) -> Result<ASTNode> {
+        let keyword = self.advance();
+        let location = keyword.location.clone();
+        
+        let name = self.consume_identifier("DSL名が必要です")?;
+        let body = self.consume(&TokenKind::RawBlock(String::new()), "PEG文法の本体には '{' が必要です")?;
+        let source = match body.kind {
+            TokenKind::RawBlock(source) => source,
+            _ => unreachable!(),
+        };
+        
+        // 文法をコンパイルしてレジストリに登録し、後続のコードで使えるようにする
+        DSLProcessor::new().register_peg(&name, &source).map_err(|e| EidosError::Parser {
+            message: format!("PEG文法 '{}' のコンパイルに失敗しました: {}", name, e),
+            file: self.file_path.clone(),
+            line: location.line,
+            column: location.column,
+        })?;
+        self.activate_dsl(&name, &location)?;
+        
+        Ok(ASTNode::new(Node::PegDef { name, source }, location))
+    }
+    
+    /// DSLの有効化を解析（`use dsl name;`）
+    fn use_dsl_declaration(&mut self) -> Result<ASTNode> {
+        let keyword = self.advance();
+        let location = keyword.location.clone();
+        self.advance();
+        
+        let name = self.consume_identifier("DSL名が必要です")?;
+        self.consume(&TokenKind::Semicolon, "'use dsl' の後には ';' が必要です")?;
+        
+        if !DSLProcessor::new().is_dsl_available(&name) {
+            return Err(EidosError::Parser {
+                message: format!("DSL拡張 '{}' が見つかりません", name),
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            });
+        }
+        self.activate_dsl(&name, &location)?;
+        
+        Ok(ASTNode::new(Node::UseDsl { name }, location))
+    }
+    
+    /// 型プロバイダの宣言を解析し、スキーマから型を生成する（`provide json_schema "schema/user.json";`）
+    ///
+    /// スキーマのパスはソースファイルのディレクトリからの相対パス。
+    fn provide_declaration(&mut self) -> Result<ASTNode> {
+        let location = self.advance().location.clone();
+        let dsl_name = self.consume_identifier("'provide' の後には型プロバイダのDSL名が必要です")?;
+        let schema = match self.peek().kind.clone() {
+            TokenKind::String(schema) => {
+                self.advance();
+                schema
+            }
+            _ => {
+                return Err(EidosError::Parser {
+                    message: "型プロバイダのDSL名の後にはスキーマのパスの文字列が必要です".to_string(),
+                    file: self.file_path.clone(),
+                    line: location.line,
+                    column: location.column,
+                })
+            }
+        };
+        self.consume(&TokenKind::Semicolon, "'provide' の宣言の後には ';' が必要です")?;
+        
+        let path = self.file_path.parent().unwrap_or(Path::new("")).join(&schema);
+        let provided = DSLProcessor::new().provide_types(&dsl_name, &path).map_err(|e| EidosError::Parser {
+            message: format!("型プロバイダ '{}' で '{}' から型を生成できません: {}", dsl_name, schema, e),
+            file: self.file_path.clone(),
+            line: location.line,
+            column: location.column,
+        })?;
+        let mut types = Vec::with_capacity(provided.len());
+        for (name, definition) in provided {
+            if self.provided_types.insert(name.clone(), definition.clone()).is_some() {
+                return Err(EidosError::Parser {
+                    message: format!("型 '{}' は既に型プロバイダで生成されています", name),
+                    file: self.file_path.clone(),
+                    line: location.line,
+                    column: location.column,
+                });
+            }
+            types.push(ASTNode::new(Node::TypeDef { name, symbol: None, definition }, location.clone()));
+        }
+        
+        Ok(ASTNode::new(Node::TypeProvider { dsl_name, schema, types }, location))
+    }
+    
+    /// 現在のスコープでDSLを有効化する（既に有効なDSLと構文が衝突する場合はエラー）
+    fn activate_dsl(&mut self, name: &str, location: &SourceLocation) -> Result<()> {
+        let mut active = self.active_dsls();
+        if active.iter().any(|dsl| dsl == name) {
+            return Ok(());
+        }
+        
+        active.push(name.to_string());
+        DSLProcessor::new().check_conflicts(&active).map_err(|e| EidosError::Parser {
+            message: match e {
+                EidosError::DSL { message, .. } => message,
+                other => other.to_string(),
+            },
+            file: self.file_path.clone(),
+            line: location.line,
+            column: location.column,
+        })?;
+        
+        if let Some(scope) = self.dsl_scopes.last_mut() {
+            scope.push(name.to_string());
+        }
+        
+        let keywords = DSLProcessor::new().keyword_map(name);
+        if !keywords.is_empty() {
+            self.localize_keywords(keywords);
+        }
+        Ok(())
+    }
+    
+    /// 現在のスコープの残りのトークンで、予約語の別名を元の予約語・演算子に読み替える
+    ///
+    /// スコープを閉じる '}' までが対象で、DSLブロック（`@name { ... }`）の中身はDSLに渡すため読み替えない。
+    fn localize_keywords(&mut self, keywords: KeywordMap) {
+        self.tokens.localize(self.current, keywords);
+    }
+    
+    /// 現在有効なDSLの一覧
+    fn active_dsls(&self) -> Vec<String> {
+        self.dsl_scopes.iter().flatten().cloned().collect()
+    }
+    
+    /// DSLのキーワードで始まる文を、文末の ';'（またはブロックの '}'）まで読み取って展開する
+    fn inline_dsl_statement(&mut self, name: String) -> Result<ASTNode> {
+        let location = self.peek().location.clone();
+        let mut content = Vec::new();
+        let mut depth = 0usize;
+        
+        while !self.is_at_end() {
+            let kind = self.peek().kind.clone();
+            match kind {
+                TokenKind::Semicolon if depth == 0 => {
+                    self.advance();
+                    break;
+                }
+                TokenKind::RightBrace if depth == 0 => break,
+                TokenKind::RightParen | TokenKind::RightBracket if depth == 0 => {
+                    let token = self.peek();
+                    return Err(EidosError::Parser {
+                        message: format!("DSL '{}' の文に対応する開き括弧のない '{}' があります", name, token.kind),
+                        file: self.file_path.clone(),
+                        line: token.location.line,
+                        column: token.location.column,
+                    });
+                }
+                TokenKind::LeftParen | TokenKind::LeftBrace | TokenKind::LeftBracket | TokenKind::DSLStart(_) => depth += 1,
+                TokenKind::RightParen | TokenKind::RightBrace | TokenKind::RightBracket => depth -= 1,
+                _ => {}
+            }
+            content.push(self.advance().source_text().into_owned());
+        }
+        
+        self.expand_dsl(name, content.join(" "), location)
+    }
+    
+    /// 括弧の外側にある終端トークン（または '}'）の直前までを規則の要素として読み取る
+    fn syntax_fragments(&mut self, terminator: &TokenKind) -> Result<Vec<SyntaxFragment>> {
+        let mut fragments = Vec::new();
+        let mut depth = 0usize;
+        
+        while !self.is_at_end() {
+            if depth == 0 && (self.check(terminator) || self.check(&TokenKind::RightBrace)) {
+                break;
+            }
+            
+            let token = self.advance();
+            match token.kind {
+                TokenKind::Dollar => {
+                    let name = self.consume_identifier("'$' の後にはキャプチャ名が必要です")?;
+                    fragments.push(SyntaxFragment::Capture(name));
+                    continue;
+                }
+                TokenKind::LeftParen | TokenKind::LeftBrace | TokenKind::LeftBracket | TokenKind::DSLStart(_) => {
+                    depth += 1;
+                }
+                TokenKind::RightParen | TokenKind::RightBrace | TokenKind::RightBracket => {
+                    depth = depth.saturating_sub(1);
+                }
+                _ => {}
+            }
+            fragments.push(SyntaxFragment::Token(token.source_text().into_owned()));
+        }
+        
+        Ok(fragments)
+    }
+    
+    /// DSLブロック（`@name { ... }`）を解析し、登録済みのDSLで展開する
+    ///
+    /// インデントで区切るDSL（`DSLExtension::layout_sensitive`）のブロックは、オフサイドルールで
+    /// 補ったトークンとともに内容にする。
+    fn dsl_block(&mut self, name: String, location: SourceLocation) -> Result<ASTNode> {
+        let mut tokens = Vec::new();
+        let mut depth = 0usize;
+        
+        loop {
+            if self.is_at_end() {
+                return Err(EidosError::Parser {
+                    message: format!("DSLブロック '{}' が閉じられていません", name),
+                    file: self.file_path.clone(),
+                    line: location.line,
+                    column: location.column,
+                });
+            }
+            
+            let token = self.advance();
+            match &token.kind {
+                TokenKind::RightBrace if depth == 0 => break,
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::DSLStart(inner) => {
+                    // 入れ子のDSLブロックも外側のスコープで有効化されている必要がある
+                    self.check_dsl_active(inner, &token.location)?;
+                    depth += 1;
+                }
+                TokenKind::RightBrace => depth -= 1,
+                _ => {}
+            }
+            tokens.push(token);
+        }
+        
+        self.check_dsl_active(&name, &location)?;
+        if DSLProcessor::new().is_layout_sensitive(&name) {
+            tokens = offside_rule(tokens)?;
+        }
+        // 表示の形は字面と食い違うことがある（`1.0` が `1` になるなど）ので、書かれたとおりの字面を渡す
+        let content: Vec<String> = tokens.iter().map(|token| token.source_text().into_owned()).collect();
+        self.expand_dsl(name, content.join(" "), location)
+    }
+    
+    /// 登録済みのDSLが現在のスコープで有効化されているか確認する
+    fn check_dsl_active(&self, name: &str, location: &SourceLocation) -> Result<()> {
+        if !self.check_dsl_scope || !DSLProcessor::new().is_dsl_available(name) {
+            return Ok(());
+        }
+        
+        if self.active_dsls().iter().any(|dsl| dsl == name) {
+            Ok(())
+        } else {
+            Err(EidosError::Parser {
+                message: format!("DSL '{}' はこのスコープで有効化されていません（'use dsl {};' が必要です）", name, name),
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            })
+        }
+    }
+    
+    /// DSLの内容を登録済みのDSLで展開し、DSLブロックのノードを作成する
+    fn expand_dsl(&mut self, name: String, content: String, location: SourceLocation) -> Result<ASTNode> {
+        let processor = DSLProcessor::new();
+        
+        // 未登録のDSLは展開せずに残し、後段の処理に委ねる
+        let processed_ast = if processor.is_dsl_available(&name) {
+            let program = Program::new(self.file_path.to_string_lossy().to_string());
+            // 展開結果にDSLブロックがあれば、その展開も入れ子で行う
+            let _guard = limits::enter(&format!("DSL '{}' の展開", name)).map_err(|message| EidosError::Parser {
+                message,
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            })?;
+            let expanded = processor.process_dsl_block(&name, &content, &program, location.clone())?;
+            Some(Box::new(expanded))
+        } else {
+            None
+        };
+        
+        Ok(ASTNode::new(
+            Node::DSLBlock {
+                name,
+                content,
+                processed_ast,
+            },
+            location,
+        ))
+    }
+    
+    /// 契約を `assert` の呼び出しにして本体の前後に置く
+    ///
+    /// `require` は本体の前で検査し、`ensure` は本体の値を `result` に束縛してから検査する。
+    /// `?` で関数から返すときは `ensure` を検査しない。
+    fn contract_body(name: &str, body: ASTNode, requires: Vec<ASTNode>, ensures: Vec<ASTNode>) -> ASTNode {
+        let location = body.location.clone();
+        let assert = |condition: ASTNode, kind: &str| {
+            let location = condition.location.clone();
+            let callee = ASTNode::new(Node::Identifier { name: Name::new("assert"), symbol: None }, location.clone());
+            let message = format!("関数 '{}' の{}を満たしません", name, kind);
+            let message = ASTNode::new(Node::Literal(Literal::String(message)), location.clone());
+            ASTNode::new(Node::FunctionCall { callee: Box::new(callee), args: vec![condition, message] }, location)
+        };
+        let mut statements: Vec<ASTNode> = requires.into_iter().map(|condition| assert(condition, "事前条件")).collect();
+        if ensures.is_empty() {
+            return ASTNode::new(Node::BlockExpr { statements, result: Some(Box::new(body)) }, location);
+        }
+        statements.push(ASTNode::new(
+            Node::VarDecl {
+                name: "result".to_string(),
+                symbol: None,
+                type_annotation: None,
+                initializer: Some(Box::new(body)),
+                is_mutable: false,
+            },
+            location.clone(),
+        ));
+        statements.extend(ensures.into_iter().map(|condition| assert(condition, "事後条件")));
+        let result = ASTNode::new(Node::Identifier { name: Name::new("result"), symbol: None }, location.clone());
+        ASTNode::new(Node::BlockExpr { statements, result: Some(Box::new(result)) }, location)
+    }
+    
+    /// `defer 式;` を解析（ブロックの値にはならないので ';' は省略できない）
+    fn defer_statement(&mut self) -> Result<ASTNode> {
+        let location = self.advance().location;
+        let expr = self.expression()?;
+        self.consume(&TokenKind::Semicolon, "'defer' の式の後には ';' が必要です")?;
+        Ok(ASTNode::new(Node::Defer { expr: Box::new(expr) }, location))
+    }
+    
+    /// 式文を解析（末尾の ';' は省略可能）
+    fn expression_statement(&mut self) -> Result<ASTNode> {
+        let expr = self.expression()?;
+        self.match_token(&TokenKind::Semicolon);
+        Ok(expr)
+    }
+    
+    /// 型注釈を解析
+    fn parse_type(&mut self) -> Result<Type> {
+        self.nested("型", Self::type_annotation)
+    }
+    
+    fn type_annotation(&mut self) -> Result<Type> {
+        let location = self.peek().location.clone();
+        // 固定長配列（`[Int; 8]`）とスライス（`&[Int]`）
+        if self.match_token(&TokenKind::LeftBracket) {
+            let element = self.parse_type()?;
+            self.consume(&TokenKind::Semicolon, "配列の要素の型の後には ';' と長さが必要です（`[Int; 8]` など）")?;
+            let length = self.array_length()?;
+            self.consume(&TokenKind::RightBracket, "配列の長さの後には ']' が必要です")?;
+            return Ok(Type::fixed_array(element, length));
+        }
+        if self.match_token(&TokenKind::Ampersand) {
+            self.consume(&TokenKind::LeftBracket, "'&' の後には '[' が必要です（スライスの型は `&[Int]` の形で書きます）")?;
+            let element = self.parse_type()?;
+            self.consume(&TokenKind::RightBracket, "スライスの要素の型の後には ']' が必要です")?;
+            return Ok(Type::slice(element));
+        }
+        // タプル（`(Int, String)`）。`()` はunit型
+        if self.match_token(&TokenKind::LeftParen) {
+            let mut elements = Vec::new();
+            while !self.check(&TokenKind::RightParen) && !self.is_at_end() {
+                elements.push(self.parse_type()?);
+                if !self.match_token(&TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.consume(&TokenKind::RightParen, "タプルの要素の型の後には ')' が必要です")?;
+            return match elements.len() {
+                0 => Ok(Type::unit()),
+                1 => Err(EidosError::Parser {
+                    message: "タプルの型には2つ以上の要素が必要です（`(Int, Bool)` など）".to_string(),
+                    file: self.file_path.clone(),
+                    line: location.line,
+                    column: location.column,
+                }),
+                _ => Ok(Type::tuple(elements)),
+            };
+        }
+        let name = self.consume_identifier("型名が必要です")?;
+        // `i32` や `f32` などのビット幅を指定した数値型
+        if let Some(numeric) = NumericType::from_name(&name) {
+            return Ok(Type::numeric(numeric));
+        }
+        
+        Ok(match name.as_str() {
+            "int" | "Int" => Type::int(),
+            "float" | "Float" => Type::float(),
+            "bool" | "Bool" => Type::bool(),
+            "char" | "Char" => Type::char(),
+            "string" | "String" => Type::string(),
+            "bigint" | "BigInt" => Type::bigint(),
+            "unit" | "Unit" => Type::unit(),
+            OPTION_TYPE_NAME | RESULT_TYPE_NAME | LIST_TYPE_NAME | RANGE_TYPE_NAME => {
+                let args = self.type_arguments(&name, &location)?;
+                match args.as_slice() {
+                    [inner] if name == OPTION_TYPE_NAME => Type::option(inner.clone()),
+                    [ok, err] if name == RESULT_TYPE_NAME => Type::result(ok.clone(), err.clone()),
+                    [element] if name == LIST_TYPE_NAME => Type::array(element.clone()),
+                    [element] if name == RANGE_TYPE_NAME => Type::range(element.clone()),
+                    _ => {
+                        let expected = match name.as_str() {
+                            OPTION_TYPE_NAME => "Option<T>",
+                            RESULT_TYPE_NAME => "Result<T, E>",
+                            RANGE_TYPE_NAME => "Range<T>",
+                            _ => "List<T>",
+                        };
+                        return Err(EidosError::Parser {
+                            message: format!("'{}' の型引数の数が違います（{} の形で指定してください）", name, expected),
+                            file: self.file_path.clone(),
+                            line: location.line,
+                            column: location.column,
+                        });
+                    }
+                }
+            }
+            _ => match self.provided_types.get(&name) {
+                Some(provided) => provided.clone(),
+                None => Type::type_ref(name),
+            },
+        })
+    }
+    
+    /// 型引数のリストを解析（`<Int, String>`）
+    fn type_arguments(&mut self, name: &str, location: &SourceLocation) -> Result<Vec<Type>> {
+        if !self.match_token(&TokenKind::Less) {
+            return Err(EidosError::Parser {
+                message: format!("'{}' には型引数が必要です（`{}<Int>` など）", name, name),
+                file: self.file_path.clone(),
+                line: location.line,
+                column: location.column,
+            });
+        }
+        let mut args = vec![self.parse_type()?];
+        while self.match_token(&TokenKind::Comma) {
+            args.push(self.parse_type()?);
         }
+        if self.check(&TokenKind::GreaterGreater) {
+            // `Option<Option<Int>>` の `>>` は内側と外側の閉じ括弧として 1 つずつ使う
+            let token = &mut self.tokens[self.current];
+            token.kind = TokenKind::Greater;
+            token.location.column += 1;
+        } else {
+            self.consume(&TokenKind::Greater, "型引数の後には '>' が必要です")?;
+        }
+        Ok(args)
     }
     
-    /// プログラム全体を解析
-    pub fn parse(&mut self) -> Result<Program> {
-        // ファイルパスを文字列に変換
-        let file_path_str = self.file_path.to_string_lossy().to_string();
+    /// 配列の長さ（0以上の整数リテラル）を解析
+    fn array_length(&mut self) -> Result<usize> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Integer(length) if length >= 0 => Ok(length as usize),
+            kind => Err(EidosError::Parser {
+                message: format!("配列の長さには0以上の整数リテラルが必要です: {}", kind),
+                file: self.file_path.clone(),
+                line: token.location.line,
+                column: token.location.column,
+            }),
+        }
+    }
+    
+    /// 式を解析
+    fn expression(&mut self) -> Result<ASTNode> {
+        self.nested("式", Self::assignment)
+    }
+    
+    /// 入れ子の構文を1段深く解析する（深さが `--recursion-limit` を超えたら、構文の名前を示すエラー）
+    fn nested<T>(&mut self, construct: &str, parse: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let _guard = limits::enter(construct).map_err(|message| EidosError::Parser {
+            message,
+            file: self.file_path.clone(),
+            line: self.peek().location.line,
+            column: self.peek().location.column,
+        })?;
+        parse(self)
+    }
+    
+    /// 代入式を解析（右結合）
+    fn assignment(&mut self) -> Result<ASTNode> {
+        let target = self.range()?;
         
-        // プログラムオブジェクトを初期化
-        let mut program = Program::new(file_path_str);
+        let compound_op = match self.peek().kind {
+            TokenKind::Equal => None,
+            TokenKind::PlusEqual => Some(BinaryOp::Add),
+            TokenKind::MinusEqual => Some(BinaryOp::Sub),
+            TokenKind::StarEqual => Some(BinaryOp::Mul),
+            _ => return Ok(target),
+        };
         
-        // EOFまで解析を続ける
-        while !self.is_at_end() {
-            match self.declaration() {
-                Ok(node) => {
-                    program.add_node(node);
-                }
-                Err(e) => {
-                    return Err(e);
-                }
-            }
+        let operator = self.advance();
+        
+        // 代入先は変数と配列の要素（`a[i]`）のみ許可する
+        if !matches!(target.kind, Node::Identifier { .. } | Node::Index { .. }) {
+            return Err(EidosError::Parser {
+                message: format!("'{}' の左辺には変数が必要です", operator.kind),
+                file: self.file_path.clone(),
+                line: operator.location.line,
+                column: operator.location.column,
+            });
         }
         
-        Ok(program)
+        let value = Box::new(self.nested("代入", Self::assignment)?);
+        let location = target.location.clone();
+        let target = Box::new(target);
+        
+        let kind = match compound_op {
+            Some(op) => Node::CompoundAssignment { op, target, value },
+            None => Node::Assignment { target, value },
+        };
+        
+        Ok(ASTNode::new(kind, location))
     }
     
-    /// 宣言を解析
-    fn declaration(&mut self) -> Result<ASTNode> {
-        // 現在の実装では、未実装の関数として、単に式を解析する
-        self.expression()
+    /// 範囲を解析（`a..b`。範囲は連結しない）
+    fn range(&mut self) -> Result<ASTNode> {
+        let start = self.logical_or()?;
+        if !self.match_token(&TokenKind::DotDot) {
+            return Ok(start);
+        }
+        let end = self.logical_or()?;
+        let location = start.location.clone();
+        Ok(ASTNode::new(Node::RangeExpr { start: Box::new(start), end: Box::new(end) }, location))
     }
     
-    /// 式を解析
-    fn expression(&mut self) -> Result<ASTNode> {
-        // 現在の実装では、単にリテラルを解析する
-        match self.peek().kind {
-            TokenKind::Integer(value) => {
+    /// 論理和を解析
+    fn logical_or(&mut self) -> Result<ASTNode> {
+        let mut expr = self.logical_and()?;
+        
+        while self.match_token(&TokenKind::PipePipe) {
+            let right = self.logical_and()?;
+            expr = Self::binary(BinaryOp::Or, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 論理積を解析
+    fn logical_and(&mut self) -> Result<ASTNode> {
+        let mut expr = self.equality()?;
+        
+        while self.match_token(&TokenKind::AmpersandAmpersand) {
+            let right = self.equality()?;
+            expr = Self::binary(BinaryOp::And, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 等価比較を解析
+    fn equality(&mut self) -> Result<ASTNode> {
+        let mut expr = self.comparison()?;
+        
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::EqualEqual => BinaryOp::Eq,
+                TokenKind::BangEqual => BinaryOp::NotEq,
+                _ => break,
+            };
+            self.advance();
+            let right = self.comparison()?;
+            expr = Self::binary(op, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 大小比較を解析
+    fn comparison(&mut self) -> Result<ASTNode> {
+        let mut expr = self.bit_or()?;
+        
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Less => BinaryOp::Lt,
+                TokenKind::LessEqual => BinaryOp::LtEq,
+                TokenKind::Greater => BinaryOp::Gt,
+                TokenKind::GreaterEqual => BinaryOp::GtEq,
+                _ => break,
+            };
+            self.advance();
+            let right = self.bit_or()?;
+            expr = Self::binary(op, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// ビット論理和を解析（`a | b == c` は `(a | b) == c`）
+    fn bit_or(&mut self) -> Result<ASTNode> {
+        let mut expr = self.bit_xor()?;
+        
+        while self.match_token(&TokenKind::Pipe) {
+            let right = self.bit_xor()?;
+            expr = Self::binary(BinaryOp::BitOr, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// ビット排他的論理和を解析
+    fn bit_xor(&mut self) -> Result<ASTNode> {
+        let mut expr = self.bit_and()?;
+        
+        while self.match_token(&TokenKind::Caret) {
+            let right = self.bit_and()?;
+            expr = Self::binary(BinaryOp::BitXor, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// ビット論理積を解析
+    fn bit_and(&mut self) -> Result<ASTNode> {
+        let mut expr = self.shift()?;
+        
+        while self.match_token(&TokenKind::Ampersand) {
+            let right = self.shift()?;
+            expr = Self::binary(BinaryOp::BitAnd, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// シフト演算を解析（`1 << n + 1` は `1 << (n + 1)`）
+    fn shift(&mut self) -> Result<ASTNode> {
+        let mut expr = self.term()?;
+        
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::LessLess => BinaryOp::LShift,
+                TokenKind::GreaterGreater => BinaryOp::RShift,
+                _ => break,
+            };
+            self.advance();
+            let right = self.term()?;
+            expr = Self::binary(op, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 加減算を解析
+    fn term(&mut self) -> Result<ASTNode> {
+        let mut expr = self.factor()?;
+        
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Plus => BinaryOp::Add,
+                TokenKind::Minus => BinaryOp::Sub,
+                _ => break,
+            };
+            self.advance();
+            let right = self.factor()?;
+            expr = Self::binary(op, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 乗除算を解析
+    fn factor(&mut self) -> Result<ASTNode> {
+        let mut expr = self.cast()?;
+        
+        loop {
+            let op = match self.peek().kind {
+                TokenKind::Star => BinaryOp::Mul,
+                TokenKind::Slash => BinaryOp::Div,
+                TokenKind::Percent => BinaryOp::Mod,
+                _ => break,
+            };
+            self.advance();
+            let right = self.cast()?;
+            expr = Self::binary(op, expr, right);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 型変換を解析（`-x as i32` は `(-x) as i32`）
+    fn cast(&mut self) -> Result<ASTNode> {
+        let mut expr = self.unary()?;
+        
+        while self.check(&TokenKind::As) {
+            let location = self.advance().location;
+            let target_type = self.parse_type()?;
+            expr = ASTNode::new(Node::Cast { expr: Box::new(expr), target_type }, location);
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 単項演算を解析
+    fn unary(&mut self) -> Result<ASTNode> {
+        let op = match &self.peek().kind {
+            TokenKind::Minus => UnaryOp::Neg,
+            TokenKind::Bang => UnaryOp::Not,
+            // `&a` は配列 `a` 全体を指すスライス
+            TokenKind::Ampersand => {
                 let token = self.advance();
-                let location = token.location.clone();
-                let literal = Literal::Int(value);
-                
-                Ok(ASTNode::new(Node::Literal(literal), location))
-            },
-            TokenKind::String(ref value) => {
+                let array = Box::new(self.nested("単項演算", Self::unary)?);
+                return Ok(ASTNode::new(Node::SliceOf { array }, token.location));
+            }
+            // `await f(x)` の `await` は、識別子が続くときだけキーワードとして扱う
+            TokenKind::Identifier(word) if word == "await" && matches!(self.peek_at(1).kind, TokenKind::Identifier(_)) => {
                 let token = self.advance();
-                let location = token.location.clone();
-                let literal = Literal::String(value.clone());
-                
-                Ok(ASTNode::new(Node::Literal(literal), location))
+                let expr = Box::new(self.nested("単項演算", Self::unary)?);
+                return Ok(ASTNode::new(Node::Await { expr }, token.location));
+            }
+            _ => return self.call(),
+        };
+        
+        let token = self.advance();
+        let expr = Box::new(self.nested("単項演算", Self::unary)?);
+        
+        Ok(ASTNode::new(Node::UnaryExpr { op, expr }, token.location))
+    }
+    
+    /// 関数呼び出しを解析
+    fn call(&mut self) -> Result<ASTNode> {
+        let mut expr = self.primary()?;
+        
+        loop {
+            // `expr?` は失敗（`None`・`Err`）なら関数から返し、成功なら中の値を取り出す
+            if self.check(&TokenKind::Question) {
+                let location = self.advance().location;
+                expr = ASTNode::new(Node::Try { expr: Box::new(expr) }, location);
+                continue;
+            }
+            if self.match_token(&TokenKind::LeftBracket) {
+                let index = self.expression()?;
+                self.consume(&TokenKind::RightBracket, "添字の後には ']' が必要です")?;
+                let location = expr.location.clone();
+                expr = ASTNode::new(Node::Index { base: Box::new(expr), index: Box::new(index) }, location);
+                continue;
+            }
+            if !self.match_token(&TokenKind::LeftParen) {
+                break;
+            }
+            let mut args = Vec::new();
+            
+            if !self.check(&TokenKind::RightParen) {
+                loop {
+                    args.push(self.expression()?);
+                    if !self.match_token(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+            }
+            
+            self.consume(&TokenKind::RightParen, "引数リストの後には ')' が必要です")?;
+            
+            let location = expr.location.clone();
+            expr = ASTNode::new(
+                Node::FunctionCall {
+                    callee: Box::new(expr),
+                    args,
+                },
+                location,
+            );
+        }
+        
+        Ok(expr)
+    }
+    
+    /// 一次式（リテラル、識別子、括弧式）を解析
+    fn primary(&mut self) -> Result<ASTNode> {
+        if self.is_at_end() {
+            return Err(EidosError::Parser {
+                message: "式の途中で入力が終了しました".to_string(),
+                file: self.file_path.clone(),
+                line: self.peek().location.line,
+                column: self.peek().location.column,
+            });
+        }
+        
+        let token = self.advance();
+        let location = token.location.clone();
+        
+        let literal = match token.kind {
+            TokenKind::Integer(value) => Literal::Int(value),
+            TokenKind::Float(value) => Literal::Float(value),
+            TokenKind::SizedInteger(value, ty) => {
+                return Ok(ASTNode::new(Node::Literal(Literal::Int(value)), location)
+                    .with_type(TypeInfo::Explicit(Type::numeric(ty))));
             },
-            TokenKind::True => {
-                let token = self.advance();
-                let location = token.location.clone();
-                let literal = Literal::Bool(true);
-                
-                Ok(ASTNode::new(Node::Literal(literal), location))
+            TokenKind::SizedFloat(value, ty) => {
+                return Ok(ASTNode::new(Node::Literal(Literal::Float(value)), location)
+                    .with_type(TypeInfo::Explicit(Type::numeric(ty))));
             },
-            TokenKind::False => {
-                let token = self.advance();
-                let location = token.location.clone();
-                let literal = Literal::Bool(false);
-                
-                Ok(ASTNode::new(Node::Literal(literal), location))
+            TokenKind::BigInteger(value) => Literal::BigInt(value),
+            TokenKind::String(value) => Literal::String(value),
+            TokenKind::Character(value) => Literal::Char(value),
+            TokenKind::True => Literal::Bool(true),
+            TokenKind::False => Literal::Bool(false),
+            TokenKind::Identifier(name) if name == "try" && self.check(&TokenKind::LeftBrace) => {
+                return self.try_catch(location);
             },
-            _ => {
-                Err(EidosError::Parser {
-                    message: format!("式を解析できません: {:?}", self.peek().kind),
+            TokenKind::Identifier(name) if name == "comptime" && self.check(&TokenKind::LeftBrace) => {
+                let block_location = self.advance().location;
+                let body = self.block(block_location)?;
+                return Ok(ASTNode::new(Node::Comptime { body: Box::new(body) }, location));
+            },
+            TokenKind::Identifier(name) if (name == "asm" || name == "wasm") && self.check(&TokenKind::Bang) => {
+                let dialect = if name == "asm" { AsmDialect::Native } else { AsmDialect::Wasm };
+                return self.inline_asm(dialect, location);
+            },
+            TokenKind::Identifier(mut name) => {
+                // `math::abs` のようなモジュール修飾名は1つの識別子にまとめる
+                while self.check_path_separator() {
+                    self.advance();
+                    self.advance();
+                    let member = self.consume_identifier("'::' の後には名前が必要です")?;
+                    name = Name::new(&format!("{}::{}", name, member));
+                }
+                return Ok(ASTNode::new(Node::Identifier { name, symbol: None }, location));
+            },
+            TokenKind::DSLStart(name) => {
+                return self.dsl_block(name, location);
+            },
+            TokenKind::If => {
+                return self.if_expression(location);
+            },
+            TokenKind::While => {
+                let condition = self.expression()?;
+                let body_location = self.peek().location.clone();
+                self.consume(&TokenKind::LeftBrace, "'while' の条件の後には '{' が必要です")?;
+                let body = self.block(body_location)?;
+                return Ok(ASTNode::new(Node::WhileLoop { condition: Box::new(condition), body: Box::new(body) }, location));
+            },
+            TokenKind::For => {
+                let variable = self.consume_identifier("'for' の後には変数名が必要です")?;
+                self.consume(&TokenKind::In, "'for' の変数名の後には 'in' が必要です")?;
+                let iterable = self.expression()?;
+                let body_location = self.peek().location.clone();
+                self.consume(&TokenKind::LeftBrace, "'for' の 'in' の式の後には '{' が必要です")?;
+                let body = self.block(body_location)?;
+                return Ok(ASTNode::new(
+                    Node::ForLoop { variable, iterable: Box::new(iterable), body: Box::new(body) },
+                    location,
+                ));
+            },
+            TokenKind::Unsafe => {
+                let block_location = self.peek().location.clone();
+                self.consume(&TokenKind::LeftBrace, "'unsafe' の後には '{' が必要です")?;
+                let body = self.block(block_location)?;
+                return Ok(ASTNode::new(Node::UnsafeBlock { body: Box::new(body) }, location));
+            },
+            TokenKind::LeftParen => {
+                let expr = self.expression()?;
+                if !self.match_token(&TokenKind::Comma) {
+                    self.consume(&TokenKind::RightParen, "式の後には ')' が必要です")?;
+                    return Ok(expr);
+                }
+                // `(a, b)` はタプル（最後の要素の後の ',' は省略できる）
+                let mut elements = vec![expr];
+                while !self.check(&TokenKind::RightParen) && !self.is_at_end() {
+                    elements.push(self.expression()?);
+                    if !self.match_token(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+                self.consume(&TokenKind::RightParen, "タプルの要素の後には ')' が必要です")?;
+                if elements.len() < 2 {
+                    return Err(EidosError::Parser {
+                        message: "タプルには2つ以上の要素が必要です（括弧で囲んだ式の後の ',' を取り除いてください）".to_string(),
+                        file: self.file_path.clone(),
+                        line: location.line,
+                        column: location.column,
+                    });
+                }
+                return Ok(ASTNode::new(Node::TupleLiteral { elements }, location));
+            },
+            TokenKind::LeftBracket => {
+                return self.array_literal(location);
+            },
+            TokenKind::LeftBrace => {
+                if self.is_map_literal_start() {
+                    return self.map_literal(location);
+                }
+                return self.block(location);
+            },
+            kind => {
+                return Err(EidosError::Parser {
+                    message: format!("式を解析できません: {:?}", kind),
                     file: self.file_path.clone(),
-                    line: self.peek().location.line,
-                    column: self.peek().location.column,
-                })
+                    line: location.line,
+                    column: location.column,
+                });
+            }
+        };
+        
+        Ok(ASTNode::new(Node::Literal(literal), location))
+    }
+    
+    /// ブロック式を解析（`{ 文; ... 式 }`）。ブロック内で有効化したDSLはブロックの外では無効になる
+    fn block(&mut self, location: SourceLocation) -> Result<ASTNode> {
+        self.dsl_scopes.push(Vec::new());
+        
+        let mut statements = Vec::new();
+        let mut result = None;
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            let statement = self.declaration()?;
+            
+            // ';' で終わらない最後の式がブロックの値になる
+            if self.check(&TokenKind::RightBrace) && self.previous().kind != TokenKind::Semicolon {
+                result = Some(Box::new(statement));
+            } else {
+                statements.push(statement);
+            }
+        }
+        
+        self.dsl_scopes.pop();
+        self.consume(&TokenKind::RightBrace, "ブロックの後には '}' が必要です")?;
+        
+        Ok(ASTNode::new(Node::BlockExpr { statements, result }, location))
+    }
+    
+    /// `if 条件 { ... } else { ... }` を解析（`if` は読み込み済み）。`else if` は `else` の中の `if` 式にする
+    fn if_expression(&mut self, location: SourceLocation) -> Result<ASTNode> {
+        let condition = self.expression()?;
+        let then_location = self.peek().location.clone();
+        self.consume(&TokenKind::LeftBrace, "'if' の条件の後には '{' が必要です")?;
+        let then_branch = self.block(then_location)?;
+        
+        let else_branch = if self.match_token(&TokenKind::Else) {
+            let else_location = self.peek().location.clone();
+            if self.match_token(&TokenKind::If) {
+                Some(Box::new(self.nested("else if", |parser| parser.if_expression(else_location))?))
+            } else {
+                self.consume(&TokenKind::LeftBrace, "'else' の後には '{' か 'if' が必要です")?;
+                Some(Box::new(self.block(else_location)?))
+            }
+        } else {
+            None
+        };
+        
+        Ok(ASTNode::new(
+            Node::IfExpr {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch,
+            },
+            location,
+        ))
+    }
+    
+    /// `try { ... } catch e { ... }` を解析（`try` は読み込み済み）
+    fn try_catch(&mut self, location: SourceLocation) -> Result<ASTNode> {
+        let body_location = self.advance().location;
+        let body = self.block(body_location)?;
+        
+        let catch_location = self.peek().location.clone();
+        if !matches!(&self.peek().kind, TokenKind::Identifier(name) if name == "catch") {
+            return Err(EidosError::Parser {
+                message: "'try' のブロックの後には 'catch エラー名 { ... }' が必要です".to_string(),
+                file: self.file_path.clone(),
+                line: catch_location.line,
+                column: catch_location.column,
+            });
+        }
+        self.advance();
+        let error_name = self.consume_identifier("'catch' の後にはエラーを受け取る変数名が必要です")?;
+        let handler_location = self.peek().location.clone();
+        self.consume(&TokenKind::LeftBrace, "'catch' の変数名の後には '{' が必要です")?;
+        let handler = self.block(handler_location)?;
+        
+        Ok(ASTNode::new(
+            Node::TryCatch {
+                body: Box::new(body),
+                error_name,
+                handler: Box::new(handler),
+            },
+            location,
+        ))
+    }
+    
+    /// `asm!("...", out(型), in(式), clobber("レジスタ"))` と `wasm!(...)` を解析（名前は読み込み済み）
+    ///
+    /// テンプレートの `{0}`, `{1}`, ... は出力、入力の順に並べたオペランドを指す。出力は1つまで。
+    fn inline_asm(&mut self, dialect: AsmDialect, location: SourceLocation) -> Result<ASTNode> {
+        let file = self.file_path.clone();
+        let error = |message: String, location: &SourceLocation| EidosError::Parser {
+            message,
+            file: file.clone(),
+            line: location.line,
+            column: location.column,
+        };
+        self.advance();
+        self.consume(&TokenKind::LeftParen, &format!("'{}!' の後には '(' が必要です", dialect.keyword()))?;
+        let template_token = self.advance();
+        let TokenKind::String(template) = template_token.kind else {
+            return Err(error("アセンブリの最初の引数にはテンプレートの文字列が必要です".to_string(), &template_token.location));
+        };
+        
+        let mut inputs = Vec::new();
+        let mut output = None;
+        let mut clobbers = Vec::new();
+        while self.match_token(&TokenKind::Comma) && !self.check(&TokenKind::RightParen) {
+            let operand_location = self.peek().location.clone();
+            if self.match_token(&TokenKind::In) {
+                self.consume(&TokenKind::LeftParen, "'in' の後には '(' が必要です")?;
+                inputs.push(self.expression()?);
+            } else {
+                let kind = self.consume_identifier("オペランドには in(式)・out(型)・clobber(\"レジスタ\") のいずれかが必要です")?;
+                self.consume(&TokenKind::LeftParen, &format!("'{}' の後には '(' が必要です", kind))?;
+                match kind.as_str() {
+                    "out" if output.is_some() => {
+                        return Err(error("アセンブリの出力は1つまでです".to_string(), &operand_location));
+                    }
+                    "out" => output = Some(self.parse_type()?),
+                    "clobber" => {
+                        let register = self.advance();
+                        let TokenKind::String(register) = register.kind else {
+                            return Err(error("clobber には破壊するレジスタ名の文字列が必要です".to_string(), &register.location));
+                        };
+                        clobbers.push(register);
+                    }
+                    _ => {
+                        return Err(error(
+                            format!("不明なオペランド '{}' です（in・out・clobber のいずれか）", kind),
+                            &operand_location,
+                        ));
+                    }
+                }
+            }
+            self.consume(&TokenKind::RightParen, "オペランドの後には ')' が必要です")?;
+        }
+        self.consume(&TokenKind::RightParen, "アセンブリの後には ')' が必要です")?;
+        
+        Ok(ASTNode::new(Node::InlineAsm { dialect, template, inputs, output, clobbers }, location))
+    }
+    
+    /// `{` の直後が `キー:` の形ならマップリテラルとみなす（`a::b` のようなパスは除く）
+    ///
+    /// `{}` は空のブロックのままとし、空のマップは `Map::new()` で作る。
+    fn is_map_literal_start(&self) -> bool {
+        let key = self.tokens.get(self.current).map(|t| &t.kind);
+        let colon = self.tokens.get(self.current + 1).map(|t| &t.kind);
+        let after = self.tokens.get(self.current + 2).map(|t| &t.kind);
+        let is_key = matches!(
+            key,
+            Some(TokenKind::String(_))
+                | Some(TokenKind::Integer(_))
+                | Some(TokenKind::SizedInteger(..))
+                | Some(TokenKind::Character(_))
+                | Some(TokenKind::True)
+                | Some(TokenKind::False)
+                | Some(TokenKind::Identifier(_))
+        );
+        is_key && matches!(colon, Some(TokenKind::Colon)) && !matches!(after, Some(TokenKind::Colon))
+    }
+    
+    /// マップリテラルを解析（`{ キー: 値, ... }`）。末尾のカンマは省略可能
+    fn map_literal(&mut self, location: SourceLocation) -> Result<ASTNode> {
+        let mut entries = Vec::new();
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            let key = self.expression()?;
+            self.consume(&TokenKind::Colon, "マップのキーの後には ':' が必要です")?;
+            let value = self.expression()?;
+            entries.push((key, value));
+            
+            if !self.match_token(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightBrace, "マップリテラルの後には '}' が必要です")?;
+        
+        Ok(ASTNode::new(Node::MapLiteral { entries }, location))
+    }
+    
+    /// 配列リテラルを解析（`[a, b, c]` / `[値; 長さ]` / `[式 for 変数 in 式 if 条件]`）。末尾のカンマは省略可能
+    fn array_literal(&mut self, location: SourceLocation) -> Result<ASTNode> {
+        let mut elements = Vec::new();
+        while !self.check(&TokenKind::RightBracket) && !self.is_at_end() {
+            elements.push(self.expression()?);
+            if elements.len() == 1 && self.match_token(&TokenKind::For) {
+                let element = Box::new(elements.remove(0));
+                return self.comprehension(element, location);
             }
+            if elements.len() == 1 && self.match_token(&TokenKind::Semicolon) {
+                let count = self.array_length()?;
+                self.consume(&TokenKind::RightBracket, "配列の長さの後には ']' が必要です")?;
+                let value = Box::new(elements.remove(0));
+                return Ok(ASTNode::new(Node::ArrayRepeat { value, count }, location));
+            }
+            if !self.match_token(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightBracket, "配列の要素の後には ']' が必要です")?;
+        Ok(ASTNode::new(Node::ArrayLiteral { elements }, location))
+    }
+    
+    /// 内包表記の `for` より後を解析（`[式 for 変数 in 式]`・`[式 for 変数 in 式 if 条件]`）
+    fn comprehension(&mut self, element: Box<ASTNode>, location: SourceLocation) -> Result<ASTNode> {
+        let variable = self.consume_identifier("内包表記の 'for' の後には変数名が必要です")?;
+        self.consume(&TokenKind::In, "内包表記の変数名の後には 'in' が必要です")?;
+        let iterable = Box::new(self.expression()?);
+        let condition = if self.match_token(&TokenKind::If) { Some(Box::new(self.expression()?)) } else { None };
+        self.consume(&TokenKind::RightBracket, "内包表記の後には ']' が必要です")?;
+        Ok(ASTNode::new(Node::Comprehension { element, variable, iterable, condition }, location))
+    }
+    
+    /// 二項演算ノードを作成
+    fn binary(op: BinaryOp, left: ASTNode, right: ASTNode) -> ASTNode {
+        let location = left.location.clone();
+        ASTNode::new(
+            Node::BinaryExpr {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+            location,
+        )
+    }
+    
+    /// 識別子を期待して、その名前を返す
+    fn consume_identifier(&mut self, message: &str) -> Result<String> {
+        let token = self.consume(&TokenKind::Identifier(Name::default()), message)?;
+        match token.kind {
+            TokenKind::Identifier(name) => Ok(name.to_string()),
+            _ => unreachable!(),
         }
     }
     
@@ -96,6 +1774,7 @@ impl Parser {
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.current += 1;
+            self.tokens.advance_to(self.current);
         }
         self.previous()
     }
@@ -110,6 +1789,70 @@ impl Parser {
         &self.tokens[self.current]
     }
     
+    /// 現在位置に、間を空けずに並んだ2つの ':'（パス区切りの `::`）があるかどうか
+    fn check_path_separator(&self) -> bool {
+        match (self.tokens.get(self.current), self.tokens.get(self.current + 1)) {
+            (Some(first), Some(second)) => {
+                first.kind == TokenKind::Colon
+                    && second.kind == TokenKind::Colon
+                    && first.location.line == second.location.line
+                    && first.location.column + 1 == second.location.column
+            }
+            _ => false,
+        }
+    }
+    
+    /// 現在から `offset` 個先のトークン（終端を越える場合は最後のトークン）
+    fn peek_at(&self, offset: usize) -> &Token {
+        self.tokens.get(self.current + offset).unwrap_or_else(|| self.tokens.last())
+    }
+    
+    /// `offset` 個先から関数定義（`fn` / `effect fn` / `const fn` / `async fn`）が始まるかどうか
+    fn function_starts_at(&self, offset: usize) -> bool {
+        match &self.peek_at(offset).kind {
+            TokenKind::Fn => true,
+            TokenKind::Identifier(word) if matches!(word.as_str(), "effect" | "const" | "async") => {
+                self.function_starts_at(offset + 1)
+            }
+            _ => false,
+        }
+    }
+    
+    /// `offset` 個先からグローバル変数の名前（`static` の場合は `mut` も）が続くかどうか
+    fn global_starts_at(&self, offset: usize) -> bool {
+        match &self.peek_at(offset).kind {
+            TokenKind::Identifier(_) => true,
+            TokenKind::Mut => matches!(&self.peek().kind, TokenKind::Identifier(word) if word == "static"),
+            _ => false,
+        }
+    }
+    
+    /// 現在のトークンが指定した名前の識別子なら進む
+    fn match_word(&mut self, word: &str) -> bool {
+        if matches!(&self.peek().kind, TokenKind::Identifier(name) if name == word) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// 次のトークンが期待するものかどうか
+    fn check_next(&self, kind: &TokenKind) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => std::mem::discriminant(&token.kind) == std::mem::discriminant(kind),
+            None => false,
+        }
+    }
+    
+    /// 次のトークンが指定した名前の識別子かどうか
+    fn check_next_word(&self, word: &str) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => matches!(&token.kind, TokenKind::Identifier(name) if name == word),
+            None => false,
+        }
+    }
+    
     /// 終端に達したかどうか
     fn is_at_end(&self) -> bool {
         self.peek().kind == TokenKind::Eof
@@ -171,4 +1914,164 @@ impl Parser {
             self.advance();
         }
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Read};
+    use crate::core::ast_dump::AstDumper;
+    use crate::frontend::lexer::Lexer;
+    use crate::frontend::macro_expander::MacroExpander;
+    
+    /// 関数定義を1つずつ生成して読ませるストリーム
+    struct Generated {
+        next: usize,
+        count: usize,
+        pending: Vec<u8>,
+    }
+    
+    impl Read for Generated {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() && self.next < self.count {
+                self.pending = format!("fn f{}(x: Int) -> Int {{ x + {} }}\n", self.next, self.next).into_bytes();
+                self.next += 1;
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+    
+    #[test]
+    fn test_parses_from_streaming_lexer() {
+        let reader = BufReader::new(Generated { next: 0, count: 10_000, pending: Vec::new() });
+        let mut parser = Parser::from_lexer(Lexer::from_reader(reader, PathBuf::from("gen.eid")), PathBuf::from("gen.eid"));
+        let mut count = 0;
+        while !parser.is_at_end() {
+            parser.declaration().unwrap();
+            count += 1;
+            // 読み込み済みのトークンは先読みの分だけ
+            assert!(parser.tokens.tokens.len() <= TOKEN_LOOKAHEAD + 2, "{}", parser.tokens.tokens.len());
+        }
+        assert_eq!(count, 10_000);
+        
+        // 予約語の別名は、有効化した後に読み込むトークンにも当てはめる
+        let source = "syntax 日本語 { keyword 関数 = fn; keyword もし = if; keyword でなければ = else; }\n\
+                      関数 a() -> Int { 1 }\n関数 b() -> Int { 2 }\n関数 c(x: Int) -> Int { もし x > 0 { 1 } でなければ { 0 } }";
+        let streamed = Parser::from_lexer(Lexer::from_reader(source.as_bytes(), PathBuf::from("ja.eid")), PathBuf::from("ja.eid"))
+            .parse()
+            .unwrap();
+        let tokens = Lexer::new(source, PathBuf::from("ja.eid")).tokenize().unwrap();
+        let parsed = Parser::new(tokens, PathBuf::from("ja.eid")).parse().unwrap();
+        let dumper = AstDumper::new();
+        assert_eq!(dumper.to_sexp(&streamed), dumper.to_sexp(&parsed));
+        
+        // 字句解析のエラーは、打ち切ったトークン列の構文エラーより優先する
+        let error = Parser::from_lexer(Lexer::from_reader(&b"fn f() -> Int { \"\xFF\" }"[..], PathBuf::from("bad.eid")), PathBuf::from("bad.eid"))
+            .parse()
+            .unwrap_err();
+        assert!(matches!(error, EidosError::Lexer { .. }), "{}", error);
+        
+        // メモリ上のソースを借りたまま、マクロを展開しながら読むこともできる
+        let source = String::from("macro twice($x) { $x + $x }\nfn f() -> Int { twice!(2) * 3 }");
+        let mut expander = MacroExpander::new();
+        let streamed = Parser::from_lexer(expander.expanding(Lexer::new(&source, PathBuf::from("m.eid"))), PathBuf::from("m.eid"))
+            .parse()
+            .unwrap();
+        let tokens = MacroExpander::new().expand(Lexer::new(&source, PathBuf::from("m.eid")).tokenize().unwrap()).unwrap();
+        let parsed = Parser::new(tokens, PathBuf::from("m.eid")).parse().unwrap();
+        assert_eq!(dumper.to_sexp(&streamed), dumper.to_sexp(&parsed));
+    }
+    
+    #[test]
+    fn test_mutable_bindings_and_compound_assignment() {
+        let parse = |source: &str| {
+            let tokens = Lexer::new(source, PathBuf::from("var.eid")).tokenize().unwrap();
+            Parser::new(tokens, PathBuf::from("var.eid")).parse()
+        };
+        fn collect<'a>(node: &'a ASTNode, nodes: &mut Vec<&'a Node>) {
+            nodes.push(&node.kind);
+            for child in node.children() {
+                collect(child, nodes);
+            }
+        }
+        
+        let program = parse("fn f(c: Int) -> Int { var a = 1; let mut b = 2; let d = 3; a = b = c; a += 1; b -= d; a *= 2; a }").unwrap();
+        let mut nodes = Vec::new();
+        for node in &program.nodes {
+            collect(node, &mut nodes);
+        }
+        let bindings: Vec<(&str, bool)> = nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::VarDecl { name, is_mutable, .. } => Some((name.as_str(), *is_mutable)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(bindings, [("a", true), ("b", true), ("d", false)]);
+        let ops: Vec<BinaryOp> = nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::CompoundAssignment { op, .. } => Some(*op),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ops, [BinaryOp::Add, BinaryOp::Sub, BinaryOp::Mul]);
+        
+        // 代入は右結合
+        let chained = nodes.iter().find_map(|node| match node {
+            Node::Assignment { target, value } => Some((target, value)),
+            _ => None,
+        });
+        let (target, value) = chained.unwrap();
+        assert!(matches!(&target.kind, Node::Identifier { name, .. } if name.as_str() == "a"));
+        assert!(matches!(value.kind, Node::Assignment { .. }));
+        
+        // 代入先は変数か配列の要素だけ
+        assert!(parse("fn f() { let mut v = [1]; v[0] += 1; }").is_ok());
+        let error = parse("fn f() { 1 = 2; }").unwrap_err();
+        assert!(error.to_string().contains("'=' の左辺には変数が必要です"), "{}", error);
+        let error = parse("fn f() { g() += 1; }").unwrap_err();
+        assert!(error.to_string().contains("の左辺には変数が必要です"), "{}", error);
+    }
+    
+    #[test]
+    fn test_inline_dsl_statement_rejects_unmatched_closer() {
+        let parse = |source: &str| {
+            let tokens = Lexer::new(source, PathBuf::from("unless.eid")).tokenize().unwrap();
+            Parser::new(tokens, PathBuf::from("unless.eid")).parse()
+        };
+        let syntax = "syntax unless { rule unless $c then $a => if !($c) { $a }; }\n";
+        assert!(parse(&format!("{}fn f() {{ unless ready then g(); }}", syntax)).is_ok());
+        
+        let error = parse(&format!("{}fn f() {{ unless ready then g()); }}", syntax)).unwrap_err();
+        assert!(error.to_string().contains("対応する開き括弧のない ')'"), "{}", error);
+        let error = parse(&format!("{}unless ready then ] g();", syntax)).unwrap_err();
+        assert!(error.to_string().contains("対応する開き括弧のない ']'"), "{}", error);
+    }
+    
+    #[test]
+    fn test_recursion_limit() {
+        let parse = |source: &str| {
+            let tokens = Lexer::new(source, PathBuf::from("deep.eid")).tokenize().unwrap();
+            Parser::new(tokens, PathBuf::from("deep.eid")).parse()
+        };
+        let parens = |depth: usize| format!("let x = {}1{};", "(".repeat(depth), ")".repeat(depth));
+        
+        limits::with_stack(|| {
+            assert!(parse(&parens(limits::recursion_limit() - 8)).is_ok());
+            
+            // 上限を超える入れ子は、スタックがあふれる前に入れ子の構文を示すエラーにする
+            let error = parse(&parens(100_000)).unwrap_err();
+            assert!(matches!(error, EidosError::Parser { .. }), "{}", error);
+            assert!(error.to_string().contains("式の入れ子が深すぎます"), "{}", error);
+            let error = parse(&format!("let x = {}1;", "-".repeat(100_000))).unwrap_err();
+            assert!(error.to_string().contains("単項演算の入れ子が深すぎます"), "{}", error);
+            let error = parse(&format!("let x: {}Int{} = 1;", "List<".repeat(1_000), ">".repeat(1_000))).unwrap_err();
+            assert!(error.to_string().contains("型の入れ子が深すぎます"), "{}", error);
+        })
+        .unwrap();
+    }
+}
@@ -2,8 +2,12 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::error::did_you_mean;
 use crate::core::ast::{ASTNode, Node, Program, NodeId};
 use crate::core::symbol::{SymbolTable, SymbolId, SymbolKind, ScopeKind};
+use super::modules::ModuleResolver;
+use super::globals::GlobalChecker;
+use super::definite_init::InitChecker;
 
 /// 意味解析器
 pub struct SemanticAnalyzer {
@@ -25,6 +29,12 @@ impl SemanticAnalyzer {
         // 意味解析の開始をログに記録
         info!("意味解析を実行中: {} ノード", program.node_count());
         
+        // 0. モジュールを解決して可視性を検査し、モジュール内の関数を修飾名のトップレベル関数に展開
+        let program = ModuleResolver::new().resolve(program)?;
+        GlobalChecker::new().check(&program)?;
+        // 初期化式なしで宣言したローカル変数を、代入されない経路が残る地点で読み取っていないか
+        InitChecker::new().check(&program)?;
+        
         // グローバルスコープに入る
         self.enter_scope(ScopeKind::Global);
         
@@ -296,67 +306,75 @@ impl SemanticAnalyzer {
                     self.node_symbols.insert(node_id, symbol_id);
                 } else {
                     return Err(EidosError::Semantic {
-                        message: format!("未定義の識別子: {}", name),
+                        message: format!(
+                            "未定義の識別子: {}{}",
+                            name,
+                            did_you_mean(name, self.symbol_table.visible_names())
+                        ),
                         file: node.location.file.clone(),
                         line: node.location.line,
                         column: node.location.column,
                     });
                 }
             },
-            Node::Let { name, type_annotation: _, initializer } => {
-                // 変数宣言をシンボルテーブルに登録
+            Node::VarDecl { name, initializer, is_mutable, .. } => {
+                // 変数宣言をシンボルテーブルに登録（`let mut` / `var` のみ可変）
                 let symbol_id = self.declare_symbol(
                     name.clone(),
                     SymbolKind::Variable,
-                    true, // デフォルトでは可変
+                    *is_mutable,
                     false, // デフォルトではエクスポートしない
                 )?;
                 
                 self.node_symbols.insert(node_id, symbol_id);
                 
                 // 初期化子があれば解析
-                if let Some(init_id) = initializer {
-                    let init_node = program.get_node(*init_id).ok_or_else(|| {
-                        EidosError::Internal(format!("ノードが見つかりません: {:?}", init_id))
-                    })?;
-                    
-                    self.analyze_node(program, *init_id, init_node)?;
+                if let Some(init_node) = initializer {
+                    self.analyze_node(program, init_node.id, init_node)?;
                 }
             },
-            Node::Assign { target, value } => {
+            Node::TupleDecl { names, initializer, is_mutable, .. } => {
+                // 初期化子を先に解析し、`_` 以外の名前を変数として登録
+                self.analyze_node(program, initializer.id, initializer)?;
+                for name in names.iter().filter(|name| name.as_str() != "_") {
+                    self.declare_symbol(name.clone(), SymbolKind::Variable, *is_mutable, false)?;
+                }
+            },
+            Node::ForLoop { variable, iterable, body } => {
+                // 反復する式は外側のスコープで解析し、ループ変数は本体だけのスコープに登録
+                self.analyze_node(program, iterable.id, iterable)?;
+                self.enter_scope(ScopeKind::Block);
+                if variable != "_" {
+                    self.declare_symbol(variable.clone(), SymbolKind::Variable, false, false)?;
+                }
+                self.analyze_node(program, body.id, body)?;
+                self.exit_scope()?;
+            },
+            Node::Comprehension { element, variable, iterable, condition } => {
+                // `for` ループと同じく、ループ変数は要素の式と条件だけのスコープに登録
+                self.analyze_node(program, iterable.id, iterable)?;
+                self.enter_scope(ScopeKind::Block);
+                if variable != "_" {
+                    self.declare_symbol(variable.clone(), SymbolKind::Variable, false, false)?;
+                }
+                if let Some(condition) = condition {
+                    self.analyze_node(program, condition.id, condition)?;
+                }
+                self.analyze_node(program, element.id, element)?;
+                self.exit_scope()?;
+            },
+            Node::Assignment { target, value } |
+            Node::CompoundAssignment { target, value, .. } => {
                 // 代入式の解析
                 
                 // まず対象を解析
-                let target_node = program.get_node(*target).ok_or_else(|| {
-                    EidosError::Internal(format!("ノードが見つかりません: {:?}", target))
-                })?;
-                
-                self.analyze_node(program, *target, target_node)?;
+                self.analyze_node(program, target.id, target)?;
                 
-                // 代入対象が変数の場合、可変かチェック
-                if let Node::Variable(name) = &target_node.kind {
-                    if let Some(symbol_id) = self.resolve_identifier(name) {
-                        let symbol = self.symbol_table.get_symbol(symbol_id).ok_or_else(|| {
-                            EidosError::Internal(format!("シンボルが見つかりません: {:?}", symbol_id))
-                        })?;
-                        
-                        if !symbol.is_mutable {
-                            return Err(EidosError::Semantic {
-                                message: format!("イミュータブルな変数に代入しようとしています: {}", name),
-                                file: node.location.file.clone(),
-                                line: node.location.line,
-                                column: node.location.column,
-                            });
-                        }
-                    }
-                }
+                // 代入対象が可変な変数かチェック
+                self.check_assignment_target(target, node)?;
                 
                 // 値を解析
-                let value_node = program.get_node(*value).ok_or_else(|| {
-                    EidosError::Internal(format!("ノードが見つかりません: {:?}", value))
-                })?;
-                
-                self.analyze_node(program, *value, value_node)?;
+                self.analyze_node(program, value.id, value)?;
             },
             Node::Function { name, parameters, return_type: _, body } => {
                 // 関数スコープに入る
@@ -522,6 +540,47 @@ impl SemanticAnalyzer {
             Node::Literal(_) => {
                 // リテラルは特に意味解析は不要
             },
+            Node::MapLiteral { entries } => {
+                // キーと値を順に解析
+                for (key, value) in entries {
+                    self.analyze_node(program, key.id, key)?;
+                    self.analyze_node(program, value.id, value)?;
+                }
+            },
+            Node::ArrayLiteral { .. }
+            | Node::ArrayRepeat { .. }
+            | Node::Index { .. }
+            | Node::SliceOf { .. }
+            | Node::TupleLiteral { .. }
+            | Node::RangeExpr { .. } => {
+                // 要素・添字・配列の式を順に解析
+                for child in node.children() {
+                    self.analyze_node(program, child.id, child)?;
+                }
+            },
+            Node::DSLBlock { processed_ast, .. } => {
+                // 展開済みのDSLブロックは展開結果を解析する
+                if let Some(expanded) = processed_ast {
+                    self.analyze_node(program, expanded.id, expanded)?;
+                }
+            },
+            Node::Await { expr } => {
+                // 待つ対象の呼び出しを解析する（`async fn` の呼び出しかどうかは効果検査で確かめる）
+                self.analyze_node(program, expr.id, expr)?;
+            },
+            Node::Defer { expr } => {
+                // 後で評価する式も、`defer` の位置で見える名前で解析する
+                self.analyze_node(program, expr.id, expr)?;
+            },
+            Node::InlineAsm { inputs, .. } => {
+                // 入力の式だけを解析する（テンプレートはバックエンドに渡す）
+                for input in inputs {
+                    self.analyze_node(program, input.id, input)?;
+                }
+            },
+            Node::SyntaxDef { .. } | Node::PegDef { .. } | Node::UseDsl { .. } => {
+                // DSL定義と有効化は構文解析時に処理済み
+            },
             _ => {
                 // その他のノード型の意味解析（必要に応じて実装）
             }
@@ -530,6 +589,38 @@ impl SemanticAnalyzer {
         Ok(())
     }
     
+    /// 代入対象が可変な変数であることを確認
+    fn check_assignment_target(&self, target: &ASTNode, node: &ASTNode) -> Result<()> {
+        let name = match &target.kind {
+            Node::Identifier { name, .. } => name,
+            _ => return Ok(()),
+        };
+        
+        if let Some(symbol_id) = self.resolve_identifier(name) {
+            let symbol = self.symbol_table.get_symbol(symbol_id).ok_or_else(|| {
+                EidosError::Internal(format!("シンボルが見つかりません: {:?}", symbol_id))
+            })?;
+            
+            if !symbol.is_mutable {
+                let operator = match &node.kind {
+                    Node::CompoundAssignment { .. } => "複合代入",
+                    _ => "代入",
+                };
+                return Err(EidosError::Semantic {
+                    message: format!(
+                        "イミュータブルな変数 '{}' への{}はできません（`let mut` または `var` で宣言してください）",
+                        name, operator
+                    ),
+                    file: node.location.file.clone(),
+                    line: node.location.line,
+                    column: node.location.column,
+                });
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// シンボルを宣言
     fn declare_symbol(&mut self, name: String, kind: SymbolKind, is_mutable: bool, is_exported: bool) -> Result<SymbolId> {
         self.symbol_table.declare_symbol(name, kind, is_mutable, is_exported)
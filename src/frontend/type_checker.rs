@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 
 use crate::core::{Result, EidosError, SourceLocation};
+use crate::core::limits;
 use crate::core::ast::{ASTNode, Node, Program, TypeInfo, NodeId};
-use crate::core::types::{Type, TypeEnvironment};
+use crate::core::types::{Type, TypeEnvironment, TypeKind};
 use crate::core::symbol::{SymbolTable, SymbolId};
+use crate::stdlib::StdlibRegistry;
+use super::const_eval::ConstEvaluator;
+use super::effect_checker::EffectChecker;
 
 /// 型チェッカー
 pub struct TypeChecker {
@@ -45,6 +49,11 @@ impl TypeChecker {
         // 関数シグネチャの収集（相互再帰関数のサポートのため）
         self.collect_function_signatures(&program)?;
         
+        // `const fn` の呼び出しをコンパイル時に評価し、結果の定数に置き換える
+        let mut const_evaluator = ConstEvaluator::new();
+        const_evaluator.collect(&program)?;
+        let program = const_evaluator.fold_program(program)?;
+        
         // 各ノードを走査して型付け（ボトムアップ方式）
         for node_id in program.traverse_post_order() {
             let node = program.get_node(node_id).ok_or_else(|| {
@@ -58,6 +67,9 @@ impl TypeChecker {
         // 型制約の検証
         self.verify_type_constraints(&program)?;
         
+        // 純粋関数から副作用のある関数を呼んでいないかの検証
+        EffectChecker::new().check(&program)?;
+        
         // ジェネリック型の具体化
         self.resolve_generic_types(&program)?;
         
@@ -84,6 +96,7 @@ impl TypeChecker {
     /// 型環境の初期化
     fn initialize_type_environment(&mut self, program: &Program) -> Result<()> {
         // 標準ライブラリの型を登録
+        StdlibRegistry::initialize()?;
         self.register_stdlib_types();
         
         // プログラム固有の初期化
@@ -157,7 +170,7 @@ impl TypeChecker {
                 let annotated_type = self.type_env.resolve_type_annotation(type_annotation)?;
                 if !self.type_env.is_compatible(node_type, &annotated_type) {
                     return Err(EidosError::Type {
-                        message: format!("型アノテーションと実際の型が一致しません: 期待 {:?}, 実際 {:?}", 
+                        message: format!("型アノテーションと実際の型が一致しません: 期待 {}, 実際 {}", 
                                         annotated_type, node_type),
                         location: node.location.clone(),
                     });
@@ -198,7 +211,7 @@ impl TypeChecker {
                 })?;
                 
                 return Err(EidosError::Type {
-                    message: format!("型推論に失敗しました: 未解決の型 {:?}", node_type),
+                    message: format!("型推論に失敗しました: 未解決の型 {}", node_type),
                     location: node.location.clone(),
                 });
             }
@@ -206,12 +219,43 @@ impl TypeChecker {
         Ok(())
     }
     /// ノードの型を推論
+    ///
+    /// 構文解析と同じ所で式の入れ子の深さを数え、`--recursion-limit` を超えたらエラーにする。二項演算・型変換・
+    /// 呼び出し・添字・`?` の連なりは構文解析が繰り返しで読み、ブロックと宣言は式として読まないので数えない。
     fn infer_node_type(&mut self, program: &Program, node: &ASTNode) -> Result<Type> {
+        let nested = !matches!(
+            node.kind,
+            Node::BinaryExpr { .. }
+                | Node::Cast { .. }
+                | Node::FunctionCall { .. }
+                | Node::Index { .. }
+                | Node::Try { .. }
+                | Node::BlockExpr { .. }
+                | Node::VarDecl { .. }
+                | Node::TupleDecl { .. }
+                | Node::GlobalDecl { .. }
+                | Node::FunctionDef { .. }
+                | Node::ModuleDef { .. }
+        );
+        let _guard = if nested {
+            Some(limits::enter("式").map_err(|message| EidosError::Type {
+                message,
+                location: node.location.clone(),
+            })?)
+        } else {
+            None
+        };
+        self.infer_kind_type(program, node)
+    }
+    
+    /// ノードの種類ごとに型を推論
+    fn infer_kind_type(&mut self, program: &Program, node: &ASTNode) -> Result<Type> {
         match &node.kind {
             Node::Literal(lit) => {
                 // リテラルの型は簡単に決定できる
                 Ok(match lit {
                     crate::core::ast::Literal::Int(_) => Type::int(),
+                    crate::core::ast::Literal::BigInt(_) => Type::bigint(),
                     crate::core::ast::Literal::Float(_) => Type::float(),
                     crate::core::ast::Literal::Bool(_) => Type::bool(),
                     crate::core::ast::Literal::Char(_) => Type::char(),
@@ -237,7 +281,7 @@ impl TypeChecker {
                                 let this_type = self.infer_node_type(program, elem)?;
                                 if !self.type_env.is_assignable(&this_type, &elem_type) {
                                     return Err(EidosError::Type {
-                                        message: format!("配列要素の型が一致しません: 期待 {:?}, 実際 {:?}", elem_type, this_type),
+                                        message: format!("配列要素の型が一致しません: 期待 {}, 実際 {}", elem_type, this_type),
                                         location: elem.location.clone(),
                                     });
                                 }
@@ -279,7 +323,18 @@ impl TypeChecker {
                     crate::core::ast::BinaryOp::Mul | 
                     crate::core::ast::BinaryOp::Div | 
                     crate::core::ast::BinaryOp::Mod => {
-                        if left_type.is_numeric() && right_type.is_numeric() {
+                        let is_bigint = |ty: &Type| ty.kind == TypeKind::BigInt;
+                        if is_bigint(&left_type) || is_bigint(&right_type) {
+                            // `BigInt` どうし、または `BigInt` と `Int` の演算は `BigInt`
+                            if [&left_type, &right_type].iter().all(|ty| matches!(ty.kind, TypeKind::BigInt | TypeKind::Int)) {
+                                Ok(Type::bigint())
+                            } else {
+                                Err(EidosError::Type {
+                                    message: format!("不適切な演算: {} {} {}", left_type, op, right_type),
+                                    location: node.location.clone(),
+                                })
+                            }
+                        } else if left_type.is_numeric() && right_type.is_numeric() {
                             // 数値同士の演算
                             if left_type.is_float() || right_type.is_float() {
                                 // どちらかがfloatならfloat
@@ -294,7 +349,7 @@ impl TypeChecker {
                             Ok(Type::string())
                         } else {
                             Err(EidosError::Type {
-                                message: format!("不適切な演算: {:?} {:?} {:?}", left_type, op, right_type),
+                                message: format!("不適切な演算: {} {} {}", left_type, op, right_type),
                                 location: node.location.clone(),
                             })
                         }
@@ -311,7 +366,7 @@ impl TypeChecker {
                             Ok(Type::bool())
                         } else {
                             Err(EidosError::Type {
-                                message: format!("比較できない型: {:?} {:?} {:?}", left_type, op, right_type),
+                                message: format!("比較できない型: {} {} {}", left_type, op, right_type),
                                 location: node.location.clone(),
                             })
                         }
@@ -323,13 +378,30 @@ impl TypeChecker {
                             Ok(Type::bool())
                         } else {
                             Err(EidosError::Type {
-                                message: format!("論理演算子はブール型のみサポート: {:?} {:?} {:?}", left_type, op, right_type),
+                                message: format!("論理演算子はブール型のみサポート: {} {} {}", left_type, op, right_type),
+                                location: node.location.clone(),
+                            })
+                        }
+                    },
+                    // ビット演算子・シフト演算子
+                    crate::core::ast::BinaryOp::BitAnd | 
+                    crate::core::ast::BinaryOp::BitOr | 
+                    crate::core::ast::BinaryOp::BitXor | 
+                    crate::core::ast::BinaryOp::LShift | 
+                    crate::core::ast::BinaryOp::RShift => {
+                        // 整数型にだけ使える（型の決まっていない値は見送る）。結果は左辺の型
+                        let integer = |ty: &Type| ty.is_integer() || ty.is_undetermined();
+                        if integer(&left_type) && integer(&right_type) {
+                            Ok(left_type)
+                        } else {
+                            Err(EidosError::Type {
+                                message: format!("ビット演算子は整数型のみサポート: {} {} {}", left_type, op, right_type),
                                 location: node.location.clone(),
                             })
                         }
                     },
                     _ => Err(EidosError::Type {
-                        message: format!("未サポートの演算子: {:?}", op),
+                        message: format!("未サポートの演算子: {}", op),
                         location: node.location.clone(),
                     }),
                 }
@@ -343,11 +415,11 @@ impl TypeChecker {
                 
                 match op {
                     crate::core::ast::UnaryOp::Neg => {
-                        if operand_type.is_numeric() {
+                        if operand_type.is_numeric() || operand_type.kind == TypeKind::BigInt {
                             Ok(operand_type.clone())
                         } else {
                             Err(EidosError::Type {
-                                message: format!("負数化は数値型のみサポート: {:?}", operand_type),
+                                message: format!("負数化は数値型のみサポート: {}", operand_type),
                                 location: node.location.clone(),
                             })
                         }
@@ -357,13 +429,13 @@ impl TypeChecker {
                             Ok(Type::bool())
                         } else {
                             Err(EidosError::Type {
-                                message: format!("論理否定はブール型のみサポート: {:?}", operand_type),
+                                message: format!("論理否定はブール型のみサポート: {}", operand_type),
                                 location: node.location.clone(),
                             })
                         }
                     },
                     _ => Err(EidosError::Type {
-                        message: format!("未サポートの単項演算子: {:?}", op),
+                        message: format!("未サポートの単項演算子: {}", op),
                         location: node.location.clone(),
                     }),
                 }
@@ -377,7 +449,7 @@ impl TypeChecker {
                 
                 if !cond_type.is_bool() {
                     return Err(EidosError::Type {
-                        message: format!("条件式はブール型である必要があります: {:?}", cond_type),
+                        message: format!("条件式はブール型である必要があります: {}", cond_type),
                         location: node.location.clone(),
                     });
                 }
@@ -401,7 +473,7 @@ impl TypeChecker {
                         Ok(else_type)
                     } else {
                         Err(EidosError::Type {
-                            message: format!("if-elseの各分岐の型が一致しません: {:?} と {:?}", then_type, else_type),
+                            message: format!("if-elseの各分岐の型が一致しません: {} と {}", then_type, else_type),
                             location: node.location.clone(),
                         })
                     }
@@ -444,7 +516,7 @@ impl TypeChecker {
                 let var_type = if let Some(type_id) = type_annotation {
                     let type_info = self.type_env.get_type(*type_id).ok_or_else(|| {
                         EidosError::Type {
-                            message: format!("不明な型: {:?}", type_id),
+                            message: format!("不明な型: {}", type_id),
                             location: node.location.clone(),
                         }
                     })?;
@@ -452,7 +524,7 @@ impl TypeChecker {
                     // 初期化子がある場合は型チェック
                     if initializer.is_some() && !self.type_env.is_assignable(&init_type, type_info) {
                         return Err(EidosError::Type {
-                            message: format!("型の不一致: 期待 {:?}, 実際 {:?}", type_info, init_type),
+                            message: format!("型の不一致: 期待 {}, 実際 {}", type_info, init_type),
                             location: node.location.clone(),
                         });
                     }
@@ -493,7 +565,7 @@ impl TypeChecker {
                 // 型の互換性をチェック
                 if !self.type_env.is_assignable(&value_type, &target_type) {
                     return Err(EidosError::Type {
-                        message: format!("型の不一致: 期待 {:?}, 実際 {:?}", target_type, value_type),
+                        message: format!("型の不一致: 期待 {}, 実際 {}", target_type, value_type),
                         location: node.location.clone(),
                     });
                 }
@@ -510,7 +582,7 @@ impl TypeChecker {
                     let param_type = if let Some(type_id) = param.type_id {
                         self.type_env.get_type(type_id).ok_or_else(|| {
                             EidosError::Type {
-                                message: format!("不明な型: {:?}", type_id),
+                                message: format!("不明な型: {}", type_id),
                                 location: node.location.clone(),
                             }
                         })?.clone()
@@ -529,7 +601,7 @@ impl TypeChecker {
                 let ret_type = if let Some(type_id) = return_type {
                     self.type_env.get_type(*type_id).ok_or_else(|| {
                         EidosError::Type {
-                            message: format!("不明な型: {:?}", type_id),
+                            message: format!("不明な型: {}", type_id),
                             location: node.location.clone(),
                         }
                     })?.clone()
@@ -548,7 +620,7 @@ impl TypeChecker {
                 // 本体の型と戻り値の型が一致するか確認
                 if !self.type_env.is_assignable(&body_type, &ret_type) {
                     return Err(EidosError::Type {
-                        message: format!("戻り値の型の不一致: 期待 {:?}, 実際 {:?}", ret_type, body_type),
+                        message: format!("戻り値の型の不一致: 期待 {}, 実際 {}", ret_type, body_type),
                         location: body_node.location.clone(),
                     });
                 }
@@ -572,7 +644,7 @@ impl TypeChecker {
                 // 関数型であることを確認
                 if !callee_type.is_function() {
                     return Err(EidosError::Type {
-                        message: format!("呼び出し可能でない値: {:?}", callee_type),
+                        message: format!("呼び出し可能でない値: {}", callee_type),
                         location: node.location.clone(),
                     });
                 }
@@ -605,7 +677,7 @@ impl TypeChecker {
                 for (i, (arg_type, param_type)) in arg_types.iter().zip(param_types.iter()).enumerate() {
                     if !self.type_env.is_assignable(arg_type, param_type) {
                         return Err(EidosError::Type {
-                            message: format!("引数 #{} の型が一致しません: 期待 {:?}, 実際 {:?}", i + 1, param_type, arg_type),
+                            message: format!("引数 #{} の型が一致しません: 期待 {}, 実際 {}", i + 1, param_type, arg_type),
                             location: node.location.clone(),
                         });
                     }
@@ -614,6 +686,259 @@ impl TypeChecker {
                 // 戻り値の型を返す
                 Ok(return_type.clone())
             },
+            Node::FunctionCall { callee, args } => {
+                let mut arg_types = Vec::with_capacity(args.len());
+                for arg in args {
+                    arg_types.push(self.infer_node_type(program, arg)?);
+                }
+                
+                // `next(it)` は範囲の次の値を `Option` で返す
+                if let (Node::Identifier { name, .. }, [iterator]) = (&callee.kind, arg_types.as_slice()) {
+                    if let Some(element) = iterator.range_element().filter(|_| name == "next") {
+                        return Ok(Type::option(element.clone()));
+                    }
+                }
+                
+                // `assert(cond, "message")` は値を返さない
+                if matches!(&callee.kind, Node::Identifier { name, .. } if name == "assert") {
+                    return Ok(Type::unit());
+                }
+                
+                // 標準ライブラリ関数は、引数の型からオーバーロードを選んで戻り値の型を決める
+                if let Node::Identifier { name, .. } = &callee.kind {
+                    let registry = StdlibRegistry::global();
+                    let registry = registry.read().unwrap();
+                    if registry.get_overloads(name).is_some() {
+                        let signature = registry.resolve_overload(name, &arg_types).map_err(|message| EidosError::Type {
+                            message,
+                            location: node.location.clone(),
+                        })?;
+                        return Ok(signature.return_type.clone());
+                    }
+                }
+                
+                Ok(Type::unknown())
+            },
+            Node::CompoundAssignment { op, target, value } => {
+                // 左辺と右辺の型を取得
+                let target_type = self.infer_node_type(program, target)?;
+                let value_type = self.infer_node_type(program, value)?;
+                
+                // `x op= y` は `x = x op y` と同じ型規則に従う
+                let is_valid = if target_type.is_numeric() && value_type.is_numeric() {
+                    // 結果を左辺に格納するため、int への float の暗黙変換は許可しない
+                    !(target_type.is_int() && value_type.is_float())
+                } else {
+                    // 文字列の連結代入
+                    *op == crate::core::ast::BinaryOp::Add && target_type.is_string() && value_type.is_string()
+                };
+                
+                if !is_valid {
+                    return Err(EidosError::Type {
+                        message: format!("不適切な複合代入: {} {}= {}", target_type, op, value_type),
+                        location: node.location.clone(),
+                    });
+                }
+                
+                // 複合代入式はunit型を返す
+                Ok(Type::unit())
+            },
+            Node::MapLiteral { entries } => {
+                // すべてのキー、すべての値がそれぞれ同じ型である必要がある
+                let mut entry_types: Option<(Type, Type)> = None;
+                for (key, value) in entries {
+                    let key_type = self.infer_node_type(program, key)?;
+                    let value_type = self.infer_node_type(program, value)?;
+                    if let Some((expected_key, expected_value)) = &entry_types {
+                        if !self.type_env.is_assignable(&key_type, expected_key) {
+                            return Err(EidosError::Type {
+                                message: format!("マップのキーの型が一致しません: 期待 {}, 実際 {}", expected_key, key_type),
+                                location: key.location.clone(),
+                            });
+                        }
+                        if !self.type_env.is_assignable(&value_type, expected_value) {
+                            return Err(EidosError::Type {
+                                message: format!("マップの値の型が一致しません: 期待 {}, 実際 {}", expected_value, value_type),
+                                location: value.location.clone(),
+                            });
+                        }
+                    } else {
+                        entry_types = Some((key_type, value_type));
+                    }
+                }
+                
+                // 実体は標準ライブラリの collections::Map
+                Ok(Type::type_ref("collections::Map".to_string()))
+            },
+            Node::ArrayLiteral { elements } => {
+                // すべての要素が同じ型である必要がある（空の配列は要素の型を決められない）
+                let (first, rest) = elements.split_first().ok_or_else(|| EidosError::Type {
+                    message: "空の配列リテラルの要素の型を決められません（`let a: [Int; 0] = [];` のように型を指定してください）".to_string(),
+                    location: node.location.clone(),
+                })?;
+                let element_type = self.infer_node_type(program, first)?;
+                for element in rest {
+                    let actual = self.infer_node_type(program, element)?;
+                    if !self.type_env.is_assignable(&actual, &element_type) {
+                        return Err(EidosError::Type {
+                            message: format!("配列の要素の型が一致しません: 期待 {}, 実際 {}", element_type, actual),
+                            location: element.location.clone(),
+                        });
+                    }
+                }
+                Ok(Type::fixed_array(element_type, elements.len()))
+            },
+            Node::ArrayRepeat { value, count } => {
+                let element_type = self.infer_node_type(program, value)?;
+                Ok(Type::fixed_array(element_type, *count))
+            },
+            Node::Index { base, index } => {
+                // 添字は整数、添字を付けられるのは固定長配列とスライス
+                let index_type = self.infer_node_type(program, index)?;
+                if !index_type.is_int() {
+                    return Err(EidosError::Type {
+                        message: format!("配列の添字は整数である必要があります: {}", index_type),
+                        location: index.location.clone(),
+                    });
+                }
+                let base_type = self.infer_node_type(program, base)?;
+                base_type.element_type().cloned().ok_or_else(|| EidosError::Type {
+                    message: format!("添字を付けられるのは配列とスライスだけです: {}", base_type),
+                    location: base.location.clone(),
+                })
+            },
+            Node::SliceOf { array } => {
+                let array_type = self.infer_node_type(program, array)?;
+                match &array_type.kind {
+                    TypeKind::FixedArray { element, .. } => Ok(Type::slice((**element).clone())),
+                    TypeKind::Slice(_) => Ok(array_type),
+                    _ => Err(EidosError::Type {
+                        message: format!("'&' でスライスを作れるのは固定長配列だけです: {}", array_type),
+                        location: array.location.clone(),
+                    }),
+                }
+            },
+            Node::RangeExpr { start, end } => {
+                // 範囲の両端は同じ整数型
+                let start_type = self.infer_node_type(program, start)?;
+                let end_type = self.infer_node_type(program, end)?;
+                let is_integer = |ty: &Type| ty.numeric_type().is_some_and(|numeric| !numeric.is_float());
+                if !is_integer(&start_type) || !is_integer(&end_type) || !self.type_env.is_assignable(&end_type, &start_type) {
+                    return Err(EidosError::Type {
+                        message: format!("範囲の両端は同じ整数型である必要があります: {} と {}", start_type, end_type),
+                        location: node.location.clone(),
+                    });
+                }
+                Ok(Type::range(start_type))
+            },
+            Node::ForLoop { variable, iterable, body } => {
+                // 範囲・固定長配列・スライスの要素を順にループ変数に束縛する
+                let iterable_type = self.infer_node_type(program, iterable)?;
+                let element_type = iterable_type.iteration_element().cloned().ok_or_else(|| EidosError::Type {
+                    message: format!("'for' で反復できるのは範囲・固定長配列・スライスだけです: {}", iterable_type),
+                    location: iterable.location.clone(),
+                })?;
+                if variable != "_" {
+                    self.type_env.set_variable_type(variable.clone(), element_type);
+                }
+                self.infer_node_type(program, body)?;
+                Ok(Type::unit())
+            },
+            Node::Comprehension { element, variable, iterable, condition } => {
+                // 条件がなければ元と同じ長さの固定長配列、条件があれば条件を満たした要素のスライス
+                let iterable_type = self.infer_node_type(program, iterable)?;
+                let variable_type = iterable_type.iteration_element().cloned().ok_or_else(|| EidosError::Type {
+                    message: format!("内包表記で反復できるのは範囲・固定長配列だけです: {}", iterable_type),
+                    location: iterable.location.clone(),
+                })?;
+                if variable != "_" {
+                    self.type_env.set_variable_type(variable.clone(), variable_type);
+                }
+                if let Some(condition) = condition {
+                    let condition_type = self.infer_node_type(program, condition)?;
+                    if !condition_type.is_bool() {
+                        return Err(EidosError::Type {
+                            message: format!("内包表記の条件はBool型である必要があります: {}", condition_type),
+                            location: condition.location.clone(),
+                        });
+                    }
+                }
+                let element_type = self.infer_node_type(program, element)?;
+                match (&iterable_type.kind, condition) {
+                    (TypeKind::FixedArray { length, .. }, None) => Ok(Type::fixed_array(element_type, *length)),
+                    _ => Ok(Type::slice(element_type)),
+                }
+            },
+            Node::TupleLiteral { elements } => {
+                let mut element_types = Vec::with_capacity(elements.len());
+                for element in elements {
+                    element_types.push(self.infer_node_type(program, element)?);
+                }
+                Ok(Type::tuple(element_types))
+            },
+            Node::TupleDecl { names, type_annotation, initializer, .. } => {
+                // 初期化子は変数の数と同じ要素数のタプルである必要がある
+                let init_type = self.infer_node_type(program, initializer)?;
+                let tuple_type = match type_annotation {
+                    Some(annotation) => {
+                        if !self.type_env.is_assignable(&init_type, annotation) {
+                            return Err(EidosError::Type {
+                                message: format!("型の不一致: 期待 {}, 実際 {}", annotation, init_type),
+                                location: node.location.clone(),
+                            });
+                        }
+                        annotation.clone()
+                    }
+                    None => init_type,
+                };
+                let element_types = match &tuple_type.kind {
+                    TypeKind::Tuple(element_types) if element_types.len() == names.len() => element_types.clone(),
+                    _ => {
+                        return Err(EidosError::Type {
+                            message: format!("{} 型の値を {} 個の変数に分解できません", tuple_type, names.len()),
+                            location: initializer.location.clone(),
+                        })
+                    }
+                };
+                for (name, element_type) in names.iter().zip(element_types) {
+                    if name != "_" {
+                        self.type_env.set_variable_type(name.clone(), element_type);
+                    }
+                }
+                Ok(Type::unit())
+            },
+            Node::DSLBlock { processed_ast: Some(expanded), .. } => {
+                // 展開済みのDSLブロックは展開結果の型を持つ
+                self.infer_node_type(program, expanded)
+            },
+            Node::SyntaxDef { .. } | Node::PegDef { .. } | Node::UseDsl { .. } => {
+                // DSL定義・有効化は値を持たない
+                Ok(Type::unit())
+            },
+            Node::Await { expr } => {
+                // 非同期関数の呼び出しを待った値は、呼び出しの戻り値と同じ型になる
+                self.infer_node_type(program, expr)
+            },
+            Node::Defer { expr } => {
+                // 後で評価する式の値は捨てる
+                self.infer_node_type(program, expr)?;
+                Ok(Type::unit())
+            },
+            Node::InlineAsm { dialect, inputs, output, .. } => {
+                // オペランドに渡せるのは数値・Bool・Charの値だけ（テンプレートの中身はバックエンドに任せる）
+                for input in inputs {
+                    let input_type = self.infer_node_type(program, input)?;
+                    let is_scalar = input_type.numeric_type().is_some()
+                        || matches!(input_type.kind, TypeKind::Bool | TypeKind::Char | TypeKind::Unknown);
+                    if !is_scalar {
+                        return Err(EidosError::Type {
+                            message: format!("{}! のオペランドに {} 型の値は渡せません", dialect.keyword(), input_type),
+                            location: input.location.clone(),
+                        });
+                    }
+                }
+                Ok(output.clone().unwrap_or_else(Type::unit))
+            },
             // その他のノード型の実装
             _ => {
                 // デフォルトでは不明な型を返す
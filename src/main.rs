@@ -1,5 +1,5 @@
 use clap::{Parser, Subcommand};
-use log::info;
+use log::{info, warn};
 use std::path::PathBuf;
 use std::process;
 
@@ -10,14 +10,34 @@ mod backend;
 mod stdlib;
 mod tools;
 
+/// `--time-passes` でフェーズごとのピークメモリを表示するため、ヒープの使用量を数える
+#[global_allocator]
+static ALLOCATOR: crate::core::session::TrackingAllocator = crate::core::session::TrackingAllocator;
+
 /// Eidos - 言語を作る言語
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
-    /// ログレベル
-    #[clap(long, default_value = "info")]
-    log_level: String,
+    /// ログレベル（既定は `info`。`--quiet` と `--json` では `error`）
+    #[clap(long, global = true)]
+    log_level: Option<String>,
+    
+    /// 結果とエラー以外を表示しない（成功の報告、統計、ログ）
+    #[clap(long, short, global = true)]
+    quiet: bool,
+    
+    /// 診断とエラーを1行ごとのJSONで標準エラー出力に書く（`eid lex` ではトークンもJSONで出力）
+    #[clap(long, global = true)]
+    json: bool,
+
+    /// 読み込むプラグイン（共有ライブラリ）。複数指定できる
+    #[clap(long = "plugin", global = true)]
+    plugins: Vec<PathBuf>,
 
+    /// 構文解析・型検査・DSLの展開で許す入れ子の深さの上限（既定は128、最大4096）
+    #[clap(long, global = true, value_parser = clap::value_parser!(u64).range(1..=core::limits::MAX_RECURSION_LIMIT as u64))]
+    recursion_limit: Option<u64>,
+    
     #[clap(subcommand)]
     command: Commands,
 }
@@ -30,13 +50,81 @@ enum Commands {
         #[clap(value_parser)]
         file: PathBuf,
 
-        /// 最適化レベル（0-3）
-        #[clap(short, long, default_value = "2")]
-        opt_level: u8,
+        /// 最適化レベル（0-3。省略すると eidos.toml の [build] の opt-level、なければ 2）
+        #[clap(short, long)]
+        opt_level: Option<u8>,
+        
+        /// 実行速度よりコードサイズを優先して最適化（インライン化とループの展開をせず、コールドブロックの切り出しなどを行う）
+        #[clap(long)]
+        opt_size: bool,
+        
+        /// 最適化パスごとのコードサイズ（EIRの命令数）の増減を表示
+        #[clap(long)]
+        opt_stats: bool,
+        
+        /// `main` と `#[export]` の関数から到達できずに取り除いた関数を表示
+        #[clap(long)]
+        print_dead_functions: bool,
 
         /// 出力ファイル
         #[clap(short, long)]
         output: Option<PathBuf>,
+
+        /// マクロ展開の過程を表示
+        #[clap(long)]
+        trace_macros: bool,
+        
+        /// 出力形式（`c` でC99ソースコード、`js` でESモジュール、`llvm` でLLVM IR、`eir` でEIRのテキスト、`eirc` で分割コンパイル用のEIRのバイナリ、`deps` で依存関係を出力。省略すると eidos.toml の [build] の target、なければ `native`）
+        #[clap(long, value_enum)]
+        emit: Option<tools::compiler::CompileTarget>,
+        
+        /// `--emit c` で使うランタイム
+        #[clap(long, value_enum, default_value = "hosted")]
+        c_runtime: tools::compiler::CRuntimeKind,
+        
+        /// フェーズごとの所要時間とピークメモリを表示
+        #[clap(long)]
+        time_passes: bool,
+        
+        /// ソースファイルの変更を監視して再ビルド
+        #[clap(long)]
+        watch: bool,
+        
+        /// 出力を2回生成し、一致しなければエラーにする（ビルドの再現性の検査）
+        #[clap(long)]
+        reproducible: bool,
+        
+        /// EIRを構築した後と最適化パスの合間にEIRを検証
+        #[clap(long)]
+        verify_ir: bool,
+        
+        /// `assert` と `require`・`ensure` の契約を検査しない（条件の式も評価しない）
+        #[clap(long)]
+        no_assertions: bool,
+        
+        /// 基本ブロックごとの実行回数を数えるカウンタを埋め込み、終了時にプロファイルを書き出す（`--emit c`・`js`・`eir`）
+        #[clap(long)]
+        coverage: bool,
+        
+        /// 生成するコードの設定（`cpu=native`、`features=+avx2,+neon` など。複数回指定できる）
+        #[clap(long = "codegen", value_name = "キー=値")]
+        codegen: Vec<backend::codegen::CodegenSetting>,
+        
+        /// 再配置モデル（共有ライブラリに入れるコードは `pic`）
+        #[clap(long, value_enum, default_value = "default")]
+        relocation_model: backend::codegen::RelocationModel,
+        
+        /// コードモデル（コードとデータを置けるアドレスの範囲）
+        #[clap(long, value_enum, default_value = "default")]
+        code_model: backend::codegen::CodeModel,
+        
+        /// 一緒に出力する、事前にコンパイルしたEIRモジュール（`.eirc`。複数回指定できる）
+        #[clap(long, value_name = "ファイル")]
+        link: Vec<PathBuf>,
+        
+        /// リンク時最適化（`full` はすべてのモジュールをまとめてから最適化、`thin` は `--emit llvm` でモジュールごとにビットコードを出力）
+        #[clap(long, value_enum)]
+        lto: Option<backend::codegen::LtoMode>,
     },
     /// インタラクティブモード（REPL）を起動
     Repl {
@@ -46,9 +134,21 @@ enum Commands {
     },
     /// 型チェックのみ実行
     Check {
-        /// チェック対象のファイル
+        /// チェック対象のファイル、ディレクトリ、またはプロジェクトのマニフェスト（`eidos.toml`）
         #[clap(value_parser)]
-        file: PathBuf,
+        path: PathBuf,
+
+        /// マクロ展開の過程を表示
+        #[clap(long)]
+        trace_macros: bool,
+        
+        /// フェーズごとの所要時間とピークメモリを表示
+        #[clap(long)]
+        time_passes: bool,
+        
+        /// ファイルの変更を監視し、変更の影響を受けた関数などだけを検査し直す
+        #[clap(long, conflicts_with = "time_passes")]
+        watch: bool,
     },
     /// Eidosプログラムを実行
     Run {
@@ -56,40 +156,415 @@ enum Commands {
         #[clap(value_parser)]
         file: PathBuf,
         
+        /// ソースファイルの変更を監視して再実行
+        #[clap(long)]
+        watch: bool,
+        
+        /// JITで実行し、変更された関数を実行中のプログラムに差し替える（`--watch` と一緒に使う）
+        #[clap(long, requires = "watch", conflicts_with_all = ["max_memory", "timeout", "no_io", "args"])]
+        hot: bool,
+        
+        /// 関数の呼び出しと戻り値（`instr` では実行したEIRの命令も）を標準エラー出力に表示する（JITで実行）
+        #[clap(
+            long,
+            value_enum,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "calls",
+            conflicts_with_all = ["hot", "max_memory", "timeout", "no_io"]
+        )]
+        trace: Option<backend::jit::TraceMode>,
+        
+        /// `--trace` で表示する関数（名前にこの文字列を含む関数だけ）
+        #[clap(long, requires = "trace")]
+        trace_filter: Option<String>,
+        
+        /// プログラムが使えるメモリの上限（MiB）
+        #[clap(long)]
+        max_memory: Option<usize>,
+        
+        /// 実行時間の上限（秒）
+        #[clap(long, value_parser = parse_seconds)]
+        timeout: Option<std::time::Duration>,
+        
+        /// 標準入力・環境変数・ファイルへのアクセスを禁止（標準出力と標準エラーは使える）
+        #[clap(long)]
+        no_io: bool,
+        
         /// コマンド引数
         #[clap(last = true)]
         args: Vec<String>,
     },
+    /// `#[bench]` 関数のベンチマークを実行
+    Bench {
+        /// ベンチマークを含むファイル
+        #[clap(value_parser)]
+        file: PathBuf,
+        
+        /// 計測する実行回数
+        #[clap(long, default_value = "100")]
+        iterations: usize,
+        
+        /// 計測前に捨てる実行回数
+        #[clap(long, default_value = "10")]
+        warmup: usize,
+        
+        /// 名前にこの文字列を含むベンチマークだけを実行
+        #[clap(long)]
+        filter: Option<String>,
+        
+        /// 結果をこの名前のベースラインとして保存
+        #[clap(long)]
+        save_baseline: Option<String>,
+        
+        /// この名前のベースラインと比較
+        #[clap(long)]
+        baseline: Option<String>,
+    },
+    /// フロントエンドのファジング用ハーネスを生成（`--minimize` でコーパスを最小化）
+    FuzzFrontend {
+        /// 出力先ディレクトリ（ハーネス、または最小化したコーパス）
+        #[clap(long, short, default_value = "fuzz")]
+        output: PathBuf,
+        
+        /// 初期コーパスに加えるファイル（複数指定できる）
+        #[clap(long = "seed")]
+        seeds: Vec<PathBuf>,
+        
+        /// 最小化するコーパスのディレクトリ
+        #[clap(long)]
+        minimize: Option<PathBuf>,
+        
+        /// 最小化で使うターゲット（lexer, parser, type_checker, structured）
+        #[clap(long, default_value = "parser")]
+        target: String,
+    },
+    /// エラーコードの詳しい説明を表示（省略するとコードの一覧を表示）
+    Explain {
+        /// エラーコード（例: E0004）
+        code: Option<String>,
+    },
+    /// ファイルを字句解析してトークンを位置と共に表示
+    Lex {
+        /// 対象のファイル（`--json` で1トークンを1行の JSON で出力）
+        #[clap(value_parser)]
+        file: PathBuf,
+    },
+    /// ファイルを構文解析してASTを出力
+    Ast {
+        /// 対象のファイル
+        #[clap(value_parser)]
+        file: PathBuf,
+        
+        /// 出力形式
+        #[clap(long, value_enum, default_value = "json")]
+        format: tools::ast::AstFormat,
+        
+        /// マクロとDSLブロックを展開した後のASTを出力
+        #[clap(long)]
+        expand: bool,
+        
+        /// 出力ファイル（省略すると標準出力）
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// ファイルから構築したEIRを表示
+    Ir {
+        /// 対象のファイル
+        #[clap(value_parser)]
+        file: PathBuf,
+        
+        /// EIRの代わりに呼び出しグラフを DOT 形式で出力
+        #[clap(long)]
+        callgraph: bool,
+        
+        /// EIRの代わりに、指定した関数の制御フローグラフを DOT 形式で出力
+        #[clap(long, value_name = "FN")]
+        dot_cfg: Option<String>,
+        
+        /// `--dot-cfg` のグラフに支配木を重ねる
+        #[clap(long, requires = "dot_cfg")]
+        dominators: bool,
+        
+        /// 最適化レベル（0-3、0 なら最適化しない）
+        #[clap(short = 'O', long, default_value = "0")]
+        opt_level: u8,
+        
+        /// `--dot-cfg` のグラフを出力する最適化パス（カンマ区切り、省略すると最適化の前後だけ）
+        #[clap(long, value_enum, value_delimiter = ',', requires = "dot_cfg")]
+        dot_after: Vec<backend::optimizer::OptimizationPass>,
+        
+        /// マクロ展開の過程を表示
+        #[clap(long)]
+        trace_macros: bool,
+        
+        /// 出力ファイル（省略すると標準出力）
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// 現在有効な文法（登録済みのDSLを含む）から構文強調の定義を生成
+    Highlight {
+        /// 出力形式
+        #[clap(long, value_enum, default_value = "tmLanguage")]
+        emit: tools::highlight::HighlightFormat,
+        
+        /// 先に読み込み、`syntax`・`peg` で定義したDSLのキーワードも含めるファイル
+        #[clap(value_parser)]
+        files: Vec<PathBuf>,
+        
+        /// 出力ファイル（省略すると標準出力）
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// HTTP サーバーを起動
+    Serve {
+        /// ソースをWebAssemblyにコンパイルして返すプレイグラウンドのサービスを提供
+        #[clap(long)]
+        playground: bool,
+        
+        /// 待ち受けるアドレス
+        #[clap(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+        
+        /// 1回のコンパイルの制限時間（秒）
+        #[clap(long, default_value = "5")]
+        timeout: u64,
+        
+        /// 1回のコンパイルで使えるヒープの上限（MiB）
+        #[clap(long, default_value = "256")]
+        max_memory: usize,
+        
+        /// 受け付けるソースの最大バイト数
+        #[clap(long, default_value = "65536")]
+        max_source_size: usize,
+    },
+    /// プレイグラウンドのコンパイルを行うプロセス（`serve --playground` が起動する）
+    #[clap(hide = true)]
+    PlaygroundWorker {
+        /// ヒープの上限（MiB）
+        #[clap(long)]
+        max_memory: usize,
+        
+        #[clap(value_parser)]
+        file: PathBuf,
+    },
+    /// カバレッジの集計
+    Cov {
+        #[clap(subcommand)]
+        command: CovCommands,
+    },
+    /// マングリングしたシンボル名を読みやすい名前に戻す（省略すると標準入力を変換）
+    Demangle {
+        /// シンボル名（例: _EN8geometry4areaE）
+        symbols: Vec<String>,
+    },
+    /// コンパイルしたWebAssemblyモジュールを埋め込むためのバインディングと型定義（`.d.ts`）を生成
+    Bindgen {
+        /// WebAssemblyモジュール（`.wasm`）
+        #[clap(value_parser)]
+        file: PathBuf,
+        
+        /// バインディングを生成するホストの言語
+        #[clap(long, value_enum, default_value = "rust")]
+        host: Vec<tools::bindgen::HostLanguage>,
+        
+        /// 出力先のディレクトリ（省略するとモジュールと同じディレクトリ）
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CovCommands {
+    /// `build --coverage` で作ったプログラムのプロファイルから、ファイルごとの行と分岐のカバレッジを表示
+    Report {
+        /// ビルド時に出力した対応表（`<出力>.covmap.json`）
+        #[clap(long)]
+        map: PathBuf,
+        
+        /// 出力形式
+        #[clap(long, value_enum, default_value = "text")]
+        format: tools::cov::CovFormat,
+        
+        /// 出力ファイル（省略すると標準出力）
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        
+        /// 実行時に書き出したプロファイル（複数指定すると合算）
+        #[clap(value_parser, required = true)]
+        profiles: Vec<PathBuf>,
+    },
+}
+
+/// 秒数（小数も可）を読む（負の数、無限大、NaN は受け付けない）
+fn parse_seconds(value: &str) -> Result<std::time::Duration, String> {
+    let seconds: f64 = value.parse().map_err(|_| format!("'{}' は秒数ではありません", value))?;
+    std::time::Duration::try_from_secs_f64(seconds).map_err(|_| format!("'{}' は0以上の有限の秒数で指定してください", value))
 }
 
 fn main() {
     let cli = Cli::parse();
     
-    // ロギングの初期化
+    tools::output::configure(cli.quiet, cli.json);
+    let json = cli.json;
+    
+    // ロギングの初期化（ログは標準エラー出力に書く）
+    let log_level = cli.log_level.clone().unwrap_or_else(|| {
+        if cli.quiet || cli.json { "error" } else { "info" }.to_string()
+    });
     env_logger::Builder::from_env(env_logger::Env::default()
-        .default_filter_or(&cli.log_level))
+        .default_filter_or(&log_level))
         .init();
     
     info!("Eidos コンパイラが起動しました");
     
-    let result = match cli.command {
-        Commands::Build { file, opt_level, output } => {
+    // 組み込みモジュールのレジストリは、最初の起動でキャッシュに書き出して次回から読み込む
+    if let Some(path) = stdlib::cache::default_path() {
+        stdlib::StdlibRegistry::use_cache(path);
+    }
+    
+    if let Err(e) = stdlib::plugin::load_plugins(&cli.plugins) {
+        let exit_code = tools::output::report_error(&anyhow::Error::from(e));
+        process::exit(exit_code.code());
+    }
+    
+    // 入れ子の深さの上限まで再帰できるよう、コマンドの処理は大きなスタックを持つスレッドで行う
+    // （型検査などの並列処理のスレッドも同じ大きさにする）
+    if let Some(limit) = cli.recursion_limit {
+        crate::core::limits::set_recursion_limit(limit as usize);
+    }
+    if let Err(e) = rayon::ThreadPoolBuilder::new()
+        .stack_size(crate::core::limits::stack_size())
+        .build_global()
+    {
+        warn!("並列処理のスレッドを作成できません: {}", e);
+    }
+    
+    let result = crate::core::limits::with_stack(|| match cli.command {
+        // プロジェクトの設定（`eidos.toml`）は build・check・run・repl で読み込む（コマンドラインのオプションが優先）
+        Commands::Build { file, opt_level, opt_size, opt_stats, print_dead_functions, output, trace_macros, emit, c_runtime, time_passes, watch, reproducible, verify_ir, no_assertions, coverage, codegen, relocation_model, code_model, link, lto } => tools::project::load(&file).and_then(|config| {
+            let opt_level = opt_level.or(config.build.opt_level).unwrap_or(2);
+            let emit = emit.or(config.build.target).unwrap_or(tools::compiler::CompileTarget::Native);
             info!("ビルドモード: ファイル={}, 最適化レベル={}", file.display(), opt_level);
-            tools::compiler::compile_file(&file, opt_level, output)
-        },
-        Commands::Repl { preload } => {
+            let mut machine = backend::codegen::MachineOptions { relocation_model, code_model, ..Default::default() };
+            for setting in codegen {
+                machine.apply(setting);
+            }
+            let options = tools::compiler::CompileOptions {
+                opt_level,
+                optimize_size: opt_size,
+                opt_stats,
+                print_dead_functions,
+                output_path: output,
+                trace_macros,
+                target: emit,
+                c_runtime,
+                time_passes,
+                reproducible,
+                verify_ir,
+                coverage,
+                machine,
+                link,
+                lto,
+                lints: config.lints,
+                check_assertions: !no_assertions,
+                ..Default::default()
+            };
+            if watch {
+                tools::watch::watch(&file, || tools::compiler::compile_with_options(&file, &options))
+            } else {
+                tools::compiler::compile_with_options(&file, &options)
+            }
+        }),
+        Commands::Repl { preload } => tools::project::load(std::path::Path::new(".")).and_then(|_| {
             info!("REPLモード");
             tools::repl::start_repl(preload)
+        }),
+        Commands::Check { path, trace_macros, time_passes, watch } => tools::project::load(&path).and_then(|_| {
+            info!("型チェックモード: パス={}", path.display());
+            if watch {
+                tools::check::check_watch(&path, trace_macros)
+            } else {
+                tools::check::check(&path, trace_macros, time_passes)
+            }
+        }),
+        Commands::Run { file, watch, hot, trace, trace_filter, max_memory, timeout, no_io, args } => tools::project::load(&file).and_then(|_| {
+            info!("実行モード: ファイル={}", file.display());
+            let max_memory = max_memory
+                .map(|mib| mib.checked_mul(1 << 20).ok_or_else(|| anyhow::anyhow!("メモリの上限 {} MiB は大きすぎます", mib)))
+                .transpose()?;
+            let limits = backend::wasm::RunLimits { max_memory, timeout, no_io };
+            let trace = trace.map(|mode| backend::jit::TraceOptions { mode, filter: trace_filter });
+            let run = |args: Vec<String>| match &trace {
+                Some(trace) => tools::runner::trace_file(&file, trace),
+                None => tools::runner::run_file(&file, args, &limits),
+            };
+            if hot {
+                tools::watch::watch_hot(&file)
+            } else if watch {
+                tools::watch::watch(&file, || {
+                    run(args.clone()).map(|code| {
+                        if code != 0 {
+                            eprintln!("プログラムは終了コード {} で終了しました", code);
+                        }
+                    })
+                })
+            } else {
+                // プログラムの `main` が返した終了コードで終了する
+                match run(args) {
+                    Ok(code) if code != 0 => process::exit(code),
+                    result => result.map(|_| ()),
+                }
+            }
+        }),
+        Commands::Bench { file, iterations, warmup, filter, save_baseline, baseline } => {
+            info!("ベンチマークモード: ファイル={}", file.display());
+            let options = tools::bench::BenchOptions { warmup, iterations, filter, save_baseline, baseline };
+            tools::bench::bench_file(&file, &options)
         },
-        Commands::Check { file } => {
-            info!("型チェックモード: ファイル={}", file.display());
-            tools::compiler::typecheck_file(&file)
+        Commands::FuzzFrontend { output, seeds, minimize, target } => {
+            info!("ファジング用ハーネスの生成: 出力先={}", output.display());
+            tools::fuzz::fuzz_frontend(&output, &seeds, minimize.as_deref(), &target)
         },
-        Commands::Run { file, args } => {
-            info!("実行モード: ファイル={}", file.display());
-            tools::runner::run_file(&file, args)
+        Commands::Explain { code } => tools::explain::explain(code.as_deref()),
+        Commands::Lex { file } => tools::lex::lex(&file, json),
+        Commands::Ast { file, format, expand, output } => {
+            info!("ASTの出力: ファイル={}", file.display());
+            let options = tools::ast::AstOptions { format, expand, output };
+            tools::ast::ast(&file, &options)
+        },
+        Commands::Ir { file, callgraph, dot_cfg, dominators, opt_level, dot_after, trace_macros, output } => {
+            info!("EIRの表示: ファイル={}", file.display());
+            let options = tools::ir::IrOptions { callgraph, dot_cfg, dominators, opt_level, dot_after, trace_macros, output };
+            tools::ir::ir(&file, &options)
+        },
+        Commands::Highlight { emit, files, output } => {
+            let options = tools::highlight::HighlightOptions { emit, files, output };
+            tools::highlight::highlight(&options)
+        },
+        Commands::Serve { playground, addr, timeout, max_memory, max_source_size } => {
+            if playground {
+                let options = tools::serve::PlaygroundOptions {
+                    addr,
+                    timeout: std::time::Duration::from_secs(timeout),
+                    max_memory,
+                    max_source_size,
+                    plugins: cli.plugins.clone(),
+                };
+                tools::serve::serve_playground(&options)
+            } else {
+                Err(anyhow::anyhow!("提供するサービスを指定してください（--playground）"))
+            }
+        },
+        Commands::PlaygroundWorker { max_memory, file } => tools::serve::playground_worker(&file, max_memory),
+        Commands::Cov { command: CovCommands::Report { map, format, output, profiles } } => {
+            let options = tools::cov::CovReportOptions { map, profiles, format, output };
+            tools::cov::report(&options)
         },
-    };
+        Commands::Demangle { symbols } => tools::demangle::demangle(&symbols),
+        Commands::Bindgen { file, host, output } => tools::bindgen::bindgen(&file, &host, output.as_deref()),
+    })
+    .unwrap_or_else(|e| Err(e.into()));
     
     match result {
         Ok(_) => {
@@ -97,8 +572,9 @@ fn main() {
             process::exit(0);
         },
         Err(e) => {
-            eprintln!("エラー: {}", e);
-            process::exit(1);
+            // 失敗の種類ごとの終了コード（`core::ExitCode`）で終了する
+            let exit_code = tools::output::report_error(&e);
+            process::exit(exit_code.code());
         }
     }
 }
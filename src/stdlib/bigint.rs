@@ -0,0 +1,79 @@
+use crate::core::bigint;
+use crate::core::types::{Type, TypeKind};
+use crate::core::{EidosError, Result};
+use crate::stdlib::{StdlibFunction, StdlibFunctionType, StdlibModule, StdlibRegistry, Value};
+
+/// 任意精度整数モジュールの初期化
+///
+/// `BigInt` の演算子と `as` による変換は、コード生成でこのモジュールの関数の呼び出しになる。
+/// `BigInt` の引数には `Int` も渡せる（`bigint::from_int` で変換する）。
+pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
+    for function in bigint::FUNCTIONS {
+        let params: Vec<Type> = function.params.iter().map(|kind| Type::new(kind.clone())).collect();
+        let returns = Type::new(function.returns.clone());
+        let names: &[&str] = match function.name {
+            "parse" => &["text"],
+            "pow" | "mod_pow" => &["base", "exponent", "modulus"],
+            _ => &["value", "other"],
+        };
+        let args = names.iter().zip(&params).map(|(name, ty)| (name.to_string(), ty.id)).collect();
+        registry.register_overload(
+            StdlibFunction::new(
+                function.name,
+                StdlibModule::BigInt,
+                StdlibFunctionType::Pure,
+                args,
+                returns.id,
+                function.description,
+            ),
+            params,
+            returns,
+        );
+    }
+    Ok(())
+}
+
+/// 任意精度整数関数の実行（`BigInt` の値は10進数の文字列で受け渡す）
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    let function = bigint::function(function_name)
+        .ok_or_else(|| EidosError::Runtime(format!("不明な任意精度整数関数: {}", function_name)))?;
+    let texts = args
+        .iter()
+        .map(|arg| match arg {
+            Value::Int(value) => Ok(value.to_string()),
+            other => other.as_str().map(str::to_string),
+        })
+        .collect::<std::result::Result<Vec<String>, _>>()?;
+    let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let result = bigint::call(function_name, &texts)
+        .map_err(|message| EidosError::Runtime(format!("bigint::{}: {}", function_name, message)))?;
+    let invalid = || EidosError::Internal(format!("bigint::{} の戻り値を変換できません: {}", function_name, result));
+    Ok(match function.returns {
+        TypeKind::Int => Value::Int(result.parse().map_err(|_| invalid())?),
+        TypeKind::Float => Value::Float(result.parse().map_err(|_| invalid())?),
+        TypeKind::Bool => Value::Bool(result == "true"),
+        _ => Value::Str(result),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bigint_functions() {
+        let mut registry = StdlibRegistry::new();
+        initialize(&mut registry).unwrap();
+        // Int の引数は BigInt に変換して渡せる
+        let add = registry.resolve_overload("bigint::add", &[Type::bigint(), Type::int()]).unwrap();
+        assert_eq!(add.return_type.kind, TypeKind::BigInt);
+        assert!(registry.resolve_overload("bigint::add", &[Type::bigint(), Type::float()]).is_err());
+        
+        let big = Value::Str("18446744073709551616".to_string());
+        assert_eq!(execute_function("mul", &[big.clone(), Value::Int(2)]).unwrap(), Value::Str("36893488147419103232".to_string()));
+        assert_eq!(execute_function("to_int", std::slice::from_ref(&big)).unwrap(), Value::Int(0));
+        assert_eq!(execute_function("fits_int", &[big]).unwrap(), Value::Bool(false));
+        let error = execute_function("rem", &[Value::Int(1), Value::Int(0)]).unwrap_err();
+        assert!(error.to_string().contains("bigint::rem: division by zero"), "{}", error);
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::core::eirc;
+use crate::core::types::Type;
+use crate::core::{EidosError, Result};
+use crate::stdlib::{StdlibFunction, StdlibModule, StdlibRegistry, StdlibSignature};
+
+/// キャッシュファイルの先頭に置くマジックナンバー
+pub const MAGIC: &[u8; 4] = b"EISL";
+
+/// キャッシュの形式のバージョン（レジストリの構造を変えたら上げる）
+pub const FORMAT_VERSION: u32 = 1;
+
+/// ヘッダーの長さ（マジックナンバーと、リトルエンディアンの形式のバージョン）
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+/// キャッシュに保存する、組み込みモジュールのレジストリ
+///
+/// ネイティブ関数（プラグインが登録する関数）は保存しない。
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    /// キャッシュを書き出したコンパイラ（`fingerprint`）
+    compiler: String,
+    #[serde(serialize_with = "eirc::sorted_map")]
+    types: HashMap<String, Type>,
+    #[serde(serialize_with = "eirc::sorted_map")]
+    functions: HashMap<String, StdlibFunction>,
+    #[serde(serialize_with = "eirc::sorted_map")]
+    signatures: HashMap<String, Vec<StdlibSignature>>,
+}
+
+/// 実行中のコンパイラを識別する文字列（バージョンと実行ファイルの更新時刻）
+///
+/// 同じバージョンのまま標準ライブラリを変えてビルドし直した場合も、古いキャッシュを使わないようにする。
+pub fn fingerprint() -> String {
+    let modified = std::env::current_exe()
+        .and_then(|exe| exe.metadata())
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0);
+    format!("{}+{}", env!("CARGO_PKG_VERSION"), modified)
+}
+
+/// キャッシュの既定の置き場所（`$EIDOS_HOME/cache/stdlib.bin`、既定の `EIDOS_HOME` は `~/.eidos`）
+pub fn default_path() -> Option<PathBuf> {
+    let home = match std::env::var_os("EIDOS_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => PathBuf::from(std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?).join(".eidos"),
+    };
+    Some(home.join("cache").join("stdlib.bin"))
+}
+
+/// レジストリの組み込みモジュールの部分をキャッシュのバイト列にする
+pub fn encode(registry: &StdlibRegistry, compiler: &str) -> Result<Vec<u8>> {
+    let is_builtin = |full_name: &String| {
+        full_name.split_once("::").is_some_and(|(module, _)| StdlibModule::from_name(module).is_some())
+    };
+    let snapshot = Snapshot {
+        compiler: compiler.to_string(),
+        types: registry.types.clone(),
+        functions: registry.functions.clone(),
+        signatures: registry
+            .signatures
+            .iter()
+            .filter(|(name, _)| is_builtin(name))
+            .map(|(name, signatures)| (name.clone(), signatures.clone()))
+            .collect(),
+    };
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bincode::serialize_into(&mut bytes, &snapshot)
+        .map_err(|e| EidosError::Runtime(format!("標準ライブラリのキャッシュを直列化できません: {}", e)))?;
+    Ok(bytes)
+}
+
+/// キャッシュのバイト列を読み込み、レジストリに登録する
+///
+/// `compiler` と異なるコンパイラが書き出したキャッシュはエラーにする。読み込んだ型のIDは
+/// 書き出したときのままなので、この後に作る型とIDが重ならないようにする。
+pub fn decode_into(bytes: &[u8], compiler: &str, registry: &mut StdlibRegistry) -> Result<()> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid_data("標準ライブラリのキャッシュではありません".to_string()));
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+    if u32::from_le_bytes(version) != FORMAT_VERSION {
+        return Err(invalid_data("標準ライブラリのキャッシュの形式が異なります".to_string()));
+    }
+    let snapshot: Snapshot = bincode::deserialize(&bytes[HEADER_LEN..])
+        .map_err(|e| invalid_data(format!("標準ライブラリのキャッシュを読み込めません: {}", e)))?;
+    if snapshot.compiler != compiler {
+        return Err(invalid_data(format!(
+            "標準ライブラリのキャッシュは別のコンパイラ（{}）が書き出したものです",
+            snapshot.compiler
+        )));
+    }
+    
+    let max_id = snapshot
+        .types
+        .values()
+        .map(|ty| ty.id)
+        .chain(snapshot.functions.values().flat_map(|function| {
+            function.args.iter().map(|(_, ty)| *ty).chain([function.return_type])
+        }))
+        .chain(snapshot.signatures.values().flatten().flat_map(|signature| {
+            signature.params.iter().map(|param| param.id).chain([signature.return_type.id])
+        }))
+        .max();
+    if let Some(max_id) = max_id {
+        Type::reserve_ids(max_id);
+    }
+    registry.types.extend(snapshot.types);
+    registry.functions.extend(snapshot.functions);
+    registry.signatures.extend(snapshot.signatures);
+    Ok(())
+}
+
+/// キャッシュから標準ライブラリを読み込む（読めなければ組み込みモジュールを登録してキャッシュに書き出す）
+///
+/// `StdlibRegistry::initialize` が、キャッシュの場所を設定したとき（`StdlibRegistry::use_cache`）に呼ぶ。
+/// 書き出しに失敗してもエラーにはせず、次の起動でもう一度書き出す。
+pub(super) fn initialize(path: &Path, registry: &mut StdlibRegistry) -> Result<()> {
+    let compiler = fingerprint();
+    match std::fs::read(path).map_err(EidosError::IO).and_then(|bytes| decode_into(&bytes, &compiler, registry)) {
+        Ok(()) => {
+            debug!("標準ライブラリをキャッシュから読み込みました: {}", path.display());
+            return Ok(());
+        }
+        Err(e) => debug!("標準ライブラリのキャッシュを使いません（{}）: {}", path.display(), e),
+    }
+    
+    registry.register_builtins()?;
+    let written = encode(registry, &compiler).and_then(|bytes| write_atomically(path, &bytes).map_err(EidosError::IO));
+    match written {
+        Ok(()) => info!("標準ライブラリのキャッシュを書き出しました: {}", path.display()),
+        Err(e) => debug!("標準ライブラリのキャッシュを書き出せません（{}）: {}", path.display(), e),
+    }
+    Ok(())
+}
+
+/// 同時に起動した別のプロセスが書きかけのファイルを読まないよう、一時ファイルに書いてから置き換える
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    let temp = dir.join(format!(".stdlib.{}.tmp", std::process::id()));
+    std::fs::write(&temp, bytes)?;
+    std::fs::rename(&temp, path)
+}
+
+fn invalid_data(message: String) -> EidosError {
+    EidosError::IO(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::TypeId;
+    
+    fn builtins() -> StdlibRegistry {
+        let mut registry = StdlibRegistry::new();
+        registry.register_builtins().unwrap();
+        registry
+    }
+    
+    #[test]
+    fn test_round_trip() {
+        let registry = builtins();
+        let bytes = encode(&registry, "test").unwrap();
+        assert_eq!(&bytes[..4], MAGIC);
+        // 書き出しの順序は `HashMap` の走査順によらない
+        assert_eq!(encode(&registry, "test").unwrap(), bytes);
+        
+        let mut loaded = StdlibRegistry::new();
+        decode_into(&bytes, "test", &mut loaded).unwrap();
+        assert_eq!(loaded.functions.len(), registry.functions.len());
+        let mut names: Vec<&String> = loaded.signatures.keys().collect();
+        names.sort();
+        let mut expected: Vec<&String> = registry.signatures.keys().collect();
+        expected.sort();
+        assert_eq!(names, expected);
+        let max = loaded.get_overloads("math::max").unwrap();
+        assert_eq!(max.len(), 2);
+        assert_eq!(max[1].to_string(), registry.get_overloads("math::max").unwrap()[1].to_string());
+        assert_eq!(loaded.functions["thread::spawn"].description, registry.functions["thread::spawn"].description);
+        
+        // 読み込んだ後に作る型は、読み込んだ型とIDが重ならない
+        let max: TypeId = loaded.functions.values().map(|function| function.return_type).max().unwrap();
+        assert!(Type::int().id > max);
+    }
+    
+    #[test]
+    fn test_rejects_stale_cache() {
+        let bytes = encode(&builtins(), "0.1.0+1").unwrap();
+        let mut registry = StdlibRegistry::new();
+        let error = decode_into(&bytes, "0.1.0+2", &mut registry).unwrap_err();
+        assert!(error.to_string().contains("別のコンパイラ（0.1.0+1）"), "{}", error);
+        assert!(registry.functions.is_empty());
+        
+        let error = decode_into(b"EIRC\x02\x00\x00\x00", "0.1.0+1", &mut registry).unwrap_err();
+        assert!(error.to_string().contains("キャッシュではありません"), "{}", error);
+        
+        // 読めないキャッシュは作り直す
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache/stdlib.bin");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"broken").unwrap();
+        initialize(&path, &mut registry).unwrap();
+        assert!(!registry.functions.is_empty());
+        let mut reloaded = StdlibRegistry::new();
+        decode_into(&std::fs::read(&path).unwrap(), &fingerprint(), &mut reloaded).unwrap();
+        assert_eq!(reloaded.functions.len(), registry.functions.len());
+    }
+}
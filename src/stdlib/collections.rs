@@ -1,6 +1,6 @@
 use crate::core::{Result, EidosError};
 use crate::core::types::{Type, TypeId, TypeKind, Field};
-use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
 use std::sync::{Mutex, atomic::{AtomicUsize, Ordering}};
 use std::collections::{HashMap, BinaryHeap};
 use std::cmp::{Ord, PartialOrd, Ordering as CmpOrdering};
@@ -124,6 +124,13 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     );
     registry.register_type("collections::HashSet", hashset_type.clone());
     
+    // Map<K, V> / Set<T> は HashMap / HashSet を実体とする標準の名前
+    let map_type = hashmap_type.clone();
+    let set_elem_type = hashset_elem_type.clone();
+    let set_type = hashset_type.clone();
+    registry.register_type("collections::Map", map_type.clone());
+    registry.register_type("collections::Set", set_type.clone());
+    
     // LinkedList（連結リスト）型の定義
     let linkedlist_elem_type = Type::generic("T");
     let linkedlist_type = Type::new(
@@ -466,7 +473,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Vector::new",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         vector_type.id,
         "新しい空のVector（動的配列）を作成します。",
@@ -476,7 +483,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Vector::with_capacity",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("capacity".to_string(), int_type.id)],
         vector_type.id,
         "指定した初期容量でVector（動的配列）を作成します。",
@@ -578,7 +585,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "HashMap::new",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         hashmap_type.id,
         "新しい空のHashMap（ハッシュマップ）を作成します。",
@@ -673,7 +680,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "HashSet::new",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         hashset_type.id,
         "新しい空のHashSet（ハッシュセット）を作成します。",
@@ -748,13 +755,219 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         "HashSetが空かどうかを返します。",
     ));
     
+    // Map関数の登録
+    
+    // Map::new - 新しいMapを作成
+    registry.register_function(StdlibFunction::new(
+        "Map::new",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![],
+        map_type.id,
+        "新しい空のMapを作成します。",
+    ));
+    
+    // Map::insert - キーと値のペアを追加
+    registry.register_function(StdlibFunction::new(
+        "Map::insert",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("map".to_string(), map_type.id),
+            ("key".to_string(), hashmap_key_type.id),
+            ("value".to_string(), hashmap_value_type.id),
+        ],
+        unit_type.id,
+        "Mapにキーと値のペアを追加します。キーが既に存在する場合は値が上書きされます。",
+    ));
+    
+    // Map::get - キーに対応する値を取得
+    registry.register_function(StdlibFunction::new(
+        "Map::get",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![
+            ("map".to_string(), map_type.id),
+            ("key".to_string(), hashmap_key_type.id),
+        ],
+        hashmap_value_type.id,
+        "キーに対応する値を取得します。キーが存在しない場合はエラーになります。",
+    ));
+    
+    // Map::remove - キーと対応する値を削除
+    registry.register_function(StdlibFunction::new(
+        "Map::remove",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("map".to_string(), map_type.id),
+            ("key".to_string(), hashmap_key_type.id),
+        ],
+        hashmap_value_type.id,
+        "キーと対応する値をMapから削除し、削除した値を返します。",
+    ));
+    
+    // Map::contains - キーが存在するかどうかを確認
+    registry.register_function(StdlibFunction::new(
+        "Map::contains",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![
+            ("map".to_string(), map_type.id),
+            ("key".to_string(), hashmap_key_type.id),
+        ],
+        bool_type.id,
+        "指定したキーがMapに存在するかどうかを返します。",
+    ));
+    
+    // Map::len - 要素数を取得
+    registry.register_function(StdlibFunction::new(
+        "Map::len",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![("map".to_string(), map_type.id)],
+        int_type.id,
+        "Mapのキーと値のペアの数を返します。",
+    ));
+    
+    // Map::is_empty - 空かどうかを確認
+    registry.register_function(StdlibFunction::new(
+        "Map::is_empty",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![("map".to_string(), map_type.id)],
+        bool_type.id,
+        "Mapが空かどうかを返します。",
+    ));
+    
+    // Map::clear - すべての要素を削除
+    registry.register_function(StdlibFunction::new(
+        "Map::clear",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![("map".to_string(), map_type.id)],
+        unit_type.id,
+        "Mapのすべてのキーと値のペアを削除します。",
+    ));
+    
+    // Map::keys - キーの一覧を取得
+    registry.register_function(StdlibFunction::new(
+        "Map::keys",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![("map".to_string(), map_type.id)],
+        vector_type.id,
+        "Mapのキーを昇順に並べたVectorを返します。",
+    ));
+    
+    // Map::values - 値の一覧を取得
+    registry.register_function(StdlibFunction::new(
+        "Map::values",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![("map".to_string(), map_type.id)],
+        vector_type.id,
+        "Mapの値をキーの昇順に並べたVectorを返します。",
+    ));
+    
+    // Set関数の登録
+    
+    // Set::new - 新しいSetを作成
+    registry.register_function(StdlibFunction::new(
+        "Set::new",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![],
+        set_type.id,
+        "新しい空のSetを作成します。",
+    ));
+    
+    // Set::insert - 要素を追加
+    registry.register_function(StdlibFunction::new(
+        "Set::insert",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("set".to_string(), set_type.id),
+            ("element".to_string(), set_elem_type.id),
+        ],
+        bool_type.id,
+        "Setに要素を追加します。新しく追加された場合はtrueを返します。",
+    ));
+    
+    // Set::remove - 要素を削除
+    registry.register_function(StdlibFunction::new(
+        "Set::remove",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("set".to_string(), set_type.id),
+            ("element".to_string(), set_elem_type.id),
+        ],
+        bool_type.id,
+        "Setから要素を削除します。要素が存在した場合はtrueを返します。",
+    ));
+    
+    // Set::contains - 要素が含まれているかどうかを確認
+    registry.register_function(StdlibFunction::new(
+        "Set::contains",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![
+            ("set".to_string(), set_type.id),
+            ("element".to_string(), set_elem_type.id),
+        ],
+        bool_type.id,
+        "指定した要素がSetに含まれているかどうかを返します。",
+    ));
+    
+    // Set::len - 要素数を取得
+    registry.register_function(StdlibFunction::new(
+        "Set::len",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![("set".to_string(), set_type.id)],
+        int_type.id,
+        "Setの要素数を返します。",
+    ));
+    
+    // Set::is_empty - 空かどうかを確認
+    registry.register_function(StdlibFunction::new(
+        "Set::is_empty",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![("set".to_string(), set_type.id)],
+        bool_type.id,
+        "Setが空かどうかを返します。",
+    ));
+    
+    // Set::clear - すべての要素を削除
+    registry.register_function(StdlibFunction::new(
+        "Set::clear",
+        StdlibModule::Collections,
+        StdlibFunctionType::Effectful,
+        vec![("set".to_string(), set_type.id)],
+        unit_type.id,
+        "Setのすべての要素を削除します。",
+    ));
+    
+    // Set::values - 要素の一覧を取得
+    registry.register_function(StdlibFunction::new(
+        "Set::values",
+        StdlibModule::Collections,
+        StdlibFunctionType::Pure,
+        vec![("set".to_string(), set_type.id)],
+        vector_type.id,
+        "Setの要素を昇順に並べたVectorを返します。",
+    ));
+    
     // LinkedList関数の登録
     
     // LinkedList::new - 新しいLinkedListを作成
     registry.register_function(StdlibFunction::new(
         "LinkedList::new",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         linkedlist_type.id,
         "新しい空のLinkedList（連結リスト）を作成します。",
@@ -826,7 +1039,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Queue::new",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         queue_type.id,
         "新しい空のQueue（キュー）を作成します。",
@@ -887,7 +1100,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Stack::new",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         stack_type.id,
         "新しい空のStack（スタック）を作成します。",
@@ -948,7 +1161,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "PriorityQueue::new",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         pqueue_type.id,
         "新しい空のPriorityQueue（優先度キュー）を作成します。",
@@ -1010,7 +1223,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Vector::from_list",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("list".to_string(), linkedlist_type.id)],
         vector_type.id,
         "連結リストをベクターに変換します。",
@@ -1019,7 +1232,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "LinkedList::from_vector",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("vector".to_string(), vector_type.id)],
         linkedlist_type.id,
         "ベクターを連結リストに変換します。",
@@ -1028,7 +1241,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "HashSet::from_vector",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("vector".to_string(), vector_type.id)],
         hashset_type.id,
         "ベクターをハッシュセットに変換します。",
@@ -1037,7 +1250,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Vector::from_hashset",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("set".to_string(), hashset_type.id)],
         vector_type.id,
         "ハッシュセットをベクターに変換します。",
@@ -1046,7 +1259,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Queue::from_vector",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("vector".to_string(), vector_type.id)],
         queue_type.id,
         "ベクターをキューに変換します。",
@@ -1055,7 +1268,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Vector::from_queue",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("queue".to_string(), queue_type.id)],
         vector_type.id,
         "キューをベクターに変換します。",
@@ -1064,7 +1277,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Stack::from_vector",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("vector".to_string(), vector_type.id)],
         stack_type.id,
         "ベクターをスタックに変換します。",
@@ -1073,7 +1286,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Vector::from_stack",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("stack".to_string(), stack_type.id)],
         vector_type.id,
         "スタックをベクターに変換します。",
@@ -1083,7 +1296,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Collection::clone",
         StdlibModule::Collections,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("collection".to_string(), vector_type.id)],
         vector_type.id,
         "コレクションをクローンします。",
@@ -1103,12 +1316,12 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
 
 // コレクションのインスタンスを管理するためのグローバル状態
 lazy_static! {
-    static ref VECTOR_INSTANCES: Mutex<HashMap<String, Vec<Value>>> = Mutex::new(HashMap::new());
-    static ref HASHMAP_INSTANCES: Mutex<HashMap<String, HashMap<String, Value>>> = Mutex::new(HashMap::new());
+    static ref VECTOR_INSTANCES: Mutex<HashMap<String, Vec<Element>>> = Mutex::new(HashMap::new());
+    static ref HASHMAP_INSTANCES: Mutex<HashMap<String, HashMap<String, Element>>> = Mutex::new(HashMap::new());
     static ref HASHSET_INSTANCES: Mutex<HashMap<String, std::collections::HashSet<String>>> = Mutex::new(HashMap::new());
-    static ref LINKEDLIST_INSTANCES: Mutex<HashMap<String, std::collections::LinkedList<Value>>> = Mutex::new(HashMap::new());
-    static ref QUEUE_INSTANCES: Mutex<HashMap<String, std::collections::VecDeque<Value>>> = Mutex::new(HashMap::new());
-    static ref STACK_INSTANCES: Mutex<HashMap<String, Vec<Value>>> = Mutex::new(HashMap::new());
+    static ref LINKEDLIST_INSTANCES: Mutex<HashMap<String, std::collections::LinkedList<Element>>> = Mutex::new(HashMap::new());
+    static ref QUEUE_INSTANCES: Mutex<HashMap<String, std::collections::VecDeque<Element>>> = Mutex::new(HashMap::new());
+    static ref STACK_INSTANCES: Mutex<HashMap<String, Vec<Element>>> = Mutex::new(HashMap::new());
     static ref PRIORITYQUEUE_INSTANCES: Mutex<HashMap<String, BinaryHeap<PriorityItem>>> = Mutex::new(HashMap::new());
     static ref INSTANCE_COUNTER: AtomicUsize = AtomicUsize::new(0);
 }
@@ -1122,7 +1335,7 @@ lazy_static! {
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct PriorityItem {
     priority: i64,
-    value: Value,
+    value: Element,
 }
 
 // 優先度キューのアイテムをOrdering実装
@@ -1140,9 +1353,9 @@ impl PartialOrd for PriorityItem {
     }
 }
 
-/// コレクション値を表現する列挙型
+/// コレクションに格納する要素を表現する列挙型
 #[derive(Clone, Debug)]
-pub enum Value {
+pub enum Element {
     Int(i64),
     Float(f64),
     Bool(bool),
@@ -1157,78 +1370,116 @@ pub enum Value {
     Null,
 }
 
-impl From<&str> for Value {
+impl From<&str> for Element {
     fn from(s: &str) -> Self {
         // 文字列値から適切な型に変換
         if s == "null" || s == "nil" {
-            return Value::Null;
+            return Element::Null;
         }
         
         if let Ok(i) = s.parse::<i64>() {
-            return Value::Int(i);
+            return Element::Int(i);
         }
         
         if let Ok(f) = s.parse::<f64>() {
-            return Value::Float(f);
+            return Element::Float(f);
         }
         
         if s == "true" {
-            return Value::Bool(true);
+            return Element::Bool(true);
         }
         
         if s == "false" {
-            return Value::Bool(false);
+            return Element::Bool(false);
         }
         
         // 特殊な参照形式を検出
         if s.starts_with("vector:") {
-            return Value::Vector(s.to_string());
+            return Element::Vector(s.to_string());
         }
         
         if s.starts_with("hashmap:") {
-            return Value::HashMap(s.to_string());
+            return Element::HashMap(s.to_string());
         }
         
         if s.starts_with("hashset:") {
-            return Value::HashSet(s.to_string());
+            return Element::HashSet(s.to_string());
         }
         
         if s.starts_with("linkedlist:") {
-            return Value::LinkedList(s.to_string());
+            return Element::LinkedList(s.to_string());
         }
         
         if s.starts_with("queue:") {
-            return Value::Queue(s.to_string());
+            return Element::Queue(s.to_string());
         }
         
         if s.starts_with("stack:") {
-            return Value::Stack(s.to_string());
+            return Element::Stack(s.to_string());
         }
         
         if s.starts_with("priorityqueue:") {
-            return Value::PriorityQueue(s.to_string());
+            return Element::PriorityQueue(s.to_string());
         }
         
         // それ以外は文字列
-        Value::String(s.to_string())
+        Element::String(s.to_string())
     }
 }
 
-impl ToString for Value {
+impl ToString for Element {
     fn to_string(&self) -> String {
         match self {
-            Value::Int(i) => i.to_string(),
-            Value::Float(f) => f.to_string(),
-            Value::Bool(b) => b.to_string(),
-            Value::String(s) => s.clone(),
-            Value::Vector(id) => id.clone(),
-            Value::HashMap(id) => id.clone(),
-            Value::HashSet(id) => id.clone(),
-            Value::LinkedList(id) => id.clone(),
-            Value::Queue(id) => id.clone(),
-            Value::Stack(id) => id.clone(),
-            Value::PriorityQueue(id) => id.clone(),
-            Value::Null => "null".to_string(),
+            Element::Int(i) => i.to_string(),
+            Element::Float(f) => f.to_string(),
+            Element::Bool(b) => b.to_string(),
+            Element::String(s) => s.clone(),
+            Element::Vector(id) => id.clone(),
+            Element::HashMap(id) => id.clone(),
+            Element::HashSet(id) => id.clone(),
+            Element::LinkedList(id) => id.clone(),
+            Element::Queue(id) => id.clone(),
+            Element::Stack(id) => id.clone(),
+            Element::PriorityQueue(id) => id.clone(),
+            Element::Null => "null".to_string(),
+        }
+    }
+}
+
+impl From<&Value> for Element {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Int(i) => Element::Int(*i),
+            Value::Float(f) => Element::Float(*f),
+            Value::Bool(b) => Element::Bool(*b),
+            // コレクションへの参照は `vector:3` のようなIDの文字列で渡される
+            Value::Str(s) => match Element::from(s.as_str()) {
+                reference if reference.is_collection_reference() => reference,
+                _ => Element::String(s.clone()),
+            },
+            Value::List(items) => Element::Vector(new_vector(items.iter().map(Element::from).collect())),
+            Value::Map(entries) => {
+                let map = entries.iter().map(|(key, value)| (key.to_string(), Element::from(value))).collect();
+                let map_id = generate_instance_id("hashmap");
+                HASHMAP_INSTANCES.lock().unwrap().insert(map_id.clone(), map);
+                Element::HashMap(map_id)
+            }
+            // `Option` と `Result` はコレクションの要素としては表示した文字列で保持する
+            Value::Option(_) | Value::Result(_) => Element::String(value.to_string()),
+            Value::Unit => Element::Null,
+        }
+    }
+}
+
+impl From<Element> for Value {
+    fn from(element: Element) -> Self {
+        match element {
+            Element::Int(i) => Value::Int(i),
+            Element::Float(f) => Value::Float(f),
+            Element::Bool(b) => Value::Bool(b),
+            Element::String(s) => Value::Str(s),
+            Element::Null => Value::Unit,
+            reference => Value::Str(reference.to_string()),
         }
     }
 }
@@ -1239,13 +1490,53 @@ fn generate_instance_id(prefix: &str) -> String {
     format!("{}:{}", prefix, id)
 }
 
+/// Map / Set の関数名を、実体であるHashMap / HashSetの関数名に読み替える
+fn resolve_alias(function_name: &str) -> &str {
+    match function_name {
+        "Map::new" => "HashMap::new",
+        "Map::insert" => "HashMap::insert",
+        "Map::get" => "HashMap::get",
+        "Map::remove" => "HashMap::remove",
+        "Map::contains" => "HashMap::contains_key",
+        "Map::len" => "HashMap::len",
+        "Map::is_empty" => "HashMap::is_empty",
+        "Map::clear" => "HashMap::clear",
+        "Map::keys" => "HashMap::keys",
+        "Map::values" => "HashMap::values",
+        "Set::new" => "HashSet::new",
+        "Set::insert" => "HashSet::add",
+        "Set::remove" => "HashSet::remove",
+        "Set::contains" => "HashSet::contains",
+        "Set::len" => "HashSet::size",
+        "Set::is_empty" => "HashSet::is_empty",
+        "Set::clear" => "HashSet::clear",
+        "Set::values" => "HashSet::values",
+        other => other,
+    }
+}
+
+/// キーの並び順。整数どうしは数値として、それ以外は文字列として比べる
+fn compare_keys(a: &str, b: &str) -> CmpOrdering {
+    match (a.parse::<i64>(), b.parse::<i64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// 値の列を新しいベクターとして登録し、その参照IDを返す
+fn new_vector(items: Vec<Element>) -> String {
+    let vector_id = generate_instance_id("vector");
+    VECTOR_INSTANCES.lock().unwrap().insert(vector_id.clone(), items);
+    vector_id
+}
+
 /// コレクション関数の実行
-pub fn execute_function(function_name: &str, args: &[String]) -> Result<String> {
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
     // 引数をValue型に変換
-    let values: Vec<Value> = args.iter().map(|s| Value::from(s.as_str())).collect();
+    let values: Vec<Element> = args.iter().map(Element::from).collect();
     
     // 各コレクション関数の実装
-    match function_name {
+    match resolve_alias(function_name) {
         // Vector関数
         "Vector::new" => {
             // 新しい空のVectorを作成
@@ -1254,7 +1545,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // グローバル状態に登録
             VECTOR_INSTANCES.lock().unwrap().insert(instance_id.clone(), Vec::new());
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "Vector::with_capacity" => {
             if values.len() != 1 {
@@ -1263,7 +1554,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // 容量を取得
             let capacity = match &values[0] {
-                Value::Int(i) => *i as usize,
+                Element::Int(i) => *i as usize,
                 _ => return Err(EidosError::Runtime("容量は整数である必要があります".to_string())),
             };
             
@@ -1271,7 +1562,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let instance_id = generate_instance_id("vector");
             VECTOR_INSTANCES.lock().unwrap().insert(instance_id.clone(), Vec::with_capacity(capacity));
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "Vector::push" => {
             if values.len() != 2 {
@@ -1280,7 +1571,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はベクター参照である必要があります".to_string())),
             };
             
@@ -1291,7 +1582,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = VECTOR_INSTANCES.lock().unwrap();
             if let Some(vector) = instances.get_mut(&vector_ref) {
                 vector.push(element);
-                Ok(vector_ref)
+                Ok(Value::Str(vector_ref))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -1303,7 +1594,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
@@ -1311,7 +1602,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = VECTOR_INSTANCES.lock().unwrap();
             if let Some(vector) = instances.get_mut(&vector_ref) {
                 if let Some(value) = vector.pop() {
-                    Ok(value.to_string())
+                    Ok(Value::from(value))
                 } else {
                     Err(EidosError::Runtime("ベクターが空です".to_string()))
                 }
@@ -1326,13 +1617,13 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はベクター参照である必要があります".to_string())),
             };
             
             // インデックスを取得
             let index = match &values[1] {
-                Value::Int(i) => *i as usize,
+                Element::Int(i) => *i as usize,
                 _ => return Err(EidosError::Runtime("インデックスは整数である必要があります".to_string())),
             };
             
@@ -1340,7 +1631,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let instances = VECTOR_INSTANCES.lock().unwrap();
             if let Some(vector) = instances.get(&vector_ref) {
                 if index < vector.len() {
-                    Ok(vector[index].to_string())
+                    Ok(Value::from(vector[index].clone()))
                 } else {
                     Err(EidosError::Runtime(format!("インデックス {} は範囲外です (len: {})", index, vector.len())))
                 }
@@ -1355,14 +1646,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
             // 長さを取得
             let instances = VECTOR_INSTANCES.lock().unwrap();
             if let Some(vector) = instances.get(&vector_ref) {
-                Ok(vector.len().to_string())
+                Ok(Value::from(vector.len()))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -1374,14 +1665,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
             // 空かどうかを確認
             let instances = VECTOR_INSTANCES.lock().unwrap();
             if let Some(vector) = instances.get(&vector_ref) {
-                Ok(vector.is_empty().to_string())
+                Ok(Value::from(vector.is_empty()))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -1393,7 +1684,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
@@ -1401,7 +1692,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = VECTOR_INSTANCES.lock().unwrap();
             if let Some(vector) = instances.get_mut(&vector_ref) {
                 vector.clear();
-                Ok(vector_ref)
+                Ok(Value::Str(vector_ref))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -1415,7 +1706,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // グローバル状態に登録
             HASHMAP_INSTANCES.lock().unwrap().insert(instance_id.clone(), HashMap::new());
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "HashMap::insert" => {
             if values.len() != 3 {
@@ -1424,7 +1715,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュマップ参照を取得
             let map_ref = match &values[0] {
-                Value::HashMap(id) => id.clone(),
+                Element::HashMap(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はハッシュマップ参照である必要があります".to_string())),
             };
             
@@ -1436,7 +1727,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = HASHMAP_INSTANCES.lock().unwrap();
             if let Some(map) = instances.get_mut(&map_ref) {
                 map.insert(key, value);
-                Ok(map_ref)
+                Ok(Value::Str(map_ref))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュマップ参照 '{}' は無効です", map_ref)))
             }
@@ -1448,7 +1739,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュマップ参照を取得
             let map_ref = match &values[0] {
-                Value::HashMap(id) => id.clone(),
+                Element::HashMap(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はハッシュマップ参照である必要があります".to_string())),
             };
             
@@ -1459,7 +1750,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let instances = HASHMAP_INSTANCES.lock().unwrap();
             if let Some(map) = instances.get(&map_ref) {
                 if let Some(value) = map.get(&key) {
-                    Ok(value.to_string())
+                    Ok(Value::from(value.clone()))
                 } else {
                     Err(EidosError::Runtime(format!("キー '{}' が見つかりません", key)))
                 }
@@ -1474,7 +1765,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュマップ参照を取得
             let map_ref = match &values[0] {
-                Value::HashMap(id) => id.clone(),
+                Element::HashMap(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はハッシュマップ参照である必要があります".to_string())),
             };
             
@@ -1484,7 +1775,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // キーが存在するか確認
             let instances = HASHMAP_INSTANCES.lock().unwrap();
             if let Some(map) = instances.get(&map_ref) {
-                Ok(map.contains_key(&key).to_string())
+                Ok(Value::Bool(map.contains_key(&key)))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュマップ参照 '{}' は無効です", map_ref)))
             }
@@ -1496,7 +1787,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュマップ参照を取得
             let map_ref = match &values[0] {
-                Value::HashMap(id) => id.clone(),
+                Element::HashMap(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はハッシュマップ参照である必要があります".to_string())),
             };
             
@@ -1507,7 +1798,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = HASHMAP_INSTANCES.lock().unwrap();
             if let Some(map) = instances.get_mut(&map_ref) {
                 if let Some(value) = map.remove(&key) {
-                    Ok(value.to_string())
+                    Ok(Value::from(value))
                 } else {
                     Err(EidosError::Runtime(format!("キー '{}' が見つかりません", key)))
                 }
@@ -1522,14 +1813,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュマップ参照を取得
             let map_ref = match &values[0] {
-                Value::HashMap(id) => id.clone(),
+                Element::HashMap(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はハッシュマップ参照である必要があります".to_string())),
             };
             
             // サイズを取得
             let instances = HASHMAP_INSTANCES.lock().unwrap();
             if let Some(map) = instances.get(&map_ref) {
-                Ok(map.len().to_string())
+                Ok(Value::from(map.len()))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュマップ参照 '{}' は無効です", map_ref)))
             }
@@ -1541,7 +1832,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュマップ参照を取得
             let map_ref = match &values[0] {
-                Value::HashMap(id) => id.clone(),
+                Element::HashMap(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はハッシュマップ参照である必要があります".to_string())),
             };
             
@@ -1549,7 +1840,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = HASHMAP_INSTANCES.lock().unwrap();
             if let Some(map) = instances.get_mut(&map_ref) {
                 map.clear();
-                Ok(map_ref)
+                Ok(Value::Str(map_ref))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュマップ参照 '{}' は無効です", map_ref)))
             }
@@ -1561,18 +1852,46 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュマップ参照を取得
             let map_ref = match &values[0] {
-                Value::HashMap(id) => id.clone(),
+                Element::HashMap(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はハッシュマップ参照である必要があります".to_string())),
             };
             
             // 空かどうかを確認
             let instances = HASHMAP_INSTANCES.lock().unwrap();
             if let Some(map) = instances.get(&map_ref) {
-                Ok(map.is_empty().to_string())
+                Ok(Value::from(map.is_empty()))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュマップ参照 '{}' は無効です", map_ref)))
             }
         },
+        "HashMap::keys" | "HashMap::values" => {
+            if values.len() != 1 {
+                return Err(EidosError::Runtime(format!("{}にはハッシュマップが必要です", function_name)));
+            }
+            
+            // ハッシュマップ参照を取得
+            let map_ref = match &values[0] {
+                Element::HashMap(id) => id.clone(),
+                _ => return Err(EidosError::Runtime("引数はハッシュマップ参照である必要があります".to_string())),
+            };
+            
+            // 反復順序を安定させるため、キーの昇順に並べる
+            let mut entries: Vec<(String, Element)> = {
+                let instances = HASHMAP_INSTANCES.lock().unwrap();
+                match instances.get(&map_ref) {
+                    Some(map) => map.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+                    None => return Err(EidosError::Runtime(format!("ハッシュマップ参照 '{}' は無効です", map_ref))),
+                }
+            };
+            entries.sort_by(|(a, _), (b, _)| compare_keys(a, b));
+            
+            let items = if resolve_alias(function_name) == "HashMap::keys" {
+                entries.into_iter().map(|(k, _)| Element::from(k.as_str())).collect()
+            } else {
+                entries.into_iter().map(|(_, v)| v).collect()
+            };
+            Ok(Value::Str(new_vector(items)))
+        },
         
         // HashSet関数
         "HashSet::new" => {
@@ -1582,7 +1901,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // グローバル状態に登録
             HASHSET_INSTANCES.lock().unwrap().insert(instance_id.clone(), std::collections::HashSet::new());
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "HashSet::add" => {
             if values.len() != 2 {
@@ -1591,7 +1910,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュセット参照を取得
             let set_ref = match &values[0] {
-                Value::HashSet(id) => id.clone(),
+                Element::HashSet(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はハッシュセット参照である必要があります".to_string())),
             };
             
@@ -1602,7 +1921,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = HASHSET_INSTANCES.lock().unwrap();
             if let Some(set) = instances.get_mut(&set_ref) {
                 let result = set.insert(element);
-                Ok(result.to_string())
+                Ok(Value::Bool(result))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref)))
             }
@@ -1614,7 +1933,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュセット参照を取得
             let set_ref = match &values[0] {
-                Value::HashSet(id) => id.clone(),
+                Element::HashSet(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はハッシュセット参照である必要があります".to_string())),
             };
             
@@ -1624,7 +1943,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // 要素が含まれているか確認
             let instances = HASHSET_INSTANCES.lock().unwrap();
             if let Some(set) = instances.get(&set_ref) {
-                Ok(set.contains(&element).to_string())
+                Ok(Value::Bool(set.contains(&element)))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref)))
             }
@@ -1636,7 +1955,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュセット参照を取得
             let set_ref = match &values[0] {
-                Value::HashSet(id) => id.clone(),
+                Element::HashSet(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はハッシュセット参照である必要があります".to_string())),
             };
             
@@ -1647,7 +1966,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = HASHSET_INSTANCES.lock().unwrap();
             if let Some(set) = instances.get_mut(&set_ref) {
                 let result = set.remove(&element);
-                Ok(result.to_string())
+                Ok(Value::Bool(result))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref)))
             }
@@ -1659,14 +1978,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュセット参照を取得
             let set_ref = match &values[0] {
-                Value::HashSet(id) => id.clone(),
+                Element::HashSet(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はハッシュセット参照である必要があります".to_string())),
             };
             
             // サイズを取得
             let instances = HASHSET_INSTANCES.lock().unwrap();
             if let Some(set) = instances.get(&set_ref) {
-                Ok(set.len().to_string())
+                Ok(Value::from(set.len()))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref)))
             }
@@ -1678,7 +1997,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュセット参照を取得
             let set_ref = match &values[0] {
-                Value::HashSet(id) => id.clone(),
+                Element::HashSet(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はハッシュセット参照である必要があります".to_string())),
             };
             
@@ -1686,7 +2005,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = HASHSET_INSTANCES.lock().unwrap();
             if let Some(set) = instances.get_mut(&set_ref) {
                 set.clear();
-                Ok(set_ref)
+                Ok(Value::Str(set_ref))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref)))
             }
@@ -1698,18 +2017,41 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュセット参照を取得
             let set_ref = match &values[0] {
-                Value::HashSet(id) => id.clone(),
+                Element::HashSet(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はハッシュセット参照である必要があります".to_string())),
             };
             
             // 空かどうかを確認
             let instances = HASHSET_INSTANCES.lock().unwrap();
             if let Some(set) = instances.get(&set_ref) {
-                Ok(set.is_empty().to_string())
+                Ok(Value::from(set.is_empty()))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref)))
             }
         },
+        "HashSet::values" => {
+            if values.len() != 1 {
+                return Err(EidosError::Runtime(format!("{}にはハッシュセットが必要です", function_name)));
+            }
+            
+            // ハッシュセット参照を取得
+            let set_ref = match &values[0] {
+                Element::HashSet(id) => id.clone(),
+                _ => return Err(EidosError::Runtime("引数はハッシュセット参照である必要があります".to_string())),
+            };
+            
+            // 反復順序を安定させるため、要素の昇順に並べる
+            let mut elements: Vec<String> = {
+                let instances = HASHSET_INSTANCES.lock().unwrap();
+                match instances.get(&set_ref) {
+                    Some(set) => set.iter().cloned().collect(),
+                    None => return Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref))),
+                }
+            };
+            elements.sort_by(|a, b| compare_keys(a, b));
+            
+            Ok(Value::Str(new_vector(elements.iter().map(|e| Element::from(e.as_str())).collect())))
+        },
         
         // LinkedList関数
         "LinkedList::new" => {
@@ -1719,7 +2061,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // グローバル状態に登録
             LINKEDLIST_INSTANCES.lock().unwrap().insert(instance_id.clone(), std::collections::LinkedList::new());
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "LinkedList::push_front" => {
             if values.len() != 2 {
@@ -1728,7 +2070,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数は連結リスト参照である必要があります".to_string())),
             };
             
@@ -1739,7 +2081,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = LINKEDLIST_INSTANCES.lock().unwrap();
             if let Some(list) = instances.get_mut(&list_ref) {
                 list.push_front(element);
-                Ok(list_ref)
+                Ok(Value::Str(list_ref))
             } else {
                 Err(EidosError::Runtime(format!("連結リスト参照 '{}' は無効です", list_ref)))
             }
@@ -1751,7 +2093,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数は連結リスト参照である必要があります".to_string())),
             };
             
@@ -1762,7 +2104,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = LINKEDLIST_INSTANCES.lock().unwrap();
             if let Some(list) = instances.get_mut(&list_ref) {
                 list.push_back(element);
-                Ok(list_ref)
+                Ok(Value::Str(list_ref))
             } else {
                 Err(EidosError::Runtime(format!("連結リスト参照 '{}' は無効です", list_ref)))
             }
@@ -1774,7 +2116,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は連結リスト参照である必要があります".to_string())),
             };
             
@@ -1782,7 +2124,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = LINKEDLIST_INSTANCES.lock().unwrap();
             if let Some(list) = instances.get_mut(&list_ref) {
                 if let Some(value) = list.pop_front() {
-                    Ok(value.to_string())
+                    Ok(Value::from(value))
                 } else {
                     Err(EidosError::Runtime("リストが空です".to_string()))
                 }
@@ -1797,7 +2139,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は連結リスト参照である必要があります".to_string())),
             };
             
@@ -1805,7 +2147,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = LINKEDLIST_INSTANCES.lock().unwrap();
             if let Some(list) = instances.get_mut(&list_ref) {
                 if let Some(value) = list.pop_back() {
-                    Ok(value.to_string())
+                    Ok(Value::from(value))
                 } else {
                     Err(EidosError::Runtime("リストが空です".to_string()))
                 }
@@ -1820,14 +2162,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は連結リスト参照である必要があります".to_string())),
             };
             
             // 長さを取得
             let instances = LINKEDLIST_INSTANCES.lock().unwrap();
             if let Some(list) = instances.get(&list_ref) {
-                Ok(list.len().to_string())
+                Ok(Value::from(list.len()))
             } else {
                 Err(EidosError::Runtime(format!("連結リスト参照 '{}' は無効です", list_ref)))
             }
@@ -1839,7 +2181,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は連結リスト参照である必要があります".to_string())),
             };
             
@@ -1847,7 +2189,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = LINKEDLIST_INSTANCES.lock().unwrap();
             if let Some(list) = instances.get_mut(&list_ref) {
                 list.clear();
-                Ok(list_ref)
+                Ok(Value::Str(list_ref))
             } else {
                 Err(EidosError::Runtime(format!("連結リスト参照 '{}' は無効です", list_ref)))
             }
@@ -1859,14 +2201,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は連結リスト参照である必要があります".to_string())),
             };
             
             // 空かどうかを確認
             let instances = LINKEDLIST_INSTANCES.lock().unwrap();
             if let Some(list) = instances.get(&list_ref) {
-                Ok(list.is_empty().to_string())
+                Ok(Value::from(list.is_empty()))
             } else {
                 Err(EidosError::Runtime(format!("連結リスト参照 '{}' は無効です", list_ref)))
             }
@@ -1880,7 +2222,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // グローバル状態に登録
             QUEUE_INSTANCES.lock().unwrap().insert(instance_id.clone(), std::collections::VecDeque::new());
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "Queue::enqueue" => {
             if values.len() != 2 {
@@ -1889,7 +2231,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // キュー参照を取得
             let queue_ref = match &values[0] {
-                Value::Queue(id) => id.clone(),
+                Element::Queue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はキュー参照である必要があります".to_string())),
             };
             
@@ -1900,7 +2242,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = QUEUE_INSTANCES.lock().unwrap();
             if let Some(queue) = instances.get_mut(&queue_ref) {
                 queue.push_back(element);
-                Ok(queue_ref)
+                Ok(Value::Str(queue_ref))
             } else {
                 Err(EidosError::Runtime(format!("キュー参照 '{}' は無効です", queue_ref)))
             }
@@ -1912,7 +2254,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // キュー参照を取得
             let queue_ref = match &values[0] {
-                Value::Queue(id) => id.clone(),
+                Element::Queue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はキュー参照である必要があります".to_string())),
             };
             
@@ -1920,7 +2262,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = QUEUE_INSTANCES.lock().unwrap();
             if let Some(queue) = instances.get_mut(&queue_ref) {
                 if let Some(value) = queue.pop_front() {
-                    Ok(value.to_string())
+                    Ok(Value::from(value))
                 } else {
                     Err(EidosError::Runtime("キューが空です".to_string()))
                 }
@@ -1935,7 +2277,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // キュー参照を取得
             let queue_ref = match &values[0] {
-                Value::Queue(id) => id.clone(),
+                Element::Queue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はキュー参照である必要があります".to_string())),
             };
             
@@ -1943,7 +2285,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let instances = QUEUE_INSTANCES.lock().unwrap();
             if let Some(queue) = instances.get(&queue_ref) {
                 if let Some(value) = queue.front() {
-                    Ok(value.to_string())
+                    Ok(Value::from(value.clone()))
                 } else {
                     Err(EidosError::Runtime("キューが空です".to_string()))
                 }
@@ -1958,14 +2300,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // キュー参照を取得
             let queue_ref = match &values[0] {
-                Value::Queue(id) => id.clone(),
+                Element::Queue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はキュー参照である必要があります".to_string())),
             };
             
             // 長さを取得
             let instances = QUEUE_INSTANCES.lock().unwrap();
             if let Some(queue) = instances.get(&queue_ref) {
-                Ok(queue.len().to_string())
+                Ok(Value::from(queue.len()))
             } else {
                 Err(EidosError::Runtime(format!("キュー参照 '{}' は無効です", queue_ref)))
             }
@@ -1977,7 +2319,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // キュー参照を取得
             let queue_ref = match &values[0] {
-                Value::Queue(id) => id.clone(),
+                Element::Queue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はキュー参照である必要があります".to_string())),
             };
             
@@ -1985,7 +2327,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = QUEUE_INSTANCES.lock().unwrap();
             if let Some(queue) = instances.get_mut(&queue_ref) {
                 queue.clear();
-                Ok(queue_ref)
+                Ok(Value::Str(queue_ref))
             } else {
                 Err(EidosError::Runtime(format!("キュー参照 '{}' は無効です", queue_ref)))
             }
@@ -1997,14 +2339,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // キュー参照を取得
             let queue_ref = match &values[0] {
-                Value::Queue(id) => id.clone(),
+                Element::Queue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はキュー参照である必要があります".to_string())),
             };
             
             // 空かどうかを確認
             let instances = QUEUE_INSTANCES.lock().unwrap();
             if let Some(queue) = instances.get(&queue_ref) {
-                Ok(queue.is_empty().to_string())
+                Ok(Value::from(queue.is_empty()))
             } else {
                 Err(EidosError::Runtime(format!("キュー参照 '{}' は無効です", queue_ref)))
             }
@@ -2018,7 +2360,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // グローバル状態に登録
             STACK_INSTANCES.lock().unwrap().insert(instance_id.clone(), Vec::new());
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "Stack::push" => {
             if values.len() != 2 {
@@ -2027,7 +2369,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // スタック参照を取得
             let stack_ref = match &values[0] {
-                Value::Stack(id) => id.clone(),
+                Element::Stack(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数はスタック参照である必要があります".to_string())),
             };
             
@@ -2038,7 +2380,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = STACK_INSTANCES.lock().unwrap();
             if let Some(stack) = instances.get_mut(&stack_ref) {
                 stack.push(element);
-                Ok(stack_ref)
+                Ok(Value::Str(stack_ref))
             } else {
                 Err(EidosError::Runtime(format!("スタック参照 '{}' は無効です", stack_ref)))
             }
@@ -2050,7 +2392,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // スタック参照を取得
             let stack_ref = match &values[0] {
-                Value::Stack(id) => id.clone(),
+                Element::Stack(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はスタック参照である必要があります".to_string())),
             };
             
@@ -2058,7 +2400,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = STACK_INSTANCES.lock().unwrap();
             if let Some(stack) = instances.get_mut(&stack_ref) {
                 if let Some(value) = stack.pop() {
-                    Ok(value.to_string())
+                    Ok(Value::from(value))
                 } else {
                     Err(EidosError::Runtime("スタックが空です".to_string()))
                 }
@@ -2073,7 +2415,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // スタック参照を取得
             let stack_ref = match &values[0] {
-                Value::Stack(id) => id.clone(),
+                Element::Stack(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はスタック参照である必要があります".to_string())),
             };
             
@@ -2081,7 +2423,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let instances = STACK_INSTANCES.lock().unwrap();
             if let Some(stack) = instances.get(&stack_ref) {
                 if let Some(value) = stack.last() {
-                    Ok(value.to_string())
+                    Ok(Value::from(value.clone()))
                 } else {
                     Err(EidosError::Runtime("スタックが空です".to_string()))
                 }
@@ -2096,14 +2438,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // スタック参照を取得
             let stack_ref = match &values[0] {
-                Value::Stack(id) => id.clone(),
+                Element::Stack(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はスタック参照である必要があります".to_string())),
             };
             
             // 長さを取得
             let instances = STACK_INSTANCES.lock().unwrap();
             if let Some(stack) = instances.get(&stack_ref) {
-                Ok(stack.len().to_string())
+                Ok(Value::from(stack.len()))
             } else {
                 Err(EidosError::Runtime(format!("スタック参照 '{}' は無効です", stack_ref)))
             }
@@ -2115,7 +2457,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // スタック参照を取得
             let stack_ref = match &values[0] {
-                Value::Stack(id) => id.clone(),
+                Element::Stack(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はスタック参照である必要があります".to_string())),
             };
             
@@ -2123,7 +2465,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = STACK_INSTANCES.lock().unwrap();
             if let Some(stack) = instances.get_mut(&stack_ref) {
                 stack.clear();
-                Ok(stack_ref)
+                Ok(Value::Str(stack_ref))
             } else {
                 Err(EidosError::Runtime(format!("スタック参照 '{}' は無効です", stack_ref)))
             }
@@ -2135,14 +2477,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // スタック参照を取得
             let stack_ref = match &values[0] {
-                Value::Stack(id) => id.clone(),
+                Element::Stack(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はスタック参照である必要があります".to_string())),
             };
             
             // 空かどうかを確認
             let instances = STACK_INSTANCES.lock().unwrap();
             if let Some(stack) = instances.get(&stack_ref) {
-                Ok(stack.is_empty().to_string())
+                Ok(Value::from(stack.is_empty()))
             } else {
                 Err(EidosError::Runtime(format!("スタック参照 '{}' は無効です", stack_ref)))
             }
@@ -2156,7 +2498,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             // グローバル状態に登録
             PRIORITYQUEUE_INSTANCES.lock().unwrap().insert(instance_id.clone(), BinaryHeap::new());
             
-            Ok(instance_id)
+            Ok(Value::Str(instance_id))
         },
         "PriorityQueue::push" => {
             if values.len() != 3 {
@@ -2165,14 +2507,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // 優先度キュー参照を取得
             let pq_ref = match &values[0] {
-                Value::PriorityQueue(id) => id.clone(),
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("第1引数は優先度キュー参照である必要があります".to_string())),
             };
             
             // 要素と優先度を取得
             let element = values[1].clone();
             let priority = match &values[2] {
-                Value::Int(i) => *i,
+                Element::Int(i) => *i,
                 _ => return Err(EidosError::Runtime("優先度は整数である必要があります".to_string())),
             };
             
@@ -2180,7 +2522,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = PRIORITYQUEUE_INSTANCES.lock().unwrap();
             if let Some(pq) = instances.get_mut(&pq_ref) {
                 pq.push(PriorityItem { priority, value: element });
-                Ok(pq_ref)
+                Ok(Value::Str(pq_ref))
             } else {
                 Err(EidosError::Runtime(format!("優先度キュー参照 '{}' は無効です", pq_ref)))
             }
@@ -2192,7 +2534,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // 優先度キュー参照を取得
             let pq_ref = match &values[0] {
-                Value::PriorityQueue(id) => id.clone(),
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は優先度キュー参照である必要があります".to_string())),
             };
             
@@ -2200,7 +2542,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = PRIORITYQUEUE_INSTANCES.lock().unwrap();
             if let Some(pq) = instances.get_mut(&pq_ref) {
                 if let Some(item) = pq.pop() {
-                    Ok(item.value.to_string())
+                    Ok(Value::from(item.value))
                 } else {
                     Err(EidosError::Runtime("優先度キューが空です".to_string()))
                 }
@@ -2215,7 +2557,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // 優先度キュー参照を取得
             let pq_ref = match &values[0] {
-                Value::PriorityQueue(id) => id.clone(),
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は優先度キュー参照である必要があります".to_string())),
             };
             
@@ -2223,7 +2565,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let instances = PRIORITYQUEUE_INSTANCES.lock().unwrap();
             if let Some(pq) = instances.get(&pq_ref) {
                 if let Some(item) = pq.peek() {
-                    Ok(item.value.to_string())
+                    Ok(Value::from(item.value.clone()))
                 } else {
                     Err(EidosError::Runtime("優先度キューが空です".to_string()))
                 }
@@ -2238,14 +2580,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // 優先度キュー参照を取得
             let pq_ref = match &values[0] {
-                Value::PriorityQueue(id) => id.clone(),
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は優先度キュー参照である必要があります".to_string())),
             };
             
             // 長さを取得
             let instances = PRIORITYQUEUE_INSTANCES.lock().unwrap();
             if let Some(pq) = instances.get(&pq_ref) {
-                Ok(pq.len().to_string())
+                Ok(Value::from(pq.len()))
             } else {
                 Err(EidosError::Runtime(format!("優先度キュー参照 '{}' は無効です", pq_ref)))
             }
@@ -2257,7 +2599,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // 優先度キュー参照を取得
             let pq_ref = match &values[0] {
-                Value::PriorityQueue(id) => id.clone(),
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は優先度キュー参照である必要があります".to_string())),
             };
             
@@ -2265,7 +2607,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let mut instances = PRIORITYQUEUE_INSTANCES.lock().unwrap();
             if let Some(pq) = instances.get_mut(&pq_ref) {
                 pq.clear();
-                Ok(pq_ref)
+                Ok(Value::Str(pq_ref))
             } else {
                 Err(EidosError::Runtime(format!("優先度キュー参照 '{}' は無効です", pq_ref)))
             }
@@ -2277,14 +2619,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // 優先度キュー参照を取得
             let pq_ref = match &values[0] {
-                Value::PriorityQueue(id) => id.clone(),
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は優先度キュー参照である必要があります".to_string())),
             };
             
             // 空かどうかを確認
             let instances = PRIORITYQUEUE_INSTANCES.lock().unwrap();
             if let Some(pq) = instances.get(&pq_ref) {
-                Ok(pq.is_empty().to_string())
+                Ok(Value::from(pq.is_empty()))
             } else {
                 Err(EidosError::Runtime(format!("優先度キュー参照 '{}' は無効です", pq_ref)))
             }
@@ -2298,7 +2640,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // リスト参照を取得
             let list_ref = match &values[0] {
-                Value::LinkedList(id) => id.clone(),
+                Element::LinkedList(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数は連結リスト参照である必要があります".to_string())),
             };
             
@@ -2315,7 +2657,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 
                 // ベクターを登録
                 VECTOR_INSTANCES.lock().unwrap().insert(vector_id.clone(), vector);
-                Ok(vector_id)
+                Ok(Value::Str(vector_id))
             } else {
                 Err(EidosError::Runtime(format!("連結リスト参照 '{}' は無効です", list_ref)))
             }
@@ -2327,7 +2669,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
@@ -2344,7 +2686,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 
                 // リストを登録
                 LINKEDLIST_INSTANCES.lock().unwrap().insert(list_id.clone(), list);
-                Ok(list_id)
+                Ok(Value::Str(list_id))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -2356,7 +2698,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
@@ -2374,7 +2716,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 
                 // セットを登録
                 HASHSET_INSTANCES.lock().unwrap().insert(set_id.clone(), set);
-                Ok(set_id)
+                Ok(Value::Str(set_id))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -2386,7 +2728,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ハッシュセット参照を取得
             let set_ref = match &values[0] {
-                Value::HashSet(id) => id.clone(),
+                Element::HashSet(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はハッシュセット参照である必要があります".to_string())),
             };
             
@@ -2399,12 +2741,12 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             if let Some(set) = instances.get(&set_ref) {
                 for item in set {
                     // 文字列をValueに変換
-                    vector.push(Value::String(item.clone()));
+                    vector.push(Element::String(item.clone()));
                 }
                 
                 // ベクターを登録
                 VECTOR_INSTANCES.lock().unwrap().insert(vector_id.clone(), vector);
-                Ok(vector_id)
+                Ok(Value::Str(vector_id))
             } else {
                 Err(EidosError::Runtime(format!("ハッシュセット参照 '{}' は無効です", set_ref)))
             }
@@ -2416,7 +2758,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
@@ -2433,7 +2775,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 
                 // キューを登録
                 QUEUE_INSTANCES.lock().unwrap().insert(queue_id.clone(), queue);
-                Ok(queue_id)
+                Ok(Value::Str(queue_id))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -2445,7 +2787,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // キュー参照を取得
             let queue_ref = match &values[0] {
-                Value::Queue(id) => id.clone(),
+                Element::Queue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はキュー参照である必要があります".to_string())),
             };
             
@@ -2462,7 +2804,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 
                 // ベクターを登録
                 VECTOR_INSTANCES.lock().unwrap().insert(vector_id.clone(), vector);
-                Ok(vector_id)
+                Ok(Value::Str(vector_id))
             } else {
                 Err(EidosError::Runtime(format!("キュー参照 '{}' は無効です", queue_ref)))
             }
@@ -2474,7 +2816,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // ベクター参照を取得
             let vector_ref = match &values[0] {
-                Value::Vector(id) => id.clone(),
+                Element::Vector(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はベクター参照である必要があります".to_string())),
             };
             
@@ -2491,7 +2833,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 
                 // スタックを登録
                 STACK_INSTANCES.lock().unwrap().insert(stack_id.clone(), stack);
-                Ok(stack_id)
+                Ok(Value::Str(stack_id))
             } else {
                 Err(EidosError::Runtime(format!("ベクター参照 '{}' は無効です", vector_ref)))
             }
@@ -2503,7 +2845,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // スタック参照を取得
             let stack_ref = match &values[0] {
-                Value::Stack(id) => id.clone(),
+                Element::Stack(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はスタック参照である必要があります".to_string())),
             };
             
@@ -2520,7 +2862,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 
                 // ベクターを登録
                 VECTOR_INSTANCES.lock().unwrap().insert(vector_id.clone(), vector);
-                Ok(vector_id)
+                Ok(Value::Str(vector_id))
             } else {
                 Err(EidosError::Runtime(format!("スタック参照 '{}' は無効です", stack_ref)))
             }
@@ -2534,14 +2876,14 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // コレクション参照を取得
             let collection_id = match &values[0] {
-                Value::Vector(id) | Value::HashMap(id) | Value::HashSet(id) | 
-                Value::LinkedList(id) | Value::Queue(id) | Value::Stack(id) |
-                Value::PriorityQueue(id) => id.clone(),
+                Element::Vector(id) | Element::HashMap(id) | Element::HashSet(id) | 
+                Element::LinkedList(id) | Element::Queue(id) | Element::Stack(id) |
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はコレクション参照である必要があります".to_string())),
             };
             
             // コレクションをクローン
-            clone_collection(&collection_id)
+            clone_collection(&collection_id).map(Value::Str)
         },
         "Collection::drop" => {
             if values.len() != 1 {
@@ -2550,15 +2892,15 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             
             // コレクション参照を取得
             let collection_id = match &values[0] {
-                Value::Vector(id) | Value::HashMap(id) | Value::HashSet(id) | 
-                Value::LinkedList(id) | Value::Queue(id) | Value::Stack(id) |
-                Value::PriorityQueue(id) => id.clone(),
+                Element::Vector(id) | Element::HashMap(id) | Element::HashSet(id) | 
+                Element::LinkedList(id) | Element::Queue(id) | Element::Stack(id) |
+                Element::PriorityQueue(id) => id.clone(),
                 _ => return Err(EidosError::Runtime("引数はコレクション参照である必要があります".to_string())),
             };
             
             // コレクションを削除
             CollectionManager::remove_instance(&collection_id)?;
-            Ok("null".to_string())
+            Ok(Value::Unit)
         },
         
         // その他のコレクション関数
@@ -2666,13 +3008,13 @@ impl CollectionManager {
 }
 
 // Value型にメソッドを追加
-impl Value {
+impl Element {
     /// このValueがコレクション参照かどうかを確認
     pub fn is_collection_reference(&self) -> bool {
         match self {
-            Value::Vector(_) | Value::HashMap(_) | Value::HashSet(_) | 
-            Value::LinkedList(_) | Value::Queue(_) | Value::Stack(_) | 
-            Value::PriorityQueue(_) => true,
+            Element::Vector(_) | Element::HashMap(_) | Element::HashSet(_) | 
+            Element::LinkedList(_) | Element::Queue(_) | Element::Stack(_) | 
+            Element::PriorityQueue(_) => true,
             _ => false,
         }
     }
@@ -2680,9 +3022,9 @@ impl Value {
     /// コレクション参照からIDを取得
     pub fn get_collection_id(&self) -> Option<String> {
         match self {
-            Value::Vector(id) | Value::HashMap(id) | Value::HashSet(id) | 
-            Value::LinkedList(id) | Value::Queue(id) | Value::Stack(id) | 
-            Value::PriorityQueue(id) => Some(id.clone()),
+            Element::Vector(id) | Element::HashMap(id) | Element::HashSet(id) | 
+            Element::LinkedList(id) | Element::Queue(id) | Element::Stack(id) | 
+            Element::PriorityQueue(id) => Some(id.clone()),
             _ => None,
         }
     }
@@ -2739,40 +3081,46 @@ mod tests {
         CollectionManager::clear_all();
     }
     
+    // 文字列で書いた引数を型付きの値に変換して呼び出す（"42" は整数、"true" は真偽値）
+    fn call(function_name: &str, args: &[String]) -> Result<String> {
+        let args: Vec<Value> = args.iter().map(|arg| Value::from(Element::from(arg.as_str()))).collect();
+        execute_function(function_name, &args).map(|value| value.to_string())
+    }
+    
     #[test]
     fn test_vector_operations() {
         setup();
         
         // 新しいベクターを作成
-        let vector_id = execute_function("Vector::new", &[]).unwrap();
+        let vector_id = call("Vector::new", &[]).unwrap();
         assert!(vector_id.starts_with("vector:"));
         
         // 要素を追加
-        let _ = execute_function("Vector::push", &[vector_id.clone(), "42".to_string()]).unwrap();
-        let _ = execute_function("Vector::push", &[vector_id.clone(), "hello".to_string()]).unwrap();
-        let _ = execute_function("Vector::push", &[vector_id.clone(), "true".to_string()]).unwrap();
+        let _ = call("Vector::push", &[vector_id.clone(), "42".to_string()]).unwrap();
+        let _ = call("Vector::push", &[vector_id.clone(), "hello".to_string()]).unwrap();
+        let _ = call("Vector::push", &[vector_id.clone(), "true".to_string()]).unwrap();
         
         // 長さを確認
-        let length = execute_function("Vector::length", &[vector_id.clone()]).unwrap();
+        let length = call("Vector::length", &[vector_id.clone()]).unwrap();
         assert_eq!(length, "3");
         
         // 要素を取得
-        let item = execute_function("Vector::get", &[vector_id.clone(), "0".to_string()]).unwrap();
+        let item = call("Vector::get", &[vector_id.clone(), "0".to_string()]).unwrap();
         assert_eq!(item, "42");
-        let item = execute_function("Vector::get", &[vector_id.clone(), "1".to_string()]).unwrap();
+        let item = call("Vector::get", &[vector_id.clone(), "1".to_string()]).unwrap();
         assert_eq!(item, "hello");
         
         // 要素をポップ
-        let item = execute_function("Vector::pop", &[vector_id.clone()]).unwrap();
+        let item = call("Vector::pop", &[vector_id.clone()]).unwrap();
         assert_eq!(item, "true");
         
         // 長さを再確認
-        let length = execute_function("Vector::length", &[vector_id.clone()]).unwrap();
+        let length = call("Vector::length", &[vector_id.clone()]).unwrap();
         assert_eq!(length, "2");
         
         // クリア
-        let _ = execute_function("Vector::clear", &[vector_id.clone()]).unwrap();
-        let is_empty = execute_function("Vector::is_empty", &[vector_id.clone()]).unwrap();
+        let _ = call("Vector::clear", &[vector_id.clone()]).unwrap();
+        let is_empty = call("Vector::is_empty", &[vector_id.clone()]).unwrap();
         assert_eq!(is_empty, "true");
     }
     
@@ -2781,30 +3129,30 @@ mod tests {
         setup();
         
         // 新しいハッシュマップを作成
-        let map_id = execute_function("HashMap::new", &[]).unwrap();
+        let map_id = call("HashMap::new", &[]).unwrap();
         assert!(map_id.starts_with("hashmap:"));
         
         // キーと値を追加
-        let _ = execute_function("HashMap::insert", &[map_id.clone(), "key1".to_string(), "value1".to_string()]).unwrap();
-        let _ = execute_function("HashMap::insert", &[map_id.clone(), "key2".to_string(), "42".to_string()]).unwrap();
+        let _ = call("HashMap::insert", &[map_id.clone(), "key1".to_string(), "value1".to_string()]).unwrap();
+        let _ = call("HashMap::insert", &[map_id.clone(), "key2".to_string(), "42".to_string()]).unwrap();
         
         // 要素を取得
-        let value = execute_function("HashMap::get", &[map_id.clone(), "key1".to_string()]).unwrap();
+        let value = call("HashMap::get", &[map_id.clone(), "key1".to_string()]).unwrap();
         assert_eq!(value, "value1");
         
         // 存在確認
-        let contains = execute_function("HashMap::contains_key", &[map_id.clone(), "key2".to_string()]).unwrap();
+        let contains = call("HashMap::contains_key", &[map_id.clone(), "key2".to_string()]).unwrap();
         assert_eq!(contains, "true");
-        let contains = execute_function("HashMap::contains_key", &[map_id.clone(), "key3".to_string()]).unwrap();
+        let contains = call("HashMap::contains_key", &[map_id.clone(), "key3".to_string()]).unwrap();
         assert_eq!(contains, "false");
         
         // 要素を削除
-        let _ = execute_function("HashMap::remove", &[map_id.clone(), "key1".to_string()]).unwrap();
-        let contains = execute_function("HashMap::contains_key", &[map_id.clone(), "key1".to_string()]).unwrap();
+        let _ = call("HashMap::remove", &[map_id.clone(), "key1".to_string()]).unwrap();
+        let contains = call("HashMap::contains_key", &[map_id.clone(), "key1".to_string()]).unwrap();
         assert_eq!(contains, "false");
         
         // 長さを確認
-        let length = execute_function("HashMap::length", &[map_id.clone()]).unwrap();
+        let length = call("HashMap::length", &[map_id.clone()]).unwrap();
         assert_eq!(length, "1");
     }
     
@@ -2813,53 +3161,92 @@ mod tests {
         setup();
         
         // 新しいハッシュセットを作成
-        let set_id = execute_function("HashSet::new", &[]).unwrap();
+        let set_id = call("HashSet::new", &[]).unwrap();
         assert!(set_id.starts_with("hashset:"));
         
         // 要素を追加
-        let _ = execute_function("HashSet::add", &[set_id.clone(), "apple".to_string()]).unwrap();
-        let _ = execute_function("HashSet::add", &[set_id.clone(), "banana".to_string()]).unwrap();
-        let _ = execute_function("HashSet::add", &[set_id.clone(), "apple".to_string()]).unwrap(); // 重複
+        let _ = call("HashSet::add", &[set_id.clone(), "apple".to_string()]).unwrap();
+        let _ = call("HashSet::add", &[set_id.clone(), "banana".to_string()]).unwrap();
+        let _ = call("HashSet::add", &[set_id.clone(), "apple".to_string()]).unwrap(); // 重複
         
         // 要素数を確認（重複は追加されない）
-        let size = execute_function("HashSet::size", &[set_id.clone()]).unwrap();
+        let size = call("HashSet::size", &[set_id.clone()]).unwrap();
         assert_eq!(size, "2");
         
         // 存在確認
-        let contains = execute_function("HashSet::contains", &[set_id.clone(), "apple".to_string()]).unwrap();
+        let contains = call("HashSet::contains", &[set_id.clone(), "apple".to_string()]).unwrap();
         assert_eq!(contains, "true");
-        let contains = execute_function("HashSet::contains", &[set_id.clone(), "orange".to_string()]).unwrap();
+        let contains = call("HashSet::contains", &[set_id.clone(), "orange".to_string()]).unwrap();
         assert_eq!(contains, "false");
         
         // 要素を削除
-        let _ = execute_function("HashSet::remove", &[set_id.clone(), "apple".to_string()]).unwrap();
-        let contains = execute_function("HashSet::contains", &[set_id.clone(), "apple".to_string()]).unwrap();
+        let _ = call("HashSet::remove", &[set_id.clone(), "apple".to_string()]).unwrap();
+        let contains = call("HashSet::contains", &[set_id.clone(), "apple".to_string()]).unwrap();
         assert_eq!(contains, "false");
     }
     
+    #[test]
+    fn test_map_and_set_operations() {
+        setup();
+        
+        let map_id = call("Map::new", &[]).unwrap();
+        assert!(map_id.starts_with("hashmap:"));
+        let _ = call("Map::insert", &[map_id.clone(), "b".to_string(), "2".to_string()]).unwrap();
+        let _ = call("Map::insert", &[map_id.clone(), "a".to_string(), "1".to_string()]).unwrap();
+        let _ = call("Map::insert", &[map_id.clone(), "b".to_string(), "3".to_string()]).unwrap();
+        
+        assert_eq!(call("Map::get", &[map_id.clone(), "b".to_string()]).unwrap(), "3");
+        assert_eq!(call("Map::contains", &[map_id.clone(), "a".to_string()]).unwrap(), "true");
+        assert_eq!(call("Map::len", std::slice::from_ref(&map_id)).unwrap(), "2");
+        
+        // キーと値はキーの昇順で列挙される
+        let keys = call("Map::keys", std::slice::from_ref(&map_id)).unwrap();
+        assert_eq!(CollectionManager::dump_collection(&keys).unwrap(), "[a, b]");
+        let map_values = call("Map::values", std::slice::from_ref(&map_id)).unwrap();
+        assert_eq!(call("Vector::get", &[map_values.clone(), "0".to_string()]).unwrap(), "1");
+        assert_eq!(call("Vector::get", &[map_values.clone(), "1".to_string()]).unwrap(), "3");
+        
+        assert_eq!(call("Map::remove", &[map_id.clone(), "a".to_string()]).unwrap(), "1");
+        assert_eq!(call("Map::contains", &[map_id.clone(), "a".to_string()]).unwrap(), "false");
+        
+        let set_id = call("Set::new", &[]).unwrap();
+        assert_eq!(call("Set::insert", &[set_id.clone(), "10".to_string()]).unwrap(), "true");
+        assert_eq!(call("Set::insert", &[set_id.clone(), "9".to_string()]).unwrap(), "true");
+        assert_eq!(call("Set::insert", &[set_id.clone(), "10".to_string()]).unwrap(), "false");
+        assert_eq!(call("Set::len", std::slice::from_ref(&set_id)).unwrap(), "2");
+        
+        // 整数の要素は数値として並べる
+        let elements = call("Set::values", std::slice::from_ref(&set_id)).unwrap();
+        assert_eq!(call("Vector::get", &[elements.clone(), "0".to_string()]).unwrap(), "9");
+        assert_eq!(call("Vector::get", &[elements.clone(), "1".to_string()]).unwrap(), "10");
+        
+        assert_eq!(call("Set::remove", &[set_id.clone(), "9".to_string()]).unwrap(), "true");
+        assert_eq!(call("Set::contains", &[set_id.clone(), "9".to_string()]).unwrap(), "false");
+    }
+    
     #[test]
     fn test_reference_counting() {
         setup();
         
         // 新しいベクターを作成
-        let vector_id = execute_function("Vector::new", &[]).unwrap();
+        let vector_id = call("Vector::new", &[]).unwrap();
         
         // クローンを作成（参照カウント増加）
-        let clone_id = execute_function("Collection::clone", &[vector_id.clone()]).unwrap();
+        let clone_id = call("Collection::clone", &[vector_id.clone()]).unwrap();
         assert_eq!(vector_id, clone_id); // 同じIDを返す
         
         // 最初の参照を削除しても、まだインスタンスは存在する
-        let _ = execute_function("Collection::drop", &[vector_id.clone()]).unwrap();
+        let _ = call("Collection::drop", &[vector_id.clone()]).unwrap();
         
         // クローンを通じてまだアクセス可能
-        let is_empty = execute_function("Vector::is_empty", &[clone_id.clone()]).unwrap();
+        let is_empty = call("Vector::is_empty", &[clone_id.clone()]).unwrap();
         assert_eq!(is_empty, "true");
         
         // クローンも削除すると、インスタンスは完全に削除される
-        let _ = execute_function("Collection::drop", &[clone_id.clone()]).unwrap();
+        let _ = call("Collection::drop", &[clone_id.clone()]).unwrap();
         
         // これ以上アクセスできない
-        let result = execute_function("Vector::is_empty", &[clone_id.clone()]);
+        let result = call("Vector::is_empty", &[clone_id.clone()]);
         assert!(result.is_err());
     }
     
@@ -2868,36 +3255,36 @@ mod tests {
         setup();
         
         // ベクターを作成して要素を追加
-        let vector_id = execute_function("Vector::new", &[]).unwrap();
-        let _ = execute_function("Vector::push", &[vector_id.clone(), "a".to_string()]).unwrap();
-        let _ = execute_function("Vector::push", &[vector_id.clone(), "b".to_string()]).unwrap();
-        let _ = execute_function("Vector::push", &[vector_id.clone(), "c".to_string()]).unwrap();
+        let vector_id = call("Vector::new", &[]).unwrap();
+        let _ = call("Vector::push", &[vector_id.clone(), "a".to_string()]).unwrap();
+        let _ = call("Vector::push", &[vector_id.clone(), "b".to_string()]).unwrap();
+        let _ = call("Vector::push", &[vector_id.clone(), "c".to_string()]).unwrap();
         
         // ベクターからリンクドリストに変換
-        let list_id = execute_function("LinkedList::from_vector", &[vector_id.clone()]).unwrap();
+        let list_id = call("LinkedList::from_vector", &[vector_id.clone()]).unwrap();
         assert!(list_id.starts_with("linkedlist:"));
         
         // リンクドリストからベクターに変換
-        let vector2_id = execute_function("Vector::from_list", &[list_id.clone()]).unwrap();
+        let vector2_id = call("Vector::from_list", &[list_id.clone()]).unwrap();
         
         // 変換後のベクターの長さを確認
-        let length = execute_function("Vector::length", &[vector2_id.clone()]).unwrap();
+        let length = call("Vector::length", &[vector2_id.clone()]).unwrap();
         assert_eq!(length, "3");
         
         // ベクターからハッシュセットに変換
-        let set_id = execute_function("HashSet::from_vector", &[vector_id.clone()]).unwrap();
+        let set_id = call("HashSet::from_vector", &[vector_id.clone()]).unwrap();
         
         // セットの要素数を確認
-        let size = execute_function("HashSet::size", &[set_id.clone()]).unwrap();
+        let size = call("HashSet::size", &[set_id.clone()]).unwrap();
         assert_eq!(size, "3");
         
         // 重複要素を持つベクターからハッシュセットに変換
-        let vector3_id = execute_function("Vector::new", &[]).unwrap();
-        let _ = execute_function("Vector::push", &[vector3_id.clone(), "a".to_string()]).unwrap();
-        let _ = execute_function("Vector::push", &[vector3_id.clone(), "a".to_string()]).unwrap(); // 重複
+        let vector3_id = call("Vector::new", &[]).unwrap();
+        let _ = call("Vector::push", &[vector3_id.clone(), "a".to_string()]).unwrap();
+        let _ = call("Vector::push", &[vector3_id.clone(), "a".to_string()]).unwrap(); // 重複
         
-        let set2_id = execute_function("HashSet::from_vector", &[vector3_id.clone()]).unwrap();
-        let size = execute_function("HashSet::size", &[set2_id.clone()]).unwrap();
+        let set2_id = call("HashSet::from_vector", &[vector3_id.clone()]).unwrap();
+        let size = call("HashSet::size", &[set2_id.clone()]).unwrap();
         assert_eq!(size, "1"); // 重複は削除される
     }
 }
\ No newline at end of file
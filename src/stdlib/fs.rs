@@ -0,0 +1,367 @@
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::core::{Result, EidosError};
+use crate::core::types::Type;
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
+
+/// ファイルシステムモジュールの初期化
+///
+/// 結果がファイルシステムの状態に依存するため、問い合わせだけの関数も含めてすべて副作用ありとして登録する。
+pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
+    // 基本型の登録
+    let int_type = Type::int();
+    let bool_type = Type::bool();
+    let string_type = Type::string();
+    let unit_type = Type::unit();
+    let string_array_type = Type::array(string_type.clone());
+    
+    // ディレクトリの走査
+    
+    // fs::read_dir - ディレクトリ直下のエントリ一覧
+    registry.register_function(StdlibFunction::new(
+        "read_dir",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        string_array_type.id,
+        "ディレクトリ直下のファイルとサブディレクトリのパスを名前順に返します。",
+    ));
+    
+    // fs::walk_dir - ディレクトリ以下のファイルを再帰的に列挙
+    registry.register_function(StdlibFunction::new(
+        "walk_dir",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        string_array_type.id,
+        "ディレクトリ以下のすべてのファイルのパスを再帰的に列挙し、名前順に返します。",
+    ));
+    
+    // メタデータ
+    
+    // fs::exists - パスが存在するか
+    registry.register_function(StdlibFunction::new(
+        "exists",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        bool_type.id,
+        "指定したパスにファイルまたはディレクトリが存在するかどうかを返します。",
+    ));
+    
+    // fs::is_file - パスが通常のファイルか
+    registry.register_function(StdlibFunction::new(
+        "is_file",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        bool_type.id,
+        "指定したパスが通常のファイルかどうかを返します。",
+    ));
+    
+    // fs::is_dir - パスがディレクトリか
+    registry.register_function(StdlibFunction::new(
+        "is_dir",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        bool_type.id,
+        "指定したパスがディレクトリかどうかを返します。",
+    ));
+    
+    // fs::size - ファイルサイズ
+    registry.register_function(StdlibFunction::new(
+        "size",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        int_type.id,
+        "ファイルのサイズをバイト単位で返します。",
+    ));
+    
+    // fs::modified - 最終更新時刻
+    registry.register_function(StdlibFunction::new(
+        "modified",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        int_type.id,
+        "ファイルの最終更新時刻をUNIXエポックからの秒数で返します。",
+    ));
+    
+    // 作成・コピー・移動・削除
+    
+    // fs::create_dir - ディレクトリを作成
+    registry.register_function(StdlibFunction::new(
+        "create_dir",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        unit_type.id,
+        "ディレクトリを作成します。途中のディレクトリも必要に応じて作成します。",
+    ));
+    
+    // fs::copy - ファイルをコピー
+    registry.register_function(StdlibFunction::new(
+        "copy",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("from".to_string(), string_type.id),
+            ("to".to_string(), string_type.id),
+        ],
+        int_type.id,
+        "ファイルをコピーし、コピーしたバイト数を返します。コピー先が存在する場合は上書きします。",
+    ));
+    
+    // fs::rename - ファイルまたはディレクトリを移動
+    registry.register_function(StdlibFunction::new(
+        "rename",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("from".to_string(), string_type.id),
+            ("to".to_string(), string_type.id),
+        ],
+        unit_type.id,
+        "ファイルまたはディレクトリの名前を変更（移動）します。",
+    ));
+    
+    // fs::remove_file - ファイルを削除
+    registry.register_function(StdlibFunction::new(
+        "remove_file",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        unit_type.id,
+        "ファイルを削除します。",
+    ));
+    
+    // fs::remove_dir - 空のディレクトリを削除
+    registry.register_function(StdlibFunction::new(
+        "remove_dir",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        unit_type.id,
+        "空のディレクトリを削除します。",
+    ));
+    
+    // fs::remove_dir_all - ディレクトリを中身ごと削除
+    registry.register_function(StdlibFunction::new(
+        "remove_dir_all",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("path".to_string(), string_type.id)],
+        unit_type.id,
+        "ディレクトリをその中身ごと削除します。",
+    ));
+    
+    // 一時ファイル
+    
+    // fs::temp_dir - 一時ディレクトリのパス
+    registry.register_function(StdlibFunction::new(
+        "temp_dir",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![],
+        string_type.id,
+        "システムの一時ディレクトリのパスを返します。",
+    ));
+    
+    // fs::temp_file - 一時ファイルを作成
+    registry.register_function(StdlibFunction::new(
+        "temp_file",
+        StdlibModule::Fs,
+        StdlibFunctionType::Effectful,
+        vec![("prefix".to_string(), string_type.id)],
+        string_type.id,
+        "一時ディレクトリに指定した接頭辞で始まる空のファイルを作成し、そのパスを返します。ファイルは自動では削除されません。",
+    ));
+    
+    Ok(())
+}
+
+/// ファイルシステム関数の実行
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    match function_name {
+        "read_dir" => {
+            let [path] = string_args(function_name, args)?;
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(path).map_err(|e| fs_error(function_name, path, e))? {
+                let entry = entry.map_err(|e| fs_error(function_name, path, e))?;
+                entries.push(entry.path().to_string_lossy().to_string());
+            }
+            entries.sort();
+            Ok(Value::from(entries))
+        }
+        "walk_dir" => {
+            let [path] = string_args(function_name, args)?;
+            let mut files = Vec::new();
+            walk(Path::new(path), &mut files).map_err(|e| fs_error(function_name, path, e))?;
+            files.sort();
+            Ok(Value::from(files))
+        }
+        "exists" => {
+            let [path] = string_args(function_name, args)?;
+            Ok(Value::Bool(Path::new(path).exists()))
+        }
+        "is_file" => {
+            let [path] = string_args(function_name, args)?;
+            Ok(Value::Bool(Path::new(path).is_file()))
+        }
+        "is_dir" => {
+            let [path] = string_args(function_name, args)?;
+            Ok(Value::Bool(Path::new(path).is_dir()))
+        }
+        "size" => {
+            let [path] = string_args(function_name, args)?;
+            let metadata = fs::metadata(path).map_err(|e| fs_error(function_name, path, e))?;
+            Ok(Value::Int(metadata.len() as i64))
+        }
+        "modified" => {
+            let [path] = string_args(function_name, args)?;
+            let modified = fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .map_err(|e| fs_error(function_name, path, e))?;
+            // エポックより前の時刻は負の秒数で表す
+            let seconds = match modified.duration_since(UNIX_EPOCH) {
+                Ok(elapsed) => elapsed.as_secs() as i64,
+                Err(e) => -(e.duration().as_secs() as i64),
+            };
+            Ok(Value::Int(seconds))
+        }
+        "create_dir" => {
+            let [path] = string_args(function_name, args)?;
+            fs::create_dir_all(path).map_err(|e| fs_error(function_name, path, e))?;
+            Ok(Value::Unit)
+        }
+        "copy" => {
+            let [from, to] = string_args(function_name, args)?;
+            let copied = fs::copy(from, to).map_err(|e| fs_error(function_name, from, e))?;
+            Ok(Value::Int(copied as i64))
+        }
+        "rename" => {
+            let [from, to] = string_args(function_name, args)?;
+            fs::rename(from, to).map_err(|e| fs_error(function_name, from, e))?;
+            Ok(Value::Unit)
+        }
+        "remove_file" => {
+            let [path] = string_args(function_name, args)?;
+            fs::remove_file(path).map_err(|e| fs_error(function_name, path, e))?;
+            Ok(Value::Unit)
+        }
+        "remove_dir" => {
+            let [path] = string_args(function_name, args)?;
+            fs::remove_dir(path).map_err(|e| fs_error(function_name, path, e))?;
+            Ok(Value::Unit)
+        }
+        "remove_dir_all" => {
+            let [path] = string_args(function_name, args)?;
+            fs::remove_dir_all(path).map_err(|e| fs_error(function_name, path, e))?;
+            Ok(Value::Unit)
+        }
+        "temp_dir" => {
+            let [] = string_args(function_name, args)?;
+            Ok(Value::Str(std::env::temp_dir().to_string_lossy().to_string()))
+        }
+        "temp_file" => {
+            let [path] = string_args(function_name, args)?;
+            let file = tempfile::Builder::new()
+                .prefix(path)
+                .tempfile()
+                .map_err(|e| fs_error(function_name, path, e))?;
+            let (_, path) = file.keep().map_err(|e| fs_error(function_name, path, e.error))?;
+            Ok(Value::Str(path.to_string_lossy().to_string()))
+        }
+        _ => Err(EidosError::Runtime(format!("不明なファイルシステム関数: {}", function_name)))
+    }
+}
+
+/// 引数の数を確かめ、すべて文字列として取り出す
+fn string_args<'a, const N: usize>(function_name: &str, args: &'a [Value]) -> Result<[&'a str; N]> {
+    if args.len() != N {
+        let required = if N == 0 {
+            "引数が不要".to_string()
+        } else {
+            format!("{}つの引数が必要", N)
+        };
+        return Err(EidosError::Runtime(format!(
+            "fs::{}関数は{}ですが、{}個の引数が渡されました。",
+            function_name,
+            required,
+            args.len()
+        )));
+    }
+    let mut strings = [""; N];
+    for (string, arg) in strings.iter_mut().zip(args) {
+        *string = arg.as_str()?;
+    }
+    Ok(strings)
+}
+
+fn fs_error(function_name: &str, path: &str, error: std::io::Error) -> EidosError {
+    EidosError::Runtime(format!("fs::{}に失敗しました: {}: {}", function_name, path, error))
+}
+
+/// ディレクトリ以下のファイルを再帰的に集める（シンボリックリンクはたどらない）
+fn walk(dir: &Path, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            walk(&path, files)?;
+        } else {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn call(name: &str, args: &[&str]) -> Result<String> {
+        let args: Vec<Value> = args.iter().map(|&arg| Value::from(arg)).collect();
+        execute_function(name, &args).map(|value| value.to_string())
+    }
+    
+    #[test]
+    fn test_file_operations() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let nested = format!("{}/a/b", root);
+        let source = format!("{}/a/b/source.txt", root);
+        let copied = format!("{}/a/copy.txt", root);
+        let moved = format!("{}/moved.txt", root);
+        
+        call("create_dir", &[&nested]).unwrap();
+        assert_eq!(call("is_dir", &[&nested]).unwrap(), "true");
+        fs::write(&source, "hello").unwrap();
+        
+        assert_eq!(call("size", &[&source]).unwrap(), "5");
+        assert!(call("modified", &[&source]).unwrap().parse::<i64>().unwrap() > 0);
+        assert_eq!(call("copy", &[&source, &copied]).unwrap(), "5");
+        assert_eq!(call("walk_dir", &[&root]).unwrap(), format!("[{}, {}]", source, copied));
+        assert_eq!(call("read_dir", &[&format!("{}/a", root)]).unwrap(), format!("[{}/a/b, {}]", root, copied));
+        
+        call("rename", &[&copied, &moved]).unwrap();
+        assert_eq!(call("exists", &[&copied]).unwrap(), "false");
+        assert_eq!(call("is_file", &[&moved]).unwrap(), "true");
+        call("remove_file", &[&moved]).unwrap();
+        assert!(call("remove_dir", &[&nested]).is_err());
+        call("remove_dir_all", &[&format!("{}/a", root)]).unwrap();
+        assert_eq!(call("read_dir", &[&root]).unwrap(), "[]");
+    }
+    
+    #[test]
+    fn test_temp_file() {
+        let path = call("temp_file", &["eidos-fs-test"]).unwrap();
+        assert!(Path::new(&path).is_file());
+        assert!(Path::new(&path).file_name().unwrap().to_string_lossy().starts_with("eidos-fs-test"));
+        call("remove_file", &[&path]).unwrap();
+    }
+}
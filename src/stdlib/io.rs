@@ -1,6 +1,6 @@
 use crate::core::{Result, EidosError};
 use crate::core::types::{Type, TypeId, TypeKind, Field};
-use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
 
 /// 入出力モジュールの初期化
 pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
@@ -272,7 +272,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "File::position",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("file".to_string(), file_type.id)],
         int_type.id,
         "現在のファイル位置を取得します。",
@@ -282,7 +282,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "File::length",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("file".to_string(), file_type.id)],
         int_type.id,
         "ファイルサイズを取得します。",
@@ -314,7 +314,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Directory::exists",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("path".to_string(), string_type.id)],
         bool_type.id,
         "指定したパスにディレクトリが存在するかどうかを確認します。",
@@ -324,7 +324,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Directory::list",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("path".to_string(), string_type.id)],
         Type::array(string_type.clone()).id,
         "ディレクトリ内のファイルとサブディレクトリの一覧を取得します。",
@@ -336,7 +336,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Path::exists",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("path".to_string(), string_type.id)],
         bool_type.id,
         "指定したパスが存在するかどうかを確認します。",
@@ -346,7 +346,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Path::is_file",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("path".to_string(), string_type.id)],
         bool_type.id,
         "指定したパスがファイルかどうかを確認します。",
@@ -356,7 +356,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Path::is_directory",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("path".to_string(), string_type.id)],
         bool_type.id,
         "指定したパスがディレクトリかどうかを確認します。",
@@ -409,7 +409,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "Path::absolute",
         StdlibModule::IO,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("path".to_string(), string_type.id)],
         string_type.id,
         "相対パスから絶対パスを取得します。",
@@ -419,7 +419,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
 }
 
 /// 入出力関数の実行
-pub fn execute_function(function_name: &str, args: &[String]) -> Result<String> {
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
     match function_name {
         // Console関数
         "Console::print" => {
@@ -430,7 +430,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 )));
             }
             print!("{}", args[0]);
-            Ok("".to_string())
+            Ok(Value::Unit)
         }
         "Console::println" => {
             if args.len() != 1 {
@@ -440,7 +440,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 )));
             }
             println!("{}", args[0]);
-            Ok("".to_string())
+            Ok(Value::Unit)
         }
         _ => Err(EidosError::Runtime(format!("入出力関数 '{}' はネイティブ実装で提供されます", function_name)))
     }
@@ -1,6 +1,6 @@
 use crate::core::{Result, EidosError};
 use crate::core::types::{Type, TypeId};
-use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
 
 /// 数学モジュールの初期化
 pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
@@ -429,11 +429,69 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         "浮動小数点数の符号を返します（負なら-1.0、0なら0.0、正なら1.0）。",
     ));
     
+    // Int版とFloat版を持つ関数（呼び出し側の引数の型でどちらを使うかが決まる）
+    for (name, params, description) in OVERLOADED {
+        for numeric in [&int_type, &float_type] {
+            registry.register_overload(
+                StdlibFunction::new(
+                    name,
+                    StdlibModule::Math,
+                    StdlibFunctionType::Pure,
+                    params.iter().map(|param| (param.to_string(), numeric.id)).collect(),
+                    numeric.id,
+                    description,
+                ),
+                vec![numeric.clone(); params.len()],
+                numeric.clone(),
+            );
+        }
+    }
+    
     Ok(())
 }
 
-/// 標準ライブラリ数学関数の実装
-pub fn execute_function(function_name: &str, args: &[f64]) -> Result<f64> {
+/// Int版（`_i`）とFloat版（`_f`）を持つ関数の名前、引数名、説明
+const OVERLOADED: [(&str, &[&str], &str); 5] = [
+    ("abs", &["value"], "絶対値を返します。"),
+    ("min", &["a", "b"], "2つの値のうち小さい方を返します。"),
+    ("max", &["a", "b"], "2つの値のうち大きい方を返します。"),
+    ("clamp", &["value", "min", "max"], "値をmin以上max以下の範囲に制限します。"),
+    ("sign", &["value"], "値の符号を返します（負なら-1、0なら0、正なら1）。"),
+];
+
+/// 整数を受け取り整数を返す関数
+const INT_FUNCTIONS: &[&str] = &["abs_i", "min_i", "max_i", "clamp_i", "sign_i"];
+
+/// 真偽値を返す判定関数
+const PREDICATES: &[&str] = &["is_nan", "is_infinite", "is_finite"];
+
+/// 数学関数の実行
+///
+/// `_i` で終わる整数版の関数は整数だけを受け取る。それ以外は整数の引数も浮動小数点数として扱う。
+/// `abs` などのオーバーロードされた関数は、引数の型に応じて整数版か浮動小数点数版を呼ぶ。
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    if OVERLOADED.iter().any(|(name, _, _)| *name == function_name) {
+        // 引数がすべて整数ならInt版、それ以外はFloat版（整数は浮動小数点数に変換される）
+        let suffix = if args.iter().all(|arg| matches!(arg, Value::Int(_))) { "_i" } else { "_f" };
+        return execute_function(&format!("{}{}", function_name, suffix), args);
+    }
+    
+    if INT_FUNCTIONS.contains(&function_name) {
+        let args = args.iter().map(Value::as_int).collect::<std::result::Result<Vec<_>, _>>()?;
+        return execute_int_function(function_name, &args).map(Value::Int);
+    }
+    
+    let args = args.iter().map(Value::as_float).collect::<std::result::Result<Vec<_>, _>>()?;
+    let result = execute_float_function(function_name, &args)?;
+    if PREDICATES.contains(&function_name) {
+        Ok(Value::Bool(result != 0.0))
+    } else {
+        Ok(Value::Float(result))
+    }
+}
+
+/// 浮動小数点数演算のための関数の実装
+pub fn execute_float_function(function_name: &str, args: &[f64]) -> Result<f64> {
     match function_name {
         // 定数
         "PI" => {
@@ -780,4 +838,43 @@ pub fn execute_int_function(function_name: &str, args: &[i64]) -> Result<i64> {
         // 未実装の関数
         _ => Err(EidosError::Runtime(format!("未実装の整数数学関数: {}", function_name))),
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::TypeKind;
+    
+    #[test]
+    fn test_overload_resolution() {
+        let mut registry = StdlibRegistry::new();
+        initialize(&mut registry).unwrap();
+        
+        let abs = registry.resolve_overload("math::abs", &[Type::int()]).unwrap();
+        assert_eq!(abs.return_type.kind, TypeKind::Int);
+        let abs = registry.resolve_overload("math::abs", &[Type::float()]).unwrap();
+        assert_eq!(abs.return_type.kind, TypeKind::Float);
+        // IntとFloatが混ざる場合はIntをFloatに変換する版が選ばれる
+        let max = registry.resolve_overload("math::max", &[Type::int(), Type::float()]).unwrap();
+        assert_eq!(max.return_type.kind, TypeKind::Float);
+        
+        let error = registry.resolve_overload("math::min", &[Type::string(), Type::int()]).unwrap_err();
+        assert!(error.contains("math::min(String, Int) に合うシグネチャがありません"), "{}", error);
+        assert!(error.contains("math::min(Int, Int) -> Int"));
+        assert!(registry.resolve_overload("math::clamp", &[Type::int()]).is_err());
+        let error = registry.resolve_overload("mth::sqrt", &[Type::float()]).unwrap_err();
+        assert!(error.contains("mth::sqrt（もしかして: 'math::sqrt'）"), "{}", error);
+    }
+    
+    #[test]
+    fn test_overloaded_execution() {
+        assert_eq!(execute_function("abs", &[Value::Int(-3)]).unwrap(), Value::Int(3));
+        assert_eq!(execute_function("abs", &[Value::Float(-1.5)]).unwrap(), Value::Float(1.5));
+        assert_eq!(execute_function("max", &[Value::Int(2), Value::Float(2.5)]).unwrap(), Value::Float(2.5));
+        assert_eq!(
+            execute_function("clamp", &[Value::Int(12), Value::Int(0), Value::Int(10)]).unwrap(),
+            Value::Int(10)
+        );
+        assert_eq!(execute_function("sign", &[Value::Int(-7)]).unwrap(), Value::Int(-1));
+    }
+}
@@ -1,19 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 
 use crate::core::{Result, EidosError};
-use crate::core::types::{Type, TypeId};
+use crate::core::error::did_you_mean;
+use crate::core::types::{Type, TypeId, TypeKind};
 
 pub mod math;
 pub mod string;
 pub mod collections;
 pub mod io;
+pub mod fs;
+pub mod net;
+pub mod regex;
 pub mod time;
 pub mod system;
+pub mod option;
+pub mod thread;
+pub mod bigint;
+pub mod value;
+pub mod plugin;
+pub mod cache;
+
+pub use value::{ConversionError, Value};
 
 /// 標準ライブラリ関数の実行タイプ
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StdlibFunctionType {
     /// 副作用のない純粋関数
     Pure,
@@ -22,7 +36,7 @@ pub enum StdlibFunctionType {
 }
 
 /// 標準ライブラリモジュール
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StdlibModule {
     /// 数学関数
     Math,
@@ -32,10 +46,22 @@ pub enum StdlibModule {
     Collections,
     /// 入出力処理
     IO,
+    /// ファイルシステム操作
+    Fs,
+    /// ネットワーク
+    Net,
+    /// 正規表現
+    Regex,
     /// 時間関連
     Time,
     /// システム関連
     System,
+    /// `Option` と `Result`
+    Option,
+    /// スレッドとアトミック操作
+    Thread,
+    /// 任意精度の整数
+    BigInt,
 }
 
 impl StdlibModule {
@@ -46,14 +72,53 @@ impl StdlibModule {
             StdlibModule::String => "string",
             StdlibModule::Collections => "collections",
             StdlibModule::IO => "io",
+            StdlibModule::Fs => "fs",
+            StdlibModule::Net => "net",
+            StdlibModule::Regex => "regex",
             StdlibModule::Time => "time",
             StdlibModule::System => "system",
+            StdlibModule::Option => "option",
+            StdlibModule::Thread => "thread",
+            StdlibModule::BigInt => "bigint",
         }
     }
+    
+    /// 名前から組み込みモジュールを取得
+    pub fn from_name(name: &str) -> Option<Self> {
+        BUILTIN_MODULES.iter().copied().find(|module| module.name() == name)
+    }
+    
+    /// 使えなくする組み込みモジュールを設定（プロジェクトの設定の `[stdlib]` で指定する）
+    ///
+    /// 無効にしたモジュールの関数を参照すると、モジュールの解決でエラーになる。
+    pub fn set_disabled(modules: HashSet<StdlibModule>) {
+        *DISABLED_MODULES.write().unwrap() = modules;
+    }
+    
+    /// 無効にした組み込みモジュール
+    pub fn disabled() -> HashSet<StdlibModule> {
+        DISABLED_MODULES.read().unwrap().clone()
+    }
 }
 
+/// 組み込みモジュールの一覧
+const BUILTIN_MODULES: [StdlibModule; 12] = [
+    StdlibModule::Math,
+    StdlibModule::String,
+    StdlibModule::Collections,
+    StdlibModule::IO,
+    StdlibModule::Fs,
+    StdlibModule::Net,
+    StdlibModule::Regex,
+    StdlibModule::Time,
+    StdlibModule::System,
+    StdlibModule::Option,
+    StdlibModule::Thread,
+    StdlibModule::BigInt,
+];
+
 /// 標準ライブラリ関数
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StdlibFunction {
     /// 関数名
     pub name: String,
@@ -93,6 +158,65 @@ impl StdlibFunction {
     pub fn full_name(&self) -> String {
         format!("{}::{}", self.module.name(), self.name)
     }
+    
+    /// 呼び出すときの名前（`Vector::push` のように型名で呼ぶ関数はその名前、それ以外は完全修飾名）
+    pub fn call_name(&self) -> String {
+        if self.name.contains("::") {
+            self.name.clone()
+        } else {
+            self.full_name()
+        }
+    }
+}
+
+/// オーバーロードされた関数の1つのシグネチャ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StdlibSignature {
+    /// 引数の型
+    pub params: Vec<Type>,
+    /// 戻り値の型
+    pub return_type: Type,
+}
+
+impl StdlibSignature {
+    /// 引数の型がこのシグネチャに合うか調べ、合う場合は暗黙の変換（Int → Float、Int → BigInt）の数を返す
+    fn conversion_cost(&self, arg_types: &[Type]) -> Option<usize> {
+        if arg_types.len() != self.params.len() {
+            return None;
+        }
+        let mut cost = 0;
+        for (arg, param) in arg_types.iter().zip(&self.params) {
+            if arg.kind == param.kind {
+                continue;
+            }
+            if arg.kind == TypeKind::Int && matches!(param.kind, TypeKind::Float | TypeKind::BigInt) {
+                cost += 1;
+            } else {
+                return None;
+            }
+        }
+        Some(cost)
+    }
+}
+
+impl std::fmt::Display for StdlibSignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<String> = self.params.iter().map(Type::to_string).collect();
+        write!(f, "({}) -> {}", params.join(", "), self.return_type)
+    }
+}
+
+/// ネイティブ関数の本体
+pub type NativeFn = dyn Fn(&[Value]) -> Result<Value> + Send + Sync;
+
+/// ホスト（埋め込み側やプラグイン）が提供するネイティブ関数
+#[derive(Clone)]
+pub struct NativeFunction(Arc<NativeFn>);
+
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFunction")
+    }
 }
 
 /// 標準ライブラリレジストリ
@@ -100,8 +224,12 @@ impl StdlibFunction {
 pub struct StdlibRegistry {
     /// 型のマップ
     pub types: HashMap<String, Type>,
-    /// 関数のマップ
+    /// 呼び出し名（`io::println` や `Vector::push`）ごとの関数
     pub functions: HashMap<String, StdlibFunction>,
+    /// 完全修飾名ごとの型付きシグネチャ（オーバーロードされた関数では複数）
+    pub signatures: HashMap<String, Vec<StdlibSignature>>,
+    /// 完全修飾名ごとのネイティブ関数
+    pub natives: HashMap<String, NativeFunction>,
 }
 
 impl StdlibRegistry {
@@ -110,6 +238,8 @@ impl StdlibRegistry {
         Self {
             types: HashMap::new(),
             functions: HashMap::new(),
+            signatures: HashMap::new(),
+            natives: HashMap::new(),
         }
     }
 
@@ -118,18 +248,43 @@ impl StdlibRegistry {
         STDLIB_REGISTRY.clone()
     }
 
-    /// 標準ライブラリを初期化
+    /// 標準ライブラリを初期化（初期化済みなら何もしない）
+    ///
+    /// `use_cache` でキャッシュの場所を設定していれば、組み込みモジュールを登録する代わりにキャッシュから読み込む。
     pub fn initialize() -> Result<()> {
-        let mut registry = Self::global().write().unwrap();
-        
-        // 各モジュールを初期化
-        math::initialize(&mut registry)?;
-        string::initialize(&mut registry)?;
-        collections::initialize(&mut registry)?;
-        io::initialize(&mut registry)?;
-        time::initialize(&mut registry)?;
-        system::initialize(&mut registry)?;
+        let global = Self::global();
+        let mut registry = global.write().unwrap();
+        if !registry.functions.is_empty() {
+            return Ok(());
+        }
         
+        match CACHE_PATH.read().unwrap().as_deref() {
+            Some(path) => cache::initialize(path, &mut registry),
+            None => registry.register_builtins(),
+        }
+    }
+    
+    /// `initialize` で、組み込みモジュールのレジストリをこのファイルにキャッシュする
+    ///
+    /// 最初の起動で書き出し、以降の起動では各モジュールを登録し直さずに読み込む（`eid repl` や `eid check` の起動を速くする）。
+    pub fn use_cache(path: PathBuf) {
+        *CACHE_PATH.write().unwrap() = Some(path);
+    }
+    
+    /// 組み込みモジュールの関数と型を登録
+    fn register_builtins(&mut self) -> Result<()> {
+        math::initialize(self)?;
+        string::initialize(self)?;
+        collections::initialize(self)?;
+        io::initialize(self)?;
+        fs::initialize(self)?;
+        net::initialize(self)?;
+        regex::initialize(self)?;
+        time::initialize(self)?;
+        system::initialize(self)?;
+        option::initialize(self)?;
+        thread::initialize(self)?;
+        bigint::initialize(self)?;
         Ok(())
     }
 
@@ -140,7 +295,105 @@ impl StdlibRegistry {
 
     /// 関数を登録
     pub fn register_function(&mut self, function: StdlibFunction) {
-        self.functions.insert(function.name.clone(), function);
+        self.functions.insert(function.call_name(), function);
+    }
+
+    /// 型付きのシグネチャとともに関数を登録する
+    ///
+    /// 同じ名前で複数回登録するとオーバーロードになり、呼び出し側の引数の型で `resolve_overload` が選ぶ。
+    pub fn register_overload(&mut self, function: StdlibFunction, params: Vec<Type>, return_type: Type) {
+        self.signatures
+            .entry(function.full_name())
+            .or_default()
+            .push(StdlibSignature { params, return_type });
+        self.functions.entry(function.call_name()).or_insert(function);
+    }
+
+    /// ネイティブ関数を登録する
+    ///
+    /// `name` は `db::query` のような完全修飾名で、組み込みモジュールの名前空間や登録済みの名前は使えない。
+    /// 登録した関数は型チェックでは `signature` で検査され、実行時は `execute_function` から呼ばれる。
+    pub fn register_native<F>(&mut self, name: &str, signature: StdlibSignature, function: F) -> Result<()>
+    where
+        F: Fn(&[Value]) -> Result<Value> + Send + Sync + 'static,
+    {
+        let module = match name.split_once("::") {
+            Some((module, function_name)) if !module.is_empty() && !function_name.is_empty() => module,
+            _ => {
+                return Err(EidosError::Runtime(format!(
+                    "ネイティブ関数の名前は `モジュール::関数名` の形式が必要です: {}",
+                    name
+                )))
+            }
+        };
+        if StdlibModule::from_name(module).is_some() {
+            return Err(EidosError::Runtime(format!(
+                "組み込みモジュール '{}' にはネイティブ関数を追加できません: {}",
+                module, name
+            )));
+        }
+        if self.signatures.contains_key(name) {
+            return Err(EidosError::Runtime(format!("ネイティブ関数 '{}' は登録済みです", name)));
+        }
+        
+        self.signatures.insert(name.to_string(), vec![signature]);
+        self.natives.insert(name.to_string(), NativeFunction(Arc::new(function)));
+        Ok(())
+    }
+    
+    /// 登録済みの関数の呼び出し名（組み込みモジュールの関数とネイティブ関数。重複を含む）
+    pub fn function_names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().chain(self.signatures.keys()).map(String::as_str)
+    }
+    
+    /// 型付きのシグネチャを完全修飾名（`math::abs`）で取得
+    pub fn get_overloads(&self, full_name: &str) -> Option<&[StdlibSignature]> {
+        self.signatures.get(full_name).map(Vec::as_slice)
+    }
+
+    /// 引数の型に合うシグネチャを選ぶ
+    ///
+    /// 型が完全に一致するものを優先し、なければ暗黙の変換（Int → Float）が最も少ないものを選ぶ。
+    /// 候補がない場合や1つに決まらない場合は、候補の一覧を含むメッセージを返す。
+    pub fn resolve_overload(&self, full_name: &str, arg_types: &[Type]) -> std::result::Result<&StdlibSignature, String> {
+        let overloads = self
+            .get_overloads(full_name)
+            .ok_or_else(|| format!("未定義の標準ライブラリ関数: {}{}", full_name, did_you_mean(full_name, self.function_names())))?;
+        let mut best: Vec<(&StdlibSignature, usize)> = Vec::new();
+        for signature in overloads {
+            let Some(cost) = signature.conversion_cost(arg_types) else {
+                continue;
+            };
+            match best.first() {
+                Some((_, best_cost)) if cost > *best_cost => {}
+                Some((_, best_cost)) if cost < *best_cost => best = vec![(signature, cost)],
+                _ => best.push((signature, cost)),
+            }
+        }
+        
+        let candidates = || {
+            overloads
+                .iter()
+                .map(|signature| format!("  {}{}", full_name, signature))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let args: Vec<String> = arg_types.iter().map(Type::to_string).collect();
+        match best.as_slice() {
+            [(signature, _)] => Ok(signature),
+            [] => Err(format!(
+                "{}({}) に合うシグネチャがありません。候補:\n{}",
+                full_name,
+                args.join(", "),
+                candidates()
+            )),
+            _ => Err(format!(
+                "{}({}) の呼び出しがあいまいです。候補:\n{}",
+                full_name,
+                args.join(", "),
+                candidates()
+            )),
+        }
     }
 
     /// 型を名前で取得
@@ -148,11 +401,21 @@ impl StdlibRegistry {
         self.types.get(name)
     }
 
-    /// 関数を名前で取得
+    /// 関数を呼び出し名で取得
     pub fn get_function(&self, name: &str) -> Option<&StdlibFunction> {
         self.functions.get(name)
     }
 
+    /// 呼び出し名（`io::println` や `Map::get`）から関数の実行タイプを取得
+    ///
+    /// ネイティブ関数は副作用の有無が分からないため、副作用ありとして扱う。
+    pub fn function_type(&self, name: &str) -> Option<StdlibFunctionType> {
+        if self.natives.contains_key(name) {
+            return Some(StdlibFunctionType::Effectful);
+        }
+        self.functions.get(name).map(|function| function.fn_type)
+    }
+    
     /// 指定されたモジュールの関数一覧を取得
     pub fn get_module_functions(&self, module: StdlibModule) -> Vec<&StdlibFunction> {
         self.functions
@@ -162,18 +425,20 @@ impl StdlibRegistry {
     }
 
     /// 標準ライブラリ関数を実行
-    pub fn execute_function(&self, function_name: &str, args: &[String]) -> Result<String> {
+    ///
+    /// 関数名は `モジュール::関数名` の形式で、関数名の部分には `HashMap::new` のように `::` を含んでもよい。
+    pub fn execute_function(&self, function_name: &str, args: &[Value]) -> Result<Value> {
+        if let Some(native) = self.natives.get(function_name) {
+            return (native.0)(args);
+        }
+        
         // モジュール名と関数名に分割
-        let parts: Vec<&str> = function_name.split("::").collect();
-        if parts.len() != 2 {
-            return Err(EidosError::Runtime(format!(
+        let (module_name, fn_name) = function_name.split_once("::").ok_or_else(|| {
+            EidosError::Runtime(format!(
                 "無効な関数名: {}（モジュール::関数名の形式が必要）",
                 function_name
-            )));
-        }
-
-        let module_name = parts[0];
-        let fn_name = parts[1];
+            ))
+        })?;
 
         // モジュールに基づいて関数を実行
         match module_name {
@@ -181,8 +446,15 @@ impl StdlibRegistry {
             "string" => string::execute_function(fn_name, args),
             "collections" => collections::execute_function(fn_name, args),
             "io" => io::execute_function(fn_name, args),
+            "fs" => fs::execute_function(fn_name, args),
+            "net" => net::execute_function(fn_name, args),
+            "regex" => regex::execute_function(fn_name, args),
             "time" => time::execute_function(fn_name, args),
             "system" => system::execute_function(fn_name, args),
+            "thread" => thread::execute_function(fn_name, args),
+            "bigint" => bigint::execute_function(fn_name, args),
+            // `Option::some` のように型名で呼び出す
+            "Option" | "Result" => option::execute_function(function_name, args),
             _ => Err(EidosError::Runtime(format!("不明なモジュール: {}", module_name))),
         }
     }
@@ -191,4 +463,6 @@ impl StdlibRegistry {
 // グローバルレジストリのシングルトンインスタンス
 lazy_static! {
     static ref STDLIB_REGISTRY: Arc<RwLock<StdlibRegistry>> = Arc::new(RwLock::new(StdlibRegistry::new()));
+    static ref DISABLED_MODULES: RwLock<HashSet<StdlibModule>> = RwLock::new(HashSet::new());
+    static ref CACHE_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
 } 
\ No newline at end of file
@@ -0,0 +1,487 @@
+use crate::core::{Result, EidosError};
+use crate::core::types::{Type, TypeKind, Field};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::sync::{Mutex, atomic::{AtomicUsize, Ordering}};
+use std::time::Duration;
+use lazy_static::lazy_static;
+
+/// HTTPクライアントの接続・読み書きのタイムアウト
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    static ref TCP_STREAMS: Mutex<HashMap<String, TcpStream>> = Mutex::new(HashMap::new());
+    static ref TCP_LISTENERS: Mutex<HashMap<String, TcpListener>> = Mutex::new(HashMap::new());
+    static ref INSTANCE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+}
+
+/// ネットワークモジュールの初期化
+pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
+    // 基本型の登録
+    let int_type = Type::int();
+    let string_type = Type::string();
+    let unit_type = Type::unit();
+    
+    // ソケットはインタプリタ側で管理し、Eidosの値としてはハンドルのIDだけを持つ
+    let socket_type = |name: &str| {
+        Type::new(TypeKind::Struct {
+            name: name.to_string(),
+            fields: vec![Field {
+                name: "id".to_string(),
+                field_type: string_type.clone(),
+                is_public: false,
+            }],
+            methods: vec![],
+            is_extern: false,
+        })
+    };
+    let stream_type = socket_type("TcpStream");
+    let listener_type = socket_type("TcpListener");
+    registry.register_type("net::TcpStream", stream_type.clone());
+    registry.register_type("net::TcpListener", listener_type.clone());
+    
+    // TCP
+    
+    // net::tcp_connect - TCP接続を開く
+    registry.register_function(StdlibFunction::new(
+        "tcp_connect",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![("address".to_string(), string_type.id)],
+        stream_type.id,
+        "指定したアドレス（例: \"example.com:80\"）にTCPで接続します。",
+    ));
+    
+    // net::tcp_listen - TCP接続を待ち受ける
+    registry.register_function(StdlibFunction::new(
+        "tcp_listen",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![("address".to_string(), string_type.id)],
+        listener_type.id,
+        "指定したアドレスでTCP接続の待ち受けを開始します。ポートに0を指定すると空いているポートを使います。",
+    ));
+    
+    // net::tcp_accept - 接続を受け入れる
+    registry.register_function(StdlibFunction::new(
+        "tcp_accept",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![("listener".to_string(), listener_type.id)],
+        stream_type.id,
+        "待ち受け中のソケットへの次の接続を受け入れます。接続が来るまでブロックします。",
+    ));
+    
+    // net::local_addr - ソケットのローカルアドレス
+    registry.register_function(StdlibFunction::new(
+        "local_addr",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![("socket".to_string(), string_type.id)],
+        string_type.id,
+        "ソケットがバインドされているローカルアドレスを返します。",
+    ));
+    
+    // net::read - ストリームから読み込む
+    registry.register_function(StdlibFunction::new(
+        "read",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("stream".to_string(), stream_type.id),
+            ("max_bytes".to_string(), int_type.id),
+        ],
+        string_type.id,
+        "ストリームから最大max_bytesバイトを読み込みます。接続が閉じられている場合は空文字列を返します。",
+    ));
+    
+    // net::write - ストリームに書き込む
+    registry.register_function(StdlibFunction::new(
+        "write",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("stream".to_string(), stream_type.id),
+            ("data".to_string(), string_type.id),
+        ],
+        int_type.id,
+        "ストリームに文字列をすべて書き込み、書き込んだバイト数を返します。",
+    ));
+    
+    // net::close - ソケットを閉じる
+    registry.register_function(StdlibFunction::new(
+        "close",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![("socket".to_string(), string_type.id)],
+        unit_type.id,
+        "TCPストリームまたは待ち受けソケットを閉じます。",
+    ));
+    
+    // UDP
+    
+    // net::udp_send - UDPデータグラムを送信
+    registry.register_function(StdlibFunction::new(
+        "udp_send",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("address".to_string(), string_type.id),
+            ("data".to_string(), string_type.id),
+        ],
+        int_type.id,
+        "指定したアドレスにUDPデータグラムを1つ送信し、送信したバイト数を返します。",
+    ));
+    
+    // HTTP
+    
+    // net::http_get - HTTP GETリクエスト
+    registry.register_function(StdlibFunction::new(
+        "http_get",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![("url".to_string(), string_type.id)],
+        string_type.id,
+        "URLにHTTP GETリクエストを送り、レスポンスの本文を返します。2xx以外のステータスはエラーになります。",
+    ));
+    
+    // net::http_post - HTTP POSTリクエスト
+    registry.register_function(StdlibFunction::new(
+        "http_post",
+        StdlibModule::Net,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("url".to_string(), string_type.id),
+            ("body".to_string(), string_type.id),
+            ("content_type".to_string(), string_type.id),
+        ],
+        string_type.id,
+        "URLに本文を付けてHTTP POSTリクエストを送り、レスポンスの本文を返します。2xx以外のステータスはエラーになります。",
+    ));
+    
+    Ok(())
+}
+
+/// ネットワーク関数の実行
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    match function_name {
+        "tcp_connect" => {
+            let [address] = string_args(function_name, args)?;
+            let stream = TcpStream::connect(address).map_err(|e| net_error(function_name, address, e))?;
+            let id = generate_instance_id("tcp_stream");
+            TCP_STREAMS.lock().unwrap().insert(id.clone(), stream);
+            Ok(Value::Str(id))
+        }
+        "tcp_listen" => {
+            let [address] = string_args(function_name, args)?;
+            let listener = TcpListener::bind(address).map_err(|e| net_error(function_name, address, e))?;
+            let id = generate_instance_id("tcp_listener");
+            TCP_LISTENERS.lock().unwrap().insert(id.clone(), listener);
+            Ok(Value::Str(id))
+        }
+        "tcp_accept" => {
+            let [handle] = string_args(function_name, args)?;
+            // 受け入れを待つ間も他のソケットを使えるよう、複製したハンドルでロックの外で待つ
+            let listener = TCP_LISTENERS
+                .lock()
+                .unwrap()
+                .get(handle)
+                .ok_or_else(|| invalid_handle(handle))?
+                .try_clone()
+                .map_err(|e| net_error(function_name, handle, e))?;
+            let (stream, _) = listener.accept().map_err(|e| net_error(function_name, handle, e))?;
+            let id = generate_instance_id("tcp_stream");
+            TCP_STREAMS.lock().unwrap().insert(id.clone(), stream);
+            Ok(Value::Str(id))
+        }
+        "local_addr" => {
+            let [handle] = string_args(function_name, args)?;
+            let address = if let Some(stream) = TCP_STREAMS.lock().unwrap().get(handle) {
+                stream.local_addr()
+            } else if let Some(listener) = TCP_LISTENERS.lock().unwrap().get(handle) {
+                listener.local_addr()
+            } else {
+                return Err(invalid_handle(handle));
+            };
+            Ok(Value::Str(address.map_err(|e| net_error(function_name, handle, e))?.to_string()))
+        }
+        "read" => {
+            expect_args(function_name, args, 2)?;
+            let handle = args[0].as_str()?;
+            let max_bytes = args[1].as_usize()?;
+            let mut stream = clone_stream(function_name, handle)?;
+            let mut buffer = vec![0; max_bytes];
+            let read = stream.read(&mut buffer).map_err(|e| net_error(function_name, handle, e))?;
+            Ok(Value::Str(String::from_utf8_lossy(&buffer[..read]).to_string()))
+        }
+        "write" => {
+            let [handle, data] = string_args(function_name, args)?;
+            let mut stream = clone_stream(function_name, handle)?;
+            stream.write_all(data.as_bytes()).map_err(|e| net_error(function_name, handle, e))?;
+            Ok(Value::from(data.len()))
+        }
+        "close" => {
+            let [handle] = string_args(function_name, args)?;
+            let removed = TCP_STREAMS.lock().unwrap().remove(handle).is_some()
+                || TCP_LISTENERS.lock().unwrap().remove(handle).is_some();
+            if !removed {
+                return Err(invalid_handle(handle));
+            }
+            Ok(Value::Unit)
+        }
+        "udp_send" => {
+            let [address, data] = string_args(function_name, args)?;
+            let socket = UdpSocket::bind("0.0.0.0:0")
+                .or_else(|_| UdpSocket::bind("[::]:0"))
+                .map_err(|e| net_error(function_name, address, e))?;
+            let sent = socket
+                .send_to(data.as_bytes(), address)
+                .map_err(|e| net_error(function_name, address, e))?;
+            Ok(Value::from(sent))
+        }
+        "http_get" => {
+            let [url] = string_args(function_name, args)?;
+            http_request("GET", url, None).map(Value::Str)
+        }
+        "http_post" => {
+            let [url, body, content_type] = string_args(function_name, args)?;
+            http_request("POST", url, Some((body, content_type))).map(Value::Str)
+        }
+        _ => Err(EidosError::Runtime(format!("不明なネットワーク関数: {}", function_name)))
+    }
+}
+
+/// 新しいソケットのIDを生成
+fn generate_instance_id(prefix: &str) -> String {
+    let id = INSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("{}:{}", prefix, id)
+}
+
+/// 読み書きの間ロックを持ち続けないよう、ストリームを複製して取り出す
+fn clone_stream(function_name: &str, id: &str) -> Result<TcpStream> {
+    TCP_STREAMS
+        .lock()
+        .unwrap()
+        .get(id)
+        .ok_or_else(|| invalid_handle(id))?
+        .try_clone()
+        .map_err(|e| net_error(function_name, id, e))
+}
+
+fn expect_args(function_name: &str, args: &[Value], expected: usize) -> Result<()> {
+    if args.len() == expected {
+        return Ok(());
+    }
+    Err(EidosError::Runtime(format!(
+        "net::{}関数は{}つの引数が必要ですが、{}個の引数が渡されました。",
+        function_name,
+        expected,
+        args.len()
+    )))
+}
+
+/// 引数の数を確かめ、すべて文字列として取り出す
+fn string_args<'a, const N: usize>(function_name: &str, args: &'a [Value]) -> Result<[&'a str; N]> {
+    expect_args(function_name, args, N)?;
+    let mut strings = [""; N];
+    for (string, arg) in strings.iter_mut().zip(args) {
+        *string = arg.as_str()?;
+    }
+    Ok(strings)
+}
+
+fn invalid_handle(id: &str) -> EidosError {
+    EidosError::Runtime(format!("無効なソケット参照: {}", id))
+}
+
+fn net_error(function_name: &str, target: &str, error: std::io::Error) -> EidosError {
+    EidosError::Runtime(format!("net::{}に失敗しました: {}: {}", function_name, target, error))
+}
+
+/// HTTP/1.1のリクエストを1回送り、レスポンスの本文を返す（`http://` のみ対応）
+fn http_request(method: &str, url: &str, body: Option<(&str, &str)>) -> Result<String> {
+    let (host, port, path) = parse_http_url(url)?;
+    let address = format!("{}:{}", host, port);
+    let http_error = |e: std::io::Error| EidosError::Runtime(format!("HTTPリクエストに失敗しました: {}: {}", url, e));
+    
+    let mut stream = TcpStream::connect(&address).map_err(http_error)?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT)).map_err(http_error)?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT)).map_err(http_error)?;
+    
+    let host_header = if port == 80 { host.clone() } else { address.clone() };
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: eidos/{}\r\nAccept: */*\r\nConnection: close\r\n",
+        method,
+        path,
+        host_header,
+        env!("CARGO_PKG_VERSION")
+    );
+    if let Some((body, content_type)) = body {
+        request.push_str(&format!("Content-Type: {}\r\nContent-Length: {}\r\n\r\n{}", content_type, body.len(), body));
+    } else {
+        request.push_str("\r\n");
+    }
+    stream.write_all(request.as_bytes()).map_err(http_error)?;
+    
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(http_error)?;
+    parse_http_response(url, &response)
+}
+
+/// `http://host[:port]/path` をホスト、ポート、パスに分ける
+fn parse_http_url(url: &str) -> Result<(String, u16, String)> {
+    let rest = match url.strip_prefix("http://") {
+        Some(rest) => rest,
+        None if url.starts_with("https://") => {
+            return Err(EidosError::Runtime(format!("HTTPSは未対応です: {}", url)));
+        }
+        None => return Err(EidosError::Runtime(format!("URLは http:// で始まる必要があります: {}", url))),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        // IPv6アドレスの中のコロンはポートの区切りではない
+        Some((host, port)) if !port.contains(']') => {
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| EidosError::Runtime(format!("URLのポート番号が不正です: {}", url)))?;
+            (host, port)
+        }
+        _ => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(EidosError::Runtime(format!("URLにホスト名がありません: {}", url)));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+/// レスポンスのステータスを確かめ、本文を取り出す
+fn parse_http_response(url: &str, response: &[u8]) -> Result<String> {
+    let malformed = || EidosError::Runtime(format!("HTTPレスポンスが不正です: {}", url));
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let body = &response[header_end + 4..];
+    
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().ok_or_else(malformed)?;
+    let mut parts = status_line.splitn(3, ' ');
+    let _version = parts.next();
+    let status = parts.next().and_then(|code| code.parse::<u16>().ok()).ok_or_else(malformed)?;
+    let reason = parts.next().unwrap_or("");
+    if !(200..300).contains(&status) {
+        return Err(EidosError::Runtime(format!("HTTP {} {}: {}", status, reason, url)));
+    }
+    
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+    let body = if chunked { decode_chunked(body).ok_or_else(malformed)? } else { body.to_vec() };
+    Ok(String::from_utf8_lossy(&body).to_string())
+}
+
+/// `Transfer-Encoding: chunked` の本文を復号する
+fn decode_chunked(mut data: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = data.windows(2).position(|window| window == b"\r\n")?;
+        let size_line = std::str::from_utf8(&data[..line_end]).ok()?;
+        // チャンク拡張（`;` 以降）は無視する
+        let size = usize::from_str_radix(size_line.split(';').next()?.trim(), 16).ok()?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(data.get(..size)?);
+        data = data.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    
+    fn call(name: &str, args: &[&str]) -> Result<String> {
+        let args: Vec<Value> = args.iter().map(|&arg| Value::from(arg)).collect();
+        execute_function(name, &args).map(|value| value.to_string())
+    }
+    
+    fn read(handle: &str, max_bytes: i64) -> Result<String> {
+        execute_function("read", &[Value::from(handle), Value::Int(max_bytes)]).map(|value| value.to_string())
+    }
+    
+    #[test]
+    fn test_tcp_echo() {
+        let listener = call("tcp_listen", &["127.0.0.1:0"]).unwrap();
+        let address = call("local_addr", &[&listener]).unwrap();
+        
+        let client = thread::spawn(move || {
+            let stream = call("tcp_connect", &[&address]).unwrap();
+            assert_eq!(call("write", &[&stream, "ping"]).unwrap(), "4");
+            let reply = read(&stream, 16).unwrap();
+            call("close", &[&stream]).unwrap();
+            reply
+        });
+        
+        let server = call("tcp_accept", &[&listener]).unwrap();
+        let request = read(&server, 16).unwrap();
+        call("write", &[&server, &format!("{}/pong", request)]).unwrap();
+        call("close", &[&server]).unwrap();
+        call("close", &[&listener]).unwrap();
+        
+        assert_eq!(client.join().unwrap(), "ping/pong");
+        assert!(call("close", &[&listener]).is_err());
+    }
+    
+    #[test]
+    fn test_http_get_and_post() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let responses = [
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+                "HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok",
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+            ];
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0; 1024];
+                let read = stream.read(&mut buffer).unwrap();
+                requests.push(String::from_utf8_lossy(&buffer[..read]).to_string());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        
+        let base = format!("http://127.0.0.1:{}", port);
+        assert_eq!(call("http_get", &[&format!("{}/greeting", base)]).unwrap(), "hello world");
+        assert_eq!(call("http_post", &[&format!("{}/items", base), "{}", "application/json"]).unwrap(), "ok");
+        let error = call("http_get", &[&format!("{}/missing", base)]).unwrap_err();
+        assert!(error.to_string().contains("404"));
+        
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /greeting HTTP/1.1\r\n"));
+        assert!(requests[1].starts_with("POST /items HTTP/1.1\r\n"));
+        assert!(requests[1].contains("Content-Type: application/json\r\nContent-Length: 2\r\n\r\n{}"));
+    }
+    
+    #[test]
+    fn test_parse_http_url() {
+        assert_eq!(parse_http_url("http://example.com").unwrap(), ("example.com".to_string(), 80, "/".to_string()));
+        assert_eq!(
+            parse_http_url("http://[::1]:8080/a?b=c").unwrap(),
+            ("[::1]".to_string(), 8080, "/a?b=c".to_string())
+        );
+        assert!(parse_http_url("https://example.com").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::core::{Result, EidosError};
+use crate::core::types::{Type, TypeKind};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
+
+/// `Option` と `Result` の関数（名前、引数の名前、説明）
+///
+/// 中の値の型は呼び出し側で決まるため、引数と戻り値は型パラメータで登録する。
+const FUNCTIONS: &[(&str, &[&str], &str)] = &[
+    ("Option::some", &["value"], "値を持つ `Some(value)` を作成します。"),
+    ("Option::none", &[], "値を持たない `None` を作成します。"),
+    ("Option::is_some", &["option"], "`Some` ならtrueを返します。"),
+    ("Option::is_none", &["option"], "`None` ならtrueを返します。"),
+    ("Option::unwrap", &["option"], "`Some` の中の値を返します。`None` なら実行時エラーになります。"),
+    ("Option::unwrap_or", &["option", "default"], "`Some` の中の値を返し、`None` なら `default` を返します。"),
+    ("Result::ok", &["value"], "成功を表す `Ok(value)` を作成します。"),
+    ("Result::err", &["error"], "失敗を表す `Err(error)` を作成します。"),
+    ("Result::is_ok", &["result"], "`Ok` ならtrueを返します。"),
+    ("Result::is_err", &["result"], "`Err` ならtrueを返します。"),
+    ("Result::unwrap", &["result"], "`Ok` の中の値を返します。`Err` なら実行時エラーになります。"),
+    ("Result::unwrap_err", &["result"], "`Err` の中の値を返します。`Ok` なら実行時エラーになります。"),
+    ("Result::unwrap_or", &["result", "default"], "`Ok` の中の値を返し、`Err` なら `default` を返します。"),
+];
+
+/// `Option` と `Result` のモジュールの初期化
+pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
+    let value_type = Type::new(TypeKind::TypeParam { name: "T".to_string() });
+    
+    for (name, params, description) in FUNCTIONS {
+        registry.register_function(StdlibFunction::new(
+            name,
+            StdlibModule::Option,
+            StdlibFunctionType::Pure,
+            params.iter().map(|param| (param.to_string(), value_type.id)).collect(),
+            value_type.id,
+            description,
+        ));
+    }
+    
+    Ok(())
+}
+
+/// `Option` と `Result` の関数を実行（関数名は `Option::some` のような形式）
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    let expected = FUNCTIONS
+        .iter()
+        .find(|(name, ..)| *name == function_name)
+        .map(|(_, params, _)| params.len())
+        .ok_or_else(|| EidosError::Runtime(format!("不明な関数です: {}", function_name)))?;
+    if args.len() != expected {
+        return Err(EidosError::Runtime(format!(
+            "{}の引数は{}個ですが、{}個渡されました",
+            function_name,
+            expected,
+            args.len()
+        )));
+    }
+    
+    match function_name {
+        "Option::some" => Ok(Value::Option(Some(Box::new(args[0].clone())))),
+        "Option::none" => Ok(Value::Option(None)),
+        "Option::is_some" => Ok(Value::Bool(args[0].as_option()?.is_some())),
+        "Option::is_none" => Ok(Value::Bool(args[0].as_option()?.is_none())),
+        "Option::unwrap" => args[0]
+            .as_option()?
+            .cloned()
+            .ok_or_else(|| EidosError::Runtime("`None` の値に対して Option::unwrap が呼び出されました".to_string())),
+        "Option::unwrap_or" => Ok(args[0].as_option()?.unwrap_or(&args[1]).clone()),
+        "Result::ok" => Ok(Value::Result(Ok(Box::new(args[0].clone())))),
+        "Result::err" => Ok(Value::Result(Err(Box::new(args[0].clone())))),
+        "Result::is_ok" => Ok(Value::Bool(args[0].as_result()?.is_ok())),
+        "Result::is_err" => Ok(Value::Bool(args[0].as_result()?.is_err())),
+        "Result::unwrap" => match args[0].as_result()? {
+            Ok(value) => Ok(value.clone()),
+            Err(error) => Err(EidosError::Runtime(format!(
+                "`Err({})` の値に対して Result::unwrap が呼び出されました",
+                error
+            ))),
+        },
+        "Result::unwrap_err" => match args[0].as_result()? {
+            Ok(value) => Err(EidosError::Runtime(format!(
+                "`Ok({})` の値に対して Result::unwrap_err が呼び出されました",
+                value
+            ))),
+            Err(error) => Ok(error.clone()),
+        },
+        "Result::unwrap_or" => Ok(args[0].as_result()?.unwrap_or(&args[1]).clone()),
+        _ => Err(EidosError::Runtime(format!("不明な関数です: {}", function_name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn some(value: i64) -> Value {
+        Value::Option(Some(Box::new(Value::Int(value))))
+    }
+    
+    #[test]
+    fn test_option_and_result_functions() {
+        assert_eq!(execute_function("Option::some", &[Value::Int(1)]).unwrap(), some(1));
+        assert_eq!(execute_function("Option::unwrap", &[some(2)]).unwrap(), Value::Int(2));
+        assert_eq!(
+            execute_function("Option::unwrap_or", &[Value::Option(None), Value::Int(7)]).unwrap(),
+            Value::Int(7)
+        );
+        assert!(execute_function("Option::unwrap", &[Value::Option(None)]).is_err());
+        
+        let error = execute_function("Result::err", &[Value::from("bad")]).unwrap();
+        assert_eq!(execute_function("Result::is_err", std::slice::from_ref(&error)).unwrap(), Value::Bool(true));
+        assert_eq!(execute_function("Result::unwrap_err", std::slice::from_ref(&error)).unwrap(), Value::from("bad"));
+        assert!(execute_function("Result::unwrap", &[error]).unwrap_err().to_string().contains("Err(bad)"));
+        
+        // 種類の違う値は変換エラーになる
+        assert!(execute_function("Option::is_some", &[Value::Result(Ok(Box::new(Value::Unit)))]).is_err());
+        assert!(execute_function("Option::some", &[]).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use libloading::Library;
+use serde_json::{json, Value as Json};
+
+use crate::core::{Result, EidosError};
+use crate::core::types::Type;
+use crate::stdlib::{StdlibRegistry, StdlibSignature, Value};
+
+/// プラグインが公開する関数
+pub type PluginFunction = unsafe extern "C" fn(args_json: *const c_char) -> *mut c_char;
+/// `PluginFunction` が返した文字列を解放する関数
+pub type PluginFree = unsafe extern "C" fn(result: *mut c_char);
+/// ホストが渡す登録用のコールバック
+pub type PluginRegister = unsafe extern "C" fn(
+    host: *mut c_void,
+    name: *const c_char,
+    signature: *const c_char,
+    function: PluginFunction,
+) -> c_int;
+/// プラグインのエントリポイント
+///
+/// プラグインはC ABIで次の2つの関数を公開する。値はJSONでやり取りするので、Rust以外の言語でも書ける。
+///
+/// ```c
+/// typedef char *(*eidos_fn)(const char *args_json);
+/// typedef int (*eidos_register_fn)(void *host, const char *name, const char *signature, eidos_fn function);
+///
+/// // 関数を登録する。失敗したら0以外を返す
+/// int eidos_plugin_register(void *host, eidos_register_fn register_fn);
+/// // eidos_fn が返した文字列を解放する
+/// void eidos_plugin_free(char *result);
+/// ```
+///
+/// `eidos_fn` は引数をJSON配列で受け取り、`{"ok": 値}` か `{"error": "メッセージ"}` を返す。
+pub type PluginEntry = unsafe extern "C" fn(host: *mut c_void, register: PluginRegister) -> c_int;
+
+const ENTRY_SYMBOL: &[u8] = b"eidos_plugin_register\0";
+const FREE_SYMBOL: &[u8] = b"eidos_plugin_free\0";
+
+lazy_static! {
+    /// 読み込んだライブラリ（登録した関数が使われる間は解放しない）
+    static ref LOADED_PLUGINS: Mutex<Vec<Library>> = Mutex::new(Vec::new());
+}
+
+/// 登録コールバックに渡す、プラグイン1つ分の登録結果
+struct PluginHost {
+    functions: Vec<(String, StdlibSignature, PluginFunction)>,
+    error: Option<String>,
+}
+
+/// プラグインを順に読み込み、グローバルな標準ライブラリレジストリに関数を登録する
+pub fn load_plugins(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        load_plugin(path)?;
+    }
+    Ok(())
+}
+
+/// プラグインを1つ読み込む
+pub fn load_plugin(path: &Path) -> Result<()> {
+    let plugin_error = |message: String| {
+        EidosError::Runtime(format!("プラグイン '{}' を読み込めません: {}", path.display(), message))
+    };
+    
+    // SAFETY: 読み込むライブラリの初期化処理はプラグインの作者を信頼する
+    let library = unsafe { Library::new(path) }.map_err(|e| plugin_error(e.to_string()))?;
+    let (entry, free) = unsafe {
+        let entry = *library.get::<PluginEntry>(ENTRY_SYMBOL).map_err(|e| plugin_error(e.to_string()))?;
+        let free = *library.get::<PluginFree>(FREE_SYMBOL).map_err(|e| plugin_error(e.to_string()))?;
+        (entry, free)
+    };
+    
+    let mut host = PluginHost { functions: Vec::new(), error: None };
+    let status = unsafe { entry(&mut host as *mut PluginHost as *mut c_void, register_callback) };
+    if let Some(error) = host.error {
+        return Err(plugin_error(error));
+    }
+    if status != 0 {
+        return Err(plugin_error(format!("eidos_plugin_register が {} を返しました", status)));
+    }
+    
+    let registry = StdlibRegistry::global();
+    let mut registry = registry.write().unwrap();
+    for (name, signature, function) in host.functions {
+        let function_name = name.clone();
+        registry.register_native(&name, signature, move |args| call_plugin_function(&function_name, function, free, args))?;
+    }
+    LOADED_PLUGINS.lock().unwrap().push(library);
+    Ok(())
+}
+
+/// プラグインから呼ばれる登録コールバック
+unsafe extern "C" fn register_callback(
+    host: *mut c_void,
+    name: *const c_char,
+    signature: *const c_char,
+    function: PluginFunction,
+) -> c_int {
+    let host = &mut *(host as *mut PluginHost);
+    if name.is_null() || signature.is_null() {
+        host.error = Some("関数名とシグネチャにはNULLを渡せません".to_string());
+        return 1;
+    }
+    let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+    let signature = CStr::from_ptr(signature).to_string_lossy();
+    match parse_signature(&signature) {
+        Ok(signature) => {
+            host.functions.push((name, signature, function));
+            0
+        }
+        Err(message) => {
+            host.error = Some(format!("{}: {}", name, message));
+            1
+        }
+    }
+}
+
+/// プラグインの関数を呼び出す
+fn call_plugin_function(name: &str, function: PluginFunction, free: PluginFree, args: &[Value]) -> Result<Value> {
+    let runtime_error = |message: String| EidosError::Runtime(format!("{}: {}", name, message));
+    let args_json = CString::new(to_json(&Value::List(args.to_vec())).to_string())
+        .map_err(|_| runtime_error("引数にNUL文字を含めることはできません".to_string()))?;
+    
+    let result = unsafe { function(args_json.as_ptr()) };
+    if result.is_null() {
+        return Err(runtime_error("プラグインが結果を返しませんでした".to_string()));
+    }
+    let text = unsafe { CStr::from_ptr(result) }.to_string_lossy().into_owned();
+    unsafe { free(result) };
+    
+    let response: Json = serde_json::from_str(&text)
+        .map_err(|e| runtime_error(format!("プラグインの結果がJSONではありません: {}", e)))?;
+    match (response.get("ok"), response.get("error")) {
+        (Some(value), None) => from_json(value).map_err(runtime_error),
+        (None, Some(Json::String(message))) => Err(runtime_error(message.clone())),
+        _ => Err(runtime_error(format!("プラグインの結果の形式が不正です: {}", text))),
+    }
+}
+
+/// `(Int, String) -> Bool` 形式のシグネチャを解析する
+fn parse_signature(signature: &str) -> std::result::Result<StdlibSignature, String> {
+    let invalid = || format!("シグネチャは `(引数の型, ...) -> 戻り値の型` の形式が必要です: {}", signature);
+    let (params, return_type) = signature.split_once("->").ok_or_else(invalid)?;
+    let params = params
+        .trim()
+        .strip_prefix('(')
+        .and_then(|params| params.strip_suffix(')'))
+        .ok_or_else(invalid)?;
+    let params = if params.trim().is_empty() {
+        Vec::new()
+    } else {
+        params.split(',').map(parse_type).collect::<std::result::Result<_, _>>()?
+    };
+    Ok(StdlibSignature { params, return_type: parse_type(return_type)? })
+}
+
+fn parse_type(name: &str) -> std::result::Result<Type, String> {
+    match name.trim() {
+        "Int" => Ok(Type::int()),
+        "Float" => Ok(Type::float()),
+        "Bool" => Ok(Type::bool()),
+        "String" => Ok(Type::string()),
+        "Unit" => Ok(Type::unit()),
+        other => Err(format!("プラグインの関数で使えない型です: {}", other)),
+    }
+}
+
+/// 値をJSONに変換する（マップはキーと値の組の配列になる）
+fn to_json(value: &Value) -> Json {
+    match value {
+        Value::Int(i) => json!(i),
+        Value::Float(f) => json!(f),
+        Value::Bool(b) => json!(b),
+        Value::Str(s) => json!(s),
+        Value::List(items) => Json::Array(items.iter().map(to_json).collect()),
+        Value::Map(entries) => Json::Array(entries.iter().map(|(k, v)| json!([to_json(k), to_json(v)])).collect()),
+        Value::Option(Some(value)) => to_json(value),
+        Value::Option(None) => Json::Null,
+        Value::Result(Ok(value)) => json!({ "Ok": to_json(value) }),
+        Value::Result(Err(error)) => json!({ "Err": to_json(error) }),
+        Value::Unit => Json::Null,
+    }
+}
+
+fn from_json(json: &Json) -> std::result::Result<Value, String> {
+    Ok(match json {
+        Json::Null => Value::Unit,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::Float(n.as_f64().ok_or_else(|| format!("表現できない数値です: {}", n))?),
+        },
+        Json::String(s) => Value::Str(s.clone()),
+        Json::Array(items) => Value::List(items.iter().map(from_json).collect::<std::result::Result<_, _>>()?),
+        Json::Object(entries) => Value::Map(
+            entries
+                .iter()
+                .map(|(k, v)| Ok((Value::Str(k.clone()), from_json(v)?)))
+                .collect::<std::result::Result<_, String>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::TypeKind;
+    
+    #[test]
+    fn test_parse_signature() {
+        let signature = parse_signature("(Int, String) -> Bool").unwrap();
+        let params: Vec<&TypeKind> = signature.params.iter().map(|param| &param.kind).collect();
+        assert_eq!(params, [&TypeKind::Int, &TypeKind::String]);
+        assert_eq!(signature.return_type.kind, TypeKind::Bool);
+        assert!(parse_signature("() -> Unit").unwrap().params.is_empty());
+        
+        assert!(parse_signature("Int -> Int").is_err());
+        assert!(parse_signature("(Socket) -> Int").is_err());
+    }
+    
+    #[test]
+    fn test_json_round_trip() {
+        let value = Value::List(vec![
+            Value::Int(1),
+            Value::Float(2.5),
+            Value::from("three"),
+            Value::Map(vec![(Value::from("k"), Value::Bool(true))]),
+            Value::Unit,
+        ]);
+        assert_eq!(from_json(&to_json(&value)).unwrap(), Value::List(vec![
+            Value::Int(1),
+            Value::Float(2.5),
+            Value::from("three"),
+            Value::List(vec![Value::List(vec![Value::from("k"), Value::Bool(true)])]),
+            Value::Unit,
+        ]));
+    }
+    
+    #[test]
+    fn test_register_native() {
+        let mut registry = StdlibRegistry::new();
+        let signature = parse_signature("(Int, Int) -> Int").unwrap();
+        registry
+            .register_native("host::add", signature.clone(), |args| Ok(Value::Int(args[0].as_int()? + args[1].as_int()?)))
+            .unwrap();
+        
+        let result = registry.execute_function("host::add", &[Value::Int(2), Value::Int(3)]).unwrap();
+        assert_eq!(result, Value::Int(5));
+        assert!(registry.resolve_overload("host::add", &[Type::int(), Type::int()]).is_ok());
+        
+        assert!(registry.register_native("host::add", signature.clone(), |_| Ok(Value::Unit)).is_err());
+        assert!(registry.register_native("math::add", signature.clone(), |_| Ok(Value::Unit)).is_err());
+        assert!(registry.register_native("add", signature, |_| Ok(Value::Unit)).is_err());
+    }
+}
@@ -0,0 +1,361 @@
+use crate::core::{Result, EidosError};
+use crate::core::types::{Type, TypeKind, Field};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
+use std::collections::HashMap;
+use std::sync::{Mutex, atomic::{AtomicUsize, Ordering}};
+use lazy_static::lazy_static;
+use ::regex::Regex;
+use regex_automata::dfa::{dense, Automaton};
+use regex_automata::util::primitives::StateID;
+use regex_automata::util::start;
+
+/// DFAテーブルの最大状態数（これを超えるパターンはテーブルにしない）
+const MAX_DFA_STATES: usize = 4096;
+
+lazy_static! {
+    static ref REGEX_INSTANCES: Mutex<HashMap<String, Regex>> = Mutex::new(HashMap::new());
+    static ref INSTANCE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+}
+
+/// 正規表現モジュールの初期化
+pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
+    // 基本型の登録
+    let bool_type = Type::bool();
+    let string_type = Type::string();
+    let string_array_type = Type::array(string_type.clone());
+    
+    // Regex型の定義
+    let regex_type = Type::new(
+        TypeKind::Struct {
+            name: "Regex".to_string(),
+            fields: vec![
+                Field {
+                    name: "pattern".to_string(),
+                    field_type: string_type.clone(),
+                    is_public: true,
+                },
+            ],
+            methods: vec![],
+            is_extern: false,
+        },
+    );
+    registry.register_type("regex::Regex", regex_type.clone());
+    
+    // regex::compile - パターンをコンパイル
+    registry.register_function(StdlibFunction::new(
+        "compile",
+        StdlibModule::Regex,
+        StdlibFunctionType::Pure,
+        vec![("pattern".to_string(), string_type.id)],
+        regex_type.id,
+        "正規表現パターンをコンパイルします。構文が不正な場合はエラーになります。",
+    ));
+    
+    // regex::is_match - マッチするかを判定
+    registry.register_function(StdlibFunction::new(
+        "is_match",
+        StdlibModule::Regex,
+        StdlibFunctionType::Pure,
+        vec![
+            ("regex".to_string(), regex_type.id),
+            ("text".to_string(), string_type.id),
+        ],
+        bool_type.id,
+        "文字列のどこかが正規表現にマッチするかどうかを返します。",
+    ));
+    
+    // regex::captures - キャプチャグループを取得
+    registry.register_function(StdlibFunction::new(
+        "captures",
+        StdlibModule::Regex,
+        StdlibFunctionType::Pure,
+        vec![
+            ("regex".to_string(), regex_type.id),
+            ("text".to_string(), string_type.id),
+        ],
+        string_array_type.id,
+        "最初のマッチの全体とキャプチャグループを順に返します。マッチしない場合は空の配列、参加しなかったグループは空文字列になります。",
+    ));
+    
+    // regex::replace_all - マッチ箇所をすべて置換
+    registry.register_function(StdlibFunction::new(
+        "replace_all",
+        StdlibModule::Regex,
+        StdlibFunctionType::Pure,
+        vec![
+            ("regex".to_string(), regex_type.id),
+            ("text".to_string(), string_type.id),
+            ("replacement".to_string(), string_type.id),
+        ],
+        string_type.id,
+        "マッチした箇所をすべて置換します。置換文字列では $1 や ${name} でキャプチャグループを参照できます。",
+    ));
+    
+    Ok(())
+}
+
+/// 正規表現関数の実行
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    match function_name {
+        "compile" => {
+            let [pattern] = string_args(function_name, args)?;
+            let regex = Regex::new(pattern)
+                .map_err(|e| EidosError::Runtime(format!("正規表現のコンパイルに失敗しました: {}", e)))?;
+            let id = generate_instance_id("regex");
+            REGEX_INSTANCES.lock().unwrap().insert(id.clone(), regex);
+            Ok(Value::Str(id))
+        }
+        "is_match" => {
+            let [id, text] = string_args(function_name, args)?;
+            let regex = get_regex(id)?;
+            Ok(Value::Bool(regex.is_match(text)))
+        }
+        "captures" => {
+            let [id, text] = string_args(function_name, args)?;
+            let regex = get_regex(id)?;
+            let groups: Vec<&str> = match regex.captures(text) {
+                Some(captures) => captures
+                    .iter()
+                    .map(|group| group.map_or("", |m| m.as_str()))
+                    .collect(),
+                None => Vec::new(),
+            };
+            Ok(Value::from(groups))
+        }
+        "replace_all" => {
+            let [id, text, replacement] = string_args(function_name, args)?;
+            let regex = get_regex(id)?;
+            Ok(Value::Str(regex.replace_all(text, replacement).to_string()))
+        }
+        _ => Err(EidosError::Runtime(format!("不明な正規表現関数: {}", function_name)))
+    }
+}
+
+/// 新しい正規表現のIDを生成
+fn generate_instance_id(prefix: &str) -> String {
+    let id = INSTANCE_COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("{}:{}", prefix, id)
+}
+
+/// コンパイル済みの正規表現を取得（`Regex` は内部で参照カウントされるので複製は安価）
+fn get_regex(id: &str) -> Result<Regex> {
+    REGEX_INSTANCES
+        .lock()
+        .unwrap()
+        .get(id)
+        .cloned()
+        .ok_or_else(|| EidosError::Runtime(format!("無効な正規表現参照: {}", id)))
+}
+
+/// 引数の数を確かめ、すべて文字列として取り出す
+fn string_args<'a, const N: usize>(function_name: &str, args: &'a [Value]) -> Result<[&'a str; N]> {
+    if args.len() != N {
+        return Err(EidosError::Runtime(format!(
+            "regex::{}関数は{}つの引数が必要ですが、{}個の引数が渡されました。",
+            function_name,
+            N,
+            args.len()
+        )));
+    }
+    let mut strings = [""; N];
+    for (string, arg) in strings.iter_mut().zip(args) {
+        *string = arg.as_str()?;
+    }
+    Ok(strings)
+}
+
+/// 状態のフラグ: この状態に入った時点で、直前までの入力にマッチが見つかっている
+pub const DFA_MATCH: u8 = 0b001;
+/// 状態のフラグ: この状態からはもうマッチしない
+pub const DFA_DEAD: u8 = 0b010;
+/// 状態のフラグ: ここで入力が終わればマッチする
+pub const DFA_EOI_MATCH: u8 = 0b100;
+
+/// `is_match` 用にコンパイルした、ランタイムを持たない環境（wasmなど）でも使えるDFAの遷移表
+///
+/// 照合は次の手順だけで行えるので、wasmのモジュールやJavaScriptのホストにデータとして埋め込める。
+///
+/// ```text
+/// state = start
+/// for byte in input:
+///     state = transitions[state * 256 + byte]
+///     if flags[state] & MATCH: return true
+///     if flags[state] & DEAD:  return false
+/// return flags[state] & EOI_MATCH != 0
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DfaTable {
+    /// 開始状態
+    pub start: u32,
+    /// 状態ごとのフラグ（`DFA_MATCH` / `DFA_DEAD` / `DFA_EOI_MATCH`）
+    pub flags: Vec<u8>,
+    /// 状態ごとに256個ずつ並べた遷移先
+    pub transitions: Vec<u32>,
+}
+
+impl DfaTable {
+    /// シリアライズしたテーブルの先頭に置く識別子
+    pub const MAGIC: &'static [u8; 4] = b"EDFA";
+    /// シリアライズ形式のバージョン
+    pub const VERSION: u32 = 1;
+    
+    /// パターンから遷移表を作成（`is_match` と同じく、文字列のどこかにマッチすれば受理する）
+    pub fn compile(pattern: &str) -> Result<Self> {
+        let dfa_error = |message: String| {
+            EidosError::Runtime(format!("正規表現 '{}' をDFAに変換できません: {}", pattern, message))
+        };
+        let dfa = dense::DFA::new(pattern).map_err(|e| dfa_error(e.to_string()))?;
+        let start = dfa
+            .start_state(&start::Config::new())
+            .map_err(|e| dfa_error(e.to_string()))?;
+        
+        // 開始状態から到達できる状態だけを番号を振り直して並べる
+        let mut ids: HashMap<StateID, u32> = HashMap::new();
+        let mut order = vec![start];
+        ids.insert(start, 0);
+        let mut table = DfaTable { start: 0, flags: Vec::new(), transitions: Vec::new() };
+        let mut index = 0;
+        while index < order.len() {
+            let state = order[index];
+            index += 1;
+            if dfa.is_quit_state(state) {
+                return Err(dfa_error("非ASCIIの単語境界など、DFAで扱えない構文が含まれています".to_string()));
+            }
+            
+            let mut flags = 0;
+            if dfa.is_match_state(state) {
+                flags |= DFA_MATCH;
+            }
+            if dfa.is_dead_state(state) {
+                flags |= DFA_DEAD;
+            }
+            if dfa.is_match_state(dfa.next_eoi_state(state)) {
+                flags |= DFA_EOI_MATCH;
+            }
+            table.flags.push(flags);
+            
+            for byte in 0..=255u8 {
+                let next = dfa.next_state(state, byte);
+                let next_index = match ids.get(&next) {
+                    Some(id) => *id,
+                    None => {
+                        let id = order.len() as u32;
+                        if order.len() >= MAX_DFA_STATES {
+                            return Err(dfa_error(format!("状態数が上限（{}）を超えました", MAX_DFA_STATES)));
+                        }
+                        ids.insert(next, id);
+                        order.push(next);
+                        id
+                    }
+                };
+                table.transitions.push(next_index);
+            }
+        }
+        Ok(table)
+    }
+    
+    /// 状態数
+    pub fn state_count(&self) -> usize {
+        self.flags.len()
+    }
+    
+    /// 入力のどこかがマッチするか（wasm側で行う照合と同じ手順）
+    pub fn is_match(&self, input: &[u8]) -> bool {
+        let mut state = self.start as usize;
+        for &byte in input {
+            state = self.transitions[state * 256 + byte as usize] as usize;
+            if self.flags[state] & DFA_MATCH != 0 {
+                return true;
+            }
+            if self.flags[state] & DFA_DEAD != 0 {
+                return false;
+            }
+        }
+        self.flags[state] & DFA_EOI_MATCH != 0
+    }
+    
+    /// wasmのデータセグメントに置ける形式にシリアライズする（すべてリトルエンディアン）
+    ///
+    /// `MAGIC`、`VERSION: u32`、状態数 `u32`、開始状態 `u32`、フラグ（状態数バイト、4バイト境界まで0で埋める）、
+    /// 遷移表（状態数 × 256 個の `u32`）の順に並べる。
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let padding = (4 - self.flags.len() % 4) % 4;
+        let mut bytes = Vec::with_capacity(16 + self.flags.len() + padding + self.transitions.len() * 4);
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&Self::VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.flags.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.start.to_le_bytes());
+        bytes.extend_from_slice(&self.flags);
+        bytes.resize(bytes.len() + padding, 0);
+        for transition in &self.transitions {
+            bytes.extend_from_slice(&transition.to_le_bytes());
+        }
+        bytes
+    }
+    
+    /// `to_bytes` の形式から復元する
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let invalid = || EidosError::Runtime("DFAテーブルの形式が不正です".to_string());
+        let read_u32 = |offset: usize| -> Result<u32> {
+            let word = bytes.get(offset..offset + 4).ok_or_else(invalid)?;
+            Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        };
+        if bytes.get(..4) != Some(&Self::MAGIC[..]) || read_u32(4)? != Self::VERSION {
+            return Err(invalid());
+        }
+        let states = read_u32(8)? as usize;
+        let start = read_u32(12)?;
+        let flags = bytes.get(16..16 + states).ok_or_else(invalid)?.to_vec();
+        let table_start = 16 + states + (4 - states % 4) % 4;
+        let transitions = (0..states * 256)
+            .map(|i| read_u32(table_start + i * 4))
+            .collect::<Result<Vec<_>>>()?;
+        if start as usize >= states || transitions.iter().any(|&next| next as usize >= states) {
+            return Err(invalid());
+        }
+        Ok(Self { start, flags, transitions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn call(name: &str, args: &[&str]) -> Result<String> {
+        let args: Vec<Value> = args.iter().map(|&arg| Value::from(arg)).collect();
+        execute_function(name, &args).map(|value| value.to_string())
+    }
+    
+    #[test]
+    fn test_regex_functions() {
+        let date = call("compile", &[r"(\d{4})-(\d{2})(?:-(\d{2}))?"]).unwrap();
+        assert_eq!(call("is_match", &[&date, "released 2024-05"]).unwrap(), "true");
+        assert_eq!(call("is_match", &[&date, "no date"]).unwrap(), "false");
+        assert_eq!(call("captures", &[&date, "released 2024-05"]).unwrap(), "[2024-05, 2024, 05, ]");
+        assert_eq!(call("captures", &[&date, "no date"]).unwrap(), "[]");
+        assert_eq!(call("replace_all", &[&date, "2024-05, 1999-12", "$2/$1"]).unwrap(), "05/2024, 12/1999");
+        
+        assert!(call("compile", &["(unclosed"]).is_err());
+        assert!(call("is_match", &["regex:unknown", "text"]).is_err());
+    }
+    
+    #[test]
+    fn test_dfa_table_agrees_with_regex() {
+        let cases: &[(&str, &[&str])] = &[
+            (r"ab+c", &["abc", "xxabbbcx", "ac", "ab", ""]),
+            (r"^[a-z]+$", &["hello", "Hello", "", "abc1"]),
+            (r"\d{3}-\d{4}$", &["call 555-1234", "555-12345", "555-123"]),
+            (r"", &["", "anything"]),
+            (r"é|ß", &["café", "strasse", "straße"]),
+        ];
+        for (pattern, inputs) in cases {
+            let regex = Regex::new(pattern).unwrap();
+            let table = DfaTable::compile(pattern).unwrap();
+            let restored = DfaTable::from_bytes(&table.to_bytes()).unwrap();
+            assert_eq!(restored, table);
+            for input in *inputs {
+                assert_eq!(table.is_match(input.as_bytes()), regex.is_match(input), "{:?} on {:?}", pattern, input);
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::core::{Result, EidosError};
 use crate::core::types::{Type, TypeId};
-use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
 
 /// 文字列モジュールの初期化
 pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
@@ -321,8 +321,20 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     Ok(())
 }
 
-/// 標準ライブラリ文字列関数の実装
-pub fn execute_function(function_name: &str, args: &[String]) -> Result<String> {
+/// 文字列関数の実行（戻り値の型ごとの実装に振り分ける）
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    match function_name {
+        "is_empty" | "contains" | "starts_with" | "ends_with" | "is_digit" | "is_alpha" | "is_alnum"
+        | "is_whitespace" => execute_bool_function(function_name, args).map(Value::Bool),
+        "length" | "index_of" | "last_index_of" | "char_at" => execute_int_function(function_name, args).map(Value::Int),
+        "split" => execute_string_array_function(function_name, args).map(Value::from),
+        "at" => execute_char_function(function_name, args).map(|ch| Value::Str(ch.to_string())),
+        _ => execute_string_function(function_name, args).map(Value::Str),
+    }
+}
+
+/// 文字列関数の実装（文字列を返すもの）
+pub fn execute_string_function(function_name: &str, args: &[Value]) -> Result<String> {
     match function_name {
         "concat" => {
             if args.len() != 2 {
@@ -330,8 +342,8 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "concat関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let mut result = args[0].clone();
-            result.push_str(&args[1]);
+            let mut result = args[0].as_str()?.to_string();
+            result.push_str(args[1].as_str()?);
             Ok(result)
         },
         "substr" => {
@@ -340,13 +352,9 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "substr関数は3つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let start: usize = args[1].parse().map_err(|_| {
-                EidosError::Runtime("startパラメータを整数に変換できません".to_string())
-            })?;
-            let length: usize = args[2].parse().map_err(|_| {
-                EidosError::Runtime("lengthパラメータを整数に変換できません".to_string())
-            })?;
+            let str = args[0].as_str()?;
+            let start: usize = args[1].as_usize()?;
+            let length: usize = args[2].as_usize()?;
             
             let chars: Vec<char> = str.chars().collect();
             if start >= chars.len() {
@@ -363,7 +371,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "to_upper関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].to_uppercase())
+            Ok(args[0].as_str()?.to_uppercase())
         },
         "to_lower" => {
             if args.len() != 1 {
@@ -371,7 +379,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "to_lower関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].to_lowercase())
+            Ok(args[0].as_str()?.to_lowercase())
         },
         "trim" => {
             if args.len() != 1 {
@@ -379,7 +387,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "trim関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].trim().to_string())
+            Ok(args[0].as_str()?.trim().to_string())
         },
         "trim_start" => {
             if args.len() != 1 {
@@ -387,7 +395,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "trim_start関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].trim_start().to_string())
+            Ok(args[0].as_str()?.trim_start().to_string())
         },
         "trim_end" => {
             if args.len() != 1 {
@@ -395,7 +403,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "trim_end関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].trim_end().to_string())
+            Ok(args[0].as_str()?.trim_end().to_string())
         },
         "replace" => {
             if args.len() != 3 {
@@ -403,7 +411,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "replace関数は3つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].replace(&args[1], &args[2]))
+            Ok(args[0].as_str()?.replace(args[1].as_str()?, args[2].as_str()?))
         },
         "replace_first" => {
             if args.len() != 3 {
@@ -411,16 +419,16 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "replace_first関数は3つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let from = &args[1];
-            let to = &args[2];
+            let str = args[0].as_str()?;
+            let from = args[1].as_str()?;
+            let to = args[2].as_str()?;
             
             if let Some(pos) = str.find(from) {
-                let mut result = str.clone();
+                let mut result = str.to_string();
                 result.replace_range(pos..pos + from.len(), to);
                 Ok(result)
             } else {
-                Ok(str.clone())
+                Ok(str.to_string())
             }
         },
         "repeat" => {
@@ -429,10 +437,8 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "repeat関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let count: usize = args[1].parse().map_err(|_| {
-                EidosError::Runtime("countパラメータを整数に変換できません".to_string())
-            })?;
+            let str = args[0].as_str()?;
+            let count: usize = args[1].as_usize()?;
             
             Ok(str.repeat(count))
         },
@@ -442,23 +448,34 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     "from_char_code関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let code: u32 = args[0].parse().map_err(|_| {
-                EidosError::Runtime("codeパラメータを整数に変換できません".to_string())
-            })?;
+            let code = args[0].as_int()?;
             
-            if let Some(ch) = std::char::from_u32(code) {
+            if let Some(ch) = u32::try_from(code).ok().and_then(std::char::from_u32) {
                 Ok(ch.to_string())
             } else {
                 Err(EidosError::Runtime(format!("無効なユニコードコードポイント: {}", code)))
             }
         },
+        "join" => {
+            if args.len() != 2 {
+                return Err(EidosError::Runtime(format!(
+                    "join関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
+                )));
+            }
+            let parts = args[0]
+                .as_list()?
+                .iter()
+                .map(Value::as_str)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(parts.join(args[1].as_str()?))
+        },
         // 他の文字列関数はランタイムシステムで提供
         _ => Err(EidosError::Runtime(format!("未実装の文字列関数: {}", function_name))),
     }
 }
 
 /// 文字列関数の実装（ブール値を返すもの）
-pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<bool> {
+pub fn execute_bool_function(function_name: &str, args: &[Value]) -> Result<bool> {
     match function_name {
         "is_empty" => {
             if args.len() != 1 {
@@ -466,7 +483,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "is_empty関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].is_empty())
+            Ok(args[0].as_str()?.is_empty())
         },
         "contains" => {
             if args.len() != 2 {
@@ -474,7 +491,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "contains関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].contains(&args[1]))
+            Ok(args[0].as_str()?.contains(args[1].as_str()?))
         },
         "starts_with" => {
             if args.len() != 2 {
@@ -482,7 +499,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "starts_with関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].starts_with(&args[1]))
+            Ok(args[0].as_str()?.starts_with(args[1].as_str()?))
         },
         "ends_with" => {
             if args.len() != 2 {
@@ -490,7 +507,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "ends_with関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].ends_with(&args[1]))
+            Ok(args[0].as_str()?.ends_with(args[1].as_str()?))
         },
         "is_digit" => {
             if args.len() != 1 {
@@ -498,7 +515,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "is_digit関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].chars().all(|c| c.is_digit(10)))
+            Ok(args[0].as_str()?.chars().all(|c| c.is_digit(10)))
         },
         "is_alpha" => {
             if args.len() != 1 {
@@ -506,7 +523,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "is_alpha関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].chars().all(|c| c.is_alphabetic()))
+            Ok(args[0].as_str()?.chars().all(|c| c.is_alphabetic()))
         },
         "is_alnum" => {
             if args.len() != 1 {
@@ -514,7 +531,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "is_alnum関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].chars().all(|c| c.is_alphanumeric()))
+            Ok(args[0].as_str()?.chars().all(|c| c.is_alphanumeric()))
         },
         "is_whitespace" => {
             if args.len() != 1 {
@@ -522,7 +539,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
                     "is_whitespace関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].chars().all(|c| c.is_whitespace()))
+            Ok(args[0].as_str()?.chars().all(|c| c.is_whitespace()))
         },
         // 他の文字列関数はランタイムシステムで提供
         _ => Err(EidosError::Runtime(format!("未実装の文字列関数: {}", function_name))),
@@ -530,7 +547,7 @@ pub fn execute_bool_function(function_name: &str, args: &[String]) -> Result<boo
 }
 
 /// 文字列関数の実装（整数値を返すもの）
-pub fn execute_int_function(function_name: &str, args: &[String]) -> Result<i64> {
+pub fn execute_int_function(function_name: &str, args: &[Value]) -> Result<i64> {
     match function_name {
         "length" => {
             if args.len() != 1 {
@@ -538,7 +555,7 @@ pub fn execute_int_function(function_name: &str, args: &[String]) -> Result<i64>
                     "length関数は1つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            Ok(args[0].chars().count() as i64)
+            Ok(args[0].as_str()?.chars().count() as i64)
         },
         "index_of" => {
             if args.len() != 2 {
@@ -546,8 +563,8 @@ pub fn execute_int_function(function_name: &str, args: &[String]) -> Result<i64>
                     "index_of関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let substr = &args[1];
+            let str = args[0].as_str()?;
+            let substr = args[1].as_str()?;
             
             // Unicodeのコードポイントを考慮した位置を計算
             if let Some(byte_pos) = str.find(substr) {
@@ -563,8 +580,8 @@ pub fn execute_int_function(function_name: &str, args: &[String]) -> Result<i64>
                     "last_index_of関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let substr = &args[1];
+            let str = args[0].as_str()?;
+            let substr = args[1].as_str()?;
             
             // Unicodeのコードポイントを考慮した位置を計算
             if let Some(byte_pos) = str.rfind(substr) {
@@ -580,10 +597,8 @@ pub fn execute_int_function(function_name: &str, args: &[String]) -> Result<i64>
                     "char_at関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let index: usize = args[1].parse().map_err(|_| {
-                EidosError::Runtime("indexパラメータを整数に変換できません".to_string())
-            })?;
+            let str = args[0].as_str()?;
+            let index: usize = args[1].as_usize()?;
             
             let chars: Vec<char> = str.chars().collect();
             if index < chars.len() {
@@ -598,7 +613,7 @@ pub fn execute_int_function(function_name: &str, args: &[String]) -> Result<i64>
 }
 
 /// 文字列関数の実装（文字列配列を返すもの）
-pub fn execute_string_array_function(function_name: &str, args: &[String]) -> Result<Vec<String>> {
+pub fn execute_string_array_function(function_name: &str, args: &[Value]) -> Result<Vec<String>> {
     match function_name {
         "split" => {
             if args.len() != 2 {
@@ -606,8 +621,8 @@ pub fn execute_string_array_function(function_name: &str, args: &[String]) -> Re
                     "split関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let delimiter = &args[1];
+            let str = args[0].as_str()?;
+            let delimiter = args[1].as_str()?;
             
             let parts: Vec<String> = str.split(delimiter).map(|s| s.to_string()).collect();
             Ok(parts)
@@ -618,7 +633,7 @@ pub fn execute_string_array_function(function_name: &str, args: &[String]) -> Re
 }
 
 /// 文字列関数の実装（文字を返すもの）
-pub fn execute_char_function(function_name: &str, args: &[String]) -> Result<char> {
+pub fn execute_char_function(function_name: &str, args: &[Value]) -> Result<char> {
     match function_name {
         "at" => {
             if args.len() != 2 {
@@ -626,10 +641,8 @@ pub fn execute_char_function(function_name: &str, args: &[String]) -> Result<cha
                     "at関数は2つの引数が必要ですが、{}個の引数が提供されました", args.len()
                 )));
             }
-            let str = &args[0];
-            let index: usize = args[1].parse().map_err(|_| {
-                EidosError::Runtime("indexパラメータを整数に変換できません".to_string())
-            })?;
+            let str = args[0].as_str()?;
+            let index: usize = args[1].as_usize()?;
             
             let chars: Vec<char> = str.chars().collect();
             if index < chars.len() {
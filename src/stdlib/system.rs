@@ -1,6 +1,30 @@
 use crate::core::{Result, EidosError};
 use crate::core::types::{Type, TypeId, TypeKind, Field};
-use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
+use std::process::Command;
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// `eidos run <file> -- <args>` で実行中のプログラムに渡されたコマンドライン引数
+    static ref PROGRAM_ARGS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+}
+
+/// 実行するプログラムのコマンドライン引数を設定する
+///
+/// 最初の要素はプログラム名（ソースファイルのパス）にする。設定されていない間は、
+/// `args` などはコンパイラ自身のコマンドライン引数を返す。
+pub fn set_program_args(args: Vec<String>) {
+    *PROGRAM_ARGS.lock().unwrap() = Some(args);
+}
+
+/// プログラムに渡されたコマンドライン引数
+fn program_args() -> Vec<String> {
+    match PROGRAM_ARGS.lock().unwrap().as_ref() {
+        Some(args) => args.clone(),
+        None => std::env::args().collect(),
+    }
+}
 
 /// システムモジュールの初期化
 pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
@@ -10,6 +34,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     let string_type = Type::string();
     let unit_type = Type::unit();
     let string_array_type = Type::array(string_type.clone());
+    let exec_result_type = Type::tuple(vec![int_type.clone(), string_type.clone(), string_type.clone()]);
     
     // 環境変数関連の関数
     
@@ -17,7 +42,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "getenv",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![("name".to_string(), string_type.id)],
         string_type.id,
         "指定された名前の環境変数の値を取得します。環境変数が存在しない場合は空文字列を返します。",
@@ -46,11 +71,34 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         "指定された名前の環境変数を削除します。",
     ));
     
+    // System::env - 環境変数を取得（getenvの別名）
+    registry.register_function(StdlibFunction::new(
+        "env",
+        StdlibModule::System,
+        StdlibFunctionType::Effectful,
+        vec![("name".to_string(), string_type.id)],
+        string_type.id,
+        "指定された名前の環境変数の値を取得します。環境変数が存在しない場合は空文字列を返します。",
+    ));
+    
+    // System::set_env - 環境変数を設定（setenvの別名）
+    registry.register_function(StdlibFunction::new(
+        "set_env",
+        StdlibModule::System,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("name".to_string(), string_type.id),
+            ("value".to_string(), string_type.id),
+        ],
+        unit_type.id,
+        "指定された名前と値で環境変数を設定します。以降にexecで起動するコマンドにも引き継がれます。",
+    ));
+    
     // System::env_vars - すべての環境変数を取得
     registry.register_function(StdlibFunction::new(
         "env_vars",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         string_array_type.id,
         "すべての環境変数のキーと値のペアを「KEY=VALUE」形式の文字列配列として返します。",
@@ -101,6 +149,19 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         "外部コマンドを引数付きで実行し、終了コードを返します。",
     ));
     
+    // System::exec - 外部コマンドを実行して終了コードと出力を取得
+    registry.register_function(StdlibFunction::new(
+        "exec",
+        StdlibModule::System,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("command".to_string(), string_type.id),
+            ("args".to_string(), string_array_type.id),
+        ],
+        exec_result_type.id,
+        "外部コマンドをシェルを介さずに実行し、(終了コード, 標準出力, 標準エラー出力) を返します。シグナルで終了した場合の終了コードは-1です。",
+    ));
+    
     // System::pid - 現在のプロセスIDを取得
     registry.register_function(StdlibFunction::new(
         "pid",
@@ -187,7 +248,17 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "current_dir",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
+        vec![],
+        string_type.id,
+        "現在の作業ディレクトリのパスを返します。",
+    ));
+    
+    // System::cwd - 現在の作業ディレクトリを取得（current_dirの別名）
+    registry.register_function(StdlibFunction::new(
+        "cwd",
+        StdlibModule::System,
+        StdlibFunctionType::Effectful,
         vec![],
         string_type.id,
         "現在の作業ディレクトリのパスを返します。",
@@ -219,7 +290,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "memory_usage",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         int_type.id,
         "現在のプロセスのメモリ使用量をバイト単位で返します。",
@@ -239,7 +310,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "free_memory",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         int_type.id,
         "システムの空き物理メモリをバイト単位で返します。",
@@ -249,7 +320,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "uptime",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         int_type.id,
         "システムの稼働時間を秒単位で返します。",
@@ -264,7 +335,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         StdlibFunctionType::Pure,
         vec![],
         string_array_type.id,
-        "コマンドライン引数の配列を返します。最初の要素はプログラム名です。",
+        "コマンドライン引数の配列を返します。最初の要素はプログラム名で、`eidos run <file> -- <args>` の `<args>` が続きます。",
     ));
     
     // System::arg_count - コマンドライン引数の数を取得
@@ -293,7 +364,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "random",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![
             ("min".to_string(), int_type.id),
             ("max".to_string(), int_type.id),
@@ -316,7 +387,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "uuid",
         StdlibModule::System,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         string_type.id,
         "ランダムなUUID（汎用一意識別子）を生成して文字列として返します。",
@@ -326,20 +397,73 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
 }
 
 /// システム関数の実行
-pub fn execute_function(function_name: &str, args: &[String]) -> Result<String> {
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
     match function_name {
-        "getenv" => {
+        "getenv" | "env" => {
             if args.len() != 1 {
                 return Err(EidosError::Runtime(format!(
                     "getenv関数は1つの引数が必要ですが、{}個の引数が渡されました。",
                     args.len()
                 )));
             }
-            let var_name = &args[0];
+            let var_name = args[0].as_str()?;
             match std::env::var(var_name) {
-                Ok(value) => Ok(value),
-                Err(_) => Ok("".to_string()), // 環境変数が存在しない場合は空文字列
+                Ok(value) => Ok(Value::Str(value)),
+                Err(_) => Ok(Value::from("")), // 環境変数が存在しない場合は空文字列
+            }
+        }
+        "setenv" | "set_env" => {
+            if args.len() != 2 {
+                return Err(EidosError::Runtime(format!(
+                    "{}関数は2つの引数が必要ですが、{}個の引数が渡されました。",
+                    function_name,
+                    args.len()
+                )));
+            }
+            let (name, value) = (args[0].as_str()?, args[1].as_str()?);
+            if name.is_empty() || name.contains('=') || name.contains('\0') || value.contains('\0') {
+                return Err(EidosError::Runtime(format!("環境変数名または値が不正です: {}", name)));
+            }
+            std::env::set_var(name, value);
+            Ok(Value::Unit)
+        }
+        "unsetenv" => {
+            if args.len() != 1 {
+                return Err(EidosError::Runtime(format!(
+                    "unsetenv関数は1つの引数が必要ですが、{}個の引数が渡されました。",
+                    args.len()
+                )));
+            }
+            let name = args[0].as_str()?;
+            if name.is_empty() || name.contains('=') || name.contains('\0') {
+                return Err(EidosError::Runtime(format!("環境変数名が不正です: {}", name)));
             }
+            std::env::remove_var(name);
+            Ok(Value::Unit)
+        }
+        "exec" => {
+            if args.len() != 2 {
+                return Err(EidosError::Runtime(format!(
+                    "exec関数は2つの引数が必要ですが、{}個の引数が渡されました。",
+                    args.len()
+                )));
+            }
+            let program = args[0].as_str()?;
+            let program_args = args[1]
+                .as_list()?
+                .iter()
+                .map(Value::as_str)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let output = Command::new(program)
+                .args(program_args)
+                .output()
+                .map_err(|e| EidosError::Runtime(format!("コマンド '{}' を実行できません: {}", program, e)))?;
+            // タプル (終了コード, 標準出力, 標準エラー出力)
+            Ok(Value::List(vec![
+                Value::Int(output.status.code().unwrap_or(-1) as i64),
+                Value::Str(String::from_utf8_lossy(&output.stdout).to_string()),
+                Value::Str(String::from_utf8_lossy(&output.stderr).to_string()),
+            ]))
         }
         "pid" => {
             if !args.is_empty() {
@@ -348,7 +472,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     args.len()
                 )));
             }
-            Ok(std::process::id().to_string())
+            Ok(Value::Int(std::process::id() as i64))
         }
         "os_name" => {
             if !args.is_empty() {
@@ -357,7 +481,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     args.len()
                 )));
             }
-            Ok(std::env::consts::OS.to_string())
+            Ok(Value::from(std::env::consts::OS))
         }
         "arch" => {
             if !args.is_empty() {
@@ -366,17 +490,18 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     args.len()
                 )));
             }
-            Ok(std::env::consts::ARCH.to_string())
+            Ok(Value::from(std::env::consts::ARCH))
         }
-        "current_dir" => {
+        "current_dir" | "cwd" => {
             if !args.is_empty() {
                 return Err(EidosError::Runtime(format!(
-                    "current_dir関数は引数が不要ですが、{}個の引数が渡されました。",
+                    "{}関数は引数が不要ですが、{}個の引数が渡されました。",
+                    function_name,
                     args.len()
                 )));
             }
             match std::env::current_dir() {
-                Ok(path) => Ok(path.to_string_lossy().to_string()),
+                Ok(path) => Ok(Value::Str(path.to_string_lossy().to_string())),
                 Err(e) => Err(EidosError::Runtime(format!("現在のディレクトリの取得に失敗しました: {}", e))),
             }
         }
@@ -387,8 +512,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     args.len()
                 )));
             }
-            let args: Vec<String> = std::env::args().collect();
-            Ok(format!("[{}]", args.join(", ")))
+            Ok(Value::from(program_args()))
         }
         "arg_count" => {
             if !args.is_empty() {
@@ -397,8 +521,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     args.len()
                 )));
             }
-            let count = std::env::args().count();
-            Ok(count.to_string())
+            Ok(Value::from(program_args().len()))
         }
         "exit" => {
             if args.len() != 1 {
@@ -407,11 +530,53 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     args.len()
                 )));
             }
-            let code = args[0].parse::<i32>().map_err(|_| {
-                EidosError::Runtime("exit関数の引数は整数である必要があります。".to_string())
+            let code = i32::try_from(args[0].as_int()?).map_err(|_| {
+                EidosError::Runtime(format!("exit関数の終了コードが範囲外です: {}", args[0]))
             })?;
             std::process::exit(code);
         }
+        "arg" => {
+            if args.len() != 1 {
+                return Err(EidosError::Runtime(format!(
+                    "arg関数は1つの引数が必要ですが、{}個の引数が渡されました。",
+                    args.len()
+                )));
+            }
+            let index = args[0].as_usize()?;
+            // 範囲外のインデックスは空文字列
+            Ok(Value::Str(program_args().get(index).cloned().unwrap_or_default()))
+        }
         _ => Err(EidosError::Runtime(format!("システム関数 '{}' はネイティブ実装で提供されます", function_name)))
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_program_args() {
+        set_program_args(vec!["main.eid".to_string(), "build".to_string(), "--release".to_string()]);
+        assert_eq!(execute_function("args", &[]).unwrap(), Value::from(vec!["main.eid", "build", "--release"]));
+        assert_eq!(execute_function("arg_count", &[]).unwrap(), Value::Int(3));
+        assert_eq!(execute_function("arg", &[Value::Int(1)]).unwrap(), Value::from("build"));
+        assert_eq!(execute_function("arg", &[Value::Int(5)]).unwrap(), Value::from(""));
+        assert!(execute_function("arg", &[Value::from("1")]).is_err());
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_and_env() {
+        execute_function("set_env", &[Value::from("EIDOS_SYSTEM_TEST"), Value::from("42")]).unwrap();
+        assert_eq!(execute_function("env", &[Value::from("EIDOS_SYSTEM_TEST")]).unwrap(), Value::from("42"));
+        
+        let script = "echo \"out, $EIDOS_SYSTEM_TEST\"; echo err >&2; exit 3";
+        let result = execute_function("exec", &[Value::from("sh"), Value::from(vec!["-c", script])]).unwrap();
+        assert_eq!(
+            result,
+            Value::List(vec![Value::Int(3), Value::from("out, 42\n"), Value::from("err\n")])
+        );
+        
+        assert!(execute_function("exec", &[Value::from("eidos-no-such-command"), Value::List(Vec::new())]).is_err());
+    }
+}
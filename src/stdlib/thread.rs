@@ -0,0 +1,251 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, atomic::{AtomicI64, Ordering}};
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+use crate::core::{Result, EidosError};
+use crate::core::types::Type;
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
+
+/// スレッドの起動と合流、アトミック操作はコード生成で命令に変換するため、ここでは実行できない関数
+pub const COMPILED_FUNCTIONS: &[&str] = &["spawn", "join", "atomic_load", "atomic_store", "atomic_add", "atomic_cas"];
+
+/// 整数を受け渡すチャネル
+#[derive(Default)]
+struct Channel {
+    queue: Mutex<VecDeque<i64>>,
+    ready: Condvar,
+}
+
+lazy_static! {
+    static ref CHANNELS: Mutex<HashMap<i64, Arc<Channel>>> = Mutex::new(HashMap::new());
+    static ref NEXT_HANDLE: AtomicI64 = AtomicI64::new(1);
+}
+
+/// スレッドモジュールの初期化
+///
+/// スレッドとチャネルは、Cランタイムでもそのまま扱えるよう整数のハンドルで表す。
+/// アトミック操作の最初の引数は `static mut` 変数で、その変数をアトミックに読み書きする。
+pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
+    // 基本型の登録
+    let int_type = Type::int();
+    let bool_type = Type::bool();
+    let unit_type = Type::unit();
+    let entry_type = Type::function(vec![], int_type.clone());
+    
+    // スレッド
+    
+    // thread::spawn - 引数のない関数を新しいスレッドで実行
+    registry.register_function(StdlibFunction::new(
+        "spawn",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("function".to_string(), entry_type.id)],
+        int_type.id,
+        "引数を取らず Int を返す関数を新しいスレッドで実行し、スレッドのハンドルを返します。",
+    ));
+    
+    // thread::join - スレッドの終了を待つ
+    registry.register_function(StdlibFunction::new(
+        "join",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("thread".to_string(), int_type.id)],
+        int_type.id,
+        "スレッドの終了を待ち、スレッドで実行した関数の戻り値を返します。",
+    ));
+    
+    // thread::sleep - 現在のスレッドを止める
+    registry.register_function(StdlibFunction::new(
+        "sleep",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("milliseconds".to_string(), int_type.id)],
+        unit_type.id,
+        "指定したミリ秒数だけ現在のスレッドを止めます。",
+    ));
+    
+    // チャネル
+    
+    // thread::channel - チャネルを作成
+    registry.register_function(StdlibFunction::new(
+        "channel",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![],
+        int_type.id,
+        "スレッドの間で整数を受け渡す、上限のないチャネルを作成してハンドルを返します。",
+    ));
+    
+    // thread::send - チャネルに送信
+    registry.register_function(StdlibFunction::new(
+        "send",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("channel".to_string(), int_type.id), ("value".to_string(), int_type.id)],
+        unit_type.id,
+        "チャネルの末尾に値を追加します。",
+    ));
+    
+    // thread::recv - チャネルから受信
+    registry.register_function(StdlibFunction::new(
+        "recv",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("channel".to_string(), int_type.id)],
+        int_type.id,
+        "チャネルの先頭の値を取り出します。チャネルが空の間は値が送られるまで待ちます。",
+    ));
+    
+    // アトミック操作
+    
+    // thread::atomic_load - アトミックな読み込み
+    registry.register_function(StdlibFunction::new(
+        "atomic_load",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("variable".to_string(), int_type.id)],
+        int_type.id,
+        "static mut 変数の値をアトミックに読み込みます。",
+    ));
+    
+    // thread::atomic_store - アトミックな書き込み
+    registry.register_function(StdlibFunction::new(
+        "atomic_store",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("variable".to_string(), int_type.id), ("value".to_string(), int_type.id)],
+        unit_type.id,
+        "static mut 変数に値をアトミックに書き込みます。",
+    ));
+    
+    // thread::atomic_add - アトミックな加算
+    registry.register_function(StdlibFunction::new(
+        "atomic_add",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![("variable".to_string(), int_type.id), ("value".to_string(), int_type.id)],
+        int_type.id,
+        "static mut 変数に値をアトミックに加え、加える前の値を返します。",
+    ));
+    
+    // thread::atomic_cas - Compare-and-Swap
+    registry.register_function(StdlibFunction::new(
+        "atomic_cas",
+        StdlibModule::Thread,
+        StdlibFunctionType::Effectful,
+        vec![
+            ("variable".to_string(), int_type.id),
+            ("expected".to_string(), int_type.id),
+            ("new".to_string(), int_type.id),
+        ],
+        bool_type.id,
+        "static mut 変数の値が expected と等しければ new に置き換え、置き換えたかどうかを返します。",
+    ));
+    
+    Ok(())
+}
+
+/// スレッド関数の実行
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
+    match function_name {
+        "sleep" => {
+            let [milliseconds] = int_args(function_name, args)?;
+            std::thread::sleep(Duration::from_millis(milliseconds.max(0) as u64));
+            Ok(Value::Unit)
+        }
+        "channel" => {
+            let [] = int_args(function_name, args)?;
+            let handle = NEXT_HANDLE.fetch_add(1, Ordering::SeqCst);
+            CHANNELS.lock().unwrap().insert(handle, Arc::new(Channel::default()));
+            Ok(Value::Int(handle))
+        }
+        "send" => {
+            let [handle, value] = int_args(function_name, args)?;
+            let channel = channel(function_name, handle)?;
+            channel.queue.lock().unwrap().push_back(value);
+            channel.ready.notify_one();
+            Ok(Value::Unit)
+        }
+        "recv" => {
+            let [handle] = int_args(function_name, args)?;
+            // 待っている間も他のスレッドがチャネルを使えるよう、チャネルごとのロックで待つ
+            let channel = channel(function_name, handle)?;
+            let mut queue = channel.queue.lock().unwrap();
+            loop {
+                if let Some(value) = queue.pop_front() {
+                    return Ok(Value::Int(value));
+                }
+                queue = channel.ready.wait(queue).unwrap();
+            }
+        }
+        _ if COMPILED_FUNCTIONS.contains(&function_name) => Err(EidosError::Runtime(format!(
+            "thread::{}関数はコード生成で命令に変換されるため、直接は実行できません",
+            function_name
+        ))),
+        _ => Err(EidosError::Runtime(format!("不明なスレッド関数: {}", function_name)))
+    }
+}
+
+/// 引数の数を確かめ、すべて整数として取り出す
+fn int_args<const N: usize>(function_name: &str, args: &[Value]) -> Result<[i64; N]> {
+    if args.len() != N {
+        let required = if N == 0 {
+            "引数が不要".to_string()
+        } else {
+            format!("{}つの引数が必要", N)
+        };
+        return Err(EidosError::Runtime(format!(
+            "thread::{}関数は{}ですが、{}個の引数が渡されました。",
+            function_name,
+            required,
+            args.len()
+        )));
+    }
+    let mut ints = [0; N];
+    for (int, arg) in ints.iter_mut().zip(args) {
+        *int = arg.as_int()?;
+    }
+    Ok(ints)
+}
+
+fn channel(function_name: &str, handle: i64) -> Result<Arc<Channel>> {
+    CHANNELS.lock().unwrap().get(&handle).cloned().ok_or_else(|| {
+        EidosError::Runtime(format!("thread::{}: 無効なチャネルのハンドル: {}", function_name, handle))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn call(name: &str, args: &[i64]) -> Result<Value> {
+        let args: Vec<Value> = args.iter().map(|&arg| Value::Int(arg)).collect();
+        execute_function(name, &args)
+    }
+    
+    #[test]
+    fn test_channel_between_threads() {
+        let Value::Int(channel) = call("channel", &[]).unwrap() else {
+            panic!("チャネルのハンドルは整数");
+        };
+        let sender = std::thread::spawn(move || {
+            call("sleep", &[10]).unwrap();
+            for value in 1..=3 {
+                call("send", &[channel, value]).unwrap();
+            }
+        });
+        
+        let received: Vec<Value> = (0..3).map(|_| call("recv", &[channel]).unwrap()).collect();
+        sender.join().unwrap();
+        assert_eq!(received, vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
+        assert!(call("recv", &[0]).is_err());
+    }
+    
+    #[test]
+    fn test_compiled_functions_are_not_executed() {
+        let error = call("atomic_add", &[0, 1]).unwrap_err();
+        assert!(error.to_string().contains("コード生成で命令に変換される"));
+    }
+}
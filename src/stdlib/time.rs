@@ -1,6 +1,18 @@
 use crate::core::{Result, EidosError};
 use crate::core::types::{Type, TypeId, TypeKind, Field};
-use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType};
+use crate::stdlib::{StdlibRegistry, StdlibFunction, StdlibModule, StdlibFunctionType, Value};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike, Utc};
+use std::time::Instant;
+use lazy_static::lazy_static;
+
+/// 実行時のDateTimeの表現（RFC 3339、ミリ秒とUTCからのオフセット付き）
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+
+lazy_static! {
+    /// 単調時計の基準点（最初に参照した時点）
+    static ref MONOTONIC_ORIGIN: Instant = Instant::now();
+}
 
 /// 時間モジュールの初期化
 pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
@@ -86,7 +98,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "now",
         StdlibModule::Time,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         datetime_type.id,
         "現在の日時を返します。",
@@ -96,7 +108,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "timestamp",
         StdlibModule::Time,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         int_type.id,
         "現在のUNIXタイムスタンプ（1970年1月1日からの秒数）を返します。",
@@ -106,7 +118,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
     registry.register_function(StdlibFunction::new(
         "timestamp_millis",
         StdlibModule::Time,
-        StdlibFunctionType::Pure,
+        StdlibFunctionType::Effectful,
         vec![],
         int_type.id,
         "現在のUNIXタイムスタンプをミリ秒単位で返します。",
@@ -122,6 +134,16 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         "指定された秒数だけ現在のスレッドをスリープさせます。",
     ));
     
+    // monotonic - 単調時計の現在値を取得
+    registry.register_function(StdlibFunction::new(
+        "monotonic",
+        StdlibModule::Time,
+        StdlibFunctionType::Effectful,
+        vec![],
+        duration_type.id,
+        "単調時計の現在値を基準点からのDurationとして返します。システム時刻の変更の影響を受けないため、経過時間の計測に使います。",
+    ));
+    
     // parse - 文字列から日時をパース
     registry.register_function(StdlibFunction::new(
         "parse",
@@ -132,7 +154,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
             ("format".to_string(), string_type.id),
         ],
         datetime_type.id,
-        "文字列を指定されたフォーマットで解析し、DateTimeオブジェクトを返します。",
+        "文字列を指定されたフォーマットで解析し、DateTimeオブジェクトを返します。フォーマットにオフセット（%z）がなければUTC、時刻がなければ0時とみなします。",
     ));
     
     // format - 日時を文字列にフォーマット
@@ -148,6 +170,46 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         "DateTimeオブジェクトを指定されたフォーマットで文字列に変換します。",
     ));
     
+    // format_tz - タイムゾーンを指定して日時を文字列にフォーマット
+    registry.register_function(StdlibFunction::new(
+        "format_tz",
+        StdlibModule::Time,
+        StdlibFunctionType::Pure,
+        vec![
+            ("datetime".to_string(), datetime_type.id),
+            ("format".to_string(), string_type.id),
+            ("timezone".to_string(), string_type.id),
+        ],
+        string_type.id,
+        "DateTimeを指定されたタイムゾーン（\"UTC\"、\"local\"、\"+09:00\" など）の時刻に変換してからフォーマットします。",
+    ));
+    
+    // to_timezone - 日時を別のタイムゾーンで表す
+    registry.register_function(StdlibFunction::new(
+        "to_timezone",
+        StdlibModule::Time,
+        StdlibFunctionType::Pure,
+        vec![
+            ("datetime".to_string(), datetime_type.id),
+            ("timezone".to_string(), string_type.id),
+        ],
+        datetime_type.id,
+        "同じ瞬間を指定されたタイムゾーンで表したDateTimeを返します。",
+    ));
+    
+    // add_duration - 日時にDurationを追加
+    registry.register_function(StdlibFunction::new(
+        "add_duration",
+        StdlibModule::Time,
+        StdlibFunctionType::Pure,
+        vec![
+            ("datetime".to_string(), datetime_type.id),
+            ("duration".to_string(), duration_type.id),
+        ],
+        datetime_type.id,
+        "DateTimeオブジェクトに指定されたDurationを追加します。",
+    ));
+    
     // add_days - 日時に日数を追加
     registry.register_function(StdlibFunction::new(
         "add_days",
@@ -210,7 +272,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
             ("datetime2".to_string(), datetime_type.id),
         ],
         duration_type.id,
-        "2つのDateTimeオブジェクト間の差をDurationとして返します。",
+        "最初のDateTimeから2番目のDateTimeを引いた差をDurationとして返します。",
     ));
     
     // is_before - ある日時が別の日時より前かどうかを確認
@@ -267,7 +329,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
             ("millisecond".to_string(), int_type.id),
         ],
         datetime_type.id,
-        "指定されたコンポーネントから新しいDateTimeオブジェクトを作成します。",
+        "指定されたコンポーネント（UTC）から新しいDateTimeオブジェクトを作成します。",
     ));
     
     // create_duration - Durationオブジェクトを作成
@@ -317,7 +379,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
         StdlibFunctionType::Pure,
         vec![("timestamp".to_string(), int_type.id)],
         datetime_type.id,
-        "UNIX タイムスタンプ（秒）を DateTime に変換します。",
+        "UNIX タイムスタンプ（秒）を UTC の DateTime に変換します。",
     ));
     
     // get_year - DateTime から年を取得
@@ -499,7 +561,7 @@ pub fn initialize(registry: &mut StdlibRegistry) -> Result<()> {
 }
 
 /// 時間関数の実行
-pub fn execute_function(function_name: &str, args: &[String]) -> Result<String> {
+pub fn execute_function(function_name: &str, args: &[Value]) -> Result<Value> {
     match function_name {
         "sleep" => {
             if args.len() != 1 {
@@ -508,12 +570,13 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                     args.len()
                 )));
             }
-            let seconds = args[0].parse::<f64>().map_err(|_| {
-                EidosError::Runtime("sleep関数の引数は数値である必要があります。".to_string())
-            })?;
+            let seconds = duration_arg(function_name, &args[0])?;
+            if seconds < 0.0 {
+                return Err(EidosError::Runtime("sleep関数の引数は0以上である必要があります。".to_string()));
+            }
             
             std::thread::sleep(std::time::Duration::from_secs_f64(seconds));
-            Ok("".to_string())
+            Ok(Value::Unit)
         }
         "timestamp" => {
             if !args.is_empty() {
@@ -527,7 +590,7 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
             let since_epoch = now.duration_since(std::time::UNIX_EPOCH)
                 .map_err(|e| EidosError::Runtime(format!("システム時間エラー: {}", e)))?;
             
-            Ok(since_epoch.as_secs().to_string())
+            Ok(Value::Int(since_epoch.as_secs() as i64))
         }
         "timestamp_millis" => {
             if !args.is_empty() {
@@ -542,8 +605,367 @@ pub fn execute_function(function_name: &str, args: &[String]) -> Result<String>
                 .map_err(|e| EidosError::Runtime(format!("システム時間エラー: {}", e)))?;
             
             let millis = since_epoch.as_secs() * 1000 + since_epoch.subsec_millis() as u64;
-            Ok(millis.to_string())
+            Ok(Value::Int(millis as i64))
+        }
+        "now" => {
+            expect_args(function_name, args, 0)?;
+            Ok(datetime_value(&Local::now().fixed_offset()))
+        }
+        "monotonic" => {
+            expect_args(function_name, args, 0)?;
+            Ok(duration_value(MONOTONIC_ORIGIN.elapsed().as_secs_f64()))
+        }
+        "parse" => {
+            expect_args(function_name, args, 2)?;
+            let (text, format) = (args[0].as_str()?, args[1].as_str()?);
+            check_format(function_name, format)?;
+            let parsed = DateTime::parse_from_str(text, format)
+                .or_else(|_| NaiveDateTime::parse_from_str(text, format).map(|dt| Utc.from_utc_datetime(&dt).fixed_offset()))
+                .or_else(|_| {
+                    NaiveDate::parse_from_str(text, format)
+                        .map(|date| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()).fixed_offset())
+                })
+                .map_err(|e| EidosError::Runtime(format!("time::parse: '{}' を '{}' として解析できません: {}", text, format, e)))?;
+            Ok(datetime_value(&parsed))
+        }
+        "format" => {
+            expect_args(function_name, args, 2)?;
+            let datetime = datetime_arg(function_name, &args[0])?;
+            let format = args[1].as_str()?;
+            check_format(function_name, format)?;
+            Ok(Value::Str(datetime.format(format).to_string()))
+        }
+        "format_tz" => {
+            expect_args(function_name, args, 3)?;
+            let datetime = datetime_arg(function_name, &args[0])?;
+            let format = args[1].as_str()?;
+            check_format(function_name, format)?;
+            let offset = timezone_arg(function_name, args[2].as_str()?, &datetime)?;
+            Ok(Value::Str(datetime.with_timezone(&offset).format(format).to_string()))
+        }
+        "to_timezone" => {
+            expect_args(function_name, args, 2)?;
+            let datetime = datetime_arg(function_name, &args[0])?;
+            let offset = timezone_arg(function_name, args[1].as_str()?, &datetime)?;
+            Ok(datetime_value(&datetime.with_timezone(&offset)))
+        }
+        "add_days" | "add_hours" | "add_minutes" | "add_seconds" => {
+            expect_args(function_name, args, 2)?;
+            let datetime = datetime_arg(function_name, &args[0])?;
+            let amount = args[1].as_int()?;
+            let unit = match function_name {
+                "add_days" => 86_400,
+                "add_hours" => 3_600,
+                "add_minutes" => 60,
+                _ => 1,
+            };
+            let millis = amount.checked_mul(unit * 1000).ok_or_else(|| out_of_range(function_name))?;
+            Ok(datetime_value(&add_millis(function_name, &datetime, millis)?))
+        }
+        "add_duration" => {
+            expect_args(function_name, args, 2)?;
+            let datetime = datetime_arg(function_name, &args[0])?;
+            let seconds = duration_arg(function_name, &args[1])?;
+            Ok(datetime_value(&add_millis(function_name, &datetime, seconds_to_millis(function_name, seconds)?)?))
+        }
+        "diff" => {
+            expect_args(function_name, args, 2)?;
+            let first = datetime_arg(function_name, &args[0])?;
+            let second = datetime_arg(function_name, &args[1])?;
+            Ok(duration_value((first - second).num_milliseconds() as f64 / 1000.0))
+        }
+        "is_before" | "is_after" | "is_same" => {
+            expect_args(function_name, args, 2)?;
+            let first = datetime_arg(function_name, &args[0])?;
+            let second = datetime_arg(function_name, &args[1])?;
+            let result = match function_name {
+                "is_before" => first < second,
+                "is_after" => first > second,
+                _ => first == second,
+            };
+            Ok(Value::Bool(result))
+        }
+        "create_datetime" => {
+            expect_args(function_name, args, 7)?;
+            let mut parts = [0i64; 7];
+            for (part, arg) in parts.iter_mut().zip(args) {
+                *part = arg.as_int()?;
+            }
+            let [year, month, day, hour, minute, second, millisecond] = parts;
+            let datetime = i32::try_from(year)
+                .ok()
+                .and_then(|year| NaiveDate::from_ymd_opt(year, u32::try_from(month).ok()?, u32::try_from(day).ok()?))
+                .and_then(|date| {
+                    date.and_hms_milli_opt(
+                        u32::try_from(hour).ok()?,
+                        u32::try_from(minute).ok()?,
+                        u32::try_from(second).ok()?,
+                        u32::try_from(millisecond).ok()?,
+                    )
+                })
+                .ok_or_else(|| EidosError::Runtime(format!("time::create_datetime: 存在しない日時です: {:?}", parts)))?;
+            Ok(datetime_value(&Utc.from_utc_datetime(&datetime).fixed_offset()))
+        }
+        "create_duration" => {
+            expect_args(function_name, args, 1)?;
+            Ok(duration_value(duration_arg(function_name, &args[0])?))
+        }
+        "duration_to_seconds" => {
+            expect_args(function_name, args, 1)?;
+            Ok(Value::Float(duration_arg(function_name, &args[0])?))
+        }
+        "duration_to_milliseconds" => {
+            expect_args(function_name, args, 1)?;
+            let seconds = duration_arg(function_name, &args[0])?;
+            Ok(Value::Int(seconds_to_millis(function_name, seconds)?))
+        }
+        "datetime_to_timestamp" => {
+            expect_args(function_name, args, 1)?;
+            Ok(Value::Int(datetime_arg(function_name, &args[0])?.timestamp()))
+        }
+        "timestamp_to_datetime" => {
+            expect_args(function_name, args, 1)?;
+            let timestamp = args[0].as_int()?;
+            let datetime = Utc.timestamp_opt(timestamp, 0).single().ok_or_else(|| out_of_range(function_name))?;
+            Ok(datetime_value(&datetime.fixed_offset()))
+        }
+        "get_year" | "get_month" | "get_day" | "get_hour" | "get_minute" | "get_second" | "get_millisecond"
+        | "get_weekday" | "get_day_of_year" => {
+            expect_args(function_name, args, 1)?;
+            // 成分はDateTime自身のオフセットでの値
+            let datetime = datetime_arg(function_name, &args[0])?;
+            let value = match function_name {
+                "get_year" => datetime.year() as i64,
+                "get_month" => datetime.month() as i64,
+                "get_day" => datetime.day() as i64,
+                "get_hour" => datetime.hour() as i64,
+                "get_minute" => datetime.minute() as i64,
+                "get_second" => datetime.second() as i64,
+                "get_millisecond" => datetime.timestamp_subsec_millis() as i64,
+                "get_weekday" => datetime.weekday().num_days_from_sunday() as i64,
+                _ => datetime.ordinal() as i64,
+            };
+            Ok(Value::Int(value))
+        }
+        "is_leap_year" => {
+            expect_args(function_name, args, 1)?;
+            let year = args[0].as_int()?;
+            let leap = i32::try_from(year).ok().and_then(|year| NaiveDate::from_ymd_opt(year, 2, 29)).is_some();
+            Ok(Value::Bool(leap))
+        }
+        "get_days_in_month" => {
+            expect_args(function_name, args, 2)?;
+            let year = args[0].as_int()?;
+            let month = args[1].as_int()?;
+            let days = i32::try_from(year)
+                .ok()
+                .filter(|_| (1..=12).contains(&month))
+                .and_then(|year| (28..=31).rev().find(|&day| NaiveDate::from_ymd_opt(year, month as u32, day).is_some()))
+                .ok_or_else(|| EidosError::Runtime(format!("time::get_days_in_month: 不正な年月です: {}-{}", year, month)))?;
+            Ok(Value::Int(days as i64))
+        }
+        "get_timezone_offset" => {
+            expect_args(function_name, args, 0)?;
+            Ok(Value::Int((Local::now().offset().fix().local_minus_utc() / 60) as i64))
+        }
+        "duration_add" | "duration_subtract" => {
+            expect_args(function_name, args, 2)?;
+            let first = duration_arg(function_name, &args[0])?;
+            let second = duration_arg(function_name, &args[1])?;
+            let result = if function_name == "duration_add" { first + second } else { first - second };
+            Ok(duration_value(result))
+        }
+        "duration_multiply" | "duration_divide" => {
+            expect_args(function_name, args, 2)?;
+            let duration = duration_arg(function_name, &args[0])?;
+            let factor = float_arg(function_name, &args[1])?;
+            let result = if function_name == "duration_multiply" {
+                duration * factor
+            } else if factor == 0.0 {
+                return Err(EidosError::Runtime("time::duration_divide: 0で除算することはできません。".to_string()));
+            } else {
+                duration / factor
+            };
+            if !result.is_finite() {
+                return Err(out_of_range(function_name));
+            }
+            Ok(duration_value(result))
         }
         _ => Err(EidosError::Runtime(format!("時間関数 '{}' はネイティブ実装で提供されます", function_name)))
     }
-} 
\ No newline at end of file
+}
+
+/// 引数の数を確認する
+fn expect_args(function_name: &str, args: &[Value], expected: usize) -> Result<()> {
+    if args.len() == expected {
+        return Ok(());
+    }
+    let required = if expected == 0 {
+        "引数が不要".to_string()
+    } else {
+        format!("{}つの引数が必要", expected)
+    };
+    Err(EidosError::Runtime(format!(
+        "time::{}関数は{}ですが、{}個の引数が渡されました。",
+        function_name,
+        required,
+        args.len()
+    )))
+}
+
+fn out_of_range(function_name: &str) -> EidosError {
+    EidosError::Runtime(format!("time::{}: 結果が表現できる日時の範囲を超えています。", function_name))
+}
+
+/// DateTimeはRFC 3339形式の文字列で表す
+fn datetime_value(datetime: &DateTime<FixedOffset>) -> Value {
+    Value::Str(datetime.format(DATETIME_FORMAT).to_string())
+}
+
+/// Durationは秒数（小数）で表す
+fn duration_value(seconds: f64) -> Value {
+    Value::Float(seconds)
+}
+
+fn datetime_arg(function_name: &str, value: &Value) -> Result<DateTime<FixedOffset>> {
+    let value = value.as_str()?;
+    DateTime::parse_from_rfc3339(value).map_err(|_| {
+        EidosError::Runtime(format!("time::{}の引数はDateTimeである必要があります: {}", function_name, value))
+    })
+}
+
+fn duration_arg(function_name: &str, value: &Value) -> Result<f64> {
+    match value.as_float()? {
+        seconds if seconds.is_finite() => Ok(seconds),
+        _ => Err(EidosError::Runtime(format!("time::{}の引数はDurationである必要があります: {}", function_name, value))),
+    }
+}
+
+fn float_arg(function_name: &str, value: &Value) -> Result<f64> {
+    match value.as_float()? {
+        number if number.is_finite() => Ok(number),
+        _ => Err(EidosError::Runtime(format!("time::{}の引数は有限の数値である必要があります: {}", function_name, value))),
+    }
+}
+
+fn seconds_to_millis(function_name: &str, seconds: f64) -> Result<i64> {
+    let millis = (seconds * 1000.0).round();
+    if millis.abs() < i64::MAX as f64 {
+        Ok(millis as i64)
+    } else {
+        Err(out_of_range(function_name))
+    }
+}
+
+fn add_millis(function_name: &str, datetime: &DateTime<FixedOffset>, millis: i64) -> Result<DateTime<FixedOffset>> {
+    chrono::Duration::try_milliseconds(millis)
+        .and_then(|delta| datetime.checked_add_signed(delta))
+        .ok_or_else(|| out_of_range(function_name))
+}
+
+/// 書式文字列を検証する（不正な指定子のまま整形するとパニックするため）
+fn check_format(function_name: &str, format: &str) -> Result<()> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(EidosError::Runtime(format!("time::{}: 不正な書式文字列です: {}", function_name, format)));
+    }
+    Ok(())
+}
+
+/// タイムゾーン指定（`UTC`、`local`、`+09:00` / `-0530` 形式のオフセット）を解釈する
+///
+/// `local` のオフセットは夏時間を考慮し、`at` の時点での値を使う。
+fn timezone_arg(function_name: &str, timezone: &str, at: &DateTime<FixedOffset>) -> Result<FixedOffset> {
+    let invalid = || EidosError::Runtime(format!("time::{}: 不正なタイムゾーンです: {}", function_name, timezone));
+    match timezone {
+        "UTC" | "utc" | "Z" => return Ok(Utc.fix()),
+        "local" => return Ok(Local.offset_from_utc_datetime(&at.naive_utc()).fix()),
+        _ => {}
+    }
+    
+    let (sign, rest) = match timezone.as_bytes().first() {
+        Some(b'+') => (1, &timezone[1..]),
+        Some(b'-') => (-1, &timezone[1..]),
+        _ => return Err(invalid()),
+    };
+    let digits = rest.replacen(':', "", 1);
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+    let minutes: i32 = digits[2..].parse().map_err(|_| invalid())?;
+    if minutes >= 60 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn call(function_name: &str, args: &[Value]) -> Value {
+        execute_function(function_name, args).unwrap()
+    }
+    
+    fn int(value: i64) -> Value {
+        Value::Int(value)
+    }
+    
+    fn string(value: &str) -> Value {
+        Value::from(value)
+    }
+    
+    #[test]
+    fn test_datetime_arithmetic_and_formatting() {
+        let datetime = call("create_datetime", &[int(2024), int(2), int(28), int(23), int(30), int(0), int(250)]);
+        assert_eq!(datetime, string("2024-02-28T23:30:00.250+00:00"));
+        
+        let next = call("add_hours", &[datetime.clone(), int(1)]);
+        assert_eq!(call("format", &[next.clone(), string("%Y-%m-%d %H:%M")]), string("2024-02-29 00:30"));
+        assert_eq!(
+            call("format_tz", &[next.clone(), string("%Y-%m-%d %H:%M %:z"), string("+09:00")]),
+            string("2024-02-29 09:30 +09:00")
+        );
+        assert_eq!(call("get_weekday", std::slice::from_ref(&next)), int(4));
+        
+        let tokyo = call("to_timezone", &[next.clone(), string("+0900")]);
+        assert_eq!(call("get_hour", std::slice::from_ref(&tokyo)), int(9));
+        assert_eq!(call("is_same", &[tokyo, next.clone()]), Value::Bool(true));
+        
+        let diff = call("diff", &[next, datetime.clone()]);
+        assert_eq!(diff, Value::Float(3600.0));
+        assert_eq!(call("duration_to_milliseconds", std::slice::from_ref(&diff)), int(3_600_000));
+        let doubled = call("duration_multiply", &[diff.clone(), int(2)]);
+        assert_eq!(call("add_duration", &[datetime.clone(), doubled]), string("2024-02-29T01:30:00.250+00:00"));
+        
+        assert_eq!(call("get_days_in_month", &[int(2023), int(2)]), int(28));
+        assert_eq!(call("is_leap_year", &[int(2000)]), Value::Bool(true));
+        
+        assert!(execute_function("create_datetime", &[int(2023), int(2), int(29), int(0), int(0), int(0), int(0)]).is_err());
+        assert!(execute_function("add_hours", &[datetime.clone(), string("1")]).is_err());
+        assert!(execute_function("format", &[datetime.clone(), string("%Q")]).is_err());
+        assert!(execute_function("format_tz", &[datetime, string("%H"), string("Mars/Olympus")]).is_err());
+        assert!(execute_function("duration_divide", &[diff, int(0)]).is_err());
+    }
+    
+    #[test]
+    fn test_parse() {
+        let parsed = call("parse", &[string("2024-03-01 12:00:00 +0900"), string("%Y-%m-%d %H:%M:%S %z")]);
+        assert_eq!(call("datetime_to_timestamp", &[parsed]), int(1_709_262_000));
+        
+        // オフセットのない書式はUTC、日付だけなら0時
+        let date = call("parse", &[string("2024-03-01"), string("%Y-%m-%d")]);
+        assert_eq!(date, string("2024-03-01T00:00:00.000+00:00"));
+        assert_eq!(call("timestamp_to_datetime", &[int(1_709_251_200)]), date);
+        
+        assert!(execute_function("parse", &[string("yesterday"), string("%Y-%m-%d")]).is_err());
+    }
+    
+    #[test]
+    fn test_monotonic() {
+        let start = call("monotonic", &[]);
+        let end = call("monotonic", &[]);
+        let elapsed = call("duration_subtract", &[end, start]);
+        assert!(elapsed.as_float().unwrap() >= 0.0);
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::core::EidosError;
+
+/// 標準ライブラリ関数の引数と戻り値
+///
+/// コレクションやソケットなどのハンドルは `"vector:3"` のようなIDの `Str` で表す。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    List(Vec<Value>),
+    /// キーと値の組（挿入順を保つ）
+    Map(Vec<(Value, Value)>),
+    /// `Some(値)` または `None`
+    Option(Option<Box<Value>>),
+    /// `Ok(値)` または `Err(値)`
+    Result(std::result::Result<Box<Value>, Box<Value>>),
+    Unit,
+}
+
+/// 期待した型と異なる値を受け取ったときのエラー
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{expected}型の値が必要ですが、{found}型の値 {value} が渡されました")]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub found: &'static str,
+    pub value: String,
+}
+
+impl From<ConversionError> for EidosError {
+    fn from(error: ConversionError) -> Self {
+        EidosError::Runtime(error.to_string())
+    }
+}
+
+impl Value {
+    /// 値の型名（エラーメッセージ用）
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "Int",
+            Value::Float(_) => "Float",
+            Value::Bool(_) => "Bool",
+            Value::Str(_) => "String",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Option(_) => "Option",
+            Value::Result(_) => "Result",
+            Value::Unit => "Unit",
+        }
+    }
+    
+    fn mismatch(&self, expected: &'static str) -> ConversionError {
+        ConversionError {
+            expected,
+            found: self.type_name(),
+            value: self.to_string(),
+        }
+    }
+    
+    pub fn as_int(&self) -> std::result::Result<i64, ConversionError> {
+        match self {
+            Value::Int(value) => Ok(*value),
+            other => Err(other.mismatch("Int")),
+        }
+    }
+    
+    /// 浮動小数点数として取り出す（整数は暗黙に変換する）
+    pub fn as_float(&self) -> std::result::Result<f64, ConversionError> {
+        match self {
+            Value::Float(value) => Ok(*value),
+            Value::Int(value) => Ok(*value as f64),
+            other => Err(other.mismatch("Float")),
+        }
+    }
+    
+    /// 負でない整数として取り出す（インデックスや個数に使う）
+    pub fn as_usize(&self) -> std::result::Result<usize, ConversionError> {
+        match self {
+            Value::Int(value) if *value >= 0 => Ok(*value as usize),
+            other => Err(other.mismatch("Int（0以上）")),
+        }
+    }
+    
+    pub fn as_bool(&self) -> std::result::Result<bool, ConversionError> {
+        match self {
+            Value::Bool(value) => Ok(*value),
+            other => Err(other.mismatch("Bool")),
+        }
+    }
+    
+    pub fn as_str(&self) -> std::result::Result<&str, ConversionError> {
+        match self {
+            Value::Str(value) => Ok(value),
+            other => Err(other.mismatch("String")),
+        }
+    }
+    
+    pub fn as_list(&self) -> std::result::Result<&[Value], ConversionError> {
+        match self {
+            Value::List(values) => Ok(values),
+            other => Err(other.mismatch("List")),
+        }
+    }
+    
+    pub fn as_map(&self) -> std::result::Result<&[(Value, Value)], ConversionError> {
+        match self {
+            Value::Map(entries) => Ok(entries),
+            other => Err(other.mismatch("Map")),
+        }
+    }
+    
+    pub fn as_option(&self) -> std::result::Result<Option<&Value>, ConversionError> {
+        match self {
+            Value::Option(value) => Ok(value.as_deref()),
+            other => Err(other.mismatch("Option")),
+        }
+    }
+    
+    pub fn as_result(&self) -> std::result::Result<std::result::Result<&Value, &Value>, ConversionError> {
+        match self {
+            Value::Result(Ok(value)) => Ok(Ok(value)),
+            Value::Result(Err(error)) => Ok(Err(error)),
+            other => Err(other.mismatch("Result")),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", value),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Option(Some(value)) => write!(f, "Some({})", value),
+            Value::Option(None) => write!(f, "None"),
+            Value::Result(Ok(value)) => write!(f, "Ok({})", value),
+            Value::Result(Err(error)) => write!(f, "Err({})", error),
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Str(value.to_string())
+    }
+}
+
+impl From<()> for Value {
+    fn from(_: ()) -> Self {
+        Value::Unit
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Value::Int(3).as_float(), Ok(3.0));
+        assert_eq!(Value::from("a").as_str(), Ok("a"));
+        assert_eq!(Value::from(vec![1i64, 2]).to_string(), "[1, 2]");
+        
+        let error = Value::from("3").as_int().unwrap_err();
+        assert_eq!(error.expected, "Int");
+        assert_eq!(error.found, "String");
+        assert!(Value::Int(-1).as_usize().is_err());
+        
+        assert_eq!(Value::Option(Some(Box::new(Value::Int(1)))).to_string(), "Some(1)");
+        assert_eq!(Value::Result(Err(Box::new(Value::from("bad")))).as_result(), Ok(Err(&Value::from("bad"))));
+        assert_eq!(Value::Unit.as_option().unwrap_err().expected, "Option");
+    }
+}
@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use log::info;
+
+use crate::core::ast_dump::AstDumper;
+use crate::frontend::lexer::Lexer;
+use crate::frontend::macro_expander::MacroExpander;
+use crate::frontend::parser::Parser;
+
+/// ASTの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum AstFormat {
+    /// JSON
+    #[default]
+    Json,
+    /// S式
+    Sexp,
+}
+
+/// `eidos ast` の設定
+#[derive(Debug, Clone, Default)]
+pub struct AstOptions {
+    pub format: AstFormat,
+    /// マクロを展開し、DSLブロックの展開結果も出力する
+    pub expand: bool,
+    /// 出力先（省略すると標準出力）
+    pub output: Option<PathBuf>,
+}
+
+/// ファイルを構文解析してASTを出力
+///
+/// 意味解析や型検査は行わないので、型の誤りがあるファイルでも出力できる。
+/// マクロはトークン列の段階で展開するため、`macro` 定義や `name!(...)` の呼び出しを含むファイルは
+/// `--expand` を付けたときだけ構文解析できる。
+///
+/// ファイルを読みながら字句解析（`--expand` ではマクロ展開も）・構文解析し、ソースやトークン列の全体を
+/// メモリに置かない（生成された大きなファイルでも先読みの分のメモリで済む）。
+pub fn ast(file: &Path, options: &AstOptions) -> Result<()> {
+    let reader = File::open(file)
+        .map(BufReader::new)
+        .with_context(|| format!("ファイルの読み込みに失敗しました: {}", file.display()))?;
+    let lexer = Lexer::from_reader(reader, file.to_path_buf());
+    let mut expander = MacroExpander::new();
+    let program = if options.expand {
+        Parser::from_lexer(expander.expanding(lexer), file.to_path_buf()).parse()?
+    } else {
+        Parser::from_lexer(lexer, file.to_path_buf()).parse()?
+    };
+    
+    let dumper = AstDumper::new().with_expansions(options.expand);
+    let text = match options.format {
+        AstFormat::Json => dumper.to_json(&program),
+        AstFormat::Sexp => dumper.to_sexp(&program),
+    };
+    
+    match &options.output {
+        Some(path) => {
+            std::fs::write(path, text)
+                .with_context(|| format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+            info!("出力しました: {}", path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
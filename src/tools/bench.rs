@@ -0,0 +1,284 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use colored::Colorize;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::jit::JitEngine;
+use crate::core::ast::{Node, Program};
+use crate::core::eir::ModuleBuilder;
+use crate::tools::runner;
+
+/// ベンチマーク関数に付ける属性（`#[bench] fn name() { ... }`）
+const BENCH_ATTRIBUTE: &str = "bench";
+/// ベンチマークをコンパイルする最適化レベル
+const BENCH_OPT_LEVEL: u8 = 3;
+/// ベースラインの保存先（ソースファイルのディレクトリからの相対パス）
+const BASELINE_DIR: &str = ".eidos/bench";
+
+/// ベンチマークの実行設定
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// 計測前に捨てる実行回数
+    pub warmup: usize,
+    /// 計測する実行回数
+    pub iterations: usize,
+    /// 名前にこの文字列を含むベンチマークだけを実行
+    pub filter: Option<String>,
+    /// 結果をこの名前のベースラインとして保存
+    pub save_baseline: Option<String>,
+    /// この名前のベースラインと比較
+    pub baseline: Option<String>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            warmup: 10,
+            iterations: 100,
+            filter: None,
+            save_baseline: None,
+            baseline: None,
+        }
+    }
+}
+
+/// 1つのベンチマークの計測結果（時間はナノ秒）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchStats {
+    pub iterations: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl BenchStats {
+    /// 1回ごとの実行時間から統計量を求める（標準偏差は標本標準偏差）
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let n = samples.len();
+        if n == 0 {
+            return Self { iterations: 0, mean: 0.0, median: 0.0, stddev: 0.0, min: 0.0, max: 0.0 };
+        }
+        
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let mean = sorted.iter().sum::<f64>() / n as f64;
+        let median = if n % 2 == 0 {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+        } else {
+            sorted[n / 2]
+        };
+        let variance = if n > 1 {
+            sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+        
+        Self {
+            iterations: n,
+            mean,
+            median,
+            stddev: variance.sqrt(),
+            min: sorted[0],
+            max: sorted[n - 1],
+        }
+    }
+    
+    /// ベースラインからの平均の変化
+    pub fn compare(&self, baseline: &BenchStats) -> Change {
+        let ratio = (self.mean - baseline.mean) / baseline.mean;
+        // 平均の差が標準誤差（ウェルチのt検定の分母）の2倍以内なら誤差とみなす
+        let standard_error = (self.stddev.powi(2) / self.iterations.max(1) as f64
+            + baseline.stddev.powi(2) / baseline.iterations.max(1) as f64)
+            .sqrt();
+        let significant = (self.mean - baseline.mean).abs() > 2.0 * standard_error;
+        
+        match (significant, ratio < 0.0) {
+            (false, _) => Change::NoChange(ratio),
+            (true, true) => Change::Improved(ratio),
+            (true, false) => Change::Regressed(ratio),
+        }
+    }
+}
+
+/// ベースラインと比べた変化（値は平均の変化率）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Improved(f64),
+    Regressed(f64),
+    NoChange(f64),
+}
+
+/// ファイル中の `#[bench]` 関数を実行して結果を表示
+pub fn bench_file(file: &Path, options: &BenchOptions) -> Result<()> {
+    info!("ベンチマークを実行中: {}", file.display());
+    
+    let ast = runner::parse_file(file)?;
+    let typed_ast = runner::check_program(ast)?;
+    
+    let names = bench_functions(&typed_ast, options.filter.as_deref());
+    if names.is_empty() {
+        println!("ベンチマーク関数がありません（`#[bench] fn name() {{ ... }}` で定義します）");
+        return Ok(());
+    }
+    
+    // トップレベルの文は実行せず、関数の定義だけを最適化してコンパイルする
+    let mut program = Program::new(typed_ast.file_path.clone());
+    for node in &typed_ast.nodes {
+        if matches!(&node.kind, Node::FunctionDef { name, .. } if name != "main") {
+            program.add_node(node.clone());
+        }
+    }
+    let module = ModuleBuilder::new(module_name(file)).build_from_ast(&program)?;
+    let mut engine = JitEngine::with_opt_level(BENCH_OPT_LEVEL)?;
+    engine.run(&module)?;
+    
+    let baseline = match &options.baseline {
+        Some(name) => Some(load_baseline(file, name)?),
+        None => None,
+    };
+    
+    let mut results = BTreeMap::new();
+    for name in names {
+        debug!("ベンチマーク {} を実行中", name);
+        for _ in 0..options.warmup {
+            engine.call(&name)?;
+        }
+        let mut samples = Vec::with_capacity(options.iterations);
+        for _ in 0..options.iterations {
+            let start = Instant::now();
+            engine.call(&name)?;
+            samples.push(start.elapsed().as_nanos() as f64);
+        }
+        
+        let stats = BenchStats::from_samples(&samples);
+        print_stats(&name, &stats, baseline.as_ref().and_then(|baseline| baseline.get(&name)));
+        results.insert(name, stats);
+    }
+    
+    if let Some(name) = &options.save_baseline {
+        let path = save_baseline(file, name, &results)?;
+        println!("ベースライン '{}' を保存しました: {}", name, path.display());
+    }
+    Ok(())
+}
+
+/// `#[bench]` の付いたトップレベル関数の名前（定義順）
+fn bench_functions(program: &Program, filter: Option<&str>) -> Vec<String> {
+    program
+        .nodes
+        .iter()
+        .filter_map(|node| match &node.kind {
+            Node::FunctionDef { name, attributes, .. } if attributes.iter().any(|a| a == BENCH_ATTRIBUTE) => Some(name.clone()),
+            _ => None,
+        })
+        .filter(|name| match filter {
+            Some(filter) => name.contains(filter),
+            None => true,
+        })
+        .collect()
+}
+
+fn module_name(file: &Path) -> String {
+    file.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "bench".to_string())
+}
+
+fn print_stats(name: &str, stats: &BenchStats, baseline: Option<&BenchStats>) {
+    println!(
+        "{} 平均 {} ± {}  中央値 {}  最小 {}  最大 {}  ({}回)",
+        format!("{:<24}", name).bold(),
+        format_nanos(stats.mean),
+        format_nanos(stats.stddev),
+        format_nanos(stats.median),
+        format_nanos(stats.min),
+        format_nanos(stats.max),
+        stats.iterations,
+    );
+    if let Some(baseline) = baseline {
+        let message = match stats.compare(baseline) {
+            Change::Improved(ratio) => format!("{:+.2}% 速くなりました", ratio * 100.0).green(),
+            Change::Regressed(ratio) => format!("{:+.2}% 遅くなりました", ratio * 100.0).red(),
+            Change::NoChange(ratio) => format!("{:+.2}% 有意な差はありません", ratio * 100.0).normal(),
+        };
+        println!("{:<24} ベースライン比: {}", "", message);
+    }
+}
+
+/// ナノ秒を読みやすい単位で表示
+fn format_nanos(nanos: f64) -> String {
+    if nanos < 1e3 {
+        format!("{:.0}ns", nanos)
+    } else if nanos < 1e6 {
+        format!("{:.2}µs", nanos / 1e3)
+    } else if nanos < 1e9 {
+        format!("{:.2}ms", nanos / 1e6)
+    } else {
+        format!("{:.2}s", nanos / 1e9)
+    }
+}
+
+fn baseline_path(file: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        bail!("ベースライン名に使えない文字が含まれています: {}", name);
+    }
+    let dir = file.parent().unwrap_or_else(|| Path::new("."));
+    Ok(dir.join(BASELINE_DIR).join(format!("{}.json", name)))
+}
+
+fn load_baseline(file: &Path, name: &str) -> Result<BTreeMap<String, BenchStats>> {
+    let path = baseline_path(file, name)?;
+    let text = fs::read_to_string(&path)
+        .context(format!("ベースライン '{}' がありません（`--save-baseline {}` で保存します）", name, name))?;
+    serde_json::from_str(&text).context(format!("ベースラインの形式が不正です: {}", path.display()))
+}
+
+fn save_baseline(file: &Path, name: &str, results: &BTreeMap<String, BenchStats>) -> Result<PathBuf> {
+    let path = baseline_path(file, name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context(format!("ディレクトリを作成できません: {}", dir.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(results)?)
+        .context(format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_stats_from_samples() {
+        let stats = BenchStats::from_samples(&[4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(stats.mean, 2.5);
+        assert_eq!(stats.median, 2.5);
+        assert_eq!((stats.min, stats.max), (1.0, 4.0));
+        assert!((stats.stddev - 1.2909944).abs() < 1e-6);
+        
+        assert_eq!(BenchStats::from_samples(&[7.0]).stddev, 0.0);
+    }
+    
+    #[test]
+    fn test_compare_with_baseline() {
+        let baseline = BenchStats::from_samples(&[100.0, 102.0, 98.0, 101.0, 99.0]);
+        let faster = BenchStats::from_samples(&[50.0, 51.0, 49.0, 50.0, 50.0]);
+        let noisy = BenchStats::from_samples(&[60.0, 140.0, 90.0, 110.0, 101.0]);
+        
+        assert!(matches!(faster.compare(&baseline), Change::Improved(ratio) if (ratio + 0.5).abs() < 1e-9));
+        assert!(matches!(baseline.compare(&faster), Change::Regressed(_)));
+        assert!(matches!(noisy.compare(&baseline), Change::NoChange(_)));
+    }
+    
+    #[test]
+    fn test_baseline_path() {
+        let path = baseline_path(Path::new("examples/fib.eid"), "main").unwrap();
+        assert_eq!(path, Path::new("examples/.eidos/bench/main.json"));
+        assert!(baseline_path(Path::new("fib.eid"), "../main").is_err());
+    }
+}
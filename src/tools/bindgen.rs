@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::info;
+use wasmtime::{Engine, ExternType, FuncType, Module, ValType};
+
+/// 生成するホスト側のコード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HostLanguage {
+    /// wasmtime の `Linker` にホスト関数を登録し、エクスポートを型付きで取り出すRustのコード
+    Rust,
+}
+
+/// WASIのインポートのモジュール名（Rustでは `wasmtime_wasi::add_to_linker` で登録するので、バインディングを作らない）
+const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+/// WebAssemblyの値の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WasmType {
+    I32,
+    I64,
+    F32,
+    F64,
+    V128,
+    FuncRef,
+    ExternRef,
+}
+
+impl WasmType {
+    fn rust(self) -> &'static str {
+        match self {
+            WasmType::I32 => "i32",
+            WasmType::I64 => "i64",
+            WasmType::F32 => "f32",
+            WasmType::F64 => "f64",
+            WasmType::V128 => "wasmtime::V128",
+            WasmType::FuncRef => "Option<wasmtime::Func>",
+            WasmType::ExternRef => "Option<wasmtime::ExternRef>",
+        }
+    }
+    
+    fn typescript(self) -> &'static str {
+        match self {
+            WasmType::I32 | WasmType::F32 | WasmType::F64 => "number",
+            WasmType::I64 => "bigint",
+            WasmType::FuncRef => "Function | null",
+            WasmType::V128 | WasmType::ExternRef => "unknown",
+        }
+    }
+}
+
+impl From<ValType> for WasmType {
+    fn from(ty: ValType) -> Self {
+        match ty {
+            ValType::I32 => WasmType::I32,
+            ValType::I64 => WasmType::I64,
+            ValType::F32 => WasmType::F32,
+            ValType::F64 => WasmType::F64,
+            ValType::V128 => WasmType::V128,
+            ValType::FuncRef => WasmType::FuncRef,
+            ValType::ExternRef => WasmType::ExternRef,
+        }
+    }
+}
+
+/// 関数のシグネチャ（WebAssemblyには引数の名前がないので、型だけを持つ）
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Signature {
+    params: Vec<WasmType>,
+    results: Vec<WasmType>,
+}
+
+impl From<&FuncType> for Signature {
+    fn from(ty: &FuncType) -> Self {
+        Self {
+            params: ty.params().map(WasmType::from).collect(),
+            results: ty.results().map(WasmType::from).collect(),
+        }
+    }
+}
+
+/// インポートする関数
+#[derive(Debug, Clone)]
+struct Import {
+    module: String,
+    name: String,
+    signature: Signature,
+}
+
+/// モジュールの外から見える関数とメモリ
+#[derive(Debug, Clone, Default)]
+struct Interface {
+    imports: Vec<Import>,
+    /// エクスポートする関数の名前とシグネチャ
+    exports: Vec<(String, Signature)>,
+    /// エクスポートするメモリの名前
+    memories: Vec<String>,
+}
+
+/// コンパイルしたWebAssemblyモジュールを埋め込むためのバインディングを生成
+///
+/// モジュールのインポートとエクスポートの関数のシグネチャを読み、`<モジュール名>.rs`（`--host rust`）と
+/// JavaScriptから使うときの型定義 `<モジュール名>.d.ts` を出力先のディレクトリに書き出す。
+pub fn bindgen(file: &Path, hosts: &[HostLanguage], output: Option<&Path>) -> Result<()> {
+    let bytes = fs::read(file).context(format!("ファイルの読み込みに失敗しました: {}", file.display()))?;
+    let interface = read_interface(&bytes).context(format!("WebAssemblyモジュールを読み込めません: {}", file.display()))?;
+    
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let source = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let dir = output.map(Path::to_path_buf).unwrap_or_else(|| file.parent().map(Path::to_path_buf).unwrap_or_default());
+    let mut outputs: Vec<(PathBuf, String)> = Vec::new();
+    for host in hosts {
+        match host {
+            HostLanguage::Rust => outputs.push((dir.join(format!("{}.rs", stem)), rust_bindings(&interface, &source))),
+        }
+    }
+    outputs.push((dir.join(format!("{}.d.ts", stem)), typescript_declarations(&interface, &source)));
+    
+    fs::create_dir_all(&dir).context(format!("ディレクトリを作成できません: {}", dir.display()))?;
+    for (path, contents) in outputs {
+        fs::write(&path, contents).context(format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+        info!("バインディングを出力しました: {}", path.display());
+    }
+    Ok(())
+}
+
+/// モジュールのインポートとエクスポートを読む（モジュール内の順序のまま）
+fn read_interface(bytes: &[u8]) -> Result<Interface> {
+    let module = Module::new(&Engine::default(), bytes)?;
+    let mut interface = Interface::default();
+    for import in module.imports() {
+        if let ExternType::Func(ty) = import.ty() {
+            interface.imports.push(Import {
+                module: import.module().to_string(),
+                name: import.name().to_string(),
+                signature: Signature::from(&ty),
+            });
+        }
+    }
+    for export in module.exports() {
+        match export.ty() {
+            ExternType::Func(ty) => interface.exports.push((export.name().to_string(), Signature::from(&ty))),
+            ExternType::Memory(_) => interface.memories.push(export.name().to_string()),
+            _ => {}
+        }
+    }
+    Ok(interface)
+}
+
+/// wasmtime に埋め込むためのRustのコード
+///
+/// インポートする関数（WASIを除く）を `Host` トレイトのメソッドにし、`add_to_linker` でそれを `Linker` に登録する。
+/// メソッドは `Caller` を受け取るので、実装からモジュールのメモリを読み書きできる。
+/// エクスポートする関数は `Exports` に型付きの `TypedFunc` として取り出す。
+fn rust_bindings(interface: &Interface, source: &str) -> String {
+    let host_imports: Vec<&Import> = interface.imports.iter().filter(|import| import.module != WASI_MODULE).collect();
+    let mut names = HashSet::new();
+    let methods: Vec<String> = host_imports
+        .iter()
+        .map(|import| {
+            let name = if import.module == "env" {
+                import.name.clone()
+            } else {
+                format!("{}_{}", import.module, import.name)
+            };
+            unique(rust_identifier(&name), &mut names)
+        })
+        .collect();
+    
+    let mut out = String::new();
+    let _ = writeln!(out, "// `eidos bindgen` が {} から生成したコードです。編集しないでください。", source);
+    out.push('\n');
+    let _ = writeln!(out, "use wasmtime::{{Caller, Instance, Linker, Store, TypedFunc}};");
+    out.push('\n');
+    
+    let _ = writeln!(out, "/// モジュールがインポートする関数の実装");
+    let _ = writeln!(out, "pub trait Host: Sized {{");
+    for (index, (import, method)) in host_imports.iter().zip(&methods).enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let params: Vec<String> = import.signature.params.iter().enumerate().map(|(i, ty)| format!(", a{}: {}", i, ty.rust())).collect();
+        let _ = writeln!(out, "    /// `{}.{}`", import.module, import.name);
+        let _ = writeln!(
+            out,
+            "    fn {}(caller: Caller<'_, Self>{}){};",
+            method,
+            params.concat(),
+            rust_return(&import.signature.results)
+        );
+    }
+    let _ = writeln!(out, "}}");
+    out.push('\n');
+    
+    let _ = writeln!(out, "/// `Host` の実装をモジュールのインポートとして登録する（WASIの関数は `wasmtime_wasi::add_to_linker` で登録する）");
+    let _ = writeln!(out, "pub fn add_to_linker<T: Host + 'static>(linker: &mut Linker<T>) -> wasmtime::Result<()> {{");
+    for (import, method) in host_imports.iter().zip(&methods) {
+        let params: Vec<String> = import.signature.params.iter().enumerate().map(|(i, ty)| format!(", a{}: {}", i, ty.rust())).collect();
+        let args: Vec<String> = (0..import.signature.params.len()).map(|i| format!(", a{}", i)).collect();
+        let _ = writeln!(
+            out,
+            "    linker.func_wrap({:?}, {:?}, |caller: Caller<'_, T>{}| T::{}(caller{}))?;",
+            import.module,
+            import.name,
+            params.concat(),
+            method,
+            args.concat()
+        );
+    }
+    if host_imports.is_empty() {
+        let _ = writeln!(out, "    let _ = linker;");
+    }
+    let _ = writeln!(out, "    Ok(())");
+    let _ = writeln!(out, "}}");
+    out.push('\n');
+    
+    let mut names = HashSet::new();
+    let fields: Vec<String> = interface.exports.iter().map(|(name, _)| unique(rust_identifier(name), &mut names)).collect();
+    let _ = writeln!(out, "/// モジュールがエクスポートする関数");
+    let _ = writeln!(out, "pub struct Exports {{");
+    for ((name, signature), field) in interface.exports.iter().zip(&fields) {
+        let _ = writeln!(out, "    /// `{}`", name);
+        let _ = writeln!(
+            out,
+            "    pub {}: TypedFunc<{}, {}>,",
+            field,
+            rust_tuple(&signature.params),
+            rust_tuple(&signature.results)
+        );
+    }
+    let _ = writeln!(out, "}}");
+    out.push('\n');
+    let _ = writeln!(out, "impl Exports {{");
+    let _ = writeln!(out, "    /// インスタンスからエクスポートを取り出す");
+    let _ = writeln!(out, "    pub fn new<T>(store: &mut Store<T>, instance: &Instance) -> wasmtime::Result<Self> {{");
+    if interface.exports.is_empty() {
+        let _ = writeln!(out, "        let _ = (store, instance);");
+    }
+    let _ = writeln!(out, "        Ok(Self {{");
+    for ((name, _), field) in interface.exports.iter().zip(&fields) {
+        let _ = writeln!(out, "            {}: instance.get_typed_func(&mut *store, {:?})?,", field, name);
+    }
+    let _ = writeln!(out, "        }})");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+    out
+}
+
+/// JavaScriptから使うときの型定義
+///
+/// `Imports` は `WebAssembly.instantiate` に渡すインポートのオブジェクト（WASIを含む）、
+/// `Exports` はインスタンスの `exports` の型。`i64` の値は `bigint` で受け渡す。
+fn typescript_declarations(interface: &Interface, source: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "// `eidos bindgen` が {} から生成した型定義です。編集しないでください。", source);
+    out.push('\n');
+    
+    let _ = writeln!(out, "/** `WebAssembly.instantiate` に渡すインポート */");
+    let _ = writeln!(out, "export interface Imports {{");
+    let mut modules: Vec<&str> = Vec::new();
+    for import in &interface.imports {
+        if !modules.contains(&import.module.as_str()) {
+            modules.push(&import.module);
+        }
+    }
+    for module in modules {
+        let _ = writeln!(out, "  {}: {{", typescript_property(module));
+        for import in interface.imports.iter().filter(|import| import.module == module) {
+            let _ = writeln!(out, "    {}{};", typescript_property(&import.name), typescript_signature(&import.signature));
+        }
+        let _ = writeln!(out, "  }};");
+    }
+    let _ = writeln!(out, "}}");
+    out.push('\n');
+    
+    let _ = writeln!(out, "/** インスタンスの `exports` */");
+    let _ = writeln!(out, "export interface Exports {{");
+    for memory in &interface.memories {
+        let _ = writeln!(out, "  {}: WebAssembly.Memory;", typescript_property(memory));
+    }
+    for (name, signature) in &interface.exports {
+        let _ = writeln!(out, "  {}{};", typescript_property(name), typescript_signature(signature));
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
+
+fn rust_return(results: &[WasmType]) -> String {
+    match results {
+        [] => String::new(),
+        _ => format!(" -> {}", rust_tuple(results)),
+    }
+}
+
+/// `TypedFunc` の引数・戻り値の型（1つなら型そのもの、それ以外はタプル）
+fn rust_tuple(types: &[WasmType]) -> String {
+    match types {
+        [ty] => ty.rust().to_string(),
+        _ => format!("({})", types.iter().map(|ty| ty.rust()).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+fn typescript_signature(signature: &Signature) -> String {
+    let params: Vec<String> = signature.params.iter().enumerate().map(|(i, ty)| format!("a{}: {}", i, ty.typescript())).collect();
+    let result = match signature.results.as_slice() {
+        [] => "void".to_string(),
+        [ty] => ty.typescript().to_string(),
+        results => format!("[{}]", results.iter().map(|ty| ty.typescript()).collect::<Vec<_>>().join(", ")),
+    };
+    format!("({}): {}", params.join(", "), result)
+}
+
+/// インポートやエクスポートの名前をRustの識別子にする（使えない文字は `_` に置き換え、予約語は生識別子にする）
+fn rust_identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for", "if",
+        "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct", "trait",
+        "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro", "override",
+        "priv", "try", "typeof", "unsized", "virtual", "yield",
+    ];
+    let mut ident: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        ident.insert_str(0, "r#");
+    }
+    ident
+}
+
+/// 置き換えで同じになった名前には番号を付けて区別する
+fn unique(name: String, names: &mut HashSet<String>) -> String {
+    let mut candidate = name.clone();
+    let mut index = 1;
+    while !names.insert(candidate.clone()) {
+        candidate = format!("{}_{}", name, index);
+        index += 1;
+    }
+    candidate
+}
+
+/// TypeScriptのプロパティ名（識別子にできない名前は文字列にする）
+fn typescript_property(name: &str) -> String {
+    let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const MODULE: &str = r#"(module
+        (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+        (import "env" "println" (func (param i32 i32)))
+        (import "host" "type" (func (param i64) (result i64)))
+        (memory (export "memory") 1)
+        (func (export "main"))
+        (func (export "add-pair") (param i64 i64) (result i64 f64)
+            local.get 0
+            local.get 1
+            i64.add
+            f64.const 0))"#;
+    
+    #[test]
+    fn test_rust_bindings() {
+        let interface = read_interface(MODULE.as_bytes()).unwrap();
+        let rust = rust_bindings(&interface, "app.wasm");
+        assert!(!rust.contains("proc_exit"), "{}", rust);
+        assert!(rust.contains("    fn println(caller: Caller<'_, Self>, a0: i32, a1: i32);"), "{}", rust);
+        assert!(rust.contains("    fn host_type(caller: Caller<'_, Self>, a0: i64) -> i64;"), "{}", rust);
+        assert!(
+            rust.contains(r#"linker.func_wrap("env", "println", |caller: Caller<'_, T>, a0: i32, a1: i32| T::println(caller, a0, a1))?;"#),
+            "{}",
+            rust
+        );
+        assert!(rust.contains("    pub main: TypedFunc<(), ()>,"), "{}", rust);
+        assert!(rust.contains("    pub add_pair: TypedFunc<(i64, i64), (i64, f64)>,"), "{}", rust);
+        assert!(rust.contains(r#"add_pair: instance.get_typed_func(&mut *store, "add-pair")?,"#), "{}", rust);
+    }
+    
+    #[test]
+    fn test_typescript_declarations() {
+        let interface = read_interface(MODULE.as_bytes()).unwrap();
+        let declarations = typescript_declarations(&interface, "app.wasm");
+        assert!(declarations.contains("  wasi_snapshot_preview1: {\n    proc_exit(a0: number): void;\n  };"), "{}", declarations);
+        assert!(declarations.contains("    type(a0: bigint): bigint;"), "{}", declarations);
+        assert!(declarations.contains("  memory: WebAssembly.Memory;"), "{}", declarations);
+        assert!(declarations.contains(r#"  "add-pair"(a0: bigint, a1: bigint): [bigint, number];"#), "{}", declarations);
+    }
+    
+    #[test]
+    fn test_rust_identifiers() {
+        assert_eq!(rust_identifier("type"), "r#type");
+        assert_eq!(rust_identifier("1st"), "_1st");
+        let mut names = HashSet::new();
+        assert_eq!(unique(rust_identifier("a-b"), &mut names), "a_b");
+        assert_eq!(unique(rust_identifier("a.b"), &mut names), "a_b_1");
+    }
+}
@@ -0,0 +1,474 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{debug, info};
+use colored::Colorize;
+
+use crate::core::error::{CommandFailed, EidosError};
+use crate::core::error_codes::{ErrorCode, ExitCode};
+use crate::core::session::CompileSession;
+use crate::core::SourceMap;
+use crate::frontend::IncrementalAnalysis;
+use crate::tools::deps::{self, SOURCE_EXTENSION};
+use crate::tools::project::MANIFEST_FILES;
+use crate::tools::{compiler, ice, output, watch};
+
+/// ディレクトリを探すときに飛ばすディレクトリ名（`.` で始まるものも飛ばす）
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// 診断の重大度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// 検査で見つかった1つの問題
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    /// エラー全体の表示（位置を含む）
+    pub message: String,
+    /// 問題のあるファイル（位置の分からないエラーは検査したファイル）
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// この問題を見つけた検査対象のファイル（問題のあるファイルを参照しているファイルを含む）
+    pub reported_by: Vec<PathBuf>,
+}
+
+/// 複数のファイルの診断をファイルごとにまとめる
+///
+/// 同じ位置の同じエラーは、別のファイルの検査で見つかっても1つにまとめて参照元を記録する。
+/// 検査の途中で別のファイルの構文エラーが見つかった場合、その検査の残りのエラーは連鎖したものとして捨てる。
+#[derive(Debug, Default)]
+pub struct DiagnosticIndex {
+    /// 検査したファイル（検査した順）
+    files: Vec<PathBuf>,
+    diagnostics: Vec<Diagnostic>,
+    /// 正規化したパスから表示に使うパスへの対応
+    display_paths: HashMap<PathBuf, PathBuf>,
+    /// 診断の位置の行を表示するためのソース
+    sources: SourceMap,
+}
+
+impl DiagnosticIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// 検査で読み込んだソースを共有して、診断の位置の行を表示する
+    pub fn with_sources(sources: SourceMap) -> Self {
+        Self { sources, ..Self::default() }
+    }
+    
+    /// 検査したファイルを登録
+    pub fn add_file(&mut self, file: &Path) {
+        let file = self.display_path(file);
+        if !self.files.contains(&file) {
+            self.files.push(file);
+        }
+    }
+    
+    /// `checked` の検査で見つかったエラーをまとめて登録
+    pub fn add_errors(&mut self, checked: &Path, errors: Vec<EidosError>) {
+        let checked = self.display_path(checked);
+        let mut positions = Vec::new();
+        for error in errors {
+            let (file, line, column) = match error_position(&error) {
+                Some((file, line, column)) => (self.display_path(&file), line, column),
+                None => (checked.clone(), 0, 0),
+            };
+            // 同じ検査で同じ位置に出たエラーは最初のものだけ残す
+            if line != 0 && positions.contains(&(file.clone(), line, column)) {
+                continue;
+            }
+            positions.push((file.clone(), line, column));
+            
+            let message = error.to_string();
+            let is_syntax = matches!(error, EidosError::Lexer { .. } | EidosError::Parser { .. });
+            self.add(Diagnostic {
+                severity: Severity::Error,
+                code: error.code(),
+                message,
+                file: file.clone(),
+                line,
+                column,
+                reported_by: vec![checked.clone()],
+            });
+            if is_syntax && file != checked {
+                debug!("{} の構文エラーより後の {} のエラーを省略", file.display(), checked.display());
+                break;
+            }
+        }
+    }
+    
+    fn add(&mut self, diagnostic: Diagnostic) {
+        let existing = self.diagnostics.iter_mut().find(|existing| {
+            existing.file == diagnostic.file
+                && existing.line == diagnostic.line
+                && existing.column == diagnostic.column
+                && existing.message == diagnostic.message
+        });
+        match existing {
+            Some(existing) => {
+                for file in diagnostic.reported_by {
+                    if !existing.reported_by.contains(&file) {
+                        existing.reported_by.push(file);
+                    }
+                }
+            }
+            None => {
+                if !self.files.contains(&diagnostic.file) {
+                    self.files.push(diagnostic.file.clone());
+                }
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+    
+    /// 同じファイルを指すパスを最初に見たときの表記にそろえる
+    fn display_path(&mut self, path: &Path) -> PathBuf {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.display_paths.entry(key).or_insert_with(|| path.to_path_buf()).clone()
+    }
+    
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    
+    pub fn error_count(&self) -> usize {
+        self.count(None, Severity::Error)
+    }
+    
+    fn count(&self, file: Option<&Path>, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity && file.is_none_or(|file| diagnostic.file == file))
+            .count()
+    }
+    
+    /// 診断を1件ずつ、参照元のファイルとともに文字列にする
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for diagnostic in &self.diagnostics {
+            let label = match diagnostic.severity {
+                Severity::Error => "エラー",
+                Severity::Warning => "警告",
+            };
+            let _ = writeln!(out, "{}[{}]: {}", label, diagnostic.code, diagnostic.message);
+            if let Some(snippet) = self.snippet(diagnostic) {
+                out.push_str(&snippet);
+            }
+            let referrers: Vec<String> = diagnostic
+                .reported_by
+                .iter()
+                .filter(|file| **file != diagnostic.file)
+                .map(|file| file.display().to_string())
+                .collect();
+            if !referrers.is_empty() {
+                let _ = writeln!(out, "  = 参照元の検査でも検出: {}", referrers.join(", "));
+            }
+        }
+        out
+    }
+    
+    /// 診断の位置の行と、列を指す印（ファイルを読めない場合や位置の分からない診断は `None`）
+    fn snippet(&self, diagnostic: &Diagnostic) -> Option<String> {
+        if diagnostic.line == 0 {
+            return None;
+        }
+        // ディスク上のファイルは変更されていれば読み直す（メモリ上のソースは登録済みのもの）
+        let file = self.sources.load(&diagnostic.file).ok().or_else(|| self.sources.find(&diagnostic.file))?;
+        let text = file.line(diagnostic.line)?;
+        // 印の前はタブをそのまま残し、ほかの文字を空白にして列をそろえる
+        let indent: String = text
+            .chars()
+            .take(diagnostic.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let number = diagnostic.line.to_string();
+        let gutter = " ".repeat(number.len());
+        Some(format!("{} |\n{} | {}\n{} | {}^\n", gutter, number, text, gutter, indent))
+    }
+    
+    /// 診断を1件ずつ1行のJSONにする（`--json`）
+    pub fn render_json(&self) -> String {
+        let mut out = String::new();
+        for diagnostic in &self.diagnostics {
+            let reported_by: Vec<String> = diagnostic.reported_by.iter().map(|file| file.display().to_string()).collect();
+            let _ = writeln!(
+                out,
+                "{}",
+                serde_json::json!({
+                    "type": "diagnostic",
+                    "severity": diagnostic.severity.name(),
+                    "code": diagnostic.code.to_string(),
+                    "message": diagnostic.message,
+                    "file": diagnostic.file.display().to_string(),
+                    "line": diagnostic.line,
+                    "column": diagnostic.column,
+                    "reported_by": reported_by,
+                })
+            );
+        }
+        out
+    }
+    
+    /// 診断から決まる終了コード（最も重大なエラーのもの）
+    pub fn exit_code(&self) -> ExitCode {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| diagnostic.code.exit_code())
+            .max()
+            .unwrap_or(ExitCode::Success)
+    }
+    
+    /// ファイルごとのエラーと警告の数を表形式の文字列にする
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "==== ファイルごとの診断 ====");
+        let _ = writeln!(out, "{:>6} {:>6}  ファイル", "エラー", "警告");
+        for file in &self.files {
+            let errors = self.count(Some(file), Severity::Error);
+            let warnings = self.count(Some(file), Severity::Warning);
+            let _ = writeln!(out, "{:>9} {:>8}  {}", errors, warnings, file.display());
+        }
+        let _ = writeln!(
+            out,
+            "{:>9} {:>8}  合計（{}ファイル）",
+            self.error_count(),
+            self.count(None, Severity::Warning),
+            self.files.len()
+        );
+        out
+    }
+}
+
+/// エラーの位置（ファイル、行、列）
+fn error_position(error: &EidosError) -> Option<(PathBuf, usize, usize)> {
+    match error {
+        EidosError::Lexer { file, line, column, .. }
+        | EidosError::Parser { file, line, column, .. }
+        | EidosError::Semantic { file, line, column, .. } => Some((file.clone(), *line, *column)),
+        EidosError::Type { location, .. } if location.line != 0 => {
+            Some((location.file.clone(), location.line, location.column))
+        }
+        _ => None,
+    }
+}
+
+/// ファイル、ディレクトリ、またはマニフェストの置かれたプロジェクトを検査
+///
+/// ディレクトリの場合は中のすべての `.eid` ファイルを、ファイルの場合はそのファイルを起点に、
+/// `import` / `use` / `mod` で参照されるファイルもあわせて検査し、診断をまとめて表示する。
+/// 複数のファイルを検査した場合は、ファイルごとのエラーと警告の数の表も表示する。
+///
+/// エラーが見つかった場合は、入力のプログラムのエラーなら `ExitCode::ProgramError`、
+/// コンパイラ内部のエラーを含めば `ExitCode::InternalError` の終了コードで失敗する。
+pub fn check(path: &Path, trace_macros: bool, time_passes: bool) -> Result<()> {
+    let files = project_files(path)?;
+    info!("{}個のファイルを検査します", files.len());
+    
+    let session = CompileSession::new(time_passes);
+    let mut index = DiagnosticIndex::with_sources(session.sources().clone());
+    for file in &files {
+        index.add_file(file);
+        let errors = ice::catch(file, &session, || compiler::check_file(file, trace_macros, &session), |candidate, session| {
+            let _ = compiler::check_file(candidate, trace_macros, session);
+        })?;
+        index.add_errors(file, errors);
+    }
+    session.print_report();
+    
+    if output::is_json() {
+        eprint!("{}", index.render_json());
+    } else {
+        eprint!("{}", index.render());
+        if files.len() > 1 && !output::is_quiet() {
+            eprint!("{}", index.summary());
+        }
+        
+        let mut codes: Vec<ErrorCode> = index.diagnostics().iter().map(|diagnostic| diagnostic.code).collect();
+        codes.sort();
+        codes.dedup();
+        if !codes.is_empty() {
+            let codes: Vec<String> = codes.iter().map(ToString::to_string).collect();
+            eprintln!("エラーの詳細は `eidos explain <コード>` で確認できます（{}）", codes.join(", "));
+        }
+    }
+    
+    match index.error_count() {
+        0 => {
+            output::status(format!("{}個のファイルにエラーはありません", files.len()).green());
+            Ok(())
+        }
+        errors => Err(CommandFailed {
+            message: format!("{}個のファイルを検査し、{}件のエラーが見つかりました", files.len(), errors),
+            exit_code: index.exit_code(),
+        }
+        .into()),
+    }
+}
+
+/// `check --watch`: ファイルを監視し、変更のたびに検査し直して診断を表示する
+///
+/// 前回の検査の結果を残しておき、型検査は変更の影響を受けた項目（トップレベルの関数など）だけやり直す。
+pub fn check_watch(file: &Path, trace_macros: bool) -> Result<()> {
+    if !file.is_file() {
+        return Err(anyhow!("--watch で検査できるのはファイルだけです: {}", file.display()));
+    }
+    
+    let session = CompileSession::new(false);
+    let mut analysis = IncrementalAnalysis::new();
+    watch::watch(file, || -> Result<()> {
+        let errors = compiler::check_file_incremental(file, trace_macros, &session, &mut analysis)?;
+        let mut index = DiagnosticIndex::with_sources(session.sources().clone());
+        index.add_file(file);
+        index.add_errors(file, errors);
+        eprint!("{}", index.render());
+        match index.error_count() {
+            0 => Ok(()),
+            errors => Err(anyhow!("{}件のエラーが見つかりました", errors)),
+        }
+    })
+}
+
+/// 検査するファイル（起点のファイルを先に、参照されるファイルを後に並べる）
+fn project_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let root = if path.file_name().is_some_and(|name| MANIFEST_FILES.iter().any(|manifest| name == *manifest)) {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    } else {
+        path.to_path_buf()
+    };
+    
+    let roots = if root.is_dir() {
+        let mut roots = Vec::new();
+        collect_sources(&root, &mut roots)?;
+        roots.sort();
+        roots
+    } else {
+        vec![root]
+    };
+    
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut seen = Vec::new();
+    for file in roots.iter().cloned().chain(roots.iter().flat_map(|root| deps::source_files(root))) {
+        let key = fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+        if !seen.contains(&key) {
+            seen.push(key);
+            files.push(file);
+        }
+    }
+    if files.is_empty() {
+        return Err(anyhow!("{} に .{} ファイルがありません", path.display(), SOURCE_EXTENSION));
+    }
+    Ok(files)
+}
+
+/// ディレクトリ以下の `.eid` ファイルを集める
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                collect_sources(&path, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == SOURCE_EXTENSION) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SourceLocation;
+    
+    fn type_error(file: &str, line: usize, message: &str) -> EidosError {
+        EidosError::Type {
+            message: message.to_string(),
+            location: SourceLocation::new(PathBuf::from(file), line, 1, 1),
+        }
+    }
+    
+    #[test]
+    fn test_duplicate_errors_are_merged_across_files() {
+        let mut index = DiagnosticIndex::new();
+        index.add_file(Path::new("main.eid"));
+        index.add_errors(Path::new("main.eid"), vec![type_error("util.eid", 3, "型が違います")]);
+        index.add_file(Path::new("util.eid"));
+        index.add_errors(
+            Path::new("util.eid"),
+            vec![type_error("util.eid", 3, "型が違います"), type_error("util.eid", 3, "続けて出たエラー")],
+        );
+        
+        assert_eq!(index.error_count(), 1);
+        let diagnostic = &index.diagnostics()[0];
+        assert_eq!(diagnostic.reported_by, vec![PathBuf::from("main.eid"), PathBuf::from("util.eid")]);
+        assert!(index.render().contains("参照元の検査でも検出: main.eid"));
+        
+        let summary = index.summary();
+        assert!(summary.contains("        0        0  main.eid"));
+        assert!(summary.contains("        1        0  util.eid"));
+        assert!(summary.contains("合計（2ファイル）"));
+        
+        let json: serde_json::Value = serde_json::from_str(index.render_json().lines().next().unwrap()).unwrap();
+        assert_eq!(json["code"], "E0004");
+        assert_eq!(json["line"], 3);
+        assert_eq!(json["reported_by"], serde_json::json!(["main.eid", "util.eid"]));
+        assert_eq!(index.exit_code(), ExitCode::ProgramError);
+        
+        index.add_errors(Path::new("util.eid"), vec![EidosError::Internal("型IDが見つかりません".to_string())]);
+        assert_eq!(index.exit_code(), ExitCode::InternalError);
+    }
+    
+    #[test]
+    fn test_errors_after_imported_syntax_error_are_dropped() {
+        let mut index = DiagnosticIndex::new();
+        let syntax_error = EidosError::Parser {
+            message: "式を解析できません".to_string(),
+            file: PathBuf::from("util.eid"),
+            line: 2,
+            column: 5,
+        };
+        index.add_errors(Path::new("main.eid"), vec![syntax_error, type_error("main.eid", 7, "未定義の関数です")]);
+        
+        assert_eq!(index.error_count(), 1);
+        assert_eq!(index.diagnostics()[0].file, PathBuf::from("util.eid"));
+    }
+    
+    #[test]
+    fn test_render_shows_source_line() {
+        let sources = SourceMap::new();
+        sources.add(PathBuf::from("main.eid"), "fn main() {\n\tlet 値 = x + 1;\n}\n".to_string());
+        let mut index = DiagnosticIndex::with_sources(sources);
+        let error = EidosError::Semantic {
+            message: "未定義の変数 'x'".to_string(),
+            file: PathBuf::from("main.eid"),
+            line: 2,
+            column: 10,
+        };
+        index.add_errors(Path::new("main.eid"), vec![error, type_error("other.eid", 4, "型が違います")]);
+        
+        let rendered = index.render();
+        assert!(rendered.contains("  |\n2 | \tlet 値 = x + 1;\n  | \t        ^\n"), "{}", rendered);
+        // ソースを読めない診断は行を表示しない
+        assert_eq!(rendered.matches('^').count(), 1, "{}", rendered);
+    }
+}
@@ -1,22 +1,49 @@
-use anyhow::{Result, Context};
+use anyhow::{bail, Result, Context};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::time::Instant;
 use log::{info, debug, warn, error};
 use colored::Colorize;
 
-use crate::core::error::{EidosError, SourceError, ErrorCollector};
-use crate::frontend::lexer::Lexer;
+use crate::core::error::{CommandFailed, EidosError, SourceError, ErrorCollector};
+use crate::frontend::lexer::{ConfusableChecker, Lexer};
 use crate::frontend::parser::Parser;
+use crate::frontend::const_eval::ConstEvaluator;
+use crate::frontend::macro_expander::MacroExpander;
 use crate::frontend::semantic_analyzer::SemanticAnalyzer;
 use crate::frontend::type_checker::TypeChecker;
+use crate::frontend::incremental::{self, IncrementalAnalysis};
 use crate::core::ast::Program;
-use crate::backend::codegen::CodeGenerator;
+use crate::core::coverage;
+use crate::core::dead_functions;
+use crate::core::induction;
+use crate::core::loop_fusion;
+use crate::core::range_analysis;
+use crate::core::size_opt::{self, SizeReport};
+use crate::core::eir::{self, Module, ModuleBuilder};
+use crate::core::eir_linker;
+use crate::core::eirc;
+use crate::core::ExitCode;
+use crate::core::session::CompileSession;
+use crate::core::source_map::SourceFile;
+use crate::backend::codegen::{self, CodeGenerator, CodegenOptions, LtoMode, MachineOptions, OutputFormat};
+use crate::backend::c_emitter::{CRuntime, FreestandingRuntime, HostedRuntime};
+use crate::backend::js::JsBackend;
+use crate::dsl::DSLProcessor;
+use crate::tools::{deps, ice, output};
 
 /// コンパイルオプション
 #[derive(Debug, Clone)]
 pub struct CompileOptions {
     /// 最適化レベル (0-3)
     pub opt_level: u8,
+    /// 速度向けのパスの代わりにコードサイズを小さくするパスを実行するか（インライン化やループの展開もしない）
+    pub optimize_size: bool,
+    /// 最適化パスごとのコードサイズの増減を表示するか
+    pub opt_stats: bool,
+    /// `main` から到達できずに取り除いた関数を表示するか
+    pub print_dead_functions: bool,
     /// デバッグ情報を含めるか
     pub debug_info: bool,
     /// 出力ファイルのパス
@@ -27,23 +54,60 @@ pub struct CompileOptions {
     pub verbose: bool,
     /// ターゲットバックエンド
     pub target: CompileTarget,
+    /// マクロ展開の過程を表示するか
+    pub trace_macros: bool,
+    /// Cバックエンドが使うランタイム
+    pub c_runtime: CRuntimeKind,
+    /// フェーズごとの所要時間とメモリを表示するか
+    pub time_passes: bool,
+    /// 出力を2回生成して一致するか検査するか
+    pub reproducible: bool,
+    /// EIRを構築した後と最適化パスの合間にEIRを検証するか（デバッグビルドでは常に検証する）
+    pub verify_ir: bool,
+    /// 基本ブロックと条件分岐にカバレッジ計測のカウンタを挿入するか（C・JS・EIRのターゲットのみ）
+    pub coverage: bool,
+    /// 生成したコードを実行するマシンの設定（ネイティブとLLVM IRのターゲットのみ）
+    pub machine: MachineOptions,
+    /// 入力ファイルのモジュールと一緒に出力する、事前にコンパイルしたEIRモジュール（`.eirc`）
+    pub link: Vec<PathBuf>,
+    /// リンク時最適化の方式
+    pub lto: Option<LtoMode>,
+    /// リントごとの扱い（指定のないリントは `LintLevel::Warn`）
+    pub lints: HashMap<String, LintLevel>,
+    /// `assert` と `require`・`ensure` の契約を実行時に検査するか（-O2 以上では常に真と分かった検査を取り除く）
+    pub check_assertions: bool,
 }
 
 impl Default for CompileOptions {
     fn default() -> Self {
         Self {
             opt_level: 2,
+            optimize_size: false,
+            opt_stats: false,
+            print_dead_functions: false,
             debug_info: false,
             output_path: None,
             run_after_compile: false,
             verbose: false,
             target: CompileTarget::Native,
+            trace_macros: false,
+            c_runtime: CRuntimeKind::Hosted,
+            time_passes: false,
+            reproducible: false,
+            verify_ir: false,
+            coverage: false,
+            machine: MachineOptions::default(),
+            link: Vec::new(),
+            lto: None,
+            lints: HashMap::new(),
+            check_assertions: true,
         }
     }
 }
 
 /// コンパイルターゲット
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CompileTarget {
     /// ネイティブバイナリ (デフォルト)
     Native,
@@ -53,6 +117,45 @@ pub enum CompileTarget {
     WASM,
     /// C言語コード
     C,
+    /// JavaScript（ESモジュール）
+    JS,
+    /// EIRのテキスト表現
+    EIR,
+    /// EIRのバイナリ表現（`.eirc`）。後のビルドで、フロントエンドを通さずにコード生成の入力にできる
+    Eirc,
+    /// 参照するソースファイルの一覧（Makefile形式の `.d` ファイルとコンパイルデータベース）
+    Deps,
+}
+
+/// リントの警告の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// 警告しない
+    Allow,
+    /// 警告を表示する
+    #[default]
+    Warn,
+    /// エラーにしてビルドを失敗させる
+    Deny,
+}
+
+/// Cバックエンドのランタイム
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CRuntimeKind {
+    /// 標準Cライブラリを使うホスト環境
+    Hosted,
+    /// libcのない組み込み環境（出力は `eidos_putchar` 経由）
+    Freestanding,
+}
+
+impl CRuntimeKind {
+    fn runtime(self) -> Box<dyn CRuntime> {
+        match self {
+            CRuntimeKind::Hosted => Box::new(HostedRuntime),
+            CRuntimeKind::Freestanding => Box::new(FreestandingRuntime::new()),
+        }
+    }
 }
 
 /// コンパイル結果の統計情報
@@ -70,58 +173,67 @@ pub struct CompileStats {
     pub ast_nodes: usize,
 }
 
-/// ファイルをコンパイル
-pub fn compile_file(file: &Path, opt_level: u8, output: Option<PathBuf>) -> Result<()> {
-    let options = CompileOptions {
-        opt_level,
-        output_path: output,
-        ..Default::default()
-    };
-    
-    compile_with_options(file, &options)
-}
-
 /// 詳細なオプションでファイルをコンパイル
 pub fn compile_with_options(file: &Path, options: &CompileOptions) -> Result<()> {
+    let session = Rc::new(CompileSession::new(options.time_passes));
+    // パニックした場合は不具合の報告にまとめる（入力を縮小するときの出力は一時ディレクトリに書き出す）
+    let result = ice::catch(file, &session, || compile_in_session(file, options, &session), |candidate, session| {
+        let options = CompileOptions {
+            output_path: Some(candidate.with_extension("out")),
+            verbose: false,
+            ..options.clone()
+        };
+        let _ = compile_in_session(candidate, &options, session);
+    });
+    // 失敗した場合も、そこまでの内訳を表示する
+    session.print_report();
+    result
+}
+
+fn compile_in_session(file: &Path, options: &CompileOptions, session: &Rc<CompileSession>) -> Result<()> {
     let start_time = Instant::now();
     info!("コンパイル開始: {}", file.display());
     debug!("コンパイルオプション: {:?}", options);
     
-    // エラーコレクタ
-    let mut error_collector = ErrorCollector::new();
-    
-    // ソースコードの読み込み
-    let source = std::fs::read_to_string(file)
-        .context(format!("ファイルの読み込みに失敗しました: {}", file.display()))?;
-    
-    // コンパイルプロセス
-    let ast = match parse_source(&source, file, &mut error_collector) {
-        Ok(ast) => ast,
-        Err(e) => {
-            error!("構文解析エラー: {}", e);
-            return Err(e.into());
-        }
-    };
-    
-    // 意味解析
-    let analyzer = SemanticAnalyzer::new();
-    if let Err(e) = analyzer.analyze(&ast) {
-        error_collector.add(e);
+    // 依存関係は `import` の字句だけから分かるので、検査の前に出力する
+    if options.target == CompileTarget::Deps {
+        return emit_deps(file, options, session, start_time);
     }
     
-    // 型検査
-    let type_checker = TypeChecker::new();
-    if let Err(e) = type_checker.check_program(&ast) {
-        error_collector.add(e);
+    if options.coverage && !matches!(options.target, CompileTarget::C | CompileTarget::JS | CompileTarget::EIR) {
+        bail!("--coverage は c、js、eir のターゲットでだけ使えます（指定されたターゲット: {:?}）", options.target);
+    }
+    if options.machine != MachineOptions::default() && !matches!(options.target, CompileTarget::Native | CompileTarget::LLVM) {
+        bail!(
+            "--codegen、--relocation-model、--code-model は native と llvm のターゲットでだけ使えます（指定されたターゲット: {:?}）",
+            options.target
+        );
+    }
+    if (!options.link.is_empty() || options.lto.is_some()) && matches!(options.target, CompileTarget::Native | CompileTarget::WASM) {
+        bail!("--link と --lto はEIRを経由するターゲット（c、js、llvm、eir、eirc）でだけ使えます（指定されたターゲット: {:?}）", options.target);
     }
     
-    // エラーがある場合は終了
-    if error_collector.has_errors() {
-        if let Some(error) = error_collector.into_error() {
-            error!("コンパイルエラー: {}", error);
-            return Err(error.into());
-        }
+    // 事前にコンパイルしたEIR（`.eirc`）は、構文解析と型チェックをせずにそのままコード生成の入力にする
+    let source = if file.extension().is_some_and(|ext| ext == eirc::EXTENSION) {
+        let module = session
+            .time("EIRの読み込み", || eirc::read(file))
+            .context(format!("EIRモジュールを読み込めません: {}", file.display()))?;
+        Source::Precompiled(module)
+    } else {
+        Source::Ast(analyze_file(file, options.trace_macros, session)?)
+    };
+    
+    match options.target {
+        CompileTarget::C => return emit_c(file, &source, options, session, start_time),
+        CompileTarget::JS => return emit_js(file, &source, options, session, start_time),
+        CompileTarget::LLVM => return emit_llvm_ir(file, &source, options, session, start_time),
+        CompileTarget::EIR => return emit_eir(file, &source, options, session, start_time),
+        CompileTarget::Eirc => return emit_eirc(file, &source, options, session, start_time),
+        _ => {}
     }
+    let Source::Ast(ast) = source else {
+        bail!("ネイティブバイナリは .eirc から生成できません（--emit c などを指定してください）");
+    };
     
     // コード生成
     let output_path = options.output_path.clone().unwrap_or_else(|| {
@@ -130,14 +242,25 @@ pub fn compile_with_options(file: &Path, options: &CompileOptions) -> Result<()>
     });
     
     let generator = CodeGenerator::new(options.opt_level);
-    generator.generate(&ast, &output_path)
+    session
+        .time("コード生成", || generator.generate(&ast, &output_path))
         .context("コード生成に失敗しました")?;
+    if options.reproducible {
+        // ネイティブバイナリはファイルに直接書き出されるので、一時ファイルにもう一度生成して比べる
+        let second_path = tempfile::NamedTempFile::new()?.into_temp_path();
+        session
+            .time("再現性の検査", || generator.generate(&ast, &second_path))
+            .context("コード生成に失敗しました")?;
+        let first = std::fs::read(&output_path)?;
+        let second = std::fs::read(&second_path)?;
+        check_reproducible(&[(output_path.clone(), first)], &[(output_path.clone(), second)])?;
+    }
     
     // 統計情報
     let elapsed = start_time.elapsed();
     info!("コンパイル完了: {} ({:?})", output_path.display(), elapsed);
     
-    if options.verbose {
+    if options.verbose && !output::is_quiet() {
         let stats = CompileStats {
             compile_time_ms: elapsed.as_millis(),
             code_size: std::fs::metadata(&output_path).map(|m| m.len() as usize).unwrap_or(0),
@@ -152,19 +275,399 @@ pub fn compile_with_options(file: &Path, options: &CompileOptions) -> Result<()>
     Ok(())
 }
 
-/// ファイルの型チェックのみ行う
-pub fn typecheck_file(file: &Path) -> Result<()> {
-    info!("型チェック開始: {}", file.display());
+/// EIRを経由してC99ソースコードを出力
+fn emit_c(file: &Path, source: &Source, options: &CompileOptions, session: &Rc<CompileSession>, start_time: Instant) -> Result<()> {
+    let output_path = options.output_path.clone().unwrap_or_else(|| file.with_extension("c"));
+    let codegen_options = CodegenOptions {
+        format: OutputFormat::C,
+        opt_level: options.opt_level,
+        debug_info: options.debug_info,
+        sources: Some(session.sources().clone()),
+        ..Default::default()
+    };
+    let mut generator = CodeGenerator::new_c(options.c_runtime.runtime()).with_session(Rc::clone(session));
+    let outputs = generate_outputs(file, source, options, session, |module| {
+        let code = generator.generate_code(module, &codegen_options).context("Cコードの生成に失敗しました")?;
+        Ok(vec![(output_path.clone(), code)])
+    })?;
+    write_outputs(&outputs, session)?;
+    
+    info!("Cコードを出力しました: {} ({:?})", output_path.display(), start_time.elapsed());
+    Ok(())
+}
+
+/// EIRを経由してESモジュールと、対応するTypeScriptの型定義を出力
+///
+/// デバッグ情報を含めるときは、元のソースとの対応を表すソースマップ（`.mjs.map`）も出力する。
+fn emit_js(file: &Path, source: &Source, options: &CompileOptions, session: &Rc<CompileSession>, start_time: Instant) -> Result<()> {
+    let output_path = options.output_path.clone().unwrap_or_else(|| file.with_extension("mjs"));
+    let declarations_path = output_path.with_extension("d.mts");
+    let map_path = PathBuf::from(format!("{}.map", output_path.display()));
+    
+    let backend = JsBackend::new();
+    let outputs = generate_outputs(file, source, options, session, |module| {
+        session.time("コード生成", || -> Result<Outputs> {
+            let declarations = backend.emit_declarations(module).context("型定義の生成に失敗しました")?;
+            let mut outputs = vec![(declarations_path.clone(), declarations.into_bytes())];
+            if options.debug_info {
+                let map_file = map_path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                let (source, map) = backend
+                    .emit_with_source_map(module, session.sources(), &map_file)
+                    .context("JavaScriptの生成に失敗しました")?;
+                outputs.insert(0, (output_path.clone(), source.into_bytes()));
+                outputs.push((map_path.clone(), map.into_bytes()));
+            } else {
+                let source = backend.emit(module).context("JavaScriptの生成に失敗しました")?;
+                outputs.insert(0, (output_path.clone(), source.into_bytes()));
+            }
+            Ok(outputs)
+        })
+    })?;
+    write_outputs(&outputs, session)?;
+    
+    info!("JavaScriptを出力しました: {} ({:?})", output_path.display(), start_time.elapsed());
+    Ok(())
+}
+
+/// EIRを経由してLLVM IRのテキストを出力（リンク時最適化ではビットコードを出力）
+fn emit_llvm_ir(file: &Path, source: &Source, options: &CompileOptions, session: &Rc<CompileSession>, start_time: Instant) -> Result<()> {
+    let (format, extension) = match options.lto {
+        Some(_) => (OutputFormat::Bitcode, "bc"),
+        None => (OutputFormat::LLVMIR, "ll"),
+    };
+    let output_path = options.output_path.clone().unwrap_or_else(|| file.with_extension(extension));
+    let codegen_options = CodegenOptions {
+        format,
+        opt_level: options.opt_level,
+        debug_info: options.debug_info,
+        lto: options.lto,
+        machine: options.machine.clone(),
+        ..Default::default()
+    };
+    let mut generator = CodeGenerator::new_llvm().with_session(Rc::clone(session));
+    let outputs = generate_outputs(file, source, options, session, |module| {
+        let code = generator.generate_code(module, &codegen_options).context("LLVM IRの生成に失敗しました")?;
+        Ok(vec![(output_path.clone(), code)])
+    })?;
+    write_outputs(&outputs, session)?;
+    
+    info!("LLVM IRを出力しました: {} ({:?})", output_path.display(), start_time.elapsed());
+    Ok(())
+}
+
+/// 構築したEIRをテキストで出力（スナップショットテストやデバッグ用）
+fn emit_eir(file: &Path, source: &Source, options: &CompileOptions, session: &Rc<CompileSession>, start_time: Instant) -> Result<()> {
+    let output_path = options.output_path.clone().unwrap_or_else(|| file.with_extension("eir"));
+    let outputs = generate_outputs(file, source, options, session, |module| {
+        Ok(vec![(output_path.clone(), module.to_string().into_bytes())])
+    })?;
+    write_outputs(&outputs, session)?;
+    
+    info!("EIRを出力しました: {} ({:?})", output_path.display(), start_time.elapsed());
+    Ok(())
+}
+
+/// 構築したEIRをバイナリ（`.eirc`）で出力（分割コンパイル用）
+fn emit_eirc(file: &Path, source: &Source, options: &CompileOptions, session: &Rc<CompileSession>, start_time: Instant) -> Result<()> {
+    let output_path = options.output_path.clone().unwrap_or_else(|| file.with_extension(eirc::EXTENSION));
+    let outputs = generate_outputs(file, source, options, session, |module| {
+        let bytes = eirc::encode(module).context("EIRの直列化に失敗しました")?;
+        Ok(vec![(output_path.clone(), bytes)])
+    })?;
+    write_outputs(&outputs, session)?;
+    
+    info!("EIRモジュールを出力しました: {} ({:?})", output_path.display(), start_time.elapsed());
+    Ok(())
+}
+
+/// コード生成の入力
+enum Source {
+    /// 検査済みのAST
+    Ast(Program),
+    /// 事前にコンパイルしたEIRモジュール（`.eirc`）
+    Precompiled(Module),
+}
+
+/// 出力ファイルのパスと内容
+type Outputs = Vec<(PathBuf, Vec<u8>)>;
+
+/// 最適化レベルに応じてEIRを最適化し、パスごとのコードサイズの増減を `report` に記録する
+///
+/// 最初に `for` の範囲のイテレータをループ変数に展開し、値域解析がループ変数の範囲を求められるようにする。
+/// -O2 以上では、値域解析で条件が常に真と分かった `assert` と契約の検査を取り除く。
+/// `--opt-size` では、ループ融合の後に帰納変数の最適化（乗算をシフトと加減算に展開する）の代わりに
+/// `switch` の表引き化・コールドブロックの切り出し・同一関数の統合を実行する。
+fn optimize(module: &mut Module, options: &CompileOptions, session: &CompileSession, report: &mut SizeReport) -> Result<()> {
+    if options.opt_level == 0 && !options.optimize_size {
+        return Ok(());
+    }
+    run_pass(session, report, "イテレータの展開", module, induction::promote_range_iterators);
+    run_pass(session, report, "値域解析", module, range_analysis::optimize);
+    if options.opt_level < 2 && !options.optimize_size {
+        return Ok(());
+    }
+    run_pass(session, report, "アサーションの除去", module, range_analysis::elide_assertions);
+    run_pass(session, report, "ループ融合", module, loop_fusion::optimize);
+    if options.optimize_size {
+        run_pass(session, report, "switchの表引き化", module, size_opt::lower_switches);
+        run_pass(session, report, "コールドブロックの切り出し", module, size_opt::outline_cold_blocks);
+        run_pass(session, report, "同一関数の統合", module, size_opt::merge_identical_functions);
+    } else {
+        run_pass(session, report, "帰納変数の最適化", module, induction::optimize);
+    }
+    if options.verify_ir || cfg!(debug_assertions) {
+        session.time("EIRの検証", || eir::verify_after(module, "最適化"))?;
+    }
+    Ok(())
+}
+
+/// 最適化パスを実行し、所要時間とコードサイズの増減を記録する
+fn run_pass<T: std::fmt::Debug>(
+    session: &CompileSession,
+    report: &mut SizeReport,
+    name: &str,
+    module: &mut Module,
+    pass: impl FnOnce(&mut Module) -> T,
+) {
+    let stats = session.time(name, || report.measure(name, module, pass));
+    debug!("{}: {:?}", name, stats);
+}
+
+/// EIRを構築して出力を生成
+///
+/// EIRのテキストとバイナリ以外のターゲットでは、最適化の後に `main` と `#[export]` の関数から到達できない関数を取り除く。
+/// `--reproducible` では、EIRの構築からやり直してもう一度生成し、出力がバイト単位で一致するか検査する。
+/// `HashMap` の走査順などに依存した非決定的な出力は、2回の生成で食い違うことで見つかる。
+///
+/// `--link` で指定したモジュールは、リンク時最適化をしなければモジュールごとに最適化してから1つにまとめ、
+/// `--lto` を指定すれば1つにまとめてから最適化する。ただしLLVMのターゲットで `--lto thin` を指定したときは、
+/// まとめずにモジュールごとに出力し（2つ目以降はモジュール名のファイルにする）、最適化をリンカに任せる。
+fn generate_outputs(
+    file: &Path,
+    source: &Source,
+    options: &CompileOptions,
+    session: &Rc<CompileSession>,
+    mut generate: impl FnMut(&Module) -> Result<Outputs>,
+) -> Result<Outputs> {
+    let modules = session.time("EIRの構築", || load_modules(file, source, options))?;
+    for module in &modules {
+        if options.verify_ir || cfg!(debug_assertions) {
+            session.time("EIRの検証", || eir::verify_after(module, "EIRの構築"))?;
+        }
+        codegen::check_intrinsics(module)?;
+        report_lints(range_analysis::diagnose(module), options)?;
+    }
+    let mut report = SizeReport::default();
+    let modules = optimize_modules(modules, options, session, &mut report)?;
+    let mut outputs = Outputs::new();
+    let mut coverage_map = None;
+    for (index, mut module) in modules.into_iter().enumerate() {
+        if !matches!(options.target, CompileTarget::EIR | CompileTarget::Eirc) {
+            let dead = session.time("未使用の関数の削除", || report.measure("未使用の関数の削除", &mut module, dead_functions::eliminate));
+            for function in dead {
+                if options.print_dead_functions {
+                    eprintln!("{}", function);
+                } else {
+                    debug!("{}", function);
+                }
+            }
+        }
+        if options.coverage {
+            coverage_map = Some(session.time("カバレッジの計測", || coverage::instrument(&mut module)));
+        }
+        outputs.extend(rename_outputs(index, &module, generate(&module)?));
+    }
+    if options.opt_stats && !report.passes.is_empty() {
+        eprint!("{}", report);
+    }
     
+    if options.reproducible {
+        let second = session.time("再現性の検査", || -> Result<Outputs> {
+            let modules = load_modules(file, source, options)?;
+            let mut outputs = Outputs::new();
+            for (index, mut module) in optimize_modules(modules, options, session, &mut SizeReport::default())?.into_iter().enumerate() {
+                if !matches!(options.target, CompileTarget::EIR | CompileTarget::Eirc) {
+                    dead_functions::eliminate(&mut module);
+                }
+                if options.coverage {
+                    coverage::instrument(&mut module);
+                }
+                outputs.extend(rename_outputs(index, &module, generate(&module)?));
+            }
+            Ok(outputs)
+        })?;
+        check_reproducible(&outputs, &second)?;
+    }
+    
+    // カウンタとソース上の位置の対応は、最初の出力ファイルの隣に置く
+    if let (Some(map), Some((primary, _))) = (coverage_map, outputs.first()) {
+        let map_path = primary.with_extension("covmap.json");
+        let json = serde_json::to_string_pretty(&map)?;
+        info!("カバレッジの対応表: {}（カウンタ {} 個）", map_path.display(), map.counters);
+        outputs.push((map_path, json.into_bytes()));
+    }
+    Ok(outputs)
+}
+
+/// 値域解析の警告を、リントごとの扱いに従って表示する（`deny` のリントがあればエラーにする）
+fn report_lints(warnings: Vec<range_analysis::RangeWarning>, options: &CompileOptions) -> Result<()> {
+    let mut denied = Vec::new();
+    for warning in warnings {
+        match options.lints.get(warning.lint).copied().unwrap_or_default() {
+            LintLevel::Allow => {}
+            LintLevel::Warn => output::warning(warning),
+            LintLevel::Deny => denied.push(format!("{} [{}]", warning, warning.lint)),
+        }
+    }
+    if denied.is_empty() {
+        return Ok(());
+    }
+    Err(CommandFailed {
+        message: format!("`deny` にしたリントの警告が{}件あります:\n{}", denied.len(), denied.join("\n")),
+        exit_code: ExitCode::ProgramError,
+    }
+    .into())
+}
+
+/// 入力ファイルのモジュールと、`--link` で指定したモジュールを読み込む
+fn load_modules(file: &Path, source: &Source, options: &CompileOptions) -> Result<Vec<Module>> {
+    let mut modules = vec![load_module(file, source, options.check_assertions)?];
+    for path in &options.link {
+        modules.push(eirc::read(path).context(format!("EIRモジュールを読み込めません: {}", path.display()))?);
+    }
+    Ok(modules)
+}
+
+/// モジュールを最適化し、出力するモジュールを返す（リンク時最適化の方式で最適化とまとめる順序が変わる）
+fn optimize_modules(
+    mut modules: Vec<Module>,
+    options: &CompileOptions,
+    session: &CompileSession,
+    report: &mut SizeReport,
+) -> Result<Vec<Module>> {
+    match options.lto {
+        Some(LtoMode::Thin) if options.target == CompileTarget::LLVM => {
+            for module in &mut modules {
+                optimize(module, options, session, report)?;
+            }
+            Ok(modules)
+        }
+        Some(_) => {
+            let mut module = session.time("モジュールのリンク", || eir_linker::link(modules))?;
+            optimize(&mut module, options, session, report)?;
+            Ok(vec![module])
+        }
+        None => {
+            for module in &mut modules {
+                optimize(module, options, session, report)?;
+            }
+            Ok(vec![session.time("モジュールのリンク", || eir_linker::link(modules))?])
+        }
+    }
+}
+
+/// 2つ目以降のモジュールの出力を、拡張子はそのままにモジュール名のファイルにする
+fn rename_outputs(index: usize, module: &Module, outputs: Outputs) -> Outputs {
+    if index == 0 {
+        return outputs;
+    }
+    outputs
+        .into_iter()
+        .map(|(path, bytes)| {
+            let mut name = module.name.clone();
+            if let Some(extension) = path.extension() {
+                name = format!("{}.{}", name, extension.to_string_lossy());
+            }
+            (path.with_file_name(name), bytes)
+        })
+        .collect()
+}
+
+/// 同じ入力から生成した2回の出力が一致するか検査（一致しなければ最初に食い違った行を示す）
+fn check_reproducible(first: &[(PathBuf, Vec<u8>)], second: &[(PathBuf, Vec<u8>)]) -> Result<()> {
+    for ((path, a), (_, b)) in first.iter().zip(second) {
+        if a == b {
+            continue;
+        }
+        let offset = a.iter().zip(b).position(|(x, y)| x != y).unwrap_or_else(|| a.len().min(b.len()));
+        let line = a[..offset].iter().filter(|&&byte| byte == b'\n').count() + 1;
+        return Err(EidosError::CodeGen(format!(
+            "ビルドが再現できません: {} の出力が同じ入力からの2回の生成で異なります（{}行目、{}バイト目）",
+            path.display(),
+            line,
+            offset
+        ))
+        .into());
+    }
+    debug!("再現性の検査: {}個の出力が一致しました", first.len());
+    Ok(())
+}
+
+/// 生成した出力をファイルに書き込む
+fn write_outputs(outputs: &[(PathBuf, Vec<u8>)], session: &CompileSession) -> Result<()> {
+    session.time("出力の書き込み", || -> Result<()> {
+        for (path, contents) in outputs {
+            std::fs::write(path, contents)
+                .context(format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+        }
+        Ok(())
+    })
+}
+
+/// 参照するソースファイルを、Makefile形式の `.d` ファイルとコンパイルデータベースに出力
+///
+/// `.d` ファイルのターゲットは `--emit native` でビルドしたときの出力ファイル。
+/// コンパイルデータベース（`compile_commands.json`）は `.d` ファイルと同じディレクトリに置き、
+/// ほかのファイルのコンパイルのエントリーは残す。
+fn emit_deps(file: &Path, options: &CompileOptions, session: &Rc<CompileSession>, start_time: Instant) -> Result<()> {
+    let deps_path = options.output_path.clone().unwrap_or_else(|| file.with_extension("d"));
+    let target = PathBuf::from(file.file_stem().unwrap_or_default());
+    let info = session.time("依存関係の収集", || deps::DependencyInfo::collect(file, target.clone()));
+    
+    let directory = std::env::current_dir().context("カレントディレクトリを取得できません")?;
+    let mut arguments = vec![
+        "eid".to_string(),
+        "build".to_string(),
+        "--opt-level".to_string(),
+        options.opt_level.to_string(),
+    ];
+    if options.optimize_size {
+        arguments.push("--opt-size".to_string());
+    }
+    arguments.extend([
+        "-o".to_string(),
+        target.display().to_string(),
+        file.display().to_string(),
+    ]);
+    let database_path = deps_path.with_file_name(deps::COMPILATION_DATABASE_FILE);
+    session.time("出力の書き込み", || -> Result<()> {
+        std::fs::write(&deps_path, info.makefile_rule(&deps_path))
+            .context(format!("ファイルの書き込みに失敗しました: {}", deps_path.display()))?;
+        deps::update_compilation_database(&database_path, info.compile_command(directory, arguments))
+    })?;
+    
+    info!(
+        "依存関係を出力しました: {}, {}（{}ファイル, {:?}）",
+        deps_path.display(),
+        database_path.display(),
+        info.files.len(),
+        start_time.elapsed()
+    );
+    Ok(())
+}
+
+/// ファイルを読み込み、構文解析・意味解析・型検査を通したASTを返す（エラーがあれば `Err`）
+fn analyze_file(file: &Path, trace_macros: bool, session: &CompileSession) -> Result<Program> {
     // エラーコレクタ
     let mut error_collector = ErrorCollector::new();
     
     // ソースコードの読み込み
-    let source = std::fs::read_to_string(file)
+    let source = session
+        .time("ソースの読み込み", || session.sources().load(file))
         .context(format!("ファイルの読み込みに失敗しました: {}", file.display()))?;
     
-    // 構文解析
-    let ast = match parse_source(&source, file, &mut error_collector) {
+    // コンパイルプロセス
+    let ast = match parse_source(&source, file, trace_macros, &mut error_collector, session) {
         Ok(ast) => ast,
         Err(e) => {
             error!("構文解析エラー: {}", e);
@@ -172,43 +675,213 @@ pub fn typecheck_file(file: &Path) -> Result<()> {
         }
     };
     
+    // 意味解析（以降はモジュールを修飾名に展開したプログラムを使う）
+    let mut analyzer = SemanticAnalyzer::new();
+    let ast = match session.time("意味解析", || analyzer.analyze(ast.clone())) {
+        Ok(analyzed) => analyzed,
+        Err(e) => {
+            error_collector.add(e);
+            ast
+        }
+    };
+    
     // 型検査
     let type_checker = TypeChecker::new();
-    if let Err(e) = type_checker.check_program(&ast) {
+    if let Err(e) = session.time("型検査", || type_checker.check_program(&ast)) {
         error_collector.add(e);
     }
+    validate_dsls(&ast, &mut error_collector, session);
     
     // エラーがある場合は終了
     if error_collector.has_errors() {
         if let Some(error) = error_collector.into_error() {
-            error!("型エラー: {}", error);
+            error!("コンパイルエラー: {}", error);
             return Err(error.into());
         }
     }
     
-    info!("型チェック完了: {}", file.display());
-    Ok(())
+    Ok(ast)
+}
+
+/// ファイルを検査してEIRモジュールを構築し、検証する（`eidos ir` など、EIRを調べるツール用）
+pub fn build_eir(file: &Path, trace_macros: bool, session: &CompileSession) -> Result<Module> {
+    let ast = analyze_file(file, trace_macros, session)?;
+    let module = session.time("EIRの構築", || build_module(file, &ast, true))?;
+    session.time("EIRの検証", || eir::verify_after(&module, "EIRの構築"))?;
+    Ok(module)
+}
+
+/// 検査済みのASTからEIRモジュールを構築（モジュール名はファイル名から取る）
+fn build_module(file: &Path, ast: &Program, check_assertions: bool) -> Result<Module> {
+    let module_name = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "main".to_string());
+    
+    let builder = ModuleBuilder::new(module_name);
+    let mut builder = if check_assertions { builder } else { builder.without_assertions() };
+    let module = builder
+        .build_from_ast(ast)
+        .context("EIRの構築に失敗しました")?;
+    Ok(module)
+}
+
+/// コード生成の入力からEIRモジュールを得る（`.eirc` から読み込んだモジュールは複製して使う）
+fn load_module(file: &Path, source: &Source, check_assertions: bool) -> Result<Module> {
+    match source {
+        Source::Ast(ast) => build_module(file, ast, check_assertions),
+        Source::Precompiled(module) => Ok(module.clone()),
+    }
+}
+
+/// ファイルの型チェックのみ行い、見つかったエラーをすべて返す（ファイルを読めない場合だけ `Err`）
+///
+/// `import` したファイルのエラーも、そのファイルの位置で返す。
+pub fn check_file(file: &Path, trace_macros: bool, session: &CompileSession) -> Result<Vec<EidosError>> {
+    info!("型チェック開始: {}", file.display());
+    
+    // エラーコレクタ
+    let mut error_collector = ErrorCollector::new();
+    let Some(ast) = analyze_for_check(file, trace_macros, &mut error_collector, session)? else {
+        return Ok(error_collector.into_errors());
+    };
+    
+    // 型検査（名前解決の後は関数の本体どうしは独立しているので、項目ごとに並列に検査する）
+    for error in session.time("型検査", || incremental::analyze_all(&ast, type_check_slice)) {
+        error_collector.add(error);
+    }
+    validate_dsls(&ast, &mut error_collector, session);
+    
+    info!("型チェック完了: {}（エラー {}件）", file.display(), error_collector.count());
+    Ok(error_collector.into_errors())
+}
+
+/// `check_file` と同じ検査を、前回の検査の結果を `analysis` に残して差分だけ行う（`check --watch` 用）
+///
+/// 構文解析と意味解析は毎回ファイル全体で行い、型検査は変更した項目と、シグネチャを変更した項目を
+/// 参照する項目だけやり直す。
+pub fn check_file_incremental(
+    file: &Path,
+    trace_macros: bool,
+    session: &CompileSession,
+    analysis: &mut IncrementalAnalysis,
+) -> Result<Vec<EidosError>> {
+    info!("型チェック開始（差分）: {}", file.display());
+    
+    // エラーコレクタ
+    let mut error_collector = ErrorCollector::new();
+    let Some(ast) = analyze_for_check(file, trace_macros, &mut error_collector, session)? else {
+        return Ok(error_collector.into_errors());
+    };
+    
+    // 型検査（影響を受けた項目だけ）
+    let update = session.time("型検査", || analysis.update(&ast, type_check_slice));
+    info!("型検査: {}個の項目を検査し、{}個の項目は前回の結果を使いました", update.analyzed.len(), update.reused);
+    for error in analysis.errors() {
+        error_collector.add(error);
+    }
+    validate_dsls(&ast, &mut error_collector, session);
+    
+    info!("型チェック完了: {}（エラー {}件）", file.display(), error_collector.count());
+    Ok(error_collector.into_errors())
+}
+
+/// 項目ごとに切り出したプログラムを型検査する（複数のスレッドから呼ばれるので検査器は毎回作る）
+fn type_check_slice(slice: &Program) -> Vec<EidosError> {
+    TypeChecker::new().check_program(slice).err().into_iter().collect()
+}
+
+/// 検査のためにファイルを読み込み、構文解析と意味解析を行う
+///
+/// 構文解析に失敗した場合は `None` を返す（原因のエラーはコレクタに入っている）。
+/// 意味解析に失敗した場合は、エラーをコレクタに入れて意味解析の前のASTを返す。
+fn analyze_for_check(
+    file: &Path,
+    trace_macros: bool,
+    error_collector: &mut ErrorCollector,
+    session: &CompileSession,
+) -> Result<Option<Program>> {
+    // ソースコードの読み込み
+    let source = session
+        .sources()
+        .load(file)
+        .context(format!("ファイルの読み込みに失敗しました: {}", file.display()))?;
+    
+    // 構文解析
+    let ast = match parse_source(&source, file, trace_macros, error_collector, session) {
+        Ok(ast) => ast,
+        Err(e) => {
+            error!("構文解析エラー: {}", e);
+            return Ok(None);
+        }
+    };
+    
+    // 意味解析
+    let mut analyzer = SemanticAnalyzer::new();
+    let ast = match session.time("意味解析", || analyzer.analyze(ast.clone())) {
+        Ok(analyzed) => analyzed,
+        Err(e) => {
+            error_collector.add(e);
+            ast
+        }
+    };
+    Ok(Some(ast))
+}
+
+/// 登録したDSL拡張の検査を実行する（型検査までにエラーがなかった場合だけ）
+fn validate_dsls(ast: &Program, error_collector: &mut ErrorCollector, session: &CompileSession) {
+    if !error_collector.has_errors() {
+        session.time("DSLの検査", || DSLProcessor::new().validate(ast, error_collector));
+    }
 }
 
 /// ソースコードを構文解析
-fn parse_source(source: &str, file_path: &Path, error_collector: &mut ErrorCollector) -> Result<Program> {
-    // 字句解析
-    let lexer = Lexer::new(source);
-    let tokens = match lexer.tokenize() {
-        Ok(tokens) => tokens,
+fn parse_source(
+    source: &SourceFile,
+    file_path: &Path,
+    trace_macros: bool,
+    error_collector: &mut ErrorCollector,
+    session: &CompileSession,
+) -> Result<Program> {
+    // 字句解析・マクロ展開・構文解析（トークン列全体は作らず、構文解析が読み進める分だけ字句解析する）
+    // マクロはトークンの段階で、紛らわしい識別子の検査は展開前のトークンで行う
+    let mut confusables = ConfusableChecker::new();
+    let mut expander = MacroExpander::new().with_trace(trace_macros);
+    let parsed = session.time("構文解析", || {
+        let tokens = expander.expanding(confusables.scan(Lexer::for_file(source)));
+        Parser::from_lexer(tokens, file_path.to_path_buf()).parse()
+    });
+    for warning in confusables.found() {
+        output::warning(warning);
+    }
+    for line in expander.trace() {
+        eprintln!("{} {}", "[macro]".cyan(), line);
+    }
+    let program = match parsed {
+        Ok(program) => program,
         Err(e) => {
             error_collector.add(e);
-            return Err(EidosError::LexerError("字句解析に失敗しました".to_string()).into());
+            return Err(EidosError::Parser {
+                message: "構文解析に失敗しました".to_string(),
+                file: file_path.to_path_buf(),
+                line: 0,
+                column: 0,
+            }.into());
         }
     };
     
-    // 構文解析
-    let parser = Parser::new(tokens);
-    match parser.parse_program() {
+    // comptime ブロックの実行（生成した宣言は意味解析と型検査の対象になる）
+    let mut evaluator = ConstEvaluator::new();
+    match session.time("コンパイル時実行", || evaluator.expand_comptime(program)) {
         Ok(program) => Ok(program),
         Err(e) => {
             error_collector.add(e);
-            Err(EidosError::ParserError("構文解析に失敗しました".to_string()).into())
+            Err(EidosError::Parser {
+                message: "comptime ブロックの実行に失敗しました".to_string(),
+                file: file_path.to_path_buf(),
+                line: 0,
+                column: 0,
+            }.into())
         }
     }
 }
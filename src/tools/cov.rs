@@ -0,0 +1,110 @@
+use anyhow::{bail, Context, Result};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use log::info;
+
+use crate::core::coverage::{self, CoverageMap, CoverageProfile, FileCoverage};
+
+/// カバレッジの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CovFormat {
+    /// ファイルごとの行・分岐の割合と、実行されなかった行
+    #[default]
+    Text,
+    /// lcov のトレースファイル（genhtml や CI のカバレッジサービスで使う）
+    Lcov,
+}
+
+/// `eidos cov report` の設定
+#[derive(Debug, Clone, Default)]
+pub struct CovReportOptions {
+    /// `eidos build --coverage` が出力した対応表
+    pub map: PathBuf,
+    /// 実行したプログラムが書き出したプロファイル（複数あれば合算する）
+    pub profiles: Vec<PathBuf>,
+    pub format: CovFormat,
+    /// 出力先（省略すると標準出力）
+    pub output: Option<PathBuf>,
+}
+
+/// プロファイルを集計し、`.eid` ファイルごとの行と分岐のカバレッジを出力
+pub fn report(options: &CovReportOptions) -> Result<()> {
+    if options.profiles.is_empty() {
+        bail!("プロファイルを1つ以上指定してください");
+    }
+    let map: CoverageMap = read_json(&options.map)?;
+    let profiles = options
+        .profiles
+        .iter()
+        .map(|path| read_json::<CoverageProfile>(path))
+        .collect::<Result<Vec<_>>>()?;
+    let files = map.report(&profiles)?;
+    
+    let text = match options.format {
+        CovFormat::Text => render_text(&files),
+        CovFormat::Lcov => coverage::to_lcov(&files),
+    };
+    match &options.output {
+        Some(path) => {
+            std::fs::write(path, text)
+                .with_context(|| format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+            info!("出力しました: {}", path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("ファイルの読み込みに失敗しました: {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("形式が正しくありません: {}", path.display()))
+}
+
+/// ファイルごとの割合の表と、実行されなかった行の範囲
+fn render_text(files: &[FileCoverage]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{:>16} {:>16}  ファイル", "行", "分岐");
+    let (mut lines_hit, mut lines_found, mut branches_hit, mut branches_found) = (0, 0, 0, 0);
+    for file in files {
+        let (hit, found) = file.line_summary();
+        let (taken, total) = file.branch_summary();
+        let _ = writeln!(out, "{:>16} {:>16}  {}", ratio(hit, found), ratio(taken, total), file.file.display());
+        lines_hit += hit;
+        lines_found += found;
+        branches_hit += taken;
+        branches_found += total;
+    }
+    let _ = writeln!(out, "{:>16} {:>16}  合計", ratio(lines_hit, lines_found), ratio(branches_hit, branches_found));
+    
+    for file in files {
+        let missed: Vec<usize> = file.lines.iter().filter(|(_, count)| **count == 0).map(|(line, _)| *line).collect();
+        if !missed.is_empty() {
+            let _ = writeln!(out, "\n{}: 実行されなかった行: {}", file.file.display(), line_ranges(&missed));
+        }
+    }
+    out
+}
+
+fn ratio(hit: usize, found: usize) -> String {
+    if found == 0 {
+        return "-".to_string();
+    }
+    format!("{}/{} ({:.1}%)", hit, found, hit as f64 * 100.0 / found as f64)
+}
+
+/// 昇順の行番号を `3, 7-9` のような範囲の並びにする
+fn line_ranges(lines: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,25 @@
+use std::io::{self, BufRead, Write};
+
+use anyhow::Result;
+
+use crate::core::mangle;
+
+/// マングリングしたシンボル名を読みやすい名前に戻して表示
+///
+/// シンボルを省略した場合は標準入力を1行ずつ読み、含まれるシンボル名を置き換えて出力する
+/// （`perf report` などの出力をパイプで通せる）。規則に合わないシンボルはそのまま表示する。
+pub fn demangle(symbols: &[String]) -> Result<()> {
+    if !symbols.is_empty() {
+        for symbol in symbols {
+            println!("{}", mangle::demangle(symbol).unwrap_or_else(|| symbol.clone()));
+        }
+        return Ok(());
+    }
+    
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in io::stdin().lock().lines() {
+        writeln!(out, "{}", mangle::demangle_text(&line?))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::frontend::lexer::{Lexer, Token, TokenKind};
+
+/// Eidosのソースファイルの拡張子
+pub const SOURCE_EXTENSION: &str = "eid";
+
+/// ルートファイルと、そこから推移的に参照されるソースファイルを集める
+///
+/// 次の形の参照を、参照元のファイルがあるディレクトリからの相対パスとして解決する。
+/// 存在しないファイル（標準ライブラリのモジュールなど）は無視する。
+///
+/// - `import "path/to/file.eid"`
+/// - `import a.b.c` / `use a::b::c;`（`a/b/c.eid`、`a/b.eid`、`a.eid` の順に探す）
+/// - `mod name;`（`name.eid` または `name/mod.eid`）
+///
+/// 字句解析に失敗したファイルは、そのファイル自身だけを結果に含める。
+pub fn source_files(root: &Path) -> Vec<PathBuf> {
+    collect_files(root).0
+}
+
+/// `source_files` のソースファイルに、型プロバイダ（`provide dsl "schema.json";`）が読み込むスキーマのファイルを加える
+///
+/// スキーマを変更したときにビルドし直すよう、`--emit deps` と `eid build --watch` が使う。
+pub fn input_files(root: &Path) -> Vec<PathBuf> {
+    let (mut files, schemas) = collect_files(root);
+    files.extend(schemas);
+    files
+}
+
+/// ソースファイルと、スキーマのファイルを集める
+fn collect_files(root: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut files = Vec::new();
+    let mut schemas = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    
+    while let Some(file) = pending.pop() {
+        let key = fs::canonicalize(&file).unwrap_or_else(|_| file.clone());
+        if !seen.insert(key) {
+            continue;
+        }
+        
+        if let Ok(source) = fs::read_to_string(&file) {
+            let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            let mut lexer = Lexer::new(&source, file.clone());
+            match lexer.tokenize() {
+                Ok(tokens) => {
+                    for reference in references(&tokens) {
+                        let Some(path) = reference.resolve(&dir) else {
+                            continue;
+                        };
+                        debug!("依存ファイル: {} -> {}", file.display(), path.display());
+                        match reference {
+                            // スキーマはEidosのソースではないので、参照をたどらない
+                            Reference::Schema(_) => {
+                                if seen.insert(fs::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                                    schemas.push(path);
+                                }
+                            }
+                            _ => pending.push(path),
+                        }
+                    }
+                }
+                Err(e) => debug!("依存関係の解析をスキップ: {}: {}", file.display(), e),
+            }
+        }
+        files.push(file);
+    }
+    
+    (files, schemas)
+}
+
+/// コンパイルデータベースのファイル名（`.d` ファイルと同じディレクトリに置く）
+pub const COMPILATION_DATABASE_FILE: &str = "compile_commands.json";
+
+/// コンパイルデータベースの1回のコンパイルのエントリー
+///
+/// `compile_commands.json` の形式に、参照するソースファイルの一覧（`dependencies`）を加えたもの。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompileCommand {
+    /// コマンドを実行するディレクトリ
+    pub directory: PathBuf,
+    /// コンパイルするファイル
+    pub file: PathBuf,
+    /// コンパイルのコマンドライン
+    pub arguments: Vec<String>,
+    /// 出力ファイル
+    pub output: PathBuf,
+    /// `file` 自身と、そこから推移的に参照されるソースファイルとスキーマのファイル
+    pub dependencies: Vec<PathBuf>,
+}
+
+/// `--emit deps` の出力（Makefile形式の `.d` ファイルとコンパイルデータベースのエントリー）
+pub struct DependencyInfo {
+    /// ビルドの出力ファイル（`.d` ファイルのターゲット）
+    pub target: PathBuf,
+    /// ルートファイルを先頭に、残りはパス順に並べたソースファイルとスキーマのファイル
+    pub files: Vec<PathBuf>,
+}
+
+impl DependencyInfo {
+    /// ルートファイルの依存関係を集める
+    pub fn collect(root: &Path, target: PathBuf) -> Self {
+        let mut files = input_files(root);
+        // 探索順はスタックの積み方で決まるので、出力が安定するよう並べ替える
+        files[1..].sort();
+        Self { target, files }
+    }
+    
+    /// Makefile形式の依存関係（`gcc -MD -MP` と同じく、参照先ごとに空のターゲットも出力する）
+    ///
+    /// 参照先のファイルが削除されても `make` がエラーにならないよう、空のターゲットを置く。
+    pub fn makefile_rule(&self, deps_path: &Path) -> String {
+        let mut rule = format!("{} {}:", make_escape(&self.target), make_escape(deps_path));
+        for file in &self.files {
+            rule.push_str(" \\\n  ");
+            rule.push_str(&make_escape(file));
+        }
+        rule.push('\n');
+        for file in self.files.iter().skip(1) {
+            rule.push_str(&format!("\n{}:\n", make_escape(file)));
+        }
+        rule
+    }
+    
+    /// コンパイルデータベースのエントリー
+    pub fn compile_command(&self, directory: PathBuf, arguments: Vec<String>) -> CompileCommand {
+        CompileCommand {
+            directory,
+            file: self.files[0].clone(),
+            arguments,
+            output: self.target.clone(),
+            dependencies: self.files.clone(),
+        }
+    }
+}
+
+/// コンパイルデータベースに1回のコンパイルを書き込む
+///
+/// 既存のデータベースがあれば、同じファイルと出力のエントリーだけを置き換え、ほかのコンパイルのエントリーは残す。
+pub fn update_compilation_database(path: &Path, command: CompileCommand) -> Result<()> {
+    let mut commands: Vec<CompileCommand> = match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text)
+            .context(format!("コンパイルデータベースの形式が不正です: {}", path.display()))?,
+        Err(_) => Vec::new(),
+    };
+    commands.retain(|existing| existing.file != command.file || existing.output != command.output);
+    commands.push(command);
+    commands.sort_by(|a, b| (&a.file, &a.output).cmp(&(&b.file, &b.output)));
+    
+    let mut text = serde_json::to_string_pretty(&commands)?;
+    text.push('\n');
+    fs::write(path, text).context(format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+    Ok(())
+}
+
+/// Makefileのターゲットや依存に書けるようパスをエスケープ
+fn make_escape(path: &Path) -> String {
+    let mut escaped = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '$' => escaped.push_str("$$"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// ソース中のファイル参照
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Reference {
+    /// `import "..."` で直接指定されたパス
+    Path(String),
+    /// `import a.b` / `use a::b` のモジュールパス
+    Module(Vec<String>),
+    /// `mod name;` で宣言された子モジュール
+    Child(String),
+    /// `provide dsl "path";` で型プロバイダが読み込むスキーマ
+    Schema(String),
+}
+
+impl Reference {
+    fn resolve(&self, dir: &Path) -> Option<PathBuf> {
+        let candidates: Vec<PathBuf> = match self {
+            Reference::Path(path) => {
+                let path = dir.join(path);
+                if path.extension().is_some() {
+                    vec![path]
+                } else {
+                    vec![path.with_extension(SOURCE_EXTENSION)]
+                }
+            }
+            // 末尾の要素は関数名や型名かもしれないので、長いパスから順に試す
+            Reference::Module(segments) => (1..=segments.len())
+                .rev()
+                .map(|len| {
+                    let mut path = dir.to_path_buf();
+                    path.extend(&segments[..len]);
+                    path.with_extension(SOURCE_EXTENSION)
+                })
+                .collect(),
+            Reference::Schema(path) => vec![dir.join(path)],
+            Reference::Child(name) => vec![
+                dir.join(name).with_extension(SOURCE_EXTENSION),
+                dir.join(name).join("mod").with_extension(SOURCE_EXTENSION),
+            ],
+        };
+        candidates.into_iter().find(|path| path.is_file())
+    }
+}
+
+fn references(tokens: &[Token]) -> Vec<Reference> {
+    let mut references = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i].kind {
+            TokenKind::Import => match tokens.get(i + 1).map(|t| &t.kind) {
+                Some(TokenKind::String(path)) => references.push(Reference::Path(path.clone())),
+                Some(TokenKind::Identifier(_)) => {
+                    references.push(Reference::Module(module_path(&tokens[i + 1..])));
+                }
+                _ => {}
+            },
+            TokenKind::Identifier(word) if word == "use" => {
+                if let Some(TokenKind::Identifier(_)) = tokens.get(i + 1).map(|t| &t.kind) {
+                    references.push(Reference::Module(module_path(&tokens[i + 1..])));
+                }
+            }
+            TokenKind::Identifier(word) if word == "provide" => {
+                let dsl = tokens.get(i + 1).map(|t| &t.kind);
+                let schema = tokens.get(i + 2).map(|t| &t.kind);
+                if let (Some(TokenKind::Identifier(_)), Some(TokenKind::String(path))) = (dsl, schema) {
+                    references.push(Reference::Schema(path.clone()));
+                }
+            }
+            TokenKind::Identifier(word) if word == "mod" => {
+                let name = tokens.get(i + 1).map(|t| &t.kind);
+                let terminator = tokens.get(i + 2).map(|t| &t.kind);
+                if let (Some(TokenKind::Identifier(name)), Some(TokenKind::Semicolon)) = (name, terminator) {
+                    references.push(Reference::Child(name.to_string()));
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    references
+}
+
+/// `a.b.c` または `a::b::c` の形のモジュールパスを読む
+fn module_path(tokens: &[Token]) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while let Some(TokenKind::Identifier(name)) = tokens.get(i).map(|t| &t.kind) {
+        segments.push(name.to_string());
+        i += 1;
+        match (tokens.get(i).map(|t| &t.kind), tokens.get(i + 1).map(|t| &t.kind)) {
+            (Some(TokenKind::Dot), _) => i += 1,
+            (Some(TokenKind::Colon), Some(TokenKind::Colon)) => i += 2,
+            _ => break,
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_source_files_follow_imports() {
+        let dir = std::env::temp_dir().join(format!("eidos-deps-{}", std::process::id()));
+        fs::create_dir_all(dir.join("geometry")).unwrap();
+        fs::write(dir.join("main.eid"), "import \"util\"\nuse geometry::point::origin;\nmod shapes;\nuse std::io;\n").unwrap();
+        fs::write(dir.join("util.eid"), "import \"main.eid\"\n").unwrap();
+        fs::write(dir.join("geometry").join("point.eid"), "").unwrap();
+        fs::write(dir.join("shapes.eid"), "provide json_schema \"shapes.json\";\n").unwrap();
+        // スキーマの中の `import` はたどらない
+        fs::write(dir.join("shapes.json"), "import \"missing.eid\"").unwrap();
+        
+        let names = |files: Vec<PathBuf>| {
+            let mut names: Vec<String> = files
+                .iter()
+                .map(|path| path.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(source_files(&dir.join("main.eid"))), vec!["geometry/point.eid", "main.eid", "shapes.eid", "util.eid"]);
+        assert_eq!(
+            names(input_files(&dir.join("main.eid"))),
+            vec!["geometry/point.eid", "main.eid", "shapes.eid", "shapes.json", "util.eid"]
+        );
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_makefile_rule() {
+        let info = DependencyInfo {
+            target: PathBuf::from("main"),
+            files: vec![PathBuf::from("main.eid"), PathBuf::from("my lib/$util.eid")],
+        };
+        assert_eq!(
+            info.makefile_rule(Path::new("main.d")),
+            "main main.d: \\\n  main.eid \\\n  my\\ lib/$$util.eid\n\nmy\\ lib/$$util.eid:\n"
+        );
+        
+        let command = info.compile_command(PathBuf::from("/work"), vec!["eid".to_string(), "build".to_string()]);
+        assert_eq!(command.file, PathBuf::from("main.eid"));
+        assert_eq!(command.dependencies.len(), 2);
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::core::error_codes::ErrorCode;
+
+/// エラーコードの説明を表示（コードを省略した場合は一覧を表示）
+pub fn explain(code: Option<&str>) -> Result<()> {
+    let code = match code {
+        Some(code) => code,
+        None => {
+            list_codes();
+            return Ok(());
+        }
+    };
+    
+    let error_code = match ErrorCode::parse(code) {
+        Some(error_code) => error_code,
+        None => bail!("不明なエラーコードです: {}（`eidos explain` でコードの一覧を表示します）", code),
+    };
+    print!("{}", render(error_code));
+    Ok(())
+}
+
+/// すべてのエラーコードと要約を表示
+fn list_codes() {
+    for code in ErrorCode::ALL {
+        println!("{}  {}", code.as_str().bold(), code.explanation().title);
+    }
+}
+
+/// エラーコードの説明をテキストにする
+fn render(code: ErrorCode) -> String {
+    let explanation = code.explanation();
+    let mut out = format!("{}: {}\n\n", code.as_str().bold(), explanation.title.bold());
+    out.push_str(explanation.description);
+    out.push_str("\n\n");
+    
+    if !explanation.example.is_empty() {
+        out.push_str(&format!("{}\n\n", "このエラーになるコードの例:".bold()));
+        out.push_str("```eidos\n");
+        out.push_str(explanation.example);
+        out.push_str("\n```\n\n");
+    }
+    
+    out.push_str(&format!("{}\n\n", "修正方法:".bold()));
+    out.push_str(explanation.fix);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_render_explanation() {
+        colored::control::set_override(false);
+        let text = render(ErrorCode::E0004);
+        assert!(text.starts_with("E0004: 型エラー\n"));
+        assert!(text.contains("```eidos\nfn add"));
+        assert!(text.contains("修正方法:"));
+        
+        // 例のないコードでは例の見出しを出さない
+        assert!(!render(ErrorCode::E0012).contains("```"));
+    }
+    
+    #[test]
+    fn test_unknown_code() {
+        assert!(explain(Some("E9999")).is_err());
+        assert!(explain(Some("E0001")).is_ok());
+    }
+}
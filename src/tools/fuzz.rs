@@ -0,0 +1,490 @@
+use anyhow::{bail, Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use log::{debug, info};
+
+use crate::core::ast::{self, ASTNode, Program, Visitor};
+use crate::frontend::lexer::Token;
+use crate::frontend::{Lexer, Parser, SemanticAnalyzer, TypeChecker};
+
+/// ファジングの対象となるフロントエンドの段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzTarget {
+    /// 任意のバイト列を字句解析する
+    Lexer,
+    /// 任意のバイト列を構文解析まで通す
+    Parser,
+    /// 任意のバイト列を型チェックまで通す
+    TypeChecker,
+    /// バイト列から文法に沿ったプログラムを組み立て、型チェックまで通す
+    Structured,
+}
+
+impl FuzzTarget {
+    pub fn all() -> [FuzzTarget; 4] {
+        [FuzzTarget::Lexer, FuzzTarget::Parser, FuzzTarget::TypeChecker, FuzzTarget::Structured]
+    }
+    
+    /// ハーネスでのターゲット名
+    pub fn name(self) -> &'static str {
+        match self {
+            FuzzTarget::Lexer => "lexer",
+            FuzzTarget::Parser => "parser",
+            FuzzTarget::TypeChecker => "type_checker",
+            FuzzTarget::Structured => "structured",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|target| target.name() == name)
+    }
+    
+    /// ハーネスから呼ぶ関数名
+    fn entry_point(self) -> &'static str {
+        match self {
+            FuzzTarget::Lexer => "fuzz_lexer",
+            FuzzTarget::Parser => "fuzz_parser",
+            FuzzTarget::TypeChecker => "fuzz_type_checker",
+            FuzzTarget::Structured => "fuzz_structured",
+        }
+    }
+}
+
+/// ファジングの入力として使うファイル名（UTF-8でないバイト列は置換文字に変わる）
+const FUZZ_FILE: &str = "<fuzz>";
+
+/// 任意のバイト列を字句解析する（エラーは正常な結果で、パニックだけがバグ）
+pub fn fuzz_lexer(data: &[u8]) {
+    let _ = tokenize(data);
+}
+
+/// 任意のバイト列を構文解析まで通す
+pub fn fuzz_parser(data: &[u8]) {
+    let _ = parse(data);
+}
+
+/// 任意のバイト列を意味解析と型チェックまで通す
+pub fn fuzz_type_checker(data: &[u8]) {
+    let _ = type_check(data);
+}
+
+/// バイト列から組み立てたプログラムを型チェックまで通す（構文エラーで止まらず奥の段階まで届く）
+pub fn fuzz_structured(data: &[u8]) {
+    fuzz_type_checker(structured_source(data).as_bytes());
+}
+
+fn tokenize(data: &[u8]) -> crate::core::Result<Vec<Token>> {
+    let source = String::from_utf8_lossy(data);
+    let tokens = Lexer::new(&source, PathBuf::from(FUZZ_FILE)).tokenize();
+    tokens
+}
+
+fn parse(data: &[u8]) -> crate::core::Result<Program> {
+    Parser::new(tokenize(data)?, PathBuf::from(FUZZ_FILE)).parse()
+}
+
+fn type_check(data: &[u8]) -> crate::core::Result<Program> {
+    let analyzed = SemanticAnalyzer::new().analyze(parse(data)?)?;
+    TypeChecker::new().check(analyzed)
+}
+
+/// バイト列を選択肢として消費し、構文的に正しいEidosプログラムを組み立てる
+///
+/// 同じバイト列からは常に同じプログラムができる。バイト列が尽きたら残りの選択はすべて0になる。
+pub fn structured_source(data: &[u8]) -> String {
+    let mut generator = SourceGenerator { data, position: 0, variables: Vec::new(), functions: Vec::new() };
+    let mut source = String::new();
+    let items = 1 + generator.choose(8);
+    for _ in 0..items {
+        generator.item(&mut source);
+    }
+    source
+}
+
+/// `structured_source` の生成器
+struct SourceGenerator<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// 参照できる変数
+    variables: Vec<String>,
+    /// 定義済みの関数と引数の数
+    functions: Vec<(String, usize)>,
+}
+
+/// 式の入れ子の深さの上限
+const MAX_EXPRESSION_DEPTH: usize = 6;
+/// 生成時に呼び出す標準ライブラリ関数と引数の数
+const STDLIB_FUNCTIONS: &[(&str, usize)] = &[("math::abs", 1), ("math::max", 2), ("math::sqrt", 1), ("string::length", 1)];
+const BINARY_OPERATORS: &[&str] = &["+", "-", "*", "/", "%", "==", "!=", "<", "<=", ">", ">=", "&&", "||"];
+
+impl SourceGenerator<'_> {
+    /// 次のバイトから `0..n` の値を選ぶ
+    fn choose(&mut self, n: usize) -> usize {
+        let byte = self.data.get(self.position).copied().unwrap_or(0);
+        self.position += 1;
+        byte as usize % n.max(1)
+    }
+    
+    fn item(&mut self, out: &mut String) {
+        match self.choose(4) {
+            0 => self.function(out),
+            1 | 2 => self.statement(out),
+            _ => {
+                // 同名の変数を再宣言することもある
+                let name = format!("v{}", self.choose(4));
+                out.push_str(&format!("let {} = ", name));
+                self.expression(out, 0);
+                out.push_str(";\n");
+                self.variables.push(name);
+            }
+        }
+    }
+    
+    fn function(&mut self, out: &mut String) {
+        let name = format!("f{}", self.functions.len());
+        let arity = self.choose(3);
+        let modifier = ["", "effect ", "const "][self.choose(3)];
+        let params: Vec<String> = (0..arity).map(|i| format!("p{}", i)).collect();
+        let signature: Vec<String> = params.iter().map(|p| format!("{}: Int", p)).collect();
+        out.push_str(&format!("{}fn {}({}) -> Int {{\n", modifier, name, signature.join(", ")));
+        
+        // 関数本体からは引数だけが見える
+        let outer = std::mem::replace(&mut self.variables, params);
+        for _ in 0..self.choose(3) {
+            self.statement(out);
+        }
+        self.expression(out, 0);
+        out.push_str("\n}\n");
+        self.variables = outer;
+        self.functions.push((name, arity));
+    }
+    
+    fn statement(&mut self, out: &mut String) {
+        match (self.choose(3), self.variables.is_empty()) {
+            (0, false) => {
+                let index = self.choose(self.variables.len());
+                let target = self.variables[index].clone();
+                let op = ["=", "+=", "-=", "*="][self.choose(4)];
+                out.push_str(&format!("{} {} ", target, op));
+            }
+            (1, _) => {
+                let name = format!("v{}", self.variables.len());
+                out.push_str(&format!("let mut {} = ", name));
+                self.expression(out, 0);
+                out.push_str(";\n");
+                self.variables.push(name);
+                return;
+            }
+            _ => {}
+        }
+        self.expression(out, 0);
+        out.push_str(";\n");
+    }
+    
+    fn expression(&mut self, out: &mut String, depth: usize) {
+        // 深くなるほど葉を選ぶ
+        let choice = if depth >= MAX_EXPRESSION_DEPTH { self.choose(5) } else { self.choose(10) };
+        match choice {
+            0 => out.push_str(&(self.choose(256) as i64 - 128).to_string()),
+            1 => out.push_str(&format!("{}.{}", self.choose(100), self.choose(10))),
+            2 => out.push_str(&format!("\"s{}\"", self.choose(10))),
+            3 => out.push_str(["true", "false"][self.choose(2)]),
+            4 => match self.variables.len() {
+                0 => out.push('0'),
+                n => {
+                    let index = self.choose(n);
+                    let name = self.variables[index].clone();
+                    out.push_str(&name);
+                }
+            },
+            5 => {
+                out.push('(');
+                self.expression(out, depth + 1);
+                out.push_str(&format!(" {} ", BINARY_OPERATORS[self.choose(BINARY_OPERATORS.len())]));
+                self.expression(out, depth + 1);
+                out.push(')');
+            }
+            6 => {
+                out.push_str(["-", "!"][self.choose(2)]);
+                self.expression(out, depth + 1);
+            }
+            7 => {
+                let (name, arity) = match self.choose(2) {
+                    0 if !self.functions.is_empty() => {
+                        let index = self.choose(self.functions.len());
+                        self.functions[index].clone()
+                    }
+                    _ => {
+                        let (name, arity) = STDLIB_FUNCTIONS[self.choose(STDLIB_FUNCTIONS.len())];
+                        (name.to_string(), arity)
+                    }
+                };
+                out.push_str(&name);
+                out.push('(');
+                for i in 0..arity {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.expression(out, depth + 1);
+                }
+                out.push(')');
+            }
+            8 => {
+                out.push_str("{ ");
+                let scope = self.variables.len();
+                for _ in 0..self.choose(3) {
+                    self.statement(out);
+                }
+                self.expression(out, depth + 1);
+                out.push_str(" }");
+                self.variables.truncate(scope);
+            }
+            _ => {
+                out.push_str("{ ");
+                for i in 0..1 + self.choose(3) {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    out.push_str(&format!("\"k{}\": ", i));
+                    self.expression(out, depth + 1);
+                }
+                out.push_str(" }");
+            }
+        }
+    }
+}
+
+/// `eid fuzz-frontend` の本体
+///
+/// `minimize` を指定するとそのコーパスを最小化して `output` に書き出し、指定しなければ `output` にハーネスを生成する。
+pub fn fuzz_frontend(output: &Path, seeds: &[PathBuf], minimize: Option<&Path>, target: &str) -> Result<()> {
+    match minimize {
+        Some(corpus) => {
+            let names: Vec<&str> = FuzzTarget::all().iter().map(|target| target.name()).collect();
+            let target = FuzzTarget::from_name(target)
+                .with_context(|| format!("未知のターゲットです: {}（{} のいずれか）", target, names.join(", ")))?;
+            let report = minimize_corpus(corpus, output, target)?;
+            println!("コーパスを最小化しました: {}件中{}件を {} に書き出しました", report.total, report.kept, output.display());
+        }
+        None => {
+            generate_harness(output, Path::new(env!("CARGO_MANIFEST_DIR")), seeds)?;
+            println!("ファジング用ハーネスを生成しました: {}", output.display());
+            for target in FuzzTarget::all() {
+                println!("  cargo fuzz run --fuzz-dir {} {}", output.display(), target.name());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// cargo-fuzz（libFuzzer）用のハーネスを `dir` に生成し、作成したファイルを返す
+///
+/// `crate_dir` はEidosのクレートのディレクトリ（ハーネスのCargo.tomlから参照する）。
+/// `seeds` のファイルは各ターゲットの初期コーパスにコピーする。
+pub fn generate_harness(dir: &Path, crate_dir: &Path, seeds: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let crate_dir = fs::canonicalize(crate_dir)
+        .context(format!("クレートのディレクトリが見つかりません: {}", crate_dir.display()))?;
+    let mut written = Vec::new();
+    
+    let mut manifest = format!(
+        "[package]\nname = \"eidos-fuzz\"\nversion = \"0.0.0\"\npublish = false\nedition = \"2021\"\n\n\
+         [package.metadata]\ncargo-fuzz = true\n\n\
+         [dependencies]\nlibfuzzer-sys = \"0.4\"\neidos = {{ path = {:?} }}\n",
+        crate_dir.display().to_string()
+    );
+    for target in FuzzTarget::all() {
+        manifest.push_str(&format!(
+            "\n[[bin]]\nname = \"{0}\"\npath = \"fuzz_targets/{0}.rs\"\ntest = false\ndoc = false\nbench = false\n",
+            target.name()
+        ));
+    }
+    written.push(write_file(&dir.join("Cargo.toml"), manifest.as_bytes())?);
+    
+    for target in FuzzTarget::all() {
+        let source = format!(
+            "#![no_main]\n\nuse libfuzzer_sys::fuzz_target;\n\nfuzz_target!(|data: &[u8]| {{\n    eidos::tools::fuzz::{}(data);\n}});\n",
+            target.entry_point()
+        );
+        written.push(write_file(&dir.join("fuzz_targets").join(format!("{}.rs", target.name())), source.as_bytes())?);
+        
+        for seed in seeds {
+            let data = fs::read(seed).context(format!("シードを読み込めません: {}", seed.display()))?;
+            let path = dir.join("corpus").join(target.name()).join(content_name(&data));
+            written.push(write_file(&path, &data)?);
+        }
+    }
+    
+    info!("ファジング用ハーネスを生成しました: {}", dir.display());
+    Ok(written)
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<PathBuf> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context(format!("ディレクトリを作成できません: {}", parent.display()))?;
+    }
+    fs::write(path, contents).context(format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+    Ok(path.to_path_buf())
+}
+
+/// 内容から決まるコーパスのファイル名（同じ入力は1つにまとまる）
+fn content_name(data: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// コーパスの最小化の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimizeReport {
+    pub total: usize,
+    pub kept: usize,
+}
+
+/// コーパスを最小化して `output` に書き出す
+///
+/// 入力ごとに、現れたトークンの種類・ASTノードの種類・エラーの種類を特徴として集め、小さい入力から順に
+/// 新しい特徴を増やすものだけを残す。カバレッジ計測を使わないので `cargo fuzz cmin` より粗いが、
+/// インストルメント済みのビルドなしで使える。
+pub fn minimize_corpus(corpus: &Path, output: &Path, target: FuzzTarget) -> Result<MinimizeReport> {
+    let mut inputs = Vec::new();
+    for entry in fs::read_dir(corpus).context(format!("コーパスを読み込めません: {}", corpus.display()))? {
+        let path = entry?.path();
+        if path.is_file() {
+            inputs.push(fs::read(&path).context(format!("ファイルを読み込めません: {}", path.display()))?);
+        }
+    }
+    if inputs.is_empty() {
+        bail!("コーパスが空です: {}", corpus.display());
+    }
+    
+    let total = inputs.len();
+    let kept = select_inputs(inputs, target);
+    fs::create_dir_all(output).context(format!("ディレクトリを作成できません: {}", output.display()))?;
+    for data in &kept {
+        write_file(&output.join(content_name(data)), data)?;
+    }
+    
+    debug!("コーパスを最小化しました: {} -> {}", total, kept.len());
+    Ok(MinimizeReport { total, kept: kept.len() })
+}
+
+/// 新しい特徴を増やす入力だけを、小さいものから選ぶ
+fn select_inputs(mut inputs: Vec<Vec<u8>>, target: FuzzTarget) -> Vec<Vec<u8>> {
+    inputs.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    inputs.dedup();
+    
+    let mut seen = BTreeSet::new();
+    let mut kept = Vec::new();
+    for data in inputs {
+        let features = features(&data, target);
+        if !features.is_subset(&seen) {
+            seen.extend(features);
+            kept.push(data);
+        }
+    }
+    kept
+}
+
+/// 入力がフロントエンドで通った経路を表す特徴
+fn features(data: &[u8], target: FuzzTarget) -> BTreeSet<String> {
+    let data = match target {
+        FuzzTarget::Structured => structured_source(data).into_bytes(),
+        _ => data.to_vec(),
+    };
+    let mut features = BTreeSet::new();
+    
+    let tokens = match tokenize(&data) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            features.insert(format!("lex-error:{}", error_kind(&e)));
+            return features;
+        }
+    };
+    features.extend(tokens.iter().map(|token| format!("token:{}", variant_name(&token.kind))));
+    if target == FuzzTarget::Lexer {
+        return features;
+    }
+    
+    let program = match Parser::new(tokens, PathBuf::from(FUZZ_FILE)).parse() {
+        Ok(program) => program,
+        Err(e) => {
+            features.insert(format!("parse-error:{}", error_kind(&e)));
+            return features;
+        }
+    };
+    NodeKinds(&mut features).visit_program(&program);
+    if target == FuzzTarget::Parser {
+        return features;
+    }
+    
+    match type_check(&data) {
+        Ok(_) => features.insert("type-ok".to_string()),
+        Err(e) => features.insert(format!("type-error:{}", error_kind(&e))),
+    };
+    features
+}
+
+/// 出現したノードの種類を特徴に加える
+struct NodeKinds<'a>(&'a mut BTreeSet<String>);
+
+impl Visitor for NodeKinds<'_> {
+    fn visit_node(&mut self, node: &ASTNode) {
+        self.0.insert(format!("node:{}", variant_name(&node.kind)));
+        ast::walk(self, node);
+    }
+}
+
+/// `Debug` 表示から列挙子の名前だけを取り出す
+fn variant_name<T: std::fmt::Debug>(value: &T) -> String {
+    let debug = format!("{:?}", value);
+    debug.split(['(', ' ', '{']).next().unwrap_or_default().to_string()
+}
+
+/// エラーの種類（位置や識別子名を除いたメッセージの先頭）
+fn error_kind(error: &crate::core::EidosError) -> String {
+    error.to_string().chars().take_while(|c| *c != ':' && *c != '\'' && !c.is_ascii_digit()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_structured_source_parses() {
+        for seed in 0u8..=255 {
+            let data: Vec<u8> = (0..64u32).map(|i| seed.wrapping_mul(31).wrapping_add((i * 7) as u8)).collect();
+            let source = structured_source(&data);
+            assert!(parse(source.as_bytes()).is_ok(), "生成したプログラムを構文解析できません:\n{}", source);
+        }
+        assert_eq!(structured_source(b"abc"), structured_source(b"abc"));
+    }
+    
+    #[test]
+    fn test_select_inputs() {
+        let inputs = vec![
+            b"let x = 1;".to_vec(),
+            b"let yy = 22;".to_vec(),
+            b"let x = 1;".to_vec(),
+            b"fn f() -> Int { 1 }".to_vec(),
+        ];
+        let kept = select_inputs(inputs, FuzzTarget::Parser);
+        assert_eq!(kept, vec![b"let x = 1;".to_vec(), b"fn f() -> Int { 1 }".to_vec()]);
+    }
+    
+    #[test]
+    fn test_generate_harness() {
+        let dir = tempfile::tempdir().unwrap();
+        let seed = dir.path().join("seed.eid");
+        fs::write(&seed, "let x = 1;").unwrap();
+        
+        let written = generate_harness(&dir.path().join("fuzz"), Path::new("."), &[seed]).unwrap();
+        assert_eq!(written.len(), 1 + FuzzTarget::all().len() * 2);
+        let target = fs::read_to_string(dir.path().join("fuzz/fuzz_targets/parser.rs")).unwrap();
+        assert!(target.contains("eidos::tools::fuzz::fuzz_parser(data);"));
+        let manifest = fs::read_to_string(dir.path().join("fuzz/Cargo.toml")).unwrap();
+        assert!(manifest.contains("name = \"structured\""));
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use log::info;
+
+use crate::dsl::highlight::HighlightSpec;
+use crate::dsl::DSLRegistry;
+use crate::frontend::lexer::Lexer;
+use crate::frontend::macro_expander::MacroExpander;
+use crate::frontend::parser::Parser;
+
+/// 構文強調の定義の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum HighlightFormat {
+    /// TextMate の文法（VS Code などで使う `.tmLanguage.json`）
+    #[default]
+    #[value(name = "tmLanguage")]
+    TmLanguage,
+    /// tree-sitter の強調クエリ（`highlights.scm`）
+    TreeSitter,
+}
+
+/// `eidos highlight` の設定
+#[derive(Debug, Clone, Default)]
+pub struct HighlightOptions {
+    pub emit: HighlightFormat,
+    /// 先に構文解析し、`syntax`・`peg` で定義したDSLを登録するファイル
+    pub files: Vec<PathBuf>,
+    /// 出力先（省略すると標準出力）
+    pub output: Option<PathBuf>,
+}
+
+/// 現在有効な文法から構文強調の定義を生成
+///
+/// 基本の言語の予約語に、プラグインなどで登録済みのDSLと、`files` の中で定義したDSLのキーワードを加える。
+pub fn highlight(options: &HighlightOptions) -> Result<()> {
+    for file in &options.files {
+        register_dsls(file)?;
+    }
+    
+    let spec = {
+        let registry = DSLRegistry::global().read().unwrap();
+        HighlightSpec::from_registry(&registry)
+    };
+    info!("DSLのキーワードを含めました: {} 個のDSL", spec.dsls.len());
+    let text = match options.emit {
+        HighlightFormat::TmLanguage => spec.to_tm_language(),
+        HighlightFormat::TreeSitter => spec.to_tree_sitter_queries(),
+    };
+    
+    match &options.output {
+        Some(path) => {
+            std::fs::write(path, text)
+                .with_context(|| format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+            info!("出力しました: {}", path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// ファイルを構文解析し、その中の `syntax`・`peg` 定義をグローバルレジストリに登録
+pub fn register_dsls(file: &Path) -> Result<()> {
+    let source = std::fs::read_to_string(file)
+        .with_context(|| format!("ファイルの読み込みに失敗しました: {}", file.display()))?;
+    let tokens = Lexer::new(&source, file.to_path_buf()).tokenize()?;
+    let tokens = MacroExpander::new().expand(tokens)?;
+    Parser::new(tokens, file.to_path_buf()).parse()?;
+    Ok(())
+}
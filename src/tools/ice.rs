@@ -0,0 +1,327 @@
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use crate::core::error::CommandFailed;
+use crate::core::session::CompileSession;
+use crate::core::ExitCode;
+use crate::dsl::DSLRegistry;
+
+/// 不具合の報告先
+const ISSUE_URL: &str = "https://github.com/eidos-lang/eidos/issues/new";
+
+/// 入力を縮小するときにコンパイルをやり直す回数の上限
+const MAX_REDUCE_ATTEMPTS: usize = 200;
+
+/// パニックの時点で記録した内容
+struct PanicRecord {
+    message: String,
+    /// パニックしたコンパイラのソース上の位置（縮小した入力で同じ不具合が起きたかの判定に使う）
+    location: Option<String>,
+    backtrace: String,
+}
+
+thread_local! {
+    static LAST_PANIC: RefCell<Option<PanicRecord>> = const { RefCell::new(None) };
+}
+
+/// コンパイラ内部のエラー（ICE）の報告
+pub struct BugReport {
+    /// 入力ファイル
+    pub file: PathBuf,
+    /// 入力のソース（読めなかった場合は `None`）
+    pub source: Option<String>,
+    /// パニックした時点で実行していたフェーズ
+    pub phase: String,
+    /// パニックのメッセージと位置
+    pub message: String,
+    pub backtrace: String,
+    /// 入力で `use dsl` により有効にしたDSL拡張
+    pub used_dsls: Vec<String>,
+    /// パニックの時点でレジストリに登録されていたDSL拡張
+    pub registered_dsls: Vec<String>,
+    /// 同じ不具合が起きる範囲で行を取り除いた入力
+    pub reduced_source: Option<String>,
+}
+
+impl BugReport {
+    /// 報告をディレクトリに書き出す
+    ///
+    /// `report.md` に報告の本文を、`source/` に入力のソースと縮小した入力を置く。
+    pub fn write(&self, dir: &Path) -> std::io::Result<()> {
+        let source_dir = dir.join("source");
+        std::fs::create_dir_all(&source_dir)?;
+        let file_name = self.file.file_name().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("input.eid"));
+        if let Some(source) = &self.source {
+            std::fs::write(source_dir.join(&file_name), source)?;
+        }
+        if let Some(reduced) = &self.reduced_source {
+            std::fs::write(source_dir.join(file_name.with_extension("reduced.eid")), reduced)?;
+        }
+        std::fs::write(dir.join("report.md"), self.render())
+    }
+    
+    /// 報告の本文（Markdown）
+    pub fn render(&self) -> String {
+        let list = |names: &[String]| if names.is_empty() { "なし".to_string() } else { names.join(", ") };
+        let mut out = String::new();
+        let _ = writeln!(out, "# コンパイラ内部のエラー\n");
+        let _ = writeln!(out, "- バージョン: eidos {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(out, "- ホスト: {}-{}", std::env::consts::ARCH, std::env::consts::OS);
+        let _ = writeln!(out, "- コマンド: `{}`", std::env::args().collect::<Vec<_>>().join(" "));
+        let _ = writeln!(out, "- 入力ファイル: {}", self.file.display());
+        let _ = writeln!(out, "- フェーズ: {}", self.phase);
+        let _ = writeln!(out, "- `use dsl` で有効にしたDSL拡張: {}", list(&self.used_dsls));
+        let _ = writeln!(out, "- 登録済みのDSL拡張: {}", list(&self.registered_dsls));
+        let _ = writeln!(out, "\n## パニック\n\n```\n{}\n```", self.message);
+        if let Some(reduced) = &self.reduced_source {
+            let _ = writeln!(out, "\n## 縮小した入力\n\n```eidos\n{}```", reduced);
+        }
+        let _ = writeln!(out, "\n## バックトレース\n\n```\n{}\n```", self.backtrace.trim_end());
+        out
+    }
+}
+
+/// コンパイルを実行し、パニックしたら不具合の報告を書き出してICEのエラーにする
+///
+/// Rustのパニックをそのまま利用者に見せる代わりに、入力のソース、実行していたフェーズ
+/// （`CompileSession::time` で囲んだ最も内側のフェーズ）、バックトレース、コンパイラのバージョン、
+/// 有効なDSL拡張を一時ディレクトリにまとめ、報告の手順を案内する。終了コードは `ExitCode::InternalError`。
+///
+/// `rerun` は候補の入力ファイルを新しいセッションでコンパイルし直す関数で、同じフェーズの同じ位置で
+/// パニックする範囲で入力の行を取り除き、縮小した入力も報告に添える。`rerun` は一時ディレクトリの
+/// ファイルを受け取るので、利用者の出力ファイルを書き換えないようにする。
+pub fn catch<T>(
+    file: &Path,
+    session: &CompileSession,
+    compile: impl FnOnce() -> Result<T>,
+    rerun: impl Fn(&Path, &Rc<CompileSession>),
+) -> Result<T> {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(record_panic));
+    let result = panic::catch_unwind(AssertUnwindSafe(compile));
+    let result = match result {
+        Ok(result) => Ok(result),
+        Err(_) => {
+            let record = take_panic();
+            let phase = session.current_phase();
+            let report = build_report(file, phase.clone(), &record, rerun);
+            Err(report)
+        }
+    };
+    panic::set_hook(previous_hook);
+    
+    match result {
+        Ok(result) => result,
+        Err(report) => {
+            let dir = bundle_dir();
+            let written = report.write(&dir);
+            let mut message = format!("コンパイラ内部のエラー: {}の途中でパニックしました: {}", report.phase, report.message);
+            match written {
+                Ok(()) => {
+                    let _ = write!(
+                        message,
+                        "\n不具合の報告を {} に書き出しました。report.md の内容と source/ のファイルを添えて {} から報告してください",
+                        dir.display(),
+                        ISSUE_URL
+                    );
+                }
+                Err(e) => {
+                    let _ = write!(message, "\n不具合の報告を書き出せませんでした（{}）: {}\n{}", dir.display(), e, report.render());
+                }
+            }
+            Err(CommandFailed { message, exit_code: ExitCode::InternalError }.into())
+        }
+    }
+}
+
+/// パニックのメッセージと位置、バックトレースを記録する（既定のパニックの表示の代わり）
+fn record_panic(info: &panic::PanicHookInfo<'_>) {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "（メッセージなし）".to_string());
+    let location = info.location().map(|location| location.to_string());
+    let backtrace = Backtrace::force_capture().to_string();
+    LAST_PANIC.with(|last| *last.borrow_mut() = Some(PanicRecord { message, location, backtrace }));
+}
+
+fn take_panic() -> PanicRecord {
+    LAST_PANIC.with(|last| last.borrow_mut().take()).unwrap_or(PanicRecord {
+        message: "（メッセージなし）".to_string(),
+        location: None,
+        backtrace: String::new(),
+    })
+}
+
+fn build_report(
+    file: &Path,
+    phase: Option<String>,
+    record: &PanicRecord,
+    rerun: impl Fn(&Path, &Rc<CompileSession>),
+) -> BugReport {
+    let source = std::fs::read_to_string(file).ok();
+    let reduced_source = source.as_ref().and_then(|source| {
+        let dir = tempfile::tempdir().ok()?;
+        let candidate = dir.path().join(file.file_name()?);
+        let mut reproduces = |text: &str| {
+            if std::fs::write(&candidate, text).is_err() {
+                return false;
+            }
+            let session = Rc::new(CompileSession::new(false));
+            let panicked = panic::catch_unwind(AssertUnwindSafe(|| rerun(&candidate, &session))).is_err();
+            panicked && take_panic().location == record.location && session.current_phase() == phase
+        };
+        // 一時ディレクトリに置くと再現しない入力（`import` など）は縮小しない
+        if !reproduces(source.as_str()) {
+            return None;
+        }
+        Some(reduce(source, reproduces))
+    });
+    
+    let registered_dsls = {
+        let registry = DSLRegistry::global().read().unwrap_or_else(|e| e.into_inner());
+        let mut names = registry.list_extensions();
+        names.sort();
+        names
+    };
+    
+    BugReport {
+        file: file.to_path_buf(),
+        used_dsls: source.as_deref().map(used_dsls).unwrap_or_default(),
+        source,
+        phase: phase.unwrap_or_else(|| "フェーズの外".to_string()),
+        message: match &record.location {
+            Some(location) => format!("{}（{}）", record.message, location),
+            None => record.message.clone(),
+        },
+        backtrace: record.backtrace.clone(),
+        registered_dsls,
+        reduced_source,
+    }
+}
+
+/// 不具合が再現する範囲で、入力の行をまとまりごとに取り除く
+///
+/// 取り除く行数を半分ずつ減らしながら試す（delta debugging の簡略版）。
+/// コンパイルのやり直しは `MAX_REDUCE_ATTEMPTS` 回までにする。
+fn reduce(source: &str, mut reproduces: impl FnMut(&str) -> bool) -> String {
+    let mut lines: Vec<&str> = source.lines().collect();
+    let mut chunk = (lines.len() / 2).max(1);
+    let mut attempts = 0;
+    loop {
+        let mut removed = false;
+        let mut start = 0;
+        while start < lines.len() && attempts < MAX_REDUCE_ATTEMPTS {
+            let end = (start + chunk).min(lines.len());
+            let candidate: Vec<&str> = lines[..start].iter().chain(&lines[end..]).copied().collect();
+            attempts += 1;
+            if reproduces(&join_lines(&candidate)) {
+                lines = candidate;
+                removed = true;
+            } else {
+                start = end;
+            }
+        }
+        if attempts >= MAX_REDUCE_ATTEMPTS || (chunk == 1 && !removed) {
+            break;
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    join_lines(&lines)
+}
+
+fn join_lines(lines: &[&str]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// 入力の `use dsl name;` で有効にしたDSL拡張の名前
+fn used_dsls(source: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for line in source.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        for window in words.windows(3) {
+            if window[0] == "use" && window[1] == "dsl" {
+                let name = window[2].trim_end_matches(';').to_string();
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    names
+}
+
+/// 報告を書き出すディレクトリ（一時ディレクトリの下に、時刻とプロセスIDで名前を付ける）
+fn bundle_dir() -> PathBuf {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    std::env::temp_dir().join(format!("eidos-ice-{}-{}", timestamp, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_reduce_keeps_failing_lines() {
+        let source = "fn a() {}\nfn b() {}\nlet x = boom();\nfn c() {}\nprintln(1);\n";
+        let mut attempts = 0;
+        let reduced = reduce(source, |candidate| {
+            attempts += 1;
+            candidate.contains("boom")
+        });
+        assert_eq!(reduced, "let x = boom();\n");
+        assert!(attempts <= MAX_REDUCE_ATTEMPTS);
+        assert_eq!(used_dsls("use dsl sql;\nuse dsl  regex ;\nuse dsl sql;"), vec!["sql", "regex"]);
+    }
+    
+    #[test]
+    fn test_panic_becomes_bug_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("crash.eid");
+        std::fs::write(&file, "use dsl sql;\nfn ok() -> Int { 1 }\ncrash();\nprintln(ok());\n").unwrap();
+        let compile = |file: &Path, session: &Rc<CompileSession>| {
+            let source = std::fs::read_to_string(file).unwrap();
+            session.time("型検査", || {
+                if source.contains("crash") {
+                    panic!("型が見つかりません");
+                }
+            });
+        };
+        
+        let session = Rc::new(CompileSession::new(false));
+        let error = catch(&file, &session, || -> Result<()> {
+            compile(&file, &session);
+            Ok(())
+        }, compile)
+        .unwrap_err();
+        let failed = error.downcast_ref::<CommandFailed>().unwrap();
+        assert_eq!(failed.exit_code, ExitCode::InternalError);
+        assert!(failed.message.contains("型検査の途中でパニックしました: 型が見つかりません"), "{}", failed.message);
+        
+        // 報告の書き出し先を案内する
+        let bundle = failed.message.lines().nth(1).unwrap();
+        let bundle = PathBuf::from(bundle.split(' ').nth(1).unwrap());
+        let report = std::fs::read_to_string(bundle.join("report.md")).unwrap();
+        assert!(report.contains("- フェーズ: 型検査"), "{}", report);
+        assert!(report.contains("`use dsl` で有効にしたDSL拡張: sql"), "{}", report);
+        assert!(report.contains(env!("CARGO_PKG_VERSION")));
+        assert!(bundle.join("source/crash.eid").exists());
+        let reduced = std::fs::read_to_string(bundle.join("source/crash.reduced.eid")).unwrap();
+        assert_eq!(reduced, "crash();\n");
+        std::fs::remove_dir_all(bundle).unwrap();
+        
+        // パニックしなければ結果をそのまま返す
+        assert_eq!(catch(&file, &session, || Ok(42), |_, _| {}).unwrap(), 42);
+    }
+}
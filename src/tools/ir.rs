@@ -0,0 +1,93 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use log::info;
+
+use crate::backend::optimizer::{OptimizationLevel, OptimizationOptions, OptimizationPass, Optimizer};
+use crate::core::analysis;
+use crate::core::call_graph::CallGraph;
+use crate::core::eir::Module;
+use crate::core::session::CompileSession;
+use crate::tools::compiler;
+
+/// `eidos ir` の設定
+#[derive(Debug, Clone, Default)]
+pub struct IrOptions {
+    /// EIRの代わりに呼び出しグラフを DOT 形式で出力する
+    pub callgraph: bool,
+    /// EIRの代わりに、この関数の制御フローグラフを DOT 形式で出力する
+    pub dot_cfg: Option<String>,
+    /// 制御フローグラフに支配木を重ねる
+    pub dominators: bool,
+    /// 最適化レベル（0 なら最適化しない）
+    pub opt_level: u8,
+    /// 制御フローグラフを出力する最適化パス（空なら最適化の前後だけ）
+    pub dot_after: Vec<OptimizationPass>,
+    /// マクロ展開の過程を表示する
+    pub trace_macros: bool,
+    /// 出力先（省略すると標準出力）
+    pub output: Option<PathBuf>,
+}
+
+/// ファイルのEIRを構築してテキストで出力
+///
+/// `--callgraph` では関数どうしの呼び出し関係を、`--dot-cfg` では1つの関数の基本ブロックを
+/// Graphviz の DOT 形式で出力する（`eidos ir --callgraph main.eid | dot -Tsvg > callgraph.svg`）。
+/// `--dot-cfg` は最適化の前のグラフに続けて、`--dot-after` で選んだパスの後（省略すると最適化の後）のグラフを出力する。
+pub fn ir(file: &Path, options: &IrOptions) -> Result<()> {
+    if !options.dot_after.is_empty() && options.opt_level == 0 {
+        bail!("--dot-after で選んだパスを実行するには -O1 以上を指定してください");
+    }
+    
+    let session = CompileSession::new(false);
+    let mut module = compiler::build_eir(file, options.trace_macros, &session)?;
+    
+    let mut graphs = Vec::new();
+    if let Some(name) = &options.dot_cfg {
+        graphs.push(function_cfg(&module, name, "最適化前", options.dominators)?);
+    }
+    
+    if options.opt_level > 0 {
+        let mut optimizer = Optimizer::new(OptimizationOptions {
+            level: OptimizationLevel::from(options.opt_level),
+            snapshot_passes: options.dot_after.iter().copied().collect::<HashSet<_>>(),
+            ..Default::default()
+        });
+        optimizer.optimize_module(&mut module)?;
+        
+        if let Some(name) = &options.dot_cfg {
+            for (pass, snapshot) in optimizer.take_snapshots() {
+                graphs.push(function_cfg(&snapshot, name, &format!("{}の後", pass), options.dominators)?);
+            }
+            if options.dot_after.is_empty() {
+                graphs.push(function_cfg(&module, name, "最適化後", options.dominators)?);
+            }
+        }
+    }
+    
+    let text = if options.callgraph {
+        CallGraph::new(&module).to_dot()
+    } else if options.dot_cfg.is_some() {
+        graphs.concat()
+    } else {
+        module.to_string()
+    };
+    
+    match &options.output {
+        Some(path) => {
+            std::fs::write(path, text)
+                .with_context(|| format!("ファイルの書き込みに失敗しました: {}", path.display()))?;
+            info!("出力しました: {}", path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// モジュール内の関数の制御フローグラフ（`stage` はグラフの見出しに添える段階の名前）
+fn function_cfg(module: &Module, name: &str, stage: &str, dominators: bool) -> Result<String> {
+    let function = module
+        .get_function_by_name(name)
+        .ok_or_else(|| anyhow!("関数 `{}` がモジュール '{}' にありません", name, module.name))?;
+    Ok(analysis::cfg_to_dot(function, &format!("{}（{}）", name, stage), dominators))
+}
@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::core::error::CommandFailed;
+use crate::core::ExitCode;
+use crate::frontend::lexer::{Lexer, TokenKind};
+
+/// ファイルを字句解析し、トークンを位置と共に1行ずつ表示
+///
+/// 既定では `行:列  種類  字面` の表形式で表示する。`json` では1トークンを1行の JSON
+/// （`kind`・`text`・`line`・`column`・`start`・`end`。`start`・`end` はバイト位置）にするので、
+/// エディタのトークナイザーやDSLの文法のデバッグに使える。字句エラーは `error` として出力し、続きを読む。
+/// `json` は全体の `--json` で指定する。
+pub fn lex(file: &Path, json: bool) -> Result<()> {
+    let source = std::fs::read_to_string(file)
+        .with_context(|| format!("ファイルの読み込みに失敗しました: {}", file.display()))?;
+    
+    let mut out = String::new();
+    let mut errors = 0;
+    for token in Lexer::new(&source, file.to_path_buf()).spanned() {
+        match token {
+            Ok(token) => {
+                let text = &source[token.span.clone()];
+                let location = &token.token.location;
+                if json {
+                    let _ = writeln!(
+                        out,
+                        "{}",
+                        serde_json::json!({
+                            "kind": kind_name(&token.token.kind),
+                            "text": text,
+                            "line": location.line,
+                            "column": location.column,
+                            "start": token.span.start,
+                            "end": token.span.end,
+                        })
+                    );
+                } else {
+                    let position = format!("{}:{}", location.line, location.column);
+                    let _ = writeln!(out, "{:<8} {:<14} {}", position, kind_name(&token.token.kind), text.escape_debug());
+                }
+            }
+            Err(error) => {
+                errors += 1;
+                if json {
+                    let _ = writeln!(out, "{}", serde_json::json!({ "error": error.to_string() }));
+                } else {
+                    let _ = writeln!(out, "error: {}", error);
+                }
+            }
+        }
+    }
+    print!("{}", out);
+    
+    if errors > 0 {
+        return Err(CommandFailed {
+            message: format!("字句エラーが{}件ありました", errors),
+            exit_code: ExitCode::ProgramError,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// トークンの種類の名前（`TokenKind` のバリアント名）
+fn kind_name(kind: &TokenKind) -> String {
+    let debug = format!("{:?}", kind);
+    match debug.find('(') {
+        Some(end) => debug[..end].to_string(),
+        None => debug,
+    }
+}
@@ -1,3 +1,20 @@
+pub mod ast;
+pub mod bench;
+pub mod bindgen;
+pub mod check;
 pub mod compiler;
+pub mod cov;
+pub mod demangle;
+pub mod deps;
+pub mod explain;
+pub mod fuzz;
+pub mod highlight;
+pub mod ice;
+pub mod ir;
+pub mod lex;
+pub mod output;
+pub mod project;
 pub mod repl;
-pub mod runner; 
\ No newline at end of file
+pub mod runner;
+pub mod serve;
+pub mod watch;
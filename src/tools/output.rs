@@ -0,0 +1,88 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use colored::Colorize;
+
+use crate::core::error::CommandFailed;
+use crate::core::{EidosError, ExitCode};
+
+/// `--quiet`: 結果以外の表示（成功の報告や統計）をしない
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// `--json`: 診断とエラーを1行ごとのJSONで出力する
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// すべてのサブコマンドに共通する出力の設定を記録する（`main` が最初に呼ぶ）
+///
+/// コマンドの結果は標準出力に、診断（エラーと警告）は標準エラー出力にだけ書く。
+/// `--json` では診断を `{"type": "diagnostic", ...}`、コマンドの失敗を `{"type": "error", ...}` の
+/// 1行のJSONにするので、スクリプトは標準エラー出力を行ごとに読めばよい。
+pub fn configure(quiet: bool, json: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+    JSON.store(json, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// 成功などの状況を標準出力に表示（`--quiet` と `--json` では表示しない）
+pub fn status(message: impl Display) {
+    if !is_quiet() && !is_json() {
+        println!("{}", message);
+    }
+}
+
+/// 警告を標準エラー出力に表示
+pub fn warning(message: impl Display) {
+    if is_json() {
+        eprintln!(
+            "{}",
+            serde_json::json!({ "type": "diagnostic", "severity": "warning", "message": message.to_string() })
+        );
+    } else {
+        eprintln!("{} {}", "警告:".yellow().bold(), message);
+    }
+}
+
+/// コマンドの失敗を標準エラー出力に表示し、終了コードを返す
+///
+/// コンパイラのエラーにはエラーコードを添え、説明の参照先を案内する。
+pub fn report_error(error: &anyhow::Error) -> ExitCode {
+    let eidos_error = error.chain().find_map(|cause| cause.downcast_ref::<EidosError>());
+    let exit_code = match (eidos_error, error.downcast_ref::<CommandFailed>()) {
+        (_, Some(failed)) => failed.exit_code,
+        (Some(eidos_error), None) => eidos_error.exit_code(),
+        (None, None) => ExitCode::Failure,
+    };
+    
+    if is_json() {
+        let code = eidos_error.map(|eidos_error| eidos_error.code().to_string());
+        eprintln!(
+            "{}",
+            serde_json::json!({
+                "type": "error",
+                "code": code,
+                "message": error.to_string(),
+                "exit_code": exit_code.code(),
+                "kind": exit_code.name(),
+            })
+        );
+        return exit_code;
+    }
+    match eidos_error {
+        Some(eidos_error) => {
+            let code = eidos_error.code();
+            eprintln!("エラー[{}]: {}", code, error);
+            eprintln!("このエラーの詳細は `eidos explain {}` で確認できます", code);
+        }
+        None => eprintln!("エラー: {}", error),
+    }
+    if exit_code == ExitCode::InternalError {
+        eprintln!("これはコンパイラの不具合です。再現するソースコードを添えて報告してください");
+    }
+    exit_code
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use log::info;
+use serde::Deserialize;
+
+use crate::core::range_analysis;
+use crate::stdlib::StdlibModule;
+use crate::tools::compiler::{CompileTarget, LintLevel};
+use crate::tools::highlight;
+
+/// プロジェクトの設定ファイルの名前（同じディレクトリにあれば先のものを使う）
+pub const MANIFEST_FILES: &[&str] = &["eidos.toml", ".eidos.toml"];
+
+/// プロジェクトの設定（`eidos.toml`）
+///
+/// `eid build`・`check`・`run`・`repl` は、入力ファイル（REPLでは現在のディレクトリ）のディレクトリから
+/// 親へ向かって最初に見つかった設定ファイルを読み込む。コマンドラインで指定したオプションは設定より優先する。
+/// `[package]` と `[dependencies]` はパッケージマネージャのために予約しており、コンパイラは読まない。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// 設定ファイルのパス（設定の中の相対パスは、このファイルのディレクトリを基準にする）
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub package: Option<toml::Table>,
+    pub dependencies: Option<toml::Table>,
+    pub build: BuildConfig,
+    /// リントごとの扱い（`division_by_zero = "deny"` など。指定のないリントは警告する）
+    pub lints: HashMap<String, LintLevel>,
+    pub dsl: DslConfig,
+    /// 標準ライブラリのモジュールごとの有効・無効（`net = false` など。指定のないモジュールは有効）
+    pub stdlib: HashMap<String, bool>,
+}
+
+/// `[build]`: `eid build` の既定値
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BuildConfig {
+    /// 最適化レベル（0-3）
+    pub opt_level: Option<u8>,
+    /// 出力形式（`--emit` と同じ名前）
+    pub target: Option<CompileTarget>,
+}
+
+/// `[dsl]`: コンパイルの前に登録するDSL拡張
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DslConfig {
+    /// 先に構文解析し、`syntax`・`peg` で定義したDSLを登録するファイル
+    pub preload: Vec<PathBuf>,
+}
+
+impl ProjectConfig {
+    /// 設定ファイルの内容を解析して検査する（`path` はエラーの表示と相対パスの基準に使う）
+    pub fn parse(text: &str, path: &Path) -> Result<Self> {
+        let mut config: ProjectConfig =
+            toml::from_str(text).with_context(|| format!("設定ファイルを読み込めません: {}", path.display()))?;
+        config.path = path.to_path_buf();
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// 設定ファイルを読み込む
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("設定ファイルの読み込みに失敗しました: {}", path.display()))?;
+        Self::parse(&text, path)
+    }
+    
+    /// `start`（ファイルまたはディレクトリ）のディレクトリから親へ向かって設定ファイルを探して読み込む
+    pub fn discover(start: &Path) -> Result<Option<Self>> {
+        let start = start.canonicalize().unwrap_or_else(|_| start.to_path_buf());
+        let dir = if start.is_dir() { start.as_path() } else { start.parent().unwrap_or(Path::new("")) };
+        for dir in dir.ancestors() {
+            for name in MANIFEST_FILES {
+                let path = dir.join(name);
+                if path.is_file() {
+                    info!("プロジェクトの設定を読み込みます: {}", path.display());
+                    return Self::load(&path).map(Some);
+                }
+            }
+        }
+        Ok(None)
+    }
+    
+    /// 設定ファイルのあるディレクトリ
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+    
+    /// 先に読み込むDSLのファイル（設定ファイルのディレクトリを基準にしたパス）
+    pub fn dsl_preloads(&self) -> Vec<PathBuf> {
+        self.dsl.preload.iter().map(|file| self.root().join(file)).collect()
+    }
+    
+    /// 無効にした標準ライブラリのモジュール
+    pub fn disabled_stdlib(&self) -> HashSet<StdlibModule> {
+        self.stdlib
+            .iter()
+            .filter(|(_, enabled)| !**enabled)
+            .filter_map(|(name, _)| StdlibModule::from_name(name))
+            .collect()
+    }
+    
+    /// DSLを登録し、標準ライブラリのモジュールを無効にする（コマンドの処理の前に1度呼ぶ）
+    pub fn apply(&self) -> Result<()> {
+        for file in self.dsl_preloads() {
+            highlight::register_dsls(&file)
+                .with_context(|| format!("{} の [dsl] で指定したファイルを読み込めません", self.path.display()))?;
+        }
+        StdlibModule::set_disabled(self.disabled_stdlib());
+        Ok(())
+    }
+    
+    fn validate(&self) -> Result<()> {
+        if let Some(level) = self.build.opt_level.filter(|level| *level > 3) {
+            bail!("{}: [build] の opt-level は 0 から 3 です（指定された値: {}）", self.path.display(), level);
+        }
+        let mut lints: Vec<&String> = self.lints.keys().collect();
+        lints.sort();
+        if let Some(lint) = lints.into_iter().find(|lint| !range_analysis::LINTS.contains(&lint.as_str())) {
+            bail!(
+                "{}: [lints] の '{}' というリントはありません（{}）",
+                self.path.display(),
+                lint,
+                range_analysis::LINTS.join(", ")
+            );
+        }
+        let mut modules: Vec<&String> = self.stdlib.keys().collect();
+        modules.sort();
+        if let Some(module) = modules.into_iter().find(|module| StdlibModule::from_name(module).is_none()) {
+            bail!("{}: [stdlib] の '{}' という標準ライブラリのモジュールはありません", self.path.display(), module);
+        }
+        Ok(())
+    }
+}
+
+/// `start` から設定ファイルを探して適用する（見つからなければ既定の設定）
+pub fn load(start: &Path) -> Result<ProjectConfig> {
+    let config = ProjectConfig::discover(start)?.unwrap_or_default();
+    config.apply()?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_config() {
+        let config = ProjectConfig::parse(
+            r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+            
+            [build]
+            opt-level = 3
+            target = "c"
+            
+            [lints]
+            division_by_zero = "deny"
+            constant_comparison = "allow"
+            
+            [dsl]
+            preload = ["dsl/sql.eid"]
+            
+            [stdlib]
+            net = false
+            fs = true
+            "#,
+            Path::new("project/eidos.toml"),
+        )
+        .unwrap();
+        assert_eq!(config.build.opt_level, Some(3));
+        assert_eq!(config.build.target, Some(CompileTarget::C));
+        assert_eq!(config.lints[range_analysis::DIVISION_BY_ZERO], LintLevel::Deny);
+        assert_eq!(config.dsl_preloads(), vec![PathBuf::from("project/dsl/sql.eid")]);
+        assert_eq!(config.disabled_stdlib(), HashSet::from([StdlibModule::Net]));
+        
+        let error = ProjectConfig::parse("[build]\noptimization = 2", Path::new("eidos.toml")).unwrap_err();
+        assert!(format!("{:#}", error).contains("optimization"), "{:#}", error);
+        let error = ProjectConfig::parse("[lints]\nunused = \"deny\"", Path::new("eidos.toml")).unwrap_err();
+        assert!(error.to_string().contains("'unused' というリントはありません"), "{}", error);
+        let error = ProjectConfig::parse("[stdlib]\ngui = false", Path::new("eidos.toml")).unwrap_err();
+        assert!(error.to_string().contains("'gui' という標準ライブラリのモジュールはありません"), "{}", error);
+    }
+    
+    #[test]
+    fn test_discover_from_nested_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/util")).unwrap();
+        std::fs::write(dir.path().join("eidos.toml"), "[build]\nopt-level = 1\n").unwrap();
+        let file = dir.path().join("src/util/math.eid");
+        std::fs::write(&file, "println(1);").unwrap();
+        
+        let config = ProjectConfig::discover(&file).unwrap().unwrap();
+        assert_eq!(config.build.opt_level, Some(1));
+        assert_eq!(config.root(), dir.path().canonicalize().unwrap());
+        
+        // 近いディレクトリの設定を優先する
+        std::fs::write(dir.path().join("src/.eidos.toml"), "[build]\nopt-level = 0\n").unwrap();
+        let config = ProjectConfig::discover(&dir.path().join("src/util")).unwrap().unwrap();
+        assert_eq!(config.build.opt_level, Some(0));
+    }
+}
@@ -6,15 +6,293 @@ use log::{info, debug, error};
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
+use crate::backend::jit::{JitEngine, JitValue};
 use crate::core::{Result, EidosError, SourceLocation};
-use crate::frontend::{Lexer, Parser, TypeChecker, SemanticAnalyzer};
+use crate::core::ast::{ASTNode, Node, Program};
+use crate::core::eir::ModuleBuilder;
+use crate::core::source_map::LineIndex;
+use crate::frontend::{Lexer, Parser};
+use crate::frontend::lexer::{Token, TokenKind};
+use crate::tools::runner;
+
+/// 定義した関数を書き出しておくセッションの記録ファイル
+///
+/// 定義が変わるたびに書き直し、次にREPLを起動したときに読み込む。
+/// REPLが異常終了しても、それまでに定義した関数は失われない。
+const JOURNAL_FILE: &str = ".eidos_session.eid";
+
+/// REPLで定義した関数
+#[derive(Clone)]
+struct Definition {
+    node: ASTNode,
+    /// 定義のソースコード（`:save` とセッションの記録で書き出す）
+    source: String,
+}
+
+/// REPLの入力をまたいで保持する状態
+struct ReplSession {
+    /// 入力をまたいで関数のコンパイル結果をキャッシュするJIT
+    engine: JitEngine,
+    /// これまでに定義された関数（名前ごとに最新の定義だけを残す）
+    definitions: Vec<Definition>,
+    /// 定義が変わるたびに書き直すセッションの記録ファイル（`None` なら記録しない）
+    journal: Option<PathBuf>,
+}
+
+impl ReplSession {
+    fn new(journal: Option<PathBuf>) -> Result<Self> {
+        Ok(Self {
+            engine: JitEngine::new()?,
+            definitions: Vec::new(),
+            journal,
+        })
+    }
+    
+    /// 入力を評価し、表示すべき値があれば返す
+    ///
+    /// 以前の入力で定義した関数を呼べるよう、それらの定義を入力の前に置いてから解析する。
+    /// 変更のない関数はJITのキャッシュが再利用されるため再コンパイルされない。
+    fn evaluate(&mut self, input: &str) -> Result<Option<String>> {
+        // 仮想ファイルパス
+        let file_path = PathBuf::from("<repl>");
+        
+        // 字句解析・構文解析
+        let (ast, tokens) = parse(input, &file_path)?;
+        
+        // 同名の関数が入力で定義し直されていれば、古い定義は使わない
+        let definitions = extract_definitions(input, &ast, &tokens);
+        let typed_ast = self.check(&definitions, &ast.nodes)?;
+        
+        // 最後の式の値を返すエントリー関数を作ってJITで実行
+        let module = ModuleBuilder::new("<repl>".to_string())
+            .returning_last_value()
+            .build_from_ast(&typed_ast)?;
+        let value = self.engine.run(&module)?;
+        
+        // 実行に成功した定義だけを次の入力に引き継ぐ
+        if !definitions.is_empty() {
+            self.replace(definitions);
+            self.write_journal();
+        }
+        
+        Ok(match value {
+            JitValue::Unit => None,
+            value => Some(value.to_string()),
+        })
+    }
+    
+    /// これまでの定義（`nodes` と同名のものを除く）と `nodes` を合わせて検査する
+    fn check(&self, replaced: &[Definition], nodes: &[ASTNode]) -> Result<Program> {
+        let mut program = Program::new(PathBuf::from("<repl>"));
+        for definition in &self.definitions {
+            if !is_replaced(definition, replaced) {
+                program.add_node(definition.node.clone());
+            }
+        }
+        for node in nodes {
+            program.add_node(node.clone());
+        }
+        
+        // comptime ブロックの実行・意味解析・型チェック（`eid run` と同じ）
+        runner::check_program(program)
+    }
+    
+    /// 同名の古い定義を取り除いてから定義を追加する
+    fn replace(&mut self, definitions: Vec<Definition>) {
+        self.definitions.retain(|definition| !is_replaced(definition, &definitions));
+        self.definitions.extend(definitions);
+    }
+    
+    /// 定義したすべての関数のソースコード
+    fn source(&self) -> String {
+        let mut source = String::new();
+        for definition in &self.definitions {
+            source.push_str(&definition.source);
+            source.push_str("\n\n");
+        }
+        source.truncate(source.trim_end().len());
+        source.push('\n');
+        source
+    }
+    
+    /// 定義したすべての関数をファイルに書き出す（`:save`）
+    fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.source()).map_err(EidosError::IO)
+    }
+    
+    /// ファイルに書き出した定義でセッションを置き換える（`:open`、起動時のセッションの復元）
+    ///
+    /// ファイルの関数定義を1つずつ型チェックし直し、コンパイルできなくなった定義は読み込まずに
+    /// 名前とエラーを返す。関数定義以外の文は読み込まない。定義の順序によらず読み込めるよう、
+    /// 読み込めた定義が増えなくなるまで残りの定義を試し直す。
+    fn open(&mut self, path: &Path) -> Result<Vec<(String, EidosError)>> {
+        info!("セッションを読み込み中: {}", path.display());
+        let source = fs::read_to_string(path).map_err(EidosError::IO)?;
+        let (ast, tokens) = parse(&source, path)?;
+        let mut pending = extract_definitions(&source, &ast, &tokens);
+        
+        self.definitions.clear();
+        let all: Vec<ASTNode> = pending.iter().map(|definition| definition.node.clone()).collect();
+        if self.check(&[], &all).is_ok() {
+            self.definitions = pending;
+            self.write_journal();
+            return Ok(Vec::new());
+        }
+        
+        let mut failures = Vec::new();
+        loop {
+            failures.clear();
+            let mut progressed = false;
+            for definition in std::mem::take(&mut pending) {
+                match self.check(std::slice::from_ref(&definition), std::slice::from_ref(&definition.node)) {
+                    Ok(_) => {
+                        self.replace(vec![definition]);
+                        progressed = true;
+                    }
+                    Err(e) => {
+                        failures.push((function_name(&definition.node).unwrap_or_default().to_string(), e));
+                        pending.push(definition);
+                    }
+                }
+            }
+            if !progressed || pending.is_empty() {
+                break;
+            }
+        }
+        self.write_journal();
+        Ok(failures)
+    }
+    
+    /// すべての定義を取り除く（`:clear`）
+    fn clear(&mut self) {
+        self.definitions.clear();
+        self.write_journal();
+    }
+    
+    /// セッションの記録ファイルを書き直す（書き込めなくてもREPLは続ける）
+    fn write_journal(&self) {
+        let Some(journal) = &self.journal else {
+            return;
+        };
+        let result = if self.definitions.is_empty() {
+            fs::remove_file(journal).or_else(|e| if e.kind() == io::ErrorKind::NotFound { Ok(()) } else { Err(e) })
+        } else {
+            fs::write(journal, self.source())
+        };
+        if let Err(err) = result {
+            debug!("セッションの記録ファイルの書き込みに失敗: {}", err);
+        }
+    }
+}
+
+/// ソースコードを字句解析・構文解析する（定義のソースコードを切り出すためにトークンも返す）
+fn parse(source: &str, file_path: &Path) -> Result<(Program, Vec<Token>)> {
+    // 字句解析
+    let mut lexer = Lexer::new(source, file_path.to_path_buf());
+    let tokens = lexer.tokenize()?;
+    
+    // トークンの表示（デバッグ用）
+    debug!("トークン: {:?}", tokens);
+    
+    // 構文解析
+    let mut parser = Parser::new(tokens.clone(), file_path.to_path_buf());
+    let ast = parser.parse()?;
+    
+    // AST表示（デバッグ用）
+    debug!("AST: {:?}", ast);
+    
+    Ok((ast, tokens))
+}
+
+/// 関数定義ならその名前を返す
+fn function_name(node: &ASTNode) -> Option<&str> {
+    match &node.kind {
+        Node::FunctionDef { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+/// 同じ名前の関数が `definitions` で定義し直されているか
+fn is_replaced(definition: &Definition, definitions: &[Definition]) -> bool {
+    let name = function_name(&definition.node);
+    definitions.iter().any(|other| function_name(&other.node) == name)
+}
+
+/// 構文解析した入力から関数定義を取り出し、それぞれのソースコードを切り出す
+///
+/// 関数定義のノードの位置は `pub` や `effect` などの修飾子の先頭を指すので、その前にある属性
+/// （`#[export]` など）のトークンまで遡り、本体の閉じ括弧までを定義のソースコードとする。
+fn extract_definitions(source: &str, ast: &Program, tokens: &[Token]) -> Vec<Definition> {
+    let lines = LineIndex::new(source);
+    let offset = |location: &SourceLocation| lines.offset(source, location.line, location.column);
+    ast.nodes
+        .iter()
+        .filter(|node| function_name(node).is_some())
+        .filter_map(|node| {
+            let start = tokens.iter().position(|token| {
+                token.location.line == node.location.line && token.location.column == node.location.column
+            })?;
+            let start = attributes_start(tokens, start);
+            let end = definition_end(tokens, start)?;
+            let text = &source[offset(&tokens[start].location)?..offset(&tokens[end].location)? + 1];
+            Some(Definition { node: node.clone(), source: text.to_string() })
+        })
+        .collect()
+}
+
+/// `index` のトークンの直前に並ぶ属性（`#[name, ...]`）の先頭のトークンの位置
+fn attributes_start(tokens: &[Token], mut index: usize) -> usize {
+    while index > 0 && tokens[index - 1].kind == TokenKind::RightBracket {
+        let mut open = index - 1;
+        while open > 0 && matches!(tokens[open - 1].kind, TokenKind::Identifier(_) | TokenKind::Export | TokenKind::Comma) {
+            open -= 1;
+        }
+        if open < 2 || tokens[open - 1].kind != TokenKind::LeftBracket || tokens[open - 2].kind != TokenKind::Hash {
+            break;
+        }
+        index = open - 2;
+    }
+    index
+}
+
+/// `start` から始まる関数定義の本体の閉じ括弧のトークンの位置
+fn definition_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(start) {
+        match token.kind {
+            TokenKind::LeftBrace => depth += 1,
+            TokenKind::RightBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
 
 /// REPLを起動
 pub fn start_repl(preload: Option<Vec<PathBuf>>) -> Result<()> {
     info!("Eidos REPL を起動中");
     
     println!("Eidos REPL v0.1.0");
-    println!("'exit' または Ctrl+D で終了");
+    println!("'exit' または Ctrl+D で終了（':help' でコマンドの一覧）");
+    
+    let mut session = ReplSession::new(Some(PathBuf::from(JOURNAL_FILE)))?;
+    
+    // 前回のセッションの記録があれば復元する
+    let journal = Path::new(JOURNAL_FILE);
+    if journal.exists() {
+        match session.open(journal) {
+            Ok(failures) => {
+                println!("前回のセッションから {} 個の定義を復元しました", session.definitions.len());
+                report_failures(&failures);
+            }
+            Err(e) => eprintln!("前回のセッションを復元できません: {}", e),
+        }
+    }
     
     // 履歴機能付きの入力エディタを初期化
     let mut rl = Editor::<()>::new().expect("Rustylineの初期化に失敗しました");
@@ -28,7 +306,7 @@ pub fn start_repl(preload: Option<Vec<PathBuf>>) -> Result<()> {
     // 事前ロードファイルの処理
     if let Some(files) = preload {
         for file in files {
-            if let Err(e) = preload_file(&file) {
+            if let Err(e) = preload_file(&mut session, &file) {
                 eprintln!("ファイルのプリロードに失敗: {}: {}", file.display(), e);
             }
         }
@@ -53,11 +331,20 @@ pub fn start_repl(preload: Option<Vec<PathBuf>>) -> Result<()> {
                 // 入力を履歴に追加
                 rl.add_history_entry(&line);
                 
+                // ':' で始まる入力はREPLのコマンド
+                if let Some(command) = line.trim().strip_prefix(':') {
+                    if let Err(e) = run_command(&mut session, command) {
+                        eprintln!("エラー: {}", e);
+                    }
+                    continue;
+                }
+                
                 // 入力を評価
-                match evaluate_input(&line) {
-                    Ok(result) => {
+                match session.evaluate(&line) {
+                    Ok(Some(result)) => {
                         println!("{}", result);
                     },
+                    Ok(None) => {},
                     Err(e) => {
                         eprintln!("エラー: {}", e);
                     }
@@ -92,39 +379,51 @@ pub fn start_repl(preload: Option<Vec<PathBuf>>) -> Result<()> {
     Ok(())
 }
 
-/// 入力を評価
-fn evaluate_input(input: &str) -> Result<String> {
-    // 仮想ファイルパス
-    let file_path = PathBuf::from("<repl>");
-    
-    // 字句解析
-    let mut lexer = Lexer::new(input, file_path.clone());
-    let tokens = lexer.tokenize()?;
-    
-    // トークンの表示（デバッグ用）
-    debug!("トークン: {:?}", tokens);
-    
-    // 構文解析
-    let mut parser = Parser::new(tokens, file_path.clone());
-    let ast = parser.parse()?;
-    
-    // AST表示（デバッグ用）
-    debug!("AST: {:?}", ast);
-    
-    // 意味解析
-    let mut analyzer = SemanticAnalyzer::new();
-    let analyzed_ast = analyzer.analyze(ast)?;
-    
-    // 型チェック
-    let mut type_checker = TypeChecker::new();
-    let typed_ast = type_checker.check(analyzed_ast)?;
-    
-    // REPLの実装はまだ完全ではないため、簡易的な結果を返す
-    Ok("評価が完了しました (具体的な値の表示は未実装)".to_string())
+/// REPLのコマンド（`:save` など）を実行
+fn run_command(session: &mut ReplSession, command: &str) -> Result<()> {
+    let (name, argument) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+    let argument = argument.trim();
+    let path = || {
+        if argument.is_empty() {
+            Err(EidosError::Runtime(format!(":{} にはファイル名が必要です", name)))
+        } else {
+            Ok(Path::new(argument))
+        }
+    };
+    match name {
+        "save" => {
+            session.save(path()?)?;
+            println!("{} 個の定義を {} に保存しました", session.definitions.len(), argument);
+        }
+        "open" => {
+            let failures = session.open(path()?)?;
+            println!("{} から {} 個の定義を読み込みました", argument, session.definitions.len());
+            report_failures(&failures);
+        }
+        "clear" => {
+            session.clear();
+            println!("すべての定義を取り除きました");
+        }
+        "help" => {
+            println!(":save <ファイル>  定義した関数をファイルに保存");
+            println!(":open <ファイル>  ファイルの関数定義でセッションを置き換える");
+            println!(":clear            定義した関数をすべて取り除く");
+            println!("exit              REPLを終了");
+        }
+        _ => return Err(EidosError::Runtime(format!("不明なコマンド: :{}（':help' でコマンドの一覧）", name))),
+    }
+    Ok(())
+}
+
+/// コンパイルできなくなって読み込まなかった定義を表示
+fn report_failures(failures: &[(String, EidosError)]) {
+    for (name, error) in failures {
+        eprintln!("定義 '{}' はコンパイルできなくなったため読み込みませんでした: {}", name, error);
+    }
 }
 
 /// ファイルをプリロード
-fn preload_file(file: &Path) -> Result<()> {
+fn preload_file(session: &mut ReplSession, file: &Path) -> Result<()> {
     info!("ファイルをプリロード中: {}", file.display());
     
     // ファイルを読み込み
@@ -133,7 +432,7 @@ fn preload_file(file: &Path) -> Result<()> {
     })?;
     
     // プリロードファイルの処理は単純に評価と同じ
-    evaluate_input(&source)?;
+    session.evaluate(&source)?;
     
     Ok(())
 } 
\ No newline at end of file
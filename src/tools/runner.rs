@@ -1,16 +1,62 @@
-use std::fs;
+use std::fs::File;
+use std::io::BufReader;
 use std::path::{Path, PathBuf};
 
 use log::{info, debug};
 
 use crate::core::{Result, EidosError, SourceLocation};
-use crate::frontend::{Lexer, Parser, TypeChecker, SemanticAnalyzer};
+use crate::core::ast::Program;
+use crate::frontend::{ConstEvaluator, Lexer, MacroExpander, Parser, TypeChecker, SemanticAnalyzer};
 use crate::core::eir::{Module, ModuleBuilder};
-use crate::backend::{Backend, CodegenOptions, OutputFormat, Target, BackendFactory};
-use crate::backend::wasm::WasmRuntime;
+use crate::backend::{Backend, CodegenOptions, OutputFormat, Target};
+use crate::backend::wasm::{RunLimits, WasmBackend, WasmRuntime};
+use crate::backend::jit::{JitEngine, JitValue, TraceOptions};
 
-/// Eidosファイルを実行
-pub fn run_file(file: &Path, args: Vec<String>) -> Result<()> {
+/// 構文解析したプログラムに、comptime ブロックの実行・意味解析・型チェックを順に行う
+///
+/// ソースから実行までを1度に行う経路（`run`・`--watch`・REPL・ベンチマーク）は、すべてこの順序で検査する。
+/// comptime ブロックが生成した宣言も、意味解析と型チェックの対象になる。
+pub fn check_program(ast: Program) -> Result<Program> {
+    debug!("comptime ブロックを実行中");
+    let ast = ConstEvaluator::new().expand_comptime(ast)?;
+    
+    debug!("意味解析を実行中");
+    let analyzed_ast = SemanticAnalyzer::new().analyze(ast)?;
+    
+    debug!("型チェックを実行中");
+    TypeChecker::new().check(analyzed_ast)
+}
+
+/// Eidosファイルを読みながら字句解析・マクロ展開・構文解析する
+///
+/// ソースやトークン列の全体はメモリに置かず、構文解析が読み進める分だけ読み込む。
+pub fn parse_file(file: &Path) -> Result<Program> {
+    debug!("構文解析を実行中");
+    let reader = File::open(file).map(BufReader::new).map_err(EidosError::IO)?;
+    let mut expander = MacroExpander::new();
+    let tokens = expander.expanding(Lexer::from_reader(reader, file.to_path_buf()));
+    Parser::from_lexer(tokens, file.to_path_buf()).parse()
+}
+
+/// Eidosファイルを読み込んで検査し、EIR（Eidos中間表現）のモジュールにする
+///
+/// `run`・`run --trace`・`run --watch --hot` はこのモジュールを実行する。モジュール名はファイル名。
+pub fn build_module(file: &Path) -> Result<Module> {
+    let ast = parse_file(file)?;
+    
+    // comptime ブロックの実行・意味解析・型チェック
+    let typed_ast = check_program(ast)?;
+    
+    debug!("中間表現に変換中");
+    let name = file.file_name().map_or_else(|| "main".to_string(), |name| name.to_string_lossy().to_string());
+    ModuleBuilder::new(name).build_from_ast(&typed_ast)
+}
+
+/// Eidosファイルを実行（`limits` の制限の下で）し、プロセスの終了コードを返す
+///
+/// `fn main(args: List<String>)` にはプログラム名（ソースファイルのパス）に続けて `args` を渡し、
+/// `main` が返した `Int` を終了コードにする。
+pub fn run_file(file: &Path, args: Vec<String>, limits: &RunLimits) -> Result<i32> {
     info!("ファイルを実行中: {}", file.display());
     
     // 引数を表示
@@ -18,46 +64,24 @@ pub fn run_file(file: &Path, args: Vec<String>) -> Result<()> {
         debug!("実行引数: {:?}", args);
     }
     
-    // ファイルを読み込み
-    debug!("ソースファイルを読み込み中");
-    let source = fs::read_to_string(file).map_err(|e| {
-        EidosError::IO(e)
-    })?;
-    
-    // 字句解析
-    debug!("字句解析を実行中");
-    let mut lexer = Lexer::new(&source, file.to_path_buf());
-    let tokens = lexer.tokenize()?;
-    
-    // 構文解析
-    debug!("構文解析を実行中");
-    let mut parser = Parser::new(tokens, file.to_path_buf());
-    let ast = parser.parse()?;
-    
-    // 意味解析
-    debug!("意味解析を実行中");
-    let mut analyzer = SemanticAnalyzer::new();
-    let analyzed_ast = analyzer.analyze(ast)?;
-    
-    // 型チェック
-    debug!("型チェックを実行中");
-    let mut type_checker = TypeChecker::new();
-    let typed_ast = type_checker.check(analyzed_ast)?;
+    // プログラムからは `main` の引数か `system::args()` で参照できる
+    let mut program_args = vec![file.display().to_string()];
+    program_args.extend(args);
+    crate::stdlib::system::set_program_args(program_args.clone());
     
     // EIR（Eidos中間表現）に変換
-    debug!("中間表現に変換中");
-    let mut module_builder = ModuleBuilder::new(file.file_name().unwrap().to_string_lossy().to_string());
-    let module = module_builder.build_from_ast(&typed_ast)?;
+    let module = build_module(file)?;
     
     // WebAssemblyバックエンドでコンパイル
     debug!("WebAssemblyにコンパイル中");
-    let backend_factory = BackendFactory::new();
-    let backend = backend_factory.create_backend(Target::Wasm)?;
+    let mut backend = WasmBackend::new();
     
     let options = CodegenOptions {
-        format: OutputFormat::WASM,
-        optimization_level: 2, // 最適化レベル（0-3）
+        format: OutputFormat::Wasm,
+        target: Target::Wasm,
+        opt_level: 2, // 最適化レベル（0-3）
         debug_info: true,
+        ..Default::default()
     };
     
     // コードの生成
@@ -65,10 +89,34 @@ pub fn run_file(file: &Path, args: Vec<String>) -> Result<()> {
     
     // WebAssemblyモジュールを実行
     debug!("WebAssemblyモジュールを実行中");
-    let mut runtime = WasmRuntime::new()?;
-    runtime.run_module(&wasm_bytes)?;
+    let mut runtime = WasmRuntime::with_limits(limits.clone())?.with_args(program_args);
+    let exit_code = runtime.run_module(&wasm_bytes)?;
+    
+    info!("実行が終了しました（終了コード: {}）", exit_code);
+    
+    Ok(exit_code)
+} 
+
+/// Eidosファイルを、関数の出入り（`--trace=instr` では命令も）を標準エラー出力に表示しながらJITで実行する
+///
+/// `main` が `Int` を返せば、その値を終了コードにする。
+pub fn trace_file(file: &Path, trace: &TraceOptions) -> Result<i32> {
+    info!("ファイルをトレースしながら実行中: {}", file.display());
+    
+    let module = build_module(file)?;
     
-    info!("実行が正常に終了しました");
+    let mut engine = JitEngine::new()?.with_trace(trace.clone());
+    let exit_code = match engine.run(&module) {
+        Ok(JitValue::Int(code)) => code as i32,
+        Ok(_) => 0,
+        // パニックはCのランタイムと同じ形で表示し、同じ終了コードで終わる
+        Err(EidosError::Runtime(message)) => {
+            eprintln!("eidos: {}", message);
+            101
+        }
+        Err(e) => return Err(e),
+    };
     
-    Ok(())
-} 
\ No newline at end of file
+    info!("実行が終了しました（終了コード: {}）", exit_code);
+    Ok(exit_code)
+}
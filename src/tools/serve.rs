@@ -0,0 +1,447 @@
+use anyhow::{anyhow, Context, Result};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+
+use crate::backend::codegen::{CodeGenerator, CodegenOptions, OutputFormat, Target};
+use crate::core::session::{CompileSession, TrackingAllocator};
+use crate::tools::check::{Diagnostic, DiagnosticIndex};
+use crate::tools::compiler;
+use crate::tools::deps;
+
+/// 一時ディレクトリに書き出すソースファイルの名前（診断の位置にもこの名前が出る）
+const SOURCE_FILE: &str = "main.eid";
+
+/// 同時に実行するコンパイルの最大数（超えた要求には 503 を返す）
+const MAX_CONCURRENT_COMPILES: usize = 4;
+
+/// 要求のヘッダーを読み終えるまでの制限時間
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 要求行とヘッダーを合わせた最大バイト数（超えた要求には 431 を返す）
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// WebAssemblyモジュールを読み込んで `main` を実行する JavaScript（ESモジュール）
+///
+/// モジュールがインポートする出力関数（`wasm::HOST_FUNCTIONS`）をホストの関数で与え、出力を1行ずつ `print` に渡す。
+/// 文字列は線形メモリ上のポインタで受け取る（先頭4バイトがバイト数）。
+const GLUE_JS: &str = r#"// `eidos serve --playground` が返すWebAssemblyモジュールを実行する
+export async function run(wasmBase64, { print = (line) => console.log(line) } = {}) {
+  const bytes = Uint8Array.from(atob(wasmBase64), (c) => c.charCodeAt(0));
+  let memory;
+  let line = "";
+  const write = (text) => { line += text; };
+  const string = (ptr) => {
+    const len = new DataView(memory.buffer).getUint32(ptr, true);
+    return new TextDecoder().decode(new Uint8Array(memory.buffer, ptr + 4, len));
+  };
+  const eidos = {
+    print_int: (value) => write(value.toString()),
+    print_u64: (value) => write(BigInt.asUintN(64, value).toString()),
+    print_float: (value) => write(String(value)),
+    print_bool: (value) => write(value !== 0 ? "true" : "false"),
+    print_char: (value) => write(String.fromCodePoint(value)),
+    print_string: (ptr) => write(string(ptr)),
+    print_newline: () => { print(line); line = ""; },
+    panic: (ptr) => { throw new Error(`eidos: panic: ${string(ptr)}`); },
+  };
+  const { instance } = await WebAssembly.instantiate(bytes, { eidos });
+  memory = instance.exports.memory;
+  try {
+    return instance.exports.main?.();
+  } finally {
+    if (line) print(line);
+  }
+}
+"#;
+
+/// `eidos serve --playground` の設定
+#[derive(Debug, Clone)]
+pub struct PlaygroundOptions {
+    /// 待ち受けるアドレス
+    pub addr: String,
+    /// 1回のコンパイルの制限時間
+    pub timeout: Duration,
+    /// 1回のコンパイルで使えるヒープの上限（MiB）
+    pub max_memory: usize,
+    /// 受け付けるソースの最大バイト数
+    pub max_source_size: usize,
+    /// コンパイルするプロセスにも読み込ませるプラグイン
+    pub plugins: Vec<PathBuf>,
+}
+
+impl Default for PlaygroundOptions {
+    fn default() -> Self {
+        Self {
+            addr: "127.0.0.1:8080".to_string(),
+            timeout: Duration::from_secs(5),
+            max_memory: 256,
+            max_source_size: 64 * 1024,
+            plugins: Vec::new(),
+        }
+    }
+}
+
+/// プレイグラウンド用の HTTP サーバーを起動
+///
+/// `POST /compile` に `{"source": "..."}` を送ると、ソースをWebAssemblyにコンパイルし、
+/// `{"success", "diagnostics", "wasm", "glue"}` の JSON を返す。`wasm` はBase64で符号化したモジュール、
+/// `glue` はそれを実行する JavaScript。コンパイルは要求ごとに別のプロセスで行い、制限時間を過ぎたものは
+/// 止め、ヒープの上限を超えたものは異常終了させるので、信頼できないソースを受け付けてもサーバーは止まらない。
+pub fn serve_playground(options: &PlaygroundOptions) -> Result<()> {
+    let listener = TcpListener::bind(&options.addr)
+        .with_context(|| format!("{} で待ち受けられません", options.addr))?;
+    info!("プレイグラウンドを起動しました: http://{}", listener.local_addr()?);
+    
+    let options = Arc::new(options.clone());
+    let running = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("接続を受け付けられません: {}", e);
+                continue;
+            }
+        };
+        let options = Arc::clone(&options);
+        let running = Arc::clone(&running);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &options, &running) {
+                debug!("接続の処理を中断しました: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// HTTP の要求
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+/// 要求を処理できない理由（応答の状態とメッセージ）
+#[derive(Debug, PartialEq, Eq)]
+struct HttpError {
+    status: &'static str,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status: &'static str, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+
+fn handle_connection(stream: TcpStream, options: &PlaygroundOptions, running: &AtomicUsize) -> Result<()> {
+    stream.set_read_timeout(Some(REQUEST_READ_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    // ソースを JSON の文字列にすると最大で6倍（`\uXXXX`）になる
+    let request = match read_request(&mut BufReader::new(stream), options.max_source_size * 6 + 1024) {
+        Ok(request) => request,
+        Err(e) => return respond_error(&mut writer, &e),
+    };
+    debug!("{} {}", request.method, request.path);
+    
+    match (request.method.as_str(), request.path.as_str()) {
+        ("OPTIONS", _) => respond(&mut writer, "204 No Content", "text/plain", b""),
+        ("GET", "/health") => respond(&mut writer, "200 OK", "text/plain", b"ok\n"),
+        ("POST", "/compile") => {
+            let source = match compile_request_source(&request.body, options.max_source_size) {
+                Ok(source) => source,
+                Err(e) => return respond_error(&mut writer, &e),
+            };
+            if running.fetch_add(1, Ordering::SeqCst) >= MAX_CONCURRENT_COMPILES {
+                running.fetch_sub(1, Ordering::SeqCst);
+                return respond_error(&mut writer, &HttpError::new("503 Service Unavailable", "混み合っています。しばらくしてから再度お試しください"));
+            }
+            let result = compile_in_worker(&source, options);
+            running.fetch_sub(1, Ordering::SeqCst);
+            let response = result.unwrap_or_else(|e| failure(&format!("コンパイルを実行できませんでした: {}", e)));
+            respond(&mut writer, "200 OK", "application/json", response.to_string().as_bytes())
+        }
+        (_, "/compile") => respond_error(&mut writer, &HttpError::new("405 Method Not Allowed", "POST で送ってください")),
+        _ => respond_error(&mut writer, &HttpError::new("404 Not Found", format!("{} はありません", request.path))),
+    }
+}
+
+/// 要求行・ヘッダー・本文を読む（本文は `Content-Length` の分だけ）
+///
+/// 要求行とヘッダーは合わせて `MAX_HEADER_SIZE` バイトまでしか読まないので、改行を送らない接続でもメモリを使い切らない。
+fn read_request(reader: &mut impl BufRead, max_body: usize) -> std::result::Result<Request, HttpError> {
+    let bad_request = |message: &str| HttpError::new("400 Bad Request", message);
+    let mut header_bytes = 0;
+    let mut line = String::new();
+    read_header_line(reader, &mut line, &mut header_bytes, "要求を読めません")?;
+    let mut parts = line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return Err(bad_request("要求行が不正です")),
+    };
+    
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if read_header_line(reader, &mut line, &mut header_bytes, "ヘッダーを読めません")? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| bad_request("Content-Length が不正です"))?;
+            }
+        }
+    }
+    if content_length > max_body {
+        return Err(HttpError::new("413 Payload Too Large", format!("本文が大きすぎます（{} バイトまで）", max_body)));
+    }
+    
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|_| bad_request("本文を読めません"))?;
+    Ok(Request { method, path, body })
+}
+
+/// 要求行かヘッダーを1行読み、読んだバイト数を返す（`read` はこれまでに読んだヘッダーのバイト数）
+///
+/// ヘッダーが `MAX_HEADER_SIZE` を超えたら 431 を、読めなければ `message` の 400 を返す。
+fn read_header_line(reader: &mut impl BufRead, line: &mut String, read: &mut usize, message: &str) -> std::result::Result<usize, HttpError> {
+    let remaining = MAX_HEADER_SIZE - *read;
+    // 上限より1バイト多く読めたら、上限を超えている
+    let mut bytes = Vec::new();
+    let n = reader
+        .by_ref()
+        .take(remaining as u64 + 1)
+        .read_until(b'\n', &mut bytes)
+        .map_err(|_| HttpError::new("400 Bad Request", message))?;
+    if n > remaining {
+        return Err(HttpError::new(
+            "431 Request Header Fields Too Large",
+            format!("ヘッダーが大きすぎます（{} バイトまで）", MAX_HEADER_SIZE),
+        ));
+    }
+    *read += n;
+    line.push_str(&String::from_utf8_lossy(&bytes));
+    Ok(n)
+}
+
+/// `POST /compile` の本文からソースを取り出す
+fn compile_request_source(body: &[u8], max_source_size: usize) -> std::result::Result<String, HttpError> {
+    let value: Value = serde_json::from_slice(body)
+        .map_err(|e| HttpError::new("400 Bad Request", format!("本文が JSON ではありません: {}", e)))?;
+    let source = value
+        .get("source")
+        .and_then(Value::as_str)
+        .ok_or_else(|| HttpError::new("400 Bad Request", "\"source\" に文字列を指定してください"))?;
+    if source.len() > max_source_size {
+        return Err(HttpError::new("413 Payload Too Large", format!("ソースが大きすぎます（{} バイトまで）", max_source_size)));
+    }
+    Ok(source.to_string())
+}
+
+fn respond(writer: &mut impl Write, status: &str, content_type: &str, body: &[u8]) -> Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    writer.write_all(body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn respond_error(writer: &mut impl Write, error: &HttpError) -> Result<()> {
+    let body = json!({ "error": error.message }).to_string();
+    respond(writer, error.status, "application/json", body.as_bytes())
+}
+
+/// 別のプロセス（`eidos playground-worker`）でコンパイルし、その結果の JSON を返す
+fn compile_in_worker(source: &str, options: &PlaygroundOptions) -> Result<Value> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(dir.path().join(SOURCE_FILE), source)?;
+    
+    let mut command = Command::new(std::env::current_exe()?);
+    command.args(["--log-level", "error"]);
+    for plugin in &options.plugins {
+        command.arg("--plugin").arg(plugin);
+    }
+    let mut child = command
+        .args(["playground-worker", "--max-memory", &options.max_memory.to_string(), SOURCE_FILE])
+        .current_dir(dir.path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("コンパイル用のプロセスを起動できません")?;
+    
+    // パイプが一杯になって子プロセスが止まらないよう、終了を待つ間も読み続ける
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let deadline = Instant::now() + options.timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    
+    let status = match status {
+        Some(status) => status,
+        None => return Ok(failure(&format!("コンパイルが制限時間（{}秒）を超えました", options.timeout.as_secs_f64()))),
+    };
+    match serde_json::from_str(&stdout) {
+        Ok(response) if status.success() => Ok(response),
+        _ if stderr.contains("memory allocation") => {
+            Ok(failure(&format!("コンパイルがメモリの上限（{} MiB）を超えました", options.max_memory)))
+        }
+        _ => Err(anyhow!("コンパイラが異常終了しました（{}）: {}", status, stderr.trim())),
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut out = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut out);
+        }
+        out
+    })
+}
+
+/// プレイグラウンドのコンパイルを行うプロセスの本体
+///
+/// `file` をWebAssemblyにコンパイルし、結果の JSON を標準出力に書く。ヒープを `max_memory` MiB までに制限する。
+/// 他のファイルを参照するソースは、サーバーのファイルを読まないよう受け付けない。
+pub fn playground_worker(file: &Path, max_memory: usize) -> Result<()> {
+    let limit = max_memory.checked_mul(1 << 20).ok_or_else(|| anyhow!("ヒープの上限 {} MiB は大きすぎます", max_memory))?;
+    TrackingAllocator::set_limit(limit);
+    let response = compile_to_wasm(file)?;
+    println!("{}", response);
+    Ok(())
+}
+
+fn compile_to_wasm(file: &Path) -> Result<Value> {
+    if deps::source_files(file).len() > 1 {
+        return Ok(failure("プレイグラウンドでは他のファイルを import できません"));
+    }
+    
+    let session = CompileSession::new(false);
+    let errors = compiler::check_file(file, false, &session)?;
+    if !errors.is_empty() {
+        let mut index = DiagnosticIndex::new();
+        index.add_errors(file, errors);
+        let diagnostics: Vec<Value> = index.diagnostics().iter().map(diagnostic_json).collect();
+        return Ok(json!({ "success": false, "diagnostics": diagnostics }));
+    }
+    
+    let module = compiler::build_eir(file, false, &session)?;
+    let codegen_options = CodegenOptions {
+        format: OutputFormat::Wasm,
+        target: Target::Wasm,
+        ..Default::default()
+    };
+    // バックエンドが対応していない値や命令を使うソースは、診断として利用者に返す
+    let wasm = match CodeGenerator::new_wasm().generate_code(&module, &codegen_options) {
+        Ok(wasm) => wasm,
+        Err(e) => return Ok(failure(&format!("WebAssemblyを生成できません: {}", e))),
+    };
+    Ok(json!({ "success": true, "diagnostics": [], "wasm": base64(&wasm), "glue": GLUE_JS }))
+}
+
+fn diagnostic_json(diagnostic: &Diagnostic) -> Value {
+    json!({
+        "code": diagnostic.code.to_string(),
+        "message": diagnostic.message,
+        "line": diagnostic.line,
+        "column": diagnostic.column,
+    })
+}
+
+/// 位置を持たない1つの診断だけの失敗の結果
+fn failure(message: &str) -> Value {
+    json!({
+        "success": false,
+        "diagnostics": [{ "code": null, "message": message, "line": 0, "column": 0 }],
+    })
+}
+
+/// 標準のBase64（パディングあり）で符号化
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_read_compile_request() {
+        let body = r#"{"source": "fn main() {}"}"#;
+        let raw = format!("POST /compile HTTP/1.1\r\nHost: localhost\r\ncontent-length: {}\r\n\r\n{}", body.len(), body);
+        let request = read_request(&mut raw.as_bytes(), 1024).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/compile");
+        assert_eq!(compile_request_source(&request.body, 1024).unwrap(), "fn main() {}");
+        
+        assert_eq!(read_request(&mut raw.as_bytes(), 8).unwrap_err().status, "413 Payload Too Large");
+        assert_eq!(compile_request_source(&request.body, 4).unwrap_err().status, "413 Payload Too Large");
+        assert_eq!(compile_request_source(b"{}", 1024).unwrap_err().status, "400 Bad Request");
+    }
+    
+    #[test]
+    fn test_header_size_limit() {
+        let header = "x".repeat(MAX_HEADER_SIZE);
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", header);
+        assert_eq!(read_request(&mut long_line.as_bytes(), 1024).unwrap_err().status, "431 Request Header Fields Too Large");
+        
+        // 1行ずつは短くても、合わせて上限を超えるヘッダーは受け付けない
+        let many_lines = format!("GET /health HTTP/1.1\r\n{}\r\n", "x-padding: y\r\n".repeat(MAX_HEADER_SIZE / 8));
+        assert_eq!(read_request(&mut many_lines.as_bytes(), 1024).unwrap_err().status, "431 Request Header Fields Too Large");
+        
+        let request = read_request(&mut "GET /health HTTP/1.1\r\n\r\n".as_bytes(), 1024).unwrap();
+        assert_eq!(request.path, "/health");
+    }
+    
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"\0asm\x01"), "AGFzbQE=");
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{Result, Context};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use log::{debug, warn};
+use colored::Colorize;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::backend::jit::{HotReload, JitEngine, JitProgram, JitValue};
+use crate::tools::{deps, runner};
+
+/// 最後の変更からこの時間だけ静かになったら再実行する
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// 変更を待つ間、実行中のプログラムが終了したかを確かめる間隔
+const POLL: Duration = Duration::from_millis(100);
+
+/// ソースファイルを監視し、変更のたびに `action` を実行し直す
+///
+/// 監視対象は `root` と、そこから `import` / `use` / `mod` で参照されるファイル。
+/// 参照関係は実行のたびに調べ直すので、新しく追加した `import` もすぐ監視対象になる。
+/// `action` のエラーは表示するだけで、監視は続ける。Ctrl+Cで終了する。
+pub fn watch<F, E>(root: &Path, mut action: F) -> Result<()>
+where
+    F: FnMut() -> std::result::Result<(), E>,
+    E: Display,
+{
+    loop {
+        clear_screen();
+        let started = Instant::now();
+        println!("{} {}", "[watch]".cyan().bold(), root.display());
+        
+        match action() {
+            Ok(()) => println!(
+                "{} {:.2}秒",
+                "[watch] 成功".green().bold(),
+                started.elapsed().as_secs_f64()
+            ),
+            Err(e) => {
+                eprintln!("{} {}", "エラー:".red().bold(), e);
+                println!("{}", "[watch] 失敗".red().bold());
+            }
+        }
+        
+        let files = tracked_files(root);
+        println!(
+            "{}",
+            format!("[watch] {}個のファイルを監視中（Ctrl+Cで終了）", files.len()).dimmed()
+        );
+        wait_for_change(&files)?;
+    }
+}
+
+/// `run --watch --hot`: プログラムをJITで実行したまま、保存のたびに変更された関数だけを差し替える
+///
+/// 関数の本体だけが変わった場合は、その関数を再コンパイルして呼び出し先をアトミックに切り替えるので、
+/// 実行中のループの変数などプログラムの状態はそのまま残る。関数のシグネチャかエントリー関数が
+/// 変わった場合は差し替えられないため、プロセスごと起動し直して最初から実行する（実行中のスレッドは
+/// 外から止められない）。プログラムが終了した後の変更では、`watch` と同じく最初から実行する。
+pub fn watch_hot(root: &Path) -> Result<()> {
+    let mut engine = JitEngine::new()?;
+    let mut program: Option<JitProgram> = None;
+    clear_screen();
+    println!("{} {}", "[watch] ホットリロード".cyan().bold(), root.display());
+    
+    loop {
+        // `eid run` と同じ手順でEIRにする
+        match runner::build_module(root) {
+            Ok(module) => match program.as_ref() {
+                Some(_) => match engine.hot_reload(&module) {
+                    Ok(HotReload::Swapped(names)) if names.is_empty() => {
+                        println!("{}", "[watch] 差し替える関数はありません".dimmed());
+                    }
+                    Ok(HotReload::Swapped(names)) => {
+                        println!("{} {}", "[watch] 差し替え:".green().bold(), names.join(", "));
+                    }
+                    Ok(HotReload::Restart(reason)) => {
+                        println!("{} {}ため、最初から実行し直します", "[watch]".cyan().bold(), reason);
+                        return restart();
+                    }
+                    Err(e) => report_failure(e),
+                },
+                None => match engine.start(&module) {
+                    Ok(started) => program = Some(started),
+                    Err(e) => report_failure(e),
+                },
+            },
+            // 実行中のプログラムはそのまま動かし続ける
+            Err(e) => report_failure(e),
+        }
+        
+        let files = tracked_files(root);
+        let finished = wait_for_change_until(&files, || program.as_ref().is_some_and(JitProgram::is_finished))?;
+        if finished {
+            match program.take().map(JitProgram::join) {
+                Some(Ok(JitValue::Int(code))) if code != 0 => {
+                    eprintln!("プログラムは終了コード {} で終了しました", code);
+                }
+                Some(Err(e)) => report_failure(e),
+                _ => println!("{}", "[watch] プログラムが終了しました".dimmed()),
+            }
+            wait_for_change(&files)?;
+        }
+    }
+}
+
+/// 同じ引数で `eid` を起動し直す
+fn restart() -> Result<()> {
+    let exe = std::env::current_exe().context("実行ファイルのパスを取得できません")?;
+    let mut command = Command::new(exe);
+    command.args(std::env::args_os().skip(1));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // 成功すれば戻らない
+        Err(command.exec()).context("eid を起動し直せません")
+    }
+    
+    #[cfg(not(unix))]
+    {
+        let status = command.status().context("eid を起動し直せません")?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+fn report_failure(error: impl Display) {
+    eprintln!("{} {}", "エラー:".red().bold(), error);
+    println!("{}", "[watch] 失敗".red().bold());
+}
+
+/// 監視対象のファイル（正規化済みの絶対パス。型プロバイダが読み込むスキーマも含む）
+fn tracked_files(root: &Path) -> HashSet<PathBuf> {
+    deps::input_files(root)
+        .into_iter()
+        .map(|path| fs::canonicalize(&path).unwrap_or(path))
+        .collect()
+}
+
+/// 監視対象のファイルが変更されるまで待ち、変更が落ち着いてから戻る
+fn wait_for_change(files: &HashSet<PathBuf>) -> Result<()> {
+    wait_for_change_until(files, || false).map(|_| ())
+}
+
+/// 監視対象のファイルが変更されるか、`stop` が真になるまで待つ（`stop` で戻ったときは `true`）
+fn wait_for_change_until(files: &HashSet<PathBuf>, stop: impl Fn() -> bool) -> Result<bool> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(tx)
+        .context("ファイル監視の初期化に失敗しました")?;
+    
+    // エディタは一時ファイルへの書き込みと置き換えで保存することがあるため、ファイルではなくディレクトリを監視する
+    let dirs: HashSet<&Path> = files.iter().filter_map(|path| path.parent()).collect();
+    for dir in dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("ディレクトリを監視できません: {}", dir.display()))?;
+    }
+    
+    loop {
+        let event = match rx.recv_timeout(POLL) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) if stop() => return Ok(true),
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => anyhow::bail!("ファイル監視が停止しました"),
+        };
+        if touches(&event, files) {
+            break;
+        }
+    }
+    debounce(&rx);
+    Ok(false)
+}
+
+/// 続けて届くイベントを読み捨て、一定時間イベントが来なくなるまで待つ
+fn debounce(rx: &Receiver<notify::Result<Event>>) {
+    while rx.recv_timeout(DEBOUNCE).is_ok() {}
+}
+
+/// イベントが監視対象のファイルの変更かどうか
+fn touches(event: &notify::Result<Event>, files: &HashSet<PathBuf>) -> bool {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            warn!("ファイル監視でエラーが発生しました: {}", e);
+            return false;
+        }
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        // 削除されたファイルは正規化できないので、親ディレクトリを正規化して比べる
+        let path = match (path.parent().and_then(|dir| fs::canonicalize(dir).ok()), path.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => path.clone(),
+        };
+        let hit = files.contains(&path);
+        if hit {
+            debug!("変更を検出: {} ({:?})", path.display(), event.kind);
+        }
+        hit
+    })
+}
+
+fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+    let _ = io::stdout().flush();
+}
@@ -0,0 +1,173 @@
+//! コード生成のスナップショットテスト
+//!
+//! `tests/fixtures/snapshots/*.eid` をコンパイルし、各出力形式の結果を
+//! `tests/snapshots/<フィクスチャ名>.<拡張子>` のゴールデンファイルと比較する。
+//! 最適化の結果も確かめるよう、`eid build` の既定と同じ `-O2` を明示してコンパイルする。
+//! 出力を意図して変えた場合は `UPDATE_SNAPSHOTS=1 cargo test --test snapshot_tests` で再生成する。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// ゴールデンファイルを再生成するかどうかを指定する環境変数
+const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// フィクスチャ先頭の `// emit: ...` で出力形式を指定しない場合の既定値
+const DEFAULT_EMITS: &[&str] = &["eir", "c", "js"];
+
+/// `--emit` に渡す名前と、ゴールデンファイルの拡張子
+const EMIT_EXTENSIONS: &[(&str, &str)] = &[
+    ("eir", "eir"),
+    ("c", "c"),
+    ("js", "mjs"),
+    ("llvm", "ll"),
+];
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/snapshots")
+}
+
+fn snapshots_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+/// フィクスチャの一覧（名前順）
+fn fixtures() -> Vec<PathBuf> {
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(fixtures_dir())
+        .expect("フィクスチャのディレクトリを読めません")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "eid"))
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+/// フィクスチャ先頭の `// emit: eir llvm` から出力形式を読み取る
+fn emits_for(source: &str) -> Vec<String> {
+    let directive = source
+        .lines()
+        .take_while(|line| line.trim_start().starts_with("//"))
+        .find_map(|line| line.trim_start().trim_start_matches('/').trim().strip_prefix("emit:"));
+    match directive {
+        Some(list) => list.split([',', ' ']).filter(|emit| !emit.is_empty()).map(str::to_string).collect(),
+        None => DEFAULT_EMITS.iter().map(|emit| emit.to_string()).collect(),
+    }
+}
+
+fn extension_for(emit: &str) -> &'static str {
+    EMIT_EXTENSIONS
+        .iter()
+        .find(|(name, _)| *name == emit)
+        .map(|(_, ext)| *ext)
+        .unwrap_or_else(|| panic!("スナップショットに対応していない出力形式です: {}", emit))
+}
+
+/// スナップショットを生成する最適化レベル（既定値が変わってもゴールデンファイルが変わらないよう明示する）
+const OPT_LEVEL: &str = "-O2";
+
+/// `eidos build --emit <emit> -O2` でフィクスチャをコンパイルし、出力を返す
+fn compile(fixture: &Path, emit: &str, out_dir: &Path) -> Result<String, String> {
+    let stem = fixture.file_stem().unwrap().to_string_lossy();
+    let output_path = out_dir.join(format!("{}.{}", stem, extension_for(emit)));
+    // 生成コードに埋め込まれるソースのパスが環境に依存しないよう、フィクスチャのディレクトリから相対パスで渡す
+    let output = Command::new(env!("CARGO_BIN_EXE_eidos"))
+        .current_dir(fixtures_dir())
+        .arg("build")
+        .arg(fixture.file_name().unwrap())
+        .args(["--emit", emit, OPT_LEVEL, "-o"])
+        .arg(&output_path)
+        .output()
+        .map_err(|e| format!("コンパイラを起動できません: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    }
+    let text = fs::read_to_string(&output_path).map_err(|e| format!("出力を読めません: {}", e))?;
+    Ok(normalize(emit, &text))
+}
+
+/// 環境によって変わる部分を取り除く（改行コード、LLVM IRのターゲット情報）
+fn normalize(emit: &str, text: &str) -> String {
+    let mut normalized = String::new();
+    for line in text.lines() {
+        if emit == "llvm" && (line.starts_with("target datalayout") || line.starts_with("target triple") || line.starts_with("source_filename")) {
+            continue;
+        }
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+    normalized
+}
+
+/// 期待値と実際の出力の最初の相違を表示用にまとめる
+fn describe_mismatch(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let line = expected_lines
+        .iter()
+        .zip(&actual_lines)
+        .position(|(e, a)| e != a)
+        .unwrap_or_else(|| expected_lines.len().min(actual_lines.len()));
+    format!(
+        "{}行目が異なります\n  期待値: {}\n  実際:   {}",
+        line + 1,
+        expected_lines.get(line).unwrap_or(&"<ファイル終端>"),
+        actual_lines.get(line).unwrap_or(&"<ファイル終端>"),
+    )
+}
+
+#[test]
+fn test_codegen_snapshots() {
+    let update = std::env::var_os(UPDATE_ENV).is_some_and(|value| value != "0");
+    let out_dir = tempfile::tempdir().expect("一時ディレクトリを作成できません");
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "フィクスチャがありません: {}", fixtures_dir().display());
+    
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        let stem = fixture.file_stem().unwrap().to_string_lossy().to_string();
+        let source = fs::read_to_string(fixture).expect("フィクスチャを読めません");
+        
+        for emit in emits_for(&source) {
+            let snapshot = snapshots_dir().join(format!("{}.{}", stem, extension_for(&emit)));
+            let actual = match compile(fixture, &emit, out_dir.path()) {
+                Ok(actual) => actual,
+                Err(message) => {
+                    failures.push(format!("{} (--emit {}): コンパイルに失敗しました\n{}", stem, emit, message));
+                    continue;
+                }
+            };
+            
+            if update {
+                fs::create_dir_all(snapshots_dir()).expect("スナップショットのディレクトリを作成できません");
+                fs::write(&snapshot, &actual).expect("スナップショットを書き込めません");
+                continue;
+            }
+            
+            match fs::read_to_string(&snapshot) {
+                Ok(expected) if normalize(&emit, &expected) == actual => {}
+                Ok(expected) => failures.push(format!(
+                    "{}: スナップショットと一致しません。{}",
+                    snapshot.display(),
+                    describe_mismatch(&normalize(&emit, &expected), &actual)
+                )),
+                Err(_) => failures.push(format!("{}: スナップショットがありません", snapshot.display())),
+            }
+        }
+    }
+    
+    assert!(
+        failures.is_empty(),
+        "{}件のスナップショットが失敗しました（意図した変更なら {}=1 で再生成してください）:\n\n{}",
+        failures.len(),
+        UPDATE_ENV,
+        failures.join("\n\n")
+    );
+}
+
+#[test]
+fn test_emit_directive() {
+    assert_eq!(emits_for("fn main() {}"), DEFAULT_EMITS);
+    assert_eq!(emits_for("// LLVM IRも確認する\n// emit: eir, llvm\nfn main() {}"), ["eir", "llvm"]);
+    // 先頭のコメント以外にある指定は無視する
+    assert_eq!(emits_for("fn main() {}\n// emit: llvm"), DEFAULT_EMITS);
+}
@@ -0,0 +1,182 @@
+/* Eidos module 'arithmetic' (runtime: hosted) */
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+typedef struct eidos_frame {
+    const char *function;
+    const char *file;
+    uint32_t line;
+    const struct eidos_frame *caller;
+} eidos_frame;
+
+static const eidos_frame *eidos_current_frame = NULL;
+
+#include <stdio.h>
+#include <stdlib.h>
+
+static void *eidos_alloc(size_t size) {
+    void *ptr = malloc(size);
+    if (ptr == NULL) {
+        fputs("eidos: out of memory\n", stderr);
+        abort();
+    }
+    return ptr;
+}
+
+static void eidos_panic(const char *message) {
+    const eidos_frame *frame;
+    fflush(stdout);
+    fprintf(stderr, "eidos: panic: %s\n", message);
+    fputs("stack trace (most recent call first):\n", stderr);
+    for (frame = eidos_current_frame; frame != NULL; frame = frame->caller) {
+        if (frame->line != 0) {
+            fprintf(stderr, "    at %s (%s:%lu)\n", frame->function, frame->file, (unsigned long)frame->line);
+        } else {
+            fprintf(stderr, "    at %s\n", frame->function);
+        }
+    }
+    exit(101);
+}
+
+static void eidos_unreachable(void) { eidos_panic("reached unreachable code"); }
+
+static void eidos_print_int(int64_t value) { printf("%lld", (long long)value); }
+/* 読み戻して同じ値になる最短の桁数で出力する（JavaScriptとJITの表示と同じく 0.1 + 0.2 は 0.30000000000000004） */
+static void eidos_print_float(double value) {
+    char text[32];
+    int precision;
+    for (precision = 15; precision < 17; precision++) {
+        snprintf(text, sizeof text, "%.*g", precision, value);
+        if (strtod(text, NULL) == value) {
+            break;
+        }
+    }
+    if (precision == 17) {
+        snprintf(text, sizeof text, "%.17g", value);
+    }
+    fputs(text, stdout);
+}
+static void eidos_print_bool(bool value) { fputs(value ? "true" : "false", stdout); }
+static void eidos_print_string(const char *value) { fputs(value, stdout); }
+static void eidos_print_newline(void) { putchar('\n'); }
+
+static void eidos_print_char(uint32_t c) {
+    if (c < 0x80) {
+        putchar((int)c);
+    } else if (c < 0x800) {
+        putchar((int)(0xC0 | (c >> 6)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else if (c < 0x10000) {
+        putchar((int)(0xE0 | (c >> 12)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else {
+        putchar((int)(0xF0 | (c >> 18)));
+        putchar((int)(0x80 | ((c >> 12) & 0x3F)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    }
+}
+
+typedef uint8_t eidos_unit;
+
+static int64_t eidos_div_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("division overflow");
+    }
+    return a / b;
+}
+
+static int64_t eidos_rem_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("remainder overflow");
+    }
+    return a % b;
+}
+
+static bool eidos_string_eq(const char *a, const char *b) {
+    while (*a != '\0' && *a == *b) {
+        a++;
+        b++;
+    }
+    return *a == *b;
+}
+
+static const char *eidos_string_concat(const char *a, const char *b) {
+    size_t len_a = 0;
+    size_t len_b = 0;
+    size_t i;
+    char *result;
+    while (a[len_a] != '\0') {
+        len_a++;
+    }
+    while (b[len_b] != '\0') {
+        len_b++;
+    }
+    result = (char *)eidos_alloc(len_a + len_b + 1);
+    for (i = 0; i < len_a; i++) {
+        result[i] = a[i];
+    }
+    for (i = 0; i <= len_b; i++) {
+        result[len_a + i] = b[i];
+    }
+    return result;
+}
+
+
+int64_t _E3add(int64_t r0, int64_t r1);
+double _E5scale(double r0, double r1);
+int64_t _E4main(void);
+
+int64_t _E3add(int64_t r0, int64_t r1) {
+    int64_t r2;
+    eidos_frame frame = { "add", "arithmetic.eid", 2, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 3;
+    r2 = r0 + r1;
+    eidos_current_frame = frame.caller;
+    return r2;
+}
+
+double _E5scale(double r0, double r1) {
+    double r2;
+    double r3;
+    eidos_frame frame = { "scale", "arithmetic.eid", 6, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 7;
+    r2 = r0 * r1;
+    r3 = r2 - 0.5;
+    eidos_current_frame = frame.caller;
+    return r3;
+}
+
+int64_t _E4main(void) {
+    int64_t r0;
+    double r1;
+    int64_t r2;
+    int64_t r3;
+    eidos_frame frame = { "main", "arithmetic.eid", 10, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 11;
+    r0 = _E3add(INT64_C(40), INT64_C(2));
+    frame.line = 12;
+    r1 = _E5scale(2.0, 1.5);
+    frame.line = 13;
+    r2 = r0 * INT64_C(2);
+    r3 = r2 - INT64_C(1);
+    eidos_current_frame = frame.caller;
+    return r3;
+}
+
+int main(int argc, char **argv) {
+    (void)argc;
+    (void)argv;
+    return (int)_E4main();
+}
@@ -0,0 +1,118 @@
+// Eidos module 'bitwise'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+const g_FLAGS = 14n;
+
+export function low_byte(r0) {
+  let r1;
+  const __frame = __enter("low_byte", "bitwise.eid", 4);
+  __frame.line = 5;
+  r1 = r0 & 255n;
+  __leave();
+  return r1;
+}
+
+export function rotate_left(r0, r1) {
+  let r2, r3, r4, r5, r6;
+  const __frame = __enter("rotate_left", "bitwise.eid", 8);
+  __frame.line = 9;
+  r2 = BigInt.asUintN(8, r0 << (r1 & 7n));
+  r3 = BigInt.asUintN(8, 8n);
+  r4 = BigInt.asUintN(8, r3 - r1);
+  r5 = r0 >> (r4 & 7n);
+  r6 = r2 | r5;
+  __leave();
+  return r6;
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4, r5, r6, r7, r8, r9, r10, r11, r12;
+  const __frame = __enter("main", "bitwise.eid", 12);
+  __frame.line = 14;
+  r0 = BigInt.asIntN(64, 1n << (65n & 63n));
+  __frame.line = 15;
+  r1 = BigInt.asIntN(64, -16n);
+  r2 = -16n >> (2n & 63n);
+  __frame.line = 16;
+  r3 = low_byte(4660n);
+  r4 = r3 ^ 14n;
+  __frame.line = 17;
+  r5 = BigInt.asUintN(8, 150n);
+  r6 = BigInt.asUintN(8, 3n);
+  r7 = rotate_left(r5, r6);
+  r8 = BigInt.asIntN(64, r7);
+  __frame.line = 18;
+  r9 = BigInt.asIntN(64, r8 + r4);
+  r10 = BigInt.asIntN(64, r9 + r0);
+  r11 = BigInt.asIntN(64, r10 + -4n);
+  r12 = r11 & 255n;
+  __leave();
+  return r12;
+}
+
+export default main;
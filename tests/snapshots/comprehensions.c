@@ -0,0 +1,340 @@
+/* Eidos module 'comprehensions' (runtime: hosted) */
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+typedef struct eidos_frame {
+    const char *function;
+    const char *file;
+    uint32_t line;
+    const struct eidos_frame *caller;
+} eidos_frame;
+
+static const eidos_frame *eidos_current_frame = NULL;
+
+#include <stdio.h>
+#include <stdlib.h>
+
+static void *eidos_alloc(size_t size) {
+    void *ptr = malloc(size);
+    if (ptr == NULL) {
+        fputs("eidos: out of memory\n", stderr);
+        abort();
+    }
+    return ptr;
+}
+
+static void eidos_panic(const char *message) {
+    const eidos_frame *frame;
+    fflush(stdout);
+    fprintf(stderr, "eidos: panic: %s\n", message);
+    fputs("stack trace (most recent call first):\n", stderr);
+    for (frame = eidos_current_frame; frame != NULL; frame = frame->caller) {
+        if (frame->line != 0) {
+            fprintf(stderr, "    at %s (%s:%lu)\n", frame->function, frame->file, (unsigned long)frame->line);
+        } else {
+            fprintf(stderr, "    at %s\n", frame->function);
+        }
+    }
+    exit(101);
+}
+
+static void eidos_unreachable(void) { eidos_panic("reached unreachable code"); }
+
+static void eidos_print_int(int64_t value) { printf("%lld", (long long)value); }
+/* 読み戻して同じ値になる最短の桁数で出力する（JavaScriptとJITの表示と同じく 0.1 + 0.2 は 0.30000000000000004） */
+static void eidos_print_float(double value) {
+    char text[32];
+    int precision;
+    for (precision = 15; precision < 17; precision++) {
+        snprintf(text, sizeof text, "%.*g", precision, value);
+        if (strtod(text, NULL) == value) {
+            break;
+        }
+    }
+    if (precision == 17) {
+        snprintf(text, sizeof text, "%.17g", value);
+    }
+    fputs(text, stdout);
+}
+static void eidos_print_bool(bool value) { fputs(value ? "true" : "false", stdout); }
+static void eidos_print_string(const char *value) { fputs(value, stdout); }
+static void eidos_print_newline(void) { putchar('\n'); }
+
+static void eidos_print_char(uint32_t c) {
+    if (c < 0x80) {
+        putchar((int)c);
+    } else if (c < 0x800) {
+        putchar((int)(0xC0 | (c >> 6)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else if (c < 0x10000) {
+        putchar((int)(0xE0 | (c >> 12)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else {
+        putchar((int)(0xF0 | (c >> 18)));
+        putchar((int)(0x80 | ((c >> 12) & 0x3F)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    }
+}
+
+typedef uint8_t eidos_unit;
+
+static int64_t eidos_div_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("division overflow");
+    }
+    return a / b;
+}
+
+static int64_t eidos_rem_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("remainder overflow");
+    }
+    return a % b;
+}
+
+static bool eidos_string_eq(const char *a, const char *b) {
+    while (*a != '\0' && *a == *b) {
+        a++;
+        b++;
+    }
+    return *a == *b;
+}
+
+static const char *eidos_string_concat(const char *a, const char *b) {
+    size_t len_a = 0;
+    size_t len_b = 0;
+    size_t i;
+    char *result;
+    while (a[len_a] != '\0') {
+        len_a++;
+    }
+    while (b[len_b] != '\0') {
+        len_b++;
+    }
+    result = (char *)eidos_alloc(len_a + len_b + 1);
+    for (i = 0; i < len_a; i++) {
+        result[i] = a[i];
+    }
+    for (i = 0; i <= len_b; i++) {
+        result[len_a + i] = b[i];
+    }
+    return result;
+}
+
+typedef struct {
+    char *data;
+    int64_t length;
+} eidos_slice;
+
+typedef struct {
+    int64_t start;
+    int64_t end;
+} eidos_range;
+
+
+int64_t _E4main(void);
+
+int64_t _E4main(void) {
+    eidos_range r0;
+    int64_t r1_slot;
+    int64_t *r1 = &r1_slot;
+    eidos_range r2;
+    eidos_range r3;
+    int64_t r4;
+    int64_t r5;
+    bool r6;
+    int64_t r7;
+    int64_t r8;
+    eidos_range r9;
+    int64_t r10;
+    int64_t r11_slot[5];
+    char *r11 = (char *)r11_slot;
+    int64_t r12;
+    int64_t *r13;
+    int64_t r14;
+    int64_t r15;
+    int64_t r16;
+    int64_t r17_slot[5];
+    char *r17 = (char *)r17_slot;
+    int64_t *r18;
+    int64_t *r19;
+    int64_t *r20;
+    int64_t *r21;
+    int64_t *r22;
+    int64_t r23_slot;
+    int64_t *r23 = &r23_slot;
+    eidos_range r24;
+    eidos_range r25;
+    eidos_range r26;
+    int64_t r27;
+    int64_t r28;
+    bool r29;
+    int64_t r30;
+    int64_t r31;
+    eidos_range r32;
+    int64_t *r33;
+    int64_t r34;
+    bool r35;
+    int64_t r36;
+    int64_t r37_slot[5];
+    char *r37 = (char *)r37_slot;
+    int64_t r38;
+    int64_t *r39;
+    int64_t r40;
+    int64_t r41;
+    eidos_slice r42;
+    int64_t *r43;
+    int64_t r44;
+    int64_t r45;
+    bool r46;
+    bool r47;
+    bool r48;
+    int64_t *r49;
+    int64_t r50;
+    int64_t r51;
+    int64_t r52;
+    bool r53;
+    bool r54;
+    bool r55;
+    int64_t *r56;
+    int64_t r57;
+    int64_t r58;
+    eidos_frame frame = { "main", "comprehensions.eid", 2, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 3;
+    *r1 = INT64_C(0);
+    r4 = INT64_C(0);
+    goto bb1;
+bb1:;
+    frame.line = 3;
+    r6 = r4 < INT64_C(5);
+    if (r6) {
+        goto bb2;
+    } else {
+        goto bb3;
+    }
+bb2:;
+    frame.line = 3;
+    r10 = r4 * r4;
+    r12 = *r1;
+    r13 = (int64_t *)(r11 + r12 * 8);
+    *r13 = r10;
+    r14 = r12 + INT64_C(1);
+    *r1 = r14;
+    goto bb4;
+bb3:;
+    frame.line = 4;
+    r15 = -INT64_C(1);
+    r16 = -INT64_C(1);
+    r18 = (int64_t *)(r17 + INT64_C(0) * 8);
+    *r18 = INT64_C(3);
+    r19 = (int64_t *)(r17 + INT64_C(1) * 8);
+    *r19 = INT64_C(-1);
+    r20 = (int64_t *)(r17 + INT64_C(2) * 8);
+    *r20 = INT64_C(4);
+    r21 = (int64_t *)(r17 + INT64_C(3) * 8);
+    *r21 = INT64_C(-1);
+    r22 = (int64_t *)(r17 + INT64_C(4) * 8);
+    *r22 = INT64_C(5);
+    frame.line = 5;
+    *r23 = INT64_C(0);
+    r27 = INT64_C(0);
+    goto bb5;
+bb4:;
+    frame.line = 3;
+    r7 = r4 + INT64_C(1);
+    r4 = r7;
+    goto bb1;
+bb5:;
+    frame.line = 5;
+    r29 = r27 < INT64_C(5);
+    if (r29) {
+        goto bb6;
+    } else {
+        goto bb7;
+    }
+bb6:;
+    frame.line = 5;
+    r33 = (int64_t *)(r17 + r27 * 8);
+    r34 = *r33;
+    r35 = r34 > INT64_C(0);
+    if (r35) {
+        goto bb9;
+    } else {
+        goto bb8;
+    }
+bb7:;
+    frame.line = 5;
+    r41 = *r23;
+    r42 = (eidos_slice){ r37, r41 };
+    frame.line = 6;
+    r43 = (int64_t *)(r11 + INT64_C(4) * 8);
+    r44 = *r43;
+    r45 = r42.length;
+    r46 = INT64_C(0) >= INT64_C(0);
+    r47 = INT64_C(0) < r45;
+    r48 = true && r47;
+    if (r48) {
+        goto bb11;
+    } else {
+        goto bb10;
+    }
+bb8:;
+    frame.line = 5;
+    r30 = r27 + INT64_C(1);
+    r27 = r30;
+    goto bb5;
+bb9:;
+    frame.line = 5;
+    r36 = (uint64_t)r34 << (INT64_C(1) & 63);
+    r38 = *r23;
+    r39 = (int64_t *)(r37 + r38 * 8);
+    *r39 = r36;
+    r40 = r38 + INT64_C(1);
+    *r23 = r40;
+    goto bb8;
+bb10:;
+    frame.line = 6;
+    eidos_panic("index out of bounds");
+    eidos_unreachable();
+bb11:;
+    frame.line = 6;
+    r49 = (int64_t *)(r42.data + INT64_C(0) * 8);
+    r50 = *r49;
+    r51 = r44 + r50;
+    r52 = r42.length;
+    r53 = INT64_C(2) >= INT64_C(0);
+    r54 = INT64_C(2) < r52;
+    r55 = true && r54;
+    if (r55) {
+        goto bb13;
+    } else {
+        goto bb12;
+    }
+bb12:;
+    frame.line = 6;
+    eidos_panic("index out of bounds");
+    eidos_unreachable();
+bb13:;
+    frame.line = 6;
+    r56 = (int64_t *)(r42.data + INT64_C(2) * 8);
+    r57 = *r56;
+    r58 = r51 + r57;
+    eidos_current_frame = frame.caller;
+    return r58;
+}
+
+int main(int argc, char **argv) {
+    (void)argc;
+    (void)argv;
+    return (int)_E4main();
+}
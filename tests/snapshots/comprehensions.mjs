@@ -0,0 +1,241 @@
+// Eidos module 'comprehensions'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+function __element(array, index) {
+  const i = Number(index);
+  return { get value() { return array[i]; }, set value(v) { array[i] = v; } };
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4, r5, r6, r7, r8, r9, r10, r11, r12, r13, r14, r15, r16, r17, r18, r19, r20, r21, r22, r23, r24, r25, r26, r27, r28, r29, r30, r31, r32, r33, r34, r35, r36, r37, r38, r39, r40, r41, r42, r43, r44, r45, r46, r47, r48, r49, r50, r51, r52, r53, r54, r55, r56, r57, r58;
+  const __frame = __enter("main", "comprehensions.eid", 2);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 3;
+        r1 = { value: undefined };
+        r1.value = 0n;
+        r11 = new Array(5);
+        r4 = 0n;
+        __block = 1;
+        continue;
+      }
+      case 1: {
+        __frame.line = 3;
+        r6 = r4 < 5n;
+        if (r6) {
+          __block = 2;
+          continue;
+        } else {
+          __block = 3;
+          continue;
+        }
+      }
+      case 2: {
+        __frame.line = 3;
+        r10 = BigInt.asIntN(64, r4 * r4);
+        r12 = r1.value;
+        r13 = __element(r11, r12);
+        r13.value = r10;
+        r14 = BigInt.asIntN(64, r12 + 1n);
+        r1.value = r14;
+        __block = 4;
+        continue;
+      }
+      case 3: {
+        __frame.line = 4;
+        r15 = BigInt.asIntN(64, -1n);
+        r16 = BigInt.asIntN(64, -1n);
+        r17 = new Array(5);
+        r18 = __element(r17, 0n);
+        r18.value = 3n;
+        r19 = __element(r17, 1n);
+        r19.value = -1n;
+        r20 = __element(r17, 2n);
+        r20.value = 4n;
+        r21 = __element(r17, 3n);
+        r21.value = -1n;
+        r22 = __element(r17, 4n);
+        r22.value = 5n;
+        __frame.line = 5;
+        r23 = { value: undefined };
+        r23.value = 0n;
+        r37 = new Array(5);
+        r27 = 0n;
+        __block = 5;
+        continue;
+      }
+      case 4: {
+        __frame.line = 3;
+        r7 = BigInt.asIntN(64, r4 + 1n);
+        r4 = r7;
+        __block = 1;
+        continue;
+      }
+      case 5: {
+        __frame.line = 5;
+        r29 = r27 < 5n;
+        if (r29) {
+          __block = 6;
+          continue;
+        } else {
+          __block = 7;
+          continue;
+        }
+      }
+      case 6: {
+        __frame.line = 5;
+        r33 = __element(r17, r27);
+        r34 = r33.value;
+        r35 = r34 > 0n;
+        if (r35) {
+          __block = 9;
+          continue;
+        } else {
+          __block = 8;
+          continue;
+        }
+      }
+      case 7: {
+        __frame.line = 5;
+        r41 = r23.value;
+        r42 = { data: r37, length: r41 };
+        __frame.line = 6;
+        r43 = __element(r11, 4n);
+        r44 = r43.value;
+        r45 = r42.length;
+        r46 = 0n >= 0n;
+        r47 = 0n < r45;
+        r48 = true && r47;
+        if (r48) {
+          __block = 11;
+          continue;
+        } else {
+          __block = 10;
+          continue;
+        }
+      }
+      case 8: {
+        __frame.line = 5;
+        r30 = BigInt.asIntN(64, r27 + 1n);
+        r27 = r30;
+        __block = 5;
+        continue;
+      }
+      case 9: {
+        __frame.line = 5;
+        r36 = BigInt.asIntN(64, r34 << (1n & 63n));
+        r38 = r23.value;
+        r39 = __element(r37, r38);
+        r39.value = r36;
+        r40 = BigInt.asIntN(64, r38 + 1n);
+        r23.value = r40;
+        __block = 8;
+        continue;
+      }
+      case 10: {
+        __frame.line = 6;
+        __panic("index out of bounds");
+        __unreachable();
+      }
+      case 11: {
+        __frame.line = 6;
+        r49 = __element(r42.data, 0n);
+        r50 = r49.value;
+        r51 = BigInt.asIntN(64, r44 + r50);
+        r52 = r42.length;
+        r53 = 2n >= 0n;
+        r54 = 2n < r52;
+        r55 = true && r54;
+        if (r55) {
+          __block = 13;
+          continue;
+        } else {
+          __block = 12;
+          continue;
+        }
+      }
+      case 12: {
+        __frame.line = 6;
+        __panic("index out of bounds");
+        __unreachable();
+      }
+      case 13: {
+        __frame.line = 6;
+        r56 = __element(r42.data, 2n);
+        r57 = r56.value;
+        r58 = BigInt.asIntN(64, r51 + r57);
+        __leave();
+        return r58;
+      }
+    }
+  }
+}
+
+export default main;
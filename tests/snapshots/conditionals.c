@@ -0,0 +1,286 @@
+/* Eidos module 'conditionals' (runtime: hosted) */
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+typedef struct eidos_frame {
+    const char *function;
+    const char *file;
+    uint32_t line;
+    const struct eidos_frame *caller;
+} eidos_frame;
+
+static const eidos_frame *eidos_current_frame = NULL;
+
+#include <stdio.h>
+#include <stdlib.h>
+
+static void *eidos_alloc(size_t size) {
+    void *ptr = malloc(size);
+    if (ptr == NULL) {
+        fputs("eidos: out of memory\n", stderr);
+        abort();
+    }
+    return ptr;
+}
+
+static void eidos_panic(const char *message) {
+    const eidos_frame *frame;
+    fflush(stdout);
+    fprintf(stderr, "eidos: panic: %s\n", message);
+    fputs("stack trace (most recent call first):\n", stderr);
+    for (frame = eidos_current_frame; frame != NULL; frame = frame->caller) {
+        if (frame->line != 0) {
+            fprintf(stderr, "    at %s (%s:%lu)\n", frame->function, frame->file, (unsigned long)frame->line);
+        } else {
+            fprintf(stderr, "    at %s\n", frame->function);
+        }
+    }
+    exit(101);
+}
+
+static void eidos_unreachable(void) { eidos_panic("reached unreachable code"); }
+
+static void eidos_print_int(int64_t value) { printf("%lld", (long long)value); }
+/* 読み戻して同じ値になる最短の桁数で出力する（JavaScriptとJITの表示と同じく 0.1 + 0.2 は 0.30000000000000004） */
+static void eidos_print_float(double value) {
+    char text[32];
+    int precision;
+    for (precision = 15; precision < 17; precision++) {
+        snprintf(text, sizeof text, "%.*g", precision, value);
+        if (strtod(text, NULL) == value) {
+            break;
+        }
+    }
+    if (precision == 17) {
+        snprintf(text, sizeof text, "%.17g", value);
+    }
+    fputs(text, stdout);
+}
+static void eidos_print_bool(bool value) { fputs(value ? "true" : "false", stdout); }
+static void eidos_print_string(const char *value) { fputs(value, stdout); }
+static void eidos_print_newline(void) { putchar('\n'); }
+
+static void eidos_print_char(uint32_t c) {
+    if (c < 0x80) {
+        putchar((int)c);
+    } else if (c < 0x800) {
+        putchar((int)(0xC0 | (c >> 6)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else if (c < 0x10000) {
+        putchar((int)(0xE0 | (c >> 12)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else {
+        putchar((int)(0xF0 | (c >> 18)));
+        putchar((int)(0x80 | ((c >> 12) & 0x3F)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    }
+}
+
+typedef uint8_t eidos_unit;
+
+static int64_t eidos_div_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("division overflow");
+    }
+    return a / b;
+}
+
+static int64_t eidos_rem_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("remainder overflow");
+    }
+    return a % b;
+}
+
+static bool eidos_string_eq(const char *a, const char *b) {
+    while (*a != '\0' && *a == *b) {
+        a++;
+        b++;
+    }
+    return *a == *b;
+}
+
+static const char *eidos_string_concat(const char *a, const char *b) {
+    size_t len_a = 0;
+    size_t len_b = 0;
+    size_t i;
+    char *result;
+    while (a[len_a] != '\0') {
+        len_a++;
+    }
+    while (b[len_b] != '\0') {
+        len_b++;
+    }
+    result = (char *)eidos_alloc(len_a + len_b + 1);
+    for (i = 0; i < len_a; i++) {
+        result[i] = a[i];
+    }
+    for (i = 0; i <= len_b; i++) {
+        result[len_a + i] = b[i];
+    }
+    return result;
+}
+
+
+int64_t _E4sign(int64_t r0);
+uint8_t _E5clamp(uint8_t r0, uint8_t r1);
+int64_t _E11checked_div(int64_t r0, int64_t r1);
+int64_t _E4main(void);
+
+int64_t _E4sign(int64_t r0) {
+    bool r1;
+    int64_t r2;
+    bool r3;
+    int64_t r4;
+    int64_t r5;
+    eidos_frame frame = { "sign", "conditionals.eid", 2, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 3;
+    r1 = r0 < INT64_C(0);
+    if (r1) {
+        goto bb1;
+    } else {
+        goto bb2;
+    }
+bb1:;
+    frame.line = 3;
+    r2 = -INT64_C(1);
+    r5 = INT64_C(-1);
+    goto bb3;
+bb2:;
+    frame.line = 3;
+    r3 = r0 == INT64_C(0);
+    r4 = r3 ? INT64_C(0) : INT64_C(1);
+    r5 = r4;
+    goto bb3;
+bb3:;
+    eidos_current_frame = frame.caller;
+    return r5;
+}
+
+uint8_t _E5clamp(uint8_t r0, uint8_t r1) {
+    bool r2;
+    uint8_t r3;
+    eidos_frame frame = { "clamp", "conditionals.eid", 6, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 7;
+    r2 = r0 > r1;
+    r3 = r2 ? r1 : r0;
+    eidos_current_frame = frame.caller;
+    return r3;
+}
+
+int64_t _E11checked_div(int64_t r0, int64_t r1) {
+    bool r2;
+    int64_t r3;
+    int64_t r4;
+    eidos_frame frame = { "checked_div", "conditionals.eid", 10, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 11;
+    r2 = r1 == INT64_C(0);
+    if (r2) {
+        goto bb1;
+    } else {
+        goto bb2;
+    }
+bb1:;
+    frame.line = 12;
+    eidos_panic("division by zero");
+    eidos_unreachable();
+bb2:;
+    frame.line = 14;
+    r3 = eidos_div_i64(r0, r1);
+    r4 = r3;
+    goto bb3;
+bb3:;
+    eidos_current_frame = frame.caller;
+    return r4;
+    eidos_unreachable();
+}
+
+int64_t _E4main(void) {
+    int64_t r0;
+    int64_t r1;
+    bool r2;
+    int64_t r3;
+    uint8_t r4;
+    uint8_t r5;
+    uint8_t r6;
+    int64_t r7;
+    int64_t r8_slot;
+    int64_t *r8 = &r8_slot;
+    bool r9;
+    int64_t r10;
+    int64_t r11;
+    bool r12;
+    uint8_t r13;
+    uint8_t r14;
+    uint8_t r15;
+    int64_t r16;
+    int64_t r17;
+    int64_t r18;
+    int64_t r19;
+    int64_t r20;
+    int64_t r21;
+    int64_t r22;
+    int64_t r23;
+    eidos_frame frame = { "main", "conditionals.eid", 18, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 19;
+    r0 = -INT64_C(5);
+    r1 = _E4sign(INT64_C(-5));
+    r2 = r1 < INT64_C(0);
+    r3 = r2 ? INT64_C(200) : INT64_C(0);
+    frame.line = 20;
+    r4 = (uint8_t)INT64_C(250);
+    r5 = (uint8_t)INT64_C(100);
+    r6 = _E5clamp(r4, r5);
+    r7 = (int64_t)r6;
+    frame.line = 21;
+    *r8 = INT64_C(0);
+    frame.line = 22;
+    r9 = r3 > INT64_C(100);
+    if (r9) {
+        goto bb1;
+    } else {
+        goto bb2;
+    }
+bb1:;
+    frame.line = 23;
+    r10 = r3 + r7;
+    *r8 = r10;
+    goto bb2;
+bb2:;
+    frame.line = 25;
+    r11 = *r8;
+    r12 = r11 > INT64_C(250);
+    r13 = (uint8_t)INT64_C(5);
+    r14 = (uint8_t)INT64_C(1);
+    r15 = r12 ? r13 : r14;
+    frame.line = 26;
+    r16 = *r8;
+    r17 = _E11checked_div(INT64_C(10), INT64_C(3));
+    r18 = r16 + r17;
+    r19 = _E4sign(INT64_C(0));
+    r20 = r18 + r19;
+    r21 = (int64_t)r15;
+    r22 = r20 + r21;
+    r23 = r22 - INT64_C(100);
+    eidos_current_frame = frame.caller;
+    return r23;
+}
+
+int main(int argc, char **argv) {
+    (void)argc;
+    (void)argv;
+    return (int)_E4main();
+}
@@ -0,0 +1,219 @@
+// Eidos module 'conditionals'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+export function sign(r0) {
+  let r1, r2, r3, r4, r5;
+  const __frame = __enter("sign", "conditionals.eid", 2);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 3;
+        r1 = r0 < 0n;
+        if (r1) {
+          __block = 1;
+          continue;
+        } else {
+          __block = 2;
+          continue;
+        }
+      }
+      case 1: {
+        __frame.line = 3;
+        r2 = BigInt.asIntN(64, -1n);
+        r5 = -1n;
+        __block = 3;
+        continue;
+      }
+      case 2: {
+        __frame.line = 3;
+        r3 = r0 === 0n;
+        r4 = r3 ? 0n : 1n;
+        r5 = r4;
+        __block = 3;
+        continue;
+      }
+      case 3: {
+        __leave();
+        return r5;
+      }
+    }
+  }
+}
+
+export function clamp(r0, r1) {
+  let r2, r3;
+  const __frame = __enter("clamp", "conditionals.eid", 6);
+  __frame.line = 7;
+  r2 = r0 > r1;
+  r3 = r2 ? r1 : r0;
+  __leave();
+  return r3;
+}
+
+export function checked_div(r0, r1) {
+  let r2, r3, r4;
+  const __frame = __enter("checked_div", "conditionals.eid", 10);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 11;
+        r2 = r1 === 0n;
+        if (r2) {
+          __block = 1;
+          continue;
+        } else {
+          __block = 2;
+          continue;
+        }
+      }
+      case 1: {
+        __frame.line = 12;
+        __panic("division by zero");
+        __unreachable();
+      }
+      case 2: {
+        __frame.line = 14;
+        r3 = __div(r0, r1);
+        r4 = r3;
+        __block = 3;
+        continue;
+      }
+      case 3: {
+        __leave();
+        return r4;
+      }
+      case 4: {
+        __unreachable();
+      }
+    }
+  }
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4, r5, r6, r7, r8, r9, r10, r11, r12, r13, r14, r15, r16, r17, r18, r19, r20, r21, r22, r23;
+  const __frame = __enter("main", "conditionals.eid", 18);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 19;
+        r0 = BigInt.asIntN(64, -5n);
+        r1 = sign(-5n);
+        r2 = r1 < 0n;
+        r3 = r2 ? 200n : 0n;
+        __frame.line = 20;
+        r4 = BigInt.asUintN(8, 250n);
+        r5 = BigInt.asUintN(8, 100n);
+        r6 = clamp(r4, r5);
+        r7 = BigInt.asIntN(64, r6);
+        __frame.line = 21;
+        r8 = { value: undefined };
+        r8.value = 0n;
+        __frame.line = 22;
+        r9 = r3 > 100n;
+        if (r9) {
+          __block = 1;
+          continue;
+        } else {
+          __block = 2;
+          continue;
+        }
+      }
+      case 1: {
+        __frame.line = 23;
+        r10 = BigInt.asIntN(64, r3 + r7);
+        r8.value = r10;
+        __block = 2;
+        continue;
+      }
+      case 2: {
+        __frame.line = 25;
+        r11 = r8.value;
+        r12 = r11 > 250n;
+        r13 = BigInt.asUintN(8, 5n);
+        r14 = BigInt.asUintN(8, 1n);
+        r15 = r12 ? r13 : r14;
+        __frame.line = 26;
+        r16 = r8.value;
+        r17 = checked_div(10n, 3n);
+        r18 = BigInt.asIntN(64, r16 + r17);
+        r19 = sign(0n);
+        r20 = BigInt.asIntN(64, r18 + r19);
+        r21 = BigInt.asIntN(64, r15);
+        r22 = BigInt.asIntN(64, r20 + r21);
+        r23 = BigInt.asIntN(64, r22 - 100n);
+        __leave();
+        return r23;
+      }
+    }
+  }
+}
+
+export default main;
@@ -0,0 +1,244 @@
+// Eidos module 'errors'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+function __unwrap(variant, ok, message) {
+  if (variant.ok !== ok) __panic(message);
+  return variant.value;
+}
+
+export function parse(r0) {
+  let r1;
+  const __frame = __enter("parse", "errors.eid", 2);
+  __frame.line = 3;
+  r1 = { ok: false, value: r0 };
+  __leave();
+  return r1;
+}
+
+export function first(r0) {
+  let r1;
+  const __frame = __enter("first", "errors.eid", 6);
+  __frame.line = 7;
+  r1 = { ok: true, value: r0 };
+  __leave();
+  return r1;
+}
+
+export function checked(r0) {
+  let r1, r2, r3, r4, r5, r6;
+  const __frame = __enter("checked", "errors.eid", 10);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 12;
+        r1 = first(r0);
+        r2 = r1.ok;
+        if (r2) {
+          __block = 4;
+          continue;
+        } else {
+          __block = 3;
+          continue;
+        }
+      }
+      case 1: {
+        r6 = 0n;
+        __block = 2;
+        continue;
+      }
+      case 2: {
+        __leave();
+        return r6;
+      }
+      case 3: {
+        r3 = undefined;
+        __block = 1;
+        continue;
+      }
+      case 4: {
+        __frame.line = 12;
+        r4 = __unwrap(r1, true, "called `Option::unwrap()` on a `None` value");
+        __frame.line = 13;
+        r5 = BigInt.asIntN(64, r4 + 1n);
+        r6 = r5;
+        __block = 2;
+        continue;
+      }
+    }
+  }
+}
+
+export function fallback(r0) {
+  let r1, r2, r3, r4, r5, r6, r7;
+  const __frame = __enter("fallback", "errors.eid", 19);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 21;
+        r1 = parse(r0);
+        r2 = r1.ok;
+        if (r2) {
+          __block = 4;
+          continue;
+        } else {
+          __block = 3;
+          continue;
+        }
+      }
+      case 1: {
+        r7 = 7n;
+        __block = 2;
+        continue;
+      }
+      case 2: {
+        __leave();
+        return r7;
+      }
+      case 3: {
+        __frame.line = 21;
+        r3 = __unwrap(r1, false, "called `Result::unwrap_err()` on an `Ok` value");
+        r4 = r3;
+        __block = 1;
+        continue;
+      }
+      case 4: {
+        __frame.line = 21;
+        r5 = __unwrap(r1, true, "called `Result::unwrap()` on an `Err` value");
+        r6 = BigInt.asIntN(64, r5 * 2n);
+        r7 = r6;
+        __block = 2;
+        continue;
+      }
+    }
+  }
+}
+
+export function strict(r0) {
+  let r1, r2, r3, r4, r5;
+  const __frame = __enter("strict", "errors.eid", 27);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 29;
+        r1 = first(r0);
+        r2 = r1.ok;
+        if (r2) {
+          __block = 4;
+          continue;
+        } else {
+          __block = 3;
+          continue;
+        }
+      }
+      case 1: {
+        __frame.line = 31;
+        __panic("missing value");
+        __unreachable();
+      }
+      case 2: {
+        __leave();
+        return r5;
+      }
+      case 3: {
+        r3 = undefined;
+        __block = 1;
+        continue;
+      }
+      case 4: {
+        __frame.line = 29;
+        r4 = __unwrap(r1, true, "called `Option::unwrap()` on a `None` value");
+        r5 = r4;
+        __block = 2;
+        continue;
+      }
+      case 5: {
+        r5 = undefined;
+        __block = 2;
+        continue;
+      }
+    }
+  }
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4;
+  const __frame = __enter("main", "errors.eid", 35);
+  __frame.line = 36;
+  r0 = checked(4n);
+  r1 = fallback("x");
+  r2 = BigInt.asIntN(64, r0 + r1);
+  r3 = strict(3n);
+  r4 = BigInt.asIntN(64, r2 + r3);
+  __leave();
+  return r4;
+}
+
+export default main;
@@ -0,0 +1,102 @@
+// Eidos module 'globals'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+const g_BUFFER_SIZE = 2049n;
+const g_PAGES = 2n;
+let g_REQUESTS = 0n;
+
+export function handle(r0) {
+  let r1, r2, r3;
+  const __frame = __enter("handle", "globals.eid", 10);
+  __frame.line = 12;
+  r1 = g_REQUESTS;
+  r2 = BigInt.asIntN(64, r1 + r0);
+  g_REQUESTS = r2;
+  __frame.line = 13;
+  r3 = g_REQUESTS;
+  __leave();
+  return r3;
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4;
+  const __frame = __enter("main", "globals.eid", 17);
+  __frame.line = 18;
+  r0 = handle(1n);
+  __frame.line = 19;
+  r1 = handle(2n);
+  __frame.line = 20;
+  r2 = handle(0n);
+  __frame.line = 21;
+  r3 = BigInt.asIntN(64, g_BUFFER_SIZE - 2046n);
+  r4 = BigInt.asIntN(64, r3 + r2);
+  __leave();
+  return r4;
+}
+
+export default main;
@@ -0,0 +1,108 @@
+// Eidos module 'modules'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+export function geometry$003A$003Aarea(r0, r1) {
+  let r2, r3;
+  const __frame = __enter("geometry::area", "modules.eid", 3);
+  __frame.line = 4;
+  r2 = geometry$003A$003Ascale(r0);
+  r3 = BigInt.asIntN(64, r2 * r1);
+  __leave();
+  return r3;
+}
+
+export function geometry$003A$003Ascale(r0) {
+  let r1;
+  const __frame = __enter("geometry::scale", "modules.eid", 7);
+  __frame.line = 8;
+  r1 = BigInt.asIntN(64, r0 * 2n);
+  __leave();
+  return r1;
+}
+
+export function geometry$003A$003Aunits$003A$003Acm(r0) {
+  let r1;
+  const __frame = __enter("geometry::units::cm", "modules.eid", 12);
+  __frame.line = 13;
+  r1 = BigInt.asIntN(64, r0 * 10n);
+  __leave();
+  return r1;
+}
+
+export function main() {
+  let r0, r1, r2;
+  const __frame = __enter("main", "modules.eid", 18);
+  __frame.line = 19;
+  r0 = geometry$003A$003Aarea(2n, 3n);
+  r1 = geometry$003A$003Aunits$003A$003Acm(1n);
+  r2 = BigInt.asIntN(64, r0 + r1);
+  __leave();
+  return r2;
+}
+
+export default main;
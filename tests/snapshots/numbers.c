@@ -0,0 +1,224 @@
+/* Eidos module 'numbers' (runtime: hosted) */
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+typedef struct eidos_frame {
+    const char *function;
+    const char *file;
+    uint32_t line;
+    const struct eidos_frame *caller;
+} eidos_frame;
+
+static const eidos_frame *eidos_current_frame = NULL;
+
+#include <stdio.h>
+#include <stdlib.h>
+
+static void *eidos_alloc(size_t size) {
+    void *ptr = malloc(size);
+    if (ptr == NULL) {
+        fputs("eidos: out of memory\n", stderr);
+        abort();
+    }
+    return ptr;
+}
+
+static void eidos_panic(const char *message) {
+    const eidos_frame *frame;
+    fflush(stdout);
+    fprintf(stderr, "eidos: panic: %s\n", message);
+    fputs("stack trace (most recent call first):\n", stderr);
+    for (frame = eidos_current_frame; frame != NULL; frame = frame->caller) {
+        if (frame->line != 0) {
+            fprintf(stderr, "    at %s (%s:%lu)\n", frame->function, frame->file, (unsigned long)frame->line);
+        } else {
+            fprintf(stderr, "    at %s\n", frame->function);
+        }
+    }
+    exit(101);
+}
+
+static void eidos_unreachable(void) { eidos_panic("reached unreachable code"); }
+
+static void eidos_print_int(int64_t value) { printf("%lld", (long long)value); }
+/* 読み戻して同じ値になる最短の桁数で出力する（JavaScriptとJITの表示と同じく 0.1 + 0.2 は 0.30000000000000004） */
+static void eidos_print_float(double value) {
+    char text[32];
+    int precision;
+    for (precision = 15; precision < 17; precision++) {
+        snprintf(text, sizeof text, "%.*g", precision, value);
+        if (strtod(text, NULL) == value) {
+            break;
+        }
+    }
+    if (precision == 17) {
+        snprintf(text, sizeof text, "%.17g", value);
+    }
+    fputs(text, stdout);
+}
+static void eidos_print_bool(bool value) { fputs(value ? "true" : "false", stdout); }
+static void eidos_print_string(const char *value) { fputs(value, stdout); }
+static void eidos_print_newline(void) { putchar('\n'); }
+
+static void eidos_print_char(uint32_t c) {
+    if (c < 0x80) {
+        putchar((int)c);
+    } else if (c < 0x800) {
+        putchar((int)(0xC0 | (c >> 6)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else if (c < 0x10000) {
+        putchar((int)(0xE0 | (c >> 12)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else {
+        putchar((int)(0xF0 | (c >> 18)));
+        putchar((int)(0x80 | ((c >> 12) & 0x3F)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    }
+}
+
+typedef uint8_t eidos_unit;
+
+static int64_t eidos_div_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("division overflow");
+    }
+    return a / b;
+}
+
+static int64_t eidos_rem_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("remainder overflow");
+    }
+    return a % b;
+}
+
+static bool eidos_string_eq(const char *a, const char *b) {
+    while (*a != '\0' && *a == *b) {
+        a++;
+        b++;
+    }
+    return *a == *b;
+}
+
+static const char *eidos_string_concat(const char *a, const char *b) {
+    size_t len_a = 0;
+    size_t len_b = 0;
+    size_t i;
+    char *result;
+    while (a[len_a] != '\0') {
+        len_a++;
+    }
+    while (b[len_b] != '\0') {
+        len_b++;
+    }
+    result = (char *)eidos_alloc(len_a + len_b + 1);
+    for (i = 0; i < len_a; i++) {
+        result[i] = a[i];
+    }
+    for (i = 0; i <= len_b; i++) {
+        result[len_a + i] = b[i];
+    }
+    return result;
+}
+
+static uint8_t const _E4MASK = INT64_C(240);
+
+uint8_t _E8wrap_add(uint8_t r0, uint8_t r1);
+float _E5scale(float r0);
+int64_t _E4main(void);
+
+uint8_t _E8wrap_add(uint8_t r0, uint8_t r1) {
+    uint8_t r2;
+    eidos_frame frame = { "wrap_add", "numbers.eid", 4, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 5;
+    r2 = (uint32_t)r0 + (uint32_t)r1;
+    eidos_current_frame = frame.caller;
+    return r2;
+}
+
+float _E5scale(float r0) {
+    float r1;
+    float r2;
+    eidos_frame frame = { "scale", "numbers.eid", 8, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 9;
+    r1 = (float)2.5;
+    r2 = r0 * r1;
+    eidos_current_frame = frame.caller;
+    return r2;
+}
+
+int64_t _E4main(void) {
+    int8_t r0;
+    int8_t r1;
+    int8_t r2;
+    int8_t r3;
+    uint8_t r4;
+    uint8_t r5;
+    uint8_t r6;
+    uint8_t r7;
+    uint8_t r8;
+    uint8_t r9;
+    float r10;
+    float r11;
+    int32_t r12;
+    int64_t r13;
+    int64_t r14;
+    int64_t r15;
+    int64_t r16;
+    int64_t r17;
+    int64_t r18;
+    int64_t r19;
+    int64_t r20;
+    int64_t r21;
+    int64_t r22;
+    eidos_frame frame = { "main", "numbers.eid", 12, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 15;
+    r0 = (int8_t)INT64_C(100);
+    r1 = -r0;
+    r2 = (int8_t)INT64_C(28);
+    r3 = (uint32_t)r1 - (uint32_t)r2;
+    frame.line = 16;
+    r4 = (uint8_t)INT64_C(200);
+    r5 = (uint8_t)INT64_C(100);
+    r6 = _E8wrap_add(r4, r5);
+    frame.line = 17;
+    r7 = (uint8_t)INT64_C(240);
+    r8 = (uint8_t)INT64_C(16);
+    r9 = (uint32_t)r7 - (uint32_t)r8;
+    frame.line = 18;
+    r10 = (float)1.5;
+    r11 = _E5scale(r10);
+    r12 = (int32_t)r11;
+    frame.line = 19;
+    r13 = (int64_t)r3;
+    r14 = (int64_t)r6;
+    r15 = r13 + r14;
+    r16 = (int64_t)r9;
+    r17 = r15 + r16;
+    r18 = (int64_t)r12;
+    r19 = r17 + r18;
+    frame.line = 20;
+    r20 = eidos_div_i64(INT64_C(1000000), INT64_C(100000));
+    r21 = r19 + INT64_C(10);
+    r22 = r21 + INT64_C(10);
+    eidos_current_frame = frame.caller;
+    return r22;
+}
+
+int main(int argc, char **argv) {
+    (void)argc;
+    (void)argv;
+    return (int)_E4main();
+}
@@ -0,0 +1,126 @@
+// Eidos module 'numbers'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+const g_MASK = BigInt.asUintN(8, 240n);
+
+export function wrap_add(r0, r1) {
+  let r2;
+  const __frame = __enter("wrap_add", "numbers.eid", 4);
+  __frame.line = 5;
+  r2 = BigInt.asUintN(8, r0 + r1);
+  __leave();
+  return r2;
+}
+
+export function scale(r0) {
+  let r1, r2;
+  const __frame = __enter("scale", "numbers.eid", 8);
+  __frame.line = 9;
+  r1 = Math.fround(2.5);
+  r2 = Math.fround(r0 * r1);
+  __leave();
+  return r2;
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4, r5, r6, r7, r8, r9, r10, r11, r12, r13, r14, r15, r16, r17, r18, r19, r20, r21, r22;
+  const __frame = __enter("main", "numbers.eid", 12);
+  __frame.line = 15;
+  r0 = BigInt.asIntN(8, 100n);
+  r1 = BigInt.asIntN(8, -r0);
+  r2 = BigInt.asIntN(8, 28n);
+  r3 = BigInt.asIntN(8, r1 - r2);
+  __frame.line = 16;
+  r4 = BigInt.asUintN(8, 200n);
+  r5 = BigInt.asUintN(8, 100n);
+  r6 = wrap_add(r4, r5);
+  __frame.line = 17;
+  r7 = BigInt.asUintN(8, 240n);
+  r8 = BigInt.asUintN(8, 16n);
+  r9 = BigInt.asUintN(8, r7 - r8);
+  __frame.line = 18;
+  r10 = Math.fround(1.5);
+  r11 = scale(r10);
+  r12 = BigInt.asIntN(32, BigInt(Math.trunc(r11)));
+  __frame.line = 19;
+  r13 = BigInt.asIntN(64, r3);
+  r14 = BigInt.asIntN(64, r6);
+  r15 = BigInt.asIntN(64, r13 + r14);
+  r16 = BigInt.asIntN(64, r9);
+  r17 = BigInt.asIntN(64, r15 + r16);
+  r18 = BigInt.asIntN(64, r12);
+  r19 = BigInt.asIntN(64, r17 + r18);
+  __frame.line = 20;
+  r20 = __div(1000000n, 100000n);
+  r21 = BigInt.asIntN(64, r19 + 10n);
+  r22 = BigInt.asIntN(64, r21 + 10n);
+  __leave();
+  return r22;
+}
+
+export default main;
@@ -0,0 +1,330 @@
+/* Eidos module 'ranges' (runtime: hosted) */
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+typedef struct eidos_frame {
+    const char *function;
+    const char *file;
+    uint32_t line;
+    const struct eidos_frame *caller;
+} eidos_frame;
+
+static const eidos_frame *eidos_current_frame = NULL;
+
+#include <stdio.h>
+#include <stdlib.h>
+
+static void *eidos_alloc(size_t size) {
+    void *ptr = malloc(size);
+    if (ptr == NULL) {
+        fputs("eidos: out of memory\n", stderr);
+        abort();
+    }
+    return ptr;
+}
+
+static void eidos_panic(const char *message) {
+    const eidos_frame *frame;
+    fflush(stdout);
+    fprintf(stderr, "eidos: panic: %s\n", message);
+    fputs("stack trace (most recent call first):\n", stderr);
+    for (frame = eidos_current_frame; frame != NULL; frame = frame->caller) {
+        if (frame->line != 0) {
+            fprintf(stderr, "    at %s (%s:%lu)\n", frame->function, frame->file, (unsigned long)frame->line);
+        } else {
+            fprintf(stderr, "    at %s\n", frame->function);
+        }
+    }
+    exit(101);
+}
+
+static void eidos_unreachable(void) { eidos_panic("reached unreachable code"); }
+
+static void eidos_print_int(int64_t value) { printf("%lld", (long long)value); }
+/* 読み戻して同じ値になる最短の桁数で出力する（JavaScriptとJITの表示と同じく 0.1 + 0.2 は 0.30000000000000004） */
+static void eidos_print_float(double value) {
+    char text[32];
+    int precision;
+    for (precision = 15; precision < 17; precision++) {
+        snprintf(text, sizeof text, "%.*g", precision, value);
+        if (strtod(text, NULL) == value) {
+            break;
+        }
+    }
+    if (precision == 17) {
+        snprintf(text, sizeof text, "%.17g", value);
+    }
+    fputs(text, stdout);
+}
+static void eidos_print_bool(bool value) { fputs(value ? "true" : "false", stdout); }
+static void eidos_print_string(const char *value) { fputs(value, stdout); }
+static void eidos_print_newline(void) { putchar('\n'); }
+
+static void eidos_print_char(uint32_t c) {
+    if (c < 0x80) {
+        putchar((int)c);
+    } else if (c < 0x800) {
+        putchar((int)(0xC0 | (c >> 6)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else if (c < 0x10000) {
+        putchar((int)(0xE0 | (c >> 12)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    } else {
+        putchar((int)(0xF0 | (c >> 18)));
+        putchar((int)(0x80 | ((c >> 12) & 0x3F)));
+        putchar((int)(0x80 | ((c >> 6) & 0x3F)));
+        putchar((int)(0x80 | (c & 0x3F)));
+    }
+}
+
+typedef uint8_t eidos_unit;
+
+static int64_t eidos_div_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("division overflow");
+    }
+    return a / b;
+}
+
+static int64_t eidos_rem_i64(int64_t a, int64_t b) {
+    if (b == 0) {
+        eidos_panic("division by zero");
+    }
+    if (a == INT64_MIN && b == -1) {
+        eidos_panic("remainder overflow");
+    }
+    return a % b;
+}
+
+static bool eidos_string_eq(const char *a, const char *b) {
+    while (*a != '\0' && *a == *b) {
+        a++;
+        b++;
+    }
+    return *a == *b;
+}
+
+static const char *eidos_string_concat(const char *a, const char *b) {
+    size_t len_a = 0;
+    size_t len_b = 0;
+    size_t i;
+    char *result;
+    while (a[len_a] != '\0') {
+        len_a++;
+    }
+    while (b[len_b] != '\0') {
+        len_b++;
+    }
+    result = (char *)eidos_alloc(len_a + len_b + 1);
+    for (i = 0; i < len_a; i++) {
+        result[i] = a[i];
+    }
+    for (i = 0; i <= len_b; i++) {
+        result[len_a + i] = b[i];
+    }
+    return result;
+}
+
+typedef union {
+    int64_t i;
+    double f;
+    bool b;
+    uint32_t c;
+    const char *s;
+} eidos_payload;
+
+typedef struct {
+    bool ok;
+    eidos_payload value;
+} eidos_variant;
+
+static eidos_payload eidos_unwrap(eidos_variant variant, bool ok, const char *message) {
+    if (variant.ok != ok) {
+        eidos_panic(message);
+    }
+    return variant.value;
+}
+
+typedef struct {
+    int64_t start;
+    int64_t end;
+} eidos_range;
+
+
+int64_t _E6sum_to(int64_t r0);
+int64_t _E9first_two(int64_t r0);
+int64_t _E4main(void);
+
+int64_t _E6sum_to(int64_t r0) {
+    int64_t r1_slot;
+    int64_t *r1 = &r1_slot;
+    eidos_range r2;
+    eidos_range r3;
+    eidos_range r4;
+    int64_t r5;
+    int64_t r6;
+    bool r7;
+    int64_t r8;
+    int64_t r9;
+    eidos_range r10;
+    int64_t r11;
+    int64_t r12;
+    int64_t r13;
+    eidos_frame frame = { "sum_to", "ranges.eid", 2, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 3;
+    *r1 = INT64_C(0);
+    r5 = INT64_C(0);
+    goto bb1;
+bb1:;
+    frame.line = 4;
+    r7 = r5 < r0;
+    if (r7) {
+        goto bb2;
+    } else {
+        goto bb3;
+    }
+bb2:;
+    frame.line = 5;
+    r11 = *r1;
+    r12 = r11 + r5;
+    *r1 = r12;
+    frame.line = 4;
+    r8 = r5 + INT64_C(1);
+    r5 = r8;
+    goto bb1;
+bb3:;
+    frame.line = 7;
+    r13 = *r1;
+    eidos_current_frame = frame.caller;
+    return r13;
+}
+
+int64_t _E9first_two(int64_t r0) {
+    int64_t r1;
+    eidos_range r2;
+    eidos_range r3_slot;
+    eidos_range *r3 = &r3_slot;
+    eidos_range r4;
+    int64_t r5;
+    int64_t r6;
+    bool r7;
+    int64_t r8;
+    int64_t r9;
+    eidos_range r10;
+    eidos_variant r11;
+    eidos_variant r12;
+    eidos_variant r13;
+    int64_t r14;
+    eidos_range r15;
+    int64_t r16;
+    int64_t r17;
+    bool r18;
+    int64_t r19;
+    int64_t r20;
+    eidos_range r21;
+    eidos_variant r22;
+    eidos_variant r23;
+    eidos_variant r24;
+    int64_t r25;
+    int64_t r26;
+    eidos_frame frame = { "first_two", "ranges.eid", 10, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 11;
+    r1 = r0 + INT64_C(3);
+    r2 = (eidos_range){ r0, r1 };
+    *r3 = r2;
+    frame.line = 12;
+    r4 = *r3;
+    r5 = r4.start;
+    r6 = r4.end;
+    r7 = r5 < r6;
+    r8 = r5 + INT64_C(1);
+    r9 = r7 ? r8 : r5;
+    r10 = (eidos_range){ r9, r6 };
+    *r3 = r10;
+    r11 = (eidos_variant){ true, { .i = r5 } };
+    r12 = (eidos_variant){ false, { .i = 0 } };
+    r13 = r7 ? r11 : r12;
+    r14 = r13.ok ? r13.value.i : INT64_C(0);
+    frame.line = 13;
+    r15 = *r3;
+    r16 = r15.start;
+    r17 = r15.end;
+    r18 = r16 < r17;
+    r19 = r16 + INT64_C(1);
+    r20 = r18 ? r19 : r16;
+    r21 = (eidos_range){ r20, r17 };
+    *r3 = r21;
+    r22 = (eidos_variant){ true, { .i = r16 } };
+    r23 = (eidos_variant){ false, { .i = 0 } };
+    r24 = r18 ? r22 : r23;
+    r25 = r24.ok ? r24.value.i : INT64_C(0);
+    r26 = r14 + r25;
+    eidos_current_frame = frame.caller;
+    return r26;
+}
+
+int64_t _E4main(void) {
+    eidos_range r0;
+    int64_t r1_slot;
+    int64_t *r1 = &r1_slot;
+    eidos_range r2;
+    eidos_range r3;
+    int64_t r4;
+    int64_t r5;
+    bool r6;
+    int64_t r7;
+    int64_t r8;
+    eidos_range r9;
+    int64_t r10;
+    int64_t r11;
+    int64_t r12;
+    int64_t r13;
+    int64_t r14;
+    int64_t r15;
+    int64_t r16;
+    eidos_frame frame = { "main", "ranges.eid", 16, eidos_current_frame };
+    eidos_current_frame = &frame;
+    frame.line = 18;
+    *r1 = INT64_C(0);
+    r4 = INT64_C(3);
+    goto bb1;
+bb1:;
+    frame.line = 19;
+    r6 = r4 < INT64_C(7);
+    if (r6) {
+        goto bb2;
+    } else {
+        goto bb3;
+    }
+bb2:;
+    frame.line = 20;
+    r10 = *r1;
+    r11 = r10 + INT64_C(1);
+    *r1 = r11;
+    frame.line = 19;
+    r7 = r4 + INT64_C(1);
+    r4 = r7;
+    goto bb1;
+bb3:;
+    frame.line = 22;
+    r12 = _E6sum_to(INT64_C(10));
+    r13 = *r1;
+    r14 = r12 + r13;
+    r15 = _E9first_two(INT64_C(5));
+    r16 = r14 + r15;
+    eidos_current_frame = frame.caller;
+    return r16;
+}
+
+int main(int argc, char **argv) {
+    (void)argc;
+    (void)argv;
+    return (int)_E4main();
+}
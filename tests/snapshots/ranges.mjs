@@ -0,0 +1,209 @@
+// Eidos module 'ranges'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+function __unwrap(variant, ok, message) {
+  if (variant.ok !== ok) __panic(message);
+  return variant.value;
+}
+
+export function sum_to(r0) {
+  let r1, r2, r3, r4, r5, r6, r7, r8, r9, r10, r11, r12, r13;
+  const __frame = __enter("sum_to", "ranges.eid", 2);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 3;
+        r1 = { value: undefined };
+        r1.value = 0n;
+        r5 = 0n;
+        __block = 1;
+        continue;
+      }
+      case 1: {
+        __frame.line = 4;
+        r7 = r5 < r0;
+        if (r7) {
+          __block = 2;
+          continue;
+        } else {
+          __block = 3;
+          continue;
+        }
+      }
+      case 2: {
+        __frame.line = 5;
+        r11 = r1.value;
+        r12 = BigInt.asIntN(64, r11 + r5);
+        r1.value = r12;
+        __frame.line = 4;
+        r8 = BigInt.asIntN(64, r5 + 1n);
+        r5 = r8;
+        __block = 1;
+        continue;
+      }
+      case 3: {
+        __frame.line = 7;
+        r13 = r1.value;
+        __leave();
+        return r13;
+      }
+    }
+  }
+}
+
+export function first_two(r0) {
+  let r1, r2, r3, r4, r5, r6, r7, r8, r9, r10, r11, r12, r13, r14, r15, r16, r17, r18, r19, r20, r21, r22, r23, r24, r25, r26;
+  const __frame = __enter("first_two", "ranges.eid", 10);
+  __frame.line = 11;
+  r1 = BigInt.asIntN(64, r0 + 3n);
+  r2 = [r0, r1];
+  r3 = { value: undefined };
+  r3.value = r2;
+  __frame.line = 12;
+  r4 = r3.value;
+  r5 = r4[0];
+  r6 = r4[1];
+  r7 = r5 < r6;
+  r8 = BigInt.asIntN(64, r5 + 1n);
+  r9 = r7 ? r8 : r5;
+  r10 = [r9, r6];
+  r3.value = r10;
+  r11 = { ok: true, value: r5 };
+  r12 = { ok: false, value: undefined };
+  r13 = r7 ? r11 : r12;
+  r14 = r13.ok ? r13.value : 0n;
+  __frame.line = 13;
+  r15 = r3.value;
+  r16 = r15[0];
+  r17 = r15[1];
+  r18 = r16 < r17;
+  r19 = BigInt.asIntN(64, r16 + 1n);
+  r20 = r18 ? r19 : r16;
+  r21 = [r20, r17];
+  r3.value = r21;
+  r22 = { ok: true, value: r16 };
+  r23 = { ok: false, value: undefined };
+  r24 = r18 ? r22 : r23;
+  r25 = r24.ok ? r24.value : 0n;
+  r26 = BigInt.asIntN(64, r14 + r25);
+  __leave();
+  return r26;
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4, r5, r6, r7, r8, r9, r10, r11, r12, r13, r14, r15, r16;
+  const __frame = __enter("main", "ranges.eid", 16);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 18;
+        r1 = { value: undefined };
+        r1.value = 0n;
+        r4 = 3n;
+        __block = 1;
+        continue;
+      }
+      case 1: {
+        __frame.line = 19;
+        r6 = r4 < 7n;
+        if (r6) {
+          __block = 2;
+          continue;
+        } else {
+          __block = 3;
+          continue;
+        }
+      }
+      case 2: {
+        __frame.line = 20;
+        r10 = r1.value;
+        r11 = BigInt.asIntN(64, r10 + 1n);
+        r1.value = r11;
+        __frame.line = 19;
+        r7 = BigInt.asIntN(64, r4 + 1n);
+        r4 = r7;
+        __block = 1;
+        continue;
+      }
+      case 3: {
+        __frame.line = 22;
+        r12 = sum_to(10n);
+        r13 = r1.value;
+        r14 = BigInt.asIntN(64, r12 + r13);
+        r15 = first_two(5n);
+        r16 = BigInt.asIntN(64, r14 + r15);
+        __leave();
+        return r16;
+      }
+    }
+  }
+}
+
+export default main;
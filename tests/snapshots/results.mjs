@@ -0,0 +1,225 @@
+// Eidos module 'results'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+function __unwrap(variant, ok, message) {
+  if (variant.ok !== ok) __panic(message);
+  return variant.value;
+}
+
+export function half(r0) {
+  let r1, r2;
+  const __frame = __enter("half", "results.eid", 2);
+  __frame.line = 3;
+  r1 = BigInt.asIntN(64, r0 / 2n);
+  r2 = { ok: true, value: r1 };
+  __leave();
+  return r2;
+}
+
+export function missing() {
+  let r0;
+  const __frame = __enter("missing", "results.eid", 6);
+  __frame.line = 7;
+  r0 = { ok: false, value: undefined };
+  __leave();
+  return r0;
+}
+
+export function quarter(r0) {
+  let r1, r2, r3, r4;
+  const __frame = __enter("quarter", "results.eid", 10);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 11;
+        r1 = half(r0);
+        r2 = r1.ok;
+        if (r2) {
+          __block = 2;
+          continue;
+        } else {
+          __block = 1;
+          continue;
+        }
+      }
+      case 1: {
+        __leave();
+        return r1;
+      }
+      case 2: {
+        __frame.line = 11;
+        r3 = __unwrap(r1, true, "called `Option::unwrap()` on a `None` value");
+        __frame.line = 12;
+        r4 = half(r3);
+        __leave();
+        return r4;
+      }
+    }
+  }
+}
+
+export function lookup() {
+  let r0, r1, r2, r3, r4;
+  const __frame = __enter("lookup", "results.eid", 15);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 16;
+        r0 = missing();
+        r1 = r0.ok;
+        if (r1) {
+          __block = 2;
+          continue;
+        } else {
+          __block = 1;
+          continue;
+        }
+      }
+      case 1: {
+        __leave();
+        return r0;
+      }
+      case 2: {
+        __frame.line = 16;
+        r2 = __unwrap(r0, true, "called `Option::unwrap()` on a `None` value");
+        __frame.line = 17;
+        r3 = BigInt.asIntN(64, r2 + 1n);
+        r4 = { ok: true, value: r3 };
+        __leave();
+        return r4;
+      }
+    }
+  }
+}
+
+export function parse(r0) {
+  let r1;
+  const __frame = __enter("parse", "results.eid", 20);
+  __frame.line = 21;
+  r1 = { ok: false, value: r0 };
+  __leave();
+  return r1;
+}
+
+export function doubled(r0) {
+  let r1, r2, r3, r4, r5;
+  const __frame = __enter("doubled", "results.eid", 24);
+  let __block = 0;
+  for (;;) {
+    switch (__block) {
+      case 0: {
+        __frame.line = 25;
+        r1 = parse(r0);
+        r2 = r1.ok;
+        if (r2) {
+          __block = 2;
+          continue;
+        } else {
+          __block = 1;
+          continue;
+        }
+      }
+      case 1: {
+        __leave();
+        return r1;
+      }
+      case 2: {
+        __frame.line = 25;
+        r3 = __unwrap(r1, true, "called `Result::unwrap()` on an `Err` value");
+        __frame.line = 26;
+        r4 = BigInt.asIntN(64, r3 * 2n);
+        r5 = { ok: true, value: r4 };
+        __leave();
+        return r5;
+      }
+    }
+  }
+}
+
+export function main() {
+  let r0, r1, r2, r3, r4, r5, r6, r7;
+  const __frame = __enter("main", "results.eid", 29);
+  __frame.line = 30;
+  r0 = quarter(40n);
+  r1 = __unwrap(r0, true, "called `Option::unwrap()` on a `None` value");
+  __frame.line = 31;
+  r2 = lookup();
+  r3 = r2.ok ? r2.value : 5n;
+  __frame.line = 32;
+  r4 = doubled("x");
+  r5 = r4.ok ? r4.value : 7n;
+  __frame.line = 33;
+  r6 = BigInt.asIntN(64, r1 + r3);
+  r7 = BigInt.asIntN(64, r6 + r5);
+  __leave();
+  return r7;
+}
+
+export default main;
@@ -0,0 +1,103 @@
+// Eidos module 'variables'
+let __output = (line) => console.log(line);
+let __line = "";
+
+/** 出力先を差し替える（1行ごとに呼び出される） */
+export function setOutput(sink) {
+  __output = sink;
+}
+
+function __show(value) {
+  if (value === undefined) return "()";
+  return String(value);
+}
+
+function __print(...values) {
+  __line += values.map(__show).join("");
+}
+
+function __println(...values) {
+  __print(...values);
+  __output(__line);
+  __line = "";
+}
+
+let __frames = [];
+
+function __enter(name, file, line) {
+  const frame = { name, file, line };
+  __frames.push(frame);
+  return frame;
+}
+
+function __leave() {
+  __frames.pop();
+}
+
+/** 実行時エラー: 呼び出しフレームをたどったスタックトレースを付けて例外を投げる */
+function __panic(message) {
+  if (__line !== "") {
+    __output(__line);
+    __line = "";
+  }
+  const trace = __frames
+    .slice()
+    .reverse()
+    .map((frame) => (frame.line ? `    at ${frame.name} (${frame.file}:${frame.line})` : `    at ${frame.name}`));
+  __frames = [];
+  const error = new Error(`eidos: panic: ${message}\nstack trace (most recent call first):\n${trace.join("\n")}`);
+  error.eidosTrace = trace;
+  throw error;
+}
+
+function __unreachable() {
+  __panic("reached unreachable code");
+}
+
+function __div(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("division overflow");
+  return a / b;
+}
+
+function __rem(a, b) {
+  if (b === 0n) __panic("division by zero");
+  if (b === -1n && a === -(2n ** 63n)) __panic("remainder overflow");
+  return a % b;
+}
+
+export function accumulate(r0) {
+  let r1, r2, r3, r4, r5, r6, r7, r8;
+  const __frame = __enter("accumulate", "variables.eid", 2);
+  __frame.line = 3;
+  r1 = { value: undefined };
+  r1.value = r0;
+  __frame.line = 4;
+  r2 = r1.value;
+  r3 = BigInt.asIntN(64, r2 + 10n);
+  r1.value = r3;
+  __frame.line = 5;
+  r4 = r1.value;
+  r5 = BigInt.asIntN(64, r4 * 3n);
+  r1.value = r5;
+  __frame.line = 6;
+  r6 = r1.value;
+  r7 = BigInt.asIntN(64, r6 - 1n);
+  r1.value = r7;
+  __frame.line = 7;
+  r8 = r1.value;
+  __leave();
+  return r8;
+}
+
+export function main() {
+  let r0, r1;
+  const __frame = __enter("main", "variables.eid", 10);
+  __frame.line = 12;
+  r0 = accumulate(2n);
+  r1 = BigInt.asIntN(64, -r0);
+  __leave();
+  return r1;
+}
+
+export default main;
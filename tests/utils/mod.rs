@@ -16,6 +16,46 @@ pub fn cleanup_test_file(path: &PathBuf) {
     let _ = fs::remove_file(path);
 }
 
+/// `eidos` の終了コード（失敗の種類ごとに決まっている）
+pub mod exit_code {
+    /// 成功
+    pub const SUCCESS: i32 = 0;
+    /// 入力のプログラムにエラーがある（字句・構文・意味・型・DSLのエラー）
+    pub const PROGRAM_ERROR: i32 = 1;
+    /// コマンドラインの誤り
+    pub const USAGE: i32 = 2;
+    /// 入出力やバックエンドなど、プログラムの誤り以外による失敗
+    pub const FAILURE: i32 = 3;
+    /// コンパイラ内部のエラー
+    pub const INTERNAL_ERROR: i32 = 101;
+}
+
+/// `eidos` の実行結果
+#[derive(Debug)]
+pub struct EidosOutput {
+    /// 終了コード（シグナルで終了した場合はNone）
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl EidosOutput {
+    /// `--json` で標準エラー出力に書かれた診断とエラー（1行に1つのJSON）
+    pub fn json_messages(&self) -> Vec<serde_json::Value> {
+        self.stderr.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+    }
+}
+
+/// 引数を指定してEidosコンパイラを実行し、終了コードと出力を返す
+pub fn run_eidos(args: &[&str]) -> std::io::Result<EidosOutput> {
+    let output = Command::new("target/debug/eidos").args(args).output()?;
+    Ok(EidosOutput {
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
 /// Eidosコンパイラのチェックコマンドを実行
 pub fn run_eidos_check(file_path: &PathBuf) -> Result<String, String> {
     let output = Command::new("target/debug/eidos")
@@ -80,11 +120,41 @@ pub fn run_eidos_run(file_path: &PathBuf, args: &[&str]) -> Result<String, Strin
     }
 }
 
+/// Eidosコンパイラのast コマンドを実行（`format` は `json` または `sexp`）
+pub fn run_eidos_ast(file_path: &PathBuf, format: &str) -> Result<String, String> {
+    let output = Command::new("target/debug/eidos")
+        .args(["ast", "--expand", "--format", format, &file_path.to_string_lossy()])
+        .output();
+        
+    match output {
+        Ok(output) => {
+            if output.status.success() {
+                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            } else {
+                Err(String::from_utf8_lossy(&output.stderr).to_string())
+            }
+        },
+        Err(e) => Err(format!("Failed to execute command: {}", e)),
+    }
+}
+
 /// ASTノードの表示用フォーマッタ
 pub trait AstFormatter {
     fn format(&self) -> String;
 }
 
+/// ソースコードを `eidos ast --format sexp` で解析したAST（解析できなければエラー出力）
+impl AstFormatter for str {
+    fn format(&self) -> String {
+        let file = tempfile::Builder::new()
+            .suffix(".eid")
+            .tempfile()
+            .expect("一時ファイルを作成できません");
+        fs::write(file.path(), self).expect("一時ファイルに書き込めません");
+        run_eidos_ast(&file.path().to_path_buf(), "sexp").unwrap_or_else(|error| error)
+    }
+}
+
 /// テスト用のサンプルEidosコード
 pub mod samples {
     /// 単純な関数定義